@@ -1,12 +1,13 @@
 //! AI Session CLI - Terminal session management optimized for AI agents
 
-use ai_session::SessionConfig;
+use ai_session::{SessionConfig, StopHook, StopHookOutcome};
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use ai_session::core::SessionId;
 use ai_session::session_persistence::{PersistentSessionManager, get_session_manager};
@@ -39,6 +40,20 @@ enum Commands {
         /// Token limit for context
         #[arg(long, default_value = "4096")]
         token_limit: usize,
+
+        /// Cleanup command to run before the session is killed (repeatable,
+        /// runs in order), e.g. --on-stop "git stash" --on-stop "docker compose down"
+        #[arg(long = "on-stop", value_name = "COMMAND")]
+        on_stop: Vec<String>,
+
+        /// Seconds each --on-stop command may run before it is killed
+        #[arg(long, default_value = "30")]
+        on_stop_timeout: u64,
+
+        /// Seconds the session may sit idle before its shell is shut down;
+        /// the next input re-spawns it
+        #[arg(long, value_name = "SECS")]
+        hibernate_after: Option<u64>,
     },
 
     /// List active sessions
@@ -65,6 +80,15 @@ enum Commands {
         /// Capture output for AI analysis
         #[arg(long)]
         capture: bool,
+
+        /// Stop the command after this many seconds: SIGINT, then SIGTERM,
+        /// then SIGKILL
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// Seconds between escalating signals once the timeout is hit
+        #[arg(long, default_value = "2", requires = "timeout")]
+        escalation: u64,
     },
 
     /// Kill a session
@@ -313,14 +337,41 @@ async fn main() -> Result<()> {
             dir,
             ai_context,
             token_limit,
-        } => create_session(name, dir, ai_context, token_limit).await?,
+            on_stop,
+            on_stop_timeout,
+            hibernate_after,
+        } => {
+            let on_stop = on_stop
+                .into_iter()
+                .map(|command| {
+                    StopHook::new(command).with_timeout(Duration::from_secs(on_stop_timeout))
+                })
+                .collect();
+            create_session(
+                name,
+                dir,
+                ai_context,
+                token_limit,
+                on_stop,
+                hibernate_after.map(Duration::from_secs),
+            )
+            .await?
+        }
         Commands::List { detailed } => list_sessions(detailed).await?,
         Commands::Attach { session } => attach_session(session).await?,
         Commands::Exec {
             session,
             command,
             capture,
-        } => exec_command(session, command, capture).await?,
+            timeout,
+            escalation,
+        } => {
+            let timeout = timeout.map(|secs| {
+                ai_session::CommandTimeout::new(Duration::from_secs(secs))
+                    .with_escalation(Duration::from_secs(escalation))
+            });
+            exec_command(session, command, capture, timeout).await?
+        }
         Commands::Kill { session, force } => kill_session(session, force).await?,
         Commands::Context { session, lines } => show_context(session, lines).await?,
         Commands::Remote { command } => handle_remote_command(command).await?,
@@ -355,10 +406,16 @@ async fn create_session(
     dir: Option<PathBuf>,
     ai_context: bool,
     token_limit: usize,
+    on_stop: Vec<StopHook>,
+    hibernate_after: Option<Duration>,
 ) -> Result<()> {
     let manager = get_session_manager().await?;
 
-    let mut config = SessionConfig::default();
+    let mut config = SessionConfig {
+        on_stop,
+        hibernate_after,
+        ..SessionConfig::default()
+    };
     if let Some(n) = name.clone() {
         config.name = Some(n);
     }
@@ -385,6 +442,12 @@ async fn create_session(
     if ai_context {
         println!("AI context enabled with {} token limit", token_limit);
     }
+    for hook in &session.config.on_stop {
+        println!("On stop: {}", hook.command);
+    }
+    if let Some(after) = session.config.hibernate_after {
+        println!("Hibernates after {}s idle", after.as_secs());
+    }
 
     Ok(())
 }
@@ -415,6 +478,19 @@ async fn list_sessions(detailed: bool) -> Result<()> {
                     session.config.working_directory.display()
                 );
                 println!("  Status: {:?}", session.status().await);
+                if let Some(event) = session.hibernation_events().await.last() {
+                    println!(
+                        "  Last {}: {} ({}s)",
+                        match event.kind {
+                            ai_session::core::hibernation::HibernationEventKind::Hibernated => {
+                                "hibernation"
+                            }
+                            ai_session::core::hibernation::HibernationEventKind::Woke => "wake",
+                        },
+                        event.at.format("%Y-%m-%d %H:%M:%S"),
+                        event.secs
+                    );
+                }
                 if session.config.enable_ai_features {
                     println!("  AI Features: Enabled");
                     println!(
@@ -434,6 +510,7 @@ async fn list_sessions(detailed: bool) -> Result<()> {
                     match session.status().await {
                         ai_session::core::SessionStatus::Running => "running",
                         ai_session::core::SessionStatus::Paused => "paused",
+                        ai_session::core::SessionStatus::Hibernated => "hibernated",
                         ai_session::core::SessionStatus::Terminated => "terminated",
                         _ => "unknown",
                     }
@@ -445,31 +522,124 @@ async fn list_sessions(detailed: bool) -> Result<()> {
     Ok(())
 }
 
+/// Key that detaches an attached terminal (Ctrl+], as in telnet).
+const DETACH_KEY: u8 = 0x1d;
+
 async fn attach_session(session: String) -> Result<()> {
+    use std::io::IsTerminal;
+
     let manager = get_session_manager().await?;
     let session_id = resolve_session_id(&manager, &session).await?;
 
-    if let Some(session) = manager.get_session(&session_id).await {
-        println!("Attaching to session: {}", session_id);
-        println!("Session status: {:?}", session.status().await);
-        println!(
-            "Working directory: {}",
-            session.config.working_directory.display()
-        );
-
-        // For now, just demonstrate that we can interact with the session
-        println!(
-            "\n(Interactive mode would start here. For now, use 'ai-session exec' to run commands)"
-        );
-    } else {
+    let Some(session) = manager.get_session(&session_id).await else {
         eprintln!("Session not found: {}", session_id);
         std::process::exit(1);
+    };
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("attach needs an interactive terminal");
     }
 
-    Ok(())
+    println!(
+        "Attached to session {} ({}). Detach with Ctrl+].",
+        session_id,
+        session.config.working_directory.display()
+    );
+    let (cols, rows) = crossterm::terminal::size()?;
+    session.resize(cols, rows).await?;
+
+    crossterm::terminal::enable_raw_mode()?;
+    let result = attach_loop(&session).await;
+    crossterm::terminal::disable_raw_mode()?;
+    println!();
+    println!("Detached from session {}", session_id);
+    result
 }
 
-async fn exec_command(session: String, command: Vec<String>, capture: bool) -> Result<()> {
+/// Relay keystrokes and output until the user detaches or the session ends.
+///
+/// The local terminal is in raw mode, so Ctrl+C, Ctrl+Z and friends arrive
+/// as bytes and go to the session's PTY, whose line discipline signals the
+/// program in the foreground there. Window changes are forwarded as resizes;
+/// a `SIGTERM` to this client is passed on to the session's foreground job.
+async fn attach_loop(session: &Arc<ai_session::AISession>) -> Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut winch = signal(SignalKind::window_change())?;
+    let mut term = signal(SignalKind::terminate())?;
+
+    // A plain thread, not tokio's stdin: a blocking read left pending after
+    // detaching would otherwise hold up runtime shutdown.
+    let (input_tx, mut input_rx) = tokio::sync::mpsc::channel::<Vec<u8>>(64);
+    std::thread::spawn(move || {
+        use std::io::Read;
+        let mut stdin = io::stdin().lock();
+        let mut buf = [0u8; 1024];
+        while let Ok(n) = stdin.read(&mut buf) {
+            if n == 0 || input_tx.blocking_send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut output = {
+        let session = session.clone();
+        tokio::spawn(async move {
+            let mut stdout = io::stdout();
+            loop {
+                match session.read_output().await {
+                    Ok(bytes) if !bytes.is_empty() => {
+                        if stdout
+                            .write_all(&bytes)
+                            .and_then(|_| stdout.flush())
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Ok(_) if session.status().await == ai_session::SessionStatus::Terminated => {
+                        break;
+                    }
+                    Ok(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+                    Err(_) => break,
+                }
+            }
+        })
+    };
+
+    let result = loop {
+        tokio::select! {
+            input = input_rx.recv() => {
+                let Some(input) = input else { break Ok(()) };
+                match input.iter().position(|b| *b == DETACH_KEY) {
+                    Some(pos) => {
+                        if pos > 0 {
+                            session.send_raw(&input[..pos]).await?;
+                        }
+                        break Ok(());
+                    }
+                    None => session.send_raw(&input).await?,
+                }
+            }
+            _ = winch.recv() => {
+                let (cols, rows) = crossterm::terminal::size()?;
+                session.resize(cols, rows).await?;
+            }
+            _ = term.recv() => {
+                break session.signal(ai_session::TerminalSignal::Terminate).await;
+            }
+            _ = &mut output => break Ok(()),
+        }
+    };
+    output.abort();
+    result
+}
+
+async fn exec_command(
+    session: String,
+    command: Vec<String>,
+    capture: bool,
+    timeout: Option<ai_session::CommandTimeout>,
+) -> Result<()> {
     let manager = get_session_manager().await?;
     let session_id = resolve_session_id(&manager, &session).await?;
 
@@ -477,9 +647,21 @@ async fn exec_command(session: String, command: Vec<String>, capture: bool) -> R
     println!("Executing in session {}: {}", session, cmd);
 
     let output_str = if let Some(session) = manager.get_session(&session_id).await {
-        let result = session.execute_command(&cmd).await?;
+        let timeout = timeout.or(session.config.command_timeout);
+        let result = session
+            .execute_command_with_timeout(&cmd, timeout.as_ref())
+            .await?;
         manager.update_session_state(&session).await?;
         println!("{}", result);
+        if let Some(timeout) = timeout
+            && session
+                .get_command_history()
+                .await
+                .last()
+                .is_some_and(|record| record.timed_out)
+        {
+            eprintln!("Command timed out after {}s", timeout.timeout_secs);
+        }
         result
     } else {
         eprintln!("Session not found: {}", session_id);
@@ -501,11 +683,31 @@ async fn kill_session(session: String, force: bool) -> Result<()> {
 
     if force {
         println!("Force killing session: {}", session);
+        manager.kill_session(&session_id).await?;
     } else {
         println!("Gracefully terminating session: {}", session);
+        let handle = manager.get_session(&session_id).await;
+        manager.remove_session(&session_id).await?;
+        if let Some(handle) = handle
+            && let Some(outcomes) = handle.get_metadata("stop_hooks").await
+        {
+            for outcome in serde_json::from_value::<Vec<StopHookOutcome>>(outcomes)? {
+                let result = if outcome.timed_out {
+                    "timed out".to_string()
+                } else {
+                    match outcome.exit_code {
+                        Some(0) => "ok".to_string(),
+                        Some(code) => format!("exit {code}"),
+                        None => "failed".to_string(),
+                    }
+                };
+                println!(
+                    "  on-stop `{}`: {} ({} ms)",
+                    outcome.command, result, outcome.duration_ms
+                );
+            }
+        }
     }
-
-    manager.remove_session(&session_id).await?;
     println!("Session terminated");
 
     Ok(())
@@ -1,6 +1,6 @@
 //! AI-Session HTTP Server - provides REST API for external command execution via curl
 
-use ai_session::{SessionConfig, SessionManager};
+use ai_session::{SessionConfig, SessionManager, StopHook, TerminalSignal};
 use anyhow::Result;
 use axum::{
     Router,
@@ -30,6 +30,9 @@ struct Args {
     host: String,
 }
 
+/// How often idle sessions are checked for hibernation.
+const HIBERNATION_SWEEP: std::time::Duration = std::time::Duration::from_secs(30);
+
 /// Server state shared across requests
 #[derive(Clone)]
 struct AppState {
@@ -47,6 +50,13 @@ struct CreateSessionRequest {
     working_directory: Option<String>,
     #[serde(default)]
     shell: Option<String>,
+    /// Cleanup commands run before the session is deleted
+    #[serde(default)]
+    on_stop: Vec<StopHook>,
+    /// Seconds idle before the session's shell is shut down until its next
+    /// command
+    #[serde(default)]
+    hibernate_after_secs: Option<u64>,
 }
 
 /// Request to execute a command
@@ -57,6 +67,19 @@ struct ExecuteCommandRequest {
     timeout_ms: u64,
 }
 
+/// Request to change a session's window size
+#[derive(Deserialize)]
+struct ResizeRequest {
+    cols: u16,
+    rows: u16,
+}
+
+/// Request to forward a signal (`int`, `quit`, `tstp`, `term`, `hup`)
+#[derive(Deserialize)]
+struct SignalRequest {
+    signal: String,
+}
+
 fn default_timeout() -> u64 {
     5000
 }
@@ -116,7 +139,21 @@ async fn main() -> Result<()> {
     let manager = Arc::new(SessionManager::new());
     let sessions = Arc::new(RwLock::new(HashMap::new()));
 
-    let state = AppState { manager, sessions };
+    let state = AppState {
+        manager: manager.clone(),
+        sessions,
+    };
+
+    // Shut idle sessions' shells down; the next command wakes them.
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(HIBERNATION_SWEEP);
+        loop {
+            tick.tick().await;
+            for id in manager.hibernate_idle().await {
+                tracing::info!("Hibernated idle session {}", id);
+            }
+        }
+    });
 
     // Build application routes
     let app = Router::new()
@@ -129,6 +166,8 @@ async fn main() -> Result<()> {
         .route("/sessions/:name/execute", post(execute_command))
         .route("/sessions/:name/status", get(get_session_status))
         .route("/sessions/:name/output", get(get_session_output))
+        .route("/sessions/:name/resize", post(resize_session))
+        .route("/sessions/:name/signal", post(signal_session))
         .with_state(state)
         .layer(CorsLayer::permissive());
 
@@ -145,6 +184,8 @@ async fn main() -> Result<()> {
     println!("  POST   /sessions/:name/execute     - Execute command");
     println!("  GET    /sessions/:name/status      - Get session status");
     println!("  GET    /sessions/:name/output      - Get session output");
+    println!("  POST   /sessions/:name/resize      - Resize terminal (cols, rows)");
+    println!("  POST   /sessions/:name/signal      - Forward a signal (int, term, ...)");
 
     println!("\n🔧 Example curl commands:");
     println!("  # Create session:");
@@ -237,6 +278,8 @@ async fn create_session(
     // Create session configuration
     let mut config = SessionConfig {
         enable_ai_features: req.enable_ai_features,
+        on_stop: req.on_stop,
+        hibernate_after: req.hibernate_after_secs.map(std::time::Duration::from_secs),
         ..Default::default()
     };
 
@@ -410,8 +453,10 @@ async fn get_session_status(
         "status": format!("{:?}", session.status().await),
         "created_at": session.created_at.to_rfc3339(),
         "last_activity": session.last_activity.read().await.to_rfc3339(),
+        "hibernation_events": session.hibernation_events().await,
         "config": {
             "enable_ai_features": session.config.enable_ai_features,
+            "hibernate_after_secs": session.config.hibernate_after.map(|d| d.as_secs()),
             "working_directory": session.config.working_directory.display().to_string(),
             "pty_size": session.config.pty_size
         }
@@ -446,6 +491,76 @@ async fn get_session_output(
     })))
 }
 
+/// Resize a session's terminal so full-screen programs follow the client's window
+async fn resize_session(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(req): Json<ResizeRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let session = get_session_by_name(&state, &name).await?;
+
+    if req.cols == 0 || req.rows == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "cols and rows must be greater than zero".to_string(),
+                code: "INVALID_SIZE".to_string(),
+            }),
+        ));
+    }
+    session.resize(req.cols, req.rows).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to resize: {}", e),
+                code: "RESIZE_FAILED".to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(serde_json::json!({
+        "session_name": name,
+        "cols": req.cols,
+        "rows": req.rows,
+    })))
+}
+
+/// Forward a signal to the session's foreground job
+async fn signal_session(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(req): Json<SignalRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let session = get_session_by_name(&state, &name).await?;
+
+    let signal = TerminalSignal::parse(&req.signal).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!(
+                    "Unknown signal '{}' (expected int, quit, tstp, term or hup)",
+                    req.signal
+                ),
+                code: "INVALID_SIGNAL".to_string(),
+            }),
+        )
+    })?;
+    session.signal(signal).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to send signal: {}", e),
+                code: "SIGNAL_FAILED".to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(serde_json::json!({
+        "session_name": name,
+        "signal": format!("{:?}", signal.signal()),
+    })))
+}
+
 /// Helper function to get session by name
 async fn get_session_by_name(
     state: &AppState,
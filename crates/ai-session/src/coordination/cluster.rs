@@ -0,0 +1,741 @@
+//! Task pools shared by several orchestrator instances (experimental).
+//!
+//! Instances on different machines publish tasks into one [`TaskPool`] and
+//! claim them with time-limited [`Lease`]s. A worker renews its lease while it
+//! runs the task; if the worker dies, the lease runs out and another node
+//! claims the task. Nodes also post [`NodeStatus`] heartbeats, so a single
+//! [`ClusterView`] shows the whole fleet.
+//!
+//! Backends, chosen by [`open_pool`]:
+//!
+//! - `dir:<path>` (or a bare path): [`DirTaskPool`] keeps the pool in a
+//!   directory every node mounts (NFS, SMB, or a local path for several
+//!   instances on one machine). Claims are serialized by an exclusive-create
+//!   lock file, which works on network filesystems where `flock` does not.
+//! - `redis://` / `rediss://`: `RedisTaskPool`, with the `cluster-redis`
+//!   feature. Leases are Redis keys that expire on their own.
+
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A task as published to the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolTask {
+    pub id: String,
+    pub task: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow: Option<String>,
+    /// Higher runs first.
+    #[serde(default)]
+    pub priority: u8,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Node that published the task.
+    pub origin: String,
+    pub published_at: DateTime<Utc>,
+}
+
+/// Where a pooled task stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum PoolTaskState {
+    Pending,
+    Leased {
+        node: String,
+        lease_id: String,
+        expires_at: DateTime<Utc>,
+    },
+    Done {
+        node: String,
+        success: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        run_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        finished_at: DateTime<Utc>,
+    },
+}
+
+/// A pooled task with its state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolEntry {
+    pub task: PoolTask,
+    pub state: PoolTaskState,
+    /// Times the task has been claimed; more than one means a lease ran out.
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl PoolEntry {
+    /// Whether a node may claim the task at `now`: it is pending, or its
+    /// lease has run out.
+    pub fn claimable(&self, now: DateTime<Utc>) -> bool {
+        match &self.state {
+            PoolTaskState::Pending => true,
+            PoolTaskState::Leased { expires_at, .. } => *expires_at <= now,
+            PoolTaskState::Done { .. } => false,
+        }
+    }
+}
+
+/// A node's hold on one task until `expires_at`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lease {
+    pub task: PoolTask,
+    pub node: String,
+    pub id: String,
+    pub expires_at: DateTime<Utc>,
+    pub attempt: u32,
+}
+
+/// How a claimed task ended.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskOutcome {
+    pub success: bool,
+    pub run_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Heartbeat of one node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeStatus {
+    pub node: String,
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_task: Option<String>,
+    #[serde(default)]
+    pub completed: u32,
+    #[serde(default)]
+    pub failed: u32,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl NodeStatus {
+    /// Whether the node has posted a heartbeat within `stale_after` of `now`.
+    pub fn is_live(&self, now: DateTime<Utc>, stale_after: Duration) -> bool {
+        now.signed_duration_since(self.last_seen)
+            .to_std()
+            .map_or(true, |age| age <= stale_after)
+    }
+}
+
+/// Everything in a pool at one moment.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterView {
+    pub tasks: Vec<PoolEntry>,
+    pub nodes: Vec<NodeStatus>,
+}
+
+/// Counts of a [`ClusterView`]'s tasks by state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolCounts {
+    pub pending: usize,
+    pub leased: usize,
+    /// Leased tasks whose lease has run out; the next claim picks them up.
+    pub expired: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+impl ClusterView {
+    pub fn counts(&self, now: DateTime<Utc>) -> PoolCounts {
+        let mut counts = PoolCounts::default();
+        for entry in &self.tasks {
+            match &entry.state {
+                PoolTaskState::Pending => counts.pending += 1,
+                PoolTaskState::Leased { expires_at, .. } if *expires_at <= now => {
+                    counts.expired += 1
+                }
+                PoolTaskState::Leased { .. } => counts.leased += 1,
+                PoolTaskState::Done { success: true, .. } => counts.succeeded += 1,
+                PoolTaskState::Done { .. } => counts.failed += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// A task queue shared by several nodes.
+#[async_trait]
+pub trait TaskPool: Send + Sync {
+    /// Add `task`; false when a task with its id is already in the pool.
+    async fn publish(&self, task: PoolTask) -> Result<bool>;
+
+    /// Lease the highest-priority claimable task to `node` for `ttl`.
+    async fn claim(&self, node: &str, ttl: Duration) -> Result<Option<Lease>>;
+
+    /// Extend `lease` by `ttl` from now; false when it was lost (it ran out
+    /// and another node claimed the task, or the task was finished).
+    async fn renew(&self, lease: &mut Lease, ttl: Duration) -> Result<bool>;
+
+    /// Record how the leased task ended; false when the lease was lost and
+    /// the outcome was discarded.
+    async fn complete(&self, lease: &Lease, outcome: TaskOutcome) -> Result<bool>;
+
+    /// Post a node heartbeat.
+    async fn heartbeat(&self, status: &NodeStatus) -> Result<()>;
+
+    /// All tasks and node heartbeats.
+    async fn view(&self) -> Result<ClusterView>;
+}
+
+/// Open the pool at `url`: `dir:<path>`, a bare path, or `redis://...`.
+pub fn open_pool(url: &str) -> Result<Box<dyn TaskPool>> {
+    if url.starts_with("redis://") || url.starts_with("rediss://") {
+        #[cfg(feature = "cluster-redis")]
+        return Ok(Box::new(RedisTaskPool::open(url, DEFAULT_REDIS_PREFIX)?));
+        #[cfg(not(feature = "cluster-redis"))]
+        bail!("Redis task pools need a build with the `cluster-redis` feature");
+    }
+    if url.starts_with("nats://") {
+        bail!("NATS task pools are not supported yet; use redis:// or a shared directory");
+    }
+    let path = url.strip_prefix("dir:").unwrap_or(url);
+    if path.is_empty() || path.contains("://") {
+        bail!("Unknown task pool '{url}' (expected dir:<path> or redis://host)");
+    }
+    Ok(Box::new(DirTaskPool::new(path)))
+}
+
+/// Pick the entry to claim next: highest priority, then oldest.
+fn next_claimable(entries: &[PoolEntry], now: DateTime<Utc>) -> Option<usize> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.claimable(now))
+        .min_by_key(|(_, e)| (std::cmp::Reverse(e.task.priority), e.task.published_at))
+        .map(|(i, _)| i)
+}
+
+fn expiry(ttl: Duration) -> DateTime<Utc> {
+    Utc::now() + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX)
+}
+
+/// A pool kept in a directory all nodes can reach.
+///
+/// Layout: `tasks.json` (every [`PoolEntry`]), `nodes/<node>.json`
+/// heartbeats, and `pool.lock` while a node updates the tasks.
+pub struct DirTaskPool {
+    dir: PathBuf,
+    /// A lock file older than this is assumed abandoned and broken.
+    stale_lock: Duration,
+}
+
+/// Held while a node rewrites `tasks.json`; removed on drop.
+struct DirLock {
+    path: PathBuf,
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl DirTaskPool {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            stale_lock: Duration::from_secs(30),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn tasks_path(&self) -> PathBuf {
+        self.dir.join("tasks.json")
+    }
+
+    async fn lock(&self) -> Result<DirLock> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create task pool {}", self.dir.display()))?;
+        let path = self.dir.join("pool.lock");
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        loop {
+            match tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .await
+            {
+                Ok(_) => return Ok(DirLock { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let abandoned = tokio::fs::metadata(&path)
+                        .await
+                        .ok()
+                        .and_then(|m| m.modified().ok())
+                        .and_then(|t| t.elapsed().ok())
+                        .is_some_and(|age| age > self.stale_lock);
+                    if abandoned {
+                        tracing::warn!("Breaking abandoned task pool lock {}", path.display());
+                        let _ = tokio::fs::remove_file(&path).await;
+                        continue;
+                    }
+                    if tokio::time::Instant::now() >= deadline {
+                        bail!("Timed out waiting for task pool lock {}", path.display());
+                    }
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to lock task pool {}", path.display()));
+                }
+            }
+        }
+    }
+
+    async fn load(&self) -> Result<Vec<PoolEntry>> {
+        match tokio::fs::read_to_string(self.tasks_path()).await {
+            Ok(text) if text.trim().is_empty() => Ok(Vec::new()),
+            Ok(text) => serde_json::from_str(&text).context("Failed to parse task pool"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, entries: &[PoolEntry]) -> Result<()> {
+        write_atomic(&self.tasks_path(), &serde_json::to_vec_pretty(entries)?).await
+    }
+
+    /// Apply `mutate` to the tasks under the pool lock, saving them after.
+    async fn update<T>(&self, mutate: impl FnOnce(&mut Vec<PoolEntry>) -> T) -> Result<T> {
+        let _lock = self.lock().await?;
+        let mut entries = self.load().await?;
+        let result = mutate(&mut entries);
+        self.save(&entries).await?;
+        Ok(result)
+    }
+}
+
+async fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp = path.with_extension(format!("tmp.{}", uuid::Uuid::new_v4().simple()));
+    tokio::fs::write(&tmp, data).await?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[async_trait]
+impl TaskPool for DirTaskPool {
+    async fn publish(&self, task: PoolTask) -> Result<bool> {
+        self.update(|entries| {
+            if entries.iter().any(|e| e.task.id == task.id) {
+                return false;
+            }
+            entries.push(PoolEntry {
+                task,
+                state: PoolTaskState::Pending,
+                attempts: 0,
+            });
+            true
+        })
+        .await
+    }
+
+    async fn claim(&self, node: &str, ttl: Duration) -> Result<Option<Lease>> {
+        self.update(|entries| {
+            let index = next_claimable(entries, Utc::now())?;
+            let entry = &mut entries[index];
+            let lease = Lease {
+                task: entry.task.clone(),
+                node: node.to_string(),
+                id: uuid::Uuid::new_v4().to_string(),
+                expires_at: expiry(ttl),
+                attempt: entry.attempts + 1,
+            };
+            entry.attempts += 1;
+            entry.state = PoolTaskState::Leased {
+                node: node.to_string(),
+                lease_id: lease.id.clone(),
+                expires_at: lease.expires_at,
+            };
+            Some(lease)
+        })
+        .await
+    }
+
+    async fn renew(&self, lease: &mut Lease, ttl: Duration) -> Result<bool> {
+        let expires_at = expiry(ttl);
+        let renewed = self
+            .update(|entries| {
+                let Some(entry) = entries.iter_mut().find(|e| e.task.id == lease.task.id) else {
+                    return false;
+                };
+                match &mut entry.state {
+                    PoolTaskState::Leased {
+                        lease_id,
+                        expires_at: current,
+                        ..
+                    } if *lease_id == lease.id => {
+                        *current = expires_at;
+                        true
+                    }
+                    _ => false,
+                }
+            })
+            .await?;
+        if renewed {
+            lease.expires_at = expires_at;
+        }
+        Ok(renewed)
+    }
+
+    async fn complete(&self, lease: &Lease, outcome: TaskOutcome) -> Result<bool> {
+        self.update(|entries| {
+            let Some(entry) = entries.iter_mut().find(|e| e.task.id == lease.task.id) else {
+                return false;
+            };
+            if !matches!(&entry.state, PoolTaskState::Leased { lease_id, .. } if *lease_id == lease.id)
+            {
+                return false;
+            }
+            entry.state = PoolTaskState::Done {
+                node: lease.node.clone(),
+                success: outcome.success,
+                run_id: outcome.run_id,
+                error: outcome.error,
+                finished_at: Utc::now(),
+            };
+            true
+        })
+        .await
+    }
+
+    async fn heartbeat(&self, status: &NodeStatus) -> Result<()> {
+        let name: String = status
+            .node
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        write_atomic(
+            &self.dir.join("nodes").join(format!("{name}.json")),
+            &serde_json::to_vec_pretty(status)?,
+        )
+        .await
+    }
+
+    async fn view(&self) -> Result<ClusterView> {
+        let tasks = self.load().await?;
+        let mut nodes = Vec::new();
+        if let Ok(mut dir) = tokio::fs::read_dir(self.dir.join("nodes")).await {
+            while let Some(entry) = dir.next_entry().await? {
+                if entry.path().extension().is_some_and(|e| e == "json")
+                    && let Ok(text) = tokio::fs::read_to_string(entry.path()).await
+                    && let Ok(status) = serde_json::from_str::<NodeStatus>(&text)
+                {
+                    nodes.push(status);
+                }
+            }
+        }
+        nodes.sort_by(|a, b| a.node.cmp(&b.node));
+        Ok(ClusterView { tasks, nodes })
+    }
+}
+
+#[cfg(feature = "cluster-redis")]
+const DEFAULT_REDIS_PREFIX: &str = "ccswarm:cluster";
+
+/// A pool kept in Redis.
+///
+/// Keys under `prefix`: `tasks` (hash of id → [`PoolEntry`] JSON), `nodes`
+/// (hash of node → [`NodeStatus`] JSON) and `lease:<id>` (the lease id,
+/// expiring with the lease). The lease key decides who holds a task; the
+/// state in `tasks` mirrors it for [`TaskPool::view`].
+#[cfg(feature = "cluster-redis")]
+pub struct RedisTaskPool {
+    client: redis::Client,
+    prefix: String,
+}
+
+#[cfg(feature = "cluster-redis")]
+impl RedisTaskPool {
+    pub fn open(url: &str, prefix: &str) -> Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url).context("Invalid Redis URL")?,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}:{name}", self.prefix)
+    }
+
+    async fn connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        self.client
+            .get_multiplexed_tokio_connection()
+            .await
+            .context("Failed to connect to Redis")
+    }
+
+    async fn entries(
+        &self,
+        conn: &mut redis::aio::MultiplexedConnection,
+    ) -> Result<Vec<PoolEntry>> {
+        let raw: std::collections::HashMap<String, String> = redis::cmd("HGETALL")
+            .arg(self.key("tasks"))
+            .query_async(conn)
+            .await?;
+        raw.values()
+            .map(|json| serde_json::from_str(json).context("Failed to parse pooled task"))
+            .collect()
+    }
+}
+
+#[cfg(feature = "cluster-redis")]
+#[async_trait]
+impl TaskPool for RedisTaskPool {
+    async fn publish(&self, task: PoolTask) -> Result<bool> {
+        let mut conn = self.connection().await?;
+        let id = task.id.clone();
+        let entry = PoolEntry {
+            task,
+            state: PoolTaskState::Pending,
+            attempts: 0,
+        };
+        let added: bool = redis::cmd("HSETNX")
+            .arg(self.key("tasks"))
+            .arg(id)
+            .arg(serde_json::to_string(&entry)?)
+            .query_async(&mut conn)
+            .await?;
+        Ok(added)
+    }
+
+    async fn claim(&self, node: &str, ttl: Duration) -> Result<Option<Lease>> {
+        let mut conn = self.connection().await?;
+        let mut entries = self.entries(&mut conn).await?;
+        // Leases expire in Redis itself, so any unfinished task is a
+        // candidate; SET NX on its lease key decides who gets it.
+        entries.retain(|e| !matches!(e.state, PoolTaskState::Done { .. }));
+        entries.sort_by_key(|e| (std::cmp::Reverse(e.task.priority), e.task.published_at));
+        for mut entry in entries {
+            let lease_id = uuid::Uuid::new_v4().to_string();
+            let acquired: Option<String> = redis::cmd("SET")
+                .arg(self.key(&format!("lease:{}", entry.task.id)))
+                .arg(&lease_id)
+                .arg("NX")
+                .arg("PX")
+                .arg(ttl.as_millis() as u64)
+                .query_async(&mut conn)
+                .await?;
+            if acquired.is_none() {
+                continue;
+            }
+            let lease = Lease {
+                task: entry.task.clone(),
+                node: node.to_string(),
+                id: lease_id.clone(),
+                expires_at: expiry(ttl),
+                attempt: entry.attempts + 1,
+            };
+            entry.attempts += 1;
+            entry.state = PoolTaskState::Leased {
+                node: node.to_string(),
+                lease_id,
+                expires_at: lease.expires_at,
+            };
+            let _: () = redis::cmd("HSET")
+                .arg(self.key("tasks"))
+                .arg(&entry.task.id)
+                .arg(serde_json::to_string(&entry)?)
+                .query_async(&mut conn)
+                .await?;
+            return Ok(Some(lease));
+        }
+        Ok(None)
+    }
+
+    async fn renew(&self, lease: &mut Lease, ttl: Duration) -> Result<bool> {
+        let mut conn = self.connection().await?;
+        let renewed: i32 = redis::Script::new(
+            "if redis.call('GET', KEYS[1]) == ARGV[1] then \
+               return redis.call('PEXPIRE', KEYS[1], ARGV[2]) \
+             else return 0 end",
+        )
+        .key(self.key(&format!("lease:{}", lease.task.id)))
+        .arg(&lease.id)
+        .arg(ttl.as_millis() as u64)
+        .invoke_async(&mut conn)
+        .await?;
+        if renewed == 1 {
+            lease.expires_at = expiry(ttl);
+        }
+        Ok(renewed == 1)
+    }
+
+    async fn complete(&self, lease: &Lease, outcome: TaskOutcome) -> Result<bool> {
+        let mut conn = self.connection().await?;
+        let entry = PoolEntry {
+            task: lease.task.clone(),
+            state: PoolTaskState::Done {
+                node: lease.node.clone(),
+                success: outcome.success,
+                run_id: outcome.run_id,
+                error: outcome.error,
+                finished_at: Utc::now(),
+            },
+            attempts: lease.attempt,
+        };
+        let done: i32 = redis::Script::new(
+            "if redis.call('GET', KEYS[1]) == ARGV[1] then \
+               redis.call('DEL', KEYS[1]); \
+               redis.call('HSET', KEYS[2], ARGV[2], ARGV[3]); \
+               return 1 \
+             else return 0 end",
+        )
+        .key(self.key(&format!("lease:{}", lease.task.id)))
+        .key(self.key("tasks"))
+        .arg(&lease.id)
+        .arg(&lease.task.id)
+        .arg(serde_json::to_string(&entry)?)
+        .invoke_async(&mut conn)
+        .await?;
+        Ok(done == 1)
+    }
+
+    async fn heartbeat(&self, status: &NodeStatus) -> Result<()> {
+        let mut conn = self.connection().await?;
+        let _: () = redis::cmd("HSET")
+            .arg(self.key("nodes"))
+            .arg(&status.node)
+            .arg(serde_json::to_string(status)?)
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn view(&self) -> Result<ClusterView> {
+        let mut conn = self.connection().await?;
+        let mut tasks = self.entries(&mut conn).await?;
+        tasks.sort_by_key(|e| e.task.published_at);
+        let raw: std::collections::HashMap<String, String> = redis::cmd("HGETALL")
+            .arg(self.key("nodes"))
+            .query_async(&mut conn)
+            .await?;
+        let mut nodes: Vec<NodeStatus> = raw
+            .values()
+            .filter_map(|json| serde_json::from_str(json).ok())
+            .collect();
+        nodes.sort_by(|a, b| a.node.cmp(&b.node));
+        Ok(ClusterView { tasks, nodes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, priority: u8) -> PoolTask {
+        PoolTask {
+            id: id.to_string(),
+            task: format!("do {id}"),
+            flow: None,
+            priority,
+            labels: Vec::new(),
+            origin: "node-a".to_string(),
+            published_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dir_pool_leases_tasks_once_and_reclaims_expired_leases() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_pool(&format!("dir:{}", dir.path().display())).unwrap();
+        assert!(pool.publish(task("low", 1)).await.unwrap());
+        assert!(pool.publish(task("high", 3)).await.unwrap());
+        assert!(!pool.publish(task("high", 3)).await.unwrap());
+
+        // Concurrent claimers never get the same task.
+        let (a, b, c) = tokio::join!(
+            pool.claim("a", Duration::from_secs(60)),
+            pool.claim("b", Duration::from_secs(60)),
+            pool.claim("c", Duration::from_secs(60)),
+        );
+        let mut claimed: Vec<Lease> = [a, b, c].into_iter().filter_map(|r| r.unwrap()).collect();
+        claimed.sort_by_key(|l| std::cmp::Reverse(l.task.priority));
+        assert_eq!(claimed.len(), 2);
+        assert_eq!(claimed[0].task.id, "high");
+        assert_ne!(claimed[0].node, claimed[1].node);
+
+        let mut done = claimed.remove(0);
+        assert!(
+            pool.renew(&mut done, Duration::from_secs(60))
+                .await
+                .unwrap()
+        );
+        let outcome = TaskOutcome {
+            success: true,
+            run_id: Some("run-1".to_string()),
+            error: None,
+        };
+        assert!(pool.complete(&done, outcome.clone()).await.unwrap());
+        assert!(!pool.complete(&done, outcome).await.unwrap());
+
+        // A lease that runs out goes to the next claimer; the old holder's
+        // renewal and result are refused.
+        let mut lapsed = claimed.remove(0);
+        let pool_dir = DirTaskPool::new(dir.path());
+        pool_dir
+            .update(|entries| {
+                for entry in entries.iter_mut() {
+                    if let PoolTaskState::Leased { expires_at, .. } = &mut entry.state {
+                        *expires_at = Utc::now() - chrono::Duration::seconds(1);
+                    }
+                }
+            })
+            .await
+            .unwrap();
+        let retaken = pool
+            .claim("d", Duration::from_secs(60))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((retaken.task.id.as_str(), retaken.attempt), ("low", 2));
+        assert!(
+            !pool
+                .renew(&mut lapsed, Duration::from_secs(60))
+                .await
+                .unwrap()
+        );
+        assert!(
+            pool.claim("e", Duration::from_secs(60))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        pool.heartbeat(&NodeStatus {
+            node: "d".to_string(),
+            host: "box".to_string(),
+            current_task: Some("low".to_string()),
+            completed: 1,
+            failed: 0,
+            last_seen: Utc::now(),
+        })
+        .await
+        .unwrap();
+        let view = pool.view().await.unwrap();
+        let counts = view.counts(Utc::now());
+        assert_eq!((counts.leased, counts.succeeded), (1, 1));
+        assert_eq!(view.nodes[0].current_task.as_deref(), Some("low"));
+        assert!(view.nodes[0].is_live(Utc::now(), Duration::from_secs(5)));
+
+        assert!(open_pool("nats://x").is_err());
+    }
+}
@@ -4,12 +4,25 @@ use anyhow::Result;
 use crossbeam_channel::{Receiver, Sender};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::core::AISession;
 
+pub mod cluster;
+pub mod shared_doc;
+
+#[cfg(feature = "cluster-redis")]
+pub use cluster::RedisTaskPool;
+pub use cluster::{
+    ClusterView, DirTaskPool, Lease, NodeStatus, PoolCounts, PoolEntry, PoolTask, PoolTaskState,
+    TaskOutcome, TaskPool, open_pool,
+};
+pub use shared_doc::{DocOp, OpId, SharedDoc, SharedDocStore};
+
 /// Default channel capacity for agent message channels
 const DEFAULT_CHANNEL_CAPACITY: usize = 1000;
 
@@ -71,6 +84,14 @@ impl MultiAgentSession {
         }
     }
 
+    /// Create a multi-agent session whose task distributor uses `policy`
+    pub fn with_scheduling(policy: SchedulingPolicy) -> Self {
+        Self {
+            task_distributor: Arc::new(TaskDistributor::with_policy(policy)),
+            ..Self::new()
+        }
+    }
+
     /// Register an agent
     pub fn register_agent(&self, agent_id: AgentId, session: Arc<AISession>) -> Result<()> {
         self.agents.insert(agent_id.clone(), session);
@@ -98,6 +119,26 @@ impl MultiAgentSession {
             .collect()
     }
 
+    /// Pause an agent: suspend its session and stop delegating tasks to it
+    pub async fn pause_agent(&self, agent_id: &AgentId) -> Result<()> {
+        let session = self
+            .get_agent(agent_id)
+            .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_id))?;
+        session.pause().await?;
+        self.task_distributor.set_paused(agent_id, true);
+        Ok(())
+    }
+
+    /// Resume a paused agent and make it eligible for tasks again
+    pub async fn resume_agent(&self, agent_id: &AgentId) -> Result<()> {
+        let session = self
+            .get_agent(agent_id)
+            .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_id))?;
+        session.resume().await?;
+        self.task_distributor.set_paused(agent_id, false);
+        Ok(())
+    }
+
     /// Send a message to an agent
     pub async fn send_message(&self, from: AgentId, to: AgentId, message: Message) -> Result<()> {
         self.message_bus.send_message(from, to, message)
@@ -570,6 +611,50 @@ pub enum MessagePriority {
     Critical,
 }
 
+/// How [`TaskDistributor`] orders queued tasks and spreads them over agents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulingPolicy {
+    /// Raise a waiting task's priority by one class every this many
+    /// seconds, so low-priority work is eventually scheduled (0 disables)
+    pub aging_secs: u64,
+    /// Rotate which agent receives the next task among equally loaded ones,
+    /// instead of always starting from the same agent
+    pub round_robin: bool,
+    /// Most tasks an agent may have waiting before new tasks overflow to
+    /// other agents (0 = unlimited)
+    pub max_queue_depth: usize,
+}
+
+impl Default for SchedulingPolicy {
+    fn default() -> Self {
+        Self {
+            aging_secs: 300,
+            round_robin: true,
+            max_queue_depth: 0,
+        }
+    }
+}
+
+impl SchedulingPolicy {
+    /// Priority of `task` after aging, as of `now`
+    pub fn effective_priority(
+        &self,
+        task: &Task,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> TaskPriority {
+        if self.aging_secs == 0 {
+            return task.priority;
+        }
+        let waited = (now - task.created_at).num_seconds().max(0) as u64;
+        task.priority.raised(waited / self.aging_secs)
+    }
+
+    fn has_room(&self, depth: usize) -> bool {
+        self.max_queue_depth == 0 || depth < self.max_queue_depth
+    }
+}
+
 /// Task distributor for workload management
 pub struct TaskDistributor {
     /// Task queue
@@ -578,6 +663,14 @@ pub struct TaskDistributor {
     agent_capabilities: Arc<DashMap<AgentId, Vec<String>>>,
     /// Task assignments
     assignments: Arc<DashMap<TaskId, AgentId>>,
+    /// Agents that must not receive tasks
+    paused: Arc<dashmap::DashSet<AgentId>>,
+    /// Tasks assigned to each agent that it has not taken yet
+    agent_queues: Arc<DashMap<AgentId, VecDeque<Task>>>,
+    /// Ordering and fairness rules
+    policy: std::sync::RwLock<SchedulingPolicy>,
+    /// Round-robin position among agents
+    next_agent: AtomicUsize,
 }
 
 impl Default for TaskDistributor {
@@ -589,13 +682,51 @@ impl Default for TaskDistributor {
 impl TaskDistributor {
     /// Create a new task distributor
     pub fn new() -> Self {
+        Self::with_policy(SchedulingPolicy::default())
+    }
+
+    /// Create a task distributor with a scheduling policy
+    pub fn with_policy(policy: SchedulingPolicy) -> Self {
         Self {
             task_queue: Arc::new(RwLock::new(Vec::new())),
             agent_capabilities: Arc::new(DashMap::new()),
             assignments: Arc::new(DashMap::new()),
+            paused: Arc::new(dashmap::DashSet::new()),
+            agent_queues: Arc::new(DashMap::new()),
+            policy: std::sync::RwLock::new(policy),
+            next_agent: AtomicUsize::new(0),
         }
     }
 
+    /// Current scheduling policy
+    pub fn policy(&self) -> SchedulingPolicy {
+        self.policy
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the scheduling policy. A lower queue depth takes effect on the
+    /// next [`distribute_tasks`](Self::distribute_tasks), which moves the
+    /// overflow to other agents.
+    pub fn set_policy(&self, policy: SchedulingPolicy) {
+        *self.policy.write().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
+    /// Mark an agent paused (no new tasks) or available again
+    pub fn set_paused(&self, agent_id: &AgentId, paused: bool) {
+        if paused {
+            self.paused.insert(agent_id.clone());
+        } else {
+            self.paused.remove(agent_id);
+        }
+    }
+
+    /// Whether an agent is paused
+    pub fn is_paused(&self, agent_id: &AgentId) -> bool {
+        self.paused.contains(agent_id)
+    }
+
     /// Register agent capabilities
     pub fn register_capabilities(&self, agent_id: AgentId, capabilities: Vec<String>) {
         self.agent_capabilities.insert(agent_id, capabilities);
@@ -607,33 +738,148 @@ impl TaskDistributor {
         Ok(())
     }
 
-    /// Assign tasks to agents
+    /// Number of tasks assigned to an agent that it has not taken yet
+    pub fn queue_depth(&self, agent_id: &AgentId) -> usize {
+        self.agent_queues.get(agent_id).map_or(0, |q| q.len())
+    }
+
+    /// Take the next task assigned to an agent
+    pub fn take_task(&self, agent_id: &AgentId) -> Option<Task> {
+        self.agent_queues.get_mut(agent_id)?.pop_front()
+    }
+
+    /// Hand a task to a specific agent. When that agent is paused or its
+    /// queue is full the task overflows to the least loaded capable agent;
+    /// when no agent has room it is queued for the next distribution.
+    pub async fn assign_task(&self, agent_id: &AgentId, task: Task) -> Result<Option<AgentId>> {
+        let policy = self.policy();
+        let target = if !self.is_paused(agent_id)
+            && self.agent_capabilities.contains_key(agent_id)
+            && policy.has_room(self.queue_depth(agent_id))
+        {
+            Some(agent_id.clone())
+        } else {
+            let agents = self.available_agents();
+            self.pick_agent(&agents, &task, &policy, 0)
+                .map(|i| agents[i].clone())
+        };
+        match &target {
+            Some(agent) => self.enqueue(agent, task),
+            None => self.task_queue.write().await.push(task),
+        }
+        Ok(target)
+    }
+
+    /// Assign queued tasks to agents.
+    ///
+    /// Tasks go out highest (aged) priority first and oldest first within a
+    /// priority class. Each goes to the capable agent with the fewest waiting
+    /// tasks, rotating among equally loaded agents, and never past the
+    /// policy's queue depth; tasks no agent has room for stay queued. Agents
+    /// already over the depth give their newest waiting tasks back first.
     pub async fn distribute_tasks(&self) -> Result<Vec<(TaskId, AgentId)>> {
         let mut assignments = Vec::new();
         let mut queue = self.task_queue.write().await;
+        let policy = self.policy();
 
-        // Simple round-robin distribution
-        // In a real implementation, this would use sophisticated matching
-        let agents: Vec<AgentId> = self
-            .agent_capabilities
-            .iter()
-            .map(|entry| entry.key().clone())
-            .collect();
+        let agents = self.available_agents();
 
+        // With every agent paused the tasks stay queued.
         if agents.is_empty() {
             return Ok(assignments);
         }
 
-        let mut agent_index = 0;
-        while let Some(task) = queue.pop() {
-            let agent_id = &agents[agent_index % agents.len()];
-            self.assignments.insert(task.id.clone(), agent_id.clone());
-            assignments.push((task.id, agent_id.clone()));
-            agent_index += 1;
+        queue.extend(self.take_overflow(&policy));
+        let now = chrono::Utc::now();
+        queue.sort_by_key(|task| {
+            (
+                std::cmp::Reverse(policy.effective_priority(task, now)),
+                task.created_at,
+            )
+        });
+
+        let mut waiting = Vec::new();
+        for task in queue.drain(..) {
+            let start = if policy.round_robin {
+                self.next_agent.load(Ordering::Relaxed)
+            } else {
+                0
+            };
+            let Some(index) = self.pick_agent(&agents, &task, &policy, start) else {
+                waiting.push(task);
+                continue;
+            };
+            self.next_agent.store(index + 1, Ordering::Relaxed);
+            assignments.push((task.id.clone(), agents[index].clone()));
+            self.enqueue(&agents[index], task);
         }
+        *queue = waiting;
 
         Ok(assignments)
     }
+
+    /// Unpaused agents in a stable order
+    fn available_agents(&self) -> Vec<AgentId> {
+        let mut agents: Vec<AgentId> = self
+            .agent_capabilities
+            .iter()
+            .map(|entry| entry.key().clone())
+            .filter(|agent| !self.is_paused(agent))
+            .collect();
+        agents.sort_by_key(|agent| agent.0);
+        agents
+    }
+
+    /// Index of the least loaded agent that can run `task` and has room,
+    /// scanning from `start` so ties rotate
+    fn pick_agent(
+        &self,
+        agents: &[AgentId],
+        task: &Task,
+        policy: &SchedulingPolicy,
+        start: usize,
+    ) -> Option<usize> {
+        (0..agents.len())
+            .map(|offset| (start + offset) % agents.len())
+            .filter(|&i| self.can_run(&agents[i], task))
+            .map(|i| (i, self.queue_depth(&agents[i])))
+            .filter(|&(_, depth)| policy.has_room(depth))
+            .min_by_key(|&(_, depth)| depth)
+            .map(|(i, _)| i)
+    }
+
+    fn can_run(&self, agent_id: &AgentId, task: &Task) -> bool {
+        self.agent_capabilities.get(agent_id).is_some_and(|caps| {
+            task.required_capabilities
+                .iter()
+                .all(|required| caps.contains(required))
+        })
+    }
+
+    fn enqueue(&self, agent_id: &AgentId, task: Task) {
+        self.assignments.insert(task.id.clone(), agent_id.clone());
+        self.agent_queues
+            .entry(agent_id.clone())
+            .or_default()
+            .push_back(task);
+    }
+
+    /// Remove the newest waiting tasks from agents over the queue depth
+    fn take_overflow(&self, policy: &SchedulingPolicy) -> Vec<Task> {
+        if policy.max_queue_depth == 0 {
+            return Vec::new();
+        }
+        let mut overflow = Vec::new();
+        for mut queue in self.agent_queues.iter_mut() {
+            while queue.len() > policy.max_queue_depth {
+                if let Some(task) = queue.pop_back() {
+                    self.assignments.remove(&task.id);
+                    overflow.push(task);
+                }
+            }
+        }
+        overflow
+    }
 }
 
 /// Task identifier
@@ -677,7 +923,7 @@ pub struct Task {
 }
 
 /// Task priority
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TaskPriority {
     Low,
     Normal,
@@ -685,6 +931,20 @@ pub enum TaskPriority {
     Critical,
 }
 
+impl TaskPriority {
+    /// This priority raised by `steps` classes, capped at `Critical`
+    pub fn raised(self, steps: u64) -> Self {
+        const ORDER: [TaskPriority; 4] = [
+            TaskPriority::Low,
+            TaskPriority::Normal,
+            TaskPriority::High,
+            TaskPriority::Critical,
+        ];
+        let index = self as usize + steps.min(ORDER.len() as u64) as usize;
+        ORDER[index.min(ORDER.len() - 1)]
+    }
+}
+
 /// Resource manager for preventing conflicts
 pub struct ResourceManager {
     /// File locks
@@ -895,6 +1155,196 @@ mod tests {
         assert_eq!(multi_session.list_agents().len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_paused_agent_gets_no_tasks() {
+        let multi_session = MultiAgentSession::new();
+        let (busy, idle) = (AgentId::new(), AgentId::new());
+        for agent in [&busy, &idle] {
+            let config = crate::core::SessionConfig {
+                shell: Some("/bin/sh".to_string()),
+                force_headless: true,
+                ..Default::default()
+            };
+            let session = Arc::new(AISession::new(config).await.unwrap());
+            session.start().await.unwrap();
+            multi_session
+                .register_agent(agent.clone(), session)
+                .unwrap();
+            multi_session
+                .task_distributor
+                .register_capabilities(agent.clone(), vec!["code".to_string()]);
+        }
+
+        multi_session.pause_agent(&busy).await.unwrap();
+        for name in ["a", "b", "c"] {
+            multi_session
+                .task_distributor
+                .submit_task(Task {
+                    id: TaskId::new(),
+                    name: name.to_string(),
+                    required_capabilities: vec![],
+                    payload: serde_json::Value::Null,
+                    priority: TaskPriority::Normal,
+                    created_at: chrono::Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+        let assigned = multi_session
+            .task_distributor
+            .distribute_tasks()
+            .await
+            .unwrap();
+        assert_eq!(assigned.len(), 3);
+        assert!(assigned.iter().all(|(_, agent)| *agent == idle));
+
+        multi_session.pause_agent(&idle).await.unwrap();
+        multi_session
+            .task_distributor
+            .submit_task(Task {
+                id: TaskId::new(),
+                name: "d".to_string(),
+                required_capabilities: vec![],
+                payload: serde_json::Value::Null,
+                priority: TaskPriority::Normal,
+                created_at: chrono::Utc::now(),
+            })
+            .await
+            .unwrap();
+        assert!(
+            multi_session
+                .task_distributor
+                .distribute_tasks()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        multi_session.resume_agent(&busy).await.unwrap();
+        let assigned = multi_session
+            .task_distributor
+            .distribute_tasks()
+            .await
+            .unwrap();
+        assert_eq!(assigned.len(), 1);
+        assert_eq!(assigned[0].1, busy);
+        for agent in [&busy, &idle] {
+            multi_session
+                .get_agent(agent)
+                .unwrap()
+                .stop()
+                .await
+                .unwrap();
+        }
+    }
+
+    fn task(name: &str, priority: TaskPriority, age_secs: i64, caps: &[&str]) -> Task {
+        Task {
+            id: TaskId::new(),
+            name: name.to_string(),
+            required_capabilities: caps.iter().map(|c| c.to_string()).collect(),
+            payload: serde_json::Value::Null,
+            priority,
+            created_at: chrono::Utc::now() - chrono::Duration::seconds(age_secs),
+        }
+    }
+
+    fn names(distributor: &TaskDistributor, agent: &AgentId) -> Vec<String> {
+        std::iter::from_fn(|| distributor.take_task(agent))
+            .map(|t| t.name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_aging_and_round_robin_order() {
+        let distributor = TaskDistributor::with_policy(SchedulingPolicy {
+            aging_secs: 60,
+            round_robin: true,
+            max_queue_depth: 0,
+        });
+        let mut agents = vec![AgentId::new(), AgentId::new()];
+        agents.sort_by_key(|a| a.0);
+        for agent in &agents {
+            distributor.register_capabilities(agent.clone(), vec!["code".to_string()]);
+        }
+
+        // "stale" waited three aging periods: Low -> Critical, ahead of "high".
+        for t in [
+            task("normal-new", TaskPriority::Normal, 0, &[]),
+            task("high", TaskPriority::High, 0, &[]),
+            task("normal-old", TaskPriority::Normal, 30, &[]),
+            task("stale", TaskPriority::Low, 200, &[]),
+        ] {
+            distributor.submit_task(t).await.unwrap();
+        }
+        let order: Vec<AgentId> = distributor
+            .distribute_tasks()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, agent)| agent)
+            .collect();
+        assert_eq!(
+            order,
+            [&agents[0], &agents[1], &agents[0], &agents[1]].map(Clone::clone)
+        );
+        assert_eq!(names(&distributor, &agents[0]), ["stale", "normal-old"]);
+        assert_eq!(names(&distributor, &agents[1]), ["high", "normal-new"]);
+
+        // Capabilities still restrict who can run a task.
+        distributor.register_capabilities(agents[1].clone(), vec!["docs".to_string()]);
+        distributor
+            .submit_task(task("docs", TaskPriority::Normal, 0, &["docs"]))
+            .await
+            .unwrap();
+        let assigned = distributor.distribute_tasks().await.unwrap();
+        assert_eq!(assigned[0].1, agents[1]);
+    }
+
+    #[tokio::test]
+    async fn test_queue_depth_overflow_and_rebalance() {
+        let distributor = TaskDistributor::with_policy(SchedulingPolicy {
+            aging_secs: 0,
+            round_robin: false,
+            max_queue_depth: 2,
+        });
+        let (favourite, other) = (AgentId::new(), AgentId::new());
+        distributor.register_capabilities(favourite.clone(), vec![]);
+        distributor.register_capabilities(other.clone(), vec![]);
+
+        for name in ["a", "b", "c"] {
+            distributor
+                .assign_task(&favourite, task(name, TaskPriority::Normal, 0, &[]))
+                .await
+                .unwrap();
+        }
+        assert_eq!(distributor.queue_depth(&favourite), 2);
+        assert_eq!(distributor.queue_depth(&other), 1);
+
+        // Both full: the next task waits in the shared queue.
+        for name in ["d", "e"] {
+            distributor
+                .assign_task(&other, task(name, TaskPriority::Normal, 0, &[]))
+                .await
+                .unwrap();
+        }
+        assert_eq!(distributor.queue_depth(&other), 2);
+        assert!(distributor.distribute_tasks().await.unwrap().is_empty());
+
+        // Lowering the depth moves the overflow to whoever has room.
+        distributor.take_task(&other);
+        distributor.take_task(&other);
+        distributor.set_policy(SchedulingPolicy {
+            max_queue_depth: 1,
+            ..distributor.policy()
+        });
+        let assigned = distributor.distribute_tasks().await.unwrap();
+        assert_eq!(assigned.len(), 1);
+        assert_eq!(assigned[0].1, other);
+        assert_eq!(distributor.queue_depth(&favourite), 1);
+        assert_eq!(distributor.queue_depth(&other), 1);
+    }
+
     #[test]
     fn test_message_bus() {
         let bus = MessageBus::new();
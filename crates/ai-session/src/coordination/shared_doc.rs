@@ -0,0 +1,451 @@
+//! Conflict-free shared documents for notes several agents edit at once
+//! (API contracts, architecture decisions, open questions).
+//!
+//! A [`SharedDoc`] is a sequence of lines kept as a replicated growable array:
+//! every line is inserted after another line (or at the top) under a unique
+//! [`OpId`], and deleting a line only marks it. Replicas that have applied
+//! the same set of [`DocOp`]s show the same text whatever order the ops
+//! arrived in, so concurrent appends and edits never conflict.
+//!
+//! [`SharedDocStore`] persists each document as an append-only NDJSON op log
+//! that any number of processes can write to and re-read.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Unique id of an inserted line: a Lamport counter plus the replica that
+/// wrote it. Ids order by counter first, so later edits sort higher.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OpId {
+    pub counter: u64,
+    pub replica: String,
+}
+
+impl fmt::Display for OpId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.counter, self.replica)
+    }
+}
+
+/// One edit. Applying an op twice has no further effect.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum DocOp {
+    /// Insert `text` as a new line after `after` (at the top when `None`).
+    Insert {
+        id: OpId,
+        after: Option<OpId>,
+        text: String,
+    },
+    /// Remove the line inserted as `id`.
+    Delete { id: OpId },
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    text: String,
+    deleted: bool,
+}
+
+/// A shared document as seen by one replica.
+#[derive(Debug, Clone)]
+pub struct SharedDoc {
+    name: String,
+    replica: String,
+    clock: u64,
+    entries: HashMap<OpId, Entry>,
+    /// Lines inserted after each line, in no particular order.
+    children: HashMap<Option<OpId>, Vec<OpId>>,
+    /// Deletes that arrived before the line they remove.
+    early_deletes: HashSet<OpId>,
+    /// Inserts waiting for the line they follow.
+    waiting: Vec<DocOp>,
+    /// Ops made on this replica and not yet persisted.
+    unsaved: Vec<DocOp>,
+}
+
+impl SharedDoc {
+    /// An empty document edited as `replica`. Replica names must be unique
+    /// among concurrent writers.
+    pub fn new(name: impl Into<String>, replica: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            replica: replica.into(),
+            clock: 0,
+            entries: HashMap::new(),
+            children: HashMap::new(),
+            early_deletes: HashSet::new(),
+            waiting: Vec::new(),
+            unsaved: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn replica(&self) -> &str {
+        &self.replica
+    }
+
+    /// Apply a remote op. Returns whether it changed anything.
+    pub fn apply(&mut self, op: DocOp) -> bool {
+        if !self.apply_one(op) {
+            return false;
+        }
+        // An insert may have unblocked others that follow it.
+        loop {
+            let ready: Vec<DocOp> = {
+                let (ready, waiting) = std::mem::take(&mut self.waiting)
+                    .into_iter()
+                    .partition(|op| self.can_apply(op));
+                self.waiting = waiting;
+                ready
+            };
+            if ready.is_empty() {
+                break;
+            }
+            for op in ready {
+                self.apply_one(op);
+            }
+        }
+        true
+    }
+
+    /// Apply every op in `ops`, returning how many changed the document.
+    pub fn merge(&mut self, ops: impl IntoIterator<Item = DocOp>) -> usize {
+        ops.into_iter().filter(|op| self.apply(op.clone())).count()
+    }
+
+    fn can_apply(&self, op: &DocOp) -> bool {
+        match op {
+            DocOp::Insert {
+                after: Some(after), ..
+            } => self.entries.contains_key(after),
+            _ => true,
+        }
+    }
+
+    fn apply_one(&mut self, op: DocOp) -> bool {
+        if !self.can_apply(&op) {
+            if !self.waiting.contains(&op) {
+                self.waiting.push(op);
+            }
+            return false;
+        }
+        match op {
+            DocOp::Insert { id, after, text } => {
+                if self.entries.contains_key(&id) {
+                    return false;
+                }
+                self.clock = self.clock.max(id.counter);
+                let deleted = self.early_deletes.remove(&id);
+                self.entries.insert(id.clone(), Entry { text, deleted });
+                self.children.entry(after).or_default().push(id);
+                true
+            }
+            DocOp::Delete { id } => match self.entries.get_mut(&id) {
+                Some(entry) if entry.deleted => false,
+                Some(entry) => {
+                    entry.deleted = true;
+                    true
+                }
+                None => self.early_deletes.insert(id),
+            },
+        }
+    }
+
+    /// Visible lines in document order with the id each was inserted under.
+    pub fn lines(&self) -> Vec<(&OpId, &str)> {
+        let mut lines = Vec::new();
+        let mut stack: Vec<&OpId> = self.sorted_children(None);
+        while let Some(id) = stack.pop() {
+            let entry = &self.entries[id];
+            if !entry.deleted {
+                lines.push((id, entry.text.as_str()));
+            }
+            stack.extend(self.sorted_children(Some(id)));
+        }
+        lines
+    }
+
+    /// Children of `after`, oldest first so the newest is popped first:
+    /// a line inserted later goes closer to the line it follows.
+    fn sorted_children(&self, after: Option<&OpId>) -> Vec<&OpId> {
+        let mut children: Vec<&OpId> = self
+            .children
+            .get(&after.cloned())
+            .map(|c| c.iter().collect())
+            .unwrap_or_default();
+        children.sort();
+        children
+    }
+
+    /// The document as text, one line per entry.
+    pub fn text(&self) -> String {
+        self.lines()
+            .iter()
+            .map(|(_, text)| format!("{text}\n"))
+            .collect()
+    }
+
+    /// Insert `text` (split into lines) after the line `after`, or at the top.
+    /// Returns the id of the last inserted line.
+    pub fn insert_after(&mut self, after: Option<&OpId>, text: &str) -> OpId {
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        let mut after = after.cloned();
+        for line in text.split('\n') {
+            self.clock += 1;
+            let id = OpId {
+                counter: self.clock,
+                replica: self.replica.clone(),
+            };
+            self.local(DocOp::Insert {
+                id: id.clone(),
+                after: after.replace(id),
+                text: line.trim_end_matches('\r').to_string(),
+            });
+        }
+        after.expect("split yields at least one line")
+    }
+
+    /// Add `text` after the last visible line.
+    pub fn append(&mut self, text: &str) -> OpId {
+        let last = self.lines().last().map(|(id, _)| (*id).clone());
+        self.insert_after(last.as_ref(), text)
+    }
+
+    pub fn delete(&mut self, id: &OpId) {
+        self.local(DocOp::Delete { id: id.clone() });
+    }
+
+    /// Replace a line's text: the old line is deleted and the new text goes
+    /// in its place, so a concurrent edit of the same line keeps both.
+    pub fn replace(&mut self, id: &OpId, text: &str) -> OpId {
+        self.delete(id);
+        self.insert_after(Some(id), text)
+    }
+
+    fn local(&mut self, op: DocOp) {
+        if self.apply(op.clone()) {
+            self.unsaved.push(op);
+        }
+    }
+
+    /// Ops made on this replica since the last call.
+    pub fn take_unsaved(&mut self) -> Vec<DocOp> {
+        std::mem::take(&mut self.unsaved)
+    }
+
+    /// Every op this replica has applied, in an order that can be replayed.
+    pub fn ops(&self) -> Vec<DocOp> {
+        let mut ops = Vec::new();
+        let mut stack: Vec<(Option<&OpId>, &OpId)> = self
+            .sorted_children(None)
+            .into_iter()
+            .map(|id| (None, id))
+            .collect();
+        while let Some((after, id)) = stack.pop() {
+            let entry = &self.entries[id];
+            ops.push(DocOp::Insert {
+                id: id.clone(),
+                after: after.cloned(),
+                text: entry.text.clone(),
+            });
+            if entry.deleted {
+                ops.push(DocOp::Delete { id: id.clone() });
+            }
+            stack.extend(
+                self.sorted_children(Some(id))
+                    .into_iter()
+                    .map(|child| (Some(id), child)),
+            );
+        }
+        ops.extend(self.waiting.iter().cloned());
+        ops.extend(
+            self.early_deletes
+                .iter()
+                .map(|id| DocOp::Delete { id: id.clone() }),
+        );
+        ops
+    }
+}
+
+/// Shared documents kept as `<dir>/<name>.ndjson` op logs.
+///
+/// Writers only ever append whole lines with a single write, so concurrent
+/// writers don't overwrite each other; a line torn by a crash is skipped
+/// when the log is read.
+#[derive(Debug, Clone)]
+pub struct SharedDocStore {
+    dir: PathBuf,
+}
+
+impl SharedDocStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        validate_name(name)?;
+        Ok(self.dir.join(format!("{name}.ndjson")))
+    }
+
+    /// Names of the stored documents, sorted.
+    pub async fn list(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(names),
+            Err(e) => return Err(e).context(format!("Failed to read {}", self.dir.display())),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name();
+            if let Some(name) = file_name.to_string_lossy().strip_suffix(".ndjson")
+                && validate_name(name).is_ok()
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Load `name` as `replica`; a document that doesn't exist yet is empty.
+    pub async fn open(&self, name: &str, replica: &str) -> Result<SharedDoc> {
+        let mut doc = SharedDoc::new(name, replica);
+        self.refresh(&mut doc).await?;
+        Ok(doc)
+    }
+
+    /// Merge ops other writers added since `doc` was loaded. Returns how many
+    /// changed it.
+    pub async fn refresh(&self, doc: &mut SharedDoc) -> Result<usize> {
+        let path = self.path(doc.name())?;
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).context(format!("Failed to read {}", path.display())),
+        };
+        Ok(doc.merge(
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<DocOp>(line).ok()),
+        ))
+    }
+
+    /// Append `doc`'s unsaved ops to its log.
+    pub async fn save(&self, doc: &mut SharedDoc) -> Result<()> {
+        let ops = doc.take_unsaved();
+        if ops.is_empty() {
+            return Ok(());
+        }
+        let path = self.path(doc.name())?;
+        let mut batch = String::new();
+        for op in &ops {
+            batch.push_str(&serde_json::to_string(op)?);
+            batch.push('\n');
+        }
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        file.write_all(batch.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Document names become file names: letters, digits, `-`, `_` and `.`,
+/// not starting with a dot.
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!(
+            "Invalid document name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_edits_converge() {
+        let mut a = SharedDoc::new("api", "a");
+        let header = a.append("# Orders API");
+        a.append("GET /orders");
+        let mut b = SharedDoc::new("api", "b");
+        b.merge(a.take_unsaved());
+
+        // Both append at the end and edit the same line at once.
+        a.append("POST /orders");
+        b.append("DELETE /orders/:id");
+        a.replace(&header, "# Orders API v2");
+        b.replace(&header, "# Orders API (draft)");
+        let from_a = a.take_unsaved();
+        let from_b = b.take_unsaved();
+        // Deliver in opposite orders, with a duplicate.
+        b.merge(from_a.iter().rev().cloned());
+        a.merge(from_b.clone());
+        a.merge(from_b);
+
+        assert_eq!(a.text(), b.text());
+        let text = a.text();
+        assert!(!text.contains("# Orders API\n"));
+        for line in [
+            "# Orders API v2",
+            "# Orders API (draft)",
+            "GET /orders",
+            "POST /orders",
+            "DELETE /orders/:id",
+        ] {
+            assert!(text.contains(line), "missing {line}: {text}");
+        }
+
+        // Replaying the op list rebuilds the same document.
+        let mut c = SharedDoc::new("api", "c");
+        c.merge(a.ops());
+        assert_eq!(c.text(), a.text());
+    }
+
+    #[tokio::test]
+    async fn test_store_merges_writers() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SharedDocStore::new(dir.path());
+        assert!(store.open("../escape", "a").await.is_err());
+
+        let mut a = store.open("design", "agent-a").await.unwrap();
+        let mut b = store.open("design", "agent-b").await.unwrap();
+        a.append("Use Postgres");
+        b.append("Cache in Redis");
+        store.save(&mut a).await.unwrap();
+        store.save(&mut b).await.unwrap();
+        assert_eq!(store.refresh(&mut a).await.unwrap(), 1);
+        assert_eq!(store.refresh(&mut b).await.unwrap(), 1);
+        assert_eq!(a.text(), b.text());
+        assert_eq!(a.lines().len(), 2);
+
+        assert_eq!(store.list().await.unwrap(), ["design"]);
+        let reopened = store.open("design", "human").await.unwrap();
+        assert_eq!(reopened.text(), a.text());
+    }
+}
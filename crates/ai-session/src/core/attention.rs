@@ -54,6 +54,10 @@ impl AttentionState {
                 crate::output::LogLevel::Error => Some(Self::Error),
                 _ => None,
             },
+            ParsedOutput::Diagnostics { .. } => {
+                (!parsed.error_diagnostics().is_empty()).then_some(Self::Error)
+            }
+            ParsedOutput::TimedOut { .. } => Some(Self::Error),
             ParsedOutput::CodeExecution { .. } | ParsedOutput::PlainText(_) => None,
         }
     }
@@ -0,0 +1,384 @@
+//! Async event hooks on a session.
+//!
+//! Embedders register handlers with [`AISession::on_output`],
+//! [`AISession::on_command`], [`AISession::on_state_change`] or
+//! [`AISession::on_event`] — or on a [`SessionManager`], for every session
+//! it manages — instead of polling for output and status.
+//!
+//! Each session delivers its events from one dispatcher task, so:
+//!
+//! - a handler sees a session's events in the order they happened, one at a
+//!   time; the next event waits until every handler has finished the
+//!   previous one;
+//! - for one event, manager handlers run before session handlers, each set
+//!   in registration order;
+//! - the session itself never waits for a handler: events queue up behind a
+//!   slow one instead;
+//! - a handler that panics is logged and skipped, and the others, and later
+//!   events, are delivered as usual.
+//!
+//! [`AISession::on_output`]: super::AISession::on_output
+//! [`AISession::on_command`]: super::AISession::on_command
+//! [`AISession::on_state_change`]: super::AISession::on_state_change
+//! [`AISession::on_event`]: super::AISession::on_event
+//! [`SessionManager`]: super::SessionManager
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use tokio::sync::mpsc;
+
+use super::{OutputChunk, SessionId, SessionStatus};
+use crate::persistence::CommandRecord;
+
+/// A command run through `execute_command`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandEvent {
+    pub record: CommandRecord,
+    /// Combined stdout and stderr.
+    pub output: String,
+}
+
+/// A change of session status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateChange {
+    pub from: SessionStatus,
+    pub to: SessionStatus,
+    pub at: DateTime<Utc>,
+}
+
+/// Something that happened in a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// Output read from the terminal or produced by a command.
+    Output(OutputChunk),
+    Command(CommandEvent),
+    StateChange(StateChange),
+}
+
+/// Which events a handler wants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventKind {
+    Output,
+    Command,
+    StateChange,
+}
+
+impl SessionEvent {
+    fn kind(&self) -> EventKind {
+        match self {
+            Self::Output(_) => EventKind::Output,
+            Self::Command(_) => EventKind::Command,
+            Self::StateChange(_) => EventKind::StateChange,
+        }
+    }
+}
+
+/// Handle for removing a registered handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type Handler = Arc<dyn Fn(SessionId, SessionEvent) -> BoxFuture + Send + Sync>;
+
+struct Hook {
+    id: HookId,
+    /// `None` for every event.
+    kind: Option<EventKind>,
+    handler: Handler,
+}
+
+/// Registered handlers, shared by a session (or a manager) and its
+/// dispatcher.
+#[derive(Default)]
+pub(crate) struct HookRegistry {
+    hooks: RwLock<Vec<Hook>>,
+}
+
+/// Hook ids are unique across registries, so removing one through the wrong
+/// session or manager cannot hit someone else's handler.
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(1);
+
+impl HookRegistry {
+    fn add(&self, kind: Option<EventKind>, handler: Handler) -> HookId {
+        let id = HookId(NEXT_HOOK_ID.fetch_add(1, Ordering::Relaxed));
+        self.write().push(Hook { id, kind, handler });
+        id
+    }
+
+    pub(crate) fn remove(&self, id: HookId) -> bool {
+        let mut hooks = self.write();
+        let before = hooks.len();
+        hooks.retain(|hook| hook.id != id);
+        hooks.len() != before
+    }
+
+    fn wants(&self, kind: EventKind) -> bool {
+        self.read()
+            .iter()
+            .any(|hook| hook.kind.is_none_or(|k| k == kind))
+    }
+
+    fn handlers(&self, kind: EventKind) -> Vec<Handler> {
+        self.read()
+            .iter()
+            .filter(|hook| hook.kind.is_none_or(|k| k == kind))
+            .map(|hook| hook.handler.clone())
+            .collect()
+    }
+
+    // A handler can only panic inside its own task, never while the lock is
+    // held, so a poisoned lock still holds a consistent list.
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<Hook>> {
+        self.hooks.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<Hook>> {
+        self.hooks.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn on_output<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, OutputChunk) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.add(
+            Some(EventKind::Output),
+            Arc::new(move |id, event| match event {
+                SessionEvent::Output(chunk) => Box::pin(handler(id, chunk)),
+                _ => Box::pin(async {}),
+            }),
+        )
+    }
+
+    pub(crate) fn on_command<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, CommandEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.add(
+            Some(EventKind::Command),
+            Arc::new(move |id, event| match event {
+                SessionEvent::Command(command) => Box::pin(handler(id, command)),
+                _ => Box::pin(async {}),
+            }),
+        )
+    }
+
+    pub(crate) fn on_state_change<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, StateChange) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.add(
+            Some(EventKind::StateChange),
+            Arc::new(move |id, event| match event {
+                SessionEvent::StateChange(change) => Box::pin(handler(id, change)),
+                _ => Box::pin(async {}),
+            }),
+        )
+    }
+
+    pub(crate) fn on_event<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, SessionEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.add(
+            None,
+            Arc::new(move |id, event| Box::pin(handler(id, event))),
+        )
+    }
+}
+
+/// A session's handlers, those it inherited from its manager, and the
+/// queue to their dispatcher.
+#[derive(Default)]
+pub(crate) struct EventHooks {
+    pub(crate) own: Arc<HookRegistry>,
+    inherited: Option<Arc<HookRegistry>>,
+    queue: OnceLock<mpsc::UnboundedSender<SessionEvent>>,
+}
+
+impl std::fmt::Debug for EventHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventHooks")
+            .field("handlers", &self.own.read().len())
+            .field("inherits", &self.inherited.is_some())
+            .finish()
+    }
+}
+
+impl EventHooks {
+    pub(crate) fn inheriting(parent: Arc<HookRegistry>) -> Self {
+        Self {
+            inherited: Some(parent),
+            ..Self::default()
+        }
+    }
+
+    fn registries(&self) -> impl Iterator<Item = &Arc<HookRegistry>> {
+        self.inherited.iter().chain(std::iter::once(&self.own))
+    }
+
+    /// Queue `event` for the handlers that want it. Must be called from
+    /// within a Tokio runtime; the dispatcher starts on the first event
+    /// anyone listens for.
+    pub(crate) fn emit(&self, session: &SessionId, event: SessionEvent) {
+        if !self
+            .registries()
+            .any(|registry| registry.wants(event.kind()))
+        {
+            return;
+        }
+        let queue = self.queue.get_or_init(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            let registries: Vec<_> = self.registries().cloned().collect();
+            tokio::spawn(dispatch(session.clone(), registries, rx));
+            tx
+        });
+        let _ = queue.send(event);
+    }
+}
+
+/// Deliver a session's events in order until the session is dropped.
+async fn dispatch(
+    session: SessionId,
+    registries: Vec<Arc<HookRegistry>>,
+    mut rx: mpsc::UnboundedReceiver<SessionEvent>,
+) {
+    while let Some(event) = rx.recv().await {
+        let kind = event.kind();
+        for handler in registries.iter().flat_map(|r| r.handlers(kind)) {
+            let (id, event) = (session.clone(), event.clone());
+            // Its own task, so a panic ends the handler and not the dispatcher.
+            let run = tokio::spawn(async move { handler(id, event).await });
+            if let Err(e) = run.await
+                && e.is_panic()
+            {
+                tracing::warn!("A {:?} hook of session {} panicked", kind, session);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn change(from: SessionStatus, to: SessionStatus) -> SessionEvent {
+        SessionEvent::StateChange(StateChange {
+            from,
+            to,
+            at: Utc::now(),
+        })
+    }
+
+    #[tokio::test]
+    async fn handlers_see_events_in_order_and_survive_a_panicking_neighbour() {
+        let parent = Arc::new(HookRegistry::default());
+        let hooks = EventHooks::inheriting(parent.clone());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let log = seen.clone();
+        parent.on_state_change(move |_, change| {
+            let log = log.clone();
+            async move {
+                // A slow first handler must not let later events overtake it.
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                log.lock().unwrap().push(format!("manager {:?}", change.to));
+            }
+        });
+        hooks.own.on_state_change(|_, change| async move {
+            if change.to == SessionStatus::Paused {
+                panic!("handler bug");
+            }
+        });
+        let log = seen.clone();
+        hooks.own.on_event(move |_, event| {
+            let log = log.clone();
+            async move {
+                let line = match event {
+                    SessionEvent::StateChange(change) => format!("session {:?}", change.to),
+                    SessionEvent::Output(chunk) => format!("output {}", chunk.text()),
+                    SessionEvent::Command(_) => "command".to_string(),
+                };
+                log.lock().unwrap().push(line);
+            }
+        });
+
+        let id = SessionId::new();
+        hooks.emit(
+            &id,
+            change(SessionStatus::Initializing, SessionStatus::Running),
+        );
+        hooks.emit(&id, change(SessionStatus::Running, SessionStatus::Paused));
+        hooks.emit(
+            &id,
+            SessionEvent::Output(OutputChunk {
+                timestamp: Utc::now(),
+                data: b"done".to_vec(),
+            }),
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                "manager Running",
+                "session Running",
+                "manager Paused",
+                "session Paused",
+                "output done",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn removed_handlers_stop_receiving_and_unwanted_events_are_dropped() {
+        let hooks = EventHooks::default();
+        let count = Arc::new(AtomicU64::new(0));
+        let seen = count.clone();
+        let id = hooks.own.on_output(move |_, _| {
+            let seen = seen.clone();
+            async move {
+                seen.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let session = SessionId::new();
+        hooks.emit(
+            &session,
+            change(SessionStatus::Running, SessionStatus::Paused),
+        );
+        assert!(
+            hooks.queue.get().is_none(),
+            "nobody listens for state changes"
+        );
+
+        hooks.emit(
+            &session,
+            SessionEvent::Output(OutputChunk {
+                timestamp: Utc::now(),
+                data: b"x".to_vec(),
+            }),
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(hooks.own.remove(id));
+        assert!(!hooks.own.remove(id));
+        hooks.emit(
+            &session,
+            SessionEvent::Output(OutputChunk {
+                timestamp: Utc::now(),
+                data: b"y".to_vec(),
+            }),
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
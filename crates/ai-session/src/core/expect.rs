@@ -0,0 +1,188 @@
+//! Expect-style scripting of interactive programs.
+//!
+//! Login prompts, REPLs and installers ask before they read. Rather than
+//! sleeping and hoping the prompt has shown up, a script waits for it:
+//!
+//! ```no_run
+//! # async fn login(session: &ai_session::AISession) -> anyhow::Result<()> {
+//! use ai_session::ExpectPattern;
+//! use std::time::Duration;
+//!
+//! session.send_line("psql -h db.internal app").await?;
+//! session.expect("Password:", Duration::from_secs(10)).await?;
+//! session.send_line("hunter2").await?;
+//! let reply = session
+//!     .expect_any(
+//!         &["app=>".into(), ExpectPattern::regex(r"FATAL: +(.+)")?],
+//!         Duration::from_secs(10),
+//!     )
+//!     .await?;
+//! if reply.index == 1 {
+//!     anyhow::bail!("login refused: {:?}", reply.captures[1]);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Output an expectation reads is consumed: what came before the match is
+//! handed back in [`ExpectMatch::before`], and what came after it is kept
+//! for the next expectation. Observers of the session still see all of it.
+//! A timeout fails with [`ExpectTimeout`], which carries the output that did
+//! arrive, so a script can branch on it with `downcast_ref`.
+
+use anyhow::Result;
+use regex::bytes::Regex;
+use std::time::Duration;
+
+/// Wait between polls of a terminal that had nothing to read.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Output an expectation waits for.
+#[derive(Debug, Clone)]
+pub enum ExpectPattern {
+    /// The exact text.
+    Literal(String),
+    /// A match of the regular expression.
+    Regex(Regex),
+}
+
+impl ExpectPattern {
+    pub fn literal(text: impl Into<String>) -> Self {
+        Self::Literal(text.into())
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self> {
+        Ok(Self::Regex(Regex::new(pattern)?))
+    }
+
+    fn find(&self, haystack: &[u8]) -> Option<(usize, usize, Vec<Option<String>>)> {
+        match self {
+            Self::Literal(text) => {
+                let needle = text.as_bytes();
+                if needle.is_empty() {
+                    return Some((0, 0, vec![Some(String::new())]));
+                }
+                let start = haystack
+                    .windows(needle.len())
+                    .position(|window| window == needle)?;
+                Some((start, start + needle.len(), vec![Some(text.clone())]))
+            }
+            Self::Regex(regex) => {
+                let captures = regex.captures(haystack)?;
+                let whole = captures.get(0)?;
+                let groups = captures
+                    .iter()
+                    .map(|group| group.map(|m| String::from_utf8_lossy(m.as_bytes()).into_owned()))
+                    .collect();
+                Some((whole.start(), whole.end(), groups))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ExpectPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Literal(text) => write!(f, "{text:?}"),
+            Self::Regex(regex) => write!(f, "/{}/", regex.as_str()),
+        }
+    }
+}
+
+impl From<&str> for ExpectPattern {
+    fn from(text: &str) -> Self {
+        Self::literal(text)
+    }
+}
+
+impl From<String> for ExpectPattern {
+    fn from(text: String) -> Self {
+        Self::Literal(text)
+    }
+}
+
+impl From<Regex> for ExpectPattern {
+    fn from(regex: Regex) -> Self {
+        Self::Regex(regex)
+    }
+}
+
+/// What an expectation found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectMatch {
+    /// Which of the patterns matched.
+    pub index: usize,
+    /// Output before the match.
+    pub before: String,
+    /// The matched text.
+    pub matched: String,
+    /// The whole match, then each regex group (`None` where a group did not
+    /// take part); a literal has just the whole match.
+    pub captures: Vec<Option<String>>,
+}
+
+/// No pattern showed up in time.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("none of {} showed up within {:.1}s; got: {output:?}", patterns.join(", "), timeout.as_secs_f64())]
+pub struct ExpectTimeout {
+    pub patterns: Vec<String>,
+    pub timeout: Duration,
+    /// Output that arrived without matching; it is kept for the next
+    /// expectation.
+    pub output: String,
+}
+
+impl ExpectTimeout {
+    pub(crate) fn new(patterns: &[ExpectPattern], timeout: Duration, output: &[u8]) -> Self {
+        Self {
+            patterns: patterns.iter().map(ToString::to_string).collect(),
+            timeout,
+            output: String::from_utf8_lossy(output).into_owned(),
+        }
+    }
+}
+
+/// The earliest match of any of `patterns` in `buffer` (the first pattern
+/// listed wins a tie), and where it ends.
+pub(crate) fn find(buffer: &[u8], patterns: &[ExpectPattern]) -> Option<(ExpectMatch, usize)> {
+    let (index, (start, end, captures)) = patterns
+        .iter()
+        .enumerate()
+        .filter_map(|(index, pattern)| pattern.find(buffer).map(|found| (index, found)))
+        .min_by_key(|(index, (start, _, _))| (*start, *index))?;
+    let found = ExpectMatch {
+        index,
+        before: String::from_utf8_lossy(&buffer[..start]).into_owned(),
+        matched: String::from_utf8_lossy(&buffer[start..end]).into_owned(),
+        captures,
+    };
+    Some((found, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn earliest_match_wins_and_regex_groups_are_captured() {
+        let buffer = b"login ok\nuser=ada uid=42\n$ ";
+        let patterns = [
+            ExpectPattern::from("$ "),
+            ExpectPattern::regex(r"uid=(\d+)").unwrap(),
+            "login".into(),
+        ];
+        let (found, end) = find(buffer, &patterns).unwrap();
+        assert_eq!(found.index, 2);
+        assert_eq!(found.before, "");
+        assert_eq!(end, 5);
+
+        let (found, end) = find(&buffer[end..], &patterns[..2]).unwrap();
+        assert_eq!(found.index, 1);
+        assert_eq!(found.before, " ok\nuser=ada ");
+        assert_eq!(found.matched, "uid=42");
+        assert_eq!(found.captures, [Some("uid=42".into()), Some("42".into())]);
+        assert_eq!(&buffer[5 + end..], b"\n$ ");
+
+        assert!(find(buffer, &["password:".into()]).is_none());
+    }
+}
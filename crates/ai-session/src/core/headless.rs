@@ -35,7 +35,10 @@ impl HeadlessHandle {
             .current_dir(working_dir)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped());
+            .stderr(std::process::Stdio::piped())
+            // Own process group, so pausing the session stops the shell's
+            // children along with it.
+            .process_group(0);
 
         for (key, value) in env {
             command.env(key, value);
@@ -110,6 +113,15 @@ impl HeadlessHandle {
         }
     }
 
+    /// Pid of the shell (and its process group).
+    pub async fn process_id(&self) -> Option<u32> {
+        self.child
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|child| child.id())
+    }
+
     /// Terminate the headless shell if it is still running.
     pub async fn shutdown(self) -> Result<()> {
         if let Some(mut child) = self.child.lock().await.take() {
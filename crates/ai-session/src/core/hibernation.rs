@@ -0,0 +1,100 @@
+//! Hibernation of idle sessions.
+//!
+//! An agent's shell sits idle between tasks, holding a PTY and a process
+//! tree the whole time. A session with `hibernate_after` set is hibernated
+//! by [`SessionManager::hibernate_idle`](super::SessionManager::hibernate_idle)
+//! once it has been idle that long: output still buffered in the terminal
+//! is kept for the next read, the terminal and its processes are shut down,
+//! and the session's context, history and metadata stay where they are. The
+//! next input re-spawns the shell in the configured working directory and
+//! environment, so callers never see the gap — only the
+//! `hibernation_events` metadata entry records it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Metadata key holding the session's [`HibernationEvent`]s, oldest first.
+pub const EVENTS_KEY: &str = "hibernation_events";
+
+/// Events kept per session; older ones are dropped.
+const EVENT_LIMIT: usize = 20;
+
+/// What happened to the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HibernationEventKind {
+    /// The terminal was shut down after the session sat idle.
+    Hibernated,
+    /// The terminal was re-spawned for new input.
+    Woke,
+}
+
+/// One hibernation or wake-up, as recorded in the session's metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HibernationEvent {
+    pub kind: HibernationEventKind,
+    pub at: DateTime<Utc>,
+    /// Seconds idle before hibernating, or spent hibernated before waking.
+    pub secs: i64,
+}
+
+/// The events recorded in `metadata`, oldest first.
+pub fn events(metadata: &HashMap<String, serde_json::Value>) -> Vec<HibernationEvent> {
+    metadata
+        .get(EVENTS_KEY)
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Append `event` to `metadata`, keeping the last [`EVENT_LIMIT`].
+pub(crate) fn record(metadata: &mut HashMap<String, serde_json::Value>, event: HibernationEvent) {
+    let mut recorded = events(metadata);
+    recorded.push(event);
+    let excess = recorded.len().saturating_sub(EVENT_LIMIT);
+    recorded.drain(..excess);
+    metadata.insert(EVENTS_KEY.to_string(), serde_json::json!(recorded));
+}
+
+/// When the session last went into hibernation, if it is still recorded.
+pub(crate) fn hibernated_at(
+    metadata: &HashMap<String, serde_json::Value>,
+) -> Option<DateTime<Utc>> {
+    events(metadata)
+        .iter()
+        .rev()
+        .find(|event| event.kind == HibernationEventKind::Hibernated)
+        .map(|event| event.at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_keeps_the_most_recent_events() {
+        let mut metadata = HashMap::new();
+        let start = Utc::now();
+        for i in 0..(EVENT_LIMIT as i64 + 5) {
+            record(
+                &mut metadata,
+                HibernationEvent {
+                    kind: if i % 2 == 0 {
+                        HibernationEventKind::Hibernated
+                    } else {
+                        HibernationEventKind::Woke
+                    },
+                    at: start + chrono::Duration::seconds(i),
+                    secs: i,
+                },
+            );
+        }
+        let recorded = events(&metadata);
+        assert_eq!(recorded.len(), EVENT_LIMIT);
+        assert_eq!(recorded.last().unwrap().secs, EVENT_LIMIT as i64 + 4);
+        assert_eq!(
+            hibernated_at(&metadata),
+            Some(start + chrono::Duration::seconds(EVENT_LIMIT as i64 + 4))
+        );
+    }
+}
@@ -2,7 +2,10 @@
 
 use super::attention::AttentionState;
 use super::headless::HeadlessHandle;
+use super::hibernation::{self, HibernationEvent, HibernationEventKind};
 use super::pty::PtyHandle;
+use super::stop_hooks::run_stop_hooks;
+use super::suspend::{self, Suspend};
 use super::terminal::TerminalHandle;
 use super::{AISession, SessionConfig, SessionStatus};
 use anyhow::Result;
@@ -17,50 +20,10 @@ pub async fn start_session(session: &AISession) -> Result<()> {
         if *status != SessionStatus::Initializing {
             return Err(anyhow::anyhow!("Session already started"));
         }
-        *status = SessionStatus::Running;
+        session.transition(&mut status, SessionStatus::Running);
     }
 
-    let shell_env = std::env::var("SHELL").ok();
-    let shell = session
-        .config
-        .shell
-        .as_deref()
-        .or(shell_env.as_deref())
-        .unwrap_or("/bin/bash");
-
-    let terminal = if session.config.force_headless {
-        TerminalHandle::Headless(
-            HeadlessHandle::spawn_shell(
-                shell,
-                &session.config.working_directory,
-                session.config.environment.iter(),
-            )
-            .await?,
-        )
-    } else {
-        match spawn_pty(&session.config, shell).await {
-            Ok(pty) => TerminalHandle::Pty(pty),
-            Err(err) => {
-                if session.config.allow_headless_fallback && is_permission_denied(&err) {
-                    tracing::warn!(
-                        "PTY unavailable ({}). Falling back to headless shell for session {}",
-                        err,
-                        session.id
-                    );
-                    TerminalHandle::Headless(
-                        HeadlessHandle::spawn_shell(
-                            shell,
-                            &session.config.working_directory,
-                            session.config.environment.iter(),
-                        )
-                        .await?,
-                    )
-                } else {
-                    return Err(err);
-                }
-            }
-        }
-    };
+    let terminal = spawn_terminal(session).await?;
 
     // Store terminal handle
     {
@@ -77,15 +40,38 @@ pub async fn start_session(session: &AISession) -> Result<()> {
     Ok(())
 }
 
-/// Stop a session
-pub async fn stop_session(session: &AISession) -> Result<()> {
+/// Stop a session. With `run_hooks`, its `on_stop` hooks run (and their
+/// outcomes are recorded under the `stop_hooks` metadata key) while the
+/// terminal is still up.
+pub async fn stop_session(session: &AISession, run_hooks: bool) -> Result<()> {
     // Update status
     {
         let mut status = session.status.write().await;
-        if *status != SessionStatus::Running && *status != SessionStatus::Paused {
+        if !matches!(
+            *status,
+            SessionStatus::Running | SessionStatus::Paused | SessionStatus::Hibernated
+        ) {
             return Ok(()); // Already stopped
         }
-        *status = SessionStatus::Terminating;
+        // Stopped processes would sit on the hangup until continued.
+        if *status == SessionStatus::Paused {
+            let _ = continue_processes(session).await;
+        }
+        session.transition(&mut status, SessionStatus::Terminating);
+    }
+
+    if run_hooks && !session.config.on_stop.is_empty() {
+        let outcomes = run_stop_hooks(
+            &session.config.on_stop,
+            &session.config.working_directory,
+            &session.config.environment,
+        )
+        .await;
+        session
+            .metadata
+            .write()
+            .await
+            .insert("stop_hooks".to_string(), serde_json::json!(outcomes));
     }
 
     // Clear terminal handle (this will close the underlying IO)
@@ -107,7 +93,7 @@ pub async fn stop_session(session: &AISession) -> Result<()> {
     // Update status
     {
         let mut status = session.status.write().await;
-        *status = SessionStatus::Terminated;
+        session.transition(&mut status, SessionStatus::Terminated);
     }
 
     // Preserve a terminal Error/Done verdict if the session already produced
@@ -122,6 +108,52 @@ pub async fn stop_session(session: &AISession) -> Result<()> {
     Ok(())
 }
 
+/// Spawn the session's shell, on a PTY unless the config forces (or
+/// permits falling back to) a headless one.
+async fn spawn_terminal(session: &AISession) -> Result<TerminalHandle> {
+    let shell_env = std::env::var("SHELL").ok();
+    let shell = session
+        .config
+        .shell
+        .as_deref()
+        .or(shell_env.as_deref())
+        .unwrap_or("/bin/bash");
+
+    Ok(if session.config.force_headless {
+        TerminalHandle::Headless(
+            HeadlessHandle::spawn_shell(
+                shell,
+                &session.config.working_directory,
+                session.config.environment.iter(),
+            )
+            .await?,
+        )
+    } else {
+        match spawn_pty(&session.config, shell).await {
+            Ok(pty) => TerminalHandle::Pty(pty),
+            Err(err) => {
+                if session.config.allow_headless_fallback && is_permission_denied(&err) {
+                    tracing::warn!(
+                        "PTY unavailable ({}). Falling back to headless shell for session {}",
+                        err,
+                        session.id
+                    );
+                    TerminalHandle::Headless(
+                        HeadlessHandle::spawn_shell(
+                            shell,
+                            &session.config.working_directory,
+                            session.config.environment.iter(),
+                        )
+                        .await?,
+                    )
+                } else {
+                    return Err(err);
+                }
+            }
+        }
+    })
+}
+
 async fn spawn_pty(config: &SessionConfig, shell: &str) -> Result<PtyHandle> {
     let pty = PtyHandle::new(config.pty_size.0, config.pty_size.1)?;
     let mut cmd = CommandBuilder::new(shell);
@@ -146,23 +178,141 @@ fn is_permission_denied(err: &anyhow::Error) -> bool {
     })
 }
 
-/// Pause a session
+/// Pause a session: stop its process group, drain the output the processes
+/// produced up to that point into the session (returned by the next read),
+/// and record a `pause_checkpoint` in its metadata.
 pub async fn pause_session(session: &AISession) -> Result<()> {
     let mut status = session.status.write().await;
     if *status != SessionStatus::Running {
         return Err(anyhow::anyhow!("Session not running"));
     }
-    *status = SessionStatus::Paused;
+
+    let mut stopped_group = None;
+    let mut drained = 0;
+    if let Some(terminal) = session.terminal.read().await.as_ref() {
+        if let Some(pgid) = terminal.process_group().await {
+            suspend::signal_group(pgid, Suspend::Stop)?;
+            stopped_group = Some(pgid);
+        }
+        let pending = terminal.take_buffered().await?;
+        drained = pending.len();
+        session.held_output.write().await.extend(pending);
+    }
+    if let Some(process) = session.process.read().await.as_ref() {
+        suspend::signal_group(process.pid(), Suspend::Stop)
+            .or_else(|_| suspend::signal_tree(process.pid(), Suspend::Stop).map(|_| ()))?;
+    }
+
+    let checkpoint = serde_json::json!({
+        "paused_at": chrono::Utc::now(),
+        "process_group": stopped_group,
+        "held_output_bytes": session.held_output.read().await.len(),
+        "drained_bytes": drained,
+        "command_count": session.get_command_count().await,
+    });
+    session
+        .metadata
+        .write()
+        .await
+        .insert("pause_checkpoint".to_string(), checkpoint);
+
+    session.transition(&mut status, SessionStatus::Paused);
+    session.set_attention(AttentionState::Waiting);
     Ok(())
 }
 
-/// Resume a session
+/// Resume a session: continue its process group and mark it running.
 pub async fn resume_session(session: &AISession) -> Result<()> {
     let mut status = session.status.write().await;
     if *status != SessionStatus::Paused {
         return Err(anyhow::anyhow!("Session not paused"));
     }
-    *status = SessionStatus::Running;
+    continue_processes(session).await?;
+    session.transition(&mut status, SessionStatus::Running);
     *session.last_activity.write().await = chrono::Utc::now();
+    session.metadata.write().await.remove("pause_checkpoint");
+    session.set_attention(AttentionState::Running);
+    Ok(())
+}
+
+async fn continue_processes(session: &AISession) -> Result<()> {
+    if let Some(terminal) = session.terminal.read().await.as_ref()
+        && let Some(pgid) = terminal.process_group().await
+    {
+        suspend::signal_group(pgid, Suspend::Continue)?;
+    }
+    if let Some(process) = session.process.read().await.as_ref() {
+        suspend::signal_group(process.pid(), Suspend::Continue)
+            .or_else(|_| suspend::signal_tree(process.pid(), Suspend::Continue).map(|_| ()))?;
+    }
+    Ok(())
+}
+
+/// Hibernate a running session: drain the output its terminal still holds
+/// into the session (returned by the next read), shut the terminal and its
+/// processes down, and record the event under the `hibernation_events`
+/// metadata key. Context, history and metadata stay with the session.
+pub async fn hibernate_session(session: &AISession) -> Result<()> {
+    let mut status = session.status.write().await;
+    if *status != SessionStatus::Running {
+        return Err(anyhow::anyhow!("Session not running"));
+    }
+
+    let now = chrono::Utc::now();
+    let idle = now - *session.last_activity.read().await;
+    if let Some(terminal) = session.terminal.write().await.take() {
+        match terminal.take_buffered().await {
+            Ok(pending) => session.held_output.write().await.extend(pending),
+            Err(err) => tracing::warn!(
+                "Dropping unread output of hibernating session {}: {}",
+                session.id,
+                err
+            ),
+        }
+        terminal.shutdown().await?;
+    }
+    if let Some(mut process) = session.process.write().await.take() {
+        let _ = process.kill().await;
+    }
+
+    hibernation::record(
+        &mut *session.metadata.write().await,
+        HibernationEvent {
+            kind: HibernationEventKind::Hibernated,
+            at: now,
+            secs: idle.num_seconds().max(0),
+        },
+    );
+    session.transition(&mut status, SessionStatus::Hibernated);
+    session.set_attention(AttentionState::Idle);
+    Ok(())
+}
+
+/// Wake a hibernated session: re-spawn its shell in the configured working
+/// directory and environment and record the event. Waking a session that
+/// is not hibernated does nothing.
+pub async fn wake_session(session: &AISession) -> Result<()> {
+    let mut status = session.status.write().await;
+    if *status != SessionStatus::Hibernated {
+        return Ok(());
+    }
+
+    let terminal = spawn_terminal(session).await?;
+    *session.terminal.write().await = Some(terminal);
+
+    let now = chrono::Utc::now();
+    let mut metadata = session.metadata.write().await;
+    let slept = hibernation::hibernated_at(&metadata).map_or(0, |at| (now - at).num_seconds());
+    hibernation::record(
+        &mut metadata,
+        HibernationEvent {
+            kind: HibernationEventKind::Woke,
+            at: now,
+            secs: slept.max(0),
+        },
+    );
+    *session.last_activity.write().await = now;
+    session.transition(&mut status, SessionStatus::Running);
+    session.set_attention(AttentionState::Running);
     Ok(())
 }
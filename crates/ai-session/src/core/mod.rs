@@ -7,6 +7,7 @@
 //!
 //! - **AISession**: Advanced terminal session with AI capabilities
 //! - **SessionManager**: Pool-based session management with automatic cleanup
+//! - **Namespace**: Isolated tenants sharing one manager
 //! - **SessionConfig**: Comprehensive configuration for AI features and performance
 //! - **Context Integration**: Seamless integration with AI conversation context
 //!
@@ -70,7 +71,7 @@
 //! ```
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -82,14 +83,28 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 
 pub mod attention;
+pub mod events;
+pub mod expect;
 pub mod headless;
+pub mod hibernation;
 pub mod lifecycle;
+pub mod namespace;
+pub mod observer;
 pub mod process;
 pub mod pty;
+pub mod stop_hooks;
+pub mod suspend;
 pub mod terminal;
+pub mod transfer;
 
 pub use attention::AttentionState;
 use attention::AttentionTracker;
+pub use events::{CommandEvent, HookId, SessionEvent, StateChange};
+use events::{EventHooks, HookRegistry};
+pub use expect::{ExpectMatch, ExpectPattern, ExpectTimeout};
+use observer::OutputBroadcaster;
+pub use observer::{OutputChunk, OutputWatcher};
+pub use terminal::TerminalSignal;
 
 use crate::context::SessionContext;
 use crate::persistence::CommandRecord;
@@ -172,6 +187,9 @@ pub enum SessionStatus {
     Running,
     /// Session is paused
     Paused,
+    /// Session's terminal was shut down while idle; the next input
+    /// re-spawns it
+    Hibernated,
     /// Session is being terminated
     Terminating,
     /// Session has been terminated
@@ -214,6 +232,14 @@ pub struct SessionConfig {
     pub force_headless: bool,
     /// Allow automatic fallback to headless mode when PTY creation fails
     pub allow_headless_fallback: bool,
+    /// Cleanup commands run, in order, before the terminal is torn down on
+    /// [`AISession::stop`] (e.g. `git stash`, `docker compose down`)
+    pub on_stop: Vec<stop_hooks::StopHook>,
+    /// Idle time after which [`SessionManager::hibernate_idle`] shuts the
+    /// session's terminal down until its next input (None: never)
+    pub hibernate_after: Option<Duration>,
+    /// Limit for each [`AISession::execute_command`] (None: no limit)
+    pub command_timeout: Option<crate::execution::CommandTimeout>,
 }
 
 /// Context configuration for AI features
@@ -244,6 +270,9 @@ impl Default for SessionConfig {
             agent_role: None,
             force_headless: false,
             allow_headless_fallback: true,
+            on_stop: Vec::new(),
+            hibernate_after: None,
+            command_timeout: None,
         }
     }
 }
@@ -285,6 +314,15 @@ pub struct AISession {
     pub total_tokens: Arc<RwLock<usize>>,
     /// Attention state — what does this session need next?
     attention: AttentionTracker,
+    /// Fan-out of terminal output to read-only observers
+    observers: OutputBroadcaster,
+    /// Output drained from the terminal when the session was paused; the
+    /// next read returns it first.
+    held_output: Arc<RwLock<Vec<u8>>>,
+    /// Output read by [`AISession::expect_any`] past its last match
+    expect_buffer: Arc<tokio::sync::Mutex<Vec<u8>>>,
+    /// Handlers for the session's output, commands and status changes
+    hooks: EventHooks,
 }
 
 impl AISession {
@@ -307,6 +345,10 @@ impl AISession {
             command_count: Arc::new(RwLock::new(0)),
             total_tokens: Arc::new(RwLock::new(0)),
             attention: AttentionTracker::new(AttentionState::Idle),
+            observers: OutputBroadcaster::new(),
+            held_output: Arc::new(RwLock::new(Vec::new())),
+            expect_buffer: Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            hooks: EventHooks::default(),
         })
     }
 
@@ -332,6 +374,10 @@ impl AISession {
             command_count: Arc::new(RwLock::new(0)),
             total_tokens: Arc::new(RwLock::new(0)),
             attention: AttentionTracker::new(AttentionState::Idle),
+            observers: OutputBroadcaster::new(),
+            held_output: Arc::new(RwLock::new(Vec::new())),
+            expect_buffer: Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            hooks: EventHooks::default(),
         })
     }
 
@@ -340,16 +386,148 @@ impl AISession {
         lifecycle::start_session(self).await
     }
 
-    /// Stop the session
+    /// Stop the session, running its `on_stop` hooks first
     pub async fn stop(&self) -> Result<()> {
-        lifecycle::stop_session(self).await
+        lifecycle::stop_session(self, true).await
+    }
+
+    /// Stop the session without running its `on_stop` hooks
+    pub async fn kill(&self) -> Result<()> {
+        lifecycle::stop_session(self, false).await
+    }
+
+    /// Suspend the session's processes (`SIGSTOP` to its process group),
+    /// keeping the output they produced so far for the next read.
+    pub async fn pause(&self) -> Result<()> {
+        lifecycle::pause_session(self).await
+    }
+
+    /// Continue a paused session's processes.
+    pub async fn resume(&self) -> Result<()> {
+        lifecycle::resume_session(self).await
+    }
+
+    /// Whether the session is paused.
+    pub async fn is_paused(&self) -> bool {
+        *self.status.read().await == SessionStatus::Paused
+    }
+
+    /// Shut the session's terminal down until its next input, keeping
+    /// unread output, context and history (see [`hibernation`]).
+    pub async fn hibernate(&self) -> Result<()> {
+        lifecycle::hibernate_session(self).await
+    }
+
+    /// Re-spawn a hibernated session's terminal. Input does this on its
+    /// own; call it to warm a session up ahead of a task.
+    pub async fn wake(&self) -> Result<()> {
+        lifecycle::wake_session(self).await
+    }
+
+    /// Whether the session is hibernated.
+    pub async fn is_hibernated(&self) -> bool {
+        *self.status.read().await == SessionStatus::Hibernated
+    }
+
+    /// The session's hibernations and wake-ups, oldest first.
+    pub async fn hibernation_events(&self) -> Vec<hibernation::HibernationEvent> {
+        hibernation::events(&*self.metadata.read().await)
+    }
+
+    /// Whether the session is running and has been idle for at least its
+    /// `hibernate_after` at `now`.
+    pub async fn is_due_for_hibernation(&self, now: DateTime<Utc>) -> bool {
+        let Some(after) = self.config.hibernate_after else {
+            return false;
+        };
+        let idle = now - *self.last_activity.read().await;
+        *self.status.read().await == SessionStatus::Running
+            && idle.to_std().is_ok_and(|idle| idle >= after)
+    }
+
+    /// Copy host file `local` to `path` in the session's working directory
+    /// (see [`transfer`]), reporting progress as it goes.
+    pub async fn put_file(
+        &self,
+        local: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+        on_progress: impl FnMut(transfer::TransferProgress),
+    ) -> Result<transfer::TransferReport> {
+        transfer::put(
+            &self.config.working_directory,
+            local.as_ref(),
+            path.as_ref(),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Copy `path` in the session's working directory to host file `local`
+    /// (see [`transfer`]), reporting progress as it goes.
+    pub async fn get_file(
+        &self,
+        path: impl AsRef<Path>,
+        local: impl AsRef<Path>,
+        on_progress: impl FnMut(transfer::TransferProgress),
+    ) -> Result<transfer::TransferReport> {
+        transfer::get(
+            &self.config.working_directory,
+            path.as_ref(),
+            local.as_ref(),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Resize the session's terminal, e.g. when an attached client's window
+    /// changes. Programs in the session get `SIGWINCH` and redraw.
+    /// A hibernated session is woken first.
+    pub async fn resize(&self, cols: u16, rows: u16) -> Result<()> {
+        self.wake().await?;
+        let terminal = self.terminal.read().await;
+        let terminal = terminal
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Session not started"))?;
+        terminal.resize(rows, cols).await
+    }
+
+    /// Forward a signal from an attached client to the session's
+    /// foreground job.
+    pub async fn signal(&self, signal: TerminalSignal) -> Result<()> {
+        if self.is_hibernated().await {
+            return Ok(()); // Nothing is running to receive it
+        }
+        let terminal = self.terminal.read().await;
+        let terminal = terminal
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Session not started"))?;
+        terminal.signal(signal).await?;
+        *self.last_activity.write().await = Utc::now();
+        Ok(())
     }
 
     /// Send input to the session
+    #[tracing::instrument(
+        name = "session.send_input",
+        level = "debug",
+        skip_all,
+        fields(session_id = %self.id, bytes = input.len())
+    )]
     pub async fn send_input(&self, input: &str) -> Result<()> {
+        self.send_raw(input.as_bytes()).await
+    }
+
+    /// Send raw bytes to the session, e.g. keystrokes from an attached
+    /// terminal (control characters included). A hibernated session is
+    /// woken first.
+    pub async fn send_raw(&self, data: &[u8]) -> Result<()> {
+        if self.is_paused().await {
+            return Err(anyhow::anyhow!("Session {} is paused", self.id));
+        }
+        self.wake().await?;
         let terminal_guard = self.terminal.read().await;
         if let Some(terminal) = terminal_guard.as_ref() {
-            terminal.write(input.as_bytes()).await?;
+            terminal.write(data).await?;
             *self.last_activity.write().await = Utc::now();
             Ok(())
         } else {
@@ -357,12 +535,68 @@ impl AISession {
         }
     }
 
+    /// Send `line` followed by a newline, as if typed at the prompt
+    pub async fn send_line(&self, line: &str) -> Result<()> {
+        self.send_raw(format!("{line}\n").as_bytes()).await
+    }
+
+    /// Wait up to `timeout` for output matching `pattern` (see the
+    /// [`expect` module](crate::core::expect))
+    pub async fn expect(
+        &self,
+        pattern: impl Into<ExpectPattern>,
+        timeout: Duration,
+    ) -> Result<ExpectMatch> {
+        self.expect_any(&[pattern.into()], timeout).await
+    }
+
+    /// Wait up to `timeout` for output matching any of `patterns`; the
+    /// earliest match wins and [`ExpectMatch::index`] says which pattern it
+    /// was, so a script can branch on it. Fails with [`ExpectTimeout`].
+    #[tracing::instrument(name = "session.expect", level = "debug", skip_all, fields(session_id = %self.id))]
+    pub async fn expect_any(
+        &self,
+        patterns: &[ExpectPattern],
+        timeout: Duration,
+    ) -> Result<ExpectMatch> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut buffer = self.expect_buffer.lock().await;
+        loop {
+            if let Some((found, end)) = expect::find(&buffer, patterns) {
+                buffer.drain(..end);
+                return Ok(found);
+            }
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                return Err(ExpectTimeout::new(patterns, timeout, &buffer).into());
+            }
+            // A PTY read blocks until output arrives; a headless one
+            // returns at once, empty when there is nothing yet.
+            if let Ok(chunk) = tokio::time::timeout(remaining, self.read_output()).await {
+                let chunk = chunk?;
+                if chunk.is_empty() {
+                    tokio::time::sleep(remaining.min(expect::POLL_INTERVAL)).await;
+                }
+                buffer.extend(chunk);
+            }
+        }
+    }
+
     /// Read output from the session
+    #[tracing::instrument(name = "session.read_output", level = "trace", skip_all, fields(session_id = %self.id))]
     pub async fn read_output(&self) -> Result<Vec<u8>> {
+        // Status before the terminal: hibernating takes them in that order.
+        let status = self.status().await;
         let terminal = self.terminal.read().await;
-        if let Some(terminal) = terminal.as_ref() {
-            let output = terminal.read().await?;
+        if terminal.is_some() || status == SessionStatus::Hibernated {
+            let mut output = std::mem::take(&mut *self.held_output.write().await);
+            if let Some(terminal) = terminal.as_ref()
+                && status != SessionStatus::Paused
+            {
+                output.extend(terminal.read().await?);
+            }
             *self.last_activity.write().await = Utc::now();
+            self.publish_output(&output);
             Ok(output)
         } else {
             Err(anyhow::anyhow!("Session not started"))
@@ -385,8 +619,25 @@ impl AISession {
         self.metadata.read().await.get(key).cloned()
     }
 
-    /// Execute a command and record it in history
+    /// Execute a command and record it in history, stopping it once it
+    /// outruns the session's `command_timeout`
     pub async fn execute_command(&self, command: &str) -> Result<String> {
+        self.execute_command_with_timeout(command, self.config.command_timeout.as_ref())
+            .await
+    }
+
+    /// Execute a command like [`Self::execute_command`] under its own limit
+    /// instead of the session's (None: no limit). A command that times out
+    /// still returns its partial output; its history record says it timed out.
+    #[tracing::instrument(name = "session.execute_command", skip_all, fields(session_id = %self.id))]
+    pub async fn execute_command_with_timeout(
+        &self,
+        command: &str,
+        timeout: Option<&crate::execution::CommandTimeout>,
+    ) -> Result<String> {
+        if self.is_paused().await {
+            return Err(anyhow::anyhow!("Session {} is paused", self.id));
+        }
         let start_time = Utc::now();
 
         let shell_env = std::env::var("SHELL").ok();
@@ -404,9 +655,13 @@ impl AISession {
             cmd.env(key, value);
         }
 
-        let execution =
-            crate::execution::run_provider_command(cmd, &self.config.working_directory, shell)
-                .await?;
+        let working_dir = &self.config.working_directory;
+        let execution = match timeout {
+            Some(timeout) => {
+                crate::execution::run_command_with_timeout(cmd, working_dir, shell, timeout).await?
+            }
+            None => crate::execution::run_provider_command(cmd, working_dir, shell).await?,
+        };
         *self.last_activity.write().await = Utc::now();
         let output = if execution.stderr.is_empty() {
             execution.stdout
@@ -415,6 +670,7 @@ impl AISession {
         } else {
             format!("{}{}", execution.stdout, execution.stderr)
         };
+        self.publish_output(output.as_bytes());
 
         // Record the command in history
         let end_time = Utc::now();
@@ -432,11 +688,27 @@ impl AISession {
                 output.clone()
             },
             duration_ms,
+            timed_out: execution.timed_out.is_some(),
         };
+        if let Some(timed_out) = &execution.timed_out {
+            tracing::warn!(
+                "Command timed out after {}s, stopped with {}: {}",
+                timed_out.timeout_secs,
+                timed_out.signal,
+                command
+            );
+        }
 
         // Update history and counters
-        self.command_history.write().await.push(record);
+        self.command_history.write().await.push(record.clone());
         *self.command_count.write().await += 1;
+        self.hooks.emit(
+            &self.id,
+            SessionEvent::Command(CommandEvent {
+                record,
+                output: output.clone(),
+            }),
+        );
 
         Ok(output)
     }
@@ -491,6 +763,92 @@ impl AISession {
         Some(next)
     }
 
+    /// Attach a read-only observer to this session's live output.
+    ///
+    /// Any number of watchers can be attached at once; none of them can send
+    /// input, and a slow watcher skips chunks rather than stalling the session.
+    pub fn watch_output(&self) -> OutputWatcher {
+        self.observers.subscribe()
+    }
+
+    /// Number of observers currently attached to this session.
+    pub fn observer_count(&self) -> usize {
+        self.observers.observer_count()
+    }
+
+    /// Call `handler` with each chunk of output the session reads (see
+    /// [`events`] for delivery order and panics).
+    pub fn on_output<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, OutputChunk) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.own.on_output(handler)
+    }
+
+    /// Call `handler` after each command run through `execute_command`.
+    pub fn on_command<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, CommandEvent) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.own.on_command(handler)
+    }
+
+    /// Call `handler` on each change of session status.
+    pub fn on_state_change<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, StateChange) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.own.on_state_change(handler)
+    }
+
+    /// Call `handler` with every event of the session.
+    pub fn on_event<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, SessionEvent) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.own.on_event(handler)
+    }
+
+    /// Unregister a handler added to this session. Returns whether it was
+    /// registered here.
+    pub fn remove_hook(&self, id: HookId) -> bool {
+        self.hooks.own.remove(id)
+    }
+
+    /// Hand output to observers and `on_output` handlers.
+    fn publish_output(&self, data: &[u8]) {
+        self.observers.publish(data);
+        if !data.is_empty() {
+            self.hooks.emit(
+                &self.id,
+                SessionEvent::Output(OutputChunk {
+                    timestamp: Utc::now(),
+                    data: data.to_vec(),
+                }),
+            );
+        }
+    }
+
+    /// Set `status` (the session's, write-locked) to `to`, telling
+    /// `on_state_change` handlers while the lock still orders transitions.
+    pub(crate) fn transition(&self, status: &mut SessionStatus, to: SessionStatus) {
+        let from = std::mem::replace(status, to);
+        if from != to {
+            self.hooks.emit(
+                &self.id,
+                SessionEvent::StateChange(StateChange {
+                    from,
+                    to,
+                    at: Utc::now(),
+                }),
+            );
+        }
+    }
+
     /// Clear command history (keep recent N commands)
     pub async fn trim_command_history(&self, keep_recent: usize) {
         let mut history = self.command_history.write().await;
@@ -599,6 +957,10 @@ pub struct SessionManager {
     sessions: Arc<DashMap<SessionId, Arc<AISession>>>,
     /// Default session configuration
     default_config: SessionConfig,
+    /// Handlers every managed session inherits
+    hooks: Arc<HookRegistry>,
+    /// Isolated tenants, by name
+    namespaces: Arc<DashMap<String, Arc<namespace::Namespace>>>,
 }
 
 impl SessionManager {
@@ -607,6 +969,8 @@ impl SessionManager {
         Self {
             sessions: Arc::new(DashMap::new()),
             default_config: SessionConfig::default(),
+            hooks: Arc::new(HookRegistry::default()),
+            namespaces: Arc::new(DashMap::new()),
         }
     }
 
@@ -621,7 +985,9 @@ impl SessionManager {
         &self,
         config: SessionConfig,
     ) -> Result<Arc<AISession>> {
-        let session = Arc::new(AISession::new(config).await?);
+        let mut session = AISession::new(config).await?;
+        session.hooks = EventHooks::inheriting(self.hooks.clone());
+        let session = Arc::new(session);
         self.sessions.insert(session.id.clone(), session.clone());
         Ok(session)
     }
@@ -638,11 +1004,57 @@ impl SessionManager {
             return Err(SessionError::AlreadyExists(id).into());
         }
 
-        let session = Arc::new(AISession::new_with_id(id.clone(), config, created_at).await?);
+        let mut session = AISession::new_with_id(id.clone(), config, created_at).await?;
+        session.hooks = EventHooks::inheriting(self.hooks.clone());
+        let session = Arc::new(session);
         self.sessions.insert(id, session.clone());
         Ok(session)
     }
 
+    /// Call `handler` with the output of every session this manager has
+    /// created or restored, or will. Manager handlers run before a
+    /// session's own.
+    pub fn on_output<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, OutputChunk) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.on_output(handler)
+    }
+
+    /// Call `handler` after each command any managed session runs.
+    pub fn on_command<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, CommandEvent) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.on_command(handler)
+    }
+
+    /// Call `handler` on each status change of any managed session.
+    pub fn on_state_change<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, StateChange) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.on_state_change(handler)
+    }
+
+    /// Call `handler` with every event of any managed session.
+    pub fn on_event<F, Fut>(&self, handler: F) -> HookId
+    where
+        F: Fn(SessionId, SessionEvent) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.on_event(handler)
+    }
+
+    /// Unregister a handler added to this manager. Returns whether it was
+    /// registered here.
+    pub fn remove_hook(&self, id: HookId) -> bool {
+        self.hooks.remove(id)
+    }
+
     /// Get a session by ID
     pub fn get_session(&self, id: &SessionId) -> Option<Arc<AISession>> {
         self.sessions.get(id).map(|entry| entry.clone())
@@ -672,6 +1084,31 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Remove a session without running its `on_stop` hooks
+    pub async fn kill_session(&self, id: &SessionId) -> Result<()> {
+        if let Some((_, session)) = self.sessions.remove(id) {
+            session.kill().await?;
+        }
+        Ok(())
+    }
+
+    /// Hibernate every running session that has been idle for longer than
+    /// its `hibernate_after`, returning their IDs. Call it periodically.
+    pub async fn hibernate_idle(&self) -> Vec<SessionId> {
+        let now = Utc::now();
+        let mut hibernated = Vec::new();
+        for session in self.list_session_refs() {
+            if !session.is_due_for_hibernation(now).await {
+                continue;
+            }
+            match session.hibernate().await {
+                Ok(()) => hibernated.push(session.id.clone()),
+                Err(e) => tracing::warn!("Failed to hibernate session {}: {}", session.id, e),
+            }
+        }
+        hibernated
+    }
+
     /// Clean up terminated sessions
     pub async fn cleanup_terminated(&self) -> Result<usize> {
         let mut removed = 0;
@@ -832,4 +1269,345 @@ mod tests {
         assert_eq!(history.len(), 1);
         assert_eq!(history[0].exit_code, Some(0));
     }
+
+    #[tokio::test]
+    async fn stop_runs_on_stop_hooks_before_terminal_shutdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SessionConfig {
+            working_directory: dir.path().to_path_buf(),
+            shell: Some("/bin/sh".to_string()),
+            force_headless: true,
+            on_stop: vec![
+                stop_hooks::StopHook::new("echo cleaned > cleanup.txt"),
+                stop_hooks::StopHook::new("exit 7"),
+            ],
+            ..SessionConfig::default()
+        };
+
+        let session = AISession::new(config.clone()).await.unwrap();
+        session.start().await.unwrap();
+        session.stop().await.unwrap();
+        assert_eq!(session.status().await, SessionStatus::Terminated);
+        let cleanup = std::fs::read_to_string(dir.path().join("cleanup.txt")).unwrap();
+        assert_eq!(cleanup.trim(), "cleaned");
+        let outcomes = session.get_metadata("stop_hooks").await.unwrap();
+        assert_eq!(outcomes[0]["exit_code"], 0);
+        assert_eq!(outcomes[1]["exit_code"], 7);
+
+        std::fs::remove_file(dir.path().join("cleanup.txt")).unwrap();
+        let killed = AISession::new(config).await.unwrap();
+        killed.start().await.unwrap();
+        killed.kill().await.unwrap();
+        assert!(!dir.path().join("cleanup.txt").exists());
+        assert!(killed.get_metadata("stop_hooks").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn manager_hooks_see_commands_output_and_status_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = SessionManager::new();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = seen.clone();
+        manager.on_state_change(move |_, change| {
+            let log = log.clone();
+            async move { log.lock().unwrap().push(format!("{:?}", change.to)) }
+        });
+        let log = seen.clone();
+        manager.on_command(move |_, command| {
+            let log = log.clone();
+            async move {
+                log.lock()
+                    .unwrap()
+                    .push(format!("{} -> {}", command.record.command, command.output))
+            }
+        });
+
+        let session = manager
+            .create_session_with_config(SessionConfig {
+                working_directory: dir.path().to_path_buf(),
+                shell: Some("/bin/sh".to_string()),
+                force_headless: true,
+                ..SessionConfig::default()
+            })
+            .await
+            .unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        session.on_output(move |id, chunk| {
+            let tx = tx.clone();
+            async move {
+                let _ = tx.send((id, chunk.text()));
+            }
+        });
+
+        session.start().await.unwrap();
+        session.execute_command("printf hooked").await.unwrap();
+        session.stop().await.unwrap();
+
+        let (id, text) = rx.recv().await.unwrap();
+        assert_eq!((id, text.as_str()), (session.id.clone(), "hooked"));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                "Running",
+                "printf hooked -> hooked",
+                "Terminating",
+                "Terminated"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn commands_outrunning_their_timeout_are_stopped_and_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = AISession::new(SessionConfig {
+            working_directory: dir.path().to_path_buf(),
+            shell: Some("/bin/sh".to_string()),
+            force_headless: true,
+            command_timeout: Some(
+                crate::execution::CommandTimeout::new(Duration::from_secs(1))
+                    .with_escalation(Duration::from_millis(200)),
+            ),
+            ..SessionConfig::default()
+        })
+        .await
+        .unwrap();
+
+        let output = session
+            .execute_command("echo begun; sleep 30")
+            .await
+            .unwrap();
+        assert_eq!(output, "begun\n");
+        let record = session.get_command_history().await.pop().unwrap();
+        assert!(record.timed_out);
+        assert!(record.duration_ms < 10_000);
+
+        // A task's own limit replaces the session's.
+        session
+            .execute_command_with_timeout("sleep 1.5; printf done", None)
+            .await
+            .unwrap();
+        let record = session.get_command_history().await.pop().unwrap();
+        assert!(!record.timed_out);
+        assert_eq!(record.exit_code, Some(0));
+    }
+
+    #[tokio::test]
+    async fn scripts_answer_prompts_and_branch_on_what_comes_back() {
+        let session = AISession::new(SessionConfig {
+            shell: Some("/bin/sh".to_string()),
+            force_headless: true,
+            ..SessionConfig::default()
+        })
+        .await
+        .unwrap();
+        session.start().await.unwrap();
+        let login =
+            r#"printf 'Password: '; read pw; [ "$pw" = secret ] && echo welcome || echo denied"#;
+        let outcomes = || ["welcome".into(), ExpectPattern::regex("den(ied)").unwrap()];
+
+        session.send_line(login).await.unwrap();
+        session
+            .expect("Password: ", Duration::from_secs(5))
+            .await
+            .unwrap();
+        session.send_line("secret").await.unwrap();
+        let reply = session
+            .expect_any(&outcomes(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!((reply.index, reply.matched.as_str()), (0, "welcome"));
+
+        session.send_line(login).await.unwrap();
+        session
+            .expect("Password: ", Duration::from_secs(5))
+            .await
+            .unwrap();
+        session.send_line("guess").await.unwrap();
+        let reply = session
+            .expect_any(&outcomes(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(reply.index, 1);
+        assert_eq!(reply.captures[1].as_deref(), Some("ied"));
+
+        session.send_line("echo late").await.unwrap();
+        let err = session
+            .expect("never", Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        let timeout = err
+            .downcast_ref::<ExpectTimeout>()
+            .expect("an expect timeout");
+        assert!(timeout.output.contains("late"), "{timeout}");
+        // What the timed-out expectation read is still there for the next.
+        session
+            .expect("late", Duration::from_secs(1))
+            .await
+            .unwrap();
+        session.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn pause_stops_the_process_group_and_keeps_output() {
+        let config = SessionConfig {
+            shell: Some("/bin/sh".to_string()),
+            force_headless: true,
+            ..SessionConfig::default()
+        };
+        let session = AISession::new(config).await.unwrap();
+        session.start().await.unwrap();
+        session.send_input("echo before-pause\n").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        session.pause().await.unwrap();
+        assert!(session.is_paused().await);
+        assert_eq!(session.attention(), AttentionState::Waiting);
+        assert!(session.get_metadata("pause_checkpoint").await.is_some());
+        assert!(session.send_input("echo nope\n").await.is_err());
+        assert!(session.execute_command("true").await.is_err());
+
+        let pgid = session
+            .terminal
+            .read()
+            .await
+            .as_ref()
+            .unwrap()
+            .process_group()
+            .await
+            .unwrap();
+        let state = suspend::wait_for_state(pgid, "T").await;
+        assert!(state.as_deref().is_none_or(|s| s == "T"), "{state:?}");
+        let held = session.read_output().await.unwrap();
+        assert!(String::from_utf8_lossy(&held).contains("before-pause"));
+
+        session.resume().await.unwrap();
+        assert!(session.get_metadata("pause_checkpoint").await.is_none());
+        session.send_input("echo after-resume\n").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let output = session.read_output().await.unwrap();
+        assert!(String::from_utf8_lossy(&output).contains("after-resume"));
+        session.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn idle_sessions_hibernate_and_wake_on_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = SessionManager::new();
+        let mut config = SessionConfig {
+            working_directory: dir.path().to_path_buf(),
+            shell: Some("/bin/sh".to_string()),
+            force_headless: true,
+            hibernate_after: Some(Duration::ZERO),
+            ..SessionConfig::default()
+        };
+        let idle = manager
+            .create_session_with_config(config.clone())
+            .await
+            .unwrap();
+        config.hibernate_after = None;
+        let busy = manager.create_session_with_config(config).await.unwrap();
+        idle.start().await.unwrap();
+        busy.start().await.unwrap();
+        idle.send_input("echo before-hibernate\n").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        assert_eq!(manager.hibernate_idle().await, vec![idle.id.clone()]);
+        assert!(idle.is_hibernated().await);
+        assert_eq!(busy.status().await, SessionStatus::Running);
+        assert!(idle.terminal.read().await.is_none());
+        let held = idle.read_output().await.unwrap();
+        assert!(String::from_utf8_lossy(&held).contains("before-hibernate"));
+        assert!(idle.is_hibernated().await, "reading must not wake it");
+
+        idle.send_input("pwd\n").await.unwrap();
+        assert_eq!(idle.status().await, SessionStatus::Running);
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let output = idle.read_output().await.unwrap();
+        let dir_name = dir.path().file_name().unwrap().to_string_lossy();
+        assert!(String::from_utf8_lossy(&output).contains(dir_name.as_ref()));
+
+        let kinds: Vec<_> = idle
+            .hibernation_events()
+            .await
+            .iter()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                hibernation::HibernationEventKind::Hibernated,
+                hibernation::HibernationEventKind::Woke
+            ]
+        );
+
+        idle.hibernate().await.unwrap();
+        idle.stop().await.unwrap();
+        assert_eq!(idle.status().await, SessionStatus::Terminated);
+        busy.stop().await.unwrap();
+    }
+
+    #[test]
+    fn terminal_signal_names_parse() {
+        assert_eq!(
+            TerminalSignal::parse("SIGINT"),
+            Some(TerminalSignal::Interrupt)
+        );
+        assert_eq!(
+            TerminalSignal::parse("term"),
+            Some(TerminalSignal::Terminate)
+        );
+        assert_eq!(TerminalSignal::parse("kill"), None);
+        assert_eq!(TerminalSignal::Interrupt.control_char(), Some(0x03));
+        assert_eq!(TerminalSignal::Hangup.control_char(), None);
+    }
+
+    #[cfg_attr(not(feature = "native-pty-tests"), ignore)]
+    #[tokio::test]
+    async fn resize_and_interrupt_reach_the_foreground_job() {
+        let config = SessionConfig {
+            shell: Some("/bin/sh".to_string()),
+            ..SessionConfig::default()
+        };
+        let session = AISession::new(config).await.unwrap();
+        session.start().await.unwrap();
+        // Echoed input contains the words too; wait for them as a line
+        // (possibly behind a prompt).
+        let has_line = |text: &str, line: &str| {
+            text.lines()
+                .any(|l| l.trim().trim_start_matches(['#', '$']).trim() == line)
+        };
+        let read_until = async |line: &str| {
+            let mut seen = String::new();
+            for _ in 0..50 {
+                let Ok(chunk) =
+                    tokio::time::timeout(std::time::Duration::from_secs(2), session.read_output())
+                        .await
+                else {
+                    break;
+                };
+                let chunk = chunk.unwrap();
+                seen.push_str(&String::from_utf8_lossy(&chunk));
+                if has_line(&seen, line) {
+                    break;
+                }
+            }
+            seen
+        };
+
+        session.resize(100, 40).await.unwrap();
+        session.send_input("stty size\n").await.unwrap();
+        let size = read_until("40 100").await;
+        assert!(has_line(&size, "40 100"), "{size:?}");
+
+        // Ctrl+C stops the foreground `sleep`, not the shell.
+        session.send_input("sleep 30; echo slept\n").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        session.signal(TerminalSignal::Interrupt).await.unwrap();
+        session.send_input("echo still-here\n").await.unwrap();
+        let output = read_until("still-here").await;
+        assert!(has_line(&output, "still-here"), "{output}");
+        assert!(!has_line(&output, "slept"), "{output}");
+        session.kill().await.unwrap();
+    }
 }
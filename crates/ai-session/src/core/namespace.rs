@@ -0,0 +1,253 @@
+//! Namespaces: isolated tenants inside one [`SessionManager`].
+//!
+//! A hosted or multi-project deployment runs a single manager but must keep
+//! its tenants apart. Each [`Namespace`] has its own session listing, its
+//! own [`MessageBus`] and its own storage directory (`<storage root>/<name>`).
+//! A session created through a namespace can only be looked up through it; the
+//! manager still sees every session, so host-wide chores such as
+//! [`SessionManager::hibernate_idle`] keep covering them.
+//!
+//! ```no_run
+//! use ai_session::SessionManager;
+//! use ai_session::core::namespace::NamespaceConfig;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let manager = SessionManager::new();
+//! let acme = manager.create_namespace(
+//!     "acme",
+//!     NamespaceConfig {
+//!         storage_root: "/var/lib/ai-session/tenants".into(),
+//!     },
+//! )?;
+//! let session = acme.create_session().await?;
+//! assert!(acme.get_session(&session.id).is_some());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Result, bail};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use super::events::{EventHooks, HookRegistry};
+use super::{AISession, SessionConfig, SessionId, SessionManager};
+use crate::coordination::MessageBus;
+
+/// Metadata key a namespaced session carries its namespace under.
+pub const NAMESPACE_METADATA_KEY: &str = "namespace";
+
+/// Longest accepted namespace name.
+const MAX_NAME_LEN: usize = 64;
+
+/// How a namespace is set up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceConfig {
+    /// Directory holding every namespace's state; this namespace stores
+    /// under `<storage_root>/<name>`.
+    pub storage_root: PathBuf,
+}
+
+impl Default for NamespaceConfig {
+    fn default() -> Self {
+        Self {
+            storage_root: dirs::data_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("ai-session")
+                .join("namespaces"),
+        }
+    }
+}
+
+/// One tenant of a [`SessionManager`].
+pub struct Namespace {
+    name: String,
+    storage_path: PathBuf,
+    sessions: DashMap<SessionId, Arc<AISession>>,
+    message_bus: Arc<MessageBus>,
+    /// The manager's own listing, kept in step with `sessions`.
+    host_sessions: Arc<DashMap<SessionId, Arc<AISession>>>,
+    hooks: Arc<HookRegistry>,
+}
+
+impl Namespace {
+    /// The namespace's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The namespace's own directory, for a [`PersistenceManager`] or other
+    /// state kept apart from other tenants.
+    ///
+    /// [`PersistenceManager`]: crate::persistence::PersistenceManager
+    pub fn storage_path(&self) -> &std::path::Path {
+        &self.storage_path
+    }
+
+    /// The namespace's message bus; agents registered here cannot reach
+    /// agents of another namespace.
+    pub fn message_bus(&self) -> &Arc<MessageBus> {
+        &self.message_bus
+    }
+
+    /// Create a session in this namespace with the default config
+    pub async fn create_session(&self) -> Result<Arc<AISession>> {
+        self.create_session_with_config(SessionConfig::default())
+            .await
+    }
+
+    /// Create a session in this namespace
+    pub async fn create_session_with_config(
+        &self,
+        config: SessionConfig,
+    ) -> Result<Arc<AISession>> {
+        let mut session = AISession::new(config).await?;
+        session.hooks = EventHooks::inheriting(self.hooks.clone());
+        session
+            .set_metadata(
+                NAMESPACE_METADATA_KEY.to_string(),
+                serde_json::Value::String(self.name.clone()),
+            )
+            .await?;
+        let session = Arc::new(session);
+        self.sessions.insert(session.id.clone(), session.clone());
+        self.host_sessions
+            .insert(session.id.clone(), session.clone());
+        Ok(session)
+    }
+
+    /// Get one of this namespace's sessions by ID
+    pub fn get_session(&self, id: &SessionId) -> Option<Arc<AISession>> {
+        self.sessions.get(id).map(|entry| entry.clone())
+    }
+
+    /// List this namespace's sessions
+    pub fn list_sessions(&self) -> Vec<SessionId> {
+        self.sessions
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    /// Stop and remove one of this namespace's sessions
+    pub async fn remove_session(&self, id: &SessionId) -> Result<()> {
+        if let Some((_, session)) = self.sessions.remove(id) {
+            self.host_sessions.remove(id);
+            session.stop().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Names double as directory names, so keep them to a safe alphabet.
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > MAX_NAME_LEN
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "invalid namespace name '{}': use 1-{} letters, digits, '-' or '_'",
+            name,
+            MAX_NAME_LEN
+        );
+    }
+    Ok(())
+}
+
+impl SessionManager {
+    /// Create the namespace `name`
+    pub fn create_namespace(&self, name: &str, config: NamespaceConfig) -> Result<Arc<Namespace>> {
+        validate_name(name)?;
+        let entry = match self.namespaces.entry(name.to_string()) {
+            dashmap::mapref::entry::Entry::Occupied(_) => {
+                bail!("namespace '{}' already exists", name)
+            }
+            dashmap::mapref::entry::Entry::Vacant(entry) => entry,
+        };
+        let storage_path = config.storage_root.join(name);
+        let namespace = Arc::new(Namespace {
+            name: name.to_string(),
+            storage_path,
+            sessions: DashMap::new(),
+            message_bus: Arc::new(MessageBus::new()),
+            host_sessions: self.sessions.clone(),
+            hooks: self.hooks.clone(),
+        });
+        entry.insert(namespace.clone());
+        Ok(namespace)
+    }
+
+    /// Get a namespace by name
+    pub fn namespace(&self, name: &str) -> Option<Arc<Namespace>> {
+        self.namespaces.get(name).map(|entry| entry.clone())
+    }
+
+    /// List namespace names, sorted
+    pub fn list_namespaces(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .namespaces
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Remove a namespace, stopping its sessions. Its persisted state is
+    /// left on disk.
+    pub async fn remove_namespace(&self, name: &str) -> Result<()> {
+        let Some((_, namespace)) = self.namespaces.remove(name) else {
+            return Ok(());
+        };
+        for id in namespace.list_sessions() {
+            namespace.remove_session(&id).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordination::AgentId;
+
+    #[tokio::test]
+    async fn namespaces_keep_tenants_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = SessionManager::new();
+        let config = || NamespaceConfig {
+            storage_root: dir.path().to_path_buf(),
+        };
+        let acme = manager.create_namespace("acme", config()).unwrap();
+        let globex = manager.create_namespace("globex", config()).unwrap();
+        assert!(manager.create_namespace("acme", config()).is_err());
+        assert!(manager.create_namespace("../up", config()).is_err());
+        assert_eq!(manager.list_namespaces(), ["acme", "globex"]);
+
+        let session = acme.create_session().await.unwrap();
+        assert_eq!(acme.list_sessions(), std::slice::from_ref(&session.id));
+        assert!(globex.list_sessions().is_empty());
+        assert!(globex.get_session(&session.id).is_none());
+        assert!(manager.get_session(&session.id).is_some());
+        assert_eq!(
+            session.get_metadata(NAMESPACE_METADATA_KEY).await,
+            Some(serde_json::json!("acme"))
+        );
+
+        let agent = AgentId::new();
+        acme.message_bus().register_agent(agent.clone()).unwrap();
+        assert!(acme.message_bus().get_receiver(&agent).is_some());
+        assert!(globex.message_bus().get_receiver(&agent).is_none());
+
+        assert_eq!(acme.storage_path(), dir.path().join("acme"));
+        assert_ne!(acme.storage_path(), globex.storage_path());
+
+        manager.remove_namespace("acme").await.unwrap();
+        assert!(manager.namespace("acme").is_none());
+        assert!(manager.get_session(&session.id).is_none());
+    }
+}
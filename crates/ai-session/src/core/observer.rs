@@ -0,0 +1,143 @@
+//! Read-only output observers for a session.
+//!
+//! Every chunk a session reads from its terminal (or produces through
+//! `execute_command`) is fanned out over a [`tokio::sync::broadcast`] channel.
+//! Observers subscribe through [`AISession::watch_output`](super::AISession::watch_output)
+//! and receive an [`OutputWatcher`], which exposes no way to write back into
+//! the session — so any number of humans can supervise an agent without
+//! being able to disturb it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Number of chunks buffered per observer before the slowest one starts lagging.
+const OBSERVER_CHANNEL_CAPACITY: usize = 256;
+
+/// One chunk of session output delivered to observers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputChunk {
+    /// When the chunk was read from the session.
+    pub timestamp: DateTime<Utc>,
+    /// Raw bytes as produced by the terminal.
+    pub data: Vec<u8>,
+}
+
+impl OutputChunk {
+    /// Lossy UTF-8 view of the chunk, for display.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+}
+
+/// Broadcast side owned by the session.
+#[derive(Debug)]
+pub(crate) struct OutputBroadcaster {
+    tx: broadcast::Sender<OutputChunk>,
+}
+
+impl OutputBroadcaster {
+    pub(crate) fn new() -> Self {
+        let (tx, _rx) = broadcast::channel(OBSERVER_CHANNEL_CAPACITY);
+        Self { tx }
+    }
+
+    /// Publish a chunk. Empty reads are dropped, and having no observers is
+    /// not an error.
+    pub(crate) fn publish(&self, data: &[u8]) {
+        if data.is_empty() || self.tx.receiver_count() == 0 {
+            return;
+        }
+        let _ = self.tx.send(OutputChunk {
+            timestamp: Utc::now(),
+            data: data.to_vec(),
+        });
+    }
+
+    pub(crate) fn subscribe(&self) -> OutputWatcher {
+        OutputWatcher {
+            rx: self.tx.subscribe(),
+            skipped: 0,
+        }
+    }
+
+    pub(crate) fn observer_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+/// Read-only handle on a session's live output.
+///
+/// Dropping the watcher detaches it; the session is never affected.
+#[derive(Debug)]
+pub struct OutputWatcher {
+    rx: broadcast::Receiver<OutputChunk>,
+    skipped: u64,
+}
+
+impl OutputWatcher {
+    /// Wait for the next chunk. Returns `None` once the session is dropped.
+    ///
+    /// A watcher that falls more than the channel capacity behind skips the
+    /// oldest chunks instead of blocking the session; the number of skipped
+    /// chunks is available through [`OutputWatcher::skipped`].
+    pub async fn next(&mut self) -> Option<OutputChunk> {
+        loop {
+            match self.rx.recv().await {
+                Ok(chunk) => return Some(chunk),
+                Err(broadcast::error::RecvError::Lagged(n)) => self.skipped += n,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Chunks dropped because this watcher lagged behind.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn every_watcher_sees_every_chunk() {
+        let broadcaster = OutputBroadcaster::new();
+        let mut a = broadcaster.subscribe();
+        let mut b = broadcaster.subscribe();
+        assert_eq!(broadcaster.observer_count(), 2);
+
+        broadcaster.publish(b"cargo build\n");
+        assert_eq!(a.next().await.unwrap().text(), "cargo build\n");
+        assert_eq!(b.next().await.unwrap().text(), "cargo build\n");
+    }
+
+    #[tokio::test]
+    async fn empty_reads_are_not_published() {
+        let broadcaster = OutputBroadcaster::new();
+        let mut watcher = broadcaster.subscribe();
+        broadcaster.publish(b"");
+        broadcaster.publish(b"x");
+        assert_eq!(watcher.next().await.unwrap().data, b"x");
+    }
+
+    #[tokio::test]
+    async fn lagging_watcher_skips_instead_of_blocking() {
+        let broadcaster = OutputBroadcaster::new();
+        let mut watcher = broadcaster.subscribe();
+        for _ in 0..OBSERVER_CHANNEL_CAPACITY + 4 {
+            broadcaster.publish(b"line\n");
+        }
+        assert!(watcher.next().await.is_some());
+        assert_eq!(watcher.skipped(), 4);
+    }
+
+    #[tokio::test]
+    async fn watcher_ends_when_broadcaster_drops() {
+        let broadcaster = OutputBroadcaster::new();
+        let mut watcher = broadcaster.subscribe();
+        drop(broadcaster);
+        assert!(watcher.next().await.is_none());
+    }
+}
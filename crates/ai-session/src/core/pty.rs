@@ -1,7 +1,10 @@
 //! PTY (Pseudo-Terminal) management
 
+use super::terminal::TerminalSignal;
 use anyhow::Result;
-use portable_pty::{Child, CommandBuilder, PtySize, native_pty_system};
+use nix::sys::signal::killpg;
+use nix::unistd::Pid;
+use portable_pty::{Child, CommandBuilder, MasterPty, PtySize, native_pty_system};
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
 use tokio::time::{Duration, timeout};
@@ -9,7 +12,9 @@ use tokio::time::{Duration, timeout};
 /// Handle to a PTY
 pub struct PtyHandle {
     /// PTY size
-    size: PtySize,
+    size: Arc<Mutex<PtySize>>,
+    /// Master side, kept open so the window size can change after spawn
+    master: Arc<Mutex<Option<Box<dyn MasterPty + Send>>>>,
     /// Child process
     child: Arc<Mutex<Option<Box<dyn Child + Send>>>>,
     /// Reader handle (thread-safe)
@@ -29,7 +34,8 @@ impl PtyHandle {
         };
 
         Ok(Self {
-            size,
+            size: Arc::new(Mutex::new(size)),
+            master: Arc::new(Mutex::new(None)),
             child: Arc::new(Mutex::new(None)),
             reader: Arc::new(Mutex::new(None)),
             writer: Arc::new(Mutex::new(None)),
@@ -39,7 +45,8 @@ impl PtyHandle {
     /// Spawn a command in the PTY
     pub async fn spawn_command(&self, cmd: CommandBuilder) -> Result<()> {
         let pty_system = native_pty_system();
-        let pair = pty_system.openpty(self.size)?;
+        let size = *self.size.lock().unwrap();
+        let pair = pty_system.openpty(size)?;
 
         let child = pair.slave.spawn_command(cmd)?;
         let mut child_lock = self.child.lock().unwrap();
@@ -54,6 +61,8 @@ impl PtyHandle {
         let mut writer_lock = self.writer.lock().unwrap();
         *writer_lock = Some(writer);
 
+        *self.master.lock().unwrap() = Some(pair.master);
+
         Ok(())
     }
 
@@ -98,26 +107,48 @@ impl PtyHandle {
         Ok(result)
     }
 
-    /// Resize the PTY
+    /// Resize the PTY. Once a command is running the kernel delivers
+    /// `SIGWINCH` to its foreground process group, so full-screen programs
+    /// redraw at the new size.
     pub async fn resize(&self, rows: u16, cols: u16) -> Result<()> {
-        // Update internal size
-        let _new_size = PtySize {
+        let new_size = PtySize {
             rows,
             cols,
             pixel_width: 0,
             pixel_height: 0,
         };
-
-        // For now, we just update the internal size
-        // In a full implementation, we'd resize the actual PTY
-        // self.size = new_size; // Can't modify due to &self
-
+        if let Some(master) = self.master.lock().unwrap().as_ref() {
+            master.resize(new_size)?;
+        }
+        *self.size.lock().unwrap() = new_size;
         Ok(())
     }
 
     /// Get the current PTY size
     pub fn size(&self) -> (u16, u16) {
-        (self.size.rows, self.size.cols)
+        let size = self.size.lock().unwrap();
+        (size.rows, size.cols)
+    }
+
+    /// Deliver a signal the way a terminal would. Keyboard signals are
+    /// written as their control character so the line discipline signals
+    /// whichever job is in the foreground (vim, not the shell behind it);
+    /// the rest go to the foreground process group directly.
+    pub async fn signal(&self, signal: TerminalSignal) -> Result<()> {
+        if let Some(byte) = signal.control_char() {
+            return self.write(&[byte]).await;
+        }
+        let group = self
+            .master
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|master| master.process_group_leader())
+            .map(|pgid| pgid as u32)
+            .or_else(|| self.process_id())
+            .ok_or_else(|| anyhow::anyhow!("PTY not initialized"))?;
+        killpg(Pid::from_raw(group as i32), signal.signal())?;
+        Ok(())
     }
 
     /// Check if child process is running
@@ -132,6 +163,16 @@ impl PtyHandle {
         false
     }
 
+    /// Pid of the child. The PTY child is a session leader, so this is also
+    /// its process group.
+    pub fn process_id(&self) -> Option<u32> {
+        self.child
+            .lock()
+            .ok()?
+            .as_ref()
+            .and_then(|child| child.process_id())
+    }
+
     /// Read data from PTY with timeout (for testing)
     pub async fn read_with_timeout(&self, timeout_ms: u64) -> Result<Vec<u8>> {
         match timeout(Duration::from_millis(timeout_ms), self.read()).await {
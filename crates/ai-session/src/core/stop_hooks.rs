@@ -0,0 +1,223 @@
+//! Commands run before a session's terminal is torn down.
+//!
+//! Killing an agent mid-task can leave a dirty worktree or services it
+//! started (a `docker compose up`, a dev server) running with nobody to stop
+//! them. A session's `on_stop` hooks get a chance to clean up first: each
+//! runs through `sh -c` in the session's working directory and environment,
+//! one after another, and a hook that overruns its timeout is killed along
+//! with everything it started. Hook failures are recorded, never fatal — the
+//! session still stops.
+
+use nix::sys::signal::{Signal, killpg};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+
+/// Output kept per hook in [`StopHookOutcome::output`].
+const OUTPUT_LIMIT: usize = 4096;
+
+/// One command to run when the session stops.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StopHook {
+    /// Shell command, e.g. `git stash --include-untracked`.
+    pub command: String,
+    /// Seconds before the hook is killed (default 30).
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+impl StopHook {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_secs = timeout.as_secs().max(1);
+        self
+    }
+}
+
+/// What happened when a hook ran.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StopHookOutcome {
+    pub command: String,
+    /// `None` when the hook timed out, could not be spawned, or was killed
+    /// by a signal.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// stdout followed by stderr (last 4 KiB), or the spawn error.
+    pub output: String,
+}
+
+impl StopHookOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Run `hooks` in order and report each one.
+pub async fn run_stop_hooks(
+    hooks: &[StopHook],
+    working_dir: &Path,
+    env: &HashMap<String, String>,
+) -> Vec<StopHookOutcome> {
+    let mut outcomes = Vec::with_capacity(hooks.len());
+    for hook in hooks {
+        let outcome = run_hook(hook, working_dir, env).await;
+        if !outcome.succeeded() {
+            tracing::warn!(
+                "on_stop hook `{}` {}",
+                hook.command,
+                if outcome.timed_out {
+                    format!("timed out after {}s", hook.timeout_secs)
+                } else {
+                    format!("failed (exit {:?})", outcome.exit_code)
+                }
+            );
+        }
+        outcomes.push(outcome);
+    }
+    outcomes
+}
+
+async fn run_hook(
+    hook: &StopHook,
+    working_dir: &Path,
+    env: &HashMap<String, String>,
+) -> StopHookOutcome {
+    let started = Instant::now();
+    let outcome = |exit_code, timed_out, output: String| StopHookOutcome {
+        command: hook.command.clone(),
+        exit_code,
+        timed_out,
+        duration_ms: started.elapsed().as_millis() as u64,
+        output,
+    };
+
+    let mut command = tokio::process::Command::new("sh");
+    command
+        .arg("-c")
+        .arg(&hook.command)
+        .current_dir(working_dir)
+        .envs(env)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        // Own group, so a timeout kills whatever the hook started too.
+        .process_group(0)
+        .kill_on_drop(true);
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => return outcome(None, false, format!("failed to spawn: {e}")),
+    };
+    let pgid = child.id();
+    let collect = |stream: Option<Box<dyn tokio::io::AsyncRead + Unpin + Send>>| {
+        tokio::spawn(async move {
+            let mut output = Vec::new();
+            if let Some(mut stream) = stream {
+                let _ = stream.read_to_end(&mut output).await;
+            }
+            output
+        })
+    };
+    let stdout = collect(child.stdout.take().map(|s| Box::new(s) as _));
+    let stderr = collect(child.stderr.take().map(|s| Box::new(s) as _));
+
+    let waited = tokio::time::timeout(Duration::from_secs(hook.timeout_secs), child.wait()).await;
+    let (exit_code, timed_out) = match waited {
+        Ok(Ok(status)) => (status.code(), false),
+        Ok(Err(_)) => (None, false),
+        Err(_) => {
+            if let Some(pgid) = pgid {
+                let _ = killpg(Pid::from_raw(pgid as i32), Signal::SIGKILL);
+            }
+            let _ = child.wait().await;
+            (None, true)
+        }
+    };
+    // A background child of the hook can keep the pipes open; don't wait
+    // on it past the hook itself.
+    let mut output = Vec::new();
+    for stream in [stdout, stderr] {
+        if let Ok(Ok(bytes)) = tokio::time::timeout(Duration::from_millis(500), stream).await {
+            output.extend(bytes);
+        }
+    }
+    let start = output.len().saturating_sub(OUTPUT_LIMIT);
+    outcome(
+        exit_code,
+        timed_out,
+        String::from_utf8_lossy(&output[start..]).into_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hooks_run_in_order_in_working_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = HashMap::from([("HOOK_VAR".to_string(), "from-session".to_string())]);
+        let hooks = [
+            StopHook::new("echo first >> order.txt; echo $HOOK_VAR"),
+            StopHook::new("echo broken >&2; exit 3"),
+            StopHook::new("echo third >> order.txt"),
+        ];
+
+        let outcomes = run_stop_hooks(&hooks, dir.path(), &env).await;
+
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes[0].succeeded());
+        assert_eq!(outcomes[0].output.trim(), "from-session");
+        assert_eq!(outcomes[1].exit_code, Some(3));
+        assert_eq!(outcomes[1].output.trim(), "broken");
+        let order = std::fs::read_to_string(dir.path().join("order.txt")).unwrap();
+        assert_eq!(
+            order, "first\nthird\n",
+            "a failing hook does not stop the rest"
+        );
+    }
+
+    #[tokio::test]
+    async fn timed_out_hook_is_killed_with_its_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = [StopHook::new("sleep 30 & echo $! > child.pid; wait")
+            .with_timeout(Duration::from_secs(1))];
+
+        let started = Instant::now();
+        let outcomes = run_stop_hooks(&hooks, dir.path(), &HashMap::new()).await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(outcomes[0].timed_out);
+        assert_eq!(outcomes[0].exit_code, None);
+        let child: i32 = std::fs::read_to_string(dir.path().join("child.pid"))
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        // Reaped by init once its parent is gone; give it a moment.
+        for _ in 0..50 {
+            if nix::sys::signal::kill(Pid::from_raw(child), None).is_err() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let state = std::fs::read_to_string(format!("/proc/{child}/stat")).ok();
+        assert!(
+            state.is_none_or(|stat| stat.contains(") Z ")),
+            "background child survived the timeout"
+        );
+    }
+}
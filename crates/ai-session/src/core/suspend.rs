@@ -0,0 +1,190 @@
+//! Suspending and resuming the processes behind a session.
+//!
+//! A session's shell runs in its own process group (the PTY child is a
+//! session leader, the headless shell is spawned with `process_group(0)`), so
+//! stopping the group stops the shell and everything it started. For a
+//! process that shares its group with others — e.g. a ccswarm pipeline run
+//! started from an interactive shell — [`signal_tree`] signals the process
+//! and its descendants instead.
+
+use anyhow::{Context, Result};
+use nix::sys::signal::{Signal, kill, killpg};
+use nix::unistd::Pid;
+
+/// What to do with the suspended processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suspend {
+    /// `SIGSTOP`: cannot be caught or ignored.
+    Stop,
+    /// `SIGCONT`.
+    Continue,
+}
+
+impl Suspend {
+    fn signal(self) -> Signal {
+        match self {
+            Suspend::Stop => Signal::SIGSTOP,
+            Suspend::Continue => Signal::SIGCONT,
+        }
+    }
+}
+
+/// Signal every process in the group led by `pgid`.
+pub fn signal_group(pgid: u32, action: Suspend) -> Result<()> {
+    killpg(Pid::from_raw(pgid as i32), action.signal())
+        .with_context(|| format!("failed to {action:?} process group {pgid}"))
+}
+
+/// Whether a process with this pid exists.
+pub fn is_alive(pid: u32) -> bool {
+    kill(Pid::from_raw(pid as i32), None).is_ok()
+}
+
+/// Signal `root` and all of its descendants. Stopping goes top-down so a
+/// parent cannot start new children mid-way; continuing goes bottom-up so
+/// children are running again before the parent waits on them. Returns the
+/// pids that were signalled.
+pub fn signal_tree(root: u32, action: Suspend) -> Result<Vec<u32>> {
+    let mut pids = descendants(root)?;
+    pids.insert(0, root);
+    if action == Suspend::Continue {
+        pids.reverse();
+    }
+    for pid in &pids {
+        // Children can exit between listing and signalling; only the root
+        // has to exist.
+        if let Err(e) = kill(Pid::from_raw(*pid as i32), action.signal())
+            && *pid == root
+        {
+            return Err(e).with_context(|| format!("failed to {action:?} process {root}"));
+        }
+    }
+    Ok(pids)
+}
+
+/// Kill `root`'s descendants with `SIGKILL`, leaving `root` itself. The
+/// signal reaches stopped processes too, so this cancels whatever a
+/// suspended process was waiting on. Returns the pids that were killed.
+pub fn kill_descendants(root: u32) -> Result<Vec<u32>> {
+    let pids = descendants(root)?;
+    for pid in &pids {
+        // Already gone is as good as killed.
+        let _ = kill(Pid::from_raw(*pid as i32), Signal::SIGKILL);
+    }
+    Ok(pids)
+}
+
+/// End `root` and all of its descendants with `SIGTERM`, or `SIGKILL` when
+/// `force`. Each process also gets `SIGCONT` so a paused one acts on the
+/// signal instead of holding it. Returns the pids that were signalled.
+pub fn terminate_tree(root: u32, force: bool) -> Result<Vec<u32>> {
+    let mut pids = descendants(root)?;
+    pids.insert(0, root);
+    let signal = if force {
+        Signal::SIGKILL
+    } else {
+        Signal::SIGTERM
+    };
+    for pid in &pids {
+        let pid_t = Pid::from_raw(*pid as i32);
+        if let Err(e) = kill(pid_t, signal)
+            && *pid == root
+        {
+            return Err(e).with_context(|| format!("failed to terminate process {root}"));
+        }
+        let _ = kill(pid_t, Signal::SIGCONT);
+    }
+    Ok(pids)
+}
+
+/// Descendants of `root`, parents before children.
+fn descendants(root: u32) -> Result<Vec<u32>> {
+    let output = std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid="])
+        .output()
+        .context("failed to list processes with ps")?;
+    Ok(tree_from_ps(&String::from_utf8_lossy(&output.stdout), root))
+}
+
+fn tree_from_ps(table: &str, root: u32) -> Vec<u32> {
+    let pairs: Vec<(u32, u32)> = table
+        .lines()
+        .filter_map(|line| {
+            let mut cols = line.split_whitespace();
+            Some((cols.next()?.parse().ok()?, cols.next()?.parse().ok()?))
+        })
+        .collect();
+    let mut found = Vec::new();
+    let mut frontier = vec![root];
+    while let Some(parent) = frontier.pop() {
+        for (pid, _) in pairs.iter().filter(|(_, ppid)| *ppid == parent) {
+            if *pid != root && !found.contains(pid) {
+                found.push(*pid);
+                frontier.push(*pid);
+            }
+        }
+    }
+    found
+}
+
+/// The single-letter state from `/proc/<pid>/stat` ("T" = stopped) once it
+/// matches `want`, polling briefly since signals are delivered
+/// asynchronously. `None` where `/proc` is unavailable.
+#[cfg(test)]
+pub(crate) async fn wait_for_state(pid: u32, want: &str) -> Option<String> {
+    let mut state = None;
+    for _ in 0..50 {
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+        // The command name is parenthesised and may contain spaces.
+        state = stat
+            .rsplit_once(')')
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .map(str::to_string);
+        if state.as_deref() == Some(want) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_from_ps_walks_descendants() {
+        let table = "  1     0\n 10     1\n 11    10\n 12    11\n 13    10\n 20     1\n";
+        assert_eq!(tree_from_ps(table, 10), [11, 13, 12]);
+        assert!(tree_from_ps(table, 12).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stop_and_continue_process_tree() {
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "sleep 30 & wait"])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let pid = child.id().unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let stopped = signal_tree(pid, Suspend::Stop).unwrap();
+        assert!(stopped.len() >= 2, "sh and sleep: {stopped:?}");
+        let mut states = Vec::new();
+        for pid in &stopped {
+            states.push(wait_for_state(*pid, "T").await);
+        }
+        let continued = signal_tree(pid, Suspend::Continue).unwrap();
+        assert!(is_alive(pid));
+        for pid in continued.iter().rev() {
+            let _ = kill(Pid::from_raw(*pid as i32), Signal::SIGKILL);
+        }
+        child.wait().await.unwrap();
+        for state in states.into_iter().flatten() {
+            assert_eq!(state, "T");
+        }
+    }
+}
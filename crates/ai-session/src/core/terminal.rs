@@ -1,10 +1,62 @@
 //! Terminal handle abstraction that can wrap either a PTY or the headless fallback.
 
 use anyhow::Result;
+use nix::sys::signal::{Signal, killpg};
+use nix::unistd::Pid;
 
 use super::headless::HeadlessHandle;
 use super::pty::PtyHandle;
 
+/// A signal an attached client forwards to the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalSignal {
+    /// Ctrl+C (`SIGINT`)
+    Interrupt,
+    /// Ctrl+Backslash (`SIGQUIT`)
+    Quit,
+    /// Ctrl+Z (`SIGTSTP`)
+    Suspend,
+    /// `SIGTERM`
+    Terminate,
+    /// `SIGHUP`, e.g. when the client's terminal goes away
+    Hangup,
+}
+
+impl TerminalSignal {
+    /// Parse `int`, `SIGINT`, `term`, ... (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        match name.strip_prefix("sig").unwrap_or(&name) {
+            "int" | "interrupt" => Some(Self::Interrupt),
+            "quit" => Some(Self::Quit),
+            "tstp" | "suspend" => Some(Self::Suspend),
+            "term" | "terminate" => Some(Self::Terminate),
+            "hup" | "hangup" => Some(Self::Hangup),
+            _ => None,
+        }
+    }
+
+    pub fn signal(self) -> Signal {
+        match self {
+            Self::Interrupt => Signal::SIGINT,
+            Self::Quit => Signal::SIGQUIT,
+            Self::Suspend => Signal::SIGTSTP,
+            Self::Terminate => Signal::SIGTERM,
+            Self::Hangup => Signal::SIGHUP,
+        }
+    }
+
+    /// The byte a terminal sends for keyboard-generated signals.
+    pub fn control_char(self) -> Option<u8> {
+        match self {
+            Self::Interrupt => Some(0x03),
+            Self::Quit => Some(0x1c),
+            Self::Suspend => Some(0x1a),
+            Self::Terminate | Self::Hangup => None,
+        }
+    }
+}
+
 /// Unified terminal handle used by `AISession`.
 pub enum TerminalHandle {
     /// Native PTY backed terminal.
@@ -46,6 +98,49 @@ impl TerminalHandle {
         }
     }
 
+    /// Take output already buffered in-process without waiting for more.
+    /// PTY output stays in the kernel buffer until read, so there is
+    /// nothing to take.
+    pub async fn take_buffered(&self) -> Result<Vec<u8>> {
+        match self {
+            TerminalHandle::Pty(_) => Ok(Vec::new()),
+            TerminalHandle::Headless(handle) => handle.read().await,
+        }
+    }
+
+    /// Change the window size. Pipes have none, so headless terminals
+    /// ignore it.
+    pub async fn resize(&self, rows: u16, cols: u16) -> Result<()> {
+        match self {
+            TerminalHandle::Pty(handle) => handle.resize(rows, cols).await,
+            TerminalHandle::Headless(_) => Ok(()),
+        }
+    }
+
+    /// Forward a signal from an attached client. Without a terminal there
+    /// is no foreground job, so headless sessions signal the shell's group.
+    pub async fn signal(&self, signal: TerminalSignal) -> Result<()> {
+        match self {
+            TerminalHandle::Pty(handle) => handle.signal(signal).await,
+            TerminalHandle::Headless(handle) => {
+                let pgid = handle
+                    .process_id()
+                    .await
+                    .ok_or_else(|| anyhow::anyhow!("Terminal not running"))?;
+                killpg(Pid::from_raw(pgid as i32), signal.signal())?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Process group of the shell, which its children inherit.
+    pub async fn process_group(&self) -> Option<u32> {
+        match self {
+            TerminalHandle::Pty(handle) => handle.process_id(),
+            TerminalHandle::Headless(handle) => handle.process_id().await,
+        }
+    }
+
     /// Shutdown the terminal, freeing all resources.
     pub async fn shutdown(self) -> Result<()> {
         match self {
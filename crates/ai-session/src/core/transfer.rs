@@ -0,0 +1,289 @@
+//! Moving files between the host and a session.
+//!
+//! A session's files live under its working directory — a worktree, a
+//! container volume, a directory on another machine mounted for the shell.
+//! [`put`] and [`get`] copy one file between a host path and a path inside
+//! that root. Session paths are confined to the root: `..` components and
+//! symlinks that lead outside it are refused.
+//!
+//! Copies are written to a temporary file next to the destination, checked
+//! against the SHA-256 of what was read, and only then renamed into place,
+//! so a reader never sees a half-written file and a failed transfer leaves
+//! the old one untouched.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Bytes read and written per step; progress is reported after each.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// How far a transfer has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TransferProgress {
+    pub bytes: u64,
+    pub total: u64,
+}
+
+/// A completed transfer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferReport {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub bytes: u64,
+    /// Hex SHA-256 of the content, verified on both ends.
+    pub sha256: String,
+}
+
+/// Copy host file `local` to `path` under the session `root`.
+pub async fn put(
+    root: &Path,
+    local: &Path,
+    path: &Path,
+    on_progress: impl FnMut(TransferProgress),
+) -> Result<TransferReport> {
+    let destination = resolve_in_root(root, path)?;
+    copy(local, &destination, on_progress).await
+}
+
+/// Copy `path` under the session `root` to host file `local`.
+pub async fn get(
+    root: &Path,
+    path: &Path,
+    local: &Path,
+    on_progress: impl FnMut(TransferProgress),
+) -> Result<TransferReport> {
+    let source = resolve_in_root(root, path)?;
+    copy(&source, local, on_progress).await
+}
+
+/// Where `path` (relative to `root`, or absolute inside it) lives, after
+/// following any symlinks that already exist along the way. Errors if that
+/// is outside `root`.
+pub fn resolve_in_root(root: &Path, path: &Path) -> Result<PathBuf> {
+    let given_root = root;
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("Session root {} is not accessible", root.display()))?;
+    let relative = if path.is_absolute() {
+        path.strip_prefix(&root)
+            .or_else(|_| path.strip_prefix(given_root))
+            .with_context(|| format!("{} is outside the session", path.display()))?
+    } else {
+        path
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    bail!("{} is outside the session", path.display());
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                bail!("{} is outside the session", path.display())
+            }
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        bail!("A file path inside the session is required");
+    }
+
+    // Resolve the deepest part that exists, so a symlink cannot lead out.
+    let candidate = root.join(&normalized);
+    let mut existing = candidate.as_path();
+    let mut rest = Vec::new();
+    let resolved = loop {
+        match existing.canonicalize() {
+            Ok(resolved) => break resolved,
+            Err(_) => {
+                rest.push(existing.file_name().unwrap_or_default().to_owned());
+                existing = existing.parent().unwrap_or(&root);
+            }
+        }
+    };
+    if !resolved.starts_with(&root) {
+        bail!(
+            "{} resolves to {}, outside the session",
+            path.display(),
+            resolved.display()
+        );
+    }
+    Ok(rest.iter().rev().fold(resolved, |dir, part| dir.join(part)))
+}
+
+/// Hex SHA-256 of a file's content.
+pub async fn sha256(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+async fn copy(
+    source: &Path,
+    destination: &Path,
+    mut on_progress: impl FnMut(TransferProgress),
+) -> Result<TransferReport> {
+    let metadata = tokio::fs::metadata(source)
+        .await
+        .with_context(|| format!("Failed to read {}", source.display()))?;
+    if !metadata.is_file() {
+        bail!("{} is not a file", source.display());
+    }
+    if tokio::fs::metadata(destination)
+        .await
+        .is_ok_and(|m| m.is_dir())
+    {
+        bail!("{} is a directory", destination.display());
+    }
+    let name = destination
+        .file_name()
+        .with_context(|| format!("{} has no file name", destination.display()))?;
+    if let Some(dir) = destination.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let partial = destination.with_file_name(format!(
+        ".{}.part-{}",
+        name.to_string_lossy(),
+        &uuid::Uuid::new_v4().to_string()[..8]
+    ));
+
+    let result = async {
+        let total = metadata.len();
+        let mut reader = tokio::fs::File::open(source).await?;
+        let mut writer = tokio::fs::File::create(&partial).await?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; CHUNK_SIZE];
+        let mut bytes = 0u64;
+        on_progress(TransferProgress { bytes, total });
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            writer.write_all(&buf[..n]).await?;
+            bytes += n as u64;
+            on_progress(TransferProgress { bytes, total });
+        }
+        writer.sync_all().await?;
+        drop(writer);
+
+        let checksum = hex(&hasher.finalize());
+        if sha256(&partial).await? != checksum {
+            bail!("Checksum mismatch writing {}", destination.display());
+        }
+        tokio::fs::set_permissions(&partial, metadata.permissions()).await?;
+        tokio::fs::rename(&partial, destination).await?;
+        Ok(TransferReport {
+            source: source.to_path_buf(),
+            destination: destination.to_path_buf(),
+            bytes,
+            sha256: checksum,
+        })
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&partial).await;
+    }
+    result.with_context(|| {
+        format!(
+            "Failed to copy {} to {}",
+            source.display(),
+            destination.display()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn files_round_trip_with_checksums_and_progress() {
+        let host = tempfile::tempdir().unwrap();
+        let session = tempfile::tempdir().unwrap();
+        let local = host.path().join("data.bin");
+        let content: Vec<u8> = (0..CHUNK_SIZE * 2 + 17).map(|i| i as u8).collect();
+        tokio::fs::write(&local, &content).await.unwrap();
+
+        let mut steps = Vec::new();
+        let put_report = put(session.path(), &local, Path::new("in/./data.bin"), |p| {
+            steps.push(p)
+        })
+        .await
+        .unwrap();
+        assert_eq!(put_report.bytes, content.len() as u64);
+        assert!(steps.len() >= 4);
+        assert_eq!(steps.last().unwrap().bytes, steps.last().unwrap().total);
+        assert_eq!(
+            tokio::fs::read(session.path().join("in/data.bin"))
+                .await
+                .unwrap(),
+            content
+        );
+
+        let back = host.path().join("back.bin");
+        let get_report = get(session.path(), Path::new("in/data.bin"), &back, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(get_report.sha256, put_report.sha256);
+        assert_eq!(sha256(&back).await.unwrap(), put_report.sha256);
+        // No partial files are left behind.
+        let mut entries = tokio::fs::read_dir(session.path().join("in"))
+            .await
+            .unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            names.push(entry.file_name());
+        }
+        assert_eq!(names, ["data.bin"]);
+    }
+
+    #[tokio::test]
+    async fn session_paths_cannot_leave_the_root() {
+        let outside = tempfile::tempdir().unwrap();
+        let session = tempfile::tempdir().unwrap();
+        let root = session.path();
+        std::os::unix::fs::symlink(outside.path(), root.join("escape")).unwrap();
+
+        assert!(resolve_in_root(root, Path::new("a/../b.txt")).is_ok());
+        assert!(resolve_in_root(root, &root.join("c.txt")).is_ok());
+        for path in ["../x", "a/../../x", "escape/x", "/etc/passwd", "."] {
+            assert!(
+                resolve_in_root(root, Path::new(path)).is_err(),
+                "{path} was allowed"
+            );
+        }
+
+        let local = outside.path().join("f");
+        tokio::fs::write(&local, "x").await.unwrap();
+        assert!(
+            put(root, &local, Path::new("escape/f2"), |_| {})
+                .await
+                .is_err()
+        );
+        assert!(!outside.path().join("f2").exists());
+    }
+}
@@ -2,16 +2,70 @@
 //!
 //! Workflow crates can still decide which provider CLI to run, but the common
 //! session concerns live here: prompt sizing, working-directory context, cwd
-//! enforcement, and structured subprocess results.
+//! enforcement, structured subprocess results, streaming a provider's
+//! output as it is produced, and stopping commands that outrun their
+//! [`CommandTimeout`].
 
 use anyhow::{Context, Result};
+use nix::sys::signal::{Signal, killpg};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::path::Path;
-use std::time::Instant;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio::process::Command;
 
 /// Default prompt byte cap before spawning a provider subprocess.
 pub const DEFAULT_MAX_PROMPT_BYTES: usize = 200_000;
 
+/// How long a command may run, and how it is stopped when it runs longer:
+/// SIGINT first, SIGTERM `escalation_ms` later, SIGKILL after another
+/// `escalation_ms`. Signals go to the command's whole process group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandTimeout {
+    pub timeout_secs: u64,
+    /// Delay between one signal and the next.
+    #[serde(default = "default_escalation_ms")]
+    pub escalation_ms: u64,
+}
+
+fn default_escalation_ms() -> u64 {
+    2_000
+}
+
+impl CommandTimeout {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout_secs: timeout.as_secs().max(1),
+            escalation_ms: default_escalation_ms(),
+        }
+    }
+
+    pub fn with_escalation(mut self, delay: Duration) -> Self {
+        self.escalation_ms = delay.as_millis() as u64;
+        self
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn escalation(&self) -> Duration {
+        Duration::from_millis(self.escalation_ms)
+    }
+}
+
+/// A command stopped for outrunning its [`CommandTimeout`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimedOut {
+    pub timeout_secs: u64,
+    /// The signal the command finally gave in to (`SIGINT`, `SIGTERM` or
+    /// `SIGKILL`).
+    pub signal: String,
+}
+
 /// Result of a single provider subprocess execution.
 #[derive(Debug, Clone)]
 pub struct CommandExecution {
@@ -23,6 +77,11 @@ pub struct CommandExecution {
     pub status: std::process::ExitStatus,
     /// Wall-clock duration in milliseconds.
     pub duration_ms: u64,
+    /// The provider was killed before it finished (see
+    /// [`run_provider_command_streaming`]).
+    pub stopped: bool,
+    /// Set when the command outran its [`CommandTimeout`].
+    pub timed_out: Option<TimedOut>,
 }
 
 impl CommandExecution {
@@ -54,12 +113,15 @@ pub fn prepare_provider_prompt(
 }
 
 /// Execute a provider command with a centrally enforced working directory.
+///
+/// Dropping the returned future (a timed-out or cancelled run) kills the
+/// provider, so it cannot keep editing the tree after its caller moved on.
 pub async fn run_provider_command(
     mut command: Command,
     working_dir: &Path,
     provider_name: &str,
 ) -> Result<CommandExecution> {
-    command.current_dir(working_dir);
+    command.current_dir(working_dir).kill_on_drop(true);
     let start = Instant::now();
     let output = command
         .output()
@@ -71,6 +133,180 @@ pub async fn run_provider_command(
         stderr: String::from_utf8_lossy(&output.stderr).to_string(),
         status: output.status,
         duration_ms: start.elapsed().as_millis() as u64,
+        stopped: false,
+        timed_out: None,
+    })
+}
+
+/// Execute a provider command like [`run_provider_command`], handing each
+/// stdout line to `on_line` as soon as the provider writes it.
+///
+/// If `stop` completes first the provider is killed and the execution comes
+/// back with `stopped` set and the output produced up to that point.
+pub async fn run_provider_command_streaming(
+    mut command: Command,
+    working_dir: &Path,
+    provider_name: &str,
+    mut on_line: impl FnMut(&str),
+    stop: impl Future<Output = ()>,
+) -> Result<CommandExecution> {
+    command
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let start = Instant::now();
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to execute provider CLI: {provider_name}"))?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("provider stdout was not captured")?;
+    let mut stderr = child
+        .stderr
+        .take()
+        .context("provider stderr was not captured")?;
+    let stderr_task = tokio::spawn(async move {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf).await;
+        buf
+    });
+
+    let mut reader = BufReader::new(stdout);
+    let mut collected = Vec::new();
+    let mut line = Vec::new();
+    let mut stopped = false;
+    tokio::pin!(stop);
+    loop {
+        tokio::select! {
+            read = reader.read_until(b'\n', &mut line) => {
+                if read.context("failed to read provider output")? == 0 {
+                    break;
+                }
+                on_line(String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']));
+                collected.append(&mut line);
+            }
+            _ = &mut stop => {
+                stopped = true;
+                collected.append(&mut line);
+                child.start_kill().ok();
+                break;
+            }
+        }
+    }
+    let status = child
+        .wait()
+        .await
+        .with_context(|| format!("Failed to wait for provider CLI: {provider_name}"))?;
+    // A killed provider's own children can keep stderr open; don't wait on
+    // them.
+    let stderr = match stopped {
+        true => tokio::time::timeout(Duration::from_secs(1), stderr_task)
+            .await
+            .ok()
+            .and_then(Result::ok),
+        false => stderr_task.await.ok(),
+    }
+    .unwrap_or_default();
+
+    Ok(CommandExecution {
+        stdout: String::from_utf8_lossy(&collected).to_string(),
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+        status,
+        duration_ms: start.elapsed().as_millis() as u64,
+        stopped,
+        timed_out: None,
+    })
+}
+
+/// Execute a command like [`run_provider_command`], stopping it once it
+/// outruns `timeout`. The command runs in its own process group so the
+/// signals reach whatever it started too; a command that gives in comes back
+/// with `timed_out` set and the output produced up to that point.
+pub async fn run_command_with_timeout(
+    mut command: Command,
+    working_dir: &Path,
+    name: &str,
+    timeout: &CommandTimeout,
+) -> Result<CommandExecution> {
+    command
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true);
+    let start = Instant::now();
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to execute command: {name}"))?;
+    let collect = |stream: Option<Box<dyn tokio::io::AsyncRead + Unpin + Send>>| {
+        tokio::spawn(async move {
+            let mut output = Vec::new();
+            if let Some(mut stream) = stream {
+                let _ = stream.read_to_end(&mut output).await;
+            }
+            output
+        })
+    };
+    let stdout = collect(child.stdout.take().map(|s| Box::new(s) as _));
+    let stderr = collect(child.stderr.take().map(|s| Box::new(s) as _));
+
+    let mut timed_out = None;
+    let status = match tokio::time::timeout(timeout.timeout(), child.wait()).await {
+        Ok(status) => status,
+        Err(_) => {
+            let pgid = child.id().map(|id| Pid::from_raw(id as i32));
+            let mut status = None;
+            for signal in [Signal::SIGINT, Signal::SIGTERM, Signal::SIGKILL] {
+                if let Some(pgid) = pgid {
+                    let _ = killpg(pgid, signal);
+                }
+                timed_out = Some(TimedOut {
+                    timeout_secs: timeout.timeout_secs,
+                    signal: signal.as_str().to_string(),
+                });
+                let wait = match signal {
+                    Signal::SIGKILL => None,
+                    _ => Some(timeout.escalation()),
+                };
+                let exited = match wait {
+                    Some(delay) => tokio::time::timeout(delay, child.wait()).await.ok(),
+                    None => Some(child.wait().await),
+                };
+                if let Some(exited) = exited {
+                    status = Some(exited);
+                    break;
+                }
+            }
+            status.context("command outlived SIGKILL")?
+        }
+    }
+    .with_context(|| format!("Failed to wait for command: {name}"))?;
+
+    // Once the command is gone, something it left running in the background
+    // can still hold the pipes open; don't wait on it.
+    let mut streams = Vec::new();
+    for stream in [stdout, stderr] {
+        let bytes = match timed_out {
+            Some(_) => tokio::time::timeout(Duration::from_millis(500), stream)
+                .await
+                .ok()
+                .and_then(Result::ok),
+            None => stream.await.ok(),
+        };
+        streams.push(bytes.unwrap_or_default());
+    }
+
+    Ok(CommandExecution {
+        stdout: String::from_utf8_lossy(&streams[0]).to_string(),
+        stderr: String::from_utf8_lossy(&streams[1]).to_string(),
+        status,
+        duration_ms: start.elapsed().as_millis() as u64,
+        stopped: false,
+        timed_out,
     })
 }
 
@@ -112,4 +348,119 @@ mod tests {
         assert!(result.success());
         assert_eq!(actual, expected);
     }
+
+    #[tokio::test]
+    async fn run_provider_command_is_killed_when_dropped() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 1; touch finished"]);
+
+        let run = run_provider_command(command, dir.path(), "sh");
+        let result = tokio::time::timeout(std::time::Duration::from_millis(200), run).await;
+        assert!(result.is_err(), "the command should still be running");
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert!(!dir.path().join("finished").exists());
+    }
+
+    #[tokio::test]
+    async fn run_provider_command_streaming_hands_over_lines_and_stops() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut command = Command::new("sh");
+        command.args(["-c", "printf 'one\\ntwo\\n'; echo oops >&2"]);
+        let mut seen = Vec::new();
+        let result = run_provider_command_streaming(
+            command,
+            dir.path(),
+            "sh",
+            |line| seen.push(line.to_string()),
+            std::future::pending(),
+        )
+        .await
+        .expect("command should run");
+        assert_eq!(seen, ["one", "two"]);
+        assert_eq!(result.stdout, "one\ntwo\n");
+        assert_eq!(result.stderr.trim(), "oops");
+        assert!(result.success() && !result.stopped);
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo first; sleep 30; echo never"]);
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let mut tx = Some(tx);
+        let result = run_provider_command_streaming(
+            command,
+            dir.path(),
+            "sh",
+            |_| {
+                if let Some(tx) = tx.take() {
+                    let _ = tx.send(());
+                }
+            },
+            async {
+                let _ = rx.await;
+            },
+        )
+        .await
+        .expect("command should run");
+        assert!(result.stopped);
+        assert!(!result.success());
+        assert_eq!(result.stdout, "first\n");
+        assert!(result.duration_ms < 10_000);
+    }
+
+    #[tokio::test]
+    async fn run_command_with_timeout_escalates_until_the_command_gives_in() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let timeout =
+            CommandTimeout::new(Duration::from_secs(1)).with_escalation(Duration::from_millis(300));
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo quick"]);
+        let result = run_command_with_timeout(command, dir.path(), "sh", &timeout)
+            .await
+            .expect("command should run");
+        assert!(result.success());
+        assert_eq!(result.stdout, "quick\n");
+        assert_eq!(result.timed_out, None);
+
+        // Shrugs off SIGINT, gives in to SIGTERM.
+        let mut command = Command::new("sh");
+        command.args(["-c", "trap '' INT; echo started; sleep 30"]);
+        let result = run_command_with_timeout(command, dir.path(), "sh", &timeout)
+            .await
+            .expect("command should run");
+        let timed_out = result.timed_out.clone().expect("timed out");
+        assert_eq!(timed_out.signal, "SIGTERM");
+        assert_eq!(timed_out.timeout_secs, 1);
+        assert_eq!(result.stdout, "started\n");
+        assert!(!result.success());
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "trap '' INT TERM; sleep 30"]);
+        let result = run_command_with_timeout(command, dir.path(), "sh", &timeout)
+            .await
+            .expect("command should run");
+        assert_eq!(result.timed_out.expect("timed out").signal, "SIGKILL");
+        assert!(result.duration_ms < 10_000);
+
+        let parsed = crate::output::OutputParser::new()
+            .parse_execution(&CommandExecution {
+                stdout: "test result: ok. 3 passed; 0 failed\n".to_string(),
+                timed_out: Some(timed_out),
+                ..result
+            })
+            .unwrap();
+        match parsed {
+            crate::output::ParsedOutput::TimedOut {
+                signal, partial, ..
+            } => {
+                assert_eq!(signal, "SIGTERM");
+                assert!(matches!(
+                    *partial,
+                    crate::output::ParsedOutput::TestResults { passed: 3, .. }
+                ));
+            }
+            other => panic!("expected a timed out result, got {other:?}"),
+        }
+    }
 }
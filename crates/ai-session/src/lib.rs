@@ -29,7 +29,8 @@
 //!
 //! ### 🔒 Security & Isolation
 //! - **Capability-based security**: Fine-grained access control for agent actions
-//! - **Namespace isolation**: Secure separation between different agent sessions
+//! - **Namespace isolation**: Separate session listings, message buses and
+//!   storage directories per tenant (see [`core::namespace`])
 //! - **Rate limiting**: Prevents resource abuse and ensures fair usage
 //!
 //! ### 💾 Session Persistence
@@ -134,6 +135,8 @@ pub mod execution;
 pub mod mcp;
 pub mod output;
 pub mod persistence;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod session_persistence;
 
 // Re-export main types
@@ -143,15 +146,19 @@ pub use context::{
 };
 pub use coordination::{
     AgentId, AgentMessage, BroadcastMessage, Message as CoordinationMessage, MessageBus,
-    MessagePriority, MessageType, MultiAgentSession, ResourceManager, Task, TaskDistributor,
-    TaskId, TaskPriority,
+    MessagePriority, MessageType, MultiAgentSession, ResourceManager, SchedulingPolicy, SharedDoc,
+    SharedDocStore, Task, TaskDistributor, TaskId, TaskPriority,
 };
 pub use core::{
-    AISession, AttentionState, ContextConfig, SessionConfig, SessionError, SessionId,
-    SessionResult, SessionStatus, pty::PtyHandle,
+    AISession, AttentionState, CommandEvent, ContextConfig, ExpectMatch, ExpectPattern,
+    ExpectTimeout, HookId, OutputChunk, OutputWatcher, SessionConfig, SessionError, SessionEvent,
+    SessionId, SessionResult, SessionStatus, StateChange, TerminalSignal,
+    pty::PtyHandle,
+    stop_hooks::{StopHook, StopHookOutcome},
 };
 pub use execution::{
-    CommandExecution, DEFAULT_MAX_PROMPT_BYTES, prepare_provider_prompt, run_provider_command,
+    CommandExecution, CommandTimeout, DEFAULT_MAX_PROMPT_BYTES, TimedOut, prepare_provider_prompt,
+    run_command_with_timeout, run_provider_command, run_provider_command_streaming,
 };
 pub use output::{OutputManager, OutputParser, ParsedOutput};
 
@@ -14,8 +14,7 @@ pub struct NativeSession {
     id: String,
     /// Session name
     name: String,
-    /// PTY master
-    #[allow(dead_code)]
+    /// PTY master, kept for resizing
     pty_master: Option<Box<dyn portable_pty::MasterPty + Send>>,
     /// Child process
     child: Option<Box<dyn portable_pty::Child + Send + Sync>>,
@@ -118,11 +117,9 @@ impl NativeSession {
             .take_writer()
             .context("Failed to take writer")?;
 
-        // Store the child process
+        // Store the child process, and the master so the window can be resized
         self.child = Some(child);
-
-        // Note: We can't store the master after taking the writer from it
-        // The master is consumed by take_writer(), so we'll handle resize differently
+        self.pty_master = Some(pty_pair.master);
 
         // Update status
         *self.status.write().await = SessionStatus::Running;
@@ -228,11 +225,11 @@ impl NativeSession {
             pixel_width: 0,
             pixel_height: 0,
         };
-
-        // Note: Since we don't store the pty_master after taking the writer,
-        // we can't resize the PTY. This is a limitation of the current design.
-        // In a production implementation, we would need to handle this differently.
-
+        if let Some(master) = &self.pty_master {
+            master
+                .resize(self.window_size)
+                .context("Failed to resize PTY")?;
+        }
         Ok(())
     }
 
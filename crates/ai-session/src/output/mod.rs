@@ -4,6 +4,16 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::execution::CommandExecution;
+
+mod plugins;
+mod progress;
+mod sampler;
+
+pub use plugins::{Diagnostic, DiagnosticParser, ParserConfig};
+pub use progress::ProgressSummary;
+pub use sampler::{OutputSampler, SampledOutput, SamplingConfig, SamplingLevel};
+
 /// Output manager for intelligent processing
 pub struct OutputManager {
     /// Output parser
@@ -86,6 +96,22 @@ impl OutputManager {
                     severity: Severity::Error,
                 });
             }
+            ParsedOutput::Diagnostics { diagnostics, .. } => {
+                highlights.extend(
+                    diagnostics
+                        .iter()
+                        .filter(|d| d.severity != Severity::Info)
+                        .map(|d| Highlight {
+                            category: if d.severity == Severity::Warning {
+                                HighlightCategory::Warning
+                            } else {
+                                HighlightCategory::Error
+                            },
+                            message: d.to_line(),
+                            severity: d.severity,
+                        }),
+                );
+            }
             ParsedOutput::StructuredLog { level, message, .. } => {
                 if matches!(level, LogLevel::Error | LogLevel::Warning) {
                     highlights.push(Highlight {
@@ -116,6 +142,8 @@ impl Default for OutputManager {
 pub struct OutputParser {
     /// Pattern matchers
     patterns: HashMap<String, regex::Regex>,
+    /// Tool-specific diagnostic parsers, tried in order
+    plugins: Vec<Box<dyn DiagnosticParser>>,
 }
 
 impl OutputParser {
@@ -137,11 +165,46 @@ impl OutputParser {
             regex::Regex::new(r"(?i)(success|passed|completed)").unwrap(),
         );
 
-        Self { patterns }
+        Self {
+            patterns,
+            plugins: plugins::builtin(),
+        }
+    }
+
+    /// Register a diagnostic parser. It is tried before the built-in ones.
+    pub fn register(&mut self, parser: Box<dyn DiagnosticParser>) {
+        self.plugins.insert(0, parser);
+    }
+
+    /// A parser with the regex parsers from `configs` registered on top of
+    /// the built-in ones.
+    pub fn with_configs(configs: &[ParserConfig]) -> Result<Self> {
+        let mut parser = Self::new();
+        for config in configs.iter().rev() {
+            parser.register(config.compile()?);
+        }
+        Ok(parser)
+    }
+
+    /// Names of the registered diagnostic parsers, in the order they are tried.
+    pub fn plugin_names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|p| p.name()).collect()
+    }
+
+    /// Diagnostics from the first registered parser that recognises `output`.
+    pub fn diagnostics(&self, output: &str) -> Option<(String, Vec<Diagnostic>)> {
+        self.plugins.iter().find_map(|plugin| {
+            let found = plugin.parse(output);
+            (!found.is_empty()).then(|| (plugin.name().to_string(), found))
+        })
     }
 
     /// Parse output into structured format
     pub fn parse(&self, output: &str) -> Result<ParsedOutput> {
+        if let Some((tool, diagnostics)) = self.diagnostics(output) {
+            return Ok(ParsedOutput::Diagnostics { tool, diagnostics });
+        }
+
         // Build output patterns
         if output.contains("BUILD SUCCESSFUL") || output.contains("Build succeeded") {
             return Ok(ParsedOutput::BuildOutput {
@@ -224,6 +287,21 @@ impl OutputParser {
 
         Ok(ParsedOutput::PlainText(output.to_string()))
     }
+
+    /// Parse a command's combined output, marking it timed out when the
+    /// command was stopped for outrunning its limit.
+    pub fn parse_execution(&self, execution: &CommandExecution) -> Result<ParsedOutput> {
+        let output = format!("{}{}", execution.stdout, execution.stderr);
+        let parsed = self.parse(&output)?;
+        Ok(match &execution.timed_out {
+            Some(timed_out) => ParsedOutput::TimedOut {
+                timeout_secs: timed_out.timeout_secs,
+                signal: timed_out.signal.clone(),
+                partial: Box::new(parsed),
+            },
+            None => parsed,
+        })
+    }
 }
 
 impl Default for OutputParser {
@@ -305,6 +383,33 @@ pub enum ParsedOutput {
         message: String,
         context: LogContext,
     },
+
+    /// Diagnostics from a tool-specific parser (eslint, tsc, ...)
+    Diagnostics {
+        tool: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+
+    /// A command stopped for outrunning its timeout, with what its output
+    /// parsed as up to then
+    TimedOut {
+        timeout_secs: u64,
+        signal: String,
+        partial: Box<ParsedOutput>,
+    },
+}
+
+impl ParsedOutput {
+    /// Diagnostics at error severity or above, if this is a diagnostics result.
+    pub fn error_diagnostics(&self) -> Vec<&Diagnostic> {
+        match self {
+            ParsedOutput::Diagnostics { diagnostics, .. } => diagnostics
+                .iter()
+                .filter(|d| matches!(d.severity, Severity::Error | Severity::Critical))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
 }
 
 /// Execution metrics
@@ -562,4 +667,78 @@ mod tests {
             other => panic!("expected TestResults, got {:?}", other),
         }
     }
+
+    // --- diagnostic plugins ---
+
+    #[test]
+    fn test_diagnostics_take_precedence_and_highlight() {
+        let mut manager = OutputManager::new();
+        let output = "src/app.ts(4,1): error TS2304: Cannot find name 'foo'.\nBuild failed";
+        let processed = manager.process_output(output).unwrap();
+        match &processed.parsed {
+            ParsedOutput::Diagnostics { tool, diagnostics } => {
+                assert_eq!(tool, "tsc");
+                assert_eq!(diagnostics.len(), 1);
+            }
+            other => panic!("expected Diagnostics, got {:?}", other),
+        }
+        assert_eq!(processed.parsed.error_diagnostics().len(), 1);
+        assert_eq!(processed.highlights[0].category, HighlightCategory::Error);
+    }
+
+    #[test]
+    fn test_registered_parser_runs_first() {
+        struct Lint;
+        impl DiagnosticParser for Lint {
+            fn name(&self) -> &str {
+                "lint"
+            }
+            fn parse(&self, output: &str) -> Vec<Diagnostic> {
+                output
+                    .lines()
+                    .filter_map(|l| l.strip_prefix("LINT "))
+                    .map(|m| Diagnostic {
+                        tool: "lint".into(),
+                        severity: Severity::Warning,
+                        file: None,
+                        line: None,
+                        column: None,
+                        code: None,
+                        message: m.into(),
+                    })
+                    .collect()
+            }
+        }
+
+        let mut parser = OutputParser::new();
+        parser.register(Box::new(Lint));
+        assert_eq!(parser.plugin_names()[0], "lint");
+        let (tool, found) = parser.diagnostics("LINT trailing space").unwrap();
+        assert_eq!(
+            (tool.as_str(), found[0].message.as_str()),
+            ("lint", "trailing space")
+        );
+
+        let configured = OutputParser::with_configs(&[ParserConfig {
+            name: "ruff".into(),
+            pattern: r"^(?P<file>\S+\.py):(?P<line>\d+):(?P<column>\d+): (?P<code>[A-Z]\d+) (?P<message>.+)$".into(),
+            severity: "warning".into(),
+        }])
+        .unwrap();
+        match configured
+            .parse("app.py:1:1: F401 `os` imported but unused")
+            .unwrap()
+        {
+            ParsedOutput::Diagnostics { tool, diagnostics } => {
+                assert_eq!(tool, "ruff");
+                assert_eq!(diagnostics[0].code.as_deref(), Some("F401"));
+            }
+            other => panic!("expected Diagnostics, got {:?}", other),
+        }
+        // Plain build markers still fall through to the legacy patterns.
+        assert!(matches!(
+            configured.parse("BUILD SUCCESSFUL").unwrap(),
+            ParsedOutput::BuildOutput { .. }
+        ));
+    }
 }
@@ -0,0 +1,554 @@
+//! Tool-specific diagnostic parsers.
+//!
+//! A [`DiagnosticParser`] recognises one tool's output and turns it into
+//! [`Diagnostic`]s. [`OutputParser`](super::OutputParser) ships parsers for
+//! eslint, tsc, go vet, Gradle and `terraform plan`; more can be registered at
+//! runtime with [`OutputParser::register`](super::OutputParser::register) or
+//! described as regexes in a [`ParserConfig`].
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::Severity;
+
+/// One problem reported by a tool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Parser that produced it (`eslint`, `tsc`, ...).
+    pub tool: String,
+    pub severity: Severity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// Rule or error code (`no-unused-vars`, `TS2322`, ...).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(tool: &str, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            tool: tool.to_string(),
+            severity,
+            file: None,
+            line: None,
+            column: None,
+            code: None,
+            message: message.into(),
+        }
+    }
+
+    fn at(mut self, file: &str, line: Option<usize>, column: Option<usize>) -> Self {
+        self.file = Some(file.to_string());
+        self.line = line;
+        self.column = column;
+        self
+    }
+
+    fn code(mut self, code: Option<&str>) -> Self {
+        self.code = code.map(str::to_string);
+        self
+    }
+
+    /// `file:line:col: severity: message [code]`
+    pub fn to_line(&self) -> String {
+        let mut location = self.file.clone().unwrap_or_default();
+        for n in [self.line, self.column].into_iter().flatten() {
+            location.push_str(&format!(":{n}"));
+        }
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        };
+        let code = self
+            .code
+            .as_deref()
+            .map(|c| format!(" [{c}]"))
+            .unwrap_or_default();
+        if location.is_empty() {
+            format!("{severity}: {}{code}", self.message)
+        } else {
+            format!("{location}: {severity}: {}{code}", self.message)
+        }
+    }
+}
+
+/// Recognises one tool's output.
+pub trait DiagnosticParser: Send + Sync {
+    /// Short tool name, used as [`Diagnostic::tool`].
+    fn name(&self) -> &str;
+
+    /// The diagnostics in `output`; empty when it isn't this tool's output.
+    fn parse(&self, output: &str) -> Vec<Diagnostic>;
+}
+
+fn severity_from(word: &str) -> Severity {
+    match word.to_ascii_lowercase().as_str() {
+        "warning" | "warn" | "w" => Severity::Warning,
+        "info" | "note" | "hint" | "i" => Severity::Info,
+        "fatal" | "critical" => Severity::Critical,
+        _ => Severity::Error,
+    }
+}
+
+fn number(caps: &regex::Captures<'_>, name: &str) -> Option<usize> {
+    caps.name(name).and_then(|m| m.as_str().parse().ok())
+}
+
+/// Parsers registered by default, in the order they are tried.
+pub(super) fn builtin() -> Vec<Box<dyn DiagnosticParser>> {
+    vec![
+        Box::new(Tsc::new()),
+        Box::new(Eslint::new()),
+        Box::new(GoVet::new()),
+        Box::new(Gradle::new()),
+        Box::new(TerraformPlan::new()),
+    ]
+}
+
+/// `src/a.ts(12,5): error TS2322: ...` and the `--pretty` form
+/// `src/a.ts:12:5 - error TS2322: ...`.
+struct Tsc {
+    line: Regex,
+}
+
+impl Tsc {
+    fn new() -> Self {
+        Self {
+            line: Regex::new(
+                r"^(?P<file>[^\s(:][^(:]*?\.[cm]?tsx?)(?:\((?P<line>\d+),(?P<col>\d+)\)|:(?P<line2>\d+):(?P<col2>\d+))(?::| -) (?P<sev>error|warning) (?P<code>TS\d+): (?P<msg>.+)$",
+            )
+            .expect("valid regex"),
+        }
+    }
+}
+
+impl DiagnosticParser for Tsc {
+    fn name(&self) -> &str {
+        "tsc"
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        output
+            .lines()
+            .filter_map(|l| self.line.captures(l.trim_end()))
+            .map(|c| {
+                let line = number(&c, "line").or_else(|| number(&c, "line2"));
+                let col = number(&c, "col").or_else(|| number(&c, "col2"));
+                Diagnostic::new("tsc", severity_from(&c["sev"]), &c["msg"])
+                    .at(&c["file"], line, col)
+                    .code(c.name("code").map(|m| m.as_str()))
+            })
+            .collect()
+    }
+}
+
+/// eslint's default "stylish" format (a file path line followed by indented
+/// `line:col  severity  message  rule` rows) and `--format unix`.
+struct Eslint {
+    stylish_row: Regex,
+    unix: Regex,
+}
+
+impl Eslint {
+    fn new() -> Self {
+        Self {
+            stylish_row: Regex::new(
+                r"^\s+(?P<line>\d+):(?P<col>\d+)\s+(?P<sev>error|warning)\s+(?P<msg>.+?)(?:\s{2,}(?P<rule>[\w@/-]+))?\s*$",
+            )
+            .expect("valid regex"),
+            unix: Regex::new(
+                r"^(?P<file>[^:\s][^:]*\.(?:[cm]?[jt]sx?|vue|svelte)):(?P<line>\d+):(?P<col>\d+): (?P<msg>.+?) \[(?P<sev>Error|Warning)(?:/(?P<rule>[\w@/-]+))?\]$",
+            )
+            .expect("valid regex"),
+        }
+    }
+}
+
+impl DiagnosticParser for Eslint {
+    fn name(&self) -> &str {
+        "eslint"
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        let mut out = Vec::new();
+        let mut file: Option<&str> = None;
+        for line in output.lines() {
+            if let Some(c) = self.unix.captures(line) {
+                out.push(
+                    Diagnostic::new("eslint", severity_from(&c["sev"]), &c["msg"])
+                        .at(&c["file"], number(&c, "line"), number(&c, "col"))
+                        .code(c.name("rule").map(|m| m.as_str())),
+                );
+            } else if let (Some(f), Some(c)) = (file, self.stylish_row.captures(line)) {
+                out.push(
+                    Diagnostic::new("eslint", severity_from(&c["sev"]), c["msg"].trim())
+                        .at(f, number(&c, "line"), number(&c, "col"))
+                        .code(c.name("rule").map(|m| m.as_str())),
+                );
+            } else if !line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+                // A stylish file header is an unindented path; anything else
+                // (the "✖ N problems" summary) ends the section.
+                file = Some(line.trim()).filter(|l| !l.contains(' ') && l.contains('.'));
+            }
+        }
+        out
+    }
+}
+
+/// `go vet` (and `go build`): `./pkg/file.go:12:5: message`, with `# pkg`
+/// headers and `vet: ` prefixes.
+struct GoVet {
+    line: Regex,
+}
+
+impl GoVet {
+    fn new() -> Self {
+        Self {
+            line: Regex::new(
+                r"^(?:vet: )?(?P<file>[^\s:][^:]*\.go):(?P<line>\d+)(?::(?P<col>\d+))?: (?P<msg>.+)$",
+            )
+            .expect("valid regex"),
+        }
+    }
+}
+
+impl DiagnosticParser for GoVet {
+    fn name(&self) -> &str {
+        "go vet"
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        output
+            .lines()
+            .filter_map(|l| self.line.captures(l.trim_end()))
+            .map(|c| {
+                Diagnostic::new("go vet", Severity::Error, &c["msg"]).at(
+                    &c["file"],
+                    number(&c, "line"),
+                    number(&c, "col"),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Gradle builds: Kotlin (`e: file:///src/A.kt:12:5 message`), javac
+/// (`/src/A.java:12: error: message`) and the `* What went wrong:` block.
+struct Gradle {
+    kotlin: Regex,
+    javac: Regex,
+}
+
+impl Gradle {
+    fn new() -> Self {
+        Self {
+            kotlin: Regex::new(
+                r"^(?P<sev>[ew]): (?:file://)?(?P<file>[^\s:]+\.kts?):(?P<line>\d+):(?P<col>\d+) (?P<msg>.+)$",
+            )
+            .expect("valid regex"),
+            javac: Regex::new(
+                r"^(?P<file>[^\s:][^:]*\.java):(?P<line>\d+): (?P<sev>error|warning): (?P<msg>.+)$",
+            )
+            .expect("valid regex"),
+        }
+    }
+}
+
+impl DiagnosticParser for Gradle {
+    fn name(&self) -> &str {
+        "gradle"
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        let mut out = Vec::new();
+        let mut lines = output.lines().peekable();
+        while let Some(line) = lines.next() {
+            let line = line.trim_end();
+            if let Some(c) = self.kotlin.captures(line) {
+                out.push(
+                    Diagnostic::new("gradle", severity_from(&c["sev"]), &c["msg"]).at(
+                        &c["file"],
+                        number(&c, "line"),
+                        number(&c, "col"),
+                    ),
+                );
+            } else if let Some(c) = self.javac.captures(line) {
+                out.push(
+                    Diagnostic::new("gradle", severity_from(&c["sev"]), &c["msg"]).at(
+                        &c["file"],
+                        number(&c, "line"),
+                        None,
+                    ),
+                );
+            } else if line == "* What went wrong:"
+                && let Some(reason) = lines.next()
+            {
+                out.push(Diagnostic::new("gradle", Severity::Error, reason.trim()));
+            }
+        }
+        // javac/kotlinc lines alone look like any compiler; only claim them
+        // when the output is recognisably Gradle's.
+        if output.contains("BUILD FAILED")
+            || output.contains("BUILD SUCCESSFUL")
+            || output.contains("> Task :")
+        {
+            out
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// `terraform plan`: `Error:` / `Warning:` blocks (with their `on file line N`
+/// location) and the `Plan: N to add, ...` summary as an info diagnostic.
+struct TerraformPlan {
+    header: Regex,
+    location: Regex,
+    summary: Regex,
+}
+
+impl TerraformPlan {
+    fn new() -> Self {
+        Self {
+            header: Regex::new(r"^[│╷\s]*(?P<sev>Error|Warning): (?P<msg>.+)$")
+                .expect("valid regex"),
+            location: Regex::new(r"^[│\s]*on (?P<file>\S+\.tf(?:\.json)?) line (?P<line>\d+)")
+                .expect("valid regex"),
+            summary: Regex::new(r"^Plan: \d+ to add, \d+ to change, \d+ to destroy\.")
+                .expect("valid regex"),
+        }
+    }
+}
+
+impl DiagnosticParser for TerraformPlan {
+    fn name(&self) -> &str {
+        "terraform"
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        if !output.contains("terraform")
+            && !output.contains("Terraform")
+            && !output.lines().any(|l| self.summary.is_match(l))
+            && !output.lines().any(|l| self.location.is_match(l))
+        {
+            return Vec::new();
+        }
+        let mut out: Vec<Diagnostic> = Vec::new();
+        let mut in_block = false;
+        for line in output.lines() {
+            if let Some(c) = self.header.captures(line) {
+                out.push(Diagnostic::new(
+                    "terraform",
+                    severity_from(&c["sev"]),
+                    c["msg"].trim(),
+                ));
+                in_block = true;
+            } else if in_block && let Some(c) = self.location.captures(line) {
+                if let Some(last) = out.last_mut() {
+                    last.file = Some(c["file"].to_string());
+                    last.line = number(&c, "line");
+                }
+                in_block = false;
+            } else if self.summary.is_match(line) {
+                out.push(Diagnostic::new("terraform", Severity::Info, line.trim()));
+            }
+        }
+        out
+    }
+}
+
+/// A regex-described parser from configuration.
+///
+/// `pattern` is matched against each output line; the named groups `file`,
+/// `line`, `column`, `severity`, `code` and `message` fill the diagnostic
+/// (only `message` is required). Lines without a `severity` group use
+/// `severity`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParserConfig {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+fn default_severity() -> String {
+    "error".to_string()
+}
+
+impl ParserConfig {
+    /// Compile into a parser. Fails on an invalid regex or one without a
+    /// `message` group.
+    pub fn compile(&self) -> Result<Box<dyn DiagnosticParser>> {
+        let regex = Regex::new(&self.pattern)
+            .with_context(|| format!("parser '{}': invalid pattern", self.name))?;
+        if !regex.capture_names().any(|n| n == Some("message")) {
+            anyhow::bail!(
+                "parser '{}': pattern needs a (?P<message>...) group",
+                self.name
+            );
+        }
+        Ok(Box::new(RegexParser {
+            name: self.name.clone(),
+            regex,
+            severity: severity_from(&self.severity),
+        }))
+    }
+}
+
+struct RegexParser {
+    name: String,
+    regex: Regex,
+    severity: Severity,
+}
+
+impl DiagnosticParser for RegexParser {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parse(&self, output: &str) -> Vec<Diagnostic> {
+        output
+            .lines()
+            .filter_map(|l| self.regex.captures(l))
+            .map(|c| {
+                let severity = c
+                    .name("severity")
+                    .map(|m| severity_from(m.as_str()))
+                    .unwrap_or(self.severity);
+                let mut d = Diagnostic::new(&self.name, severity, c["message"].trim())
+                    .code(c.name("code").map(|m| m.as_str()));
+                if let Some(file) = c.name("file") {
+                    d = d.at(file.as_str(), number(&c, "line"), number(&c, "column"));
+                }
+                d
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first(parser: &dyn DiagnosticParser, output: &str) -> Diagnostic {
+        parser
+            .parse(output)
+            .into_iter()
+            .next()
+            .expect("a diagnostic")
+    }
+
+    #[test]
+    fn test_tsc_both_formats() {
+        let d = first(
+            &Tsc::new(),
+            "src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.",
+        );
+        assert_eq!(d.file.as_deref(), Some("src/app.ts"));
+        assert_eq!((d.line, d.column), (Some(12), Some(5)));
+        assert_eq!(d.code.as_deref(), Some("TS2322"));
+        let d = first(
+            &Tsc::new(),
+            "src/ui/App.tsx:3:1 - warning TS6133: 'x' is declared but never used.",
+        );
+        assert_eq!(d.severity, Severity::Warning);
+        assert_eq!(d.line, Some(3));
+    }
+
+    #[test]
+    fn test_eslint_stylish_and_unix() {
+        let stylish = "\n/repo/src/index.js\n  1:10  error    'foo' is defined but never used  no-unused-vars\n  \
+                       4:1   warning  Unexpected console statement      no-console\n\n\
+                       ✖ 2 problems (1 error, 1 warning)\n";
+        let ds = Eslint::new().parse(stylish);
+        assert_eq!(ds.len(), 2);
+        assert_eq!(ds[0].file.as_deref(), Some("/repo/src/index.js"));
+        assert_eq!(ds[0].message, "'foo' is defined but never used");
+        assert_eq!(ds[0].code.as_deref(), Some("no-unused-vars"));
+        assert_eq!(ds[1].severity, Severity::Warning);
+
+        let d = first(
+            &Eslint::new(),
+            "src/a.ts:3:7: Missing semicolon. [Error/semi]",
+        );
+        assert_eq!(d.code.as_deref(), Some("semi"));
+        assert_eq!(
+            d.to_line(),
+            "src/a.ts:3:7: error: Missing semicolon. [semi]"
+        );
+    }
+
+    #[test]
+    fn test_go_vet() {
+        let out = "# example.com/app\nvet: ./main.go:9:2: fmt.Printf format %d has arg s of wrong type string\n";
+        let d = first(&GoVet::new(), out);
+        assert_eq!(d.file.as_deref(), Some("./main.go"));
+        assert_eq!((d.line, d.column), (Some(9), Some(2)));
+        assert!(GoVet::new().parse("all good").is_empty());
+    }
+
+    #[test]
+    fn test_gradle_kotlin_javac_and_what_went_wrong() {
+        let out = "> Task :app:compileKotlin FAILED\n\
+                   e: file:///repo/app/src/Main.kt:7:13 Unresolved reference: foo\n\
+                   /repo/lib/src/A.java:4: error: cannot find symbol\n\n\
+                   FAILURE: Build failed with an exception.\n\n* What went wrong:\n\
+                   Execution failed for task ':app:compileKotlin'.\n\nBUILD FAILED in 3s\n";
+        let ds = Gradle::new().parse(out);
+        assert_eq!(ds.len(), 3);
+        assert_eq!(ds[0].file.as_deref(), Some("/repo/app/src/Main.kt"));
+        assert_eq!(ds[1].line, Some(4));
+        assert_eq!(
+            ds[2].message,
+            "Execution failed for task ':app:compileKotlin'."
+        );
+        // Bare javac output is left to other parsers.
+        assert!(Gradle::new().parse("A.java:4: error: x").is_empty());
+    }
+
+    #[test]
+    fn test_terraform_plan() {
+        let out = "╷\n│ Error: Unsupported argument\n│ \n│   on main.tf line 12, in resource \"aws_s3_bucket\" \"b\":\n\
+                   │   12:   acl2 = \"private\"\n╵\n";
+        let d = first(&TerraformPlan::new(), out);
+        assert_eq!(d.message, "Unsupported argument");
+        assert_eq!((d.file.as_deref(), d.line), (Some("main.tf"), Some(12)));
+
+        let ds = TerraformPlan::new().parse("Plan: 1 to add, 0 to change, 0 to destroy.\n");
+        assert_eq!(ds[0].severity, Severity::Info);
+    }
+
+    #[test]
+    fn test_config_parser() {
+        let parser = ParserConfig {
+            name: "mypy".into(),
+            pattern:
+                r"^(?P<file>[^:]+\.py):(?P<line>\d+): (?P<severity>error|note): (?P<message>.+)$"
+                    .into(),
+            severity: "error".into(),
+        }
+        .compile()
+        .unwrap();
+        let d = first(
+            parser.as_ref(),
+            "app/models.py:3: error: Incompatible types",
+        );
+        assert_eq!(d.tool, "mypy");
+        assert_eq!(d.line, Some(3));
+
+        let missing_group = ParserConfig {
+            name: "x".into(),
+            pattern: "(?P<file>.+)".into(),
+            severity: "error".into(),
+        };
+        assert!(missing_group.compile().is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Interim summaries of output from commands that are still running.
+//!
+//! A test run or build can take minutes. [`ProgressSummary::of`] reads the
+//! output produced so far and reduces it to what an agent can act on before
+//! the command finishes: per-test results counted line by line (cargo,
+//! pytest, go test, jest/vitest), the names of the first failures, error and
+//! warning counts, and the latest line printed.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+use super::{OutputParser, Severity};
+
+/// Failing test names kept in a summary.
+const MAX_FAILURES: usize = 5;
+/// Characters kept of the latest line.
+const MAX_LATEST_CHARS: usize = 160;
+
+static TEST_OK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^test \S+ \.\.\. ok$|^--- PASS: |^\S+\s+PASSED\b|^\s*[✓✔√] ").expect("valid regex")
+});
+static TEST_FAILED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^test (\S+) \.\.\. FAILED$|^--- FAIL: (\S+)|^(\S+)\s+FAILED\b|^\s*[✕✗×] (.+?)(?: \(\d+ ?m?s\))?$")
+        .expect("valid regex")
+});
+static ERROR_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(error(\[\w+\])?:|Error:|FATAL\b|panicked at\b|thread '.*' panicked)")
+        .expect("valid regex")
+});
+static WARNING_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(warning(\[\w+\])?:|WARN\b)").expect("valid regex"));
+
+/// What a running command's output shows so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressSummary {
+    pub lines: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// Names of the first failing tests.
+    pub failures: Vec<String>,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Last non-empty line, shortened.
+    pub latest: Option<String>,
+}
+
+impl ProgressSummary {
+    /// Summarize `partial` output, using `parser`'s diagnostic plugins to
+    /// count errors from tools that don't print one per line.
+    pub fn of(parser: &OutputParser, partial: &str) -> Self {
+        let mut summary = Self::default();
+        for line in partial.lines() {
+            let trimmed = line.trim_end();
+            if trimmed.trim().is_empty() {
+                continue;
+            }
+            summary.lines += 1;
+            summary.latest = Some(trimmed.trim().to_string());
+            if TEST_OK.is_match(trimmed) {
+                summary.passed += 1;
+            } else if let Some(caps) = TEST_FAILED.captures(trimmed) {
+                summary.failed += 1;
+                if summary.failures.len() < MAX_FAILURES
+                    && let Some(name) = caps.iter().skip(1).flatten().next()
+                {
+                    summary.failures.push(name.as_str().to_string());
+                }
+            } else if ERROR_LINE.is_match(trimmed) {
+                summary.errors += 1;
+            } else if WARNING_LINE.is_match(trimmed) {
+                summary.warnings += 1;
+            }
+        }
+        if summary.errors == 0
+            && let Some((_, diagnostics)) = parser.diagnostics(partial)
+        {
+            for diagnostic in diagnostics {
+                match diagnostic.severity {
+                    Severity::Error | Severity::Critical => summary.errors += 1,
+                    Severity::Warning => summary.warnings += 1,
+                    _ => {}
+                }
+            }
+        }
+        summary.latest =
+            summary
+                .latest
+                .map(|line| match line.char_indices().nth(MAX_LATEST_CHARS) {
+                    Some((cut, _)) => format!("{}…", &line[..cut]),
+                    None => line,
+                });
+        summary
+    }
+
+    /// One or two lines of text, e.g. `300 tests passed so far, 2 failed
+    /// (a::b, c::d); 1 error`.
+    pub fn render(&self) -> String {
+        let mut parts = Vec::new();
+        if self.passed + self.failed > 0 {
+            let mut tests = format!(
+                "{} test{} passed so far, {} failed",
+                self.passed,
+                if self.passed == 1 { "" } else { "s" },
+                self.failed
+            );
+            if !self.failures.is_empty() {
+                tests.push_str(&format!(" ({})", self.failures.join(", ")));
+            }
+            parts.push(tests);
+        }
+        for (count, noun) in [(self.errors, "error"), (self.warnings, "warning")] {
+            if count > 0 {
+                parts.push(format!(
+                    "{count} {noun}{}",
+                    if count == 1 { "" } else { "s" }
+                ));
+            }
+        }
+        if parts.is_empty() {
+            parts.push(format!(
+                "{} line{} of output so far",
+                self.lines,
+                if self.lines == 1 { "" } else { "s" }
+            ));
+        }
+        let mut text = parts.join("; ");
+        if let Some(latest) = &self.latest {
+            text.push_str(&format!("\nlatest: {latest}"));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_results_from_partial_runs() {
+        let parser = OutputParser::new();
+        let mut cargo = String::from("running 400 tests\n");
+        for i in 0..300 {
+            cargo.push_str(&format!("test suite::case_{i} ... ok\n"));
+        }
+        cargo.push_str("test suite::broken ... FAILED\ntest suite::flaky ... FAILED\n");
+        let summary = ProgressSummary::of(&parser, &cargo);
+        assert_eq!((summary.passed, summary.failed), (300, 2));
+        assert_eq!(summary.failures, vec!["suite::broken", "suite::flaky"]);
+        assert!(
+            summary
+                .render()
+                .starts_with("300 tests passed so far, 2 failed (suite::broken, suite::flaky)")
+        );
+
+        let go = "=== RUN   TestA\n--- PASS: TestA (0.00s)\n--- FAIL: TestB (0.01s)\n";
+        let summary = ProgressSummary::of(&parser, go);
+        assert_eq!(summary.failures, vec!["TestB"]);
+
+        let build = "   Compiling a v0.1.0\nerror[E0308]: mismatched types\nwarning: unused\n";
+        let summary = ProgressSummary::of(&parser, build);
+        assert_eq!((summary.errors, summary.warnings), (1, 1));
+        assert_eq!(
+            summary.render(),
+            "1 error; 1 warning\nlatest: warning: unused"
+        );
+
+        assert_eq!(
+            ProgressSummary::of(&parser, "Downloading...\n").render(),
+            "1 line of output so far\nlatest: Downloading..."
+        );
+    }
+}
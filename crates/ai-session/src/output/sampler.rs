@@ -0,0 +1,322 @@
+//! Salient-line sampling of long command output.
+//!
+//! Chatty commands (verbose builds, test runners, package installs) print far
+//! more than a model needs. [`OutputSampler`] keeps the lines that matter —
+//! errors, warnings, diagnostics the [`OutputParser`] recognises and final
+//! summaries — plus the start and end of the output, collapses repeats, and
+//! caps how many salient lines get through. Output shorter than
+//! [`SamplingConfig::threshold_lines`] is passed through untouched.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{BuildStatus, OutputParser, ParsedOutput, Severity};
+
+/// How hard to cut.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingLevel {
+    /// Pass output through whole.
+    Off,
+    /// Salient lines with a little surrounding context.
+    #[default]
+    Light,
+    /// Salient lines only, with a short head and tail.
+    Aggressive,
+}
+
+/// The `output_sampling` config knob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplingConfig {
+    pub level: SamplingLevel,
+    /// Output with at most this many lines is never sampled.
+    pub threshold_lines: usize,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            level: SamplingLevel::Light,
+            threshold_lines: 200,
+        }
+    }
+}
+
+struct Limits {
+    head: usize,
+    tail: usize,
+    /// Lines kept on each side of a salient line.
+    context: usize,
+    /// Distinct salient lines kept; the rest are only counted.
+    max_salient: usize,
+}
+
+impl SamplingLevel {
+    fn limits(self) -> Option<Limits> {
+        match self {
+            SamplingLevel::Off => None,
+            SamplingLevel::Light => Some(Limits {
+                head: 20,
+                tail: 30,
+                context: 2,
+                max_salient: 200,
+            }),
+            SamplingLevel::Aggressive => Some(Limits {
+                head: 5,
+                tail: 10,
+                context: 0,
+                max_salient: 50,
+            }),
+        }
+    }
+}
+
+/// Result of [`OutputSampler::sample`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampledOutput {
+    pub text: String,
+    pub total_lines: usize,
+    pub kept_lines: usize,
+    /// False when the output was passed through whole.
+    pub sampled: bool,
+}
+
+/// Keeps the salient lines of long output.
+pub struct OutputSampler {
+    config: SamplingConfig,
+    error: Regex,
+    warning: Regex,
+    summary: Regex,
+    digits: Regex,
+}
+
+impl OutputSampler {
+    pub fn new(config: SamplingConfig) -> Self {
+        Self {
+            config,
+            error: Regex::new(
+                r"(?i)\b(error|errors|exception|panicked|fatal|failed|failure|traceback)\b|^E\s",
+            )
+            .expect("valid regex"),
+            warning: Regex::new(r"(?i)\b(warning|warn|deprecated)\b").expect("valid regex"),
+            summary: Regex::new(
+                r"(?i)^\s*(test result:|tests?:\s|test suites?:|finished\b|build (successful|succeeded|failed)|done in\b|ran \d+ tests?|=+ .*\b(passed|failed)\b.* =+$|\d+ (passed|failed|problems?)\b|(added|removed|changed) \d+ packages?)",
+            )
+            .expect("valid regex"),
+            digits: Regex::new(r"\d+").expect("valid regex"),
+        }
+    }
+
+    pub fn config(&self) -> &SamplingConfig {
+        &self.config
+    }
+
+    /// Sample `raw`, using `parser` for diagnostics and the overall verdict.
+    pub fn sample(&self, parser: &OutputParser, raw: &str) -> SampledOutput {
+        let lines: Vec<&str> = raw.lines().collect();
+        let total = lines.len();
+        let limits = match self.config.level.limits() {
+            Some(limits) if total > self.config.threshold_lines => limits,
+            _ => {
+                return SampledOutput {
+                    text: raw.to_string(),
+                    total_lines: total,
+                    kept_lines: total,
+                    sampled: false,
+                };
+            }
+        };
+
+        let diagnostics = parser.diagnostics(raw);
+        let flagged: Vec<&str> = diagnostics
+            .iter()
+            .flat_map(|(_, found)| found.iter().map(|d| d.message.as_str()))
+            .collect();
+
+        let mut keep = vec![false; total];
+        for index in (0..limits.head.min(total)).chain(total.saturating_sub(limits.tail)..total) {
+            keep[index] = true;
+        }
+        // First index of each distinct salient line, and how often it repeated.
+        let mut repeats: HashMap<String, (usize, usize)> = HashMap::new();
+        let mut dropped = 0;
+        for (index, line) in lines.iter().enumerate() {
+            if !self.is_salient(line, &flagged) {
+                continue;
+            }
+            let key = self.digits.replace_all(line.trim(), "#").to_lowercase();
+            if let Some((_, count)) = repeats.get_mut(&key) {
+                *count += 1;
+                continue;
+            }
+            if repeats.len() >= limits.max_salient {
+                dropped += 1;
+                continue;
+            }
+            repeats.insert(key, (index, 0));
+            let from = index.saturating_sub(limits.context);
+            let to = (index + limits.context).min(total - 1);
+            keep[from..=to].iter_mut().for_each(|k| *k = true);
+        }
+        let repeated: HashMap<usize, usize> = repeats
+            .into_values()
+            .filter(|(_, count)| *count > 0)
+            .collect();
+
+        let kept = keep.iter().filter(|k| **k).count();
+        let mut text = format!("[output sampled: kept {kept} of {total} lines");
+        if let Some(verdict) = verdict(parser, raw, diagnostics.as_ref()) {
+            text.push_str(&format!("; {verdict}"));
+        }
+        if dropped > 0 {
+            text.push_str(&format!("; {dropped} more salient lines not shown"));
+        }
+        text.push_str("]\n");
+        let mut omitted = 0;
+        for (index, line) in lines.iter().enumerate() {
+            if !keep[index] {
+                omitted += 1;
+                continue;
+            }
+            if omitted > 0 {
+                text.push_str(&format!("… {omitted} lines omitted …\n"));
+                omitted = 0;
+            }
+            text.push_str(line);
+            if let Some(count) = repeated.get(&index) {
+                text.push_str(&format!("  [+{count} similar]"));
+            }
+            text.push('\n');
+        }
+        if omitted > 0 {
+            text.push_str(&format!("… {omitted} lines omitted …\n"));
+        }
+
+        SampledOutput {
+            text,
+            total_lines: total,
+            kept_lines: kept,
+            sampled: true,
+        }
+    }
+
+    fn is_salient(&self, line: &str, flagged: &[&str]) -> bool {
+        let trimmed = line.trim();
+        !trimmed.is_empty()
+            && (self.summary.is_match(trimmed)
+                || self.error.is_match(trimmed)
+                || self.warning.is_match(trimmed)
+                || flagged.iter().any(|message| trimmed.contains(message)))
+    }
+}
+
+impl Default for OutputSampler {
+    fn default() -> Self {
+        Self::new(SamplingConfig::default())
+    }
+}
+
+/// One-line verdict from the parser, when it recognises the output.
+fn verdict(
+    parser: &OutputParser,
+    raw: &str,
+    diagnostics: Option<&(String, Vec<super::Diagnostic>)>,
+) -> Option<String> {
+    if let Some((tool, found)) = diagnostics {
+        let errors = found
+            .iter()
+            .filter(|d| matches!(d.severity, Severity::Error | Severity::Critical))
+            .count();
+        return Some(format!(
+            "{tool}: {errors} error(s), {} other diagnostic(s)",
+            found.len() - errors
+        ));
+    }
+    match parser.parse(raw).ok()? {
+        ParsedOutput::TestResults { passed, failed, .. } => {
+            Some(format!("tests: {passed} passed, {failed} failed"))
+        }
+        ParsedOutput::BuildOutput { status, .. } => Some(match status {
+            BuildStatus::Success => "build succeeded".to_string(),
+            BuildStatus::Failed(reason) | BuildStatus::Warning(reason) => reason,
+            BuildStatus::InProgress => "build in progress".to_string(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chatty() -> String {
+        let mut out = String::new();
+        for i in 0..500 {
+            out.push_str(&format!("   Compiling crate-{i} v0.1.{i}\n"));
+            if i == 250 {
+                out.push_str("error[E0308]: mismatched types\n --> src/lib.rs:4:5\n");
+            }
+            if i % 100 == 0 {
+                out.push_str(&format!("warning: unused variable `x{i}`\n"));
+            }
+        }
+        out.push_str("test result: FAILED. 10 passed; 2 failed; 0 ignored\n");
+        out
+    }
+
+    #[test]
+    fn test_sample_keeps_salient_lines_and_collapses_repeats() {
+        let parser = OutputParser::new();
+        let raw = chatty();
+        let sampled = OutputSampler::default().sample(&parser, &raw);
+
+        assert!(sampled.sampled);
+        assert!(sampled.kept_lines < sampled.total_lines / 3);
+        assert!(
+            sampled
+                .text
+                .starts_with(&format!("[output sampled: kept {} of", sampled.kept_lines))
+        );
+        assert!(sampled.text.contains("tests: 10 passed, 2 failed"));
+        assert!(sampled.text.contains("error[E0308]: mismatched types"));
+        // Context around the error is kept in light mode.
+        assert!(sampled.text.contains(" --> src/lib.rs:4:5"));
+        assert!(
+            sampled
+                .text
+                .contains("warning: unused variable `x0`  [+4 similar]")
+        );
+        assert!(!sampled.text.contains("warning: unused variable `x100`"));
+        assert!(sampled.text.contains("lines omitted"));
+        assert!(sampled.text.trim_end().ends_with("0 ignored"));
+
+        let aggressive = OutputSampler::new(SamplingConfig {
+            level: SamplingLevel::Aggressive,
+            ..SamplingConfig::default()
+        })
+        .sample(&parser, &raw);
+        assert!(aggressive.kept_lines < sampled.kept_lines);
+        assert!(aggressive.text.contains("error[E0308]"));
+        assert!(!aggressive.text.contains("src/lib.rs:4:5"));
+    }
+
+    #[test]
+    fn test_short_or_unsampled_output_passes_through() {
+        let parser = OutputParser::new();
+        let short = "error: one\nok\n";
+        let sampled = OutputSampler::default().sample(&parser, short);
+        assert!(!sampled.sampled);
+        assert_eq!(sampled.text, short);
+
+        let raw = chatty();
+        let off = OutputSampler::new(SamplingConfig {
+            level: SamplingLevel::Off,
+            ..SamplingConfig::default()
+        })
+        .sample(&parser, &raw);
+        assert!(!off.sampled);
+        assert_eq!(off.text, raw);
+    }
+}
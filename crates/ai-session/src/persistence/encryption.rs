@@ -0,0 +1,331 @@
+//! Encryption at rest for persisted session state, snapshots and transcripts
+//!
+//! Session contexts hold prompts, provider output and command history, which
+//! routinely include proprietary code and occasionally secrets. A [`Keyring`]
+//! seals that data with AES-256-GCM before it touches disk.
+//!
+//! Sealed data starts with a short header naming the key that sealed it, so a
+//! keyring holding the current key plus retired ones can open anything written
+//! before a rotation. Data without the header is treated as legacy plaintext
+//! and passed through unchanged, which keeps stores written before encryption
+//! was enabled readable.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::path::Path;
+
+/// Base64-encoded 32-byte key used to seal new data.
+pub const KEY_ENV: &str = "AI_SESSION_ENCRYPTION_KEY";
+/// File holding the current key, for when it should not sit in the environment.
+pub const KEY_FILE_ENV: &str = "AI_SESSION_ENCRYPTION_KEY_FILE";
+/// Comma-separated retired keys, still accepted when opening data.
+pub const OLD_KEYS_ENV: &str = "AI_SESSION_ENCRYPTION_OLD_KEYS";
+
+/// Marks sealed data; the last byte is the format version.
+const MAGIC: &[u8; 4] = b"AIS\x01";
+const KEY_ID_LEN: usize = 4;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + KEY_ID_LEN + NONCE_LEN;
+
+/// A 256-bit AES-GCM key.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    bytes: [u8; 32],
+    id: [u8; KEY_ID_LEN],
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id())
+            .finish_non_exhaustive()
+    }
+}
+
+impl EncryptionKey {
+    /// Build a key from exactly 32 raw bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| anyhow!("encryption key must be 32 bytes, got {}", bytes.len()))?;
+        // The tag of an empty message under a fixed nonce identifies the key
+        // without revealing anything about it.
+        let check = Aes256Gcm::new(&bytes.into())
+            .encrypt(Nonce::from_slice(&[0; NONCE_LEN]), &[][..])
+            .map_err(|_| anyhow!("failed to derive key id"))?;
+        let mut id = [0; KEY_ID_LEN];
+        id.copy_from_slice(&check[..KEY_ID_LEN]);
+        Ok(Self { bytes, id })
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.bytes.into())
+    }
+
+    /// Parse a base64-encoded key, e.g. the output of `openssl rand -base64 32`.
+    pub fn parse(encoded: &str) -> Result<Self> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .context("encryption key is not valid base64")?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// A fresh random key.
+    pub fn generate() -> Self {
+        let key = Aes256Gcm::generate_key(&mut OsRng);
+        Self::from_bytes(&key).expect("generated keys are 32 bytes")
+    }
+
+    /// The key in the base64 form [`EncryptionKey::parse`] accepts.
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.bytes)
+    }
+
+    /// Short hex identifier recorded in the header of data this key seals.
+    pub fn id(&self) -> String {
+        hex(&self.id)
+    }
+}
+
+/// The key new data is sealed with, plus retired keys that can still open
+/// data sealed before a rotation.
+#[derive(Debug, Clone)]
+pub struct Keyring {
+    current: EncryptionKey,
+    previous: Vec<EncryptionKey>,
+}
+
+impl Keyring {
+    /// A keyring sealing with `current`.
+    pub fn new(current: EncryptionKey) -> Self {
+        Self {
+            current,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Also accept data sealed with `key`.
+    pub fn with_previous(mut self, key: EncryptionKey) -> Self {
+        if key.id != self.current.id && self.previous.iter().all(|k| k.id != key.id) {
+            self.previous.push(key);
+        }
+        self
+    }
+
+    /// Load keys from [`KEY_ENV`] or [`KEY_FILE_ENV`], plus [`OLD_KEYS_ENV`].
+    ///
+    /// Returns `None` when no current key is configured, i.e. encryption is off.
+    pub fn from_env() -> Result<Option<Self>> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let current = match (var(KEY_ENV), var(KEY_FILE_ENV)) {
+            (Some(_), Some(_)) => bail!("{KEY_ENV} and {KEY_FILE_ENV} are mutually exclusive"),
+            (Some(key), None) => {
+                EncryptionKey::parse(&key).with_context(|| format!("in {KEY_ENV}"))?
+            }
+            (None, Some(path)) => Self::read_key_file(Path::new(&path))?,
+            (None, None) => {
+                if var(OLD_KEYS_ENV).is_some() {
+                    bail!(
+                        "{OLD_KEYS_ENV} is set but no current key is ({KEY_ENV} or {KEY_FILE_ENV})"
+                    );
+                }
+                return Ok(None);
+            }
+        };
+        let mut keyring = Self::new(current);
+        for (i, old) in var(OLD_KEYS_ENV)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .enumerate()
+        {
+            let key = EncryptionKey::parse(old)
+                .with_context(|| format!("in {OLD_KEYS_ENV} (entry {})", i + 1))?;
+            keyring = keyring.with_previous(key);
+        }
+        Ok(Some(keyring))
+    }
+
+    fn read_key_file(path: &Path) -> Result<EncryptionKey> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read encryption key {}", path.display()))?;
+        EncryptionKey::parse(&text).with_context(|| format!("in {}", path.display()))
+    }
+
+    /// The key new data is sealed with.
+    pub fn current(&self) -> &EncryptionKey {
+        &self.current
+    }
+
+    /// Encrypt `plaintext` with the current key.
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .current
+            .cipher()
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("failed to encrypt data"))?;
+        let mut sealed = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&self.current.id);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypt sealed `data` with whichever key sealed it; legacy plaintext is
+    /// returned unchanged.
+    pub fn open(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let Some(id) = sealed_key_id(data) else {
+            return Ok(data.to_vec());
+        };
+        let key = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|k| k.id == id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "data was encrypted with key {}, which is not in the keyring (add it to {OLD_KEYS_ENV})",
+                    hex(&id)
+                )
+            })?;
+        let nonce = Nonce::from_slice(&data[MAGIC.len() + KEY_ID_LEN..HEADER_LEN]);
+        key.cipher()
+            .decrypt(nonce, &data[HEADER_LEN..])
+            .map_err(|_| {
+                anyhow!(
+                    "failed to decrypt data sealed with key {}: corrupted or tampered",
+                    key.id()
+                )
+            })
+    }
+
+    /// Seal `text` as a single base64 line, for line-oriented files such as
+    /// JSONL transcripts.
+    pub fn seal_line(&self, text: &str) -> Result<String> {
+        Ok(BASE64.encode(self.seal(text.as_bytes())?))
+    }
+
+    /// Whether `data` is plaintext or sealed with a key other than the current
+    /// one, i.e. whether a key rotation should rewrite it.
+    pub fn needs_rekey(&self, data: &[u8]) -> bool {
+        sealed_key_id(data) != Some(self.current.id)
+    }
+}
+
+/// Whether `data` carries the sealed-data header.
+pub fn is_sealed(data: &[u8]) -> bool {
+    sealed_key_id(data).is_some()
+}
+
+/// Open `data` with `keyring`, or pass it through when it is plaintext.
+///
+/// Fails with a pointer to the key variables when sealed data is read without
+/// a keyring.
+pub fn open_with(keyring: Option<&Keyring>, data: &[u8]) -> Result<Vec<u8>> {
+    match keyring {
+        Some(keyring) => keyring.open(data),
+        None if is_sealed(data) => {
+            bail!("data is encrypted; set {KEY_ENV} or {KEY_FILE_ENV} to read it")
+        }
+        None => Ok(data.to_vec()),
+    }
+}
+
+/// Open a line written by [`Keyring::seal_line`]; lines that are not sealed
+/// are returned unchanged.
+pub fn open_line(keyring: Option<&Keyring>, line: &str) -> Result<String> {
+    match BASE64.decode(line.trim()) {
+        Ok(data) if is_sealed(&data) => {
+            String::from_utf8(open_with(keyring, &data)?).context("sealed line is not UTF-8")
+        }
+        _ => Ok(line.to_string()),
+    }
+}
+
+/// Whether `line` was written by [`Keyring::seal_line`] with a key other than
+/// the current one, or is plaintext.
+pub fn line_needs_rekey(keyring: &Keyring, line: &str) -> bool {
+    match BASE64.decode(line.trim()) {
+        Ok(data) => keyring.needs_rekey(&data),
+        Err(_) => true,
+    }
+}
+
+fn sealed_key_id(data: &[u8]) -> Option<[u8; KEY_ID_LEN]> {
+    if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+        return None;
+    }
+    let mut id = [0; KEY_ID_LEN];
+    id.copy_from_slice(&data[MAGIC.len()..MAGIC.len() + KEY_ID_LEN]);
+    Some(id)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_and_rotation() {
+        let old = EncryptionKey::generate();
+        let new = EncryptionKey::generate();
+        assert_ne!(old.id(), new.id());
+        assert_eq!(
+            EncryptionKey::parse(&old.to_base64()).unwrap().id(),
+            old.id()
+        );
+
+        let sealed = Keyring::new(old.clone()).seal(b"secret context").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+
+        // After rotating, the old key still opens what it sealed.
+        let rotated = Keyring::new(new.clone()).with_previous(old);
+        assert!(rotated.needs_rekey(&sealed));
+        assert_eq!(rotated.open(&sealed).unwrap(), b"secret context");
+        let resealed = rotated.seal(b"secret context").unwrap();
+        assert!(!rotated.needs_rekey(&resealed));
+
+        // Without the old key, the error names the missing key.
+        let err = Keyring::new(new).open(&sealed).unwrap_err().to_string();
+        assert!(err.contains(OLD_KEYS_ENV), "{err}");
+
+        let mut tampered = resealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(rotated.open(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_passes_through() {
+        let keyring = Keyring::new(EncryptionKey::generate());
+        assert_eq!(
+            keyring.open(b"{\"legacy\":true}").unwrap(),
+            b"{\"legacy\":true}"
+        );
+        assert!(keyring.needs_rekey(b"{}"));
+        assert_eq!(open_with(None, b"plain").unwrap(), b"plain");
+
+        let line = keyring.seal_line("{\"a\":1}").unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(open_line(Some(&keyring), &line).unwrap(), "{\"a\":1}");
+        assert_eq!(open_line(Some(&keyring), "{\"a\":1}").unwrap(), "{\"a\":1}");
+        assert!(!line_needs_rekey(&keyring, &line));
+        assert!(line_needs_rekey(&keyring, "{\"a\":1}"));
+
+        let sealed = keyring.seal(b"x").unwrap();
+        assert!(
+            open_with(None, &sealed)
+                .unwrap_err()
+                .to_string()
+                .contains(KEY_ENV)
+        );
+        assert!(EncryptionKey::parse("c2hvcnQ=").is_err());
+    }
+}
@@ -1,5 +1,7 @@
 //! Session state persistence and recovery
 
+pub mod encryption;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -10,14 +12,16 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use crate::context::SessionContext;
 use crate::core::{SessionConfig, SessionId, SessionStatus};
 
+pub use encryption::{EncryptionKey, Keyring};
+
 /// Manages persistent storage of session state
 pub struct PersistenceManager {
     /// Base directory for session storage
     storage_path: PathBuf,
     /// Compression enabled
     enable_compression: bool,
-    /// Encryption key (optional)
-    encryption_key: Option<Vec<u8>>,
+    /// Keys sealing state at rest (optional)
+    keyring: Option<Keyring>,
 }
 
 impl PersistenceManager {
@@ -26,16 +30,26 @@ impl PersistenceManager {
         Self {
             storage_path,
             enable_compression: true,
-            encryption_key: None,
+            keyring: None,
         }
     }
 
-    /// Enable encryption with key
-    pub fn with_encryption(mut self, key: Vec<u8>) -> Self {
-        self.encryption_key = Some(key);
+    /// Enable encryption with keyring
+    pub fn with_encryption(mut self, keyring: Keyring) -> Self {
+        self.keyring = Some(keyring);
         self
     }
 
+    /// Create a persistence manager, encrypting if a key is configured in
+    /// the environment (see [`Keyring::from_env`])
+    pub fn from_env(storage_path: PathBuf) -> Result<Self> {
+        let manager = Self::new(storage_path);
+        Ok(match Keyring::from_env()? {
+            Some(keyring) => manager.with_encryption(keyring),
+            None => manager,
+        })
+    }
+
     /// Save session state
     pub async fn save_session(&self, session_id: &SessionId, state: &SessionState) -> Result<()> {
         let session_dir = self.session_directory(session_id);
@@ -52,10 +66,9 @@ impl PersistenceManager {
         };
 
         // Optionally encrypt
-        let data = if let Some(key) = &self.encryption_key {
-            self.encrypt_data(&data, key)?
-        } else {
-            data
+        let data = match &self.keyring {
+            Some(keyring) => keyring.seal(&data)?,
+            None => data,
         };
 
         // Write to file
@@ -76,12 +89,9 @@ impl PersistenceManager {
         let mut data = Vec::new();
         file.read_to_end(&mut data).await?;
 
-        // Optionally decrypt
-        let data = if let Some(key) = &self.encryption_key {
-            self.decrypt_data(&data, key)?
-        } else {
-            data
-        };
+        // Decrypt if sealed; state saved before encryption was enabled is
+        // read as is
+        let data = encryption::open_with(self.keyring.as_ref(), &data)?;
 
         // Optionally decompress
         let data = if self.enable_compression {
@@ -140,16 +150,28 @@ impl PersistenceManager {
         decode_all(data).map_err(|e| anyhow::anyhow!("Failed to decompress data: {}", e))
     }
 
-    /// Encrypt data (simplified - use proper crypto in production)
-    fn encrypt_data(&self, data: &[u8], _key: &[u8]) -> Result<Vec<u8>> {
-        // TODO: Implement proper encryption
-        Ok(data.to_vec())
-    }
-
-    /// Decrypt data (simplified - use proper crypto in production)
-    fn decrypt_data(&self, data: &[u8], _key: &[u8]) -> Result<Vec<u8>> {
-        // TODO: Implement proper decryption
-        Ok(data.to_vec())
+    /// Re-encrypt every saved session that is plaintext or sealed with a
+    /// retired key, returning how many were rewritten
+    pub async fn rekey_sessions(&self) -> Result<usize> {
+        let Some(keyring) = &self.keyring else {
+            anyhow::bail!("no encryption key configured");
+        };
+        if !self.storage_path.exists() {
+            return Ok(0);
+        }
+        let mut rewritten = 0;
+        for session_id in self.list_sessions().await? {
+            let state_file = self.session_directory(&session_id).join("state.json");
+            let Ok(data) = fs::read(&state_file).await else {
+                continue;
+            };
+            if keyring.needs_rekey(&data) {
+                let state = self.load_session(&session_id).await?;
+                self.save_session(&session_id, &state).await?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
     }
 }
 
@@ -186,6 +208,9 @@ pub struct CommandRecord {
     pub output_preview: String,
     /// Execution duration
     pub duration_ms: u64,
+    /// The command was stopped for outrunning its timeout
+    #[serde(default)]
+    pub timed_out: bool,
 }
 
 /// Session metadata
@@ -233,12 +258,23 @@ pub struct SessionSnapshot {
 pub struct SnapshotManager {
     /// Base directory for snapshots
     snapshot_path: PathBuf,
+    /// Keys sealing snapshots at rest (optional)
+    keyring: Option<Keyring>,
 }
 
 impl SnapshotManager {
     /// Create new snapshot manager
     pub fn new(snapshot_path: PathBuf) -> Self {
-        Self { snapshot_path }
+        Self {
+            snapshot_path,
+            keyring: None,
+        }
+    }
+
+    /// Enable encryption with keyring
+    pub fn with_encryption(mut self, keyring: Keyring) -> Self {
+        self.keyring = Some(keyring);
+        self
     }
 
     /// Create a snapshot
@@ -260,6 +296,10 @@ impl SnapshotManager {
 
         let snapshot_file = snapshot_dir.join(format!("{}.json", snapshot.id));
         let data = serde_json::to_vec_pretty(&snapshot)?;
+        let data = match &self.keyring {
+            Some(keyring) => keyring.seal(&data)?,
+            None => data,
+        };
         fs::write(&snapshot_file, data).await?;
 
         Ok(snapshot.id)
@@ -283,6 +323,10 @@ impl SnapshotManager {
                 .unwrap_or(false)
             {
                 let data = fs::read(entry.path()).await?;
+                let Ok(data) = encryption::open_with(self.keyring.as_ref(), &data) else {
+                    tracing::warn!("Skipping unreadable snapshot {}", entry.path().display());
+                    continue;
+                };
                 if let Ok(snapshot) = serde_json::from_slice::<SessionSnapshot>(&data) {
                     snapshots.push(snapshot);
                 }
@@ -306,10 +350,43 @@ impl SnapshotManager {
             .join(format!("{}.json", snapshot_id));
 
         let data = fs::read(&snapshot_file).await?;
+        let data = encryption::open_with(self.keyring.as_ref(), &data)?;
         let snapshot: SessionSnapshot = serde_json::from_slice(&data)?;
 
         Ok(snapshot.state)
     }
+
+    /// Re-encrypt every snapshot that is plaintext or sealed with a retired
+    /// key, returning how many were rewritten
+    pub async fn rekey_snapshots(&self) -> Result<usize> {
+        let Some(keyring) = &self.keyring else {
+            anyhow::bail!("no encryption key configured");
+        };
+        if !self.snapshot_path.exists() {
+            return Ok(0);
+        }
+        let mut rewritten = 0;
+        let mut sessions = fs::read_dir(&self.snapshot_path).await?;
+        while let Some(session) = sessions.next_entry().await? {
+            if !session.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut entries = fs::read_dir(session.path()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if path.extension().is_none_or(|e| e != "json") {
+                    continue;
+                }
+                let data = fs::read(&path).await?;
+                if keyring.needs_rekey(&data) {
+                    let plain = keyring.open(&data)?;
+                    fs::write(&path, keyring.seal(&plain)?).await?;
+                    rewritten += 1;
+                }
+            }
+        }
+        Ok(rewritten)
+    }
 }
 
 #[cfg(test)]
@@ -338,4 +415,72 @@ mod tests {
 
         assert_eq!(loaded.session_id, state.session_id);
     }
+
+    #[tokio::test]
+    async fn test_encrypted_persistence_and_rekey() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = EncryptionKey::generate();
+        let new = EncryptionKey::generate();
+
+        let session_id = SessionId::new_v4();
+        let mut context = SessionContext::new(session_id.clone());
+        context.metadata.insert(
+            "secret".to_string(),
+            serde_json::json!("proprietary-marker"),
+        );
+        let state = SessionState {
+            session_id: session_id.clone(),
+            config: SessionConfig::default(),
+            status: SessionStatus::Running,
+            context,
+            command_history: vec![],
+            metadata: SessionMetadata::default(),
+        };
+
+        // A plaintext session from before encryption was turned on...
+        let plain = PersistenceManager::new(temp_dir.path().join("sessions"));
+        plain.save_session(&session_id, &state).await.unwrap();
+        let snapshots = SnapshotManager::new(temp_dir.path().join("snapshots"))
+            .with_encryption(Keyring::new(old.clone()));
+        let snapshot_id = snapshots
+            .create_snapshot(&session_id, &state, None)
+            .await
+            .unwrap();
+        let snapshot_file = temp_dir
+            .path()
+            .join("snapshots")
+            .join(session_id.to_string())
+            .join(format!("{snapshot_id}.json"));
+        let raw = std::fs::read(&snapshot_file).unwrap();
+        assert!(encryption::is_sealed(&raw));
+        assert!(!String::from_utf8_lossy(&raw).contains("proprietary-marker"));
+
+        // ...is still readable once a key is configured, and rotation rewrites
+        // both it and the snapshot sealed with the retired key.
+        let keyring = Keyring::new(new.clone()).with_previous(old);
+        let manager = PersistenceManager::new(temp_dir.path().join("sessions"))
+            .with_encryption(keyring.clone());
+        assert!(manager.load_session(&session_id).await.is_ok());
+        assert_eq!(manager.rekey_sessions().await.unwrap(), 1);
+        assert_eq!(manager.rekey_sessions().await.unwrap(), 0);
+        let snapshots =
+            SnapshotManager::new(temp_dir.path().join("snapshots")).with_encryption(keyring);
+        assert_eq!(snapshots.rekey_snapshots().await.unwrap(), 1);
+
+        // Only the new key is needed from here on.
+        let current = Keyring::new(new);
+        let manager = PersistenceManager::new(temp_dir.path().join("sessions"))
+            .with_encryption(current.clone());
+        let loaded = manager.load_session(&session_id).await.unwrap();
+        assert_eq!(loaded.context.metadata["secret"], "proprietary-marker");
+        let restored = SnapshotManager::new(temp_dir.path().join("snapshots"))
+            .with_encryption(current)
+            .restore_snapshot(&session_id, &snapshot_id)
+            .await
+            .unwrap();
+        assert_eq!(restored.session_id, session_id);
+
+        // Without any key the sealed state is refused, not misparsed.
+        assert!(plain.load_session(&session_id).await.is_err());
+    }
 }
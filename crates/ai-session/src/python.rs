@@ -0,0 +1,373 @@
+//! Python bindings (`python` feature).
+//!
+//! Exposes [`SessionManager`](crate::SessionManager), session I/O and the
+//! conversation context to Python as the `ai_session` module, so notebooks
+//! and agent frameworks can drive sessions without going through the CLI or
+//! the HTTP server. Build a wheel with `maturin build --release` from this
+//! crate's directory (see `pyproject.toml`).
+//!
+//! Every call blocks the calling Python thread on a runtime private to the
+//! module, with the GIL released while it waits:
+//!
+//! ```python
+//! import ai_session
+//!
+//! manager = ai_session.SessionManager()
+//! session = manager.create_session(name="build", headless=True)
+//! session.start()
+//! print(session.execute("cargo --version"))
+//! session.add_message("user", "check the build")
+//! print(session.messages())
+//! session.stop()
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::context::{Message, MessageRole};
+use crate::core::{AISession, SessionConfig, SessionId};
+
+static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+fn runtime() -> PyResult<&'static tokio::runtime::Runtime> {
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("ai-session-py")
+        .build()
+        .map_err(|e| PyRuntimeError::new_err(format!("failed to start runtime: {e}")))?;
+    // Another thread may have won the race; its runtime is used and ours dropped.
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+/// Run `future` to completion without holding the GIL.
+fn block_on<F>(py: Python<'_>, future: F) -> PyResult<F::Output>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    let runtime = runtime()?;
+    Ok(py.detach(|| runtime.block_on(future)))
+}
+
+fn to_py_err(err: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{err:#}"))
+}
+
+fn parse_session_id(id: &str) -> PyResult<SessionId> {
+    SessionId::parse_str(id).map_err(|e| PyValueError::new_err(format!("invalid session id: {e}")))
+}
+
+fn parse_role(role: &str) -> PyResult<MessageRole> {
+    match role.to_ascii_lowercase().as_str() {
+        "system" => Ok(MessageRole::System),
+        "user" => Ok(MessageRole::User),
+        "assistant" => Ok(MessageRole::Assistant),
+        "tool" => Ok(MessageRole::Tool),
+        other => Err(PyValueError::new_err(format!(
+            "unknown role '{other}' (expected system, user, assistant or tool)"
+        ))),
+    }
+}
+
+fn role_name(role: MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "system",
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::Tool => "tool",
+    }
+}
+
+fn message_dict<'py>(py: Python<'py>, message: &Message) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("role", role_name(message.role))?;
+    dict.set_item("content", &message.content)?;
+    dict.set_item("timestamp", message.timestamp.to_rfc3339())?;
+    dict.set_item("token_count", message.token_count)?;
+    Ok(dict)
+}
+
+/// Creates, looks up and removes sessions.
+#[pyclass(name = "SessionManager", module = "ai_session", frozen)]
+pub struct PySessionManager {
+    inner: crate::core::SessionManager,
+}
+
+#[pymethods]
+impl PySessionManager {
+    #[new]
+    fn new() -> Self {
+        Self {
+            inner: crate::core::SessionManager::new(),
+        }
+    }
+
+    /// Create a session (not yet started).
+    #[pyo3(signature = (
+        name=None,
+        working_directory=None,
+        shell=None,
+        env=None,
+        agent_role=None,
+        headless=false,
+        enable_ai_features=true,
+        max_tokens=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn create_session(
+        &self,
+        py: Python<'_>,
+        name: Option<String>,
+        working_directory: Option<PathBuf>,
+        shell: Option<String>,
+        env: Option<HashMap<String, String>>,
+        agent_role: Option<String>,
+        headless: bool,
+        enable_ai_features: bool,
+        max_tokens: Option<usize>,
+    ) -> PyResult<PySession> {
+        let mut config = SessionConfig {
+            name,
+            shell,
+            agent_role,
+            force_headless: headless,
+            enable_ai_features,
+            ..SessionConfig::default()
+        };
+        if let Some(dir) = working_directory {
+            config.working_directory = dir;
+        }
+        if let Some(env) = env {
+            config.environment.extend(env);
+        }
+        if let Some(max_tokens) = max_tokens {
+            config.context_config.max_tokens = max_tokens;
+        }
+        let session =
+            block_on(py, self.inner.create_session_with_config(config))?.map_err(to_py_err)?;
+        Ok(PySession { inner: session })
+    }
+
+    /// Session with the given id, or `None`.
+    fn get_session(&self, id: &str) -> PyResult<Option<PySession>> {
+        Ok(self
+            .inner
+            .get_session(&parse_session_id(id)?)
+            .map(|inner| PySession { inner }))
+    }
+
+    /// Session with the given name, or `None`.
+    fn find_session(&self, name: &str) -> Option<PySession> {
+        self.inner
+            .find_session_by_name(name)
+            .map(|inner| PySession { inner })
+    }
+
+    /// Ids of all sessions.
+    fn list_sessions(&self) -> Vec<String> {
+        self.inner
+            .list_sessions()
+            .iter()
+            .map(SessionId::to_string)
+            .collect()
+    }
+
+    /// Stop a session and forget it.
+    fn remove_session(&self, py: Python<'_>, id: &str) -> PyResult<()> {
+        let id = parse_session_id(id)?;
+        block_on(py, self.inner.remove_session(&id))?.map_err(to_py_err)
+    }
+
+    /// Forget terminated sessions; returns how many were removed.
+    fn cleanup_terminated(&self, py: Python<'_>) -> PyResult<usize> {
+        block_on(py, self.inner.cleanup_terminated())?.map_err(to_py_err)
+    }
+}
+
+/// One terminal session and its conversation context.
+#[pyclass(name = "Session", module = "ai_session", frozen)]
+pub struct PySession {
+    inner: Arc<AISession>,
+}
+
+#[pymethods]
+impl PySession {
+    #[getter]
+    fn id(&self) -> String {
+        self.inner.id.to_string()
+    }
+
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.inner.config.name.clone()
+    }
+
+    #[getter]
+    fn working_directory(&self) -> PathBuf {
+        self.inner.config.working_directory.clone()
+    }
+
+    /// Lifecycle state, e.g. `"running"` or `"terminated"`.
+    #[getter]
+    fn status(&self, py: Python<'_>) -> PyResult<String> {
+        let status = block_on(py, self.inner.status())?;
+        Ok(format!("{status:?}").to_lowercase())
+    }
+
+    fn start(&self, py: Python<'_>) -> PyResult<()> {
+        block_on(py, self.inner.start())?.map_err(to_py_err)
+    }
+
+    /// Run the session's `on_stop` hooks, then stop it.
+    fn stop(&self, py: Python<'_>) -> PyResult<()> {
+        block_on(py, self.inner.stop())?.map_err(to_py_err)
+    }
+
+    fn kill(&self, py: Python<'_>) -> PyResult<()> {
+        block_on(py, self.inner.kill())?.map_err(to_py_err)
+    }
+
+    /// Write raw input to the terminal (include the trailing newline).
+    fn send_input(&self, py: Python<'_>, data: &str) -> PyResult<()> {
+        let data = data.to_string();
+        block_on(py, async { self.inner.send_input(&data).await })?.map_err(to_py_err)
+    }
+
+    /// Output produced since the last read.
+    fn read_output<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let output = block_on(py, self.inner.read_output())?.map_err(to_py_err)?;
+        Ok(PyBytes::new(py, &output))
+    }
+
+    /// Run a command and return its output.
+    fn execute(&self, py: Python<'_>, command: &str) -> PyResult<String> {
+        let command = command.to_string();
+        block_on(py, async { self.inner.execute_command(&command).await })?.map_err(to_py_err)
+    }
+
+    /// Append a message to the conversation context.
+    fn add_message(&self, py: Python<'_>, role: &str, content: String) -> PyResult<()> {
+        let role = parse_role(role)?;
+        block_on(py, async {
+            self.inner
+                .context
+                .write()
+                .await
+                .add_message_raw(role, content)
+        })
+    }
+
+    /// Conversation messages as dicts, oldest first; `limit` keeps the most
+    /// recent ones.
+    #[pyo3(signature = (limit=None))]
+    fn messages<'py>(
+        &self,
+        py: Python<'py>,
+        limit: Option<usize>,
+    ) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let messages: Vec<Message> = block_on(py, async {
+            let context = self.inner.context.read().await;
+            context
+                .get_recent_messages(limit.unwrap_or(usize::MAX))
+                .into_iter()
+                .cloned()
+                .collect()
+        })?;
+        messages
+            .iter()
+            .map(|message| message_dict(py, message))
+            .collect()
+    }
+
+    /// Estimated tokens held in the conversation context.
+    fn context_tokens(&self, py: Python<'_>) -> PyResult<usize> {
+        block_on(py, async {
+            self.inner.context.read().await.get_total_tokens()
+        })
+    }
+
+    /// Compress older messages if the context is over its threshold;
+    /// returns whether it did.
+    fn compress_context(&self, py: Python<'_>) -> PyResult<bool> {
+        block_on(py, async {
+            self.inner.context.write().await.compress_context().await
+        })
+    }
+
+    /// Message count, current task and tracked files of the context.
+    fn context_summary<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let summary = block_on(py, async { self.inner.context.read().await.summarize() })?;
+        let dict = PyDict::new(py);
+        dict.set_item("session_id", summary.session_id.to_string())?;
+        dict.set_item("message_count", summary.message_count)?;
+        dict.set_item("current_task", summary.current_task)?;
+        dict.set_item("agent_state", summary.agent_state)?;
+        dict.set_item("workspace_files", summary.workspace_files)?;
+        Ok(dict)
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.inner.config.name {
+            Some(name) => format!(
+                "<ai_session.Session {} ({name})>",
+                self.inner.id.to_string()
+            ),
+            None => format!("<ai_session.Session {}>", self.inner.id.to_string()),
+        }
+    }
+}
+
+#[pymodule]
+fn ai_session(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", crate::VERSION)?;
+    m.add_class::<PySessionManager>()?;
+    m.add_class::<PySession>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_manages_sessions_and_context() {
+        pyo3::append_to_inittab!(ai_session);
+        Python::initialize();
+        let dir = tempfile::tempdir().unwrap();
+        Python::attach(|py| {
+            let locals = PyDict::new(py);
+            locals.set_item("cwd", dir.path()).unwrap();
+            py.run(
+                c"
+import ai_session
+assert ai_session.__version__
+manager = ai_session.SessionManager()
+session = manager.create_session(name='build', working_directory=cwd, headless=True)
+assert manager.list_sessions() == [session.id]
+assert manager.find_session('build').id == session.id
+session.add_message('user', 'check the build')
+assert [m['content'] for m in session.messages()] == ['check the build']
+try:
+    session.add_message('nobody', 'x')
+    raise AssertionError('unknown role accepted')
+except ValueError:
+    pass
+manager.remove_session(session.id)
+assert manager.list_sessions() == []
+",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+        });
+    }
+}
@@ -45,7 +45,7 @@ impl PersistentSessionManager {
         std::fs::create_dir_all(&storage_path)?;
         eprintln!("Using storage path: {}", storage_path.display());
 
-        let persistence = PersistenceManager::new(storage_path.clone());
+        let persistence = PersistenceManager::from_env(storage_path.clone())?;
         let inner = InnerSessionManager::new();
 
         let mut manager = Self {
@@ -69,9 +69,12 @@ impl PersistentSessionManager {
         for session_id in session_ids {
             match self.persistence.load_session(&session_id).await {
                 Ok(state) => {
-                    // Only restore sessions that were running or paused
+                    // Only restore sessions that were running, paused or
+                    // hibernated
                     match state.status {
-                        SessionStatus::Running | SessionStatus::Paused => {
+                        SessionStatus::Running
+                        | SessionStatus::Paused
+                        | SessionStatus::Hibernated => {
                             // Restore the session with its original ID
                             match self
                                 .inner
@@ -82,7 +85,15 @@ impl PersistentSessionManager {
                                 )
                                 .await
                             {
-                                Ok(_session) => {
+                                Ok(session) => {
+                                    // A hibernated session stays down until
+                                    // its next input, with its context back.
+                                    if state.status == SessionStatus::Hibernated {
+                                        *session.status.write().await = SessionStatus::Hibernated;
+                                        *session.context.write().await = state.context.clone();
+                                        *session.metadata.write().await =
+                                            state.metadata.custom.clone();
+                                    }
                                     eprintln!("Restored session: {}", session_id);
                                 }
                                 Err(e) => {
@@ -207,6 +218,47 @@ impl PersistentSessionManager {
         Ok(())
     }
 
+    /// Remove a session without running its `on_stop` hooks
+    pub async fn kill_session(&self, id: &SessionId) -> Result<()> {
+        self.inner.kill_session(id).await?;
+        self.persistence.delete_session(id).await?;
+        Ok(())
+    }
+
+    /// Pause a session and persist its checkpoint (status and
+    /// `pause_checkpoint` metadata)
+    pub async fn pause_session(&self, id: &SessionId) -> Result<()> {
+        let session = self
+            .inner
+            .get_session(id)
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", id))?;
+        session.pause().await?;
+        self.update_session_state(&session).await
+    }
+
+    /// Resume a paused session and persist its status
+    pub async fn resume_session(&self, id: &SessionId) -> Result<()> {
+        let session = self
+            .inner
+            .get_session(id)
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", id))?;
+        session.resume().await?;
+        self.update_session_state(&session).await
+    }
+
+    /// Hibernate idle sessions (see [`InnerSessionManager::hibernate_idle`])
+    /// and persist their state, context included, so they can be woken
+    /// after a restart too.
+    pub async fn hibernate_idle(&self) -> Result<Vec<SessionId>> {
+        let hibernated = self.inner.hibernate_idle().await;
+        for id in &hibernated {
+            if let Some(session) = self.inner.get_session(id) {
+                self.update_session_state(&session).await?;
+            }
+        }
+        Ok(hibernated)
+    }
+
     /// Update session state in persistence
     pub async fn update_session_state(&self, session: &AISession) -> Result<()> {
         let state = SessionState {
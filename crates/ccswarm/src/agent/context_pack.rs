@@ -0,0 +1,316 @@
+//! Choosing which context items go into a prompt.
+//!
+//! Appending candidates until the budget runs out lets one large item crowd
+//! out several smaller ones that are worth more together. [`pack`] instead
+//! treats the budget as a knapsack: every candidate has a token cost and a
+//! utility from a [`Scorer`], and the packer picks the subset with the
+//! highest total utility that fits. Items marked `required` always go in.
+//!
+//! Each call returns a [`Packing`] whose [`Decision`]s say, per item, whether
+//! it was included and why; [`Packing::report`] renders them for logs.
+
+use serde::Serialize;
+
+use super::prompt_builder::estimate_tokens;
+
+/// The knapsack is solved over at most this many capacity units; larger
+/// budgets are scaled down, rounding item costs up so the result still fits.
+const MAX_UNITS: usize = 2048;
+
+/// Tokens for the blank line joining an item to the next.
+const SEPARATOR_TOKENS: usize = 1;
+
+/// One candidate piece of context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextItem {
+    /// Stable name for reports, e.g. `stage:plan`.
+    pub id: String,
+    pub text: String,
+    /// Caller-assigned importance; what [`PriorityScorer`] scores by.
+    pub priority: u32,
+    /// Included whatever it costs.
+    pub required: bool,
+}
+
+impl ContextItem {
+    pub fn new(id: impl Into<String>, text: impl Into<String>, priority: u32) -> Self {
+        Self {
+            id: id.into(),
+            text: text.into(),
+            priority,
+            required: false,
+        }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Estimated tokens of the text.
+    pub fn tokens(&self) -> usize {
+        estimate_tokens(&self.text)
+    }
+}
+
+/// Utility of including an item. Items scoring zero or less are left out.
+pub trait Scorer {
+    fn score(&self, item: &ContextItem) -> f64;
+}
+
+impl<F: Fn(&ContextItem) -> f64> Scorer for F {
+    fn score(&self, item: &ContextItem) -> f64 {
+        self(item)
+    }
+}
+
+/// Scores an item by its priority.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityScorer;
+
+impl Scorer for PriorityScorer {
+    fn score(&self, item: &ContextItem) -> f64 {
+        f64::from(item.priority)
+    }
+}
+
+/// Why an item was or was not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// Marked required.
+    Required,
+    /// Part of the best-scoring set that fits.
+    Selected,
+    /// Scored zero or less.
+    NoValue,
+    /// Larger than the budget left after required items.
+    TooLarge,
+    /// Fits on its own, but the budget buys more utility spent elsewhere.
+    Outscored,
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Required => "required",
+            Self::Selected => "selected",
+            Self::NoValue => "no value",
+            Self::TooLarge => "too large",
+            Self::Outscored => "outscored",
+        })
+    }
+}
+
+/// What happened to one item.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Decision {
+    pub id: String,
+    pub tokens: usize,
+    pub score: f64,
+    pub included: bool,
+    pub reason: Reason,
+}
+
+/// The outcome of [`pack`].
+#[derive(Debug, Clone)]
+pub struct Packing {
+    /// Included items, in their original order.
+    pub included: Vec<ContextItem>,
+    /// One per candidate, in their original order.
+    pub decisions: Vec<Decision>,
+    pub budget: usize,
+    /// Tokens the included items take, separators counted.
+    pub used: usize,
+    pub utility: f64,
+}
+
+impl Packing {
+    /// The included texts joined by blank lines.
+    pub fn join(&self) -> String {
+        self.included
+            .iter()
+            .map(|item| item.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    pub fn excluded(&self) -> impl Iterator<Item = &Decision> {
+        self.decisions.iter().filter(|d| !d.included)
+    }
+
+    /// One line per item: `+`/`-`, id, tokens, score and reason.
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "packed {}/{} tokens, utility {:.1}",
+            self.used, self.budget, self.utility
+        );
+        for d in &self.decisions {
+            out.push_str(&format!(
+                "\n{} {} ({} tokens, score {:.1}): {}",
+                if d.included { '+' } else { '-' },
+                d.id,
+                d.tokens,
+                d.score,
+                d.reason
+            ));
+        }
+        out
+    }
+}
+
+/// Pick the items with the highest total score that fit in `budget` tokens.
+pub fn pack(items: Vec<ContextItem>, budget: usize, scorer: &dyn Scorer) -> Packing {
+    let costs: Vec<usize> = items
+        .iter()
+        .map(|i| i.tokens() + SEPARATOR_TOKENS)
+        .collect();
+    let scores: Vec<f64> = items.iter().map(|i| scorer.score(i)).collect();
+
+    let required: usize = items
+        .iter()
+        .zip(&costs)
+        .filter(|(item, _)| item.required)
+        .map(|(_, cost)| cost)
+        .sum();
+    let room = budget.saturating_sub(required);
+    let candidates: Vec<usize> = (0..items.len())
+        .filter(|&i| !items[i].required && scores[i] > 0.0 && costs[i] <= room)
+        .collect();
+    let chosen = knapsack(
+        &candidates.iter().map(|&i| costs[i]).collect::<Vec<_>>(),
+        &candidates.iter().map(|&i| scores[i]).collect::<Vec<_>>(),
+        room,
+    );
+
+    let mut decisions = Vec::with_capacity(items.len());
+    let mut included = Vec::new();
+    let (mut used, mut utility) = (0, 0.0);
+    for (i, item) in items.into_iter().enumerate() {
+        let reason = if item.required {
+            Reason::Required
+        } else if scores[i] <= 0.0 {
+            Reason::NoValue
+        } else if costs[i] > room {
+            Reason::TooLarge
+        } else if candidates
+            .iter()
+            .position(|&c| c == i)
+            .is_some_and(|n| chosen[n])
+        {
+            Reason::Selected
+        } else {
+            Reason::Outscored
+        };
+        let take = matches!(reason, Reason::Required | Reason::Selected);
+        decisions.push(Decision {
+            id: item.id.clone(),
+            tokens: costs[i] - SEPARATOR_TOKENS,
+            score: scores[i],
+            included: take,
+            reason,
+        });
+        if take {
+            used += costs[i];
+            utility += scores[i].max(0.0);
+            included.push(item);
+        }
+    }
+    Packing {
+        included,
+        decisions,
+        budget,
+        used,
+        utility,
+    }
+}
+
+/// 0/1 knapsack: which of the items with `costs` and `values` to take for
+/// the highest total value within `capacity`.
+fn knapsack(costs: &[usize], values: &[f64], capacity: usize) -> Vec<bool> {
+    let unit = capacity.div_ceil(MAX_UNITS).max(1);
+    let capacity = capacity / unit;
+    let costs: Vec<usize> = costs.iter().map(|c| c.div_ceil(unit)).collect();
+
+    let mut best = vec![0.0f64; capacity + 1];
+    let mut took = vec![vec![false; capacity + 1]; costs.len()];
+    for (i, (&cost, &value)) in costs.iter().zip(values).enumerate() {
+        if cost > capacity {
+            continue;
+        }
+        for c in (cost..=capacity).rev() {
+            let with = best[c - cost] + value;
+            if with > best[c] {
+                best[c] = with;
+                took[i][c] = true;
+            }
+        }
+    }
+
+    let mut chosen = vec![false; costs.len()];
+    let mut c = capacity;
+    for i in (0..costs.len()).rev() {
+        if took[i][c] {
+            chosen[i] = true;
+            c -= costs[i];
+        }
+    }
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packing_beats_greedy_and_explains_exclusions() {
+        let item = |id: &str, tokens: usize, priority| {
+            ContextItem::new(id, "x".repeat(tokens * 4 - 1), priority)
+        };
+        let items = vec![
+            item("big", 600, 5),
+            item("left", 500, 4),
+            item("right", 500, 4),
+            item("huge", 5000, 9),
+            item("noise", 10, 0),
+            item("task", 50, 1).required(),
+        ];
+
+        // Greedy by priority would take `big` and nothing else fits.
+        let packing = pack(items.clone(), 1100, &PriorityScorer);
+        let ids: Vec<&str> = packing.included.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["left", "right", "task"]);
+        assert!(packing.used <= 1100);
+        assert_eq!(packing.utility, 9.0);
+        let reasons: Vec<Reason> = packing.decisions.iter().map(|d| d.reason).collect();
+        assert_eq!(
+            reasons,
+            [
+                Reason::Outscored,
+                Reason::Selected,
+                Reason::Selected,
+                Reason::TooLarge,
+                Reason::NoValue,
+                Reason::Required,
+            ]
+        );
+        assert!(
+            packing
+                .report()
+                .contains("- big (600 tokens, score 5.0): outscored")
+        );
+        assert_eq!(packing.excluded().count(), 3);
+
+        // A custom scorer that values size flips the choice.
+        let by_size = |item: &ContextItem| item.tokens() as f64;
+        let packing = pack(items, 700, &by_size);
+        let ids: Vec<&str> = packing.included.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["big", "noise", "task"]);
+
+        // Budgets past the unit cap still never overflow.
+        let many: Vec<ContextItem> = (0..40).map(|n| item(&format!("i{n}"), 997, 1)).collect();
+        let packing = pack(many, 20_000, &PriorityScorer);
+        assert!(packing.used <= 20_000);
+        assert_eq!(packing.included.len(), 20);
+    }
+}
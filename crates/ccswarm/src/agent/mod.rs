@@ -1,9 +1,12 @@
 pub mod claude;
+pub mod context_pack;
 pub mod interleaved_thinking;
 pub mod isolation;
 pub mod orchestrator;
 pub mod personality;
 pub mod phronesis;
+pub mod prompt_builder;
+pub mod scratchpad;
 pub mod task;
 pub mod task_builder;
 pub mod task_builder_typestate;
@@ -19,6 +22,7 @@ use uuid::Uuid;
 pub use isolation::{IsolationConfig, IsolationMode};
 pub use personality::{AgentPersonality, PersonalityTraits, Skill, TaskApproach, WorkingStyle};
 pub use phronesis::{LearningEventType, PhronesisManager, PracticalWisdom, WisdomCategory};
+pub use prompt_builder::{BuiltPrompt, Overflow, PromptBuilder, PromptSection, Slot};
 pub use task::{Priority, Task, TaskResult, TaskType};
 pub use task_builder::TaskBuilder;
 pub use task_builder_typestate::{
@@ -233,6 +237,10 @@ impl ClaudeCodeAgent {
         let mut env_vars = std::collections::HashMap::new();
         env_vars.insert("CCSWARM_AGENT_ID".to_string(), agent_id.to_string());
         env_vars.insert("CCSWARM_SESSION_ID".to_string(), session_id.to_string());
+        env_vars.insert(
+            crate::run_id::RUN_ID_ENV.to_string(),
+            crate::run_id::RunId::current().to_string(),
+        );
         env_vars.insert(
             "CCSWARM_ROLE".to_string(),
             agent_id.split('-').next().unwrap_or("unknown").to_string(),
@@ -276,6 +284,19 @@ impl ClaudeCodeAgent {
                 .context("Failed to create parent directory")?;
         }
 
+        // A worktree left on our branch by an earlier run is reused on restart.
+        if self.worktree_path.join(".git").exists()
+            && crate::git::GitUtils::get_current_branch(&self.worktree_path)
+                .await
+                .is_ok_and(|branch| branch == self.branch_name)
+        {
+            tracing::info!(
+                "Adopting existing worktree at: {}",
+                self.worktree_path.display()
+            );
+            return Ok(());
+        }
+
         // Create worktree with new branch in a single command
         // git worktree add -b <branch> <path> creates both the branch and worktree
         tracing::debug!(
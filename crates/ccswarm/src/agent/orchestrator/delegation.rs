@@ -0,0 +1,292 @@
+//! Choosing the specialist agent for a task.
+//!
+//! Delegation is a cheap heuristic, not a model call: each agent has a
+//! keyword vocabulary and task types it owns, and a strategy scores the
+//! task against them. `orchestrator::eval` measures how often that lands on
+//! the agent a human would have picked.
+
+use crate::agent::TaskType;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Agents a task can be delegated to, in tie-break order.
+pub const AGENTS: &[&str] = &["frontend", "backend", "devops", "qa"];
+
+/// Agent chosen when nothing in the task points anywhere else.
+const FALLBACK_AGENT: &str = "backend";
+
+/// Weighted keywords per agent. Phrases are matched as substrings of the
+/// lower-cased description, single words on word boundaries.
+const KEYWORDS: &[(&str, &[(&str, u32)])] = &[
+    (
+        "frontend",
+        &[
+            ("ui", 3),
+            ("ux", 3),
+            ("frontend", 4),
+            ("front-end", 4),
+            ("react", 4),
+            ("vue", 4),
+            ("svelte", 4),
+            ("angular", 4),
+            ("component", 3),
+            ("css", 3),
+            ("tailwind", 3),
+            ("html", 2),
+            ("page", 2),
+            ("button", 3),
+            ("form", 2),
+            ("modal", 3),
+            ("layout", 2),
+            ("responsive", 3),
+            ("dark mode", 3),
+            ("accessibility", 3),
+            ("a11y", 3),
+            ("browser", 2),
+            ("client-side", 3),
+            ("style", 2),
+            ("animation", 2),
+        ],
+    ),
+    (
+        "backend",
+        &[
+            ("backend", 4),
+            ("back-end", 4),
+            ("api", 3),
+            ("endpoint", 3),
+            ("rest", 2),
+            ("graphql", 3),
+            ("database", 3),
+            ("sql", 3),
+            ("schema", 2),
+            ("migration", 3),
+            ("query", 2),
+            ("server", 2),
+            ("auth", 2),
+            ("authentication", 3),
+            ("jwt", 3),
+            ("oauth", 3),
+            ("cache", 2),
+            ("redis", 3),
+            ("queue", 2),
+            ("webhook", 3),
+            ("service", 1),
+            ("model", 1),
+            ("rate limit", 3),
+            ("pagination", 2),
+        ],
+    ),
+    (
+        "devops",
+        &[
+            ("devops", 4),
+            ("deploy", 3),
+            ("deployment", 3),
+            ("docker", 4),
+            ("dockerfile", 4),
+            ("kubernetes", 4),
+            ("k8s", 4),
+            ("helm", 4),
+            ("terraform", 4),
+            ("ci", 3),
+            ("pipeline", 2),
+            ("github actions", 4),
+            ("workflow", 1),
+            ("infrastructure", 3),
+            ("monitoring", 3),
+            ("alerting", 3),
+            ("prometheus", 4),
+            ("grafana", 4),
+            ("logging", 2),
+            ("nginx", 3),
+            ("ssl", 3),
+            ("tls", 3),
+            ("backup", 3),
+            ("scaling", 2),
+            ("container", 3),
+        ],
+    ),
+    (
+        "qa",
+        &[
+            ("qa", 4),
+            ("test", 3),
+            ("tests", 3),
+            ("testing", 3),
+            ("e2e", 4),
+            ("end-to-end", 4),
+            ("unit test", 4),
+            ("integration test", 4),
+            ("coverage", 4),
+            ("flaky", 4),
+            ("regression", 2),
+            ("playwright", 4),
+            ("cypress", 4),
+            ("jest", 4),
+            ("assert", 2),
+            ("fixture", 3),
+            ("mock", 2),
+            ("benchmark", 2),
+            ("load test", 4),
+            ("verify", 1),
+        ],
+    ),
+];
+
+/// How a task is matched to an agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DelegationStrategy {
+    /// Keyword scores from the description only.
+    Keyword,
+    /// The task type alone (development and other generic types fall back
+    /// to the backend agent).
+    TaskType,
+    /// Keyword scores, with the agent owning the task type as a bonus.
+    #[default]
+    Hybrid,
+}
+
+impl DelegationStrategy {
+    pub fn all() -> [Self; 3] {
+        [Self::Keyword, Self::TaskType, Self::Hybrid]
+    }
+}
+
+impl std::fmt::Display for DelegationStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Keyword => "keyword",
+            Self::TaskType => "task_type",
+            Self::Hybrid => "hybrid",
+        })
+    }
+}
+
+impl FromStr for DelegationStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "keyword" | "content" => Ok(Self::Keyword),
+            "task_type" | "type" => Ok(Self::TaskType),
+            "hybrid" => Ok(Self::Hybrid),
+            _ => Err(anyhow::anyhow!(
+                "Unknown delegation strategy: {} (expected keyword, task_type or hybrid)",
+                s
+            )),
+        }
+    }
+}
+
+/// Where a task should go and why.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DelegationDecision {
+    pub agent: String,
+    /// Share of the total score that went to `agent` (1.0 when only one
+    /// agent scored, 0.0 for a fallback).
+    pub confidence: f64,
+    pub reasoning: String,
+}
+
+/// Agent that owns a task type outright, if any.
+fn agent_for_type(task_type: TaskType) -> Option<&'static str> {
+    match task_type {
+        TaskType::Testing => Some("qa"),
+        TaskType::Infrastructure => Some("devops"),
+        _ => None,
+    }
+}
+
+fn keyword_hits(text: &str, keyword: &str) -> bool {
+    if keyword.contains(' ') || keyword.contains('-') {
+        return text.contains(keyword);
+    }
+    text.split(|c: char| !c.is_alphanumeric())
+        .any(|word| word == keyword)
+}
+
+/// Score of each agent in [`AGENTS`] order, with the keywords that matched.
+fn keyword_scores(description: &str) -> Vec<(&'static str, u32, Vec<&'static str>)> {
+    let text = description.to_lowercase();
+    KEYWORDS
+        .iter()
+        .map(|(agent, words)| {
+            let matched: Vec<&str> = words
+                .iter()
+                .filter(|(word, _)| keyword_hits(&text, word))
+                .map(|(word, _)| *word)
+                .collect();
+            let score = words
+                .iter()
+                .filter(|(word, _)| matched.contains(word))
+                .map(|(_, weight)| weight)
+                .sum();
+            (*agent, score, matched)
+        })
+        .collect()
+}
+
+/// Bonus the hybrid strategy gives the agent owning the task type.
+const TYPE_BONUS: u32 = 4;
+
+/// Pick an agent for `description` using `strategy`.
+pub fn delegate(
+    strategy: DelegationStrategy,
+    description: &str,
+    task_type: Option<TaskType>,
+) -> DelegationDecision {
+    let owner = task_type.and_then(|t| agent_for_type(t).map(|agent| (t, agent)));
+    if strategy == DelegationStrategy::TaskType {
+        return match owner {
+            Some((task_type, agent)) => DelegationDecision {
+                agent: agent.to_string(),
+                confidence: 1.0,
+                reasoning: format!("task type {task_type} → {agent}"),
+            },
+            None => fallback("task type does not name an agent"),
+        };
+    }
+
+    let mut scores = keyword_scores(description);
+    if strategy == DelegationStrategy::Hybrid
+        && let Some((_, owner)) = owner
+        && let Some(entry) = scores.iter_mut().find(|(agent, _, _)| *agent == owner)
+    {
+        entry.1 += TYPE_BONUS;
+    }
+    let total: u32 = scores.iter().map(|(_, score, _)| score).sum();
+    // Earliest agent wins ties, so the order of AGENTS is the tie-break.
+    let Some((agent, score, matched)) = scores
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, score, _)| *score)
+        .filter(|(_, score, _)| *score > 0)
+    else {
+        return fallback("no keywords matched");
+    };
+    let mut reasons = Vec::new();
+    if !matched.is_empty() {
+        reasons.push(format!("keywords: {}", matched.join(", ")));
+    }
+    if strategy == DelegationStrategy::Hybrid
+        && let Some((task_type, owner)) = owner
+        && owner == agent
+    {
+        reasons.push(format!("task type {task_type}"));
+    }
+    DelegationDecision {
+        agent: agent.to_string(),
+        confidence: f64::from(score) / f64::from(total),
+        reasoning: reasons.join("; "),
+    }
+}
+
+fn fallback(why: &str) -> DelegationDecision {
+    DelegationDecision {
+        agent: FALLBACK_AGENT.to_string(),
+        confidence: 0.0,
+        reasoning: format!("{why}; defaulting to {FALLBACK_AGENT}"),
+    }
+}
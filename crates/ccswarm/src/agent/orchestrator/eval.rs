@@ -0,0 +1,270 @@
+//! Measuring delegation accuracy against labeled tasks.
+//!
+//! A dataset is a list of task descriptions with the agent a reviewer would
+//! assign. [`evaluate`] runs a [`DelegationStrategy`] over it and reports
+//! accuracy, a confusion matrix and the misses, so a keyword or weighting
+//! change can be compared before and after instead of argued about.
+
+use super::delegation::{AGENTS, DelegationStrategy, delegate};
+use crate::agent::TaskType;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const DEFAULT_DATASET: &str = include_str!("../../../templates/eval/delegation-default.yaml");
+
+/// One labeled task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalSample {
+    pub task: String,
+    /// Task type name as accepted by `task add --type`.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub task_type: Option<String>,
+    pub expected: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalDataset {
+    #[serde(default)]
+    pub name: String,
+    pub samples: Vec<EvalSample>,
+}
+
+impl EvalDataset {
+    /// `default` is the built-in dataset; anything else is read as a YAML
+    /// or JSON file.
+    pub fn load(name_or_path: &str) -> Result<Self> {
+        if name_or_path == "default" {
+            return Self::parse(DEFAULT_DATASET);
+        }
+        let path = Path::new(name_or_path);
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read dataset {}", path.display()))?;
+        let mut dataset =
+            Self::parse(&content).with_context(|| format!("Invalid dataset {}", path.display()))?;
+        if dataset.name.is_empty() {
+            dataset.name = name_or_path.to_string();
+        }
+        Ok(dataset)
+    }
+
+    /// Parse and check a dataset. Unknown agents and task types are errors
+    /// so a typo cannot silently count as a miss.
+    pub fn parse(content: &str) -> Result<Self> {
+        // YAML is a superset of JSON, so one parser covers both.
+        let dataset: Self = serde_yml::from_str(content).context("Failed to parse dataset")?;
+        if dataset.samples.is_empty() {
+            anyhow::bail!("Dataset '{}' has no samples", dataset.name);
+        }
+        for (index, sample) in dataset.samples.iter().enumerate() {
+            if !AGENTS.contains(&sample.expected.as_str()) {
+                anyhow::bail!(
+                    "Sample {} expects unknown agent '{}' (expected one of: {})",
+                    index + 1,
+                    sample.expected,
+                    AGENTS.join(", ")
+                );
+            }
+            if let Some(task_type) = &sample.task_type {
+                task_type
+                    .parse::<TaskType>()
+                    .with_context(|| format!("Sample {}", index + 1))?;
+            }
+        }
+        Ok(dataset)
+    }
+}
+
+/// A sample the strategy sent to the wrong agent.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalMiss {
+    pub task: String,
+    pub expected: String,
+    pub predicted: String,
+    pub reasoning: String,
+}
+
+/// Per-agent precision and recall.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentScore {
+    pub agent: String,
+    /// Samples labeled with this agent.
+    pub support: usize,
+    pub precision: f64,
+    pub recall: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub dataset: String,
+    pub strategy: DelegationStrategy,
+    pub total: usize,
+    pub correct: usize,
+    pub accuracy: f64,
+    /// `confusion[expected][predicted]` = sample count, over every agent.
+    pub confusion: BTreeMap<String, BTreeMap<String, usize>>,
+    pub per_agent: Vec<AgentScore>,
+    pub misses: Vec<EvalMiss>,
+}
+
+impl EvalReport {
+    fn count(&self, expected: &str, predicted: &str) -> usize {
+        self.confusion
+            .get(expected)
+            .and_then(|row| row.get(predicted))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Confusion matrix as an aligned text table, expected agents as rows.
+    pub fn confusion_table(&self) -> String {
+        let width = AGENTS.iter().map(|a| a.len()).max().unwrap_or(0).max(8);
+        let mut table = format!("{:<width$}", "expected");
+        for agent in AGENTS {
+            table.push_str(&format!(" {agent:>width$}"));
+        }
+        table.push('\n');
+        for expected in AGENTS {
+            table.push_str(&format!("{expected:<width$}"));
+            for predicted in AGENTS {
+                table.push_str(&format!(" {:>width$}", self.count(expected, predicted)));
+            }
+            table.push('\n');
+        }
+        table
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// Score `strategy` on every sample in `dataset`.
+pub fn evaluate(strategy: DelegationStrategy, dataset: &EvalDataset) -> EvalReport {
+    let mut confusion: BTreeMap<String, BTreeMap<String, usize>> = AGENTS
+        .iter()
+        .map(|expected| {
+            let row = AGENTS.iter().map(|p| (p.to_string(), 0)).collect();
+            (expected.to_string(), row)
+        })
+        .collect();
+    let mut misses = Vec::new();
+    for sample in &dataset.samples {
+        let task_type = sample.task_type.as_deref().and_then(|t| t.parse().ok());
+        let decision = delegate(strategy, &sample.task, task_type);
+        *confusion
+            .entry(sample.expected.clone())
+            .or_default()
+            .entry(decision.agent.clone())
+            .or_default() += 1;
+        if decision.agent != sample.expected {
+            misses.push(EvalMiss {
+                task: sample.task.clone(),
+                expected: sample.expected.clone(),
+                predicted: decision.agent,
+                reasoning: decision.reasoning,
+            });
+        }
+    }
+
+    let total = dataset.samples.len();
+    let correct = total - misses.len();
+    let mut report = EvalReport {
+        dataset: dataset.name.clone(),
+        strategy,
+        total,
+        correct,
+        accuracy: ratio(correct, total),
+        confusion,
+        per_agent: Vec::new(),
+        misses,
+    };
+    report.per_agent = AGENTS
+        .iter()
+        .map(|agent| {
+            let hits = report.count(agent, agent);
+            let support: usize = AGENTS.iter().map(|p| report.count(agent, p)).sum();
+            let predicted: usize = AGENTS.iter().map(|e| report.count(e, agent)).sum();
+            AgentScore {
+                agent: agent.to_string(),
+                support,
+                precision: ratio(hits, predicted),
+                recall: ratio(hits, support),
+            }
+        })
+        .collect();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_dataset_loads_and_hybrid_beats_task_type_only() {
+        let dataset = EvalDataset::load("default").unwrap();
+        assert!(dataset.samples.len() >= 40);
+        for agent in AGENTS {
+            assert!(
+                dataset.samples.iter().any(|s| s.expected == *agent),
+                "no samples for {agent}"
+            );
+        }
+
+        let hybrid = evaluate(DelegationStrategy::Hybrid, &dataset);
+        let by_type = evaluate(DelegationStrategy::TaskType, &dataset);
+        assert_eq!(hybrid.total, dataset.samples.len());
+        assert_eq!(hybrid.correct + hybrid.misses.len(), hybrid.total);
+        assert!(hybrid.accuracy > by_type.accuracy);
+        // Baseline for the built-in keywords; lower it only on purpose.
+        assert!(
+            hybrid.accuracy >= 0.8,
+            "hybrid accuracy {}",
+            hybrid.accuracy
+        );
+    }
+
+    #[test]
+    fn test_confusion_matrix_counts_every_sample() {
+        let dataset = EvalDataset::parse(
+            r#"
+name: tiny
+samples:
+  - { task: "Add a REST endpoint for invoices", expected: backend }
+  - { task: "Write a Dockerfile", type: infrastructure, expected: devops }
+  - { task: "Polish the onboarding copy", expected: frontend }
+"#,
+        )
+        .unwrap();
+        let report = evaluate(DelegationStrategy::Hybrid, &dataset);
+        assert_eq!(report.correct, 2);
+        assert_eq!(report.count("frontend", "backend"), 1, "fallback miss");
+        assert_eq!(report.misses[0].expected, "frontend");
+        let total: usize = report.confusion.values().flat_map(|row| row.values()).sum();
+        assert_eq!(total, 3);
+
+        let frontend = &report.per_agent[0];
+        assert_eq!((frontend.support, frontend.recall), (1, 0.0));
+        let backend = &report.per_agent[1];
+        assert_eq!((backend.precision, backend.recall), (0.5, 1.0));
+        assert!(report.confusion_table().starts_with("expected"));
+    }
+
+    #[test]
+    fn test_dataset_rejects_unknown_agent_and_type() {
+        let err = EvalDataset::parse("name: bad\nsamples:\n  - { task: x, expected: designer }\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown agent 'designer'"));
+        let err = EvalDataset::parse(
+            "name: bad\nsamples:\n  - { task: x, type: chores, expected: qa }\n",
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("Unknown task type"));
+    }
+}
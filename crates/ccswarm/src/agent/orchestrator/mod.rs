@@ -1,5 +1,8 @@
 pub mod agent_orchestrator;
+pub mod delegation;
+pub mod eval;
 pub mod task_plan;
 
 pub use agent_orchestrator::{AgentOrchestrator, OrchestrationBuilder};
+pub use delegation::{DelegationDecision, DelegationStrategy};
 pub use task_plan::{ParallelTask, StepResult, StepType, TaskPlan, TaskStep};
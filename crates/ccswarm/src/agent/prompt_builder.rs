@@ -0,0 +1,477 @@
+//! Token-aware prompt assembly.
+//!
+//! Prompts used to be built by pushing strings onto a `Vec` and joining
+//! them, so section order depended on the call site and nothing bounded how
+//! much previous output ended up in front of the model. [`PromptBuilder`]
+//! fixes the layout — every section has a [`Slot`], and slots always render
+//! in the same order — and gives each section a token budget with an
+//! [`Overflow`] strategy for when it does not fit.
+//!
+//! Tokens are estimated (about four characters each), which is close enough
+//! to keep prompts inside a context window without a tokenizer dependency.
+
+use serde::Serialize;
+
+/// Where a section goes. Variants are listed in render order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Slot {
+    /// System instructions; returned separately in [`BuiltPrompt::system`]
+    /// so providers can forward them as a system prompt.
+    System,
+    /// Project instructions such as CLAUDE.md.
+    ProjectInstructions,
+    /// The task being worked on.
+    Task,
+    /// What to do for this step.
+    Instructions,
+    /// Tools, permissions and other limits.
+    Constraints,
+    /// Results of earlier steps and other relevant context.
+    Context,
+    /// Recent agent or terminal output.
+    RecentOutput,
+    /// How to format the response (rubrics, routing tags).
+    ResponseFormat,
+}
+
+impl Slot {
+    /// Budget applied when a section does not set its own.
+    pub fn default_budget(self) -> Option<usize> {
+        match self {
+            Self::ProjectInstructions => Some(2000),
+            Self::Context => Some(4000),
+            Self::RecentOutput => Some(1000),
+            _ => None,
+        }
+    }
+
+    /// What is lost when a section in this slot is too long.
+    pub fn default_overflow(self) -> Overflow {
+        match self {
+            // The newest output is at the end.
+            Self::RecentOutput => Overflow::KeepTail,
+            _ => Overflow::KeepHead,
+        }
+    }
+
+    /// Order in which slots give up tokens when the whole prompt is over
+    /// its total budget. Slots not listed are never trimmed.
+    const SHED_ORDER: [Slot; 3] = [Slot::RecentOutput, Slot::Context, Slot::ProjectInstructions];
+}
+
+/// What to do with a section that exceeds its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Keep the beginning.
+    KeepHead,
+    /// Keep the end.
+    KeepTail,
+    /// Keep the beginning and the end, cutting the middle.
+    KeepEnds,
+    /// Leave the section out entirely.
+    Drop,
+}
+
+/// Sections smaller than this after trimming are dropped instead; a few
+/// words of a log are noise.
+const MIN_SECTION_TOKENS: usize = 32;
+
+/// Rough token count: four characters per token, rounded up.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// One section of a prompt.
+#[derive(Debug, Clone)]
+pub struct PromptSection {
+    pub slot: Slot,
+    /// Rendered as `## heading` above the body.
+    pub heading: Option<String>,
+    pub body: String,
+    /// Token budget for the body; `None` uses [`Slot::default_budget`].
+    pub budget: Option<usize>,
+    pub overflow: Overflow,
+}
+
+impl PromptSection {
+    pub fn new(slot: Slot, body: impl Into<String>) -> Self {
+        Self {
+            slot,
+            heading: None,
+            body: body.into(),
+            budget: slot.default_budget(),
+            overflow: slot.default_overflow(),
+        }
+    }
+
+    pub fn with_heading(mut self, heading: impl Into<String>) -> Self {
+        self.heading = Some(heading.into());
+        self
+    }
+
+    pub fn with_budget(mut self, tokens: usize) -> Self {
+        self.budget = Some(tokens);
+        self
+    }
+
+    pub fn unbounded(mut self) -> Self {
+        self.budget = None;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+/// How a section fared in [`PromptBuilder::build`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionReport {
+    pub slot: Slot,
+    pub heading: Option<String>,
+    /// Estimated tokens of the body as given.
+    pub original_tokens: usize,
+    /// Estimated tokens of the body as rendered (0 when dropped).
+    pub tokens: usize,
+    pub truncated: bool,
+    pub dropped: bool,
+}
+
+/// An assembled prompt.
+#[derive(Debug, Clone)]
+pub struct BuiltPrompt {
+    pub system: Option<String>,
+    pub user: String,
+    pub sections: Vec<SectionReport>,
+}
+
+impl BuiltPrompt {
+    /// Estimated tokens of the system and user prompts together.
+    pub fn estimated_tokens(&self) -> usize {
+        self.system.as_deref().map_or(0, estimate_tokens) + estimate_tokens(&self.user)
+    }
+}
+
+/// Assembles prompt sections into a fixed layout.
+#[derive(Debug, Clone, Default)]
+pub struct PromptBuilder {
+    sections: Vec<PromptSection>,
+    total_budget: Option<usize>,
+}
+
+impl PromptBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cap the whole prompt (system included) at `tokens`. When the sections
+    /// add up to more, recent output is trimmed first, then context, then
+    /// project instructions; the task, instructions, constraints and
+    /// response format are never cut.
+    pub fn with_total_budget(mut self, tokens: usize) -> Self {
+        self.total_budget = Some(tokens);
+        self
+    }
+
+    pub fn section(mut self, section: PromptSection) -> Self {
+        self.sections.push(section);
+        self
+    }
+
+    pub fn system(self, text: impl Into<String>) -> Self {
+        self.section(PromptSection::new(Slot::System, text))
+    }
+
+    pub fn project_instructions(self, text: impl Into<String>) -> Self {
+        self.section(PromptSection::new(Slot::ProjectInstructions, text))
+    }
+
+    pub fn task(self, heading: impl Into<String>, text: impl Into<String>) -> Self {
+        self.section(PromptSection::new(Slot::Task, text).with_heading(heading))
+    }
+
+    pub fn instructions(self, text: impl Into<String>) -> Self {
+        self.section(PromptSection::new(Slot::Instructions, text))
+    }
+
+    pub fn constraint(self, text: impl Into<String>) -> Self {
+        self.section(PromptSection::new(Slot::Constraints, text))
+    }
+
+    pub fn context(self, heading: impl Into<String>, text: impl Into<String>) -> Self {
+        self.section(PromptSection::new(Slot::Context, text).with_heading(heading))
+    }
+
+    pub fn recent_output(self, heading: impl Into<String>, text: impl Into<String>) -> Self {
+        self.section(PromptSection::new(Slot::RecentOutput, text).with_heading(heading))
+    }
+
+    pub fn response_format(self, text: impl Into<String>) -> Self {
+        self.section(PromptSection::new(Slot::ResponseFormat, text))
+    }
+
+    /// Render the prompt. Sections are ordered by slot, then by the order
+    /// they were added; empty sections are skipped.
+    pub fn build(self) -> BuiltPrompt {
+        let mut sections: Vec<PromptSection> = self
+            .sections
+            .into_iter()
+            .filter(|section| !section.body.trim().is_empty())
+            .collect();
+        // Stable, so insertion order holds within a slot.
+        sections.sort_by_key(|section| section.slot);
+
+        let mut rendered: Vec<Rendered> = sections
+            .into_iter()
+            .map(|section| {
+                let original_tokens = estimate_tokens(&section.body);
+                let body = match section.budget {
+                    Some(budget) => fit(&section.body, budget, section.overflow),
+                    None => Some(section.body.clone()),
+                };
+                Rendered {
+                    section,
+                    original_tokens,
+                    body,
+                }
+            })
+            .collect();
+
+        if let Some(total) = self.total_budget {
+            shed(&mut rendered, total);
+        }
+
+        let mut system = Vec::new();
+        let mut user = Vec::new();
+        let mut reports = Vec::with_capacity(rendered.len());
+        for Rendered {
+            section,
+            original_tokens,
+            body,
+        } in rendered
+        {
+            let tokens = body.as_deref().map_or(0, estimate_tokens);
+            reports.push(SectionReport {
+                slot: section.slot,
+                heading: section.heading.clone(),
+                original_tokens,
+                tokens,
+                truncated: body.as_deref().is_some_and(|b| b != section.body),
+                dropped: body.is_none(),
+            });
+            let Some(body) = body else { continue };
+            let text = match &section.heading {
+                Some(heading) => format!("## {heading}\n\n{body}"),
+                None => body,
+            };
+            if section.slot == Slot::System {
+                system.push(text);
+            } else {
+                user.push(text);
+            }
+        }
+
+        BuiltPrompt {
+            system: (!system.is_empty()).then(|| system.join("\n\n")),
+            user: user.join("\n\n"),
+            sections: reports,
+        }
+    }
+}
+
+struct Rendered {
+    section: PromptSection,
+    original_tokens: usize,
+    /// `None` once dropped.
+    body: Option<String>,
+}
+
+impl Rendered {
+    /// Tokens this section adds to the prompt: `## heading\n\n`, the body
+    /// and the `\n\n` separator.
+    fn tokens(&self) -> usize {
+        self.body.as_deref().map_or(0, |body| {
+            let heading = self
+                .section
+                .heading
+                .as_deref()
+                .map_or(0, |h| h.chars().count() + 5);
+            (heading + body.chars().count() + 2).div_ceil(4)
+        })
+    }
+}
+
+/// Trim sheddable sections until the prompt fits in `total` tokens or
+/// nothing sheddable is left.
+fn shed(rendered: &mut [Rendered], total: usize) {
+    let mut used: usize = rendered.iter().map(Rendered::tokens).sum();
+    for slot in Slot::SHED_ORDER {
+        // Later sections in a slot were added last; cut those first.
+        for entry in rendered.iter_mut().rev().filter(|r| r.section.slot == slot) {
+            if used <= total {
+                return;
+            }
+            let Some(body) = &entry.body else { continue };
+            let before = entry.tokens();
+            // One token of slack for rounding in the per-section estimate.
+            let allowance = estimate_tokens(body).saturating_sub(used - total + 1);
+            entry.body = if allowance < MIN_SECTION_TOKENS {
+                None
+            } else {
+                fit(body, allowance, entry.section.overflow)
+            };
+            used = used - before + entry.tokens();
+        }
+    }
+}
+
+const HEAD_MARKER: &str = "\n… [truncated]";
+const TAIL_MARKER: &str = "[earlier output truncated] …\n";
+const MIDDLE_MARKER: &str = "\n… [truncated] …\n";
+
+/// `text` cut to about `budget` tokens, or `None` for [`Overflow::Drop`].
+fn fit(text: &str, budget: usize, overflow: Overflow) -> Option<String> {
+    if estimate_tokens(text) <= budget {
+        return Some(text.to_string());
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let keep = |marker: &str| (budget * 4).saturating_sub(marker.chars().count());
+    let slice = |range: std::ops::Range<usize>| chars[range].iter().collect::<String>();
+    match overflow {
+        Overflow::Drop => None,
+        Overflow::KeepHead => Some(slice(0..keep(HEAD_MARKER)) + HEAD_MARKER),
+        Overflow::KeepTail => {
+            let keep = keep(TAIL_MARKER);
+            Some(format!(
+                "{TAIL_MARKER}{}",
+                slice(chars.len() - keep..chars.len())
+            ))
+        }
+        Overflow::KeepEnds => {
+            let keep = keep(MIDDLE_MARKER);
+            let head = keep.div_ceil(2);
+            let tail = keep - head;
+            Some(format!(
+                "{}{MIDDLE_MARKER}{}",
+                slice(0..head),
+                slice(chars.len() - tail..chars.len())
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_is_fixed_by_slot_not_call_order() {
+        let a = PromptBuilder::new()
+            .response_format("Reply with [STEP:1].")
+            .context("Context from previous steps", "plan: do it")
+            .instructions("Implement the plan.")
+            .task("User Task", "Add login")
+            .system("You are the backend agent.")
+            .constraint("Permission level: Edit")
+            .recent_output("Recent output", "cargo test ok")
+            .project_instructions("Use anyhow for errors.")
+            .build();
+        let b = PromptBuilder::new()
+            .system("You are the backend agent.")
+            .project_instructions("Use anyhow for errors.")
+            .task("User Task", "Add login")
+            .instructions("Implement the plan.")
+            .constraint("Permission level: Edit")
+            .context("Context from previous steps", "plan: do it")
+            .recent_output("Recent output", "cargo test ok")
+            .response_format("Reply with [STEP:1].")
+            .build();
+
+        assert_eq!(a.user, b.user);
+        assert_eq!(a.system.as_deref(), Some("You are the backend agent."));
+        assert_eq!(
+            a.user,
+            "Use anyhow for errors.\n\n## User Task\n\nAdd login\n\nImplement the plan.\n\n\
+             Permission level: Edit\n\n## Context from previous steps\n\nplan: do it\n\n\
+             ## Recent output\n\ncargo test ok\n\nReply with [STEP:1]."
+        );
+    }
+
+    #[test]
+    fn test_overflow_strategies_respect_budget() {
+        let text: String = (0..400).map(|i| format!("line{i:03}\n")).collect();
+        let budget = 40;
+
+        let head = fit(&text, budget, Overflow::KeepHead).unwrap();
+        assert!(head.starts_with("line000") && head.ends_with("[truncated]"));
+        let tail = fit(&text, budget, Overflow::KeepTail).unwrap();
+        assert!(tail.starts_with("[earlier output truncated]") && tail.ends_with("line399\n"));
+        let ends = fit(&text, budget, Overflow::KeepEnds).unwrap();
+        assert!(ends.starts_with("line000") && ends.ends_with("line399\n"));
+        assert!(ends.contains("[truncated]"));
+        for fitted in [&head, &tail, &ends] {
+            assert!(estimate_tokens(fitted) <= budget, "{}", fitted.len());
+        }
+        assert_eq!(fit(&text, budget, Overflow::Drop), None);
+        assert_eq!(
+            fit("short", budget, Overflow::Drop).as_deref(),
+            Some("short")
+        );
+        // Multi-byte text is cut on character boundaries.
+        assert!(fit(&"あ".repeat(500), 10, Overflow::KeepEnds).is_some());
+    }
+
+    #[test]
+    fn test_section_budgets_and_reports() {
+        let built = PromptBuilder::new()
+            .task("Task", "Fix the flaky test")
+            .section(
+                PromptSection::new(Slot::Context, "x".repeat(1000))
+                    .with_heading("Logs")
+                    .with_budget(50)
+                    .with_overflow(Overflow::Drop),
+            )
+            .recent_output("Recent", "y".repeat(8000))
+            .context("Empty", "   ")
+            .build();
+
+        assert!(!built.user.contains("## Logs"));
+        assert!(!built.user.contains("## Empty"));
+        assert_eq!(built.sections.len(), 3, "empty sections are not reported");
+        let logs = &built.sections[1];
+        assert!(logs.dropped && logs.tokens == 0 && logs.original_tokens == 250);
+        let recent = &built.sections[2];
+        assert!(recent.truncated && !recent.dropped);
+        assert_eq!(recent.original_tokens, 2000);
+        assert!(recent.tokens <= Slot::RecentOutput.default_budget().unwrap());
+    }
+
+    #[test]
+    fn test_total_budget_sheds_recent_output_then_context() {
+        let build = |total| {
+            PromptBuilder::new()
+                .with_total_budget(total)
+                .task("Task", "t".repeat(400))
+                .context("Context", "c".repeat(2000))
+                .recent_output("Recent", "r".repeat(2000))
+                .build()
+        };
+
+        let roomy = build(10_000);
+        assert!(roomy.sections.iter().all(|s| !s.truncated && !s.dropped));
+
+        // 100 task + 500 context + 500 recent (+ headings) into 900.
+        let tight = build(900);
+        assert!(tight.estimated_tokens() <= 900);
+        assert!(tight.sections[2].truncated, "recent output goes first");
+        assert!(!tight.sections[1].truncated);
+
+        let starved = build(120);
+        assert_eq!(starved.sections[0].tokens, 100, "task is never trimmed");
+        assert!(starved.sections[1].dropped && starved.sections[2].dropped);
+        assert!(starved.user.starts_with("## Task"));
+    }
+}
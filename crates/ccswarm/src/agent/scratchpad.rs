@@ -0,0 +1,295 @@
+//! Per-agent scratchpad that outlives tasks and sessions.
+//!
+//! Each agent keeps notes, TODOs and hypotheses in
+//! `.ccswarm/agents/<agent>/scratchpad.json` of the main worktree. The agent
+//! edits it through the `notes` typed tool, sees it at the top of every
+//! prompt that carries the tool, and a person reads it with
+//! `ccswarm agent notes <agent>`.
+//!
+//! The pad is bounded by `scratchpad.max_chars`. Once its rendering grows
+//! past that, the oldest entries — finished TODOs first — are folded into a
+//! one-line-each summary, which is itself kept to a quarter of the budget.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const SCRATCHPAD_FILE: &str = "scratchpad.json";
+
+/// Characters of an entry kept when it is folded into the summary.
+const MAX_DIGEST: usize = 120;
+
+/// The `scratchpad` config knob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScratchpadConfig {
+    /// Size budget of the rendered pad, in characters.
+    pub max_chars: usize,
+}
+
+impl Default for ScratchpadConfig {
+    fn default() -> Self {
+        Self { max_chars: 4_000 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Note,
+    Todo,
+    Hypothesis,
+}
+
+impl EntryKind {
+    fn heading(self) -> &'static str {
+        match self {
+            Self::Note => "Notes",
+            Self::Todo => "TODO",
+            Self::Hypothesis => "Hypotheses",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u32,
+    pub kind: EntryKind,
+    pub text: String,
+    /// A TODO that has been done.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub done: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Entry {
+    /// One line standing in for the entry once it is summarized.
+    fn digest(&self) -> String {
+        let line = self.text.lines().next().unwrap_or("").trim();
+        let line = match line.char_indices().nth(MAX_DIGEST) {
+            Some((end, _)) => format!("{}…", &line[..end]),
+            None => line.to_string(),
+        };
+        match (self.kind, self.done) {
+            (EntryKind::Todo, true) => format!("done: {line}"),
+            (EntryKind::Todo, false) => format!("todo: {line}"),
+            (EntryKind::Note, _) => format!("note: {line}"),
+            (EntryKind::Hypothesis, _) => format!("hypothesis: {line}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scratchpad {
+    /// Digests of entries folded away to stay within the budget, oldest
+    /// first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub summary: Vec<String>,
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+    #[serde(default)]
+    next_id: u32,
+}
+
+/// Whether `agent` can name a scratchpad directory.
+pub fn is_valid_agent(agent: &str) -> bool {
+    !agent.is_empty()
+        && !agent.starts_with('.')
+        && agent
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Where `agent`'s pad lives under the main worktree `repo`.
+pub fn path(repo: &Path, agent: &str) -> Result<PathBuf> {
+    if !is_valid_agent(agent) {
+        bail!("Invalid agent name '{}'", agent);
+    }
+    Ok(repo
+        .join(".ccswarm")
+        .join("agents")
+        .join(agent)
+        .join(SCRATCHPAD_FILE))
+}
+
+impl Scratchpad {
+    /// `agent`'s pad, empty if it has none yet.
+    pub async fn load(repo: &Path, agent: &str) -> Result<Self> {
+        let path = path(repo, agent)?;
+        match tokio::fs::read_to_string(&path).await {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub async fn save(&self, repo: &Path, agent: &str) -> Result<()> {
+        let path = path(repo, agent)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_string_pretty(self)?).await?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.summary.is_empty() && self.entries.is_empty()
+    }
+
+    /// Add an entry and return its id.
+    pub fn add(&mut self, kind: EntryKind, text: &str) -> Result<u32> {
+        let text = text.trim();
+        if text.is_empty() {
+            bail!("Scratchpad entries need text");
+        }
+        self.next_id += 1;
+        self.entries.push(Entry {
+            id: self.next_id,
+            kind,
+            text: text.to_string(),
+            done: false,
+            created_at: Utc::now(),
+        });
+        Ok(self.next_id)
+    }
+
+    /// Mark a TODO done.
+    pub fn complete(&mut self, id: u32) -> Result<()> {
+        let entry = self.entry_mut(id)?;
+        if entry.kind != EntryKind::Todo {
+            bail!("Entry #{} is not a TODO", id);
+        }
+        entry.done = true;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: u32) -> Result<()> {
+        self.entry_mut(id)?;
+        self.entries.retain(|e| e.id != id);
+        Ok(())
+    }
+
+    fn entry_mut(&mut self, id: u32) -> Result<&mut Entry> {
+        self.entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .with_context(|| format!("No scratchpad entry #{id}"))
+    }
+
+    /// Fold the oldest entries into the summary until the pad renders within
+    /// `max_chars`. Returns how many entries were folded.
+    pub fn compact(&mut self, max_chars: usize) -> usize {
+        let summary_budget = max_chars / 4;
+        let mut folded = 0;
+        loop {
+            while self
+                .summary
+                .iter()
+                .map(|l| l.chars().count() + 3)
+                .sum::<usize>()
+                > summary_budget
+            {
+                self.summary.remove(0);
+            }
+            if self.entries.is_empty() || self.render().chars().count() <= max_chars {
+                break;
+            }
+            let index = self.entries.iter().position(|e| e.done).unwrap_or_default();
+            let entry = self.entries.remove(index);
+            self.summary.push(entry.digest());
+            folded += 1;
+        }
+        while !self.summary.is_empty() && self.render().chars().count() > max_chars {
+            self.summary.remove(0);
+        }
+        folded
+    }
+
+    /// The pad as Markdown, as the agent and `ccswarm agent notes` see it.
+    pub fn render(&self) -> String {
+        let mut sections = Vec::new();
+        if !self.summary.is_empty() {
+            let lines: Vec<String> = self.summary.iter().map(|l| format!("- {l}")).collect();
+            sections.push(format!("### Earlier (summarized)\n{}", lines.join("\n")));
+        }
+        for kind in [EntryKind::Todo, EntryKind::Hypothesis, EntryKind::Note] {
+            let lines: Vec<String> = self
+                .entries
+                .iter()
+                .filter(|e| e.kind == kind)
+                .map(|e| match (kind, e.done) {
+                    (EntryKind::Todo, true) => format!("- [x] #{} {}", e.id, e.text),
+                    (EntryKind::Todo, false) => format!("- [ ] #{} {}", e.id, e.text),
+                    _ => format!("- #{} {}", e.id, e.text),
+                })
+                .collect();
+            if !lines.is_empty() {
+                sections.push(format!("### {}\n{}", kind.heading(), lines.join("\n")));
+            }
+        }
+        sections.join("\n\n")
+    }
+
+    /// Prompt section for an agent whose pad has anything in it.
+    pub fn prompt_section(&self) -> Option<String> {
+        (!self.is_empty()).then(|| {
+            format!(
+                "## Your scratchpad\n\nKept across tasks; update it with the `notes` tool.\n\n{}",
+                self.render()
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pad_persists_and_folds_old_entries_past_its_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pad = Scratchpad::load(dir.path(), "backend").await.unwrap();
+        assert!(pad.is_empty());
+
+        let todo = pad.add(EntryKind::Todo, "wire up the orders API").unwrap();
+        pad.add(EntryKind::Hypothesis, "flaky test is a port clash")
+            .unwrap();
+        pad.add(EntryKind::Note, "migrations live in db/migrate")
+            .unwrap();
+        pad.complete(todo).unwrap();
+        assert!(pad.complete(todo + 1).is_err());
+        assert!(pad.render().contains("- [x] #1 wire up the orders API"));
+
+        pad.save(dir.path(), "backend").await.unwrap();
+        let mut pad = Scratchpad::load(dir.path(), "backend").await.unwrap();
+        assert_eq!(pad.entries.len(), 3);
+        assert!(Scratchpad::load(dir.path(), "../x").await.is_err());
+
+        for i in 0..20 {
+            pad.add(
+                EntryKind::Note,
+                &format!("observation number {i} about the build"),
+            )
+            .unwrap();
+        }
+        let folded = pad.compact(600);
+        assert!(folded > 0);
+        assert!(pad.render().chars().count() <= 600);
+        // The finished TODO goes first, the newest entry stays.
+        assert!(pad.entries.iter().all(|e| !e.done));
+        assert!(
+            pad.entries
+                .last()
+                .unwrap()
+                .text
+                .contains("observation number 19")
+        );
+        assert!(pad.render().contains("### Earlier (summarized)"));
+        assert_eq!(pad.add(EntryKind::Note, "next").unwrap(), 24);
+    }
+}
@@ -71,6 +71,11 @@ impl CommandRegistry {
             runner.list_agents(*all)
         );
 
+        register_command!(self, "agent", runner, cmd,
+            Commands::Agent { action } =>
+            runner.handle_agent(action)
+        );
+
         register_command!(self, "agent-gen", runner, cmd,
             Commands::AgentGen { action } =>
             runner.handle_agent_gen(action)
@@ -97,8 +102,8 @@ impl CommandRegistry {
         );
 
         register_command!(self, "pipeline", runner, cmd,
-            Commands::Pipeline { task, flow, output_format, timeout, verbose, output_file, isolate, budget, run_budget_tokens, model_override, auto_commit, create_pr, dry_run, .. } =>
-            runner.handle_pipeline_with_dry_run(task, flow, output_format, *timeout, *verbose, output_file.as_deref(), *isolate, *budget, *run_budget_tokens, model_override.as_deref(), *auto_commit, *create_pr, None, *dry_run)
+            Commands::Pipeline { task, flow, output_format, timeout, verbose, output_file, isolate, budget, run_budget_tokens, model_override, auto_commit, create_pr, dry_run, name, .. } =>
+            runner.handle_pipeline_with_dry_run(task, flow, output_format, *timeout, *verbose, output_file.as_deref(), *isolate, *budget, *run_budget_tokens, model_override.as_deref(), *auto_commit, *create_pr, None, *dry_run, name.as_deref())
         );
 
         register_command!(self, "doctor", runner, cmd,
@@ -111,6 +116,11 @@ impl CommandRegistry {
             runner.handle_quickstart(name.as_deref(), *no_prompt, *all_agents, *with_tests)
         );
 
+        register_command!(self, "demo", runner, cmd,
+            Commands::Demo { path, no_run } =>
+            runner.handle_demo(path, *no_run)
+        );
+
         register_command!(self, "flow", runner, cmd,
             Commands::Flow { action } =>
             runner.handle_piece(action)
@@ -146,9 +156,19 @@ impl CommandRegistry {
             runner.handle_run(action)
         );
 
+        register_command!(self, "runs", runner, cmd,
+            Commands::Runs { action } =>
+            runner.handle_runs(action)
+        );
+
+        register_command!(self, "secrets", runner, cmd,
+            Commands::Secrets { action } =>
+            runner.handle_secrets(action)
+        );
+
         register_command!(self, "scaffold", runner, cmd,
-            Commands::Scaffold { dir, task, flow, timeout } =>
-            runner.handle_scaffold(dir, task, flow, *timeout)
+            Commands::Scaffold { dir, task, template, flow, timeout, auto_deploy } =>
+            runner.handle_scaffold(dir, task.as_deref(), template.as_deref(), flow.as_deref(), *timeout, *auto_deploy)
         );
 
         register_command!(self, "facets", runner, cmd,
@@ -161,9 +181,24 @@ impl CommandRegistry {
             runner.handle_tail(run_id.as_deref(), *no_follow)
         );
 
+        register_command!(self, "status", runner, cmd,
+            Commands::Status { detailed } =>
+            runner.handle_status(*detailed)
+        );
+
+        register_command!(self, "delegate", runner, cmd,
+            Commands::Delegate { action } =>
+            runner.handle_delegate(action)
+        );
+
+        register_command!(self, "chat", runner, cmd,
+            Commands::Chat { message, yes } =>
+            runner.handle_chat(message.as_deref(), *yes)
+        );
+
         register_command!(self, "cost", runner, cmd,
-            Commands::Cost { run_id } =>
-            runner.handle_cost(run_id.as_deref())
+            Commands::Cost { run_id, estimates } =>
+            runner.handle_cost(run_id.as_deref(), *estimates)
         );
 
         register_command!(self, "queue", runner, cmd,
@@ -171,6 +206,11 @@ impl CommandRegistry {
             runner.handle_queue(action)
         );
 
+        register_command!(self, "cluster", runner, cmd,
+            Commands::Cluster { pool, node, action } =>
+            runner.handle_cluster(pool.as_deref(), node.as_deref(), action)
+        );
+
         register_command!(self, "undo", runner, cmd,
             Commands::Undo { run_id } =>
             runner.handle_undo(run_id.as_deref())
@@ -181,6 +221,90 @@ impl CommandRegistry {
             runner.handle_replay(run_id.as_deref(), flow.as_deref(), *timeout)
         );
 
+        register_command!(self, "halt", runner, cmd,
+            Commands::Halt { reason } =>
+            runner.handle_halt(reason.as_deref())
+        );
+
+        register_command!(self, "resume-all", runner, runner.handle_resume_all());
+
+        register_command!(self, "recover", runner, cmd,
+            Commands::Recover { dry_run } =>
+            runner.handle_recover(*dry_run)
+        );
+
+        register_command!(self, "bench", runner, cmd,
+            Commands::Bench { tasks, io_mib, output, baseline, tolerance } =>
+            runner.handle_bench(*tasks, *io_mib, output.as_deref(), baseline.as_deref(), *tolerance)
+        );
+
+        register_command!(self, "bisect", runner, cmd,
+            Commands::Bisect { good, bad, command, agent, max_steps, no_fix_task } =>
+            runner.handle_bisect(good, bad, command.as_deref(), agent, *max_steps, !no_fix_task)
+        );
+
+        register_command!(self, "template", runner, cmd,
+            Commands::Template { action } =>
+            runner.handle_template(action)
+        );
+
+        register_command!(self, "context", runner, cmd,
+            Commands::Context { action } =>
+            runner.handle_context(action)
+        );
+
+        register_command!(self, "bundle", runner, cmd,
+            Commands::Bundle { action } =>
+            runner.handle_bundle(action)
+        );
+
+        register_command!(self, "human", runner, cmd,
+            Commands::Human { action } =>
+            runner.handle_human(action)
+        );
+
+        register_command!(self, "tui", runner, cmd,
+            Commands::Tui { theme, view } =>
+            runner.handle_tui(theme.as_deref(), view.as_ref())
+        );
+
+        register_command!(self, "quality", runner, cmd,
+            Commands::Quality { action } =>
+            runner.handle_quality(action)
+        );
+
+        register_command!(self, "hooks", runner, cmd,
+            Commands::Hooks { action } =>
+            runner.handle_hooks(action)
+        );
+
+        register_command!(self, "tools", runner, cmd,
+            Commands::Tools { action } =>
+            runner.handle_tools(action)
+        );
+
+        register_command!(self, "completions", runner, cmd,
+            Commands::Completions { shell } =>
+            runner.handle_completions(*shell)
+        );
+
+        register_command!(self, "man", runner, cmd,
+            Commands::Man { out_dir } =>
+            runner.handle_man(out_dir.as_deref())
+        );
+
+        register_command!(self, "daemon", runner, cmd,
+            Commands::Daemon { action } =>
+            runner.handle_daemon(action)
+        );
+
+        register_command!(
+            self,
+            "stop",
+            runner,
+            runner.handle_daemon(&super::DaemonAction::Stop)
+        );
+
         register_command!(self, "auto", runner, cmd,
             Commands::Auto { task, flow, watch, poll_secs, max_iterations, wall_budget_secs, stop_on_error, timeout, create_pr, require_approval, approval_timeout } =>
             runner.handle_auto(task.as_deref(), flow, *watch, *poll_secs, *max_iterations, *wall_budget_secs, *stop_on_error, *timeout, *create_pr, require_approval.then(|| std::time::Duration::from_secs(*approval_timeout)))
@@ -219,6 +343,7 @@ impl CommandRegistry {
             Commands::Init { .. } => "init",
             Commands::Task { .. } => "task",
             Commands::Agents { .. } => "agents",
+            Commands::Agent { .. } => "agent",
             Commands::AgentGen { .. } => "agent-gen",
             Commands::Worktree { .. } => "worktree",
             Commands::Logs { .. } => "logs",
@@ -226,12 +351,22 @@ impl CommandRegistry {
             Commands::Interactive { .. } => "interactive",
             Commands::Doctor { .. } => "doctor",
             Commands::Quickstart { .. } => "quickstart",
+            Commands::Demo { .. } => "demo",
             Commands::Facets { .. } => "facets",
             Commands::Tail { .. } => "tail",
+            Commands::Status { .. } => "status",
+            Commands::Delegate { .. } => "delegate",
+            Commands::Chat { .. } => "chat",
             Commands::Cost { .. } => "cost",
             Commands::Queue { .. } => "queue",
+            Commands::Cluster { .. } => "cluster",
             Commands::Undo { .. } => "undo",
             Commands::Replay { .. } => "replay",
+            Commands::Halt { .. } => "halt",
+            Commands::ResumeAll => "resume-all",
+            Commands::Recover { .. } => "recover",
+            Commands::Bench { .. } => "bench",
+            Commands::Bisect { .. } => "bisect",
             Commands::Auto { .. } => "auto",
             Commands::Pipeline { .. } => "pipeline",
             Commands::Flow { .. } => "flow",
@@ -241,7 +376,21 @@ impl CommandRegistry {
             Commands::Approve { .. } => "approve",
             Commands::Session { .. } => "session",
             Commands::Run { .. } => "run",
+            Commands::Runs { .. } => "runs",
+            Commands::Secrets { .. } => "secrets",
             Commands::Scaffold { .. } => "scaffold",
+            Commands::Template { .. } => "template",
+            Commands::Context { .. } => "context",
+            Commands::Bundle { .. } => "bundle",
+            Commands::Human { .. } => "human",
+            Commands::Tui { .. } => "tui",
+            Commands::Quality { .. } => "quality",
+            Commands::Hooks { .. } => "hooks",
+            Commands::Tools { .. } => "tools",
+            Commands::Completions { .. } => "completions",
+            Commands::Man { .. } => "man",
+            Commands::Daemon { .. } => "daemon",
+            Commands::Stop => "stop",
         }
     }
 }
@@ -0,0 +1,189 @@
+//! `ccswarm lab ab`: run one task under two configurations and record the winner.
+
+use super::super::*;
+use super::workflow::AskOptions;
+use crate::workflow::ab_eval::{self, ExperimentRecord, Variant, VariantOutcome, Winner};
+
+/// Arguments of `ccswarm lab ab`.
+pub(crate) struct AbOptions<'a> {
+    pub task: &'a str,
+    pub a: &'a str,
+    pub b: &'a str,
+    pub flow: &'a str,
+    pub timeout: u64,
+    pub no_review: bool,
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_ab(&self, options: AbOptions<'_>) -> Result<()> {
+        let a = Variant::parse("A", options.a)?;
+        let b = Variant::parse("B", options.b)?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let out_dir = ab_eval::experiments_dir(&self.repo_path).join(&id);
+        tokio::fs::create_dir_all(&out_dir).await?;
+
+        for variant in [&a, &b] {
+            println!(
+                "{} {} {}",
+                "▶".bright_cyan().bold(),
+                variant.label.bright_yellow(),
+                variant.describe(options.flow).bright_white()
+            );
+        }
+
+        // Both runs get their own worktree, so they can't see each other's edits.
+        let (a_outcome, b_outcome) = tokio::join!(
+            self.run_ab_variant(&a, &options, &out_dir),
+            self.run_ab_variant(&b, &options, &out_dir)
+        );
+
+        let review = if options.no_review || !(a_outcome.success && b_outcome.success) {
+            None
+        } else {
+            let a_output = read_output(&out_dir, &a).await;
+            let b_output = read_output(&out_dir, &b).await;
+            let prompt =
+                ab_eval::review_prompt(options.task, &a_outcome, &a_output, &b_outcome, &b_output);
+            match self.review_ab(&prompt).await {
+                Ok(reply) => {
+                    let parsed = ab_eval::parse_review_reply(&reply);
+                    if parsed.is_none() {
+                        warn!("A/B reviewer gave no WINNER line — deciding by duration");
+                    }
+                    parsed
+                }
+                Err(e) => {
+                    warn!("A/B review failed, deciding by duration: {}", e);
+                    None
+                }
+            }
+        };
+
+        let verdict = ab_eval::decide(&a_outcome, &b_outcome, review);
+        let record = ExperimentRecord {
+            id,
+            task: options.task.to_string(),
+            created_at: chrono::Utc::now(),
+            a: a_outcome,
+            b: b_outcome,
+            verdict,
+        };
+        ab_eval::append_record(&self.repo_path, &record).await?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": record,
+                }))?
+            );
+            return Ok(());
+        }
+
+        println!();
+        for outcome in [&record.a, &record.b] {
+            let status = if outcome.success {
+                "ok".bright_green()
+            } else {
+                "failed".bright_red()
+            };
+            println!(
+                "  {} [{}] {} {:.0}s run {}",
+                outcome.variant.label.bright_yellow(),
+                status,
+                outcome.config,
+                outcome.duration_ms as f64 / 1000.0,
+                outcome
+                    .run_id
+                    .chars()
+                    .take(8)
+                    .collect::<String>()
+                    .bright_black()
+            );
+            if let Some(error) = &outcome.error {
+                println!("      {}", error.bright_black());
+            }
+        }
+        let winner = match record.verdict.winner {
+            Winner::Tie => "tie".bright_white().bold(),
+            side => format!("{side} wins").bright_green().bold(),
+        };
+        println!(
+            "\n{} ({:?}): {}",
+            winner, record.verdict.decided_by, record.verdict.reason
+        );
+        println!(
+            "Recorded experiment {} in {}",
+            record.id.chars().take(8).collect::<String>(),
+            ab_eval::experiments_dir(&self.repo_path)
+                .join(ab_eval::EXPERIMENTS_FILE)
+                .display()
+        );
+        Ok(())
+    }
+
+    /// Run one variant in an isolated worktree. Failures become a failed
+    /// outcome rather than an error so the other side still gets recorded.
+    async fn run_ab_variant(
+        &self,
+        variant: &Variant,
+        options: &AbOptions<'_>,
+        out_dir: &Path,
+    ) -> VariantOutcome {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let output_file = output_path(out_dir, variant);
+        let started = std::time::Instant::now();
+        let result = self
+            .execute_pipeline_core(
+                Some(&run_id),
+                &variant.task_text(options.task),
+                variant.flow_or(options.flow),
+                "text",
+                options.timeout,
+                false,
+                Some(&output_file),
+                true,
+                None,
+                None,
+                variant.model.as_deref(),
+            )
+            .await;
+
+        let (success, stages, error) = match result {
+            Ok((_, output)) => (output.is_success(), output.movement_count, output.error),
+            Err(e) => (false, 0, Some(e.to_string())),
+        };
+        VariantOutcome {
+            variant: variant.clone(),
+            config: variant.describe(options.flow),
+            run_id,
+            success,
+            duration_ms: started.elapsed().as_millis() as u64,
+            stages,
+            error,
+        }
+    }
+
+    /// One readonly reviewer call comparing the two runs.
+    async fn review_ab(&self, prompt: &str) -> Result<String> {
+        let result = self
+            .ask_provider(
+                "ab-reviewer",
+                prompt,
+                AskOptions::read_only(&self.repo_path),
+            )
+            .await?;
+        Ok(result.raw)
+    }
+}
+
+fn output_path(out_dir: &Path, variant: &Variant) -> PathBuf {
+    out_dir.join(format!("{}.txt", variant.label.to_lowercase()))
+}
+
+async fn read_output(out_dir: &Path, variant: &Variant) -> String {
+    tokio::fs::read_to_string(output_path(out_dir, variant))
+        .await
+        .unwrap_or_default()
+}
@@ -0,0 +1,51 @@
+//! `ccswarm agent`: one agent's persistent state.
+
+use super::super::*;
+use crate::agent::scratchpad::{self, Scratchpad};
+
+impl CliRunner {
+    pub(crate) async fn handle_agent(&self, action: &AgentAction) -> Result<()> {
+        match action {
+            AgentAction::Notes { name, clear } => {
+                let repo = crate::workflow::coverage::main_worktree(&self.repo_path).await;
+                if *clear {
+                    let path = scratchpad::path(&repo, name)?;
+                    if tokio::fs::try_exists(&path).await? {
+                        tokio::fs::remove_file(&path).await?;
+                    }
+                    if self.json_output {
+                        println!(
+                            "{}",
+                            serde_json::to_string_pretty(&serde_json::json!({
+                                "status": "success",
+                                "data": { "agent": name, "cleared": true },
+                            }))?
+                        );
+                    } else {
+                        println!("{} scratchpad of {} cleared", "✓".bright_green(), name);
+                    }
+                    return Ok(());
+                }
+                let pad = Scratchpad::load(&repo, name).await?;
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "data": { "agent": name, "scratchpad": pad },
+                        }))?
+                    );
+                    return Ok(());
+                }
+                if pad.is_empty() {
+                    println!("{} has no notes yet", name.bright_cyan());
+                    return Ok(());
+                }
+                println!("{} {}", "Scratchpad of".bold(), name.bright_cyan().bold());
+                println!();
+                println!("{}", pad.render());
+                Ok(())
+            }
+        }
+    }
+}
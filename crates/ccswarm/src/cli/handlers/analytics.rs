@@ -1,4 +1,5 @@
 use super::super::*;
+use crate::workflow::{ab_eval, sangha_tally};
 
 impl CliRunner {
     pub(crate) async fn handle_search_cmd(&self, action: &SearchAction) -> Result<()> {
@@ -193,15 +194,26 @@ impl CliRunner {
                     }
                 }
 
+                let ab_records = ab_eval::load_records(&self.repo_path).await?;
+                let ab_tally = ab_eval::tally(&ab_records);
+                let mut quality = sangha_tally::decision_quality(
+                    &sangha_tally::load_proposals(&self.repo_path).await?,
+                );
+                if let Some(filter) = agent {
+                    quality.retain(|q| &q.voter == filter);
+                }
+
                 if format == "json" || self.json_output {
                     println!(
                         "{}",
                         serde_json::to_string_pretty(&serde_json::json!({
                             "status": "success",
                             "data": metrics,
+                            "ab_tally": ab_tally,
+                            "decision_quality": quality,
                         }))?
                     );
-                } else if metrics.is_empty() {
+                } else if metrics.is_empty() && ab_tally.is_empty() && quality.is_empty() {
                     println!("No agent metrics found in coordination/agent-status/");
                 } else {
                     println!("{}", "Agent Metrics".bright_cyan().bold());
@@ -218,6 +230,8 @@ impl CliRunner {
                         );
                     }
                     println!("\nTotal: {} agents", metrics.len());
+                    print_ab_tally(&ab_tally, ab_records.len());
+                    print_decision_quality(&quality);
                 }
             }
             EvolutionAction::Patterns { agent, limit } => {
@@ -321,9 +335,18 @@ impl CliRunner {
                     0
                 };
 
+                let ab_records = ab_eval::load_records(&self.repo_path).await?;
+                let ab_tally = ab_eval::tally(&ab_records);
+                let quality = sangha_tally::decision_quality(
+                    &sangha_tally::load_proposals(&self.repo_path).await?,
+                );
+
                 let report = serde_json::json!({
                     "agent_count": agent_count,
                     "task_count": task_count,
+                    "ab_experiments": ab_records.len(),
+                    "ab_tally": ab_tally,
+                    "decision_quality": quality,
                     "generated_at": chrono::Utc::now().to_rfc3339(),
                 });
 
@@ -341,6 +364,7 @@ impl CliRunner {
                         println!("# Evolution Report\n");
                         println!("- **Agents tracked**: {}", agent_count);
                         println!("- **Tasks in queue**: {}", task_count);
+                        println!("- **A/B experiments**: {}", ab_records.len());
                         println!(
                             "- **Generated**: {}",
                             report
@@ -348,12 +372,36 @@ impl CliRunner {
                                 .and_then(|v| v.as_str())
                                 .unwrap_or("?")
                         );
+                        if !ab_tally.is_empty() {
+                            println!("\n## A/B configurations\n");
+                            println!("| Configuration | Wins | Losses | Ties |");
+                            println!("|---|---|---|---|");
+                            for t in &ab_tally {
+                                println!(
+                                    "| `{}` | {} | {} | {} |",
+                                    t.config, t.wins, t.losses, t.ties
+                                );
+                            }
+                        }
+                        if !quality.is_empty() {
+                            println!("\n## Sangha decision quality\n");
+                            println!("| Voter | Settled votes | With the outcome | Weight |");
+                            println!("|---|---|---|---|");
+                            for q in &quality {
+                                println!(
+                                    "| `{}` | {} | {} | {:.2} |",
+                                    q.voter, q.settled_votes, q.agreed, q.weight
+                                );
+                            }
+                        }
                     }
                     _ => {
                         println!("{}", "Evolution Report".bright_cyan().bold());
                         println!("{}", "================".bright_cyan());
                         println!("  Agents tracked: {}", agent_count);
                         println!("  Tasks in queue: {}", task_count);
+                        print_ab_tally(&ab_tally, ab_records.len());
+                        print_decision_quality(&quality);
                     }
                 }
             }
@@ -362,3 +410,43 @@ impl CliRunner {
         Ok(())
     }
 }
+
+/// A/B win/loss lines for `lab evolution metrics` and `report`.
+fn print_ab_tally(tally: &[ab_eval::ConfigTally], experiments: usize) {
+    if tally.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "{} ({} experiments)",
+        "A/B Configurations".bright_cyan().bold(),
+        experiments
+    );
+    for t in tally {
+        println!(
+            "  {}  {} won, {} lost, {} tied",
+            t.config.bright_yellow(),
+            t.wins.to_string().bright_green(),
+            t.losses.to_string().bright_red(),
+            t.ties
+        );
+    }
+}
+
+/// Sangha voting records for `lab evolution metrics` and `report`.
+fn print_decision_quality(quality: &[sangha_tally::DecisionQuality]) {
+    if quality.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}", "Sangha Decision Quality".bright_cyan().bold());
+    for q in quality {
+        println!(
+            "  {}  {}/{} votes with the outcome, weight {:.2}",
+            q.voter.bright_yellow(),
+            q.agreed,
+            q.settled_votes,
+            q.weight
+        );
+    }
+}
@@ -1,5 +1,35 @@
 use super::super::*;
+use super::workflow::{AskOptions, ask_with};
 use crate::hitl::{ApprovalRecord, ApprovalStatus, ApprovalStore, Gate};
+use crate::security::command_risk::{self, CommandAssessment, CommandExplainer};
+
+/// Explains held commands with a single read-only provider call.
+pub(crate) struct ProviderExplainer {
+    bridge: crate::session::bridge::AISessionBridge,
+    provider: crate::providers::ProviderKind,
+    repo_path: PathBuf,
+}
+
+#[async_trait::async_trait]
+impl CommandExplainer for ProviderExplainer {
+    async fn explain(&self, assessment: &CommandAssessment) -> Result<String> {
+        let result = ask_with(
+            &self.bridge,
+            self.provider,
+            "command-explainer",
+            &command_risk::explanation_prompt(assessment),
+            AskOptions {
+                role: crate::identity::default_devops_role(),
+                ..AskOptions::read_only(&self.repo_path)
+            },
+        )
+        .await?;
+        if !result.success || result.raw.trim().is_empty() {
+            return Err(anyhow!("provider gave no explanation"));
+        }
+        Ok(result.raw.trim().to_string())
+    }
+}
 
 impl CliRunner {
     pub(crate) async fn handle_approve(&self, action: &ApproveAction) -> Result<()> {
@@ -24,6 +54,17 @@ impl CliRunner {
                 self.process_approval(Gate::Commit, id, *reject, reason.as_deref())
                     .await?;
             }
+            ApproveAction::Resume { id, reject, reason } => {
+                self.process_approval(Gate::Resume, id, *reject, reason.as_deref())
+                    .await?;
+            }
+            ApproveAction::Command { id, reject, reason } => {
+                self.process_approval(Gate::Command, id, *reject, reason.as_deref())
+                    .await?;
+            }
+            ApproveAction::Show { id, explain } => {
+                self.show_approval(id, *explain).await?;
+            }
             ApproveAction::List { status } => {
                 self.list_approvals(status.as_deref()).await?;
             }
@@ -73,6 +114,105 @@ impl CliRunner {
         Ok(())
     }
 
+    /// Explainer for commands held by auto-accept, on the configured provider.
+    pub(crate) fn command_explainer(&self) -> Result<ProviderExplainer> {
+        Ok(ProviderExplainer {
+            bridge: self.session_bridge()?,
+            provider: self.provider_kind(),
+            repo_path: self.repo_path.clone(),
+        })
+    }
+
+    async fn show_approval(&self, id: &str, explain: bool) -> Result<()> {
+        let store = ApprovalStore::new(&self.repo_path);
+        let record = store
+            .get(id)
+            .await?
+            .ok_or_else(|| anyhow!("No approval request '{}'", id))?;
+        let mut explanation = store.explanation(id).await?;
+        if explanation.is_none()
+            && explain
+            && let Some(assessment) = &record.command
+        {
+            let text = self.command_explainer()?.explain(assessment).await?;
+            store.set_explanation(id, &text).await?;
+            explanation = Some(text);
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": record,
+                    "explanation": explanation,
+                }))?
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{} [{}] {}",
+            record.id.bright_yellow(),
+            record.gate.to_string().bright_cyan(),
+            status_label(record.status)
+        );
+        if let Some(by) = &record.requested_by {
+            println!(
+                "  Requested by {} at {}",
+                by,
+                record.requested_at.as_deref().unwrap_or("?")
+            );
+        }
+        match &record.command {
+            Some(assessment) => {
+                println!(
+                    "\n  {} {}",
+                    "$".bright_black(),
+                    assessment.command.bright_white()
+                );
+                println!("  Risk: {}", risk_label(assessment.score));
+                for finding in &assessment.findings {
+                    println!("    • [{}/10] {}", finding.score, finding.effect);
+                }
+                println!("\n  {}", "What it does".bold());
+                match &explanation {
+                    Some(text) => {
+                        for line in text.lines() {
+                            println!("  {}", line);
+                        }
+                    }
+                    None => {
+                        println!("  {}", assessment.summary());
+                        println!(
+                            "  {}",
+                            "(static analysis only; --explain asks the provider)".bright_black()
+                        );
+                    }
+                }
+                if record.status == ApprovalStatus::Pending {
+                    println!(
+                        "\n  ccswarm approve command --id {}            run it",
+                        record.id
+                    );
+                    println!(
+                        "  ccswarm approve command --id {} --reject   refuse it",
+                        record.id
+                    );
+                }
+            }
+            None => {
+                if let Some(task) = &record.task {
+                    println!("  Task: {}", task);
+                }
+            }
+        }
+        if let Some(reason) = &record.reason {
+            println!("  Reason: {}", reason);
+        }
+        Ok(())
+    }
+
     async fn list_approvals(&self, status_filter: Option<&str>) -> Result<()> {
         let store = ApprovalStore::new(&self.repo_path);
         let approvals: Vec<ApprovalRecord> = store
@@ -105,11 +245,7 @@ impl CliRunner {
             println!("{}", "Approvals".bright_cyan().bold());
             println!("{}", "=========".bright_cyan());
             for record in &approvals {
-                let status_colored = match record.status {
-                    ApprovalStatus::Approved => "approved".bright_green(),
-                    ApprovalStatus::Rejected => "rejected".bright_red(),
-                    ApprovalStatus::Pending => "pending".bright_yellow(),
-                };
+                let status_colored = status_label(record.status);
                 let ts = record
                     .decided_at
                     .as_deref()
@@ -122,6 +258,21 @@ impl CliRunner {
                     status_colored,
                     ts.bright_black()
                 );
+                if let Some(assessment) = &record.command {
+                    println!(
+                        "      {} {}  {}",
+                        "$".bright_black(),
+                        assessment.command,
+                        risk_label(assessment.score)
+                    );
+                    let explanation = store.explanation(&record.id).await?;
+                    let what = explanation
+                        .as_deref()
+                        .and_then(|text| text.lines().find(|l| !l.trim().is_empty()))
+                        .map(str::to_string)
+                        .unwrap_or_else(|| assessment.summary());
+                    println!("      {}", what.bright_black());
+                }
             }
             println!("\nTotal: {} records", approvals.len());
         }
@@ -129,3 +280,20 @@ impl CliRunner {
         Ok(())
     }
 }
+
+fn status_label(status: ApprovalStatus) -> colored::ColoredString {
+    match status {
+        ApprovalStatus::Approved => "approved".bright_green(),
+        ApprovalStatus::Rejected => "rejected".bright_red(),
+        ApprovalStatus::Pending => "pending".bright_yellow(),
+    }
+}
+
+fn risk_label(score: u8) -> colored::ColoredString {
+    let label = format!("risk {}/10", score);
+    if score >= command_risk::HIGH_RISK {
+        label.bright_red().bold()
+    } else {
+        label.bright_yellow()
+    }
+}
@@ -3,13 +3,23 @@
 //! Pulls tasks from the `.ccswarm/queue.yaml` file (or takes a single `--task`), runs them
 //! through the pipeline with `--auto-commit` enabled, optionally creates a PR, and repeats.
 //! All y/n prompts are suppressed. Decisions are emitted as events so the operator can
-//! inspect what the loop did via `ccswarm tail` / `ccswarm runs list`.
+//! inspect what the loop did via `ccswarm tail` / `ccswarm runs list`. With `--watch`,
+//! the loop also runs the proactive master review between polls (see `proactive`), and
+//! wakes as soon as the queue file changes (see `coordination.watch`) rather than only
+//! every `--poll-secs`.
 
 use super::super::*;
-use super::queue_state::{QUEUE_FILE, QueueState, load_queue};
+use super::queue::{
+    print_retry_plan, print_retry_scheduled, reconcile_retry_queue, schedule_retry,
+};
+use super::queue_state::{QUEUE_FILE, QueueState, TaskAttempt, TimeBox, load_queue};
+use super::{blockers, time_box};
+use crate::governance::watch::ChangeWatcher;
+use crate::notify::{self, Notification, NotifyEvent};
+use crate::workflow::retry_policy::AttemptPlan;
 use chrono::Utc;
 use std::path::Path;
-use tracing::info;
+use tracing::{Instrument, info};
 
 const AUTO_LOG: &str = ".ccswarm/auto.ndjson";
 
@@ -68,6 +78,7 @@ impl CliRunner {
                     "direct",
                     task_body,
                     flow,
+                    &AttemptPlan::first(),
                     timeout,
                     create_pr,
                     approval_gate,
@@ -86,7 +97,20 @@ impl CliRunner {
         }
 
         // Path 2: drain the queue, optionally watching for more.
+        self.recover_abandoned().await;
         let queue_path = self.repo_path.join(QUEUE_FILE);
+        let master = &self.config.project.master_claude;
+        let mut next_review = std::time::Instant::now();
+        let mut watcher = if watch {
+            let coordination = &self.config.coordination;
+            Some(ChangeWatcher::new(
+                coordination.watch,
+                vec![queue_path.clone()],
+                std::time::Duration::from_secs(coordination.sync_interval),
+            )?)
+        } else {
+            None
+        };
 
         loop {
             if let Some(dl) = deadline
@@ -119,16 +143,35 @@ impl CliRunner {
                 break;
             }
 
-            if !watch {
+            let Some(watcher) = watcher.as_mut() else {
                 break;
+            };
+
+            if master.enable_proactive_mode && std::time::Instant::now() >= next_review {
+                let interval = match self.proactive_review().await {
+                    Ok(true) => master.high_frequency,
+                    Ok(false) => master.proactive_frequency,
+                    Err(e) => {
+                        eprintln!("{} proactive review failed: {}", "⚠".bright_yellow(), e);
+                        master.proactive_frequency
+                    }
+                };
+                next_review = std::time::Instant::now() + std::time::Duration::from_secs(interval);
             }
 
             println!(
-                "{} queue empty — sleeping {}s before next poll",
+                "{} queue empty — waiting up to {}s for changes ({})",
                 "…".bright_black(),
-                poll_secs
+                poll_secs,
+                if watcher.is_event_driven() {
+                    "file events"
+                } else {
+                    "polling"
+                }
             );
-            tokio::time::sleep(std::time::Duration::from_secs(poll_secs)).await;
+            watcher
+                .changed(std::time::Duration::from_secs(poll_secs))
+                .await;
         }
 
         self.auto_summary_line(ok, ng, processed).await;
@@ -149,38 +192,55 @@ impl CliRunner {
         ok: &mut usize,
         ng: &mut usize,
     ) -> Result<LoopSignal> {
-        let queue = load_queue(queue_path).await?;
-        let pending = queue
-            .tasks
-            .iter()
-            .filter(|task| task.state == "pending")
-            .cloned()
-            .collect::<Vec<_>>();
+        let queue_state = QueueState::new(queue_path.to_path_buf());
+        reconcile_retry_queue(&queue_state).await?;
+        time_box::reconcile_paused(&queue_state, &self.repo_path).await?;
+        blockers::reconcile_blocked(&queue_state, &self.repo_path, &self.config.preflight).await?;
+
+        let pending = load_queue(queue_path).await?.ready_tasks();
 
         if pending.is_empty() {
             return Ok(LoopSignal::Continue);
         }
 
-        let queue_state = QueueState::new(queue_path.to_path_buf());
-
         for queued_task in pending {
             if max_iterations > 0 && *processed >= max_iterations {
                 return Ok(LoopSignal::Stop("max iterations reached".to_string()));
             }
 
             let task_id = queued_task.id.clone();
-            let task_body = queued_task.task.clone();
             let flow_name = queued_task
                 .flow
                 .clone()
                 .unwrap_or_else(|| piece_default.to_string());
+            let policy = self.config.retry.policy_for(&flow_name);
+            let plan = time_box::plan_next(&queued_task, policy);
+            let task_body = time_box::task_text(&queued_task, &plan);
             let run_id = uuid::Uuid::new_v4().to_string();
+            let budget = queued_task.time_box.as_ref().map(TimeBox::budget);
+            let pipeline_timeout = queued_task
+                .time_box
+                .map_or(timeout, |tb| timeout.max(tb.budget_secs));
 
             if !queue_state.try_claim(&task_id, &run_id).await? {
                 info!(task_id = %task_id, "skipping: already claimed by another drain");
                 continue;
             }
 
+            match self
+                .park_if_unavailable(&queue_state, &task_id, &queued_task.labels)
+                .await
+            {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    queue_state
+                        .release(&task_id, &format!("failed: {e}"))
+                        .await?;
+                    return Err(e);
+                }
+            }
+
             if let Err(e) = queue_state
                 .update_task(&task_id, |task| {
                     task.state = "running".to_string();
@@ -194,18 +254,52 @@ impl CliRunner {
                 return Err(e);
             }
 
-            let (outcome, run_id) = self
-                .auto_run_one(
+            let started_at = Utc::now();
+            let run = self.auto_run_one(
+                &run_id,
+                &task_id,
+                &task_body,
+                &flow_name,
+                &plan,
+                pipeline_timeout,
+                create_pr,
+                approval_gate,
+            );
+            let Some((outcome, run_id)) = time_box::within_budget(budget, run).await else {
+                *processed += 1;
+                let action = match time_box::pause_over_budget(
+                    &queue_state,
+                    &self.repo_path,
+                    &queued_task,
+                    &plan,
                     &run_id,
-                    &task_id,
-                    &task_body,
-                    &flow_name,
-                    timeout,
-                    create_pr,
-                    approval_gate,
+                    started_at,
+                    "auto",
+                )
+                .await
+                {
+                    Ok(action) => action,
+                    Err(e) => {
+                        queue_state
+                            .release(&task_id, &format!("failed: {e}"))
+                            .await?;
+                        return Err(e);
+                    }
+                };
+                time_box::print_over_budget(&queued_task, action);
+                self.auto_log(
+                    "auto.task_over_budget",
+                    serde_json::json!({
+                        "task_id": task_id,
+                        "run_id": run_id,
+                        "action": action.to_string(),
+                    }),
                 )
                 .await;
+                continue;
+            };
             *processed += 1;
+            let attempt_run_id = run_id.clone().unwrap_or_default();
 
             match outcome {
                 Ok(()) => {
@@ -216,6 +310,12 @@ impl CliRunner {
                             // Record run_id so operators can jump from the queue file
                             // to the run events even for autonomous executions.
                             task.run_id = run_id.clone();
+                            task.attempts.push(TaskAttempt::finished(
+                                &plan,
+                                &attempt_run_id,
+                                started_at,
+                                None,
+                            ));
                             Ok(())
                         })
                         .await
@@ -230,6 +330,38 @@ impl CliRunner {
                 }
                 Err(e) => {
                     let error_message = e.to_string();
+                    let attempt = TaskAttempt::finished(
+                        &plan,
+                        &attempt_run_id,
+                        started_at,
+                        Some(error_message.clone()),
+                    );
+                    match self
+                        .park_if_blocked(&queue_state, &task_id, &attempt_run_id, &attempt)
+                        .await
+                    {
+                        Ok(true) => continue,
+                        Ok(false) => {}
+                        Err(park_error) => {
+                            queue_state
+                                .release(&task_id, &format!("failed: {park_error}"))
+                                .await?;
+                            return Err(park_error);
+                        }
+                    }
+                    match schedule_retry(&queue_state, policy, &task_id, attempt).await {
+                        Ok(Some(delay)) => {
+                            print_retry_scheduled(&task_id, &error_message, plan.attempt, delay);
+                            continue;
+                        }
+                        Ok(None) => {}
+                        Err(schedule_error) => {
+                            queue_state
+                                .release(&task_id, &format!("failed: {schedule_error}"))
+                                .await?;
+                            return Err(schedule_error);
+                        }
+                    }
                     if let Err(update_error) = queue_state
                         .update_task(&task_id, |task| {
                             task.state = "failed".to_string();
@@ -276,6 +408,7 @@ impl CliRunner {
         task_id: &str,
         task_body: &str,
         flow_name: &str,
+        plan: &AttemptPlan,
         timeout: u64,
         create_pr: bool,
         approval_gate: Option<std::time::Duration>,
@@ -288,12 +421,14 @@ impl CliRunner {
             flow_name.bright_white(),
             create_pr
         );
+        print_retry_plan(plan);
         self.auto_log(
             "auto.task_start",
             serde_json::json!({
                 "task_id": task_id,
                 "flow": flow_name,
                 "create_pr": create_pr,
+                "attempt": plan.attempt,
             }),
         )
         .await;
@@ -310,11 +445,18 @@ impl CliRunner {
                 false,
                 None,
                 None, // run_budget_tokens
-                None, // model override
+                plan.model.as_deref(),
                 /* auto_commit = */ true,
                 create_pr,
                 approval_gate,
             )
+            .instrument(tracing::info_span!(
+                "queue.task",
+                task_id = %task_id,
+                flow = %flow_name,
+                attempt = plan.attempt,
+                run_id = %run_id,
+            ))
             .await;
 
         match &result {
@@ -325,6 +467,15 @@ impl CliRunner {
                     task_id.bright_yellow(),
                     run_id.chars().take(8).collect::<String>().bright_black()
                 );
+                notify::send(
+                    &self.config.notifications,
+                    Notification::new(
+                        NotifyEvent::TaskCompleted,
+                        format!("ccswarm: {task_id} completed"),
+                        task_body.lines().next().unwrap_or_default(),
+                    ),
+                )
+                .await;
                 self.auto_log(
                     "auto.task_end",
                     serde_json::json!({
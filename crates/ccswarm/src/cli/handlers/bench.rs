@@ -0,0 +1,557 @@
+//! `ccswarm bench`: a standardized synthetic workload for catching
+//! performance regressions in ccswarm itself.
+//!
+//! A scratch git repository gets `--tasks` queued tasks of four kinds
+//! (feature, fix, docs, refactor), each with its own flow of one to three
+//! stages, and the queue is drained with every provider call answered by a
+//! canned reply (see [`crate::providers::transcript`]). With the provider
+//! out of the picture, what is left is ccswarm's own cost:
+//!
+//! - orchestration overhead: how long each task takes from claim to result;
+//! - scheduling latency: the gap between one task ending and the next
+//!   being claimed;
+//! - session I/O: how fast output streams out of an ai-session shell;
+//! - memory: the process's resident and peak resident set.
+//!
+//! `--output` saves the report; `--baseline` compares a run against a saved
+//! one and fails when a metric got worse by more than `--tolerance` percent.
+
+use super::super::*;
+use super::queue_state::{QUEUE_FILE, QueueState, QueueTask, load_queue};
+use crate::git::shell::git;
+use crate::providers::ProviderOptions;
+use crate::providers::transcript::{ProviderResponse, TranscriptReplay, entry};
+use crate::session::{AISession, AISessionConfig};
+use anyhow::bail;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Task kinds, cycled through in order: flow name, task wording, stages.
+const WORKLOAD: &[(&str, &str, &[&str])] = &[
+    (
+        "bench-feature",
+        "Add a /v1/orders/{n} endpoint with validation",
+        &["plan", "implement", "review"],
+    ),
+    (
+        "bench-fix",
+        "Fix the off-by-one in pagination of report {n}",
+        &["implement", "verify"],
+    ),
+    (
+        "bench-docs",
+        "Document configuration option number {n}",
+        &["write"],
+    ),
+    (
+        "bench-refactor",
+        "Refactor module {n} into smaller functions",
+        &["implement", "review"],
+    ),
+];
+
+/// Marker the session I/O probe waits for; the command echoes it in two
+/// halves so the command line itself never matches.
+const IO_MARKER: &str = "__CCSWARM_BENCH_END__";
+
+/// Summary statistics of a set of durations, in milliseconds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Stats {
+    pub count: usize,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Stats {
+    fn from_ms(mut samples: Vec<f64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_by(f64::total_cmp);
+        let at = |q: f64| samples[((samples.len() - 1) as f64 * q).round() as usize];
+        Self {
+            count: samples.len(),
+            mean_ms: samples.iter().sum::<f64>() / samples.len() as f64,
+            p50_ms: at(0.5),
+            p95_ms: at(0.95),
+            max_ms: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// What one `ccswarm bench` run measured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct BenchReport {
+    pub version: String,
+    pub recorded_at: chrono::DateTime<Utc>,
+    pub tasks: usize,
+    pub completed: usize,
+    pub wall_ms: f64,
+    pub tasks_per_sec: f64,
+    /// Claim to result, per task.
+    pub orchestration: Stats,
+    /// Previous task's end (or the drain's start) to claim, per task.
+    pub scheduling: Stats,
+    pub session_io_bytes: usize,
+    pub session_io_mib_per_sec: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rss_kib: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_rss_kib: Option<u64>,
+}
+
+/// A metric that got worse than the baseline allows.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Regression {
+    pub metric: &'static str,
+    pub baseline: f64,
+    pub current: f64,
+    pub change_pct: f64,
+}
+
+impl BenchReport {
+    /// Metrics that are more than `tolerance_pct` percent worse than in
+    /// `baseline`. Times and memory are worse higher, throughput lower.
+    pub(crate) fn regressions(&self, baseline: &Self, tolerance_pct: f64) -> Vec<Regression> {
+        let kib = |v: Option<u64>| v.map(|v| v as f64);
+        let metrics: [(&'static str, Option<f64>, Option<f64>, bool); 6] = [
+            (
+                "orchestration.p95_ms",
+                Some(baseline.orchestration.p95_ms),
+                Some(self.orchestration.p95_ms),
+                true,
+            ),
+            (
+                "scheduling.p95_ms",
+                Some(baseline.scheduling.p95_ms),
+                Some(self.scheduling.p95_ms),
+                true,
+            ),
+            (
+                "tasks_per_sec",
+                Some(baseline.tasks_per_sec),
+                Some(self.tasks_per_sec),
+                false,
+            ),
+            (
+                "session_io_mib_per_sec",
+                Some(baseline.session_io_mib_per_sec),
+                Some(self.session_io_mib_per_sec),
+                false,
+            ),
+            ("rss_kib", kib(baseline.rss_kib), kib(self.rss_kib), true),
+            (
+                "peak_rss_kib",
+                kib(baseline.peak_rss_kib),
+                kib(self.peak_rss_kib),
+                true,
+            ),
+        ];
+        metrics
+            .into_iter()
+            .filter_map(|(metric, baseline, current, higher_is_worse)| {
+                let (baseline, current) = (baseline?, current?);
+                if baseline <= 0.0 {
+                    return None;
+                }
+                let change_pct = (current - baseline) / baseline * 100.0;
+                let worse = if higher_is_worse {
+                    change_pct
+                } else {
+                    -change_pct
+                };
+                (worse > tolerance_pct).then_some(Regression {
+                    metric,
+                    baseline,
+                    current,
+                    change_pct,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `(VmRSS, VmHWM)` in KiB, where `/proc` has them.
+fn memory_kib() -> (Option<u64>, Option<u64>) {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|kib| kib.parse().ok())
+    };
+    (field("VmRSS:"), field("VmHWM:"))
+}
+
+fn flow_yaml(name: &str, stages: &[&str]) -> String {
+    let mut yaml = format!("name: {name}\ninitial_movement: {}\nstages:\n", stages[0]);
+    for (i, stage) in stages.iter().enumerate() {
+        yaml.push_str(&format!("  - id: {stage}\n    instruction: \"{{task}}\"\n"));
+        if let Some(next) = stages.get(i + 1) {
+            yaml.push_str(&format!(
+                "    rules:\n      - condition: success\n        next: {next}\n"
+            ));
+        }
+    }
+    yaml
+}
+
+/// A scratch repository with the bench flows and `tasks` queued tasks.
+/// Returns the number of provider calls the workload makes.
+async fn seed_workload(repo: &Path, tasks: usize) -> Result<usize> {
+    for args in [
+        &["init", "-q"][..],
+        &["config", "user.name", "ccswarm bench"],
+        &["config", "user.email", "bench@example.com"],
+        &["config", "commit.gpgsign", "false"],
+    ] {
+        git(repo, args).await?;
+    }
+    tokio::fs::write(repo.join("README.md"), "# bench\n").await?;
+    tokio::fs::write(repo.join(".gitignore"), ".ccswarm/\n").await?;
+    git(repo, &["add", "-A"]).await?;
+    git(repo, &["commit", "-q", "-m", "chore: seed bench"]).await?;
+
+    let flows = repo.join(".ccswarm").join("flows");
+    tokio::fs::create_dir_all(&flows).await?;
+    for (name, _, stages) in WORKLOAD {
+        tokio::fs::write(flows.join(format!("{name}.yaml")), flow_yaml(name, stages)).await?;
+    }
+
+    let mut calls = 0;
+    let queued: Vec<QueueTask> = (0..tasks)
+        .map(|n| {
+            let (flow, wording, stages) = WORKLOAD[n % WORKLOAD.len()];
+            calls += stages.len();
+            QueueTask {
+                id: format!("q-bench{n:03}"),
+                task: wording.replace("{n}", &n.to_string()),
+                flow: Some(flow.to_string()),
+                state: "pending".to_string(),
+                created_at: Utc::now(),
+                completed_at: None,
+                run_id: None,
+                claim: Default::default(),
+                attempts: Vec::new(),
+                time_box: None,
+                command_timeout: None,
+                model: None,
+                priority: None,
+                labels: vec!["bench".to_string()],
+                depends_on: Vec::new(),
+                blocker: None,
+                acceptance: None,
+            }
+        })
+        .collect();
+    QueueState::new(repo.join(QUEUE_FILE))
+        .update_queue(|queue| {
+            queue.tasks = queued;
+            Ok(())
+        })
+        .await?;
+    Ok(calls)
+}
+
+/// Canned replies for `calls` provider calls.
+fn mock_provider(calls: usize) -> TranscriptReplay {
+    TranscriptReplay::new(
+        (0..calls)
+            .map(|i| {
+                entry(
+                    crate::providers::ProviderKind::Claude,
+                    "default",
+                    "",
+                    &ProviderOptions::default(),
+                    ProviderResponse {
+                        success: true,
+                        stdout: format!("Done (synthetic reply {i})."),
+                        stderr: String::new(),
+                    },
+                    0,
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Stream `bytes` of output out of a headless ai-session shell. Returns
+/// MiB per second.
+async fn session_io(bytes: usize) -> Result<f64> {
+    let session = AISession::new(AISessionConfig {
+        shell: Some("/bin/sh".to_string()),
+        force_headless: true,
+        ..AISessionConfig::default()
+    })
+    .await?;
+    session.start().await?;
+    let (head, tail) = IO_MARKER.split_at(IO_MARKER.len() / 2);
+    let started = Instant::now();
+    session
+        .send_line(&format!(
+            "head -c {bytes} /dev/zero | tr '\\0' x; printf '\\n%s%s\\n' '{head}' '{tail}'"
+        ))
+        .await?;
+    session
+        .expect(IO_MARKER, Duration::from_secs(120))
+        .await
+        .context("session output did not arrive")?;
+    let elapsed = started.elapsed().as_secs_f64();
+    let _ = session.stop().await;
+    Ok(bytes as f64 / (1024.0 * 1024.0) / elapsed.max(f64::EPSILON))
+}
+
+/// Drain the workload in `repo` and time it.
+async fn run_workload(runner: &CliRunner, tasks: usize, calls: usize) -> Result<BenchReport> {
+    let repo = runner.repo_path.clone();
+    let drain_runner = CliRunner {
+        config: runner.config.clone(),
+        repo_path: repo.clone(),
+        config_path: repo.join("ccswarm.json"),
+        // Keep the drain's own progress output terse.
+        json_output: true,
+        formatter: create_formatter(true),
+        default_provider: Some(crate::providers::ProviderKind::Claude),
+        replay: Some(Arc::new(mock_provider(calls))),
+    };
+    let drain_started = Utc::now();
+    let started = Instant::now();
+    drain_runner
+        .handle_queue(&QueueAction::Drain {
+            flow: None,
+            timeout: 600,
+            fail_fast: false,
+            interactive: false,
+            create_pr: false,
+            reconcile_only: false,
+            require_approval: false,
+            approval_timeout: 600,
+            max_task_cost: None,
+        })
+        .await?;
+    let wall = started.elapsed().as_secs_f64();
+
+    let queue = load_queue(&repo.join(QUEUE_FILE)).await?;
+    let mut attempts: Vec<_> = queue
+        .tasks
+        .iter()
+        .flat_map(|task| task.attempts.iter())
+        .collect();
+    attempts.sort_by_key(|attempt| attempt.started_at);
+    let ms = |d: chrono::Duration| d.num_microseconds().unwrap_or(0) as f64 / 1000.0;
+    let orchestration = attempts
+        .iter()
+        .map(|a| ms(a.ended_at - a.started_at))
+        .collect();
+    let mut previous_end = drain_started;
+    let scheduling = attempts
+        .iter()
+        .map(|a| {
+            let gap = ms(a.started_at - previous_end).max(0.0);
+            previous_end = a.ended_at;
+            gap
+        })
+        .collect();
+    let completed = queue
+        .tasks
+        .iter()
+        .filter(|task| task.state == "completed")
+        .count();
+
+    Ok(BenchReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        recorded_at: Utc::now(),
+        tasks,
+        completed,
+        wall_ms: wall * 1000.0,
+        tasks_per_sec: tasks as f64 / wall.max(f64::EPSILON),
+        orchestration: Stats::from_ms(orchestration),
+        scheduling: Stats::from_ms(scheduling),
+        session_io_bytes: 0,
+        session_io_mib_per_sec: 0.0,
+        rss_kib: None,
+        peak_rss_kib: None,
+    })
+}
+
+fn print_stats(label: &str, stats: &Stats) {
+    println!(
+        "  {:<22} p50 {:>8.1} ms  p95 {:>8.1} ms  max {:>8.1} ms  mean {:>8.1} ms",
+        label, stats.p50_ms, stats.p95_ms, stats.max_ms, stats.mean_ms
+    );
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_bench(
+        &self,
+        tasks: usize,
+        io_mib: usize,
+        output: Option<&Path>,
+        baseline: Option<&Path>,
+        tolerance: f64,
+    ) -> Result<()> {
+        if tasks == 0 {
+            bail!("--tasks must be at least 1");
+        }
+        let baseline = match baseline {
+            Some(path) => Some(
+                serde_json::from_str::<BenchReport>(
+                    &tokio::fs::read_to_string(path)
+                        .await
+                        .with_context(|| format!("Failed to read {}", path.display()))?,
+                )
+                .with_context(|| format!("{} is not a bench report", path.display()))?,
+            ),
+            None => None,
+        };
+        if !self.json_output {
+            println!(
+                "{} {} synthetic tasks with a mock provider, {} MiB of session I/O",
+                "Benchmarking".bright_cyan().bold(),
+                tasks,
+                io_mib
+            );
+        }
+
+        let scratch = tempfile::tempdir()?;
+        let calls = seed_workload(scratch.path(), tasks).await?;
+        let workload_runner = CliRunner {
+            config: CcswarmConfig::default(),
+            repo_path: scratch.path().to_path_buf(),
+            config_path: scratch.path().join("ccswarm.json"),
+            json_output: true,
+            formatter: create_formatter(true),
+            default_provider: None,
+            replay: None,
+        };
+        let mut report = run_workload(&workload_runner, tasks, calls).await?;
+        report.session_io_bytes = io_mib * 1024 * 1024;
+        if io_mib > 0 {
+            report.session_io_mib_per_sec = session_io(report.session_io_bytes).await?;
+        }
+        (report.rss_kib, report.peak_rss_kib) = memory_kib();
+
+        if let Some(path) = output {
+            tokio::fs::write(path, serde_json::to_string_pretty(&report)?)
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        let regressions = baseline
+            .as_ref()
+            .map(|baseline| report.regressions(baseline, tolerance))
+            .unwrap_or_default();
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if regressions.is_empty() { "success" } else { "regressed" },
+                    "data": { "report": report, "regressions": regressions },
+                }))?
+            );
+        } else {
+            println!(
+                "  {:<22} {}/{} completed in {:.2}s ({:.1} tasks/s)",
+                "workload",
+                report.completed,
+                report.tasks,
+                report.wall_ms / 1000.0,
+                report.tasks_per_sec
+            );
+            print_stats("orchestration overhead", &report.orchestration);
+            print_stats("scheduling latency", &report.scheduling);
+            if io_mib > 0 {
+                println!(
+                    "  {:<22} {:.1} MiB/s",
+                    "session I/O", report.session_io_mib_per_sec
+                );
+            }
+            if let (Some(rss), Some(peak)) = (report.rss_kib, report.peak_rss_kib) {
+                println!(
+                    "  {:<22} {:.1} MiB resident, {:.1} MiB peak",
+                    "memory",
+                    rss as f64 / 1024.0,
+                    peak as f64 / 1024.0
+                );
+            }
+            if let Some(path) = output {
+                println!("  report: {}", path.display());
+            }
+            if baseline.is_some() && regressions.is_empty() {
+                println!(
+                    "{} within {:.0}% of the baseline",
+                    "✓".bright_green(),
+                    tolerance
+                );
+            }
+            for regression in &regressions {
+                println!(
+                    "{} {} {:.1} → {:.1} ({:+.1}%)",
+                    "✗".bright_red(),
+                    regression.metric,
+                    regression.baseline,
+                    regression.current,
+                    regression.change_pct
+                );
+            }
+        }
+        if report.completed < report.tasks {
+            bail!(
+                "{} of {} bench tasks did not complete",
+                report.tasks - report.completed,
+                report.tasks
+            );
+        }
+        if !regressions.is_empty() {
+            bail!(
+                "{} metric{} regressed beyond {:.0}%",
+                regressions.len(),
+                if regressions.len() == 1 { "" } else { "s" },
+                tolerance
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn the_synthetic_workload_drains_and_slowdowns_are_flagged() {
+        let scratch = tempfile::tempdir().unwrap();
+        let calls = seed_workload(scratch.path(), 5).await.unwrap();
+        assert_eq!(calls, 3 + 2 + 1 + 2 + 3);
+        let runner = CliRunner {
+            config: CcswarmConfig::default(),
+            repo_path: scratch.path().to_path_buf(),
+            config_path: scratch.path().join("ccswarm.json"),
+            json_output: true,
+            formatter: create_formatter(true),
+            default_provider: None,
+            replay: None,
+        };
+        let report = run_workload(&runner, 5, calls).await.unwrap();
+        assert_eq!(report.completed, 5);
+        assert_eq!(report.orchestration.count, 5);
+        assert!(report.scheduling.p50_ms >= 0.0);
+
+        let slower = BenchReport {
+            orchestration: Stats {
+                p95_ms: report.orchestration.p95_ms * 2.0 + 10.0,
+                ..report.orchestration.clone()
+            },
+            ..report.clone()
+        };
+        let regressions = slower.regressions(&report, 25.0);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].metric, "orchestration.p95_ms");
+        assert!(report.regressions(&slower, 25.0).is_empty());
+    }
+}
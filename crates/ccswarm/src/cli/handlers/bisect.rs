@@ -0,0 +1,290 @@
+//! `ccswarm bisect` and regression tasks in the queue (see
+//! [`crate::workflow::bisect`]).
+
+use super::super::*;
+use super::queue_state::{ClaimState, QUEUE_FILE, QueueState, QueueTask};
+use crate::agent::Priority;
+use crate::workflow::bisect::{self, BisectOptions, BisectReport, BisectStep, Verdict};
+use chrono::Utc;
+use std::collections::BTreeMap;
+
+/// Label carried by fix tasks a bisection queues.
+const BISECT_LABEL: &str = "bisect";
+
+/// What `ccswarm bisect` was asked to do.
+pub(crate) struct BisectRequest<'a> {
+    pub(crate) good: &'a str,
+    pub(crate) bad: &'a str,
+    pub(crate) command: Option<&'a str>,
+    pub(crate) agent: &'a str,
+    pub(crate) max_steps: usize,
+    pub(crate) queue_fix: bool,
+}
+
+/// The fix task for `report`'s culprit, addressed to the responsible agent.
+fn fix_task(report: &BisectReport, original: Option<&QueueTask>) -> Option<QueueTask> {
+    let body = report.fix_task_body(original.map(|task| task.task.as_str()))?;
+    let mut labels = vec![BISECT_LABEL.to_string()];
+    if let Some(responsible) = &report.responsible {
+        labels.push(format!("agent:{}", responsible.agent));
+    }
+    if let Some(original) = original {
+        labels.push(format!("follow-up:{}", original.id));
+    }
+    Some(QueueTask {
+        id: format!("q-{}", &uuid::Uuid::new_v4().to_string()[..8]),
+        task: body,
+        flow: original.and_then(|task| task.flow.clone()),
+        state: "pending".to_string(),
+        created_at: Utc::now(),
+        completed_at: None,
+        run_id: None,
+        claim: ClaimState::default(),
+        attempts: Vec::new(),
+        time_box: original.and_then(|task| task.time_box),
+        command_timeout: original.and_then(|task| task.command_timeout),
+        model: original.and_then(|task| task.model.clone()),
+        priority: Some(Priority::High),
+        labels,
+        depends_on: Vec::new(),
+        blocker: None,
+        acceptance: None,
+    })
+}
+
+fn print_step(step: &BisectStep) {
+    let verdict = match step.verdict {
+        Verdict::Good => "good".bright_green(),
+        Verdict::Bad => "bad".bright_red(),
+        Verdict::Skip => "skip".bright_yellow(),
+    };
+    println!(
+        "  {} {:<5} {:.1}s{}",
+        step.commit[..step.commit.len().min(10)].bright_black(),
+        verdict,
+        step.duration_ms as f64 / 1000.0,
+        if step.timed_out {
+            " (timed out)".bright_yellow().to_string()
+        } else {
+            String::new()
+        }
+    );
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_bisect(
+        &self,
+        good: &str,
+        bad: &str,
+        command: Option<&str>,
+        agent: &str,
+        max_steps: usize,
+        queue_fix: bool,
+    ) -> Result<()> {
+        let request = BisectRequest {
+            good,
+            bad,
+            command,
+            agent,
+            max_steps,
+            queue_fix,
+        };
+        let report = self.bisect_regression(&request, None).await?;
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if report.culprit.is_some() { "success" } else { "inconclusive" },
+                    "data": report,
+                }))?
+            );
+        }
+        Ok(())
+    }
+
+    /// Bisect, pin the culprit on an agent, queue its fix task (when
+    /// `request.queue_fix`) and save the report.
+    pub(crate) async fn bisect_regression(
+        &self,
+        request: &BisectRequest<'_>,
+        original: Option<&QueueTask>,
+    ) -> Result<BisectReport> {
+        let (command, env) = match request.command {
+            Some(command) => (command.to_string(), BTreeMap::new()),
+            None => bisect::default_command(&self.config.quality.checks, &self.repo_path)?,
+        };
+        let options = BisectOptions {
+            good: request.good.to_string(),
+            bad: request.bad.to_string(),
+            command,
+            env,
+            agent: request.agent.to_string(),
+            max_steps: request.max_steps,
+            task_id: original.map(|task| task.id.clone()),
+            command_timeout: original.and_then(|task| task.command_timeout),
+        };
+        let quiet = self.json_output;
+        if !quiet {
+            println!(
+                "{} {}..{} with `{}` in a {} session",
+                "Bisecting".bright_cyan().bold(),
+                options.good,
+                options.bad,
+                options.command,
+                options.agent
+            );
+        }
+        let mut report = bisect::run(&self.repo_path, &options, |step| {
+            if !quiet {
+                print_step(step)
+            }
+        })
+        .await?;
+
+        if let Some(culprit) = &report.culprit {
+            let agents: BTreeMap<String, String> = self
+                .config
+                .agents
+                .iter()
+                .map(|(name, agent)| (name.clone(), agent.branch.clone()))
+                .collect();
+            report.responsible =
+                Some(bisect::responsible_agent(&self.repo_path, culprit, &agents).await);
+        }
+        if request.queue_fix
+            && let Some(task) = fix_task(&report, original)
+        {
+            report.fix_task = Some(task.id.clone());
+            QueueState::new(self.repo_path.join(QUEUE_FILE))
+                .update_queue(|queue| {
+                    queue.tasks.push(task);
+                    Ok(())
+                })
+                .await?;
+        }
+        let path = bisect::save_report(&self.repo_path, &report).await?;
+
+        if !quiet {
+            match (&report.culprit, &report.responsible) {
+                (Some(culprit), Some(responsible)) => {
+                    println!(
+                        "{} {} {} ({})",
+                        "Culprit".bright_red().bold(),
+                        culprit.short().bright_yellow(),
+                        culprit.subject,
+                        culprit.author
+                    );
+                    println!(
+                        "  responsible: {} — {}",
+                        responsible.agent.bright_cyan(),
+                        responsible.reason
+                    );
+                }
+                _ => println!(
+                    "{} no culprit: {}",
+                    "!".yellow().bold(),
+                    report.inconclusive.as_deref().unwrap_or("unknown")
+                ),
+            }
+            if let Some(id) = &report.fix_task {
+                println!("  fix task {} queued", id.bright_cyan());
+            }
+            println!("  report: {}", path.display());
+        }
+        Ok(report)
+    }
+
+    /// Run a regression task from the queue: bisect from its good commit to
+    /// `HEAD` and queue the fix. Returns whether a culprit was found.
+    pub(super) async fn drain_regression(
+        &self,
+        queue_state: &QueueState,
+        task: &QueueTask,
+        good: &str,
+    ) -> Result<Option<bool>> {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        if !queue_state.try_claim(&task.id, &run_id).await? {
+            return Ok(None);
+        }
+        let request = BisectRequest {
+            good,
+            bad: "HEAD",
+            command: None,
+            agent: bisect::DEFAULT_AGENT,
+            max_steps: bisect::DEFAULT_MAX_STEPS,
+            queue_fix: true,
+        };
+        let outcome = self.bisect_regression(&request, Some(task)).await;
+        let (state, reason) = match &outcome {
+            Ok(report) if report.culprit.is_some() => ("completed", "completed".to_string()),
+            Ok(report) => (
+                "failed",
+                format!(
+                    "failed: bisect inconclusive: {}",
+                    report.inconclusive.as_deref().unwrap_or("unknown")
+                ),
+            ),
+            Err(e) => ("failed", format!("failed: {e}")),
+        };
+        queue_state
+            .update_task(&task.id, |queued| {
+                queued.state = state.to_string();
+                queued.completed_at = Some(Utc::now());
+                Ok(())
+            })
+            .await?;
+        queue_state.release(&task.id, &reason).await?;
+        outcome.map(|report| Some(report.culprit.is_some()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::bisect::{Culprit, Responsible};
+
+    #[test]
+    fn fix_tasks_go_to_the_responsible_agent_and_follow_the_regression() {
+        let now = Utc::now();
+        let report = BisectReport {
+            id: "bisect-1".into(),
+            good: "aaa".into(),
+            bad: "bbb".into(),
+            command: "cargo test".into(),
+            agent: "qa".into(),
+            task_id: Some("q-reg".into()),
+            started_at: now,
+            finished_at: now,
+            steps: Vec::new(),
+            culprit: Some(Culprit {
+                commit: "0123456789abcdef".into(),
+                subject: "Add invoices endpoint".into(),
+                author: "dev <dev@example.com>".into(),
+                files: vec!["src/api.rs".into()],
+            }),
+            responsible: Some(Responsible {
+                agent: "backend".into(),
+                reason: "the commit is on its branch agent/backend".into(),
+            }),
+            inconclusive: None,
+            fix_task: None,
+        };
+        let original = QueueTask {
+            flow: Some("review-fix".into()),
+            ..fix_task(&report, None).unwrap()
+        };
+
+        let task = fix_task(&report, Some(&original)).unwrap();
+        assert_eq!(task.priority, Some(Priority::High));
+        assert_eq!(task.flow.as_deref(), Some("review-fix"));
+        assert!(task.labels.contains(&"agent:backend".to_string()));
+        assert!(task.labels.contains(&format!("follow-up:{}", original.id)));
+        assert!(task.task.contains("0123456789"));
+
+        let inconclusive = BisectReport {
+            culprit: None,
+            ..report
+        };
+        assert!(fix_task(&inconclusive, None).is_none());
+    }
+}
@@ -0,0 +1,470 @@
+//! Queue tasks parked on a structured blocker.
+//!
+//! When a run stops on a [`Blocker`], the dispatcher parks its task as
+//! `blocked` instead of retrying or failing it. Blockers a person has to
+//! resolve become a question in a human's inbox; answering it requeues the
+//! task with the answer appended, declining it fails the task. Reconciliation
+//! requeues tasks on their own once what they wait for shows up: the task
+//! they named completes, the credential's environment variable is set, or
+//! the failed pre-flight probe passes again.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::queue_state::{ClaimState, QUEUE_FILE, QueueState, TaskAttempt, load_queue};
+use crate::agent::{Priority, Task, TaskType};
+use crate::cli::CliRunner;
+use crate::governance::human::{HumanAgentConfig, HumanInbox};
+use crate::workflow::blocker::{self, Blocker, BlockerKind};
+use crate::workflow::preflight::{self, PreflightConfig};
+
+/// Queue state of a task waiting for its blocker to clear.
+pub(crate) const BLOCKED_STATE: &str = "blocked";
+
+/// `delegated_by` of inbox items asking a human about a blocker.
+pub(super) const BLOCKER_DELEGATOR: &str = "blocker";
+
+/// Capability that marks a human as the default recipient of blocker questions.
+const BLOCKER_CAPABILITY: &str = "blockers";
+
+impl CliRunner {
+    /// Park `task_id` if its run stopped on a blocker. Returns `false` when
+    /// the run failed for some other reason and the caller should go on with
+    /// its retry policy.
+    pub(super) async fn park_if_blocked(
+        &self,
+        state: &QueueState,
+        task_id: &str,
+        run_id: &str,
+        attempt: &TaskAttempt,
+    ) -> Result<bool> {
+        let run_dir = self.repo_path.join(".ccswarm").join("runs").join(run_id);
+        let Some(blocker) = blocker::from_run(&run_dir) else {
+            return Ok(false);
+        };
+
+        let mut attempt = attempt.clone();
+        attempt.outcome = BLOCKED_STATE.to_string();
+        self.park(state, task_id, blocker, Some(attempt)).await?;
+        Ok(true)
+    }
+
+    /// Park `task_id`, which the caller has just claimed, if a pre-flight
+    /// probe for its `labels` fails. No attempt is recorded: the task never
+    /// started.
+    pub(super) async fn park_if_unavailable(
+        &self,
+        state: &QueueState,
+        task_id: &str,
+        labels: &[String],
+    ) -> Result<bool> {
+        let probes = self.config.preflight.for_labels(labels);
+        let Some(blocker) = preflight::run(probes, &self.repo_path).await else {
+            return Ok(false);
+        };
+        self.park(state, task_id, blocker, None).await?;
+        Ok(true)
+    }
+
+    /// Mark a claimed task blocked on `blocker`, release it, and ask a human
+    /// when one has to act.
+    async fn park(
+        &self,
+        state: &QueueState,
+        task_id: &str,
+        blocker: Blocker,
+        attempt: Option<TaskAttempt>,
+    ) -> Result<()> {
+        let number = attempt.as_ref().map_or(0, |a| a.attempt);
+        let parked = blocker.clone();
+        state
+            .update_task(task_id, |task| {
+                task.state = BLOCKED_STATE.to_string();
+                task.blocker = Some(parked);
+                if let Some(attempt) = attempt {
+                    task.run_id = Some(attempt.run_id.clone());
+                    task.attempts.push(attempt);
+                }
+                Ok(())
+            })
+            .await?;
+        state.release(task_id, "blocked").await?;
+
+        println!(
+            "{} {} blocked — {}",
+            "⛔".bright_red(),
+            task_id.bright_yellow(),
+            blocker.summary()
+        );
+        if blocker.needs_human() {
+            match self.ask_about_blocker(task_id, number, &blocker).await {
+                Ok(Some(human)) => println!("   question sent to {}", human.bright_cyan()),
+                Ok(None) => println!(
+                    "   no human registered; unblock with `ccswarm queue unblock {task_id}`"
+                ),
+                Err(e) => tracing::warn!(task_id, "failed to route blocker question: {e}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Put the blocker's question in a human's inbox; `None` when nobody is
+    /// registered.
+    async fn ask_about_blocker(
+        &self,
+        task_id: &str,
+        attempt: u32,
+        blocker: &Blocker,
+    ) -> Result<Option<String>> {
+        let Some(human) = pick_human(&self.config.humans, blocker.kind) else {
+            return Ok(None);
+        };
+        let task = Task::new(
+            format!("blocked-{task_id}-{attempt}"),
+            blocker.question_text(),
+            Priority::High,
+            TaskType::Development,
+        );
+        self.delegate_to_human(&human, task, BLOCKER_DELEGATOR, Some(task_id.to_string()))
+            .await?;
+        Ok(Some(human))
+    }
+
+    /// `ccswarm queue unblock`: requeue a blocked task by hand.
+    pub(super) async fn queue_unblock(
+        &self,
+        path: &Path,
+        id: &str,
+        note: Option<&str>,
+    ) -> Result<()> {
+        QueueState::new(path.to_path_buf())
+            .update_task(id, |task| {
+                if task.state != BLOCKED_STATE {
+                    anyhow::bail!("task {id} is {}, not blocked", task.state);
+                }
+                requeue(
+                    task,
+                    note.map(|note| format!("Note on the blocker: {note}")),
+                );
+                Ok(())
+            })
+            .await?;
+        if !self.json_output {
+            println!(
+                "{} {} requeued",
+                "✓".bright_green().bold(),
+                id.bright_yellow()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A blocked queue task as shown by `ccswarm status`.
+#[derive(Debug, Serialize)]
+pub(super) struct BlockedTask {
+    pub(super) id: String,
+    pub(super) blocker: Blocker,
+    /// Human whose inbox holds the open question, if one was asked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) asked: Option<String>,
+}
+
+/// Every blocked task in `repo`'s queue, oldest first.
+pub(super) async fn blocked_tasks(repo: &Path) -> Result<Vec<BlockedTask>> {
+    let queue = load_queue(&repo.join(QUEUE_FILE)).await?;
+    let mut blocked: Vec<BlockedTask> = queue
+        .tasks
+        .into_iter()
+        .filter(|task| task.state == BLOCKED_STATE)
+        .filter_map(|task| {
+            Some(BlockedTask {
+                id: task.id,
+                blocker: task.blocker?,
+                asked: None,
+            })
+        })
+        .collect();
+    if blocked.is_empty() {
+        return Ok(blocked);
+    }
+    let questions = HumanInbox::open(repo).await?.list(None).await?;
+    for task in &mut blocked {
+        task.asked = questions
+            .iter()
+            .find(|item| {
+                item.delegated_by == BLOCKER_DELEGATOR
+                    && !item.status.is_closed()
+                    && item.queue_task.as_deref() == Some(task.id.as_str())
+            })
+            .map(|item| item.human.clone());
+    }
+    Ok(blocked)
+}
+
+/// Recipient of a blocker question: the first human (by name) with the
+/// `blockers` capability or one named after the blocker kind, else the first
+/// registered human.
+fn pick_human(humans: &HashMap<String, HumanAgentConfig>, kind: BlockerKind) -> Option<String> {
+    let mut names: Vec<&String> = humans.keys().collect();
+    names.sort();
+    let kind = serde_json::to_value(kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    names
+        .iter()
+        .find(|name| {
+            humans[name.as_str()]
+                .capabilities
+                .iter()
+                .any(|c| c == BLOCKER_CAPABILITY || *c == kind)
+        })
+        .or(names.first())
+        .map(|name| name.to_string())
+}
+
+/// Back to pending with the blocker cleared; `context` is appended to the
+/// task text so the next attempt knows what changed.
+pub(crate) fn requeue(task: &mut super::queue_state::QueueTask, context: Option<String>) {
+    task.state = "pending".to_string();
+    task.claim = ClaimState::Unclaimed;
+    task.blocker = None;
+    task.completed_at = None;
+    if let Some(context) = context {
+        task.task = format!("{}\n\n{context}", task.task);
+    }
+}
+
+/// Requeue blocked tasks whose blocker cleared on its own: the task they wait
+/// on completed, the missing credential is now in the environment, or the
+/// service's probe passes (or is no longer configured). Returns how many
+/// were requeued.
+pub(crate) async fn reconcile_blocked(
+    state: &QueueState,
+    repo: &Path,
+    preflight: &PreflightConfig,
+) -> Result<usize> {
+    let queue = state.load().await?;
+    let mut services: Vec<&str> = queue
+        .tasks
+        .iter()
+        .filter(|t| t.state == BLOCKED_STATE)
+        .filter_map(|t| t.blocker.as_ref()?.service.as_deref())
+        .collect();
+    services.sort_unstable();
+    services.dedup();
+    let mut up = Vec::new();
+    for service in services {
+        let passes = match preflight.probe(service) {
+            Some(probe) => probe.check(repo).await.is_ok(),
+            None => true,
+        };
+        if passes {
+            up.push(service.to_string());
+        }
+    }
+    reconcile_blocked_with(
+        state,
+        |var| std::env::var_os(var).is_some(),
+        |service| up.iter().any(|s| s == service),
+    )
+    .await
+}
+
+async fn reconcile_blocked_with(
+    state: &QueueState,
+    credential_set: impl Fn(&str) -> bool,
+    service_up: impl Fn(&str) -> bool,
+) -> Result<usize> {
+    let queue = state.load().await?;
+    let completed: Vec<&str> = queue
+        .tasks
+        .iter()
+        .filter(|t| t.state == "completed")
+        .map(|t| t.id.as_str())
+        .collect();
+
+    let mut cleared = Vec::new();
+    for task in queue.tasks.iter().filter(|t| t.state == BLOCKED_STATE) {
+        let Some(blocker) = &task.blocker else {
+            continue;
+        };
+        let context = match (blocker.kind, &blocker.task, &blocker.credential) {
+            (BlockerKind::ServiceUnavailable, ..) => match &blocker.service {
+                Some(service) if service_up(service) => {
+                    format!("{service}, which was unavailable, is back up.")
+                }
+                _ => continue,
+            },
+            (BlockerKind::WaitingOnTask, Some(dep), _) if completed.contains(&dep.as_str()) => {
+                format!("{dep}, which this task was waiting on, has completed.")
+            }
+            (BlockerKind::MissingCredential, _, Some(var)) if credential_set(var) => {
+                format!("{var} is now set in the environment.")
+            }
+            _ => continue,
+        };
+        cleared.push((task.id.clone(), context));
+    }
+
+    for (id, context) in &cleared {
+        state
+            .update_task(id, |task| {
+                if task.state == BLOCKED_STATE {
+                    requeue(task, Some(context.clone()));
+                }
+                Ok(())
+            })
+            .await
+            .with_context(|| format!("Failed to unblock {id}"))?;
+    }
+    Ok(cleared.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::queue_state::QueueTask;
+    use chrono::Utc;
+
+    fn task(id: &str, state: &str, blocker: Option<Blocker>) -> QueueTask {
+        QueueTask {
+            id: id.to_string(),
+            task: format!("task {id}"),
+            flow: None,
+            state: state.to_string(),
+            created_at: Utc::now(),
+            completed_at: None,
+            run_id: None,
+            claim: ClaimState::default(),
+            attempts: Vec::new(),
+            time_box: None,
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker,
+            acceptance: None,
+        }
+    }
+
+    fn blocker(kind: BlockerKind, task: Option<&str>, credential: Option<&str>) -> Blocker {
+        Blocker {
+            kind,
+            detail: "stuck".to_string(),
+            credential: credential.map(str::to_string),
+            task: task.map(str::to_string),
+            service: None,
+            question: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reconcile_requeues_blockers_that_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = QueueState::new(dir.path().join("queue.yaml"));
+        state
+            .update_queue(|queue| {
+                queue.tasks = vec![
+                    task("q-schema", "completed", None),
+                    task(
+                        "q-api",
+                        BLOCKED_STATE,
+                        Some(blocker(BlockerKind::WaitingOnTask, Some("q-schema"), None)),
+                    ),
+                    task(
+                        "q-pay",
+                        BLOCKED_STATE,
+                        Some(blocker(
+                            BlockerKind::MissingCredential,
+                            None,
+                            Some("STRIPE_KEY"),
+                        )),
+                    ),
+                    task(
+                        "q-ask",
+                        BLOCKED_STATE,
+                        Some(blocker(BlockerKind::AmbiguousRequirement, None, None)),
+                    ),
+                    task(
+                        "q-db",
+                        BLOCKED_STATE,
+                        Some(Blocker {
+                            service: Some("db".to_string()),
+                            ..blocker(BlockerKind::ServiceUnavailable, None, None)
+                        }),
+                    ),
+                ];
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            reconcile_blocked_with(&state, |_| false, |_| false)
+                .await
+                .unwrap(),
+            1
+        );
+        let queue = state.load().await.unwrap();
+        assert_eq!(queue.tasks[1].state, "pending");
+        assert!(queue.tasks[1].blocker.is_none());
+        assert!(
+            queue.tasks[1]
+                .task
+                .ends_with("q-schema, which this task was waiting on, has completed.")
+        );
+        assert_eq!(queue.tasks[2].state, BLOCKED_STATE);
+
+        assert_eq!(
+            reconcile_blocked_with(&state, |var| var == "STRIPE_KEY", |_| false)
+                .await
+                .unwrap(),
+            1
+        );
+        let queue = state.load().await.unwrap();
+        assert_eq!(queue.tasks[2].state, "pending");
+        assert_eq!(queue.tasks[3].state, BLOCKED_STATE);
+
+        assert_eq!(
+            reconcile_blocked_with(&state, |_| false, |service| service == "db")
+                .await
+                .unwrap(),
+            1
+        );
+        let queue = state.load().await.unwrap();
+        assert!(
+            queue.tasks[4]
+                .task
+                .ends_with("db, which was unavailable, is back up.")
+        );
+    }
+
+    #[test]
+    fn pick_human_prefers_the_blockers_capability() {
+        let mut humans = HashMap::new();
+        humans.insert("alice".to_string(), HumanAgentConfig::default());
+        humans.insert(
+            "bob".to_string(),
+            HumanAgentConfig {
+                capabilities: vec!["blockers".to_string()],
+                notify_command: None,
+            },
+        );
+        assert_eq!(
+            pick_human(&humans, BlockerKind::Other).as_deref(),
+            Some("bob")
+        );
+        humans.remove("bob");
+        assert_eq!(
+            pick_human(&humans, BlockerKind::Other).as_deref(),
+            Some("alice")
+        );
+        assert_eq!(pick_human(&HashMap::new(), BlockerKind::Other), None);
+    }
+}
@@ -0,0 +1,191 @@
+//! `ccswarm bundle`: export and import the swarm setup as one archive.
+
+use super::super::*;
+use crate::config::bundle::{self, Bundle, CONFIG_ENTRY};
+use crate::workflow::facets::ccswarm_home;
+
+impl CliRunner {
+    pub(crate) async fn handle_bundle(&self, action: &BundleAction) -> Result<()> {
+        match action {
+            BundleAction::Export {
+                output,
+                no_templates,
+            } => self.bundle_export(output, *no_templates).await,
+            BundleAction::Import {
+                file,
+                force,
+                dry_run,
+            } => self.bundle_import(file, *force, *dry_run).await,
+        }
+    }
+
+    async fn bundle_export(&self, output: &Path, no_templates: bool) -> Result<()> {
+        // The file as written, so settings this build doesn't model survive.
+        let config = match tokio::fs::read_to_string(&self.config_path).await {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Invalid JSON in {}", self.config_path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                serde_json::to_value(&self.config)?
+            }
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read {}", self.config_path.display()));
+            }
+        };
+        let home = if no_templates { None } else { ccswarm_home() };
+        let bundle = Bundle::collect(&self.repo_path, &config, home.as_deref())?;
+        tokio::fs::write(output, bundle.to_tar()?)
+            .await
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": { "output": output, "manifest": bundle.manifest },
+                }))?
+            );
+            return Ok(());
+        }
+        println!(
+            "{} wrote {} ({} files)",
+            "OK".bright_green().bold(),
+            output.display().to_string().bright_cyan(),
+            bundle.manifest.files.len()
+        );
+        for file in &bundle.manifest.files {
+            println!("   {}", file);
+        }
+        print_omitted(&bundle.manifest.omitted, "Left out");
+        Ok(())
+    }
+
+    async fn bundle_import(&self, file: &Path, force: bool, dry_run: bool) -> Result<()> {
+        let bytes = tokio::fs::read(file)
+            .await
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let bundle = Bundle::from_tar(&bytes)?;
+        let home = ccswarm_home();
+
+        // (destination, contents, already there and identical)
+        let mut plan = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut skipped = Vec::new();
+        for (name, data) in &bundle.entries {
+            let Some(dest) =
+                bundle::destination(name, &self.repo_path, &self.config_path, home.as_deref())
+            else {
+                skipped.push(name.clone());
+                continue;
+            };
+            let data = if name == CONFIG_ENTRY {
+                let mut config: serde_json::Value = serde_json::from_slice(data)
+                    .context("The bundle's ccswarm.json is not valid JSON")?;
+                bundle::localize(&mut config, &self.repo_path);
+                serde_json::from_value::<CcswarmConfig>(config.clone())
+                    .context("The bundle's ccswarm.json is not a valid configuration")?
+                    .validate()?;
+                serde_json::to_vec_pretty(&config)?
+            } else {
+                data.clone()
+            };
+            let unchanged = match tokio::fs::read(&dest).await {
+                Ok(existing) if existing == data => true,
+                Ok(_) => {
+                    conflicts.push(dest.display().to_string());
+                    false
+                }
+                Err(_) => false,
+            };
+            plan.push((dest, data, unchanged));
+        }
+        if !conflicts.is_empty() && !force && !dry_run {
+            anyhow::bail!(
+                "{} file(s) differ from the bundle; rerun with --force to overwrite them:\n  {}",
+                conflicts.len(),
+                conflicts.join("\n  ")
+            );
+        }
+
+        let mut written = Vec::new();
+        for (dest, data, unchanged) in &plan {
+            if *unchanged {
+                continue;
+            }
+            if !dry_run {
+                if let Some(parent) = dest.parent()
+                    && !parent.as_os_str().is_empty()
+                {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(dest, data)
+                    .await
+                    .with_context(|| format!("Failed to write {}", dest.display()))?;
+            }
+            written.push(dest);
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": {
+                        "dry_run": dry_run,
+                        "project": bundle.manifest.project,
+                        "written": written,
+                        "conflicts": conflicts,
+                        "skipped": skipped,
+                        "omitted": bundle.manifest.omitted,
+                    },
+                }))?
+            );
+            return Ok(());
+        }
+        let verb = if dry_run { "would write" } else { "wrote" };
+        println!(
+            "{} {} {} of {} files from '{}'",
+            "OK".bright_green().bold(),
+            verb,
+            written.len(),
+            plan.len(),
+            bundle.manifest.project
+        );
+        for dest in &written {
+            let overwrite = conflicts.contains(&dest.display().to_string());
+            println!(
+                "   {}{}",
+                dest.display(),
+                if overwrite {
+                    " (overwritten)".bright_yellow().to_string()
+                } else {
+                    String::new()
+                }
+            );
+        }
+        if !skipped.is_empty() {
+            println!(
+                "   {} no ccswarm home directory for: {}",
+                "skipped".bright_yellow(),
+                skipped.join(", ")
+            );
+        }
+        print_omitted(&bundle.manifest.omitted, "Fill in");
+        Ok(())
+    }
+}
+
+fn print_omitted(omitted: &[String], heading: &str) {
+    if omitted.is_empty() {
+        return;
+    }
+    println!(
+        "{} {} secret(s) or credential file(s):",
+        heading.bright_yellow(),
+        omitted.len()
+    );
+    for item in omitted {
+        println!("   {}", item);
+    }
+}
@@ -0,0 +1,844 @@
+//! `ccswarm chat`: talk to the master orchestrator about project state.
+//!
+//! Every message goes to the provider together with a snapshot of
+//! `.ccswarm/queue.yaml`, recent run summaries, the proactive review's
+//! findings (stalled tasks, failed dependencies, idle agents) and the recent
+//! conversation. The master answers in prose and may end its reply with an
+//! `actions` block proposing queue changes: new tasks, priorities, flows,
+//! releasing a stuck task or holding one for a human. Proposals are applied
+//! once the operator confirms them and are journaled to
+//! `.ccswarm/decisions.ndjson` like the proactive review's.
+//!
+//! The conversation is kept in `.ccswarm/master/chat.ndjson`, so a later
+//! `ccswarm chat` continues where the last one stopped.
+
+use super::super::*;
+use super::proactive::{self, DECISIONS_LOG};
+use super::queue_state::{ClaimState, QUEUE_FILE, QueueFile, QueueState, QueueTask};
+use super::task_dedup::find_duplicates;
+use super::workflow::AskOptions;
+use crate::agent::Priority;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+pub(crate) const CHAT_LOG: &str = ".ccswarm/master/chat.ndjson";
+/// Earlier turns replayed into each prompt.
+const HISTORY_TURNS: usize = 20;
+/// Queue tasks listed in the snapshot.
+const SNAPSHOT_TASKS: usize = 40;
+/// Run summaries listed in the snapshot.
+const SNAPSHOT_RUNS: usize = 10;
+/// Label carried by every task the chat queues.
+const CHAT_LABEL: &str = "chat";
+
+/// A queue change the master proposed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum MasterAction {
+    CreateTask {
+        task: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        flow: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        priority: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        depends_on: Vec<String>,
+    },
+    SetPriority {
+        id: String,
+        priority: String,
+    },
+    /// Route a pending task through a different flow.
+    SetFlow {
+        id: String,
+        flow: String,
+    },
+    /// Put a stuck running task back in the queue.
+    Release {
+        id: String,
+        reason: String,
+    },
+    /// Hold a task for a registered human instead of an agent.
+    AssignHuman {
+        id: String,
+        human: String,
+    },
+}
+
+impl std::fmt::Display for MasterAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CreateTask {
+                task,
+                flow,
+                priority,
+                depends_on,
+            } => {
+                write!(f, "queue \"{}\"", first_line(task, 70))?;
+                if let Some(flow) = flow {
+                    write!(f, " on flow {flow}")?;
+                }
+                if let Some(priority) = priority {
+                    write!(f, " at {priority} priority")?;
+                }
+                if !depends_on.is_empty() {
+                    write!(f, " after {}", depends_on.join(", "))?;
+                }
+                Ok(())
+            }
+            Self::SetPriority { id, priority } => write!(f, "set {id} to {priority} priority"),
+            Self::SetFlow { id, flow } => write!(f, "run {id} through flow {flow}"),
+            Self::Release { id, reason } => write!(f, "release {id} ({reason})"),
+            Self::AssignHuman { id, human } => write!(f, "hold {id} for {human}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Speaker {
+    Operator,
+    Master,
+}
+
+/// One message of the persisted conversation.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct ChatTurn {
+    pub(crate) ts: DateTime<Utc>,
+    pub(crate) speaker: Speaker,
+    pub(crate) text: String,
+    /// Actions the master proposed in this turn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) actions: Vec<MasterAction>,
+    /// What happened to each proposed action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) outcomes: Vec<String>,
+}
+
+impl ChatTurn {
+    fn new(speaker: Speaker, text: impl Into<String>) -> Self {
+        Self {
+            ts: Utc::now(),
+            speaker,
+            text: text.into(),
+            actions: Vec::new(),
+            outcomes: Vec::new(),
+        }
+    }
+}
+
+/// The conversation so far; unreadable lines are skipped.
+pub(crate) fn load_history(repo_path: &Path) -> Vec<ChatTurn> {
+    std::fs::read_to_string(repo_path.join(CHAT_LOG))
+        .map(|raw| {
+            raw.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn append_history(repo_path: &Path, turns: &[ChatTurn]) -> Result<()> {
+    let path = repo_path.join(CHAT_LOG);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut body = String::new();
+    for turn in turns {
+        body.push_str(&serde_json::to_string(turn)?);
+        body.push('\n');
+    }
+    use std::io::Write;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?
+        .write_all(body.as_bytes())?;
+    Ok(())
+}
+
+/// Split the master's reply into its prose and the actions in its
+/// ```` ```actions ```` block. A block that does not parse is reported as an
+/// error and no actions are taken from it.
+pub(crate) fn parse_reply(raw: &str) -> (String, Result<Vec<MasterAction>>) {
+    let Some(start) = raw.find("```actions") else {
+        return (raw.trim().to_string(), Ok(Vec::new()));
+    };
+    let body_start = raw[start..]
+        .find('\n')
+        .map(|i| start + i + 1)
+        .unwrap_or(raw.len());
+    let body_end = raw[body_start..]
+        .find("```")
+        .map(|i| body_start + i)
+        .unwrap_or(raw.len());
+    let after = (body_end + 3).min(raw.len());
+    let text = format!("{}{}", &raw[..start], &raw[after..])
+        .trim()
+        .to_string();
+    let actions = serde_json::from_str::<Vec<MasterAction>>(raw[body_start..body_end].trim())
+        .map_err(|e| anyhow!("could not read the proposed actions: {e}"));
+    (text, actions)
+}
+
+/// Flows a task can be routed through: the built-in ones and
+/// `.ccswarm/flows/*.yaml`.
+fn flow_names(repo_path: &Path) -> Vec<String> {
+    let mut names: Vec<String> = crate::workflow::flow::builtin_flows()
+        .into_iter()
+        .map(|flow| flow.name)
+        .collect();
+    if let Ok(entries) = std::fs::read_dir(repo_path.join(".ccswarm").join("flows")) {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_some_and(|ext| ext == "yaml")
+                && let Some(stem) = path.file_stem()
+            {
+                names.push(stem.to_string_lossy().to_string());
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn first_line(text: &str, max: usize) -> String {
+    let line = text.lines().next().unwrap_or("").trim();
+    if line.chars().count() > max {
+        format!("{}…", line.chars().take(max).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// What the master knows about the project when it answers.
+pub(crate) struct Snapshot<'a> {
+    pub(crate) queue: &'a QueueFile,
+    pub(crate) runs: &'a [crate::events::RunSummary],
+    pub(crate) review: &'a proactive::ProactiveReview,
+    pub(crate) agents: &'a [String],
+    pub(crate) humans: &'a [String],
+    pub(crate) flows: &'a [String],
+    pub(crate) now: DateTime<Utc>,
+}
+
+impl Snapshot<'_> {
+    fn render(&self) -> String {
+        let mut out = format!(
+            "## Project state ({})\n\n### Queue\n",
+            self.now.to_rfc3339()
+        );
+        // Open work first, then the most recently finished.
+        let mut tasks: Vec<&QueueTask> = self
+            .queue
+            .tasks
+            .iter()
+            .filter(|t| !matches!(t.state.as_str(), "completed" | "failed"))
+            .collect();
+        let mut done: Vec<&QueueTask> = self
+            .queue
+            .tasks
+            .iter()
+            .filter(|t| matches!(t.state.as_str(), "completed" | "failed"))
+            .collect();
+        done.sort_by_key(|t| std::cmp::Reverse(t.completed_at.unwrap_or(t.created_at)));
+        tasks.extend(done);
+        if tasks.is_empty() {
+            out.push_str("(empty)\n");
+        }
+        for task in tasks.into_iter().take(SNAPSHOT_TASKS) {
+            out.push_str(&format!(
+                "- {} [{}, {} priority, flow {}] {}",
+                task.id,
+                task.state,
+                format!("{:?}", task.priority.unwrap_or(Priority::Medium)).to_lowercase(),
+                task.flow.as_deref().unwrap_or("default"),
+                first_line(&task.task, 100)
+            ));
+            if let ClaimState::Running {
+                run_id,
+                started_at_ms,
+            } = &task.claim
+                && let Some(started) = DateTime::from_timestamp_millis(*started_at_ms as i64)
+            {
+                out.push_str(&format!(
+                    " — running {}m as run {}",
+                    (self.now - started).num_minutes(),
+                    run_id
+                ));
+            }
+            if !task.depends_on.is_empty() {
+                out.push_str(&format!(" — after {}", task.depends_on.join(", ")));
+            }
+            out.push('\n');
+            if let Some(error) = task.attempts.iter().rev().find_map(|a| a.error.as_deref()) {
+                out.push_str(&format!("  last error: {}\n", first_line(error, 200)));
+            }
+        }
+
+        out.push_str("\n### Findings\n");
+        if self.review.decisions.is_empty() {
+            out.push_str("(nothing blocked)\n");
+        }
+        for decision in &self.review.decisions {
+            out.push_str(&format!(
+                "- {} {}: {}\n",
+                decision.kind, decision.subject, decision.reason
+            ));
+        }
+
+        out.push_str("\n### Recent runs\n");
+        let mut runs: Vec<_> = self.runs.iter().collect();
+        runs.sort_by_key(|run| std::cmp::Reverse(run.started_at));
+        if runs.is_empty() {
+            out.push_str("(none)\n");
+        }
+        for run in runs.into_iter().take(SNAPSHOT_RUNS) {
+            out.push_str(&format!(
+                "- {} started {}{}, {} task(s) done, {} provider error(s), agents: {}\n",
+                run.run_id,
+                run.started_at.to_rfc3339(),
+                if run.ended_at.is_some() {
+                    ""
+                } else {
+                    " (unfinished)"
+                },
+                run.tasks_completed,
+                run.tasks_failed,
+                if run.agents_used.is_empty() {
+                    "-".to_string()
+                } else {
+                    run.agents_used.join(", ")
+                }
+            ));
+        }
+
+        let list = |items: &[String]| {
+            if items.is_empty() {
+                "(none)".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+        out.push_str(&format!(
+            "\n### Agents: {}\n### Humans: {}\n### Flows: {}\n",
+            list(self.agents),
+            list(self.humans),
+            list(self.flows)
+        ));
+        out
+    }
+}
+
+/// The prompt for one operator message.
+pub(crate) fn chat_prompt(
+    project: &str,
+    snapshot: &Snapshot<'_>,
+    history: &[ChatTurn],
+    message: &str,
+) -> String {
+    let mut prompt = format!(
+        "You are the master orchestrator of the ccswarm project \"{project}\". You \
+coordinate specialist agents through the task queue; you do not write code \
+yourself. Answer the operator's questions about the project from the state \
+below, reading the repository when that helps. Be concise and specific: name \
+task IDs, runs and errors.\n\n\
+When the operator asks you to change the work, end your reply with a fenced \
+block tagged `actions` holding a JSON array of changes:\n\
+- {{\"action\":\"create_task\",\"task\":\"...\",\"flow\":\"default\",\"priority\":\"high\",\"depends_on\":[\"q-...\"]}}\n\
+- {{\"action\":\"set_priority\",\"id\":\"q-...\",\"priority\":\"low|medium|high|critical\"}}\n\
+- {{\"action\":\"set_flow\",\"id\":\"q-...\",\"flow\":\"<flow>\"}} (pending tasks only)\n\
+- {{\"action\":\"release\",\"id\":\"q-...\",\"reason\":\"...\"}} (puts a stuck running task back)\n\
+- {{\"action\":\"assign_human\",\"id\":\"q-...\",\"human\":\"<human>\"}}\n\
+Only propose changes the operator asked for or agreed to; otherwise leave the \
+block out. The operator confirms them before they are applied.\n\n{}",
+        snapshot.render()
+    );
+    let recent = &history[history.len().saturating_sub(HISTORY_TURNS)..];
+    if !recent.is_empty() {
+        prompt.push_str("\n## Conversation so far\n");
+        for turn in recent {
+            let who = match turn.speaker {
+                Speaker::Operator => "Operator",
+                Speaker::Master => "Master",
+            };
+            prompt.push_str(&format!("{who}: {}\n", turn.text));
+            for outcome in &turn.outcomes {
+                prompt.push_str(&format!("  (applied: {outcome})\n"));
+            }
+        }
+    }
+    prompt.push_str(&format!("\n## Operator\n{message}\n"));
+    prompt
+}
+
+/// Apply a queue-only action to `queue`. Releases and human assignments go
+/// through their own paths (see [`CliRunner::apply_master_action`]).
+fn apply_to_queue(
+    queue: &mut QueueFile,
+    action: &MasterAction,
+    flows: &[String],
+) -> Result<String> {
+    let check_flow = |flow: &str| {
+        if flows.iter().any(|f| f == flow) {
+            Ok(())
+        } else {
+            Err(anyhow!("unknown flow '{flow}'"))
+        }
+    };
+    match action {
+        MasterAction::CreateTask {
+            task,
+            flow,
+            priority,
+            depends_on,
+        } => {
+            if task.trim().is_empty() {
+                return Err(anyhow!("the task is empty"));
+            }
+            if let Some(flow) = flow {
+                check_flow(flow)?;
+            }
+            let priority = priority
+                .as_deref()
+                .map(str::parse::<Priority>)
+                .transpose()?;
+            for dep in depends_on {
+                if !queue.tasks.iter().any(|t| &t.id == dep) {
+                    return Err(anyhow!("no queue task {dep}"));
+                }
+            }
+            if let Some(open) = find_duplicates(queue, task)
+                .into_iter()
+                .find(|m| m.blocks())
+            {
+                return Err(anyhow!("already covered by {}", open.describe()));
+            }
+            let id = format!("q-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+            queue.tasks.push(QueueTask {
+                id: id.clone(),
+                task: task.clone(),
+                flow: flow.clone(),
+                state: "pending".to_string(),
+                created_at: Utc::now(),
+                completed_at: None,
+                run_id: None,
+                claim: ClaimState::default(),
+                attempts: Vec::new(),
+                time_box: None,
+                command_timeout: None,
+                model: None,
+                priority,
+                labels: vec![CHAT_LABEL.to_string()],
+                depends_on: depends_on.clone(),
+                blocker: None,
+                acceptance: None,
+            });
+            Ok(format!("queued {id}"))
+        }
+        MasterAction::SetPriority { id, priority } => {
+            let priority: Priority = priority.parse()?;
+            let task = find(queue, id)?;
+            task.priority = Some(priority);
+            Ok(format!(
+                "{id} is now {} priority",
+                format!("{priority:?}").to_lowercase()
+            ))
+        }
+        MasterAction::SetFlow { id, flow } => {
+            check_flow(flow)?;
+            let task = find(queue, id)?;
+            if task.state != "pending" {
+                return Err(anyhow!(
+                    "{id} is {}; only pending tasks can change flow",
+                    task.state
+                ));
+            }
+            task.flow = Some(flow.clone());
+            Ok(format!("{id} will run through {flow}"))
+        }
+        MasterAction::Release { .. } | MasterAction::AssignHuman { .. } => {
+            Err(anyhow!("not a queue edit"))
+        }
+    }
+}
+
+fn find<'a>(queue: &'a mut QueueFile, id: &str) -> Result<&'a mut QueueTask> {
+    queue
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or_else(|| anyhow!("no queue task {id}"))
+}
+
+/// Append the applied actions to the decision journal.
+fn journal(repo_path: &Path, actions: &[(MasterAction, String)]) -> Result<()> {
+    if actions.is_empty() {
+        return Ok(());
+    }
+    let path = repo_path.join(DECISIONS_LOG);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let ts = Utc::now().to_rfc3339();
+    let mut body = String::new();
+    for (action, outcome) in actions {
+        let mut line = serde_json::to_value(action)?;
+        line["outcome"] = serde_json::Value::String(outcome.clone());
+        line["ts"] = serde_json::Value::String(ts.clone());
+        line["source"] = serde_json::Value::String("chat".to_string());
+        body.push_str(&line.to_string());
+        body.push('\n');
+    }
+    use std::io::Write;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?
+        .write_all(body.as_bytes())?;
+    Ok(())
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_chat(&self, message: Option<&str>, yes: bool) -> Result<()> {
+        if let Some(message) = message {
+            return self.chat_once(message, yes).await;
+        }
+
+        println!(
+            "{}",
+            "◆ ccswarm chat with the master".bright_magenta().bold()
+        );
+        let history = load_history(&self.repo_path);
+        if !history.is_empty() {
+            println!(
+                "   continuing a conversation of {} message(s); /clear starts over",
+                history.len()
+            );
+        }
+        println!(
+            "   Commands: {} {} {}",
+            "/history".bright_green(),
+            "/clear".bright_green(),
+            "/quit".bright_red()
+        );
+        println!();
+
+        loop {
+            print!("{}", "you> ".bright_yellow());
+            std::io::stdout().flush()?;
+            let mut input = String::new();
+            if std::io::stdin().read_line(&mut input)? == 0 {
+                break;
+            }
+            let input = input.trim();
+            match input {
+                "" => continue,
+                "/quit" | "/exit" => break,
+                "/history" => {
+                    for turn in load_history(&self.repo_path) {
+                        print_turn(&turn);
+                    }
+                    continue;
+                }
+                "/clear" => {
+                    self.archive_chat()?;
+                    println!("   conversation archived; the master starts fresh");
+                    continue;
+                }
+                _ => {}
+            }
+            // A failed call ends the turn, not the chat.
+            if let Err(e) = self.chat_turn(input, yes, true).await {
+                println!("{} {:#}", "✗".bright_red(), e);
+            }
+            println!();
+        }
+        Ok(())
+    }
+
+    /// `ccswarm chat --message`: one exchange, for scripts. Without `--yes`
+    /// proposed actions are only listed.
+    async fn chat_once(&self, message: &str, yes: bool) -> Result<()> {
+        let turn = self.chat_turn(message, yes, false).await?;
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": turn,
+                }))?
+            );
+        } else if !turn.actions.is_empty() && turn.outcomes.is_empty() {
+            println!("   rerun with --yes to apply");
+        }
+        Ok(())
+    }
+
+    /// Send one operator message, show the reply, apply confirmed actions
+    /// and persist both turns. Returns the master's turn.
+    async fn chat_turn(&self, message: &str, yes: bool, interactive: bool) -> Result<ChatTurn> {
+        let history = load_history(&self.repo_path);
+        let queue = QueueState::new(self.repo_path.join(QUEUE_FILE))
+            .load()
+            .await?;
+        let runs = proactive::load_run_summaries(&self.repo_path);
+        let mut agents: Vec<String> = self.config.agents.keys().cloned().collect();
+        agents.sort();
+        let mut humans: Vec<String> = self.config.humans.keys().cloned().collect();
+        humans.sort();
+        let flows = flow_names(&self.repo_path);
+        let now = Utc::now();
+        let review = proactive::review(&queue, &runs, &agents, now);
+        let snapshot = Snapshot {
+            queue: &queue,
+            runs: &runs,
+            review: &review,
+            agents: &agents,
+            humans: &humans,
+            flows: &flows,
+            now,
+        };
+        let prompt = chat_prompt(&self.config.project.name, &snapshot, &history, message);
+
+        let raw = self.ask_master(&prompt).await?;
+        let (text, actions) = parse_reply(&raw);
+        let mut reply = ChatTurn::new(Speaker::Master, text);
+        if !self.json_output {
+            print_turn(&reply);
+        }
+        match actions {
+            Ok(actions) => reply.actions = actions,
+            Err(e) => println!("   {} {}", "!".bright_yellow(), e),
+        }
+
+        if !reply.actions.is_empty() {
+            if !self.json_output {
+                println!("   {}", "Proposed:".bold());
+                for action in &reply.actions {
+                    println!("   • {}", action);
+                }
+            }
+            let apply = yes || (interactive && super::workflow::ask_yn("Apply these changes?"));
+            if apply {
+                let mut applied = Vec::new();
+                for action in &reply.actions {
+                    let outcome = match self.apply_master_action(action, &flows).await {
+                        Ok(outcome) => {
+                            applied.push((action.clone(), outcome.clone()));
+                            outcome
+                        }
+                        Err(e) => format!("not applied: {action}: {e:#}"),
+                    };
+                    if !self.json_output {
+                        println!("   {} {}", "→".bright_cyan(), outcome);
+                    }
+                    reply.outcomes.push(outcome);
+                }
+                journal(&self.repo_path, &applied)?;
+            }
+        }
+
+        append_history(
+            &self.repo_path,
+            &[ChatTurn::new(Speaker::Operator, message), reply.clone()],
+        )?;
+        Ok(reply)
+    }
+
+    async fn apply_master_action(&self, action: &MasterAction, flows: &[String]) -> Result<String> {
+        let path = self.repo_path.join(QUEUE_FILE);
+        let state = QueueState::new(path.clone());
+        match action {
+            MasterAction::Release { id, reason } => {
+                let running = state
+                    .load()
+                    .await?
+                    .tasks
+                    .iter()
+                    .find(|t| &t.id == id)
+                    .map(|t| t.state == "running")
+                    .ok_or_else(|| anyhow!("no queue task {id}"))?;
+                if !running {
+                    return Err(anyhow!("{id} is not running"));
+                }
+                state.release(id, reason).await?;
+                Ok(format!("released {id}"))
+            }
+            MasterAction::AssignHuman { id, human } => {
+                self.assign_queue_task(&path, id, human).await?;
+                Ok(format!("{id} is held for {human}"))
+            }
+            _ => {
+                let mut outcome = String::new();
+                state
+                    .update_queue(|queue| {
+                        outcome = apply_to_queue(queue, action, flows)?;
+                        Ok(())
+                    })
+                    .await?;
+                Ok(outcome)
+            }
+        }
+    }
+
+    /// One read-only provider call as the master.
+    async fn ask_master(&self, prompt: &str) -> Result<String> {
+        let result = self
+            .ask_provider(
+                "master",
+                prompt,
+                AskOptions {
+                    role: crate::identity::AgentRole::Master {
+                        oversight_roles: vec!["Orchestration".to_string()],
+                        quality_standards: Default::default(),
+                    },
+                    ..AskOptions::read_only(&self.repo_path)
+                },
+            )
+            .await?;
+        if !result.success || result.raw.trim().is_empty() {
+            return Err(anyhow!("the master gave no answer"));
+        }
+        Ok(result.raw)
+    }
+
+    /// Move the conversation aside so the next message starts fresh.
+    fn archive_chat(&self) -> Result<()> {
+        let path = self.repo_path.join(CHAT_LOG);
+        if path.exists() {
+            let archived = path.with_file_name(format!(
+                "chat-{}.ndjson",
+                Utc::now().format("%Y%m%dT%H%M%S")
+            ));
+            std::fs::rename(&path, archived)?;
+        }
+        Ok(())
+    }
+}
+
+fn print_turn(turn: &ChatTurn) {
+    let who = match turn.speaker {
+        Speaker::Operator => "you".bright_yellow().bold(),
+        Speaker::Master => "master".bright_magenta().bold(),
+    };
+    println!("{}> {}", who, turn.text);
+    for outcome in &turn.outcomes {
+        println!("   {} {}", "→".bright_cyan(), outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, state: &str) -> QueueTask {
+        QueueTask {
+            id: id.to_string(),
+            task: format!("task {id}"),
+            flow: None,
+            state: state.to_string(),
+            created_at: Utc::now(),
+            completed_at: None,
+            run_id: None,
+            claim: ClaimState::default(),
+            attempts: Vec::new(),
+            time_box: None,
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker: None,
+            acceptance: None,
+        }
+    }
+
+    #[test]
+    fn parse_reply_splits_prose_and_actions() {
+        let raw = "q-1 waits on q-0, which failed.\n\n```actions\n\
+            [{\"action\":\"set_priority\",\"id\":\"q-1\",\"priority\":\"high\"},\
+             {\"action\":\"create_task\",\"task\":\"Fix q-0\"}]\n```\n";
+        let (text, actions) = parse_reply(raw);
+        assert_eq!(text, "q-1 waits on q-0, which failed.");
+        let actions = actions.unwrap();
+        assert_eq!(
+            actions[0],
+            MasterAction::SetPriority {
+                id: "q-1".to_string(),
+                priority: "high".to_string()
+            }
+        );
+        assert_eq!(actions[1].to_string(), "queue \"Fix q-0\"");
+
+        let (text, actions) = parse_reply("All quiet.");
+        assert_eq!(text, "All quiet.");
+        assert!(actions.unwrap().is_empty());
+        assert!(parse_reply("```actions\nnot json\n```").1.is_err());
+    }
+
+    #[test]
+    fn queue_actions_validate_before_editing() {
+        let flows = vec!["default".to_string(), "review".to_string()];
+        let mut queue = QueueFile {
+            tasks: vec![task("q-1", "pending"), task("q-2", "running")],
+            ..Default::default()
+        };
+
+        let set_flow = |id: &str, flow: &str| MasterAction::SetFlow {
+            id: id.to_string(),
+            flow: flow.to_string(),
+        };
+        apply_to_queue(&mut queue, &set_flow("q-1", "review"), &flows).unwrap();
+        assert_eq!(queue.tasks[0].flow.as_deref(), Some("review"));
+        assert!(apply_to_queue(&mut queue, &set_flow("q-2", "review"), &flows).is_err());
+        assert!(apply_to_queue(&mut queue, &set_flow("q-1", "nope"), &flows).is_err());
+
+        let create = MasterAction::CreateTask {
+            task: "Add retries to the invoice client".to_string(),
+            flow: None,
+            priority: Some("critical".to_string()),
+            depends_on: vec!["q-1".to_string()],
+        };
+        let outcome = apply_to_queue(&mut queue, &create, &flows).unwrap();
+        let created = queue.tasks.last().unwrap();
+        assert_eq!(outcome, format!("queued {}", created.id));
+        assert_eq!(created.priority, Some(Priority::Critical));
+        assert_eq!(created.labels, vec![CHAT_LABEL.to_string()]);
+        // The same task again is a duplicate of the open one.
+        assert!(apply_to_queue(&mut queue, &create, &flows).is_err());
+    }
+
+    #[test]
+    fn prompt_carries_state_and_recent_conversation() {
+        let dir = tempfile::tempdir().unwrap();
+        let question = ChatTurn::new(Speaker::Operator, "why is q-2 stuck?");
+        let mut answer = ChatTurn::new(Speaker::Master, "It waits on q-1.");
+        answer.outcomes.push("q-1 is now high priority".to_string());
+        append_history(dir.path(), &[question, answer]).unwrap();
+        let history = load_history(dir.path());
+        assert_eq!(history.len(), 2);
+
+        let queue = QueueFile {
+            tasks: vec![task("q-1", "pending")],
+            ..Default::default()
+        };
+        let review = proactive::ProactiveReview::default();
+        let snapshot = Snapshot {
+            queue: &queue,
+            runs: &[],
+            review: &review,
+            agents: &["backend".to_string()],
+            humans: &[],
+            flows: &["default".to_string()],
+            now: Utc::now(),
+        };
+        let prompt = chat_prompt("shop", &snapshot, &history, "and now?");
+        assert!(prompt.contains("- q-1 [pending, medium priority, flow default] task q-1"));
+        assert!(prompt.contains("Operator: why is q-2 stuck?"));
+        assert!(prompt.contains("(applied: q-1 is now high priority)"));
+        assert!(prompt.ends_with("## Operator\nand now?\n"));
+    }
+}
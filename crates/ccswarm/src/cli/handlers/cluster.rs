@@ -0,0 +1,557 @@
+//! `ccswarm cluster`: share queued tasks with other ccswarm instances.
+//!
+//! `publish` moves pending tasks from `.ccswarm/queue.yaml` into a task pool
+//! (see [`ai_session::coordination::cluster`]) and marks them `assigned` with
+//! the `cluster` label, so the local drain leaves them alone. `work` claims
+//! pooled tasks under a lease and runs each through the pipeline in this
+//! instance's checkout, renewing the lease while it runs; a worker that dies
+//! stops renewing and the task goes back to the pool. `status` prints the
+//! pool and marks published tasks that finished there `completed` or `failed`
+//! locally.
+
+use super::super::*;
+use super::queue_state::{QUEUE_FILE, QueueFile, QueueState};
+use crate::governance::human::ASSIGNED_STATE;
+use ai_session::coordination::{
+    ClusterView, NodeStatus, PoolTask, PoolTaskState, TaskOutcome, TaskPool, open_pool,
+};
+use chrono::Utc;
+use std::time::Duration;
+use tracing::Instrument;
+
+/// Label carried by local tasks published to the pool.
+const CLUSTER_LABEL: &str = "cluster";
+
+/// Pool priority of a queue priority; higher runs first.
+fn pool_priority(priority: Option<Priority>) -> u8 {
+    match priority.unwrap_or(Priority::Medium) {
+        Priority::Low => 0,
+        Priority::Medium => 1,
+        Priority::High => 2,
+        Priority::Critical => 3,
+    }
+}
+
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Publish the local tasks `ids` (or every ready task with `all`) to `pool`
+/// and mark them assigned. Returns each task's id and whether the pool took
+/// it; a task the pool already held is left as it is locally.
+async fn publish_tasks(
+    state: &QueueState,
+    pool: &dyn TaskPool,
+    node: &str,
+    ids: &[String],
+    all: bool,
+) -> Result<Vec<(String, bool)>> {
+    let queue = state.load().await?;
+    let tasks = if all {
+        queue.ready_tasks()
+    } else {
+        ids.iter()
+            .map(|id| {
+                let task = queue
+                    .tasks
+                    .iter()
+                    .find(|task| task.id == *id)
+                    .with_context(|| format!("No queue task '{id}'"))?;
+                if task.state != "pending" {
+                    anyhow::bail!("Task '{id}' is {}, not pending", task.state);
+                }
+                Ok(task.clone())
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut published = Vec::new();
+    for task in tasks {
+        let added = pool
+            .publish(PoolTask {
+                id: task.id.clone(),
+                task: task.task.clone(),
+                flow: task.flow.clone(),
+                priority: pool_priority(task.priority),
+                labels: task.labels.clone(),
+                origin: node.to_string(),
+                published_at: Utc::now(),
+            })
+            .await?;
+        if added {
+            state
+                .update_task(&task.id, |task| {
+                    task.state = ASSIGNED_STATE.to_string();
+                    if !task.labels.iter().any(|label| label == CLUSTER_LABEL) {
+                        task.labels.push(CLUSTER_LABEL.to_string());
+                    }
+                    Ok(())
+                })
+                .await?;
+        }
+        published.push((task.id, added));
+    }
+    Ok(published)
+}
+
+/// Published local tasks the pool reports finished: id, success and run id.
+fn finished_in_pool(queue: &QueueFile, view: &ClusterView) -> Vec<(String, bool, Option<String>)> {
+    queue
+        .tasks
+        .iter()
+        .filter(|task| {
+            task.state == ASSIGNED_STATE && task.labels.iter().any(|label| label == CLUSTER_LABEL)
+        })
+        .filter_map(|task| {
+            let entry = view.tasks.iter().find(|entry| entry.task.id == task.id)?;
+            match &entry.state {
+                PoolTaskState::Done {
+                    success, run_id, ..
+                } => Some((task.id.clone(), *success, run_id.clone())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Mark published tasks that finished in the pool completed or failed.
+async fn reconcile_finished(state: &QueueState, view: &ClusterView) -> Result<Vec<String>> {
+    let finished = finished_in_pool(&state.load().await?, view);
+    let mut ids = Vec::new();
+    for (id, success, run_id) in finished {
+        state
+            .update_task(&id, |task| {
+                task.state = if success { "completed" } else { "failed" }.to_string();
+                task.completed_at = Some(Utc::now());
+                task.run_id = run_id;
+                Ok(())
+            })
+            .await?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+fn ago(at: chrono::DateTime<Utc>) -> String {
+    format!(
+        "{}s ago",
+        Utc::now().signed_duration_since(at).num_seconds().max(0)
+    )
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_cluster(
+        &self,
+        pool: Option<&str>,
+        node: Option<&str>,
+        action: &ClusterAction,
+    ) -> Result<()> {
+        let url = pool
+            .map(String::from)
+            .or_else(|| self.config.cluster.pool.clone())
+            .context("No task pool: pass --pool or set cluster.pool in ccswarm.json")?;
+        let pool = open_pool(&url)?;
+        let node = node
+            .map(String::from)
+            .or_else(|| self.config.cluster.node.clone())
+            .unwrap_or_else(|| format!("{}-{}", host_name(), std::process::id()));
+        let state = QueueState::new(self.repo_path.join(QUEUE_FILE));
+
+        match action {
+            ClusterAction::Publish { ids, all } => {
+                if ids.is_empty() && !all {
+                    anyhow::bail!("Name the tasks to publish or pass --all");
+                }
+                let published = publish_tasks(&state, pool.as_ref(), &node, ids, *all).await?;
+                if self.json_output {
+                    let report: Vec<_> = published
+                        .iter()
+                        .map(|(id, added)| serde_json::json!({ "id": id, "published": added }))
+                        .collect();
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else if published.is_empty() {
+                    println!("No pending tasks to publish.");
+                } else {
+                    for (id, added) in &published {
+                        if *added {
+                            println!("{} {} published", "→".bright_cyan(), id.bright_yellow());
+                        } else {
+                            println!("  {} already in the pool", id.bright_black());
+                        }
+                    }
+                }
+                Ok(())
+            }
+            ClusterAction::Work {
+                once,
+                max_tasks,
+                flow,
+                timeout,
+                create_pr,
+            } => {
+                self.cluster_work(
+                    pool.as_ref(),
+                    &node,
+                    *once,
+                    *max_tasks,
+                    flow.as_deref(),
+                    *timeout,
+                    *create_pr,
+                )
+                .await
+            }
+            ClusterAction::Status => self.cluster_status(pool.as_ref(), &state).await,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn cluster_work(
+        &self,
+        pool: &dyn TaskPool,
+        node: &str,
+        once: bool,
+        max_tasks: Option<usize>,
+        flow_override: Option<&str>,
+        timeout: u64,
+        create_pr: bool,
+    ) -> Result<()> {
+        let ttl = Duration::from_secs(self.config.cluster.lease_secs.max(30));
+        let poll = Duration::from_secs(self.config.cluster.poll_secs.max(1));
+        let mut status = NodeStatus {
+            node: node.to_string(),
+            host: host_name(),
+            current_task: None,
+            completed: 0,
+            failed: 0,
+            last_seen: Utc::now(),
+        };
+        println!("{} working as {}", "→".bright_cyan(), node.bright_white());
+
+        let mut ran = 0usize;
+        while max_tasks.is_none_or(|max| ran < max) {
+            status.current_task = None;
+            status.last_seen = Utc::now();
+            pool.heartbeat(&status).await?;
+
+            let Some(mut lease) = pool.claim(node, ttl).await? else {
+                if once {
+                    println!("Nothing left to claim.");
+                    break;
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(poll) => continue,
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+            };
+            status.current_task = Some(lease.task.id.clone());
+            pool.heartbeat(&status).await?;
+
+            let flow = flow_override
+                .map(String::from)
+                .or_else(|| lease.task.flow.clone())
+                .unwrap_or_else(|| "default".to_string());
+            let run_id = uuid::Uuid::new_v4().to_string();
+            let task_body = lease.task.task.clone();
+            println!();
+            println!(
+                "{} {} flow={} attempt={} (from {})",
+                "▶".bright_cyan().bold(),
+                lease.task.id.bright_yellow(),
+                flow.bright_white(),
+                lease.attempt,
+                lease.task.origin
+            );
+
+            let mut pipeline = Box::pin(
+                self.handle_pipeline_returning_reserved_id(
+                    &run_id, &task_body, &flow, "text", timeout, false, None, false, None,
+                    None, // run_budget_tokens
+                    None, true, create_pr, None,
+                )
+                .instrument(tracing::info_span!(
+                    "cluster.task",
+                    task_id = %lease.task.id,
+                    flow = %flow,
+                    run_id = %run_id,
+                )),
+            );
+            // Renew well before the lease runs out; a lost lease means another
+            // node may already be running the task, so this one stops.
+            let mut renew = tokio::time::interval(ttl / 3);
+            renew.tick().await;
+            let result = loop {
+                tokio::select! {
+                    result = &mut pipeline => break Some(result),
+                    _ = renew.tick() => match pool.renew(&mut lease, ttl).await {
+                        Ok(true) => {
+                            status.last_seen = Utc::now();
+                            if let Err(e) = pool.heartbeat(&status).await {
+                                warn!("Cluster heartbeat failed: {e}");
+                            }
+                        }
+                        Ok(false) => break None,
+                        Err(e) => warn!("Lease renewal for {} failed: {e}", lease.task.id),
+                    },
+                }
+            };
+            ran += 1;
+
+            let Some(result) = result else {
+                // Dropping the run kills its provider CLI, so this checkout
+                // stops changing while the new lease holder works the task.
+                drop(pipeline);
+                println!(
+                    "{} {} lease lost; abandoning the run",
+                    "✗".bright_red().bold(),
+                    lease.task.id.bright_yellow()
+                );
+                continue;
+            };
+            let outcome = match result {
+                Ok(run_id) => {
+                    status.completed += 1;
+                    println!(
+                        "{} {} completed (run {})",
+                        "✓".bright_green().bold(),
+                        lease.task.id.bright_yellow(),
+                        run_id.chars().take(8).collect::<String>().bright_black()
+                    );
+                    TaskOutcome {
+                        success: true,
+                        run_id: Some(run_id),
+                        error: None,
+                    }
+                }
+                Err(e) => {
+                    status.failed += 1;
+                    println!(
+                        "{} {} failed: {e}",
+                        "✗".bright_red().bold(),
+                        lease.task.id.bright_yellow()
+                    );
+                    TaskOutcome {
+                        success: false,
+                        run_id: Some(run_id.clone()),
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            if !pool.complete(&lease, outcome).await? {
+                warn!(
+                    "Lease on {} ran out before its result was recorded",
+                    lease.task.id
+                );
+            }
+        }
+
+        status.current_task = None;
+        status.last_seen = Utc::now();
+        pool.heartbeat(&status).await?;
+        println!(
+            "{} {} completed, {} failed",
+            "■".bright_cyan(),
+            status.completed,
+            status.failed
+        );
+        Ok(())
+    }
+
+    async fn cluster_status(&self, pool: &dyn TaskPool, state: &QueueState) -> Result<()> {
+        let view = pool.view().await?;
+        let reconciled = reconcile_finished(state, &view).await?;
+        let now = Utc::now();
+        let counts = view.counts(now);
+        let stale_after = Duration::from_secs(self.config.cluster.lease_secs.max(30));
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "counts": counts,
+                    "nodes": view.nodes,
+                    "tasks": view.tasks,
+                    "reconciled": reconciled,
+                }))?
+            );
+            return Ok(());
+        }
+
+        println!("{}", "Nodes".bright_white().bold());
+        if view.nodes.is_empty() {
+            println!("  (none yet)");
+        }
+        for node in &view.nodes {
+            let live = node.is_live(now, stale_after);
+            println!(
+                "  {} {} on {}: {} done, {} failed, {}{}",
+                if live {
+                    "●".bright_green()
+                } else {
+                    "○".bright_black()
+                },
+                node.node.bright_white(),
+                node.host,
+                node.completed,
+                node.failed,
+                node.current_task
+                    .as_deref()
+                    .map(|id| format!("running {id}, "))
+                    .unwrap_or_default(),
+                ago(node.last_seen)
+            );
+        }
+
+        println!();
+        println!(
+            "{} {} pending, {} leased, {} expired, {} succeeded, {} failed",
+            "Tasks".bright_white().bold(),
+            counts.pending,
+            counts.leased,
+            counts.expired,
+            counts.succeeded,
+            counts.failed
+        );
+        for entry in &view.tasks {
+            let state = match &entry.state {
+                PoolTaskState::Pending => "pending".bright_black().to_string(),
+                PoolTaskState::Leased {
+                    node, expires_at, ..
+                } if *expires_at <= now => format!("{} (was {node})", "expired".bright_red()),
+                PoolTaskState::Leased { node, .. } => format!("{} by {node}", "leased".cyan()),
+                PoolTaskState::Done {
+                    node,
+                    success: true,
+                    ..
+                } => format!("{} by {node}", "succeeded".bright_green()),
+                PoolTaskState::Done { node, error, .. } => format!(
+                    "{} by {node}{}",
+                    "failed".bright_red(),
+                    error
+                        .as_deref()
+                        .map(|e| format!(": {e}"))
+                        .unwrap_or_default()
+                ),
+            };
+            let summary: String = entry
+                .task
+                .task
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(60)
+                .collect();
+            println!(
+                "  {} {} {}{}",
+                entry.task.id.bright_yellow(),
+                state,
+                summary,
+                if entry.attempts > 1 {
+                    format!(" (attempt {})", entry.attempts)
+                } else {
+                    String::new()
+                }
+            );
+        }
+        if !reconciled.is_empty() {
+            println!();
+            println!(
+                "Marked {} finished in the local queue.",
+                reconciled.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::queue_state::{ClaimState, QueueTask};
+    use super::*;
+    use ai_session::coordination::DirTaskPool;
+
+    #[tokio::test]
+    async fn published_tasks_are_assigned_and_reconciled_when_the_pool_finishes_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = QueueState::new(dir.path().join(QUEUE_FILE));
+        state
+            .update_queue(|queue| {
+                for (id, priority) in [("q-1", Some(Priority::High)), ("q-2", None)] {
+                    queue.tasks.push(QueueTask {
+                        id: id.to_string(),
+                        task: format!("do {id}"),
+                        flow: None,
+                        state: "pending".to_string(),
+                        created_at: Utc::now(),
+                        completed_at: None,
+                        run_id: None,
+                        claim: ClaimState::default(),
+                        attempts: Vec::new(),
+                        time_box: None,
+                        command_timeout: None,
+                        model: None,
+                        priority,
+                        labels: Vec::new(),
+                        depends_on: Vec::new(),
+                        blocker: None,
+                        acceptance: None,
+                    });
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+        let pool = DirTaskPool::new(dir.path().join("pool"));
+
+        let published = publish_tasks(&state, &pool, "a", &[], true).await.unwrap();
+        assert_eq!(
+            published,
+            vec![("q-1".to_string(), true), ("q-2".to_string(), true)]
+        );
+        assert!(
+            publish_tasks(&state, &pool, "a", &["q-1".to_string()], false)
+                .await
+                .is_err()
+        );
+        let queue = state.load().await.unwrap();
+        assert!(
+            queue
+                .tasks
+                .iter()
+                .all(|t| t.state == ASSIGNED_STATE && t.labels == vec![CLUSTER_LABEL.to_string()])
+        );
+
+        let lease = pool
+            .claim("b", Duration::from_secs(60))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lease.task.id, "q-1");
+        pool.complete(
+            &lease,
+            TaskOutcome {
+                success: true,
+                run_id: Some("run-b".to_string()),
+                error: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let view = pool.view().await.unwrap();
+        assert_eq!(
+            reconcile_finished(&state, &view).await.unwrap(),
+            vec!["q-1"]
+        );
+        let queue = state.load().await.unwrap();
+        assert_eq!(queue.tasks[0].state, "completed");
+        assert_eq!(queue.tasks[0].run_id.as_deref(), Some("run-b"));
+        assert_eq!(queue.tasks[1].state, ASSIGNED_STATE);
+        assert!(reconcile_finished(&state, &view).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+//! `ccswarm completions` and `ccswarm man`: generated from the clap definition,
+//! so every nested subcommand and flag is covered without a hand-kept list.
+
+use super::super::*;
+use clap::CommandFactory;
+
+impl CliRunner {
+    pub(crate) async fn handle_completions(&self, shell: clap_complete::Shell) -> Result<()> {
+        let mut cmd = Cli::command();
+        clap_complete::generate(shell, &mut cmd, "ccswarm", &mut std::io::stdout());
+        Ok(())
+    }
+
+    pub(crate) async fn handle_man(&self, out_dir: Option<&Path>) -> Result<()> {
+        let cmd = Cli::command();
+        let Some(dir) = out_dir else {
+            clap_mangen::Man::new(cmd)
+                .render(&mut std::io::stdout())
+                .context("Failed to render man page")?;
+            return Ok(());
+        };
+
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let pages = write_man_pages(cmd, dir)
+            .with_context(|| format!("Failed to write man pages to {}", dir.display()))?
+            .len();
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "out_dir": dir,
+                    "pages": pages,
+                }))?
+            );
+        } else {
+            println!(
+                "{} wrote {} man page(s) to {}",
+                "OK".bright_green().bold(),
+                pages,
+                dir.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// One page per visible command, like `clap_mangen::generate_to`, but
+/// returning the pages written so files already in `dir` are not counted.
+fn write_man_pages(cmd: clap::Command, dir: &Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    fn write(
+        cmd: clap::Command,
+        dir: &Path,
+        pages: &mut Vec<std::path::PathBuf>,
+    ) -> std::io::Result<()> {
+        for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()).cloned() {
+            write(sub, dir, pages)?;
+        }
+        pages.push(clap_mangen::Man::new(cmd).generate_to(dir)?);
+        Ok(())
+    }
+
+    let mut cmd = cmd.disable_help_subcommand(true);
+    cmd.build();
+    let mut pages = Vec::new();
+    write(cmd, dir, &mut pages)?;
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completions_cover_nested_subcommands() {
+        let mut cmd = Cli::command();
+        let mut out = Vec::new();
+        clap_complete::generate(clap_complete::Shell::Bash, &mut cmd, "ccswarm", &mut out);
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("drain"));
+        assert!(script.contains("--reconcile-only"));
+    }
+
+    #[test]
+    fn test_man_pages_written_per_subcommand() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("unrelated.1"), "")?;
+        let pages = write_man_pages(Cli::command(), dir.path())?;
+        assert!(dir.path().join("ccswarm.1").exists());
+        assert!(dir.path().join("ccswarm-queue-drain.1").exists());
+        assert!(!pages.iter().any(|p| p.ends_with("unrelated.1")));
+        let on_disk = std::fs::read_dir(dir.path())?.count();
+        assert_eq!(pages.len(), on_disk - 1);
+        Ok(())
+    }
+}
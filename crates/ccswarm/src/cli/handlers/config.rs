@@ -82,7 +82,7 @@ impl CliRunner {
                     }
                 };
 
-                let config: CcswarmConfig = match serde_json::from_str(&raw_contents) {
+                let config = match CcswarmConfig::from_json(&raw_contents, file).await {
                     Ok(cfg) => cfg,
                     Err(e) => {
                         if self.json_output {
@@ -90,19 +90,19 @@ impl CliRunner {
                                 "{}",
                                 serde_json::to_string_pretty(&serde_json::json!({
                                     "status": "error",
-                                    "message": "Configuration file is not valid JSON",
+                                    "message": "Configuration file could not be loaded",
                                     "file": file,
-                                    "error": e.to_string(),
+                                    "error": format!("{e:#}"),
                                 }))?
                             );
                         } else {
                             println!(
-                                "❌ Configuration {} is not valid JSON: {}",
+                                "❌ Configuration {} could not be loaded: {:#}",
                                 file.display(),
                                 e
                             );
                         }
-                        return Err(e.into());
+                        return Err(e);
                     }
                 };
 
@@ -139,6 +139,9 @@ impl CliRunner {
                                 println!("{}", serde_json::to_string_pretty(agent_cfg)?);
                             } else {
                                 println!("📄 Agent configuration: {}", agent_name);
+                                if let Some(preset) = &agent_cfg.preset {
+                                    println!("  Preset: {}", preset);
+                                }
                                 println!("  Specialization: {}", agent_cfg.specialization);
                                 println!("  Worktree: {}", agent_cfg.worktree);
                                 println!("  Branch: {}", agent_cfg.branch);
@@ -202,21 +205,103 @@ impl CliRunner {
                     println!("Agents ({}):", config.agents.len());
                     for (name, agent) in &config.agents {
                         println!(
-                            "  - {} [{}] -> {}",
-                            name, agent.specialization, agent.worktree
+                            "  - {} [{}] -> {}{}",
+                            name,
+                            agent.specialization,
+                            agent.worktree,
+                            agent
+                                .preset
+                                .as_ref()
+                                .map(|p| format!(" (preset {p})"))
+                                .unwrap_or_default()
                         );
                     }
                     println!(
-                        "Coordination: method={}, sync={}s, quality_gate={}, master_review={}",
+                        "Coordination: method={}, watch={}, sync={}s, quality_gate={}, master_review={}",
                         config.coordination.communication_method,
+                        config.coordination.watch,
                         config.coordination.sync_interval,
                         config.coordination.quality_gate_frequency,
                         config.coordination.master_review_trigger
                     );
+                    let scheduling = &config.coordination.scheduling;
+                    println!(
+                        "Scheduling: aging={}s, round_robin={}, max_queue_depth={}",
+                        scheduling.aging_secs,
+                        scheduling.round_robin,
+                        if scheduling.max_queue_depth == 0 {
+                            "unlimited".to_string()
+                        } else {
+                            scheduling.max_queue_depth.to_string()
+                        }
+                    );
                 }
             }
+            ConfigAction::Presets { file, refresh } => {
+                self.handle_config_presets(file, *refresh).await?;
+            }
         }
 
         Ok(())
     }
+
+    async fn handle_config_presets(&self, file: &Path, refresh: bool) -> Result<()> {
+        use crate::config::presets::{PresetRef, PresetResolver};
+
+        let contents = tokio::fs::read_to_string(file)
+            .await
+            .with_context(|| format!("Failed to read configuration {}", file.display()))?;
+        let mut raw: serde_json::Value = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid JSON in {}", file.display()))?;
+        let project_dir = file
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let resolved = PresetResolver::new(project_dir)
+            .refreshing(refresh)
+            .expand(&mut raw)
+            .await?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "file": file,
+                    "presets": resolved,
+                }))?
+            );
+            return Ok(());
+        }
+
+        if resolved.is_empty() {
+            println!("No agents in {} use a preset.", file.display());
+            return Ok(());
+        }
+        println!("📦 Agent presets ({}):", file.display());
+        for preset in &resolved {
+            let pinned = PresetRef::parse(&preset.reference)
+                .map(|r| r.is_pinned())
+                .unwrap_or(false);
+            println!(
+                "  - {} ← {}{}",
+                preset.agent.bright_cyan(),
+                preset.reference,
+                if pinned {
+                    String::new()
+                } else {
+                    " (unpinned)".bright_yellow().to_string()
+                }
+            );
+            println!("      {}", preset.path.display().to_string().bright_black());
+            if let Some(checkout) = &preset.checkout {
+                println!(
+                    "      {} fetched {}",
+                    checkout.commit.chars().take(12).collect::<String>(),
+                    checkout.fetched_at.format("%Y-%m-%d %H:%M UTC")
+                );
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,618 @@
+//! `ccswarm context seed`: turn repository docs into per-persona knowledge facets.
+//! `ccswarm context distill`: turn the swarm's history into DOCTRINE.md proposals.
+//! `ccswarm context search`: find passages of past sessions by meaning.
+
+use super::super::*;
+use super::workflow::AskOptions;
+use crate::workflow::context_seed::{
+    discover_sources, excerpt_content, excerpts_for, seeded_facet, summary_prompt,
+};
+use crate::workflow::doctrine::{self, DistillState, Lesson, LessonKind, Verdict};
+use crate::workflow::facets::builtin_personas;
+use crate::workflow::sangha_tally::{Electorate, TallyMethod};
+
+impl CliRunner {
+    pub(crate) async fn handle_context(&self, action: &ContextAction) -> Result<()> {
+        match action {
+            ContextAction::Seed {
+                personas,
+                no_summarize,
+                dry_run,
+            } => self.context_seed(personas, *no_summarize, *dry_run).await,
+            ContextAction::Distill {
+                no_summarize,
+                dry_run,
+                watch: false,
+                ..
+            } => self.context_distill(*no_summarize, *dry_run).await,
+            ContextAction::Distill {
+                no_summarize,
+                dry_run,
+                watch: true,
+                interval_secs,
+            } => {
+                if *dry_run {
+                    anyhow::bail!("--dry-run cannot be combined with --watch");
+                }
+                let interval = std::time::Duration::from_secs((*interval_secs).max(60));
+                loop {
+                    if let Err(e) = self.context_distill(*no_summarize, false).await {
+                        warn!("context distill failed: {:#}", e);
+                    }
+                    tokio::time::sleep(interval).await;
+                }
+            }
+            ContextAction::Search {
+                query,
+                limit,
+                agent,
+                rebuild,
+            } => {
+                self.context_search(query, *limit, agent.as_deref(), *rebuild)
+                    .await
+            }
+        }
+    }
+
+    async fn context_search(
+        &self,
+        query: &str,
+        limit: usize,
+        agent: Option<&str>,
+        rebuild: bool,
+    ) -> Result<()> {
+        use crate::session::search;
+
+        let sessions_dir = self.repo_path.join(".ccswarm").join("sessions");
+        if rebuild {
+            match tokio::fs::remove_file(sessions_dir.join(search::INDEX_FILE)).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        let keyring = ai_session::persistence::Keyring::from_env()?;
+        let embedder = &self.config.context_search;
+        let (index, stats) = search::update(&sessions_dir, keyring.as_ref(), embedder).await?;
+        let query_embedding = embedder
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("The embedder returned no vector for the query"))?;
+        let hits = search::search(&index, &query_embedding, limit, agent);
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": { "index": stats, "hits": hits },
+                }))?
+            );
+            return Ok(());
+        }
+        println!(
+            "{} {} session(s), {} passage(s); embedded {} new",
+            "→".bright_cyan(),
+            stats.sessions,
+            stats.passages,
+            stats.embedded
+        );
+        if hits.is_empty() {
+            println!("No matching passages.");
+            return Ok(());
+        }
+        for hit in &hits {
+            println!(
+                "\n{} {} {} {} {}",
+                format!("{:.2}", hit.score).bright_green().bold(),
+                hit.agent.as_deref().unwrap_or("-").bright_cyan(),
+                format!("{:?}", hit.role).to_lowercase(),
+                hit.timestamp.format("%Y-%m-%d %H:%M"),
+                hit.session[..8.min(hit.session.len())].bright_black()
+            );
+            let excerpt: String = hit.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if excerpt.chars().count() > 300 {
+                println!("  {}…", excerpt.chars().take(300).collect::<String>());
+            } else {
+                println!("  {excerpt}");
+            }
+        }
+        Ok(())
+    }
+
+    async fn context_seed(
+        &self,
+        personas: &[String],
+        no_summarize: bool,
+        dry_run: bool,
+    ) -> Result<()> {
+        let sources = discover_sources(&self.repo_path).await?;
+        if sources.is_empty() {
+            return Err(anyhow!(
+                "No README, CONTRIBUTING, ADRs or package manifests found in {}",
+                self.repo_path.display()
+            ));
+        }
+        if !self.json_output {
+            println!(
+                "{} scanned {} source(s): {}",
+                "→".bright_cyan(),
+                sources.len(),
+                sources
+                    .iter()
+                    .map(|s| s.path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        let builtins = builtin_personas();
+        let targets: Vec<(String, String)> = if personas.is_empty() {
+            builtins
+                .iter()
+                .map(|p| (p.name.clone(), p.role.clone()))
+                .collect()
+        } else {
+            personas
+                .iter()
+                .map(|name| {
+                    let role = builtins
+                        .iter()
+                        .find(|p| &p.name == name)
+                        .map(|p| p.role.clone())
+                        .unwrap_or_else(|| name.clone());
+                    (name.clone(), role)
+                })
+                .collect()
+        };
+
+        let knowledge_dir = self
+            .repo_path
+            .join(".ccswarm")
+            .join("facets")
+            .join("knowledge");
+        let mut seeded = Vec::new();
+
+        for (persona, role) in &targets {
+            let excerpts = excerpts_for(persona, &sources);
+            if excerpts.is_empty() {
+                if !self.json_output {
+                    println!(
+                        "  {} {} — nothing relevant",
+                        "-".bright_black(),
+                        persona.bright_white()
+                    );
+                }
+                continue;
+            }
+
+            let (content, summarized) = if no_summarize || dry_run {
+                (excerpt_content(&excerpts), false)
+            } else {
+                match self
+                    .summarize("context-seed", &summary_prompt(persona, role, &excerpts))
+                    .await
+                {
+                    Ok(summary) => (summary, true),
+                    Err(e) => {
+                        warn!("context seed: summarizing for '{}' failed: {}", persona, e);
+                        (excerpt_content(&excerpts), false)
+                    }
+                }
+            };
+
+            let facet = seeded_facet(persona, content, &excerpts);
+            let path = knowledge_dir.join(format!("{}.yaml", facet.name));
+            if !dry_run {
+                tokio::fs::create_dir_all(&knowledge_dir).await?;
+                tokio::fs::write(&path, serde_yml::to_string(&facet)?)
+                    .await
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+            if !self.json_output {
+                println!(
+                    "  {} {} ← {} ({})",
+                    if dry_run {
+                        "?".bright_yellow()
+                    } else {
+                        "✓".bright_green()
+                    },
+                    persona.bright_white(),
+                    facet.references.join(", "),
+                    if summarized { "summarized" } else { "excerpts" }
+                );
+            }
+            seeded.push(serde_json::json!({
+                "persona": persona,
+                "facet": facet.name,
+                "path": path,
+                "references": facet.references,
+                "summarized": summarized,
+            }));
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "dry_run": dry_run,
+                    "seeded": seeded,
+                }))?
+            );
+        } else if !dry_run {
+            println!();
+            println!(
+                "{} seeded {} persona(s) into {}",
+                "OK".bright_green().bold(),
+                seeded.len(),
+                knowledge_dir.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Settle doctrine proposals that have been voted on, then propose a new
+    /// draft from whatever happened since the last distillation.
+    async fn context_distill(&self, no_summarize: bool, dry_run: bool) -> Result<()> {
+        let proposals_dir = self.repo_path.join("coordination/proposals");
+        let proposals = read_proposals(&proposals_dir).await?;
+        let electorate =
+            Electorate::load(&self.repo_path, proposals.iter().map(|(_, p)| p)).await?;
+
+        let mut adopted = Vec::new();
+        let mut rejected = Vec::new();
+        let mut pending = None;
+        for (path, proposal) in &proposals {
+            if !is_doctrine(proposal) || proposal_str(proposal, "status") != "open" {
+                continue;
+            }
+            let id = proposal_str(proposal, "id");
+            match electorate.tally(proposal).verdict {
+                Verdict::Open => pending = Some(id.to_string()),
+                verdict => {
+                    let approved = verdict == Verdict::Approved;
+                    if !dry_run {
+                        self.settle_doctrine(path, proposal, approved).await?;
+                    }
+                    if approved {
+                        adopted.push(id.to_string());
+                    } else {
+                        rejected.push(id.to_string());
+                    }
+                }
+            }
+        }
+
+        let mut state = DistillState::load(&self.repo_path).await?;
+        let lessons = if pending.is_some() {
+            Vec::new()
+        } else {
+            self.mine_lessons(&proposals, state.last_distilled_at)
+                .await?
+        };
+
+        let mut proposed = None;
+        let mut draft = None;
+        if !lessons.is_empty() {
+            let current = tokio::fs::read_to_string(self.repo_path.join(doctrine::DOCTRINE_FILE))
+                .await
+                .ok()
+                .filter(|text| !text.trim().is_empty());
+            let fallback = || {
+                doctrine::draft(
+                    current.as_deref(),
+                    &lessons,
+                    chrono::Utc::now().date_naive(),
+                )
+            };
+            let text = if no_summarize || dry_run {
+                fallback()
+            } else {
+                match self
+                    .summarize(
+                        "doctrine",
+                        &doctrine::distill_prompt(current.as_deref(), &lessons),
+                    )
+                    .await
+                {
+                    Ok(text) => format!("{}\n", text.trim_end()),
+                    Err(e) => {
+                        warn!("context distill: provider draft failed: {}", e);
+                        fallback()
+                    }
+                }
+            };
+            if !dry_run {
+                proposed = Some(
+                    self.propose_doctrine(&proposals_dir, &lessons, &text)
+                        .await?,
+                );
+                state.last_distilled_at = Some(chrono::Utc::now());
+                state.save(&self.repo_path).await?;
+            }
+            draft = Some(text);
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "dry_run": dry_run,
+                    "data": {
+                        "adopted": adopted,
+                        "rejected": rejected,
+                        "pending": pending,
+                        "proposed": proposed,
+                        "lessons": lessons,
+                        "draft": if dry_run { draft } else { None },
+                    },
+                }))?
+            );
+            return Ok(());
+        }
+
+        for id in &adopted {
+            println!(
+                "{} adopted {} into {}",
+                "✓".bright_green(),
+                id.bright_cyan(),
+                doctrine::DOCTRINE_FILE
+            );
+        }
+        for id in &rejected {
+            println!("{} {} was rejected", "-".bright_black(), id.bright_cyan());
+        }
+        if let Some(id) = &pending {
+            println!(
+                "{} doctrine proposal {} is still open; vote with `ccswarm lab sangha vote {} --approve`",
+                "…".bright_yellow(),
+                id.bright_cyan(),
+                id
+            );
+        } else if lessons.is_empty() {
+            println!("Nothing new to distill.");
+        } else if let Some(id) = &proposed {
+            println!(
+                "{} proposed {} from {} lesson(s): {}",
+                "OK".bright_green().bold(),
+                doctrine::DOCTRINE_FILE,
+                lessons.len(),
+                id.bright_cyan()
+            );
+            println!(
+                "  Draft: {}",
+                doctrine::draft_path(&self.repo_path, id).display()
+            );
+            println!(
+                "  Vote:  ccswarm lab sangha vote {} --approve",
+                id.bright_yellow()
+            );
+        } else if let Some(text) = &draft {
+            print!("{}", text);
+        }
+        Ok(())
+    }
+
+    /// Everything since `since` that could shape the doctrine.
+    async fn mine_lessons(
+        &self,
+        proposals: &[(PathBuf, serde_json::Value)],
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<Lesson>> {
+        let is_new = |at: &chrono::DateTime<chrono::Utc>| since.is_none_or(|since| *at > since);
+        let mut lessons = Vec::new();
+
+        let queue =
+            super::queue_state::load_queue(&self.repo_path.join(super::queue_state::QUEUE_FILE))
+                .await?;
+        for task in queue.tasks.iter().filter(|t| t.state == "completed") {
+            let Some(at) = task.completed_at.filter(is_new) else {
+                continue;
+            };
+            let mut text: String = task.task.chars().take(200).collect();
+            if !task.labels.is_empty() {
+                text.push_str(&format!(" [{}]", task.labels.join(", ")));
+            }
+            lessons.push(Lesson {
+                kind: LessonKind::Task,
+                at,
+                source: format!("queue:{}", task.id),
+                text,
+            });
+        }
+
+        for record in crate::hitl::ApprovalStore::new(&self.repo_path)
+            .list()
+            .await?
+        {
+            let (Some(reason), Some(at)) = (
+                record.reason.as_deref().filter(|r| !r.trim().is_empty()),
+                record.decided_at.as_deref().and_then(parse_time),
+            ) else {
+                continue;
+            };
+            if is_new(&at) {
+                let status = match record.status {
+                    crate::hitl::ApprovalStatus::Approved => "approved",
+                    crate::hitl::ApprovalStatus::Rejected => "rejected",
+                    crate::hitl::ApprovalStatus::Pending => continue,
+                };
+                lessons.push(Lesson {
+                    kind: LessonKind::Review,
+                    at,
+                    source: format!("approval:{}", record.id),
+                    text: format!("{} gate {}: {}", record.gate, status, reason.trim()),
+                });
+            }
+        }
+
+        for (_, proposal) in proposals.iter().filter(|(_, p)| !is_doctrine(p)) {
+            let id = proposal_str(proposal, "id");
+            let title = proposal_str(proposal, "title");
+            let votes = proposal
+                .get("votes")
+                .and_then(|v| v.as_array())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut last_vote = None;
+            for vote in votes {
+                let Some(at) = vote
+                    .get("voted_at")
+                    .and_then(|v| v.as_str())
+                    .and_then(parse_time)
+                else {
+                    continue;
+                };
+                last_vote = last_vote.max(Some(at));
+                if let Some(reason) = vote
+                    .get("reason")
+                    .and_then(|r| r.as_str())
+                    .filter(|r| !r.trim().is_empty())
+                    && is_new(&at)
+                {
+                    lessons.push(Lesson {
+                        kind: LessonKind::Review,
+                        at,
+                        source: format!("sangha:{id}"),
+                        text: format!("On \"{}\": {}", title, reason.trim()),
+                    });
+                }
+            }
+            let outcome = match doctrine::verdict(proposal) {
+                Verdict::Open => continue,
+                Verdict::Approved => "Approved",
+                Verdict::Rejected => "Rejected",
+            };
+            if let Some(at) = last_vote.filter(is_new) {
+                let description = proposal_str(proposal, "description");
+                lessons.push(Lesson {
+                    kind: LessonKind::Decision,
+                    at,
+                    source: format!("sangha:{id}"),
+                    text: if description.is_empty() {
+                        format!("{outcome}: {title}")
+                    } else {
+                        format!("{outcome}: {title} — {description}")
+                    },
+                });
+            }
+        }
+
+        lessons.sort_by_key(|lesson| lesson.at);
+        Ok(lessons)
+    }
+
+    /// Adopt an approved draft into DOCTRINE.md, or close a rejected one.
+    async fn settle_doctrine(
+        &self,
+        path: &Path,
+        proposal: &serde_json::Value,
+        approved: bool,
+    ) -> Result<()> {
+        let mut proposal = proposal.clone();
+        let now = chrono::Utc::now().to_rfc3339();
+        if approved {
+            let draft = doctrine::draft_path(&self.repo_path, proposal_str(&proposal, "id"));
+            let text = tokio::fs::read_to_string(&draft)
+                .await
+                .with_context(|| format!("Failed to read doctrine draft {}", draft.display()))?;
+            tokio::fs::write(self.repo_path.join(doctrine::DOCTRINE_FILE), text).await?;
+            proposal["status"] = serde_json::json!("accepted");
+            proposal["adopted_at"] = serde_json::json!(now);
+        } else {
+            proposal["status"] = serde_json::json!("rejected");
+            proposal["closed_at"] = serde_json::json!(now);
+        }
+        tokio::fs::write(path, serde_json::to_string_pretty(&proposal)?).await?;
+        Ok(())
+    }
+
+    /// Submit `draft` to the Sangha; returns the proposal ID.
+    async fn propose_doctrine(
+        &self,
+        proposals_dir: &Path,
+        lessons: &[Lesson],
+        draft: &str,
+    ) -> Result<String> {
+        tokio::fs::create_dir_all(proposals_dir).await?;
+        let mut proposal = super::sangha::create_sangha_proposal(
+            proposals_dir,
+            &format!("Update {}", doctrine::DOCTRINE_FILE),
+            &doctrine::proposal_description(lessons),
+            doctrine::PROPOSAL_TYPE,
+            TallyMethod::Majority,
+            None,
+        )
+        .await?;
+        let id = proposal_str(&proposal, "id").to_string();
+        let draft_path = doctrine::draft_path(&self.repo_path, &id);
+        if let Some(dir) = draft_path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&draft_path, draft).await?;
+        proposal["draft"] = serde_json::json!(
+            draft_path
+                .strip_prefix(&self.repo_path)
+                .unwrap_or(&draft_path)
+        );
+        tokio::fs::write(
+            proposals_dir.join(format!("{id}.json")),
+            serde_json::to_string_pretty(&proposal)?,
+        )
+        .await?;
+        Ok(id)
+    }
+
+    /// One readonly provider call that condenses a prompt's material into text.
+    async fn summarize(&self, agent_id: &str, prompt: &str) -> Result<String> {
+        let result = self
+            .ask_provider(agent_id, prompt, AskOptions::read_only(&self.repo_path))
+            .await?;
+        let summary = result.raw.trim();
+        if summary.is_empty() {
+            return Err(anyhow!("provider returned an empty summary"));
+        }
+        Ok(summary.to_string())
+    }
+}
+
+/// Every parsable proposal under `dir`, oldest first.
+async fn read_proposals(dir: &Path) -> Result<Vec<(PathBuf, serde_json::Value)>> {
+    let mut proposals = Vec::new();
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(proposals),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dir.display())),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|e| e == "json")
+            && let Ok(content) = tokio::fs::read_to_string(&path).await
+            && let Ok(proposal) = serde_json::from_str::<serde_json::Value>(&content)
+        {
+            proposals.push((path, proposal));
+        }
+    }
+    proposals
+        .sort_by(|a, b| proposal_str(&a.1, "created_at").cmp(proposal_str(&b.1, "created_at")));
+    Ok(proposals)
+}
+
+fn proposal_str<'a>(proposal: &'a serde_json::Value, key: &str) -> &'a str {
+    proposal
+        .get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+}
+
+fn is_doctrine(proposal: &serde_json::Value) -> bool {
+    proposal_str(proposal, "proposal_type") == doctrine::PROPOSAL_TYPE
+}
+
+fn parse_time(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|at| at.with_timezone(&chrono::Utc))
+}
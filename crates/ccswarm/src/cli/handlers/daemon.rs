@@ -0,0 +1,208 @@
+//! `ccswarm daemon` and `ccswarm stop`: the supervised background mode.
+
+use super::super::*;
+use crate::daemon::{ControlRequest, DaemonPaths, DaemonStatus, SubsystemState, Supervisor};
+use std::os::unix::process::CommandExt;
+
+/// How long `daemon start` waits for the detached daemon to answer.
+const START_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+impl CliRunner {
+    pub(crate) async fn handle_daemon(&self, action: &DaemonAction) -> Result<()> {
+        let repo = absolute(&self.repo_path)?;
+        let paths = DaemonPaths::new(&repo);
+        match action {
+            DaemonAction::Start { foreground: true } => {
+                let result = Supervisor::new(
+                    &repo,
+                    self.subsystem_command(&repo)?,
+                    self.config.daemon.clone(),
+                )
+                .run()
+                .await;
+                // Agents are down with the daemon; so are their secrets.
+                if let Err(e) = self.revoke_secrets(None).await {
+                    warn!("Failed to revoke scoped secrets: {e:#}");
+                }
+                result
+            }
+            DaemonAction::Start { foreground: false } => self.start_daemon(&repo, &paths).await,
+            DaemonAction::Stop => {
+                if paths.running_pid().is_none() {
+                    return self.print_daemon(None, "ccswarm daemon is not running");
+                }
+                let status = crate::daemon::request(&paths, ControlRequest::Stop).await?;
+                // Stop answers straight away; wait for the subsystems to go down.
+                let deadline = std::time::Instant::now()
+                    + std::time::Duration::from_secs(self.config.daemon.stop_timeout_secs + 5);
+                while paths.running_pid().is_some() && std::time::Instant::now() < deadline {
+                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                }
+                if paths.running_pid().is_some() {
+                    anyhow::bail!(
+                        "ccswarm daemon (pid {}) is still shutting down; see {}",
+                        status.pid,
+                        paths.log.display()
+                    );
+                }
+                // The daemon revokes on the way out; this covers one that died.
+                self.revoke_secrets(None).await?;
+                self.print_daemon(
+                    Some(&status),
+                    &format!("Stopped ccswarm daemon (pid {})", status.pid),
+                )
+            }
+            DaemonAction::Status => match daemon_status(&paths).await {
+                Some(status) => self.print_daemon_status(&status, &paths),
+                None => self.print_daemon(None, "ccswarm daemon is not running"),
+            },
+        }
+    }
+
+    async fn start_daemon(&self, repo: &Path, paths: &DaemonPaths) -> Result<()> {
+        if let Some(pid) = paths.running_pid() {
+            anyhow::bail!("ccswarm daemon is already running (pid {})", pid);
+        }
+        self.config.daemon.validate()?;
+        tokio::fs::create_dir_all(&paths.dir).await?;
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&paths.log)
+            .with_context(|| format!("Failed to open {}", paths.log.display()))?;
+        let command = self.subsystem_command(repo)?;
+        // A new process group keeps the terminal's Ctrl-C and hangup away
+        // from the daemon once this command returns.
+        let mut child = std::process::Command::new(&command[0])
+            .args(&command[1..])
+            .args(["daemon", "start", "--foreground"])
+            .current_dir(repo)
+            .stdin(std::process::Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .process_group(0)
+            .spawn()
+            .context("Failed to start the daemon process")?;
+
+        let deadline = std::time::Instant::now() + START_TIMEOUT;
+        loop {
+            if let Some(status) = daemon_status(paths).await {
+                return self.print_daemon(
+                    Some(&status),
+                    &format!(
+                        "Started ccswarm daemon (pid {}); log: {}",
+                        status.pid,
+                        paths.log.display()
+                    ),
+                );
+            }
+            if let Some(exit) = child.try_wait()? {
+                anyhow::bail!(
+                    "ccswarm daemon exited during startup ({}); see {}",
+                    exit,
+                    paths.log.display()
+                );
+            }
+            if std::time::Instant::now() >= deadline {
+                anyhow::bail!(
+                    "ccswarm daemon (pid {}) did not come up within {:?}; see {}",
+                    child.id(),
+                    START_TIMEOUT,
+                    paths.log.display()
+                );
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        }
+    }
+
+    /// This binary with the global flags every subsystem needs.
+    fn subsystem_command(&self, repo: &Path) -> Result<Vec<String>> {
+        let exe = std::env::current_exe().context("Cannot locate the ccswarm binary")?;
+        let mut command = vec![
+            exe.to_string_lossy().into_owned(),
+            "--repo".to_string(),
+            repo.to_string_lossy().into_owned(),
+            "--config".to_string(),
+            absolute(&self.config_path)?.to_string_lossy().into_owned(),
+        ];
+        if let Some(provider) = self.default_provider {
+            command.push("--provider".to_string());
+            command.push(provider.as_str().to_string());
+        }
+        Ok(command)
+    }
+
+    fn print_daemon(&self, status: Option<&DaemonStatus>, message: &str) -> Result<()> {
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": { "running": status.is_some(), "daemon": status },
+                }))?
+            );
+        } else {
+            println!("{}", message);
+        }
+        Ok(())
+    }
+
+    fn print_daemon_status(&self, status: &DaemonStatus, paths: &DaemonPaths) -> Result<()> {
+        if self.json_output {
+            return self.print_daemon(Some(status), "");
+        }
+        println!(
+            "{} pid {}, up since {}",
+            "Daemon:".bright_cyan().bold(),
+            status.pid,
+            status.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        print_subsystems(status);
+        println!("  log: {}", paths.log.display());
+        Ok(())
+    }
+}
+
+/// Status of a running daemon, or `None` when none answers.
+pub(super) async fn daemon_status(paths: &DaemonPaths) -> Option<DaemonStatus> {
+    paths.running_pid()?;
+    crate::daemon::request(paths, ControlRequest::Status)
+        .await
+        .ok()
+}
+
+/// One line per subsystem, shared with `ccswarm status`.
+pub(super) fn print_subsystems(status: &DaemonStatus) {
+    for subsystem in &status.subsystems {
+        let state = match subsystem.state {
+            SubsystemState::Running => {
+                format!("running (pid {})", subsystem.pid.unwrap_or_default()).green()
+            }
+            SubsystemState::Backoff => match subsystem.restart_at {
+                Some(at) => format!("restarting at {}", at.format("%H:%M:%S")).yellow(),
+                None => "restarting".yellow(),
+            },
+            SubsystemState::Stopped => "stopped".dimmed(),
+        };
+        print!("  {:<12} {}", subsystem.name, state);
+        if subsystem.restarts > 0 {
+            print!("  restarts: {}", subsystem.restarts);
+        }
+        match &subsystem.last_exit {
+            Some(exit) => println!("  last exit: {}", exit),
+            None => println!(),
+        }
+    }
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    Ok(path
+        .components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect())
+}
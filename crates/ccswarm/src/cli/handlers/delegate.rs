@@ -0,0 +1,178 @@
+use super::super::*;
+use super::queue_state::{QUEUE_FILE, load_queue};
+use crate::agent::orchestrator::DelegationStrategy;
+use crate::agent::orchestrator::delegation::delegate;
+use crate::agent::orchestrator::eval::{EvalDataset, EvalReport, evaluate};
+use crate::workflow::cost_estimate::{self, CostEstimate};
+
+impl CliRunner {
+    pub(crate) async fn handle_delegate(&self, action: &DelegateAction) -> Result<()> {
+        match action {
+            DelegateAction::Analyze {
+                description,
+                verbose,
+                strategy,
+                task_type,
+            } => {
+                let strategy: DelegationStrategy = strategy.parse()?;
+                let task_type = task_type
+                    .as_deref()
+                    .map(str::parse::<TaskType>)
+                    .transpose()?;
+                let decision = delegate(strategy, description, task_type);
+                let estimate = self
+                    .estimate_task_cost(description, &decision.agent)
+                    .await?;
+                let ceiling = self.config.cost.max_task_usd;
+                let over_ceiling = ceiling.is_some_and(|c| estimate.usd > c);
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "strategy": strategy,
+                            "agent": decision.agent,
+                            "confidence": decision.confidence,
+                            "reasoning": decision.reasoning,
+                            "estimate": estimate,
+                            "over_ceiling": over_ceiling,
+                        }))?
+                    );
+                } else {
+                    println!(
+                        "{} {} ({:.0}% confidence, {} strategy)",
+                        "Suggested agent:".bright_cyan().bold(),
+                        decision.agent.bright_green().bold(),
+                        decision.confidence * 100.0,
+                        strategy
+                    );
+                    if *verbose {
+                        println!("  {}", decision.reasoning.dimmed());
+                    }
+                    println!(
+                        "{} {}",
+                        "Estimated cost:".bright_cyan().bold(),
+                        estimate.describe()
+                    );
+                    if let Some(ceiling) = ceiling.filter(|_| over_ceiling) {
+                        println!(
+                            "  {}",
+                            format!(
+                                "over the ${ceiling:.2} per-task ceiling; queue drain would leave it pending"
+                            )
+                            .bright_yellow()
+                        );
+                    }
+                }
+            }
+            DelegateAction::Eval {
+                dataset,
+                strategy,
+                verbose,
+            } => {
+                let dataset = EvalDataset::load(dataset)?;
+                let strategies = if strategy == "all" {
+                    DelegationStrategy::all().to_vec()
+                } else {
+                    vec![strategy.parse()?]
+                };
+                let reports: Vec<EvalReport> = strategies
+                    .into_iter()
+                    .map(|strategy| evaluate(strategy, &dataset))
+                    .collect();
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "dataset": dataset.name,
+                            "reports": reports,
+                        }))?
+                    );
+                    return Ok(());
+                }
+                for report in &reports {
+                    print_eval_report(report, *verbose);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CliRunner {
+    /// Estimate `task` on the model `agent` runs, from the queue's finished
+    /// tasks.
+    async fn estimate_task_cost(&self, task: &str, agent: &str) -> Result<CostEstimate> {
+        let model = self
+            .config
+            .agents
+            .iter()
+            .find(|(name, config)| name.as_str() == agent || config.specialization == agent)
+            .map(|(_, config)| config.claude_config.model.clone())
+            .unwrap_or_else(|| self.config.cost.default_model.clone());
+        let queue = load_queue(&self.repo_path.join(QUEUE_FILE)).await?;
+        let history = cost_estimate::past_tasks(&self.repo_path, queue.finished_runs());
+        Ok(cost_estimate::estimate(
+            task,
+            &model,
+            &history,
+            &self.config.cost,
+        ))
+    }
+}
+
+fn print_eval_report(report: &EvalReport, verbose: bool) {
+    println!(
+        "{} {} on '{}': {}/{} correct ({:.1}%)",
+        "Delegation eval:".bright_cyan().bold(),
+        report.strategy.to_string().bright_yellow(),
+        report.dataset,
+        report.correct,
+        report.total,
+        report.accuracy * 100.0
+    );
+    println!();
+    println!(
+        "{}",
+        "Confusion matrix (rows: expected, columns: predicted)".bold()
+    );
+    for line in report.confusion_table().lines() {
+        println!("  {}", line);
+    }
+    println!();
+    println!(
+        "  {:<10} {:>7} {:>9} {:>7}",
+        "agent", "support", "precision", "recall"
+    );
+    for score in &report.per_agent {
+        println!(
+            "  {:<10} {:>7} {:>8.0}% {:>6.0}%",
+            score.agent,
+            score.support,
+            score.precision * 100.0,
+            score.recall * 100.0
+        );
+    }
+    if !report.misses.is_empty() {
+        println!();
+        if verbose {
+            println!("{} ({})", "Misrouted".yellow().bold(), report.misses.len());
+            for miss in &report.misses {
+                println!(
+                    "  {} → {} (expected {}): {}",
+                    miss.task,
+                    miss.predicted.red(),
+                    miss.expected.green(),
+                    miss.reasoning.dimmed()
+                );
+            }
+        } else {
+            println!(
+                "{} misrouted task(s); rerun with --verbose to list them",
+                report.misses.len()
+            );
+        }
+    }
+    println!();
+}
@@ -0,0 +1,439 @@
+//! `ccswarm demo`: a working end-to-end run for newcomers, with no provider
+//! CLI and no API key.
+//!
+//! A sample repository is scaffolded around `greet.sh`, a tiny POSIX shell
+//! app with its own tests, so it needs no toolchain. Three dependent tasks
+//! are queued, and `.ccswarm/mock.yaml` gives the mock provider (see
+//! [`crate::providers::mock`]) a canned reply for each: the files a real
+//! agent would have written. The queue is then drained like any other, so
+//! the runs, commits, quality checks and status the tour shows afterwards
+//! are real.
+
+use super::super::*;
+use super::queue_state::{QUEUE_FILE, QueueState, QueueTask, load_queue};
+use crate::git::shell::git;
+use crate::providers::ProviderKind;
+use crate::providers::mock::SCENARIO_FILE;
+use crate::workflow::quality_checks::QualityCheck;
+use anyhow::bail;
+use chrono::Utc;
+
+const GREET_V1: &str = r#"#!/bin/sh
+# greet.sh: the demo app ccswarm's agents work on.
+echo "Hello, ${1:-world}!"
+"#;
+
+const GREET_V2: &str = r#"#!/bin/sh
+# greet.sh: the demo app ccswarm's agents work on.
+case "$1" in
+    bye) echo "Goodbye, ${2:-world}!" ;;
+    *) echo "Hello, ${1:-world}!" ;;
+esac
+"#;
+
+const GREET_V3: &str = r#"#!/bin/sh
+# greet.sh: the demo app ccswarm's agents work on.
+shout=false
+if [ "$1" = "--shout" ]; then
+    shout=true
+    shift
+fi
+case "$1" in
+    bye) message="Goodbye, ${2:-world}!" ;;
+    *) message="Hello, ${1:-world}!" ;;
+esac
+if $shout; then
+    echo "$message" | tr '[:lower:]' '[:upper:]'
+else
+    echo "$message"
+fi
+"#;
+
+const TESTS_HEAD: &str = r#"#!/bin/sh
+# Tests for greet.sh; fails when any case does.
+status=0
+check() {
+    expected=$1
+    shift
+    actual=$(sh greet.sh "$@")
+    if [ "$actual" = "$expected" ]; then
+        echo "ok   greet.sh $*"
+    else
+        echo "FAIL greet.sh $*: got '$actual', want '$expected'"
+        status=1
+    fi
+}
+check "Hello, world!"
+check "Hello, Ada!" Ada
+"#;
+
+const TESTS_BYE: &str = "check \"Goodbye, Ada!\" bye Ada\n";
+const TESTS_SHOUT: &str = "check \"HELLO, ADA!\" --shout Ada\n";
+const TESTS_TAIL: &str = "exit $status\n";
+
+const README: &str = "# greet\n\nA tiny app for trying out ccswarm (`ccswarm demo`).\n";
+
+const README_USAGE: &str = r#"
+## Usage
+
+```sh
+sh greet.sh Ada          # Hello, Ada!
+sh greet.sh bye Ada      # Goodbye, Ada!
+sh greet.sh --shout Ada  # HELLO, ADA!
+```
+
+Run the tests with `sh tests/run.sh`.
+"#;
+
+/// Checks the pipeline runs after each task, before committing.
+const CHECK_IMPL_SKILL: &str = "# check-impl\n\n```sh\nsh -n greet.sh\nsh tests/run.sh\n```\n";
+
+/// State the runs leave behind stays out of the commits; the scenario is
+/// part of the project.
+const GITIGNORE: &str = ".ccswarm/*\n!.ccswarm/mock.yaml\n";
+
+/// The seeded backlog: id, task, the files its implementation writes.
+fn backlog() -> Vec<(&'static str, &'static str, Vec<(&'static str, String)>)> {
+    let tests = |cases: &[&str]| format!("{TESTS_HEAD}{}{TESTS_TAIL}", cases.concat());
+    vec![
+        (
+            "q-demo-1",
+            "Add a bye subcommand to greet.sh that says goodbye",
+            vec![
+                ("greet.sh", GREET_V2.to_string()),
+                ("tests/run.sh", tests(&[TESTS_BYE])),
+            ],
+        ),
+        (
+            "q-demo-2",
+            "Add a --shout flag to greet.sh that upper-cases the message",
+            vec![
+                ("greet.sh", GREET_V3.to_string()),
+                ("tests/run.sh", tests(&[TESTS_BYE, TESTS_SHOUT])),
+            ],
+        ),
+        (
+            "q-demo-3",
+            "Document how to use greet.sh in README.md",
+            vec![("README.md", format!("{README}{README_USAGE}"))],
+        ),
+    ]
+}
+
+/// The mock scenario: an approving review, the backlog's implementations,
+/// an approving Sangha and a plan for everything else.
+fn scenario() -> Result<String> {
+    let mut replies = vec![serde_json::json!({
+        "match": "Review the implementation",
+        "stdout": "Reviewed the change: it is correct, tested and complete. APPROVED",
+    })];
+    for (_, task, files) in backlog() {
+        let files: serde_json::Map<String, serde_json::Value> = files
+            .into_iter()
+            .map(|(path, content)| (path.to_string(), content.into()))
+            .collect();
+        let updated = files.keys().cloned().collect::<Vec<_>>().join(" and ");
+        replies.push(serde_json::json!({
+            "match": task,
+            "stdout": format!("Done: {}. Updated {updated}.", task.to_lowercase()),
+            "files": files,
+        }));
+    }
+    // After the implementations: the implement prompt carries the Sangha
+    // decision forward, and Sangha members may not edit anyway.
+    replies.push(serde_json::json!({
+        "match": "SANGHA_DECISION=",
+        "stdout": "The plan is small and testable.\nSANGHA_DECISION=APPROVE",
+    }));
+    replies.push(serde_json::json!({
+        "stdout": "Plan: change greet.sh, cover the change in tests/run.sh, then run the tests. Done.",
+    }));
+    Ok(format!(
+        "# Canned replies for `ccswarm --provider mock` (see `ccswarm demo`).\n{}",
+        serde_yml::to_string(&serde_json::json!({ "replies": replies }))?
+    ))
+}
+
+/// Scaffold the sample repository in `dir` and queue the backlog.
+async fn seed(dir: &Path) -> Result<CcswarmConfig> {
+    for args in [
+        &["init", "-q"][..],
+        &["config", "user.name", "ccswarm demo"],
+        &["config", "user.email", "demo@example.com"],
+        &["config", "commit.gpgsign", "false"],
+    ] {
+        git(dir, args).await?;
+    }
+
+    let mut config = CcswarmConfig::default();
+    config.project.name = "greet".to_string();
+    config.quality.checks = vec![
+        QualityCheck {
+            name: "lint".to_string(),
+            command: "sh -n greet.sh".to_string(),
+            fix: None,
+            env: Default::default(),
+            cargo: false,
+        },
+        QualityCheck {
+            name: "test".to_string(),
+            command: "sh tests/run.sh".to_string(),
+            fix: None,
+            env: Default::default(),
+            cargo: false,
+        },
+    ];
+    config.to_file(dir.join("ccswarm.json")).await?;
+
+    let files = [
+        ("greet.sh", GREET_V1.to_string()),
+        ("tests/run.sh", format!("{TESTS_HEAD}{TESTS_TAIL}")),
+        ("README.md", README.to_string()),
+        (".gitignore", GITIGNORE.to_string()),
+        (
+            ".claude/skills/check-impl/SKILL.md",
+            CHECK_IMPL_SKILL.to_string(),
+        ),
+        (SCENARIO_FILE, scenario()?),
+    ];
+    for (path, content) in files {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, content).await?;
+    }
+    git(dir, &["add", "-A"]).await?;
+    git(
+        dir,
+        &["commit", "-q", "-m", "chore: scaffold the ccswarm demo"],
+    )
+    .await?;
+
+    let mut previous: Option<&str> = None;
+    let queued: Vec<QueueTask> = backlog()
+        .into_iter()
+        .map(|(id, task, _)| {
+            let depends_on = previous.map(|p| vec![p.to_string()]).unwrap_or_default();
+            previous = Some(id);
+            QueueTask {
+                id: id.to_string(),
+                task: task.to_string(),
+                flow: None,
+                state: "pending".to_string(),
+                created_at: Utc::now(),
+                completed_at: None,
+                run_id: None,
+                claim: Default::default(),
+                attempts: Vec::new(),
+                time_box: None,
+                command_timeout: None,
+                model: None,
+                priority: None,
+                labels: vec!["demo".to_string()],
+                depends_on,
+                blocker: None,
+                acceptance: None,
+            }
+        })
+        .collect();
+    QueueState::new(dir.join(QUEUE_FILE))
+        .update_queue(|queue| {
+            queue.tasks = queued;
+            Ok(())
+        })
+        .await?;
+    Ok(config)
+}
+
+fn step(n: usize, text: &str) {
+    println!();
+    println!("{} {}", format!("[{n}/4]").bright_cyan(), text.bold());
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_demo(&self, path: &Path, no_run: bool) -> Result<()> {
+        if path.exists() && std::fs::read_dir(path)?.next().is_some() {
+            bail!(
+                "{} already exists and is not empty; pick another directory",
+                path.display()
+            );
+        }
+        tokio::fs::create_dir_all(path).await?;
+        let dir = std::path::absolute(path)?;
+
+        if !self.json_output {
+            println!(
+                "{} a sample project in {}, worked on by a simulated swarm",
+                "ccswarm demo:".bright_cyan().bold(),
+                dir.display()
+            );
+            step(1, "Scaffolding greet.sh, its tests and ccswarm.json");
+        }
+        let config = seed(&dir).await?;
+        let demo = CliRunner {
+            config,
+            repo_path: dir.clone(),
+            config_path: dir.join("ccswarm.json"),
+            json_output: self.json_output,
+            formatter: create_formatter(self.json_output),
+            default_provider: Some(ProviderKind::Mock),
+            replay: None,
+        };
+        if !self.json_output {
+            step(2, "Seeding a backlog of three tasks");
+            demo.handle_queue(&QueueAction::List).await?;
+        }
+
+        if !no_run {
+            if !self.json_output {
+                step(3, "Draining the backlog with the mock provider");
+                println!(
+                    "   Each task runs the default flow: plan → Sangha → implement → review,\n   \
+                     then the tests, then a commit. Replies come from {SCENARIO_FILE}."
+                );
+            }
+            demo.handle_queue(&QueueAction::Drain {
+                flow: None,
+                timeout: 600,
+                fail_fast: true,
+                interactive: false,
+                create_pr: false,
+                reconcile_only: false,
+                require_approval: false,
+                approval_timeout: 600,
+                max_task_cost: None,
+            })
+            .await?;
+        }
+
+        let queue = load_queue(&dir.join(QUEUE_FILE)).await?;
+        let completed = queue
+            .tasks
+            .iter()
+            .filter(|task| task.state == "completed")
+            .count();
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": {
+                        "path": dir,
+                        "tasks": queue.tasks.len(),
+                        "completed": completed,
+                    },
+                }))?
+            );
+            return Ok(());
+        }
+
+        step(4, "A look around");
+        if !no_run {
+            println!("{}", "$ ccswarm status".bright_black());
+            demo.handle_status(false).await?;
+            println!();
+            println!("{}", "$ ccswarm quality check".bright_black());
+            demo.handle_quality(&QualityAction::Check {
+                skip: Vec::new(),
+                only: Vec::new(),
+                fail_fast: false,
+                jobs: None,
+                profile: None,
+                branch: None,
+            })
+            .await?;
+        }
+
+        let provider = "CCSWARM_PROVIDER=mock";
+        println!();
+        println!("{}", "Next, from the demo directory:".bold());
+        println!("  cd {}", dir.display());
+        for (command, what) in [
+            ("git log --oneline", "one commit per task"),
+            ("ccswarm run list", "the runs, with their stages"),
+            (
+                "ccswarm status --detailed",
+                "the latest run and agent confidence",
+            ),
+            ("ccswarm tui", "the live dashboard (q quits)"),
+            ("ccswarm quality check", "the project's checks"),
+        ] {
+            println!("  {:<30} {}", command, format!("# {what}").bright_black());
+        }
+        println!(
+            "  {:<30} {}",
+            format!("{provider} ccswarm queue drain"),
+            if no_run {
+                "# run the backlog".bright_black()
+            } else {
+                "# after `queue add`: more simulated work".bright_black()
+            }
+        );
+        println!("\nWhen you are ready for real agents, drop {provider} and run `ccswarm doctor`.");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn the_demo_backlog_drains_into_passing_commits() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path().to_path_buf();
+        let runner = CliRunner {
+            config: seed(&dir).await.unwrap(),
+            repo_path: dir.clone(),
+            config_path: dir.join("ccswarm.json"),
+            json_output: true,
+            formatter: create_formatter(true),
+            default_provider: Some(ProviderKind::Mock),
+            replay: None,
+        };
+        assert!(
+            crate::providers::mock::MockScenario::load(&dir)
+                .unwrap()
+                .reply("Document how to use greet.sh in README.md", true)
+                .unwrap()
+                .files
+                .contains_key("README.md")
+        );
+        runner
+            .handle_queue(&QueueAction::Drain {
+                flow: None,
+                timeout: 600,
+                fail_fast: true,
+                interactive: false,
+                create_pr: false,
+                reconcile_only: false,
+                require_approval: false,
+                approval_timeout: 600,
+                max_task_cost: None,
+            })
+            .await
+            .unwrap();
+
+        let queue = load_queue(&dir.join(QUEUE_FILE)).await.unwrap();
+        assert!(queue.tasks.iter().all(|task| task.state == "completed"));
+        let log = std::process::Command::new("git")
+            .args(["log", "--format=%s"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&log.stdout).lines().count(), 4);
+        let tests = std::process::Command::new("sh")
+            .arg("tests/run.sh")
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert!(tests.status.success());
+        assert!(String::from_utf8_lossy(&tests.stdout).contains("ok   greet.sh --shout Ada"));
+        assert!(
+            std::fs::read_to_string(dir.join("README.md"))
+                .unwrap()
+                .contains("## Usage")
+        );
+    }
+}
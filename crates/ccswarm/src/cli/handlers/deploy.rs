@@ -0,0 +1,87 @@
+use super::super::*;
+use super::workflow::AskOptions;
+
+use crate::deploy::DeploySpec;
+
+impl CliRunner {
+    /// `scaffold --auto-deploy`: have the DevOps agent prepare `dir` for the
+    /// template's deploy target, then deploy, verify and roll back on failure.
+    pub(crate) async fn auto_deploy(&self, dir: &std::path::Path, spec: &DeploySpec) -> Result<()> {
+        let target = crate::deploy::resolve(spec)?;
+        eprintln!(
+            "  {} Preparing {} deploy (devops agent)...",
+            "\u{25b6}".bright_blue(),
+            target.kind().as_str()
+        );
+        let prompt = format!(
+            "Prepare this project for deployment. {}\n\n\
+             Do not deploy it yourself and do not change application behaviour; \
+             ccswarm runs the deploy once you are done. Commit your changes.",
+            target.agent_brief(spec)
+        );
+        self.run_devops_agent(dir, &prompt).await?;
+
+        eprintln!(
+            "  {} Deploying with {}...",
+            "\u{25b6}".bright_blue(),
+            target.kind().as_str()
+        );
+        let report = crate::deploy::run(dir, target.as_ref(), spec).await?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if report.succeeded() { "success" } else { "error" },
+                    "deploy": report,
+                }))?
+            );
+        }
+        match &report.failure {
+            None => {
+                eprintln!(
+                    "{} Deployed with {}{}",
+                    "OK".bright_green().bold(),
+                    report.target,
+                    report
+                        .url
+                        .as_deref()
+                        .map(|u| format!(" — {u} is healthy"))
+                        .unwrap_or_default()
+                );
+                Ok(())
+            }
+            Some(failure) => {
+                eprintln!(
+                    "{} Deploy failed: {}",
+                    "\u{2717}".bright_red().bold(),
+                    failure
+                );
+                if report.rolled_back {
+                    eprintln!("  Rolled back.");
+                } else {
+                    eprintln!("  Nothing to roll back to; check the target manually.");
+                }
+                anyhow::bail!("deploy to {} failed", report.target)
+            }
+        }
+    }
+
+    async fn run_devops_agent(&self, dir: &std::path::Path, prompt: &str) -> Result<()> {
+        let result = self
+            .ask_provider(
+                "devops",
+                prompt,
+                AskOptions {
+                    role: crate::identity::default_devops_role(),
+                    retries: 2,
+                    ..AskOptions::writable(dir)
+                },
+            )
+            .await?;
+        if !result.success {
+            return Err(anyhow!("devops agent could not prepare the deploy"));
+        }
+        Ok(())
+    }
+}
@@ -177,7 +177,7 @@ impl CliRunner {
             issues.push("git_repo");
         }
 
-        // Check agent provider CLIs (Claude/Codex/Copilot)
+        // Check agent provider CLIs (Claude/Codex/Gemini/Aider/Copilot)
         println!("Checking agent provider CLIs...");
 
         for (label, probe, fix_key, required, caveat) in [
@@ -195,6 +195,20 @@ impl CliRunner {
                 false,
                 None,
             ),
+            (
+                "  gemini",
+                &["gemini", "--version"][..],
+                "gemini_cli",
+                false,
+                None,
+            ),
+            (
+                "  aider",
+                &["aider", "--version"][..],
+                "aider_cli",
+                false,
+                None,
+            ),
             (
                 "  gh copilot",
                 &["gh", "copilot", "--version"][..],
@@ -242,7 +256,7 @@ impl CliRunner {
 
         // Check worktree health
         print!("Checking worktree health... ");
-        if let Ok(manager) = crate::git::shell::ShellWorktreeManager::new(self.repo_path.clone()) {
+        if let Ok(manager) = self.worktree_manager() {
             match manager.list_worktrees().await {
                 Ok(worktrees) => {
                     let stale_count = worktrees
@@ -322,9 +336,7 @@ impl CliRunner {
                         "stale_worktrees" => {
                             if fix {
                                 println!("• Pruning stale worktrees...");
-                                if let Ok(manager) = crate::git::shell::ShellWorktreeManager::new(
-                                    self.repo_path.clone(),
-                                ) {
+                                if let Ok(manager) = self.worktree_manager() {
                                     if let Err(e) = manager.prune_worktrees().await {
                                         println!("  ❌ Failed to prune: {}", e);
                                     } else {
@@ -0,0 +1,166 @@
+//! `ccswarm halt` and `ccswarm resume-all` (see [`crate::governance::halt`]).
+
+use super::super::*;
+use super::session::{Suspended, suspend_run_dir};
+use crate::events::SessionInfo;
+use crate::governance::halt::{self, HaltState, HaltedRun};
+use ai_session::core::suspend::{Suspend, kill_descendants};
+
+impl CliRunner {
+    pub(crate) async fn handle_halt(&self, reason: Option<&str>) -> Result<()> {
+        if let Some(state) = halt::load(&self.repo_path).await? {
+            anyhow::bail!(
+                "Already halted since {}; run `ccswarm resume-all` to continue",
+                state.halted_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+        }
+        // Recorded first: from here on nothing new is dispatched.
+        let mut state = HaltState::new(reason.map(str::to_string));
+        halt::save(&self.repo_path, &state).await?;
+
+        let mut failures = Vec::new();
+        for (run_id, run_dir, pid) in self.live_runs().await? {
+            let events = tokio::fs::read_to_string(run_dir.join("events.ndjson"))
+                .await
+                .unwrap_or_default();
+            let paused = SessionInfo::from_events(&run_id, &events).status == "paused";
+            let stopped = if paused {
+                false
+            } else {
+                match suspend_run_dir(&run_dir, &run_id, Suspend::Stop).await {
+                    Ok(_) => true,
+                    Err(e) => {
+                        failures.push(format!("{run_id}: {e}"));
+                        false
+                    }
+                }
+            };
+            let cancelled = kill_descendants(pid).unwrap_or_else(|e| {
+                failures.push(format!("{run_id}: {e}"));
+                Vec::new()
+            });
+            state.runs.push(HaltedRun {
+                run_id,
+                pid,
+                stopped,
+                cancelled,
+            });
+        }
+        halt::save(&self.repo_path, &state).await?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if failures.is_empty() { "success" } else { "partial" },
+                    "data": { "halt": state, "failures": failures },
+                }))?
+            );
+            return Ok(());
+        }
+        let stopped = state.runs.iter().filter(|r| r.stopped).count();
+        let cancelled: usize = state.runs.iter().map(|r| r.cancelled.len()).sum();
+        println!(
+            "{} {} session{} stopped, {} process{} cancelled, queue frozen",
+            "HALTED".bright_red().bold(),
+            stopped,
+            if stopped == 1 { "" } else { "s" },
+            cancelled,
+            if cancelled == 1 { "" } else { "es" }
+        );
+        for run in &state.runs {
+            println!(
+                "  {} {}{}",
+                run.run_id.bright_yellow(),
+                if run.stopped {
+                    "stopped"
+                } else {
+                    "already paused"
+                },
+                if run.cancelled.is_empty() {
+                    String::new()
+                } else {
+                    format!(", killed {:?}", run.cancelled)
+                }
+            );
+        }
+        for failure in &failures {
+            println!("  {} {}", "!".yellow().bold(), failure);
+        }
+        println!(
+            "Continue with {}",
+            "ccswarm resume-all".bright_cyan().bold()
+        );
+        Ok(())
+    }
+
+    pub(crate) async fn handle_resume_all(&self) -> Result<()> {
+        let Some(state) = halt::load(&self.repo_path).await? else {
+            anyhow::bail!("ccswarm is not halted");
+        };
+        // Lifted first, so the resumed sessions may call their providers.
+        halt::clear(&self.repo_path).await?;
+
+        let runs_dir = self.repo_path.join(".ccswarm").join("runs");
+        let mut resumed = Vec::new();
+        let mut failures = Vec::new();
+        for run in state.runs.iter().filter(|r| r.stopped) {
+            match suspend_run_dir(&runs_dir.join(&run.run_id), &run.run_id, Suspend::Continue).await
+            {
+                Ok(Suspended { away, .. }) => resumed.push((run.run_id.clone(), away)),
+                Err(e) => failures.push(format!("{}: {e}", run.run_id)),
+            }
+        }
+
+        if self.json_output {
+            let resumed: Vec<_> = resumed
+                .iter()
+                .map(|(run_id, away)| serde_json::json!({ "run_id": run_id, "away": away }))
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": { "resumed": resumed, "failures": failures },
+                }))?
+            );
+            return Ok(());
+        }
+        println!(
+            "{} halt from {} lifted; {} session{} resumed, queue unfrozen",
+            "✓".bright_green(),
+            state.halted_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            resumed.len(),
+            if resumed.len() == 1 { "" } else { "s" }
+        );
+        for (run_id, _) in &resumed {
+            println!("  {}", run_id.bright_yellow());
+        }
+        for failure in &failures {
+            println!("  {} {}", "!".yellow().bold(), failure);
+        }
+        Ok(())
+    }
+
+    /// Runs with a live process: `(run id, run dir, pid)`.
+    async fn live_runs(&self) -> Result<Vec<(String, PathBuf, u32)>> {
+        let mut runs = Vec::new();
+        let Ok(mut entries) =
+            tokio::fs::read_dir(self.repo_path.join(".ccswarm").join("runs")).await
+        else {
+            return Ok(runs);
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let dir = entry.path();
+            let Some(pid) = crate::events::run_pid(&dir) else {
+                continue;
+            };
+            if pid == std::process::id() {
+                continue;
+            }
+            runs.push((entry.file_name().to_string_lossy().to_string(), dir, pid));
+        }
+        runs.sort();
+        Ok(runs)
+    }
+}
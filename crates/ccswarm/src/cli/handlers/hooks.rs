@@ -0,0 +1,169 @@
+//! `ccswarm hooks` (see [`crate::git::hooks`]).
+
+use super::super::*;
+use crate::git::hooks::{self, BYPASS_ENV, Hook, HookCommand};
+use crate::security::prompt_guard::{self, AuditEntry};
+use std::path::{Path, PathBuf};
+
+impl CliRunner {
+    pub(crate) async fn handle_hooks(&self, action: &HooksAction) -> Result<()> {
+        match action {
+            HooksAction::Install { worktree, profile } => {
+                let command = HookCommand::current(&self.config_path, profile.clone())?;
+                let mut installed = Vec::new();
+                for path in self.hook_targets(worktree.as_deref()).await? {
+                    hooks::install(&path, &command).await?;
+                    installed.push(path);
+                }
+                self.print_hook_paths(&installed, "Installed commit hooks in")
+            }
+            HooksAction::Uninstall { worktree } => {
+                let mut removed = Vec::new();
+                for path in self.hook_targets(worktree.as_deref()).await? {
+                    if hooks::uninstall(&path).await? {
+                        removed.push(path);
+                    }
+                }
+                self.print_hook_paths(&removed, "Removed commit hooks from")
+            }
+            HooksAction::Status => {
+                let mut rows = Vec::new();
+                for path in self.hook_targets(None).await? {
+                    let installed = hooks::installed(&path).await?;
+                    rows.push((path, installed));
+                }
+                if self.json_output {
+                    let data: Vec<_> = rows
+                        .iter()
+                        .map(|(path, installed)| {
+                            serde_json::json!({ "worktree": path, "installed": installed })
+                        })
+                        .collect();
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "data": data,
+                        }))?
+                    );
+                    return Ok(());
+                }
+                if rows.is_empty() {
+                    println!("No agent worktrees.");
+                }
+                for (path, installed) in &rows {
+                    println!(
+                        "  {} {}",
+                        if *installed {
+                            "✓".bright_green()
+                        } else {
+                            "-".dimmed()
+                        },
+                        path.display()
+                    );
+                }
+                Ok(())
+            }
+            HooksAction::Run { hook, profile, .. } => {
+                let hook =
+                    Hook::parse(hook).ok_or_else(|| anyhow::anyhow!("Unknown hook '{hook}'"))?;
+                self.run_hook(hook, profile.as_deref()).await
+            }
+        }
+    }
+
+    /// What a hook script does: run the checks, or record why they were not.
+    /// An error refuses the commit or push.
+    async fn run_hook(&self, hook: Hook, profile: Option<&str>) -> Result<()> {
+        if hook == Hook::PostCommit {
+            if !hooks::take_checked(&self.repo_path).await? {
+                let commit = crate::git::GitUtils::get_head_commit(&self.repo_path)
+                    .await
+                    .unwrap_or_default();
+                // Audited as the gate that was skipped.
+                self.audit_bypass(Hook::PreCommit, format!("--no-verify (commit {commit})"))
+                    .await;
+            }
+            return Ok(());
+        }
+
+        let reason = std::env::var(BYPASS_ENV).unwrap_or_default();
+        if !reason.trim().is_empty() {
+            self.audit_bypass(hook, format!("{BYPASS_ENV}: {}", reason.trim()))
+                .await;
+        } else {
+            let report = self.quality_gate(profile).await?;
+            if !report.passed() {
+                anyhow::bail!(
+                    "Quality checks failed; {} refused (set {BYPASS_ENV}=<reason> to override)",
+                    if hook == Hook::PrePush {
+                        "push"
+                    } else {
+                        "commit"
+                    }
+                );
+            }
+        }
+        if hook == Hook::PreCommit {
+            hooks::record_checked(&self.repo_path).await?;
+        }
+        Ok(())
+    }
+
+    /// Record a bypassed gate in the main repository's audit log.
+    async fn audit_bypass(&self, hook: Hook, detail: String) {
+        eprintln!(
+            "{} {} checks bypassed: {detail} (recorded in the audit log)",
+            "⚠".bright_yellow(),
+            hook.name()
+        );
+        let mut entry = AuditEntry::bypass(hook.name(), detail);
+        entry.agent = std::env::var("CCSWARM_AGENT_ID").ok().or_else(|| {
+            self.repo_path
+                .canonicalize()
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        });
+        let main = crate::workflow::coverage::main_worktree(&self.repo_path).await;
+        if let Err(e) = prompt_guard::audit(&main, &entry).await {
+            warn!("Failed to record the hook bypass: {e:#}");
+        }
+    }
+
+    /// `worktree`, or every agent worktree: all but the main one.
+    async fn hook_targets(&self, worktree: Option<&Path>) -> Result<Vec<PathBuf>> {
+        if let Some(worktree) = worktree {
+            return Ok(vec![worktree.to_path_buf()]);
+        }
+        // `git worktree list` puts the main worktree (or bare repository) first.
+        Ok(self
+            .worktree_manager()?
+            .list_worktrees()
+            .await?
+            .into_iter()
+            .skip(1)
+            .filter(|w| !w.is_bare && !w.is_prunable)
+            .map(|w| w.path)
+            .collect())
+    }
+
+    fn print_hook_paths(&self, paths: &[PathBuf], label: &str) -> Result<()> {
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": paths,
+                }))?
+            );
+        } else if paths.is_empty() {
+            println!("No agent worktrees to change.");
+        } else {
+            println!("✅ {label}:");
+            for path in paths {
+                println!("   {}", path.display());
+            }
+        }
+        Ok(())
+    }
+}
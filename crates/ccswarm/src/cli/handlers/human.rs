@@ -0,0 +1,310 @@
+//! `ccswarm human`: register people as participants and work through the
+//! tasks delegated to them.
+
+use super::super::*;
+use super::blockers;
+use super::queue_state::{QUEUE_FILE, QueueState, load_queue};
+use crate::agent::{Priority, Task, TaskType};
+use crate::governance::human::{
+    self, ASSIGNED_STATE, HumanAgentConfig, HumanInbox, InboxItem, InboxStatus,
+};
+
+impl CliRunner {
+    pub(crate) async fn handle_human(&self, action: &HumanAction) -> Result<()> {
+        match action {
+            HumanAction::Register {
+                name,
+                capabilities,
+                notify_command,
+            } => {
+                self.human_register(name, capabilities, notify_command.as_deref())
+                    .await
+            }
+            HumanAction::List => self.human_list(),
+            HumanAction::Delegate {
+                name,
+                task,
+                priority,
+                task_type,
+            } => {
+                let id = format!("h-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+                let task = Task::new(
+                    id,
+                    task.clone(),
+                    priority.parse::<Priority>()?,
+                    task_type.parse::<TaskType>()?,
+                );
+                let item = self.delegate_to_human(name, task, "cli", None).await?;
+                self.print_inbox_item("delegated to", &item)
+            }
+            HumanAction::Inbox { human, all } => self.human_inbox(human.as_deref(), *all).await,
+            HumanAction::Accept { id } => {
+                let item = HumanInbox::open(&self.repo_path).await?.accept(id).await?;
+                self.print_inbox_item("accepted by", &item)
+            }
+            HumanAction::Complete { id, summary } => {
+                let inbox = HumanInbox::open(&self.repo_path).await?;
+                let item = inbox.finish(id, true, summary).await?;
+                settle_queue_task(&self.repo_path, &item).await?;
+                self.print_inbox_item("completed by", &item)
+            }
+            HumanAction::Decline { id, reason } => {
+                let inbox = HumanInbox::open(&self.repo_path).await?;
+                let item = inbox.finish(id, false, reason).await?;
+                settle_queue_task(&self.repo_path, &item).await?;
+                self.print_inbox_item("declined by", &item)
+            }
+            HumanAction::Comment { id, text, author } => {
+                let inbox = HumanInbox::open(&self.repo_path).await?;
+                let author = match author {
+                    Some(author) => author.clone(),
+                    None => inbox
+                        .get(id)
+                        .await?
+                        .map(|item| item.human)
+                        .context(format!("No inbox item {id}"))?,
+                };
+                let item = inbox.comment(id, &author, text).await?;
+                self.print_inbox_item("commented on by", &item)
+            }
+        }
+    }
+
+    async fn human_register(
+        &self,
+        name: &str,
+        capabilities: &[String],
+        notify_command: Option<&str>,
+    ) -> Result<()> {
+        crate::run_id::validate_run_id(name).context("invalid human name")?;
+        if self.config.agents.contains_key(name) {
+            anyhow::bail!(
+                "'{}' is already an agent in {}",
+                name,
+                self.config_path.display()
+            );
+        }
+        let mut config = self.config.clone();
+        let entry = config.humans.entry(name.to_string()).or_default();
+        if !capabilities.is_empty() {
+            entry.capabilities = capabilities.to_vec();
+        }
+        if let Some(command) = notify_command {
+            entry.notify_command = (!command.is_empty()).then(|| command.to_string());
+        }
+        let entry = entry.clone();
+        config.to_file(self.config_path.clone()).await?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": { "name": name, "human": entry },
+                }))?
+            );
+        } else {
+            println!(
+                "{} registered {} in {}",
+                "OK".bright_green().bold(),
+                name.bright_cyan(),
+                self.config_path.display()
+            );
+        }
+        Ok(())
+    }
+
+    fn human_list(&self) -> Result<()> {
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": self.config.humans,
+                }))?
+            );
+            return Ok(());
+        }
+        if self.config.humans.is_empty() {
+            println!("No humans registered. Add one with `ccswarm human register <name>`.");
+            return Ok(());
+        }
+        let mut names: Vec<_> = self.config.humans.keys().collect();
+        names.sort();
+        for name in names {
+            let human = &self.config.humans[name];
+            println!(
+                "  {} {}{}",
+                name.bright_cyan(),
+                human.capabilities.join(", "),
+                if human.notify_command.is_some() {
+                    " (notified)".bright_black().to_string()
+                } else {
+                    String::new()
+                }
+            );
+        }
+        Ok(())
+    }
+
+    fn registered_human(&self, name: &str) -> Result<&HumanAgentConfig> {
+        self.config.humans.get(name).with_context(|| {
+            format!("'{name}' is not a registered human; run `ccswarm human register {name}`")
+        })
+    }
+
+    /// Put `task` in `name`'s inbox and notify them.
+    pub(super) async fn delegate_to_human(
+        &self,
+        name: &str,
+        task: Task,
+        delegated_by: &str,
+        queue_task: Option<String>,
+    ) -> Result<InboxItem> {
+        let config = self.registered_human(name)?;
+        let item = HumanInbox::open(&self.repo_path)
+            .await?
+            .delegate(name, task, delegated_by, queue_task)
+            .await?;
+        human::notify(config, &item).await;
+        Ok(item)
+    }
+
+    /// Hold queue task `id` for `name` until they complete or decline it.
+    pub(super) async fn assign_queue_task(&self, path: &Path, id: &str, name: &str) -> Result<()> {
+        self.registered_human(name)?;
+        QueueState::new(path.to_path_buf())
+            .update_task(id, |task| {
+                task.state = ASSIGNED_STATE.to_string();
+                Ok(())
+            })
+            .await?;
+        let queued = load_queue(path)
+            .await?
+            .tasks
+            .into_iter()
+            .find(|task| task.id == id)
+            .context(format!("Queue task {id} disappeared"))?;
+        let task = Task::new(
+            id.to_string(),
+            queued.task,
+            queued.priority.unwrap_or(Priority::Medium),
+            TaskType::Development,
+        );
+        self.delegate_to_human(name, task, "queue", Some(id.to_string()))
+            .await?;
+        if !self.json_output {
+            println!("   assigned to {}", name.bright_cyan());
+        }
+        Ok(())
+    }
+
+    async fn human_inbox(&self, human: Option<&str>, all: bool) -> Result<()> {
+        let items: Vec<InboxItem> = HumanInbox::open(&self.repo_path)
+            .await?
+            .list(human)
+            .await?
+            .into_iter()
+            .filter(|item| all || !item.status.is_closed())
+            .collect();
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": items,
+                }))?
+            );
+            return Ok(());
+        }
+        if items.is_empty() {
+            println!("Inbox is empty.");
+            return Ok(());
+        }
+        for item in &items {
+            let status = match item.status {
+                InboxStatus::New => "new".bright_yellow(),
+                InboxStatus::Accepted => "accepted".bright_blue(),
+                InboxStatus::Completed => "completed".bright_green(),
+                InboxStatus::Declined => "declined".bright_red(),
+            };
+            let preview: String = item
+                .task
+                .description
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(60)
+                .collect();
+            println!(
+                "  {:<12} {:<9} {:<10} {}",
+                item.id.bright_yellow(),
+                status,
+                item.human.bright_cyan(),
+                preview
+            );
+            for comment in &item.comments {
+                println!(
+                    "      {} {}",
+                    format!("{}:", comment.author).bright_black(),
+                    comment.text
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn print_inbox_item(&self, verb: &str, item: &InboxItem) -> Result<()> {
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": item,
+                }))?
+            );
+        } else {
+            println!(
+                "{} {} {} {}",
+                "OK".bright_green().bold(),
+                item.id.bright_yellow(),
+                verb,
+                item.human.bright_cyan()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Carry a closed inbox item's outcome over to the queue task it holds, so
+/// tasks depending on it can run.
+pub(super) async fn settle_queue_task(repo: &Path, item: &InboxItem) -> Result<()> {
+    let Some(id) = item.queue_task.as_deref() else {
+        return Ok(());
+    };
+    if !item.status.is_closed() {
+        return Ok(());
+    }
+    let completed = item.status == InboxStatus::Completed;
+    QueueState::new(repo.join(QUEUE_FILE))
+        .update_task(id, |task| {
+            // A blocker question: the answer goes back to the agent. Once the
+            // task was unblocked some other way the answer is moot.
+            if item.delegated_by == blockers::BLOCKER_DELEGATOR {
+                if task.state == blockers::BLOCKED_STATE && completed {
+                    let answer = item.result.as_deref().unwrap_or("done");
+                    blockers::requeue(task, Some(format!("Answer from {}: {answer}", item.human)));
+                    return Ok(());
+                }
+                if task.state != blockers::BLOCKED_STATE {
+                    return Ok(());
+                }
+            }
+            task.state = if completed { "completed" } else { "failed" }.to_string();
+            task.completed_at = Some(chrono::Utc::now());
+            Ok(())
+        })
+        .await
+}
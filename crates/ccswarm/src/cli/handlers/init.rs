@@ -64,6 +64,7 @@ impl CliRunner {
                 branch: format!("feature/{}", agent_type),
                 claude_config: crate::config::ClaudeConfig::for_agent(agent_type),
                 claude_md_template: format!("{}_specialist", agent_type),
+                ..Default::default()
             };
             config.agents.insert(agent_type.clone(), agent_config);
         }
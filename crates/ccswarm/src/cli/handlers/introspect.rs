@@ -143,7 +143,10 @@ impl CliRunner {
 }
 
 impl CliRunner {
-    pub(crate) async fn handle_cost(&self, run_id: Option<&str>) -> Result<()> {
+    pub(crate) async fn handle_cost(&self, run_id: Option<&str>, estimates: bool) -> Result<()> {
+        if estimates {
+            return self.cost_estimates();
+        }
         let run_path = resolve_run_path(&self.repo_path, run_id).await?;
         let summary_path = run_path.join("summary.json");
         let events_path = run_path.join("events.ndjson");
@@ -265,6 +268,75 @@ impl CliRunner {
     }
 }
 
+impl CliRunner {
+    /// Print each journaled pre-run estimate next to its run's actual cost.
+    fn cost_estimates(&self) -> Result<()> {
+        use crate::workflow::cost_estimate::reconcile;
+
+        let reconciled = reconcile(&self.repo_path, &self.config.cost)?;
+        let measured: Vec<f64> = reconciled.iter().filter_map(|r| r.ratio()).collect();
+        let within = reconciled
+            .iter()
+            .filter(|r| r.within_envelope() == Some(true))
+            .count();
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "estimates": reconciled,
+                    "measured": measured.len(),
+                    "within_envelope": within,
+                }))?
+            );
+            return Ok(());
+        }
+        if reconciled.is_empty() {
+            println!("No cost estimates recorded yet; `ccswarm queue drain` records one per task.");
+            return Ok(());
+        }
+
+        println!(
+            "  {:<10} {:<8} {:>9} {:>9} {:>7}",
+            "task", "run", "estimate", "actual", "ratio"
+        );
+        for entry in &reconciled {
+            let estimate = &entry.record.estimate;
+            let actual = entry
+                .actual_usd
+                .map_or_else(|| "-".to_string(), |usd| format!("${usd:.2}"));
+            let ratio = entry
+                .ratio()
+                .map_or_else(|| "-".to_string(), |r| format!("{r:.2}x"));
+            let ratio = match entry.within_envelope() {
+                Some(true) => ratio.bright_green(),
+                Some(false) => ratio.bright_red(),
+                None => ratio.bright_black(),
+            };
+            println!(
+                "  {:<10} {:<8} {:>9} {:>9} {:>7}",
+                entry.record.task_id.as_deref().unwrap_or("-"),
+                entry.record.run_id.chars().take(8).collect::<String>(),
+                format!("${:.2}", estimate.usd),
+                actual,
+                ratio
+            );
+        }
+        if !measured.is_empty() {
+            let mut sorted = measured.clone();
+            sorted.sort_by(f64::total_cmp);
+            println!();
+            println!(
+                "{} {} of {} runs inside the estimated range; median actual/estimate {:.2}x",
+                "Accuracy:".bright_cyan().bold(),
+                within,
+                measured.len(),
+                sorted[sorted.len() / 2]
+            );
+        }
+        Ok(())
+    }
+}
+
 fn print_event_line(line: &str) {
     let line = line.trim();
     if line.is_empty() {
@@ -1,5 +1,5 @@
 //! `ccswarm lab` — experimental / research commands grouped away from the primary flow.
-//! Dispatches to existing sangha/extend/search/evolution handlers.
+//! Dispatches to existing sangha/extend/search/evolution/ab handlers.
 
 use super::super::*;
 
@@ -10,6 +10,24 @@ impl CliRunner {
             LabAction::Extend { action } => self.handle_extend(action).await,
             LabAction::Evolution { action } => self.handle_evolution(action).await,
             LabAction::Search { action } => self.handle_search_cmd(action).await,
+            LabAction::Ab {
+                task,
+                a,
+                b,
+                flow,
+                timeout,
+                no_review,
+            } => {
+                self.handle_ab(super::ab::AbOptions {
+                    task,
+                    a,
+                    b,
+                    flow,
+                    timeout: *timeout,
+                    no_review: *no_review,
+                })
+                .await
+            }
         }
     }
 }
@@ -1,4 +1,7 @@
 use super::super::*;
+use crate::git::bootstrap::{self, Step, WorktreeBindings, WorktreePolicy};
+use crate::git::disk;
+use crate::git::scaffold;
 
 impl CliRunner {
     pub(crate) async fn show_logs(
@@ -110,7 +113,7 @@ impl CliRunner {
             if follow {
                 println!();
                 println!("⚠️  Follow mode requires the TUI");
-                println!("   Run: ccswarm tui --follow-logs");
+                println!("   Run: ccswarm tui logs");
             }
         }
 
@@ -138,8 +141,13 @@ impl CliRunner {
         Ok(())
     }
 
+    /// The worktree backend `repository.git_backend` selects.
+    pub(crate) fn worktree_manager(&self) -> Result<Box<dyn crate::git::WorktreeBackend>> {
+        crate::git::backend::open(self.config.project.repository.git_backend, &self.repo_path)
+    }
+
     pub(crate) async fn handle_worktree(&self, action: &WorktreeAction) -> Result<()> {
-        let manager = crate::git::shell::ShellWorktreeManager::new(self.repo_path.clone())?;
+        let manager = self.worktree_manager()?;
 
         match action {
             WorktreeAction::List => {
@@ -148,14 +156,27 @@ impl CliRunner {
                 if self.json_output {
                     println!("{}", serde_json::to_string_pretty(&worktrees)?);
                 } else {
-                    println!("🌳 Git Worktrees");
-                    println!("===============");
+                    let title = crate::t!("worktree-list-title");
+                    println!("🌳 {title}");
+                    println!("{}", "=".repeat(title.chars().count() + 3));
 
                     for wt in &worktrees {
-                        println!("Path: {}", wt.path.display());
-                        println!("  Branch: {}", wt.branch);
-                        println!("  Head: {}", wt.head_commit);
-                        println!("  Locked: {}", wt.is_locked);
+                        println!(
+                            "{}",
+                            crate::t!("worktree-path", path = wt.path.display().to_string())
+                        );
+                        println!(
+                            "  {}",
+                            crate::t!("worktree-branch", branch = wt.branch.as_str())
+                        );
+                        println!(
+                            "  {}",
+                            crate::t!("worktree-head", head = wt.head_commit.as_str())
+                        );
+                        println!(
+                            "  {}",
+                            crate::t!("worktree-locked", locked = wt.is_locked.to_string())
+                        );
                         println!();
                     }
                 }
@@ -170,13 +191,23 @@ impl CliRunner {
                 } else {
                     manager.create_worktree(path, branch).await?
                 };
+                if self.config.coordination.quality_gate_frequency == "on_commit" {
+                    let command = crate::git::hooks::HookCommand::current(&self.config_path, None)?;
+                    crate::git::hooks::install(&info.path, &command).await?;
+                }
 
                 if self.json_output {
                     println!("{}", serde_json::to_string_pretty(&info)?);
                 } else {
-                    println!("✅ Worktree created");
-                    println!("   Path: {}", info.path.display());
-                    println!("   Branch: {}", info.branch);
+                    println!("✅ {}", crate::t!("worktree-created"));
+                    println!(
+                        "   {}",
+                        crate::t!("worktree-path", path = info.path.display().to_string())
+                    );
+                    println!(
+                        "   {}",
+                        crate::t!("worktree-branch", branch = info.branch.as_str())
+                    );
                 }
             }
             WorktreeAction::Remove { path, force } => {
@@ -196,7 +227,53 @@ impl CliRunner {
                         }))?
                     );
                 } else {
-                    println!("✅ Worktree removed: {}", path.display());
+                    println!(
+                        "✅ {}",
+                        crate::t!("worktree-removed", path = path.display().to_string())
+                    );
+                }
+            }
+            WorktreeAction::Status { path } => {
+                let path = path.as_deref().unwrap_or(&self.repo_path);
+                let branch = manager.current_branch(path).await?;
+                let files = manager.status(path).await?;
+
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "data": { "path": path, "branch": branch, "files": files },
+                        }))?
+                    );
+                } else {
+                    let branch = if branch.is_empty() {
+                        crate::t!("worktree-detached")
+                    } else {
+                        branch
+                    };
+                    println!(
+                        "{}",
+                        crate::t!(
+                            "worktree-on-branch",
+                            path = path.display().to_string(),
+                            branch = branch.bright_cyan().to_string()
+                        )
+                    );
+                    if files.is_empty() {
+                        println!("  {}", crate::t!("worktree-clean"));
+                    }
+                    for file in &files {
+                        let side = |change: Option<crate::git::backend::Change>| {
+                            change.map_or_else(|| "-".to_string(), |c| format!("{c:?}"))
+                        };
+                        println!(
+                            "  {:<11} {:<11} {}",
+                            side(file.index),
+                            side(file.worktree),
+                            file.path.display()
+                        );
+                    }
                 }
             }
             WorktreeAction::Prune => {
@@ -211,7 +288,7 @@ impl CliRunner {
                         }))?
                     );
                 } else {
-                    println!("✅ Stale worktrees pruned");
+                    println!("✅ {}", crate::t!("worktree-pruned"));
                 }
             }
             WorktreeAction::Clean { force } => {
@@ -234,25 +311,28 @@ impl CliRunner {
                             }))?
                         );
                     } else {
-                        println!("✅ No ccswarm worktrees to clean");
+                        println!("✅ {}", crate::t!("worktree-nothing-to-clean"));
                     }
                     return Ok(());
                 }
 
                 // Ask for confirmation unless forced
                 if !force {
-                    println!("⚠️  Found {} ccswarm worktrees:", ccswarm_worktrees.len());
+                    println!(
+                        "⚠️  {}",
+                        crate::t!("worktree-found", count = ccswarm_worktrees.len())
+                    );
                     for w in &ccswarm_worktrees {
                         println!("   - {} ({})", w.path.display(), w.branch);
                     }
-                    print!("\nAre you sure you want to remove all these worktrees? [y/N] ");
+                    print!("\n{} ", crate::t!("worktree-confirm-clean"));
                     io::stdout().flush()?;
 
                     let mut response = String::new();
                     io::stdin().read_line(&mut response)?;
 
                     if !response.trim().eq_ignore_ascii_case("y") {
-                        println!("❌ Cleanup cancelled");
+                        println!("❌ {}", crate::t!("worktree-clean-cancelled"));
                         return Ok(());
                     }
                 }
@@ -314,8 +394,376 @@ impl CliRunner {
                     );
                 }
             }
+            WorktreeAction::Usage { quota } => {
+                let quota = self.worktree_quota(quota.as_deref())?;
+                let usages = self.measure_worktrees(manager.as_ref()).await?;
+                let over: Vec<_> = usages.iter().filter(|u| u.over_quota(quota)).collect();
+
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": if over.is_empty() { "success" } else { "over_quota" },
+                            "quota_bytes": quota,
+                            "worktrees": usages,
+                        }))?
+                    );
+                } else {
+                    println!("💾 Worktree Disk Usage");
+                    println!("=====================");
+                    for usage in &usages {
+                        let marker = if usage.over_quota(quota) {
+                            " ⚠️  over quota"
+                        } else {
+                            ""
+                        };
+                        println!(
+                            "{}  {} (artifacts {}){}",
+                            usage.path.display(),
+                            disk::format_size(usage.bytes),
+                            disk::format_size(usage.artifact_bytes()),
+                            marker
+                        );
+                        for artifact in &usage.artifacts {
+                            println!(
+                                "    {:<12} {:>10}  {}",
+                                artifact.kind.to_string(),
+                                disk::format_size(artifact.bytes),
+                                artifact.relative.display()
+                            );
+                        }
+                    }
+                    let total: u64 = usages.iter().map(|u| u.bytes).sum();
+                    println!("\nTotal: {}", disk::format_size(total));
+                }
+
+                if let Some(quota) = quota
+                    && !over.is_empty()
+                {
+                    anyhow::bail!(
+                        "{} worktree(s) over the {} quota; run `ccswarm worktree gc --prune` to reclaim space",
+                        over.len(),
+                        disk::format_size(quota)
+                    );
+                }
+            }
+            WorktreeAction::Bootstrap { policy, dry_run } => {
+                let policy = policy.unwrap_or(self.config.project.repository.worktree_policy);
+                self.bootstrap_worktrees(policy, *dry_run).await?;
+            }
+            WorktreeAction::Gc {
+                stale_days,
+                quota,
+                prune,
+                link,
+            } => {
+                let options = disk::GcOptions {
+                    stale_after: chrono::Duration::days(i64::from(*stale_days)),
+                    quota: self.worktree_quota(quota.as_deref())?,
+                    prune: *prune,
+                    link: *link,
+                    now: chrono::Utc::now(),
+                };
+                let usages = self.measure_worktrees(manager.as_ref()).await?;
+                let report =
+                    tokio::task::spawn_blocking(move || disk::collect_garbage(&usages, &options))
+                        .await??;
+
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "report": report,
+                        }))?
+                    );
+                    return Ok(());
+                }
+
+                if report.items.is_empty() {
+                    println!("✅ Nothing to reclaim");
+                    return Ok(());
+                }
+                println!("🧹 Worktree Artifacts");
+                println!("====================");
+                for item in &report.items {
+                    let action = match (item.action, item.applied) {
+                        (disk::GcAction::Prune, true) => "pruned",
+                        (disk::GcAction::Prune, false) => "prune",
+                        (disk::GcAction::Link, true) => "linked",
+                        (disk::GcAction::Link, false) => "link",
+                    };
+                    println!(
+                        "  {:<7} {:>10}  {} ({})",
+                        action,
+                        disk::format_size(item.bytes),
+                        item.artifact.display(),
+                        item.reason
+                    );
+                }
+                println!();
+                if *prune || *link {
+                    println!("✅ Reclaimed {}", disk::format_size(report.reclaimed_bytes));
+                }
+                let pending = report.reclaimable_bytes - report.reclaimed_bytes;
+                if pending > 0 {
+                    println!(
+                        "{} more can be reclaimed with --prune / --link",
+                        disk::format_size(pending)
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn bootstrap_worktrees(&self, policy: WorktreePolicy, dry_run: bool) -> Result<()> {
+        let agents = bootstrap::agent_worktrees(&self.config, &self.repo_path);
+        let state = bootstrap::RepoState::load(&self.repo_path, &agents).await?;
+        let steps = bootstrap::plan(&agents, &state, policy);
+        let base = &self.config.project.repository.main_branch;
+
+        if dry_run {
+            if self.json_output {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "status": "success",
+                        "data": { "policy": policy, "dry_run": true, "agents": steps },
+                    }))?
+                );
+            } else {
+                println!("🌳 Worktree bootstrap plan ({policy:?})");
+                for planned in &steps {
+                    println!(
+                        "  {:<12} {}  {}",
+                        planned.agent,
+                        describe_step(&planned.step, base),
+                        planned.path.display()
+                    );
+                }
+            }
+            return Ok(());
+        }
+
+        let mut outcomes = bootstrap::apply(&self.repo_path, base, steps).await?;
+        let mut bindings = WorktreeBindings::load(&self.repo_path).await?;
+        bindings.record(&outcomes);
+        self.restore_associations(&mut bindings, &outcomes).await?;
+        bindings.save(&self.repo_path).await?;
+        let scaffolded = self.scaffold_worktrees(&mut outcomes).await?;
+        let failed = outcomes.iter().filter(|o| o.error.is_some()).count();
+
+        if self.json_output {
+            let agents: Vec<_> = outcomes
+                .iter()
+                .map(|o| {
+                    serde_json::json!({
+                        "outcome": o,
+                        "binding": bindings.agents.get(&o.planned.agent),
+                        "scaffolded": scaffolded.get(&o.planned.agent),
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if failed == 0 { "success" } else { "partial" },
+                    "data": { "policy": policy, "dry_run": false, "agents": agents },
+                }))?
+            );
+        } else {
+            println!("🌳 Worktree bootstrap ({policy:?})");
+            for outcome in &outcomes {
+                let planned = &outcome.planned;
+                match &outcome.error {
+                    Some(error) => println!(
+                        "  {} {:<12} {}",
+                        "✗".bright_red(),
+                        planned.agent,
+                        error.bright_red()
+                    ),
+                    None => {
+                        println!(
+                            "  {} {:<12} {}  {}",
+                            "✓".bright_green(),
+                            planned.agent,
+                            describe_step(&planned.step, base),
+                            planned.path.display()
+                        );
+                        let binding = bindings.agents.get(&planned.agent);
+                        let restored: Vec<String> = [
+                            ("session", binding.and_then(|b| b.session.as_deref())),
+                            ("run", binding.and_then(|b| b.run_id.as_deref())),
+                            ("task", binding.and_then(|b| b.task_id.as_deref())),
+                        ]
+                        .into_iter()
+                        .filter_map(|(label, value)| value.map(|v| format!("{label} {v}")))
+                        .collect();
+                        if planned.step.keeps_work() && !restored.is_empty() {
+                            println!("    restored {}", restored.join(", ").dimmed());
+                        }
+                        if let Some(created) = scaffolded.get(&planned.agent) {
+                            println!(
+                                "    scaffolded {}",
+                                created
+                                    .iter()
+                                    .map(|p| {
+                                        p.strip_prefix(&planned.path)
+                                            .unwrap_or(p)
+                                            .display()
+                                            .to_string()
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                                    .dimmed()
+                            );
+                        }
+                    }
+                }
+            }
         }
+        if failed > 0 {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
 
+    /// Write each agent's `scaffold` entries into the worktrees bootstrap
+    /// just put in place, and its granted secrets into every worktree it
+    /// kept or added (so re-running bootstrap rotates them). A failure is
+    /// recorded on the agent's outcome.
+    async fn scaffold_worktrees(
+        &self,
+        outcomes: &mut [bootstrap::Outcome],
+    ) -> Result<std::collections::BTreeMap<String, Vec<std::path::PathBuf>>> {
+        let project = super::template::project_variables(&self.config, &self.repo_path, &[])?;
+        let secrets = self.config.secrets.load(&self.repo_path)?;
+        let mut scaffolded = std::collections::BTreeMap::new();
+        for outcome in outcomes {
+            let planned = &outcome.planned;
+            let Some(agent) = self.config.agents.get(&planned.agent) else {
+                continue;
+            };
+            if outcome.error.is_some() || matches!(planned.step, Step::Blocked { .. }) {
+                continue;
+            }
+            let mut created = Vec::new();
+            if planned.step.adds_worktree() && !agent.scaffold.is_empty() {
+                let mut variables = project.clone();
+                variables.extend([
+                    ("agent".to_string(), planned.agent.clone()),
+                    ("branch".to_string(), planned.branch.clone()),
+                    ("worktree".to_string(), planned.path.display().to_string()),
+                    ("specialization".to_string(), agent.specialization.clone()),
+                ]);
+                match scaffold::apply(&agent.scaffold, &self.repo_path, &planned.path, &variables)
+                    .await
+                {
+                    Ok(files) => created.extend(files),
+                    Err(e) => {
+                        outcome.error = Some(format!("scaffold: {e:#}"));
+                        continue;
+                    }
+                }
+            }
+            match secrets.write_env_file(&planned.path, &planned.agent).await {
+                Ok(Some(path)) => created.push(path),
+                Ok(None) => {}
+                Err(e) => outcome.error = Some(format!("secrets: {e:#}")),
+            }
+            if !created.is_empty() {
+                scaffolded.insert(planned.agent.clone(), created);
+            }
+        }
+        Ok(scaffolded)
+    }
+
+    /// Fill in missing associations of agents whose work survived: the latest
+    /// named session reserved for the agent, and the queue task its run
+    /// belongs to.
+    async fn restore_associations(
+        &self,
+        bindings: &mut WorktreeBindings,
+        outcomes: &[bootstrap::Outcome],
+    ) -> Result<()> {
+        let names = crate::session::names::SessionNames::load(&self.repo_path).await?;
+        let queue =
+            super::queue_state::load_queue(&self.repo_path.join(super::queue_state::QUEUE_FILE))
+                .await?;
+        for outcome in outcomes {
+            if outcome.error.is_some() || !outcome.planned.step.keeps_work() {
+                continue;
+            }
+            let agent = &outcome.planned.agent;
+            let Some(binding) = bindings.agents.get_mut(agent) else {
+                continue;
+            };
+            if binding.session.is_none()
+                && let Some((name, session)) = names
+                    .iter()
+                    .filter(|(_, s)| s.agent.as_deref() == Some(agent.as_str()))
+                    .max_by_key(|(_, s)| s.created_at)
+            {
+                binding.session = Some(name.to_string());
+                binding.run_id = binding.run_id.take().or(session.run_id.clone());
+            }
+            if binding.task_id.is_none()
+                && let Some(run_id) = &binding.run_id
+            {
+                binding.task_id = queue
+                    .tasks
+                    .iter()
+                    .find(|t| {
+                        t.run_id.as_ref() == Some(run_id)
+                            || t.attempts.iter().any(|a| a.run_id == *run_id)
+                    })
+                    .map(|t| t.id.clone());
+            }
+        }
         Ok(())
     }
+
+    /// `--quota` if given, else the configured `repository.worktree_quota`.
+    fn worktree_quota(&self, flag: Option<&str>) -> Result<Option<u64>> {
+        flag.or(self.config.project.repository.worktree_quota.as_deref())
+            .map(disk::parse_size)
+            .transpose()
+    }
+
+    async fn measure_worktrees(
+        &self,
+        manager: &dyn crate::git::WorktreeBackend,
+    ) -> Result<Vec<disk::WorktreeUsage>> {
+        // `git worktree list` puts the main worktree (or bare repository) first.
+        let worktrees: Vec<(std::path::PathBuf, String, bool)> = manager
+            .list_worktrees()
+            .await?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, w)| !w.is_bare)
+            .map(|(index, w)| (w.path, w.branch, index == 0))
+            .collect();
+        tokio::task::spawn_blocking(move || {
+            worktrees
+                .iter()
+                .map(|(path, branch, is_main)| disk::measure(path, branch, *is_main))
+                .collect()
+        })
+        .await
+        .map_err(Into::into)
+    }
+}
+
+fn describe_step(step: &Step, base: &str) -> String {
+    match step {
+        Step::Adopt => "adopt existing worktree".to_string(),
+        Step::Attach => "check out existing branch".to_string(),
+        Step::Create => format!("create from {base}"),
+        Step::Move { from } => format!("move from {}", from.display()),
+        Step::Reset => format!("reset to {base}"),
+        Step::Recreate { .. } => format!("recreate from {base}"),
+        Step::Blocked { reason } => format!("blocked: {reason}"),
+    }
 }
@@ -1,21 +1,55 @@
+mod ab;
+mod agent;
 pub mod agent_gen;
 mod analytics;
 mod approve;
 mod auto;
+mod bench;
+mod bisect;
+mod blockers;
+mod bundle;
+mod chat;
+mod cluster;
+mod completions;
 mod config;
+mod context;
+mod daemon;
+mod delegate;
+mod demo;
+mod deploy;
 mod diagnostics;
+mod halt;
 mod harness;
+mod hooks;
+mod human;
 mod init;
 mod introspect;
 mod lab;
 mod misc;
+mod proactive;
+mod quality;
 mod queue;
-mod queue_state;
+pub(crate) mod queue_state;
+pub(crate) mod queue_wal;
+mod recover;
 mod replay;
 pub mod run;
 pub(crate) mod run_utils;
+mod runs;
 mod sangha;
 pub mod scaffold;
+pub(crate) mod scaffold_template;
+mod secrets;
 mod session;
+mod session_group;
+mod status;
 mod task;
+mod task_bulk;
+mod task_dedup;
+mod task_export;
+mod task_undo;
+pub(crate) mod template;
+mod time_box;
+mod tools;
+mod tui;
 pub(crate) mod workflow;
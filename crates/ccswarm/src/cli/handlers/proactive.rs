@@ -0,0 +1,445 @@
+//! Proactive master review for `ccswarm auto --watch`.
+//!
+//! When `project.master_claude.enable_proactive_mode` is set, the watch loop
+//! periodically steps back from draining the queue and looks at what the
+//! agents have been doing: recent run summaries under `.ccswarm/runs/` and the
+//! state of `.ccswarm/queue.yaml`. It flags blockers (failed tasks, tasks stuck
+//! in `running`, pending tasks waiting on a failed dependency) and configured
+//! agents that sat idle while work was ready, and queues a corrective task for
+//! each recent failure. Every decision is appended to
+//! `.ccswarm/decisions.ndjson` so the operator can see why a task appeared.
+//!
+//! Reviews run every `proactive_frequency` seconds, or every `high_frequency`
+//! seconds while the previous review found blockers.
+
+use super::super::*;
+use super::queue_state::{ClaimState, QUEUE_FILE, QueueFile, QueueState, QueueTask};
+use super::task_dedup::find_duplicates;
+use crate::agent::Priority;
+use crate::events::RunSummary;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+pub(crate) const DECISIONS_LOG: &str = ".ccswarm/decisions.ndjson";
+
+/// How far back failures and runs are considered.
+const REVIEW_WINDOW_HOURS: i64 = 24;
+/// A task still `running` after this long is reported as stalled.
+const STALLED_AFTER_MINUTES: i64 = 60;
+/// Label carried by every task the review queues.
+const PROACTIVE_LABEL: &str = "proactive";
+
+/// What a review decided about one task or agent.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DecisionKind {
+    /// A corrective task was queued for a failed task.
+    Corrective,
+    /// A failed task already has a corrective task, or an open task covers it.
+    AlreadyCovered,
+    /// A task has been running longer than expected.
+    Stalled,
+    /// A pending task waits on a dependency that failed.
+    BlockedDependency,
+    /// A configured agent ran nothing in the review window while work was ready.
+    IdleAgent,
+}
+
+impl std::fmt::Display for DecisionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Corrective => "corrective",
+            Self::AlreadyCovered => "already_covered",
+            Self::Stalled => "stalled",
+            Self::BlockedDependency => "blocked_dependency",
+            Self::IdleAgent => "idle_agent",
+        })
+    }
+}
+
+/// One entry of the decision journal.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct Decision {
+    pub(crate) kind: DecisionKind,
+    /// Queue task ID or agent name the decision is about.
+    pub(crate) subject: String,
+    pub(crate) reason: String,
+    /// ID of the task queued because of this decision.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) queued: Option<String>,
+}
+
+impl Decision {
+    fn is_blocker(&self) -> bool {
+        matches!(
+            self.kind,
+            DecisionKind::Corrective
+                | DecisionKind::AlreadyCovered
+                | DecisionKind::Stalled
+                | DecisionKind::BlockedDependency
+        )
+    }
+}
+
+/// Result of one review: the decisions, plus the tasks to queue for them.
+#[derive(Debug, Default)]
+pub(crate) struct ProactiveReview {
+    pub(crate) decisions: Vec<Decision>,
+    pub(crate) follow_ups: Vec<QueueTask>,
+}
+
+impl ProactiveReview {
+    /// Whether anything is blocked, which shortens the next review interval.
+    pub(crate) fn has_blockers(&self) -> bool {
+        self.decisions.iter().any(Decision::is_blocker)
+    }
+}
+
+/// Review `queue` and the `runs` that started in the window ending at `now`.
+pub(crate) fn review(
+    queue: &QueueFile,
+    runs: &[RunSummary],
+    agents: &[String],
+    now: DateTime<Utc>,
+) -> ProactiveReview {
+    let cutoff = now - chrono::Duration::hours(REVIEW_WINDOW_HOURS);
+    let mut review = ProactiveReview::default();
+
+    for task in &queue.tasks {
+        match task.state.as_str() {
+            "failed" if task.completed_at.unwrap_or(task.created_at) >= cutoff => {
+                review_failure(queue, task, &mut review);
+            }
+            "running" => {
+                if let ClaimState::Running { started_at_ms, .. } = &task.claim
+                    && let Some(started) = DateTime::from_timestamp_millis(*started_at_ms as i64)
+                    && now - started >= chrono::Duration::minutes(STALLED_AFTER_MINUTES)
+                {
+                    review.decisions.push(Decision {
+                        kind: DecisionKind::Stalled,
+                        subject: task.id.clone(),
+                        reason: format!("running for {} minutes", (now - started).num_minutes()),
+                        queued: None,
+                    });
+                }
+            }
+            "pending" => {
+                let failed: Vec<&str> = task
+                    .depends_on
+                    .iter()
+                    .filter(|dep| {
+                        queue
+                            .tasks
+                            .iter()
+                            .any(|other| other.id == **dep && other.state == "failed")
+                    })
+                    .map(String::as_str)
+                    .collect();
+                if !failed.is_empty() {
+                    review.decisions.push(Decision {
+                        kind: DecisionKind::BlockedDependency,
+                        subject: task.id.clone(),
+                        reason: format!("depends on failed {}", failed.join(", ")),
+                        queued: None,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !queue.ready_tasks().is_empty() {
+        let active: BTreeSet<&str> = runs
+            .iter()
+            .filter(|run| run.started_at >= cutoff)
+            .flat_map(|run| run.agents_used.iter().map(String::as_str))
+            .collect();
+        for agent in agents {
+            if !active.contains(agent.as_str()) {
+                review.decisions.push(Decision {
+                    kind: DecisionKind::IdleAgent,
+                    subject: agent.clone(),
+                    reason: format!(
+                        "no runs in the last {REVIEW_WINDOW_HOURS}h while {} task(s) are ready",
+                        queue.ready_tasks().len()
+                    ),
+                    queued: None,
+                });
+            }
+        }
+    }
+
+    review
+}
+
+fn review_failure(queue: &QueueFile, task: &QueueTask, review: &mut ProactiveReview) {
+    // Corrective tasks are not corrected again; a failing fix needs a human.
+    if task.labels.iter().any(|label| label == PROACTIVE_LABEL) {
+        return;
+    }
+    let marker = follow_up_label(&task.id);
+    let existing = queue
+        .tasks
+        .iter()
+        .chain(&review.follow_ups)
+        .find(|other| other.labels.contains(&marker));
+    if let Some(existing) = existing {
+        review.decisions.push(Decision {
+            kind: DecisionKind::AlreadyCovered,
+            subject: task.id.clone(),
+            reason: format!("corrective task {} already queued", existing.id),
+            queued: None,
+        });
+        return;
+    }
+
+    let body = corrective_body(task);
+    if let Some(open) = find_duplicates(queue, &body)
+        .into_iter()
+        .find(|m| m.blocks())
+    {
+        review.decisions.push(Decision {
+            kind: DecisionKind::AlreadyCovered,
+            subject: task.id.clone(),
+            reason: format!("covered by {}", open.describe()),
+            queued: None,
+        });
+        return;
+    }
+
+    let id = format!("q-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    review.decisions.push(Decision {
+        kind: DecisionKind::Corrective,
+        subject: task.id.clone(),
+        reason: failure_reason(task),
+        queued: Some(id.clone()),
+    });
+    review.follow_ups.push(QueueTask {
+        id,
+        task: body,
+        flow: task.flow.clone(),
+        state: "pending".to_string(),
+        created_at: Utc::now(),
+        completed_at: None,
+        run_id: None,
+        claim: ClaimState::default(),
+        attempts: Vec::new(),
+        time_box: task.time_box,
+        command_timeout: task.command_timeout,
+        model: task.model.clone(),
+        priority: Some(Priority::High),
+        labels: vec![PROACTIVE_LABEL.to_string(), marker],
+        depends_on: Vec::new(),
+        blocker: None,
+        acceptance: None,
+    });
+}
+
+fn follow_up_label(task_id: &str) -> String {
+    format!("follow-up:{task_id}")
+}
+
+fn failure_reason(task: &QueueTask) -> String {
+    task.attempts
+        .iter()
+        .rev()
+        .find_map(|attempt| attempt.error.clone())
+        .or_else(|| match &task.claim {
+            ClaimState::Released { reason } => Some(reason.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| "failed without an error message".to_string())
+}
+
+fn corrective_body(task: &QueueTask) -> String {
+    format!(
+        "Investigate and fix the failure of queue task {}.\n\nError: {}\n\nOriginal task:\n{}",
+        task.id,
+        failure_reason(task),
+        task.task
+    )
+}
+
+/// Summaries of runs under `.ccswarm/runs/`; unreadable runs are skipped.
+pub(super) fn load_run_summaries(repo_path: &Path) -> Vec<RunSummary> {
+    let Ok(entries) = std::fs::read_dir(repo_path.join(".ccswarm").join("runs")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("summary.json")).ok())
+        .filter_map(|raw| serde_json::from_str(&raw).ok())
+        .collect()
+}
+
+impl CliRunner {
+    /// Run one proactive review, queue its follow-ups and journal its
+    /// decisions. Returns whether blockers were found.
+    pub(crate) async fn proactive_review(&self) -> Result<bool> {
+        let queue_state = QueueState::new(self.repo_path.join(QUEUE_FILE));
+        let mut agents: Vec<String> = self.config.agents.keys().cloned().collect();
+        agents.sort();
+        let runs = load_run_summaries(&self.repo_path);
+
+        let mut outcome = ProactiveReview::default();
+        queue_state
+            .update_queue(|queue| {
+                outcome = review(queue, &runs, &agents, Utc::now());
+                queue.tasks.extend(outcome.follow_ups.iter().cloned());
+                Ok(())
+            })
+            .await?;
+
+        for decision in &outcome.decisions {
+            let line = match &decision.queued {
+                Some(id) => format!("{} → queued {}", decision.reason, id.bright_yellow()),
+                None => decision.reason.clone(),
+            };
+            println!(
+                "{} {} {}: {}",
+                "◆".bright_magenta().bold(),
+                decision.kind,
+                decision.subject.bright_yellow(),
+                line
+            );
+        }
+        append_decisions(&self.repo_path, &outcome.decisions)?;
+        Ok(outcome.has_blockers())
+    }
+}
+
+/// Append `decisions` to the decision journal.
+fn append_decisions(repo_path: &Path, decisions: &[Decision]) -> Result<()> {
+    if decisions.is_empty() {
+        return Ok(());
+    }
+    let path = repo_path.join(DECISIONS_LOG);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let ts = Utc::now().to_rfc3339();
+    let mut body = String::new();
+    for decision in decisions {
+        let mut line = serde_json::to_value(decision)?;
+        line["ts"] = serde_json::Value::String(ts.clone());
+        line["source"] = serde_json::Value::String("proactive".to_string());
+        body.push_str(&line.to_string());
+        body.push('\n');
+    }
+    use std::io::Write;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?
+        .write_all(body.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, text: &str, state: &str) -> QueueTask {
+        QueueTask {
+            id: id.to_string(),
+            task: text.to_string(),
+            flow: None,
+            state: state.to_string(),
+            created_at: Utc::now(),
+            completed_at: (state == "failed").then(Utc::now),
+            run_id: None,
+            claim: ClaimState::default(),
+            attempts: Vec::new(),
+            time_box: None,
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker: None,
+            acceptance: None,
+        }
+    }
+
+    fn run(agents: &[&str]) -> RunSummary {
+        RunSummary {
+            run_id: "r-1".to_string(),
+            started_at: Utc::now(),
+            ended_at: None,
+            total_events: 0,
+            tasks_completed: 0,
+            tasks_failed: 0,
+            agents_used: agents.iter().map(|a| a.to_string()).collect(),
+            orchestrator_run_id: None,
+        }
+    }
+
+    #[test]
+    fn failed_task_gets_one_corrective_task() {
+        let mut failed = task(
+            "q-fail",
+            "Add rate limiting to the login endpoint",
+            "failed",
+        );
+        failed.claim = ClaimState::Released {
+            reason: "failed: tests did not pass".to_string(),
+        };
+        let mut blocked = task("q-next", "Document the rate limits", "pending");
+        blocked.depends_on = vec!["q-fail".to_string()];
+        let mut queue = QueueFile {
+            tasks: vec![failed, blocked],
+            ..Default::default()
+        };
+
+        let first = review(&queue, &[], &[], Utc::now());
+        assert!(first.has_blockers());
+        assert_eq!(first.follow_ups.len(), 1);
+        let follow_up = &first.follow_ups[0];
+        assert!(follow_up.task.contains("tests did not pass"));
+        assert!(follow_up.labels.contains(&"follow-up:q-fail".to_string()));
+        let kinds: Vec<&DecisionKind> = first.decisions.iter().map(|d| &d.kind).collect();
+        assert_eq!(
+            kinds,
+            [&DecisionKind::Corrective, &DecisionKind::BlockedDependency]
+        );
+
+        queue.tasks.extend(first.follow_ups);
+        let second = review(&queue, &[], &[], Utc::now());
+        assert!(
+            second.follow_ups.is_empty(),
+            "corrective task is not re-queued"
+        );
+        assert_eq!(second.decisions[0].kind, DecisionKind::AlreadyCovered);
+    }
+
+    #[test]
+    fn stalled_tasks_and_idle_agents_are_reported() {
+        let mut stuck = task("q-stuck", "Refactor the scheduler", "running");
+        stuck.claim = ClaimState::Running {
+            run_id: "r-0".to_string(),
+            started_at_ms: (Utc::now() - chrono::Duration::hours(2)).timestamp_millis() as u64,
+        };
+        let queue = QueueFile {
+            tasks: vec![stuck, task("q-ready", "Fix typo in the README", "pending")],
+            ..Default::default()
+        };
+        let agents = vec!["backend".to_string(), "frontend".to_string()];
+
+        let outcome = review(&queue, &[run(&["frontend"])], &agents, Utc::now());
+        let decisions: Vec<(&DecisionKind, &str)> = outcome
+            .decisions
+            .iter()
+            .map(|d| (&d.kind, d.subject.as_str()))
+            .collect();
+        assert_eq!(
+            decisions,
+            [
+                (&DecisionKind::Stalled, "q-stuck"),
+                (&DecisionKind::IdleAgent, "backend"),
+            ]
+        );
+        assert!(outcome.follow_ups.is_empty());
+    }
+}
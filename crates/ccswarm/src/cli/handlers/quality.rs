@@ -0,0 +1,685 @@
+//! `ccswarm quality`: format, lint, build, test and security checks.
+
+use super::super::*;
+use crate::utils::command::shell_quote;
+use crate::workflow::commit_hygiene::{self, HygieneRules};
+use crate::workflow::quality_baseline::{self, Baseline, CheckBaseline, Ratchet};
+use crate::workflow::quality_checks::{
+    self, CheckReport, CheckResult, CheckState, QualityCheck, RunOptions,
+};
+use crate::workflow::quality_profiles;
+use crate::workflow::test_impact::{self, Impact};
+use chrono::Utc;
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+impl CliRunner {
+    pub(crate) async fn handle_quality(&self, action: &QualityAction) -> Result<()> {
+        if let QualityAction::Status { detailed } = action {
+            return self.quality_status(*detailed).await;
+        }
+        if let QualityAction::Commits {
+            base,
+            max_lines,
+            any_message,
+        } = action
+        {
+            let rules = HygieneRules {
+                base: base.clone(),
+                max_commit_lines: *max_lines,
+                conventional: !any_message,
+                generated: Vec::new(),
+            };
+            return self.quality_commits(&rules).await;
+        }
+        let checks = quality_checks::project_checks(&self.config.quality.checks, &self.repo_path)?;
+        if let QualityAction::Baseline {
+            only,
+            ratchet,
+            step,
+            every_days,
+            show,
+            clear,
+        } = action
+        {
+            let ratchet = ratchet.then(|| Ratchet {
+                step: *step,
+                every_days: *every_days,
+            });
+            return self
+                .quality_baseline(checks, only, ratchet, *show, *clear)
+                .await;
+        }
+
+        let mut profile_name = None;
+        let (selected, jobs, fail_fast) = match action {
+            QualityAction::Check {
+                skip,
+                only,
+                fail_fast,
+                jobs,
+                profile,
+                branch,
+            } => {
+                let checks = match self
+                    .quality_profile(profile.as_deref(), branch.as_deref())
+                    .await
+                {
+                    Some(name) => {
+                        let checks = quality_profiles::checks(&self.config.quality, &name, checks)?;
+                        profile_name = Some(name);
+                        checks
+                    }
+                    None => checks,
+                };
+                (
+                    quality_checks::select(checks, only, skip)?,
+                    jobs.unwrap_or_else(quality_checks::default_jobs),
+                    *fail_fast,
+                )
+            }
+            QualityAction::Format { fix } => (vec![pick(&checks, "format", *fix)?], 1, false),
+            QualityAction::Lint { fix } => (vec![pick(&checks, "lint", *fix)?], 1, false),
+            QualityAction::Test {
+                pattern,
+                unit,
+                integration,
+                security,
+                full,
+                base,
+            } => {
+                let check = pick(&checks, "test", false)?;
+                let Some(mut check) = self.scope_test_check(check, *full, base).await? else {
+                    return Ok(());
+                };
+                let filter = pattern
+                    .clone()
+                    .or_else(|| security.then(|| "security".to_string()));
+                let mut args = Vec::new();
+                if *unit {
+                    args.push("--lib".to_string());
+                }
+                if *integration {
+                    args.push("--test '*'".to_string());
+                }
+                if let Some(filter) = filter {
+                    args.push(format!("-- {}", shell_quote(&filter)));
+                }
+                append_cargo_args(&mut check, &args)?;
+                (vec![check], 1, false)
+            }
+            QualityAction::Build {
+                release,
+                all_targets,
+            } => {
+                let mut check = pick(&checks, "build", false)?;
+                let args: Vec<String> = [(*release, "--release"), (*all_targets, "--all-targets")]
+                    .into_iter()
+                    .filter(|(on, _)| *on)
+                    .map(|(_, arg)| arg.to_string())
+                    .collect();
+                append_cargo_args(&mut check, &args)?;
+                (vec![check], 1, false)
+            }
+            QualityAction::Security { audit, deps } => {
+                let mut selected = Vec::new();
+                if *audit || !*deps {
+                    selected.push(pick(&checks, "security", false)?);
+                }
+                if *deps {
+                    selected.push(match checks.iter().find(|c| c.name == "deps") {
+                        Some(check) => check.clone(),
+                        None => QualityCheck {
+                            name: "deps".to_string(),
+                            command: "cargo deny check".to_string(),
+                            fix: None,
+                            env: Default::default(),
+                            cargo: false,
+                        },
+                    });
+                }
+                let jobs = selected.len();
+                (selected, jobs, false)
+            }
+            QualityAction::Status { .. }
+            | QualityAction::Commits { .. }
+            | QualityAction::Baseline { .. } => {
+                unreachable!("handled above")
+            }
+        };
+        if selected.is_empty() {
+            anyhow::bail!("No quality checks selected");
+        }
+
+        let mut report = self.run_quality_checks(&selected, jobs, fail_fast).await?;
+        report.profile = profile_name;
+
+        // Only a full `check` settles the baseline; a narrower run would
+        // drop the issues of everything it left out.
+        let baseline_path = quality_baseline::baseline_path(&self.repo_path).await;
+        if let Some(mut baseline) = quality_baseline::load(&baseline_path).await? {
+            quality_baseline::apply(&mut baseline, &mut report, Utc::now());
+            if matches!(action, QualityAction::Check { .. }) {
+                quality_baseline::save(&baseline_path, &baseline).await?;
+            }
+        }
+
+        if matches!(action, QualityAction::Check { .. }) {
+            quality_checks::save_report(&self.repo_path, &report).await?;
+        }
+        self.print_quality_report(&report, false)?;
+        if !report.passed() {
+            let failed: Vec<&str> = report
+                .checks
+                .iter()
+                .filter(|c| c.state == CheckState::Failed)
+                .map(|c| c.name.as_str())
+                .collect();
+            crate::notify::send(
+                &self.config.notifications,
+                crate::notify::Notification::new(
+                    crate::notify::NotifyEvent::QualityGateFailed,
+                    "ccswarm: quality checks failed",
+                    failed.join(", "),
+                ),
+            )
+            .await;
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+
+    /// Run the checks of `profile` (or the branch's) for a commit gate,
+    /// stopping at the first failure, and print the report.
+    pub(crate) async fn quality_gate(&self, profile: Option<&str>) -> Result<CheckReport> {
+        let checks = quality_checks::project_checks(&self.config.quality.checks, &self.repo_path)?;
+        let profile_name = self.quality_profile(profile, None).await;
+        let selected = match &profile_name {
+            Some(name) => quality_profiles::checks(&self.config.quality, name, checks)?,
+            None => checks,
+        };
+        if selected.is_empty() {
+            anyhow::bail!("No quality checks selected");
+        }
+        let mut report = self
+            .run_quality_checks(&selected, quality_checks::default_jobs(), true)
+            .await?;
+        report.profile = profile_name;
+        self.print_quality_report(&report, false)?;
+        Ok(report)
+    }
+
+    /// The profile `quality check` runs: `profile` when given, else the one
+    /// `quality.branches` picks for `branch` or the checked-out branch.
+    async fn quality_profile(&self, profile: Option<&str>, branch: Option<&str>) -> Option<String> {
+        let (name, reason) = match (profile, branch) {
+            (Some(name), _) => (name.to_string(), "--profile".to_string()),
+            (None, branch) => {
+                let branch = match branch {
+                    Some(branch) => branch.to_string(),
+                    None => quality_profiles::current_branch(&self.repo_path).await?,
+                };
+                let name = quality_profiles::for_branch(&self.config.quality, &branch)?;
+                (name.to_string(), format!("branch {branch}"))
+            }
+        };
+        if !self.json_output {
+            println!(
+                "{}",
+                crate::t!("quality-profile", profile = name.as_str(), reason = reason)
+                    .bright_cyan()
+            );
+        }
+        Some(name)
+    }
+
+    /// Run `selected` behind the progress table.
+    async fn run_quality_checks(
+        &self,
+        selected: &[QualityCheck],
+        jobs: usize,
+        fail_fast: bool,
+    ) -> Result<CheckReport> {
+        let options = RunOptions {
+            jobs,
+            fail_fast,
+            target_dir: quality_checks::shared_target_dir(&self.repo_path),
+            timeout: quality_checks::DEFAULT_TIMEOUT,
+        };
+        let redactor = self.config.redaction.compile()?;
+        let table = ProgressTable::new(!self.json_output && std::io::stdout().is_terminal());
+        if !self.json_output {
+            println!(
+                "{}",
+                crate::t!(
+                    "quality-running",
+                    count = selected.len(),
+                    jobs = options.jobs.max(1)
+                )
+                .bright_cyan()
+            );
+        }
+
+        let on_change = |rows: &[CheckResult]| {
+            if !self.json_output {
+                table.update(rows)
+            }
+        };
+        let run = quality_checks::run(selected, &self.repo_path, &options, &redactor, &on_change);
+        tokio::pin!(run);
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+        Ok(loop {
+            tokio::select! {
+                report = &mut run => break report,
+                _ = tick.tick() => table.redraw(),
+            }
+        })
+    }
+
+    /// `quality baseline`: record what the checks report now, or show or
+    /// clear the recorded baseline.
+    async fn quality_baseline(
+        &self,
+        checks: Vec<QualityCheck>,
+        only: &[String],
+        ratchet: Option<Ratchet>,
+        show: bool,
+        clear: bool,
+    ) -> Result<()> {
+        let path = quality_baseline::baseline_path(&self.repo_path).await;
+        if clear {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => println!("Removed the quality baseline"),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    println!("No quality baseline is recorded")
+                }
+                Err(e) => return Err(e.into()),
+            }
+            return Ok(());
+        }
+        if show {
+            let baseline = quality_baseline::load(&path).await?;
+            return self.print_baseline(baseline.as_ref());
+        }
+
+        let selected = quality_checks::select(checks, only, &[])?;
+        let jobs = quality_checks::default_jobs();
+        let report = self.run_quality_checks(&selected, jobs, false).await?;
+        let now = Utc::now();
+        let mut baseline = Baseline::new(ratchet, now);
+        let mut unrecorded = Vec::new();
+        for check in &report.checks {
+            let recordable = check.state == CheckState::Passed
+                || (check.state == CheckState::Failed
+                    && !check.timed_out
+                    && !check.issues.is_empty());
+            if recordable {
+                let issues = check.issues.iter().cloned().collect();
+                baseline
+                    .checks
+                    .insert(check.name.clone(), CheckBaseline::new(issues, now));
+            } else {
+                unrecorded.push(check.name.clone());
+            }
+        }
+        quality_baseline::save(&path, &baseline).await?;
+        if !self.json_output {
+            for name in &unrecorded {
+                println!(
+                    "{} '{}' failed without issues ccswarm recognises; it is not baselined",
+                    "!".yellow().bold(),
+                    name
+                );
+            }
+        }
+        self.print_baseline(Some(&baseline))
+    }
+
+    fn print_baseline(&self, baseline: Option<&Baseline>) -> Result<()> {
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": baseline,
+                }))?
+            );
+            return Ok(());
+        }
+        let Some(baseline) = baseline else {
+            println!("No quality baseline is recorded. Run: ccswarm quality baseline");
+            return Ok(());
+        };
+        println!(
+            "Quality baseline from {}",
+            baseline.recorded_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        if let Some(ratchet) = &baseline.ratchet {
+            println!(
+                "Ratchet: {} issue(s) fewer every {} day(s)",
+                ratchet.step, ratchet.every_days
+            );
+        }
+        let now = Utc::now();
+        for (name, check) in &baseline.checks {
+            println!(
+                "  {:<10} {} known issue(s), {} allowed today",
+                name.bold(),
+                check.issues.len(),
+                check.allowed_at(baseline.ratchet.as_ref(), now)
+            );
+        }
+        Ok(())
+    }
+
+    /// Narrow the test check to the tests the changes since `base` affect.
+    /// `None` means no change affects a test, so there is nothing to run.
+    async fn scope_test_check(
+        &self,
+        mut check: QualityCheck,
+        full: bool,
+        base: &str,
+    ) -> Result<Option<QualityCheck>> {
+        if full {
+            return Ok(Some(check));
+        }
+        let analysis = async {
+            let changed = test_impact::changed_files(&self.repo_path, base).await?;
+            let impact = test_impact::analyze(&self.repo_path, &changed).await?;
+            anyhow::Ok((impact, changed.len()))
+        };
+        let reason = match analysis.await {
+            Ok((Impact::Scoped { ecosystem, targets }, changed)) => {
+                match test_impact::scope_command(&check, ecosystem, &targets) {
+                    Some(command) => {
+                        if !self.json_output {
+                            println!(
+                                "{} {} target(s) from {} changed file(s): {}",
+                                "Impact".bright_cyan().bold(),
+                                targets.len(),
+                                changed,
+                                targets.join(", ")
+                            );
+                        }
+                        check.command = command;
+                        return Ok(Some(check));
+                    }
+                    None => format!("can't narrow '{}'", check.command),
+                }
+            }
+            Ok((Impact::Nothing, _)) => {
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "data": { "impact": Impact::Nothing },
+                        }))?
+                    );
+                } else {
+                    println!(
+                        "No changes since {} affect the tests. Run with --full to test anyway.",
+                        base
+                    );
+                }
+                return Ok(None);
+            }
+            Ok((Impact::Full { reason }, _)) => reason,
+            Err(e) => format!("impact analysis failed: {e}"),
+        };
+        if !self.json_output {
+            println!("{} full suite ({})", "Impact".bright_cyan().bold(), reason);
+        }
+        Ok(Some(check))
+    }
+
+    async fn quality_status(&self, detailed: bool) -> Result<()> {
+        let Some(report) = quality_checks::load_report(&self.repo_path).await? else {
+            if self.json_output {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "status": "success",
+                        "data": null,
+                    }))?
+                );
+            } else {
+                println!("No quality check has run yet. Run: ccswarm quality check");
+            }
+            return Ok(());
+        };
+        if !self.json_output {
+            println!(
+                "Last quality check: {}",
+                report.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+            for row in &report.checks {
+                println!("{}", format_row(row));
+            }
+        }
+        self.print_quality_report(&report, detailed)
+    }
+
+    async fn quality_commits(&self, rules: &HygieneRules) -> Result<()> {
+        let report = commit_hygiene::check(&self.repo_path, rules).await?;
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if report.passed() { "success" } else { "failed" },
+                    "data": report,
+                }))?
+            );
+        } else if report.passed() {
+            println!(
+                "{} {} commit(s) since {} are clean",
+                "✓".bright_green().bold(),
+                report.commits,
+                report.base
+            );
+        } else {
+            println!(
+                "{} {} problem(s) in {} commit(s) since {}",
+                "✗".bright_red().bold(),
+                report.violations.len(),
+                report.commits,
+                report.base
+            );
+            for violation in &report.violations {
+                println!("  {}", violation.to_line());
+                println!("    {} {}", "fix:".bright_cyan(), violation.fix);
+            }
+        }
+        if !report.passed() {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+
+    /// JSON report, or a summary plus the output of failed checks (of every
+    /// check with `all_output`).
+    fn print_quality_report(&self, report: &CheckReport, all_output: bool) -> Result<()> {
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if report.passed() { "success" } else { "failed" },
+                    "data": report,
+                }))?
+            );
+            return Ok(());
+        }
+
+        for row in &report.checks {
+            if (all_output || row.state == CheckState::Failed) && !row.output_tail.is_empty() {
+                println!("\n{} {}", "──".dimmed(), row.name.bold());
+                println!("{}", row.output_tail);
+            }
+        }
+        for row in &report.checks {
+            let Some(comparison) = &row.baseline else {
+                continue;
+            };
+            println!(
+                "\n{} {}: {}",
+                "baseline".bright_cyan(),
+                row.name.bold(),
+                crate::t!(
+                    "quality-baseline-summary",
+                    found = comparison.found,
+                    allowed = comparison.allowed,
+                    new = comparison.new.len(),
+                    fixed = comparison.fixed.len()
+                )
+            );
+            for issue in comparison.new.iter().take(BASELINE_LISTED_ISSUES) {
+                println!("  {} {}", "+".bright_red(), issue);
+            }
+            if comparison.new.len() > BASELINE_LISTED_ISSUES {
+                println!(
+                    "  {}",
+                    crate::t!(
+                        "quality-more-issues",
+                        count = comparison.new.len() - BASELINE_LISTED_ISSUES
+                    )
+                );
+            }
+        }
+        let count = |state| report.checks.iter().filter(|c| c.state == state).count();
+        let summary = crate::t!(
+            "quality-summary",
+            passed = count(CheckState::Passed),
+            failed = count(CheckState::Failed),
+            cancelled = count(CheckState::Cancelled)
+        );
+        if report.passed() {
+            println!(
+                "\n{} {}",
+                crate::t!("quality-pass").bright_green().bold(),
+                summary
+            );
+        } else {
+            println!(
+                "\n{} {}",
+                crate::t!("quality-fail").bright_red().bold(),
+                summary
+            );
+        }
+        Ok(())
+    }
+}
+
+/// New issues listed per check under its baseline summary.
+const BASELINE_LISTED_ISSUES: usize = 20;
+
+/// The check called `name`, running its fix command when `fix` is set.
+fn pick(checks: &[QualityCheck], name: &str, fix: bool) -> Result<QualityCheck> {
+    let mut check = checks
+        .iter()
+        .find(|c| c.name == name)
+        .cloned()
+        .ok_or_else(|| anyhow!("No '{}' check configured in quality.checks", name))?;
+    if fix {
+        check.command = check
+            .fix
+            .clone()
+            .ok_or_else(|| anyhow!("The '{}' check has no fix command", name))?;
+    }
+    Ok(check)
+}
+
+/// Extra cargo flags only make sense for cargo checks.
+fn append_cargo_args(check: &mut QualityCheck, args: &[String]) -> Result<()> {
+    if args.is_empty() {
+        return Ok(());
+    }
+    if !check.cargo {
+        anyhow::bail!(
+            "'{}' is not a cargo check; run its command directly to pass extra flags",
+            check.name
+        );
+    }
+    check.command = format!("{} {}", check.command, args.join(" "));
+    Ok(())
+}
+
+/// Check progress on stdout: redrawn in place on a terminal, one line per
+/// finished check otherwise.
+struct ProgressTable {
+    live: bool,
+    rows: Mutex<Vec<CheckResult>>,
+    /// Lines drawn last time (live) or checks already reported (not live).
+    drawn: Mutex<usize>,
+    reported: Mutex<Vec<String>>,
+}
+
+impl ProgressTable {
+    fn new(live: bool) -> Self {
+        Self {
+            live,
+            rows: Mutex::new(Vec::new()),
+            drawn: Mutex::new(0),
+            reported: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn update(&self, rows: &[CheckResult]) {
+        *self.rows.lock().unwrap_or_else(|e| e.into_inner()) = rows.to_vec();
+        if self.live {
+            self.redraw();
+            return;
+        }
+        let mut reported = self.reported.lock().unwrap_or_else(|e| e.into_inner());
+        for row in rows.iter().filter(|r| r.state.is_done()) {
+            if !reported.contains(&row.name) {
+                reported.push(row.name.clone());
+                println!("{}", format_row(row));
+            }
+        }
+    }
+
+    fn redraw(&self) {
+        if !self.live {
+            return;
+        }
+        let rows = self.rows.lock().unwrap_or_else(|e| e.into_inner());
+        let mut drawn = self.drawn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = std::io::stdout().lock();
+        if *drawn > 0 {
+            let _ = write!(out, "\x1b[{}A", *drawn);
+        }
+        for row in rows.iter() {
+            let _ = writeln!(out, "\x1b[2K{}", format_row(row));
+        }
+        let _ = out.flush();
+        *drawn = rows.len();
+    }
+}
+
+fn format_row(row: &CheckResult) -> String {
+    let (icon, state) = match row.state {
+        CheckState::Pending => ("·".dimmed(), "pending".dimmed()),
+        CheckState::Running => ("▶".bright_cyan(), "running".bright_cyan()),
+        CheckState::Passed => ("✓".bright_green(), "passed".bright_green()),
+        CheckState::Failed => ("✗".bright_red(), "failed".bright_red()),
+        CheckState::Cancelled => ("⊘".yellow(), "cancelled".yellow()),
+    };
+    let detail = match (row.state, row.exit_code, row.timed_out) {
+        (CheckState::Failed, _, true) => "timed out".to_string(),
+        (CheckState::Failed, Some(code), _) => format!("exit {code}"),
+        _ => row.command.clone(),
+    };
+    let elapsed = match row.state {
+        CheckState::Pending => String::new(),
+        _ => format!("{:.1}s", row.elapsed().as_secs_f64()),
+    };
+    format!(
+        "  {} {:<10} {:<10} {:>7}  {}",
+        icon,
+        row.name,
+        state,
+        elapsed,
+        detail.dimmed()
+    )
+}
@@ -4,13 +4,23 @@
 //! and says y/n at commit + PR time — no per-task babysitting.
 
 use super::super::*;
-use super::queue_state::{ClaimState, QUEUE_FILE, QueueState, QueueTask, load_queue};
+use super::queue_state::{
+    ClaimState, OverBudget, QUEUE_FILE, QueueState, QueueTask, TaskAttempt, TimeBox, load_queue,
+};
+use super::task_dedup::{duplicate_error, find_duplicates, warn_duplicates};
+use super::{blockers, time_box};
+use crate::governance::human::ASSIGNED_STATE;
+use crate::notify::{self, Notification, NotifyEvent};
 use crate::run_id::validate_run_id;
 use crate::tracker::{default_tracker_name, resolve_tracker};
+use crate::workflow::acceptance::{self, Acceptance, Criterion, REJECTED};
+use crate::workflow::cost_estimate;
+use crate::workflow::preflight::PreflightConfig;
+use crate::workflow::retry_policy::{AttemptPlan, RetryPolicy};
 use chrono::Utc;
 use serde::Serialize;
 use std::path::Path;
-use tracing::info;
+use tracing::{Instrument, info};
 
 type TrackerResolver = fn(&str) -> Result<Box<dyn crate::tracker::TrackerAdapter>>;
 
@@ -43,6 +53,10 @@ pub struct DispatchPlan {
 struct ReconcileOnlyReport {
     active_runs: ReconcileReport,
     retry_queue: DispatchPlan,
+    /// Paused (over-budget) tasks whose resume was approved.
+    resumed: usize,
+    /// Blocked tasks whose dependency completed or credential appeared.
+    unblocked: usize,
 }
 
 struct QueueDrainOptions<'a> {
@@ -53,6 +67,7 @@ struct QueueDrainOptions<'a> {
     create_pr: bool,
     reconcile_only: bool,
     approval_gate: Option<std::time::Duration>,
+    max_task_usd: Option<f64>,
 }
 
 pub async fn reconcile_active_runs(state: &QueueState, runs_dir: &Path) -> Result<ReconcileReport> {
@@ -85,6 +100,15 @@ pub async fn reconcile_active_runs(state: &QueueState, runs_dir: &Path) -> Resul
             {
                 report.released += 1;
             }
+        } else if owner_exited(&runs_dir.join(&run_id)) {
+            // The process that claimed the task died mid-run (crash, kill, reboot):
+            // hand the task back to the queue instead of leaving it claimed forever.
+            if state
+                .requeue_orphaned(&task.id, &run_id, started_at_ms)
+                .await?
+            {
+                report.requeued += 1;
+            }
         } else {
             report.kept_running += 1;
         }
@@ -93,6 +117,11 @@ pub async fn reconcile_active_runs(state: &QueueState, runs_dir: &Path) -> Resul
     Ok(report)
 }
 
+/// Whether the run recorded the pid of its process and that process is gone.
+fn owner_exited(run_dir: &Path) -> bool {
+    run_dir.join(crate::events::RUN_PID_FILE).exists() && crate::events::run_pid(run_dir).is_none()
+}
+
 pub async fn reconcile_retry_queue(state: &QueueState) -> Result<DispatchPlan> {
     let queue = state.load().await?;
     let now = now_ms();
@@ -116,15 +145,21 @@ pub async fn reconcile_retry_queue(state: &QueueState) -> Result<DispatchPlan> {
 
 async fn run_reconciliation_pass(
     state: &QueueState,
-    runs_dir: &Path,
+    repo: &Path,
+    preflight: &PreflightConfig,
 ) -> Result<ReconcileOnlyReport> {
-    let active_runs = reconcile_active_runs(state, runs_dir).await?;
+    let runs_dir = repo.join(".ccswarm").join("runs");
+    let active_runs = reconcile_active_runs(state, &runs_dir).await?;
     tracing::info!(report = ?active_runs, "reconciliation pass complete");
     let retry_queue = reconcile_retry_queue(state).await?;
     tracing::info!(report = ?retry_queue, "reconciliation pass complete");
+    let resumed = time_box::reconcile_paused(state, repo).await?;
+    let unblocked = blockers::reconcile_blocked(state, repo, preflight).await?;
     Ok(ReconcileOnlyReport {
         active_runs,
         retry_queue,
+        resumed,
+        unblocked,
     })
 }
 
@@ -185,6 +220,113 @@ async fn run_summary_terminal_status(
     }
 }
 
+/// Pending tasks ready to run (see [`QueueFile::ready_tasks`]) and the IDs of
+/// those still waiting on a dependency.
+async fn load_pending(path: &Path) -> Result<(Vec<QueueTask>, Vec<String>)> {
+    let queue = load_queue(path).await?;
+    Ok((queue.ready_tasks(), queue.blocked_ids()))
+}
+
+/// Earliest `due_at_ms` among tasks waiting out a retry backoff.
+async fn next_retry_due(state: &QueueState) -> Result<Option<u64>> {
+    let queue = state.load().await?;
+    Ok(queue
+        .tasks
+        .iter()
+        .filter_map(|task| match task.claim {
+            ClaimState::RetryQueued { due_at_ms, .. } => Some(due_at_ms),
+            _ => None,
+        })
+        .min())
+}
+
+/// Record a failed attempt and, if the task type's policy allows another one,
+/// park the task in `RetryQueued` until its backoff expires. Returns the backoff
+/// when a retry was scheduled; `None` means the caller should mark the task failed.
+pub(crate) async fn schedule_retry(
+    state: &QueueState,
+    policy: &RetryPolicy,
+    task_id: &str,
+    mut attempt: TaskAttempt,
+) -> Result<Option<std::time::Duration>> {
+    let Some(delay) = policy.next_retry(attempt.attempt) else {
+        state.record_attempt(task_id, attempt).await?;
+        return Ok(None);
+    };
+
+    let retry_at = Utc::now() + chrono::Duration::from_std(delay)?;
+    let next_attempt = attempt.attempt + 1;
+    attempt.retry_at = Some(retry_at);
+    state.record_attempt(task_id, attempt).await?;
+    state
+        .mark_retry_queued(
+            task_id,
+            next_attempt,
+            retry_at.timestamp_millis().max(0) as u64,
+        )
+        .await?;
+    Ok(Some(delay))
+}
+
+pub(crate) fn print_retry_plan(plan: &AttemptPlan) {
+    if plan.attempt <= 1 {
+        return;
+    }
+    let mut details = Vec::new();
+    if let Some(model) = &plan.model {
+        details.push(format!("model={model}"));
+    }
+    if let Some(mode) = &plan.think_mode {
+        details.push(format!("think={mode}"));
+    }
+    println!(
+        "  {} attempt {}{}",
+        "↻".bright_yellow(),
+        plan.attempt,
+        if details.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", details.join(", "))
+        }
+    );
+}
+
+pub(crate) fn print_retry_scheduled(
+    task_id: &str,
+    error: &str,
+    attempt: u32,
+    delay: std::time::Duration,
+) {
+    println!(
+        "{} {} attempt {} failed: {} — retrying in {}s",
+        "↻".bright_yellow().bold(),
+        task_id.bright_yellow(),
+        attempt,
+        error,
+        delay.as_secs()
+    );
+}
+
+/// Print what a run cost next to its estimate, when its events recorded any usage.
+fn print_actual_cost(
+    repo: &Path,
+    run_id: &str,
+    estimate: &cost_estimate::CostEstimate,
+    config: &cost_estimate::CostConfig,
+) {
+    let Ok(Some(usage)) = cost_estimate::Usage::of_run(&repo.join(".ccswarm/runs").join(run_id))
+    else {
+        return;
+    };
+    if let Some(actual) = usage.usd(&config.price(&estimate.model)) {
+        println!(
+            "  {} {}",
+            "$".bright_black(),
+            format!("actual ${actual:.2} (estimated ${:.2})", estimate.usd).bright_black()
+        );
+    }
+}
+
 fn issue_to_queue_body(issue: crate::tracker::NormalizedIssue) -> String {
     format!(
         "{}\n\n{}",
@@ -202,19 +344,90 @@ impl CliRunner {
                 from_issue,
                 file,
                 flow,
+                time_budget,
+                on_budget,
+                allow_duplicate,
+                assign,
+                regression_since,
+                accept_cmd,
+                accept_file,
+                accept_url,
+                max_bounces,
             } => {
-                self.queue_add(
-                    &path,
-                    task,
-                    from_issue.as_deref(),
-                    file.as_deref(),
-                    flow.as_deref(),
-                )
-                .await
+                let time_box = time_budget
+                    .map(|budget_secs| {
+                        Ok::<_, anyhow::Error>(TimeBox {
+                            budget_secs,
+                            on_exceeded: on_budget.parse::<OverBudget>()?,
+                        })
+                    })
+                    .transpose()?;
+                if let Some(human) = assign
+                    && !self.config.humans.contains_key(human)
+                {
+                    return Err(anyhow!(
+                        "'{human}' is not a registered human; run `ccswarm human register {human}`"
+                    ));
+                }
+                let id = self
+                    .queue_add(
+                        &path,
+                        task,
+                        from_issue.as_deref(),
+                        file.as_deref(),
+                        flow.as_deref(),
+                        time_box,
+                        *allow_duplicate,
+                    )
+                    .await?;
+                if let Some(good) = regression_since {
+                    QueueState::new(path.clone())
+                        .update_task(&id, |task| {
+                            task.labels
+                                .extend(crate::workflow::bisect::regression_labels(good));
+                            Ok(())
+                        })
+                        .await?;
+                    println!("   regression since {good}: draining bisects to the culprit");
+                }
+                let criteria: Vec<Criterion> = accept_cmd
+                    .iter()
+                    .map(|command| Criterion::Command {
+                        command: command.clone(),
+                    })
+                    .chain(
+                        accept_file
+                            .iter()
+                            .map(|file| Criterion::File { file: file.clone() }),
+                    )
+                    .chain(accept_url.iter().map(|endpoint| Criterion::Endpoint {
+                        endpoint: endpoint.clone(),
+                    }))
+                    .collect();
+                if !criteria.is_empty() {
+                    let count = criteria.len();
+                    QueueState::new(path.clone())
+                        .update_task(&id, |task| {
+                            task.acceptance = Some(Acceptance {
+                                criteria,
+                                max_bounces: *max_bounces,
+                            });
+                            Ok(())
+                        })
+                        .await?;
+                    println!("   {count} acceptance criteria, checked when the agent finishes");
+                }
+                match assign {
+                    Some(human) => self.assign_queue_task(&path, &id, human).await,
+                    None => Ok(()),
+                }
             }
             QueueAction::List => self.queue_list(&path).await,
             QueueAction::Clear => self.queue_clear(&path).await,
             QueueAction::Release { id, reason } => self.queue_release(&path, id, reason).await,
+            QueueAction::Unblock { id, note } => {
+                self.queue_unblock(&path, id, note.as_deref()).await
+            }
             QueueAction::Drain {
                 flow,
                 timeout,
@@ -224,6 +437,7 @@ impl CliRunner {
                 reconcile_only,
                 require_approval,
                 approval_timeout,
+                max_task_cost,
             } => {
                 self.queue_drain(
                     &path,
@@ -236,6 +450,7 @@ impl CliRunner {
                         reconcile_only: *reconcile_only,
                         approval_gate: require_approval
                             .then(|| std::time::Duration::from_secs(*approval_timeout)),
+                        max_task_usd: max_task_cost.or(self.config.cost.max_task_usd),
                     },
                 )
                 .await
@@ -243,6 +458,7 @@ impl CliRunner {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn queue_add(
         &self,
         path: &std::path::Path,
@@ -250,7 +466,9 @@ impl CliRunner {
         from_issue: Option<&str>,
         file: Option<&std::path::Path>,
         flow: Option<&str>,
-    ) -> Result<()> {
+        time_box: Option<TimeBox>,
+        allow_duplicate: bool,
+    ) -> Result<String> {
         let tracker_name = default_tracker_name();
         self.queue_add_with_tracker_resolver(
             path,
@@ -258,6 +476,8 @@ impl CliRunner {
             from_issue,
             file,
             flow,
+            time_box,
+            allow_duplicate,
             QueueAddTracker {
                 name: &tracker_name,
                 resolver: resolve_tracker,
@@ -266,6 +486,7 @@ impl CliRunner {
         .await
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn queue_add_with_tracker_resolver(
         &self,
         path: &std::path::Path,
@@ -273,8 +494,10 @@ impl CliRunner {
         from_issue: Option<&str>,
         file: Option<&std::path::Path>,
         flow: Option<&str>,
+        time_box: Option<TimeBox>,
+        allow_duplicate: bool,
         tracker: QueueAddTracker<'_>,
-    ) -> Result<()> {
+    ) -> Result<String> {
         // Input precedence: --from-issue > --file > `-` (stdin) > positional argument.
         // Allowing all four would be confusing; we error if >1 is given.
         let sources_count = [from_issue.is_some(), file.is_some(), task == "-"]
@@ -320,13 +543,28 @@ impl CliRunner {
             completed_at: None,
             run_id: None,
             claim: ClaimState::default(),
+            attempts: Vec::new(),
+            time_box,
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker: None,
+            acceptance: None,
         };
+        let mut duplicates = Vec::new();
         QueueState::new(path.to_path_buf())
             .update_queue(|queue| {
+                duplicates = find_duplicates(queue, &body);
+                if let Some(err) = duplicate_error(&body, &duplicates, allow_duplicate) {
+                    return Err(err);
+                }
                 queue.tasks.push(entry);
                 Ok(())
             })
             .await?;
+        warn_duplicates(&duplicates);
         let preview = body.lines().next().unwrap_or("").trim();
         println!(
             "{} queued {} — {}",
@@ -334,7 +572,13 @@ impl CliRunner {
             id.bright_cyan(),
             preview
         );
-        Ok(())
+        if let Some(time_box) = time_box {
+            println!(
+                "   time budget {}s, then {}",
+                time_box.budget_secs, time_box.on_exceeded
+            );
+        }
+        Ok(id)
     }
 
     async fn queue_list(&self, path: &std::path::Path) -> Result<()> {
@@ -370,6 +614,9 @@ impl CliRunner {
                 "running" => t.state.bright_blue(),
                 "completed" => t.state.bright_green(),
                 "failed" => t.state.bright_red(),
+                time_box::PAUSED_STATE => t.state.bright_magenta(),
+                blockers::BLOCKED_STATE => t.state.red(),
+                ASSIGNED_STATE => t.state.bright_cyan(),
                 _ => t.state.bright_white(),
             };
             let preview = t
@@ -385,14 +632,23 @@ impl CliRunner {
                 .as_deref()
                 .map(|r| r.chars().take(8).collect::<String>())
                 .unwrap_or_else(|| "-".into());
+            let after = if t.depends_on.is_empty() {
+                String::new()
+            } else {
+                format!(" (after {})", t.depends_on.join(", "))
+            };
             println!(
-                "{:<12}  {:<9}  {:<10}  {:<10}  {}",
+                "{:<12}  {:<9}  {:<10}  {:<10}  {}{}",
                 t.id.bright_cyan(),
                 state,
                 t.flow.as_deref().unwrap_or("-"),
                 run_short.bright_black(),
-                preview
+                preview,
+                after.bright_black()
             );
+            if let Some(blocker) = &t.blocker {
+                println!("{:<12}  {} {}", "", "⛔".red(), blocker.summary().red());
+            }
         }
         println!();
         println!("Total: {} task(s)", queue.tasks.len());
@@ -446,25 +702,26 @@ impl CliRunner {
         path: &std::path::Path,
         options: QueueDrainOptions<'_>,
     ) -> Result<()> {
+        self.recover_abandoned().await;
         let queue_state = QueueState::new(path.to_path_buf());
-        let runs_dir = self.repo_path.join(".ccswarm").join("runs");
-        let reconcile_report = run_reconciliation_pass(&queue_state, &runs_dir).await?;
+        let reconcile_report =
+            run_reconciliation_pass(&queue_state, &self.repo_path, &self.config.preflight).await?;
 
         if options.reconcile_only {
             println!("{}", serde_json::to_string_pretty(&reconcile_report)?);
             return Ok(());
         }
 
-        let queue = load_queue(path).await?;
-        let pending: Vec<QueueTask> = queue
-            .tasks
-            .iter()
-            .filter(|task| task.state == "pending")
-            .cloned()
-            .collect();
-
-        if pending.is_empty() {
-            println!("No pending tasks to drain.");
+        let (mut pending, mut blocked) = load_pending(path).await?;
+        if pending.is_empty() && next_retry_due(&queue_state).await?.is_none() {
+            if blocked.is_empty() {
+                println!("No pending tasks to drain.");
+            } else {
+                println!(
+                    "No tasks ready to drain; {} waiting on dependencies.",
+                    blocked.len()
+                );
+            }
             return Ok(());
         }
 
@@ -485,136 +742,420 @@ impl CliRunner {
 
         let mut ok = 0usize;
         let mut ng = 0usize;
-
-        for queued_task in pending {
-            let task_id = queued_task.id.clone();
-            let task_body = queued_task.task.clone();
-            let flow = options
-                .flow_override
-                .map(String::from)
-                .or_else(|| queued_task.flow.clone())
-                .unwrap_or_else(|| "default".to_string());
-            let run_id = uuid::Uuid::new_v4().to_string();
-
-            println!();
-            println!(
-                "{} {} flow={} auto_commit={} create_pr={}",
-                "▶".bright_cyan().bold(),
-                task_id.bright_yellow(),
-                flow.bright_white(),
-                auto_commit,
-                options.create_pr
-            );
-
-            if !queue_state.try_claim(&task_id, &run_id).await? {
-                info!(task_id = %task_id, "skipping: already claimed by another drain");
-                continue;
-            }
-
-            if let Err(e) = queue_state
-                .update_task(&task_id, |task| {
-                    task.state = "running".to_string();
-                    Ok(())
-                })
-                .await
-            {
-                queue_state
-                    .release(&task_id, &format!("failed: {e}"))
-                    .await?;
-                return Err(e);
-            }
-
-            let result = self
-                .handle_pipeline_returning_reserved_id(
-                    &run_id,
-                    &task_body,
-                    &flow,
-                    "text",
-                    options.timeout,
-                    false,
-                    None,
-                    false,
-                    None,
-                    None, // run_budget_tokens
-                    None, // model override
-                    auto_commit,
-                    options.create_pr,
-                    options.approval_gate,
-                )
-                .await;
-
-            match result {
-                Ok(run_id) => {
-                    if let Err(e) = queue_state
-                        .update_task(&task_id, |task| {
-                            task.state = "completed".to_string();
-                            task.completed_at = Some(Utc::now());
-                            // #43 fix: record run_id so `queue list` can show it.
-                            task.run_id = Some(run_id.clone());
-                            Ok(())
-                        })
+        // Tasks over the cost ceiling stay pending for a later drain; later
+        // passes of this one leave them alone instead of re-estimating them.
+        let mut over_ceiling = std::collections::HashSet::new();
+
+        loop {
+            for queued_task in pending {
+                let task_id = queued_task.id.clone();
+                if let Some(good) = crate::workflow::bisect::regression_since(&queued_task.labels) {
+                    println!();
+                    println!(
+                        "{} {} regression since {}",
+                        "▶".bright_cyan().bold(),
+                        task_id.bright_yellow(),
+                        good
+                    );
+                    match self
+                        .drain_regression(&queue_state, &queued_task, good)
                         .await
                     {
-                        queue_state
-                            .release(&task_id, &format!("failed: {e}"))
-                            .await?;
-                        return Err(e);
+                        Ok(None) => {}
+                        Ok(Some(true)) => ok += 1,
+                        Ok(Some(false)) => ng += 1,
+                        Err(e) => {
+                            ng += 1;
+                            println!(
+                                "{} {} failed: {}",
+                                "✗".bright_red().bold(),
+                                task_id.bright_yellow(),
+                                e
+                            );
+                        }
                     }
-                    queue_state.release(&task_id, "completed").await?;
-                    ok += 1;
+                    continue;
+                }
+                let flow = options
+                    .flow_override
+                    .map(String::from)
+                    .or_else(|| queued_task.flow.clone())
+                    .unwrap_or_else(|| "default".to_string());
+                let policy = self.config.retry.policy_for(&flow);
+                let plan = time_box::plan_next(&queued_task, policy);
+                let task_body = time_box::task_text(&queued_task, &plan);
+                let run_id = uuid::Uuid::new_v4().to_string();
+                // The time box, not the drain-wide timeout, bounds a budgeted task.
+                let budget = queued_task.time_box.as_ref().map(TimeBox::budget);
+                let pipeline_timeout = queued_task
+                    .time_box
+                    .map_or(options.timeout, |tb| options.timeout.max(tb.budget_secs));
+
+                println!();
+                println!(
+                    "{} {} flow={} auto_commit={} create_pr={}",
+                    "▶".bright_cyan().bold(),
+                    task_id.bright_yellow(),
+                    flow.bright_white(),
+                    auto_commit,
+                    options.create_pr
+                );
+                print_retry_plan(&plan);
+
+                let model = plan
+                    .model
+                    .clone()
+                    .unwrap_or_else(|| self.config.cost.default_model.clone());
+                let queue = queue_state.load().await?;
+                let history = cost_estimate::past_tasks(&self.repo_path, queue.finished_runs());
+                let estimate =
+                    cost_estimate::estimate(&queued_task.task, &model, &history, &self.config.cost);
+                println!(
+                    "  {} {}",
+                    "$".bright_black(),
+                    estimate.describe().bright_black()
+                );
+                if let Some(ceiling) = options.max_task_usd
+                    && estimate.usd > ceiling
+                {
+                    over_ceiling.insert(task_id.clone());
                     println!(
-                        "{} {} completed (run {})",
-                        "✓".bright_green().bold(),
+                        "{} {} estimated at ${:.2}, over the ${:.2} per-task ceiling; left pending",
+                        "⏸".bright_yellow().bold(),
                         task_id.bright_yellow(),
-                        run_id.chars().take(8).collect::<String>().bright_black()
+                        estimate.usd,
+                        ceiling
                     );
+                    continue;
                 }
-                Err(e) => {
-                    let error_message = e.to_string();
-                    if let Err(update_error) = queue_state
-                        .update_task(&task_id, |task| {
-                            task.state = "failed".to_string();
-                            task.completed_at = Some(Utc::now());
-                            task.run_id = Some(run_id.clone());
-                            Ok(())
-                        })
-                        .await
-                    {
+                let run_budget_tokens = options
+                    .max_task_usd
+                    .map(|ceiling| estimate.token_cap(ceiling, &self.config.cost));
+
+                if !queue_state.try_claim(&task_id, &run_id).await? {
+                    info!(task_id = %task_id, "skipping: already claimed by another drain");
+                    continue;
+                }
+
+                match self
+                    .park_if_unavailable(&queue_state, &task_id, &queued_task.labels)
+                    .await
+                {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => {
                         queue_state
-                            .release(&task_id, &format!("failed: {update_error}"))
+                            .release(&task_id, &format!("failed: {e}"))
                             .await?;
-                        return Err(update_error);
+                        return Err(e);
                     }
+                }
+
+                if let Err(e) = queue_state
+                    .update_task(&task_id, |task| {
+                        task.state = "running".to_string();
+                        Ok(())
+                    })
+                    .await
+                {
                     queue_state
-                        .release(&task_id, &format!("failed: {error_message}"))
+                        .release(&task_id, &format!("failed: {e}"))
                         .await?;
-                    ng += 1;
-                    println!(
-                        "{} {} failed: {}",
-                        "✗".bright_red().bold(),
-                        task_id.bright_yellow(),
-                        error_message
-                    );
-                    if options.fail_fast {
-                        return Err(anyhow!(
-                            "queue drain stopped: {} tasks ok, {} failed",
-                            ok,
-                            ng
-                        ));
+                    return Err(e);
+                }
+
+                if let Err(e) = cost_estimate::record(
+                    &self.repo_path,
+                    &cost_estimate::EstimateRecord {
+                        run_id: run_id.clone(),
+                        task_id: Some(task_id.clone()),
+                        estimate: estimate.clone(),
+                        recorded_at: Utc::now(),
+                    },
+                )
+                .await
+                {
+                    warn!("Failed to record cost estimate: {e}");
+                }
+
+                let started_at = Utc::now();
+                let pipeline = self
+                    .handle_pipeline_returning_reserved_id(
+                        &run_id,
+                        &task_body,
+                        &flow,
+                        "text",
+                        pipeline_timeout,
+                        false,
+                        None,
+                        false,
+                        None,
+                        run_budget_tokens,
+                        plan.model.as_deref(),
+                        auto_commit,
+                        options.create_pr,
+                        options.approval_gate,
+                    )
+                    .instrument(tracing::info_span!(
+                        "queue.task",
+                        task_id = %task_id,
+                        flow = %flow,
+                        attempt = plan.attempt,
+                        run_id = %run_id,
+                    ));
+                let Some(result) = time_box::within_budget(budget, pipeline).await else {
+                    let action = match time_box::pause_over_budget(
+                        &queue_state,
+                        &self.repo_path,
+                        &queued_task,
+                        &plan,
+                        &run_id,
+                        started_at,
+                        "drain",
+                    )
+                    .await
+                    {
+                        Ok(action) => action,
+                        Err(e) => {
+                            queue_state
+                                .release(&task_id, &format!("failed: {e}"))
+                                .await?;
+                            return Err(e);
+                        }
+                    };
+                    time_box::print_over_budget(&queued_task, action);
+                    continue;
+                };
+                print_actual_cost(&self.repo_path, &run_id, &estimate, &self.config.cost);
+
+                // The agent says it is done; its acceptance criteria decide.
+                let result = match (result, &queued_task.acceptance) {
+                    (Ok(run_id), Some(acceptance)) => {
+                        match self.accept(&queued_task, acceptance, &run_id).await {
+                            Ok(None) => Ok(run_id),
+                            Ok(Some(failures)) => {
+                                let rejections = queued_task
+                                    .attempts
+                                    .iter()
+                                    .filter(|a| a.outcome == REJECTED)
+                                    .count()
+                                    as u32;
+                                let mut attempt = TaskAttempt::finished(
+                                    &plan,
+                                    &run_id,
+                                    started_at,
+                                    Some(failures.clone()),
+                                );
+                                if rejections < acceptance.max_bounces {
+                                    attempt.outcome = REJECTED.to_string();
+                                    attempt.retry_at = Some(Utc::now());
+                                    queue_state.record_attempt(&task_id, attempt).await?;
+                                    queue_state
+                                        .mark_retry_queued(&task_id, plan.attempt + 1, now_ms())
+                                        .await?;
+                                    println!(
+                                        "{} {} sent back: acceptance criteria not met\n{}",
+                                        "↩".bright_yellow().bold(),
+                                        task_id.bright_yellow(),
+                                        failures
+                                    );
+                                    continue;
+                                }
+                                Err(anyhow!("acceptance criteria not met:\n{failures}"))
+                            }
+                            Err(e) => Err(e),
+                        }
+                    }
+                    (result, _) => result,
+                };
+
+                match result {
+                    Ok(run_id) => {
+                        if let Err(e) = queue_state
+                            .update_task(&task_id, |task| {
+                                task.state = "completed".to_string();
+                                task.completed_at = Some(Utc::now());
+                                // #43 fix: record run_id so `queue list` can show it.
+                                task.run_id = Some(run_id.clone());
+                                task.attempts
+                                    .push(TaskAttempt::finished(&plan, &run_id, started_at, None));
+                                Ok(())
+                            })
+                            .await
+                        {
+                            queue_state
+                                .release(&task_id, &format!("failed: {e}"))
+                                .await?;
+                            return Err(e);
+                        }
+                        queue_state.release(&task_id, "completed").await?;
+                        ok += 1;
+                        println!(
+                            "{} {} completed (run {})",
+                            "✓".bright_green().bold(),
+                            task_id.bright_yellow(),
+                            run_id.chars().take(8).collect::<String>().bright_black()
+                        );
+                        notify::send(
+                            &self.config.notifications,
+                            Notification::new(
+                                NotifyEvent::TaskCompleted,
+                                format!("ccswarm: {task_id} completed"),
+                                queued_task.task.lines().next().unwrap_or_default(),
+                            ),
+                        )
+                        .await;
+                    }
+                    Err(e) => {
+                        let error_message = e.to_string();
+                        let attempt = TaskAttempt::finished(
+                            &plan,
+                            &run_id,
+                            started_at,
+                            Some(error_message.clone()),
+                        );
+                        match self
+                            .park_if_blocked(&queue_state, &task_id, &run_id, &attempt)
+                            .await
+                        {
+                            Ok(true) => continue,
+                            Ok(false) => {}
+                            Err(park_error) => {
+                                queue_state
+                                    .release(&task_id, &format!("failed: {park_error}"))
+                                    .await?;
+                                return Err(park_error);
+                            }
+                        }
+                        match schedule_retry(&queue_state, policy, &task_id, attempt).await {
+                            Ok(Some(delay)) => {
+                                print_retry_scheduled(
+                                    &task_id,
+                                    &error_message,
+                                    plan.attempt,
+                                    delay,
+                                );
+                                continue;
+                            }
+                            Ok(None) => {}
+                            Err(schedule_error) => {
+                                queue_state
+                                    .release(&task_id, &format!("failed: {schedule_error}"))
+                                    .await?;
+                                return Err(schedule_error);
+                            }
+                        }
+                        if let Err(update_error) = queue_state
+                            .update_task(&task_id, |task| {
+                                task.state = "failed".to_string();
+                                task.completed_at = Some(Utc::now());
+                                task.run_id = Some(run_id.clone());
+                                Ok(())
+                            })
+                            .await
+                        {
+                            queue_state
+                                .release(&task_id, &format!("failed: {update_error}"))
+                                .await?;
+                            return Err(update_error);
+                        }
+                        queue_state
+                            .release(&task_id, &format!("failed: {error_message}"))
+                            .await?;
+                        ng += 1;
+                        println!(
+                            "{} {} failed: {}",
+                            "✗".bright_red().bold(),
+                            task_id.bright_yellow(),
+                            error_message
+                        );
+                        if options.fail_fast {
+                            return Err(anyhow!(
+                                "queue drain stopped: {} tasks ok, {} failed",
+                                ok,
+                                ng
+                            ));
+                        }
                     }
                 }
             }
+
+            // Tasks whose dependencies completed during this pass run next.
+            let (ready, still_blocked) = load_pending(path).await?;
+            let unblocked: Vec<QueueTask> = ready
+                .into_iter()
+                .filter(|task| blocked.contains(&task.id))
+                .collect();
+            blocked = still_blocked;
+            if !unblocked.is_empty() {
+                pending = unblocked;
+                continue;
+            }
+
+            // Retries scheduled during this pass belong to the same drain: wait out
+            // the earliest backoff, then pick up whatever became due.
+            let Some(due_at_ms) = next_retry_due(&queue_state).await? else {
+                break;
+            };
+            let wait = std::time::Duration::from_millis(due_at_ms.saturating_sub(now_ms()));
+            println!();
+            println!(
+                "{} waiting {}s for the next retry",
+                "…".bright_black(),
+                wait.as_secs()
+            );
+            tokio::time::sleep(wait).await;
+            reconcile_retry_queue(&queue_state).await?;
+            (pending, blocked) = load_pending(path).await?;
+            pending.retain(|task| !over_ceiling.contains(&task.id));
         }
 
         println!();
         println!(
-            "{} queue drained — {} ok, {} failed",
+            "{} queue drained — {} ok, {} failed{}",
             "✓".bright_green().bold(),
             ok,
-            ng
+            ng,
+            if !over_ceiling.is_empty() {
+                format!(", {} over the cost ceiling", over_ceiling.len())
+            } else {
+                String::new()
+            }
         );
         Ok(())
     }
+
+    /// Check a finished run against the task's acceptance criteria. Returns
+    /// the unmet ones, or `None` when all pass.
+    async fn accept(
+        &self,
+        task: &QueueTask,
+        acceptance: &Acceptance,
+        run_id: &str,
+    ) -> Result<Option<String>> {
+        println!(
+            "  {} checking {} acceptance criteria",
+            "…".bright_black(),
+            acceptance.criteria.len()
+        );
+        let verification = acceptance::verify(&acceptance.criteria, &self.repo_path).await;
+        let run_dir = self.repo_path.join(".ccswarm").join("runs").join(run_id);
+        if let Err(e) = verification.save(&run_dir).await {
+            warn!(task_id = %task.id, "Failed to save acceptance results: {e}");
+        }
+        for result in &verification.results {
+            println!(
+                "    {} {}",
+                if result.passed {
+                    "✓".bright_green()
+                } else {
+                    "✗".bright_red()
+                },
+                result.criterion
+            );
+        }
+        Ok((!verification.passed()).then(|| verification.failures()))
+    }
 }
 
 #[cfg(test)]
@@ -637,6 +1178,15 @@ mod tests {
             completed_at: None,
             run_id: None,
             claim,
+            attempts: Vec::new(),
+            time_box: None,
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker: None,
+            acceptance: None,
         }
     }
 
@@ -711,6 +1261,8 @@ mod tests {
                 Some("TEAM-42"),
                 None,
                 Some("review-fix"),
+                None,
+                false,
                 QueueAddTracker {
                     name: "fake",
                     resolver: fake_tracker_resolver,
@@ -727,6 +1279,28 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_queue_add_blocks_near_duplicate_unless_allowed() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let queue_path = dir.path().join(QUEUE_FILE);
+        let runner = runner_for_repo(dir.path()).await?;
+        let add = |task: &'static str, allow_duplicate: bool| {
+            runner.queue_add(&queue_path, task, None, None, None, None, allow_duplicate)
+        };
+
+        add("Add rate limiting to the login endpoint", false).await?;
+        let err = add("add rate-limiting for login endpoints", false)
+            .await
+            .expect_err("near-duplicate of an open task should be refused");
+        assert!(err.to_string().contains("--allow-duplicate"), "{err}");
+        assert_eq!(load_queue(&queue_path).await?.tasks.len(), 1);
+
+        add("add rate-limiting for login endpoints", true).await?;
+        add("Fix typo in the README", false).await?;
+        assert_eq!(load_queue(&queue_path).await?.tasks.len(), 3);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_queue_add_from_issue_linear_returns_not_implemented() -> Result<()> {
         let dir = tempfile::tempdir()?;
@@ -740,6 +1314,8 @@ mod tests {
                 Some("ENG-123"),
                 None,
                 None,
+                None,
+                false,
                 QueueAddTracker {
                     name: "linear",
                     resolver: resolve_tracker,
@@ -891,6 +1467,40 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_reconcile_requeues_claims_whose_process_exited() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let queue_path = dir.path().join("queue.yaml");
+        let runs_dir = dir.path().join("runs");
+        let started_at_ms = now_ms().saturating_sub(60_000);
+
+        write_queue(
+            &queue_path,
+            vec![queue_task(
+                "q-1",
+                ClaimState::Running {
+                    run_id: "run-crashed".to_string(),
+                    started_at_ms,
+                },
+            )],
+        )
+        .await?;
+        // No process has this pid, so the drain that claimed the task is gone.
+        let run_dir = runs_dir.join("run-crashed");
+        tokio::fs::create_dir_all(&run_dir).await?;
+        tokio::fs::write(run_dir.join(crate::events::RUN_PID_FILE), "999999999").await?;
+
+        let state = QueueState::new(queue_path.clone());
+        let report = reconcile_active_runs(&state, &runs_dir).await?;
+
+        assert_eq!(report.requeued, 1);
+        assert_eq!(report.kept_running, 0);
+        let queue = load_queue(&queue_path).await?;
+        assert_eq!(queue.tasks[0].state, "pending");
+        assert!(state.try_claim("q-1", "run-next").await?);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_reconcile_retry_queue_marks_due_task_pending() -> Result<()> {
         let dir = tempfile::tempdir()?;
@@ -917,6 +1527,54 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_schedule_retry_parks_task_until_budget_is_spent() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let queue_path = dir.path().join("queue.yaml");
+        write_queue(&queue_path, vec![queue_task("q-1", ClaimState::Unclaimed)]).await?;
+        let state = QueueState::new(queue_path.clone());
+        let policy = RetryPolicy {
+            max_retries: 1,
+            initial_backoff_secs: 0,
+            ..RetryPolicy::default()
+        };
+
+        assert!(state.try_claim("q-1", "run-1").await?);
+        let first = TaskAttempt::finished(
+            &policy.plan(1),
+            "run-1",
+            Utc::now(),
+            Some("boom".to_string()),
+        );
+        let delay = schedule_retry(&state, &policy, "q-1", first).await?;
+        assert_eq!(delay, Some(std::time::Duration::ZERO));
+
+        let queue = load_queue(&queue_path).await?;
+        assert!(matches!(
+            queue.tasks[0].claim,
+            ClaimState::RetryQueued { attempt: 2, .. }
+        ));
+        assert_eq!(queue.tasks[0].attempts.len(), 1);
+        assert!(queue.tasks[0].attempts[0].retry_at.is_some());
+
+        assert_eq!(reconcile_retry_queue(&state).await?.ready, 1);
+        assert!(state.try_claim("q-1", "run-2").await?);
+        let queue = load_queue(&queue_path).await?;
+        let second = TaskAttempt::finished(
+            &policy.plan(queue.tasks[0].next_attempt()),
+            "run-2",
+            Utc::now(),
+            Some("boom again".to_string()),
+        );
+        assert_eq!(schedule_retry(&state, &policy, "q-1", second).await?, None);
+
+        let queue = load_queue(&queue_path).await?;
+        assert_eq!(queue.tasks[0].attempts.len(), 2);
+        assert_eq!(queue.tasks[0].attempts[1].attempt, 2);
+        assert!(matches!(queue.tasks[0].claim, ClaimState::Running { .. }));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_reconcile_only_flag_does_not_dispatch() -> Result<()> {
         let dir = tempfile::tempdir()?;
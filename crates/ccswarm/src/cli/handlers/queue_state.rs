@@ -11,8 +11,13 @@ use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock};
+use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
 
+use super::queue_wal::{self, WalRecord};
+use crate::recovery::{Operation, RecoveryJournal};
+use crate::workflow::retry_policy::AttemptPlan;
+
 #[cfg(unix)]
 use nix::fcntl::{Flock, FlockArg};
 #[cfg(unix)]
@@ -51,38 +56,242 @@ pub(crate) struct QueueTask {
     pub(crate) run_id: Option<String>,
     #[serde(default)]
     pub claim: ClaimState,
+    /// Every execution of this task, oldest first. More than one entry means
+    /// the retry policy re-ran it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) attempts: Vec<TaskAttempt>,
+    /// Wall-clock budget for each attempt, if the task declared one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) time_box: Option<TimeBox>,
+    /// Limit on each command run in the task's session, if the task declared
+    /// one; it overrides the session's own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) command_timeout: Option<ai_session::CommandTimeout>,
+    /// Model the task runs on, in place of the one `model_routing` would
+    /// pick.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) model: Option<String>,
+    /// Drain order: higher priorities run first (unset counts as medium).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) priority: Option<crate::agent::Priority>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) labels: Vec<String>,
+    /// Queue task IDs that must complete before this one is drained.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) depends_on: Vec<String>,
+    /// Why the agent stopped, while the task is `blocked`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) blocker: Option<crate::workflow::blocker::Blocker>,
+    /// What the drain checks before accepting the agent's "done".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) acceptance: Option<crate::workflow::acceptance::Acceptance>,
+}
+
+/// Per-attempt wall-clock budget of a queued task.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct TimeBox {
+    pub(crate) budget_secs: u64,
+    #[serde(default)]
+    pub(crate) on_exceeded: OverBudget,
+}
+
+impl TimeBox {
+    pub(crate) fn budget(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.budget_secs)
+    }
+}
+
+/// What happens to a task whose attempt runs out of its time budget.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OverBudget {
+    /// Put the task back in the queue; the next attempt resumes from the checkpoint.
+    #[default]
+    Requeue,
+    /// Requeue with the next think-mode step.
+    Escalate,
+    /// Pause until a human approves or rejects resuming it.
+    Ask,
+}
+
+impl std::fmt::Display for OverBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Requeue => "requeue",
+            Self::Escalate => "escalate",
+            Self::Ask => "ask",
+        })
+    }
+}
+
+impl std::str::FromStr for OverBudget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "requeue" => Ok(Self::Requeue),
+            "escalate" => Ok(Self::Escalate),
+            "ask" => Ok(Self::Ask),
+            other => Err(anyhow::anyhow!(
+                "unknown over-budget action '{other}' (expected requeue, escalate or ask)"
+            )),
+        }
+    }
+}
+
+/// One execution of a queued task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TaskAttempt {
+    pub(crate) attempt: u32,
+    pub(crate) run_id: String,
+    pub(crate) started_at: DateTime<Utc>,
+    pub(crate) ended_at: DateTime<Utc>,
+    /// `completed`, `failed`, `blocked`, `timed_out` when the time budget ran
+    /// out, or `rejected` when the acceptance criteria were not met.
+    pub(crate) outcome: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) think_mode: Option<crate::config::ThinkMode>,
+    /// When the follow-up attempt was scheduled to start, if one was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) retry_at: Option<DateTime<Utc>>,
+    /// Checkpoint written when the attempt was paused, relative to the repo root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) checkpoint: Option<PathBuf>,
+    /// The ccswarm invocation that ran the attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) orchestrator_run_id: Option<crate::run_id::RunId>,
+}
+
+impl TaskAttempt {
+    /// Record for an attempt that just ended; `error` marks it failed.
+    pub(crate) fn finished(
+        plan: &AttemptPlan,
+        run_id: &str,
+        started_at: DateTime<Utc>,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            attempt: plan.attempt,
+            run_id: run_id.to_string(),
+            started_at,
+            ended_at: Utc::now(),
+            outcome: if error.is_some() {
+                "failed"
+            } else {
+                "completed"
+            }
+            .to_string(),
+            error,
+            model: plan.model.clone(),
+            think_mode: plan.think_mode.clone(),
+            retry_at: None,
+            checkpoint: None,
+            orchestrator_run_id: Some(crate::run_id::RunId::current().clone()),
+        }
+    }
+}
+
+impl QueueTask {
+    /// Number of the attempt that runs next.
+    pub(crate) fn next_attempt(&self) -> u32 {
+        self.attempts.len() as u32 + 1
+    }
 }
 
 pub(crate) fn default_state() -> String {
     "pending".to_string()
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub(crate) struct QueueFile {
     #[serde(default)]
     pub(crate) tasks: Vec<QueueTask>,
+    /// Sequence number of the last queue log record this snapshot includes.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub(crate) wal_seq: u64,
 }
 
-pub(crate) async fn load_queue(path: &Path) -> Result<QueueFile> {
-    if !path.exists() {
-        return Ok(QueueFile::default());
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+impl QueueFile {
+    /// Pending tasks whose dependencies have all completed, highest priority
+    /// first and in queue order within a priority.
+    pub(crate) fn ready_tasks(&self) -> Vec<QueueTask> {
+        let mut ready: Vec<QueueTask> = self
+            .tasks
+            .iter()
+            .filter(|task| task.state == "pending" && self.dependencies_met(task))
+            .cloned()
+            .collect();
+        ready.sort_by_key(|task| {
+            std::cmp::Reverse(task.priority.unwrap_or(crate::agent::Priority::Medium))
+        });
+        ready
     }
-    let raw = tokio::fs::read_to_string(path).await?;
-    if raw.trim().is_empty() {
-        return Ok(QueueFile::default());
+
+    /// Finished tasks that ran, as `(id, description, run id)`.
+    pub(crate) fn finished_runs(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.tasks
+            .iter()
+            .filter(|task| task.state == "completed" || task.state == "failed")
+            .filter_map(|task| {
+                Some((
+                    task.id.as_str(),
+                    task.task.as_str(),
+                    task.run_id.as_deref()?,
+                ))
+            })
     }
-    serde_yml::from_str(&raw).context("Failed to parse queue file")
+
+    /// IDs of pending tasks still waiting on a dependency.
+    pub(crate) fn blocked_ids(&self) -> Vec<String> {
+        self.tasks
+            .iter()
+            .filter(|task| task.state == "pending" && !self.dependencies_met(task))
+            .map(|task| task.id.clone())
+            .collect()
+    }
+
+    fn dependencies_met(&self, task: &QueueTask) -> bool {
+        task.depends_on.iter().all(|dep| {
+            self.tasks
+                .iter()
+                .any(|other| other.id == *dep && other.state == "completed")
+        })
+    }
+}
+
+/// Load the queue snapshot and replay any queue log records written after it,
+/// so readers see every committed transition even after a crash.
+pub(crate) async fn load_queue(path: &Path) -> Result<QueueFile> {
+    let mut queue = if path.exists() {
+        let raw = tokio::fs::read_to_string(path).await?;
+        if raw.trim().is_empty() {
+            QueueFile::default()
+        } else {
+            serde_yml::from_str(&raw).context("Failed to parse queue file")?
+        }
+    } else {
+        QueueFile::default()
+    };
+    queue_wal::replay(path, &mut queue).await?;
+    Ok(queue)
 }
 
 /// Save the queue atomically. Codex #2 fix: previous implementation did a plain
 /// `tokio::fs::write` which is not atomic — a crash or concurrent writer could leave a
 /// truncated / zero-byte `queue.yaml` and deadlock all future queue operations.
 ///
-/// We now write to a same-directory temp file and `rename` into place. On POSIX the
-/// rename is atomic, so concurrent readers either see the old file or the new one, never
-/// a half-written state. This still doesn't protect against *lost updates* when
-/// `queue drain` and `auto` run concurrently — both read → mutate → write — but at least
-/// the file never becomes unparseable.
+/// We now write to a same-directory temp file, fsync it and `rename` into place. On
+/// POSIX the rename is atomic, so concurrent readers either see the old file or the new
+/// one, never a half-written state. Lost updates between processes are prevented by
+/// `QueueState`, which also logs each change to the queue WAL before saving.
 pub(crate) async fn save_queue(path: &Path, queue: &QueueFile) -> Result<()> {
     if let Some(parent) = path.parent() {
         tokio::fs::create_dir_all(parent).await?;
@@ -98,7 +307,10 @@ pub(crate) async fn save_queue(path: &Path, queue: &QueueFile) -> Result<()> {
         )),
         None => path.with_extension(format!("yaml.tmp.{}", uuid::Uuid::new_v4())),
     };
-    tokio::fs::write(&tmp_path, body).await?;
+    let mut tmp = tokio::fs::File::create(&tmp_path).await?;
+    tmp.write_all(body.as_bytes()).await?;
+    tmp.sync_all().await?;
+    drop(tmp);
     tokio::fs::rename(&tmp_path, path).await?;
     Ok(())
 }
@@ -126,34 +338,36 @@ impl QueueState {
     }
 
     pub(crate) async fn try_claim(&self, id: &str, run_id: &str) -> Result<bool> {
-        let task_lock = self.task_lock(id);
-        let _task_guard = task_lock.lock().await;
-        let _file_guard = self.file_lock.lock().await;
-        let _process_guard = self.acquire_process_lock().await?;
-
-        let mut queue = load_queue(&self.path).await?;
-        let task = find_task_mut(&mut queue, id)?;
-
-        match task.claim {
-            ClaimState::Unclaimed | ClaimState::Released { .. } if task.state == "pending" => {
-                task.claim = ClaimState::Running {
-                    run_id: run_id.to_string(),
-                    started_at_ms: now_ms(),
-                };
-                task.state = "running".to_string();
-                task.completed_at = None;
-                task.run_id = Some(run_id.to_string());
-                save_queue(&self.path, &queue).await?;
-                Ok(true)
-            }
-            ClaimState::Unclaimed
-            | ClaimState::Released { .. }
-            | ClaimState::Running { .. }
-            | ClaimState::RetryQueued { .. } => Ok(false),
+        // A halted project hands nothing out (`ccswarm halt`).
+        if let Some(dir) = self.path.parent() {
+            crate::governance::halt::ensure_not_halted(dir).await?;
         }
+        let mut claimed = false;
+        self.update_task(id, |task| {
+            match task.claim {
+                ClaimState::Unclaimed | ClaimState::Released { .. } if task.state == "pending" => {
+                    task.claim = ClaimState::Running {
+                        run_id: run_id.to_string(),
+                        started_at_ms: now_ms(),
+                    };
+                    task.state = "running".to_string();
+                    task.completed_at = None;
+                    task.run_id = Some(run_id.to_string());
+                    claimed = true;
+                }
+                ClaimState::Unclaimed
+                | ClaimState::Released { .. }
+                | ClaimState::Running { .. }
+                | ClaimState::RetryQueued { .. } => {}
+            }
+            Ok(())
+        })
+        .await?;
+        // The claim is in the fsynced queue log before it is reported, so a task
+        // is dispatched at most once even if this process dies right after.
+        Ok(claimed)
     }
 
-    #[allow(dead_code)]
     pub(crate) async fn mark_retry_queued(
         &self,
         id: &str,
@@ -187,6 +401,14 @@ impl QueueState {
         .await
     }
 
+    pub(crate) async fn record_attempt(&self, id: &str, attempt: TaskAttempt) -> Result<()> {
+        self.update_task(id, |task| {
+            task.attempts.push(attempt);
+            Ok(())
+        })
+        .await
+    }
+
     pub(crate) async fn release_terminal_if_running(
         &self,
         id: &str,
@@ -222,6 +444,35 @@ impl QueueState {
         Ok(updated)
     }
 
+    /// Put a task whose claiming process died back to `pending`, if it is
+    /// still held by the observed run.
+    pub(crate) async fn requeue_orphaned(
+        &self,
+        id: &str,
+        observed_run_id: &str,
+        observed_started_at_ms: u64,
+    ) -> Result<bool> {
+        let mut updated = false;
+        self.update_task(id, |task| {
+            if matches!(
+                &task.claim,
+                ClaimState::Running { run_id, started_at_ms }
+                    if run_id == observed_run_id && *started_at_ms == observed_started_at_ms
+            ) {
+                task.claim = ClaimState::Released {
+                    reason: "owner_exited".to_string(),
+                };
+                task.state = default_state();
+                task.completed_at = None;
+                task.run_id = None;
+                updated = true;
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(updated)
+    }
+
     pub(crate) async fn make_retry_ready_if_due(
         &self,
         id: &str,
@@ -255,15 +506,13 @@ impl QueueState {
     {
         let task_lock = self.task_lock(id);
         let _task_guard = task_lock.lock().await;
-        let _file_guard = self.file_lock.lock().await;
-        let _process_guard = self.acquire_process_lock().await?;
-
-        let mut queue = load_queue(&self.path).await?;
-        let task = find_task_mut(&mut queue, id)?;
-        mutate(task)?;
-        save_queue(&self.path, &queue).await
+        self.update_queue(|queue| mutate(find_task_mut(queue, id)?))
+            .await
     }
 
+    /// Apply `mutate` as one transaction: under the in-process and cross-process
+    /// locks, validate the task state changes, log them to the queue WAL, then
+    /// save the snapshot and checkpoint the log.
     pub(crate) async fn update_queue<F>(&self, mutate: F) -> Result<()>
     where
         F: FnOnce(&mut QueueFile) -> Result<()>,
@@ -271,9 +520,27 @@ impl QueueState {
         let _file_guard = self.file_lock.lock().await;
         let _process_guard = self.acquire_process_lock().await?;
 
-        let mut queue = load_queue(&self.path).await?;
+        let before = load_queue(&self.path).await?;
+        let mut queue = before.clone();
         mutate(&mut queue)?;
-        save_queue(&self.path, &queue).await
+        let Some(record) = WalRecord::diff(&before, &queue)? else {
+            return Ok(());
+        };
+        // Claims are orchestrator decisions too: journal them for crash
+        // recovery before they take effect, and their end after.
+        let journal = RecoveryJournal::new(self.path.parent().unwrap_or(Path::new(".")));
+        let (claimed, ended) = claim_changes(&before, &queue);
+        for op in claimed {
+            journal.begin(op).await?;
+        }
+        queue_wal::append(&self.path, &record).await?;
+        queue.wal_seq = record.seq;
+        save_queue(&self.path, &queue).await?;
+        queue_wal::checkpoint(&self.path).await?;
+        for op in &ended {
+            journal.done(op).await;
+        }
+        Ok(())
     }
 
     async fn update_claim<F>(&self, id: &str, mutate: F) -> Result<()>
@@ -354,6 +621,27 @@ impl QueueProcessLock {
     }
 }
 
+/// Claims `after` takes and claims it ends, relative to `before`.
+fn claim_changes(before: &QueueFile, after: &QueueFile) -> (Vec<Operation>, Vec<Operation>) {
+    fn running(queue: &QueueFile) -> Vec<Operation> {
+        queue
+            .tasks
+            .iter()
+            .filter_map(|task| match &task.claim {
+                ClaimState::Running { run_id, .. } => Some(Operation::TaskAssigned {
+                    task_id: task.id.clone(),
+                    run_id: run_id.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+    let (was, now) = (running(before), running(after));
+    let claimed = now.iter().filter(|op| !was.contains(op)).cloned().collect();
+    let ended = was.into_iter().filter(|op| !now.contains(op)).collect();
+    (claimed, ended)
+}
+
 fn find_task_mut<'a>(queue: &'a mut QueueFile, id: &str) -> Result<&'a mut QueueTask> {
     queue
         .tasks
@@ -380,11 +668,27 @@ mod tests {
             completed_at: None,
             run_id: None,
             claim: ClaimState::Unclaimed,
+            attempts: Vec::new(),
+            time_box: None,
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker: None,
+            acceptance: None,
         }
     }
 
     async fn write_queue(path: &Path, tasks: Vec<QueueTask>) -> Result<()> {
-        save_queue(path, &QueueFile { tasks }).await
+        save_queue(
+            path,
+            &QueueFile {
+                tasks,
+                ..Default::default()
+            },
+        )
+        .await
     }
 
     #[test]
@@ -456,6 +760,58 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_logged_claim_survives_crash_before_snapshot() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("queue.yaml");
+        write_queue(&path, vec![queue_task("q-1")]).await?;
+
+        // Simulate a claim that reached the log but not the snapshot.
+        let before = load_queue(&path).await?;
+        let mut claimed = before.clone();
+        claimed.tasks[0].state = "running".to_string();
+        claimed.tasks[0].claim = ClaimState::Running {
+            run_id: "run-1".to_string(),
+            started_at_ms: now_ms(),
+        };
+        let record = WalRecord::diff(&before, &claimed)?.expect("claim changes the task");
+        queue_wal::append(&path, &record).await?;
+
+        let state = QueueState::new(path.clone());
+        assert_eq!(state.load().await?.tasks[0].state, "running");
+        assert!(!state.try_claim("q-1", "run-2").await?);
+
+        state.release("q-1", "manual").await?;
+        let queue = load_queue(&path).await?;
+        assert_eq!(queue.wal_seq, 2);
+        assert_eq!(
+            tokio::fs::read_to_string(queue_wal::wal_path(&path)).await?,
+            ""
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_task_rejects_invalid_transition() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("queue.yaml");
+        let mut task = queue_task("q-1");
+        task.state = "completed".to_string();
+        write_queue(&path, vec![task]).await?;
+        let state = QueueState::new(path.clone());
+
+        let err = state
+            .update_task("q-1", |task| {
+                task.state = "running".to_string();
+                Ok(())
+            })
+            .await
+            .expect_err("completed is final");
+        assert!(err.to_string().contains("from completed to running"));
+        assert_eq!(load_queue(&path).await?.tasks[0].state, "completed");
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_release_resets_claim() -> Result<()> {
         let dir = tempfile::tempdir()?;
@@ -0,0 +1,324 @@
+//! Write-ahead log and state machine for `.ccswarm/queue.yaml`.
+//!
+//! Every queue mutation made through `QueueState` is committed in three steps:
+//!
+//! 1. The changed tasks (and removed IDs) are appended to `queue.wal` as one
+//!    JSON line with the next sequence number, and the log is fsynced.
+//! 2. The full queue is written to a temp file, fsynced and renamed over
+//!    `queue.yaml`, which records the sequence number it includes.
+//! 3. The log is truncated (checkpoint).
+//!
+//! A crash between 1 and 2 leaves a record newer than the snapshot, which
+//! every reader replays on load, so an acknowledged transition is never lost.
+//! A crash during 1 leaves a torn last line; it was never acknowledged and is
+//! ignored. Task state changes are checked against [`check_transition`]
+//! before anything is written.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::queue_state::{QueueFile, QueueTask};
+
+/// One committed queue mutation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct WalRecord {
+    pub(crate) seq: u64,
+    pub(crate) at: DateTime<Utc>,
+    /// Tasks as they are after the mutation. New tasks are appended in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) upsert: Vec<QueueTask>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) remove: Vec<String>,
+}
+
+impl WalRecord {
+    /// Record turning `before` into `after`, or `None` when nothing changed.
+    pub(crate) fn diff(before: &QueueFile, after: &QueueFile) -> Result<Option<Self>> {
+        let mut upsert = Vec::new();
+        for task in &after.tasks {
+            let unchanged = match before.tasks.iter().find(|t| t.id == task.id) {
+                Some(prior) => {
+                    check_transition(&task.id, &prior.state, &task.state)?;
+                    serde_json::to_value(prior)? == serde_json::to_value(task)?
+                }
+                None => false,
+            };
+            if !unchanged {
+                upsert.push(task.clone());
+            }
+        }
+        let kept: HashSet<&str> = after.tasks.iter().map(|t| t.id.as_str()).collect();
+        let remove: Vec<String> = before
+            .tasks
+            .iter()
+            .filter(|t| !kept.contains(t.id.as_str()))
+            .map(|t| t.id.clone())
+            .collect();
+
+        if upsert.is_empty() && remove.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            seq: before.wal_seq + 1,
+            at: Utc::now(),
+            upsert,
+            remove,
+        }))
+    }
+
+    fn apply(&self, queue: &mut QueueFile) {
+        queue.tasks.retain(|t| !self.remove.contains(&t.id));
+        for task in &self.upsert {
+            match queue.tasks.iter_mut().find(|t| t.id == task.id) {
+                Some(existing) => *existing = task.clone(),
+                None => queue.tasks.push(task.clone()),
+            }
+        }
+        queue.wal_seq = self.seq;
+    }
+}
+
+/// Task states with a known lifecycle. Anything else is left unchecked.
+const KNOWN_STATES: &[&str] = &[
+    "pending",
+    "assigned",
+    "running",
+    "paused",
+    "blocked",
+    "completed",
+    "failed",
+];
+
+/// Reject a task state change the queue lifecycle doesn't allow:
+///
+/// ```text
+/// pending ─→ running ─→ completed
+///    │  ↑       │   └──→ failed ─→ pending
+///    │  └───────┘ (release / retry)
+///    ├─→ assigned ─→ completed | failed | pending
+///    └─→ paused ─→ pending | failed
+///
+/// running ─→ blocked ─→ pending | failed
+/// ```
+///
+/// `completed` is final.
+pub(crate) fn check_transition(id: &str, from: &str, to: &str) -> Result<()> {
+    if from == to || !KNOWN_STATES.contains(&from) || !KNOWN_STATES.contains(&to) {
+        return Ok(());
+    }
+    let allowed = match from {
+        "pending" => &["running", "assigned", "paused", "failed"][..],
+        "running" => &["pending", "paused", "blocked", "completed", "failed"][..],
+        "assigned" => &["pending", "completed", "failed"][..],
+        "paused" => &["pending", "failed"][..],
+        "blocked" => &["pending", "failed"][..],
+        "failed" => &["pending"][..],
+        _ => &[][..],
+    };
+    if allowed.contains(&to) {
+        Ok(())
+    } else {
+        Err(anyhow!("Queue task {id} cannot move from {from} to {to}"))
+    }
+}
+
+/// Log file next to the queue file.
+pub(crate) fn wal_path(queue_path: &Path) -> PathBuf {
+    queue_path.with_extension("wal")
+}
+
+/// Append `record` to the log and fsync it. A torn line left by an earlier
+/// crash is terminated first so the new record stays on a line of its own.
+pub(crate) async fn append(queue_path: &Path, record: &WalRecord) -> Result<()> {
+    let path = wal_path(queue_path);
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open queue log {}", path.display()))?;
+        if file.metadata()?.len() > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                line.insert(0, '\n');
+            }
+        }
+        file.write_all(line.as_bytes())?;
+        file.sync_data()
+            .with_context(|| format!("Failed to sync queue log {}", path.display()))
+    })
+    .await
+    .context("Queue log writer panicked")?
+}
+
+/// Apply every record newer than the snapshot, in sequence. Returns how many
+/// were replayed; a gap in the sequence stops the replay.
+pub(crate) async fn replay(queue_path: &Path, queue: &mut QueueFile) -> Result<usize> {
+    let path = wal_path(queue_path);
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read queue log {}", path.display()));
+        }
+    };
+    let mut replayed = 0;
+    for record in content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<WalRecord>(line).ok())
+    {
+        if record.seq <= queue.wal_seq {
+            continue;
+        }
+        if record.seq != queue.wal_seq + 1 {
+            tracing::warn!(
+                "Queue log {} skips from {} to {}; ignoring the rest",
+                path.display(),
+                queue.wal_seq,
+                record.seq
+            );
+            break;
+        }
+        record.apply(queue);
+        replayed += 1;
+    }
+    Ok(replayed)
+}
+
+/// Drop the log once the snapshot holds everything in it.
+pub(crate) async fn checkpoint(queue_path: &Path) -> Result<()> {
+    let path = wal_path(queue_path);
+    match tokio::fs::OpenOptions::new().write(true).open(&path).await {
+        Ok(file) => {
+            file.set_len(0).await?;
+            file.sync_all().await?;
+            Ok(())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => {
+            Err(e).with_context(|| format!("Failed to truncate queue log {}", path.display()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::queue_state::{ClaimState, default_state};
+
+    fn task(id: &str, state: &str) -> QueueTask {
+        QueueTask {
+            id: id.to_string(),
+            task: "test task".to_string(),
+            flow: None,
+            state: state.to_string(),
+            created_at: Utc::now(),
+            completed_at: None,
+            run_id: None,
+            claim: ClaimState::Unclaimed,
+            attempts: Vec::new(),
+            time_box: None,
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker: None,
+            acceptance: None,
+        }
+    }
+
+    #[test]
+    fn test_transitions_follow_lifecycle() {
+        assert!(check_transition("q", "pending", "running").is_ok());
+        assert!(check_transition("q", "running", "completed").is_ok());
+        assert!(check_transition("q", "failed", "pending").is_ok());
+        assert!(check_transition("q", "running", "running").is_ok());
+        assert!(check_transition("q", "completed", "pending").is_err());
+        assert!(check_transition("q", "pending", "completed").is_err());
+        assert!(check_transition("q", "paused", "running").is_err());
+        assert!(check_transition("q", "running", "blocked").is_ok());
+        assert!(check_transition("q", "blocked", "running").is_err());
+    }
+
+    #[test]
+    fn test_diff_carries_changed_and_removed_tasks() -> Result<()> {
+        let before = QueueFile {
+            tasks: vec![task("a", "pending"), task("b", "pending")],
+            ..Default::default()
+        };
+        let mut after = before.clone();
+        after.tasks[0].state = "running".to_string();
+        after.tasks.remove(1);
+        after.tasks.push(task("c", "pending"));
+
+        let record = WalRecord::diff(&before, &after)?.expect("changes");
+        assert_eq!(record.seq, 1);
+        let ids: Vec<&str> = record.upsert.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+        assert_eq!(record.remove, ["b"]);
+
+        assert!(WalRecord::diff(&before, &before.clone())?.is_none());
+
+        let mut invalid = before.clone();
+        invalid.tasks[0].state = "completed".to_string();
+        assert!(WalRecord::diff(&before, &invalid).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replay_applies_records_past_snapshot_and_skips_torn_line() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let queue_path = dir.path().join("queue.yaml");
+        let snapshot = QueueFile {
+            tasks: vec![task("a", &default_state())],
+            ..Default::default()
+        };
+
+        let mut claimed = snapshot.clone();
+        claimed.tasks[0].state = "running".to_string();
+        let first = WalRecord::diff(&snapshot, &claimed)?.expect("changes");
+        append(&queue_path, &first).await?;
+        claimed.wal_seq = first.seq;
+
+        let mut added = claimed.clone();
+        added.tasks.push(task("b", "pending"));
+        let second = WalRecord::diff(&claimed, &added)?.expect("changes");
+        append(&queue_path, &second).await?;
+
+        // A crash mid-append leaves half a record behind.
+        let log = wal_path(&queue_path);
+        let mut content = tokio::fs::read_to_string(&log).await?;
+        content.push_str("{\"seq\":3,\"at\":");
+        tokio::fs::write(&log, content).await?;
+
+        let mut recovered = snapshot.clone();
+        assert_eq!(replay(&queue_path, &mut recovered).await?, 2);
+        assert_eq!(recovered.wal_seq, 2);
+        assert_eq!(recovered.tasks[0].state, "running");
+        assert_eq!(recovered.tasks[1].id, "b");
+
+        // The next append starts a fresh line after the torn one.
+        let mut done = recovered.clone();
+        done.tasks[0].state = "completed".to_string();
+        let third = WalRecord::diff(&recovered, &done)?.expect("changes");
+        append(&queue_path, &third).await?;
+        let mut again = snapshot.clone();
+        assert_eq!(replay(&queue_path, &mut again).await?, 3);
+        assert_eq!(again.tasks[0].state, "completed");
+
+        checkpoint(&queue_path).await?;
+        let mut after_checkpoint = snapshot;
+        assert_eq!(replay(&queue_path, &mut after_checkpoint).await?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! `ccswarm recover` (see [`crate::recovery`]).
+
+use super::super::*;
+use crate::recovery::{Compensation, RecoveryJournal};
+
+impl CliRunner {
+    pub(crate) async fn handle_recover(&self, dry_run: bool) -> Result<()> {
+        let journal = RecoveryJournal::for_repo(&self.repo_path);
+        if dry_run {
+            let open = journal.open().await?;
+            if self.json_output {
+                let data: Vec<_> = open
+                    .iter()
+                    .map(|o| serde_json::json!({ "operation": o, "in_flight": o.in_flight() }))
+                    .collect();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "status": "success",
+                        "data": data,
+                    }))?
+                );
+                return Ok(());
+            }
+            if open.is_empty() {
+                println!("Nothing to recover.");
+            }
+            for o in &open {
+                println!(
+                    "  {} {} (pid {}, {})",
+                    o.began_at.format("%Y-%m-%d %H:%M:%S"),
+                    o.op,
+                    o.pid,
+                    if o.in_flight() {
+                        "still running".bright_green()
+                    } else {
+                        "abandoned".bright_yellow()
+                    }
+                );
+            }
+            return Ok(());
+        }
+
+        let compensations = journal.recover().await?;
+        let failed = compensations.iter().filter(|c| !c.ok).count();
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if failed == 0 { "success" } else { "error" },
+                    "data": compensations,
+                }))?
+            );
+        } else if compensations.is_empty() {
+            println!("Nothing to recover.");
+        } else {
+            print_compensations(&compensations);
+        }
+        if failed > 0 {
+            anyhow::bail!(
+                "{} of {} abandoned operation{} could not be undone",
+                failed,
+                compensations.len(),
+                if compensations.len() == 1 { "" } else { "s" }
+            );
+        }
+        Ok(())
+    }
+
+    /// Undo what a crashed ccswarm left half-done before a loop starts
+    /// dispatching work. Reported on stderr; failures are left for
+    /// `ccswarm recover`.
+    pub(crate) async fn recover_abandoned(&self) {
+        match RecoveryJournal::for_repo(&self.repo_path).recover().await {
+            Ok(compensations) => print_compensations(&compensations),
+            Err(e) => warn!("Crash recovery failed: {e:#}"),
+        }
+    }
+}
+
+fn print_compensations(compensations: &[Compensation]) {
+    for c in compensations {
+        eprintln!(
+            "{} {}: {}",
+            if c.ok {
+                "↺".bright_cyan()
+            } else {
+                "✗".bright_red()
+            },
+            c.op,
+            c.outcome
+        );
+    }
+}
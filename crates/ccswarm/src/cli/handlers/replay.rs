@@ -86,6 +86,18 @@ impl CliRunner {
                     println!("{}", "To revert a commit:".bright_cyan().bold());
                     println!("  git revert <hash>");
                     println!();
+                    if run_path.join("journal.jsonl").exists()
+                        && let Some(id) = run_path.file_name()
+                    {
+                        println!(
+                            "{}",
+                            "To revert everything the run changed (preview first):"
+                                .bright_cyan()
+                                .bold()
+                        );
+                        println!("  ccswarm task undo {}", id.to_string_lossy());
+                        println!();
+                    }
                     println!(
                         "{}",
                         "(ccswarm never revises history for you — copy the command above.)"
@@ -1,4 +1,5 @@
 use super::super::*;
+use super::run_utils::resolve_run_path;
 
 impl CliRunner {
     pub(crate) async fn handle_run(&self, action: &RunAction) -> Result<()> {
@@ -11,9 +12,8 @@ impl CliRunner {
 
     /// Compare timelines of two runs (event_type + stage pairs, in order).
     async fn run_diff(&self, a: &str, b: &str) -> Result<()> {
-        let runs_dir = self.repo_path.join(".ccswarm/runs");
-        let read_timeline = |id: &str| -> Result<Vec<(String, String, Option<u64>)>> {
-            let p = runs_dir.join(id).join("events.ndjson");
+        let read_timeline = |id: &str, dir: &Path| -> Result<Vec<(String, String, Option<u64>)>> {
+            let p = dir.join("events.ndjson");
             if !p.exists() {
                 anyhow::bail!("events.ndjson not found for run '{}'", id);
             }
@@ -43,8 +43,8 @@ impl CliRunner {
             Ok(items)
         };
 
-        let ta = read_timeline(a)?;
-        let tb = read_timeline(b)?;
+        let ta = read_timeline(a, &resolve_run_path(&self.repo_path, Some(a)).await?)?;
+        let tb = read_timeline(b, &resolve_run_path(&self.repo_path, Some(b)).await?)?;
 
         println!(
             "{}  {}  vs  {}",
@@ -230,11 +230,9 @@ impl CliRunner {
     }
 
     async fn run_view(&self, id: &str) -> Result<()> {
-        let run_dir = self.repo_path.join(".ccswarm/runs").join(id);
-
-        if !run_dir.exists() {
-            anyhow::bail!("Run '{}' not found in .ccswarm/runs/", id);
-        }
+        let run_dir = resolve_run_path(&self.repo_path, Some(id))
+            .await
+            .map_err(|e| anyhow!("Run '{}' not found: {}", id, e))?;
 
         // ── Summary ──────────────────────────────────────────────────────────
         let summary_path = run_dir.join("summary.json");
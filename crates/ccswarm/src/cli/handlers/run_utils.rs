@@ -8,8 +8,10 @@ use anyhow::{Context, Result, anyhow};
 use std::path::{Path, PathBuf};
 
 use crate::run_id::validate_run_id;
+use crate::session::names::SessionNames;
 
 /// Resolve a run path. When `run_id` is `None`, returns the most recently created run.
+/// Session names and aliases resolve to the run they point at.
 ///
 /// All user-supplied IDs are validated by [`validate_run_id`] before being joined.
 pub(crate) async fn resolve_run_path(repo_path: &Path, run_id: Option<&str>) -> Result<PathBuf> {
@@ -24,14 +26,32 @@ pub(crate) async fn resolve_run_path(repo_path: &Path, run_id: Option<&str>) ->
         Some(id) => {
             validate_run_id(id).context("invalid run ID")?;
             let path = runs_dir.join(id);
-            if !path.is_dir() {
-                return Err(anyhow!(
+            if path.is_dir() {
+                return Ok(path);
+            }
+            // Not a run id: try session names and aliases.
+            let names = SessionNames::load(repo_path).await?;
+            match names.get(id) {
+                Some((name, session)) => match &session.run_id {
+                    Some(run_id) if runs_dir.join(run_id).is_dir() => Ok(runs_dir.join(run_id)),
+                    Some(run_id) => Err(anyhow!(
+                        "session '{}' points at run '{}', which no longer exists",
+                        name,
+                        run_id
+                    )),
+                    None => Err(anyhow!(
+                        "session '{}' is reserved but has not run yet; start it with \
+                         ccswarm pipeline --name {} --task ...",
+                        name,
+                        name
+                    )),
+                },
+                None => Err(anyhow!(
                     "run '{}' not found under {}",
                     id,
                     runs_dir.display()
-                ));
+                )),
             }
-            Ok(path)
         }
         None => {
             let mut entries: Vec<PathBuf> = Vec::new();
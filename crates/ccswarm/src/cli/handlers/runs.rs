@@ -0,0 +1,202 @@
+//! `ccswarm runs`: past ccswarm invocations, each with the pipeline runs
+//! and queue attempts it produced (see [`crate::run_ledger`]).
+
+use super::super::*;
+use super::queue_state::{QUEUE_FILE, load_queue};
+use crate::run_id::RunId;
+use crate::run_ledger::{self, RunRecord};
+use serde::Serialize;
+
+/// A queue attempt made by the invocation being shown.
+#[derive(Debug, Serialize)]
+struct AttemptRef {
+    task_id: String,
+    attempt: u32,
+    run_id: String,
+    outcome: String,
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_runs(&self, action: &RunsAction) -> Result<()> {
+        match action {
+            RunsAction::List { limit } => self.runs_list(*limit).await,
+            RunsAction::Show { id } => self.runs_show(id.as_deref()).await,
+        }
+    }
+
+    async fn runs_list(&self, limit: usize) -> Result<()> {
+        let mut records = run_ledger::list(&self.repo_path).await?;
+        records.truncate(limit);
+        if self.json_output {
+            let data: Vec<serde_json::Value> = records
+                .iter()
+                .map(|r| serde_json::json!({ "status": r.status(), "run": r }))
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": data,
+                }))?
+            );
+            return Ok(());
+        }
+        if records.is_empty() {
+            println!("No ccswarm runs recorded yet.");
+            return Ok(());
+        }
+        println!(
+            "  {:<26} {:<12} {:>9}  command",
+            "run", "status", "pipelines"
+        );
+        for record in &records {
+            println!(
+                "  {:<26} {:<12} {:>9}  {}",
+                record.id.bright_yellow(),
+                status_label(record.status()),
+                record.pipeline_runs.len(),
+                command_line(record).bright_black()
+            );
+        }
+        Ok(())
+    }
+
+    /// `runs show [id]`: `id` may be a unique prefix; the newest run without one.
+    async fn runs_show(&self, id: Option<&str>) -> Result<()> {
+        let records = run_ledger::list(&self.repo_path).await?;
+        let record = match id {
+            None => records.first(),
+            Some(id) => {
+                let matches: Vec<&RunRecord> =
+                    records.iter().filter(|r| r.id.starts_with(id)).collect();
+                if matches.len() > 1 {
+                    anyhow::bail!("Run ID '{}' is ambiguous ({} matches)", id, matches.len());
+                }
+                matches.first().copied()
+            }
+        };
+        let Some(record) = record else {
+            anyhow::bail!(
+                "No ccswarm run found{}",
+                id.map(|i| format!(" for '{i}'")).unwrap_or_default()
+            );
+        };
+
+        let run_id = RunId::try_from(record.id.clone())?;
+        let queue = load_queue(&self.repo_path.join(QUEUE_FILE)).await?;
+        let attempts: Vec<AttemptRef> = queue
+            .tasks
+            .iter()
+            .flat_map(|task| {
+                task.attempts
+                    .iter()
+                    .filter(|a| a.orchestrator_run_id.as_ref() == Some(&run_id))
+                    .map(|a| AttemptRef {
+                        task_id: task.id.clone(),
+                        attempt: a.attempt,
+                        run_id: a.run_id.clone(),
+                        outcome: a.outcome.clone(),
+                    })
+            })
+            .collect();
+        let runs_dir = self.repo_path.join(".ccswarm").join("runs");
+        let pipeline_status = |id: &str| -> &'static str {
+            let summary = std::fs::read_to_string(runs_dir.join(id).join("summary.json"))
+                .ok()
+                .and_then(|text| serde_json::from_str::<crate::events::RunSummary>(&text).ok());
+            match summary {
+                Some(s) if s.tasks_failed == 0 => "completed",
+                Some(_) => "failed",
+                None if crate::events::run_pid(&runs_dir.join(id)).is_some() => "running",
+                None => "unfinished",
+            }
+        };
+
+        if self.json_output {
+            let pipelines: Vec<serde_json::Value> = record
+                .pipeline_runs
+                .iter()
+                .map(|p| serde_json::json!({ "status": pipeline_status(&p.run_id), "run": p }))
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": {
+                        "run": record,
+                        "run_status": record.status(),
+                        "pipeline_runs": pipelines,
+                        "attempts": attempts,
+                    },
+                }))?
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{} {}  {}",
+            "Run".bright_cyan().bold(),
+            record.id.bright_yellow(),
+            status_label(record.status())
+        );
+        println!("  command:  {}", command_line(record));
+        if let Some(version) = &record.version {
+            println!("  version:  {version}");
+        }
+        if let Some(started) = record.started_at {
+            println!("  started:  {}", started.format("%Y-%m-%d %H:%M:%S UTC"));
+        }
+        if let Some(finished) = record.finished_at {
+            println!("  finished: {}", finished.format("%Y-%m-%d %H:%M:%S UTC"));
+        }
+        if let Some(error) = &record.error {
+            println!("  error:    {}", error.bright_red());
+        }
+
+        println!();
+        println!("{}", "Pipeline runs".bold());
+        for p in &record.pipeline_runs {
+            println!(
+                "  {}  {:<10} {:<12} {}",
+                p.run_id.chars().take(8).collect::<String>().bright_yellow(),
+                p.flow,
+                status_label(pipeline_status(&p.run_id)),
+                p.task
+            );
+        }
+        if !attempts.is_empty() {
+            println!();
+            println!("{}", "Queue attempts".bold());
+            for a in &attempts {
+                println!(
+                    "  {} #{}  {:<10} run {}",
+                    a.task_id.bright_yellow(),
+                    a.attempt,
+                    a.outcome,
+                    a.run_id.chars().take(8).collect::<String>().bright_black()
+                );
+            }
+        }
+        println!();
+        println!(
+            "  {} ccswarm run view <run>   (events of one pipeline run)",
+            "→".bright_cyan()
+        );
+        Ok(())
+    }
+}
+
+fn status_label(status: &str) -> colored::ColoredString {
+    match status {
+        "completed" => status.bright_green(),
+        "failed" => status.bright_red(),
+        "running" => status.bright_cyan(),
+        _ => status.bright_yellow(),
+    }
+}
+
+/// The invocation's arguments, without the program path.
+fn command_line(record: &RunRecord) -> String {
+    let args = record.command.get(1..).unwrap_or_default();
+    format!("ccswarm {}", args.join(" "))
+}
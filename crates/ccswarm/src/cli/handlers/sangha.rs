@@ -1,4 +1,5 @@
 use super::super::*;
+use crate::workflow::sangha_tally::{self, Electorate, TallyMethod, Verdict};
 
 impl CliRunner {
     pub(crate) async fn handle_sangha(&self, action: &SanghaAction) -> Result<()> {
@@ -10,10 +11,18 @@ impl CliRunner {
                 title,
                 description,
                 proposal_type,
+                tally,
             } => {
-                let proposal =
-                    create_sangha_proposal(&proposals_dir, title, description, proposal_type, None)
-                        .await?;
+                let tally = TallyMethod::parse(tally)?;
+                let proposal = create_sangha_proposal(
+                    &proposals_dir,
+                    title,
+                    description,
+                    proposal_type,
+                    tally,
+                    None,
+                )
+                .await?;
                 let id = proposal
                     .get("id")
                     .and_then(|value| value.as_str())
@@ -36,6 +45,7 @@ impl CliRunner {
                     );
                     println!("  Title: {}", title);
                     println!("  Type:  {}", proposal_type);
+                    println!("  Tally: {}", tally.as_str());
                     println!(
                         "  Vote:  ccswarm sangha vote {} --approve",
                         id.bright_yellow()
@@ -46,6 +56,7 @@ impl CliRunner {
                 id,
                 approve,
                 reason,
+                voter,
             } => {
                 let filepath = coordination_json_path(&proposals_dir, id, "Proposal")?;
                 if !filepath.exists() {
@@ -54,12 +65,18 @@ impl CliRunner {
 
                 let content = tokio::fs::read_to_string(&filepath).await?;
                 let mut proposal: serde_json::Value = serde_json::from_str(&content)?;
+                if proposal.get("status").and_then(|s| s.as_str()) != Some("open") {
+                    anyhow::bail!("Proposal '{}' is already closed", id);
+                }
 
-                let vote = serde_json::json!({
+                let mut vote = serde_json::json!({
                     "approve": approve,
                     "reason": reason,
                     "voted_at": chrono::Utc::now().to_rfc3339(),
                 });
+                if let Some(voter) = voter {
+                    vote["voter"] = serde_json::json!(voter);
+                }
 
                 if let Some(votes) = proposal.get_mut("votes").and_then(|v| v.as_array_mut()) {
                     votes.push(vote);
@@ -151,6 +168,7 @@ impl CliRunner {
 
                 let content = tokio::fs::read_to_string(&filepath).await?;
                 let proposal: serde_json::Value = serde_json::from_str(&content)?;
+                let tally = self.electorate().await?.tally(&proposal);
 
                 if self.json_output {
                     println!(
@@ -158,6 +176,7 @@ impl CliRunner {
                         serde_json::to_string_pretty(&serde_json::json!({
                             "status": "success",
                             "data": proposal,
+                            "tally": tally,
                         }))?
                     );
                 } else {
@@ -187,26 +206,184 @@ impl CliRunner {
                     println!("  Description: {}", desc);
                     println!("  Type:        {}", ptype);
                     println!("  Status:      {}", st);
-
-                    if let Some(votes) = proposal.get("votes").and_then(|v| v.as_array()) {
-                        let approve_count = votes
+                    println!("  Tally:       {}", tally.method.as_str());
+                    println!(
+                        "  Votes:       {} approve, {} reject ({:?})",
+                        format_weight(tally.approve).bright_green(),
+                        format_weight(tally.reject).bright_red(),
+                        tally.verdict
+                    );
+                    for vote in &tally.votes {
+                        let voter = vote.voter.as_deref().unwrap_or("(anonymous)");
+                        let choice = if vote.approve {
+                            "approve".bright_green()
+                        } else {
+                            "reject".bright_red()
+                        };
+                        let via = vote
+                            .via
+                            .as_deref()
+                            .map(|via| format!(" via {via}"))
+                            .unwrap_or_default();
+                        if tally.method == TallyMethod::Weighted {
+                            println!(
+                                "    {} {} ×{}{}",
+                                voter,
+                                choice,
+                                format_weight(vote.weight),
+                                via.bright_black()
+                            );
+                        } else {
+                            println!("    {} {}{}", voter, choice, via.bright_black());
+                        }
+                    }
+                }
+            }
+            SanghaAction::Delegate {
+                from,
+                to,
+                categories,
+                revoke,
+            } => {
+                let mut delegations = sangha_tally::load_delegations(&self.repo_path).await?;
+                let scopes: Vec<Option<&str>> = if categories.is_empty() {
+                    vec![None]
+                } else {
+                    categories.iter().map(|c| Some(c.as_str())).collect()
+                };
+                let message = if *revoke {
+                    let removed = if categories.is_empty() {
+                        sangha_tally::revoke(&mut delegations, from, None)
+                    } else {
+                        scopes
                             .iter()
-                            .filter(|v| v.get("approve").and_then(|a| a.as_bool()).unwrap_or(false))
-                            .count();
-                        let reject_count = votes.len() - approve_count;
+                            .map(|scope| sangha_tally::revoke(&mut delegations, from, Some(*scope)))
+                            .sum()
+                    };
+                    if removed == 0 {
+                        anyhow::bail!("'{}' has no matching delegations", from);
+                    }
+                    format!("Revoked {} delegation(s) of {}", removed, from)
+                } else {
+                    let to = to
+                        .as_deref()
+                        .ok_or_else(|| anyhow::anyhow!("--to is required"))?;
+                    for scope in &scopes {
+                        sangha_tally::delegate(&mut delegations, from, to, *scope)?;
+                    }
+                    format!(
+                        "{} delegates to {} on {}",
+                        from,
+                        to,
+                        if categories.is_empty() {
+                            "all proposals".to_string()
+                        } else {
+                            categories.join(", ")
+                        }
+                    )
+                };
+                sangha_tally::save_delegations(&self.repo_path, &delegations).await?;
+
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "message": message,
+                            "data": delegations,
+                        }))?
+                    );
+                } else {
+                    println!("{} {}", "OK".bright_green().bold(), message);
+                }
+            }
+            SanghaAction::Delegations => {
+                let delegations = sangha_tally::load_delegations(&self.repo_path).await?;
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "data": delegations,
+                        }))?
+                    );
+                } else if delegations.is_empty() {
+                    println!("No delegations.");
+                } else {
+                    println!("{}", "Delegations".bright_cyan().bold());
+                    println!("{}", "===========".bright_cyan());
+                    for d in &delegations {
                         println!(
-                            "  Votes:       {} approve, {} reject",
-                            approve_count.to_string().bright_green(),
-                            reject_count.to_string().bright_red()
+                            "  {} → {} ({})",
+                            d.from.bright_yellow(),
+                            d.to.bright_cyan(),
+                            d.category.as_deref().unwrap_or("all proposals")
                         );
                     }
                 }
             }
+            SanghaAction::Close { id } => {
+                let filepath = coordination_json_path(&proposals_dir, id, "Proposal")?;
+                if !filepath.exists() {
+                    anyhow::bail!("Proposal '{}' not found", id);
+                }
+
+                let content = tokio::fs::read_to_string(&filepath).await?;
+                let mut proposal: serde_json::Value = serde_json::from_str(&content)?;
+                if proposal.get("status").and_then(|s| s.as_str()) != Some("open") {
+                    anyhow::bail!("Proposal '{}' is already closed", id);
+                }
+                if proposal.get("proposal_type").and_then(|t| t.as_str())
+                    == Some(crate::workflow::doctrine::PROPOSAL_TYPE)
+                {
+                    anyhow::bail!("Doctrine proposals are settled by `ccswarm context distill`");
+                }
+                let tally = self.electorate().await?.tally(&proposal);
+                let status = match tally.verdict {
+                    Verdict::Open => anyhow::bail!(
+                        "Proposal '{}' is undecided under the {} tally ({} approve, {} reject)",
+                        id,
+                        tally.method.as_str(),
+                        format_weight(tally.approve),
+                        format_weight(tally.reject)
+                    ),
+                    Verdict::Approved => "accepted",
+                    Verdict::Rejected => "rejected",
+                };
+                proposal["status"] = serde_json::json!(status);
+                proposal["closed_at"] = serde_json::json!(chrono::Utc::now().to_rfc3339());
+                proposal["tally_result"] = serde_json::json!(tally);
+                tokio::fs::write(&filepath, serde_json::to_string_pretty(&proposal)?).await?;
+
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "message": format!("Proposal {}", status),
+                            "data": proposal,
+                        }))?
+                    );
+                } else {
+                    println!(
+                        "{} Proposal {} {}",
+                        "OK".bright_green().bold(),
+                        id.bright_cyan(),
+                        status.bright_yellow()
+                    );
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Standing delegations, weighted by every proposal's voting record.
+    async fn electorate(&self) -> Result<Electorate> {
+        let proposals = sangha_tally::load_proposals(&self.repo_path).await?;
+        Electorate::load(&self.repo_path, &proposals).await
+    }
+
     pub(crate) async fn handle_extend(&self, action: &ExtendAction) -> Result<()> {
         let extensions_dir = self.repo_path.join("coordination/extensions");
         let proposals_dir = self.repo_path.join("coordination/proposals");
@@ -236,6 +413,7 @@ impl CliRunner {
                         title,
                         description,
                         "extension",
+                        TallyMethod::Majority,
                         Some(&id),
                     )
                     .await?;
@@ -306,6 +484,7 @@ impl CliRunner {
                         &title,
                         &description,
                         "extension",
+                        TallyMethod::Majority,
                         Some(&id),
                     )
                     .await?;
@@ -513,11 +692,12 @@ impl CliRunner {
     }
 }
 
-async fn create_sangha_proposal(
+pub(super) async fn create_sangha_proposal(
     proposals_dir: &std::path::Path,
     title: &str,
     description: &str,
     proposal_type: &str,
+    tally: TallyMethod,
     related_extension_id: Option<&str>,
 ) -> Result<serde_json::Value> {
     let id = format!("prop-{}", &uuid::Uuid::new_v4().to_string()[..8]);
@@ -527,6 +707,7 @@ async fn create_sangha_proposal(
         "description": description,
         "proposal_type": proposal_type,
         "status": "open",
+        "tally": tally.as_str(),
         "votes": [],
         "created_at": chrono::Utc::now().to_rfc3339(),
     });
@@ -562,3 +743,12 @@ fn coordination_json_path(
 
     Ok(base_dir.join(format!("{}.json", id)))
 }
+
+/// Vote totals without trailing zeros: `2` or `1.33`.
+fn format_weight(weight: f64) -> String {
+    let formatted = format!("{weight:.2}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
@@ -1,5 +1,6 @@
 //! Scaffold handler: create project + run pipeline in one command
 
+use super::scaffold_template::ScaffoldTemplate;
 use anyhow::{Context, Result};
 use colored::Colorize;
 use std::path::Path;
@@ -10,9 +11,18 @@ pub async fn handle_scaffold(
     task: &str,
     flow: &str,
     timeout: u64,
+    template: Option<&ScaffoldTemplate>,
     default_provider: Option<crate::providers::ProviderKind>,
 ) -> Result<()> {
     eprintln!("{} {}", "Scaffolding:".bright_cyan().bold(), dir.display());
+    if let Some(template) = template {
+        eprintln!(
+            "  {} template {} ({})",
+            "\u{25b6}".bright_blue(),
+            template.name,
+            template.stack.join(", ")
+        );
+    }
 
     // 1. Create directory
     tokio::fs::create_dir_all(dir)
@@ -46,15 +56,19 @@ pub async fn handle_scaffold(
         .output()
         .await;
 
-    // 3. Create minimal project files
-    tokio::fs::write(
-        dir.join("package.json"),
-        r#"{"scripts":{"test":"node -e \"console.log('No tests configured yet')\""}}
+    // 3. Create the template's files, or a minimal project without one
+    if let Some(template) = template {
+        template.write_files(dir).await?;
+    } else {
+        tokio::fs::write(
+            dir.join("package.json"),
+            r#"{"scripts":{"test":"node -e \"console.log('No tests configured yet')\""}}
 "#,
-    )
-    .await?;
-    tokio::fs::create_dir_all(dir.join("public")).await?;
-    tokio::fs::create_dir_all(dir.join("e2e")).await?;
+        )
+        .await?;
+        tokio::fs::create_dir_all(dir.join("public")).await?;
+        tokio::fs::create_dir_all(dir.join("e2e")).await?;
+    }
 
     // 4. Initial commit
     let _ = tokio::process::Command::new("git")
@@ -0,0 +1,277 @@
+//! Named project templates for `ccswarm scaffold --template`.
+//!
+//! A template fixes the stack, the files and directories written before the
+//! first commit, and a per-agent task breakdown that is folded into the
+//! pipeline task. Builtins (todo-app, rest-api, cli-tool, static-site) ship in
+//! `templates/scaffold/`; YAML files in `$CCSWARM_HOME/templates/scaffold/`
+//! add more and replace builtins of the same name, and the project's own
+//! `.ccswarm/templates/scaffold/` (where `ccswarm template copy` puts them)
+//! replaces both.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+pub(crate) const BUILTIN: &[(&str, &str)] = &[
+    (
+        "todo-app",
+        include_str!("../../../templates/scaffold/todo-app.yaml"),
+    ),
+    (
+        "rest-api",
+        include_str!("../../../templates/scaffold/rest-api.yaml"),
+    ),
+    (
+        "cli-tool",
+        include_str!("../../../templates/scaffold/cli-tool.yaml"),
+    ),
+    (
+        "static-site",
+        include_str!("../../../templates/scaffold/static-site.yaml"),
+    ),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ScaffoldTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Default task when `--task` is not given.
+    pub task: String,
+    /// Flow to run when `--flow` is not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow: Option<String>,
+    #[serde(default)]
+    pub stack: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<TemplateFile>,
+    #[serde(default)]
+    pub agents: Vec<AgentTasks>,
+    /// Target for `--auto-deploy`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy: Option<crate::deploy::DeploySpec>,
+}
+
+/// A file to write, or a directory to create when `path` ends in `/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TemplateFile {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct AgentTasks {
+    pub agent: String,
+    pub tasks: Vec<String>,
+}
+
+impl ScaffoldTemplate {
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.task.trim().is_empty() {
+            bail!("template '{}' has an empty task", self.name);
+        }
+        if let Some(deploy) = &self.deploy {
+            crate::deploy::resolve(deploy).with_context(|| format!("template '{}'", self.name))?;
+        }
+        for file in &self.files {
+            let path = Path::new(&file.path);
+            if path.is_absolute()
+                || path
+                    .components()
+                    .any(|c| matches!(c, Component::ParentDir | Component::Prefix(_)))
+            {
+                bail!(
+                    "template '{}': file path '{}' must stay inside the project",
+                    self.name,
+                    file.path
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the template's directories and files under `dir`.
+    pub async fn write_files(&self, dir: &Path) -> Result<()> {
+        for file in &self.files {
+            let target = dir.join(&file.path);
+            if file.path.ends_with('/') {
+                tokio::fs::create_dir_all(&target).await?;
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&target, file.content.as_deref().unwrap_or_default())
+                .await
+                .with_context(|| format!("Failed to write {}", target.display()))?;
+        }
+        Ok(())
+    }
+
+    /// The pipeline task: `task` (or the template's own) followed by the
+    /// stack and the per-agent breakdown.
+    pub fn render_task(&self, task: Option<&str>) -> String {
+        let mut out = task.unwrap_or(&self.task).trim().to_string();
+        out.push_str(&format!("\n\nProject template: {}", self.name));
+        if !self.description.is_empty() {
+            out.push_str(&format!(" — {}", self.description));
+        }
+        if !self.stack.is_empty() {
+            out.push_str(&format!("\nStack: {}", self.stack.join(", ")));
+        }
+        if !self.agents.is_empty() {
+            out.push_str("\n\nWork breakdown:");
+            for agent in &self.agents {
+                out.push_str(&format!("\n[{}]", agent.agent));
+                for task in &agent.tasks {
+                    out.push_str(&format!("\n- {task}"));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// `$CCSWARM_HOME/templates/scaffold`
+pub(crate) fn user_template_dir() -> Option<PathBuf> {
+    crate::workflow::facets::ccswarm_home().map(|home| home.join("templates").join("scaffold"))
+}
+
+/// `<repo>/.ccswarm/templates/scaffold`
+pub(crate) fn project_template_dir(repo: &Path) -> PathBuf {
+    repo.join(".ccswarm").join("templates").join("scaffold")
+}
+
+/// Builtins plus every `*.yaml` / `*.yml` in `dirs`, sorted by name. A
+/// later directory's template replaces an earlier one of the same name.
+pub(crate) async fn load_templates(dirs: &[&Path]) -> Result<Vec<ScaffoldTemplate>> {
+    let mut templates = BUILTIN
+        .iter()
+        .map(|(name, source)| {
+            serde_yml::from_str::<ScaffoldTemplate>(source)
+                .with_context(|| format!("builtin scaffold template '{name}' is invalid"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for dir in dirs.iter().filter(|d| d.is_dir()) {
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if !matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("yaml" | "yml")
+            ) {
+                continue;
+            }
+            let source = tokio::fs::read_to_string(&path).await?;
+            let template: ScaffoldTemplate = serde_yml::from_str(&source)
+                .with_context(|| format!("Invalid scaffold template {}", path.display()))?;
+            templates.retain(|t| t.name != template.name);
+            templates.push(template);
+        }
+    }
+
+    for template in &templates {
+        template.validate()?;
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// Template directories in precedence order: the user's, then `repo`'s.
+pub(crate) fn template_dirs(repo: &Path) -> Vec<PathBuf> {
+    user_template_dir()
+        .into_iter()
+        .chain(std::iter::once(project_template_dir(repo)))
+        .collect()
+}
+
+/// Look up `name` among the builtin, user and project templates.
+pub(crate) async fn find_template(name: &str, repo: &Path) -> Result<ScaffoldTemplate> {
+    let dirs = template_dirs(repo);
+    let templates = load_templates(&dirs.iter().map(PathBuf::as_path).collect::<Vec<_>>()).await?;
+    let names = templates
+        .iter()
+        .map(|t| t.name.clone())
+        .collect::<Vec<_>>()
+        .join(", ");
+    templates
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| anyhow!("Unknown scaffold template '{}'. Available: {}", name, names))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_builtins_load_and_validate() {
+        let templates = load_templates(&[]).await.unwrap();
+        let names: Vec<_> = templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["cli-tool", "rest-api", "static-site", "todo-app"]);
+        assert!(templates.iter().all(|t| !t.agents.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_user_template_overrides_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("todo.yaml"),
+            "name: todo-app\ntask: My own todo app\nstack: [Svelte]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("game.yml"),
+            "name: game\ntask: Snake\nfiles:\n  - path: assets/\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let templates = load_templates(&[dir.path()]).await.unwrap();
+        assert_eq!(templates.len(), 5);
+        let todo = templates.iter().find(|t| t.name == "todo-app").unwrap();
+        assert_eq!(todo.stack, ["Svelte"]);
+    }
+
+    #[tokio::test]
+    async fn test_paths_outside_project_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("bad.yaml"),
+            "name: bad\ntask: x\nfiles:\n  - path: ../escape.txt\n    content: hi\n",
+        )
+        .unwrap();
+        let err = load_templates(&[dir.path()]).await.unwrap_err();
+        assert!(err.to_string().contains("must stay inside the project"));
+    }
+
+    #[tokio::test]
+    async fn test_write_files_and_render_task() {
+        let template: ScaffoldTemplate = serde_yml::from_str(
+            "name: t\ndescription: demo\ntask: Default task\nstack: [Rust, axum]\n\
+             files:\n  - path: src/\n  - path: docs/README.md\n    content: hello\n\
+             agents:\n  - agent: backend\n    tasks: [routes, storage]\n",
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        template.write_files(dir.path()).await.unwrap();
+        assert!(dir.path().join("src").is_dir());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("docs/README.md")).unwrap(),
+            "hello"
+        );
+
+        assert_eq!(
+            template.render_task(None),
+            "Default task\n\nProject template: t — demo\nStack: Rust, axum\n\n\
+             Work breakdown:\n[backend]\n- routes\n- storage"
+        );
+        assert!(
+            template
+                .render_task(Some("Custom"))
+                .starts_with("Custom\n\n")
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! `ccswarm secrets`: per-role secret grants and the scoped `.env` files in
+//! agent worktrees (see [`crate::secrets`]).
+
+use super::super::*;
+use crate::git::bootstrap::{AgentWorktree, agent_worktrees};
+
+impl CliRunner {
+    pub(crate) async fn handle_secrets(&self, action: &SecretsAction) -> Result<()> {
+        match action {
+            SecretsAction::List => self.secrets_list().await,
+            SecretsAction::Sync { agent } => self.secrets_sync(agent.as_deref()).await,
+            SecretsAction::Revoke { agent } => {
+                let revoked = self.revoke_secrets(agent.as_deref()).await?;
+                self.print_secrets_result("revoked", &revoked)
+            }
+        }
+    }
+
+    async fn secrets_list(&self) -> Result<()> {
+        let scope = self.config.secrets.load(&self.repo_path)?;
+        let mut roles = Vec::new();
+        for role in scope.roles() {
+            let worktree = agent_worktrees(&self.config, &self.repo_path)
+                .into_iter()
+                .find(|w| w.agent == role);
+            let scoped = match &worktree {
+                Some(w) => crate::secrets::is_scoped(&w.path).await,
+                None => false,
+            };
+            roles.push((role, scope.granted(role), scoped));
+        }
+        if self.json_output {
+            let data: Vec<serde_json::Value> = roles
+                .iter()
+                .map(|(role, granted, scoped)| {
+                    serde_json::json!({
+                        "role": role,
+                        "secrets": granted
+                            .iter()
+                            .map(|(name, value)| serde_json::json!({ "name": name, "set": value.is_some() }))
+                            .collect::<Vec<_>>(),
+                        "worktree_env": scoped,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": data,
+                }))?
+            );
+            return Ok(());
+        }
+        if roles.is_empty() {
+            println!("No secrets granted; add `secrets.grants` to the config.");
+            return Ok(());
+        }
+        for (role, granted, scoped) in &roles {
+            let names: Vec<String> = granted
+                .iter()
+                .map(|(name, value)| match value {
+                    Some(_) => name.to_string(),
+                    None => format!("{} {}", name, "(not set)".bright_red()),
+                })
+                .collect();
+            println!(
+                "  {:<12} {}{}",
+                role.bright_yellow(),
+                names.join(", "),
+                if *scoped {
+                    "  [worktree .env]".bright_black().to_string()
+                } else {
+                    String::new()
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// Write (or rotate) the scoped `.env` of every agent worktree on disk.
+    async fn secrets_sync(&self, agent: Option<&str>) -> Result<()> {
+        let scope = self.config.secrets.load(&self.repo_path)?;
+        let mut written = Vec::new();
+        for worktree in self.secret_worktrees(agent)? {
+            if !worktree.path.exists() {
+                continue;
+            }
+            if scope
+                .write_env_file(&worktree.path, &worktree.agent)
+                .await?
+                .is_some()
+            {
+                written.push(worktree.agent);
+            }
+        }
+        self.print_secrets_result("written", &written)
+    }
+
+    /// Remove scoped `.env` files from agent worktrees; returns the agents
+    /// that had one.
+    pub(crate) async fn revoke_secrets(&self, agent: Option<&str>) -> Result<Vec<String>> {
+        let mut revoked = Vec::new();
+        for worktree in self.secret_worktrees(agent)? {
+            if crate::secrets::revoke(&worktree.path).await? {
+                revoked.push(worktree.agent);
+            }
+        }
+        Ok(revoked)
+    }
+
+    fn secret_worktrees(&self, agent: Option<&str>) -> Result<Vec<AgentWorktree>> {
+        let worktrees: Vec<AgentWorktree> = agent_worktrees(&self.config, &self.repo_path)
+            .into_iter()
+            .filter(|w| agent.is_none_or(|a| w.agent == a))
+            .collect();
+        if let Some(agent) = agent
+            && worktrees.is_empty()
+        {
+            anyhow::bail!("No agent '{}' in the config", agent);
+        }
+        Ok(worktrees)
+    }
+
+    fn print_secrets_result(&self, verb: &str, agents: &[String]) -> Result<()> {
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": { verb: agents },
+                }))?
+            );
+        } else if agents.is_empty() {
+            println!("No scoped secrets {verb}.");
+        } else {
+            println!("Scoped secrets {verb} for {}", agents.join(", "));
+        }
+        Ok(())
+    }
+}
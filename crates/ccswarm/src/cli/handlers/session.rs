@@ -1,5 +1,11 @@
 use super::super::*;
-use crate::events::SessionInfo;
+use super::queue_state::{QUEUE_FILE, load_queue};
+use super::run_utils::resolve_run_path;
+use crate::events::{Event, EventLevel, EventRecorder, EventType, SessionInfo};
+use crate::session::names::SessionNames;
+use crate::session::resume::{self, AwaySummary};
+use ai_session::core::suspend::{Suspend, signal_tree, terminate_tree};
+use ai_session::core::transfer;
 
 impl CliRunner {
     pub(crate) async fn handle_session(&self, action: &SessionAction) -> Result<()> {
@@ -10,17 +16,42 @@ impl CliRunner {
                 agent,
                 workspace,
                 background,
+                name,
             } => {
-                self.session_create(agent, workspace.as_deref(), *background)
+                self.session_create(agent, workspace.as_deref(), *background, name.as_deref())
                     .await
             }
+            SessionAction::Rename { session, new_name } => {
+                self.session_rename(session, new_name).await
+            }
+            SessionAction::Alias {
+                session,
+                alias,
+                remove,
+            } => self.session_alias(session, alias.as_deref(), *remove).await,
             SessionAction::Pause { session_id } => self.session_pause(session_id).await,
             SessionAction::Resume { session_id } => self.session_resume(session_id).await,
             SessionAction::Attach { session_id } => self.session_attach(session_id).await,
             SessionAction::Detach { session_id } => self.session_detach(session_id).await,
+            SessionAction::Watch {
+                session_id,
+                no_follow,
+            } => self.session_watch(session_id.as_deref(), *no_follow).await,
+            SessionAction::Timeline { session_id, width } => {
+                self.session_timeline(session_id.as_deref(), *width).await
+            }
+            SessionAction::Rekey {
+                transcripts,
+                generate_key,
+            } => self.session_rekey(transcripts, *generate_key).await,
+            SessionAction::Cp {
+                source,
+                destination,
+            } => self.session_cp(source, destination).await,
             SessionAction::Kill { session_id, force } => {
                 self.session_kill(session_id, *force).await
             }
+            SessionAction::Group { action } => self.handle_session_group(action).await,
         }
     }
 
@@ -102,10 +133,17 @@ impl CliRunner {
                     movements_completed: 0,
                     agents_used: Vec::new(),
                     has_errors: false,
+                    name: None,
+                    progress: None,
                 });
             }
         }
 
+        let names = SessionNames::load(&self.repo_path).await?;
+        for info in &mut sessions {
+            info.name = names.name_of(&info.run_id).map(str::to_string);
+        }
+
         // Sort by started_at descending (newest first). Sessions without
         // timestamps sort to the end.
         sessions.sort_by(|a, b| {
@@ -158,8 +196,9 @@ impl CliRunner {
 
         // ── Table header ────────────────────────────────────────────────
         println!(
-            "{:<36}  {:<19}  {:<10}  {:>8}  {:>6}  {:<12}  {}",
+            "{:<36}  {:<16}  {:<19}  {:<10}  {:>8}  {:>6}  {:<12}  {}",
             "Session ID".bright_cyan().bold(),
+            "Name".bright_cyan().bold(),
             "Date".bright_cyan().bold(),
             "Status".bright_cyan().bold(),
             "Duration".bright_cyan().bold(),
@@ -167,7 +206,7 @@ impl CliRunner {
             "Piece".bright_cyan().bold(),
             "Movement".bright_cyan().bold(),
         );
-        println!("{}", "─".repeat(110).bright_black());
+        println!("{}", "─".repeat(128).bright_black());
 
         for info in &sessions {
             let started_str = info
@@ -186,11 +225,16 @@ impl CliRunner {
             };
 
             let task_str = info.task.as_deref().unwrap_or("—");
-            let movement_str = info.last_movement.as_deref().unwrap_or("—");
+            let movement_str = match (&info.last_movement, &info.progress) {
+                (Some(stage), Some(progress)) => format!("{stage} · {}", progress.summary()),
+                (Some(stage), None) => stage.clone(),
+                (None, _) => "—".to_string(),
+            };
 
             println!(
-                "{:<36}  {:<19}  {:<10}  {:>8}  {:>6}  {:<12}  {}",
+                "{:<36}  {:<16}  {:<19}  {:<10}  {:>8}  {:>6}  {:<12}  {}",
                 info.run_id.bright_yellow(),
+                info.name.as_deref().unwrap_or("—"),
                 started_str,
                 status_colored,
                 duration_str,
@@ -226,11 +270,9 @@ impl CliRunner {
     }
 
     async fn session_view(&self, id: &str) -> Result<()> {
-        let run_dir = self.repo_path.join(".ccswarm/runs").join(id);
-
-        if !run_dir.exists() {
-            anyhow::bail!("Session '{}' not found in .ccswarm/runs/", id);
-        }
+        let run_dir = resolve_run_path(&self.repo_path, Some(id))
+            .await
+            .map_err(|e| anyhow!("Session '{}' not found: {}", id, e))?;
 
         let summary_path = run_dir.join("summary.json");
         let summary: Option<serde_json::Value> = if summary_path.exists() {
@@ -360,6 +402,7 @@ impl CliRunner {
         agent: &str,
         workspace: Option<&str>,
         background: bool,
+        name: Option<&str>,
     ) -> Result<()> {
         let valid_agents = ["frontend", "backend", "devops", "qa"];
         if !valid_agents.contains(&agent) {
@@ -374,6 +417,17 @@ impl CliRunner {
             .map(PathBuf::from)
             .unwrap_or_else(|| self.repo_path.clone());
 
+        if let Some(name) = name {
+            self.ensure_not_run_id(name)?;
+            let mut names = SessionNames::load(&self.repo_path).await?;
+            names.reserve(name, Some(agent))?;
+            names.save(&self.repo_path).await?;
+        }
+        let suggestion = match name {
+            Some(name) => format!("ccswarm pipeline --name {name} --task ..."),
+            None => "ccswarm pipeline".to_string(),
+        };
+
         if self.json_output {
             println!(
                 "{}",
@@ -383,7 +437,8 @@ impl CliRunner {
                     "agent": agent,
                     "workspace": workspace_path.display().to_string(),
                     "background": background,
-                    "suggestion": "Use 'ccswarm pipeline' to execute workflows with sessions."
+                    "name": name,
+                    "suggestion": format!("Use '{suggestion}' to execute workflows with sessions.")
                 }))?
             );
         } else {
@@ -399,16 +454,92 @@ impl CliRunner {
             if background {
                 println!("  Mode:      background");
             }
+            if let Some(name) = name {
+                println!("  Name:      {} (reserved)", name.bright_yellow());
+            }
             println!();
             println!(
                 "Use {} to execute workflows with automatic session management.",
-                "ccswarm pipeline".bright_cyan()
+                suggestion.bright_cyan()
             );
         }
 
         Ok(())
     }
 
+    /// A name must not shadow an existing run directory, or it could never
+    /// be resolved.
+    fn ensure_not_run_id(&self, name: &str) -> Result<()> {
+        crate::session::names::validate_name(name)?;
+        if self.repo_path.join(".ccswarm/runs").join(name).is_dir() {
+            anyhow::bail!("'{}' is already a run id", name);
+        }
+        Ok(())
+    }
+
+    async fn session_rename(&self, session: &str, new_name: &str) -> Result<()> {
+        self.ensure_not_run_id(new_name)?;
+        let mut names = SessionNames::load(&self.repo_path).await?;
+        names.rename(session, new_name)?;
+        names.save(&self.repo_path).await?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "session": session,
+                    "name": new_name,
+                }))?
+            );
+        } else {
+            println!(
+                "{} Session '{}' renamed to '{}'",
+                "✓".bright_green(),
+                session,
+                new_name.bright_yellow()
+            );
+        }
+        Ok(())
+    }
+
+    async fn session_alias(&self, session: &str, alias: Option<&str>, remove: bool) -> Result<()> {
+        let mut names = SessionNames::load(&self.repo_path).await?;
+        let (verb, alias) = if remove {
+            names.remove_alias(session)?;
+            ("removed", session)
+        } else {
+            let alias = alias.ok_or_else(|| anyhow!("Give the alias to add"))?;
+            self.ensure_not_run_id(alias)?;
+            if names.get(session).is_none() {
+                // A bare run id: make sure it exists before naming it.
+                resolve_run_path(&self.repo_path, Some(session)).await?;
+            }
+            names.add_alias(session, alias)?;
+            ("added", alias)
+        };
+        names.save(&self.repo_path).await?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "alias": alias,
+                    "state": verb,
+                }))?
+            );
+        } else {
+            println!(
+                "{} Alias '{}' {}",
+                "✓".bright_green(),
+                alias.bright_yellow(),
+                verb
+            );
+        }
+        Ok(())
+    }
+
     fn print_session_action_hint<W: Write>(
         &self,
         writer: &mut W,
@@ -450,15 +581,151 @@ impl CliRunner {
     }
 
     async fn session_pause(&self, session_id: &str) -> Result<()> {
-        let stdout = std::io::stdout();
-        let mut stdout = stdout.lock();
-        self.print_session_action_hint(&mut stdout, session_id, "pause", "/resume")
+        self.suspend_run(session_id, Suspend::Stop).await
     }
 
     async fn session_resume(&self, session_id: &str) -> Result<()> {
-        let stdout = std::io::stdout();
-        let mut stdout = stdout.lock();
-        self.print_session_action_hint(&mut stdout, session_id, "resume", "")
+        self.suspend_run(session_id, Suspend::Continue).await
+    }
+
+    /// Pause or resume a running pipeline and report what was signalled.
+    async fn suspend_run(&self, session_id: &str, action: Suspend) -> Result<()> {
+        let run_dir = resolve_run_path(&self.repo_path, Some(session_id)).await?;
+        let run_id = run_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| session_id.to_string());
+        let Suspended { processes, away } = suspend_run_dir(&run_dir, &run_id, action).await?;
+        let verb = match action {
+            Suspend::Stop => "paused",
+            Suspend::Continue => "resumed",
+        };
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "session_id": session_id,
+                    "state": verb,
+                    "processes": processes,
+                    "away": away,
+                }))?
+            );
+        } else {
+            println!(
+                "{} Session '{}' {} ({} process{})",
+                "✓".bright_green(),
+                session_id.bright_yellow(),
+                verb,
+                processes.len(),
+                if processes.len() == 1 { "" } else { "es" }
+            );
+            if action == Suspend::Stop {
+                println!(
+                    "Continue it with {}",
+                    format!("ccswarm session resume {session_id}").bright_cyan()
+                );
+            }
+            if let Some(away) = &away {
+                println!(
+                    "While it was away: {} commit{}, {} changed file{}, {} completed task{} \
+                     (passed on to its next stage)",
+                    away.commits.len(),
+                    if away.commits.len() == 1 { "" } else { "s" },
+                    away.changed_files.len(),
+                    if away.changed_files.len() == 1 {
+                        ""
+                    } else {
+                        "s"
+                    },
+                    away.completed_tasks.len(),
+                    if away.completed_tasks.len() == 1 {
+                        ""
+                    } else {
+                        "s"
+                    }
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy a file between the host and a session's workspace.
+    async fn session_cp(&self, source: &str, destination: &str) -> Result<()> {
+        let (session, report) = match (session_path(source), session_path(destination)) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("Copy between two sessions through the host instead")
+            }
+            (None, None) => {
+                anyhow::bail!("One side of the copy must be <session>:<path>")
+            }
+            (Some((session, path)), None) => {
+                let root = self.session_workspace(session).await?;
+                let progress = self.transfer_progress();
+                let report =
+                    transfer::get(&root, Path::new(path), Path::new(destination), progress).await?;
+                (session, report)
+            }
+            (None, Some((session, path))) => {
+                let root = self.session_workspace(session).await?;
+                let progress = self.transfer_progress();
+                let report =
+                    transfer::put(&root, Path::new(source), Path::new(path), progress).await?;
+                (session, report)
+            }
+        };
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "session_id": session,
+                    "data": report,
+                }))?
+            );
+        } else {
+            println!(
+                "{} Copied {} → {} ({} bytes, sha256 {})",
+                "✓".bright_green(),
+                source.bright_yellow(),
+                destination.bright_yellow(),
+                report.bytes,
+                &report.sha256[..12]
+            );
+        }
+        Ok(())
+    }
+
+    /// The workspace a session's run works in: the directory holding its
+    /// `.ccswarm/runs/`.
+    pub(super) async fn session_workspace(&self, session: &str) -> Result<PathBuf> {
+        let run_dir = resolve_run_path(&self.repo_path, Some(session)).await?;
+        Ok(run_dir
+            .ancestors()
+            .nth(3)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.repo_path.clone()))
+    }
+
+    /// A progress line on stderr for transfers over a mebibyte.
+    fn transfer_progress(&self) -> impl FnMut(transfer::TransferProgress) {
+        let quiet = self.json_output;
+        move |progress: transfer::TransferProgress| {
+            const MIB: f64 = 1024.0 * 1024.0;
+            if quiet || progress.total < 1024 * 1024 {
+                return;
+            }
+            eprint!(
+                "\r  {:.1} / {:.1} MiB",
+                progress.bytes as f64 / MIB,
+                progress.total as f64 / MIB
+            );
+            if progress.bytes == progress.total {
+                eprintln!();
+            }
+        }
     }
 
     async fn session_attach(&self, session_id: &str) -> Result<()> {
@@ -473,34 +740,601 @@ impl CliRunner {
         self.print_session_action_hint(&mut stdout, session_id, "detach", "")
     }
 
+    /// Print a session's stages, provider calls, gate commands, waits and
+    /// pauses as bars against its wall clock, then where the time went.
+    async fn session_timeline(&self, session_id: Option<&str>, width: usize) -> Result<()> {
+        use crate::events::timeline::{self, MarkKind, Timeline, format_ms};
+
+        let run_dir = resolve_run_path(&self.repo_path, session_id).await?;
+        let run_id = run_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let content = tokio::fs::read_to_string(run_dir.join("events.ndjson"))
+            .await
+            .unwrap_or_default();
+        let timeline = Timeline::from_events(&run_id, &content);
+        let now = timeline::clock(&run_dir, &timeline);
+        let breakdown = timeline.breakdown(now);
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": {
+                        "timeline": timeline,
+                        "breakdown": breakdown,
+                    }
+                }))?
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{}  {}",
+            "Timeline".bright_cyan().bold(),
+            run_id.bright_yellow().bold()
+        );
+        println!("{}", "=".repeat(70).bright_black());
+        if timeline.spans.is_empty() {
+            println!("No timeline events recorded for this session.");
+            return Ok(());
+        }
+        for row in timeline.render_text(width.max(10), now) {
+            println!("{}", row);
+        }
+
+        let share = |ms: u64| (ms * 100).checked_div(breakdown.total_ms).unwrap_or(0);
+        println!();
+        println!(
+            "{} {}{}",
+            "Where the time went:".bold(),
+            format_ms(breakdown.total_ms),
+            if timeline.is_running() {
+                " (running)"
+            } else {
+                ""
+            }
+        );
+        for (label, ms) in [
+            ("provider", breakdown.provider_ms),
+            ("commands", breakdown.commands_ms),
+            ("waiting", breakdown.waiting_ms),
+            ("paused", breakdown.paused_ms),
+            ("other", breakdown.other_ms),
+        ] {
+            if ms > 0 {
+                println!("  {:<9} {:>8}  {:>3}%", label, format_ms(ms), share(ms));
+            }
+        }
+
+        let errors: Vec<_> = timeline
+            .marks
+            .iter()
+            .filter(|m| m.kind == MarkKind::Error)
+            .collect();
+        if !errors.is_empty() {
+            println!();
+            println!("{}", "Errors".bright_red().bold());
+            for mark in errors {
+                let at = timeline
+                    .started
+                    .map(|s| format_ms((mark.ts - s).num_milliseconds().max(0) as u64))
+                    .unwrap_or_default();
+                println!("  +{:<8} {}", at, mark.message);
+            }
+        }
+        Ok(())
+    }
+
+    /// Rotate encryption keys: rewrite every persisted session (and the given
+    /// transcripts) that is plaintext or sealed with a retired key so only the
+    /// current key is needed to read them.
+    async fn session_rekey(&self, transcripts: &[PathBuf], generate_key: bool) -> Result<()> {
+        use ai_session::persistence::encryption::{KEY_ENV, KEY_FILE_ENV, OLD_KEYS_ENV};
+        use ai_session::persistence::{EncryptionKey, Keyring, PersistenceManager};
+
+        if generate_key {
+            let key = EncryptionKey::generate();
+            if self.json_output {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "status": "success",
+                        "data": { "key": key.to_base64(), "key_id": key.id() }
+                    }))?
+                );
+            } else {
+                println!("{}", key.to_base64());
+                eprintln!(
+                    "Key {} — set it as {KEY_ENV} (or write it to a file named by {KEY_FILE_ENV})",
+                    key.id()
+                );
+            }
+            return Ok(());
+        }
+
+        let keyring = Keyring::from_env()?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No encryption key configured. Set {KEY_ENV} or {KEY_FILE_ENV} to the new key \
+                 and {OLD_KEYS_ENV} to the keys it replaces (`ccswarm session rekey --generate-key` makes one)"
+            )
+        })?;
+        let sessions = PersistenceManager::new(self.repo_path.join(".ccswarm").join("sessions"))
+            .with_encryption(keyring.clone())
+            .rekey_sessions()
+            .await?;
+        let mut rewritten = Vec::new();
+        for path in transcripts {
+            rewritten.push((path, crate::providers::transcript::rekey(path, &keyring)?));
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": {
+                        "key_id": keyring.current().id(),
+                        "sessions": sessions,
+                        "transcripts": rewritten
+                            .iter()
+                            .map(|(path, lines)| serde_json::json!({
+                                "path": path,
+                                "lines": lines,
+                            }))
+                            .collect::<Vec<_>>(),
+                    }
+                }))?
+            );
+            return Ok(());
+        }
+        println!(
+            "{} Re-encrypted {} session{} with key {}",
+            "✓".bright_green(),
+            sessions,
+            if sessions == 1 { "" } else { "s" },
+            keyring.current().id().bright_yellow()
+        );
+        for (path, lines) in rewritten {
+            println!(
+                "  {} ({} line{})",
+                path.display(),
+                lines,
+                if lines == 1 { "" } else { "s" }
+            );
+        }
+        Ok(())
+    }
+
+    /// Follow a session's event stream without any way to send input.
+    ///
+    /// Each watcher reads `events.ndjson` independently, so several people can
+    /// watch the same run at once. A marker file under `watchers/` lets each
+    /// of them see how many others are attached; it is removed on exit,
+    /// including Ctrl-C, and markers left by killed watchers are not counted.
+    /// Agents' replies are printed from `chat.ndjson` as they are written.
+    async fn session_watch(&self, session_id: Option<&str>, no_follow: bool) -> Result<()> {
+        let run_dir = resolve_run_path(&self.repo_path, session_id).await?;
+        let events_path = run_dir.join("events.ndjson");
+        let summary_path = run_dir.join("summary.json");
+        let run_id = run_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let _guard = WatcherGuard::register(&run_dir).await;
+        let others = count_watchers(&run_dir).await.saturating_sub(1);
+
+        println!(
+            "{} Watching session {} {}",
+            "👁".bright_cyan(),
+            run_id.bright_yellow().bold(),
+            "(read-only)".bright_black()
+        );
+        if others > 0 {
+            println!("  {} other watcher(s) attached", others);
+        }
+        println!();
+
+        let chat_path = run_dir.join(crate::session::stream::CHAT_STREAM_FILE);
+        let mut offset: u64 = 0;
+        let mut chat_offset: u64 = 0;
+        let mut replies = ReplyPrinter::default();
+        loop {
+            let events = read_new_lines(&events_path, &mut offset).await?;
+            if !events.is_empty() {
+                print!("{}", replies.interrupt());
+            }
+            for line in events.lines() {
+                for rendered in render_watch_event(line) {
+                    println!("{}", rendered);
+                }
+            }
+            let chat = read_new_lines(&chat_path, &mut chat_offset).await?;
+            for chunk in crate::session::stream::parse_chunks(&chat) {
+                print!("{}", replies.render(&chunk));
+            }
+            std::io::Write::flush(&mut std::io::stdout())?;
+
+            if no_follow || summary_path.exists() {
+                break;
+            }
+            // Following is ended with Ctrl-C; return so the guard drops.
+            tokio::select! {
+                _ = tokio::time::sleep(std::time::Duration::from_millis(200)) => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+        print!("{}", replies.interrupt());
+
+        if summary_path.exists() {
+            println!("{} session finished", "✓".bright_green().bold());
+        }
+        Ok(())
+    }
+
     async fn session_kill(&self, session_id: &str, force: bool) -> Result<()> {
+        let run_dir = resolve_run_path(&self.repo_path, Some(session_id)).await?;
+        let processes = kill_run_dir(&run_dir, session_id, force)?;
         if self.json_output {
             println!(
                 "{}",
                 serde_json::to_string_pretty(&serde_json::json!({
-                    "status": "info",
+                    "status": "success",
                     "session_id": session_id,
+                    "state": "killed",
                     "force": force,
-                    "message": "Session termination is managed by the pipeline engine."
+                    "processes": processes,
                 }))?
             );
         } else {
             println!(
-                "{} Session '{}' termination is managed by the pipeline engine.",
-                "ℹ".bright_cyan(),
-                session_id.bright_yellow()
+                "{} Session '{}' killed ({} process{})",
+                "✓".bright_green(),
+                session_id.bright_yellow(),
+                processes.len(),
+                if processes.len() == 1 { "" } else { "es" }
             );
-            if force {
-                println!("  (force flag noted)");
+        }
+        Ok(())
+    }
+}
+
+/// Presence marker for one `session watch` process. Dropping it detaches.
+struct WatcherGuard {
+    path: Option<PathBuf>,
+}
+
+impl WatcherGuard {
+    async fn register(run_dir: &Path) -> Self {
+        let dir = run_dir.join("watchers");
+        let path = dir.join(format!("{}", std::process::id()));
+        let registered = tokio::fs::create_dir_all(&dir).await.is_ok()
+            && tokio::fs::write(&path, chrono::Utc::now().to_rfc3339())
+                .await
+                .is_ok();
+        Self {
+            path: registered.then_some(path),
+        }
+    }
+}
+
+impl Drop for WatcherGuard {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Watchers whose process is still alive. Markers left behind by a watcher
+/// that was killed are removed.
+async fn count_watchers(run_dir: &Path) -> usize {
+    let mut count = 0;
+    if let Ok(mut entries) = tokio::fs::read_dir(run_dir.join("watchers")).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let pid = entry.file_name().to_string_lossy().parse::<i32>().ok();
+            // Signal 0 only checks that the process exists.
+            if pid.is_some_and(|pid| {
+                nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), None).is_ok()
+            }) {
+                count += 1;
+            } else {
+                let _ = tokio::fs::remove_file(entry.path()).await;
             }
-            println!(
-                "Use {} to manage running pipelines.",
-                "ccswarm pipeline".bright_cyan()
-            );
         }
+    }
+    count
+}
 
-        Ok(())
+/// Render one NDJSON event for `session watch`: a header line, followed by
+/// the stage's output preview (if any) with parsed errors and warnings
+/// highlighted.
+/// Whole lines appended to `path` since `offset`, which is moved past them.
+/// A half-written last line is left for the next poll instead of dropped.
+async fn read_new_lines(path: &Path, offset: &mut u64) -> Result<String> {
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    let content = tokio::fs::read(path).await?;
+    let Some(fresh) = content.get(*offset as usize..) else {
+        return Ok(String::new());
+    };
+    let complete = fresh
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    *offset += complete as u64;
+    Ok(String::from_utf8_lossy(&fresh[..complete]).into_owned())
+}
+
+/// Prints streamed replies for `session watch`: each piece of text as it
+/// arrives, under a header whenever another reply takes over.
+#[derive(Default)]
+struct ReplyPrinter {
+    current: Option<String>,
+    mid_line: bool,
+}
+
+impl ReplyPrinter {
+    fn render(&mut self, chunk: &crate::session::stream::ChatChunk) -> String {
+        use crate::session::stream::ChunkKind;
+
+        let mut out = String::new();
+        if self.current.as_deref() != Some(chunk.reply.as_str()) {
+            if matches!(chunk.kind, ChunkKind::End) && chunk.text.is_empty() {
+                return out;
+            }
+            out.push_str(self.interrupt());
+            let stage = chunk
+                .stage
+                .as_deref()
+                .map(|stage| format!(" ({stage})"))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "{} {}{}\n",
+                "💬".bright_cyan(),
+                chunk.agent.bright_cyan().bold(),
+                stage.bright_black()
+            ));
+            self.current = Some(chunk.reply.clone());
+        }
+        out.push_str(&chunk.text);
+        if !chunk.text.is_empty() {
+            self.mid_line = !chunk.text.ends_with('\n');
+        }
+        match chunk.kind {
+            ChunkKind::Start | ChunkKind::Delta => {}
+            ChunkKind::End => out.push_str(self.interrupt()),
+            ChunkKind::Stopped => {
+                out.push_str(self.interrupt());
+                out.push_str(&format!("{}\n", "■ stopped".bright_red()));
+            }
+        }
+        out
+    }
+
+    /// End the reply being printed, so other output starts on its own line
+    /// and the reply gets a fresh header if it continues.
+    fn interrupt(&mut self) -> &'static str {
+        self.current = None;
+        if std::mem::take(&mut self.mid_line) {
+            "\n"
+        } else {
+            ""
+        }
+    }
+}
+
+fn render_watch_event(line: &str) -> Vec<String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Vec::new();
+    }
+    let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+        return vec![line.to_string()];
+    };
+
+    let ts = event.get("ts").and_then(|v| v.as_str()).unwrap_or("");
+    let ts_short = ts.split('T').nth(1).and_then(|t| t.get(..8)).unwrap_or(ts);
+    let stage = event.get("stage").and_then(|v| v.as_str()).unwrap_or("");
+    let message = event.get("message").and_then(|v| v.as_str()).unwrap_or("");
+    let level = event
+        .get("level")
+        .and_then(|v| v.as_str())
+        .unwrap_or("info");
+    let message = match level {
+        "error" => message.bright_red().bold(),
+        "warn" => message.bright_yellow(),
+        _ => message.normal(),
+    };
+
+    let mut rendered = vec![format!(
+        "{} {:<12} {}",
+        ts_short.bright_black(),
+        stage.bright_magenta(),
+        message
+    )];
+
+    let preview = event
+        .get("metadata")
+        .and_then(|m| m.get("output_preview"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    if preview.is_empty() {
+        return rendered;
+    }
+
+    let mut manager = ai_session::OutputManager::new();
+    if let Ok(processed) = manager.process_output(preview) {
+        for highlight in &processed.highlights {
+            let label = format!("{:?}", highlight.category).to_lowercase();
+            let first_line = highlight.message.lines().next().unwrap_or("");
+            rendered.push(format!(
+                "    {} {}",
+                format!("[{}]", label).bright_red().bold(),
+                first_line
+            ));
+        }
     }
+    for preview_line in preview.lines() {
+        rendered.push(format!("    {}", highlight_output_line(preview_line)));
+    }
+    rendered
+}
+
+/// Color a single output line by what it looks like: compiler/test errors in
+/// red, warnings in yellow, file locations in cyan, everything else dimmed.
+fn highlight_output_line(line: &str) -> colored::ColoredString {
+    let trimmed = line.trim_start();
+    let lower = trimmed.to_lowercase();
+    if lower.starts_with("error")
+        || lower.contains("panicked at")
+        || lower.contains("failed")
+        || lower.starts_with("fail ")
+    {
+        line.bright_red()
+    } else if lower.starts_with("warning") || lower.starts_with("warn") {
+        line.bright_yellow()
+    } else if trimmed.starts_with("--> ") || trimmed.starts_with("at ") {
+        line.bright_cyan()
+    } else {
+        line.bright_black()
+    }
+}
+
+/// Terminate a running pipeline and everything it spawned; returns the
+/// signalled pids.
+pub(crate) fn kill_run_dir(run_dir: &Path, session_id: &str, force: bool) -> Result<Vec<u32>> {
+    let pid = crate::events::run_pid(run_dir)
+        .ok_or_else(|| anyhow!("Session '{}' is not running", session_id))?;
+    terminate_tree(pid, force)
+}
+
+/// What [`suspend_run_dir`] did.
+pub(crate) struct Suspended {
+    /// The signalled pids.
+    pub(crate) processes: Vec<u32>,
+    /// On resume, what changed while the run was paused, if anything.
+    pub(crate) away: Option<AwaySummary>,
+}
+
+/// Stop or continue the process running a pipeline and everything it
+/// spawned (provider CLIs, gate commands), and record the transition in the
+/// run's event log. The run's events are flushed line by line, so the log is
+/// a consistent checkpoint while it is stopped. A pause records the
+/// workspace's `HEAD`; a resume leaves a summary of what changed since for
+/// the run's next stage (see [`crate::session::resume`]).
+pub(crate) async fn suspend_run_dir(
+    run_dir: &Path,
+    session_id: &str,
+    action: Suspend,
+) -> Result<Suspended> {
+    let pid = crate::events::run_pid(run_dir)
+        .ok_or_else(|| anyhow!("Session '{}' is not running", session_id))?;
+    let events = tokio::fs::read_to_string(run_dir.join("events.ndjson"))
+        .await
+        .unwrap_or_default();
+    let paused = SessionInfo::from_events(session_id, &events).status == "paused";
+    match action {
+        Suspend::Stop if paused => anyhow::bail!("Session '{}' is already paused", session_id),
+        Suspend::Continue if !paused => {
+            anyhow::bail!("Session '{}' is not paused", session_id)
+        }
+        _ => {}
+    }
+
+    // `.ccswarm/runs/<id>` sits in the workspace the run works in.
+    let workspace = run_dir.ancestors().nth(3).unwrap_or(run_dir);
+    let (head, away) = match action {
+        Suspend::Stop => (resume::head(workspace).await, None),
+        Suspend::Continue => {
+            let away = away_summary(workspace, session_id, &events).await;
+            // Written before the run continues, so its next stage sees it.
+            if let Some(summary) = &away {
+                resume::write(run_dir, summary).await?;
+            }
+            (None, away)
+        }
+    };
+
+    let processes = signal_tree(pid, action)?;
+    let (event_type, verb) = match action {
+        Suspend::Stop => (EventType::SessionPaused, "paused"),
+        Suspend::Continue => (EventType::SessionResumed, "resumed"),
+    };
+    let runs_dir = run_dir.parent().unwrap_or(run_dir).to_path_buf();
+    let mut metadata = serde_json::json!({
+        "pid": pid,
+        "processes": processes,
+    });
+    if let Some(head) = head {
+        metadata["head"] = serde_json::json!(head);
+    }
+    if let Some(summary) = &away {
+        metadata["away"] = serde_json::to_value(summary)?;
+    }
+    EventRecorder::new_in_runs_dir(runs_dir, session_id)
+        .await?
+        .record(
+            Event::new(
+                session_id,
+                EventLevel::Info,
+                event_type,
+                format!("Session {verb}"),
+            )
+            .with_metadata(metadata),
+        )
+        .await?;
+    Ok(Suspended { processes, away })
+}
+
+/// What changed in `workspace` since the run's last pause: commits after
+/// the `HEAD` it recorded and queue tasks other runs completed. `None` when
+/// nothing did.
+async fn away_summary(workspace: &Path, session_id: &str, events: &str) -> Option<AwaySummary> {
+    let pause = events
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<Event>(line).ok())
+        .find(|event| event.event_type == EventType::SessionPaused)?;
+    let mut summary = AwaySummary {
+        paused_at: Some(pause.ts),
+        ..Default::default()
+    };
+    if let Some(head) = pause
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("head"))
+        .and_then(|h| h.as_str())
+    {
+        (summary.commits, summary.changed_files) = resume::changes_since(workspace, head).await;
+    }
+    if let Ok(queue) = load_queue(&workspace.join(QUEUE_FILE)).await {
+        summary.completed_tasks = queue
+            .tasks
+            .iter()
+            .filter(|task| task.state == "completed")
+            .filter(|task| task.completed_at.is_some_and(|at| at > pause.ts))
+            .filter(|task| task.run_id.as_deref() != Some(session_id))
+            .map(|task| format!("{} {}", task.id, task.task.lines().next().unwrap_or("")))
+            .collect();
+    }
+    (!summary.is_empty()).then_some(summary)
+}
+
+/// `(session, path)` for a `<session>:<path>` argument. A colon after a
+/// slash belongs to a local path.
+fn session_path(arg: &str) -> Option<(&str, &str)> {
+    let (session, path) = arg.split_once(':')?;
+    (!session.is_empty()
+        && session
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'))
+    .then_some((session, path))
 }
 
 #[cfg(test)]
@@ -514,9 +1348,11 @@ mod tests {
         CliRunner {
             config: CcswarmConfig::default(),
             repo_path: PathBuf::from("."),
+            config_path: PathBuf::from("ccswarm.json"),
             json_output,
             formatter: create_formatter(json_output),
             default_provider: None,
+            replay: None,
         }
     }
 
@@ -563,4 +1399,137 @@ mod tests {
             "ℹ Session 'session-123' pause/resume is managed by the pipeline engine.\nUse ccswarm pipeline to manage running pipelines.\n"
         );
     }
+
+    #[test]
+    fn render_watch_event_highlights_errors_in_output_preview() {
+        let _lock = COLOR_OVERRIDE_LOCK
+            .lock()
+            .expect("color override lock should not be poisoned");
+        colored::control::set_override(false);
+
+        let line = serde_json::json!({
+            "ts": "2026-01-01T12:34:56.789Z",
+            "level": "info",
+            "event_type": "movement_end",
+            "stage": "implement",
+            "message": "Stage 'implement' completed",
+            "metadata": {"output_preview": "Compiling app\nerror[E0308]: mismatched types"},
+        })
+        .to_string();
+        let rendered = render_watch_event(&line);
+
+        colored::control::unset_override();
+
+        assert!(rendered[0].starts_with("12:34:56 implement"));
+        assert!(rendered.iter().any(|l| l.contains("[log]")));
+        assert_eq!(
+            rendered.last().map(String::as_str),
+            Some("    error[E0308]: mismatched types")
+        );
+    }
+
+    #[test]
+    fn watch_prints_replies_piece_by_piece_under_their_agent() {
+        use crate::session::stream::{ChatChunk, ChunkKind};
+
+        let _lock = COLOR_OVERRIDE_LOCK
+            .lock()
+            .expect("color override lock should not be poisoned");
+        colored::control::set_override(false);
+
+        let chunk = |reply: &str, agent: &str, kind, text: &str| ChatChunk {
+            ts: chrono::Utc::now(),
+            reply: reply.to_string(),
+            agent: agent.to_string(),
+            stage: Some("implement".to_string()),
+            kind,
+            text: text.to_string(),
+        };
+        let mut printer = ReplyPrinter::default();
+        let mut out = String::new();
+        for c in [
+            chunk("r1", "backend", ChunkKind::Start, ""),
+            chunk("r1", "backend", ChunkKind::Delta, "Adding "),
+            chunk("r2", "frontend", ChunkKind::Start, ""),
+            chunk("r2", "frontend", ChunkKind::Delta, "Form "),
+            chunk("r1", "backend", ChunkKind::Delta, "the endpoint"),
+            chunk("r1", "backend", ChunkKind::End, ""),
+            chunk("r2", "frontend", ChunkKind::Stopped, ""),
+        ] {
+            out.push_str(&printer.render(&c));
+        }
+        colored::control::unset_override();
+
+        assert_eq!(
+            out,
+            "💬 backend (implement)\nAdding \n💬 frontend (implement)\nForm \n\
+             💬 backend (implement)\nthe endpoint\n💬 frontend (implement)\n■ stopped\n"
+        );
+    }
+
+    #[test]
+    fn render_watch_event_passes_through_non_json() {
+        assert_eq!(render_watch_event("not json"), vec!["not json".to_string()]);
+        assert!(render_watch_event("   ").is_empty());
+    }
+
+    #[tokio::test]
+    async fn watcher_guard_registers_and_detaches() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = WatcherGuard::register(dir.path()).await;
+        assert_eq!(count_watchers(dir.path()).await, 1);
+        drop(guard);
+        assert_eq!(count_watchers(dir.path()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn count_watchers_skips_and_removes_dead_markers() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = WatcherGuard::register(dir.path()).await;
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+        let stale = dir.path().join("watchers").join(dead.to_string());
+        std::fs::write(&stale, "").unwrap();
+
+        assert_eq!(count_watchers(dir.path()).await, 1);
+        assert!(!stale.exists());
+    }
+
+    #[tokio::test]
+    async fn cp_moves_files_through_a_sessions_workspace() {
+        let repo = tempfile::tempdir().unwrap();
+        let run_id = "0b6c4d3e-1111-4222-8333-944455556666";
+        tokio::fs::create_dir_all(repo.path().join(".ccswarm/runs").join(run_id))
+            .await
+            .unwrap();
+        let local = repo.path().join("seed.sql");
+        tokio::fs::write(&local, "insert 1;").await.unwrap();
+        let mut runner = cli_runner(true);
+        runner.repo_path = repo.path().to_path_buf();
+
+        let inside = format!("{run_id}:db/seed.sql");
+        runner
+            .session_cp(local.to_str().unwrap(), &inside)
+            .await
+            .unwrap();
+        assert!(repo.path().join("db/seed.sql").exists());
+        let back = repo.path().join("back.sql");
+        runner
+            .session_cp(&inside, back.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(tokio::fs::read_to_string(&back).await.unwrap(), "insert 1;");
+
+        let escape = format!("{run_id}:../outside.sql");
+        assert!(
+            runner
+                .session_cp(local.to_str().unwrap(), &escape)
+                .await
+                .is_err()
+        );
+        assert!(runner.session_cp("a.txt", "b.txt").await.is_err());
+        assert_eq!(session_path("/tmp/a:b"), None);
+        assert_eq!(session_path("run-1:src/a.rs"), Some(("run-1", "src/a.rs")));
+    }
 }
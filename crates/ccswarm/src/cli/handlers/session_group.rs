@@ -0,0 +1,388 @@
+//! `ccswarm session group`: named groups of sessions and the bulk commands
+//! run across them (see [`crate::session::groups`]).
+
+use super::super::*;
+use super::run_utils::resolve_run_path;
+use super::session::{kill_run_dir, suspend_run_dir};
+use crate::session::groups::SessionGroups;
+use ai_session::core::suspend::Suspend;
+use std::collections::BTreeMap;
+
+/// Output lines kept per session from `group run`.
+const RUN_OUTPUT_TAIL: usize = 20;
+
+/// One session's result of a bulk command.
+#[derive(Debug, serde::Serialize)]
+struct MemberResult {
+    session: String,
+    ok: bool,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+}
+
+impl MemberResult {
+    fn from_result(session: &str, result: Result<String>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                session: session.to_string(),
+                ok: true,
+                detail,
+                output: None,
+            },
+            Err(e) => Self {
+                session: session.to_string(),
+                ok: false,
+                detail: format!("{e:#}"),
+                output: None,
+            },
+        }
+    }
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_session_group(&self, action: &SessionGroupAction) -> Result<()> {
+        match action {
+            SessionGroupAction::List => self.session_group_list().await,
+            SessionGroupAction::Create { group, sessions } => {
+                self.edit_session_groups(group, "created", |g| g.create(group, sessions))
+                    .await
+            }
+            SessionGroupAction::Add { group, sessions } => {
+                self.edit_session_groups(group, "updated", |g| g.add(group, sessions))
+                    .await
+            }
+            SessionGroupAction::Remove { group, sessions } => {
+                self.edit_session_groups(group, "updated", |g| g.remove(group, sessions))
+                    .await
+            }
+            SessionGroupAction::Delete { group } => {
+                self.edit_session_groups(group, "deleted", |g| g.delete(group))
+                    .await
+            }
+            SessionGroupAction::Run {
+                group,
+                timeout,
+                command,
+            } => {
+                let results = self
+                    .session_group_run(group, &command.join(" "), *timeout)
+                    .await?;
+                self.print_group_results(group, "run", &results)
+            }
+            SessionGroupAction::Pause { group } => {
+                let results = self.session_group_suspend(group, Suspend::Stop).await?;
+                self.print_group_results(group, "pause", &results)
+            }
+            SessionGroupAction::Resume { group } => {
+                let results = self.session_group_suspend(group, Suspend::Continue).await?;
+                self.print_group_results(group, "resume", &results)
+            }
+            SessionGroupAction::Kill { group, force } => {
+                let results = self.session_group_kill(group, *force).await?;
+                self.print_group_results(group, "kill", &results)
+            }
+        }
+    }
+
+    async fn session_group_list(&self) -> Result<()> {
+        let groups = SessionGroups::load(&self.repo_path).await?;
+        if self.json_output {
+            let data: serde_json::Map<String, serde_json::Value> = groups
+                .iter()
+                .map(|(name, group)| (name.to_string(), serde_json::json!(group.members)))
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": data,
+                }))?
+            );
+            return Ok(());
+        }
+        if groups.iter().next().is_none() {
+            println!("No session groups; create one with `ccswarm session group create`.");
+            return Ok(());
+        }
+        for (name, group) in groups.iter() {
+            println!(
+                "  {:<16} {}",
+                name.bright_yellow(),
+                if group.members.is_empty() {
+                    "(empty)".bright_black().to_string()
+                } else {
+                    group.members.join(", ")
+                }
+            );
+        }
+        Ok(())
+    }
+
+    async fn edit_session_groups(
+        &self,
+        group: &str,
+        verb: &str,
+        edit: impl FnOnce(&mut SessionGroups) -> Result<()>,
+    ) -> Result<()> {
+        let mut groups = SessionGroups::load(&self.repo_path).await?;
+        edit(&mut groups)?;
+        groups.save(&self.repo_path).await?;
+        let members = groups
+            .get(group)
+            .map(|g| g.members.clone())
+            .unwrap_or_default();
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "group": group,
+                    "state": verb,
+                    "sessions": members,
+                }))?
+            );
+        } else {
+            println!(
+                "{} Session group '{}' {} ({} session{})",
+                "✓".bright_green(),
+                group.bright_yellow(),
+                verb,
+                members.len(),
+                if members.len() == 1 { "" } else { "s" }
+            );
+        }
+        Ok(())
+    }
+
+    async fn group_members(&self, group: &str) -> Result<Vec<String>> {
+        let groups = SessionGroups::load(&self.repo_path).await?;
+        let members = groups.get(group)?.members.clone();
+        if members.is_empty() {
+            anyhow::bail!("Session group '{}' is empty", group);
+        }
+        Ok(members)
+    }
+
+    /// Run `command` once in each distinct workspace of the group's
+    /// sessions, concurrently; sessions sharing a workspace share its result.
+    async fn session_group_run(
+        &self,
+        group: &str,
+        command: &str,
+        timeout: u64,
+    ) -> Result<Vec<MemberResult>> {
+        let members = self.group_members(group).await?;
+        let mut workspaces: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+        let mut results = Vec::new();
+        for member in &members {
+            match self.session_workspace(member).await {
+                Ok(workspace) => workspaces
+                    .entry(workspace)
+                    .or_default()
+                    .push(member.clone()),
+                Err(e) => results.push(MemberResult::from_result(member, Err(e))),
+            }
+        }
+        let timeout = std::time::Duration::from_secs(timeout);
+        let runs = workspaces.keys().map(|workspace| async move {
+            let output = tokio::time::timeout(
+                timeout,
+                tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .current_dir(workspace)
+                    .stdin(std::process::Stdio::null())
+                    .kill_on_drop(true)
+                    .output(),
+            )
+            .await;
+            match output {
+                Err(_) => Err(anyhow!("timed out after {}s", timeout.as_secs())),
+                Ok(Err(e)) => Err(anyhow::Error::new(e).context("failed to start sh")),
+                Ok(Ok(output)) => Ok(output),
+            }
+        });
+        let outcomes = futures::future::join_all(runs).await;
+        for ((workspace, sessions), outcome) in workspaces.iter().zip(outcomes) {
+            for session in sessions {
+                results.push(match &outcome {
+                    Ok(output) => {
+                        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                        text.push_str(&String::from_utf8_lossy(&output.stderr));
+                        let lines: Vec<&str> = text.lines().collect();
+                        let tail = lines[lines.len().saturating_sub(RUN_OUTPUT_TAIL)..].join("\n");
+                        MemberResult {
+                            session: session.clone(),
+                            ok: output.status.success(),
+                            detail: format!(
+                                "exit {} in {}",
+                                output
+                                    .status
+                                    .code()
+                                    .map_or("signal".to_string(), |c| c.to_string()),
+                                workspace.display()
+                            ),
+                            output: (!tail.is_empty()).then_some(tail),
+                        }
+                    }
+                    Err(e) => MemberResult::from_result(session, Err(anyhow!("{e:#}"))),
+                });
+            }
+        }
+        // Report in the group's order.
+        results.sort_by_key(|r| members.iter().position(|m| *m == r.session));
+        Ok(results)
+    }
+
+    async fn session_group_suspend(
+        &self,
+        group: &str,
+        action: Suspend,
+    ) -> Result<Vec<MemberResult>> {
+        let verb = match action {
+            Suspend::Stop => "paused",
+            Suspend::Continue => "resumed",
+        };
+        let mut results = Vec::new();
+        for member in self.group_members(group).await? {
+            let result = async {
+                let run_dir = resolve_run_path(&self.repo_path, Some(&member)).await?;
+                let run_id = run_dir
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| member.clone());
+                let suspended = suspend_run_dir(&run_dir, &run_id, action).await?;
+                Ok(format!(
+                    "{verb} ({} process{})",
+                    suspended.processes.len(),
+                    if suspended.processes.len() == 1 {
+                        ""
+                    } else {
+                        "es"
+                    }
+                ))
+            }
+            .await;
+            results.push(MemberResult::from_result(&member, result));
+        }
+        Ok(results)
+    }
+
+    async fn session_group_kill(&self, group: &str, force: bool) -> Result<Vec<MemberResult>> {
+        let mut results = Vec::new();
+        for member in self.group_members(group).await? {
+            let result = async {
+                let run_dir = resolve_run_path(&self.repo_path, Some(&member)).await?;
+                let processes = kill_run_dir(&run_dir, &member, force)?;
+                Ok(format!(
+                    "killed ({} process{})",
+                    processes.len(),
+                    if processes.len() == 1 { "" } else { "es" }
+                ))
+            }
+            .await;
+            results.push(MemberResult::from_result(&member, result));
+        }
+        Ok(results)
+    }
+
+    /// Print each session's result; fails when any session did.
+    fn print_group_results(&self, group: &str, verb: &str, results: &[MemberResult]) -> Result<()> {
+        let failed = results.iter().filter(|r| !r.ok).count();
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if failed == 0 { "success" } else { "error" },
+                    "group": group,
+                    "action": verb,
+                    "data": results,
+                }))?
+            );
+        } else {
+            for result in results {
+                println!(
+                    "{} {:<16} {}",
+                    if result.ok {
+                        "✓".bright_green()
+                    } else {
+                        "✗".bright_red()
+                    },
+                    result.session.bright_yellow(),
+                    result.detail
+                );
+                if let Some(output) = &result.output {
+                    for line in output.lines() {
+                        println!("    {}", line.bright_black());
+                    }
+                }
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!(
+                "{} failed for {} of {} session{} in '{}'",
+                verb,
+                failed,
+                results.len(),
+                if results.len() == 1 { "" } else { "s" },
+                group
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn group_run_reports_each_session_once_per_workspace() {
+        let repo = tempfile::tempdir().unwrap();
+        for run in ["run-a", "run-b"] {
+            std::fs::create_dir_all(repo.path().join(".ccswarm/runs").join(run)).unwrap();
+        }
+        let mut groups = SessionGroups::default();
+        groups
+            .create(
+                "sprint",
+                &["run-a".into(), "missing".into(), "run-b".into()],
+            )
+            .unwrap();
+        groups.save(repo.path()).await.unwrap();
+
+        let runner = CliRunner {
+            config: CcswarmConfig::default(),
+            repo_path: repo.path().to_path_buf(),
+            config_path: PathBuf::from("ccswarm.json"),
+            json_output: false,
+            formatter: create_formatter(false),
+            default_provider: None,
+            replay: None,
+        };
+        let results = runner
+            .session_group_run("sprint", "echo pulled >> log; wc -l < log", 10)
+            .await
+            .unwrap();
+        let sessions: Vec<&str> = results.iter().map(|r| r.session.as_str()).collect();
+        assert_eq!(sessions, ["run-a", "missing", "run-b"]);
+        assert!(results[0].ok && results[2].ok && !results[1].ok);
+        // Both runs live in the same workspace, so the command ran once.
+        assert_eq!(results[0].output.as_deref(), Some("1"));
+        assert_eq!(results[2].output.as_deref(), Some("1"));
+        assert!(
+            runner
+                .print_group_results("sprint", "run", &results)
+                .is_err()
+        );
+
+        // Nothing is running, so pause fails per session rather than outright.
+        let paused = runner
+            .session_group_suspend("sprint", Suspend::Stop)
+            .await
+            .unwrap();
+        assert!(paused.iter().all(|r| !r.ok));
+        assert!(paused[0].detail.contains("not running"));
+    }
+}
@@ -0,0 +1,212 @@
+//! `ccswarm status`: the latest run, how confident agents have been,
+//! whether the daemon is up, how deep the provider queue is and which queue
+//! tasks are blocked.
+
+use super::super::*;
+use super::run_utils::resolve_run_path;
+use crate::events::SessionInfo;
+use crate::workflow::self_rating::RatingHistory;
+
+/// Ratings listed by `status --detailed`.
+const RECENT_RATINGS: usize = 10;
+
+impl CliRunner {
+    pub(crate) async fn handle_status(&self, detailed: bool) -> Result<()> {
+        let latest = match resolve_run_path(&self.repo_path, None).await {
+            Ok(path) => Some(load_session_info(&path).await),
+            Err(_) => None,
+        };
+        let history = RatingHistory::load(&self.repo_path.join(".ccswarm").join("runs")).await;
+        let by_agent = history.by_agent();
+        let recent: Vec<_> = history.ratings.iter().rev().take(RECENT_RATINGS).collect();
+        let weak_areas = history.weak_areas();
+        let daemon =
+            super::daemon::daemon_status(&crate::daemon::DaemonPaths::new(&self.repo_path)).await;
+        let blocked = super::blockers::blocked_tasks(&self.repo_path).await?;
+        let provider_queue =
+            crate::providers::scheduler::stats(&self.repo_path.join(".ccswarm")).await?;
+
+        if self.json_output {
+            let mut data = serde_json::json!({
+                "latest_run": latest,
+                "agents": by_agent,
+                "daemon": daemon,
+                "provider_queue": provider_queue,
+                "blocked_tasks": blocked,
+            });
+            if detailed {
+                data["recent_ratings"] = serde_json::json!(recent);
+                data["low_confidence_areas"] = serde_json::json!(weak_areas);
+            }
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": data,
+                }))?
+            );
+            return Ok(());
+        }
+
+        match &latest {
+            Some(info) => {
+                println!(
+                    "{} {} ({}{})",
+                    "Latest run:".bright_cyan().bold(),
+                    info.run_id.bright_yellow(),
+                    info.status,
+                    info.duration
+                        .as_deref()
+                        .map(|d| format!(", {d}"))
+                        .unwrap_or_default()
+                );
+                if let Some(task) = &info.task {
+                    println!("  flow: {}", task);
+                }
+                println!(
+                    "  stages completed: {}  agents: {}",
+                    info.movements_completed,
+                    info.agents_used.join(", ")
+                );
+                if let (Some(stage), Some(progress)) = (&info.last_movement, &info.progress) {
+                    println!("  {}: {}", stage, progress.summary().bright_green());
+                }
+            }
+            None => println!("No pipeline runs yet. Start one with: ccswarm pipeline --task ..."),
+        }
+        if let Some(daemon) = &daemon {
+            println!("{} pid {}", "Daemon:".bright_cyan().bold(), daemon.pid);
+            super::daemon::print_subsystems(daemon);
+        }
+        if provider_queue.in_flight + provider_queue.waiting > 0 {
+            println!(
+                "{} {} in flight, {} waiting{}",
+                "Provider queue:".bright_cyan().bold(),
+                provider_queue.in_flight,
+                provider_queue.waiting,
+                provider_queue
+                    .oldest_wait_secs
+                    .map(|secs| format!(" (oldest {secs}s)"))
+                    .unwrap_or_default()
+            );
+            for (provider, stats) in &provider_queue.providers {
+                println!(
+                    "  {:<10} {} in flight, {} waiting, {} started in the last minute{}",
+                    provider,
+                    stats.in_flight,
+                    stats.waiting,
+                    stats.started_last_minute,
+                    stats
+                        .cooling_down_until
+                        .map(
+                            |until| format!(", rate limited until {}", until.format("%H:%M:%S"))
+                                .bright_yellow()
+                                .to_string()
+                        )
+                        .unwrap_or_default()
+                );
+            }
+        }
+        if !blocked.is_empty() {
+            println!();
+            println!("{}", "Blocked tasks:".bright_cyan().bold());
+            for task in &blocked {
+                println!(
+                    "  {:<12} {}",
+                    task.id.bright_yellow(),
+                    task.blocker.summary()
+                );
+                match &task.asked {
+                    Some(human) => println!("  {:<12} waiting on {}", "", human.bright_cyan()),
+                    None if task.blocker.needs_human() => println!(
+                        "  {:<12} unblock with `ccswarm queue unblock {}`",
+                        "", task.id
+                    ),
+                    None => {}
+                }
+            }
+        }
+
+        println!();
+        if by_agent.is_empty() {
+            println!("No agent self-ratings recorded yet.");
+            return Ok(());
+        }
+        println!("{}", "Agent self-ratings:".bright_cyan().bold());
+        println!(
+            "  {:<20} {:>10} {:>12} {:>8} {:>7}",
+            "Agent", "Confidence", "Completeness", "Ratings", "Review"
+        );
+        for (agent, agg) in &by_agent {
+            let confidence = format!("{:.1}", agg.avg_confidence);
+            println!(
+                "  {:<20} {:>10} {:>12.1} {:>8} {:>7}",
+                agent,
+                if agg.avg_confidence < 3.0 {
+                    confidence.bright_red()
+                } else {
+                    confidence.normal()
+                },
+                agg.avg_completeness,
+                agg.count,
+                agg.needs_review
+            );
+        }
+
+        if detailed {
+            println!();
+            println!("{}", "Recent ratings:".bright_cyan().bold());
+            for r in &recent {
+                let flag = if r.needs_review {
+                    " review".bright_yellow().to_string()
+                } else {
+                    String::new()
+                };
+                println!(
+                    "  {} {:<8} {:<16} {:<14} {}/5 conf, {}/5 done{}",
+                    r.ts.get(..19).unwrap_or(&r.ts).bright_black(),
+                    r.run_id.get(..8).unwrap_or(&r.run_id),
+                    r.stage,
+                    r.agent,
+                    r.rating.confidence,
+                    r.rating.completeness,
+                    flag
+                );
+                if !r.rating.low_confidence_areas.is_empty() {
+                    println!(
+                        "      unsure about: {}",
+                        r.rating.low_confidence_areas.join(", ")
+                    );
+                }
+                if !r.rating.notes.is_empty() {
+                    println!("      {}", r.rating.notes.bright_black());
+                }
+            }
+            if !weak_areas.is_empty() {
+                println!();
+                println!(
+                    "{} {}",
+                    "Low-confidence areas (reviewed when delegated):"
+                        .bright_cyan()
+                        .bold(),
+                    weak_areas.into_iter().collect::<Vec<_>>().join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn load_session_info(run_path: &std::path::Path) -> SessionInfo {
+    let run_id = run_path
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if let Ok(summary) = super::run_utils::read_summary(run_path) {
+        return SessionInfo::from_summary(&summary);
+    }
+    let events = tokio::fs::read_to_string(run_path.join("events.ndjson"))
+        .await
+        .unwrap_or_default();
+    SessionInfo::from_events(&run_id, &events)
+}
@@ -1,4 +1,6 @@
 use super::super::*;
+use super::queue_state::{ClaimState, QUEUE_FILE, load_queue};
+use super::task_dedup::{duplicate_error, find_duplicates, warn_duplicates};
 
 impl CliRunner {
     pub(crate) async fn handle_task(&self, action: &TaskAction) -> Result<()> {
@@ -13,6 +15,7 @@ impl CliRunner {
                 template: _,
                 template_vars: _,
                 interactive: _,
+                allow_duplicate,
             } => {
                 self.add_task(
                     description,
@@ -20,6 +23,7 @@ impl CliRunner {
                     task_type,
                     details.as_deref(),
                     *duration,
+                    *allow_duplicate,
                 )
                 .await
             }
@@ -47,6 +51,7 @@ impl CliRunner {
                 self.show_task_status(task_id, *history, *orchestration)
                     .await
             }
+            TaskAction::Show { task_id } => self.show_queue_task(task_id).await,
             TaskAction::Cancel {
                 task_id,
                 force,
@@ -79,6 +84,28 @@ impl CliRunner {
             } => self.merge_task_branch(task_id, *cleanup, *yes).await,
             TaskAction::Retry { task_id, force } => self.retry_task(task_id, *force).await,
             TaskAction::Delete { task_id, force } => self.delete_task(task_id, *force).await,
+            TaskAction::Undo {
+                task_id,
+                dry_run,
+                yes,
+            } => self.undo_task(task_id, *dry_run, *yes).await,
+            TaskAction::Bulk {
+                file,
+                format,
+                dry_run,
+                allow_duplicate,
+            } => {
+                self.bulk_add_tasks(file, format.as_deref(), *dry_run, *allow_duplicate)
+                    .await
+            }
+            TaskAction::Export {
+                format,
+                since,
+                output,
+            } => {
+                self.export_tasks(format, since.as_deref(), output.as_deref())
+                    .await
+            }
         }
     }
 
@@ -89,6 +116,7 @@ impl CliRunner {
         task_type: &str,
         details: Option<&str>,
         duration: Option<u32>,
+        allow_duplicate: bool,
     ) -> Result<()> {
         use crate::utils::user_error::CommonErrors;
 
@@ -100,6 +128,13 @@ impl CliRunner {
             return Err(anyhow!("Invalid task description"));
         }
 
+        let queue = load_queue(&self.repo_path.join(QUEUE_FILE)).await?;
+        let duplicates = find_duplicates(&queue, description);
+        if let Some(err) = duplicate_error(description, &duplicates, allow_duplicate) {
+            return Err(err);
+        }
+        warn_duplicates(&duplicates);
+
         println!(
             "Creating task: {}...",
             description.chars().take(50).collect::<String>()
@@ -238,9 +273,7 @@ impl CliRunner {
         if branches {
             println!("{}", "Worktree Summary".bright_cyan().bold());
             println!("{}", "================".bright_cyan());
-            if let Ok(manager) =
-                crate::git::shell::ShellWorktreeManager::new(self.repo_path.clone())
-            {
+            if let Ok(manager) = self.worktree_manager() {
                 match manager.list_worktrees().await {
                     Ok(worktrees) => {
                         let task_worktrees: Vec<_> = worktrees
@@ -279,7 +312,7 @@ impl CliRunner {
         cleanup: bool,
         _yes: bool,
     ) -> Result<()> {
-        let manager = crate::git::shell::ShellWorktreeManager::new(self.repo_path.clone())?;
+        let manager = self.worktree_manager()?;
 
         // Find the task's worktree branch
         let safe_id: String = task_id
@@ -308,7 +341,14 @@ impl CliRunner {
             return Err(anyhow!("No worktree found for branch '{}'", branch_name));
         }
 
-        // Merge using git merge
+        // Merge using git merge. A conflict is left for the user to resolve;
+        // only a crash mid-merge is aborted by crash recovery.
+        let recovery = crate::recovery::RecoveryJournal::for_repo(&self.repo_path);
+        let merge = crate::recovery::Operation::MergeStarted {
+            repo: self.repo_path.clone(),
+            branch: branch_name.clone(),
+        };
+        recovery.begin(merge.clone()).await?;
         let output = tokio::process::Command::new("git")
             .args([
                 "merge",
@@ -320,7 +360,9 @@ impl CliRunner {
             .current_dir(&self.repo_path)
             .output()
             .await
-            .context("Failed to execute git merge")?;
+            .context("Failed to execute git merge");
+        recovery.done(&merge).await;
+        let output = output?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -361,7 +403,7 @@ impl CliRunner {
             })
             .collect();
 
-        if let Ok(manager) = crate::git::shell::ShellWorktreeManager::new(self.repo_path.clone()) {
+        if let Ok(manager) = self.worktree_manager() {
             let worktrees = manager.list_worktrees().await.unwrap_or_default();
             let branch_name = format!("task/{}", safe_id);
             if let Some(wt) = worktrees.iter().find(|wt| wt.branch == branch_name) {
@@ -401,6 +443,120 @@ impl CliRunner {
         Ok(())
     }
 
+    /// Show a queued task together with every attempt the retry policy made.
+    pub(crate) async fn show_queue_task(&self, task_id: &str) -> Result<()> {
+        let queue = load_queue(&self.repo_path.join(QUEUE_FILE)).await?;
+        let task = queue
+            .tasks
+            .iter()
+            .find(|task| task.id == task_id)
+            .ok_or_else(|| anyhow!("Queue task not found: {}", task_id))?;
+
+        if self.json_output {
+            println!("{}", serde_json::to_string_pretty(task)?);
+            return Ok(());
+        }
+
+        println!(
+            "{} {} [{}]",
+            "Task".bright_cyan().bold(),
+            task.id.bright_white(),
+            task.state
+        );
+        println!(
+            "   {} {}",
+            "Flow:".bright_cyan(),
+            task.flow.as_deref().unwrap_or("default")
+        );
+        println!(
+            "   {} {}",
+            "Created:".bright_cyan(),
+            task.created_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        if let ClaimState::RetryQueued { attempt, due_at_ms } = &task.claim {
+            let due = chrono::DateTime::<chrono::Utc>::from_timestamp_millis(*due_at_ms as i64)
+                .map(|due| due.format("%H:%M:%S").to_string())
+                .unwrap_or_else(|| "-".to_string());
+            println!(
+                "   {} attempt {} at {}",
+                "Retry queued:".bright_yellow(),
+                attempt,
+                due
+            );
+        }
+        if let Some(time_box) = &task.time_box {
+            println!(
+                "   {} {}s, then {}",
+                "Time budget:".bright_cyan(),
+                time_box.budget_secs,
+                time_box.on_exceeded
+            );
+        }
+        println!(
+            "   {} {}",
+            "Task:".bright_cyan(),
+            task.task.lines().next().unwrap_or("").trim()
+        );
+
+        println!();
+        if task.attempts.is_empty() {
+            println!("No attempts recorded yet.");
+            return Ok(());
+        }
+        println!(
+            "{} ({})",
+            "Retry chain".bright_cyan().bold(),
+            task.attempts.len()
+        );
+        for attempt in &task.attempts {
+            let outcome = match attempt.outcome.as_str() {
+                "completed" => attempt.outcome.bright_green(),
+                "timed_out" => attempt.outcome.bright_yellow(),
+                _ => attempt.outcome.bright_red(),
+            };
+            let mut details = vec![format!(
+                "{}s",
+                (attempt.ended_at - attempt.started_at).num_seconds()
+            )];
+            if let Some(model) = &attempt.model {
+                details.push(format!("model={model}"));
+            }
+            if let Some(mode) = &attempt.think_mode {
+                details.push(format!("think={mode}"));
+            }
+            println!(
+                "  #{:<2} {:<9}  run {}  {}",
+                attempt.attempt,
+                outcome,
+                attempt
+                    .run_id
+                    .chars()
+                    .take(8)
+                    .collect::<String>()
+                    .bright_black(),
+                details.join(" ")
+            );
+            if let Some(error) = &attempt.error {
+                println!("      {} {}", "error:".bright_red(), error);
+            }
+            if let Some(retry_at) = &attempt.retry_at {
+                println!(
+                    "      {} {}",
+                    "retry at".bright_yellow(),
+                    retry_at.format("%H:%M:%S")
+                );
+            }
+            if let Some(checkpoint) = &attempt.checkpoint {
+                println!(
+                    "      {} {}",
+                    "checkpoint".bright_yellow(),
+                    checkpoint.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
     /// Cancel a task
     pub(crate) async fn cancel_task(
         &self,
@@ -0,0 +1,534 @@
+//! `ccswarm task bulk`: queue a batch of tasks from one file.
+//!
+//! YAML and JSON files hold a list of entries (or a `tasks:` list); CSV files
+//! have a header row naming the columns, with `labels` and `depends_on`
+//! separated by `;`. Every format is turned into the same entries and checked
+//! as a whole — one bad entry means nothing is queued.
+
+use super::super::*;
+use super::queue_state::{ClaimState, QUEUE_FILE, QueueFile, QueueState, QueueTask, default_state};
+use super::task_dedup::{duplicate_error, find_in};
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+/// One task in a bulk file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BulkEntry {
+    /// Name other entries use in `depends_on`.
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(alias = "task")]
+    description: String,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    #[serde(default)]
+    flow: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BulkFormat {
+    Yaml,
+    Json,
+    Csv,
+}
+
+impl BulkFormat {
+    fn detect(path: &Path, explicit: Option<&str>) -> Result<Self> {
+        let name = match explicit {
+            Some(format) => format.to_lowercase(),
+            None => path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("yaml")
+                .to_lowercase(),
+        };
+        match name.as_str() {
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(anyhow!(
+                "unknown bulk file format '{other}' (expected yaml, json or csv)"
+            )),
+        }
+    }
+}
+
+/// Raw entries of a bulk file, before validation.
+fn parse_entries(raw: &str, format: BulkFormat) -> Result<Vec<serde_json::Value>> {
+    let value: serde_json::Value = match format {
+        BulkFormat::Yaml => serde_yml::from_str(raw).context("invalid YAML")?,
+        BulkFormat::Json => serde_json::from_str(raw).context("invalid JSON")?,
+        BulkFormat::Csv => return csv_entries(raw),
+    };
+    let list = match value {
+        serde_json::Value::Object(mut map) => map.remove("tasks").unwrap_or_default(),
+        other => other,
+    };
+    match list {
+        serde_json::Value::Array(items) => Ok(items),
+        serde_json::Value::Null => Ok(Vec::new()),
+        _ => Err(anyhow!("expected a list of tasks or a `tasks:` list")),
+    }
+}
+
+/// CSV rows as entry objects keyed by the header row.
+fn csv_entries(raw: &str) -> Result<Vec<serde_json::Value>> {
+    let mut rows = csv_rows(raw)?.into_iter();
+    let Some(header) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    rows.filter(|row| row.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|row| {
+            let mut entry = serde_json::Map::new();
+            for (column, cell) in header.iter().zip(row) {
+                let cell = cell.trim();
+                if cell.is_empty() {
+                    continue;
+                }
+                let value = match column.as_str() {
+                    "labels" | "depends_on" => serde_json::Value::from(
+                        cell.split(';')
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .collect::<Vec<_>>(),
+                    ),
+                    _ => serde_json::Value::from(cell),
+                };
+                entry.insert(column.clone(), value);
+            }
+            Ok(serde_json::Value::Object(entry))
+        })
+        .collect()
+}
+
+/// Split CSV text into rows of cells. Quoted cells may contain commas,
+/// newlines and doubled quotes.
+fn csv_rows(raw: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut cell)),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            '\r' if !quoted => {}
+            c => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow!("unterminated quoted CSV field"));
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Check every entry against the batch and the existing queue and build the
+/// queue tasks. Returns every problem found, not just the first. Entries that
+/// nearly match an open task (or an earlier entry) are problems unless
+/// `allow_duplicate` is set.
+fn plan_batch(
+    entries: Vec<serde_json::Value>,
+    existing: &QueueFile,
+    allow_duplicate: bool,
+) -> std::result::Result<Vec<(Option<String>, QueueTask)>, Vec<String>> {
+    let mut problems = Vec::new();
+    let mut parsed = Vec::new();
+    for (i, value) in entries.into_iter().enumerate() {
+        match serde_json::from_value::<BulkEntry>(value) {
+            Ok(entry) => parsed.push((i + 1, entry)),
+            Err(e) => problems.push(format!("entry {}: {e}", i + 1)),
+        }
+    }
+    if parsed.is_empty() && problems.is_empty() {
+        problems.push("the file has no tasks".to_string());
+    }
+
+    let mut ids: HashMap<String, String> = HashMap::new();
+    for (n, entry) in &parsed {
+        let Some(key) = entry.key.as_deref().map(str::trim) else {
+            continue;
+        };
+        if key.is_empty() {
+            problems.push(format!("entry {n}: key is empty"));
+        } else if ids.contains_key(key) {
+            problems.push(format!("entry {n}: duplicate key '{key}'"));
+        } else {
+            let id = format!("q-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+            ids.insert(key.to_string(), id);
+        }
+    }
+
+    let existing_ids: HashSet<&str> = existing.tasks.iter().map(|t| t.id.as_str()).collect();
+    let mut tasks = Vec::new();
+    for (n, entry) in parsed {
+        let description = entry.description.trim();
+        if description.is_empty() {
+            problems.push(format!("entry {n}: description is empty"));
+        } else {
+            let earlier = existing.tasks.iter().chain(tasks.iter().map(|(_, t)| t));
+            let duplicates = find_in(earlier, description);
+            if let Some(err) = duplicate_error(description, &duplicates, allow_duplicate) {
+                problems.push(format!("entry {n}: {}", err.to_string().replace('\n', " ")));
+            }
+        }
+        let priority = match entry.priority.as_deref().map(str::parse::<Priority>) {
+            Some(Ok(priority)) => Some(priority),
+            Some(Err(_)) => {
+                problems.push(format!(
+                    "entry {n}: unknown priority '{}' (expected low, medium, high or critical)",
+                    entry.priority.as_deref().unwrap_or_default()
+                ));
+                None
+            }
+            None => None,
+        };
+        let mut depends_on = Vec::new();
+        for dep in entry.depends_on.iter().map(|d| d.trim()) {
+            if let Some(id) = ids.get(dep) {
+                depends_on.push(id.clone());
+            } else if existing_ids.contains(dep) {
+                depends_on.push(dep.to_string());
+            } else {
+                problems.push(format!(
+                    "entry {n}: depends on '{dep}', which is neither a key in this file nor a queued task"
+                ));
+            }
+        }
+        let key = entry.key.as_deref().map(str::trim).map(String::from);
+        let id = key
+            .as_ref()
+            .and_then(|k| ids.get(k).cloned())
+            .unwrap_or_else(|| format!("q-{}", &uuid::Uuid::new_v4().to_string()[..8]));
+        let mut labels: Vec<String> = Vec::new();
+        for label in entry.labels.iter().map(|l| l.trim()) {
+            if !label.is_empty() && !labels.iter().any(|l| l == label) {
+                labels.push(label.to_string());
+            }
+        }
+        tasks.push((
+            key,
+            QueueTask {
+                id,
+                task: description.to_string(),
+                flow: entry.flow,
+                state: default_state(),
+                created_at: Utc::now(),
+                completed_at: None,
+                run_id: None,
+                claim: ClaimState::default(),
+                attempts: Vec::new(),
+                time_box: None,
+                command_timeout: None,
+                model: None,
+                priority,
+                labels,
+                depends_on,
+                blocker: None,
+                acceptance: None,
+            },
+        ));
+    }
+
+    if let Some(cycle) = dependency_cycle(&tasks) {
+        problems.push(format!("dependency cycle: {}", cycle.join(" -> ")));
+    }
+    if problems.is_empty() {
+        Ok(tasks)
+    } else {
+        Err(problems)
+    }
+}
+
+/// A dependency cycle among the new tasks, as keys (or IDs for unkeyed
+/// entries), first node repeated at the end.
+fn dependency_cycle(tasks: &[(Option<String>, QueueTask)]) -> Option<Vec<String>> {
+    let mut deps: HashMap<&str, &[String]> = HashMap::new();
+    for (_, task) in tasks {
+        deps.entry(&task.id).or_insert(&task.depends_on);
+    }
+    let name = |id: &str| {
+        tasks
+            .iter()
+            .find(|(_, t)| t.id == id)
+            .and_then(|(key, _)| key.clone())
+            .unwrap_or_else(|| id.to_string())
+    };
+
+    fn visit<'a>(
+        id: &'a str,
+        deps: &HashMap<&'a str, &'a [String]>,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Option<Vec<&'a str>> {
+        if let Some(start) = path.iter().position(|p| *p == id) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(id);
+            return Some(cycle);
+        }
+        if !done.insert(id) {
+            return None;
+        }
+        path.push(id);
+        for dep in deps.get(id).copied().unwrap_or_default() {
+            if deps.contains_key(dep.as_str())
+                && let Some(cycle) = visit(dep, deps, path, done)
+            {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        None
+    }
+
+    let mut done = HashSet::new();
+    tasks.iter().find_map(|(_, t)| {
+        visit(&t.id, &deps, &mut Vec::new(), &mut done)
+            .map(|cycle| cycle.into_iter().map(name).collect())
+    })
+}
+
+fn problems_error(file: &Path, problems: &[String]) -> anyhow::Error {
+    anyhow!(
+        "{} problem(s) in {}; nothing was queued:\n{}",
+        problems.len(),
+        file.display(),
+        problems
+            .iter()
+            .map(|p| format!("  - {p}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+impl CliRunner {
+    pub(crate) async fn bulk_add_tasks(
+        &self,
+        file: &Path,
+        format: Option<&str>,
+        dry_run: bool,
+        allow_duplicate: bool,
+    ) -> Result<()> {
+        let format = BulkFormat::detect(file, format)?;
+        let raw = tokio::fs::read_to_string(file)
+            .await
+            .with_context(|| format!("Failed to read task file {}", file.display()))?;
+        let entries = parse_entries(&raw, format)
+            .with_context(|| format!("Failed to parse {}", file.display()))?;
+
+        let queue_state = QueueState::new(self.repo_path.join(QUEUE_FILE));
+        let planned = if dry_run {
+            plan_batch(entries, &queue_state.load().await?, allow_duplicate)
+                .map_err(|problems| problems_error(file, &problems))?
+        } else {
+            let mut planned = Vec::new();
+            queue_state
+                .update_queue(|queue| {
+                    planned = plan_batch(entries, queue, allow_duplicate)
+                        .map_err(|problems| problems_error(file, &problems))?;
+                    queue
+                        .tasks
+                        .extend(planned.iter().map(|(_, task)| task.clone()));
+                    Ok(())
+                })
+                .await?;
+            planned
+        };
+
+        if self.json_output {
+            let created: Vec<_> = planned
+                .iter()
+                .map(|(key, task)| {
+                    serde_json::json!({
+                        "id": task.id,
+                        "key": key,
+                        "description": task.task,
+                        "priority": task.priority,
+                        "labels": task.labels,
+                        "depends_on": task.depends_on,
+                        "flow": task.flow,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "dry_run": dry_run,
+                    "created": created,
+                    "total": planned.len(),
+                }))?
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{} {} {} task(s) from {}",
+            if dry_run {
+                "DRY RUN".bright_yellow().bold()
+            } else {
+                "OK".bright_green().bold()
+            },
+            if dry_run { "would queue" } else { "queued" },
+            planned.len(),
+            file.display()
+        );
+        for (key, task) in &planned {
+            let preview: String = task
+                .task
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(60)
+                .collect();
+            let mut extra = Vec::new();
+            if let Some(priority) = task.priority {
+                extra.push(format!("{priority:?}").to_lowercase());
+            }
+            if !task.labels.is_empty() {
+                extra.push(format!("labels: {}", task.labels.join(", ")));
+            }
+            if !task.depends_on.is_empty() {
+                extra.push(format!("after {}", task.depends_on.join(", ")));
+            }
+            println!(
+                "   {}  {:<14} {}{}",
+                task.id.bright_cyan(),
+                key.as_deref().unwrap_or("-"),
+                preview,
+                if extra.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", extra.join("; "))
+                        .bright_black()
+                        .to_string()
+                }
+            );
+        }
+        if !dry_run {
+            println!("Run them with {}", "ccswarm queue drain".bright_cyan());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(raw: &str, format: BulkFormat) -> std::result::Result<Vec<QueueTask>, Vec<String>> {
+        let entries = parse_entries(raw, format).unwrap();
+        plan_batch(entries, &QueueFile::default(), false)
+            .map(|tasks| tasks.into_iter().map(|(_, t)| t).collect())
+    }
+
+    #[test]
+    fn test_yaml_batch_resolves_keys_to_queue_ids() {
+        let tasks = plan(
+            "tasks:\n\
+             - key: schema\n  description: Add users table\n  priority: high\n  labels: [db, db]\n\
+             - description: Add signup endpoint\n  depends_on: [schema]\n  flow: tdd\n",
+            BulkFormat::Yaml,
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].priority, Some(Priority::High));
+        assert_eq!(tasks[0].labels, ["db"]);
+        assert_eq!(tasks[1].depends_on, [tasks[0].id.clone()]);
+        assert_eq!(tasks[1].flow.as_deref(), Some("tdd"));
+    }
+
+    #[test]
+    fn test_csv_batch_with_quotes_and_lists() {
+        let tasks = plan(
+            "key,description,priority,labels,depends_on\n\
+             a,\"Fix login, then \"\"logout\"\"\",critical,auth;web,\n\
+             b,Write docs,,docs,a\n",
+            BulkFormat::Csv,
+        )
+        .unwrap();
+        assert_eq!(tasks[0].task, "Fix login, then \"logout\"");
+        assert_eq!(tasks[0].labels, ["auth", "web"]);
+        assert_eq!(tasks[1].priority, None);
+        assert_eq!(tasks[1].depends_on, [tasks[0].id.clone()]);
+    }
+
+    #[test]
+    fn test_invalid_batch_reports_every_problem() {
+        let problems = plan(
+            r#"[
+                {"key": "a", "description": "one", "depends_on": ["b"]},
+                {"key": "b", "description": "two", "depends_on": ["a"]},
+                {"key": "a", "description": " ", "priority": "urgent"},
+                {"description": "four", "depends_on": ["q-missing"], "owner": "me"}
+            ]"#,
+            BulkFormat::Json,
+        )
+        .unwrap_err();
+        let all = problems.join("\n");
+        assert!(all.contains("entry 4: unknown field `owner`"), "{all}");
+        assert!(all.contains("entry 3: duplicate key 'a'"), "{all}");
+        assert!(all.contains("entry 3: description is empty"), "{all}");
+        assert!(all.contains("unknown priority 'urgent'"), "{all}");
+        assert!(all.contains("dependency cycle: a -> b -> a"), "{all}");
+    }
+
+    #[tokio::test]
+    async fn test_bulk_add_is_all_or_nothing() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let repo = dir.path().to_str().context("temp path is not UTF-8")?;
+        let cli = Cli::try_parse_from(["ccswarm", "--json", "--repo", repo, "task", "list"])?;
+        let runner = CliRunner::new(&cli).await?;
+        let queue_path = dir.path().join(QUEUE_FILE);
+
+        let bad = dir.path().join("bad.yaml");
+        tokio::fs::write(&bad, "- description: ok\n- description: ''\n").await?;
+        assert!(
+            runner
+                .bulk_add_tasks(&bad, None, false, false)
+                .await
+                .is_err()
+        );
+        assert!(!queue_path.exists());
+
+        let good = dir.path().join("good.json");
+        tokio::fs::write(
+            &good,
+            r#"[{"key": "a", "description": "first", "priority": "low"},
+                {"description": "second", "priority": "high", "depends_on": ["a"]}]"#,
+        )
+        .await?;
+        runner.bulk_add_tasks(&good, None, true, false).await?;
+        assert!(!queue_path.exists());
+        runner.bulk_add_tasks(&good, None, false, false).await?;
+
+        let queue = super::super::queue_state::load_queue(&queue_path).await?;
+        assert_eq!(queue.tasks.len(), 2);
+        // "second" outranks "first" but waits for it.
+        let ready = queue.ready_tasks();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].task, "first");
+        assert_eq!(queue.blocked_ids(), [queue.tasks[1].id.clone()]);
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+//! Near-duplicate detection for tasks on their way into the queue.
+//!
+//! A new description is embedded (see [`crate::utils::embedding`]) and
+//! compared with every open task and with tasks that finished in the last
+//! week. Teams that funnel work in from several places (trackers, bulk
+//! files, people typing `queue add`) otherwise end up running the same
+//! change twice. A very close match against an open task blocks the enqueue
+//! unless `--allow-duplicate` is given; anything else close is a warning.
+
+use super::super::*;
+use super::queue_state::{QueueFile, QueueTask};
+use crate::utils::embedding::embed;
+use chrono::Utc;
+
+/// Similarity at or above which a match against an open task blocks.
+const BLOCK_SIMILARITY: f32 = 0.9;
+/// Similarity at or above which a match is reported at all.
+const WARN_SIMILARITY: f32 = 0.75;
+/// How far back finished tasks are compared.
+const RECENT_DAYS: i64 = 7;
+
+/// An existing task that resembles the one being added.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct DuplicateMatch {
+    pub(crate) id: String,
+    pub(crate) state: String,
+    pub(crate) similarity: f32,
+    pub(crate) preview: String,
+}
+
+impl DuplicateMatch {
+    /// Whether this match stops the task from being queued.
+    pub(crate) fn blocks(&self) -> bool {
+        is_open(&self.state) && self.similarity >= BLOCK_SIMILARITY
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        format!(
+            "{} ({}, {:.0}% similar): {}",
+            self.id,
+            self.state,
+            self.similarity * 100.0,
+            self.preview
+        )
+    }
+}
+
+fn is_open(state: &str) -> bool {
+    !matches!(state, "completed" | "failed")
+}
+
+/// Open or recently finished tasks in `queue` that resemble `description`,
+/// most similar first.
+pub(crate) fn find_duplicates(queue: &QueueFile, description: &str) -> Vec<DuplicateMatch> {
+    find_in(queue.tasks.iter(), description)
+}
+
+pub(crate) fn find_in<'a>(
+    tasks: impl IntoIterator<Item = &'a QueueTask>,
+    description: &str,
+) -> Vec<DuplicateMatch> {
+    let candidate = embed(description);
+    let cutoff = Utc::now() - chrono::Duration::days(RECENT_DAYS);
+    let mut matches: Vec<DuplicateMatch> = tasks
+        .into_iter()
+        .filter(|task| {
+            is_open(&task.state) || task.completed_at.unwrap_or(task.created_at) >= cutoff
+        })
+        .filter_map(|task| {
+            let similarity = candidate.cosine(&embed(&task.task));
+            (similarity >= WARN_SIMILARITY).then(|| DuplicateMatch {
+                id: task.id.clone(),
+                state: task.state.clone(),
+                similarity,
+                preview: preview(&task.task),
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    matches
+}
+
+/// Error for a blocked enqueue, or `None` when `matches` only warrant a
+/// warning (or `allow_duplicate` overrides them).
+pub(crate) fn duplicate_error(
+    description: &str,
+    matches: &[DuplicateMatch],
+    allow_duplicate: bool,
+) -> Option<anyhow::Error> {
+    if allow_duplicate {
+        return None;
+    }
+    let blocking = matches.iter().find(|m| m.blocks())?;
+    Some(anyhow!(
+        "'{}' looks like a duplicate of {}\nPass --allow-duplicate to queue it anyway.",
+        preview(description),
+        blocking.describe()
+    ))
+}
+
+/// Print the matches that were let through.
+pub(crate) fn warn_duplicates(matches: &[DuplicateMatch]) {
+    for m in matches {
+        eprintln!(
+            "{} similar to {}",
+            "WARN".bright_yellow().bold(),
+            m.describe()
+        );
+    }
+}
+
+fn preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or("").trim();
+    if line.chars().count() > 60 {
+        format!("{}…", line.chars().take(60).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::queue_state::{ClaimState, default_state};
+    use super::*;
+
+    fn task(id: &str, text: &str, state: &str, age_days: i64) -> QueueTask {
+        let at = Utc::now() - chrono::Duration::days(age_days);
+        QueueTask {
+            id: id.to_string(),
+            task: text.to_string(),
+            flow: None,
+            state: state.to_string(),
+            created_at: at,
+            completed_at: (state != default_state()).then_some(at),
+            run_id: None,
+            claim: ClaimState::default(),
+            attempts: Vec::new(),
+            time_box: None,
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker: None,
+            acceptance: None,
+        }
+    }
+
+    #[test]
+    fn open_near_duplicate_blocks_and_finished_one_only_warns() {
+        let queue = QueueFile {
+            tasks: vec![
+                task(
+                    "q-open",
+                    "Add rate limiting to the login endpoint",
+                    "pending",
+                    0,
+                ),
+                task(
+                    "q-done",
+                    "Add rate limiting to the login endpoint",
+                    "completed",
+                    1,
+                ),
+                task(
+                    "q-old",
+                    "Add rate limiting to the login endpoint",
+                    "completed",
+                    30,
+                ),
+                task("q-other", "Fix typo in the README", "pending", 0),
+            ],
+            ..Default::default()
+        };
+
+        let matches = find_duplicates(&queue, "add rate-limiting for login endpoints");
+        let ids: Vec<&str> = matches.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["q-open", "q-done"]);
+        assert!(matches[0].blocks());
+        assert!(!matches[1].blocks(), "finished tasks never block");
+
+        let err = duplicate_error("add rate-limiting", &matches, false).unwrap();
+        assert!(err.to_string().contains("q-open"));
+        assert!(duplicate_error("add rate-limiting", &matches, true).is_none());
+        assert!(duplicate_error("x", &matches[1..], false).is_none());
+    }
+}
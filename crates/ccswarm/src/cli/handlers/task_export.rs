@@ -0,0 +1,373 @@
+//! `ccswarm task export`: the queue's task history for spreadsheets and BI
+//! tools.
+//!
+//! One row per queued task with its metadata, the agents that worked on it,
+//! attempt count and wall time, token usage and cost summed from its runs'
+//! `events.ndjson`, and how it ended. The columns are [`COLUMNS`], in that
+//! order; new columns are only ever appended. Rows are written as they are
+//! built and each run's events are read line by line, so long histories
+//! don't have to fit in memory.
+
+use super::super::*;
+use super::queue_state::{QUEUE_FILE, QueueTask, load_queue};
+use anyhow::bail;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+
+/// Export columns, in order. Append only: spreadsheets and dashboards key
+/// on these names and positions.
+pub(crate) const COLUMNS: &[&str] = &[
+    "id",
+    "task",
+    "state",
+    "priority",
+    "labels",
+    "flow",
+    "model",
+    "created_at",
+    "completed_at",
+    "agents",
+    "attempts",
+    "retries",
+    "duration_secs",
+    "outcome",
+    "error",
+    "tokens_in",
+    "tokens_out",
+    "cost_usd",
+    "run_ids",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Csv,
+    /// One JSON array.
+    Json,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            other => bail!("unknown export format '{other}' (expected csv, json or jsonl)"),
+        }
+    }
+}
+
+/// One exported task. Field order matches [`COLUMNS`].
+#[derive(Debug, Clone, Default, Serialize)]
+struct ExportRow {
+    id: String,
+    task: String,
+    state: String,
+    priority: Option<String>,
+    labels: Vec<String>,
+    flow: Option<String>,
+    model: Option<String>,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    agents: Vec<String>,
+    attempts: usize,
+    retries: usize,
+    duration_secs: i64,
+    outcome: Option<String>,
+    error: Option<String>,
+    tokens_in: u64,
+    tokens_out: u64,
+    cost_usd: f64,
+    run_ids: Vec<String>,
+}
+
+impl ExportRow {
+    fn csv_fields(&self) -> Vec<String> {
+        let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+        vec![
+            self.id.clone(),
+            self.task.clone(),
+            self.state.clone(),
+            opt(&self.priority),
+            self.labels.join(";"),
+            opt(&self.flow),
+            opt(&self.model),
+            self.created_at.to_rfc3339(),
+            self.completed_at
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            self.agents.join(";"),
+            self.attempts.to_string(),
+            self.retries.to_string(),
+            self.duration_secs.to_string(),
+            opt(&self.outcome),
+            opt(&self.error),
+            self.tokens_in.to_string(),
+            self.tokens_out.to_string(),
+            format!("{:.6}", self.cost_usd),
+            self.run_ids.join(";"),
+        ]
+    }
+}
+
+/// Quote a CSV field when it needs it.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `--since`: an age such as `30d`, `12h` or `2w`, or a date (`2024-05-01`).
+fn parse_since(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    let split = value.char_indices().last().map_or(0, |(i, _)| i);
+    let (number, unit) = value.split_at(split);
+    let amount: i64 = number
+        .parse()
+        .map_err(|_| anyhow!("invalid --since '{value}' (e.g. 30d, 12h, 2w or 2024-05-01)"))?;
+    let age = match unit {
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        "w" => chrono::Duration::weeks(amount),
+        _ => bail!("invalid --since '{value}' (units: m, h, d, w)"),
+    };
+    Ok(now - age)
+}
+
+/// Add one run's agents, tokens and cost to `row`, reading its events a
+/// line at a time.
+fn add_run_usage(
+    runs_dir: &Path,
+    run_id: &str,
+    row: &mut ExportRow,
+    agents: &mut BTreeSet<String>,
+) {
+    let Ok(file) = std::fs::File::open(runs_dir.join(run_id).join("events.ndjson")) else {
+        return;
+    };
+    for line in std::io::BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        if let Some(agent) = event.get("agent").and_then(|a| a.as_str()) {
+            agents.insert(agent.to_string());
+        }
+        let Some(metadata) = event.get("metadata") else {
+            continue;
+        };
+        row.tokens_in += metadata
+            .get("tokens_in")
+            .and_then(|n| n.as_u64())
+            .unwrap_or(0);
+        row.tokens_out += metadata
+            .get("tokens_out")
+            .and_then(|n| n.as_u64())
+            .unwrap_or(0);
+        row.cost_usd += metadata
+            .get("cost_usd")
+            .and_then(|n| n.as_f64())
+            .unwrap_or(0.0);
+    }
+}
+
+fn export_row(task: &QueueTask, runs_dir: &Path) -> ExportRow {
+    let mut run_ids: Vec<String> = task.attempts.iter().map(|a| a.run_id.clone()).collect();
+    if let Some(run_id) = &task.run_id
+        && !run_ids.contains(run_id)
+    {
+        run_ids.push(run_id.clone());
+    }
+    let last = task.attempts.last();
+    let mut row = ExportRow {
+        id: task.id.clone(),
+        task: task.task.clone(),
+        state: task.state.clone(),
+        priority: task.priority.map(|p| format!("{p:?}").to_lowercase()),
+        labels: task.labels.clone(),
+        flow: task.flow.clone(),
+        model: task.model.clone(),
+        created_at: task.created_at,
+        completed_at: task.completed_at,
+        attempts: task.attempts.len(),
+        retries: task.attempts.len().saturating_sub(1),
+        duration_secs: task
+            .attempts
+            .iter()
+            .map(|a| (a.ended_at - a.started_at).num_seconds().max(0))
+            .sum(),
+        outcome: last.map(|a| a.outcome.clone()),
+        error: last.and_then(|a| a.error.clone()),
+        ..Default::default()
+    };
+    let mut agents = BTreeSet::new();
+    for run_id in &run_ids {
+        add_run_usage(runs_dir, run_id, &mut row, &mut agents);
+    }
+    row.agents = agents.into_iter().collect();
+    row.run_ids = run_ids;
+    row
+}
+
+/// Write `tasks` created at or after `since` to `out`, one row at a time.
+/// Returns the number of rows.
+fn write_export(
+    tasks: &[QueueTask],
+    runs_dir: &Path,
+    since: Option<DateTime<Utc>>,
+    format: ExportFormat,
+    out: &mut dyn Write,
+) -> Result<usize> {
+    match format {
+        ExportFormat::Csv => writeln!(out, "{}", COLUMNS.join(","))?,
+        ExportFormat::Json => write!(out, "[")?,
+        ExportFormat::Jsonl => {}
+    }
+    let mut rows = 0;
+    for task in tasks
+        .iter()
+        .filter(|t| since.is_none_or(|since| t.created_at >= since))
+    {
+        let row = export_row(task, runs_dir);
+        match format {
+            ExportFormat::Csv => {
+                let fields: Vec<String> = row.csv_fields().iter().map(|f| csv_field(f)).collect();
+                writeln!(out, "{}", fields.join(","))?;
+            }
+            ExportFormat::Json => {
+                if rows > 0 {
+                    write!(out, ",")?;
+                }
+                write!(out, "\n  {}", serde_json::to_string(&row)?)?;
+            }
+            ExportFormat::Jsonl => writeln!(out, "{}", serde_json::to_string(&row)?)?,
+        }
+        rows += 1;
+    }
+    if format == ExportFormat::Json {
+        writeln!(out, "{}]", if rows > 0 { "\n" } else { "" })?;
+    }
+    out.flush()?;
+    Ok(rows)
+}
+
+impl CliRunner {
+    pub(crate) async fn export_tasks(
+        &self,
+        format: &str,
+        since: Option<&str>,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        let format: ExportFormat = format.parse()?;
+        let since = since.map(|s| parse_since(s, Utc::now())).transpose()?;
+        let queue = load_queue(&self.repo_path.join(QUEUE_FILE)).await?;
+        let runs_dir = self.repo_path.join(".ccswarm").join("runs");
+        match output {
+            Some(path) => {
+                let file = std::fs::File::create(path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                let mut out = std::io::BufWriter::new(file);
+                let rows = write_export(&queue.tasks, &runs_dir, since, format, &mut out)?;
+                eprintln!(
+                    "{} exported {} task{} to {}",
+                    "✓".bright_green(),
+                    rows,
+                    if rows == 1 { "" } else { "s" },
+                    path.display()
+                );
+            }
+            None => {
+                let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+                write_export(&queue.tasks, &runs_dir, since, format, &mut out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::queue_state::TaskAttempt;
+
+    #[test]
+    fn export_rows_sum_run_usage_and_keep_the_column_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        std::fs::create_dir_all(runs.join("r1")).unwrap();
+        std::fs::create_dir_all(runs.join("r2")).unwrap();
+        std::fs::write(
+            runs.join("r1/events.ndjson"),
+            "{\"agent\":\"backend\",\"metadata\":{\"tokens_in\":100,\"tokens_out\":20}}\n\
+             {\"metadata\":{\"cost_usd\":0.5}}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            runs.join("r2/events.ndjson"),
+            "{\"agent\":\"qa\",\"metadata\":{\"tokens_in\":5,\"tokens_out\":1,\"cost_usd\":0.25}}\n",
+        )
+        .unwrap();
+
+        let now = Utc::now();
+        let attempt = |run_id: &str, outcome: &str, error: Option<&str>| TaskAttempt {
+            attempt: 1,
+            run_id: run_id.to_string(),
+            started_at: now - chrono::Duration::seconds(90),
+            ended_at: now - chrono::Duration::seconds(30),
+            outcome: outcome.to_string(),
+            error: error.map(str::to_string),
+            model: None,
+            think_mode: None,
+            retry_at: None,
+            checkpoint: None,
+            orchestrator_run_id: None,
+        };
+        let mut task: QueueTask = serde_yml::from_str(&format!(
+            "id: q-1\ntask: 'Fix login, then \"logout\"'\nstate: completed\ncreated_at: {}\nlabels: [auth, bug]\n",
+            now.to_rfc3339()
+        ))
+        .unwrap();
+        task.attempts = vec![
+            attempt("r1", "failed", Some("tests failed")),
+            attempt("r2", "completed", None),
+        ];
+        let mut old = task.clone();
+        old.id = "q-0".to_string();
+        old.created_at = now - chrono::Duration::days(40);
+
+        let mut csv = Vec::new();
+        let since = parse_since("30d", now).unwrap();
+        let rows = write_export(
+            &[old, task.clone()],
+            &runs,
+            Some(since),
+            ExportFormat::Csv,
+            &mut csv,
+        )
+        .unwrap();
+        assert_eq!(rows, 1);
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), COLUMNS.join(","));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("q-1,\"Fix login, then \"\"logout\"\"\",completed,,auth;bug,"));
+        assert!(row.contains(",backend;qa,2,1,120,completed,,105,21,0.750000,r1;r2"));
+
+        let mut json = Vec::new();
+        write_export(&[task], &runs, None, ExportFormat::Json, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let object = json[0].as_object().unwrap();
+        assert_eq!(object.keys().len(), COLUMNS.len());
+        assert_eq!(json[0]["retries"], 1);
+        assert!(parse_since("soon", now).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! `ccswarm task undo`: revert a task's changes using the action journal its
+//! pipeline runs recorded (see [`crate::journal`]).
+
+use super::super::*;
+use super::queue_state::{QUEUE_FILE, load_queue};
+use super::run_utils::resolve_run_path;
+use crate::journal::{ActionJournal, FileChange, UndoPlan, apply_undo, plan_undo};
+
+impl CliRunner {
+    pub(crate) async fn undo_task(&self, id: &str, dry_run: bool, yes: bool) -> Result<()> {
+        let run_ids = self.task_run_ids(id).await?;
+        let mut entries = Vec::new();
+        for run_id in &run_ids {
+            entries.extend(
+                ActionJournal::for_run(&self.repo_path, run_id)
+                    .entries()
+                    .await?,
+            );
+        }
+        if entries.is_empty() {
+            return Err(anyhow!(
+                "Nothing to undo for '{}': no journal was recorded (read-only runs write none)",
+                id
+            ));
+        }
+        let plans = plan_undo(&entries).await?;
+        let pending: Vec<UndoPlan> = plans.into_iter().filter(|p| !p.is_empty()).collect();
+        // The undo is journaled in the task's latest run.
+        let journal = ActionJournal::for_run(&self.repo_path, &run_ids[run_ids.len() - 1]);
+
+        if self.json_output {
+            // No prompt in JSON mode: without --yes this is a preview.
+            let apply = yes && !dry_run && !pending.is_empty();
+            if apply {
+                apply_undo(&pending, &journal).await?;
+            }
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "task_id": id,
+                    "runs": run_ids,
+                    "applied": apply,
+                    "plans": pending,
+                }))?
+            );
+            return Ok(());
+        }
+
+        if pending.is_empty() {
+            println!(
+                "{} Nothing to undo: '{}' is already reverted",
+                "OK".bright_green().bold(),
+                id
+            );
+            return Ok(());
+        }
+        for plan in &pending {
+            print_plan(plan);
+        }
+        if dry_run {
+            return Ok(());
+        }
+        if !yes {
+            use std::io::Write;
+            print!("Revert these changes? [y/N] ");
+            std::io::stdout().flush()?;
+            let mut response = String::new();
+            std::io::stdin().read_line(&mut response)?;
+            if !response.trim().eq_ignore_ascii_case("y") {
+                println!("Undo cancelled");
+                return Ok(());
+            }
+        }
+
+        apply_undo(&pending, &journal).await?;
+        println!(
+            "{} Reverted '{}'; the undone state is kept in the journal of run {}",
+            "OK".bright_green().bold(),
+            id,
+            run_ids[run_ids.len() - 1]
+        );
+        Ok(())
+    }
+
+    /// Runs belonging to `id`, oldest first: every attempt of a queue task,
+    /// or `id` itself when it names a run.
+    async fn task_run_ids(&self, id: &str) -> Result<Vec<String>> {
+        let queue = load_queue(&self.repo_path.join(QUEUE_FILE)).await?;
+        if let Some(task) = queue.tasks.iter().find(|task| task.id == id) {
+            let mut runs: Vec<String> = task.attempts.iter().map(|a| a.run_id.clone()).collect();
+            if let Some(run_id) = &task.run_id
+                && !runs.contains(run_id)
+            {
+                runs.push(run_id.clone());
+            }
+            if runs.is_empty() {
+                return Err(anyhow!("Queue task '{}' has not run yet", id));
+            }
+            return Ok(runs);
+        }
+        resolve_run_path(&self.repo_path, Some(id))
+            .await
+            .with_context(|| format!("'{}' is not a queue task either", id))?;
+        Ok(vec![id.to_string()])
+    }
+}
+
+fn print_plan(plan: &UndoPlan) {
+    println!(
+        "{} {} → back to #{} ({} {}, {})",
+        "Undo".bright_cyan().bold(),
+        plan.work_dir.display(),
+        plan.target.seq,
+        plan.target.action,
+        plan.target.label,
+        plan.target.at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if !plan.commits.is_empty() {
+        println!("  {} {}", "commits dropped:".bold(), plan.commits.len());
+        for commit in &plan.commits {
+            println!("    {}", commit.red());
+        }
+    }
+    for (change, path) in &plan.files {
+        let line = match change {
+            FileChange::Created => format!("  - {} (created, will be deleted)", path).red(),
+            FileChange::Modified => format!("  ~ {} (modified, will be restored)", path).yellow(),
+            FileChange::Deleted => format!("  + {} (deleted, will be restored)", path).green(),
+        };
+        println!("{}", line);
+    }
+    println!();
+}
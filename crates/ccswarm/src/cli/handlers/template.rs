@@ -0,0 +1,673 @@
+//! `ccswarm template`: the builtin CLAUDE.md and task templates, and the
+//! project's customized copies of them.
+//!
+//! CLAUDE.md templates are the agent instructions in `templates/*.md`; their
+//! `{{variable}}` placeholders are filled from the project (see
+//! [`project_variables`]) or `--var`. Task templates are the scaffold
+//! templates of [`super::scaffold_template`]. `copy` puts a template under
+//! `.ccswarm/templates/<claude-md|scaffold>/`, where it replaces the builtin
+//! of the same name.
+
+use super::super::*;
+use super::scaffold_template::{self, ScaffoldTemplate};
+use anyhow::bail;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const CLAUDE_MD: &[(&str, &str)] = &[
+    (
+        "backend",
+        include_str!("../../../templates/backend_instructions.md"),
+    ),
+    (
+        "devops",
+        include_str!("../../../templates/devops_instructions.md"),
+    ),
+    (
+        "framework-specialist",
+        include_str!("../../../templates/framework_specialist.md"),
+    ),
+    (
+        "frontend",
+        include_str!("../../../templates/frontend_instructions.md"),
+    ),
+    (
+        "general",
+        include_str!("../../../templates/general_instructions.md"),
+    ),
+    (
+        "language-expert",
+        include_str!("../../../templates/language_expert.md"),
+    ),
+    ("qa", include_str!("../../../templates/qa_instructions.md")),
+    (
+        "security-specialist",
+        include_str!("../../../templates/security_specialist.md"),
+    ),
+];
+
+/// Placeholders a CLAUDE.md template may use without `--var`.
+const KNOWN_VARIABLES: &[&str] = &[
+    "project_name",
+    "main_branch",
+    "agents",
+    "language",
+    "framework_name",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum TemplateKind {
+    ClaudeMd,
+    Task,
+}
+
+impl TemplateKind {
+    fn parse(kind: &str) -> Result<Self> {
+        match kind {
+            "claude-md" => Ok(Self::ClaudeMd),
+            "task" => Ok(Self::Task),
+            other => bail!(
+                "Unknown template kind '{}' (expected: claude-md | task)",
+                other
+            ),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::ClaudeMd => "claude-md",
+            Self::Task => "task",
+        }
+    }
+
+    /// Directory under `.ccswarm/templates/` that holds the project's copies.
+    fn project_dir(self, repo: &Path) -> PathBuf {
+        match self {
+            Self::ClaudeMd => repo.join(".ccswarm").join("templates").join("claude-md"),
+            Self::Task => scaffold_template::project_template_dir(repo),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::ClaudeMd => "md",
+            Self::Task => "yaml",
+        }
+    }
+}
+
+/// A template and where it came from.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct TemplateEntry {
+    pub name: String,
+    pub kind: TemplateKind,
+    pub description: String,
+    /// `builtin`, or the file that replaces or adds to the builtins.
+    pub origin: String,
+    #[serde(skip)]
+    pub source: String,
+}
+
+/// Findings of [`validate_source`].
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Validation {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// The first Markdown heading, or the first line.
+fn describe_markdown(source: &str) -> String {
+    source
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .trim_start_matches('#')
+        .trim()
+        .to_string()
+}
+
+/// Every `{{name}}` placeholder in `source`, or an error for one left open.
+fn placeholders(source: &str) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| {
+            anyhow!(
+                "unclosed '{{{{' near '{}'",
+                after.lines().next().unwrap_or("")
+            )
+        })?;
+        let name = after[..end].trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            bail!("invalid placeholder '{{{{{}}}}}'", &after[..end]);
+        }
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    Ok(names)
+}
+
+/// Fill the placeholders `variables` has a value for; the rest are left.
+pub(crate) fn render(source: &str, variables: &BTreeMap<String, String>) -> String {
+    variables
+        .iter()
+        .fold(source.to_string(), |out, (name, value)| {
+            out.replace(&format!("{{{{{name}}}}}"), value)
+        })
+}
+
+/// Check a custom template's source. Placeholders outside
+/// [`KNOWN_VARIABLES`] are warnings: they need `--var` to render.
+pub(crate) fn validate_source(kind: TemplateKind, source: &str) -> Validation {
+    let mut validation = Validation::default();
+    match kind {
+        TemplateKind::ClaudeMd => {
+            if source.trim().is_empty() {
+                validation.errors.push("template is empty".to_string());
+                return validation;
+            }
+            if !source.trim_start().starts_with('#') {
+                validation
+                    .warnings
+                    .push("does not start with a Markdown heading".to_string());
+            }
+            match placeholders(source) {
+                Ok(names) => validation.warnings.extend(
+                    names
+                        .into_iter()
+                        .filter(|name| !KNOWN_VARIABLES.contains(&name.as_str()))
+                        .map(|name| format!("unknown variable '{name}' needs --var to render")),
+                ),
+                Err(e) => validation.errors.push(e.to_string()),
+            }
+        }
+        TemplateKind::Task => match serde_yml::from_str::<ScaffoldTemplate>(source) {
+            Ok(template) => {
+                if let Err(e) = template.validate() {
+                    validation.errors.push(e.to_string());
+                }
+            }
+            Err(e) => validation.errors.push(format!("invalid YAML: {e}")),
+        },
+    }
+    validation
+}
+
+/// The language of the project in `repo`, from its manifests.
+fn detect_language(repo: &Path) -> Option<&'static str> {
+    const MANIFESTS: &[(&str, &str)] = &[
+        ("Cargo.toml", "Rust"),
+        ("go.mod", "Go"),
+        ("tsconfig.json", "TypeScript"),
+        ("package.json", "JavaScript"),
+        ("pyproject.toml", "Python"),
+        ("requirements.txt", "Python"),
+        ("pom.xml", "Java"),
+        ("build.gradle", "Java"),
+        ("build.gradle.kts", "Kotlin"),
+        ("Gemfile", "Ruby"),
+    ];
+    MANIFESTS
+        .iter()
+        .find(|(file, _)| repo.join(file).is_file())
+        .map(|(_, language)| *language)
+}
+
+/// Values for the placeholders, from the config and the repository, then
+/// `overrides` (`key=value`).
+pub(crate) fn project_variables(
+    config: &CcswarmConfig,
+    repo: &Path,
+    overrides: &[String],
+) -> Result<BTreeMap<String, String>> {
+    let mut variables = BTreeMap::from([
+        ("project_name".to_string(), config.project.name.clone()),
+        (
+            "main_branch".to_string(),
+            config.project.repository.main_branch.clone(),
+        ),
+        (
+            "agents".to_string(),
+            config.agents.keys().cloned().collect::<Vec<_>>().join(", "),
+        ),
+    ]);
+    if let Some(language) = detect_language(repo) {
+        variables.insert("language".to_string(), language.to_string());
+    }
+    for pair in overrides {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| anyhow!("--var expects key=value, got '{}'", pair))?;
+        variables.insert(key.trim().to_string(), value.to_string());
+    }
+    Ok(variables)
+}
+
+/// Builtins, then the project's copies in place of the builtins they share
+/// a name with. Task templates also take `$CCSWARM_HOME`'s.
+pub(crate) async fn catalog(repo: &Path) -> Result<Vec<TemplateEntry>> {
+    let mut entries: Vec<TemplateEntry> = CLAUDE_MD
+        .iter()
+        .map(|(name, source)| TemplateEntry {
+            name: name.to_string(),
+            kind: TemplateKind::ClaudeMd,
+            description: describe_markdown(source),
+            origin: "builtin".to_string(),
+            source: source.to_string(),
+        })
+        .collect();
+
+    let dir = TemplateKind::ClaudeMd.project_dir(repo);
+    if dir.is_dir() {
+        let mut files = tokio::fs::read_dir(&dir).await?;
+        while let Some(file) = files.next_entry().await? {
+            let path = file.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("md") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let source = tokio::fs::read_to_string(&path).await?;
+            entries.retain(|e| !(e.kind == TemplateKind::ClaudeMd && e.name == name));
+            entries.push(TemplateEntry {
+                name: name.to_string(),
+                kind: TemplateKind::ClaudeMd,
+                description: describe_markdown(&source),
+                origin: path.display().to_string(),
+                source,
+            });
+        }
+    }
+
+    // Task templates: which file (if any) each one was last loaded from.
+    let dirs = scaffold_template::template_dirs(repo);
+    let mut origins = BTreeMap::new();
+    for dir in dirs.iter().filter(|d| d.is_dir()) {
+        for path in std::fs::read_dir(dir)?.filter_map(|e| e.ok().map(|e| e.path())) {
+            if !matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("yaml" | "yml")
+            ) {
+                continue;
+            }
+            let source = tokio::fs::read_to_string(&path).await?;
+            if let Ok(template) = serde_yml::from_str::<ScaffoldTemplate>(&source) {
+                origins.insert(template.name, (path.display().to_string(), source));
+            }
+        }
+    }
+    let templates =
+        scaffold_template::load_templates(&dirs.iter().map(PathBuf::as_path).collect::<Vec<_>>())
+            .await?;
+    for template in templates {
+        let (origin, source) = match origins.remove(&template.name) {
+            Some(found) => found,
+            None => (
+                "builtin".to_string(),
+                scaffold_template::BUILTIN
+                    .iter()
+                    .find(|(name, _)| *name == template.name)
+                    .map(|(_, source)| source.to_string())
+                    .unwrap_or_default(),
+            ),
+        };
+        entries.push(TemplateEntry {
+            name: template.name,
+            kind: TemplateKind::Task,
+            description: template.description,
+            origin,
+            source,
+        });
+    }
+
+    entries.sort_by(|a, b| (a.kind.as_str(), &a.name).cmp(&(b.kind.as_str(), &b.name)));
+    Ok(entries)
+}
+
+/// Look up `name`, or `<kind>/<name>` when both kinds have one.
+fn find<'a>(entries: &'a [TemplateEntry], name: &str) -> Result<&'a TemplateEntry> {
+    let (kind, name) = match name.split_once('/') {
+        Some((kind, name)) => (Some(TemplateKind::parse(kind)?), name),
+        None => (None, name),
+    };
+    let matches: Vec<_> = entries
+        .iter()
+        .filter(|e| e.name == name && kind.is_none_or(|k| e.kind == k))
+        .collect();
+    match matches.as_slice() {
+        [entry] => Ok(entry),
+        [] => bail!(
+            "Unknown template '{}'. Run `ccswarm template list` to see them",
+            name
+        ),
+        _ => bail!(
+            "Template name '{}' is ambiguous: use claude-md/{} or task/{}",
+            name,
+            name,
+            name
+        ),
+    }
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_template(&self, action: &TemplateAction) -> Result<()> {
+        match action {
+            TemplateAction::List { kind } => self.template_list(kind.as_deref()).await,
+            TemplateAction::Show {
+                template,
+                source,
+                vars,
+            } => self.template_show(template, *source, vars).await,
+            TemplateAction::Copy {
+                template,
+                name,
+                force,
+            } => self.template_copy(template, name.as_deref(), *force).await,
+            TemplateAction::Validate { path, strict } => {
+                self.template_validate(path.as_deref(), *strict).await
+            }
+        }
+    }
+
+    async fn template_list(&self, kind: Option<&str>) -> Result<()> {
+        let kind = kind.map(TemplateKind::parse).transpose()?;
+        let entries: Vec<_> = catalog(&self.repo_path)
+            .await?
+            .into_iter()
+            .filter(|e| kind.is_none_or(|k| e.kind == k))
+            .collect();
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": entries,
+                }))?
+            );
+            return Ok(());
+        }
+        for entry in &entries {
+            println!(
+                "{:<10} {:<22} {}{}",
+                entry.kind.as_str().bright_black(),
+                entry.name.bright_cyan(),
+                entry.description,
+                if entry.origin == "builtin" {
+                    String::new()
+                } else {
+                    format!(" ({})", entry.origin).bright_yellow().to_string()
+                }
+            );
+        }
+        Ok(())
+    }
+
+    async fn template_show(&self, name: &str, raw: bool, vars: &[String]) -> Result<()> {
+        let entries = catalog(&self.repo_path).await?;
+        let entry = find(&entries, name)?;
+        let variables = project_variables(&self.config, &self.repo_path, vars)?;
+        let rendered = if raw {
+            entry.source.clone()
+        } else {
+            match entry.kind {
+                TemplateKind::ClaudeMd => render(&entry.source, &variables),
+                TemplateKind::Task => {
+                    let template: ScaffoldTemplate = serde_yml::from_str(&entry.source)?;
+                    let mut out = render(&template.render_task(None), &variables);
+                    if !template.files.is_empty() {
+                        out.push_str("\n\nFiles:");
+                        for file in &template.files {
+                            out.push_str(&format!("\n- {}", file.path));
+                        }
+                    }
+                    out
+                }
+            }
+        };
+        let unresolved = if raw {
+            Vec::new()
+        } else {
+            placeholders(&rendered).unwrap_or_default()
+        };
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": {
+                        "template": entry,
+                        "variables": variables,
+                        "unresolved": unresolved,
+                        "rendered": rendered,
+                    },
+                }))?
+            );
+            return Ok(());
+        }
+        println!(
+            "{} {} ({})\n",
+            entry.kind.as_str().bright_black(),
+            entry.name.bright_cyan().bold(),
+            entry.origin
+        );
+        println!("{}", rendered.trim_end());
+        if !unresolved.is_empty() {
+            println!(
+                "\n{} unresolved: {} (pass --var {}=...)",
+                "!".yellow().bold(),
+                unresolved.join(", "),
+                unresolved[0]
+            );
+        }
+        Ok(())
+    }
+
+    async fn template_copy(&self, name: &str, as_name: Option<&str>, force: bool) -> Result<()> {
+        let entries = catalog(&self.repo_path).await?;
+        let entry = find(&entries, name)?;
+        let target_name = as_name.unwrap_or(&entry.name);
+        let dir = entry.kind.project_dir(&self.repo_path);
+        let target = dir.join(format!("{}.{}", target_name, entry.kind.extension()));
+        if target.exists() && !force {
+            bail!(
+                "{} already exists (use --force to overwrite)",
+                target.display()
+            );
+        }
+        let source = match (entry.kind, as_name) {
+            // The copy is looked up by the name inside the YAML.
+            (TemplateKind::Task, Some(new_name)) => {
+                let mut template: ScaffoldTemplate = serde_yml::from_str(&entry.source)?;
+                template.name = new_name.to_string();
+                serde_yml::to_string(&template)?
+            }
+            _ => entry.source.clone(),
+        };
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(&target, source)
+            .await
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "data": { "template": entry.name, "kind": entry.kind, "path": target },
+                }))?
+            );
+        } else {
+            println!(
+                "{} {} to {}",
+                "Copied".bright_green().bold(),
+                entry.name.bright_cyan(),
+                target.display()
+            );
+            println!("  edit it, then check it with `ccswarm template validate`");
+        }
+        Ok(())
+    }
+
+    /// Validate `path`, or every custom template of the project and user.
+    async fn template_validate(&self, path: Option<&Path>, strict: bool) -> Result<()> {
+        let files: Vec<(PathBuf, TemplateKind)> = match path {
+            Some(path) => {
+                let kind = match path.extension().and_then(|e| e.to_str()) {
+                    Some("md") => TemplateKind::ClaudeMd,
+                    Some("yaml" | "yml") => TemplateKind::Task,
+                    _ => bail!("{}: expected a .md or .yaml template", path.display()),
+                };
+                vec![(path.to_path_buf(), kind)]
+            }
+            None => {
+                let mut dirs = vec![(
+                    TemplateKind::ClaudeMd.project_dir(&self.repo_path),
+                    TemplateKind::ClaudeMd,
+                )];
+                dirs.extend(
+                    scaffold_template::template_dirs(&self.repo_path)
+                        .into_iter()
+                        .map(|dir| (dir, TemplateKind::Task)),
+                );
+                let mut files = Vec::new();
+                for (dir, kind) in dirs.into_iter().filter(|(d, _)| d.is_dir()) {
+                    for path in std::fs::read_dir(&dir)?.filter_map(|e| e.ok().map(|e| e.path())) {
+                        let ext = path.extension().and_then(|e| e.to_str());
+                        let matches = match kind {
+                            TemplateKind::ClaudeMd => ext == Some("md"),
+                            TemplateKind::Task => matches!(ext, Some("yaml" | "yml")),
+                        };
+                        if matches {
+                            files.push((path, kind));
+                        }
+                    }
+                }
+                files.sort();
+                files
+            }
+        };
+
+        let mut results = Vec::new();
+        for (path, kind) in &files {
+            let source = tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            results.push((path, kind, validate_source(*kind, &source)));
+        }
+        let failed = results
+            .iter()
+            .filter(|(_, _, v)| !v.errors.is_empty() || (strict && !v.warnings.is_empty()))
+            .count();
+
+        if self.json_output {
+            let data: Vec<_> = results
+                .iter()
+                .map(|(path, kind, validation)| {
+                    serde_json::json!({
+                        "path": path,
+                        "kind": kind,
+                        "errors": validation.errors,
+                        "warnings": validation.warnings,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": if failed == 0 { "success" } else { "error" },
+                    "data": data,
+                }))?
+            );
+        } else if results.is_empty() {
+            println!("No custom templates (copy one with `ccswarm template copy <name>`)");
+        } else {
+            for (path, kind, validation) in &results {
+                let mark = if !validation.errors.is_empty() {
+                    "✗".bright_red()
+                } else if !validation.warnings.is_empty() {
+                    "!".bright_yellow()
+                } else {
+                    "✓".bright_green()
+                };
+                println!(
+                    "{} {} {}",
+                    mark,
+                    kind.as_str().bright_black(),
+                    path.display()
+                );
+                for error in &validation.errors {
+                    println!("    error: {error}");
+                }
+                for warning in &validation.warnings {
+                    println!("    warning: {warning}");
+                }
+            }
+        }
+        if failed > 0 {
+            bail!(
+                "{} of {} templates failed validation",
+                failed,
+                results.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn project_copies_replace_builtins_and_render_with_project_variables() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::write(repo.path().join("Cargo.toml"), "[package]\n").unwrap();
+        let dir = TemplateKind::ClaudeMd.project_dir(repo.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("language-expert.md"),
+            "# {{language}} expert for {{project_name}}\n\nOwn {{crate_name}}.\n",
+        )
+        .unwrap();
+
+        let entries = catalog(repo.path()).await.unwrap();
+        assert!(
+            entries
+                .iter()
+                .any(|e| e.kind == TemplateKind::Task && e.name == "todo-app")
+        );
+        let expert = find(&entries, "claude-md/language-expert").unwrap();
+        assert_ne!(expert.origin, "builtin");
+        assert_eq!(find(&entries, "qa").unwrap().origin, "builtin");
+
+        let mut config = CcswarmConfig::default();
+        config.project.name = "shop".to_string();
+        let variables =
+            project_variables(&config, repo.path(), &["crate_name=shop-core".into()]).unwrap();
+        assert_eq!(
+            render(&expert.source, &variables),
+            "# Rust expert for shop\n\nOwn shop-core.\n"
+        );
+
+        let validation = validate_source(TemplateKind::ClaudeMd, &expert.source);
+        assert!(validation.errors.is_empty());
+        assert_eq!(validation.warnings.len(), 1);
+        assert!(
+            !validate_source(TemplateKind::ClaudeMd, "# x {{oops")
+                .errors
+                .is_empty()
+        );
+        assert!(
+            !validate_source(TemplateKind::Task, "name: t\ntask: ''\n")
+                .errors
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,525 @@
+//! Wall-clock budgets for queued tasks.
+//!
+//! A task queued with `--time-budget` runs every attempt under that budget.
+//! When it runs over, the attempt is cancelled and a checkpoint is written to
+//! `.ccswarm/checkpoints/<task-id>/attempt-<n>/`: the tail of the run's event
+//! stream, the files the agent touched and the diff of the directory the run
+//! last wrote to, new files included. The work itself stays in the tree. The task's [`OverBudget`] action then decides
+//! what happens:
+//!
+//! - `requeue` puts it back as pending; the next attempt is told to continue
+//!   from the checkpoint instead of starting over.
+//! - `escalate` does the same with one more think-mode step.
+//! - `ask` pauses it behind a `resume` approval; approving requeues it,
+//!   rejecting fails it.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::queue_state::{ClaimState, OverBudget, QueueState, QueueTask, TaskAttempt};
+use crate::git::shell::git;
+use crate::hitl::{ApprovalStatus, ApprovalStore, Gate};
+use crate::workflow::retry_policy::{AttemptPlan, RetryPolicy};
+
+pub(crate) const CHECKPOINT_DIR: &str = ".ccswarm/checkpoints";
+
+/// Queue state of a task waiting for a human to decide whether it resumes.
+pub(crate) const PAUSED_STATE: &str = "paused";
+
+/// Trailing run events kept in a checkpoint.
+const RECENT_EVENTS: usize = 20;
+
+/// Git's empty tree, the base of a diff in a repository without commits.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Snapshot of an attempt that ran out of time, stored as `checkpoint.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TaskCheckpoint {
+    pub(crate) task_id: String,
+    pub(crate) attempt: u32,
+    pub(crate) run_id: String,
+    pub(crate) started_at: DateTime<Utc>,
+    pub(crate) paused_at: DateTime<Utc>,
+    pub(crate) budget_secs: u64,
+    /// Working directory the run last wrote to (an agent worktree, or the
+    /// project root).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) work_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) head: Option<String>,
+    #[serde(default)]
+    pub(crate) changed_files: Vec<String>,
+    #[serde(default)]
+    pub(crate) untracked_files: Vec<String>,
+    /// `worktree.diff` next to this file, when the tree had changes; files
+    /// the agent created are included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) diff: Option<String>,
+    /// Last events of `.ccswarm/runs/<run-id>/events.ndjson`.
+    #[serde(default)]
+    pub(crate) recent_events: Vec<serde_json::Value>,
+}
+
+/// Await `work`, giving up once `budget` elapses. `None` means it ran over.
+pub(crate) async fn within_budget<F: Future>(
+    budget: Option<Duration>,
+    work: F,
+) -> Option<F::Output> {
+    match budget {
+        Some(budget) => tokio::time::timeout(budget, work).await.ok(),
+        None => Some(work.await),
+    }
+}
+
+/// Attempt plan for the task's next run: the retry policy's plan, bumped one
+/// think step per earlier attempt that timed out under `escalate`.
+pub(crate) fn plan_next(task: &QueueTask, policy: &RetryPolicy) -> AttemptPlan {
+    let mut plan = policy.plan(task.next_attempt());
+    // A model the retry policy switches to wins over the task's own.
+    if plan.model.is_none() {
+        plan.model = task.model.clone();
+    }
+    if task
+        .time_box
+        .is_some_and(|time_box| time_box.on_exceeded == OverBudget::Escalate)
+    {
+        for _ in task.attempts.iter().filter(|a| a.outcome == "timed_out") {
+            plan.escalate();
+        }
+    }
+    plan
+}
+
+/// Prompt for the task's next run, with its acceptance criteria. After a
+/// timed-out attempt the agent is pointed at the checkpoint so it picks up
+/// where the last one stopped; after a rejected one it hears what failed.
+pub(crate) fn task_text(task: &QueueTask, plan: &AttemptPlan) -> String {
+    let text = match &task.acceptance {
+        Some(acceptance) => {
+            let rejection = task
+                .attempts
+                .last()
+                .filter(|a| a.outcome == crate::workflow::acceptance::REJECTED)
+                .and_then(|a| a.error.as_deref());
+            plan.task_text(&format!(
+                "{}\n\n{}",
+                task.task.trim_end(),
+                acceptance.prompt(rejection)
+            ))
+        }
+        None => plan.task_text(&task.task),
+    };
+    let Some(last) = task.attempts.last() else {
+        return text;
+    };
+    let (Some(checkpoint), Some(time_box)) = (&last.checkpoint, task.time_box) else {
+        return text;
+    };
+    format!(
+        "{}\n\nAttempt {} ran out of its {}s time budget. Its changes are still in the \
+         working tree; the checkpoint at {} lists them. Continue from there instead of \
+         starting over.",
+        text.trim_end(),
+        last.attempt,
+        time_box.budget_secs,
+        checkpoint.display()
+    )
+}
+
+/// Checkpoint an attempt that ran over, record it as `timed_out`, and apply
+/// the task's over-budget action. Returns the action taken.
+pub(crate) async fn pause_over_budget(
+    state: &QueueState,
+    repo: &Path,
+    task: &QueueTask,
+    plan: &AttemptPlan,
+    run_id: &str,
+    started_at: DateTime<Utc>,
+    requested_by: &str,
+) -> Result<OverBudget> {
+    let time_box = task
+        .time_box
+        .with_context(|| format!("Queue task {} has no time budget", task.id))?;
+
+    let checkpoint = match write_checkpoint(
+        repo,
+        &task.id,
+        plan.attempt,
+        run_id,
+        started_at,
+        time_box.budget_secs,
+    )
+    .await
+    {
+        Ok(dir) => Some(dir),
+        Err(e) => {
+            tracing::warn!(task_id = %task.id, "failed to write checkpoint: {e}");
+            None
+        }
+    };
+
+    let mut attempt = TaskAttempt::finished(
+        plan,
+        run_id,
+        started_at,
+        Some(format!("time budget of {}s exceeded", time_box.budget_secs)),
+    );
+    attempt.outcome = "timed_out".to_string();
+    attempt.checkpoint = checkpoint;
+
+    let action = time_box.on_exceeded;
+    state
+        .update_task(&task.id, |task| {
+            task.attempts.push(attempt);
+            task.run_id = Some(run_id.to_string());
+            task.completed_at = None;
+            match action {
+                OverBudget::Requeue | OverBudget::Escalate => {
+                    task.state = "pending".to_string();
+                    task.claim = ClaimState::Unclaimed;
+                }
+                OverBudget::Ask => {
+                    task.state = PAUSED_STATE.to_string();
+                    task.claim = ClaimState::Released {
+                        reason: "time budget exceeded".to_string(),
+                    };
+                }
+            }
+            Ok(())
+        })
+        .await?;
+
+    if action == OverBudget::Ask {
+        ApprovalStore::new(repo)
+            .request(&task.id, Gate::Resume, &task.task, requested_by)
+            .await?;
+    }
+    Ok(action)
+}
+
+pub(crate) fn print_over_budget(task: &QueueTask, action: OverBudget) {
+    let budget = task.time_box.map_or(0, |time_box| time_box.budget_secs);
+    let next = match action {
+        OverBudget::Requeue => "requeued; the next run resumes from the checkpoint".to_string(),
+        OverBudget::Escalate => "requeued with a deeper think mode".to_string(),
+        OverBudget::Ask => format!(
+            "paused — resume with `ccswarm approve resume --id {}`",
+            task.id
+        ),
+    };
+    println!(
+        "{} {} ran over its {}s budget — checkpointed and {}",
+        "⏸".bright_yellow().bold(),
+        task.id.bright_yellow(),
+        budget,
+        next
+    );
+}
+
+/// Apply human decisions on paused tasks: approved ones go back to pending,
+/// rejected ones fail. Returns how many were requeued.
+pub(crate) async fn reconcile_paused(state: &QueueState, repo: &Path) -> Result<usize> {
+    let queue = state.load().await?;
+    let store = ApprovalStore::new(repo);
+    let mut resumed = 0;
+
+    for task in queue.tasks.iter().filter(|t| t.state == PAUSED_STATE) {
+        let Some(record) = store.get(&task.id).await? else {
+            continue;
+        };
+        if record.gate != Gate::Resume {
+            continue;
+        }
+        match record.status {
+            ApprovalStatus::Pending => {}
+            ApprovalStatus::Approved => {
+                state
+                    .update_task(&task.id, |task| {
+                        if task.state == PAUSED_STATE {
+                            task.state = "pending".to_string();
+                            task.claim = ClaimState::Unclaimed;
+                        }
+                        Ok(())
+                    })
+                    .await?;
+                resumed += 1;
+            }
+            ApprovalStatus::Rejected => {
+                let reason = record
+                    .reason
+                    .unwrap_or_else(|| "resume rejected".to_string());
+                state
+                    .update_task(&task.id, |task| {
+                        if task.state == PAUSED_STATE {
+                            task.state = "failed".to_string();
+                            task.completed_at = Some(Utc::now());
+                            task.claim = ClaimState::Released { reason };
+                        }
+                        Ok(())
+                    })
+                    .await?;
+            }
+        }
+    }
+    Ok(resumed)
+}
+
+/// Write the checkpoint for one attempt; returns its directory relative to `repo`.
+async fn write_checkpoint(
+    repo: &Path,
+    task_id: &str,
+    attempt: u32,
+    run_id: &str,
+    started_at: DateTime<Utc>,
+    budget_secs: u64,
+) -> Result<PathBuf> {
+    let relative = Path::new(CHECKPOINT_DIR)
+        .join(task_id)
+        .join(format!("attempt-{attempt}"));
+    let dir = repo.join(&relative);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let work_dir = run_work_dir(repo, run_id).await;
+    let diff = worktree_diff(&work_dir).await.ok();
+    let diff_file = match diff.as_deref() {
+        Some(diff) if !diff.is_empty() => {
+            tokio::fs::write(dir.join("worktree.diff"), diff).await?;
+            Some("worktree.diff".to_string())
+        }
+        _ => None,
+    };
+
+    let checkpoint = TaskCheckpoint {
+        task_id: task_id.to_string(),
+        attempt,
+        run_id: run_id.to_string(),
+        started_at,
+        paused_at: Utc::now(),
+        budget_secs,
+        head: git(&work_dir, &["rev-parse", "HEAD"])
+            .await
+            .ok()
+            .map(|head| head.trim().to_string()),
+        changed_files: lines(git(&work_dir, &["diff", "HEAD", "--name-only"]).await.ok()),
+        untracked_files: lines(
+            git(&work_dir, &["ls-files", "--others", "--exclude-standard"])
+                .await
+                .ok(),
+        ),
+        work_dir: Some(work_dir),
+        diff: diff_file,
+        recent_events: recent_events(&repo.join(".ccswarm").join("runs").join(run_id)).await,
+    };
+    tokio::fs::write(
+        dir.join("checkpoint.json"),
+        serde_json::to_string_pretty(&checkpoint)?,
+    )
+    .await?;
+    Ok(relative)
+}
+
+/// Where the run last snapshotted before writing, per its action journal;
+/// the project root when it never wrote.
+async fn run_work_dir(repo: &Path, run_id: &str) -> PathBuf {
+    crate::journal::ActionJournal::for_run(repo, run_id)
+        .entries()
+        .await
+        .ok()
+        .and_then(|entries| entries.last().map(|entry| entry.work_dir.clone()))
+        .unwrap_or_else(|| repo.to_path_buf())
+}
+
+/// `dir`'s changes against `HEAD`, untracked files included.
+async fn worktree_diff(dir: &Path) -> Result<String> {
+    let (top, head, snapshot) = crate::journal::snapshot_now(dir).await?;
+    let base = head.as_deref().unwrap_or(EMPTY_TREE);
+    git(&top, &["diff", "--binary", base, &snapshot]).await
+}
+
+fn lines(output: Option<String>) -> Vec<String> {
+    output
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+async fn recent_events(run_dir: &Path) -> Vec<serde_json::Value> {
+    let Ok(raw) = tokio::fs::read_to_string(run_dir.join("events.ndjson")).await else {
+        return Vec::new();
+    };
+    let events: Vec<serde_json::Value> = raw
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = events.len().saturating_sub(RECENT_EVENTS);
+    events.into_iter().skip(skip).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::handlers::queue_state::{QueueFile, TimeBox, save_queue};
+
+    fn timed_task(id: &str, on_exceeded: OverBudget) -> QueueTask {
+        QueueTask {
+            id: id.to_string(),
+            task: "port the parser".to_string(),
+            flow: None,
+            state: "running".to_string(),
+            created_at: Utc::now(),
+            completed_at: None,
+            run_id: None,
+            claim: ClaimState::Running {
+                run_id: "run-1".to_string(),
+                started_at_ms: 0,
+            },
+            attempts: Vec::new(),
+            time_box: Some(TimeBox {
+                budget_secs: 60,
+                on_exceeded,
+            }),
+            command_timeout: None,
+            model: None,
+            priority: None,
+            labels: Vec::new(),
+            depends_on: Vec::new(),
+            blocker: None,
+            acceptance: None,
+        }
+    }
+
+    async fn pause(dir: &Path, task: &QueueTask) -> Result<(QueueState, OverBudget)> {
+        let path = dir.join(".ccswarm").join("queue.yaml");
+        save_queue(
+            &path,
+            &QueueFile {
+                tasks: vec![task.clone()],
+                ..Default::default()
+            },
+        )
+        .await?;
+        let state = QueueState::new(path);
+        let action = pause_over_budget(
+            &state,
+            dir,
+            task,
+            &AttemptPlan::first(),
+            "run-1",
+            Utc::now(),
+            "drain",
+        )
+        .await?;
+        Ok((state, action))
+    }
+
+    #[tokio::test]
+    async fn test_within_budget_gives_up_on_slow_work() {
+        let slow = tokio::time::sleep(Duration::from_secs(5));
+        assert!(
+            within_budget(Some(Duration::from_millis(10)), slow)
+                .await
+                .is_none()
+        );
+        assert_eq!(within_budget(None, async { 7 }).await, Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_requeue_checkpoints_and_resumes_from_it() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (state, action) = pause(dir.path(), &timed_task("q-1", OverBudget::Requeue)).await?;
+        assert_eq!(action, OverBudget::Requeue);
+
+        let task = state.load().await?.tasks.remove(0);
+        assert_eq!(task.state, "pending");
+        assert_eq!(task.claim, ClaimState::Unclaimed);
+        let attempt = &task.attempts[0];
+        assert_eq!(attempt.outcome, "timed_out");
+        let checkpoint = attempt.checkpoint.clone().context("missing checkpoint")?;
+        assert!(
+            dir.path()
+                .join(&checkpoint)
+                .join("checkpoint.json")
+                .exists()
+        );
+
+        let plan = plan_next(&task, &RetryPolicy::default());
+        assert_eq!(plan.attempt, 2);
+        assert_eq!(plan.think_mode, None);
+        assert!(task_text(&task, &plan).contains(&checkpoint.display().to_string()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_escalate_bumps_think_mode() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (state, _) = pause(dir.path(), &timed_task("q-2", OverBudget::Escalate)).await?;
+        let task = state.load().await?.tasks.remove(0);
+        let plan = plan_next(&task, &RetryPolicy::default());
+        assert_eq!(plan.think_mode, Some(crate::config::ThinkMode::Think));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_diffs_the_worktree_the_run_wrote_to() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let repo = dir.path().join("project");
+        let worktree = dir.path().join("agent");
+        std::fs::create_dir(&repo)?;
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "t@example.com"],
+            &["config", "user.name", "t"],
+            &["commit", "-q", "--allow-empty", "-m", "init"],
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "agent",
+                &worktree.to_string_lossy(),
+            ],
+        ] {
+            git(&repo, args).await?;
+        }
+        crate::journal::ActionJournal::for_run(&repo, "run-1")
+            .record(crate::journal::JournalAction::Stage, "implement", &worktree)
+            .await?;
+        std::fs::write(worktree.join("parser.rs"), "fn parse() {}\n")?;
+
+        let (state, _) = pause(&repo, &timed_task("q-4", OverBudget::Requeue)).await?;
+        let task = state.load().await?.tasks.remove(0);
+        let checkpoint = repo.join(task.attempts[0].checkpoint.as_ref().unwrap());
+        let diff = std::fs::read_to_string(checkpoint.join("worktree.diff"))?;
+        assert!(diff.contains("+fn parse() {}"), "{diff}");
+        let saved: TaskCheckpoint = serde_json::from_str(&std::fs::read_to_string(
+            checkpoint.join("checkpoint.json"),
+        )?)?;
+        assert_eq!(saved.untracked_files, ["parser.rs"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ask_pauses_until_resume_is_decided() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (state, _) = pause(dir.path(), &timed_task("q-3", OverBudget::Ask)).await?;
+        assert_eq!(state.load().await?.tasks[0].state, PAUSED_STATE);
+        assert!(!state.try_claim("q-3", "run-2").await?);
+
+        assert_eq!(reconcile_paused(&state, dir.path()).await?, 0);
+        ApprovalStore::new(dir.path())
+            .decide("q-3", Gate::Resume, true, None)
+            .await?;
+        assert_eq!(reconcile_paused(&state, dir.path()).await?, 1);
+        assert!(state.try_claim("q-3", "run-2").await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//! `ccswarm tools`: typed tools for function-calling providers.
+
+use super::super::*;
+use crate::session::sampling::ContextSampler;
+use crate::session::tool_server;
+use crate::session::tools::{SessionToolbox, allowed_tools};
+use crate::workflow::PermissionEnforcer;
+use crate::workflow::flow::MovementPermission;
+
+impl CliRunner {
+    pub(crate) async fn handle_tools(&self, action: &ToolsAction) -> Result<()> {
+        match action {
+            ToolsAction::List { permission } => {
+                let permission = parse_permission(permission)?;
+                let tools = allowed_tools(&PermissionEnforcer::new(permission));
+                if self.json_output {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&serde_json::json!({
+                            "status": "success",
+                            "data": {
+                                "tools": tools.iter().map(|t| t.definition()).collect::<Vec<_>>(),
+                            },
+                        }))?
+                    );
+                    return Ok(());
+                }
+                for tool in tools {
+                    println!("{}", tool.name().bright_cyan().bold());
+                    println!("  {}", tool.description());
+                    println!(
+                        "  {}",
+                        serde_json::to_string(&tool.input_schema())?.dimmed()
+                    );
+                }
+                Ok(())
+            }
+            ToolsAction::Serve {
+                dir,
+                permission,
+                allow,
+                agent,
+            } => {
+                let dir = if dir.is_absolute() {
+                    dir.clone()
+                } else {
+                    std::env::current_dir()?.join(dir)
+                };
+                let toolbox = SessionToolbox::new(
+                    &dir,
+                    parse_permission(permission)?,
+                    allow,
+                    self.config.redaction.compile()?,
+                )
+                .await?
+                .with_prompt_guard(self.config.prompt_guard.compile()?)
+                .with_output_sampling(
+                    ContextSampler::new(self.config.output_sampling.clone())
+                        .with_log_store(&self.repo_path),
+                    ai_session::output::OutputParser::with_configs(&self.config.parsers)?,
+                )
+                .with_partial_output(self.config.partial_output.clone())
+                .with_command_approval(
+                    &self.repo_path,
+                    self.config.auto_accept.clone(),
+                    Some(std::sync::Arc::new(self.command_explainer()?)),
+                );
+                let toolbox = match agent {
+                    Some(agent) => toolbox.with_agent_id(agent).with_scratchpad(
+                        &crate::workflow::coverage::main_worktree(&dir).await,
+                        agent,
+                        self.config.scratchpad.clone(),
+                    ),
+                    None => toolbox,
+                };
+                let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+                tool_server::serve(&toolbox, stdin, tokio::io::stdout()).await
+            }
+        }
+    }
+}
+
+fn parse_permission(name: &str) -> Result<MovementPermission> {
+    serde_json::from_value(serde_json::Value::from(name.to_ascii_lowercase())).map_err(|_| {
+        anyhow!(
+            "Unknown permission '{}' (expected readonly, edit or full)",
+            name
+        )
+    })
+}
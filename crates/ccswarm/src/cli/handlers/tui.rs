@@ -0,0 +1,815 @@
+//! `ccswarm tui`: interactive terminal views.
+
+use super::super::*;
+use super::workflow::AskOptions;
+use crate::governance::human::{HumanInbox, InboxItem};
+use crate::tui::chat::{self, ChatApp};
+use crate::tui::diff::{FileDiff, parse_unified};
+use crate::tui::docs::DocsApp;
+use crate::tui::inbox::{InboxApp, Reply, Response};
+use crate::tui::logs::{self, LogLine, LogsApp};
+use crate::tui::review::{Decision, ReviewApp};
+use crate::tui::timeline::TimelineApp;
+use crate::tui::usage::{AgentUsage, Budget, RunState, UsageApp};
+use crate::tui::{Action, Screen, next_key};
+use ai_session::coordination::SharedDocStore;
+use ai_session::core::suspend::Suspend;
+use std::io::IsTerminal;
+use std::time::Duration;
+
+/// The view `ccswarm tui` opens without a subcommand, with default options.
+fn named_view(name: &str) -> Result<TuiView> {
+    Ok(match name {
+        "review" => TuiView::Review {
+            worktree: None,
+            base: None,
+            check: None,
+        },
+        "usage" => TuiView::Usage {
+            budget_tokens: None,
+            budget_usd: None,
+        },
+        "docs" => TuiView::Docs { doc: None },
+        "logs" => TuiView::Logs {
+            run: None,
+            agent: None,
+            level: "debug".to_string(),
+            search: None,
+        },
+        "inbox" => TuiView::Inbox { human: None },
+        "timeline" => TuiView::Timeline { session: None },
+        "chat" => TuiView::Chat { session: None },
+        other => {
+            return Err(anyhow!(
+                "unknown default_view '{other}' in the TUI config \
+                 (expected review, usage, docs, logs, inbox, timeline or chat)"
+            ));
+        }
+    })
+}
+
+impl CliRunner {
+    pub(crate) async fn handle_tui(
+        &self,
+        theme: Option<&str>,
+        view: Option<&TuiView>,
+    ) -> Result<()> {
+        if !std::io::stdout().is_terminal() {
+            return Err(anyhow!("ccswarm tui needs an interactive terminal"));
+        }
+        let settings = crate::tui::settings::install(theme)?;
+        let default_view;
+        let view = match view {
+            Some(view) => view,
+            None => {
+                default_view = named_view(settings.default_view.as_deref().unwrap_or("inbox"))?;
+                &default_view
+            }
+        };
+        match view {
+            TuiView::Review {
+                worktree,
+                base,
+                check,
+            } => {
+                self.tui_review(worktree.as_deref(), base.as_deref(), check.as_deref())
+                    .await
+            }
+            TuiView::Usage {
+                budget_tokens,
+                budget_usd,
+            } => {
+                self.tui_usage(Budget {
+                    tokens: *budget_tokens,
+                    usd: *budget_usd,
+                })
+                .await
+            }
+            TuiView::Docs { doc } => self.tui_docs(doc.as_deref()).await,
+            TuiView::Logs {
+                run,
+                agent,
+                level,
+                search,
+            } => {
+                self.tui_logs(run.as_deref(), agent.clone(), level, search.as_deref())
+                    .await
+            }
+            TuiView::Inbox { human } => self.tui_inbox(human.as_deref()).await,
+            TuiView::Timeline { session } => self.tui_timeline(session.as_deref()).await,
+            TuiView::Chat { session } => self.tui_chat(session.as_deref()).await,
+        }
+    }
+
+    async fn tui_chat(&self, session: Option<&str>) -> Result<()> {
+        let run_dir = super::run_utils::resolve_run_path(&self.repo_path, session).await?;
+        let path = run_dir.join(crate::session::stream::CHAT_STREAM_FILE);
+        let load = || async {
+            let content = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+            chat::bubbles(&crate::session::stream::parse_chunks(&content))
+        };
+        let mut app = ChatApp::new(load().await);
+
+        // Replies grow token by token, so reload on every idle tick.
+        let mut screen = Screen::enter()?;
+        loop {
+            screen.draw(|frame| app.render(frame))?;
+            let action = match next_key(Duration::from_millis(100))? {
+                Some(key) => app.handle_key(key),
+                None => Action::Reload,
+            };
+            match action {
+                Action::Quit => break,
+                Action::Reload => app.set_bubbles(load().await),
+                Action::Stop => {
+                    let Some(agent) = app.take_stop() else {
+                        continue;
+                    };
+                    match crate::session::stream::request_stop(&run_dir, &agent) {
+                        Ok(()) => app.set_status(format!("Stopping {agent}…")),
+                        Err(e) => app.set_status(format!("Failed: {e}")),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn tui_timeline(&self, session: Option<&str>) -> Result<()> {
+        let run_dir = super::run_utils::resolve_run_path(&self.repo_path, session).await?;
+        let (timeline, now) = load_timeline(&run_dir).await;
+        let mut app = TimelineApp::new(timeline, now);
+
+        let refresh = Duration::from_secs(1);
+        let mut last_load = std::time::Instant::now();
+        let mut screen = Screen::enter()?;
+        loop {
+            screen.draw(|frame| app.render(frame))?;
+            let action = match next_key(Duration::from_millis(250))? {
+                Some(key) => app.handle_key(key),
+                None if last_load.elapsed() >= refresh => Action::Reload,
+                None => continue,
+            };
+            match action {
+                Action::Quit => break,
+                Action::Reload => {
+                    let (timeline, now) = load_timeline(&run_dir).await;
+                    app.update(timeline, now);
+                    last_load = std::time::Instant::now();
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn tui_inbox(&self, human: Option<&str>) -> Result<()> {
+        let inbox = HumanInbox::open(&self.repo_path).await?;
+        let mut app = InboxApp::new(inbox.list(human).await?);
+
+        let refresh = Duration::from_secs(1);
+        let mut last_load = std::time::Instant::now();
+        {
+            let mut screen = Screen::enter()?;
+            loop {
+                screen.draw(|frame| app.render(frame))?;
+                let action = match next_key(Duration::from_millis(250))? {
+                    Some(key) => app.handle_key(key),
+                    None if last_load.elapsed() >= refresh => Action::Reload,
+                    None => continue,
+                };
+                match action {
+                    Action::Quit => break,
+                    Action::Respond => {
+                        let Some(response) = app.take_response() else {
+                            continue;
+                        };
+                        match self.record_reply(&inbox, &response).await {
+                            Ok(item) => app.set_status(format!("{} {}", item.id, item.status)),
+                            Err(e) => app.set_status(format!("Failed: {e}")),
+                        }
+                        app.set_items(inbox.list(human).await?);
+                    }
+                    Action::Reload => {
+                        app.set_items(inbox.list(human).await?);
+                        last_load = std::time::Instant::now();
+                    }
+                    Action::None
+                    | Action::SendBack
+                    | Action::Pause
+                    | Action::Resume
+                    | Action::Open
+                    | Action::Save
+                    | Action::Stop => {}
+                }
+            }
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "open": app.items().iter().filter(|item| !item.status.is_closed()).count(),
+                }))?
+            );
+        }
+        Ok(())
+    }
+
+    async fn record_reply(&self, inbox: &HumanInbox, response: &Response) -> Result<InboxItem> {
+        let id = response.id.as_str();
+        let item = match &response.reply {
+            Reply::Accept => inbox.accept(id).await?,
+            Reply::Complete(summary) => inbox.finish(id, true, summary).await?,
+            Reply::Decline(reason) => inbox.finish(id, false, reason).await?,
+            Reply::Comment(text) => {
+                let author = inbox
+                    .get(id)
+                    .await?
+                    .map(|item| item.human)
+                    .unwrap_or_default();
+                inbox.comment(id, &author, text).await?
+            }
+        };
+        super::human::settle_queue_task(&self.repo_path, &item).await?;
+        Ok(item)
+    }
+
+    async fn tui_logs(
+        &self,
+        run: Option<&str>,
+        agent: Option<String>,
+        level: &str,
+        search: Option<&str>,
+    ) -> Result<()> {
+        let level = match level.to_ascii_lowercase().as_str() {
+            "debug" => crate::events::EventLevel::Debug,
+            "info" => crate::events::EventLevel::Info,
+            "warn" | "warning" => crate::events::EventLevel::Warn,
+            "error" => crate::events::EventLevel::Error,
+            other => anyhow::bail!(
+                "Unknown level '{}' (expected debug, info, warn or error)",
+                other
+            ),
+        };
+        let run_dir = match run {
+            Some(run) => {
+                Some(super::run_utils::resolve_run_path(&self.repo_path, Some(run)).await?)
+            }
+            None => None,
+        };
+        let parser = ai_session::output::OutputParser::with_configs(&self.config.parsers)?;
+        let mut app = LogsApp::new(self.load_logs(run_dir.as_deref(), &parser).await?);
+        app.set_min_level(level);
+        app.set_agent(agent);
+        if let Some(pattern) = search {
+            app.set_search(pattern)
+                .with_context(|| format!("Invalid --search regex '{pattern}'"))?;
+        }
+
+        let refresh = Duration::from_secs(1);
+        let mut last_load = std::time::Instant::now();
+        {
+            let mut screen = Screen::enter()?;
+            loop {
+                screen.draw(|frame| app.render(frame))?;
+                let action = match next_key(Duration::from_millis(250))? {
+                    Some(key) => app.handle_key(key),
+                    // A paused view isn't reloaded under the reader.
+                    None if app.is_following() && last_load.elapsed() >= refresh => Action::Reload,
+                    None => continue,
+                };
+                match action {
+                    Action::Quit => break,
+                    Action::Reload => {
+                        app.set_lines(self.load_logs(run_dir.as_deref(), &parser).await?);
+                        last_load = std::time::Instant::now();
+                    }
+                    Action::None
+                    | Action::SendBack
+                    | Action::Pause
+                    | Action::Resume
+                    | Action::Open
+                    | Action::Save
+                    | Action::Stop
+                    | Action::Respond => {}
+                }
+            }
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "lines": app.visible().count(),
+                }))?
+            );
+        }
+        Ok(())
+    }
+
+    /// Events of `run` (default: the live runs, else the latest), the agent
+    /// logs in .ccswarm/logs and the daemon log.
+    async fn load_logs(
+        &self,
+        run: Option<&Path>,
+        parser: &ai_session::output::OutputParser,
+    ) -> Result<Vec<LogLine>> {
+        let ccswarm_dir = self.repo_path.join(".ccswarm");
+        let runs = match run {
+            Some(dir) => vec![dir.to_path_buf()],
+            None => shown_runs(&ccswarm_dir.join("runs")).await?,
+        };
+        let mut lines = Vec::new();
+        for dir in runs {
+            let run_id = dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let events = tokio::fs::read_to_string(dir.join("events.ndjson"))
+                .await
+                .unwrap_or_default();
+            lines.extend(logs::from_events(&run_id, &events));
+        }
+
+        let mut files = Vec::new();
+        if let Ok(mut rd) = tokio::fs::read_dir(ccswarm_dir.join("logs")).await {
+            while let Some(entry) = rd.next_entry().await? {
+                if entry.path().is_file() {
+                    files.push(entry.path());
+                }
+            }
+        }
+        files.sort();
+        files.push(crate::daemon::DaemonPaths::new(&self.repo_path).log);
+        for path in files {
+            let Ok(content) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let agent = self
+                .config
+                .agents
+                .keys()
+                .find(|agent| name.contains(agent.as_str()));
+            lines.extend(logs::from_text(
+                &name,
+                agent.map(String::as_str),
+                tail(&content, MAX_LOG_LINES),
+                parser,
+            ));
+        }
+        Ok(lines)
+    }
+
+    async fn tui_docs(&self, doc: Option<&str>) -> Result<()> {
+        let store = SharedDocStore::new(self.repo_path.join(".ccswarm").join("docs"));
+        // The pid keeps two open views from writing under the same replica.
+        let user = std::env::var("USER").unwrap_or_else(|_| "human".to_string());
+        let replica = format!("{user}:{}", std::process::id());
+        let mut app = DocsApp::new(store.list().await?);
+        if let Some(name) = doc {
+            app.select(name);
+            app.show(store.open(name, &replica).await?);
+        }
+
+        let refresh = Duration::from_secs(1);
+        let mut last_load = std::time::Instant::now();
+        {
+            let mut screen = Screen::enter()?;
+            loop {
+                screen.draw(|frame| app.render(frame))?;
+                let action = match next_key(Duration::from_millis(250))? {
+                    Some(key) => app.handle_key(key),
+                    None if last_load.elapsed() >= refresh => Action::Reload,
+                    None => continue,
+                };
+                match action {
+                    Action::Quit => break,
+                    Action::Open => {
+                        let Some(name) = app.selected_name().map(str::to_string) else {
+                            continue;
+                        };
+                        match store.open(&name, &replica).await {
+                            Ok(doc) => app.show(doc),
+                            Err(e) => app.set_status(e.to_string()),
+                        }
+                    }
+                    Action::Save => {
+                        if let Some(doc) = app.doc_mut()
+                            && let Err(e) = store.save(doc).await
+                        {
+                            app.set_status(format!("Save failed: {e}"));
+                        }
+                    }
+                    Action::Reload => {
+                        app.set_names(store.list().await?);
+                        if let Some(doc) = app.doc_mut() {
+                            store.refresh(doc).await?;
+                        }
+                        app.clamp_cursor();
+                        last_load = std::time::Instant::now();
+                    }
+                    Action::None
+                    | Action::SendBack
+                    | Action::Pause
+                    | Action::Resume
+                    | Action::Respond
+                    | Action::Stop => {}
+                }
+            }
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "dir": store.dir(),
+                    "documents": app.names(),
+                }))?
+            );
+        }
+        Ok(())
+    }
+
+    async fn tui_usage(&self, budget: Budget) -> Result<()> {
+        let runs_dir = self.repo_path.join(".ccswarm").join("runs");
+        let mut app = UsageApp::new(load_usage(&runs_dir).await?, budget);
+        if app.rows().is_empty() {
+            println!(
+                "No token usage recorded yet. Run a pipeline first: ccswarm pipeline --task ..."
+            );
+            return Ok(());
+        }
+
+        let refresh = Duration::from_secs(1);
+        let mut last_load = std::time::Instant::now();
+        {
+            let mut screen = Screen::enter()?;
+            loop {
+                screen.draw(|frame| app.render(frame))?;
+                let action = match next_key(Duration::from_millis(250))? {
+                    Some(key) => app.handle_key(key),
+                    None if last_load.elapsed() >= refresh => Action::Reload,
+                    None => continue,
+                };
+                let suspend = match action {
+                    Action::Quit => break,
+                    Action::Pause => Suspend::Stop,
+                    Action::Resume => Suspend::Continue,
+                    Action::Reload => {
+                        app.update(load_usage(&runs_dir).await?);
+                        last_load = std::time::Instant::now();
+                        continue;
+                    }
+                    Action::None
+                    | Action::SendBack
+                    | Action::Open
+                    | Action::Save
+                    | Action::Stop
+                    | Action::Respond => continue,
+                };
+                let Some(run_id) = app.selected().map(|r| r.run_id.clone()) else {
+                    continue;
+                };
+                match super::session::suspend_run_dir(&runs_dir.join(&run_id), &run_id, suspend)
+                    .await
+                {
+                    Ok(super::session::Suspended { processes, .. }) => {
+                        app.update(load_usage(&runs_dir).await?);
+                        last_load = std::time::Instant::now();
+                        let verb = match suspend {
+                            Suspend::Stop => "Paused",
+                            Suspend::Continue => "Resumed",
+                        };
+                        app.set_status(format!(
+                            "{verb} run {run_id} ({} process{})",
+                            processes.len(),
+                            if processes.len() == 1 { "" } else { "es" }
+                        ));
+                    }
+                    Err(e) => app.set_status(e.to_string()),
+                }
+            }
+        }
+
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "agents": app.rows().iter().map(|r| serde_json::json!({
+                        "run_id": r.run_id,
+                        "agent": r.agent,
+                        "tokens_in": r.tokens_in,
+                        "tokens_out": r.tokens_out,
+                        "cost_usd": r.cost_usd,
+                    })).collect::<Vec<_>>(),
+                }))?
+            );
+        }
+        Ok(())
+    }
+
+    async fn tui_review(
+        &self,
+        worktree: Option<&str>,
+        base: Option<&str>,
+        check: Option<&str>,
+    ) -> Result<()> {
+        let (dir, label) = self.review_target(worktree).await?;
+        let base = base.unwrap_or(&self.config.project.repository.main_branch);
+        let files = load_review_diff(&dir, base).await?;
+        if files.is_empty() {
+            println!("No changes in {} against {}", dir.display(), base);
+            return Ok(());
+        }
+        let parser = ai_session::output::OutputParser::with_configs(&self.config.parsers)?;
+        let title = format!("{label} vs {base}");
+        let load = async |files: Vec<FileDiff>| -> Result<ReviewApp> {
+            let app = ReviewApp::new(title.clone(), base, files);
+            Ok(match check {
+                Some(command) => app.with_diagnostics(run_check(&dir, command, &parser).await?),
+                None => app,
+            })
+        };
+
+        let mut app = load(files).await?;
+        let mut sent = 0usize;
+        {
+            let mut screen = Screen::enter()?;
+            loop {
+                screen.draw(|frame| app.render(frame))?;
+                let Some(key) = next_key(Duration::from_millis(250))? else {
+                    continue;
+                };
+                match app.handle_key(key) {
+                    Action::None
+                    | Action::Pause
+                    | Action::Resume
+                    | Action::Open
+                    | Action::Save
+                    | Action::Stop
+                    | Action::Respond => {}
+                    Action::Quit => break,
+                    Action::Reload => {
+                        app = load(load_review_diff(&dir, base).await?).await?;
+                        app.set_status("Reloaded");
+                    }
+                    Action::SendBack => {
+                        let Some(prompt) = app.follow_up_prompt() else {
+                            continue;
+                        };
+                        let count = app.hunks_with(Decision::Rejected).len();
+                        app.set_status(format!(
+                            "Sending {count} rejected hunk(s) to the agent — waiting for it to finish…"
+                        ));
+                        screen.draw(|frame| app.render(frame))?;
+
+                        let outcome = self.send_review_follow_up(&dir, &prompt).await;
+                        // Provider output may have landed on the alternate screen.
+                        screen.clear()?;
+                        match outcome {
+                            Ok(()) => {
+                                sent += count;
+                                app = load(load_review_diff(&dir, base).await?).await?;
+                                app.set_status(format!(
+                                    "Agent reworked {count} hunk(s); diff reloaded"
+                                ));
+                            }
+                            Err(e) => app.set_status(format!("Follow-up failed: {e}")),
+                        }
+                    }
+                }
+            }
+        }
+
+        let accepted = app.hunks_with(Decision::Accepted).len();
+        let rejected = app.hunks_with(Decision::Rejected).len();
+        if self.json_output {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "status": "success",
+                    "worktree": dir,
+                    "base": base,
+                    "accepted": accepted,
+                    "rejected": rejected,
+                    "sent_back": sent,
+                }))?
+            );
+        } else {
+            println!(
+                "{} reviewed {}: {} accepted, {} rejected, {} sent back to the agent",
+                "OK".bright_green().bold(),
+                label,
+                accepted,
+                rejected,
+                sent
+            );
+        }
+        Ok(())
+    }
+
+    /// Resolve `--worktree` (a path, a branch, or a worktree directory name)
+    /// to a directory and a label for the title bar.
+    async fn review_target(&self, worktree: Option<&str>) -> Result<(PathBuf, String)> {
+        if let Some(arg) = worktree {
+            let path = self.repo_path.join(arg);
+            if path.is_dir() {
+                return Ok((path, arg.to_string()));
+            }
+        }
+
+        let manager = self.worktree_manager()?;
+        // The first entry is the main working tree.
+        let agents: Vec<_> = manager
+            .list_worktrees()
+            .await?
+            .into_iter()
+            .skip(1)
+            .filter(|wt| !wt.is_bare)
+            .collect();
+        let label = |wt: &crate::git::shell::ShellWorktreeInfo| {
+            if wt.branch.is_empty() {
+                wt.path.display().to_string()
+            } else {
+                wt.branch.clone()
+            }
+        };
+
+        match worktree {
+            Some(arg) => agents
+                .iter()
+                .find(|wt| wt.branch == arg || wt.path.file_name().is_some_and(|name| name == arg))
+                .map(|wt| (wt.path.clone(), label(wt)))
+                .ok_or_else(|| anyhow!("No worktree matches '{}'", arg)),
+            None => match agents.as_slice() {
+                [] => Ok((self.repo_path.clone(), "working tree".to_string())),
+                [only] => Ok((only.path.clone(), label(only))),
+                many => Err(anyhow!(
+                    "Several agent worktrees exist; pick one with --worktree: {}",
+                    many.iter().map(label).collect::<Vec<_>>().join(", ")
+                )),
+            },
+        }
+    }
+
+    /// Run the reviewer's follow-up in the reviewed worktree.
+    async fn send_review_follow_up(&self, dir: &Path, prompt: &str) -> Result<()> {
+        let result = self
+            .ask_provider("review-follow-up", prompt, AskOptions::writable(dir))
+            .await?;
+        if !result.success {
+            return Err(anyhow!("agent did not complete the follow-up"));
+        }
+        Ok(())
+    }
+}
+
+/// Run the `--check` command in `dir` and parse the diagnostics it prints.
+/// A failing exit status is expected (that is when there is something to
+/// show); output no parser recognises yields no diagnostics.
+async fn run_check(
+    dir: &Path,
+    command: &str,
+    parser: &ai_session::output::OutputParser,
+) -> Result<Vec<ai_session::output::Diagnostic>> {
+    let output = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run check `{}`", command))?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(parser
+        .diagnostics(&text)
+        .map(|(_, found)| found)
+        .unwrap_or_default())
+}
+
+/// Changes in `dir` since it forked from `base`, uncommitted and untracked
+/// files included.
+async fn load_review_diff(dir: &Path, base: &str) -> Result<Vec<FileDiff>> {
+    let output = tokio::process::Command::new("git")
+        .args(["diff", "--no-color", "--no-ext-diff", "--merge-base", base])
+        .current_dir(dir)
+        .output()
+        .await
+        .context("Failed to run git diff")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git diff --merge-base {} failed: {}",
+            base,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+
+    let untracked = tokio::process::Command::new("git")
+        .args(["ls-files", "--others", "--exclude-standard", "-z"])
+        .current_dir(dir)
+        .output()
+        .await
+        .context("Failed to list untracked files")?;
+    for file in String::from_utf8_lossy(&untracked.stdout)
+        .split('\0')
+        .filter(|f| !f.is_empty())
+    {
+        // Exits 1 when the files differ, which they always do here.
+        let new_file = tokio::process::Command::new("git")
+            .args(["diff", "--no-color", "--no-index", "--", "/dev/null", file])
+            .current_dir(dir)
+            .output()
+            .await?;
+        text.push_str(&String::from_utf8_lossy(&new_file.stdout));
+    }
+    Ok(parse_unified(&text))
+}
+
+/// Usage rows for every live run, or for the latest run when none is live.
+/// Lines read from the end of each plain-text log.
+const MAX_LOG_LINES: usize = 5000;
+
+/// The last `max` lines of `content`.
+fn tail(content: &str, max: usize) -> &str {
+    match content.match_indices('\n').rev().nth(max) {
+        Some((i, _)) => &content[i + 1..],
+        None => content,
+    }
+}
+
+/// Run directories of live runs, or the latest run when none is live.
+async fn shown_runs(runs_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut runs: Vec<PathBuf> = Vec::new();
+    if let Ok(mut rd) = tokio::fs::read_dir(runs_dir).await {
+        while let Some(entry) = rd.next_entry().await? {
+            if entry.path().is_dir() {
+                runs.push(entry.path());
+            }
+        }
+    }
+    runs.sort();
+    let live: Vec<PathBuf> = runs
+        .iter()
+        .filter(|dir| crate::events::run_pid(dir).is_some())
+        .cloned()
+        .collect();
+    Ok(if live.is_empty() {
+        runs.pop().into_iter().collect()
+    } else {
+        live
+    })
+}
+
+async fn load_timeline(
+    run_dir: &Path,
+) -> (
+    crate::events::timeline::Timeline,
+    chrono::DateTime<chrono::Utc>,
+) {
+    let run_id = run_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let events = tokio::fs::read_to_string(run_dir.join("events.ndjson"))
+        .await
+        .unwrap_or_default();
+    let timeline = crate::events::timeline::Timeline::from_events(&run_id, &events);
+    let now = crate::events::timeline::clock(run_dir, &timeline);
+    (timeline, now)
+}
+
+async fn load_usage(runs_dir: &Path) -> Result<Vec<AgentUsage>> {
+    let mut rows = Vec::new();
+    for dir in shown_runs(runs_dir).await? {
+        let run_id = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let events = tokio::fs::read_to_string(dir.join("events.ndjson"))
+            .await
+            .unwrap_or_default();
+        let state = if crate::events::run_pid(&dir).is_none() {
+            RunState::Finished
+        } else if crate::events::SessionInfo::from_events(&run_id, &events).status == "paused" {
+            RunState::Paused
+        } else {
+            RunState::Running
+        };
+        rows.extend(crate::tui::usage::from_events(&run_id, &events, state));
+    }
+    Ok(rows)
+}
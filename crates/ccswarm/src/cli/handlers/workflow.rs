@@ -1,7 +1,85 @@
 use super::super::*;
+use crate::identity::AgentRole;
+use crate::session::bridge::{AISessionBridge, BridgeResult};
+
+/// Tools for one-shot provider calls that only read the repository.
+const READ_TOOLS: &[&str] = &["read", "grep", "glob"];
+/// Tools for one-shot provider calls that change files in their directory.
+const WRITE_TOOLS: &[&str] = &["read", "write", "edit", "bash", "grep", "glob"];
+
+/// Where and how a one-shot provider call runs (see [`CliRunner::ask_provider`]).
+pub(crate) struct AskOptions<'a> {
+    /// Directory the agent works in.
+    pub dir: &'a Path,
+    pub role: AgentRole,
+    pub tools: &'static [&'static str],
+    /// Retries after the first attempt.
+    pub retries: u32,
+}
+
+impl<'a> AskOptions<'a> {
+    /// A read-only call in `dir`, retried once.
+    pub fn read_only(dir: &'a Path) -> Self {
+        Self {
+            dir,
+            role: AgentRole::Frontend {
+                technologies: Vec::new(),
+                responsibilities: Vec::new(),
+                boundaries: Vec::new(),
+            },
+            tools: READ_TOOLS,
+            retries: 1,
+        }
+    }
+
+    /// A call that may edit files and run commands in `dir`, retried once.
+    pub fn writable(dir: &'a Path) -> Self {
+        Self {
+            tools: WRITE_TOOLS,
+            ..Self::read_only(dir)
+        }
+    }
+}
+
+/// One single-turn call to `provider` through `bridge` as `agent_id`.
+pub(crate) async fn ask_with(
+    bridge: &AISessionBridge,
+    provider: crate::providers::ProviderKind,
+    agent_id: &str,
+    prompt: &str,
+    options: AskOptions<'_>,
+) -> Result<BridgeResult> {
+    let identity = crate::identity::AgentIdentity {
+        agent_id: agent_id.to_string(),
+        specialization: options.role,
+        workspace_path: options.dir.to_path_buf(),
+        env_vars: std::collections::HashMap::new(),
+        session_id: uuid::Uuid::new_v4().to_string(),
+        parent_process_id: std::process::id().to_string(),
+        initialized_at: chrono::Utc::now(),
+    };
+    let exec = crate::session::bridge::MovementExecOptions {
+        provider: Some(provider),
+        tools: options.tools.iter().map(|t| t.to_string()).collect(),
+        continuation: crate::session::bridge::ContinuationPolicy::SingleTurn,
+        ..Default::default()
+    };
+    bridge
+        .execute_with_retry(
+            agent_id,
+            prompt,
+            &identity,
+            options.dir,
+            None,
+            options.retries,
+            1000,
+            &exec,
+        )
+        .await
+}
 
 /// Ask a yes/no question on stderr, return true for 'y'
-fn ask_yn(question: &str) -> bool {
+pub(super) fn ask_yn(question: &str) -> bool {
     eprint!("  {} [y/N] ", question);
     let _ = std::io::Write::flush(&mut std::io::stderr());
     let mut input = String::new();
@@ -181,10 +259,38 @@ impl CliRunner {
         create_pr: bool,
         approval_gate: Option<std::time::Duration>,
         dry_run: bool,
+        name: Option<&str>,
     ) -> Result<()> {
         if dry_run {
             return self.handle_pipeline_dry_run(task, flow).await;
         }
+        if let Some(name) = name {
+            // Bind the name before the run starts so a clash fails fast and
+            // session commands can address the run while it is still going.
+            let run_id = uuid::Uuid::new_v4().to_string();
+            let mut names = crate::session::names::SessionNames::load(&self.repo_path).await?;
+            names.bind(name, &run_id)?;
+            names.save(&self.repo_path).await?;
+            return self
+                .handle_pipeline_returning_reserved_id(
+                    &run_id,
+                    task,
+                    flow,
+                    output_format,
+                    timeout,
+                    verbose,
+                    output_file,
+                    isolate,
+                    budget,
+                    run_budget_tokens,
+                    model_override,
+                    auto_commit,
+                    create_pr,
+                    approval_gate,
+                )
+                .await
+                .map(|_run_id| ());
+        }
         self.handle_pipeline(
             task,
             flow,
@@ -435,12 +541,81 @@ impl CliRunner {
         Ok(run_id)
     }
 
+    /// Provider for calls outside a flow: `--provider`, then `CCSWARM_PROVIDER`,
+    /// then Claude.
+    pub(crate) fn provider_kind(&self) -> crate::providers::ProviderKind {
+        self.default_provider
+            .or_else(|| {
+                std::env::var("CCSWARM_PROVIDER")
+                    .ok()
+                    .as_deref()
+                    .and_then(crate::providers::ProviderKind::parse)
+            })
+            .unwrap_or(crate::providers::ProviderKind::Claude)
+    }
+
+    /// One single-turn provider call as `agent_id`, outside any flow.
+    pub(crate) async fn ask_provider(
+        &self,
+        agent_id: &str,
+        prompt: &str,
+        options: AskOptions<'_>,
+    ) -> Result<BridgeResult> {
+        ask_with(
+            &self.session_bridge()?,
+            self.provider_kind(),
+            agent_id,
+            prompt,
+            options,
+        )
+        .await
+    }
+
+    /// Session bridge rooted at `.ccswarm/sessions` that applies the project's
+    /// `redaction` rules and `parsers` to provider output, scopes `secrets` by
+    /// role, queues live calls behind the `scheduler` limits, answers from the
+    /// runner's scripted replay if it has one, and otherwise honors the
+    /// transcript record/replay and encryption key environment variables.
+    pub(crate) fn session_bridge(&self) -> Result<crate::session::bridge::AISessionBridge> {
+        let secrets = self.config.secrets.load(&self.repo_path)?;
+        let bridge = crate::session::bridge::AISessionBridge::new(
+            self.repo_path.join(".ccswarm").join("sessions"),
+        )
+        .with_redactor(
+            self.config
+                .redaction
+                .compile()?
+                .with_literals(secrets.values()),
+        )
+        .with_secrets(secrets)
+        .with_output_parser(ai_session::output::OutputParser::with_configs(
+            &self.config.parsers,
+        )?)
+        .with_transcripts(match &self.replay {
+            Some(replay) => crate::providers::transcript::TranscriptMode::Replay(replay.clone()),
+            None => crate::providers::transcript::TranscriptMode::from_env()?,
+        })
+        .with_shadows(&self.config.shadows);
+        let bridge = if self.config.scheduler.enabled {
+            bridge.with_scheduler(crate::providers::scheduler::Scheduler::new(
+                &self.repo_path.join(".ccswarm"),
+                self.config.scheduler.clone(),
+            ))
+        } else {
+            bridge
+        };
+        Ok(match ai_session::persistence::Keyring::from_env()? {
+            Some(keyring) => bridge.with_encryption(keyring),
+            None => bridge,
+        })
+    }
+
     /// Core pipeline execution logic without post-pipeline flow.
     /// Returns (run_id, PipelineOutput) on success.
     /// Separated from `handle_pipeline` to allow `run_post_pipeline_flow`
     /// to run fix pipelines without triggering recursive post-pipeline flows.
     #[allow(clippy::too_many_arguments)]
-    async fn execute_pipeline_core(
+    pub(crate) async fn execute_pipeline_core(
         &self,
         reserved_run_id: Option<&str>,
         task: &str,
@@ -471,13 +646,21 @@ impl CliRunner {
         let run_id = reserved_run_id
             .map(str::to_string)
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if let Err(e) = crate::run_ledger::record_pipeline_run(
+            &self.repo_path,
+            crate::run_id::RunId::current(),
+            &run_id,
+            flow,
+            task,
+        )
+        .await
+        {
+            warn!("Failed to record run {run_id} in the ledger: {e}");
+        }
 
         // Configure bridge for real provider CLI execution through ai-session.
         let mut engine = crate::workflow::flow::FlowEngine::new();
-        let bridge = crate::session::bridge::AISessionBridge::new(
-            self.repo_path.join(".ccswarm").join("sessions"),
-        );
-        engine.set_bridge(std::sync::Arc::new(bridge));
+        engine.set_bridge(std::sync::Arc::new(self.session_bridge()?));
         engine.set_working_dir(self.repo_path.clone());
         if let Some(b) = budget {
             engine.set_budget(b);
@@ -488,6 +671,21 @@ impl CliRunner {
         if let Some(provider) = self.default_provider {
             engine.set_default_provider(provider);
         }
+        engine.set_subagents(self.config.subagents.clone());
+        engine.set_quality(self.config.quality.clone());
+        engine.set_model_routing(self.config.model_routing.clone());
+        engine.set_supervisor(self.config.supervisor.clone());
+        engine.set_notifications(self.config.notifications.clone());
+        engine.set_agents(self.config.agents.clone());
+        engine.set_prompt_guard(self.config.prompt_guard.compile()?);
+        engine.set_output_sampling(
+            crate::session::sampling::ContextSampler::new(self.config.output_sampling.clone())
+                .with_log_store(&self.repo_path),
+        );
+        if let Some(doctrine) = crate::workflow::doctrine::load(&self.repo_path).await? {
+            engine.set_doctrine(doctrine);
+        }
+        engine.set_locales(crate::i18n::Locales::from_config(&self.config));
         if let Some(model) = model_override {
             engine.set_model_override(model);
         }
@@ -523,7 +721,19 @@ impl CliRunner {
         let recorder = crate::events::EventRecorder::new_in_runs_dir(runs_dir, &run_id)
             .await
             .context("Failed to initialize run event recorder")?;
+        if let Err(e) = recorder.write_pid().await {
+            warn!("Run {} cannot be paused: {}", run_id, e);
+        }
+        let recovery = crate::recovery::RecoveryJournal::for_repo(&self.repo_path);
+        let spawned = crate::recovery::Operation::SessionSpawned {
+            run_id: run_id.clone(),
+        };
+        recovery.begin(spawned.clone()).await?;
         engine.set_event_recorder(recorder);
+        engine.set_journal(std::sync::Arc::new(crate::journal::ActionJournal::for_run(
+            &self.repo_path,
+            &run_id,
+        )));
 
         // Set up real-time progress display
         let (progress_tx, mut progress_rx) =
@@ -560,7 +770,9 @@ impl CliRunner {
         });
 
         let runner = PipelineRunner::with_engine(engine);
-        let result = runner.execute(config).await?;
+        let result = runner.execute(config).await;
+        recovery.done(&spawned).await;
+        let result = result?;
 
         // Clean up progress display
         drop(progress_handle);
@@ -602,6 +814,7 @@ impl CliRunner {
         approval_gate: Option<std::time::Duration>,
     ) -> Result<()> {
         let repo = &self.repo_path;
+        let journal = crate::journal::ActionJournal::for_run(repo, run_id);
         eprintln!();
 
         // Step 1: Auto-detect and run tests, with auto-fix loop (max 3 retries)
@@ -619,59 +832,21 @@ impl CliRunner {
             // Route auto-fix through AISessionBridge so post-pipeline repair
             // gets the same ai-session parsing, persistence, retry handling,
             // and provider selection as normal flow stages.
-            let provider_kind = self
-                .default_provider
-                .or_else(|| {
-                    std::env::var("CCSWARM_PROVIDER")
-                        .ok()
-                        .as_deref()
-                        .and_then(crate::providers::ProviderKind::parse)
-                })
-                .unwrap_or(crate::providers::ProviderKind::Claude);
-            let bridge = crate::session::bridge::AISessionBridge::new(
-                repo.join(".ccswarm").join("sessions"),
-            );
-            let identity = crate::identity::AgentIdentity {
-                agent_id: "auto-fix".to_string(),
-                specialization: crate::identity::AgentRole::Frontend {
-                    technologies: Vec::new(),
-                    responsibilities: Vec::new(),
-                    boundaries: Vec::new(),
-                },
-                workspace_path: repo.clone(),
-                env_vars: std::collections::HashMap::new(),
-                session_id: uuid::Uuid::new_v4().to_string(),
-                parent_process_id: std::process::id().to_string(),
-                initialized_at: chrono::Utc::now(),
-            };
-            let options = crate::session::bridge::MovementExecOptions {
-                provider: Some(provider_kind),
-                tools: vec![
-                    "read".to_string(),
-                    "write".to_string(),
-                    "edit".to_string(),
-                    "bash".to_string(),
-                    "grep".to_string(),
-                    "glob".to_string(),
-                ],
-                model: None,
-                system_prompt: None,
-                max_budget: None,
-                worktree_name: None,
-                session_id: None,
-                continuation: crate::session::bridge::ContinuationPolicy::SingleTurn,
-                rate_limit_fallbacks: Vec::new(),
-            };
-            let fix_output = bridge
-                .execute_with_retry(
-                    "auto-fix",
-                "Fix the failing tests. Read the test output, identify the issue, and fix the code.",
-                    &identity,
+            journal
+                .record_or_warn(
+                    crate::journal::JournalAction::AutoFix,
+                    &format!("attempt {fix_attempts}"),
                     repo,
-                    None,
-                    0,
-                    1000,
-                    &options,
+                )
+                .await;
+            let fix_output = self
+                .ask_provider(
+                    "auto-fix",
+                    "Fix the failing tests. Read the test output, identify the issue, and fix the code.",
+                    AskOptions {
+                        retries: 0,
+                        ..AskOptions::writable(repo)
+                    },
                 )
                 .await;
 
@@ -709,7 +884,9 @@ impl CliRunner {
         let committed = if auto_commit && test_passed {
             match approval_gate {
                 Some(timeout) => match self.gate_commit(run_id, task, create_pr, timeout).await? {
-                    crate::hitl::GateOutcome::Approved => self.do_commit(repo, task).await,
+                    crate::hitl::GateOutcome::Approved => {
+                        self.do_commit(repo, task, &journal).await
+                    }
                     crate::hitl::GateOutcome::Rejected(reason) => {
                         return Err(anyhow!(
                             "commit gate rejected{} — changes left uncommitted in the working tree",
@@ -724,11 +901,11 @@ impl CliRunner {
                         ));
                     }
                 },
-                None => self.do_commit(repo, task).await,
+                None => self.do_commit(repo, task, &journal).await,
             }
         } else if test_passed {
             if ask_yn("Commit changes?") {
-                self.do_commit(repo, task).await
+                self.do_commit(repo, task, &journal).await
             } else {
                 false
             }
@@ -736,8 +913,9 @@ impl CliRunner {
             false
         };
 
-        // Step 3: PR (auto or ask)
-        if committed && (create_pr || ask_yn("Create pull request?")) {
+        // Step 3: PR (auto or ask). Unattended runs only open one when told
+        // to; asking would block a drain on stdin.
+        if committed && (create_pr || (!auto_commit && ask_yn("Create pull request?"))) {
             self.do_create_pr(repo, task, flow, result, run_id).await;
         }
 
@@ -803,6 +981,15 @@ impl CliRunner {
             "    reject:  ccswarm approve commit --id {} --reject --reason \"...\"",
             run_id
         );
+        crate::notify::send(
+            &self.config.notifications,
+            crate::notify::Notification::new(
+                crate::notify::NotifyEvent::ApprovalPending,
+                "ccswarm: commit approval pending",
+                format!("ccswarm approve commit --id {run_id}"),
+            ),
+        )
+        .await;
 
         let outcome = store
             .wait_for_decision(
@@ -1017,7 +1204,16 @@ impl CliRunner {
     /// Untracked secret-like files (.env, *.key, credentials*) or anything the user has
     /// gitignored are left untouched. If the task genuinely needed to add new files, the
     /// user can do it manually in interactive mode.
-    async fn do_commit(&self, repo: &std::path::Path, task: &str) -> bool {
+    async fn do_commit(
+        &self,
+        repo: &std::path::Path,
+        task: &str,
+        journal: &crate::journal::ActionJournal,
+    ) -> bool {
+        // Stash point for `ccswarm task undo`, taken before anything is staged.
+        journal
+            .record_or_warn(crate::journal::JournalAction::Commit, "commit", repo)
+            .await;
         // Stage modifications + deletions of tracked files only.
         let _ = tokio::process::Command::new("git")
             .args(["add", "-u"])
@@ -10,6 +10,8 @@ mod error_help;
 mod output;
 mod progress;
 mod quickstart_simple;
+#[cfg(test)]
+mod scenario;
 
 pub(crate) mod handlers;
 
@@ -40,7 +42,8 @@ use crate::config::CcswarmConfig;
       ccswarm queue add \"...\"; ccswarm queue drain  # batch\n\n\
     Inspection:\n  \
       ccswarm doctor           Environment and provider CLI checks\n  \
-      ccswarm runs list        Past pipeline runs\n  \
+      ccswarm run list         Past pipeline runs\n  \
+      ccswarm runs list        Past ccswarm invocations and what they ran\n  \
       ccswarm tail             Follow the current run's event stream\n  \
       ccswarm cost <run-id>    Duration + token breakdown"
 )]
@@ -67,7 +70,7 @@ pub struct Cli {
     pub fix: bool,
 
     /// Default provider for stages that don't pin one in flow YAML
-    /// (claude | codex | copilot). Overrides CCSWARM_PROVIDER.
+    /// (claude | codex | gemini | aider | copilot | mock). Overrides CCSWARM_PROVIDER.
     #[arg(long, global = true)]
     pub provider: Option<String>,
 
@@ -111,6 +114,7 @@ pub enum Commands {
         Examples:\n  \
           ccswarm task execute \"Fix login bug\"\n  \
           ccswarm task list --branches\n  \
+          ccswarm task show q-1a2b3c4d\n  \
           ccswarm task merge <id>"
     )]
     Task {
@@ -125,6 +129,12 @@ pub enum Commands {
         all: bool,
     },
 
+    /// Inspect one agent's persistent state
+    Agent {
+        #[command(subcommand)]
+        action: AgentAction,
+    },
+
     /// Generate .claude/agents/*.md from facets or validate existing definitions
     #[command(name = "agent-gen")]
     AgentGen {
@@ -177,7 +187,8 @@ pub enum Commands {
         executes each stage sequentially, passing context between steps.\n\n\
         Examples:\n  \
           ccswarm pipeline --task \"Fix README typo\" --flow default\n  \
-          ccswarm pipeline --task \"Add tests\" --output-format json --verbose"
+          ccswarm pipeline --task \"Add tests\" --output-format json --verbose\n  \
+          ccswarm pipeline --task \"Add pagination\" --name api-work"
     )]
     Pipeline {
         /// Task description to execute
@@ -239,6 +250,11 @@ pub enum Commands {
         /// for the round-trip.
         #[arg(long)]
         dry_run: bool,
+
+        /// Name the run so session commands accept it in place of the run id
+        /// (claims a name reserved with `ccswarm session create --name`)
+        #[arg(long)]
+        name: Option<String>,
     },
 
     /// Check system health and diagnose issues
@@ -282,6 +298,29 @@ pub enum Commands {
         with_tests: bool,
     },
 
+    /// Try ccswarm end to end on a sample project, no API key needed
+    #[command(
+        long_about = "Scaffold a sample project and watch a simulated swarm work on it.\n\n\
+        Creates a git repository around greet.sh, a tiny shell app with tests and\n\
+        quality checks, queues three dependent tasks, and drains them with the\n\
+        mock provider, whose canned replies are in .ccswarm/mock.yaml. Each task\n\
+        runs the default flow and ends in a commit. Then `status` and `quality\n\
+        check` run on the result and the next commands to try are listed.\n\n\
+        --no-run only scaffolds and queues, for draining yourself.\n\n\
+        Examples:\n  \
+          ccswarm demo\n  \
+          ccswarm demo /tmp/greet --no-run"
+    )]
+    Demo {
+        /// Directory to create (must be empty or missing)
+        #[arg(default_value = "ccswarm-demo")]
+        path: PathBuf,
+
+        /// Scaffold and queue the backlog without draining it
+        #[arg(long)]
+        no_run: bool,
+    },
+
     /// Manage workflow flows (list, eject, inspect)
     #[command(
         name = "flow",
@@ -313,11 +352,12 @@ pub enum Commands {
         action: RepertoireAction,
     },
 
-    /// Experimental / research commands (sangha, extend, evolution, search)
+    /// Experimental / research commands (sangha, extend, evolution, search, ab)
     #[command(long_about = "Experimental features grouped under `lab`:\n  \
           ccswarm lab sangha propose ...      Collective voting on proposals\n  \
           ccswarm lab extend propose ...      Agent self-extension tracking\n  \
           ccswarm lab evolution report        Agent performance analytics\n  \
+          ccswarm lab ab \"...\" --a/--b ...   Compare two run configurations\n  \
           ccswarm lab search docs \"...\"       Ripgrep over docs/ and source\n\n\
         These sit below the primary flow (task / pipeline / queue / runs). They exist for \
         research and may change without notice.")]
@@ -348,6 +388,9 @@ pub enum Commands {
         Examples:\n  \
           ccswarm approve plan --id run-abc123\n  \
           ccswarm approve deploy --id task-456 --reject --reason \"needs more tests\"\n  \
+          ccswarm approve resume --id q-1a2b3c4d\n  \
+          ccswarm approve show cmd-3f2a9c1d4e5b\n  \
+          ccswarm approve command --id cmd-3f2a9c1d4e5b --reject --reason \"not on main\"\n  \
           ccswarm approve list --status pending")]
     Approve {
         #[command(subcommand)]
@@ -359,10 +402,18 @@ pub enum Commands {
         long_about = "Browse and manage pipeline sessions stored in .ccswarm/runs/.\n\n\
         Sessions are created automatically when running pipelines. Each session\n\
         records events as NDJSON and produces a summary on completion.\n\n\
+        A session can carry a name and aliases (kept in\n\
+        .ccswarm/sessions/names.json); any of them works wherever a session id\n\
+        is accepted. Names and aliases are unique across all sessions.\n\n\
         Examples:\n  \
           ccswarm session list\n  \
           ccswarm session list --all\n  \
-          ccswarm session view <session-id>"
+          ccswarm session view <session-id>\n  \
+          ccswarm session watch <session-id>\n  \
+          ccswarm session create --agent backend --name api-work\n  \
+          ccswarm pipeline --name api-work --task \"Add pagination\"\n  \
+          ccswarm session rename api-work payments-api\n  \
+          ccswarm session alias payments-api pay"
     )]
     Session {
         #[command(subcommand)]
@@ -383,6 +434,39 @@ pub enum Commands {
         action: RunAction,
     },
 
+    /// Past ccswarm invocations, each tying together its pipeline runs
+    #[command(
+        long_about = "Every ccswarm invocation gets a run ID (exported to child\n\
+        processes as CCSWARM_RUN_ID and attached to log lines, pipeline run\n\
+        summaries, queue attempts and provider transcripts). Invocations that\n\
+        start pipeline runs are recorded in .ccswarm/ledger/.\n\n\
+        Examples:\n  \
+          ccswarm runs list\n  \
+          ccswarm runs show 20261017T091502\n  \
+          ccswarm runs show            # the most recent"
+    )]
+    Runs {
+        #[command(subcommand)]
+        action: RunsAction,
+    },
+
+    /// Which secrets each agent role receives
+    #[command(
+        long_about = "Secrets are granted per role under `secrets.grants` in the config.\n\
+        Provider calls see only their role's grants; agent worktrees get them\n\
+        as a scoped .env, written by `worktree bootstrap` or `secrets sync` and\n\
+        removed by `secrets revoke` and when the daemon stops. Values are\n\
+        never printed.\n\n\
+        Examples:\n  \
+          ccswarm secrets list\n  \
+          ccswarm secrets sync             # write or rotate every worktree's .env\n  \
+          ccswarm secrets revoke --agent backend"
+    )]
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+
     /// List all registered facets (personas, policies, knowledge)
     #[command(
         long_about = "Show built-in and project-local facets available to flows.\n\n\
@@ -407,14 +491,44 @@ pub enum Commands {
         Examples:\n  \
           ccswarm queue add \"Add login form\"\n  \
           ccswarm queue add --from-issue 42\n  \
+          ccswarm queue add \"Migrate auth to OAuth\" --time-budget 1800 --on-budget ask\n  \
+          ccswarm queue add \"Sign off on the API contract\" --assign alice\n  \
           ccswarm queue list\n  \
-          ccswarm queue drain --timeout 600"
+          ccswarm queue drain --timeout 600\n  \
+          ccswarm queue drain --max-task-cost 2.50"
     )]
     Queue {
         #[command(subcommand)]
         action: QueueAction,
     },
 
+    /// Share queued tasks with other ccswarm instances (experimental)
+    #[command(
+        long_about = "Several ccswarm instances, on one machine or many, work through one\n\
+        task pool. `publish` moves pending tasks from .ccswarm/queue.yaml into the\n\
+        pool; `work` claims tasks one at a time under a lease it keeps renewing, so\n\
+        a task whose worker dies goes back to the pool when the lease runs out;\n\
+        `status` shows every node and task and marks published tasks finished in\n\
+        the local queue.\n\n\
+        The pool is a directory every node can reach (dir:<path>) or Redis\n\
+        (redis://host, in builds with the cluster-redis feature). Set it with\n\
+        --pool or cluster.pool in ccswarm.json.\n\n\
+        Examples:\n  \
+          ccswarm cluster --pool dir:/mnt/shared/pool publish --all\n  \
+          ccswarm cluster --pool redis://queue.internal work --node build-1\n  \
+          ccswarm cluster status"
+    )]
+    Cluster {
+        /// Task pool: dir:<path> or redis://host (default: cluster.pool)
+        #[arg(long, global = true)]
+        pool: Option<String>,
+        /// Node name to report as (default: cluster.node or <hostname>-<pid>)
+        #[arg(long, global = true)]
+        node: Option<String>,
+        #[command(subcommand)]
+        action: ClusterAction,
+    },
+
     /// Fully autonomous mode — no y/n prompts, auto-commit, auto-PR
     #[command(
         long_about = "Self-driving loop: pull tasks → pipeline → auto-fix → auto-commit → auto-PR → repeat.\n\n\
@@ -441,7 +555,8 @@ pub enum Commands {
         #[arg(long)]
         watch: bool,
 
-        /// Poll interval in seconds when --watch is set
+        /// Longest wait in seconds between queue checks when --watch is set;
+        /// changes to the queue wake the loop sooner (`coordination.watch`)
         #[arg(long, default_value_t = 30)]
         poll_secs: u64,
 
@@ -505,16 +620,188 @@ pub enum Commands {
         timeout: u64,
     },
 
+    /// Emergency stop: suspend every session and freeze the queue
+    #[command(
+        long_about = "Stop everything now, for when an agent starts doing something dangerous.\n\n\
+        Writes .ccswarm/halt.json first, so the queue hands out no more tasks and\n\
+        no new provider request starts. Then every running session is stopped\n\
+        (SIGSTOP) and the processes it had running — provider CLIs, gate\n\
+        commands — are killed, so in-flight requests do not finish. Nothing\n\
+        moves again until `ccswarm resume-all`.\n\n\
+        Examples:\n  \
+          ccswarm halt\n  \
+          ccswarm halt --reason \"backend agent is rewriting migrations\""
+    )]
+    Halt {
+        /// Why, kept with the halt
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    /// Lift a halt: resume the sessions it stopped and unfreeze the queue
+    ResumeAll,
+
+    /// Undo what a crashed ccswarm left half-done
+    #[command(
+        long_about = "Compensate orchestrator operations a crashed ccswarm began and never\n\
+        finished, from the journal in .ccswarm/recovery.wal: task claims whose run\n\
+        never finished go back to the queue, runs whose process died are marked\n\
+        interrupted, half-created worktrees are removed and merges left in progress\n\
+        are aborted. Operations of processes still running are left alone.\n\n\
+        `queue drain`, `auto` and the daemon do this when they start.\n\n\
+        Examples:\n  \
+          ccswarm recover --dry-run\n  \
+          ccswarm recover"
+    )]
+    Recover {
+        /// List abandoned operations without touching anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Benchmark ccswarm's own overhead on a synthetic workload
+    #[command(
+        long_about = "Measure ccswarm itself, so performance regressions show up as numbers.\n\n\
+        Drains --tasks synthetic tasks (features, fixes, docs, refactors, with\n\
+        flows of one to three stages) in a scratch repository, answering every\n\
+        provider call with a canned reply. Reports orchestration overhead per\n\
+        task, scheduling latency between tasks, session I/O throughput (output\n\
+        streamed out of an ai-session shell) and memory use.\n\n\
+        --output saves the report as JSON; --baseline compares against a saved\n\
+        report and fails when a metric is more than --tolerance percent worse.\n\n\
+        Examples:\n  \
+          ccswarm bench\n  \
+          ccswarm bench --tasks 100 --output bench.json\n  \
+          ccswarm bench --baseline bench.json --tolerance 15"
+    )]
+    Bench {
+        /// Tasks in the synthetic workload
+        #[arg(long, default_value_t = 20)]
+        tasks: usize,
+        /// Output streamed through a session, in MiB (0 skips it)
+        #[arg(long, default_value_t = 4)]
+        io_mib: usize,
+        /// Save the report as JSON
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Saved report to compare with
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+        /// Percent a metric may be worse than the baseline
+        #[arg(long, default_value_t = 25.0)]
+        tolerance: f64,
+    },
+
+    /// Find the commit that caused a regression with git bisect
+    #[command(
+        long_about = "Bisect between a known good commit and a bad one (HEAD by default).\n\n\
+        The QA agent's session builds and tests each commit git picks in a scratch\n\
+        worktree under .ccswarm/bisect/: exit 0 is good, 125 skips the commit and\n\
+        anything else is bad. Without --command the project's build and test\n\
+        quality checks are used. The first bad commit is pinned on the agent whose\n\
+        branch carries it (or the one delegation picks for what it touched), which\n\
+        gets a high-priority fix task in the queue. The report is kept in\n\
+        .ccswarm/bisect/<id>.json.\n\n\
+        Queue tasks added with `queue add --regression-since <good>` are bisected\n\
+        this way when the queue is drained.\n\n\
+        Examples:\n  \
+          ccswarm bisect --good v0.9.0\n  \
+          ccswarm bisect --good HEAD~20 --command 'cargo test -p api'\n  \
+          ccswarm --json bisect --good v0.9.0 --no-fix-task"
+    )]
+    Bisect {
+        /// Last commit known to work
+        #[arg(long)]
+        good: String,
+        /// First commit known to be broken
+        #[arg(long, default_value = "HEAD")]
+        bad: String,
+        /// Build and test command run at each step
+        #[arg(long)]
+        command: Option<String>,
+        /// Agent whose session runs the steps
+        #[arg(long, default_value = crate::workflow::bisect::DEFAULT_AGENT)]
+        agent: String,
+        /// Give up after this many steps
+        #[arg(long, default_value_t = crate::workflow::bisect::DEFAULT_MAX_STEPS)]
+        max_steps: usize,
+        /// Report the culprit without queuing a fix task
+        #[arg(long)]
+        no_fix_task: bool,
+    },
+
+    /// Show the latest run and agent self-ratings
+    #[command(
+        long_about = "Summarize the most recent pipeline run and the confidence agents\n\
+        reported for their own work (self-ratings recorded in events.ndjson).\n\n\
+        --detailed lists recent ratings with the areas agents were unsure about,\n\
+        and the low-confidence areas team_leader stages route through review.\n\n\
+        Examples:\n  \
+          ccswarm status\n  \
+          ccswarm status --detailed\n  \
+          ccswarm --json status --detailed"
+    )]
+    Status {
+        /// Show recent ratings and low-confidence areas
+        #[arg(short, long)]
+        detailed: bool,
+    },
+
+    /// Route tasks to specialist agents and measure the routing
+    #[command(
+        long_about = "Pick the specialist agent (frontend, backend, devops, qa) for a task \
+        with a delegation strategy, or score a strategy against labeled tasks.\n\n\
+        Examples:\n  \
+          ccswarm delegate analyze \"Add a REST endpoint for invoices\"\n  \
+          ccswarm delegate eval --dataset default\n  \
+          ccswarm delegate eval --dataset samples.yaml --strategy keyword --verbose"
+    )]
+    Delegate {
+        #[command(subcommand)]
+        action: DelegateAction,
+    },
+
+    /// Talk to the master orchestrator about project state
+    #[command(long_about = "Open a conversation with the master orchestrator.\n\n\
+        Ask about the queue, runs and agents (\"why is the backend task stuck?\"); each\n\
+        answer is grounded in .ccswarm/queue.yaml, recent run summaries and the\n\
+        proactive review's findings. The master can propose queue changes: new\n\
+        tasks, priorities, the flow a pending task runs through, releasing a stuck\n\
+        task or holding one for a human. They are applied once you confirm and\n\
+        are journaled to .ccswarm/decisions.ndjson.\n\n\
+        The conversation is kept in .ccswarm/master/chat.ndjson and continues in\n\
+        the next chat; /clear archives it and starts over.\n\n\
+        Examples:\n  \
+          ccswarm chat\n  \
+          ccswarm chat --message \"what is blocking the release?\"\n  \
+          ccswarm chat --message \"requeue the stuck migration task\" --yes")]
+    Chat {
+        /// Send one message and exit instead of opening a prompt
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// Apply proposed changes without asking
+        #[arg(long)]
+        yes: bool,
+    },
+
     /// Show token / duration breakdown for a past run
     #[command(
         long_about = "Aggregate per-stage and per-agent metrics from events.ndjson.\n\n\
+        With --estimates, compare the cost estimated for each queued task before\n\
+        it ran with what its run actually used.\n\n\
         Examples:\n  \
           ccswarm cost\n  \
-          ccswarm cost <run-id>"
+          ccswarm cost <run-id>\n  \
+          ccswarm cost --estimates"
     )]
     Cost {
         /// Run ID (default: most recent)
+        #[arg(conflicts_with = "estimates")]
         run_id: Option<String>,
+        /// Reconcile pre-run cost estimates with actual usage
+        #[arg(long)]
+        estimates: bool,
     },
 
     /// Tail a pipeline run's event stream (live when still running)
@@ -537,156 +824,804 @@ pub enum Commands {
     /// Create a new project and run pipeline in one command
     #[command(
         long_about = "Scaffold a new project: create directory, git init, run pipeline.\n\n\
+        --template starts from a named template (todo-app, rest-api, cli-tool,\n\
+        static-site, or any YAML in $CCSWARM_HOME/templates/scaffold/ or the\n\
+        project's .ccswarm/templates/scaffold/) that sets\n\
+        the stack, the initial files and a per-agent task breakdown.\n\n\
+        --auto-deploy deploys the finished project to the template's `deploy:`\n\
+        target (docker-compose, fly, vercel or rsync): the DevOps agent prepares\n\
+        it, ccswarm deploys, checks the health URL and rolls back on failure.\n\n\
         Examples:\n  \
           ccswarm scaffold --dir /tmp/myapp --task \"Create a todo app\"\n  \
-          ccswarm scaffold --dir ./myapp --task \"Build a REST API\" --flow quick"
+          ccswarm scaffold --dir ./myapp --task \"Build a REST API\" --flow quick\n  \
+          ccswarm scaffold --dir ./todo --template todo-app\n  \
+          ccswarm scaffold --dir ./api --template rest-api --task \"Inventory API for a bike shop\"\n  \
+          ccswarm scaffold --dir ./todo --template todo-app --auto-deploy"
     )]
     Scaffold {
         /// Directory to create
         #[arg(short, long)]
         dir: PathBuf,
 
-        /// Task description
-        #[arg(short, long)]
-        task: String,
+        /// Task description (default: the template's task)
+        #[arg(short, long, required_unless_present = "template")]
+        task: Option<String>,
 
-        /// Flow to use (default: "default")
-        #[arg(short, long, default_value = "default")]
-        flow: String,
+        /// Project template to start from
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Flow to use (default: the template's flow, else "default")
+        #[arg(short, long)]
+        flow: Option<String>,
 
         /// Timeout in seconds
         #[arg(long, default_value = "600")]
         timeout: u64,
+
+        /// Deploy to the template's deploy target once the pipeline succeeds
+        #[arg(long, requires = "template")]
+        auto_deploy: bool,
+    },
+
+    /// Manage CLAUDE.md and task templates
+    #[command(long_about = "List, preview, customize and validate templates.\n\n\
+        CLAUDE.md templates hold agent instructions (backend, frontend, qa, ...);\n\
+        their {{variable}} placeholders are filled from the project: project_name,\n\
+        main_branch, agents and language (detected from the manifests), or --var.\n\
+        Task templates are the scaffold templates `scaffold --template` starts\n\
+        from.\n\n\
+        `copy` writes a template to .ccswarm/templates/claude-md/ or\n\
+        .ccswarm/templates/scaffold/, where it replaces the builtin of the same\n\
+        name; `validate` checks those copies (and $CCSWARM_HOME's scaffold\n\
+        templates).\n\n\
+        Examples:\n  \
+          ccswarm template list --kind claude-md\n  \
+          ccswarm template show framework-specialist --var framework_name=axum\n  \
+          ccswarm template copy rest-api --name shop-api\n  \
+          ccswarm template validate --strict")]
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+
+    /// Seed agent knowledge and distill the project doctrine
+    #[command(long_about = "Manage the project knowledge agents start with.\n\n\
+        `seed` scans README, CONTRIBUTING, ADRs and package manifests, summarizes\n\
+        them per persona with the provider, and writes knowledge facets\n\
+        (.ccswarm/facets/knowledge/project-<persona>.yaml) that stages use when\n\
+        they don't pin their own `knowledge:`.\n\n\
+        `distill` mines completed queue tasks, approval and vote reasons, and\n\
+        settled Sangha proposals since its last run, drafts an updated DOCTRINE.md\n\
+        and submits it as a `doctrine` Sangha proposal. Once that proposal has\n\
+        more approving than rejecting votes, the next run adopts the draft; every\n\
+        stage prompt starts with the adopted DOCTRINE.md. To run it periodically,\n\
+        add a daemon subsystem: {\"name\": \"doctrine\", \"args\": [\"context\", \"distill\", \"--watch\"]}.\n\n\
+        `search` ranks passages of persisted agent sessions by meaning, using\n\
+        local embeddings or the provider set in `context_search`; the index in\n\
+        .ccswarm/sessions/search-index.json only embeds what changed.\n\n\
+        Examples:\n  \
+          ccswarm context search \"where did we decide on the auth token format\"\n  \
+          ccswarm context seed\n  \
+          ccswarm context seed --personas coder,reviewer\n  \
+          ccswarm context seed --no-summarize --dry-run\n  \
+          ccswarm context distill --dry-run\n  \
+          ccswarm lab sangha vote <proposal-id> --approve && ccswarm context distill")]
+    Context {
+        #[command(subcommand)]
+        action: ContextAction,
     },
-}
 
-#[derive(Subcommand)]
-pub enum RepertoireAction {
-    /// Install a flow package from a Git repository
-    Add {
-        /// Git URL of the flow package
-        url: String,
+    /// Export or import the swarm setup as one archive
+    #[command(
+        long_about = "Move a project's swarm setup between machines or repositories.\n\n\
+        `export` writes a tar archive with ccswarm.json (agents, humans, quality\n\
+        gates and the other settings), DOCTRINE.md, the project flows and facets\n\
+        in .ccswarm/flows and .ccswarm/facets, and your scaffold templates.\n\
+        Credential files are skipped and secret-looking values (passwords, tokens,\n\
+        API keys) are blanked; the bundle's manifest lists what was left out.\n\
+        Queue, runs, logs and other state are never included.\n\n\
+        `import` unpacks a bundle into the current repository, pointing the config\n\
+        at it. Files that already exist with different content are reported and\n\
+        left alone unless --force is given.\n\n\
+        Examples:\n  \
+          ccswarm bundle export\n  \
+          ccswarm bundle export --output team-swarm.tar\n  \
+          ccswarm bundle import team-swarm.tar --dry-run\n  \
+          ccswarm bundle import team-swarm.tar --force"
+    )]
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
     },
 
-    /// List installed flow packages
-    List,
+    /// Delegate tasks to people and work through their inbox
+    #[command(
+        long_about = "Register people as participants alongside AI agents.\n\n\
+        A registered human receives delegated tasks through the same task queue\n\
+        and coordination bus as agents. Each delegation lands in their inbox\n\
+        (.ccswarm/coordination/inbox) and runs their `--notify-command`, if any,\n\
+        with CCSWARM_HUMAN, CCSWARM_TASK_ID and CCSWARM_TASK set. Accepting,\n\
+        commenting, completing and declining post progress, comments and results\n\
+        back on the bus. `queue add --assign <name>` holds a queue task for a\n\
+        human; tasks that depend on it run once it is completed. `ccswarm tui\n\
+        inbox` does the same interactively.\n\n\
+        Examples:\n  \
+          ccswarm human register alice --capabilities design-review,legal\n  \
+          ccswarm human register bob --notify-command 'notify-send \"$CCSWARM_TASK\"'\n  \
+          ccswarm human delegate alice \"Review the pricing page copy\"\n  \
+          ccswarm human inbox --human alice\n  \
+          ccswarm human complete <id> --summary \"Approved with two edits\""
+    )]
+    Human {
+        #[command(subcommand)]
+        action: HumanAction,
+    },
+
+    /// Interactive terminal views
+    #[command(long_about = "Open an interactive terminal view.\n\n\
+        `review` shows an agent worktree's changes against the base branch side\n\
+        by side. Accept or reject each hunk (with an optional note), then press\n\
+        `s` to send the rejected hunks back to the agent as follow-up\n\
+        instructions; the diff reloads once the agent is done. With `--check`,\n\
+        the command's diagnostics (eslint, tsc, go vet, gradle, terraform, or a\n\
+        configured parser) annotate the diff and go along with rejected hunks.\n\n\
+        `usage` shows live token and cost totals per agent for running\n\
+        pipelines (or the latest run), with a sparkline of tokens per stage.\n\
+        Meters turn yellow at 75% and red at 90% of the per-agent budget; `p`\n\
+        pauses the selected agent's run and `r` resumes it.\n\n\
+        `docs` lists the shared documents in .ccswarm/docs (API contracts,\n\
+        architecture notes) and edits them line by line alongside agents.\n\
+        Concurrent edits merge without conflicts; the view picks up agents'\n\
+        changes every second.\n\n\
+        `logs` merges run events, agent logs in .ccswarm/logs and the daemon\n\
+        log into one stream that follows new lines as they arrive. Filter by\n\
+        level (`l`) and agent (`a`), search with a regex (`/`, then `n`/`N`),\n\
+        pause or resume following (`f`) and jump between errors (`e`/`E`),\n\
+        including compiler and linter errors found by the output parsers.\n\n\
+        `inbox` lists tasks delegated to humans. Accept (`a`), complete (`c`),\n\
+        decline (`d`) or comment on (`m`) the selected task; results go back to\n\
+        the delegating agent over the coordination bus.\n\n\
+        `timeline` lays a session's stages, provider calls, gate commands,\n\
+        approval waits and pauses out as bars on one clock, marks detected\n\
+        errors on their stage and sums where the time went. It follows the\n\
+        session while it runs.\n\n\
+        Examples:\n  \
+          ccswarm tui review\n  \
+          ccswarm tui review --worktree ccswarm-3f2a91c0\n  \
+          ccswarm tui review --worktree ../feature-wt --base develop\n  \
+          ccswarm tui review --check \"npx tsc --noEmit\"\n  \
+          ccswarm tui usage --budget-tokens 200000 --budget-usd 5\n  \
+          ccswarm tui docs api-contract\n  \
+          ccswarm tui logs --level warn --agent backend --search \"timeout|refused\"\n  \
+          ccswarm tui inbox --human alice\n  \
+          ccswarm tui timeline my-session\n  \
+          ccswarm tui --theme high-contrast inbox\n\n\
+        Colors, pane layout, the view opened by a bare `ccswarm tui` and extra\n\
+        keys come from ~/.config/ccswarm/tui.toml (or $CCSWARM_TUI_CONFIG);\n\
+        edits apply while the TUI runs:\n\n  \
+          theme = \"light\"          # default | high-contrast | light\n  \
+          default_view = \"inbox\"\n  \
+          [colors]                 # header_fg header_bg text muted accent info\n  \
+          warn = \"#b58900\"         # special ok warn error prompt_fg\n  \
+          [layout]\n  \
+          panes = \"stacked\"        # side-by-side | stacked\n  \
+          list_percent = 40\n  \
+          [keys]                   # quit down up top bottom page_down page_up reload\n  \
+          quit = [\"x\", \"ctrl-q\"]")]
+    Tui {
+        /// Color theme for this session: default, high-contrast or light
+        /// (overrides `theme` in ~/.config/ccswarm/tui.toml)
+        #[arg(long, global = true)]
+        theme: Option<String>,
 
-    /// Remove an installed flow package
-    Remove {
-        /// Package name to remove
-        name: String,
+        #[command(subcommand)]
+        view: Option<TuiView>,
+    },
+
+    /// Run format, lint, build, test and security checks
+    #[command(long_about = "Run the project's quality checks.\n\n\
+        `check` runs every check at once, up to `--jobs` at a time, with a live\n\
+        progress table. Cargo checks share one target directory so crates are\n\
+        compiled once rather than per check. Cargo projects get fmt, clippy,\n\
+        build, test and audit; other projects list their checks under\n\
+        quality.checks in ccswarm.json. Results are saved for `quality status`.\n\n\
+        `test` runs only the tests affected by the changes since `--base`\n\
+        (cargo packages and their dependents, jest related tests, matching\n\
+        pytest files); `--full` runs the whole suite.\n\n\
+        `baseline` records the lint diagnostics and failing tests the checks\n\
+        report now; after that a check fails only on issues the baseline\n\
+        doesn't know. `--ratchet` also lowers the issues each check may report\n\
+        by `--step` every `--every-days` days.\n\n\
+        Examples:\n  \
+          ccswarm quality check\n  \
+          ccswarm quality check --jobs 2 --skip security --fail-fast\n  \
+          ccswarm quality lint --fix\n  \
+          ccswarm quality test --pattern session\n  \
+          ccswarm quality test --base main\n  \
+          ccswarm quality baseline --only lint,test --ratchet\n  \
+          ccswarm quality status --detailed")]
+    Quality {
+        #[command(subcommand)]
+        action: QualityAction,
     },
-}
 
-#[derive(Subcommand)]
-pub enum LabAction {
-    /// Collective voting on proposals
-    Sangha {
+    /// Gate commits and pushes in agent worktrees on the quality checks
+    #[command(
+        long_about = "Install git hooks that run the quality checks on commit and push.\n\n\
+        `install` writes pre-commit, post-commit and pre-push hooks into each\n\
+        agent worktree (or the one given) and points that worktree's\n\
+        core.hooksPath at them; the main worktree keeps its own hooks. A commit\n\
+        or push runs the branch's quality profile (or --profile) and is refused\n\
+        when a check fails. The repository's own hooks still run afterwards,\n\
+        from the shared core.hooksPath (husky, lefthook) when one is set.\n\n\
+        Setting CCSWARM_HOOK_BYPASS=<reason> lets a commit through without the\n\
+        checks; that, and `git commit --no-verify`, are recorded in\n\
+        .ccswarm/security/audit.ndjson. `git push --no-verify` runs no hook and\n\
+        is not recorded.\n\n\
+        With coordination.quality_gate_frequency set to \"on_commit\",\n\
+        `ccswarm worktree create` installs the hooks itself.\n\n\
+        Examples:\n  \
+          ccswarm hooks install\n  \
+          ccswarm hooks install --worktree ../worktrees/backend-1 --profile fast\n  \
+          ccswarm hooks status\n  \
+          ccswarm hooks uninstall"
+    )]
+    Hooks {
         #[command(subcommand)]
-        action: SanghaAction,
+        action: HooksAction,
     },
-    /// Agent self-extension tracking
-    Extend {
+
+    /// Typed tools for function-calling providers
+    #[command(
+        long_about = "Typed tools (run_command, read_file, write_file, search_code) that\n\
+        map to session operations. A stage with `typed_tools: true` hands them\n\
+        to its provider over MCP instead of the provider's own shell and file\n\
+        tools. Each call is checked against the stage permission level, kept\n\
+        inside the working directory and screened for protected files and\n\
+        blocked commands.\n\n\
+        `serve` is started by the provider CLI and speaks MCP on stdin/stdout.\n\n\
+        Examples:\n  \
+          ccswarm tools list --permission readonly\n  \
+          ccswarm tools serve --dir . --permission edit"
+    )]
+    Tools {
         #[command(subcommand)]
-        action: ExtendAction,
+        action: ToolsAction,
     },
-    /// Agent performance analytics
-    Evolution {
-        #[command(subcommand)]
-        action: EvolutionAction,
+
+    /// Generate shell completion scripts
+    #[command(
+        long_about = "Print a completion script for the given shell to stdout.\n\n\
+        Examples:\n  \
+          ccswarm completions bash > ~/.local/share/bash-completion/completions/ccswarm\n  \
+          ccswarm completions zsh > ~/.zfunc/_ccswarm\n  \
+          ccswarm completions fish > ~/.config/fish/completions/ccswarm.fish\n  \
+          ccswarm completions powershell >> $PROFILE"
+    )]
+    Completions {
+        /// Target shell
+        shell: clap_complete::Shell,
     },
-    /// Ripgrep over docs/ and source
-    Search {
+
+    /// Generate man pages
+    #[command(
+        long_about = "Render the ccswarm man page to stdout, or write one page per\n\
+        subcommand (ccswarm-queue-drain.1, ...) into a directory.\n\n\
+        Examples:\n  \
+          ccswarm man | man -l -\n  \
+          ccswarm man --out-dir ~/.local/share/man/man1"
+    )]
+    Man {
+        /// Write ccswarm.1 and a page for every nested subcommand here
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Run ccswarm in the background under a supervisor
+    #[command(
+        long_about = "Start a background daemon that keeps long-running subsystems alive.\n\n\
+        The daemon detaches from the terminal, writes its pid to\n\
+        .ccswarm/daemon/daemon.pid and its output (and that of its subsystems)\n\
+        to .ccswarm/daemon/daemon.log. Each subsystem in `daemon.subsystems`\n\
+        (default: the queue watcher, `ccswarm auto --watch`) is restarted when\n\
+        it exits, with a delay that doubles after every crash in a row.\n\n\
+        `status` and `stop` talk to the daemon over .ccswarm/daemon/control.sock.\n\n\
+        Examples:\n  \
+          ccswarm daemon start\n  \
+          ccswarm daemon start --foreground   # supervise without detaching\n  \
+          ccswarm daemon status\n  \
+          ccswarm stop"
+    )]
+    Daemon {
         #[command(subcommand)]
-        action: SearchAction,
+        action: DaemonAction,
     },
+
+    /// Stop the background daemon
+    #[command(
+        long_about = "Ask the daemon started by `ccswarm daemon start` to stop its\n\
+        subsystems and exit. Same as `ccswarm daemon stop`."
+    )]
+    Stop,
 }
 
 #[derive(Subcommand)]
-pub enum SanghaAction {
-    /// Create a proposal for collective voting
-    Propose {
-        #[arg(short, long)]
-        title: String,
-        #[arg(short, long)]
-        description: String,
-        /// feature | refactor | policy | tooling
-        #[arg(long, default_value = "feature")]
-        proposal_type: String,
-    },
-    /// Vote on a proposal
-    Vote {
-        /// Proposal ID
-        id: String,
-        /// Approve (omit to reject)
+pub enum DaemonAction {
+    /// Start the daemon
+    Start {
+        /// Supervise in this terminal instead of detaching
         #[arg(long)]
-        approve: bool,
-        #[arg(short, long)]
-        reason: Option<String>,
-    },
-    /// List proposals
-    List {
-        /// Filter: open | accepted | rejected
-        #[arg(short, long)]
-        status: Option<String>,
-    },
-    /// Show proposal details and vote tally
-    Status {
-        /// Proposal ID
-        id: String,
+        foreground: bool,
     },
+    /// Stop the daemon and its subsystems
+    Stop,
+    /// Show the daemon's subsystems, restarts and last exits
+    Status,
 }
 
 #[derive(Subcommand)]
-pub enum ExtendAction {
-    /// Propose a capability extension
-    Propose {
-        #[arg(short, long)]
-        title: String,
-        #[arg(short, long)]
-        description: String,
-        /// Target agent: frontend | backend | devops | qa | all
-        #[arg(short, long, default_value = "all")]
-        agent: String,
-        /// Also create a Sangha proposal for consensus approval
+pub enum ContextAction {
+    /// Seed per-persona knowledge facets from README, CONTRIBUTING, ADRs and manifests
+    Seed {
+        /// Personas to seed (default: all builtin personas)
+        #[arg(long, value_delimiter = ',')]
+        personas: Vec<String>,
+
+        /// Store raw excerpts instead of asking the provider to summarize them
         #[arg(long)]
-        auto_sangha: bool,
-    },
-    /// Generate an extension proposal from local workflow context
-    AutoPropose {
-        /// Target agent: frontend | backend | devops | qa | all
-        #[arg(short, long, default_value = "all")]
-        agent: String,
-        /// Optional reason or observed gap to include in the proposal
-        #[arg(short, long)]
-        reason: Option<String>,
-        /// Do not create a linked Sangha proposal
-        #[arg(long = "no-auto-sangha", action = clap::ArgAction::SetFalse, default_value_t = true)]
-        auto_sangha: bool,
-    },
-    /// List extensions
-    List {
-        /// Filter: proposed | approved | active | deprecated
-        #[arg(short, long)]
-        status: Option<String>,
+        no_summarize: bool,
+
+        /// Show what would be written without writing it
+        #[arg(long)]
+        dry_run: bool,
     },
-    /// Show extension details
-    Status {
-        /// Extension ID
-        id: String,
+    /// Distill completed tasks, review notes and Sangha decisions into DOCTRINE.md
+    Distill {
+        /// Append the mined lessons instead of asking the provider to rewrite the doctrine
+        #[arg(long)]
+        no_summarize: bool,
+
+        /// Print the draft without proposing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Keep running, distilling once per interval
+        #[arg(long)]
+        watch: bool,
+
+        /// Seconds between distillations when --watch is set
+        #[arg(long, default_value = "86400")]
+        interval_secs: u64,
     },
-    /// Show recent extension history
-    History {
-        /// Max entries to show
-        #[arg(default_value = "20")]
+    /// Search persisted session conversations by meaning
+    Search {
+        /// What to look for, in plain words
+        query: String,
+
+        /// Results to show
+        #[arg(short = 'n', long, default_value = "5")]
         limit: usize,
+
+        /// Only search this agent's sessions
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Drop the index and embed every session again
+        #[arg(long)]
+        rebuild: bool,
     },
 }
 
 #[derive(Subcommand)]
-pub enum SearchAction {
-    /// Search documentation files
-    Docs {
+pub enum AgentAction {
+    /// Show the agent's scratchpad: notes, TODOs and hypotheses it keeps
+    /// across tasks with the `notes` tool
+    Notes {
+        /// Agent name
+        name: String,
+
+        /// Empty the scratchpad instead
+        #[arg(long)]
+        clear: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ToolsAction {
+    /// Show the tools and their argument schemas
+    List {
+        /// Permission level to list tools for (readonly, edit, full)
+        #[arg(long, default_value = "full")]
+        permission: String,
+    },
+    /// Serve the tools over MCP on stdin/stdout
+    Serve {
+        /// Session working directory; every path is confined to it
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Permission level (readonly, edit, full)
+        #[arg(long, default_value = "edit")]
+        permission: String,
+
+        /// Permission-model tool names to allow (e.g. read,grep)
+        #[arg(long, value_delimiter = ',')]
+        allow: Vec<String>,
+
+        /// Agent the session belongs to; enables its `notes` scratchpad
+        #[arg(long)]
+        agent: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TuiView {
+    /// Review an agent's diff hunk by hunk
+    Review {
+        /// Worktree path or branch (default: the only agent worktree, else the repository)
+        #[arg(long)]
+        worktree: Option<String>,
+
+        /// Base to diff against (default: project.repository.main_branch)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Command to run in the worktree (e.g. "npx tsc --noEmit"); its
+        /// diagnostics are shown next to the lines they point at
+        #[arg(long)]
+        check: Option<String>,
+    },
+    /// Live per-agent token and cost meters for running pipelines
+    Usage {
+        /// Token budget per agent; meters turn yellow and red as it nears
+        #[arg(long)]
+        budget_tokens: Option<u64>,
+
+        /// Cost budget per agent in USD
+        #[arg(long)]
+        budget_usd: Option<f64>,
+    },
+    /// Browse and edit the shared documents agents keep in .ccswarm/docs
+    Docs {
+        /// Open this document directly (created on the first edit)
+        doc: Option<String>,
+    },
+    /// Follow, filter and search run events and agent logs
+    Logs {
+        /// Only this run's events (default: the live runs, else the latest)
+        #[arg(long)]
+        run: Option<String>,
+
+        /// Start filtered to this agent
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Lowest level shown: debug, info, warn or error
+        #[arg(long, default_value = "debug")]
+        level: String,
+
+        /// Start with this regex search
+        #[arg(long)]
+        search: Option<String>,
+    },
+    /// Work through tasks delegated to humans
+    Inbox {
+        /// Only this human's tasks
+        #[arg(long)]
+        human: Option<String>,
+    },
+    /// Where a session's time goes, updated live while it runs
+    Timeline {
+        /// Session/run ID, name or alias (default: most recent)
+        session: Option<String>,
+    },
+    /// Agents' replies as chat bubbles, streamed while they are written
+    Chat {
+        /// Session/run ID, name or alias (default: most recent)
+        session: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BundleAction {
+    /// Write the swarm setup to an archive
+    Export {
+        /// Archive to write
+        #[arg(short, long, default_value = "ccswarm-bundle.tar")]
+        output: PathBuf,
+
+        /// Leave out the scaffold templates in the ccswarm home directory
+        #[arg(long)]
+        no_templates: bool,
+    },
+    /// Set up this repository from an archive
+    Import {
+        /// Archive written by `ccswarm bundle export`
+        file: PathBuf,
+
+        /// Overwrite files that differ from the bundle
+        #[arg(long)]
+        force: bool,
+
+        /// Show what would be written without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HumanAction {
+    /// Register (or update) a human participant
+    Register {
+        /// Name tasks are delegated to
+        name: String,
+
+        /// What they can take on
+        #[arg(long, value_delimiter = ',')]
+        capabilities: Vec<String>,
+
+        /// Shell command run on every delegation
+        #[arg(long)]
+        notify_command: Option<String>,
+    },
+    /// List registered humans
+    List,
+    /// Delegate a task to a human
+    Delegate {
+        /// Registered human
+        name: String,
+
+        /// Task description
+        task: String,
+
+        /// Task priority (low, medium, high, critical)
+        #[arg(long, default_value = "medium")]
+        priority: String,
+
+        /// Task type (development, review, documentation, ...)
+        #[arg(long = "type", default_value = "review")]
+        task_type: String,
+    },
+    /// Show delegated tasks, open ones first
+    Inbox {
+        /// Only this human's tasks
+        #[arg(long)]
+        human: Option<String>,
+
+        /// Include completed and declined tasks
+        #[arg(long)]
+        all: bool,
+    },
+    /// Accept a delegated task
+    Accept {
+        /// Inbox item ID
+        id: String,
+    },
+    /// Complete a delegated task
+    Complete {
+        /// Inbox item ID
+        id: String,
+
+        /// What was done
+        #[arg(long, default_value = "")]
+        summary: String,
+    },
+    /// Decline a delegated task
+    Decline {
+        /// Inbox item ID
+        id: String,
+
+        /// Why the task is declined
+        #[arg(long)]
+        reason: String,
+    },
+    /// Comment on a delegated task
+    Comment {
+        /// Inbox item ID
+        id: String,
+
+        /// Comment text
+        text: String,
+
+        /// Comment author (default: the assigned human)
+        #[arg(long)]
+        author: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RepertoireAction {
+    /// Install a flow package from a Git repository
+    Add {
+        /// Git URL of the flow package
+        url: String,
+    },
+
+    /// List installed flow packages
+    List,
+
+    /// Remove an installed flow package
+    Remove {
+        /// Package name to remove
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LabAction {
+    /// Collective voting on proposals
+    Sangha {
+        #[command(subcommand)]
+        action: SanghaAction,
+    },
+    /// Agent self-extension tracking
+    Extend {
+        #[command(subcommand)]
+        action: ExtendAction,
+    },
+    /// Agent performance analytics
+    Evolution {
+        #[command(subcommand)]
+        action: EvolutionAction,
+    },
+    /// Ripgrep over docs/ and source
+    Search {
+        #[command(subcommand)]
+        action: SearchAction,
+    },
+    /// Run one task under two configurations in parallel and compare them
+    #[command(
+        long_about = "Run the same task twice in parallel, each in its own isolated worktree,\n\
+        with a different configuration, then compare the outcomes.\n\n\
+        A variant is a comma-separated list of model=<name>, think=<mode> and\n\
+        flow=<name> (the prompt template). A run that fails loses to one that\n\
+        succeeds; when both succeed a reviewer reads the two results and picks\n\
+        the better one. The experiment is recorded in .ccswarm/experiments/ab.jsonl\n\
+        and counted in `ccswarm lab evolution metrics` and `report`.\n\n\
+        Examples:\n  \
+          ccswarm lab ab \"Add a /health endpoint\" --a model=sonnet --b model=opus\n  \
+          ccswarm lab ab \"Fix the flaky test\" --a think=think --b think=ultrathink\n  \
+          ccswarm lab ab \"Refactor the parser\" --a flow=default --b flow=review-fix --no-review"
+    )]
+    Ab {
+        /// Task description to run under both configurations
+        task: String,
+
+        /// Configuration A, e.g. "model=sonnet,think=think_hard"
+        #[arg(long)]
+        a: String,
+
+        /// Configuration B, e.g. "model=opus,flow=review-fix"
+        #[arg(long)]
+        b: String,
+
+        /// Flow for variants that don't set one
+        #[arg(short, long, default_value = "default")]
+        flow: String,
+
+        /// Timeout per run in seconds
+        #[arg(long, default_value_t = 600)]
+        timeout: u64,
+
+        /// Skip the reviewer; two successful runs are decided by duration
+        #[arg(long)]
+        no_review: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SanghaAction {
+    /// Create a proposal for collective voting
+    Propose {
+        #[arg(short, long)]
+        title: String,
+        #[arg(short, long)]
+        description: String,
+        /// feature | refactor | policy | tooling
+        #[arg(long, default_value = "feature")]
+        proposal_type: String,
+        /// majority | supermajority | unanimous | weighted (by decision quality)
+        #[arg(long, default_value = "majority")]
+        tally: String,
+    },
+    /// Vote on a proposal
+    Vote {
+        /// Proposal ID
+        id: String,
+        /// Approve (omit to reject)
+        #[arg(long)]
+        approve: bool,
+        #[arg(short, long)]
+        reason: Option<String>,
+        /// Agent casting the vote; needed for delegation and weighting
+        #[arg(long)]
+        voter: Option<String>,
+    },
+    /// Let another agent vote for an agent that does not vote itself
+    Delegate {
+        /// Agent delegating its vote
+        from: String,
+        /// Delegate
+        #[arg(long, required_unless_present = "revoke")]
+        to: Option<String>,
+        /// Proposal type the delegation covers (repeatable; default: all)
+        #[arg(long = "category")]
+        categories: Vec<String>,
+        /// Remove the delegations instead
+        #[arg(long, conflicts_with = "to")]
+        revoke: bool,
+    },
+    /// List standing vote delegations
+    Delegations,
+    /// Settle an open proposal by its tally method
+    Close {
+        /// Proposal ID
+        id: String,
+    },
+    /// List proposals
+    List {
+        /// Filter: open | accepted | rejected
+        #[arg(short, long)]
+        status: Option<String>,
+    },
+    /// Show proposal details and vote tally
+    Status {
+        /// Proposal ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExtendAction {
+    /// Propose a capability extension
+    Propose {
+        #[arg(short, long)]
+        title: String,
+        #[arg(short, long)]
+        description: String,
+        /// Target agent: frontend | backend | devops | qa | all
+        #[arg(short, long, default_value = "all")]
+        agent: String,
+        /// Also create a Sangha proposal for consensus approval
+        #[arg(long)]
+        auto_sangha: bool,
+    },
+    /// Generate an extension proposal from local workflow context
+    AutoPropose {
+        /// Target agent: frontend | backend | devops | qa | all
+        #[arg(short, long, default_value = "all")]
+        agent: String,
+        /// Optional reason or observed gap to include in the proposal
+        #[arg(short, long)]
+        reason: Option<String>,
+        /// Do not create a linked Sangha proposal
+        #[arg(long = "no-auto-sangha", action = clap::ArgAction::SetFalse, default_value_t = true)]
+        auto_sangha: bool,
+    },
+    /// List extensions
+    List {
+        /// Filter: proposed | approved | active | deprecated
+        #[arg(short, long)]
+        status: Option<String>,
+    },
+    /// Show extension details
+    Status {
+        /// Extension ID
+        id: String,
+    },
+    /// Show recent extension history
+    History {
+        /// Max entries to show
+        #[arg(default_value = "20")]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SearchAction {
+    /// Search documentation files
+    Docs {
         /// Search query
         query: String,
         #[arg(short, long, default_value = "10")]
@@ -776,6 +1711,31 @@ pub enum ApproveAction {
         #[arg(long)]
         reason: Option<String>,
     },
+    /// Resume (or, with --reject, fail) a queue task paused after running out of time
+    Resume {
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        reject: bool,
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Run (or, with --reject, refuse) a command auto-accept held as risky
+    Command {
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        reject: bool,
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Show one request, with the held command explained
+    Show {
+        id: String,
+        /// Ask the provider for an explanation now if none is attached yet
+        #[arg(long)]
+        explain: bool,
+    },
     /// List approval requests
     List {
         #[arg(short, long)]
@@ -800,6 +1760,37 @@ pub enum QueueAction {
         /// Flow to run when this task is drained (default: "default")
         #[arg(short, long)]
         flow: Option<String>,
+        /// Wall-clock budget per attempt in seconds; the attempt is checkpointed
+        /// and paused when it runs over
+        #[arg(long, value_name = "SECS")]
+        time_budget: Option<u64>,
+        /// What to do when the budget runs out: requeue | escalate | ask
+        #[arg(long, default_value = "requeue", requires = "time_budget")]
+        on_budget: String,
+        /// Queue the task even if a near-identical one is already open
+        #[arg(long)]
+        allow_duplicate: bool,
+        /// Hold the task for this registered human instead of an agent
+        #[arg(long, value_name = "HUMAN", conflicts_with_all = ["time_budget"])]
+        assign: Option<String>,
+        /// Report a regression since this commit; draining the task bisects
+        /// to the culprit and queues a fix for the responsible agent
+        #[arg(long, value_name = "GOOD", conflicts_with_all = ["assign", "flow"])]
+        regression_since: Option<String>,
+        /// Acceptance criterion: a command that must pass once the agent is done
+        /// (repeatable)
+        #[arg(long = "accept-cmd", value_name = "COMMAND")]
+        accept_cmd: Vec<String>,
+        /// Acceptance criterion: a file that must exist (repeatable)
+        #[arg(long = "accept-file", value_name = "PATH")]
+        accept_file: Vec<String>,
+        /// Acceptance criterion: a URL that must respond (repeatable)
+        #[arg(long = "accept-url", value_name = "URL")]
+        accept_url: Vec<String>,
+        /// Times a task missing its acceptance criteria goes back to the agent
+        /// before it fails
+        #[arg(long, default_value_t = crate::workflow::acceptance::default_max_bounces())]
+        max_bounces: u32,
     },
     /// Show queued tasks
     List,
@@ -813,6 +1804,14 @@ pub enum QueueAction {
         #[arg(long)]
         reason: String,
     },
+    /// Requeue a blocked task without waiting for its blocker to clear
+    Unblock {
+        /// Queue task ID to unblock
+        id: String,
+        /// Appended to the task so the next attempt sees how it was resolved
+        #[arg(long)]
+        note: Option<String>,
+    },
     /// Execute all queued tasks through the pipeline.
     /// `drain` runs unattended by default — all commit/PR prompts are suppressed so
     /// the queue fully empties without user input. Pass `--interactive` to restore
@@ -842,7 +1841,43 @@ pub enum QueueAction {
         /// Seconds to wait for a commit approval before failing the task
         #[arg(long, default_value_t = 600)]
         approval_timeout: u64,
+        /// Leave tasks estimated above this many USD pending, and stop a run
+        /// once it would cost more (default: cost.max_task_usd)
+        #[arg(long)]
+        max_task_cost: Option<f64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ClusterAction {
+    /// Move pending queue tasks into the shared pool
+    Publish {
+        /// Task IDs to publish
+        ids: Vec<String>,
+        /// Publish every ready pending task
+        #[arg(long, conflicts_with = "ids")]
+        all: bool,
+    },
+    /// Claim and run tasks from the pool until it is empty or interrupted
+    Work {
+        /// Exit when the pool has nothing left to claim instead of waiting
+        #[arg(long)]
+        once: bool,
+        /// Stop after running this many tasks
+        #[arg(long)]
+        max_tasks: Option<usize>,
+        /// Flow override; by default each task uses its published flow or "default"
+        #[arg(short, long)]
+        flow: Option<String>,
+        /// Timeout per task in seconds
+        #[arg(long, default_value = "600")]
+        timeout: u64,
+        /// Also create a GitHub PR for each successful task (requires `gh` CLI)
+        #[arg(long)]
+        create_pr: bool,
     },
+    /// Show every node and pooled task
+    Status,
 }
 
 #[derive(Subcommand)]
@@ -863,6 +1898,39 @@ pub enum RunAction {
     },
 }
 
+#[derive(Subcommand)]
+pub enum RunsAction {
+    /// List ccswarm invocations, newest first
+    List {
+        /// Show at most this many
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show what one invocation ran: pipeline runs and queue attempts
+    Show {
+        /// Run ID or a unique prefix (default: the most recent)
+        id: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SecretsAction {
+    /// Show each role's granted secrets and whether they are set
+    List,
+    /// Write each agent's grants to its worktree, replacing older values
+    Sync {
+        /// Only this agent
+        #[arg(long)]
+        agent: Option<String>,
+    },
+    /// Remove scoped secrets from agent worktrees
+    Revoke {
+        /// Only this agent
+        #[arg(long)]
+        agent: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum AgentGenAction {
     /// Generate a .claude/agents/*.md file from facets
@@ -1024,6 +2092,44 @@ pub enum FlowAction {
     },
 }
 
+#[derive(Subcommand)]
+pub enum HooksAction {
+    /// Install the hooks into agent worktrees
+    Install {
+        /// Only this worktree (default: every agent worktree)
+        #[arg(long)]
+        worktree: Option<PathBuf>,
+
+        /// Run this quality profile instead of the branch's
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Remove the hooks from agent worktrees
+    Uninstall {
+        /// Only this worktree (default: every agent worktree)
+        #[arg(long)]
+        worktree: Option<PathBuf>,
+    },
+
+    /// Show which agent worktrees have the hooks
+    Status,
+
+    /// Run a hook (called by the installed scripts)
+    #[command(hide = true)]
+    Run {
+        #[arg(value_parser = ["pre-commit", "post-commit", "pre-push"])]
+        hook: String,
+
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Arguments git passed to the hook
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum WorktreeAction {
     /// List all worktrees
@@ -1052,6 +2158,12 @@ pub enum WorktreeAction {
         force: bool,
     },
 
+    /// Show the branch and changed files of a worktree
+    Status {
+        /// Worktree path (default: the repository)
+        path: Option<PathBuf>,
+    },
+
     /// Prune stale worktrees
     Prune,
 
@@ -1061,6 +2173,69 @@ pub enum WorktreeAction {
         #[arg(short, long)]
         force: bool,
     },
+
+    /// Show disk usage per worktree and fail if any exceeds the quota
+    Usage {
+        /// Per-worktree quota, e.g. 5G or 500M (default: repository.worktree_quota)
+        #[arg(long)]
+        quota: Option<String>,
+    },
+
+    /// Reconcile agent worktrees and branches with ccswarm.json after a restart
+    #[command(
+        long_about = "Bring every configured agent's worktree back after a restart or crash.\n\n\
+        Existing worktrees and branches are reused rather than duplicated: a branch\n\
+        without a worktree is checked out again, a worktree that moved is moved\n\
+        back, and stale registrations are pruned. --policy (default:\n\
+        repository.worktree_policy) decides what happens to existing work: adopt\n\
+        keeps it, reset resets the branch to the main branch in place, recreate\n\
+        removes the worktree and starts over. Directories that aren't worktrees and\n\
+        the main worktree are never touched.\n\n\
+        Adopted worktrees get back the session, run and queue task they were on\n\
+        (kept in .ccswarm/worktrees.json).\n\n\
+        Examples:\n  \
+          ccswarm worktree bootstrap --dry-run\n  \
+          ccswarm worktree bootstrap --policy recreate"
+    )]
+    Bootstrap {
+        /// adopt, reset or recreate
+        #[arg(long)]
+        policy: Option<crate::git::bootstrap::WorktreePolicy>,
+
+        /// Show the plan without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Find stale or duplicated build artifacts (target/, node_modules) in agent worktrees
+    #[command(
+        long_about = "Find build artifacts in agent worktrees that can be reclaimed.\n\n\
+        target/ and node_modules/ directories nothing has written to for --stale-days\n\
+        are pruned, as are the largest artifacts of worktrees over the quota.\n\
+        node_modules trees duplicated across worktrees are hard-linked to one copy.\n\
+        Without --prune or --link nothing changes; the report shows what would be\n\
+        reclaimed. The main worktree is never modified.\n\n\
+        Examples:\n  \
+          ccswarm worktree gc\n  \
+          ccswarm worktree gc --prune --link --quota 5G"
+    )]
+    Gc {
+        /// Days without writes after which an artifact is stale
+        #[arg(long, default_value_t = 7)]
+        stale_days: u32,
+
+        /// Per-worktree quota, e.g. 5G or 500M (default: repository.worktree_quota)
+        #[arg(long)]
+        quota: Option<String>,
+
+        /// Delete stale and over-quota artifacts
+        #[arg(long)]
+        prune: bool,
+
+        /// Hard-link files duplicated across worktrees' node_modules
+        #[arg(long)]
+        link: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -1101,6 +2276,10 @@ pub enum TaskAction {
         /// Interactive template variable input
         #[arg(long)]
         interactive: bool,
+
+        /// Add the task even if a near-identical one is already open
+        #[arg(long)]
+        allow_duplicate: bool,
     },
 
     /// List all tasks
@@ -1140,6 +2319,12 @@ pub enum TaskAction {
         orchestration: bool,
     },
 
+    /// Show a queued task and its retry chain
+    Show {
+        /// Queue task ID (see `ccswarm queue list`)
+        task_id: String,
+    },
+
     /// Cancel a task
     Cancel {
         /// Task ID
@@ -1213,19 +2398,73 @@ pub enum TaskAction {
         /// Task ID to retry
         task_id: String,
 
-        /// Force retry even if task is not in failed state
-        #[arg(short, long)]
-        force: bool,
+        /// Force retry even if task is not in failed state
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Delete a task and its associated worktree
+    Delete {
+        /// Task ID to delete
+        task_id: String,
+
+        /// Force deletion even if task is in progress
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Revert what the agent did for a task: commits, edits, created and
+    /// deleted files, across every stage and retry. Shows a preview first.
+    Undo {
+        /// Queue task ID, or a pipeline run ID
+        task_id: String,
+
+        /// Only show what would be reverted
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Queue a batch of tasks from a YAML, JSON or CSV file.
+    /// Entries take `description`, `priority`, `labels`, `depends_on`,
+    /// `key` and `flow`; `depends_on` names other entries' keys or existing
+    /// queue IDs. The whole file is validated before anything is queued.
+    Bulk {
+        /// File with the task list
+        #[arg(short, long)]
+        file: PathBuf,
+
+        /// File format: yaml | json | csv (default: from the extension)
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Validate and print the plan without queueing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Queue entries even if they nearly match an open task
+        #[arg(long)]
+        allow_duplicate: bool,
     },
 
-    /// Delete a task and its associated worktree
-    Delete {
-        /// Task ID to delete
-        task_id: String,
+    /// Export the queue's task history for spreadsheets and BI tools: one
+    /// row per task with its agents, attempts, duration, tokens, cost and
+    /// outcome, in a fixed column order
+    Export {
+        /// Output format: csv | json | jsonl
+        #[arg(long, default_value = "csv")]
+        format: String,
 
-        /// Force deletion even if task is in progress
+        /// Only tasks created since this age (30d, 12h, 2w) or date (2024-05-01)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Write to a file instead of stdout
         #[arg(short, long)]
-        force: bool,
+        output: Option<PathBuf>,
     },
 }
 
@@ -1261,6 +2500,16 @@ pub enum ConfigAction {
         #[arg(long)]
         raw: bool,
     },
+
+    /// Resolve the agent presets a configuration references
+    Presets {
+        /// Configuration file
+        #[arg(short, long, default_value = "ccswarm.json")]
+        file: PathBuf,
+        /// Re-fetch unpinned git presets instead of using the cache
+        #[arg(long)]
+        refresh: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -1291,15 +2540,42 @@ pub enum SessionAction {
         /// Background mode
         #[arg(short, long)]
         background: bool,
+
+        /// Reserve a name for the session; start it with `ccswarm pipeline --name`
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+
+    /// Give a named session a new name (its aliases are kept)
+    Rename {
+        /// Current name or alias
+        session: String,
+
+        /// New name
+        new_name: String,
+    },
+
+    /// Add an alias to a session, or remove one with --remove
+    Alias {
+        /// Session id, name or alias (with --remove: the alias to remove)
+        session: String,
+
+        /// Alias to add
+        #[arg(required_unless_present = "remove")]
+        alias: Option<String>,
+
+        /// Remove the alias given as SESSION
+        #[arg(long)]
+        remove: bool,
     },
 
-    /// Pause a running session
+    /// Pause a running session (SIGSTOP its pipeline and child processes)
     Pause {
         /// Session ID
         session_id: String,
     },
 
-    /// Resume a paused session
+    /// Resume a paused session (SIGCONT) and brief it on what changed meanwhile
     Resume {
         /// Session ID
         session_id: String,
@@ -1317,45 +2593,166 @@ pub enum SessionAction {
         session_id: String,
     },
 
-    /// Kill a session
+    /// Watch a session's live output read-only (safe for demos and supervision)
+    Watch {
+        /// Session/run ID (default: most recent)
+        session_id: Option<String>,
+
+        /// Print the output recorded so far and exit
+        #[arg(long)]
+        no_follow: bool,
+    },
+
+    /// Show where a session's time went: stages, provider calls, gate
+    /// commands, approval waits and pauses on one timeline
+    Timeline {
+        /// Session/run ID, name or alias (default: most recent)
+        session_id: Option<String>,
+
+        /// Width of the timeline bars in columns
+        #[arg(long, default_value = "60")]
+        width: usize,
+    },
+
+    /// Re-encrypt persisted session state (and any given transcripts) with
+    /// the current key from AI_SESSION_ENCRYPTION_KEY or
+    /// AI_SESSION_ENCRYPTION_KEY_FILE, after moving the old key to
+    /// AI_SESSION_ENCRYPTION_OLD_KEYS
+    Rekey {
+        /// Provider transcript to re-encrypt as well (repeatable)
+        #[arg(long = "transcript")]
+        transcripts: Vec<PathBuf>,
+
+        /// Print a new random key and exit
+        #[arg(long)]
+        generate_key: bool,
+    },
+
+    /// Copy a file into or out of a session's workspace
+    #[command(
+        long_about = "Copy one file between the host and a session's workspace.\n\n\
+        Exactly one side is written <session>:<path>, with the session given by\n\
+        run ID, name or alias and the path relative to its workspace; paths that\n\
+        lead outside the workspace, through `..` or a symlink, are refused. The\n\
+        copy is written to a temporary file, checked against the SHA-256 of the\n\
+        source and then renamed into place.\n\n\
+        Examples:\n  \
+          ccswarm session cp my-feature:target/report.html ./report.html\n  \
+          ccswarm session cp fixtures/seed.sql my-feature:db/seed.sql"
+    )]
+    Cp {
+        /// Source: a local path or <session>:<path>
+        source: String,
+
+        /// Destination: a local path or <session>:<path>
+        destination: String,
+    },
+
+    /// Kill a running session (SIGTERM its pipeline and child processes)
     Kill {
         /// Session ID
         session_id: String,
 
-        /// Force kill
+        /// SIGKILL instead of SIGTERM
         #[arg(short, long)]
         force: bool,
     },
+
+    /// Named groups of sessions: run a command in all of them, or pause,
+    /// resume or kill them together
+    #[command(long_about = "Named groups of sessions for bulk operations.\n\n\
+        A group lists sessions by run ID, name or alias; members are resolved\n\
+        each time the group is used. Bulk commands act on every member and\n\
+        report each session's result; they fail if any session did.\n\n\
+        Examples:\n  \
+          ccswarm session group create sprint api-work docs-work\n  \
+          ccswarm session group run sprint -- git pull --rebase\n  \
+          ccswarm session group pause sprint")]
+    Group {
+        #[command(subcommand)]
+        action: SessionGroupAction,
+    },
 }
 
 #[derive(Subcommand)]
-pub enum DelegateAction {
-    /// Delegate a task to specific agent
-    Task {
-        /// Task description
-        description: String,
+pub enum SessionGroupAction {
+    /// List groups and their sessions
+    List,
 
-        /// Target agent type (frontend, backend, devops, qa)
-        #[arg(short, long)]
-        agent: String,
+    /// Create a group of sessions
+    Create {
+        /// Group name
+        group: String,
 
-        /// Task priority
-        #[arg(short, long, default_value = "medium")]
-        priority: String,
+        /// Sessions (run ID, name or alias)
+        sessions: Vec<String>,
+    },
 
-        /// Task type
-        #[arg(short, long, default_value = "development")]
-        task_type: String,
+    /// Add sessions to a group
+    Add {
+        /// Group name
+        group: String,
 
-        /// Additional details
-        #[arg(long)]
-        details: Option<String>,
+        /// Sessions (run ID, name or alias)
+        #[arg(required = true)]
+        sessions: Vec<String>,
+    },
 
-        /// Force delegation even if agent doesn't match
-        #[arg(long)]
+    /// Remove sessions from a group
+    Remove {
+        /// Group name
+        group: String,
+
+        /// Sessions, as they were added
+        #[arg(required = true)]
+        sessions: Vec<String>,
+    },
+
+    /// Delete a group (its sessions are left alone)
+    Delete {
+        /// Group name
+        group: String,
+    },
+
+    /// Run a shell command in every session's workspace, once per workspace
+    Run {
+        /// Group name
+        group: String,
+
+        /// Seconds each command may take
+        #[arg(long, default_value = "300")]
+        timeout: u64,
+
+        /// Command to run, after `--`
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Pause every running session in the group
+    Pause {
+        /// Group name
+        group: String,
+    },
+
+    /// Resume every paused session in the group
+    Resume {
+        /// Group name
+        group: String,
+    },
+
+    /// Kill every running session in the group
+    Kill {
+        /// Group name
+        group: String,
+
+        /// SIGKILL instead of SIGTERM
+        #[arg(short, long)]
         force: bool,
     },
+}
 
+#[derive(Subcommand)]
+pub enum DelegateAction {
     /// Analyze task and suggest optimal agent
     Analyze {
         /// Task description
@@ -1365,65 +2762,75 @@ pub enum DelegateAction {
         #[arg(short, long)]
         verbose: bool,
 
-        /// Delegation strategy to use
+        /// Delegation strategy to use (keyword, task_type, hybrid)
         #[arg(short, long, default_value = "hybrid")]
         strategy: String,
-    },
 
-    /// Show delegation statistics
-    Stats {
-        /// Show detailed breakdown
+        /// Task type, used by the task_type and hybrid strategies
         #[arg(short, long)]
-        detailed: bool,
-
-        /// Time period to analyze (hours)
-        #[arg(long, default_value = "24")]
-        period: u32,
+        task_type: Option<String>,
     },
 
-    /// Interactive delegation mode
-    Interactive,
+    /// Score a delegation strategy against labeled tasks
+    Eval {
+        /// Built-in dataset name (`default`) or a YAML/JSON file of samples
+        #[arg(short, long, default_value = "default")]
+        dataset: String,
 
-    /// Show configuration
-    Show {
-        /// Configuration file
-        #[arg(short, long, default_value = "ccswarm.json")]
-        file: PathBuf,
+        /// Delegation strategy to score (keyword, task_type, hybrid, or all)
+        #[arg(short, long, default_value = "hybrid")]
+        strategy: String,
+
+        /// List every misrouted task
+        #[arg(short, long)]
+        verbose: bool,
     },
 }
 
 #[derive(Subcommand)]
 pub enum QualityAction {
-    /// Run all quality checks through agents
+    /// Run all quality checks concurrently
     Check {
-        /// Skip specific check types
+        /// Skip specific checks
         #[arg(long, value_delimiter = ',')]
         skip: Vec<String>,
 
-        /// Run only specific check types
+        /// Run only specific checks
         #[arg(long, value_delimiter = ',')]
         only: Vec<String>,
 
-        /// Fail fast on first error
+        /// Cancel the remaining checks on the first failure
         #[arg(long)]
         fail_fast: bool,
+
+        /// Checks run at the same time (default: number of CPUs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Run this quality profile instead of the branch's
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Pick the profile for this branch instead of the checked-out one
+        #[arg(long, conflicts_with = "profile")]
+        branch: Option<String>,
     },
 
-    /// Run format checks (DevOps agent)
+    /// Run the format check
     Format {
         /// Automatically fix formatting issues
         #[arg(long)]
         fix: bool,
     },
 
-    /// Run linting checks (DevOps agent)  
+    /// Run the lint check
     Lint {
         /// Automatically fix linting issues where possible
         #[arg(long)]
         fix: bool,
     },
 
-    /// Run test suite (QA agent)
+    /// Run the test suite
     Test {
         /// Test filter pattern
         #[arg(short, long)]
@@ -1440,9 +2847,17 @@ pub enum QualityAction {
         /// Run only security tests
         #[arg(long)]
         security: bool,
+
+        /// Run the whole suite instead of only the tests the changes affect
+        #[arg(long)]
+        full: bool,
+
+        /// Compare against this git ref to find the changes
+        #[arg(long, default_value = "HEAD")]
+        base: String,
     },
 
-    /// Run build verification (DevOps agent)
+    /// Run the build check
     Build {
         /// Build in release mode
         #[arg(long)]
@@ -1453,243 +2868,128 @@ pub enum QualityAction {
         all_targets: bool,
     },
 
-    /// Run security analysis (Backend agent)
+    /// Run security analysis
     Security {
-        /// Run vulnerability scan
+        /// Run vulnerability scan (the default)
         #[arg(long)]
         audit: bool,
 
-        /// Check dependencies
+        /// Check dependency licenses and sources with cargo-deny
         #[arg(long)]
         deps: bool,
     },
 
-    /// Show quality gate status
-    Status {
-        /// Show detailed status for each check
-        #[arg(short, long)]
-        detailed: bool,
-    },
-}
-
-#[derive(Subcommand, Debug)]
-pub enum TemplateAction {
-    /// List available templates
-    List {
-        /// Show all templates including disabled ones
-        #[arg(short, long)]
-        all: bool,
-
-        /// Filter by category
-        #[arg(short, long)]
-        category: Option<String>,
-
-        /// Filter by tags
-        #[arg(short, long, value_delimiter = ',')]
-        tags: Vec<String>,
-
-        /// Search term for name or description
-        #[arg(short, long)]
-        search: Option<String>,
-
-        /// Sort by popularity
+    /// Lint the branch's commits: size, generated files, messages, secrets
+    Commits {
+        /// Compare against this ref (default: origin/HEAD, main or master)
         #[arg(long)]
-        popular: bool,
+        base: Option<String>,
 
-        /// Sort by success rate
-        #[arg(long)]
-        quality: bool,
-
-        /// Show detailed information
-        #[arg(short, long)]
-        detailed: bool,
-    },
-
-    /// Show template details
-    Show {
-        /// Template ID or name
-        template: String,
-
-        /// Show template source code
-        #[arg(long)]
-        source: bool,
+        /// Lines a single commit may change
+        #[arg(long, default_value_t = 1000)]
+        max_lines: usize,
 
-        /// Show usage statistics
+        /// Don't require Conventional Commits messages
         #[arg(long)]
-        stats: bool,
+        any_message: bool,
     },
 
-    /// Create a new template
-    Create {
-        /// Template ID
-        #[arg(short, long)]
-        id: String,
+    /// Record the issues the checks report now as the accepted baseline
+    Baseline {
+        /// Checks to record (default: all)
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
 
-        /// Template name
-        #[arg(short, long)]
-        name: String,
+        /// Also shrink each check's allowance over time
+        #[arg(long)]
+        ratchet: bool,
 
-        /// Template description
-        #[arg(short, long)]
-        description: String,
+        /// Issues the ratchet takes off each period
+        #[arg(long, default_value_t = 1)]
+        step: usize,
 
-        /// Template category
-        #[arg(short, long)]
-        category: String,
+        /// Length of a ratchet period in days
+        #[arg(long, default_value_t = 7)]
+        every_days: u32,
 
-        /// Open editor to define template details
-        #[arg(long)]
-        editor: bool,
+        /// Show the recorded baseline instead of recording one
+        #[arg(long, conflicts_with = "clear")]
+        show: bool,
 
-        /// Use existing template as base
+        /// Delete the baseline, so checks fail on every issue again
         #[arg(long)]
-        from: Option<String>,
+        clear: bool,
     },
 
-    /// Edit an existing template
-    Edit {
-        /// Template ID or name
-        template: String,
-
-        /// Open in external editor
-        #[arg(long)]
-        editor: bool,
+    /// Show the results of the last `quality check`
+    Status {
+        /// Show the output of each check
+        #[arg(short, long)]
+        detailed: bool,
     },
+}
 
-    /// Delete a template
-    Delete {
-        /// Template ID or name
-        template: String,
-
-        /// Force deletion without confirmation
+#[derive(Subcommand, Debug)]
+pub enum TemplateAction {
+    /// List the builtin templates and the project's customized copies
+    List {
+        /// Only this kind (claude-md | task)
         #[arg(short, long)]
-        force: bool,
+        kind: Option<String>,
     },
 
-    /// Apply a template to create a task
-    Apply {
-        /// Template ID or name
+    /// Preview a template rendered with the project's variables
+    Show {
+        /// Template name, or <kind>/<name>
         template: String,
 
-        /// Variable values (key=value)
-        #[arg(short, long, value_delimiter = ',')]
-        vars: Vec<String>,
-
-        /// Interactive mode to prompt for variables
-        #[arg(short, long)]
-        interactive: bool,
-
-        /// Preview the generated task without creating it
+        /// Show the template as written instead of rendered
         #[arg(long)]
-        preview: bool,
+        source: bool,
 
-        /// Auto-assign to best agent
-        #[arg(long)]
-        auto_assign: bool,
+        /// Variable values (key=value), over the project's
+        #[arg(long = "var", value_delimiter = ',')]
+        vars: Vec<String>,
     },
 
-    /// Validate a template
-    Validate {
-        /// Template ID or name
+    /// Copy a template into .ccswarm/templates/ to customize it
+    Copy {
+        /// Template name, or <kind>/<name>
         template: String,
 
-        /// Show detailed validation report
-        #[arg(short, long)]
-        detailed: bool,
-
-        /// Treat warnings as errors
+        /// Name of the copy (default: the template's)
         #[arg(long)]
-        strict: bool,
-    },
-
-    /// Clone a template
-    Clone {
-        /// Source template ID or name
-        source: String,
-
-        /// New template ID
-        #[arg(short, long)]
-        id: String,
-
-        /// New template name (optional)
-        #[arg(short, long)]
         name: Option<String>,
-    },
 
-    /// Import templates from file
-    Import {
-        /// JSON file containing templates
-        file: String,
-
-        /// Overwrite existing templates
-        #[arg(long)]
-        force: bool,
-    },
-
-    /// Export templates to file
-    Export {
-        /// Output file path
+        /// Overwrite an existing copy
         #[arg(short, long)]
-        output: String,
-
-        /// Export specific templates only
-        #[arg(short, long, value_delimiter = ',')]
-        templates: Vec<String>,
-
-        /// Include usage statistics
-        #[arg(long)]
-        stats: bool,
-    },
-
-    /// Install predefined templates
-    Install {
-        /// Install all predefined templates
-        #[arg(long)]
-        all: bool,
-
-        /// Install specific template categories
-        #[arg(short, long, value_delimiter = ',')]
-        categories: Vec<String>,
-
-        /// Force reinstall existing templates
-        #[arg(long)]
         force: bool,
     },
 
-    /// Show template usage statistics
-    Stats {
-        /// Show global statistics
-        #[arg(short, long)]
-        global: bool,
-
-        /// Show statistics for specific template
-        #[arg(short, long)]
-        template: Option<String>,
-    },
-
-    /// Search templates
-    Search {
-        /// Search query
-        query: String,
-
-        /// Limit number of results
-        #[arg(short, long, default_value = "10")]
-        limit: usize,
+    /// Validate custom templates
+    Validate {
+        /// Template file (default: every custom template)
+        path: Option<PathBuf>,
 
-        /// Minimum quality score (0.0-1.0)
+        /// Treat warnings as errors
         #[arg(long)]
-        min_quality: Option<f64>,
+        strict: bool,
     },
 }
 
 pub struct CliRunner {
     config: CcswarmConfig,
     repo_path: PathBuf,
+    /// `--config` path, passed on to the daemon's subsystems.
+    config_path: PathBuf,
     json_output: bool,
     formatter: OutputFormatter,
     /// `--provider` flag: default provider for stages that don't pin one in
     /// flow YAML. Overrides the CCSWARM_PROVIDER env var.
     default_provider: Option<crate::providers::ProviderKind>,
+    /// Recorded provider replies every session bridge draws from, in place
+    /// of `CCSWARM_PROVIDER_REPLAY` (set by scripted scenarios).
+    replay: Option<std::sync::Arc<crate::providers::transcript::TranscriptReplay>>,
 }
 
 impl CliRunner {
@@ -1706,6 +3006,25 @@ impl CliRunner {
             }
             create_default_config(&cli.repo)?
         };
+        crate::i18n::init(config.i18n.locale.as_deref());
+
+        // Bring coordination files written by older versions (or left
+        // half-written by a crash) up to date before anything reads them.
+        match crate::governance::migrate::on_startup(&cli.repo).await {
+            Ok(Some(report)) if !report.is_clean() => {
+                for path in report.upgraded.iter().chain(&report.repaired) {
+                    info!("Migrated coordination file {}", path);
+                }
+                for entry in &report.corrupt {
+                    warn!(
+                        "Corrupt coordination file {} moved to {}: {}",
+                        entry.path, entry.moved_to, entry.error
+                    );
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Coordination state migration failed: {:#}", e),
+        }
 
         let formatter = create_formatter(cli.json);
 
@@ -1714,7 +3033,7 @@ impl CliRunner {
         let default_provider = match cli.provider.as_deref() {
             Some(name) => Some(crate::providers::ProviderKind::parse(name).ok_or_else(|| {
                 anyhow::anyhow!(
-                    "unknown provider '{}' (expected: claude | codex | copilot)",
+                    "unknown provider '{}' (expected: claude | codex | gemini | aider | copilot | mock)",
                     name
                 )
             })?),
@@ -1724,9 +3043,11 @@ impl CliRunner {
         Ok(Self {
             config,
             repo_path: cli.repo.clone(),
+            config_path: cli.config.clone(),
             json_output: cli.json,
             formatter,
             default_provider,
+            replay: None,
         })
     }
 
@@ -1741,11 +3062,51 @@ impl CliRunner {
     pub async fn handle_scaffold(
         &self,
         dir: &std::path::Path,
-        task: &str,
-        flow: &str,
+        task: Option<&str>,
+        template: Option<&str>,
+        flow: Option<&str>,
         timeout: u64,
+        auto_deploy: bool,
     ) -> Result<()> {
-        handlers::scaffold::handle_scaffold(dir, task, flow, timeout, self.default_provider).await
+        let template = match template {
+            Some(name) => {
+                Some(handlers::scaffold_template::find_template(name, &self.repo_path).await?)
+            }
+            None => None,
+        };
+        let (task, flow) = match &template {
+            Some(t) => (
+                t.render_task(task),
+                flow.or(t.flow.as_deref()).unwrap_or("default").to_string(),
+            ),
+            None => (
+                task.ok_or_else(|| anyhow!("--task is required without --template"))?
+                    .to_string(),
+                flow.unwrap_or("default").to_string(),
+            ),
+        };
+        let deploy = match (&template, auto_deploy) {
+            (Some(t), true) => Some(t.deploy.clone().ok_or_else(|| {
+                anyhow!(
+                    "template '{}' has no deploy section for --auto-deploy",
+                    t.name
+                )
+            })?),
+            _ => None,
+        };
+        handlers::scaffold::handle_scaffold(
+            dir,
+            &task,
+            &flow,
+            timeout,
+            template.as_ref(),
+            self.default_provider,
+        )
+        .await?;
+        match deploy {
+            Some(spec) => self.auto_deploy(dir, &spec).await,
+            None => Ok(()),
+        }
     }
 
     /// Handle agent-gen subcommands
@@ -1824,6 +3185,7 @@ fn create_default_config(repo_path: &Path) -> Result<CcswarmConfig> {
             branch: "feature/frontend-ui".to_string(),
             claude_config: crate::config::ClaudeConfig::for_agent("frontend"),
             claude_md_template: "frontend_specialist".to_string(),
+            ..Default::default()
         },
     );
 
@@ -1835,6 +3197,7 @@ fn create_default_config(repo_path: &Path) -> Result<CcswarmConfig> {
             branch: "feature/backend-api".to_string(),
             claude_config: crate::config::ClaudeConfig::for_agent("backend"),
             claude_md_template: "backend_specialist".to_string(),
+            ..Default::default()
         },
     );
 
@@ -1846,6 +3209,7 @@ fn create_default_config(repo_path: &Path) -> Result<CcswarmConfig> {
             branch: "feature/infrastructure".to_string(),
             claude_config: crate::config::ClaudeConfig::for_agent("devops"),
             claude_md_template: "devops_specialist".to_string(),
+            ..Default::default()
         },
     );
 
@@ -1869,12 +3233,37 @@ fn create_default_config(repo_path: &Path) -> Result<CcswarmConfig> {
             },
         },
         agents,
+        humans: std::collections::HashMap::new(),
         coordination: crate::config::CoordinationConfig {
             communication_method: "json_files".to_string(),
             sync_interval: 30,
             quality_gate_frequency: "on_commit".to_string(),
             master_review_trigger: "all_tasks_complete".to_string(),
+            watch: Default::default(),
+            scheduling: Default::default(),
         },
+        retry: Default::default(),
+        redaction: Default::default(),
+        prompt_guard: Default::default(),
+        parsers: Vec::new(),
+        subagents: Default::default(),
+        quality: Default::default(),
+        output_sampling: Default::default(),
+        daemon: Default::default(),
+        auto_accept: Default::default(),
+        partial_output: Default::default(),
+        cluster: Default::default(),
+        cost: Default::default(),
+        shadows: Default::default(),
+        i18n: Default::default(),
+        context_search: Default::default(),
+        model_routing: Default::default(),
+        supervisor: Default::default(),
+        scratchpad: Default::default(),
+        preflight: Default::default(),
+        notifications: Default::default(),
+        secrets: Default::default(),
+        scheduler: Default::default(),
     })
 }
 
@@ -1897,6 +3286,7 @@ fn create_frontend_only_config(repo_path: &Path) -> Result<CcswarmConfig> {
             branch: "feature/frontend".to_string(),
             claude_config: crate::config::ClaudeConfig::for_agent("frontend"),
             claude_md_template: "frontend_specialist".to_string(),
+            ..Default::default()
         },
     );
 
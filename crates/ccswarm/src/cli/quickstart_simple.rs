@@ -178,7 +178,30 @@ pub async fn handle_quickstart_simple(
             },
         },
         agents: HashMap::new(),
+        humans: HashMap::new(),
         coordination: Default::default(),
+        retry: Default::default(),
+        redaction: Default::default(),
+        prompt_guard: Default::default(),
+        parsers: Vec::new(),
+        subagents: Default::default(),
+        quality: Default::default(),
+        output_sampling: Default::default(),
+        daemon: Default::default(),
+        auto_accept: Default::default(),
+        partial_output: Default::default(),
+        cluster: Default::default(),
+        cost: Default::default(),
+        shadows: Default::default(),
+        i18n: Default::default(),
+        context_search: Default::default(),
+        model_routing: Default::default(),
+        supervisor: Default::default(),
+        scratchpad: Default::default(),
+        preflight: Default::default(),
+        notifications: Default::default(),
+        secrets: Default::default(),
+        scheduler: Default::default(),
     };
 
     // Add configured agents
@@ -191,6 +214,7 @@ pub async fn handle_quickstart_simple(
                 branch: format!("{}-agent", agent_name),
                 claude_config: ClaudeConfig::for_agent(agent_name),
                 claude_md_template: "default".to_string(),
+                ..Default::default()
             },
         );
     }
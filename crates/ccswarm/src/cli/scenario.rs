@@ -0,0 +1,368 @@
+//! Scripted end-to-end scenarios for regression tests.
+//!
+//! A scenario (`tests/scenarios/<name>.yaml`) seeds a scratch git repo,
+//! runs a list of `ccswarm` invocations in-process — `init`, `queue add`,
+//! `queue drain` and so on — and answers every provider call from the
+//! replies it lists instead of spawning a provider CLI. What the run leaves
+//! behind (project config, queue, each run's events, the commits made, and
+//! which agent delegation picks for each task) is normalized and compared
+//! with `<name>.golden.json` next to the script.
+//!
+//! After an intended behavior change, rewrite the golden files with
+//! `CCSWARM_UPDATE_GOLDEN=1 cargo test -p ccswarm --lib scenario` and
+//! review their diff like any other.
+
+use super::handlers::queue_state::{QUEUE_FILE, load_queue};
+use super::{Cli, CliRunner};
+use crate::agent::orchestrator::delegation::{DelegationStrategy, delegate};
+use crate::providers::ProviderOptions;
+use crate::providers::transcript::{ProviderResponse, TranscriptReplay, entry};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Where scripts and their golden files live, relative to the crate root.
+const SCENARIO_DIR: &str = "tests/scenarios";
+/// Set to rewrite golden files from the current outcome.
+const UPDATE_ENV: &str = "CCSWARM_UPDATE_GOLDEN";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scenario {
+    /// Files committed to the scratch repo before the first step.
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+    /// Flow YAML by name, written to `.ccswarm/flows/` (not committed).
+    #[serde(default)]
+    flows: BTreeMap<String, String>,
+    /// Provider replies, served in order per provider and agent.
+    #[serde(default)]
+    replies: Vec<Reply>,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Reply {
+    #[serde(default = "default_provider")]
+    provider: String,
+    #[serde(default = "default_agent")]
+    agent: String,
+    stdout: String,
+    /// Whether the provider CLI exits successfully.
+    #[serde(default = "default_success")]
+    success: bool,
+}
+
+fn default_provider() -> String {
+    "claude".to_string()
+}
+
+fn default_agent() -> String {
+    "default".to_string()
+}
+
+fn default_success() -> bool {
+    true
+}
+
+/// One `ccswarm` invocation: arguments after the global flags.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Step {
+    run: Vec<String>,
+    /// The command is expected to fail.
+    #[serde(default)]
+    fails: bool,
+}
+
+impl Reply {
+    fn to_entry(&self) -> Result<crate::providers::transcript::TranscriptEntry> {
+        let provider = crate::providers::ProviderKind::parse(&self.provider)
+            .with_context(|| format!("unknown provider '{}'", self.provider))?;
+        Ok(entry(
+            provider,
+            &self.agent,
+            "",
+            &ProviderOptions::default(),
+            ProviderResponse {
+                success: self.success,
+                stdout: self.stdout.clone(),
+                stderr: String::new(),
+            },
+            0,
+        ))
+    }
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A git repo holding `files` in one commit, with a local identity so the
+/// commits ccswarm makes don't depend on the machine's git config.
+fn seed_repo(repo: &Path, files: &BTreeMap<String, String>) -> Result<()> {
+    git(repo, &["init", "-q", "-b", "main"])?;
+    git(repo, &["config", "user.name", "Scenario"])?;
+    git(repo, &["config", "user.email", "scenario@example.com"])?;
+    git(repo, &["config", "commit.gpgsign", "false"])?;
+    for (path, content) in files {
+        let path = repo.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)?;
+    }
+    git(repo, &["add", "-A"])?;
+    git(
+        repo,
+        &[
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            "chore: seed scenario",
+        ],
+    )?;
+    Ok(())
+}
+
+/// Run `scenario` in a scratch repo and return its normalized outcome.
+async fn play(scenario: &Scenario) -> Result<Value> {
+    let dir = tempfile::tempdir()?;
+    let repo = dir.path();
+    seed_repo(repo, &scenario.files)?;
+    for (name, yaml) in &scenario.flows {
+        let path = repo.join(".ccswarm/flows").join(format!("{name}.yaml"));
+        std::fs::create_dir_all(path.parent().context("flow path has no parent")?)?;
+        std::fs::write(path, yaml)?;
+    }
+    let replay = Arc::new(TranscriptReplay::new(
+        scenario
+            .replies
+            .iter()
+            .map(Reply::to_entry)
+            .collect::<Result<_>>()?,
+    ));
+
+    let repo_arg = repo.display().to_string();
+    let config_arg = repo.join("ccswarm.json").display().to_string();
+    for (i, step) in scenario.steps.iter().enumerate() {
+        let mut args = vec![
+            "ccswarm",
+            "--repo",
+            &repo_arg,
+            "--config",
+            &config_arg,
+            "--provider",
+            "claude",
+        ];
+        args.extend(step.run.iter().map(String::as_str));
+        let cli = Cli::try_parse_from(&args)
+            .with_context(|| format!("step {}: invalid arguments", i + 1))?;
+        // A fresh runner per step, like separate CLI invocations, so a step
+        // sees the config an earlier `init` wrote.
+        let mut runner = CliRunner::new(&cli).await?;
+        runner.replay = Some(replay.clone());
+        match (runner.run(&cli.command).await, step.fails) {
+            (Ok(()), true) => bail!("step {} ({}) succeeded", i + 1, step.run.join(" ")),
+            (Err(e), false) => {
+                return Err(e.context(format!("step {} ({})", i + 1, step.run.join(" "))));
+            }
+            _ => {}
+        }
+    }
+
+    let mut outcome = snapshot(repo).await?;
+    outcome["unused_replies"] = json!(replay.remaining());
+    normalize(outcome, repo)
+}
+
+/// What the run left in `repo`, before volatile values are replaced.
+async fn snapshot(repo: &Path) -> Result<Value> {
+    let project = match std::fs::read_to_string(repo.join("ccswarm.json")) {
+        Ok(raw) => {
+            let config: Value = serde_json::from_str(&raw)?;
+            let mut agents: Vec<&String> = config["agents"]
+                .as_object()
+                .map(|agents| agents.keys().collect())
+                .unwrap_or_default();
+            agents.sort();
+            json!({ "name": config["project"]["name"], "agents": agents })
+        }
+        Err(_) => Value::Null,
+    };
+
+    let queue = load_queue(&repo.join(QUEUE_FILE)).await?;
+    let mut runs = Vec::new();
+    let tasks: Vec<Value> = queue
+        .tasks
+        .iter()
+        .map(|task| {
+            let attempts: Vec<Value> = task
+                .attempts
+                .iter()
+                .map(|a| json!({ "run": a.run_id, "outcome": a.outcome, "error": a.error }))
+                .collect();
+            for attempt in &task.attempts {
+                runs.push(json!({
+                    "run": attempt.run_id,
+                    "events": run_events(repo, &attempt.run_id),
+                }));
+            }
+            json!({
+                "id": task.id,
+                "task": task.task,
+                "flow": task.flow,
+                "state": task.state,
+                "delegated_to": delegate(DelegationStrategy::Hybrid, &task.task, None).agent,
+                "attempts": attempts,
+            })
+        })
+        .collect();
+
+    // `subject (file, file)`, newest first.
+    let commits: Vec<String> = git(repo, &["log", "--format=%x00%s", "--name-only"])?
+        .split('\0')
+        .filter(|commit| !commit.trim().is_empty())
+        .map(|commit| {
+            let mut lines = commit.lines().filter(|line| !line.is_empty());
+            let subject = lines.next().unwrap_or_default();
+            let files: Vec<&str> = lines.collect();
+            format!("{subject} ({})", files.join(", "))
+        })
+        .collect();
+
+    Ok(json!({
+        "project": project,
+        "tasks": tasks,
+        "runs": runs,
+        "commits": commits,
+    }))
+}
+
+/// `event_type stage: message` for each event the run recorded.
+fn run_events(repo: &Path, run_id: &str) -> Vec<String> {
+    let path = repo
+        .join(".ccswarm/runs")
+        .join(run_id)
+        .join("events.ndjson");
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .map(|event| {
+            let kind = event["event_type"].as_str().unwrap_or("?");
+            let message = event["message"].as_str().unwrap_or("");
+            match event["stage"].as_str() {
+                Some(stage) => format!("{kind} {stage}: {message}"),
+                None => format!("{kind}: {message}"),
+            }
+        })
+        .collect()
+}
+
+/// Replace the scratch path and the task and run IDs (numbered in queue
+/// order), which differ on every run.
+fn normalize(outcome: Value, repo: &Path) -> Result<Value> {
+    let mut text = serde_json::to_string_pretty(&outcome)?;
+    text = text.replace(&repo.display().to_string(), "<repo>");
+    let mut ids: Vec<String> = Vec::new();
+    for task in outcome["tasks"].as_array().into_iter().flatten() {
+        ids.extend(task["id"].as_str().map(String::from));
+    }
+    let task_count = ids.len();
+    for run in outcome["runs"].as_array().into_iter().flatten() {
+        ids.extend(run["run"].as_str().map(String::from));
+    }
+    for (i, id) in ids.iter().enumerate() {
+        let label = if i < task_count {
+            format!("<task-{}>", i + 1)
+        } else {
+            format!("<run-{}>", i - task_count + 1)
+        };
+        text = text.replace(id.as_str(), &label);
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+fn scenario_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(SCENARIO_DIR)
+}
+
+/// Play the script at `path` and compare with its golden file, or rewrite
+/// the golden file when [`UPDATE_ENV`] is set.
+async fn check(path: &Path) -> Result<()> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let scenario: Scenario = serde_yml::from_str(&raw)
+        .with_context(|| format!("Invalid scenario {}", path.display()))?;
+    let outcome = play(&scenario)
+        .await
+        .with_context(|| format!("Scenario {} failed", path.display()))?;
+    let golden_path = path.with_extension("golden.json");
+    let actual = serde_json::to_string_pretty(&outcome)? + "\n";
+    if std::env::var_os(UPDATE_ENV).is_some_and(|v| !v.is_empty()) {
+        std::fs::write(&golden_path, actual)?;
+        return Ok(());
+    }
+    let expected = std::fs::read_to_string(&golden_path).with_context(|| {
+        format!(
+            "No golden file {}; run with {UPDATE_ENV}=1 to create it",
+            golden_path.display()
+        )
+    })?;
+    pretty_assertions::assert_eq!(
+        expected,
+        actual,
+        "{} differs from {}",
+        path.display(),
+        golden_path.display()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scenarios_match_their_golden_files() -> Result<()> {
+    let mut scripts: Vec<PathBuf> = std::fs::read_dir(scenario_dir())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no scenarios in {SCENARIO_DIR}");
+    // Everything a run writes belongs in its scratch repo, not in the
+    // directory the tests happen to run from.
+    let stray_runs = || -> BTreeSet<PathBuf> {
+        std::fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(".ccswarm/runs"))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect()
+    };
+    let before = stray_runs();
+    for script in scripts {
+        check(&script).await?;
+    }
+    let leaked: Vec<_> = stray_runs().difference(&before).cloned().collect();
+    assert!(
+        leaked.is_empty(),
+        "scenarios wrote outside their repo: {leaked:?}"
+    );
+    Ok(())
+}
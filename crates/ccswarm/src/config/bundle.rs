@@ -0,0 +1,455 @@
+//! Portable swarm setup for `ccswarm bundle export` and `ccswarm bundle import`.
+//!
+//! A bundle is a tar archive with a `manifest.json` and the files that define
+//! how a project's swarm behaves:
+//!
+//! - `ccswarm.json`: agents, humans, quality gates, retry, redaction and the
+//!   other settings
+//! - `DOCTRINE.md`: the adopted project doctrine
+//! - `.ccswarm/flows/`: project flows
+//! - `.ccswarm/facets/`: personas, policies and knowledge, the prompts stages
+//!   are built from
+//! - `home/templates/scaffold/`: the user's scaffold templates, imported into
+//!   the ccswarm home directory
+//!
+//! Secrets stay behind. Files that look like credentials (`.env`, keys,
+//! anything named `secret` or `credential`) are skipped. Config keys and
+//! YAML lines that name a password, token, API key or client secret keep
+//! their key but lose their value. The manifest lists everything left out so
+//! the importer knows what to fill in.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+pub const MANIFEST_ENTRY: &str = "manifest.json";
+pub const CONFIG_ENTRY: &str = "ccswarm.json";
+
+/// Bumped when the archive layout changes incompatibly.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Repository files and directories carried by a bundle.
+const PROJECT_FILES: &[&str] = &[crate::workflow::doctrine::DOCTRINE_FILE];
+const PROJECT_DIRS: &[&str] = &[".ccswarm/flows", ".ccswarm/facets"];
+
+/// Entries under this prefix belong in the ccswarm home directory.
+const HOME_PREFIX: &str = "home";
+const HOME_DIRS: &[&str] = &["templates/scaffold"];
+
+/// Key names whose values are never exported (`webhook_secret`, `api_key`,
+/// `GITHUB_TOKEN`; not `max_tokens`).
+static SECRET_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(password|passwd|secret|api[_-]?key|token|bearer|private[_-]?key|credentials?)$",
+    )
+    .expect("valid regex")
+});
+
+/// `key: value` / `key = value` lines with a secret-looking key.
+static SECRET_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)^(\s*-?\s*["']?[\w.-]*(password|passwd|secret|api[_-]?key|token|private[_-]?key)["']?\s*[:=]\s*)(\S.*)$"#)
+        .expect("valid regex")
+});
+
+/// What a bundle holds and what was left out of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub ccswarm_version: String,
+    pub project: String,
+    pub created_at: DateTime<Utc>,
+    /// Archive entries besides the manifest, in archive order.
+    pub files: Vec<String>,
+    /// Skipped files and blanked values, e.g. `.env` or
+    /// `ccswarm.json: humans.alice.notify_token`.
+    #[serde(default)]
+    pub omitted: Vec<String>,
+}
+
+/// A bundle in memory: the manifest and each entry's path and contents.
+#[derive(Debug)]
+pub struct Bundle {
+    pub manifest: Manifest,
+    pub entries: Vec<(String, Vec<u8>)>,
+}
+
+impl Bundle {
+    /// Gather the swarm setup of `repo`. `config` is the project's
+    /// `ccswarm.json`; `home` is the ccswarm home directory, if known.
+    pub fn collect(repo: &Path, config: &serde_json::Value, home: Option<&Path>) -> Result<Self> {
+        let mut omitted = Vec::new();
+        let mut config = config.clone();
+        blank_secrets(&mut config, "", &mut omitted);
+        let project = config
+            .pointer("/project/name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let mut entries = vec![(
+            CONFIG_ENTRY.to_string(),
+            serde_json::to_vec_pretty(&config)?,
+        )];
+
+        for file in PROJECT_FILES {
+            let path = repo.join(file);
+            if path.is_file() {
+                add_file(&mut entries, &mut omitted, file.to_string(), &path)?;
+            }
+        }
+        for dir in PROJECT_DIRS {
+            add_dir(&mut entries, &mut omitted, repo, Path::new(dir), "")?;
+        }
+        if let Some(home) = home {
+            for dir in HOME_DIRS {
+                add_dir(
+                    &mut entries,
+                    &mut omitted,
+                    home,
+                    Path::new(dir),
+                    HOME_PREFIX,
+                )?;
+            }
+        }
+
+        Ok(Self {
+            manifest: Manifest {
+                format: FORMAT_VERSION,
+                ccswarm_version: env!("CARGO_PKG_VERSION").to_string(),
+                project,
+                created_at: Utc::now(),
+                files: entries.iter().map(|(name, _)| name.clone()).collect(),
+                omitted,
+            },
+            entries,
+        })
+    }
+
+    /// The bundle as a tar archive.
+    pub fn to_tar(&self) -> Result<Vec<u8>> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mtime = self.manifest.created_at.timestamp().max(0) as u64;
+        let manifest = serde_json::to_vec_pretty(&self.manifest)?;
+        let all = std::iter::once((MANIFEST_ENTRY, manifest.as_slice())).chain(
+            self.entries
+                .iter()
+                .map(|(name, data)| (name.as_str(), data.as_slice())),
+        );
+        for (name, data) in all {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            builder
+                .append_data(&mut header, name, data)
+                .with_context(|| format!("Failed to add {name} to the bundle"))?;
+        }
+        Ok(builder.into_inner()?)
+    }
+
+    /// Read a bundle written by [`Bundle::to_tar`], rejecting entries outside
+    /// the bundle layout.
+    pub fn from_tar(bytes: &[u8]) -> Result<Self> {
+        let mut archive = tar::Archive::new(bytes);
+        let mut manifest = None;
+        let mut entries = Vec::new();
+        for entry in archive.entries().context("Not a ccswarm bundle")? {
+            let mut entry = entry.context("Corrupt bundle entry")?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = entry.path()?.to_string_lossy().to_string();
+            let mut data = Vec::new();
+            std::io::Read::read_to_end(&mut entry, &mut data)?;
+            if name == MANIFEST_ENTRY {
+                manifest = Some(
+                    serde_json::from_slice::<Manifest>(&data).context("Invalid bundle manifest")?,
+                );
+            } else {
+                check_entry(&name)?;
+                entries.push((name, data));
+            }
+        }
+        let manifest = manifest.context("Bundle has no manifest.json")?;
+        if manifest.format > FORMAT_VERSION {
+            bail!(
+                "Bundle format {} is newer than this ccswarm supports ({}); upgrade ccswarm",
+                manifest.format,
+                FORMAT_VERSION
+            );
+        }
+        Ok(Self { manifest, entries })
+    }
+}
+
+/// Where entry `name` is written on import: the config to `config_path`,
+/// `home/...` under `home`, the rest under `repo`. `None` for home entries
+/// when there is no home directory.
+pub fn destination(
+    name: &str,
+    repo: &Path,
+    config_path: &Path,
+    home: Option<&Path>,
+) -> Option<PathBuf> {
+    if name == CONFIG_ENTRY {
+        return Some(config_path.to_path_buf());
+    }
+    match name
+        .strip_prefix(HOME_PREFIX)
+        .and_then(|rest| rest.strip_prefix('/'))
+    {
+        Some(rest) => home.map(|home| home.join(rest)),
+        None => Some(repo.join(name)),
+    }
+}
+
+/// Point an imported `ccswarm.json` at the repository it was imported into.
+/// Remote repository URLs are kept; local paths from the exporting machine
+/// are not.
+pub fn localize(config: &mut serde_json::Value, repo: &Path) {
+    let Some(repository) = config
+        .pointer_mut("/project/repository")
+        .and_then(|v| v.as_object_mut())
+    else {
+        return;
+    };
+    let url = repository
+        .get("url")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    if !(url.contains("://") || url.starts_with("git@")) {
+        repository.insert(
+            "url".to_string(),
+            serde_json::Value::String(repo.to_string_lossy().to_string()),
+        );
+    }
+    repository.remove("local_path");
+}
+
+fn check_entry(name: &str) -> Result<()> {
+    let path = Path::new(name);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        bail!("Bundle entry '{name}' is not a plain relative path");
+    }
+    let under = |dir: &str| path.starts_with(dir) && path != Path::new(dir);
+    let known = name == CONFIG_ENTRY
+        || PROJECT_FILES.contains(&name)
+        || PROJECT_DIRS.iter().any(|dir| under(dir))
+        || HOME_DIRS
+            .iter()
+            .any(|dir| under(&format!("{HOME_PREFIX}/{dir}")));
+    if !known {
+        bail!("Unexpected bundle entry '{name}'");
+    }
+    Ok(())
+}
+
+fn add_dir(
+    entries: &mut Vec<(String, Vec<u8>)>,
+    omitted: &mut Vec<String>,
+    root: &Path,
+    dir: &Path,
+    prefix: &str,
+) -> Result<()> {
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(root.join(dir))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+    for path in files {
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let name = Path::new(prefix).join(relative);
+        add_file(entries, omitted, name.to_string_lossy().to_string(), &path)?;
+    }
+    Ok(())
+}
+
+fn add_file(
+    entries: &mut Vec<(String, Vec<u8>)>,
+    omitted: &mut Vec<String>,
+    name: String,
+    path: &Path,
+) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if is_secret_file(&file_name) {
+        omitted.push(name);
+        return Ok(());
+    }
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let data = match String::from_utf8(data) {
+        Ok(text) => blank_secret_lines(&name, &text, omitted).into_bytes(),
+        Err(e) => e.into_bytes(),
+    };
+    entries.push((name, data));
+    Ok(())
+}
+
+fn is_secret_file(name: &str) -> bool {
+    name == ".env"
+        || name.starts_with(".env.")
+        || [".pem", ".key", ".p12", ".pfx"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+        || name.contains("secret")
+        || name.contains("credential")
+}
+
+/// Empty every string value whose key names a secret.
+fn blank_secrets(value: &mut serde_json::Value, at: &str, omitted: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                let path = if at.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{at}.{key}")
+                };
+                match child {
+                    serde_json::Value::String(s) if SECRET_KEY.is_match(key) && !s.is_empty() => {
+                        s.clear();
+                        omitted.push(format!("{CONFIG_ENTRY}: {path}"));
+                    }
+                    _ => blank_secrets(child, &path, omitted),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                blank_secrets(child, &format!("{at}[{i}]"), omitted);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Empty the values of secret-looking `key: value` lines in a text file.
+fn blank_secret_lines(name: &str, text: &str, omitted: &mut Vec<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let (body, newline) = match line.strip_suffix('\n') {
+            Some(body) => (body, "\n"),
+            None => (line, ""),
+        };
+        match SECRET_LINE.captures(body) {
+            Some(caps) if !matches!(&caps[3], "\"\"" | "''" | "~" | "null") => {
+                out.push_str(&caps[1]);
+                out.push_str("\"\"");
+                omitted.push(format!("{name}:{}", i + 1));
+            }
+            _ => out.push_str(body),
+        }
+        out.push_str(newline);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_round_trips_without_secrets() {
+        let repo = tempfile::tempdir().unwrap();
+        let home = tempfile::tempdir().unwrap();
+        let write = |root: &Path, path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(repo.path(), "DOCTRINE.md", "# Doctrine\n");
+        write(
+            repo.path(),
+            ".ccswarm/flows/review.yaml",
+            "name: review\nenv:\n  GITHUB_TOKEN: ghp_abcdefgh12345678\n  api_key: \"\"\n",
+        );
+        write(
+            repo.path(),
+            ".ccswarm/facets/personas/coder.yaml",
+            "name: coder\n",
+        );
+        write(
+            repo.path(),
+            ".ccswarm/facets/.env",
+            "OPENAI_API_KEY=sk-123\n",
+        );
+        write(repo.path(), ".ccswarm/queue.yaml", "tasks: []\n");
+        write(
+            home.path(),
+            "templates/scaffold/api/template.yaml",
+            "name: api\n",
+        );
+        let config = serde_json::json!({
+            "project": { "name": "demo" },
+            "humans": { "alice": { "notify_command": "notify", "webhook_secret": "s3cr3t" } },
+        });
+
+        let bundle = Bundle::collect(repo.path(), &config, Some(home.path())).unwrap();
+        let read = Bundle::from_tar(&bundle.to_tar().unwrap()).unwrap();
+
+        assert_eq!(read.manifest.project, "demo");
+        assert_eq!(
+            read.manifest.files,
+            [
+                "ccswarm.json",
+                "DOCTRINE.md",
+                ".ccswarm/flows/review.yaml",
+                ".ccswarm/facets/personas/coder.yaml",
+                "home/templates/scaffold/api/template.yaml",
+            ]
+        );
+        assert_eq!(
+            read.manifest.omitted,
+            [
+                "ccswarm.json: humans.alice.webhook_secret",
+                ".ccswarm/flows/review.yaml:3",
+                ".ccswarm/facets/.env",
+            ]
+        );
+        let text = |name: &str| {
+            let (_, data) = read.entries.iter().find(|(n, _)| n == name).unwrap();
+            String::from_utf8(data.clone()).unwrap()
+        };
+        assert!(text("ccswarm.json").contains("\"webhook_secret\": \"\""));
+        assert!(text("ccswarm.json").contains("\"notify_command\": \"notify\""));
+        assert_eq!(
+            text(".ccswarm/flows/review.yaml"),
+            "name: review\nenv:\n  GITHUB_TOKEN: \"\"\n  api_key: \"\"\n"
+        );
+
+        let config_path = repo.path().join("ccswarm.json");
+        assert_eq!(
+            destination(
+                "home/templates/scaffold/api/template.yaml",
+                repo.path(),
+                &config_path,
+                Some(home.path())
+            ),
+            Some(home.path().join("templates/scaffold/api/template.yaml"))
+        );
+        assert_eq!(
+            destination("ccswarm.json", repo.path(), &config_path, None),
+            Some(config_path)
+        );
+    }
+
+    #[test]
+    fn test_unexpected_entries_are_rejected() {
+        for name in [
+            "../evil.sh",
+            ".ccswarm/queue.yaml",
+            "/etc/passwd",
+            ".ccswarm/flows",
+        ] {
+            assert!(check_entry(name).is_err(), "{name}");
+        }
+        assert!(check_entry(".ccswarm/facets/personas/coder.yaml").is_ok());
+        assert!(check_entry("home/templates/scaffold/api/template.yaml").is_ok());
+    }
+}
@@ -1,3 +1,6 @@
+pub mod bundle;
+pub mod presets;
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -23,6 +26,17 @@ impl ThinkMode {
             ThinkMode::MegaThink => "megathink",
         }
     }
+
+    /// Next step up the thinking ladder; `UltraThink` is the ceiling.
+    pub fn escalate(&self) -> ThinkMode {
+        match self {
+            ThinkMode::Think => ThinkMode::ThinkHard,
+            ThinkMode::ThinkHard => ThinkMode::ThinkHarder,
+            ThinkMode::ThinkHarder | ThinkMode::MegaThink | ThinkMode::UltraThink => {
+                ThinkMode::UltraThink
+            }
+        }
+    }
 }
 
 impl std::fmt::Display for ThinkMode {
@@ -56,6 +70,7 @@ impl OutputFormat {
 
 /// Claude Code configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ClaudeConfig {
     /// Model to use (e.g., "claude-3.5-sonnet")
     pub model: String,
@@ -174,9 +189,33 @@ impl ClaudeConfig {
     }
 }
 
+/// Settings for an agent on another agent CLI (`codex_config`,
+/// `gemini_config`, `aider_config`), used whenever that provider runs it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderConfig {
+    /// Model passed to the CLI; a stage's own `model` wins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Extra command-line arguments, placed before the prompt
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+impl ProviderConfig {
+    pub fn is_empty(&self) -> bool {
+        self.model.is_none() && self.args.is_empty()
+    }
+}
+
 /// Agent configuration from JSON
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AgentConfig {
+    /// Shared preset the other fields were filled from (see [`presets`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+
     /// Agent specialization
     pub specialization: String,
 
@@ -187,10 +226,65 @@ pub struct AgentConfig {
     pub branch: String,
 
     /// Claude configuration
+    #[serde(default)]
     pub claude_config: ClaudeConfig,
 
     /// CLAUDE.md template to use
+    #[serde(default)]
     pub claude_md_template: String,
+
+    /// Provider this agent runs on: claude, codex, gemini, aider, copilot or
+    /// mock. A stage's own `provider` wins; this wins over `--provider`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Codex CLI settings
+    #[serde(default, skip_serializing_if = "ProviderConfig::is_empty")]
+    pub codex_config: ProviderConfig,
+
+    /// Gemini CLI settings
+    #[serde(default, skip_serializing_if = "ProviderConfig::is_empty")]
+    pub gemini_config: ProviderConfig,
+
+    /// Aider settings
+    #[serde(default, skip_serializing_if = "ProviderConfig::is_empty")]
+    pub aider_config: ProviderConfig,
+
+    /// Tools the agent may use; empty leaves the provider default
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+
+    /// Checks the agent's work has to pass, on top of `quality.checks`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quality_gates: Vec<crate::workflow::quality_checks::QualityCheck>,
+
+    /// Language the agent works in; overrides `i18n.working_language`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+
+    /// IANA time zone the agent works in; overrides `i18n.timezone`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// Files and directories a newly created worktree starts with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scaffold: Vec<crate::git::scaffold::ScaffoldEntry>,
+}
+
+impl AgentConfig {
+    /// The agent's settings for `provider`; Claude's live in `claude_config`.
+    pub(crate) fn provider_config(
+        &self,
+        provider: crate::providers::ProviderKind,
+    ) -> Option<&ProviderConfig> {
+        use crate::providers::ProviderKind;
+        match provider {
+            ProviderKind::Codex => Some(&self.codex_config),
+            ProviderKind::Gemini => Some(&self.gemini_config),
+            ProviderKind::Aider => Some(&self.aider_config),
+            ProviderKind::Claude | ProviderKind::Copilot | ProviderKind::Mock => None,
+        }
+    }
 }
 
 /// Project configuration
@@ -222,6 +316,17 @@ pub struct RepositoryConfig {
     /// Enable worktree isolation for task execution
     #[serde(default)]
     pub worktree_isolation: bool,
+    /// Disk quota per agent worktree, e.g. `5G` (see `ccswarm worktree usage`)
+    #[serde(default)]
+    pub worktree_quota: Option<String>,
+    /// What `ccswarm worktree bootstrap` does with agent worktrees that
+    /// already exist: adopt, reset or recreate
+    #[serde(default)]
+    pub worktree_policy: crate::git::bootstrap::WorktreePolicy,
+    /// How worktree, branch and status operations reach git: `shell` (the
+    /// git CLI) or `native` (libgit2, with the `native-git` feature)
+    #[serde(default)]
+    pub git_backend: crate::git::GitBackend,
 }
 
 impl Default for RepositoryConfig {
@@ -231,6 +336,9 @@ impl Default for RepositoryConfig {
             main_branch: "main".to_string(),
             local_path: None,
             worktree_isolation: false,
+            worktree_quota: None,
+            worktree_policy: Default::default(),
+            git_backend: Default::default(),
         }
     }
 }
@@ -289,8 +397,19 @@ impl Default for MasterClaudeConfig {
 pub struct CoordinationConfig {
     pub communication_method: String,
     pub sync_interval: u64,
+    /// `on_commit` makes `ccswarm worktree create` install the commit hooks
+    /// (see [`crate::git::hooks`]).
     pub quality_gate_frequency: String,
     pub master_review_trigger: String,
+    /// How waiting loops notice queue changes: `auto` (file-system events,
+    /// else polling), `events` or `polling`. Polling backs off up to
+    /// `sync_interval` seconds.
+    #[serde(default)]
+    pub watch: crate::governance::watch::WatchMode,
+    /// Task ordering and fairness: priority aging, round-robin between
+    /// equally loaded agents, and per-agent queue depth.
+    #[serde(default)]
+    pub scheduling: ai_session::coordination::SchedulingPolicy,
 }
 
 impl Default for CoordinationConfig {
@@ -300,6 +419,8 @@ impl Default for CoordinationConfig {
             sync_interval: 30,
             quality_gate_frequency: "on_task_completion".to_string(),
             master_review_trigger: "auto".to_string(),
+            watch: Default::default(),
+            scheduling: Default::default(),
         }
     }
 }
@@ -310,7 +431,123 @@ pub struct CcswarmConfig {
     pub project: ProjectConfig,
     #[serde(default)]
     pub agents: HashMap<String, AgentConfig>,
+    /// People who take delegated tasks through `ccswarm human inbox`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub humans: HashMap<String, crate::governance::human::HumanAgentConfig>,
     pub coordination: CoordinationConfig,
+    /// Automatic retry policies for queued tasks.
+    #[serde(default)]
+    pub retry: crate::workflow::retry_policy::RetryPolicies,
+    /// Regex rules applied to command and provider output before it is stored.
+    #[serde(default)]
+    pub redaction: crate::redaction::RedactionConfig,
+    /// Prompt-injection defense for output fed back to agents.
+    #[serde(default)]
+    pub prompt_guard: crate::security::prompt_guard::PromptGuardConfig,
+    /// Extra diagnostic parsers for tool output, tried before the built-in
+    /// eslint/tsc/go vet/gradle/terraform ones.
+    #[serde(default)]
+    pub parsers: Vec<ai_session::output::ParserConfig>,
+    /// How aggressively long gate and tool output is cut to its salient
+    /// lines before it reaches a model.
+    #[serde(default)]
+    pub output_sampling: ai_session::output::SamplingConfig,
+    /// Limits for temporary sub-agents that flow stages may spawn.
+    #[serde(default)]
+    pub subagents: crate::workflow::subagent::SubagentConfig,
+    /// Settings for quality gates.
+    #[serde(default)]
+    pub quality: QualityConfig,
+    /// Subsystems supervised by `ccswarm daemon`.
+    #[serde(default)]
+    pub daemon: crate::daemon::DaemonConfig,
+    /// Which typed-tools commands run unreviewed; riskier ones wait for approval.
+    #[serde(default)]
+    pub auto_accept: crate::session::AutoAcceptConfig,
+    /// Progress summaries typed-tools commands send while they run.
+    #[serde(default)]
+    pub partial_output: crate::session::tools::PartialOutputConfig,
+    /// Shared task pool for `ccswarm cluster` (experimental).
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    /// Model prices and the per-task cost ceiling for estimates.
+    #[serde(default)]
+    pub cost: crate::workflow::cost_estimate::CostConfig,
+    /// Roles that keep a standby shadow agent for failover, by role.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub shadows: std::collections::BTreeMap<String, crate::session::shadow::ShadowConfig>,
+    /// CLI language, and the language and time zone agents work in.
+    #[serde(default)]
+    pub i18n: crate::i18n::I18nConfig,
+    /// Embeddings behind `ccswarm context search`.
+    #[serde(default)]
+    pub context_search: crate::session::search::Embedder,
+    /// Cheaper models for simple tasks, the flagship for complex ones.
+    #[serde(default)]
+    pub model_routing: crate::providers::routing::ModelRoutingConfig,
+    /// When to intervene in an agent's runaway command loop.
+    #[serde(default)]
+    pub supervisor: crate::workflow::supervisor::SupervisorConfig,
+    /// Size budget of each agent's persistent scratchpad.
+    #[serde(default)]
+    pub scratchpad: crate::agent::scratchpad::ScratchpadConfig,
+    /// Health probes run before a task that needs external services starts.
+    #[serde(default)]
+    pub preflight: crate::workflow::preflight::PreflightConfig,
+    /// Desktop toasts for completed tasks, pending approvals and failed gates.
+    #[serde(default)]
+    pub notifications: crate::notify::NotificationsConfig,
+    /// Which secrets each agent role may receive.
+    #[serde(default)]
+    pub secrets: crate::secrets::SecretsConfig,
+    /// Concurrency and rate limits on provider calls across all processes.
+    #[serde(default)]
+    pub scheduler: crate::providers::scheduler::SchedulerConfig,
+}
+
+/// Quality gate settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityConfig {
+    /// Environment matrix run by the `test_matrix` gate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<crate::workflow::test_matrix::TestMatrix>,
+    /// Checks run by `ccswarm quality`; Cargo projects default to fmt,
+    /// clippy, build, test and audit.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<crate::workflow::quality_checks::QualityCheck>,
+    /// Named sets of checks (see [`crate::workflow::quality_profiles`]).
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub profiles:
+        std::collections::BTreeMap<String, crate::workflow::quality_profiles::QualityProfile>,
+    /// Branch patterns mapped to profiles; the first match wins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<crate::workflow::quality_profiles::BranchProfile>,
+}
+
+/// Cluster mode settings: several instances sharing one task pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClusterConfig {
+    /// Pool location: `dir:<shared path>` or `redis://host` (needs the
+    /// `cluster-redis` feature). `ccswarm cluster --pool` overrides it.
+    pub pool: Option<String>,
+    /// Name this instance reports as; defaults to `<hostname>-<pid>`.
+    pub node: Option<String>,
+    /// How long a claimed task stays leased without a renewal.
+    pub lease_secs: u64,
+    /// How often an idle worker looks for new tasks.
+    pub poll_secs: u64,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            pool: None,
+            node: None,
+            lease_secs: 300,
+            poll_secs: 10,
+        }
+    }
 }
 
 impl CcswarmConfig {
@@ -319,14 +556,29 @@ impl CcswarmConfig {
         let contents = tokio::fs::read_to_string(&path).await.map_err(|e| {
             anyhow::anyhow!("Failed to read config file '{}': {}", path.display(), e)
         })?;
-        let config: Self = serde_json::from_str(&contents)
-            .map_err(|e| anyhow::anyhow!("Invalid JSON in '{}': {}", path.display(), e))?;
+        let config = Self::from_json(&contents, &path).await?;
         config.validate().map_err(|e| {
             anyhow::anyhow!("Config validation failed for '{}': {}", path.display(), e)
         })?;
         Ok(config)
     }
 
+    /// Parse the contents of the config file at `path`, expanding agent
+    /// presets relative to its directory. Does not validate.
+    pub async fn from_json(contents: &str, path: &std::path::Path) -> anyhow::Result<Self> {
+        let mut raw: serde_json::Value = serde_json::from_str(contents)
+            .map_err(|e| anyhow::anyhow!("Invalid JSON in '{}': {}", path.display(), e))?;
+        let project_dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(std::path::Path::new("."));
+        presets::PresetResolver::new(project_dir)
+            .expand(&mut raw)
+            .await?;
+        serde_json::from_value(raw)
+            .map_err(|e| anyhow::anyhow!("Invalid config in '{}': {}", path.display(), e))
+    }
+
     /// Save configuration to file
     pub async fn to_file(&self, path: PathBuf) -> anyhow::Result<()> {
         let contents = serde_json::to_string_pretty(self)?;
@@ -371,6 +623,25 @@ impl CcswarmConfig {
             if agent.branch.trim().is_empty() {
                 anyhow::bail!("agents.{}.branch must not be empty", name);
             }
+            if let Some(provider) = &agent.provider
+                && crate::providers::ProviderKind::parse(provider).is_none()
+            {
+                anyhow::bail!(
+                    "agents.{}.provider '{}' is not one of claude, codex, gemini, aider, copilot, mock",
+                    name,
+                    provider
+                );
+            }
+            if let Some(timezone) = &agent.timezone {
+                crate::i18n::parse_timezone(timezone)
+                    .map_err(|e| anyhow::anyhow!("agents.{}.timezone: {}", name, e))?;
+            }
+        }
+
+        // Time zones must be IANA names
+        if let Some(timezone) = &self.i18n.timezone {
+            crate::i18n::parse_timezone(timezone)
+                .map_err(|e| anyhow::anyhow!("i18n.timezone: {}", e))?;
         }
 
         // Sync interval must be positive
@@ -378,6 +649,25 @@ impl CcswarmConfig {
             anyhow::bail!("coordination.sync_interval must be greater than 0");
         }
 
+        // Redaction patterns must compile
+        self.redaction.compile()?;
+        self.prompt_guard.compile()?;
+
+        // Daemon subsystems must be runnable
+        self.daemon.validate()?;
+
+        // Diagnostic parser patterns must compile
+        for parser in &self.parsers {
+            parser.compile()?;
+        }
+
+        // A configured test matrix must have at least one cell
+        if let Some(matrix) = &self.quality.matrix {
+            matrix.validate()?;
+        }
+        crate::workflow::quality_profiles::validate(&self.quality)?;
+        self.model_routing.validate()?;
+
         Ok(())
     }
 }
@@ -406,6 +696,30 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_config_validation_rejects_invalid_redaction_pattern() {
+        let mut config = CcswarmConfig::default();
+        config
+            .redaction
+            .rules
+            .push(crate::redaction::RedactionRule {
+                pattern: "[unclosed".to_string(),
+                replacement: "x".to_string(),
+            });
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_rejects_parser_without_message_group() {
+        let mut config = CcswarmConfig::default();
+        config.parsers.push(ai_session::output::ParserConfig {
+            name: "mypy".to_string(),
+            pattern: r"^(?P<file>\S+):(?P<line>\d+)".to_string(),
+            severity: "error".to_string(),
+        });
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_config_validation_invalid_threshold() {
         let mut config = CcswarmConfig::default();
@@ -413,6 +727,25 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_coordination_scheduling_defaults_when_omitted() {
+        let coordination: CoordinationConfig = serde_json::from_value(serde_json::json!({
+            "communication_method": "json",
+            "sync_interval": 30,
+            "quality_gate_frequency": "on_task_completion",
+            "master_review_trigger": "auto",
+            "scheduling": {"max_queue_depth": 3}
+        }))
+        .unwrap();
+        assert_eq!(coordination.scheduling.max_queue_depth, 3);
+        assert_eq!(coordination.scheduling.aging_secs, 300);
+        assert!(coordination.scheduling.round_robin);
+        assert_eq!(
+            coordination.watch,
+            crate::governance::watch::WatchMode::Auto
+        );
+    }
+
     #[test]
     fn test_config_validation_zero_sync_interval() {
         let mut config = CcswarmConfig::default();
@@ -0,0 +1,521 @@
+//! Shareable agent presets referenced from `ccswarm.json`.
+//!
+//! An agent entry can name a preset instead of spelling out its role prompt,
+//! tools, quality gates and provider settings:
+//!
+//! ```json
+//! "agents": {
+//!   "backend": { "preset": "github:acme/ccswarm-presets@v1.2.0#backend-go" },
+//!   "frontend": { "preset": "react-ts", "branch": "feature/web" }
+//! }
+//! ```
+//!
+//! Reference forms:
+//!
+//! - `github:<owner>/<repo>[@<rev>]#<name>`: a preset in a GitHub repository
+//! - `git+<url>[@<rev>]#<name>`: a preset in any git repository
+//! - `file:<dir>#<name>`: a preset in a local registry directory
+//! - `<name>`: looked up in `.ccswarm/presets/`, then `$CCSWARM_HOME/presets/`
+//!
+//! A preset is `<name>.json`, `.yaml` or `.yml` at the root of the registry
+//! or under its `presets/` directory, holding any agent fields
+//! (`specialization`, `claude_config`, `provider`, `tools`, `quality_gates`,
+//! ...). Fields set on the agent entry itself win over the preset's.
+//!
+//! Git registries are cloned once into `$CCSWARM_HOME/cache/presets/` and
+//! read from there afterwards. A pinned `@<rev>` (tag, branch or commit) is
+//! never fetched again; unpinned checkouts are refreshed by
+//! `ccswarm config presets --refresh`.
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::git::shell::git;
+use crate::workflow::facets::ccswarm_home;
+
+/// Project registry directory for bare preset names, relative to the repo.
+pub const PROJECT_PRESETS_DIR: &str = ".ccswarm/presets";
+
+/// Metadata written next to a cached git checkout.
+const CACHE_META: &str = ".ccswarm-preset.json";
+
+/// Where a preset comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresetSource {
+    /// Bare name, looked up in the project and user registries.
+    Registry,
+    /// A local registry directory.
+    Dir(PathBuf),
+    /// A git repository, optionally pinned to a revision.
+    Git { url: String, rev: Option<String> },
+}
+
+/// A parsed `preset` reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresetRef {
+    pub source: PresetSource,
+    pub name: String,
+}
+
+impl PresetRef {
+    pub fn parse(reference: &str) -> Result<Self> {
+        let reference = reference.trim();
+        let Some((location, name)) = reference.rsplit_once('#') else {
+            if reference.is_empty() || reference.contains(['/', ':']) {
+                bail!("preset reference '{reference}' needs a '#<name>' suffix");
+            }
+            return Ok(Self {
+                source: PresetSource::Registry,
+                name: reference.to_string(),
+            });
+        };
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            bail!("invalid preset name '{name}' in '{reference}'");
+        }
+
+        let source = if let Some(repo) = location.strip_prefix("github:") {
+            let (repo, rev) = split_rev(repo);
+            if repo.split('/').filter(|p| !p.is_empty()).count() != 2 {
+                bail!("expected github:<owner>/<repo> in '{reference}'");
+            }
+            PresetSource::Git {
+                url: format!("https://github.com/{repo}.git"),
+                rev,
+            }
+        } else if let Some(url) = location.strip_prefix("git+") {
+            let (url, rev) = split_rev(url);
+            PresetSource::Git {
+                url: url.to_string(),
+                rev,
+            }
+        } else if let Some(dir) = location.strip_prefix("file:") {
+            PresetSource::Dir(PathBuf::from(dir))
+        } else {
+            bail!(
+                "unknown preset source '{location}' (expected github:, git+ or file:) in '{reference}'"
+            );
+        };
+        Ok(Self {
+            source,
+            name: name.to_string(),
+        })
+    }
+
+    /// Whether the reference names a fixed revision.
+    pub fn is_pinned(&self) -> bool {
+        match &self.source {
+            PresetSource::Git { rev, .. } => rev.is_some(),
+            PresetSource::Registry | PresetSource::Dir(_) => true,
+        }
+    }
+}
+
+/// Split a trailing `@<rev>` off a repository location. Only an `@` after the
+/// last `/` counts, so `git@host:org/repo.git` keeps its user part.
+fn split_rev(location: &str) -> (&str, Option<String>) {
+    let tail_start = location.rfind('/').map_or(0, |i| i + 1);
+    match location[tail_start..].rfind('@') {
+        Some(at) if at > 0 => {
+            let at = tail_start + at;
+            let rev = &location[at + 1..];
+            (&location[..at], (!rev.is_empty()).then(|| rev.to_string()))
+        }
+        _ => (location, None),
+    }
+}
+
+/// A git registry checkout in the local cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedCheckout {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    /// Commit the checkout is at.
+    pub commit: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// A preset resolved for one agent.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedPreset {
+    pub agent: String,
+    pub reference: String,
+    /// File the preset was read from.
+    pub path: PathBuf,
+    /// Cache metadata when the preset came from git.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkout: Option<CachedCheckout>,
+}
+
+/// Resolves preset references against the registries and the git cache.
+#[derive(Debug, Clone)]
+pub struct PresetResolver {
+    project_dir: PathBuf,
+    home: Option<PathBuf>,
+    refresh: bool,
+}
+
+impl PresetResolver {
+    /// Resolver for the project rooted at `project_dir` (where `ccswarm.json` lives).
+    pub fn new(project_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            project_dir: project_dir.into(),
+            home: ccswarm_home(),
+            refresh: false,
+        }
+    }
+
+    /// Use `home` instead of `$CCSWARM_HOME` for the user registry and cache.
+    pub fn with_home(mut self, home: impl Into<PathBuf>) -> Self {
+        self.home = Some(home.into());
+        self
+    }
+
+    /// Re-fetch unpinned git checkouts instead of reading them from the cache.
+    pub fn refreshing(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Replace every `agents.<name>.preset` in a raw config with the preset's
+    /// fields merged under the agent's own, filling in a worktree and branch
+    /// derived from the agent name when neither sets one.
+    pub async fn expand(&self, config: &mut serde_json::Value) -> Result<Vec<ResolvedPreset>> {
+        let mut resolved = Vec::new();
+        let Some(agents) = config.get_mut("agents").and_then(|a| a.as_object_mut()) else {
+            return Ok(resolved);
+        };
+        for (agent, entry) in agents.iter_mut() {
+            let Some(reference) = entry.get("preset").and_then(|p| p.as_str()) else {
+                continue;
+            };
+            let reference = reference.to_string();
+            let preset_ref =
+                PresetRef::parse(&reference).with_context(|| format!("agents.{agent}.preset"))?;
+            let (path, checkout) = self
+                .locate(&preset_ref)
+                .await
+                .with_context(|| format!("agents.{agent}.preset '{reference}'"))?;
+            let mut merged = read_preset(&path).await?;
+            merge(&mut merged, entry.take());
+            if let Some(fields) = merged.as_object_mut() {
+                fields
+                    .entry("worktree")
+                    .or_insert_with(|| format!("../worktrees/{agent}-agent").into());
+                fields
+                    .entry("branch")
+                    .or_insert_with(|| format!("feature/{agent}").into());
+            }
+            *entry = merged;
+            resolved.push(ResolvedPreset {
+                agent: agent.clone(),
+                reference,
+                path,
+                checkout,
+            });
+        }
+        Ok(resolved)
+    }
+
+    async fn locate(&self, preset: &PresetRef) -> Result<(PathBuf, Option<CachedCheckout>)> {
+        match &preset.source {
+            PresetSource::Registry => {
+                let mut dirs = vec![self.project_dir.join(PROJECT_PRESETS_DIR)];
+                dirs.extend(self.home.as_ref().map(|home| home.join("presets")));
+                for dir in &dirs {
+                    if let Some(path) = find_preset_file(dir, &preset.name) {
+                        return Ok((path, None));
+                    }
+                }
+                Err(anyhow!(
+                    "no preset '{}' in {}",
+                    preset.name,
+                    dirs.iter()
+                        .map(|d| d.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                ))
+            }
+            PresetSource::Dir(dir) => {
+                let dir = self.project_dir.join(dir);
+                find_preset_file(&dir, &preset.name)
+                    .map(|path| (path, None))
+                    .ok_or_else(|| anyhow!("no preset '{}' in {}", preset.name, dir.display()))
+            }
+            PresetSource::Git { url, rev } => {
+                let (dir, checkout) = self.checkout(url, rev.as_deref()).await?;
+                let path = find_preset_file(&dir, &preset.name).ok_or_else(|| {
+                    anyhow!(
+                        "no preset '{}' in {} ({})",
+                        preset.name,
+                        url,
+                        checkout.commit
+                    )
+                })?;
+                Ok((path, Some(checkout)))
+            }
+        }
+    }
+
+    /// Cached checkout of `url` at `rev`, cloning it on first use.
+    async fn checkout(&self, url: &str, rev: Option<&str>) -> Result<(PathBuf, CachedCheckout)> {
+        let home = self
+            .home
+            .as_ref()
+            .ok_or_else(|| anyhow!("Could not determine the ccswarm home directory"))?;
+        let dir = home
+            .join("cache")
+            .join("presets")
+            .join(cache_key(url))
+            .join(rev.map_or_else(|| "HEAD".to_string(), cache_key));
+
+        let stale = self.refresh && rev.is_none();
+        if !stale && let Some(meta) = read_cache_meta(&dir).await {
+            return Ok((dir, meta));
+        }
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir)
+                .await
+                .with_context(|| format!("Failed to clear {}", dir.display()))?;
+        }
+        if let Some(parent) = dir.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let target = dir.to_string_lossy().into_owned();
+        let mut clone = vec!["clone", "--quiet", "--depth", "1"];
+        if let Some(rev) = rev {
+            clone.extend(["--branch", rev]);
+        }
+        clone.extend([url, &target]);
+        if let Err(e) = git(Path::new("."), &clone).await {
+            // `--branch` only takes branches and tags; a commit needs a full clone.
+            let Some(rev) = rev else {
+                return Err(e);
+            };
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+            git(Path::new("."), &["clone", "--quiet", url, &target]).await?;
+            git(&dir, &["checkout", "--quiet", rev]).await?;
+        }
+
+        let meta = CachedCheckout {
+            url: url.to_string(),
+            rev: rev.map(str::to_string),
+            commit: git(&dir, &["rev-parse", "HEAD"]).await?.trim().to_string(),
+            fetched_at: Utc::now(),
+        };
+        tokio::fs::write(dir.join(CACHE_META), serde_json::to_string_pretty(&meta)?).await?;
+        Ok((dir, meta))
+    }
+}
+
+async fn read_cache_meta(dir: &Path) -> Option<CachedCheckout> {
+    let content = tokio::fs::read_to_string(dir.join(CACHE_META)).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Filesystem-safe directory name for a URL or revision.
+fn cache_key(value: &str) -> String {
+    value
+        .trim_end_matches(".git")
+        .split("://")
+        .last()
+        .unwrap_or(value)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn find_preset_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    [dir.to_path_buf(), dir.join("presets")]
+        .into_iter()
+        .flat_map(|base| ["json", "yaml", "yml"].map(|ext| base.join(format!("{name}.{ext}"))))
+        .find(|path| path.is_file())
+}
+
+async fn read_preset(path: &Path) -> Result<serde_json::Value> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read preset {}", path.display()))?;
+    let value: serde_json::Value = if path.extension().is_some_and(|e| e == "json") {
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid JSON in preset {}", path.display()))?
+    } else {
+        serde_yml::from_str(&content)
+            .with_context(|| format!("Invalid YAML in preset {}", path.display()))?
+    };
+    if !value.is_object() {
+        bail!(
+            "preset {} must be an object of agent fields",
+            path.display()
+        );
+    }
+    Ok(value)
+}
+
+/// Deep-merge `overlay` into `base`; objects merge key by key, anything else
+/// in `overlay` replaces what `base` had.
+fn merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_references() -> Result<()> {
+        let github = PresetRef::parse("github:acme/ccswarm-presets@v1.2.0#backend-go")?;
+        assert_eq!(
+            github.source,
+            PresetSource::Git {
+                url: "https://github.com/acme/ccswarm-presets.git".to_string(),
+                rev: Some("v1.2.0".to_string()),
+            }
+        );
+        assert_eq!(github.name, "backend-go");
+        assert!(github.is_pinned());
+
+        let ssh = PresetRef::parse("git+git@gitlab.com:acme/presets.git#qa")?;
+        assert_eq!(
+            ssh.source,
+            PresetSource::Git {
+                url: "git@gitlab.com:acme/presets.git".to_string(),
+                rev: None,
+            }
+        );
+        assert!(!ssh.is_pinned());
+
+        assert_eq!(
+            PresetRef::parse("file:../presets#react-ts")?.source,
+            PresetSource::Dir(PathBuf::from("../presets"))
+        );
+        assert_eq!(PresetRef::parse("react-ts")?.source, PresetSource::Registry);
+
+        assert!(PresetRef::parse("github:acme#x").is_err());
+        assert!(PresetRef::parse("github:acme/presets").is_err());
+        assert!(PresetRef::parse("s3:bucket#x").is_err());
+        assert!(PresetRef::parse("file:dir#../escape").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expand_merges_agent_fields_over_preset() -> Result<()> {
+        let project = tempfile::tempdir()?;
+        let home = tempfile::tempdir()?;
+        let registry = home.path().join("presets");
+        tokio::fs::create_dir_all(&registry).await?;
+        tokio::fs::write(
+            registry.join("backend-go.yaml"),
+            "specialization: go_services\n\
+             claude_md_template: backend_specialist\n\
+             provider: codex\n\
+             tools: [read, edit, bash]\n\
+             claude_config:\n  model: opus\n  append_system_prompt: You own the Go services.\n",
+        )
+        .await?;
+
+        let mut config = json!({
+            "agents": {
+                "backend": {
+                    "preset": "backend-go",
+                    "branch": "feature/api",
+                    "claude_config": { "model": "sonnet" }
+                },
+                "plain": { "specialization": "docs" }
+            }
+        });
+        let resolved = PresetResolver::new(project.path())
+            .with_home(home.path())
+            .expand(&mut config)
+            .await?;
+
+        assert_eq!(resolved.len(), 1);
+        let backend = &config["agents"]["backend"];
+        assert_eq!(backend["specialization"], "go_services");
+        assert_eq!(backend["branch"], "feature/api");
+        assert_eq!(backend["worktree"], "../worktrees/backend-agent");
+        assert_eq!(backend["claude_config"]["model"], "sonnet");
+        assert_eq!(
+            backend["claude_config"]["append_system_prompt"],
+            "You own the Go services."
+        );
+        assert_eq!(backend["preset"], "backend-go");
+        assert_eq!(
+            config["agents"]["plain"],
+            json!({ "specialization": "docs" })
+        );
+
+        let agent: crate::config::AgentConfig = serde_json::from_value(backend.clone())?;
+        assert_eq!(agent.provider.as_deref(), Some("codex"));
+        assert_eq!(agent.tools, ["read", "edit", "bash"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_git_preset_is_cloned_once_and_read_from_cache() -> Result<()> {
+        let project = tempfile::tempdir()?;
+        let home = tempfile::tempdir()?;
+        let upstream = tempfile::tempdir()?;
+        let repo = upstream.path();
+        git(repo, &["init", "--quiet"]).await?;
+        tokio::fs::create_dir_all(repo.join("presets")).await?;
+        tokio::fs::write(
+            repo.join("presets/qa.json"),
+            r#"{ "specialization": "qa" }"#,
+        )
+        .await?;
+        git(repo, &["add", "."]).await?;
+        git(
+            repo,
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                "qa",
+            ],
+        )
+        .await?;
+        git(repo, &["tag", "v1"]).await?;
+
+        let reference = format!("git+{}@v1#qa", repo.display());
+        let resolver = PresetResolver::new(project.path()).with_home(home.path());
+        let mut config = json!({ "agents": { "qa": { "preset": reference } } });
+        let resolved = resolver.expand(&mut config).await?;
+        let checkout = resolved[0].checkout.clone().expect("git checkout");
+        assert_eq!(checkout.rev.as_deref(), Some("v1"));
+        assert_eq!(config["agents"]["qa"]["specialization"], "qa");
+
+        // The pinned checkout is served from the cache without touching upstream.
+        tokio::fs::remove_dir_all(repo).await?;
+        let mut again = json!({ "agents": { "qa": { "preset": reference } } });
+        let cached = resolver.refreshing(true).expand(&mut again).await?;
+        assert_eq!(cached[0].checkout.as_ref().unwrap().commit, checkout.commit);
+        Ok(())
+    }
+}
@@ -0,0 +1,591 @@
+//! Background supervisor behind `ccswarm daemon`.
+//!
+//! The daemon runs each configured [`Subsystem`] (by default the queue
+//! watcher, `ccswarm auto --watch`) as a child process and restarts it when it
+//! exits, waiting longer after each crash in a row ([`Backoff`]). It keeps its
+//! pid, log and control socket under [`DAEMON_DIR`]; `ccswarm daemon status`
+//! and `ccswarm stop` talk to it over the socket with one JSON line each way.
+//!
+//! Configured under `daemon` in `ccswarm.json`:
+//!
+//! ```json
+//! "daemon": {
+//!   "subsystems": [
+//!     { "name": "queue", "args": ["auto", "--watch", "--poll-secs", "15"] }
+//!   ],
+//!   "backoff_max_secs": 120
+//! }
+//! ```
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::watch;
+
+/// Daemon state directory, relative to the repository.
+pub const DAEMON_DIR: &str = ".ccswarm/daemon";
+
+/// How long a client waits for the daemon to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A long-running ccswarm command the daemon keeps alive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subsystem {
+    pub name: String,
+    /// ccswarm arguments, e.g. `["auto", "--watch"]`.
+    pub args: Vec<String>,
+}
+
+fn default_subsystems() -> Vec<Subsystem> {
+    vec![Subsystem {
+        name: "queue".to_string(),
+        args: vec!["auto".to_string(), "--watch".to_string()],
+    }]
+}
+
+fn default_backoff_initial_secs() -> u64 {
+    1
+}
+
+fn default_backoff_max_secs() -> u64 {
+    300
+}
+
+fn default_healthy_after_secs() -> u64 {
+    60
+}
+
+fn default_stop_timeout_secs() -> u64 {
+    10
+}
+
+/// The `daemon` section of the config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    #[serde(default = "default_subsystems")]
+    pub subsystems: Vec<Subsystem>,
+    /// Delay before the first restart; doubles with every crash in a row.
+    #[serde(default = "default_backoff_initial_secs")]
+    pub backoff_initial_secs: u64,
+    #[serde(default = "default_backoff_max_secs")]
+    pub backoff_max_secs: u64,
+    /// A subsystem that ran at least this long before exiting counts as
+    /// healthy, and its next restart starts from the initial delay again.
+    #[serde(default = "default_healthy_after_secs")]
+    pub healthy_after_secs: u64,
+    /// Grace period between SIGTERM and SIGKILL when stopping a subsystem.
+    #[serde(default = "default_stop_timeout_secs")]
+    pub stop_timeout_secs: u64,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            subsystems: default_subsystems(),
+            backoff_initial_secs: default_backoff_initial_secs(),
+            backoff_max_secs: default_backoff_max_secs(),
+            healthy_after_secs: default_healthy_after_secs(),
+            stop_timeout_secs: default_stop_timeout_secs(),
+        }
+    }
+}
+
+impl DaemonConfig {
+    pub fn validate(&self) -> Result<()> {
+        let mut names = std::collections::HashSet::new();
+        for (i, subsystem) in self.subsystems.iter().enumerate() {
+            if subsystem.name.is_empty() {
+                bail!("daemon.subsystems[{}].name must not be empty", i);
+            }
+            if !names.insert(subsystem.name.as_str()) {
+                bail!(
+                    "daemon.subsystems has two entries named '{}'",
+                    subsystem.name
+                );
+            }
+            match subsystem.args.first().map(String::as_str) {
+                None => bail!("daemon.subsystems[{}].args must not be empty", i),
+                Some("daemon" | "stop") => {
+                    bail!("daemon.subsystems[{}] would start another daemon", i)
+                }
+                Some(_) => {}
+            }
+        }
+        if self.backoff_initial_secs == 0 || self.backoff_max_secs < self.backoff_initial_secs {
+            bail!("daemon backoff must satisfy 0 < backoff_initial_secs <= backoff_max_secs");
+        }
+        Ok(())
+    }
+}
+
+/// Exponential restart delay.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    next: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            next: initial,
+        }
+    }
+
+    /// The delay to wait now; the one after it is twice as long, up to the max.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next = self.initial;
+    }
+}
+
+/// Files the daemon keeps under [`DAEMON_DIR`].
+#[derive(Debug, Clone)]
+pub struct DaemonPaths {
+    pub dir: PathBuf,
+    pub pid: PathBuf,
+    pub log: PathBuf,
+    pub socket: PathBuf,
+}
+
+impl DaemonPaths {
+    pub fn new(repo: &Path) -> Self {
+        let dir = repo.join(DAEMON_DIR);
+        Self {
+            pid: dir.join("daemon.pid"),
+            log: dir.join("daemon.log"),
+            socket: dir.join("control.sock"),
+            dir,
+        }
+    }
+
+    /// Pid of a live daemon, from the pidfile.
+    pub fn running_pid(&self) -> Option<u32> {
+        let pid: i32 = std::fs::read_to_string(&self.pid)
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        // Signal 0 only checks that the process exists.
+        nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), None)
+            .is_ok()
+            .then_some(pid as u32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubsystemState {
+    Running,
+    /// Exited; waiting to be restarted.
+    Backoff,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemStatus {
+    pub name: String,
+    pub state: SubsystemState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    pub restarts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_at: Option<DateTime<Utc>>,
+}
+
+/// What `status` and `stop` report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    pub subsystems: Vec<SubsystemStatus>,
+}
+
+/// One request on the control socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ControlRequest {
+    Status,
+    /// Stop every subsystem and exit.
+    Stop,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum ControlResponse {
+    Status(DaemonStatus),
+    Error { error: String },
+}
+
+/// Send `request` to the daemon for `repo`.
+pub async fn request(paths: &DaemonPaths, request: ControlRequest) -> Result<DaemonStatus> {
+    let exchange = async {
+        let stream = UnixStream::connect(&paths.socket).await?;
+        let (read, mut write) = stream.into_split();
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        write.write_all(line.as_bytes()).await?;
+        let mut reply = String::new();
+        BufReader::new(read).read_line(&mut reply).await?;
+        anyhow::Ok(reply)
+    };
+    let reply = tokio::time::timeout(REQUEST_TIMEOUT, exchange)
+        .await
+        .map_err(|_| anyhow!("Daemon did not answer within {:?}", REQUEST_TIMEOUT))?
+        .with_context(|| format!("No daemon listening on {}", paths.socket.display()))?;
+    match serde_json::from_str(&reply).context("Invalid reply from daemon")? {
+        ControlResponse::Status(status) => Ok(status),
+        ControlResponse::Error { error } => Err(anyhow!(error)),
+    }
+}
+
+/// Runs and restarts the subsystems until told to stop.
+pub struct Supervisor {
+    paths: DaemonPaths,
+    repo: PathBuf,
+    /// The ccswarm binary and the global arguments every subsystem gets.
+    command: Vec<String>,
+    config: DaemonConfig,
+}
+
+impl Supervisor {
+    /// `command` is the ccswarm binary followed by global arguments such as
+    /// `--config`; subsystem arguments are appended to it.
+    pub fn new(repo: &Path, command: Vec<String>, config: DaemonConfig) -> Self {
+        Self {
+            paths: DaemonPaths::new(repo),
+            repo: repo.to_path_buf(),
+            command,
+            config,
+        }
+    }
+
+    /// Run in the foreground until a `stop` request, SIGTERM or Ctrl-C.
+    pub async fn run(self) -> Result<()> {
+        self.config.validate()?;
+        if self.command.is_empty() {
+            bail!("No ccswarm command to run subsystems with");
+        }
+        let own_pid = std::process::id();
+        if let Some(pid) = self.paths.running_pid().filter(|pid| *pid != own_pid) {
+            bail!("ccswarm daemon is already running (pid {})", pid);
+        }
+        tokio::fs::create_dir_all(&self.paths.dir).await?;
+        tokio::fs::write(&self.paths.pid, format!("{own_pid}\n")).await?;
+        // A socket left behind by a daemon that died.
+        let _ = tokio::fs::remove_file(&self.paths.socket).await;
+        let listener = UnixListener::bind(&self.paths.socket)
+            .with_context(|| format!("Failed to bind {}", self.paths.socket.display()))?;
+
+        let status = Arc::new(Mutex::new(DaemonStatus {
+            pid: own_pid,
+            started_at: Utc::now(),
+            subsystems: self
+                .config
+                .subsystems
+                .iter()
+                .map(|s| SubsystemStatus {
+                    name: s.name.clone(),
+                    state: SubsystemState::Stopped,
+                    pid: None,
+                    restarts: 0,
+                    started_at: None,
+                    last_exit: None,
+                    restart_at: None,
+                })
+                .collect(),
+        }));
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        log(&format!(
+            "daemon started (pid {}, {} subsystem(s))",
+            own_pid,
+            self.config.subsystems.len()
+        ));
+        // Whatever the subsystems of a daemon that died left half-done.
+        match crate::recovery::RecoveryJournal::for_repo(&self.repo)
+            .recover()
+            .await
+        {
+            Ok(compensations) => {
+                for c in compensations {
+                    log(&format!("recovery: {}: {}", c.op, c.outcome));
+                }
+            }
+            Err(e) => log(&format!("recovery failed: {e:#}")),
+        }
+
+        let supervisors: Vec<_> = self
+            .config
+            .subsystems
+            .iter()
+            .enumerate()
+            .map(|(index, subsystem)| {
+                tokio::spawn(supervise(
+                    index,
+                    subsystem.clone(),
+                    self.command.clone(),
+                    self.repo.clone(),
+                    self.config.clone(),
+                    status.clone(),
+                    shutdown_rx.clone(),
+                ))
+            })
+            .collect();
+
+        let mut sigterm =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let Ok((stream, _)) = accepted else { continue };
+                    if serve(stream, &status).await == Some(ControlRequest::Stop) {
+                        log("stop requested");
+                        break;
+                    }
+                }
+                _ = sigterm.recv() => {
+                    log("received SIGTERM");
+                    break;
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    log("interrupted");
+                    break;
+                }
+            }
+        }
+
+        let _ = shutdown_tx.send(true);
+        futures::future::join_all(supervisors).await;
+        let _ = tokio::fs::remove_file(&self.paths.socket).await;
+        let _ = tokio::fs::remove_file(&self.paths.pid).await;
+        log("daemon stopped");
+        Ok(())
+    }
+}
+
+/// Answer one control connection; returns the request it carried.
+async fn serve(stream: UnixStream, status: &Mutex<DaemonStatus>) -> Option<ControlRequest> {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let mut line = String::new();
+    let read_line = reader.read_line(&mut line);
+    if !matches!(
+        tokio::time::timeout(REQUEST_TIMEOUT, read_line).await,
+        Ok(Ok(_))
+    ) {
+        return None;
+    }
+    let (request, response) = match serde_json::from_str::<ControlRequest>(&line) {
+        Ok(request) => {
+            let snapshot = status.lock().expect("daemon status lock").clone();
+            (Some(request), ControlResponse::Status(snapshot))
+        }
+        Err(e) => (
+            None,
+            ControlResponse::Error {
+                error: format!("Invalid request: {e}"),
+            },
+        ),
+    };
+    if let Ok(mut reply) = serde_json::to_string(&response) {
+        reply.push('\n');
+        let _ = write.write_all(reply.as_bytes()).await;
+    }
+    request
+}
+
+/// Keep one subsystem running until shutdown.
+async fn supervise(
+    index: usize,
+    subsystem: Subsystem,
+    command: Vec<String>,
+    repo: PathBuf,
+    config: DaemonConfig,
+    status: Arc<Mutex<DaemonStatus>>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let update = |f: &dyn Fn(&mut SubsystemStatus)| {
+        f(&mut status.lock().expect("daemon status lock").subsystems[index]);
+    };
+    let mut backoff = Backoff::new(
+        Duration::from_secs(config.backoff_initial_secs),
+        Duration::from_secs(config.backoff_max_secs),
+    );
+    while !*shutdown.borrow() {
+        let started = Instant::now();
+        let spawned = tokio::process::Command::new(&command[0])
+            .args(&command[1..])
+            .args(&subsystem.args)
+            .current_dir(&repo)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn();
+        let exit = match spawned {
+            Err(e) => format!("failed to start: {e}"),
+            Ok(mut child) => {
+                let pid = child.id();
+                update(&|s| {
+                    s.state = SubsystemState::Running;
+                    s.pid = pid;
+                    s.started_at = Some(Utc::now());
+                    s.restart_at = None;
+                });
+                log(&format!(
+                    "{} started (pid {})",
+                    subsystem.name,
+                    pid.unwrap_or_default()
+                ));
+                tokio::select! {
+                    exited = child.wait() => match exited {
+                        Ok(status) => status.to_string(),
+                        Err(e) => format!("wait failed: {e}"),
+                    },
+                    _ = shutdown.changed() => {
+                        terminate(&mut child, Duration::from_secs(config.stop_timeout_secs)).await;
+                        log(&format!("{} stopped", subsystem.name));
+                        break;
+                    }
+                }
+            }
+        };
+
+        let ran = started.elapsed();
+        if ran >= Duration::from_secs(config.healthy_after_secs) {
+            backoff.reset();
+        }
+        let delay = backoff.next_delay();
+        let restart_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+        update(&|s| {
+            s.state = SubsystemState::Backoff;
+            s.pid = None;
+            s.restarts += 1;
+            s.last_exit = Some(exit.clone());
+            s.restart_at = Some(restart_at);
+        });
+        log(&format!(
+            "{} exited ({}) after {}s; restarting in {}s",
+            subsystem.name,
+            exit,
+            ran.as_secs(),
+            delay.as_secs()
+        ));
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.changed() => break,
+        }
+    }
+    update(&|s| {
+        s.state = SubsystemState::Stopped;
+        s.pid = None;
+        s.restart_at = None;
+    });
+}
+
+/// SIGTERM, then SIGKILL if the child is still there after `grace`.
+async fn terminate(child: &mut tokio::process::Child, grace: Duration) {
+    if let Some(pid) = child.id() {
+        let _ = nix::sys::signal::kill(
+            nix::unistd::Pid::from_raw(pid as i32),
+            nix::sys::signal::Signal::SIGTERM,
+        );
+    }
+    if tokio::time::timeout(grace, child.wait()).await.is_err() {
+        let _ = child.kill().await;
+    }
+}
+
+/// The daemon's stdout is its log file.
+fn log(message: &str) {
+    println!("[{}] {}", Utc::now().format("%Y-%m-%d %H:%M:%S"), message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max_and_resets() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 5, 5]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+
+        let mut config = DaemonConfig::default();
+        assert!(config.validate().is_ok());
+        config.subsystems.push(Subsystem {
+            name: "nested".to_string(),
+            args: vec!["daemon".to_string(), "start".to_string()],
+        });
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_restarts_crashed_subsystem_and_stops_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = DaemonConfig {
+            subsystems: vec![
+                Subsystem {
+                    name: "crashy".to_string(),
+                    args: vec!["-c".to_string(), "exit 3".to_string()],
+                },
+                Subsystem {
+                    name: "steady".to_string(),
+                    args: vec!["-c".to_string(), "sleep 30".to_string()],
+                },
+            ],
+            backoff_initial_secs: 1,
+            backoff_max_secs: 1,
+            healthy_after_secs: 60,
+            stop_timeout_secs: 1,
+        };
+        let supervisor = Supervisor::new(dir.path(), vec!["sh".to_string()], config);
+        let daemon = tokio::spawn(supervisor.run());
+        let paths = DaemonPaths::new(dir.path());
+
+        let mut status = None;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if let Ok(s) = request(&paths, ControlRequest::Status).await
+                && s.subsystems[0].restarts >= 2
+            {
+                status = Some(s);
+                break;
+            }
+        }
+        let status = status.expect("crashy subsystem was not restarted");
+        assert_eq!(status.pid, std::process::id());
+        assert!(
+            status.subsystems[0]
+                .last_exit
+                .as_deref()
+                .unwrap()
+                .contains('3')
+        );
+        assert_eq!(status.subsystems[1].state, SubsystemState::Running);
+        assert_eq!(status.subsystems[1].restarts, 0);
+        assert_eq!(paths.running_pid(), Some(std::process::id()));
+
+        request(&paths, ControlRequest::Stop).await.unwrap();
+        daemon.await.unwrap().unwrap();
+        assert!(!paths.pid.exists());
+        assert!(!paths.socket.exists());
+    }
+}
@@ -0,0 +1,388 @@
+//! Deploy targets for `ccswarm scaffold --auto-deploy`.
+//!
+//! A scaffold template's `deploy:` section names a target and how to verify
+//! it. The DevOps agent prepares the project for that target first (compose
+//! file, `fly.toml`, build output, ...); [`run`] then snapshots whatever a
+//! rollback would restore, runs the target's deploy commands, polls the
+//! health check, and rolls back when the deploy or the check fails.
+//!
+//! Targets only describe commands; [`run`] owns execution, like
+//! `providers` does for agent CLIs.
+
+mod targets;
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// The `deploy:` section of a scaffold template.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeploySpec {
+    /// `docker-compose`, `fly`, `vercel` or `rsync`.
+    pub target: String,
+    /// Fly app name (`--app`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    /// rsync destination host (`user@host`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// rsync destination directory on `host`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// rsync: local directory to upload (default `dist`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<HealthCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    /// URL that must answer 2xx. Targets that assign a URL (Vercel) use it
+    /// when this is omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default = "default_health_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_health_timeout() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeployKind {
+    Compose,
+    Fly,
+    Vercel,
+    Rsync,
+}
+
+impl DeployKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "docker-compose" | "compose" | "docker" => Some(Self::Compose),
+            "fly" | "fly.io" | "flyio" => Some(Self::Fly),
+            "vercel" => Some(Self::Vercel),
+            "rsync" | "ssh" => Some(Self::Rsync),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Compose => "docker-compose",
+            Self::Fly => "fly",
+            Self::Vercel => "vercel",
+            Self::Rsync => "rsync",
+        }
+    }
+}
+
+/// A command line; `argv[0]` is the program.
+pub(crate) type Argv = Vec<String>;
+
+/// Contract for a deploy target.
+pub(crate) trait DeployTarget {
+    fn kind(&self) -> DeployKind;
+
+    /// What the DevOps agent must put in place before deploying.
+    fn agent_brief(&self, spec: &DeploySpec) -> String;
+
+    /// Command whose stdout captures what a rollback restores. `None` when
+    /// the target needs nothing captured.
+    fn snapshot(&self, _spec: &DeploySpec) -> Option<Argv> {
+        None
+    }
+
+    /// Deploy commands, run in order. Errors on missing spec fields.
+    fn deploy(&self, spec: &DeploySpec) -> Result<Vec<Argv>>;
+
+    /// URL the deploy assigned, parsed from its stdout.
+    fn deployed_url(&self, _stdout: &str) -> Option<String> {
+        None
+    }
+
+    /// Commands that undo the deploy given the snapshot output. `None` when
+    /// there is nothing to roll back to.
+    fn rollback(&self, spec: &DeploySpec, snapshot: Option<&str>) -> Option<Vec<Argv>>;
+}
+
+/// Resolve the target named by `spec.target`.
+pub(crate) fn resolve(spec: &DeploySpec) -> Result<Box<dyn DeployTarget + Send + Sync>> {
+    let kind = DeployKind::parse(&spec.target).ok_or_else(|| {
+        anyhow!(
+            "Unknown deploy target '{}' (expected docker-compose, fly, vercel or rsync)",
+            spec.target
+        )
+    })?;
+    Ok(match kind {
+        DeployKind::Compose => Box::new(targets::Compose),
+        DeployKind::Fly => Box::new(targets::Fly),
+        DeployKind::Vercel => Box::new(targets::Vercel),
+        DeployKind::Rsync => Box::new(targets::Rsync),
+    })
+}
+
+/// Outcome of [`run`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeployReport {
+    pub target: String,
+    pub url: Option<String>,
+    /// `None` when there was nothing to check (no URL).
+    pub healthy: Option<bool>,
+    pub rolled_back: bool,
+    /// Why the deploy was rolled back (or failed without a rollback).
+    pub failure: Option<String>,
+}
+
+impl DeployReport {
+    pub fn succeeded(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Deploy `dir` to `target` and verify it, rolling back on failure.
+pub(crate) async fn run(
+    dir: &Path,
+    target: &(dyn DeployTarget + Send + Sync),
+    spec: &DeploySpec,
+) -> Result<DeployReport> {
+    let commands = target.deploy(spec)?;
+    let mut report = DeployReport {
+        target: target.kind().as_str().to_string(),
+        ..Default::default()
+    };
+
+    let snapshot = match target.snapshot(spec) {
+        Some(argv) => match exec(dir, &argv).await {
+            Ok(stdout) => Some(stdout),
+            Err(e) => {
+                tracing::warn!("deploy snapshot failed, rollback may be unavailable: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut deploy_stdout = String::new();
+    for argv in &commands {
+        match exec(dir, argv).await {
+            Ok(stdout) => deploy_stdout.push_str(&stdout),
+            Err(e) => {
+                report.failure = Some(e.to_string());
+                break;
+            }
+        }
+    }
+
+    if report.failure.is_none() {
+        report.url = spec
+            .health_check
+            .as_ref()
+            .and_then(|h| h.url.clone())
+            .or_else(|| target.deployed_url(&deploy_stdout));
+        if let Some(url) = &report.url {
+            let timeout = spec
+                .health_check
+                .as_ref()
+                .map_or_else(default_health_timeout, |h| h.timeout_secs);
+            let healthy = wait_healthy(url, Duration::from_secs(timeout)).await;
+            report.healthy = Some(healthy);
+            if !healthy {
+                report.failure = Some(format!(
+                    "health check {} did not answer 2xx within {}s",
+                    url, timeout
+                ));
+            }
+        }
+    }
+
+    if report.failure.is_some() {
+        match target.rollback(spec, snapshot.as_deref()) {
+            Some(rollback) => {
+                for argv in &rollback {
+                    exec(dir, argv).await.context("rollback failed")?;
+                }
+                report.rolled_back = true;
+            }
+            None => tracing::warn!(
+                "{} deploy failed and there is nothing to roll back to",
+                report.target
+            ),
+        }
+    }
+    Ok(report)
+}
+
+async fn exec(dir: &Path, argv: &[String]) -> Result<String> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow!("empty deploy command"))?;
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "`{}` exited with {}: {}",
+            argv.join(" "),
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Poll `url` every two seconds until it answers 2xx or `timeout` passes.
+async fn wait_healthy(url: &str, timeout: Duration) -> bool {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+    {
+        Ok(client) => client,
+        Err(_) => return false,
+    };
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if let Ok(response) = client.get(url).send().await
+            && response.status().is_success()
+        {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_secs(2).min(timeout)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes marker files instead of deploying anything.
+    struct Fake {
+        fail_deploy: bool,
+    }
+
+    fn sh(script: &str) -> Argv {
+        vec!["sh".into(), "-c".into(), script.into()]
+    }
+
+    impl DeployTarget for Fake {
+        fn kind(&self) -> DeployKind {
+            DeployKind::Compose
+        }
+        fn agent_brief(&self, _spec: &DeploySpec) -> String {
+            String::new()
+        }
+        fn snapshot(&self, _spec: &DeploySpec) -> Option<Argv> {
+            Some(sh("echo v1"))
+        }
+        fn deploy(&self, _spec: &DeploySpec) -> Result<Vec<Argv>> {
+            Ok(vec![if self.fail_deploy {
+                sh("exit 3")
+            } else {
+                sh("touch deployed")
+            }])
+        }
+        fn rollback(&self, _spec: &DeploySpec, snapshot: Option<&str>) -> Option<Vec<Argv>> {
+            let snapshot = snapshot?.trim().to_string();
+            Some(vec![sh(&format!("echo {snapshot} > restored"))])
+        }
+    }
+
+    /// A one-shot HTTP server answering every request with `status`.
+    async fn serve(status: u16) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket
+                    .write_all(
+                        format!("HTTP/1.1 {status} X\r\ncontent-length: 0\r\n\r\n").as_bytes(),
+                    )
+                    .await;
+            }
+        });
+        format!("http://{addr}/health")
+    }
+
+    fn spec_with_health(url: String) -> DeploySpec {
+        DeploySpec {
+            target: "fake".into(),
+            health_check: Some(HealthCheck {
+                url: Some(url),
+                timeout_secs: 1,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_healthy_deploy_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec = spec_with_health(serve(200).await);
+        let report = run(dir.path(), &Fake { fail_deploy: false }, &spec)
+            .await
+            .unwrap();
+        assert!(report.succeeded());
+        assert_eq!(report.healthy, Some(true));
+        assert!(!report.rolled_back);
+        assert!(dir.path().join("deployed").exists());
+    }
+
+    #[tokio::test]
+    async fn test_failed_health_check_rolls_back_to_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec = spec_with_health(serve(503).await);
+        let report = run(dir.path(), &Fake { fail_deploy: false }, &spec)
+            .await
+            .unwrap();
+        assert_eq!(report.healthy, Some(false));
+        assert!(report.rolled_back);
+        assert!(report.failure.unwrap().contains("did not answer 2xx"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("restored")).unwrap(),
+            "v1\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_deploy_command_rolls_back_without_health_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec = DeploySpec {
+            target: "fake".into(),
+            ..Default::default()
+        };
+        let report = run(dir.path(), &Fake { fail_deploy: true }, &spec)
+            .await
+            .unwrap();
+        assert_eq!(report.healthy, None);
+        assert!(report.rolled_back);
+        assert!(report.failure.unwrap().contains("exited with 3"));
+    }
+
+    #[test]
+    fn test_resolve_rejects_unknown_target() {
+        let spec = DeploySpec {
+            target: "heroku".into(),
+            ..Default::default()
+        };
+        assert!(resolve(&spec).is_err());
+        for name in ["docker-compose", "fly", "vercel", "rsync"] {
+            let spec = DeploySpec {
+                target: name.into(),
+                ..Default::default()
+            };
+            assert_eq!(resolve(&spec).unwrap().kind().as_str(), name);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! The built-in deploy targets.
+
+use anyhow::{Result, anyhow};
+
+use super::{Argv, DeployKind, DeploySpec, DeployTarget};
+
+fn argv(parts: &[&str]) -> Argv {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+fn with_app(mut argv: Argv, spec: &DeploySpec) -> Argv {
+    if let Some(app) = &spec.app {
+        argv.push("--app".into());
+        argv.push(app.clone());
+    }
+    argv
+}
+
+/// Quote `s` for a POSIX shell (used for commands run over ssh).
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// `docker compose up -d --build` in the project directory. A freshly
+/// scaffolded project has no previous stack, so rollback takes the new one down.
+pub(super) struct Compose;
+
+impl DeployTarget for Compose {
+    fn kind(&self) -> DeployKind {
+        DeployKind::Compose
+    }
+
+    fn agent_brief(&self, _spec: &DeploySpec) -> String {
+        "Add a Dockerfile and a compose.yaml that build and run the project with \
+         `docker compose up -d --build`. Publish the service port on localhost."
+            .to_string()
+    }
+
+    fn deploy(&self, _spec: &DeploySpec) -> Result<Vec<Argv>> {
+        Ok(vec![argv(&["docker", "compose", "up", "-d", "--build"])])
+    }
+
+    fn rollback(&self, _spec: &DeploySpec, _snapshot: Option<&str>) -> Option<Vec<Argv>> {
+        Some(vec![argv(&["docker", "compose", "down"])])
+    }
+}
+
+/// `fly deploy`. Rollback redeploys the image of the release that was
+/// current before, when there was one.
+pub(super) struct Fly;
+
+impl Fly {
+    /// The newest release's image in `fly releases --json` output.
+    fn previous_image(snapshot: &str) -> Option<String> {
+        let releases: serde_json::Value = serde_json::from_str(snapshot).ok()?;
+        releases.as_array()?.iter().find_map(|release| {
+            release
+                .get("ImageRef")
+                .or_else(|| release.get("image_ref"))
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        })
+    }
+}
+
+impl DeployTarget for Fly {
+    fn kind(&self) -> DeployKind {
+        DeployKind::Fly
+    }
+
+    fn agent_brief(&self, spec: &DeploySpec) -> String {
+        let app = spec
+            .app
+            .as_deref()
+            .map(|a| format!(" with app = \"{a}\""))
+            .unwrap_or_default();
+        format!(
+            "Add a Dockerfile and a fly.toml{app} so `fly deploy --remote-only` builds \
+             and runs the project. Configure an HTTP service on the port the app listens on."
+        )
+    }
+
+    fn snapshot(&self, spec: &DeploySpec) -> Option<Argv> {
+        Some(with_app(argv(&["fly", "releases", "--json"]), spec))
+    }
+
+    fn deploy(&self, spec: &DeploySpec) -> Result<Vec<Argv>> {
+        Ok(vec![with_app(
+            argv(&["fly", "deploy", "--remote-only"]),
+            spec,
+        )])
+    }
+
+    fn rollback(&self, spec: &DeploySpec, snapshot: Option<&str>) -> Option<Vec<Argv>> {
+        let image = Self::previous_image(snapshot?)?;
+        Some(vec![with_app(
+            argv(&["fly", "deploy", "--image", &image]),
+            spec,
+        )])
+    }
+}
+
+/// `vercel deploy --prod`. The deployment URL comes from its output, and
+/// rollback points production back at the previous deployment.
+pub(super) struct Vercel;
+
+impl DeployTarget for Vercel {
+    fn kind(&self) -> DeployKind {
+        DeployKind::Vercel
+    }
+
+    fn agent_brief(&self, _spec: &DeploySpec) -> String {
+        "Make sure the project builds on Vercel: add a vercel.json if the framework \
+         preset is not detected automatically, and set the output directory."
+            .to_string()
+    }
+
+    fn deploy(&self, _spec: &DeploySpec) -> Result<Vec<Argv>> {
+        Ok(vec![argv(&["vercel", "deploy", "--prod", "--yes"])])
+    }
+
+    fn deployed_url(&self, stdout: &str) -> Option<String> {
+        stdout
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| line.starts_with("https://"))
+            .map(str::to_string)
+    }
+
+    fn rollback(&self, _spec: &DeploySpec, _snapshot: Option<&str>) -> Option<Vec<Argv>> {
+        Some(vec![argv(&["vercel", "rollback"])])
+    }
+}
+
+/// rsync over ssh. The remote directory is copied aside before the upload
+/// and moved back on rollback.
+pub(super) struct Rsync;
+
+const SAVED: &str = "saved";
+
+impl Rsync {
+    fn destination(spec: &DeploySpec) -> Result<(&str, &str)> {
+        let host = spec
+            .host
+            .as_deref()
+            .ok_or_else(|| anyhow!("rsync deploy needs `host`"))?;
+        let path = spec
+            .path
+            .as_deref()
+            .ok_or_else(|| anyhow!("rsync deploy needs `path`"))?;
+        Ok((host, path.trim_end_matches('/')))
+    }
+}
+
+impl DeployTarget for Rsync {
+    fn kind(&self) -> DeployKind {
+        DeployKind::Rsync
+    }
+
+    fn agent_brief(&self, spec: &DeploySpec) -> String {
+        format!(
+            "Build the project so that everything to publish ends up in `{}/`. \
+             It will be uploaded as-is with rsync.",
+            spec.source.as_deref().unwrap_or("dist")
+        )
+    }
+
+    fn snapshot(&self, spec: &DeploySpec) -> Option<Argv> {
+        let (host, path) = Self::destination(spec).ok()?;
+        let (p, prev) = (sh_quote(path), sh_quote(&format!("{path}.ccswarm-prev")));
+        Some(argv(&[
+            "ssh",
+            host,
+            &format!("if [ -d {p} ]; then rm -rf {prev} && cp -a {p} {prev} && echo {SAVED}; fi"),
+        ]))
+    }
+
+    fn deploy(&self, spec: &DeploySpec) -> Result<Vec<Argv>> {
+        let (host, path) = Self::destination(spec)?;
+        let source = spec
+            .source
+            .as_deref()
+            .unwrap_or("dist")
+            .trim_end_matches('/');
+        Ok(vec![
+            argv(&["ssh", host, &format!("mkdir -p {}", sh_quote(path))]),
+            argv(&[
+                "rsync",
+                "-az",
+                "--delete",
+                &format!("{source}/"),
+                &format!("{host}:{path}/"),
+            ]),
+        ])
+    }
+
+    fn rollback(&self, spec: &DeploySpec, snapshot: Option<&str>) -> Option<Vec<Argv>> {
+        let (host, path) = Self::destination(spec).ok()?;
+        let p = sh_quote(path);
+        let script = if snapshot.is_some_and(|s| s.trim() == SAVED) {
+            let prev = sh_quote(&format!("{path}.ccswarm-prev"));
+            format!("rm -rf {p} && mv {prev} {p}")
+        } else {
+            format!("rm -rf {p}")
+        };
+        Some(vec![argv(&["ssh", host, &script])])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(target: &str) -> DeploySpec {
+        DeploySpec {
+            target: target.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fly_rolls_back_to_previous_image() {
+        let spec = DeploySpec {
+            app: Some("todo".into()),
+            ..spec("fly")
+        };
+        assert_eq!(
+            Fly.deploy(&spec).unwrap()[0],
+            ["fly", "deploy", "--remote-only", "--app", "todo"]
+        );
+        let releases = r#"[{"Version":3,"ImageRef":"registry.fly.io/todo:v3"},{"Version":2,"ImageRef":"registry.fly.io/todo:v2"}]"#;
+        assert_eq!(
+            Fly.rollback(&spec, Some(releases)).unwrap()[0],
+            [
+                "fly",
+                "deploy",
+                "--image",
+                "registry.fly.io/todo:v3",
+                "--app",
+                "todo"
+            ]
+        );
+        // First deploy of a new app: nothing to go back to.
+        assert!(Fly.rollback(&spec, Some("[]")).is_none());
+        assert!(Fly.rollback(&spec, Some("Error: app not found")).is_none());
+    }
+
+    #[test]
+    fn test_vercel_url_from_output() {
+        let stdout = "Vercel CLI 39.1.0\nInspect: https://vercel.com/me/site/abc\n\
+                      https://site-abc.vercel.app\n";
+        assert_eq!(
+            Vercel.deployed_url(stdout).as_deref(),
+            Some("https://site-abc.vercel.app")
+        );
+        assert_eq!(Vercel.deployed_url("Error: not logged in"), None);
+    }
+
+    #[test]
+    fn test_rsync_requires_destination_and_quotes_paths() {
+        assert!(Rsync.deploy(&spec("rsync")).is_err());
+
+        let spec = DeploySpec {
+            host: Some("deploy@example.com".into()),
+            path: Some("/srv/it's/".into()),
+            ..spec("rsync")
+        };
+        let commands = Rsync.deploy(&spec).unwrap();
+        assert_eq!(
+            commands[1],
+            [
+                "rsync",
+                "-az",
+                "--delete",
+                "dist/",
+                "deploy@example.com:/srv/it's/"
+            ]
+        );
+        assert_eq!(
+            Rsync.rollback(&spec, Some("saved\n")).unwrap()[0][2],
+            r"rm -rf '/srv/it'\''s' && mv '/srv/it'\''s.ccswarm-prev' '/srv/it'\''s'"
+        );
+        assert_eq!(
+            Rsync.rollback(&spec, None).unwrap()[0][2],
+            r"rm -rf '/srv/it'\''s'"
+        );
+    }
+}
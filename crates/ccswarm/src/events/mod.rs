@@ -11,6 +11,8 @@
 //! 3. Call [`EventRecorder::write_summary`] with a [`RunSummary`] when the run
 //!    finishes.
 
+pub mod timeline;
+
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -56,6 +58,20 @@ pub enum EventType {
     // Provider interactions
     ProviderCall,
     ProviderError,
+    // Agent's assessment of its own stage output
+    SelfRating,
+    // Run suspended / continued with `ccswarm session pause|resume`
+    SessionPaused,
+    SessionResumed,
+    // Commands the run executes itself (stage gates)
+    CommandStart,
+    CommandEnd,
+    // A block of output arriving at once (provider reply, command output)
+    OutputBurst,
+    // Errors found in provider or command output
+    ErrorDetected,
+    // Where the agent says it is (`PROGRESS=` lines, see session::progress)
+    Progress,
 }
 
 // ─── Event ───────────────────────────────────────────────────────────────────
@@ -155,10 +171,42 @@ pub struct RunSummary {
     pub tasks_failed: usize,
     /// Deduplicated list of agent names seen across all events.
     pub agents_used: Vec<String>,
+    /// The ccswarm invocation that started this run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orchestrator_run_id: Option<crate::run_id::RunId>,
 }
 
 // ─── EventRecorder ───────────────────────────────────────────────────────────
 
+/// File in a run directory holding the pid of the process executing the run
+/// and when that process started.
+pub const RUN_PID_FILE: &str = "pid";
+
+/// Pid of the process executing the run in `run_dir`, if it is still alive.
+/// A run that died leaves its pid behind, and the pid may since have been
+/// given to an unrelated process; it only counts while the process holding
+/// it started when the recorded one did.
+pub fn run_pid(run_dir: &std::path::Path) -> Option<u32> {
+    let text = std::fs::read_to_string(run_dir.join(RUN_PID_FILE)).ok()?;
+    let mut fields = text.split_whitespace();
+    let pid: u32 = fields.next()?.parse().ok()?;
+    let started: u64 = fields.next()?.parse().ok()?;
+    (process_start_time(pid) == Some(started)).then_some(pid)
+}
+
+/// When process `pid` started, in seconds since the epoch, if it exists.
+fn process_start_time(pid: u32) -> Option<u64> {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+    system.process(pid).map(|process| process.start_time())
+}
+
 /// Appends events as NDJSON to `.ccswarm/runs/{run_id}/events.ndjson`.
 ///
 /// The recorder is cheap to clone (all state lives behind `Arc`-equivalent
@@ -229,9 +277,23 @@ impl EventRecorder {
         fs::write(&path, content)
             .await
             .with_context(|| format!("failed to write summary to {:?}", path))?;
+        // The run is over; nothing is left to pause.
+        let _ = fs::remove_file(self.run_dir.join(RUN_PID_FILE)).await;
         Ok(())
     }
 
+    /// Record the current process as the one executing this run, so
+    /// `ccswarm session pause` can find it.
+    pub async fn write_pid(&self) -> Result<()> {
+        let path = self.run_dir.join(RUN_PID_FILE);
+        let pid = std::process::id();
+        let started =
+            process_start_time(pid).context("failed to read this process's start time")?;
+        fs::write(&path, format!("{pid} {started}"))
+            .await
+            .with_context(|| format!("failed to write {:?}", path))
+    }
+
     /// The run ID used by this recorder.
     pub fn run_id(&self) -> &str {
         &self.run_id
@@ -251,6 +313,16 @@ impl EventRecorder {
     pub fn summary_path(&self) -> PathBuf {
         self.run_dir.join("summary.json")
     }
+
+    /// This run's directory.
+    pub fn run_dir(&self) -> &std::path::Path {
+        &self.run_dir
+    }
+
+    /// The directory holding this and every other run.
+    pub fn runs_dir(&self) -> Option<&std::path::Path> {
+        self.run_dir.parent()
+    }
 }
 
 // ─── SessionInfo ────────────────────────────────────────────────────────────
@@ -269,7 +341,7 @@ pub struct SessionInfo {
     pub ended_at: Option<DateTime<Utc>>,
     /// Human-readable duration string (e.g. "2m 15s").
     pub duration: Option<String>,
-    /// Inferred status: completed, failed, running, or incomplete.
+    /// Inferred status: completed, failed, paused, running, or incomplete.
     pub status: String,
     /// Total number of events in the NDJSON log.
     pub total_events: usize,
@@ -283,6 +355,13 @@ pub struct SessionInfo {
     pub agents_used: Vec<String>,
     /// Whether any stage or task reported a failure status.
     pub has_errors: bool,
+    /// Session name from `.ccswarm/sessions/names.json`, if the run has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The agent's latest progress report in the current stage, while the
+    /// run is going.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<crate::session::progress::ProgressReport>,
 }
 
 impl SessionInfo {
@@ -372,6 +451,8 @@ impl SessionInfo {
             movements_completed,
             agents_used,
             has_errors: tasks_failed > 0,
+            name: None,
+            progress: None,
         }
     }
 
@@ -388,6 +469,8 @@ impl SessionInfo {
         let mut agents: HashSet<String> = HashSet::new();
         let mut has_errors = false;
         let mut has_task_end = false;
+        let mut paused = false;
+        let mut progress = None;
 
         for line in content.lines() {
             let line = line.trim();
@@ -430,6 +513,7 @@ impl SessionInfo {
                     has_task_end = true;
                 }
                 "movement_start" => {
+                    progress = None;
                     last_movement = event
                         .get("stage")
                         .and_then(|v| v.as_str())
@@ -447,6 +531,13 @@ impl SessionInfo {
                 "provider_error" => {
                     has_errors = true;
                 }
+                "progress" => {
+                    progress = event
+                        .get("metadata")
+                        .and_then(|m| serde_json::from_value(m.clone()).ok());
+                }
+                "session_paused" => paused = true,
+                "session_resumed" => paused = false,
                 _ => {}
             }
 
@@ -470,6 +561,8 @@ impl SessionInfo {
             "failed".to_owned()
         } else if has_task_end {
             "completed".to_owned()
+        } else if paused {
+            "paused".to_owned()
         } else if total_events > 0 {
             "running".to_owned()
         } else {
@@ -481,13 +574,15 @@ impl SessionInfo {
             started_at,
             ended_at,
             duration,
-            status,
             total_events,
             task,
             last_movement,
             movements_completed,
             agents_used: agents.into_iter().collect(),
             has_errors,
+            name: None,
+            progress: progress.filter(|_| matches!(status.as_str(), "running" | "paused")),
+            status,
         }
     }
 }
@@ -554,6 +649,26 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn a_reused_pid_does_not_pass_for_the_run() {
+        let dir = tempdir().unwrap();
+        let recorder = recorder_in(dir.path(), "run-pid").await;
+        assert_eq!(run_pid(recorder.run_dir()), None);
+        recorder.write_pid().await.unwrap();
+        assert_eq!(run_pid(recorder.run_dir()), Some(std::process::id()));
+
+        // The same pid, but a process that started at another time.
+        let path = recorder.run_dir().join(RUN_PID_FILE);
+        let text = std::fs::read_to_string(&path).unwrap();
+        let (pid, started) = text.split_once(' ').unwrap();
+        let other = started.parse::<u64>().unwrap() - 60;
+        std::fs::write(&path, format!("{pid} {other}")).unwrap();
+        assert_eq!(run_pid(recorder.run_dir()), None);
+        // A bare pid cannot be told apart from a reused one.
+        std::fs::write(&path, pid).unwrap();
+        assert_eq!(run_pid(recorder.run_dir()), None);
+    }
+
     #[test]
     fn test_event_serialization() {
         let event = Event::new(
@@ -700,6 +815,7 @@ mod tests {
             tasks_completed: 3,
             tasks_failed: 1,
             agents_used: vec!["frontend".to_owned(), "backend".to_owned()],
+            orchestrator_run_id: None,
         };
 
         recorder
@@ -826,6 +942,41 @@ mod tests {
         assert!(!info.has_errors);
     }
 
+    #[test]
+    fn test_session_info_from_events_progress() {
+        let ndjson = r#"{"ts":"2026-03-26T15:30:12.140Z","level":"info","run_id":"r","event_type":"movement_start","stage":"plan","message":"Stage 'plan' started"}
+{"ts":"2026-03-26T15:30:20.000Z","level":"info","run_id":"r","event_type":"progress","stage":"plan","message":"40% schema","metadata":{"percent":40,"step":"schema"}}
+"#;
+        let info = SessionInfo::from_events("r", ndjson);
+        assert_eq!(info.progress.unwrap().summary(), "40% schema");
+
+        // A new stage starts without a report; a finished run keeps none.
+        let next = format!(
+            "{ndjson}{}\n",
+            r#"{"ts":"2026-03-26T15:31:00.000Z","level":"info","run_id":"r","event_type":"movement_start","stage":"code","message":"Stage 'code' started"}"#
+        );
+        assert!(SessionInfo::from_events("r", &next).progress.is_none());
+        let done = format!(
+            "{ndjson}{}\n",
+            r#"{"ts":"2026-03-26T15:31:00.000Z","level":"info","run_id":"r","event_type":"task_end","message":"Task completed"}"#
+        );
+        assert!(SessionInfo::from_events("r", &done).progress.is_none());
+    }
+
+    #[test]
+    fn test_session_info_from_events_paused() {
+        let ndjson = r#"{"ts":"2026-03-26T15:30:12.139Z","level":"info","run_id":"p","event_type":"task_start","message":"Starting flow 'default'"}
+{"ts":"2026-03-26T15:31:00.000Z","level":"info","run_id":"p","event_type":"session_paused","message":"Session paused"}
+"#;
+        assert_eq!(SessionInfo::from_events("p", ndjson).status, "paused");
+
+        let resumed = format!(
+            "{ndjson}{}\n",
+            r#"{"ts":"2026-03-26T15:32:00.000Z","level":"info","run_id":"p","event_type":"session_resumed","message":"Session resumed"}"#
+        );
+        assert_eq!(SessionInfo::from_events("p", &resumed).status, "running");
+    }
+
     #[test]
     fn test_session_info_from_events_empty() {
         let info = SessionInfo::from_events("empty-run", "");
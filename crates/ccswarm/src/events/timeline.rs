@@ -0,0 +1,486 @@
+//! Per-session timeline built from a run's `events.ndjson`.
+//!
+//! Start/end event pairs become [`Span`]s (stages, provider calls, gate
+//! commands, approval waits, pauses) and one-off events become [`Mark`]s
+//! (output bursts, detected errors, progress reports). The result answers
+//! "where did the time go" for `ccswarm session timeline` and the TUI
+//! timeline view.
+
+use super::{Event, EventLevel, EventType};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// What a span of time was spent on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanKind {
+    Stage,
+    Provider,
+    Command,
+    Waiting,
+    Paused,
+}
+
+impl SpanKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpanKind::Stage => "stage",
+            SpanKind::Provider => "provider",
+            SpanKind::Command => "command",
+            SpanKind::Waiting => "waiting",
+            SpanKind::Paused => "paused",
+        }
+    }
+}
+
+/// A stretch of the session. `end` is `None` while it is still running.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Span {
+    pub kind: SpanKind,
+    pub label: String,
+    pub stage: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+    /// How it ended (`passed`, `failed`, `approved`, ...), when known.
+    pub status: Option<String>,
+}
+
+impl Span {
+    /// Milliseconds from start to end, or to `now` for a running span.
+    pub fn duration_ms(&self, now: DateTime<Utc>) -> u64 {
+        let end = self.end.unwrap_or(now);
+        (end - self.start).num_milliseconds().max(0) as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkKind {
+    Output,
+    Error,
+    /// The agent's own progress report.
+    Progress,
+}
+
+/// A point in the session worth seeing on the timeline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mark {
+    pub kind: MarkKind,
+    pub ts: DateTime<Utc>,
+    pub stage: Option<String>,
+    pub message: String,
+}
+
+/// Total time per span kind. `other` is whatever no provider call, command,
+/// approval wait or pause accounts for (prompt building, git, bookkeeping).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Breakdown {
+    pub total_ms: u64,
+    pub provider_ms: u64,
+    pub commands_ms: u64,
+    pub waiting_ms: u64,
+    pub paused_ms: u64,
+    pub other_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Timeline {
+    pub run_id: String,
+    pub started: Option<DateTime<Utc>>,
+    /// Timestamp of the last event seen.
+    pub last_event: Option<DateTime<Utc>>,
+    pub spans: Vec<Span>,
+    pub marks: Vec<Mark>,
+}
+
+impl Timeline {
+    /// Build the timeline from the NDJSON contents of one run. Lines that do
+    /// not parse as events are skipped.
+    pub fn from_events(run_id: &str, content: &str) -> Self {
+        let mut timeline = Timeline {
+            run_id: run_id.to_string(),
+            ..Default::default()
+        };
+        // Index into `spans` of each span still waiting for its end event.
+        let mut open: HashMap<(SpanKind, String), usize> = HashMap::new();
+
+        for line in content.lines() {
+            let Ok(event) = serde_json::from_str::<Event>(line.trim()) else {
+                continue;
+            };
+            timeline.started.get_or_insert(event.ts);
+            timeline.last_event = Some(event.ts);
+            let meta = |key: &str| {
+                event
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.get(key))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            };
+            let stage = event.stage.clone();
+
+            let mut begin = |kind: SpanKind, key: String, label: String| {
+                open.insert((kind, key), timeline.spans.len());
+                timeline.spans.push(Span {
+                    kind,
+                    label,
+                    stage: stage.clone(),
+                    start: event.ts,
+                    end: None,
+                    status: None,
+                });
+            };
+            match event.event_type {
+                EventType::MovementStart => {
+                    let id = stage.clone().unwrap_or_default();
+                    begin(SpanKind::Stage, id.clone(), id);
+                }
+                EventType::CommandStart => {
+                    let gate = meta("gate").unwrap_or_default();
+                    let key = format!("{}/{}", stage.as_deref().unwrap_or(""), gate);
+                    begin(SpanKind::Command, key, gate);
+                }
+                EventType::HitlRequest => {
+                    let gate = meta("gate").unwrap_or_else(|| "approval".to_string());
+                    let label = format!("{gate} approval");
+                    begin(SpanKind::Waiting, gate, label);
+                }
+                EventType::SessionPaused => {
+                    begin(SpanKind::Paused, String::new(), "paused".to_string());
+                }
+                EventType::MovementEnd => {
+                    let id = stage.clone().unwrap_or_default();
+                    timeline.close(&mut open, (SpanKind::Stage, id), event.ts, meta("status"));
+                }
+                EventType::CommandEnd => {
+                    let gate = meta("gate").unwrap_or_default();
+                    let key = format!("{}/{}", stage.as_deref().unwrap_or(""), gate);
+                    timeline.close(
+                        &mut open,
+                        (SpanKind::Command, key),
+                        event.ts,
+                        meta("status"),
+                    );
+                }
+                EventType::HitlDecision => {
+                    let gate = meta("gate").unwrap_or_else(|| "approval".to_string());
+                    timeline.close(
+                        &mut open,
+                        (SpanKind::Waiting, gate),
+                        event.ts,
+                        meta("decision"),
+                    );
+                }
+                EventType::SessionResumed => {
+                    timeline.close(
+                        &mut open,
+                        (SpanKind::Paused, String::new()),
+                        event.ts,
+                        Some("resumed".to_string()),
+                    );
+                }
+                EventType::ProviderCall => {
+                    // Logged once the call returns; the start is derived from
+                    // its elapsed time. Older logs carry none and are skipped.
+                    let elapsed = event
+                        .metadata
+                        .as_ref()
+                        .and_then(|m| m.get("elapsed_ms"))
+                        .and_then(|v| v.as_u64());
+                    if let Some(elapsed) = elapsed {
+                        timeline.spans.push(Span {
+                            kind: SpanKind::Provider,
+                            label: "provider call".to_string(),
+                            stage: stage.clone(),
+                            start: event.ts - chrono::Duration::milliseconds(elapsed as i64),
+                            end: Some(event.ts),
+                            status: None,
+                        });
+                    }
+                }
+                EventType::OutputBurst => timeline.marks.push(Mark {
+                    kind: MarkKind::Output,
+                    ts: event.ts,
+                    stage,
+                    message: event.message,
+                }),
+                EventType::Progress => timeline.marks.push(Mark {
+                    kind: MarkKind::Progress,
+                    ts: event.ts,
+                    stage,
+                    message: event.message,
+                }),
+                EventType::ErrorDetected | EventType::ProviderError => timeline.marks.push(Mark {
+                    kind: MarkKind::Error,
+                    ts: event.ts,
+                    stage,
+                    message: event.message,
+                }),
+                _ if event.level == EventLevel::Error => timeline.marks.push(Mark {
+                    kind: MarkKind::Error,
+                    ts: event.ts,
+                    stage,
+                    message: event.message,
+                }),
+                _ => {}
+            }
+        }
+        timeline.spans.sort_by_key(|s| s.start);
+        timeline
+    }
+
+    fn close(
+        &mut self,
+        open: &mut HashMap<(SpanKind, String), usize>,
+        key: (SpanKind, String),
+        ts: DateTime<Utc>,
+        status: Option<String>,
+    ) {
+        if let Some(i) = open.remove(&key) {
+            self.spans[i].end = Some(ts);
+            self.spans[i].status = status;
+        }
+    }
+
+    /// Whether any span is still open.
+    pub fn is_running(&self) -> bool {
+        self.spans.iter().any(|s| s.end.is_none())
+    }
+
+    /// Milliseconds from the first event to `now`.
+    pub fn total_ms(&self, now: DateTime<Utc>) -> u64 {
+        self.started
+            .map(|start| (now - start).num_milliseconds().max(0) as u64)
+            .unwrap_or(0)
+    }
+
+    /// Where the time went, with running spans counted up to `now`.
+    pub fn breakdown(&self, now: DateTime<Utc>) -> Breakdown {
+        let sum = |kind: SpanKind| -> u64 {
+            self.spans
+                .iter()
+                .filter(|s| s.kind == kind)
+                .map(|s| s.duration_ms(now))
+                .sum()
+        };
+        let total_ms = self.total_ms(now);
+        let provider_ms = sum(SpanKind::Provider);
+        let commands_ms = sum(SpanKind::Command);
+        let waiting_ms = sum(SpanKind::Waiting);
+        let paused_ms = sum(SpanKind::Paused);
+        Breakdown {
+            total_ms,
+            provider_ms,
+            commands_ms,
+            waiting_ms,
+            paused_ms,
+            other_ms: total_ms.saturating_sub(provider_ms + commands_ms + waiting_ms + paused_ms),
+        }
+    }
+
+    /// Column range a span covers in a bar `width` cells wide spanning the
+    /// whole session. Always at least one cell.
+    pub fn bar_range(&self, span: &Span, width: usize, now: DateTime<Utc>) -> (usize, usize) {
+        let total = self.total_ms(now).max(1) as f64;
+        let Some(started) = self.started else {
+            return (0, 1.min(width));
+        };
+        let offset = (span.start - started).num_milliseconds().max(0) as f64;
+        let from = ((offset / total) * width as f64) as usize;
+        let from = from.min(width.saturating_sub(1));
+        let len = ((span.duration_ms(now) as f64 / total) * width as f64).round() as usize;
+        (from, (from + len.max(1)).min(width))
+    }
+
+    /// Plain-text gantt rows: label, bar and duration for each span.
+    pub fn render_text(&self, width: usize, now: DateTime<Utc>) -> Vec<String> {
+        let label_width = self
+            .spans
+            .iter()
+            .map(|s| display_label(s).chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(28);
+        self.spans
+            .iter()
+            .map(|span| {
+                let (from, to) = self.bar_range(span, width, now);
+                let fill = match span.kind {
+                    SpanKind::Stage => '━',
+                    SpanKind::Provider => '█',
+                    SpanKind::Command => '▓',
+                    SpanKind::Waiting => '░',
+                    SpanKind::Paused => '·',
+                };
+                let bar: String = (0..width)
+                    .map(|i| if (from..to).contains(&i) { fill } else { ' ' })
+                    .collect();
+                let label: String = display_label(span).chars().take(label_width).collect();
+                let state = match (&span.end, &span.status) {
+                    (None, _) => " running".to_string(),
+                    (Some(_), Some(status)) => format!(" {status}"),
+                    _ => String::new(),
+                };
+                format!(
+                    "{label:<label_width$} │{bar}│ {}{state}",
+                    format_ms(span.duration_ms(now))
+                )
+            })
+            .collect()
+    }
+}
+
+/// The instant open spans run up to: now while the run's process is alive,
+/// otherwise the last event (a crashed run's spans stop where it did).
+pub fn clock(run_dir: &std::path::Path, timeline: &Timeline) -> DateTime<Utc> {
+    let live = super::run_pid(run_dir).is_some() && !run_dir.join("summary.json").exists();
+    match timeline.last_event {
+        Some(last) if !live => last,
+        _ => Utc::now(),
+    }
+}
+
+/// Span label indented under its stage.
+pub fn display_label(span: &Span) -> String {
+    match span.kind {
+        SpanKind::Stage | SpanKind::Waiting | SpanKind::Paused => span.label.clone(),
+        SpanKind::Provider | SpanKind::Command => format!("  {}", span.label),
+    }
+}
+
+/// Compact duration: `850ms`, `12.3s`, `4m05s`, `1h02m`.
+pub fn format_ms(ms: u64) -> String {
+    match ms {
+        0..=999 => format!("{ms}ms"),
+        1_000..=59_999 => format!("{:.1}s", ms as f64 / 1000.0),
+        60_000..=3_599_999 => format!("{}m{:02}s", ms / 60_000, ms % 60_000 / 1000),
+        _ => format!("{}h{:02}m", ms / 3_600_000, ms % 3_600_000 / 60_000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(
+        ts: &str,
+        event_type: &str,
+        stage: Option<&str>,
+        metadata: serde_json::Value,
+    ) -> String {
+        serde_json::json!({
+            "ts": ts,
+            "level": "info",
+            "run_id": "r1",
+            "event_type": event_type,
+            "stage": stage,
+            "message": event_type,
+            "metadata": metadata,
+        })
+        .to_string()
+    }
+
+    fn sample() -> String {
+        [
+            line(
+                "2026-01-01T00:00:00Z",
+                "movement_start",
+                Some("plan"),
+                serde_json::json!({}),
+            ),
+            line(
+                "2026-01-01T00:00:40Z",
+                "provider_call",
+                Some("plan"),
+                serde_json::json!({ "elapsed_ms": 30_000 }),
+            ),
+            line(
+                "2026-01-01T00:00:41Z",
+                "output_burst",
+                Some("plan"),
+                serde_json::json!({ "source": "provider" }),
+            ),
+            line(
+                "2026-01-01T00:00:45Z",
+                "command_start",
+                Some("plan"),
+                serde_json::json!({ "gate": "tests" }),
+            ),
+            line(
+                "2026-01-01T00:00:55Z",
+                "command_end",
+                Some("plan"),
+                serde_json::json!({ "gate": "tests", "status": "failed" }),
+            ),
+            line(
+                "2026-01-01T00:00:55Z",
+                "error_detected",
+                Some("plan"),
+                serde_json::json!({ "source": "gate:tests" }),
+            ),
+            line(
+                "2026-01-01T00:01:00Z",
+                "movement_end",
+                Some("plan"),
+                serde_json::json!({ "status": "completed", "duration_ms": 60_000 }),
+            ),
+            line(
+                "2026-01-01T00:01:00Z",
+                "hitl_request",
+                None,
+                serde_json::json!({ "gate": "commit" }),
+            ),
+            "not json".to_string(),
+        ]
+        .join("\n")
+    }
+
+    #[test]
+    fn test_pairs_events_into_spans_and_marks() {
+        let timeline = Timeline::from_events("r1", &sample());
+        let kinds: Vec<_> = timeline.spans.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SpanKind::Stage,
+                SpanKind::Provider,
+                SpanKind::Command,
+                SpanKind::Waiting
+            ]
+        );
+        let gate = &timeline.spans[2];
+        assert_eq!(gate.label, "tests");
+        assert_eq!(gate.status.as_deref(), Some("failed"));
+        assert!(timeline.spans[3].end.is_none());
+        assert!(timeline.is_running());
+        assert_eq!(timeline.marks.len(), 2);
+        assert_eq!(timeline.marks[1].kind, MarkKind::Error);
+    }
+
+    #[test]
+    fn test_breakdown_counts_running_spans_up_to_now() {
+        let timeline = Timeline::from_events("r1", &sample());
+        let now = "2026-01-01T00:02:00Z".parse().unwrap();
+        let breakdown = timeline.breakdown(now);
+        assert_eq!(breakdown.total_ms, 120_000);
+        assert_eq!(breakdown.provider_ms, 30_000);
+        assert_eq!(breakdown.commands_ms, 10_000);
+        assert_eq!(breakdown.waiting_ms, 60_000);
+        assert_eq!(breakdown.other_ms, 20_000);
+
+        let rows = timeline.render_text(12, now);
+        assert_eq!(rows.len(), 4);
+        assert!(rows[3].ends_with("1m00s running"), "{}", rows[3]);
+        assert!(rows[3].contains("      ░░░░░░"), "{}", rows[3]);
+    }
+
+    #[test]
+    fn test_format_ms() {
+        assert_eq!(format_ms(850), "850ms");
+        assert_eq!(format_ms(12_340), "12.3s");
+        assert_eq!(format_ms(245_000), "4m05s");
+        assert_eq!(format_ms(3_720_000), "1h02m");
+    }
+}
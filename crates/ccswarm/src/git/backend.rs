@@ -0,0 +1,136 @@
+//! The worktree, branch and status operations ccswarm needs from git, and
+//! the backends that provide them.
+//!
+//! [`ShellWorktreeManager`] runs the `git` CLI for each operation. With the
+//! `native-git` feature, [`super::native::NativeWorktreeManager`] does the
+//! same through libgit2, in-process. `repository.git_backend` in
+//! `ccswarm.json` picks one; the shell backend is the default, and the
+//! fallback whenever the native one is not compiled in or cannot open the
+//! repository.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use super::shell::{ShellWorktreeInfo, ShellWorktreeManager};
+
+/// Which implementation of [`WorktreeBackend`] to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitBackend {
+    /// The `git` command line.
+    #[default]
+    Shell,
+    /// libgit2 (needs the `native-git` feature).
+    Native,
+}
+
+/// Failures callers may want to tell apart; other errors carry git's own
+/// message. Both backends return these inside `anyhow::Error`, so
+/// `err.downcast_ref::<GitError>()` works the same for either.
+#[derive(Debug, thiserror::Error)]
+pub enum GitError {
+    #[error("{} is not a git repository", .0.display())]
+    NotARepository(PathBuf),
+    #[error("A branch named '{0}' already exists")]
+    BranchExists(String),
+    #[error("{} is not a worktree of this repository", .0.display())]
+    UnknownWorktree(PathBuf),
+    #[error("{} has uncommitted changes; use --force to remove it anyway", .0.display())]
+    DirtyWorktree(PathBuf),
+    #[error("git {operation} failed: {message}")]
+    Failed {
+        operation: &'static str,
+        message: String,
+    },
+}
+
+/// A change to one side of a file's status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    TypeChange,
+    Untracked,
+    Conflicted,
+}
+
+/// One changed file in a worktree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStatus {
+    pub path: PathBuf,
+    /// Staged change, relative to `HEAD`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<Change>,
+    /// Unstaged change, relative to the index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<Change>,
+}
+
+#[async_trait]
+pub trait WorktreeBackend: Send + Sync + std::fmt::Debug {
+    fn kind(&self) -> GitBackend;
+
+    /// Every worktree of the repository; the main worktree comes first.
+    async fn list_worktrees(&self) -> Result<Vec<ShellWorktreeInfo>>;
+
+    /// Add a worktree at `path` on `branch`, creating the branch from `HEAD`
+    /// when `create_new_branch` is set or it does not exist yet.
+    async fn create_worktree_full(
+        &self,
+        path: &Path,
+        branch: &str,
+        create_new_branch: bool,
+    ) -> Result<ShellWorktreeInfo>;
+
+    /// Remove the worktree at `path`. Without `force`, one with uncommitted
+    /// changes is refused.
+    async fn remove_worktree_full(&self, path: &Path, force: bool) -> Result<()>;
+
+    /// Drop the registrations of worktrees whose directory is gone.
+    async fn prune_worktrees(&self) -> Result<()>;
+
+    async fn branch_exists(&self, branch: &str) -> Result<bool>;
+
+    /// The branch checked out in the worktree at `path`; empty when `HEAD`
+    /// is detached.
+    async fn current_branch(&self, path: &Path) -> Result<String>;
+
+    /// Changed and untracked files in the worktree at `path`.
+    async fn status(&self, path: &Path) -> Result<Vec<FileStatus>>;
+
+    /// Stage everything in the worktree at `path` and commit it. Does
+    /// nothing when there are no changes.
+    async fn commit_worktree_changes(&self, path: &Path, message: &str) -> Result<()>;
+
+    async fn create_worktree(&self, path: &Path, branch: &str) -> Result<ShellWorktreeInfo> {
+        self.create_worktree_full(path, branch, true).await
+    }
+
+    async fn remove_worktree(&self, path: &Path) -> Result<()> {
+        self.remove_worktree_full(path, false).await
+    }
+}
+
+/// The `kind` backend for `repo`, or the shell backend when that one is
+/// unavailable.
+pub fn open(kind: GitBackend, repo: &Path) -> Result<Box<dyn WorktreeBackend>> {
+    match kind {
+        GitBackend::Shell => {}
+        #[cfg(feature = "native-git")]
+        GitBackend::Native => match super::native::NativeWorktreeManager::open(repo) {
+            Ok(manager) => return Ok(Box::new(manager)),
+            Err(e) => warn!("Native git backend unavailable, using the git CLI: {e:#}"),
+        },
+        #[cfg(not(feature = "native-git"))]
+        GitBackend::Native => {
+            warn!("This ccswarm was built without the native-git feature; using the git CLI")
+        }
+    }
+    Ok(Box::new(ShellWorktreeManager::new(repo.to_path_buf())?))
+}
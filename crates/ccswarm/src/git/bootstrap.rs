@@ -0,0 +1,548 @@
+//! Bring agent worktrees back in line with `ccswarm.json` after a restart.
+//!
+//! Every configured agent names a worktree path and a branch. After a crash
+//! or restart some of those already exist, some only half exist (a branch
+//! without its worktree, a worktree moved elsewhere, a registration whose
+//! directory is gone) and some are missing. [`plan`] compares the config with
+//! what git reports and picks one [`Step`] per agent; [`apply`] carries the
+//! steps out. What happens to work that already exists is up to the
+//! [`WorktreePolicy`].
+//!
+//! Which session, run and queue task each agent was on is kept in
+//! [`BINDINGS_FILE`], so an adopted worktree gets its associations back
+//! instead of starting over.
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use super::shell::{ShellWorktreeInfo, ShellWorktreeManager, git};
+use crate::config::CcswarmConfig;
+
+/// Agent → worktree bindings, relative to the repository root.
+pub const BINDINGS_FILE: &str = ".ccswarm/worktrees.json";
+
+/// What to do with an agent worktree or branch that already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorktreePolicy {
+    /// Keep it as it is, moving or re-attaching it if needed.
+    #[default]
+    Adopt,
+    /// Keep the worktree but reset its branch to the base branch.
+    Reset,
+    /// Remove it and create the branch and worktree afresh from the base.
+    Recreate,
+}
+
+impl FromStr for WorktreePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "adopt" => Ok(Self::Adopt),
+            "reset" => Ok(Self::Reset),
+            "recreate" => Ok(Self::Recreate),
+            _ => Err(anyhow!(
+                "Unknown worktree policy '{}' (expected adopt, reset or recreate)",
+                s
+            )),
+        }
+    }
+}
+
+/// Where the config wants an agent's worktree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentWorktree {
+    pub agent: String,
+    pub path: PathBuf,
+    pub branch: String,
+}
+
+/// Configured agent worktrees, sorted by agent, with paths resolved against
+/// the repository.
+pub fn agent_worktrees(config: &CcswarmConfig, repo: &Path) -> Vec<AgentWorktree> {
+    let mut agents: Vec<AgentWorktree> = config
+        .agents
+        .iter()
+        .map(|(agent, c)| AgentWorktree {
+            agent: agent.clone(),
+            path: resolve(&repo.join(&c.worktree)),
+            branch: c.branch.clone(),
+        })
+        .collect();
+    agents.sort_by(|a, b| a.agent.cmp(&b.agent));
+    agents
+}
+
+/// Worktrees and branches as git sees them.
+#[derive(Debug, Clone, Default)]
+pub struct RepoState {
+    /// Live worktrees; the main worktree comes first.
+    pub worktrees: Vec<ShellWorktreeInfo>,
+    pub branches: HashSet<String>,
+    /// Paths that exist on disk, among those the agents are configured at.
+    pub occupied: HashSet<PathBuf>,
+}
+
+impl RepoState {
+    pub async fn load(repo: &Path, agents: &[AgentWorktree]) -> Result<Self> {
+        let manager = ShellWorktreeManager::new(repo.to_path_buf())?;
+        // Registrations whose directory is gone are pruned before applying.
+        let worktrees = manager
+            .list_worktrees()
+            .await?
+            .into_iter()
+            .filter(|w| !w.is_prunable)
+            .map(|mut w| {
+                w.path = resolve(&w.path);
+                w
+            })
+            .collect();
+        let branches = git(repo, &["branch", "--format=%(refname:short)"])
+            .await?
+            .lines()
+            .map(str::to_string)
+            .collect();
+        let occupied = agents
+            .iter()
+            .filter(|a| a.path.exists())
+            .map(|a| a.path.clone())
+            .collect();
+        Ok(Self {
+            worktrees,
+            branches,
+            occupied,
+        })
+    }
+}
+
+/// One agent's way back to its configured worktree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum Step {
+    /// Already in place; nothing to do.
+    Adopt,
+    /// Check the existing branch out in a new worktree.
+    Attach,
+    /// Create the branch from the base in a new worktree.
+    Create,
+    /// Move the worktree that has the branch to the configured path.
+    Move { from: PathBuf },
+    /// Reset the branch to the base inside the existing worktree.
+    Reset,
+    /// Remove these worktrees, then create the branch afresh from the base.
+    Recreate { remove: Vec<PathBuf> },
+    /// Needs a human; nothing is touched.
+    Blocked { reason: String },
+}
+
+impl Step {
+    /// Whether the agent's earlier work (and so its associations) survives.
+    pub fn keeps_work(&self) -> bool {
+        matches!(self, Self::Adopt | Self::Attach | Self::Move { .. })
+    }
+
+    /// Whether the step puts a worktree at the agent's path.
+    pub fn adds_worktree(&self) -> bool {
+        matches!(
+            self,
+            Self::Attach | Self::Create | Self::Move { .. } | Self::Recreate { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Planned {
+    pub agent: String,
+    pub path: PathBuf,
+    pub branch: String,
+    #[serde(flatten)]
+    pub step: Step,
+}
+
+/// Pick a step for every agent. Never plans to touch the main worktree or a
+/// directory git doesn't know as a worktree.
+pub fn plan(agents: &[AgentWorktree], state: &RepoState, policy: WorktreePolicy) -> Vec<Planned> {
+    let main = state.worktrees.first().map(|w| w.path.as_path());
+    agents
+        .iter()
+        .map(|agent| Planned {
+            agent: agent.agent.clone(),
+            path: agent.path.clone(),
+            branch: agent.branch.clone(),
+            step: plan_one(agent, state, policy, main),
+        })
+        .collect()
+}
+
+fn plan_one(
+    agent: &AgentWorktree,
+    state: &RepoState,
+    policy: WorktreePolicy,
+    main: Option<&Path>,
+) -> Step {
+    let blocked = |reason: String| Step::Blocked { reason };
+    let at_path = state.worktrees.iter().find(|w| w.path == agent.path);
+    let on_branch = state.worktrees.iter().find(|w| w.branch == agent.branch);
+
+    if let Some(w) = [at_path, on_branch]
+        .into_iter()
+        .flatten()
+        .find(|w| Some(w.path.as_path()) == main)
+    {
+        return blocked(format!(
+            "{} is the main worktree; point the agent somewhere else",
+            w.path.display()
+        ));
+    }
+
+    match (at_path, on_branch) {
+        (Some(here), Some(there)) if here.path == there.path => match policy {
+            WorktreePolicy::Adopt => Step::Adopt,
+            WorktreePolicy::Reset => Step::Reset,
+            WorktreePolicy::Recreate => Step::Recreate {
+                remove: vec![agent.path.clone()],
+            },
+        },
+        (Some(here), elsewhere) => match policy {
+            WorktreePolicy::Adopt => blocked(format!(
+                "{} has {} checked out, not {}",
+                here.path.display(),
+                describe_branch(&here.branch),
+                agent.branch
+            )),
+            WorktreePolicy::Reset | WorktreePolicy::Recreate => Step::Recreate {
+                remove: std::iter::once(here)
+                    .chain(elsewhere)
+                    .map(|w| w.path.clone())
+                    .collect(),
+            },
+        },
+        (None, Some(there)) if state.occupied.contains(&agent.path) => blocked(format!(
+            "{} exists but is not a worktree, and {} is checked out at {}",
+            agent.path.display(),
+            agent.branch,
+            there.path.display()
+        )),
+        (None, Some(there)) => match policy {
+            WorktreePolicy::Adopt => Step::Move {
+                from: there.path.clone(),
+            },
+            WorktreePolicy::Reset | WorktreePolicy::Recreate => Step::Recreate {
+                remove: vec![there.path.clone()],
+            },
+        },
+        (None, None) if state.occupied.contains(&agent.path) => blocked(format!(
+            "{} exists but is not a worktree; move it away first",
+            agent.path.display()
+        )),
+        (None, None) if state.branches.contains(&agent.branch) => match policy {
+            WorktreePolicy::Adopt => Step::Attach,
+            WorktreePolicy::Reset | WorktreePolicy::Recreate => Step::Create,
+        },
+        (None, None) => Step::Create,
+    }
+}
+
+fn describe_branch(branch: &str) -> String {
+    if branch.is_empty() {
+        "a detached HEAD".to_string()
+    } else {
+        branch.to_string()
+    }
+}
+
+/// Result of applying one planned step.
+#[derive(Debug, Clone, Serialize)]
+pub struct Outcome {
+    #[serde(flatten)]
+    pub planned: Planned,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Carry out `steps`, branching new worktrees from `base`. A failing agent
+/// doesn't stop the others.
+pub async fn apply(repo: &Path, base: &str, steps: Vec<Planned>) -> Result<Vec<Outcome>> {
+    git(repo, &["worktree", "prune"]).await?;
+    let mut outcomes = Vec::new();
+    for planned in steps {
+        let error = apply_one(repo, base, &planned)
+            .await
+            .err()
+            .map(|e| format!("{e:#}"));
+        outcomes.push(Outcome { planned, error });
+    }
+    Ok(outcomes)
+}
+
+async fn apply_one(repo: &Path, base: &str, planned: &Planned) -> Result<()> {
+    let path = path_arg(&planned.path)?;
+    let branch = planned.branch.as_str();
+    if planned.step.adds_worktree()
+        && let Some(parent) = planned.path.parent()
+    {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    match &planned.step {
+        Step::Adopt => Ok(()),
+        Step::Blocked { reason } => Err(anyhow!("{}", reason)),
+        Step::Attach => git(repo, &["worktree", "add", path, branch])
+            .await
+            .map(drop),
+        Step::Create => git(repo, &["worktree", "add", "-B", branch, path, base])
+            .await
+            .map(drop),
+        Step::Move { from } => git(repo, &["worktree", "move", path_arg(from)?, path])
+            .await
+            .map(drop),
+        Step::Reset => {
+            git(&planned.path, &["reset", "--hard", base]).await?;
+            git(&planned.path, &["clean", "-fd"]).await.map(drop)
+        }
+        Step::Recreate { remove } => {
+            for old in remove {
+                git(repo, &["worktree", "remove", "--force", path_arg(old)?]).await?;
+            }
+            git(repo, &["worktree", "add", "-B", branch, path, base])
+                .await
+                .map(drop)
+        }
+    }
+}
+
+/// Session, run and queue task an agent worktree was last used for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub path: PathBuf,
+    pub branch: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The bindings registry, keyed by agent name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorktreeBindings {
+    #[serde(default)]
+    pub agents: BTreeMap<String, Binding>,
+}
+
+impl WorktreeBindings {
+    pub fn path(repo: &Path) -> PathBuf {
+        repo.join(BINDINGS_FILE)
+    }
+
+    /// Load the registry; a missing file is an empty registry.
+    pub async fn load(repo: &Path) -> Result<Self> {
+        crate::governance::migrate::load_json(&Self::path(repo)).await
+    }
+
+    pub async fn save(&self, repo: &Path) -> Result<()> {
+        crate::governance::migrate::save_json(&Self::path(repo), self).await
+    }
+
+    /// Record where each successfully bootstrapped agent now lives. Agents
+    /// whose work survived keep their associations; the rest start clean.
+    pub fn record(&mut self, outcomes: &[Outcome]) {
+        for outcome in outcomes.iter().filter(|o| o.error.is_none()) {
+            let planned = &outcome.planned;
+            let previous = self
+                .agents
+                .remove(&planned.agent)
+                .filter(|_| planned.step.keeps_work());
+            self.agents.insert(
+                planned.agent.clone(),
+                Binding {
+                    path: planned.path.clone(),
+                    branch: planned.branch.clone(),
+                    session: previous.as_ref().and_then(|b| b.session.clone()),
+                    run_id: previous.as_ref().and_then(|b| b.run_id.clone()),
+                    task_id: previous.and_then(|b| b.task_id),
+                    updated_at: Utc::now(),
+                },
+            );
+        }
+    }
+}
+
+fn path_arg(path: &Path) -> Result<&str> {
+    path.to_str()
+        .ok_or_else(|| anyhow!("Invalid UTF-8 in worktree path: {:?}", path))
+}
+
+/// `path` without `.`/`..` components and with the part that exists
+/// canonicalized, so configured and git-reported paths compare equal.
+fn resolve(path: &Path) -> PathBuf {
+    let absolute;
+    let path = match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => {
+            absolute = cwd.join(path);
+            absolute.as_path()
+        }
+        _ => path,
+    };
+    let mut lexical = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                lexical.pop();
+            }
+            other => lexical.push(other),
+        }
+    }
+    let mut missing = Vec::new();
+    let mut existing = lexical.as_path();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |acc: PathBuf, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return lexical,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worktree(path: &str, branch: &str) -> ShellWorktreeInfo {
+        ShellWorktreeInfo {
+            path: PathBuf::from(path),
+            branch: branch.to_string(),
+            head_commit: "abc".to_string(),
+            is_locked: false,
+            is_bare: false,
+            is_prunable: false,
+        }
+    }
+
+    fn agent(name: &str, path: &str, branch: &str) -> AgentWorktree {
+        AgentWorktree {
+            agent: name.to_string(),
+            path: PathBuf::from(path),
+            branch: branch.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_plan_per_policy() {
+        let state = RepoState {
+            worktrees: vec![
+                worktree("/repo", "main"),
+                worktree("/wt/frontend", "feature/ui"),
+                worktree("/wt/old-backend", "feature/api"),
+                worktree("/wt/devops", "scratch"),
+            ],
+            branches: ["main", "feature/ui", "feature/api", "feature/qa", "scratch"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            occupied: HashSet::from([PathBuf::from("/wt/docs")]),
+        };
+        let agents = [
+            agent("frontend", "/wt/frontend", "feature/ui"),
+            agent("backend", "/wt/backend", "feature/api"),
+            agent("devops", "/wt/devops", "feature/infra"),
+            agent("qa", "/wt/qa", "feature/qa"),
+            agent("docs", "/wt/docs", "feature/docs"),
+            agent("new", "/wt/new", "feature/new"),
+            agent("main", "/repo", "main"),
+        ];
+        let steps = |policy| -> Vec<Step> {
+            plan(&agents, &state, policy)
+                .into_iter()
+                .map(|p| p.step)
+                .collect()
+        };
+
+        let adopt = steps(WorktreePolicy::Adopt);
+        assert_eq!(adopt[0], Step::Adopt);
+        assert_eq!(
+            adopt[1],
+            Step::Move {
+                from: PathBuf::from("/wt/old-backend")
+            }
+        );
+        assert!(matches!(&adopt[2], Step::Blocked { reason } if reason.contains("scratch")));
+        assert_eq!(adopt[3], Step::Attach);
+        assert!(matches!(adopt[4], Step::Blocked { .. }));
+        assert_eq!(adopt[5], Step::Create);
+        assert!(matches!(&adopt[6], Step::Blocked { reason } if reason.contains("main worktree")));
+
+        let recreate = steps(WorktreePolicy::Recreate);
+        assert_eq!(
+            recreate[0],
+            Step::Recreate {
+                remove: vec![PathBuf::from("/wt/frontend")]
+            }
+        );
+        assert_eq!(
+            recreate[1],
+            Step::Recreate {
+                remove: vec![PathBuf::from("/wt/old-backend")]
+            }
+        );
+        assert_eq!(
+            recreate[2],
+            Step::Recreate {
+                remove: vec![PathBuf::from("/wt/devops")]
+            }
+        );
+        assert_eq!(recreate[3], Step::Create);
+        assert!(matches!(recreate[4], Step::Blocked { .. }));
+        assert!(matches!(recreate[6], Step::Blocked { .. }));
+
+        assert_eq!(steps(WorktreePolicy::Reset)[0], Step::Reset);
+    }
+
+    #[test]
+    fn test_bindings_survive_only_when_work_does() {
+        let binding = |session: &str| Binding {
+            path: PathBuf::from("/wt/a"),
+            branch: "feature/a".to_string(),
+            session: Some(session.to_string()),
+            run_id: Some("run-1".to_string()),
+            task_id: Some("t-1".to_string()),
+            updated_at: Utc::now(),
+        };
+        let mut bindings = WorktreeBindings::default();
+        bindings.agents.insert("a".to_string(), binding("api-work"));
+        bindings.agents.insert("b".to_string(), binding("ui-work"));
+        let outcome = |name: &str, step: Step| Outcome {
+            planned: Planned {
+                agent: name.to_string(),
+                path: PathBuf::from(format!("/wt/{name}")),
+                branch: format!("feature/{name}"),
+                step,
+            },
+            error: None,
+        };
+
+        bindings.record(&[
+            outcome("a", Step::Adopt),
+            outcome("b", Step::Recreate { remove: vec![] }),
+        ]);
+        assert_eq!(bindings.agents["a"].session.as_deref(), Some("api-work"));
+        assert_eq!(bindings.agents["a"].task_id.as_deref(), Some("t-1"));
+        assert_eq!(bindings.agents["b"].session, None);
+        assert_eq!(bindings.agents["b"].path, PathBuf::from("/wt/b"));
+    }
+}
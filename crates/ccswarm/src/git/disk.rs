@@ -0,0 +1,569 @@
+//! Worktree disk usage, quotas and build-artifact cleanup.
+//!
+//! Each agent works in its own worktree, and each worktree grows its own
+//! `target/` and `node_modules/`. Four agents on one project can hold four
+//! copies of the same dependencies. This module measures that, checks it
+//! against an optional per-worktree quota, and reclaims space: artifacts
+//! nobody has built into for a while are pruned, and `node_modules` trees
+//! duplicated across worktrees are hard-linked to one copy. The main
+//! worktree is measured but never modified.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// A directory of generated files that can be rebuilt or reinstalled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// Cargo's `target/` directory.
+    CargoTarget,
+    NodeModules,
+}
+
+impl ArtifactKind {
+    fn detect(dir: &Path) -> Option<Self> {
+        match dir.file_name()?.to_str()? {
+            "node_modules" => Some(Self::NodeModules),
+            // Only a `target/` that cargo made, not any directory of that name.
+            "target"
+                if dir.join("CACHEDIR.TAG").exists()
+                    || dir.parent().is_some_and(|p| p.join("Cargo.toml").exists()) =>
+            {
+                Some(Self::CargoTarget)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::CargoTarget => "target",
+            Self::NodeModules => "node_modules",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Artifact {
+    pub kind: ArtifactKind,
+    pub path: PathBuf,
+    /// Path relative to the worktree root; duplicates share it.
+    pub relative: PathBuf,
+    pub bytes: u64,
+    /// Newest modification time of anything inside.
+    pub modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorktreeUsage {
+    pub path: PathBuf,
+    pub branch: String,
+    pub is_main: bool,
+    pub bytes: u64,
+    pub artifacts: Vec<Artifact>,
+}
+
+impl WorktreeUsage {
+    pub fn artifact_bytes(&self) -> u64 {
+        self.artifacts.iter().map(|a| a.bytes).sum()
+    }
+
+    /// Quotas apply to agent worktrees; the main worktree is the user's.
+    pub fn over_quota(&self, quota: Option<u64>) -> bool {
+        !self.is_main && quota.is_some_and(|quota| self.bytes > quota)
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    bytes: u64,
+    newest: Option<SystemTime>,
+}
+
+impl Tally {
+    fn add(&mut self, other: &Tally) {
+        self.bytes += other.bytes;
+        self.newest = self.newest.max(other.newest);
+    }
+}
+
+/// Identity of the file behind `meta`, so hard links are counted once.
+#[cfg(unix)]
+fn file_id(meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// A directory holding its own repository or worktree; measured on its own.
+fn is_nested_checkout(dir: &Path, root: &Path) -> bool {
+    dir != root && dir.join(".git").exists()
+}
+
+fn tally(dir: &Path, seen: &mut HashSet<(u64, u64)>) -> Tally {
+    let mut tally = Tally::default();
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        if file_id(&meta).is_some_and(|id| !seen.insert(id)) {
+            continue;
+        }
+        tally.bytes += meta.len();
+        tally.newest = tally.newest.max(meta.modified().ok());
+    }
+    tally
+}
+
+/// Measure the worktree at `path`. `.git` and nested checkouts are left out.
+pub fn measure(path: &Path, branch: &str, is_main: bool) -> WorktreeUsage {
+    let mut seen = HashSet::new();
+    let mut total = Tally::default();
+    let mut artifacts = Vec::new();
+    let mut walker = WalkDir::new(path).into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else { continue };
+        let entry_path = entry.path();
+        if entry.file_type().is_dir() {
+            if entry.file_name() == ".git" || is_nested_checkout(entry_path, path) {
+                walker.skip_current_dir();
+            } else if let Some(kind) = ArtifactKind::detect(entry_path) {
+                let inner = tally(entry_path, &mut seen);
+                total.add(&inner);
+                artifacts.push(Artifact {
+                    kind,
+                    path: entry_path.to_path_buf(),
+                    relative: entry_path
+                        .strip_prefix(path)
+                        .unwrap_or(entry_path)
+                        .to_path_buf(),
+                    bytes: inner.bytes,
+                    modified: inner.newest.map(DateTime::<Utc>::from),
+                });
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+        // A linked worktree's `.git` is a file pointing at the main repository.
+        if !entry.file_type().is_file() || entry.file_name() == ".git" {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        if file_id(&meta).is_some_and(|id| !seen.insert(id)) {
+            continue;
+        }
+        total.bytes += meta.len();
+    }
+    artifacts.sort_by_key(|a| std::cmp::Reverse(a.bytes));
+    WorktreeUsage {
+        path: path.to_path_buf(),
+        branch: branch.to_string(),
+        is_main,
+        bytes: total.bytes,
+        artifacts,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GcAction {
+    /// Delete the artifact directory.
+    Prune,
+    /// Replace files identical to another worktree's copy with hard links.
+    Link,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GcItem {
+    pub action: GcAction,
+    pub worktree: PathBuf,
+    pub artifact: PathBuf,
+    pub kind: ArtifactKind,
+    /// For [`GcAction::Link`], the copy files are linked to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+    pub reason: String,
+    /// Bytes the action frees.
+    pub bytes: u64,
+    /// Whether the action was carried out (false on a dry run).
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GcReport {
+    pub items: Vec<GcItem>,
+    pub reclaimable_bytes: u64,
+    pub reclaimed_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct GcOptions {
+    /// Artifacts with nothing newer than this are stale.
+    pub stale_after: chrono::Duration,
+    /// Per-worktree limit; artifacts of worktrees over it are pruned,
+    /// largest first, until the worktree fits.
+    pub quota: Option<u64>,
+    /// Delete stale and over-quota artifacts.
+    pub prune: bool,
+    /// Hard-link duplicated `node_modules`.
+    pub link: bool,
+    pub now: DateTime<Utc>,
+}
+
+/// Find reclaimable artifacts in agent worktrees and, when `options` asks
+/// for it, reclaim them.
+pub fn collect_garbage(usages: &[WorktreeUsage], options: &GcOptions) -> Result<GcReport> {
+    let mut items = Vec::new();
+    let mut pruned: HashSet<&Path> = HashSet::new();
+
+    for usage in usages.iter().filter(|u| !u.is_main) {
+        let mut remaining = usage.bytes;
+        for artifact in &usage.artifacts {
+            let stale = artifact
+                .modified
+                .is_none_or(|modified| options.now - modified > options.stale_after);
+            let reason = if stale {
+                match artifact.modified {
+                    Some(modified) => {
+                        format!("untouched for {} days", (options.now - modified).num_days())
+                    }
+                    None => "empty".to_string(),
+                }
+            } else if options.quota.is_some_and(|quota| remaining > quota) {
+                // Still over quota with the artifacts pruned so far.
+                "worktree over quota".to_string()
+            } else {
+                continue;
+            };
+            remaining -= artifact.bytes.min(remaining);
+            pruned.insert(&artifact.path);
+            items.push(GcItem {
+                action: GcAction::Prune,
+                worktree: usage.path.clone(),
+                artifact: artifact.path.clone(),
+                kind: artifact.kind,
+                source: None,
+                reason,
+                bytes: artifact.bytes,
+                applied: false,
+            });
+        }
+    }
+
+    // node_modules at the same place in several worktrees: keep the first
+    // copy (the main worktree's, if it has one) and link the rest to it.
+    let mut sources: Vec<&Artifact> = Vec::new();
+    let ordered = usages
+        .iter()
+        .filter(|u| u.is_main)
+        .chain(usages.iter().filter(|u| !u.is_main));
+    for usage in ordered {
+        for artifact in &usage.artifacts {
+            if artifact.kind != ArtifactKind::NodeModules
+                || pruned.contains(artifact.path.as_path())
+            {
+                continue;
+            }
+            let Some(source) = sources.iter().find(|s| s.relative == artifact.relative) else {
+                sources.push(artifact);
+                continue;
+            };
+            if usage.is_main {
+                continue;
+            }
+            let bytes = link_duplicates(&source.path, &artifact.path, false)?;
+            if bytes > 0 {
+                items.push(GcItem {
+                    action: GcAction::Link,
+                    worktree: usage.path.clone(),
+                    artifact: artifact.path.clone(),
+                    kind: artifact.kind,
+                    source: Some(source.path.clone()),
+                    reason: format!("duplicates {}", source.path.display()),
+                    bytes,
+                    applied: false,
+                });
+            }
+        }
+    }
+
+    let mut reclaimed_bytes = 0;
+    for item in &mut items {
+        match item.action {
+            GcAction::Prune if options.prune => {
+                std::fs::remove_dir_all(&item.artifact)
+                    .with_context(|| format!("Failed to remove {}", item.artifact.display()))?;
+            }
+            GcAction::Link if options.link => {
+                if let Some(source) = &item.source {
+                    item.bytes = link_duplicates(source, &item.artifact, true)?;
+                }
+            }
+            _ => continue,
+        }
+        item.applied = true;
+        reclaimed_bytes += item.bytes;
+    }
+
+    Ok(GcReport {
+        reclaimable_bytes: items.iter().map(|i| i.bytes).sum(),
+        reclaimed_bytes,
+        items,
+    })
+}
+
+/// Bytes in `target` that are byte-identical to the file at the same path
+/// under `source` and not already linked to it. With `apply`, each such file
+/// is replaced by a hard link to the `source` copy.
+fn link_duplicates(source: &Path, target: &Path, apply: bool) -> Result<u64> {
+    let mut bytes = 0;
+    for entry in WalkDir::new(target).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(target) else {
+            continue;
+        };
+        let original = source.join(relative);
+        let (Ok(ours), Ok(theirs)) = (entry.metadata(), std::fs::symlink_metadata(&original))
+        else {
+            continue;
+        };
+        if !theirs.is_file()
+            || ours.len() != theirs.len()
+            || file_id(&ours).is_some_and(|id| file_id(&theirs) == Some(id))
+            || std::fs::read(entry.path()).ok() != std::fs::read(&original).ok()
+        {
+            continue;
+        }
+        if apply {
+            // Link beside the file, then rename over it, so a failure never
+            // leaves the file missing.
+            let staging = entry.path().with_extension("ccswarm-link");
+            if std::fs::hard_link(&original, &staging).is_err() {
+                // Different filesystem or no permission; leave it alone.
+                continue;
+            }
+            std::fs::rename(&staging, entry.path())
+                .with_context(|| format!("Failed to replace {}", entry.path().display()))?;
+        }
+        bytes += ours.len();
+    }
+    Ok(bytes)
+}
+
+/// Parse a size such as `500M`, `5G`, `1.5GiB` or `1048576` (bytes).
+/// Units are powers of 1024.
+pub fn parse_size(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid size '{}'", text))?;
+    let unit = unit.trim().to_ascii_uppercase();
+    let multiplier: u64 = match unit.trim_end_matches("IB").trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => anyhow::bail!("Invalid size unit in '{}' (expected K, M, G or T)", text),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// `bytes` for display, e.g. `1.5 GiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn age(path: &Path, days: u64) {
+        let when = SystemTime::now() - std::time::Duration::from_secs(days * 86_400);
+        for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() {
+                fs::File::options()
+                    .write(true)
+                    .open(entry.path())
+                    .unwrap()
+                    .set_modified(when)
+                    .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_size_parsing_and_formatting() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("500M").unwrap(), 500 << 20);
+        assert_eq!(parse_size("1.5GiB").unwrap(), 3 << 29);
+        assert_eq!(parse_size(" 2 gb ").unwrap(), 2 << 30);
+        assert!(parse_size("5X").is_err());
+        assert!(parse_size("lots").is_err());
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(3 << 29), "1.5 GiB");
+    }
+
+    #[test]
+    fn test_measure_finds_artifacts_and_skips_git_and_nested_checkouts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(&root.join("Cargo.toml"), "[package]");
+        write(&root.join("target/debug/app"), &"x".repeat(1000));
+        write(
+            &root.join("web/node_modules/react/index.js"),
+            &"y".repeat(300),
+        );
+        write(&root.join("docs/target/notes.md"), "not cargo's");
+        write(&root.join(".git/objects/pack"), &"z".repeat(5000));
+        write(&root.join("agents/qa/.git"), "gitdir: elsewhere");
+        write(&root.join("agents/qa/big.bin"), &"q".repeat(5000));
+
+        let usage = measure(root, "agent-qa", false);
+
+        let kinds: Vec<_> = usage
+            .artifacts
+            .iter()
+            .map(|a| (a.kind, a.relative.clone(), a.bytes))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (ArtifactKind::CargoTarget, PathBuf::from("target"), 1000),
+                (
+                    ArtifactKind::NodeModules,
+                    PathBuf::from("web/node_modules"),
+                    300
+                ),
+            ]
+        );
+        let expected = "[package]".len() + 1000 + 300 + "not cargo's".len();
+        assert_eq!(usage.bytes, expected as u64);
+        assert!(usage.over_quota(Some(1000)));
+        assert!(!usage.over_quota(None));
+        assert!(!measure(root, "main", true).over_quota(Some(1000)));
+    }
+
+    #[test]
+    fn test_gc_prunes_stale_artifacts_and_links_duplicate_node_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main");
+        let agent = dir.path().join("agent-frontend");
+        let stale = dir.path().join("agent-backend");
+        for root in [&main, &agent] {
+            write(&root.join("node_modules/lib/a.js"), &"a".repeat(400));
+            write(&root.join("node_modules/lib/b.js"), "same");
+        }
+        write(&agent.join("node_modules/lib/b.js"), "diff");
+        write(&stale.join("Cargo.toml"), "[package]");
+        write(&stale.join("target/debug/app"), &"t".repeat(2000));
+        age(&stale.join("target"), 30);
+
+        let usages = vec![
+            measure(&main, "main", true),
+            measure(&agent, "agent-frontend", false),
+            measure(&stale, "agent-backend", false),
+        ];
+        let mut options = GcOptions {
+            stale_after: chrono::Duration::days(7),
+            quota: None,
+            prune: false,
+            link: false,
+            now: Utc::now(),
+        };
+
+        let dry_run = collect_garbage(&usages, &options).unwrap();
+        assert_eq!(dry_run.reclaimed_bytes, 0);
+        assert_eq!(dry_run.reclaimable_bytes, 2400);
+        assert!(stale.join("target").exists());
+
+        options.prune = true;
+        options.link = true;
+        let report = collect_garbage(&usages, &options).unwrap();
+        assert_eq!(report.reclaimed_bytes, 2400);
+        let prune = &report.items[0];
+        assert_eq!(prune.action, GcAction::Prune);
+        assert!(prune.reason.starts_with("untouched for 30 days"));
+        assert!(!stale.join("target").exists());
+        assert_eq!(
+            fs::read_to_string(agent.join("node_modules/lib/b.js")).unwrap(),
+            "diff",
+            "files that differ are left alone"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let linked = fs::metadata(agent.join("node_modules/lib/a.js")).unwrap();
+            let original = fs::metadata(main.join("node_modules/lib/a.js")).unwrap();
+            assert_eq!(linked.ino(), original.ino());
+        }
+
+        let usages: Vec<_> = [(&main, true), (&agent, false), (&stale, false)]
+            .into_iter()
+            .map(|(root, is_main)| measure(root, "", is_main))
+            .collect();
+        let again = collect_garbage(&usages, &options).unwrap();
+        assert!(again.items.is_empty(), "already linked files are skipped");
+    }
+
+    #[test]
+    fn test_gc_prunes_largest_artifacts_of_over_quota_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = dir.path().join("agent");
+        write(&agent.join("Cargo.toml"), "[package]");
+        write(&agent.join("target/debug/app"), &"t".repeat(3000));
+        write(&agent.join("app/node_modules/x.js"), &"n".repeat(500));
+        let usages = vec![measure(&agent, "agent", false)];
+
+        let report = collect_garbage(
+            &usages,
+            &GcOptions {
+                stale_after: chrono::Duration::days(7),
+                quota: Some(1000),
+                prune: false,
+                link: false,
+                now: Utc::now(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(report.items.len(), 1, "pruning target/ is enough");
+        assert_eq!(report.items[0].reason, "worktree over quota");
+        assert_eq!(report.items[0].artifact, agent.join("target"));
+    }
+}
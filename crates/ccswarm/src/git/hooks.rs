@@ -0,0 +1,342 @@
+//! Git hooks that gate agent commits on the quality checks.
+//!
+//! `quality_gate_frequency: on_commit` asks for the checks on every commit
+//! rather than when a task completes. `ccswarm hooks install` makes git ask
+//! for them: it writes `pre-commit`, `post-commit` and `pre-push` scripts into
+//! the worktree's own git directory and points that worktree's
+//! `core.hooksPath` at them, so the main worktree and the other agents keep
+//! their hooks. Each script calls back into `ccswarm hooks run`, then chains
+//! to the repository's own hook of the same name, if there is one: in the
+//! shared `core.hooksPath` when that is set (husky, lefthook), otherwise in
+//! the common git directory's `hooks/`.
+//!
+//! A commit is let through without the checks when [`BYPASS_ENV`] is set (its
+//! value is the reason) or with `git commit --no-verify`. Both end up in the
+//! security audit log: the first when `pre-commit` sees the variable, the
+//! second when `post-commit` finds the committed tree is not the one
+//! `pre-commit` checked. `git push --no-verify` runs no hook at all and
+//! cannot be recorded.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::shell::git;
+use crate::utils::command::shell_quote;
+
+/// Set to the reason to let a commit or push through without the checks.
+pub const BYPASS_ENV: &str = "CCSWARM_HOOK_BYPASS";
+
+/// Directory under the worktree's git directory the scripts are written to.
+const HOOKS_DIR: &str = "ccswarm-hooks";
+
+/// The tree `pre-commit` last checked, under the worktree's git directory.
+const CHECKED_TREE_FILE: &str = "ccswarm-checked-tree";
+
+/// A hook `ccswarm hooks install` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PreCommit,
+    PostCommit,
+    PrePush,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 3] = [Hook::PreCommit, Hook::PostCommit, Hook::PrePush];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PreCommit => "pre-commit",
+            Hook::PostCommit => "post-commit",
+            Hook::PrePush => "pre-push",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|hook| hook.name() == name)
+    }
+}
+
+/// The ccswarm invocation the scripts call back into.
+#[derive(Debug, Clone)]
+pub struct HookCommand {
+    pub exe: PathBuf,
+    /// `--config` to pass, absolute so it holds in any worktree.
+    pub config: Option<PathBuf>,
+    /// Quality profile to run instead of the branch's.
+    pub profile: Option<String>,
+}
+
+impl HookCommand {
+    /// The current executable with `config`, made absolute.
+    pub fn current(config: &Path, profile: Option<String>) -> Result<Self> {
+        let exe = std::env::current_exe().context("Failed to locate the ccswarm executable")?;
+        let config = if config.exists() {
+            Some(std::path::absolute(config)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            exe,
+            config,
+            profile,
+        })
+    }
+}
+
+/// The script for `hook`, chaining to the hook of the same name in
+/// `chain_dir`, or in git's default hooks directory when that is `None`.
+pub fn script(hook: Hook, command: &HookCommand, chain_dir: Option<&str>) -> String {
+    let mut run = shell_quote(&command.exe.to_string_lossy());
+    if let Some(config) = &command.config {
+        run.push_str(&format!(
+            " --config {}",
+            shell_quote(&config.to_string_lossy())
+        ));
+    }
+    run.push_str(&format!(" hooks run {}", hook.name()));
+    if let Some(profile) = &command.profile {
+        run.push_str(&format!(" --profile {}", shell_quote(profile)));
+    }
+    let name = hook.name();
+    let chained = match chain_dir {
+        Some(dir) => shell_quote(&format!("{}/{name}", dir.trim_end_matches('/'))),
+        None => format!("\"$(git rev-parse --git-common-dir)/hooks/{name}\""),
+    };
+    // git feeds `pre-push` the refs being pushed on stdin; keep a copy for
+    // the chained hook.
+    let (run, chain) = match hook {
+        Hook::PrePush => (
+            format!("input=$(cat)\nprintf '%s\\n' \"$input\" | {run} \"$@\" || exit $?"),
+            "printf '%s\\n' \"$input\" | \"$chained\" \"$@\"".to_string(),
+        ),
+        _ => (
+            format!("{run} \"$@\" || exit $?"),
+            "\"$chained\" \"$@\"".to_string(),
+        ),
+    };
+    format!(
+        "#!/bin/sh\n\
+         # Installed by `ccswarm hooks install`; remove with `ccswarm hooks uninstall`.\n\
+         {run}\n\
+         chained={chained}\n\
+         if [ -x \"$chained\" ]; then\n    exec {chain}\nfi\n"
+    )
+}
+
+/// Install the hooks into `worktree`. Returns the directory they went to.
+pub async fn install(worktree: &Path, command: &HookCommand) -> Result<PathBuf> {
+    let dir = git_dir(worktree).await?.join(HOOKS_DIR);
+    let shared = shared_hooks_path(worktree)
+        .await
+        .filter(|shared| Path::new(shared) != dir);
+    tokio::fs::create_dir_all(&dir).await?;
+    for hook in Hook::ALL {
+        let path = dir.join(hook.name());
+        tokio::fs::write(&path, script(hook, command, shared.as_deref()))
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).await?;
+        }
+    }
+    // Without worktreeConfig, `--worktree` writes the shared config and the
+    // hooks would gate every worktree.
+    git(worktree, &["config", "extensions.worktreeConfig", "true"]).await?;
+    git(
+        worktree,
+        &[
+            "config",
+            "--worktree",
+            "core.hooksPath",
+            &dir.to_string_lossy(),
+        ],
+    )
+    .await?;
+    Ok(dir)
+}
+
+/// Remove the hooks from `worktree`. Returns whether any were installed.
+pub async fn uninstall(worktree: &Path) -> Result<bool> {
+    let was_installed = installed(worktree).await?;
+    if was_installed {
+        git(
+            worktree,
+            &["config", "--worktree", "--unset", "core.hooksPath"],
+        )
+        .await?;
+    }
+    let git_dir = git_dir(worktree).await?;
+    for path in [git_dir.join(HOOKS_DIR), git_dir.join(CHECKED_TREE_FILE)] {
+        if path.is_dir() {
+            tokio::fs::remove_dir_all(&path).await?;
+        } else if path.exists() {
+            tokio::fs::remove_file(&path).await?;
+        }
+    }
+    Ok(was_installed)
+}
+
+/// Whether `worktree`'s hooks path points at the installed hooks.
+pub async fn installed(worktree: &Path) -> Result<bool> {
+    let dir = git_dir(worktree).await?.join(HOOKS_DIR);
+    let hooks_path = git(
+        worktree,
+        &["config", "--worktree", "--get", "core.hooksPath"],
+    )
+    .await
+    .unwrap_or_default();
+    let hooks_path = hooks_path.trim();
+    Ok(!hooks_path.is_empty() && Path::new(hooks_path) == dir)
+}
+
+/// Remember the staged tree as checked, for [`take_checked`].
+pub async fn record_checked(worktree: &Path) -> Result<()> {
+    let tree = git(worktree, &["write-tree"]).await?;
+    let path = git_dir(worktree).await?.join(CHECKED_TREE_FILE);
+    tokio::fs::write(&path, tree).await?;
+    Ok(())
+}
+
+/// Whether the tree of the commit just made is the one `pre-commit` checked.
+/// Forgets the checked tree either way.
+pub async fn take_checked(worktree: &Path) -> Result<bool> {
+    let path = git_dir(worktree).await?.join(CHECKED_TREE_FILE);
+    let checked = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+    let _ = tokio::fs::remove_file(&path).await;
+    let committed = git(worktree, &["rev-parse", "HEAD^{tree}"]).await?;
+    Ok(!checked.trim().is_empty() && checked.trim() == committed.trim())
+}
+
+/// The `core.hooksPath` every worktree shares, as set by husky or lefthook.
+/// The worktree's own setting is skipped: that is where the scripts go.
+async fn shared_hooks_path(worktree: &Path) -> Option<String> {
+    for scope in ["--local", "--global", "--system"] {
+        let path = git(
+            worktree,
+            &["config", scope, "--type=path", "--get", "core.hooksPath"],
+        )
+        .await
+        .unwrap_or_default();
+        if !path.trim().is_empty() {
+            return Some(path.trim().to_string());
+        }
+    }
+    None
+}
+
+/// The worktree's own git directory (`.git/worktrees/<name>` for a linked
+/// worktree).
+async fn git_dir(worktree: &Path) -> Result<PathBuf> {
+    git(worktree, &["rev-parse", "--absolute-git-dir"])
+        .await
+        .map(|dir| PathBuf::from(dir.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sh(dir: &Path, args: &[&str]) {
+        git(dir, args).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn hooks_are_scoped_to_the_worktree_and_detect_no_verify() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        sh(&repo, &["init", "-q"]).await;
+        sh(&repo, &["config", "user.email", "t@example.com"]).await;
+        sh(&repo, &["config", "user.name", "t"]).await;
+        sh(&repo, &["commit", "-q", "--allow-empty", "-m", "init"]).await;
+        let agent = tmp.path().join("agent");
+        sh(
+            &repo,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "agent",
+                &agent.to_string_lossy(),
+            ],
+        )
+        .await;
+
+        // A stand-in for ccswarm that only passes commits of `ok`.
+        let fake = tmp.path().join("fake-ccswarm");
+        std::fs::write(
+            &fake,
+            "#!/bin/sh\n[ \"$3\" != pre-commit ] || git diff --cached --name-only | grep -qx ok\n",
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let command = HookCommand {
+            exe: fake,
+            config: None,
+            profile: Some("fast".into()),
+        };
+        assert!(
+            script(Hook::PrePush, &command, None).contains("hooks run pre-push --profile fast")
+        );
+        assert!(
+            script(Hook::PreCommit, &command, Some(".husky/_/"))
+                .contains("chained=.husky/_/pre-commit\n")
+        );
+
+        // A husky-style shared hooks directory the scripts chain to.
+        let shared = tmp.path().join("shared-hooks");
+        std::fs::create_dir(&shared).unwrap();
+        let chained = tmp.path().join("chained");
+        std::fs::write(
+            shared.join("pre-commit"),
+            format!("#!/bin/sh\ntouch '{}'\n", chained.display()),
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(
+                shared.join("pre-commit"),
+                std::fs::Permissions::from_mode(0o755),
+            )
+            .unwrap();
+        }
+        sh(
+            &repo,
+            &["config", "core.hooksPath", &shared.to_string_lossy()],
+        )
+        .await;
+
+        install(&agent, &command).await.unwrap();
+        assert!(installed(&agent).await.unwrap());
+        assert!(!installed(&repo).await.unwrap());
+
+        std::fs::write(agent.join("bad"), "").unwrap();
+        sh(&agent, &["add", "bad"]).await;
+        assert!(git(&agent, &["commit", "-q", "-m", "bad"]).await.is_err());
+        std::fs::write(agent.join("ok"), "").unwrap();
+        sh(&agent, &["reset", "-q"]).await;
+        sh(&agent, &["add", "ok"]).await;
+        sh(&agent, &["commit", "-q", "-m", "ok"]).await;
+        assert!(
+            chained.exists(),
+            "the shared pre-commit hook was not chained"
+        );
+
+        record_checked(&agent).await.unwrap();
+        assert!(take_checked(&agent).await.unwrap());
+        sh(&agent, &["add", "bad"]).await;
+        sh(&agent, &["commit", "-q", "--no-verify", "-m", "bad"]).await;
+        assert!(!take_checked(&agent).await.unwrap());
+
+        assert!(uninstall(&agent).await.unwrap());
+        assert!(!installed(&agent).await.unwrap());
+    }
+}
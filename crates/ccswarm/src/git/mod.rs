@@ -1,5 +1,13 @@
+pub mod backend;
+pub mod bootstrap;
+pub mod disk;
+pub mod hooks;
+#[cfg(feature = "native-git")]
+pub mod native;
+pub mod scaffold;
 pub mod shell;
 
+pub use backend::{GitBackend, GitError, WorktreeBackend};
 pub use shell::{ShellWorktreeInfo as WorktreeInfo, ShellWorktreeManager as WorktreeManager};
 
 /// Git utilities without libgit2 dependency
@@ -0,0 +1,445 @@
+//! libgit2-backed [`WorktreeBackend`] (feature `native-git`).
+//!
+//! Every operation opens the repository in-process instead of starting a
+//! `git` process, and reports failures as [`GitError`] variants rather than
+//! git's stderr. libgit2 calls block, so each runs on the blocking pool.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use git2::{
+    BranchType, IndexAddOption, Repository, Status, StatusOptions, WorktreeAddOptions,
+    WorktreeLockStatus, WorktreePruneOptions,
+};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::backend::{Change, FileStatus, GitBackend, GitError, WorktreeBackend};
+use super::shell::ShellWorktreeInfo;
+
+#[derive(Debug)]
+pub struct NativeWorktreeManager {
+    repo_path: PathBuf,
+}
+
+impl NativeWorktreeManager {
+    /// Errors with [`GitError::NotARepository`] unless `repo_path` is in a
+    /// repository libgit2 can open.
+    pub fn open(repo_path: &Path) -> Result<Self> {
+        Repository::open(repo_path)
+            .map_err(|_| GitError::NotARepository(repo_path.to_path_buf()))?;
+        Ok(Self {
+            repo_path: repo_path.to_path_buf(),
+        })
+    }
+
+    /// Run `f` against the repository on the blocking pool.
+    async fn with_repo<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Repository) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let path = self.repo_path.clone();
+        tokio::task::spawn_blocking(move || f(&open_repo(&path)?))
+            .await
+            .context("git task panicked")?
+    }
+}
+
+fn open_repo(path: &Path) -> Result<Repository> {
+    Ok(Repository::open(path).map_err(|_| GitError::NotARepository(path.to_path_buf()))?)
+}
+
+fn failed(operation: &'static str) -> impl FnOnce(git2::Error) -> anyhow::Error {
+    move |e| {
+        GitError::Failed {
+            operation,
+            message: e.message().to_string(),
+        }
+        .into()
+    }
+}
+
+/// `path` without a trailing separator, resolved where it exists, so paths
+/// from libgit2 and from callers compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .unwrap_or_else(|_| path.components().collect())
+}
+
+/// Branch and commit checked out in `repo`.
+fn head_of(repo: &Repository) -> (String, String) {
+    match repo.head() {
+        Ok(head) => (
+            if head.is_branch() {
+                head.shorthand().unwrap_or_default().to_string()
+            } else {
+                String::new()
+            },
+            head.target()
+                .map_or_else(|| "unknown".to_string(), |oid| oid.to_string()),
+        ),
+        Err(_) => (String::new(), "unknown".to_string()),
+    }
+}
+
+fn list(repo: &Repository) -> Result<Vec<ShellWorktreeInfo>> {
+    let (branch, head_commit) = head_of(repo);
+    let mut worktrees = vec![ShellWorktreeInfo {
+        path: normalize(repo.workdir().unwrap_or_else(|| repo.path())),
+        branch,
+        head_commit,
+        is_locked: false,
+        is_bare: repo.is_bare(),
+        is_prunable: false,
+    }];
+    let names = repo.worktrees().map_err(failed("worktree list"))?;
+    for name in names.iter().flatten() {
+        let worktree = repo.find_worktree(name).map_err(failed("worktree list"))?;
+        let is_prunable = worktree.validate().is_err();
+        let (branch, head_commit) = match Repository::open_from_worktree(&worktree) {
+            Ok(repo) if !is_prunable => head_of(&repo),
+            _ => (String::new(), String::new()),
+        };
+        worktrees.push(ShellWorktreeInfo {
+            path: normalize(worktree.path()),
+            branch,
+            head_commit,
+            is_locked: matches!(worktree.is_locked(), Ok(WorktreeLockStatus::Locked(_))),
+            is_bare: false,
+            is_prunable,
+        });
+    }
+    Ok(worktrees)
+}
+
+/// The registered worktree checked out at `path`.
+fn find_worktree(repo: &Repository, path: &Path) -> Result<git2::Worktree> {
+    let wanted = normalize(path);
+    let names = repo.worktrees().map_err(failed("worktree list"))?;
+    for name in names.iter().flatten() {
+        if let Ok(worktree) = repo.find_worktree(name)
+            && normalize(worktree.path()) == wanted
+        {
+            return Ok(worktree);
+        }
+    }
+    Err(GitError::UnknownWorktree(path.to_path_buf()).into())
+}
+
+fn statuses(repo: &Repository) -> Result<Vec<FileStatus>> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(failed("status"))?;
+    let mut files = Vec::new();
+    for entry in statuses.iter() {
+        let status = entry.status();
+        if status.is_ignored() {
+            continue;
+        }
+        let (index, worktree) = if status.is_conflicted() {
+            (Some(Change::Conflicted), Some(Change::Conflicted))
+        } else {
+            (index_change(status), worktree_change(status))
+        };
+        let path = entry
+            .head_to_index()
+            .and_then(|delta| delta.new_file().path().map(Path::to_path_buf))
+            .or_else(|| entry.path().map(PathBuf::from))
+            .unwrap_or_default();
+        files.push(FileStatus {
+            path,
+            index,
+            worktree,
+        });
+    }
+    Ok(files)
+}
+
+fn index_change(status: Status) -> Option<Change> {
+    [
+        (Status::INDEX_NEW, Change::Added),
+        (Status::INDEX_MODIFIED, Change::Modified),
+        (Status::INDEX_DELETED, Change::Deleted),
+        (Status::INDEX_RENAMED, Change::Renamed),
+        (Status::INDEX_TYPECHANGE, Change::TypeChange),
+    ]
+    .into_iter()
+    .find(|(flag, _)| status.contains(*flag))
+    .map(|(_, change)| change)
+}
+
+fn worktree_change(status: Status) -> Option<Change> {
+    [
+        (Status::WT_NEW, Change::Untracked),
+        (Status::WT_MODIFIED, Change::Modified),
+        (Status::WT_DELETED, Change::Deleted),
+        (Status::WT_RENAMED, Change::Renamed),
+        (Status::WT_TYPECHANGE, Change::TypeChange),
+    ]
+    .into_iter()
+    .find(|(flag, _)| status.contains(*flag))
+    .map(|(_, change)| change)
+}
+
+#[async_trait]
+impl WorktreeBackend for NativeWorktreeManager {
+    fn kind(&self) -> GitBackend {
+        GitBackend::Native
+    }
+
+    async fn list_worktrees(&self) -> Result<Vec<ShellWorktreeInfo>> {
+        self.with_repo(list).await
+    }
+
+    async fn create_worktree_full(
+        &self,
+        path: &Path,
+        branch: &str,
+        create_new_branch: bool,
+    ) -> Result<ShellWorktreeInfo> {
+        let (path, branch) = (path.to_path_buf(), branch.to_string());
+        let info = self
+            .with_repo(move |repo| {
+                let existing = repo.find_branch(&branch, BranchType::Local).ok();
+                let reference = match existing {
+                    Some(_) if create_new_branch => {
+                        return Err(GitError::BranchExists(branch).into());
+                    }
+                    Some(existing) => existing.into_reference(),
+                    None => {
+                        let head = repo
+                            .head()
+                            .and_then(|head| head.peel_to_commit())
+                            .map_err(failed("worktree add"))?;
+                        repo.branch(&branch, &head, false)
+                            .map_err(failed("branch"))?
+                            .into_reference()
+                    }
+                };
+
+                // Named after the directory, like `git worktree add`.
+                let base = path
+                    .file_name()
+                    .map_or_else(|| branch.replace('/', "-"), |n| n.to_string_lossy().into());
+                let mut name = base.clone();
+                let mut n = 1;
+                while repo.find_worktree(&name).is_ok() {
+                    name = format!("{base}{n}");
+                    n += 1;
+                }
+                let worktree = repo
+                    .worktree(
+                        &name,
+                        &path,
+                        Some(WorktreeAddOptions::new().reference(Some(&reference))),
+                    )
+                    .map_err(failed("worktree add"))?;
+                let checkout =
+                    Repository::open_from_worktree(&worktree).map_err(failed("worktree add"))?;
+                Ok(ShellWorktreeInfo {
+                    path,
+                    branch,
+                    head_commit: head_of(&checkout).1,
+                    is_locked: false,
+                    is_bare: false,
+                    is_prunable: false,
+                })
+            })
+            .await?;
+        info!(
+            "Created worktree: {} on branch {}",
+            info.path.display(),
+            info.branch
+        );
+        Ok(info)
+    }
+
+    async fn remove_worktree_full(&self, path: &Path, force: bool) -> Result<()> {
+        let path = path.to_path_buf();
+        let removed = path.clone();
+        self.with_repo(move |repo| {
+            let worktree = find_worktree(repo, &path)?;
+            if !force
+                && let Ok(checkout) = Repository::open_from_worktree(&worktree)
+                && !statuses(&checkout)?.is_empty()
+            {
+                return Err(GitError::DirtyWorktree(path).into());
+            }
+            worktree
+                .prune(Some(
+                    WorktreePruneOptions::new()
+                        .valid(true)
+                        .locked(force)
+                        .working_tree(true),
+                ))
+                .map_err(failed("worktree remove"))
+        })
+        .await?;
+        info!("Removed worktree: {}", removed.display());
+        Ok(())
+    }
+
+    async fn prune_worktrees(&self) -> Result<()> {
+        self.with_repo(|repo| {
+            let names = repo.worktrees().map_err(failed("worktree prune"))?;
+            for name in names.iter().flatten() {
+                let worktree = repo.find_worktree(name).map_err(failed("worktree prune"))?;
+                if worktree.is_prunable(None).unwrap_or(false) {
+                    worktree.prune(None).map_err(failed("worktree prune"))?;
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        info!("Pruned stale worktrees");
+        Ok(())
+    }
+
+    async fn branch_exists(&self, branch: &str) -> Result<bool> {
+        let branch = branch.to_string();
+        self.with_repo(move |repo| Ok(repo.find_branch(&branch, BranchType::Local).is_ok()))
+            .await
+    }
+
+    async fn current_branch(&self, path: &Path) -> Result<String> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || Ok(head_of(&open_repo(&path)?).0))
+            .await
+            .context("git task panicked")?
+    }
+
+    async fn status(&self, path: &Path) -> Result<Vec<FileStatus>> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || statuses(&open_repo(&path)?))
+            .await
+            .context("git task panicked")?
+    }
+
+    async fn commit_worktree_changes(&self, path: &Path, message: &str) -> Result<()> {
+        let (path, message) = (path.to_path_buf(), message.to_string());
+        let committed = tokio::task::spawn_blocking(move || {
+            let repo = open_repo(&path)?;
+            if statuses(&repo)?.is_empty() {
+                return Ok(false);
+            }
+            let mut index = repo.index().map_err(failed("add"))?;
+            index
+                .add_all(["*"], IndexAddOption::DEFAULT, None)
+                .and_then(|()| index.update_all(["*"], None))
+                .and_then(|()| index.write())
+                .map_err(failed("add"))?;
+            let tree = index
+                .write_tree()
+                .and_then(|oid| repo.find_tree(oid))
+                .map_err(failed("commit"))?;
+            let signature = repo.signature().map_err(failed("commit"))?;
+            let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                &message,
+                &tree,
+                &parent.iter().collect::<Vec<_>>(),
+            )
+            .map_err(failed("commit"))?;
+            anyhow::Ok(true)
+        })
+        .await
+        .context("git task panicked")??;
+        if committed {
+            info!("Committed changes in worktree");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::shell::ShellWorktreeManager;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    fn repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q", "-b", "main"]);
+        git(dir.path(), &["config", "user.name", "ccswarm"]);
+        git(dir.path(), &["config", "user.email", "ccswarm@example.com"]);
+        std::fs::write(dir.path().join("README.md"), "hi\n").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-qm", "init"]);
+        dir
+    }
+
+    #[tokio::test]
+    async fn native_backend_matches_the_git_cli() {
+        let dir = repo();
+        let native = NativeWorktreeManager::open(dir.path()).unwrap();
+        let shell = ShellWorktreeManager::new(dir.path().to_path_buf()).unwrap();
+        let agent = dir.path().join("agent-a");
+
+        let created = native
+            .create_worktree(&agent, "feature/agent-a")
+            .await
+            .unwrap();
+        assert!(native.branch_exists("feature/agent-a").await.unwrap());
+        let err = native
+            .create_worktree(&dir.path().join("agent-b"), "feature/agent-a")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<GitError>(),
+            Some(GitError::BranchExists(_))
+        ));
+
+        // The CLI sees the worktree libgit2 added, and both list the same.
+        let by_shell = WorktreeBackend::list_worktrees(&shell).await.unwrap();
+        let by_native = native.list_worktrees().await.unwrap();
+        let summary = |list: &[ShellWorktreeInfo]| -> Vec<(PathBuf, String, String)> {
+            list.iter()
+                .map(|w| (normalize(&w.path), w.branch.clone(), w.head_commit.clone()))
+                .collect()
+        };
+        assert_eq!(summary(&by_native), summary(&by_shell));
+        assert_eq!(by_native[1].head_commit, created.head_commit);
+        assert_eq!(
+            native.current_branch(&agent).await.unwrap(),
+            "feature/agent-a"
+        );
+
+        std::fs::write(agent.join("README.md"), "changed\n").unwrap();
+        std::fs::write(agent.join("new.txt"), "new\n").unwrap();
+        let mut status = native.status(&agent).await.unwrap();
+        status.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            status,
+            WorktreeBackend::status(&shell, &agent).await.unwrap()
+        );
+        let err = native.remove_worktree(&agent).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<GitError>(),
+            Some(GitError::DirtyWorktree(_))
+        ));
+
+        native
+            .commit_worktree_changes(&agent, "agent work")
+            .await
+            .unwrap();
+        assert!(native.status(&agent).await.unwrap().is_empty());
+        native.remove_worktree(&agent).await.unwrap();
+        assert!(!agent.exists());
+        assert_eq!(native.list_worktrees().await.unwrap().len(), 1);
+    }
+}
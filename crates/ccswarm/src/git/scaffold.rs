@@ -0,0 +1,188 @@
+//! Files an agent's worktree starts with.
+//!
+//! An agent in `ccswarm.json` can list `scaffold` entries — a `.env` from a
+//! template, editor settings, a pre-commit config — that are written into
+//! its worktree whenever `ccswarm worktree bootstrap` creates or attaches
+//! it. Contents go through the template engine first, so `{{agent}}`,
+//! `{{branch}}`, `{{project_name}}` and the like come out per agent. What
+//! the worktree already has is never overwritten.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// One file or directory to put into a new worktree.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScaffoldEntry {
+    /// Relative to the worktree; a trailing `/` makes it a directory.
+    pub path: String,
+
+    /// The file's contents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// A file in the repository to take the contents from instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+
+    /// Make the file executable, e.g. for a hook script.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub executable: bool,
+}
+
+impl ScaffoldEntry {
+    fn is_dir(&self) -> bool {
+        self.path.ends_with('/')
+    }
+
+    /// The entry's path inside `worktree`, refusing ones that would leave it.
+    fn target(&self, worktree: &Path) -> Result<PathBuf> {
+        let relative = Path::new(self.path.trim_end_matches('/'));
+        if relative.as_os_str().is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            bail!(
+                "scaffold path '{}' must be relative and stay inside the worktree",
+                self.path
+            );
+        }
+        Ok(worktree.join(relative))
+    }
+
+    async fn source(&self, repo: &Path) -> Result<String> {
+        match (&self.content, &self.from) {
+            (Some(_), Some(_)) => bail!("scaffold entry '{}' has both content and from", self.path),
+            (Some(content), None) => Ok(content.clone()),
+            (None, Some(from)) => {
+                let path = repo.join(from);
+                tokio::fs::read_to_string(&path)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))
+            }
+            (None, None) => Ok(String::new()),
+        }
+    }
+}
+
+/// Write `entries` into `worktree`, rendering contents with `variables`.
+/// Returns what was created; existing files and directories are left as
+/// they are.
+pub async fn apply(
+    entries: &[ScaffoldEntry],
+    repo: &Path,
+    worktree: &Path,
+    variables: &BTreeMap<String, String>,
+) -> Result<Vec<PathBuf>> {
+    let mut created = Vec::new();
+    for entry in entries {
+        let target = entry.target(worktree)?;
+        if tokio::fs::symlink_metadata(&target).await.is_ok() {
+            continue;
+        }
+        if entry.is_dir() {
+            tokio::fs::create_dir_all(&target)
+                .await
+                .with_context(|| format!("Failed to create {}", target.display()))?;
+            created.push(target);
+            continue;
+        }
+        let source = entry.source(repo).await?;
+        let contents = crate::cli::handlers::template::render(&source, variables);
+        let parent = target
+            .parent()
+            .ok_or_else(|| anyhow!("Invalid scaffold path '{}'", entry.path))?;
+        tokio::fs::create_dir_all(parent).await?;
+        tokio::fs::write(&target, contents)
+            .await
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        #[cfg(unix)]
+        if entry.executable {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755)).await?;
+        }
+        created.push(target);
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scaffolding_is_rendered_and_never_overwrites() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let worktree = dir.path().join("wt");
+        std::fs::create_dir_all(repo.join("templates")).unwrap();
+        std::fs::create_dir_all(worktree.join(".vscode")).unwrap();
+        std::fs::write(
+            repo.join("templates/env"),
+            "AGENT={{agent}}\nDB=app_{{agent}}\n",
+        )
+        .unwrap();
+        std::fs::write(worktree.join(".vscode/settings.json"), "{\"mine\": 1}").unwrap();
+
+        let entries = vec![
+            ScaffoldEntry {
+                path: ".env".into(),
+                from: Some("templates/env".into()),
+                ..Default::default()
+            },
+            ScaffoldEntry {
+                path: ".vscode/settings.json".into(),
+                content: Some("{}".into()),
+                ..Default::default()
+            },
+            ScaffoldEntry {
+                path: "tmp/cache/".into(),
+                ..Default::default()
+            },
+            ScaffoldEntry {
+                path: ".githooks/pre-commit".into(),
+                content: Some("#!/bin/sh\necho {{branch}}\n".into()),
+                executable: true,
+                ..Default::default()
+            },
+        ];
+        let variables = BTreeMap::from([
+            ("agent".to_string(), "backend".to_string()),
+            ("branch".to_string(), "feature/api".to_string()),
+        ]);
+        let created = apply(&entries, &repo, &worktree, &variables).await.unwrap();
+
+        assert_eq!(created.len(), 3);
+        assert_eq!(
+            std::fs::read_to_string(worktree.join(".env")).unwrap(),
+            "AGENT=backend\nDB=app_backend\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(worktree.join(".vscode/settings.json")).unwrap(),
+            "{\"mine\": 1}"
+        );
+        assert!(worktree.join("tmp/cache").is_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(worktree.join(".githooks/pre-commit"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        let escape = ScaffoldEntry {
+            path: "../outside".into(),
+            content: Some("x".into()),
+            ..Default::default()
+        };
+        assert!(
+            apply(&[escape], &repo, &worktree, &variables)
+                .await
+                .is_err()
+        );
+    }
+}
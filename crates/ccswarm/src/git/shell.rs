@@ -1,9 +1,14 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use tokio::process::Command;
 use tracing::{info, warn};
 
+use super::backend::{Change, FileStatus, GitBackend, GitError, WorktreeBackend};
+use crate::recovery::{Operation, RecoveryJournal};
+
 /// Git worktree information (shell command version)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShellWorktreeInfo {
@@ -12,6 +17,9 @@ pub struct ShellWorktreeInfo {
     pub head_commit: String,
     pub is_locked: bool,
     pub is_bare: bool,
+    /// Registered, but its directory is gone (`git worktree prune` drops it).
+    #[serde(default)]
+    pub is_prunable: bool,
 }
 
 /// Shell command-based Git worktree management
@@ -163,18 +171,27 @@ impl ShellWorktreeManager {
             args.push(branch_name);
         }
 
+        let recovery = RecoveryJournal::for_repo(&self.repo_path);
+        let created = Operation::WorktreeCreated {
+            repo: self.repo_path.clone(),
+            path: self.repo_path.join(worktree_path),
+        };
+        recovery.begin(created.clone()).await?;
         let output = Command::new("git")
             .args(&args)
             .current_dir(&self.repo_path)
             .output()
             .await
-            .context("Failed to execute git worktree add")?;
+            .context("Failed to execute git worktree add");
+        recovery.done(&created).await;
+        let output = output?;
 
         if !output.status.success() {
-            return Err(anyhow::anyhow!(
-                "Failed to create worktree: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("already exists") && stderr.contains("branch") {
+                return Err(GitError::BranchExists(branch_name.to_string()).into());
+            }
+            return Err(anyhow::anyhow!("Failed to create worktree: {}", stderr));
         }
 
         // Get worktree information
@@ -187,6 +204,7 @@ impl ShellWorktreeManager {
             head_commit,
             is_locked,
             is_bare: false,
+            is_prunable: false,
         };
 
         info!(
@@ -222,10 +240,14 @@ impl ShellWorktreeManager {
             .context("Failed to execute git worktree remove")?;
 
         if !output.status.success() {
-            return Err(anyhow::anyhow!(
-                "Failed to remove worktree: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("use --force") {
+                return Err(GitError::DirtyWorktree(worktree_path.to_path_buf()).into());
+            }
+            if stderr.contains("is not a working tree") {
+                return Err(GitError::UnknownWorktree(worktree_path.to_path_buf()).into());
+            }
+            return Err(anyhow::anyhow!("Failed to remove worktree: {}", stderr));
         }
 
         info!("Removed worktree: {}", worktree_path.display());
@@ -292,8 +314,48 @@ impl ShellWorktreeManager {
         Ok(())
     }
 
+    /// Changed and untracked files in a worktree
+    pub async fn status(&self, worktree_path: &Path) -> Result<Vec<FileStatus>> {
+        let output = Command::new("git")
+            .args(["status", "--porcelain=v1", "-z", "--untracked-files=all"])
+            .current_dir(worktree_path)
+            .output()
+            .await
+            .context("Failed to execute git status")?;
+
+        if !output.status.success() {
+            return Err(GitError::Failed {
+                operation: "status",
+                message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }
+            .into());
+        }
+        Ok(parse_porcelain_status(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    /// Branch checked out in a worktree (empty when detached)
+    pub async fn current_branch(&self, worktree_path: &Path) -> Result<String> {
+        let output = Command::new("git")
+            .args(["branch", "--show-current"])
+            .current_dir(worktree_path)
+            .output()
+            .await
+            .context("Failed to execute git branch")?;
+
+        if !output.status.success() {
+            return Err(GitError::Failed {
+                operation: "branch --show-current",
+                message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }
+            .into());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
     /// Check if branch exists
-    async fn branch_exists(&self, branch_name: &str) -> Result<bool> {
+    pub async fn branch_exists(&self, branch_name: &str) -> Result<bool> {
         let output = Command::new("git")
             .args(["branch", "--list", branch_name])
             .current_dir(&self.repo_path)
@@ -348,6 +410,7 @@ impl ShellWorktreeManager {
                     head_commit: String::new(),
                     is_locked: false,
                     is_bare: false,
+                    is_prunable: false,
                 });
             } else if let Some(ref mut wt) = current_worktree {
                 if line.starts_with("HEAD ") {
@@ -362,6 +425,8 @@ impl ShellWorktreeManager {
                     wt.is_bare = true;
                 } else if line == "locked" {
                     wt.is_locked = true;
+                } else if line.starts_with("prunable") {
+                    wt.is_prunable = true;
                 }
             }
         }
@@ -374,3 +439,141 @@ impl ShellWorktreeManager {
         Ok(worktrees)
     }
 }
+
+#[async_trait]
+impl WorktreeBackend for ShellWorktreeManager {
+    fn kind(&self) -> GitBackend {
+        GitBackend::Shell
+    }
+
+    async fn list_worktrees(&self) -> Result<Vec<ShellWorktreeInfo>> {
+        ShellWorktreeManager::list_worktrees(self).await
+    }
+
+    async fn create_worktree_full(
+        &self,
+        path: &Path,
+        branch: &str,
+        create_new_branch: bool,
+    ) -> Result<ShellWorktreeInfo> {
+        ShellWorktreeManager::create_worktree_full(self, path, branch, create_new_branch).await
+    }
+
+    async fn remove_worktree_full(&self, path: &Path, force: bool) -> Result<()> {
+        ShellWorktreeManager::remove_worktree_full(self, path, force).await
+    }
+
+    async fn prune_worktrees(&self) -> Result<()> {
+        ShellWorktreeManager::prune_worktrees(self).await
+    }
+
+    async fn branch_exists(&self, branch: &str) -> Result<bool> {
+        ShellWorktreeManager::branch_exists(self, branch).await
+    }
+
+    async fn current_branch(&self, path: &Path) -> Result<String> {
+        ShellWorktreeManager::current_branch(self, path).await
+    }
+
+    async fn status(&self, path: &Path) -> Result<Vec<FileStatus>> {
+        ShellWorktreeManager::status(self, path).await
+    }
+
+    async fn commit_worktree_changes(&self, path: &Path, message: &str) -> Result<()> {
+        ShellWorktreeManager::commit_worktree_changes(self, path, message).await
+    }
+}
+
+/// Parse `git status --porcelain=v1 -z` output.
+fn parse_porcelain_status(output: &str) -> Vec<FileStatus> {
+    let change = |code: char| match code {
+        'A' | 'C' => Some(Change::Added),
+        'M' => Some(Change::Modified),
+        'D' => Some(Change::Deleted),
+        'R' => Some(Change::Renamed),
+        'T' => Some(Change::TypeChange),
+        _ => None,
+    };
+    let mut files = Vec::new();
+    let mut entries = output.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        let mut codes = entry.chars();
+        let (Some(x), Some(y)) = (codes.next(), codes.next()) else {
+            continue;
+        };
+        let path = PathBuf::from(entry.get(3..).unwrap_or_default());
+        // A rename or copy is followed by its original path.
+        if matches!(x, 'R' | 'C') {
+            entries.next();
+        }
+        let (index, worktree) = match (x, y) {
+            ('?', '?') => (None, Some(Change::Untracked)),
+            ('!', '!') => continue,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => {
+                (Some(Change::Conflicted), Some(Change::Conflicted))
+            }
+            (x, y) => (change(x), change(y)),
+        };
+        files.push(FileStatus {
+            path,
+            index,
+            worktree,
+        });
+    }
+    files
+}
+
+/// Run `git args` in `dir` and return its stdout. A non-zero exit is an
+/// error carrying git's stderr.
+pub async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    git_with_env(dir, &[], args).await
+}
+
+/// [`git`] with `env` added to git's environment.
+pub async fn git_with_env(dir: &Path, env: &[(&str, &OsStr)], args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .envs(env.iter().copied())
+        .current_dir(dir)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porcelain_status_splits_staged_and_unstaged_changes() {
+        let output = "M  src/lib.rs\0 M README.md\0R  new.rs\0old.rs\0?? notes.txt\0UU merge.rs\0";
+        let status = parse_porcelain_status(output);
+        let summary: Vec<_> = status
+            .iter()
+            .map(|f| (f.path.to_str().unwrap(), f.index, f.worktree))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("src/lib.rs", Some(Change::Modified), None),
+                ("README.md", None, Some(Change::Modified)),
+                ("new.rs", Some(Change::Renamed), None),
+                ("notes.txt", None, Some(Change::Untracked)),
+                (
+                    "merge.rs",
+                    Some(Change::Conflicted),
+                    Some(Change::Conflicted)
+                ),
+            ]
+        );
+    }
+}
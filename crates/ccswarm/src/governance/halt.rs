@@ -0,0 +1,163 @@
+//! Emergency stop.
+//!
+//! `ccswarm halt` writes `.ccswarm/halt.json` before it touches anything:
+//! from then on the queue hands out no tasks and no provider request starts
+//! under the project. It then stops every running session and kills what
+//! those sessions had running (provider CLIs, gate commands), so an agent in
+//! the middle of something dangerous does not get to finish it. Nothing
+//! moves again until `ccswarm resume-all` continues the sessions the halt
+//! stopped and removes the file.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Where the halt is recorded, relative to the project root.
+pub const HALT_FILE: &str = ".ccswarm/halt.json";
+
+/// A halt in force.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HaltState {
+    pub halted_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Sessions the halt touched.
+    #[serde(default)]
+    pub runs: Vec<HaltedRun>,
+}
+
+/// A session the halt touched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HaltedRun {
+    pub run_id: String,
+    pub pid: u32,
+    /// Whether the halt stopped it; a session already paused stays paused
+    /// after `resume-all`.
+    pub stopped: bool,
+    /// Child processes killed.
+    #[serde(default)]
+    pub cancelled: Vec<u32>,
+}
+
+impl HaltState {
+    pub fn new(reason: Option<String>) -> Self {
+        Self {
+            halted_at: Utc::now(),
+            reason,
+            runs: Vec::new(),
+        }
+    }
+}
+
+/// The halt in force for `repo`, if any.
+pub async fn load(repo: &Path) -> Result<Option<HaltState>> {
+    let path = repo.join(HALT_FILE);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(text) => Ok(Some(serde_json::from_str(&text).with_context(|| {
+            format!("{} is corrupt; delete it to lift the halt", path.display())
+        })?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+pub async fn save(repo: &Path, state: &HaltState) -> Result<()> {
+    let path = repo.join(HALT_FILE);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, serde_json::to_string_pretty(state)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub async fn clear(repo: &Path) -> Result<()> {
+    match tokio::fs::remove_file(repo.join(HALT_FILE)).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// The halt file governing `dir`: the project's, found from the main
+/// worktree of the repository `dir` is in — agent worktrees sit beside the
+/// project (`../worktrees/<agent>-agent`), not below it — else the nearest
+/// ancestor's, so subdirectories of a halted project are halted too.
+pub async fn halt_file_for(dir: &Path) -> Option<PathBuf> {
+    let project = crate::workflow::coverage::main_worktree(dir).await;
+    std::iter::once(project.as_path())
+        .chain(dir.ancestors())
+        .map(|ancestor| ancestor.join(HALT_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Fail when `dir` is in a halted project.
+pub async fn ensure_not_halted(dir: &Path) -> Result<()> {
+    if let Some(path) = halt_file_for(dir).await {
+        bail!(
+            "ccswarm is halted ({}); run `ccswarm resume-all` to continue",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_halt_covers_the_project_and_its_agent_worktrees_until_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        // The layout `ccswarm init` sets up: worktrees beside the project.
+        let project = dir.path().join("project");
+        let worktree = dir.path().join("worktrees/backend-agent");
+        std::fs::create_dir(&project).unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@example.com",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "init",
+            ],
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "feature/backend",
+                "../worktrees/backend-agent",
+            ],
+        ] {
+            crate::git::shell::git(&project, args).await.unwrap();
+        }
+        let nested = project.join("src/api");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert!(ensure_not_halted(&worktree).await.is_ok());
+        assert!(load(&project).await.unwrap().is_none());
+
+        let mut state = HaltState::new(Some("deleting prod data".into()));
+        state.runs.push(HaltedRun {
+            run_id: "r1".into(),
+            pid: 42,
+            stopped: true,
+            cancelled: vec![43],
+        });
+        save(&project, &state).await.unwrap();
+        for dir in [&worktree, &nested, &project] {
+            let err = ensure_not_halted(dir).await.unwrap_err();
+            assert!(err.to_string().contains("resume-all"));
+        }
+        assert_eq!(load(&project).await.unwrap().unwrap().runs[0].pid, 42);
+
+        clear(&project).await.unwrap();
+        assert!(ensure_not_halted(&worktree).await.is_ok());
+        clear(&project).await.unwrap();
+    }
+}
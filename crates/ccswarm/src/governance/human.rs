@@ -0,0 +1,364 @@
+//! Humans as first-class participants in coordination.
+//!
+//! A human registered under `humans:` in `ccswarm.json` receives delegated
+//! tasks like any agent: the task goes into the [`TaskQueue`], a
+//! `TaskAssignment` goes out on the [`CoordinationBus`], and an inbox item
+//! under `.ccswarm/coordination/inbox/` tracks it. Accepting, commenting and
+//! completing (from `ccswarm human` or `ccswarm tui inbox`) send the same
+//! `TaskProgress`, `Coordination` and `TaskCompleted` messages an AI agent
+//! would, so mixed human/AI workflows read one message stream.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{AgentMessage, CoordinationBus, CoordinationType, TaskQueue};
+use crate::agent::{Task, TaskResult};
+
+/// Queue state of a `queue.yaml` task held by a human. Drain only picks up
+/// `pending` tasks, so the task waits until the human completes it.
+pub const ASSIGNED_STATE: &str = "assigned";
+
+const INBOX_DIR: &str = ".ccswarm/coordination/inbox";
+
+/// How long a notify command may run before it is abandoned.
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A human participant, configured under `humans.<name>`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HumanAgentConfig {
+    /// What the human can take on, e.g. `design-review` or `legal`.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Shell command run on every delegation, with `CCSWARM_HUMAN`,
+    /// `CCSWARM_TASK_ID` and `CCSWARM_TASK` set (e.g. a `notify-send` or
+    /// chat webhook call).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_command: Option<String>,
+}
+
+/// Where an inbox item stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InboxStatus {
+    New,
+    Accepted,
+    Completed,
+    Declined,
+}
+
+impl InboxStatus {
+    /// True once the human has completed or declined the task.
+    pub fn is_closed(self) -> bool {
+        matches!(self, InboxStatus::Completed | InboxStatus::Declined)
+    }
+}
+
+impl std::fmt::Display for InboxStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            InboxStatus::New => "new",
+            InboxStatus::Accepted => "accepted",
+            InboxStatus::Completed => "completed",
+            InboxStatus::Declined => "declined",
+        })
+    }
+}
+
+/// A comment left on an inbox item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboxComment {
+    pub at: DateTime<Utc>,
+    pub author: String,
+    pub text: String,
+}
+
+/// A task delegated to a human, stored as `.ccswarm/coordination/inbox/{id}.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboxItem {
+    /// Same as the task ID.
+    pub id: String,
+    pub human: String,
+    pub task: Task,
+    pub delegated_by: String,
+    pub delegated_at: DateTime<Utc>,
+    pub status: InboxStatus,
+    /// The `queue.yaml` task this item stands for, when delegated from the queue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_task: Option<String>,
+    #[serde(default)]
+    pub comments: Vec<InboxComment>,
+    /// Completion summary or decline reason.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// File-backed inbox of tasks delegated to humans.
+pub struct HumanInbox {
+    dir: PathBuf,
+    queue: TaskQueue,
+    bus: CoordinationBus,
+}
+
+impl HumanInbox {
+    /// Inbox rooted at `repo`, sharing the repository's task queue and bus.
+    pub async fn open(repo: &Path) -> Result<Self> {
+        let dir = repo.join(INBOX_DIR);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let queue_dir = repo.join("coordination").join("task-queue");
+        Ok(Self {
+            dir,
+            queue: TaskQueue::with_dir(&queue_dir.to_string_lossy()).await?,
+            bus: CoordinationBus::new(repo).await?,
+        })
+    }
+
+    /// Hand `task` to `human`: queue it, announce the assignment on the bus
+    /// and open an inbox item.
+    pub async fn delegate(
+        &self,
+        human: &str,
+        mut task: Task,
+        delegated_by: &str,
+        queue_task: Option<String>,
+    ) -> Result<InboxItem> {
+        crate::run_id::validate_run_id(&task.id).context("invalid task ID")?;
+        task.assigned_to = Some(human.to_string());
+        self.queue.add_task(&task).await?;
+        self.bus
+            .send_message(AgentMessage::TaskAssignment {
+                task_id: task.id.clone(),
+                agent_id: human.to_string(),
+                task_data: serde_json::to_value(&task)?,
+            })
+            .await?;
+        let now = Utc::now();
+        let item = InboxItem {
+            id: task.id.clone(),
+            human: human.to_string(),
+            task,
+            delegated_by: delegated_by.to_string(),
+            delegated_at: now,
+            status: InboxStatus::New,
+            queue_task,
+            comments: Vec::new(),
+            result: None,
+            updated_at: now,
+        };
+        self.write(&item).await?;
+        Ok(item)
+    }
+
+    /// Items for `human` (all humans when `None`), open ones first, oldest first.
+    pub async fn list(&self, human: Option<&str>) -> Result<Vec<InboxItem>> {
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        let mut items = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "json")
+                && let Ok(content) = tokio::fs::read_to_string(&path).await
+                && let Ok(item) = serde_json::from_str::<InboxItem>(&content)
+                && human.is_none_or(|h| item.human == h)
+            {
+                items.push(item);
+            }
+        }
+        items.sort_by_key(|item| (item.status.is_closed(), item.delegated_at));
+        Ok(items)
+    }
+
+    /// The item with `id`, if any.
+    pub async fn get(&self, id: &str) -> Result<Option<InboxItem>> {
+        crate::run_id::validate_run_id(id).context("invalid task ID")?;
+        let path = self.dir.join(format!("{id}.json"));
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                Ok(Some(serde_json::from_str(&content).with_context(|| {
+                    format!("Failed to parse {}", path.display())
+                })?))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Take on a new task.
+    pub async fn accept(&self, id: &str) -> Result<InboxItem> {
+        let mut item = self.open_item(id).await?;
+        item.status = InboxStatus::Accepted;
+        item.updated_at = Utc::now();
+        self.bus
+            .send_message(AgentMessage::TaskProgress {
+                agent_id: item.human.clone(),
+                task_id: item.id.clone(),
+                progress: 0.0,
+                message: "accepted".to_string(),
+            })
+            .await?;
+        self.write(&item).await?;
+        Ok(item)
+    }
+
+    /// Leave a comment; it is forwarded to whoever delegated the task.
+    pub async fn comment(&self, id: &str, author: &str, text: &str) -> Result<InboxItem> {
+        let mut item = self.get(id).await?.context(format!("No inbox item {id}"))?;
+        let now = Utc::now();
+        item.comments.push(InboxComment {
+            at: now,
+            author: author.to_string(),
+            text: text.to_string(),
+        });
+        item.updated_at = now;
+        self.bus
+            .send_message(AgentMessage::Coordination {
+                from_agent: author.to_string(),
+                to_agent: item.delegated_by.clone(),
+                message_type: CoordinationType::Information,
+                payload: serde_json::json!({ "task_id": item.id, "comment": text }),
+            })
+            .await?;
+        self.write(&item).await?;
+        Ok(item)
+    }
+
+    /// Complete (`success`) or decline the task, report the result on the bus
+    /// and take it off the task queue.
+    pub async fn finish(&self, id: &str, success: bool, summary: &str) -> Result<InboxItem> {
+        let mut item = self.open_item(id).await?;
+        let now = Utc::now();
+        let taken = (now - item.delegated_at).to_std().unwrap_or_default();
+        let result = if success {
+            TaskResult::success(
+                serde_json::json!({ "summary": summary, "completed_by": item.human }),
+                taken,
+            )
+        } else {
+            TaskResult::failure(format!("Declined by {}: {}", item.human, summary), taken)
+        };
+        item.status = if success {
+            InboxStatus::Completed
+        } else {
+            InboxStatus::Declined
+        };
+        item.result = Some(summary.to_string());
+        item.updated_at = now;
+        self.bus
+            .send_message(AgentMessage::TaskCompleted {
+                agent_id: item.human.clone(),
+                task_id: item.id.clone(),
+                result,
+            })
+            .await?;
+        if let Err(e) = self.queue.remove_task(&item.id).await {
+            tracing::debug!("Task {} was not in the task queue: {}", item.id, e);
+        }
+        self.write(&item).await?;
+        Ok(item)
+    }
+
+    /// Persisted bus messages, oldest first.
+    pub async fn messages(&self) -> Result<Vec<AgentMessage>> {
+        self.bus.load_persisted_messages().await
+    }
+
+    async fn open_item(&self, id: &str) -> Result<InboxItem> {
+        let item = self.get(id).await?.context(format!("No inbox item {id}"))?;
+        if item.status.is_closed() {
+            bail!("Inbox item {} is already {}", id, item.status);
+        }
+        Ok(item)
+    }
+
+    async fn write(&self, item: &InboxItem) -> Result<()> {
+        let path = self.dir.join(format!("{}.json", item.id));
+        super::migrate::write_atomic(&path, &serde_json::to_string_pretty(item)?).await
+    }
+}
+
+/// Run the human's notify command for a new item. Failures are logged, not
+/// returned: a broken notifier must not undo the delegation.
+pub async fn notify(config: &HumanAgentConfig, item: &InboxItem) {
+    let Some(command) = config.notify_command.as_deref() else {
+        return;
+    };
+    let run = tokio::process::Command::new("sh")
+        .args(["-c", command])
+        .env("CCSWARM_HUMAN", &item.human)
+        .env("CCSWARM_TASK_ID", &item.id)
+        .env("CCSWARM_TASK", &item.task.description)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(NOTIFY_TIMEOUT, run).await {
+        Ok(Ok(output)) if output.status.success() => {}
+        Ok(Ok(output)) => tracing::warn!(
+            "Notify command for {} failed: {}",
+            item.human,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Ok(Err(e)) => tracing::warn!("Failed to run notify command for {}: {}", item.human, e),
+        Err(_) => tracing::warn!("Notify command for {} timed out", item.human),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{Priority, TaskType};
+
+    #[tokio::test]
+    async fn test_delegated_task_flows_back_through_queue_and_bus() {
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = HumanInbox::open(dir.path()).await.unwrap();
+        let task = Task::new(
+            "review-1".to_string(),
+            "Review the pricing copy".to_string(),
+            Priority::High,
+            TaskType::Review,
+        );
+
+        let item = inbox
+            .delegate("alice", task, "orchestrator", None)
+            .await
+            .unwrap();
+        assert_eq!(item.status, InboxStatus::New);
+        assert_eq!(item.task.assigned_to.as_deref(), Some("alice"));
+        assert_eq!(inbox.queue.get_pending_tasks().await.unwrap().len(), 1);
+        assert!(inbox.list(Some("bob")).await.unwrap().is_empty());
+
+        inbox.accept("review-1").await.unwrap();
+        inbox
+            .comment("review-1", "alice", "Tone is off in section 2")
+            .await
+            .unwrap();
+        let done = inbox
+            .finish("review-1", true, "Rewrote section 2")
+            .await
+            .unwrap();
+        assert_eq!(done.status, InboxStatus::Completed);
+        assert_eq!(done.comments.len(), 1);
+        assert!(inbox.queue.get_pending_tasks().await.unwrap().is_empty());
+        assert!(inbox.accept("review-1").await.is_err());
+
+        let messages = inbox.messages().await.unwrap();
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            AgentMessage::TaskAssignment { agent_id, .. } if agent_id == "alice"
+        )));
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            AgentMessage::Coordination { to_agent, .. } if to_agent == "orchestrator"
+        )));
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            AgentMessage::TaskCompleted { result, .. } if result.success
+        )));
+    }
+}
@@ -0,0 +1,610 @@
+//! Startup migration of the coordination state files.
+//!
+//! Agents and the orchestrator coordinate through small JSON files: queued
+//! tasks (`coordination/task-queue/`), agent status (`coordination/agent-status/`),
+//! Sangha proposals, extensions and delegations (`coordination/proposals/`,
+//! `coordination/extensions/`, `coordination/sangha/delegations.json`), bus
+//! messages (`.ccswarm/coordination/messages/`) and the human inbox
+//! (`.ccswarm/coordination/inbox/`). Their readers skip files they cannot
+//! parse, so a half-written or outdated file makes a task, vote or status
+//! silently disappear.
+//!
+//! Every CLI start runs [`on_startup`] over them first:
+//!
+//! - files in an older shape are upgraded: rewritten through today's types,
+//!   which fills in fields that were added since;
+//! - files with trailing garbage after a complete document (an interrupted
+//!   overwrite) are repaired by keeping the document;
+//! - files that still do not parse, or do not hold what their directory
+//!   holds, are moved to `.ccswarm/coordination/corrupt/` and reported,
+//!   instead of being skipped without a word;
+//! - temp files left by interrupted writes are removed.
+//!
+//! Agents keep writing while another process starts, so a file modified
+//! within the last [`SETTLE_TIME`] may be a write in progress: it is left
+//! for a later pass rather than judged half-written.
+//!
+//! `.ccswarm/coordination/state.json` records the state version and when
+//! the last pass ran. A pass after a version bump looks at every file;
+//! otherwise only files modified since the last pass are read, so a start
+//! with nothing new costs a directory listing.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::agent::{AgentStatus, Task};
+use crate::governance::BusEnvelope;
+use crate::governance::human::InboxItem;
+use crate::workflow::sangha_tally::{DELEGATIONS_FILE, Delegation, PROPOSALS_DIR};
+
+/// Current shape of the coordination files; bump it when a migration
+/// should revisit every file.
+pub const STATE_VERSION: u32 = 1;
+
+/// Migration marker, relative to the repository root.
+pub const STATE_FILE: &str = ".ccswarm/coordination/state.json";
+
+/// Where corrupt files are moved, relative to the repository root.
+pub const CORRUPT_DIR: &str = ".ccswarm/coordination/corrupt";
+
+/// How long a file must have been left alone before a pass touches it.
+pub const SETTLE_TIME: Duration = Duration::from_secs(30);
+
+/// What a coordination file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Task,
+    AgentStatus,
+    Proposal,
+    Extension,
+    Delegations,
+    Message,
+    Inbox,
+}
+
+/// Directories of one-document-per-file state, and what they hold.
+const DIRS: &[(&str, Kind)] = &[
+    ("coordination/task-queue", Kind::Task),
+    ("coordination/agent-status", Kind::AgentStatus),
+    (PROPOSALS_DIR, Kind::Proposal),
+    ("coordination/extensions", Kind::Extension),
+    (".ccswarm/coordination/messages", Kind::Message),
+    (".ccswarm/coordination/inbox", Kind::Inbox),
+];
+
+/// Single-file state.
+const FILES: &[(&str, Kind)] = &[(DELEGATIONS_FILE, Kind::Delegations)];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StateMarker {
+    version: u32,
+    checked_at: DateTime<Utc>,
+}
+
+/// A file that could not be kept.
+#[derive(Debug, Clone, Serialize)]
+pub struct CorruptEntry {
+    /// Relative to the repository root.
+    pub path: String,
+    pub error: String,
+    /// Where the file went, relative to the repository root.
+    pub moved_to: String,
+}
+
+/// What a migration pass did, with paths relative to the repository root.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MigrationReport {
+    pub scanned: usize,
+    pub upgraded: Vec<String>,
+    pub repaired: Vec<String>,
+    pub corrupt: Vec<CorruptEntry>,
+    pub removed_temp: Vec<String>,
+    /// Modified too recently to judge; left for a later pass.
+    pub deferred: Vec<String>,
+    /// When the oldest deferred file was modified.
+    #[serde(skip)]
+    deferred_since: Option<SystemTime>,
+}
+
+impl MigrationReport {
+    /// Nothing needed changing.
+    pub fn is_clean(&self) -> bool {
+        self.upgraded.is_empty()
+            && self.repaired.is_empty()
+            && self.corrupt.is_empty()
+            && self.removed_temp.is_empty()
+    }
+
+    /// Leave `path`, modified at `modified`, for a later pass.
+    fn defer(&mut self, path: String, modified: SystemTime) {
+        self.deferred.push(path);
+        self.deferred_since = Some(self.deferred_since.map_or(modified, |t| t.min(modified)));
+    }
+}
+
+/// How a file's content fared.
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    Valid,
+    Upgraded(String),
+    Repaired(String),
+    Corrupt(String),
+}
+
+/// Migrate the coordination files under `repo` if anything may need it.
+/// Returns `None` when the repository has no coordination state.
+pub async fn on_startup(repo: &Path) -> Result<Option<MigrationReport>> {
+    let present = DIRS
+        .iter()
+        .chain(FILES)
+        .any(|(path, _)| repo.join(path).exists());
+    if !present {
+        return Ok(None);
+    }
+    let marker_path = repo.join(STATE_FILE);
+    let marker: Option<StateMarker> = match tokio::fs::read_to_string(&marker_path).await {
+        Ok(content) => serde_json::from_str(&content).ok(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", marker_path.display()));
+        }
+    };
+    let since = marker
+        .filter(|marker| marker.version >= STATE_VERSION)
+        .map(|marker| SystemTime::from(marker.checked_at));
+
+    let report = migrate(repo, since).await?;
+    // Stamped after the pass so the files it rewrote are not rescanned,
+    // unless files were deferred: the next pass must see those.
+    let marker = StateMarker {
+        version: STATE_VERSION,
+        checked_at: report.deferred_since.map_or_else(Utc::now, DateTime::from),
+    };
+    if let Some(dir) = marker_path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(&marker_path, serde_json::to_string_pretty(&marker)?)
+        .await
+        .with_context(|| format!("Failed to write {}", marker_path.display()))?;
+    Ok(Some(report))
+}
+
+/// Validate, repair and upgrade the coordination files under `repo`; with
+/// `since`, only those modified after it. Files modified within
+/// [`SETTLE_TIME`] are deferred.
+pub async fn migrate(repo: &Path, since: Option<SystemTime>) -> Result<MigrationReport> {
+    let mut report = MigrationReport::default();
+    let settled = SystemTime::now() - SETTLE_TIME;
+    let unsettled = |metadata: &std::fs::Metadata| {
+        metadata
+            .modified()
+            .ok()
+            .filter(|modified| *modified > settled)
+    };
+    let mut targets: Vec<(PathBuf, Kind)> = FILES
+        .iter()
+        .map(|(path, kind)| (repo.join(path), *kind))
+        .collect();
+    for (dir, kind) in DIRS {
+        let mut entries = match tokio::fs::read_dir(repo.join(dir)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to list {dir}")),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".tmp") {
+                // A write in progress renames its temp file into place.
+                if let Some(modified) = unsettled(&entry.metadata().await?) {
+                    report.defer(relative(repo, &path), modified);
+                    continue;
+                }
+                tokio::fs::remove_file(&path).await?;
+                report.removed_temp.push(relative(repo, &path));
+            } else if name.ends_with(".json") && entry.file_type().await?.is_file() {
+                targets.push((path, *kind));
+            }
+        }
+    }
+
+    for (path, kind) in targets {
+        let Ok(metadata) = tokio::fs::metadata(&path).await else {
+            continue;
+        };
+        if let Some(since) = since
+            && metadata.modified().is_ok_and(|modified| modified < since)
+        {
+            continue;
+        }
+        if let Some(modified) = unsettled(&metadata) {
+            report.defer(relative(repo, &path), modified);
+            continue;
+        }
+        report.scanned += 1;
+        let content = match tokio::fs::read(&path).await {
+            Ok(bytes) => String::from_utf8(bytes).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let verdict = match content {
+            Ok(content) => check(kind, &stem, &content),
+            Err(e) => Verdict::Corrupt(e),
+        };
+        let rel = relative(repo, &path);
+        match verdict {
+            Verdict::Valid => {}
+            Verdict::Upgraded(content) => {
+                write_atomic(&path, &content).await?;
+                report.upgraded.push(rel);
+            }
+            Verdict::Repaired(content) => {
+                write_atomic(&path, &content).await?;
+                report.repaired.push(rel);
+            }
+            Verdict::Corrupt(error) => {
+                let moved_to = quarantine(repo, &path).await?;
+                tracing::warn!("Corrupt coordination file {}: {}", rel, error);
+                report.corrupt.push(CorruptEntry {
+                    path: rel,
+                    error,
+                    moved_to,
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Judge `content` of a `kind` file named `stem`.
+fn check(kind: Kind, stem: &str, content: &str) -> Verdict {
+    let (value, repaired) = match serde_json::from_str::<Value>(content) {
+        Ok(value) => (value, false),
+        Err(e) => match first_document(content) {
+            Some(value) => (value, true),
+            None => return Verdict::Corrupt(e.to_string()),
+        },
+    };
+    let upgraded = match upgrade(kind, stem, value.clone()) {
+        Ok(upgraded) => upgraded,
+        Err(e) => return Verdict::Corrupt(format!("{e:#}")),
+    };
+    if !repaired && upgraded == value {
+        return Verdict::Valid;
+    }
+    let content = match serde_json::to_string_pretty(&upgraded) {
+        Ok(content) => content,
+        Err(e) => return Verdict::Corrupt(e.to_string()),
+    };
+    if repaired {
+        Verdict::Repaired(content)
+    } else {
+        Verdict::Upgraded(content)
+    }
+}
+
+/// The complete JSON document at the start of `content`, if any.
+fn first_document(content: &str) -> Option<Value> {
+    serde_json::Deserializer::from_str(content)
+        .into_iter::<Value>()
+        .next()?
+        .ok()
+}
+
+/// `value` in today's shape for `kind`.
+fn upgrade(kind: Kind, stem: &str, value: Value) -> Result<Value> {
+    fn through<T: Serialize + for<'de> Deserialize<'de>>(value: Value) -> Result<Value> {
+        Ok(serde_json::to_value(serde_json::from_value::<T>(value)?)?)
+    }
+
+    match kind {
+        Kind::Task => through::<Task>(value),
+        Kind::Message => Ok(serde_json::to_value(BusEnvelope::from_value(value)?)?),
+        Kind::Inbox => through::<InboxItem>(value),
+        Kind::Delegations => through::<Vec<Delegation>>(value),
+        Kind::AgentStatus => {
+            let mut status = object(value)?;
+            let Some(state) = status.get("status") else {
+                return Err(anyhow!("missing status"));
+            };
+            serde_json::from_value::<AgentStatus>(state.clone()).context("unknown agent status")?;
+            status
+                .entry("agent_id")
+                .or_insert_with(|| Value::from(stem));
+            status
+                .entry("additional_info")
+                .or_insert_with(|| serde_json::json!({}));
+            Ok(Value::Object(status))
+        }
+        Kind::Proposal | Kind::Extension => {
+            let mut record = object(value)?;
+            if !record.get("id").is_some_and(Value::is_string) {
+                record.insert("id".into(), Value::from(stem));
+            }
+            if kind == Kind::Proposal {
+                record
+                    .entry("status")
+                    .or_insert_with(|| Value::from("open"));
+                record
+                    .entry("tally")
+                    .or_insert_with(|| Value::from("majority"));
+                record
+                    .entry("votes")
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                if !record.get("votes").is_some_and(Value::is_array) {
+                    return Err(anyhow!("votes is not a list"));
+                }
+            }
+            Ok(Value::Object(record))
+        }
+    }
+}
+
+fn object(value: Value) -> Result<serde_json::Map<String, Value>> {
+    match value {
+        Value::Object(map) => Ok(map),
+        other => Err(anyhow!("expected an object, found {}", kind_of(&other))),
+    }
+}
+
+fn kind_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Move `path` under [`CORRUPT_DIR`], keeping its relative path and adding
+/// a timestamp so repeated corruption does not overwrite earlier evidence.
+async fn quarantine(repo: &Path, path: &Path) -> Result<String> {
+    let rel = relative(repo, path);
+    let target = repo.join(CORRUPT_DIR).join(format!(
+        "{}.{}",
+        rel,
+        Utc::now().format("%Y%m%dT%H%M%S%.3f")
+    ));
+    if let Some(dir) = target.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::rename(path, &target)
+        .await
+        .with_context(|| format!("Failed to move {} aside", path.display()))?;
+    Ok(relative(repo, &target))
+}
+
+/// Write through a temp file so neither a crash nor a concurrent reader
+/// sees the file half-written. Temp files end in `.tmp`, which a pass
+/// removes once they have settled.
+pub(crate) async fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+    let tmp = PathBuf::from(tmp);
+    tokio::fs::write(&tmp, content)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Load a JSON registry; a missing file is the default (empty) registry.
+pub(crate) async fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Save a JSON registry with [`write_atomic`], creating its directory.
+pub(crate) async fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    write_atomic(path, &serde_json::to_string_pretty(value)?).await
+}
+
+fn relative(repo: &Path, path: &Path) -> String {
+    path.strip_prefix(repo)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a file last modified long enough ago for a pass to touch it.
+    async fn write(repo: &Path, path: &str, content: &str) {
+        let path = repo.join(path);
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(&path, content).await.unwrap();
+        age(&path);
+    }
+
+    fn age(path: &Path) {
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - 2 * SETTLE_TIME)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn startup_upgrades_repairs_and_quarantines_coordination_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        assert!(on_startup(repo).await.unwrap().is_none());
+
+        let task = serde_json::to_string_pretty(&Task::new(
+            "t-1".into(),
+            "Add login".into(),
+            crate::agent::Priority::High,
+            crate::agent::TaskType::Development,
+        ))
+        .unwrap();
+        write(repo, "coordination/task-queue/t-1.json", &task).await;
+        write(
+            repo,
+            "coordination/task-queue/t-2.json",
+            &format!("{task}\n  \"priority\": \"Low\"\n}}"),
+        )
+        .await;
+        write(
+            repo,
+            "coordination/task-queue/t-3.json",
+            "{\"id\": \"t-3\", \"desc",
+        )
+        .await;
+        write(repo, "coordination/task-queue/t-4.json.tmp", "{").await;
+        write(
+            repo,
+            "coordination/proposals/prop-1.json",
+            r#"{"title": "Adopt rustfmt", "proposal_type": "policy"}"#,
+        )
+        .await;
+        write(
+            repo,
+            "coordination/agent-status/backend.json",
+            r#"{"status": "Available", "timestamp": "2026-01-01T00:00:00Z"}"#,
+        )
+        .await;
+
+        let report = on_startup(repo).await.unwrap().unwrap();
+        assert_eq!(report.scanned, 5);
+        assert_eq!(report.repaired, ["coordination/task-queue/t-2.json"]);
+        assert_eq!(
+            report.removed_temp,
+            ["coordination/task-queue/t-4.json.tmp"]
+        );
+        let mut upgraded = report.upgraded.clone();
+        upgraded.sort();
+        assert_eq!(
+            upgraded,
+            [
+                "coordination/agent-status/backend.json",
+                "coordination/proposals/prop-1.json"
+            ]
+        );
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(report.corrupt[0].path, "coordination/task-queue/t-3.json");
+        assert!(repo.join(&report.corrupt[0].moved_to).exists());
+        assert!(!repo.join("coordination/task-queue/t-3.json").exists());
+
+        let proposal: Value = serde_json::from_str(
+            &std::fs::read_to_string(repo.join("coordination/proposals/prop-1.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(proposal["id"], "prop-1");
+        assert_eq!(proposal["tally"], "majority");
+        assert_eq!(proposal["votes"], serde_json::json!([]));
+        let repaired: Task = serde_json::from_str(
+            &std::fs::read_to_string(repo.join("coordination/task-queue/t-2.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(repaired.id, "t-1");
+
+        // Nothing changed since the last pass: nothing is read again.
+        let report = on_startup(repo).await.unwrap().unwrap();
+        assert_eq!(report.scanned, 0);
+        assert!(report.is_clean());
+
+        // A message or temp file being written right now is left alone...
+        let message = repo.join(".ccswarm/coordination/messages/1-a.json");
+        let temp = repo.join(".ccswarm/coordination/messages/2-b.json.tmp");
+        std::fs::create_dir_all(message.parent().unwrap()).unwrap();
+        std::fs::write(&message, "{\"type\": ").unwrap();
+        std::fs::write(&temp, "{").unwrap();
+        let report = on_startup(repo).await.unwrap().unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.deferred.len(), 2);
+        assert!(message.exists() && temp.exists());
+        // ...the next pass still looks at it...
+        let marker: StateMarker =
+            serde_json::from_str(&std::fs::read_to_string(repo.join(STATE_FILE)).unwrap()).unwrap();
+        let modified = std::fs::metadata(&message).unwrap().modified().unwrap();
+        assert!(SystemTime::from(marker.checked_at) <= modified);
+        // ...and judges it once it has settled.
+        age(&message);
+        age(&temp);
+        let report = migrate(repo, None).await.unwrap();
+        assert!(!report.deferred.iter().any(|path| path.contains("messages")));
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(
+            report.corrupt[0].path,
+            ".ccswarm/coordination/messages/1-a.json"
+        );
+        assert_eq!(
+            report.removed_temp,
+            [".ccswarm/coordination/messages/2-b.json.tmp"]
+        );
+    }
+
+    #[test]
+    fn statuses_and_proposals_that_cannot_be_upgraded_are_corrupt() {
+        assert!(matches!(
+            check(Kind::AgentStatus, "qa", r#"{"status": "Sleeping"}"#),
+            Verdict::Corrupt(_)
+        ));
+        assert!(matches!(
+            check(Kind::Proposal, "prop-2", r#"{"id": "prop-2", "votes": {}}"#),
+            Verdict::Corrupt(_)
+        ));
+        assert!(matches!(
+            check(Kind::Delegations, "delegations", "[1, 2]"),
+            Verdict::Corrupt(_)
+        ));
+        assert_eq!(
+            check(Kind::Delegations, "delegations", "[]"),
+            Verdict::Valid
+        );
+    }
+
+    #[tokio::test]
+    async fn json_registries_round_trip_without_leaving_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions/names.json");
+        let empty: std::collections::BTreeMap<String, u32> = load_json(&path).await.unwrap();
+        assert!(empty.is_empty());
+
+        let registry = std::collections::BTreeMap::from([("api".to_string(), 1u32)]);
+        save_json(&path, &registry).await.unwrap();
+        assert_eq!(
+            load_json::<std::collections::BTreeMap<String, u32>>(&path)
+                .await
+                .unwrap(),
+            registry
+        );
+        let files: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(files, ["names.json"]);
+
+        tokio::fs::write(&path, "{").await.unwrap();
+        assert!(
+            load_json::<std::collections::BTreeMap<String, u32>>(&path)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -2,11 +2,17 @@ use anyhow::{Context, Result};
 use async_channel::{Receiver, Sender};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use tracing::{debug, error, info};
 
 use crate::agent::{AgentStatus, TaskResult};
+use crate::telemetry::TraceContext;
+
+pub mod halt;
+pub mod human;
+pub mod migrate;
+pub mod watch;
 
 /// Message priority levels
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -131,24 +137,61 @@ pub enum AgentMessage {
     },
 }
 
+/// A bus message together with the trace context of the span that sent it.
+/// This is also what is persisted, so the context survives the hop to
+/// another process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusEnvelope {
+    pub message: AgentMessage,
+    #[serde(default)]
+    pub trace: TraceContext,
+}
+
+impl BusEnvelope {
+    /// A persisted envelope, or a bare message written before envelopes were
+    /// persisted (with no trace context).
+    pub fn from_value(value: serde_json::Value) -> Result<Self> {
+        if value.get("message").is_some() {
+            return Ok(serde_json::from_value(value)?);
+        }
+        Ok(Self {
+            message: serde_json::from_value(value)?,
+            trace: TraceContext::default(),
+        })
+    }
+
+    /// A `bus.handle` span continuing the sender's trace. Handle the message
+    /// inside it so the work shows up under the span that sent it.
+    pub fn handling_span(&self) -> tracing::Span {
+        let span = tracing::info_span!("bus.handle");
+        self.trace.attach(&span);
+        span
+    }
+}
+
 /// Coordination bus for inter-agent communication
 #[derive(Clone)]
 pub struct CoordinationBus {
     /// Channel for sending messages
-    sender: Sender<AgentMessage>,
+    sender: Sender<BusEnvelope>,
 
     /// Channel for receiving messages
-    receiver: Receiver<AgentMessage>,
+    receiver: Receiver<BusEnvelope>,
 
     /// Directory for persistent message storage
     message_dir: PathBuf,
 }
 
 impl CoordinationBus {
-    /// Create a new coordination bus
-    pub async fn new() -> Result<Self> {
+    /// Create a coordination bus persisting under `repo`'s `.ccswarm`
+    pub async fn new(repo: &Path) -> Result<Self> {
+        Self::with_dir(repo.join(".ccswarm/coordination/messages")).await
+    }
+
+    /// Create a coordination bus that persists messages under `dir`
+    pub async fn with_dir(dir: impl Into<PathBuf>) -> Result<Self> {
         let (sender, receiver) = async_channel::bounded(1000);
-        let message_dir = PathBuf::from(".ccswarm/coordination/messages");
+        let message_dir = dir.into();
 
         // Create message directory
         fs::create_dir_all(&message_dir)
@@ -162,24 +205,35 @@ impl CoordinationBus {
         })
     }
 
-    /// Send a message through the bus
+    /// Send a message through the bus, stamped with the caller's trace context
+    #[tracing::instrument(name = "bus.send", skip_all)]
     pub async fn send_message(&self, message: AgentMessage) -> Result<()> {
         debug!("Sending message: {:?}", message);
 
-        // Send through channel
+        let envelope = BusEnvelope {
+            message,
+            trace: TraceContext::current(),
+        };
+        // Persist first: the channel takes the envelope.
+        self.persist_message(&envelope).await?;
         self.sender
-            .send(message.clone())
+            .send(envelope)
             .await
             .context("Failed to send message through channel")?;
 
-        // Also persist to disk for durability
-        self.persist_message(&message).await?;
-
         Ok(())
     }
 
-    /// Receive a message from the bus
+    /// Receive a message from the bus. The receipt is recorded under the
+    /// sender's trace; use [`Self::receive_traced`] to handle the message
+    /// there too.
     pub async fn receive_message(&self) -> Result<AgentMessage> {
+        self.receive_traced().await.map(received)
+    }
+
+    /// Receive a message along with the sender's trace context. Handlers run
+    /// inside [`BusEnvelope::handling_span`] to continue the sender's trace.
+    pub async fn receive_traced(&self) -> Result<BusEnvelope> {
         self.receiver
             .recv()
             .await
@@ -188,11 +242,11 @@ impl CoordinationBus {
 
     /// Try to receive a message without blocking
     pub fn try_receive_message(&self) -> Option<AgentMessage> {
-        self.receiver.try_recv().ok()
+        self.receiver.try_recv().ok().map(received)
     }
 
     /// Persist message to disk
-    async fn persist_message(&self, message: &AgentMessage) -> Result<()> {
+    async fn persist_message(&self, envelope: &BusEnvelope) -> Result<()> {
         let timestamp = Utc::now();
         let filename = format!(
             "{}-{}.json",
@@ -201,8 +255,8 @@ impl CoordinationBus {
         );
         let filepath = self.message_dir.join(&filename);
 
-        let content = serde_json::to_string_pretty(message)?;
-        fs::write(&filepath, content)
+        let content = serde_json::to_string_pretty(envelope)?;
+        migrate::write_atomic(&filepath, &content)
             .await
             .context("Failed to persist message")?;
 
@@ -218,7 +272,8 @@ impl CoordinationBus {
         let mut files = Vec::new();
 
         while let Some(entry) = entries.next_entry().await? {
-            if let Ok(metadata) = entry.metadata().await
+            if is_json(&entry.path())
+                && let Ok(metadata) = entry.metadata().await
                 && metadata.is_file()
             {
                 files.push((entry.path(), metadata.modified()?));
@@ -247,11 +302,24 @@ impl CoordinationBus {
     /// giving a stable, deterministic ordering regardless of which `AgentMessage`
     /// variant is stored.
     pub async fn load_persisted_messages(&self) -> Result<Vec<AgentMessage>> {
+        Ok(self
+            .load_persisted()
+            .await?
+            .into_iter()
+            .map(|envelope| envelope.message)
+            .collect())
+    }
+
+    /// Load messages from disk with their trace context, oldest first.
+    pub async fn load_persisted(&self) -> Result<Vec<BusEnvelope>> {
         let mut files: Vec<(std::path::PathBuf, String)> = Vec::new();
         let mut entries = fs::read_dir(&self.message_dir).await?;
 
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
+            if !is_json(&path) {
+                continue;
+            }
             let name = path
                 .file_name()
                 .map(|n| n.to_string_lossy().into_owned())
@@ -262,20 +330,21 @@ impl CoordinationBus {
         // Sort by filename (timestamp-prefixed) for deterministic ordering.
         files.sort_by(|(_, a), (_, b)| a.cmp(b));
 
-        let mut messages = Vec::new();
+        let mut envelopes = Vec::new();
         for (path, _) in files {
             if let Ok(content) = fs::read_to_string(&path).await
-                && let Ok(message) = serde_json::from_str::<AgentMessage>(&content)
+                && let Ok(value) = serde_json::from_str(&content)
+                && let Ok(envelope) = BusEnvelope::from_value(value)
             {
-                messages.push(message);
+                envelopes.push(envelope);
             }
         }
 
-        Ok(messages)
+        Ok(envelopes)
     }
 
     /// Get a channel sender for agents to use
-    pub fn get_sender(&self) -> Sender<AgentMessage> {
+    pub fn get_sender(&self) -> Sender<BusEnvelope> {
         self.sender.clone()
     }
 
@@ -313,7 +382,7 @@ impl TaskQueue {
         let filepath = self.task_dir.join(&filename);
 
         let content = serde_json::to_string_pretty(task)?;
-        fs::write(&filepath, content).await?;
+        migrate::write_atomic(&filepath, &content).await?;
 
         info!("Task {} added to queue", task.id);
         Ok(())
@@ -325,7 +394,8 @@ impl TaskQueue {
         let mut entries = fs::read_dir(&self.task_dir).await?;
 
         while let Some(entry) = entries.next_entry().await? {
-            if let Ok(content) = fs::read_to_string(entry.path()).await
+            if is_json(&entry.path())
+                && let Ok(content) = fs::read_to_string(entry.path()).await
                 && let Ok(task) = serde_json::from_str(&content)
             {
                 tasks.push(task);
@@ -384,7 +454,7 @@ impl StatusTracker {
         });
 
         let content = serde_json::to_string_pretty(&status_info)?;
-        fs::write(&filepath, content).await?;
+        migrate::write_atomic(&filepath, &content).await?;
 
         Ok(())
     }
@@ -410,7 +480,8 @@ impl StatusTracker {
         let mut entries = fs::read_dir(&self.status_dir).await?;
 
         while let Some(entry) = entries.next_entry().await? {
-            if let Ok(content) = fs::read_to_string(entry.path()).await
+            if is_json(&entry.path())
+                && let Ok(content) = fs::read_to_string(entry.path()).await
                 && let Ok(status) = serde_json::from_str(&content)
             {
                 statuses.push(status);
@@ -420,3 +491,16 @@ impl StatusTracker {
         Ok(statuses)
     }
 }
+
+/// Log a received message under the sender's trace.
+fn received(envelope: BusEnvelope) -> AgentMessage {
+    envelope
+        .handling_span()
+        .in_scope(|| debug!("Received message: {:?}", envelope.message));
+    envelope.message
+}
+
+/// A coordination document, not the temp file of a write in progress.
+fn is_json(path: &std::path::Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
@@ -0,0 +1,219 @@
+//! Waking coordination loops when the files they coordinate through change.
+//!
+//! Coordination state lives in files (`.ccswarm/queue.yaml`, the message
+//! directory), so a loop waiting for work either sleeps a fixed interval or
+//! is told when a file changes. `coordination.watch` picks how:
+//!
+//! - `auto` (default): file-system events (inotify, kqueue, FSEvents),
+//!   falling back to polling where they cannot be set up.
+//! - `events`: file-system events only; failing to set them up is an error.
+//! - `polling`: adaptive polling only.
+//!
+//! Adaptive polling checks every 100ms right after a change and doubles the
+//! interval while nothing changes, up to `coordination.sync_interval`. A
+//! watched directory counts as changed when entries are added, removed or
+//! renamed, not when an existing file is rewritten in place.
+
+use anyhow::{Context, Result};
+use fswatch::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Shortest poll interval, and how long a burst of events may settle.
+const MIN_POLL: Duration = Duration::from_millis(100);
+
+/// How coordination loops notice changes (`coordination.watch`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMode {
+    /// File-system events, else adaptive polling.
+    #[default]
+    Auto,
+    /// File-system events only.
+    Events,
+    /// Adaptive polling only.
+    Polling,
+}
+
+impl std::fmt::Display for WatchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            WatchMode::Auto => "auto",
+            WatchMode::Events => "events",
+            WatchMode::Polling => "polling",
+        })
+    }
+}
+
+/// Waits for any of a set of files or directories to change.
+pub struct ChangeWatcher {
+    paths: Vec<PathBuf>,
+    max_poll: Duration,
+    backend: Backend,
+}
+
+enum Backend {
+    Events {
+        // Dropping the watcher stops the events.
+        _watcher: fswatch::RecommendedWatcher,
+        rx: mpsc::UnboundedReceiver<()>,
+    },
+    Polling {
+        seen: Vec<Option<(SystemTime, u64)>>,
+        interval: Duration,
+    },
+}
+
+impl ChangeWatcher {
+    /// Watch `paths`; polling never waits longer than `max_poll` between checks.
+    pub fn new(mode: WatchMode, paths: Vec<PathBuf>, max_poll: Duration) -> Result<Self> {
+        let backend = match mode {
+            WatchMode::Polling => polling(&paths),
+            WatchMode::Events => events(&paths)?,
+            WatchMode::Auto => events(&paths).unwrap_or_else(|e| {
+                warn!("file-system events unavailable, polling instead: {e:#}");
+                polling(&paths)
+            }),
+        };
+        Ok(Self {
+            paths,
+            max_poll: max_poll.max(MIN_POLL),
+            backend,
+        })
+    }
+
+    pub fn is_event_driven(&self) -> bool {
+        matches!(self.backend, Backend::Events { .. })
+    }
+
+    /// Wait until a watched path changes (`true`) or `timeout` elapses.
+    pub async fn changed(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        if let Backend::Events { rx, .. } = &mut self.backend {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(())) => {
+                    // Writers touch a file more than once (temp file, rename);
+                    // report the burst as one change.
+                    tokio::time::sleep(MIN_POLL).await;
+                    while rx.try_recv().is_ok() {}
+                    return true;
+                }
+                Err(_) => return false,
+                Ok(None) => {
+                    warn!("file-system watcher stopped, polling instead");
+                    self.backend = polling(&self.paths);
+                }
+            }
+        }
+        let Backend::Polling { seen, interval } = &mut self.backend else {
+            unreachable!("event backend returns above");
+        };
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            tokio::time::sleep((*interval).min(deadline - now)).await;
+            let current = fingerprint(&self.paths);
+            if current != *seen {
+                *seen = current;
+                *interval = MIN_POLL;
+                return true;
+            }
+            *interval = (*interval * 2).min(self.max_poll);
+        }
+    }
+}
+
+fn polling(paths: &[PathBuf]) -> Backend {
+    Backend::Polling {
+        seen: fingerprint(paths),
+        interval: MIN_POLL,
+    }
+}
+
+/// Watch each directory itself and each file through its parent, since
+/// atomic writes replace the file (and its inode) on every save.
+fn events(paths: &[PathBuf]) -> Result<Backend> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let targets = paths.to_vec();
+    let mut watcher = fswatch::recommended_watcher(move |res: fswatch::Result<fswatch::Event>| {
+        let Ok(event) = res else { return };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        let relevant = event.paths.iter().any(|p| {
+            targets
+                .iter()
+                .any(|t| p == t || p.parent() == Some(t.as_path()))
+        });
+        if relevant {
+            let _ = tx.send(());
+        }
+    })
+    .context("Failed to create a file-system watcher")?;
+    for path in paths {
+        let dir = if path.is_dir() {
+            path.as_path()
+        } else {
+            path.parent()
+                .context("watched file has no parent directory")?
+        };
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+    }
+    Ok(Backend::Events {
+        _watcher: watcher,
+        rx,
+    })
+}
+
+fn fingerprint(paths: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    paths
+        .iter()
+        .map(|path| {
+            let meta = std::fs::metadata(path).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn both_backends_wake_on_an_atomic_rewrite_and_time_out_otherwise() {
+        for mode in [WatchMode::Events, WatchMode::Polling] {
+            let dir = tempfile::tempdir().unwrap();
+            let file = dir.path().join(".ccswarm/queue.yaml");
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            let mut watcher =
+                ChangeWatcher::new(mode, vec![file.clone()], Duration::from_secs(1)).unwrap();
+            assert_eq!(watcher.is_event_driven(), mode == WatchMode::Events);
+            assert!(!watcher.changed(Duration::from_millis(300)).await, "{mode}");
+
+            let writer = tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(150)).await;
+                let tmp = file.with_extension("tmp");
+                std::fs::write(&tmp, "tasks: []\n").unwrap();
+                std::fs::rename(&tmp, &file).unwrap();
+            });
+            let started = std::time::Instant::now();
+            assert!(watcher.changed(Duration::from_secs(10)).await, "{mode}");
+            assert!(started.elapsed() < Duration::from_secs(2), "{mode}");
+            writer.await.unwrap();
+
+            // Unrelated files next to the watched one do not count.
+            std::fs::write(dir.path().join(".ccswarm/other"), "x").unwrap();
+            assert!(!watcher.changed(Duration::from_millis(300)).await, "{mode}");
+        }
+    }
+}
@@ -8,6 +8,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+
+use crate::security::command_risk::CommandAssessment;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
@@ -21,6 +23,10 @@ pub enum Gate {
     Merge,
     /// Commit (and optional PR) of an unattended pipeline run's output.
     Commit,
+    /// Resuming a queued task that was paused when it ran out of time.
+    Resume,
+    /// A shell command auto-accept flagged as risky.
+    Command,
 }
 
 impl std::fmt::Display for Gate {
@@ -31,6 +37,8 @@ impl std::fmt::Display for Gate {
             Gate::Deploy => "deploy",
             Gate::Merge => "merge",
             Gate::Commit => "commit",
+            Gate::Resume => "resume",
+            Gate::Command => "command",
         };
         f.write_str(s)
     }
@@ -65,6 +73,9 @@ pub struct ApprovalRecord {
     pub decided_at: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub decided_by: Option<String>,
+    /// The held command and its static analysis, for [`Gate::Command`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<CommandAssessment>,
 }
 
 /// Outcome of waiting on a gate.
@@ -107,11 +118,64 @@ impl ApprovalStore {
             requested_by: Some(requested_by.to_string()),
             decided_at: None,
             decided_by: None,
+            command: None,
+        };
+        self.write(&record).await?;
+        Ok(record)
+    }
+
+    /// Hold the command in `assessment` for approval under `id`.
+    pub async fn request_command(
+        &self,
+        id: &str,
+        assessment: &CommandAssessment,
+        requested_by: &str,
+    ) -> Result<ApprovalRecord> {
+        crate::run_id::validate_run_id(id).context("invalid approval ID")?;
+        let record = ApprovalRecord {
+            id: id.to_string(),
+            gate: Gate::Command,
+            status: ApprovalStatus::Pending,
+            reason: None,
+            task: Some(assessment.summary()),
+            requested_at: Some(chrono::Utc::now().to_rfc3339()),
+            requested_by: Some(requested_by.to_string()),
+            decided_at: None,
+            decided_by: None,
+            command: Some(assessment.clone()),
         };
         self.write(&record).await?;
         Ok(record)
     }
 
+    /// Attach a written explanation to `id`. It lives in a file of its own,
+    /// `{id}.explanation.md`, so it can arrive while a decision is being
+    /// written without either overwriting the other.
+    pub async fn set_explanation(&self, id: &str, explanation: &str) -> Result<()> {
+        crate::run_id::validate_run_id(id).context("invalid approval ID")?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(format!("{}.explanation.md", id));
+        let tmp = self.dir.join(format!("{}.explanation.md.tmp", id));
+        tokio::fs::write(&tmp, explanation.trim())
+            .await
+            .with_context(|| format!("failed to write {:?}", tmp))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .with_context(|| format!("failed to rename {:?} -> {:?}", tmp, path))?;
+        Ok(())
+    }
+
+    /// The explanation attached to `id`, if one has been written.
+    pub async fn explanation(&self, id: &str) -> Result<Option<String>> {
+        crate::run_id::validate_run_id(id).context("invalid approval ID")?;
+        let path = self.dir.join(format!("{}.explanation.md", id));
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("failed to read {:?}", path)),
+        }
+    }
+
     /// Record a decision for `id`. Merges onto an existing pending record when
     /// present (preserving request context); otherwise upserts a fresh record —
     /// the CLI has always allowed approving an ID with no prior request.
@@ -133,6 +197,7 @@ impl ApprovalStore {
             requested_by: None,
             decided_at: None,
             decided_by: None,
+            command: None,
         });
         record.gate = gate;
         record.status = if approve {
@@ -409,6 +474,33 @@ mod tests {
         assert_eq!(outcome, GateOutcome::TimedOut);
     }
 
+    #[tokio::test]
+    async fn command_request_keeps_assessment_and_explanation_separately() {
+        let (_dir, store) = store();
+        let assessment = crate::security::command_risk::assess("git push --force origin main");
+        store
+            .request_command("cmd-1", &assessment, "typed-tools")
+            .await
+            .expect("request");
+        store
+            .set_explanation("cmd-1", "Overwrites main on origin.\n")
+            .await
+            .expect("explain");
+        store
+            .decide("cmd-1", Gate::Command, false, Some("not on main"))
+            .await
+            .expect("decide");
+
+        let record = store.get("cmd-1").await.expect("get").expect("record");
+        assert_eq!(record.status, ApprovalStatus::Rejected);
+        assert_eq!(record.command, Some(assessment));
+        assert_eq!(
+            store.explanation("cmd-1").await.expect("read").as_deref(),
+            Some("Overwrites main on origin.")
+        );
+        assert_eq!(store.list().await.expect("list").len(), 1);
+    }
+
     #[tokio::test]
     async fn list_returns_all_records_sorted() {
         let (_dir, store) = store();
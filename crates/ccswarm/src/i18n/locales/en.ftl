@@ -0,0 +1,47 @@
+# CLI and TUI text. Every message here must also be in each other locale
+# file, in the same order; English is the fallback for anything missing.
+
+## quality
+
+quality-running = Running { $count } check(s), { $jobs } at a time
+quality-profile = Profile { $profile } ({ $reason })
+quality-baseline-summary = { $found } issue(s), { $allowed } allowed, { $new } new, { $fixed } fixed
+quality-more-issues = ... and { $count } more
+quality-summary = { $passed } passed, { $failed } failed, { $cancelled } cancelled
+quality-pass = PASS
+quality-fail = FAIL
+
+## worktree
+
+worktree-list-title = Git Worktrees
+worktree-path = Path: { $path }
+worktree-branch = Branch: { $branch }
+worktree-head = Head: { $head }
+worktree-locked = Locked: { $locked }
+worktree-created = Worktree created
+worktree-removed = Worktree removed: { $path }
+worktree-on-branch = { $path } on { $branch }
+worktree-detached = (detached)
+worktree-clean = clean
+worktree-pruned = Stale worktrees pruned
+worktree-nothing-to-clean = No ccswarm worktrees to clean
+worktree-found = Found { $count } ccswarm worktrees:
+worktree-confirm-clean = Are you sure you want to remove all these worktrees? [y/N]
+worktree-clean-cancelled = Cleanup cancelled
+
+## tui
+
+tui-timeline = Timeline
+tui-detail = Detail
+tui-timeline-help = j/k select  R reload  q quit
+tui-agents = Agents
+tui-tokens-per-stage = tokens per stage
+tui-usage-help = j/k agent · p pause run · r resume run · R reload · q quit
+tui-tasks = Tasks
+tui-task = Task
+tui-no-human-tasks = No tasks delegated to humans.
+tui-input-help = (Enter to send, Esc to cancel)
+tui-inbox-help = j/k task · a accept · c complete · d decline · m comment · R reload · q quit
+tui-chat-help = j/k reply · g/G first/last · a agent · s stop reply · q quit
+tui-no-replies = No replies streamed in this run yet.
+tui-reply-finished = That reply has already finished
@@ -0,0 +1,46 @@
+# CLI と TUI の表示文。en.ftl と同じメッセージを同じ順に並べること。
+
+## quality
+
+quality-running = { $count } 件のチェックを実行中 (同時 { $jobs } 件)
+quality-profile = プロファイル { $profile } ({ $reason })
+quality-baseline-summary = 問題 { $found } 件 (許容 { $allowed } 件、新規 { $new } 件、解消 { $fixed } 件)
+quality-more-issues = ... ほか { $count } 件
+quality-summary = 成功 { $passed } 件、失敗 { $failed } 件、中止 { $cancelled } 件
+quality-pass = 合格
+quality-fail = 不合格
+
+## worktree
+
+worktree-list-title = Git ワークツリー
+worktree-path = パス: { $path }
+worktree-branch = ブランチ: { $branch }
+worktree-head = HEAD: { $head }
+worktree-locked = ロック: { $locked }
+worktree-created = ワークツリーを作成しました
+worktree-removed = ワークツリーを削除しました: { $path }
+worktree-on-branch = { $path } (ブランチ { $branch })
+worktree-detached = (デタッチ)
+worktree-clean = 変更なし
+worktree-pruned = 古いワークツリーを整理しました
+worktree-nothing-to-clean = 削除する ccswarm のワークツリーはありません
+worktree-found = ccswarm のワークツリーが { $count } 件あります:
+worktree-confirm-clean = これらのワークツリーをすべて削除しますか? [y/N]
+worktree-clean-cancelled = 削除を中止しました
+
+## tui
+
+tui-timeline = タイムライン
+tui-detail = 詳細
+tui-timeline-help = j/k 選択  R 再読込  q 終了
+tui-agents = エージェント
+tui-tokens-per-stage = ステージ別トークン
+tui-usage-help = j/k エージェント · p 一時停止 · r 再開 · R 再読込 · q 終了
+tui-tasks = タスク
+tui-task = タスク
+tui-no-human-tasks = 人に委任されたタスクはありません。
+tui-input-help = (Enter で送信、Esc で取消)
+tui-inbox-help = j/k タスク · a 受諾 · c 完了 · d 辞退 · m コメント · R 再読込 · q 終了
+tui-chat-help = j/k 返信 · g/G 最初/最後 · a エージェント · s 生成を停止 · q 終了
+tui-no-replies = この実行ではまだ返信がストリームされていません。
+tui-reply-finished = その返信はすでに完了しています
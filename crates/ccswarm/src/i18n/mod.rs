@@ -0,0 +1,348 @@
+//! Translated CLI and TUI text, and the language and time zone agents work in.
+//!
+//! Messages live in Fluent files under `i18n/locales/`, one per language,
+//! compiled in. The CLI picks its language from `CCSWARM_LANG`, then
+//! `i18n.locale` in `ccswarm.json`, then `LC_ALL`, `LC_MESSAGES` and `LANG`.
+//! A language without a translation, or a message its translation lacks,
+//! falls back to English.
+//!
+//! What agents are told is separate from what the person at the terminal
+//! reads: `i18n.working_language` and `i18n.timezone` (or an agent's own
+//! `locale` and `timezone`) become a prompt constraint, so every agent in a
+//! project writes in the same language whatever the CLI shows.
+
+use chrono_tz::Tz;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub use fluent_bundle::FluentValue;
+
+/// Compiled-in translations; the first is the fallback.
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.ftl")),
+    ("ja", include_str!("locales/ja.ftl")),
+];
+
+/// Environment variables consulted for the CLI language, after
+/// `CCSWARM_LANG` and the configured locale.
+const LOCALE_VARS: &[&str] = &["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// `i18n` in `ccswarm.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct I18nConfig {
+    /// Language of CLI and TUI text, e.g. `ja`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Language agents write replies, comments and commit messages in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_language: Option<String>,
+    /// IANA time zone agents use for dates and times, e.g. `Asia/Tokyo`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+struct Translator {
+    locale: &'static str,
+    bundle: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+static TRANSLATOR: OnceLock<Translator> = OnceLock::new();
+
+fn bundle(locale: &str, source: &'static str) -> FluentBundle<FluentResource> {
+    let id = locale.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![id]);
+    // Isolation marks around arguments show up as stray characters in a
+    // terminal.
+    bundle.set_use_isolating(false);
+    let resource =
+        FluentResource::try_new(source.to_string()).unwrap_or_else(|(partial, _)| partial);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+impl Translator {
+    fn new(locale: &'static str) -> Self {
+        let source = LOCALES
+            .iter()
+            .find(|(code, _)| *code == locale)
+            .map_or(LOCALES[0].1, |(_, source)| *source);
+        Self {
+            locale,
+            bundle: bundle(locale, source),
+            fallback: bundle(LOCALES[0].0, LOCALES[0].1),
+        }
+    }
+
+    fn format(&self, key: &str, args: Option<&FluentArgs>) -> String {
+        [&self.bundle, &self.fallback]
+            .into_iter()
+            .find_map(|bundle| {
+                let pattern = bundle.get_message(key)?.value()?;
+                let mut errors = Vec::new();
+                Some(
+                    bundle
+                        .format_pattern(pattern, args, &mut errors)
+                        .into_owned(),
+                )
+            })
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+/// The translated language for `value` (`ja_JP.UTF-8`, `ja-JP`, `ja`), if
+/// there is one.
+fn supported(value: &str) -> Option<&'static str> {
+    let language = value
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    LOCALES
+        .iter()
+        .map(|(code, _)| *code)
+        .find(|code| *code == language)
+}
+
+/// The CLI language: the first of `CCSWARM_LANG`, `configured` and the
+/// POSIX locale variables that is set, if it has a translation, else English.
+pub fn resolve(configured: Option<&str>, env: impl Fn(&str) -> Option<String>) -> &'static str {
+    let chosen = env("CCSWARM_LANG")
+        .or_else(|| configured.map(str::to_string))
+        .or_else(|| {
+            LOCALE_VARS
+                .iter()
+                .filter_map(|var| env(var))
+                .find(|value| !matches!(value.as_str(), "" | "C" | "POSIX"))
+        })
+        .filter(|value| !value.is_empty());
+    chosen
+        .as_deref()
+        .and_then(supported)
+        .unwrap_or(LOCALES[0].0)
+}
+
+/// Fix the CLI language for this process. Only the first call has an
+/// effect; text translated before it uses the environment alone.
+pub fn init(configured: Option<&str>) -> &'static str {
+    TRANSLATOR
+        .get_or_init(|| Translator::new(resolve(configured, |var| std::env::var(var).ok())))
+        .locale
+}
+
+fn translator() -> &'static Translator {
+    TRANSLATOR.get_or_init(|| Translator::new(resolve(None, |var| std::env::var(var).ok())))
+}
+
+/// The CLI language in use.
+pub fn locale() -> &'static str {
+    translator().locale
+}
+
+/// Message `key` in the CLI language, with `args` substituted.
+pub fn tr(key: &str, args: &[(&str, FluentValue<'_>)]) -> String {
+    if args.is_empty() {
+        return translator().format(key, None);
+    }
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+    translator().format(key, Some(&fluent_args))
+}
+
+/// `t!("key")` or `t!("key", name = value, ...)`: [`tr`] with named
+/// arguments.
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::tr($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::tr(
+            $key,
+            &[$((stringify!($name), $crate::i18n::FluentValue::from($value))),+],
+        )
+    };
+}
+
+/// English name of a language code, for prompts.
+pub fn language_name(code: &str) -> String {
+    let language = code.split(['_', '-', '.']).next().unwrap_or(code);
+    match language.to_ascii_lowercase().as_str() {
+        "en" => "English",
+        "ja" => "Japanese",
+        "zh" => "Chinese",
+        "ko" => "Korean",
+        "fr" => "French",
+        "de" => "German",
+        "es" => "Spanish",
+        "pt" => "Portuguese",
+        "it" => "Italian",
+        "ru" => "Russian",
+        _ => return code.to_string(),
+    }
+    .to_string()
+}
+
+/// Check a configured time zone name.
+pub fn parse_timezone(name: &str) -> anyhow::Result<Tz> {
+    name.parse()
+        .map_err(|_| anyhow::anyhow!("unknown time zone '{}' (expected e.g. Asia/Tokyo)", name))
+}
+
+/// The language and time zone one agent works in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentLocale {
+    pub language: Option<String>,
+    pub timezone: Option<Tz>,
+}
+
+impl AgentLocale {
+    /// The prompt constraint stating them, if either is set.
+    pub fn prompt(&self) -> Option<String> {
+        let mut lines = Vec::new();
+        if let Some(language) = &self.language {
+            let name = language_name(language);
+            lines.push(format!(
+                "Working language: {name}. Write replies, code comments, documentation \
+                 and commit messages in {name} unless the task says otherwise."
+            ));
+        }
+        if let Some(timezone) = &self.timezone {
+            lines.push(format!(
+                "Time zone: {}. Read and write dates and times in this zone.",
+                timezone.name()
+            ));
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+/// The project's [`AgentLocale`] and each agent's overrides.
+#[derive(Debug, Clone, Default)]
+pub struct Locales {
+    project: AgentLocale,
+    agents: HashMap<String, AgentLocale>,
+}
+
+impl Locales {
+    pub fn from_config(config: &crate::config::CcswarmConfig) -> Self {
+        let project = AgentLocale {
+            language: config.i18n.working_language.clone(),
+            timezone: config
+                .i18n
+                .timezone
+                .as_deref()
+                .and_then(|tz| parse_timezone(tz).ok()),
+        };
+        let agents = config
+            .agents
+            .iter()
+            .map(|(name, agent)| {
+                let locale = AgentLocale {
+                    language: agent.locale.clone().or_else(|| project.language.clone()),
+                    timezone: agent
+                        .timezone
+                        .as_deref()
+                        .and_then(|tz| parse_timezone(tz).ok())
+                        .or(project.timezone),
+                };
+                (name.clone(), locale)
+            })
+            .collect();
+        Self { project, agents }
+    }
+
+    /// The locale of the first of `names` configured as an agent, else the
+    /// project's.
+    pub fn for_agent<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> &AgentLocale {
+        names
+            .into_iter()
+            .find_map(|name| self.agents.get(name))
+            .unwrap_or(&self.project)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_language_follows_env_then_config_then_posix_locale() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |var: &str| {
+                vars.iter()
+                    .find(|(name, _)| *name == var)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(resolve(None, env(&[])), "en");
+        assert_eq!(resolve(None, env(&[("LANG", "ja_JP.UTF-8")])), "ja");
+        assert_eq!(
+            resolve(None, env(&[("LC_ALL", "C"), ("LANG", "ja_JP")])),
+            "ja"
+        );
+        assert_eq!(resolve(Some("en"), env(&[("LANG", "ja_JP.UTF-8")])), "en");
+        assert_eq!(resolve(Some("en"), env(&[("CCSWARM_LANG", "ja")])), "ja");
+        assert_eq!(resolve(Some("fr"), env(&[])), "en");
+
+        let mut args = FluentArgs::new();
+        args.set("count", 2);
+        args.set("jobs", 4);
+        assert_eq!(
+            Translator::new("en").format("quality-running", Some(&args)),
+            "Running 2 check(s), 4 at a time"
+        );
+        let ja = Translator::new("ja");
+        assert_eq!(
+            ja.format("quality-running", Some(&args)),
+            "2 件のチェックを実行中 (同時 4 件)"
+        );
+        assert_eq!(ja.format("no-such-message", None), "no-such-message");
+    }
+
+    #[test]
+    fn every_translation_has_every_english_message() {
+        let ids = |source: &str| -> Vec<String> {
+            source
+                .lines()
+                .filter(|line| !line.starts_with([' ', '#']) && line.contains(" ="))
+                .map(|line| line.split(" =").next().unwrap().to_string())
+                .collect()
+        };
+        let english = ids(LOCALES[0].1);
+        for (code, source) in &LOCALES[1..] {
+            assert_eq!(ids(source), english, "{code} is out of step with en");
+        }
+    }
+
+    #[test]
+    fn agents_override_the_project_locale() {
+        let mut config = crate::config::CcswarmConfig::default();
+        config.i18n.working_language = Some("ja".into());
+        config.i18n.timezone = Some("Asia/Tokyo".into());
+        let mut agent = crate::config::AgentConfig::default();
+        agent.timezone = Some("Europe/Berlin".into());
+        config.agents.insert("backend".into(), agent);
+
+        let locales = Locales::from_config(&config);
+        let backend = locales.for_agent(["backend"]);
+        assert_eq!(backend.language.as_deref(), Some("ja"));
+        let prompt = backend.prompt().unwrap();
+        assert!(prompt.contains("Working language: Japanese."));
+        assert!(prompt.contains("Time zone: Europe/Berlin."));
+        assert!(
+            locales
+                .for_agent(["unknown"])
+                .prompt()
+                .unwrap()
+                .contains("Asia/Tokyo")
+        );
+        assert!(AgentLocale::default().prompt().is_none());
+    }
+}
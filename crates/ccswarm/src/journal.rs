@@ -0,0 +1,517 @@
+//! Reversible action journal behind `ccswarm task undo`.
+//!
+//! Before an agent gets a chance to change the tree — a writing stage, an
+//! auto-fix attempt, the post-pipeline commit — the pipeline records a
+//! snapshot of the working directory: a commit object holding every tracked
+//! and untracked (non-ignored) file, parented on the `HEAD` of the moment and
+//! pinned under `refs/ccswarm/journal/<run-id>/<seq>` so `git gc` keeps it.
+//! The real index and working tree are never touched while recording.
+//!
+//! Entries are appended to `.ccswarm/runs/<run-id>/journal.jsonl`. Undoing
+//! a task restores each working directory to its earliest snapshot: commits
+//! made since are dropped from the branch (`reset --soft`), files the agent
+//! created are deleted, and files it changed or deleted come back. `.ccswarm`
+//! itself is never snapshotted or restored.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+use crate::git::shell::{git, git_with_env};
+
+/// Paths left out of snapshots and restores.
+const EXCLUDE: &str = ":(exclude).ccswarm";
+
+/// What was about to happen when a snapshot was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalAction {
+    /// A flow stage with write access.
+    Stage,
+    /// A post-pipeline auto-fix attempt.
+    AutoFix,
+    /// The post-pipeline commit.
+    Commit,
+    /// `ccswarm task undo` itself, so an undo can be undone by hand.
+    Undo,
+}
+
+impl std::fmt::Display for JournalAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Stage => "stage",
+            Self::AutoFix => "auto-fix",
+            Self::Commit => "commit",
+            Self::Undo => "undo",
+        })
+    }
+}
+
+/// One line of `journal.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub seq: usize,
+    pub at: DateTime<Utc>,
+    pub action: JournalAction,
+    pub label: String,
+    /// Top level of the repository or worktree that was snapshotted.
+    pub work_dir: PathBuf,
+    /// `HEAD` at the time; `None` before the first commit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+    /// Snapshot commit.
+    pub snapshot: String,
+}
+
+/// Journal of one pipeline run.
+#[derive(Debug)]
+pub struct ActionJournal {
+    run_id: String,
+    path: PathBuf,
+    /// Parallel stages record concurrently; sequence numbers must not clash.
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl ActionJournal {
+    pub fn for_run(repo: &Path, run_id: &str) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            path: repo
+                .join(".ccswarm")
+                .join("runs")
+                .join(run_id)
+                .join("journal.jsonl"),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Snapshot `work_dir` and append an entry.
+    pub async fn record(
+        &self,
+        action: JournalAction,
+        label: &str,
+        work_dir: &Path,
+    ) -> Result<JournalEntry> {
+        let _guard = self.lock.lock().await;
+        let seq = self.entries().await?.len();
+        let top = toplevel(work_dir).await?;
+        let head = rev_parse(&top, "HEAD").await;
+        let message = format!(
+            "ccswarm journal {} #{}: {} {}",
+            self.run_id, seq, action, label
+        );
+        let snapshot = snapshot(&top, head.as_deref(), &message).await?;
+        git(
+            &top,
+            &[
+                "update-ref",
+                &format!("refs/ccswarm/journal/{}/{}", self.run_id, seq),
+                &snapshot,
+            ],
+        )
+        .await?;
+
+        let entry = JournalEntry {
+            seq,
+            at: Utc::now(),
+            action,
+            label: label.to_string(),
+            work_dir: top,
+            head,
+            snapshot,
+        };
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(entry)
+    }
+
+    /// Record, logging instead of failing: the journal must never stop a run.
+    pub async fn record_or_warn(&self, action: JournalAction, label: &str, work_dir: &Path) {
+        if let Err(e) = self.record(action, label, work_dir).await {
+            tracing::warn!(run_id = %self.run_id, "journal: no snapshot before {action} {label}: {e:#}");
+        }
+    }
+
+    /// Entries in the order they were recorded; empty if nothing was.
+    pub async fn entries(&self) -> Result<Vec<JournalEntry>> {
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()));
+            }
+        };
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).context("Invalid journal entry"))
+            .collect()
+    }
+}
+
+/// How a file differs between the undo target and now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    /// Created since the snapshot; undo deletes it.
+    Created,
+    /// Changed since the snapshot; undo restores it.
+    Modified,
+    /// Deleted since the snapshot; undo restores it.
+    Deleted,
+}
+
+/// What undoing will do to one working directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct UndoPlan {
+    pub work_dir: PathBuf,
+    /// The snapshot restored.
+    pub target: JournalEntry,
+    /// `HEAD` now.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_head: Option<String>,
+    /// Commits made since the snapshot (`<short sha> <subject>`, newest
+    /// first); undo drops them from the branch.
+    pub commits: Vec<String>,
+    pub files: Vec<(FileChange, String)>,
+}
+
+impl UndoPlan {
+    pub fn is_empty(&self) -> bool {
+        self.commits.is_empty() && self.files.is_empty()
+    }
+}
+
+/// Plan an undo of `entries` (one or more runs' journals, oldest first):
+/// every working directory they touched goes back to its earliest snapshot.
+pub async fn plan_undo(entries: &[JournalEntry]) -> Result<Vec<UndoPlan>> {
+    let mut plans: Vec<UndoPlan> = Vec::new();
+    for entry in entries {
+        if entry.action == JournalAction::Undo
+            || plans.iter().any(|plan| plan.work_dir == entry.work_dir)
+        {
+            continue;
+        }
+        let top = &entry.work_dir;
+        let current_head = rev_parse(top, "HEAD").await;
+        let current = snapshot(top, current_head.as_deref(), "ccswarm undo preview").await?;
+        let commits = match (&entry.head, &current_head) {
+            (Some(then), Some(now)) if then != now => {
+                let range = format!("{then}..{now}");
+                git(top, &["log", "--format=%h %s", &range])
+                    .await?
+                    .lines()
+                    .map(str::to_string)
+                    .collect()
+            }
+            (None, Some(_)) => git(top, &["log", "--format=%h %s", "HEAD"])
+                .await?
+                .lines()
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        };
+        let files = changed_files(top, &entry.snapshot, &current).await?;
+        plans.push(UndoPlan {
+            work_dir: top.clone(),
+            target: entry.clone(),
+            current_head,
+            commits,
+            files,
+        });
+    }
+    Ok(plans)
+}
+
+/// Carry out `plans`. The state being undone is recorded in `journal` first.
+pub async fn apply_undo(plans: &[UndoPlan], journal: &ActionJournal) -> Result<()> {
+    for plan in plans.iter().filter(|plan| !plan.is_empty()) {
+        let top = &plan.work_dir;
+        journal
+            .record(
+                JournalAction::Undo,
+                &format!("to #{}", plan.target.seq),
+                top,
+            )
+            .await
+            .context("Failed to save the current state before undoing")?;
+
+        match &plan.target.head {
+            Some(head) if plan.current_head.as_ref() != Some(head) => {
+                git(top, &["reset", "-q", "--soft", head]).await?;
+            }
+            None if plan.current_head.is_some() => {
+                // Back to an unborn branch: drop the ref the commits are on.
+                let branch = git(top, &["symbolic-ref", "-q", "HEAD"]).await?;
+                git(top, &["update-ref", "-d", branch.trim()]).await?;
+            }
+            _ => {}
+        }
+
+        let mut restore = Vec::new();
+        for (change, path) in &plan.files {
+            match change {
+                FileChange::Created => remove_created(top, Path::new(path)).await?,
+                FileChange::Modified | FileChange::Deleted => restore.push(path.as_str()),
+            }
+        }
+        for chunk in restore.chunks(100) {
+            let mut args = vec![
+                "--literal-pathspecs",
+                "checkout",
+                plan.target.snapshot.as_str(),
+                "--",
+            ];
+            args.extend(chunk);
+            git(top, &args).await?;
+        }
+        // `checkout <commit> -- paths` stages what it restores; undo leaves
+        // the index matching HEAD so the restored changes show as unstaged.
+        if plan.target.head.is_some() {
+            git(top, &["reset", "-q"]).await?;
+        } else {
+            git(top, &["read-tree", "--empty"]).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn remove_created(top: &Path, relative: &Path) -> Result<()> {
+    let path = top.join(relative);
+    match tokio::fs::remove_file(&path).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+    }
+    // Directories the agent created for the file go too, if now empty.
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == top || tokio::fs::remove_dir(current).await.is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+    Ok(())
+}
+
+async fn changed_files(top: &Path, from: &str, to: &str) -> Result<Vec<(FileChange, String)>> {
+    let raw = git(
+        top,
+        &[
+            "diff-tree",
+            "-r",
+            "-z",
+            "--no-renames",
+            "--name-status",
+            from,
+            to,
+            "--",
+            ".",
+            EXCLUDE,
+        ],
+    )
+    .await?;
+    let mut fields = raw.split('\0').filter(|field| !field.is_empty());
+    let mut files = Vec::new();
+    while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+        let change = match status {
+            "A" => FileChange::Created,
+            "D" => FileChange::Deleted,
+            _ => FileChange::Modified,
+        };
+        files.push((change, path.to_string()));
+    }
+    Ok(files)
+}
+
+/// Commit the full working tree of `top` without touching its index.
+async fn snapshot(top: &Path, head: Option<&str>, message: &str) -> Result<String> {
+    let git_path = |name: String| async move {
+        git(
+            top,
+            &["rev-parse", "--path-format=absolute", "--git-path", &name],
+        )
+        .await
+        .map(|path| PathBuf::from(path.trim()))
+    };
+    let index = git_path(format!("ccswarm-journal-{}.index", uuid::Uuid::new_v4())).await?;
+    // Start from the real index so unchanged files are not re-hashed.
+    let real_index = git_path("index".to_string()).await?;
+    let tree = async {
+        if tokio::fs::copy(&real_index, &index).await.is_err() && head.is_some() {
+            snapshot_git(top, Some(&index), &["read-tree", "HEAD"]).await?;
+        }
+        snapshot_git(top, Some(&index), &["add", "-A", "--", ".", EXCLUDE]).await?;
+        snapshot_git(top, Some(&index), &["write-tree"]).await
+    }
+    .await;
+    let _ = tokio::fs::remove_file(&index).await;
+    let tree = tree?;
+    let mut args = vec!["commit-tree", tree.trim(), "-m", message];
+    if let Some(head) = head {
+        args.extend(["-p", head]);
+    }
+    Ok(snapshot_git(top, None, &args).await?.trim().to_string())
+}
+
+/// Snapshot `dir` as it is now without journaling it: the top level of its
+/// repository or worktree, its `HEAD` and a commit holding every tracked and
+/// untracked (non-ignored) file.
+pub(crate) async fn snapshot_now(dir: &Path) -> Result<(PathBuf, Option<String>, String)> {
+    let top = toplevel(dir).await?;
+    let head = rev_parse(&top, "HEAD").await;
+    let snapshot = snapshot(&top, head.as_deref(), "ccswarm snapshot").await?;
+    Ok((top, head, snapshot))
+}
+
+async fn toplevel(dir: &Path) -> Result<PathBuf> {
+    let top = git(dir, &["rev-parse", "--show-toplevel"])
+        .await
+        .with_context(|| format!("{} is not in a git repository", dir.display()))?;
+    Ok(PathBuf::from(top.trim()))
+}
+
+async fn rev_parse(top: &Path, rev: &str) -> Option<String> {
+    git(top, &["rev-parse", "-q", "--verify", rev])
+        .await
+        .ok()
+        .map(|sha| sha.trim().to_string())
+}
+
+/// [`git`] for making snapshots, optionally on `index` instead of the
+/// repository's index.
+async fn snapshot_git(dir: &Path, index: Option<&Path>, args: &[&str]) -> Result<String> {
+    // Snapshots are made by ccswarm, not the user; don't require an identity.
+    let mut env = vec![
+        ("GIT_AUTHOR_NAME", OsStr::new("ccswarm")),
+        ("GIT_AUTHOR_EMAIL", OsStr::new("ccswarm@localhost")),
+        ("GIT_COMMITTER_NAME", OsStr::new("ccswarm")),
+        ("GIT_COMMITTER_EMAIL", OsStr::new("ccswarm@localhost")),
+    ];
+    if let Some(index) = index {
+        env.push(("GIT_INDEX_FILE", index.as_os_str()));
+    }
+    git_with_env(dir, &env, args).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sh(dir: &Path, script: &str) {
+        let status = tokio::process::Command::new("sh")
+            .args(["-c", script])
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "t")
+            .env("GIT_AUTHOR_EMAIL", "t@t")
+            .env("GIT_COMMITTER_NAME", "t")
+            .env("GIT_COMMITTER_EMAIL", "t@t")
+            .status()
+            .await
+            .unwrap();
+        assert!(status.success(), "{script}");
+    }
+
+    fn read(dir: &Path, path: &str) -> Option<String> {
+        std::fs::read_to_string(dir.join(path)).ok()
+    }
+
+    #[tokio::test]
+    async fn test_undo_reverts_writes_and_commits_across_stages() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        sh(
+            repo,
+            "git init -q && echo one > kept.txt && echo old > edit.txt && echo x > gone.txt \
+             && git add . && git commit -qm base && echo wip > user-untracked.txt \
+             && echo staged >> kept.txt && git add kept.txt",
+        )
+        .await;
+        let journal = ActionJournal::for_run(repo, "run-1");
+
+        journal
+            .record(JournalAction::Stage, "implement", repo)
+            .await
+            .unwrap();
+        sh(
+            repo,
+            "echo new > edit.txt && rm gone.txt && mkdir -p src/deep && echo n > src/deep/new.rs",
+        )
+        .await;
+        journal
+            .record(JournalAction::Commit, "commit", repo)
+            .await
+            .unwrap();
+        sh(
+            repo,
+            "git add -A && git commit -qm agent && echo keep > .ccswarm/state",
+        )
+        .await;
+
+        let entries = journal.entries().await.unwrap();
+        assert_eq!(entries.len(), 2);
+        let plans = plan_undo(&entries).await.unwrap();
+        assert_eq!(plans.len(), 1, "one working directory");
+        let plan = &plans[0];
+        assert_eq!(plan.target.seq, 0);
+        assert_eq!(plan.commits.len(), 1);
+        assert!(plan.commits[0].ends_with(" agent"));
+        let mut files = plan.files.clone();
+        files.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            files,
+            vec![
+                (FileChange::Modified, "edit.txt".to_string()),
+                (FileChange::Deleted, "gone.txt".to_string()),
+                (FileChange::Created, "src/deep/new.rs".to_string()),
+            ]
+        );
+
+        apply_undo(&plans, &journal).await.unwrap();
+
+        assert_eq!(read(repo, "edit.txt").as_deref(), Some("old\n"));
+        assert_eq!(read(repo, "gone.txt").as_deref(), Some("x\n"));
+        assert_eq!(read(repo, "kept.txt").as_deref(), Some("one\nstaged\n"));
+        assert_eq!(read(repo, "user-untracked.txt").as_deref(), Some("wip\n"));
+        assert_eq!(read(repo, ".ccswarm/state").as_deref(), Some("keep\n"));
+        assert!(
+            !repo.join("src").exists(),
+            "created directories are removed"
+        );
+        let log = git(repo, &["log", "--format=%s"]).await.unwrap();
+        assert_eq!(log.trim(), "base");
+
+        // The undone state is journaled, and undoing again is a no-op.
+        let entries = journal.entries().await.unwrap();
+        assert_eq!(entries.last().unwrap().action, JournalAction::Undo);
+        let again = plan_undo(&entries).await.unwrap();
+        assert!(again[0].is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_record_fails_outside_a_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = ActionJournal::for_run(dir.path(), "run-2");
+        assert!(
+            journal
+                .record(JournalAction::Stage, "plan", dir.path())
+                .await
+                .is_err()
+        );
+        assert!(journal.entries().await.unwrap().is_empty());
+    }
+}
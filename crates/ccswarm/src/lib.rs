@@ -9,18 +9,30 @@
 pub mod agent;
 pub mod cli;
 pub mod config;
+pub mod daemon;
+pub mod deploy;
 pub mod error;
 pub mod events;
 pub mod git;
 pub mod governance;
 pub mod hitl;
 pub mod hooks;
+pub mod i18n;
 pub mod identity;
+pub mod journal;
+pub mod notify;
 pub(crate) mod providers;
+pub mod recovery;
+pub mod redaction;
 pub mod resource;
 pub mod run_id;
+pub mod run_ledger;
+pub mod secrets;
+pub mod security;
 pub mod session;
+pub mod telemetry;
 pub mod tracker;
+pub mod tui;
 pub mod utils;
 pub mod workflow;
 
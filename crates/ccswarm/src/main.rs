@@ -1,14 +1,28 @@
 use clap::Parser;
+use std::path::Path;
+use tracing::Instrument;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use ccswarm::cli::{Cli, CliRunner};
-
-#[tokio::main]
-async fn main() {
-    let exit_code = run_main().await;
-    // Flush buffered OTel spans before the process exits — the batch exporter
-    // would otherwise silently drop them for short-lived invocations.
-    shutdown_otel();
+use ccswarm::run_id::{RUN_ID_ENV, RunId};
+
+fn main() {
+    // Child processes (provider CLIs, hooks, nested ccswarm) inherit the
+    // invocation's run ID.
+    // SAFETY: no other thread exists yet; the runtime is started below.
+    unsafe { std::env::set_var(RUN_ID_ENV, RunId::current().as_str()) };
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start the tokio runtime");
+    let exit_code = runtime.block_on(async {
+        let exit_code = run_main().await;
+        // Flush buffered OTel spans before the process exits — the batch
+        // exporter would otherwise silently drop them for short-lived
+        // invocations.
+        shutdown_otel();
+        exit_code
+    });
     if exit_code != 0 {
         std::process::exit(exit_code);
     }
@@ -25,7 +39,9 @@ async fn run_main() -> i32 {
     if args.len() == 1 {
         // Interactive mode: ccswarm (no args)
         init_logging(false, "text");
-        if let Err(e) = run_interactive().await {
+        let result = run_interactive().instrument(run_span()).await;
+        finish_run(Path::new("."), &result).await;
+        if let Err(e) = result {
             display_error(&e, false);
             return 1;
         }
@@ -36,7 +52,9 @@ async fn run_main() -> i32 {
         // Direct task mode: ccswarm "タスクを書くだけ"
         init_logging(false, "text");
         let task = args[1..].join(" ");
-        if let Err(e) = run_direct_task(&task).await {
+        let result = run_direct_task(&task).instrument(run_span()).await;
+        finish_run(Path::new("."), &result).await;
+        if let Err(e) = result {
             display_error(&e, false);
             return 1;
         }
@@ -47,13 +65,28 @@ async fn run_main() -> i32 {
 
     init_logging(cli.verbose, &cli.log_format);
 
-    if let Err(e) = run_cli(&cli).await {
+    let result = run_cli(&cli).instrument(run_span()).await;
+    finish_run(&cli.repo, &result).await;
+    if let Err(e) = result {
         display_error(&e, cli.verbose);
         return 1;
     }
     0
 }
 
+/// Root span carrying the run ID onto every log line.
+fn run_span() -> tracing::Span {
+    tracing::info_span!("ccswarm", run_id = %RunId::current())
+}
+
+/// Close the run's ledger, if it started any pipeline runs.
+async fn finish_run(repo: &Path, result: &anyhow::Result<()>) {
+    let error = result.as_ref().err().map(|e| format!("{e:#}"));
+    if let Err(e) = ccswarm::run_ledger::finish(repo, RunId::current(), error).await {
+        tracing::warn!("Failed to close the run ledger: {e}");
+    }
+}
+
 fn is_known_subcommand(arg: &str) -> bool {
     matches!(
         arg,
@@ -70,6 +103,7 @@ fn is_known_subcommand(arg: &str) -> bool {
             | "pipeline"
             | "health"
             | "quickstart"
+            | "demo"
             | "flow"
             | "repertoire"
             | "lab"
@@ -77,14 +111,30 @@ fn is_known_subcommand(arg: &str) -> bool {
             | "approve"
             | "session"
             | "run"
+            | "runs"
+            | "secrets"
             | "facets"
             | "queue"
             | "auto"
             | "undo"
             | "replay"
+            | "recover"
             | "cost"
+            | "delegate"
+            | "status"
             | "tail"
             | "scaffold"
+            | "context"
+            | "bundle"
+            | "human"
+            | "tui"
+            | "quality"
+            | "hooks"
+            | "tools"
+            | "completions"
+            | "man"
+            | "daemon"
+            | "stop"
     )
 }
 
@@ -0,0 +1,244 @@
+//! Notifications for events a person may want to hear about while ccswarm
+//! runs unattended: a task completed, an approval is waiting, a quality
+//! gate failed.
+//!
+//! Sinks implement [`Notifier`]; [`send`] builds the ones `notifications`
+//! in config enables and hands each the events it subscribed to. The
+//! built-in sink is [`DesktopNotifier`], a local toast through
+//! `notify-send` on Linux and `osascript` on macOS.
+//!
+//! ```yaml
+//! notifications:
+//!   desktop:
+//!     enabled: true
+//!     quality_gate_failed: false
+//! ```
+//!
+//! Delivery is best effort: a missing tool or a failing sink is logged and
+//! never fails the run that raised the event.
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long a sink may take to deliver one notification.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    TaskCompleted,
+    ApprovalPending,
+    QualityGateFailed,
+}
+
+/// One notification: a short title and a line or two of body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub event: NotifyEvent,
+    pub title: String,
+    pub body: String,
+}
+
+impl Notification {
+    pub fn new(event: NotifyEvent, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            event,
+            title: title.into(),
+            body: body.into(),
+        }
+    }
+}
+
+/// A place notifications go.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Sink name, for logs.
+    fn name(&self) -> &str;
+
+    /// Whether the sink subscribed to `event`.
+    fn wants(&self, event: NotifyEvent) -> bool;
+
+    async fn deliver(&self, notification: &Notification) -> Result<()>;
+}
+
+/// The `notifications` config knob.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub desktop: DesktopConfig,
+}
+
+/// Desktop toasts, off unless enabled; each event type can be turned off on
+/// its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesktopConfig {
+    pub enabled: bool,
+    pub task_completed: bool,
+    pub approval_pending: bool,
+    pub quality_gate_failed: bool,
+}
+
+impl Default for DesktopConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            task_completed: true,
+            approval_pending: true,
+            quality_gate_failed: true,
+        }
+    }
+}
+
+/// Local desktop notifications.
+pub struct DesktopNotifier {
+    config: DesktopConfig,
+}
+
+impl DesktopNotifier {
+    pub fn new(config: DesktopConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Notifier for DesktopNotifier {
+    fn name(&self) -> &str {
+        "desktop"
+    }
+
+    fn wants(&self, event: NotifyEvent) -> bool {
+        self.config.enabled
+            && match event {
+                NotifyEvent::TaskCompleted => self.config.task_completed,
+                NotifyEvent::ApprovalPending => self.config.approval_pending,
+                NotifyEvent::QualityGateFailed => self.config.quality_gate_failed,
+            }
+    }
+
+    async fn deliver(&self, notification: &Notification) -> Result<()> {
+        let Some((program, args)) = desktop_command(std::env::consts::OS, notification) else {
+            bail!(
+                "desktop notifications are not supported on {}",
+                std::env::consts::OS
+            );
+        };
+        let status = tokio::process::Command::new(program)
+            .args(&args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .status()
+            .await?;
+        if !status.success() {
+            bail!("{program} exited with {status}");
+        }
+        Ok(())
+    }
+}
+
+/// The command that shows `notification` on `os`.
+fn desktop_command(os: &str, notification: &Notification) -> Option<(&'static str, Vec<String>)> {
+    match os {
+        "linux" | "freebsd" | "openbsd" | "netbsd" => Some((
+            "notify-send",
+            // Task titles are free text; one starting with `-` must not be
+            // taken for an option.
+            vec![
+                "--app-name=ccswarm".to_string(),
+                "--".to_string(),
+                notification.title.clone(),
+                notification.body.clone(),
+            ],
+        )),
+        "macos" => {
+            let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+            Some((
+                "osascript",
+                vec![
+                    "-e".to_string(),
+                    format!(
+                        "display notification {} with title {}",
+                        quote(&notification.body),
+                        quote(&notification.title)
+                    ),
+                ],
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The sinks `config` enables.
+pub fn notifiers(config: &NotificationsConfig) -> Vec<Box<dyn Notifier>> {
+    let mut sinks: Vec<Box<dyn Notifier>> = Vec::new();
+    if config.desktop.enabled {
+        sinks.push(Box::new(DesktopNotifier::new(config.desktop.clone())));
+    }
+    sinks
+}
+
+/// Deliver `notification` to every enabled sink that wants it.
+pub async fn send(config: &NotificationsConfig, notification: Notification) {
+    for sink in notifiers(config) {
+        if !sink.wants(notification.event) {
+            continue;
+        }
+        match tokio::time::timeout(DELIVERY_TIMEOUT, sink.deliver(&notification)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("{} notification failed: {e:#}", sink.name()),
+            Err(_) => tracing::warn!("{} notification timed out", sink.name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_sink_honours_per_event_toggles_and_quotes_for_macos() {
+        let config: NotificationsConfig =
+            serde_json::from_str(r#"{"desktop": {"enabled": true, "quality_gate_failed": false}}"#)
+                .unwrap();
+        let sinks = notifiers(&config);
+        assert_eq!(sinks.len(), 1);
+        assert!(sinks[0].wants(NotifyEvent::TaskCompleted));
+        assert!(sinks[0].wants(NotifyEvent::ApprovalPending));
+        assert!(!sinks[0].wants(NotifyEvent::QualityGateFailed));
+        assert!(notifiers(&NotificationsConfig::default()).is_empty());
+
+        let notification = Notification::new(
+            NotifyEvent::ApprovalPending,
+            "Approval pending",
+            r#"commit "fix \ parser""#,
+        );
+        let (program, args) = desktop_command("linux", &notification).unwrap();
+        assert_eq!(program, "notify-send");
+        assert_eq!(
+            args[1..],
+            ["--", "Approval pending", r#"commit "fix \ parser""#]
+        );
+        let (program, args) = desktop_command("macos", &notification).unwrap();
+        assert_eq!(program, "osascript");
+        assert_eq!(
+            args[1],
+            r#"display notification "commit \"fix \\ parser\"" with title "Approval pending""#
+        );
+        assert!(desktop_command("windows", &notification).is_none());
+    }
+
+    #[test]
+    fn notify_send_takes_dash_led_text_as_positional() {
+        let notification =
+            Notification::new(NotifyEvent::TaskCompleted, "--urgency=critical", "-u low");
+        let (_, args) = desktop_command("linux", &notification).unwrap();
+        let end = args.iter().position(|a| a == "--").unwrap();
+        assert_eq!(args[end + 1..], ["--urgency=critical", "-u low"]);
+        assert!(args[..end].iter().all(|a| a.starts_with("--app-name")));
+    }
+}
@@ -0,0 +1,54 @@
+//! Aider provider (`aider` binary from Aider-AI/aider).
+//!
+//! `aider --message "<prompt>"` runs a single turn against the repository in
+//! the working directory and exits. `--yes-always` answers Aider's own
+//! prompts (adding files to the chat, creating files) and `--no-auto-commits`
+//! leaves committing to ccswarm, like every other provider. Read-only stages
+//! run with `--dry-run`, so Aider proposes edits without writing them.
+//!
+//! The system prompt is prepended to the user prompt; Aider has no MCP
+//! support, so typed tools are not offered, and no session continuation.
+
+use std::path::Path;
+
+use super::{AgentProvider, ProviderKind, ProviderOptions, may_edit};
+
+pub(crate) struct AiderProvider;
+
+impl AgentProvider for AiderProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Aider
+    }
+
+    fn build_command(
+        &self,
+        prompt: &str,
+        working_dir: &Path,
+        options: &ProviderOptions,
+    ) -> tokio::process::Command {
+        let mut cmd = tokio::process::Command::new("aider");
+        cmd.args([
+            "--yes-always",
+            "--no-auto-commits",
+            "--no-pretty",
+            "--no-stream",
+            "--no-check-update",
+        ]);
+        if !may_edit(options) {
+            cmd.arg("--dry-run");
+        }
+        if let Some(model) = &options.model {
+            cmd.args(["--model", model]);
+        }
+        cmd.args(&options.extra_args);
+
+        let merged_prompt = match &options.system_prompt {
+            Some(sys) if !sys.is_empty() => format!("{sys}\n\n---\n\n{prompt}"),
+            _ => prompt.to_string(),
+        };
+        cmd.args(["--message", &merged_prompt]);
+
+        cmd.current_dir(working_dir);
+        cmd
+    }
+}
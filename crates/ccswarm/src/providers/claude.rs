@@ -8,6 +8,9 @@ use super::{
 
 pub(crate) struct ClaudeProvider;
 
+/// Claude Code's own shell and file tools, disabled when typed tools are on.
+const CLAUDE_BUILTIN_FILE_TOOLS: &str = "Bash,Read,Edit,MultiEdit,Write,NotebookEdit,Grep,Glob,LS";
+
 impl AgentProvider for ClaudeProvider {
     fn kind(&self) -> ProviderKind {
         ProviderKind::Claude
@@ -28,6 +31,9 @@ impl AgentProvider for ClaudeProvider {
             // stream-json requires --verbose to actually emit per-event lines;
             // without it, Claude Code only prints the final result envelope.
             cmd.args(["-p", prompt, "--output-format", "stream-json", "--verbose"]);
+            if options.claude_partial_messages {
+                cmd.arg("--include-partial-messages");
+            }
         } else {
             cmd.args(["-p", prompt, "--output-format", "text"]);
         }
@@ -43,7 +49,13 @@ impl AgentProvider for ClaudeProvider {
             cmd.arg("--continue");
         }
 
-        if !options.allowed_tools.is_empty() {
+        if let Some(typed) = &options.typed_tools {
+            // Shell and file work goes through ccswarm's checked tools; the
+            // built-in equivalents are switched off so they cannot bypass them.
+            cmd.args(["--mcp-config", &typed.mcp_config()]);
+            cmd.args(["--allowed-tools", &typed.qualified_names().join(",")]);
+            cmd.args(["--disallowed-tools", CLAUDE_BUILTIN_FILE_TOOLS]);
+        } else if !options.allowed_tools.is_empty() {
             let tools_str = options
                 .allowed_tools
                 .iter()
@@ -19,6 +19,10 @@ pub(crate) struct StreamSummary {
     /// telemetry than the previous text-only output.
     pub tool_uses: Vec<ToolUse>,
 
+    /// Shell commands the model ran through the `Bash` tool, in order, with
+    /// whether their `tool_result` came back as an error.
+    pub commands: Vec<super::CommandRun>,
+
     /// Per-turn usage records, if the CLI reported them.
     pub usage: Vec<Usage>,
 
@@ -37,6 +41,8 @@ pub(crate) struct StreamSummary {
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // `input` is deliberately not propagated past the bridge — it can embed whole file contents. Only `name` reaches events.
 pub(crate) struct ToolUse {
+    /// `tool_use` block ID, which the matching `tool_result` refers to.
+    pub id: String,
     pub name: String,
     /// Raw input JSON; opaque to us but useful when logged.
     pub input: serde_json::Value,
@@ -57,6 +63,7 @@ pub(crate) struct Usage {
 /// the whole run — surfacing one malformed line shouldn't lose the answer.
 pub(crate) fn parse_stream(stdout: &str) -> StreamSummary {
     let mut summary = StreamSummary::default();
+    let mut failed_tool_uses = std::collections::HashSet::new();
 
     for raw in stdout.lines() {
         let line = raw.trim();
@@ -88,13 +95,60 @@ pub(crate) fn parse_stream(stdout: &str) -> StreamSummary {
             Some("result") => {
                 extract_result(&value, &mut summary);
             }
-            _ => {} // user echo, tool_result blocks, unknown — ignored
+            Some("user") => {
+                failed_tool_uses.extend(failed_tool_results(&value));
+            }
+            _ => {} // unknown — ignored
         }
     }
 
+    summary.commands = summary
+        .tool_uses
+        .iter()
+        .filter(|t| t.name == "Bash")
+        .filter_map(|t| {
+            Some(super::CommandRun {
+                command: t.input.get("command")?.as_str()?.to_string(),
+                failed: failed_tool_uses.contains(t.id.as_str()),
+            })
+        })
+        .collect();
     summary
 }
 
+/// IDs of the tool uses a `user` line reports as failed (`tool_result`
+/// blocks with `is_error`).
+fn failed_tool_results(value: &serde_json::Value) -> Vec<String> {
+    value
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|block| block.get("type").and_then(|v| v.as_str()) == Some("tool_result"))
+        .filter(|block| block.get("is_error").and_then(|v| v.as_bool()) == Some(true))
+        .filter_map(|block| block.get("tool_use_id")?.as_str().map(str::to_string))
+        .collect()
+}
+
+/// Reply text in one `stream_event` line, present when the CLI runs with
+/// `--include-partial-messages`: the `text_delta` of a content block.
+pub(crate) fn text_delta(line: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    if value.get("type")?.as_str()? != "stream_event" {
+        return None;
+    }
+    let event = value.get("event")?;
+    if event.get("type")?.as_str()? != "content_block_delta" {
+        return None;
+    }
+    let delta = event.get("delta")?;
+    if delta.get("type")?.as_str()? != "text_delta" {
+        return None;
+    }
+    Some(delta.get("text")?.as_str()?.to_string())
+}
+
 fn extract_assistant(value: &serde_json::Value, summary: &mut StreamSummary) {
     // Shape: { type: "assistant", message: { content: [ {type, text|name|input, ...} ], usage: {...} } }
     let Some(message) = value.get("message") else {
@@ -128,11 +182,16 @@ fn extract_assistant(value: &serde_json::Value, summary: &mut StreamSummary) {
                     if name.is_empty() {
                         continue;
                     }
+                    let id = block
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string();
                     let input = block
                         .get("input")
                         .cloned()
                         .unwrap_or(serde_json::Value::Null);
-                    summary.tool_uses.push(ToolUse { name, input });
+                    summary.tool_uses.push(ToolUse { id, name, input });
                 }
                 _ => {}
             }
@@ -223,6 +282,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pairs_bash_commands_with_their_failures() {
+        let stdout = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","is_error":true,"content":"error[E0425]"}]}}
+{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t2","name":"Bash","input":{"command":"ls"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t2","content":"src"}]}}"#;
+        let s = parse_stream(stdout);
+        assert_eq!(
+            s.commands,
+            [
+                super::super::CommandRun {
+                    command: "cargo test".into(),
+                    failed: true
+                },
+                super::super::CommandRun {
+                    command: "ls".into(),
+                    failed: false
+                },
+            ]
+        );
+    }
+
     #[test]
     fn skips_malformed_lines_without_aborting() {
         let stdout = "{\"type\":\"system\",\"session_id\":\"ok\"}\nthis is not json\n{\"type\":\"result\",\"result\":\"done\"}\n";
@@ -231,6 +312,20 @@ mod tests {
         assert_eq!(s.session_id.as_deref(), Some("ok"));
     }
 
+    #[test]
+    fn text_deltas_come_from_partial_message_events_only() {
+        let delta = r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hel"}}}"#;
+        assert_eq!(text_delta(delta).as_deref(), Some("Hel"));
+        let tool_input = r#"{"type":"stream_event","event":{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{"}}}"#;
+        assert_eq!(text_delta(tool_input), None);
+        let whole =
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Hello"}]}}"#;
+        assert_eq!(text_delta(whole), None);
+        // Partial-message events don't disturb the final projection.
+        let s = parse_stream(&format!("{delta}\n{whole}"));
+        assert_eq!(s.result_text, "Hello");
+    }
+
     #[test]
     fn handles_empty_stdout() {
         let s = parse_stream("");
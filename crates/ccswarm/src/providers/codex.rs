@@ -51,6 +51,26 @@ impl AgentProvider for CodexProvider {
             cmd.args(["--model", model]);
         }
 
+        // Typed tools are registered as an MCP server through config
+        // overrides (TOML values; JSON strings and arrays are valid TOML).
+        // Codex has no flag to switch off its own shell tool, so for Codex
+        // the typed tools are offered alongside it.
+        if let Some(typed) = &options.typed_tools {
+            let server = crate::session::tool_server::SERVER_NAME;
+            cmd.args([
+                "-c".to_string(),
+                format!(
+                    "mcp_servers.{server}.command={}",
+                    serde_json::Value::from(typed.command.as_str())
+                ),
+                "-c".to_string(),
+                format!(
+                    "mcp_servers.{server}.args={}",
+                    serde_json::Value::from(typed.args.clone())
+                ),
+            ]);
+        }
+
         // JSONL event output: needed for telemetry (real token counts) and
         // mandatory for multi-turn, where the thread ID arrives via the
         // `thread.started` event.
@@ -61,6 +81,8 @@ impl AgentProvider for CodexProvider {
         // Codex has no allow-list, worktree, or budget flag. Unsupported options are
         // silently ignored so the same flow YAML stays portable across providers.
 
+        cmd.args(&options.extra_args);
+
         // Codex expects the prompt as the final positional argument (after flags).
         let merged_prompt = match &options.system_prompt {
             Some(sys) if !sys.is_empty() => format!("{sys}\n\n---\n\n{prompt}"),
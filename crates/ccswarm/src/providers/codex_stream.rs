@@ -27,6 +27,9 @@ pub(crate) struct CodexStreamSummary {
     /// `command_execution`), the closest analogue to Claude's tool names.
     pub tool_names: Vec<String>,
 
+    /// `command_execution` items, with whether they exited non-zero.
+    pub commands: Vec<super::CommandRun>,
+
     /// Real token totals `(input, output)` from the last `turn.completed`
     /// usage record. Codex's `input_tokens` already includes cached tokens
     /// (`cached_input_tokens` is a breakdown subset, not an addend).
@@ -36,6 +39,20 @@ pub(crate) struct CodexStreamSummary {
     pub failed: Option<String>,
 }
 
+/// Agent message text in one JSONL line. Codex has no token deltas, so a
+/// reply streams a message at a time.
+pub(crate) fn message_text(line: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    if value.get("type")?.as_str()? != "item.completed" {
+        return None;
+    }
+    let item = value.get("item")?;
+    if item.get("type")?.as_str()? != "agent_message" {
+        return None;
+    }
+    Some(format!("{}\n", item.get("text")?.as_str()?))
+}
+
 /// Parse a full `codex exec --json` stdout buffer (JSONL). Lines that fail to
 /// parse are skipped with a debug log rather than aborting the run.
 pub(crate) fn parse_stream(stdout: &str) -> CodexStreamSummary {
@@ -80,6 +97,21 @@ pub(crate) fn parse_stream(stdout: &str) -> CodexStreamSummary {
                     }
                     // Reasoning is internal narration, not a tool action.
                     Some("reasoning") | None => {}
+                    Some("command_execution") => {
+                        summary.tool_names.push("command_execution".to_string());
+                        if let Some(command) = item.get("command").and_then(|v| v.as_str()) {
+                            let failed = item.get("status").and_then(|v| v.as_str())
+                                == Some("failed")
+                                || item
+                                    .get("exit_code")
+                                    .and_then(|v| v.as_i64())
+                                    .is_some_and(|code| code != 0);
+                            summary.commands.push(super::CommandRun {
+                                command: command.to_string(),
+                                failed,
+                            });
+                        }
+                    }
                     Some(other) => summary.tool_names.push(other.to_string()),
                 }
             }
@@ -129,6 +161,9 @@ mod tests {
         assert_eq!(s.thread_id.as_deref(), Some("abc-123"));
         assert_eq!(s.tokens, Some((13648, 17)));
         assert!(s.failed.is_none());
+
+        let streamed: Vec<String> = stdout.lines().filter_map(message_text).collect();
+        assert_eq!(streamed, ["ok\n"]);
     }
 
     #[test]
@@ -0,0 +1,47 @@
+//! Gemini CLI provider (`gemini` binary from google-gemini/gemini-cli).
+//!
+//! Runs headless with `gemini --prompt "<prompt>"`, which prints the reply as
+//! plain text and exits. The system prompt is prepended to the user prompt,
+//! as for Codex. Stages that may edit run with `--yolo` so tool calls are
+//! approved automatically; read-only stages leave approvals on, which in
+//! headless mode declines any tool that would change the workspace.
+//!
+//! Gemini only takes MCP servers from its settings file, so typed tools are
+//! not offered; nor is there session continuation from the command line.
+
+use std::path::Path;
+
+use super::{AgentProvider, ProviderKind, ProviderOptions, may_edit};
+
+pub(crate) struct GeminiProvider;
+
+impl AgentProvider for GeminiProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Gemini
+    }
+
+    fn build_command(
+        &self,
+        prompt: &str,
+        working_dir: &Path,
+        options: &ProviderOptions,
+    ) -> tokio::process::Command {
+        let mut cmd = tokio::process::Command::new("gemini");
+        if may_edit(options) {
+            cmd.arg("--yolo");
+        }
+        if let Some(model) = &options.model {
+            cmd.args(["--model", model]);
+        }
+        cmd.args(&options.extra_args);
+
+        let merged_prompt = match &options.system_prompt {
+            Some(sys) if !sys.is_empty() => format!("{sys}\n\n---\n\n{prompt}"),
+            _ => prompt.to_string(),
+        };
+        cmd.args(["--prompt", &merged_prompt]);
+
+        cmd.current_dir(working_dir);
+        cmd
+    }
+}
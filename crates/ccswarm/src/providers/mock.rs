@@ -0,0 +1,185 @@
+//! Mock provider (`--provider mock`): answers every call from a scenario of
+//! canned replies, so the full init → start → task → review loop runs with
+//! no provider CLI and no API key.
+//!
+//! Unlike transcript replay (see [`super::transcript`]), the mock still
+//! spawns a subprocess: `build_command` turns the chosen reply into a `sh`
+//! script that writes the reply's files into the working directory and
+//! prints its output. Everything downstream — worktrees, gates, diffs,
+//! commits — sees a real agent run.
+//!
+//! The scenario is read from `CCSWARM_MOCK_SCENARIO`, else from
+//! `.ccswarm/mock.yaml` in the working directory, else the built-in one,
+//! which plans, approves in Sangha, writes `MOCK_AGENT.md` and approves the
+//! review:
+//!
+//! ```yaml
+//! replies:
+//!   - match: "Review the implementation"
+//!     stdout: "Looks correct. APPROVED"
+//!   - stdout: "Added the /health route."
+//!     files:
+//!       src/health.rs: "pub fn health() -> &'static str { \"ok\" }\n"
+//!   - stdout: "Plan: add the route, then test it."
+//! ```
+//!
+//! A call gets the first reply whose `match` (case-insensitive) appears in
+//! the prompt. Replies with `files` are skipped for stages that may not edit
+//! (tools but neither `write` nor `edit`), as a real agent would be. The
+//! same prompt always gets the same reply.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use super::{AgentProvider, ProviderKind, ProviderOptions, may_edit};
+use crate::utils::command::shell_quote;
+
+pub(crate) const SCENARIO_ENV: &str = "CCSWARM_MOCK_SCENARIO";
+/// Scenario file looked up in the working directory.
+pub(crate) const SCENARIO_FILE: &str = ".ccswarm/mock.yaml";
+
+const DEFAULT_SCENARIO: &str = r##"
+replies:
+  - match: "Review the implementation"
+    stdout: "Reviewed the change: it is correct and complete. APPROVED"
+  - stdout: "Implemented the task and recorded it in MOCK_AGENT.md. Done."
+    files:
+      MOCK_AGENT.md: "# Mock agent\n\nWritten by the ccswarm mock provider.\n"
+  - match: "SANGHA_DECISION="
+    stdout: "The plan is scoped and testable.\nSANGHA_DECISION=APPROVE"
+  - stdout: "Plan: make the smallest change that completes the task, then verify it. Done."
+"##;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MockScenario {
+    pub replies: Vec<MockReply>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MockReply {
+    /// Substring of the prompt this reply answers; any prompt when absent.
+    #[serde(default, rename = "match")]
+    pub matches: Option<String>,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    #[serde(default = "default_success")]
+    pub success: bool,
+    /// Files written relative to the working directory, path to content.
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+impl MockScenario {
+    /// The scenario for a call in `working_dir`.
+    pub(crate) fn load(working_dir: &Path) -> Result<Self> {
+        let (source, text) = match std::env::var_os(SCENARIO_ENV).filter(|v| !v.is_empty()) {
+            Some(path) => {
+                let path = Path::new(&path);
+                (
+                    path.display().to_string(),
+                    std::fs::read_to_string(path).with_context(|| {
+                        format!("Failed to read mock scenario {}", path.display())
+                    })?,
+                )
+            }
+            None => match std::fs::read_to_string(working_dir.join(SCENARIO_FILE)) {
+                Ok(text) => (SCENARIO_FILE.to_string(), text),
+                Err(_) => ("built-in".to_string(), DEFAULT_SCENARIO.to_string()),
+            },
+        };
+        Self::parse(&text).with_context(|| format!("invalid mock scenario ({source})"))
+    }
+
+    pub(crate) fn parse(text: &str) -> Result<Self> {
+        let scenario: Self = serde_yml::from_str(text)?;
+        for reply in &scenario.replies {
+            for path in reply.files.keys() {
+                let relative = Path::new(path)
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+                if path.is_empty() || !relative {
+                    bail!("file '{path}' must be a relative path inside the working directory");
+                }
+            }
+        }
+        Ok(scenario)
+    }
+
+    /// The reply for `prompt`, given whether the stage may edit files.
+    pub(crate) fn reply(&self, prompt: &str, can_edit: bool) -> Option<&MockReply> {
+        let prompt = prompt.to_lowercase();
+        self.replies.iter().find(|reply| {
+            (can_edit || reply.files.is_empty())
+                && reply
+                    .matches
+                    .as_ref()
+                    .is_none_or(|m| prompt.contains(&m.to_lowercase()))
+        })
+    }
+}
+
+/// `sh` script playing `reply`; texts travel in environment variables so
+/// they need no quoting.
+fn script(reply: &MockReply, cmd: &mut tokio::process::Command) -> String {
+    let mut script = String::new();
+    for (i, (path, content)) in reply.files.iter().enumerate() {
+        let var = format!("CCSWARM_MOCK_FILE_{i}");
+        script.push_str(&format!(
+            "mkdir -p \"$(dirname {path})\" && printf '%s' \"${var}\" > {path} || exit 1\n",
+            path = shell_quote(path)
+        ));
+        cmd.env(var, content);
+    }
+    cmd.env("CCSWARM_MOCK_STDOUT", &reply.stdout);
+    cmd.env("CCSWARM_MOCK_STDERR", &reply.stderr);
+    script.push_str("printf '%s\\n' \"$CCSWARM_MOCK_STDOUT\"\n");
+    if !reply.stderr.is_empty() {
+        script.push_str("printf '%s\\n' \"$CCSWARM_MOCK_STDERR\" >&2\n");
+    }
+    script.push_str(if reply.success {
+        "exit 0\n"
+    } else {
+        "exit 1\n"
+    });
+    script
+}
+
+pub(crate) struct MockProvider;
+
+impl AgentProvider for MockProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Mock
+    }
+
+    fn build_command(
+        &self,
+        prompt: &str,
+        working_dir: &Path,
+        options: &ProviderOptions,
+    ) -> tokio::process::Command {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.current_dir(working_dir);
+        let body = match MockScenario::load(working_dir) {
+            Ok(scenario) => match scenario.reply(prompt, may_edit(options)) {
+                Some(reply) => script(reply, &mut cmd),
+                None => "echo 'ccswarm: no mock reply matches this prompt' >&2; exit 2".into(),
+            },
+            Err(e) => {
+                cmd.env("CCSWARM_MOCK_STDERR", format!("ccswarm: {e:#}"));
+                "printf '%s\\n' \"$CCSWARM_MOCK_STDERR\" >&2; exit 2".into()
+            }
+        };
+        cmd.arg("-c").arg(body);
+        cmd
+    }
+}
@@ -1,4 +1,5 @@
-//! Agent provider abstraction — build subprocess commands for Claude / Codex / Copilot CLIs.
+//! Agent provider abstraction — build subprocess commands for Claude / Codex / Gemini /
+//! Aider / Copilot CLIs (and the scenario-driven mock).
 //!
 //! The `AISessionBridge` owns context/persistence/parsing logic; providers only know how to
 //! construct an executable command from a prompt and [`ProviderOptions`]. This keeps the
@@ -34,6 +35,9 @@ pub(crate) struct ProviderOptions {
     /// text format. Toggled on by `AISessionBridge` when the environment sets
     /// `CCSWARM_CLAUDE_STREAM_JSON=1`.
     pub claude_stream_json: bool,
+    /// Add `--include-partial-messages` to stream-json output, so reply text
+    /// arrives as it is generated (see [`crate::session::stream`]). Claude only.
+    pub claude_partial_messages: bool,
     /// Request Codex's JSONL event output (`codex exec --json`). The bridge
     /// parses each line to extract the final agent message, real token usage,
     /// and the thread ID needed for `codex exec resume`. No effect on Claude /
@@ -41,6 +45,113 @@ pub(crate) struct ProviderOptions {
     /// `CCSWARM_CODEX_JSON=1` — and forced on during codex multi-turn runs,
     /// which need the thread ID to continue.
     pub codex_json: bool,
+    /// ccswarm's typed tool server (`ccswarm tools serve`), offered over MCP
+    /// in place of the provider's own shell and file tools.
+    pub typed_tools: Option<TypedTools>,
+    /// Extra CLI arguments from the agent's provider section (e.g.
+    /// `gemini_config.args`), placed before the prompt. Codex / Gemini / Aider.
+    pub extra_args: Vec<String>,
+}
+
+/// Whether a stage offering `allowed_tools` may change files; no tool list
+/// means no limit.
+pub(crate) fn may_edit(options: &ProviderOptions) -> bool {
+    options.allowed_tools.is_empty()
+        || options
+            .allowed_tools
+            .iter()
+            .any(|t| matches!(t.to_ascii_lowercase().as_str(), "write" | "edit"))
+}
+
+/// A shell command the agent ran during a call, as its structured output
+/// reports it. Feeds the loop supervisor ([`crate::workflow::supervisor`]).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CommandRun {
+    pub command: String,
+    /// The command exited non-zero (or the provider reported it as failed).
+    pub failed: bool,
+}
+
+/// How to start the typed tool server for one session, and which tools it
+/// will expose.
+#[derive(Debug, Clone)]
+pub(crate) struct TypedTools {
+    pub command: String,
+    pub args: Vec<String>,
+    pub tools: Vec<crate::session::tools::SessionTool>,
+}
+
+impl TypedTools {
+    /// Server for `working_dir` at the stage's permission level and tools.
+    /// With an `agent`, it also serves that agent's `notes` scratchpad.
+    pub(crate) fn for_session(
+        working_dir: &Path,
+        permission: &crate::workflow::flow::MovementPermission,
+        allowed: &[String],
+        agent: Option<&str>,
+    ) -> Self {
+        use crate::workflow::flow::MovementPermission;
+
+        let command = std::env::current_exe()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|_| "ccswarm".to_string());
+        let permission_name = match permission {
+            MovementPermission::Readonly => "readonly",
+            MovementPermission::Edit => "edit",
+            MovementPermission::Full => "full",
+        };
+        let mut args = vec![
+            "tools".to_string(),
+            "serve".to_string(),
+            "--dir".to_string(),
+            working_dir.display().to_string(),
+            "--permission".to_string(),
+            permission_name.to_string(),
+        ];
+        if !allowed.is_empty() {
+            args.extend(["--allow".to_string(), allowed.join(",")]);
+        }
+        if let Some(agent) = agent {
+            args.extend(["--agent".to_string(), agent.to_string()]);
+        }
+        let enforcer =
+            crate::workflow::PermissionEnforcer::from_movement(permission.clone(), allowed);
+        Self {
+            command,
+            args,
+            tools: crate::session::tools::allowed_tools(&enforcer)
+                .into_iter()
+                .filter(|t| *t != crate::session::tools::SessionTool::Notes || agent.is_some())
+                .collect(),
+        }
+    }
+
+    /// Tool names as the provider sees them (`mcp__ccswarm__read_file`).
+    pub(crate) fn qualified_names(&self) -> Vec<String> {
+        self.tools
+            .iter()
+            .map(|t| {
+                format!(
+                    "mcp__{}__{}",
+                    crate::session::tool_server::SERVER_NAME,
+                    t.name()
+                )
+            })
+            .collect()
+    }
+
+    /// `mcpServers` JSON as taken by `claude --mcp-config`.
+    pub(crate) fn mcp_config(&self) -> String {
+        serde_json::json!({
+            "mcpServers": {
+                crate::session::tool_server::SERVER_NAME: {
+                    "command": self.command,
+                    "args": self.args,
+                }
+            }
+        })
+        .to_string()
+    }
 }
 
 /// Provider support for continuing a stage in the same conversation thread.
@@ -67,7 +178,11 @@ impl SameThreadContinuation {
 pub(crate) enum ProviderKind {
     Claude,
     Codex,
+    Gemini,
+    Aider,
     Copilot,
+    /// Canned replies from a scenario file (see [`mock`]).
+    Mock,
 }
 
 impl ProviderKind {
@@ -75,7 +190,10 @@ impl ProviderKind {
         match s.to_ascii_lowercase().as_str() {
             "claude" | "claude-code" => Some(Self::Claude),
             "codex" => Some(Self::Codex),
+            "gemini" | "gemini-cli" => Some(Self::Gemini),
+            "aider" => Some(Self::Aider),
             "copilot" | "gh-copilot" | "github-copilot" => Some(Self::Copilot),
+            "mock" => Some(Self::Mock),
             _ => None,
         }
     }
@@ -84,7 +202,10 @@ impl ProviderKind {
         match self {
             Self::Claude => "claude",
             Self::Codex => "codex",
+            Self::Gemini => "gemini",
+            Self::Aider => "aider",
             Self::Copilot => "copilot",
+            Self::Mock => "mock",
         }
     }
 }
@@ -106,11 +227,17 @@ pub(crate) trait AgentProvider {
     ) -> tokio::process::Command;
 }
 
+pub mod aider;
 pub mod claude;
 pub(crate) mod claude_stream;
 pub mod codex;
 pub(crate) mod codex_stream;
 pub mod copilot;
+pub mod gemini;
+pub(crate) mod mock;
+pub mod routing;
+pub mod scheduler;
+pub(crate) mod transcript;
 
 #[cfg(test)]
 mod tests;
@@ -120,7 +247,25 @@ pub(crate) fn resolve(kind: ProviderKind) -> Box<dyn AgentProvider + Send + Sync
     match kind {
         ProviderKind::Claude => Box::new(claude::ClaudeProvider),
         ProviderKind::Codex => Box::new(codex::CodexProvider),
+        ProviderKind::Gemini => Box::new(gemini::GeminiProvider),
+        ProviderKind::Aider => Box::new(aider::AiderProvider),
         ProviderKind::Copilot => Box::new(copilot::CopilotProvider),
+        ProviderKind::Mock => Box::new(mock::MockProvider),
+    }
+}
+
+/// Reply text in one line of `kind`'s stdout, for streaming to viewers.
+/// Structured output yields just the reply (token by token for Claude with
+/// partial messages); otherwise stdout is the reply itself.
+pub(crate) fn reply_text(
+    kind: ProviderKind,
+    options: &ProviderOptions,
+    line: &str,
+) -> Option<String> {
+    match kind {
+        ProviderKind::Claude if options.claude_stream_json => claude_stream::text_delta(line),
+        ProviderKind::Codex if options.codex_json => codex_stream::message_text(line),
+        _ => Some(format!("{line}\n")),
     }
 }
 
@@ -0,0 +1,380 @@
+//! Model routing by task complexity.
+//!
+//! Renaming a field or writing a doc comment doesn't need the flagship
+//! model; redesigning a protocol across a dozen files does. With routing on,
+//! a stage that no flow, promotion rule or `--model` pins to a model gets
+//! one picked from how complex its task looks: the task description's
+//! wording and length, and how much code the paths it mentions cover.
+//!
+//! ```json
+//! "model_routing": {
+//!   "enabled": true,
+//!   "models": { "claude": { "low": "haiku", "high": "opus" } },
+//!   "agents": { "architect": "opus" }
+//! }
+//! ```
+//!
+//! A tier with no model (here `medium`) leaves the provider's default.
+//! `agents` pins a model per agent whatever the task, and a queued task's
+//! own `model` overrides both.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::ProviderKind;
+
+/// Wording of tasks that rarely need much reasoning.
+const LOW_SIGNALS: &[&str] = &[
+    "doc comment",
+    "docstring",
+    "typo",
+    "rename",
+    "readme",
+    "changelog",
+    "spelling",
+    "wording",
+    "bump",
+    "formatting",
+    "reformat",
+    "comment",
+];
+
+/// Wording of tasks that usually do.
+const HIGH_SIGNALS: &[&str] = &[
+    "architecture",
+    "redesign",
+    "refactor",
+    "migrat",
+    "concurren",
+    "race condition",
+    "deadlock",
+    "security",
+    "performance",
+    "protocol",
+    "distributed",
+    "algorithm",
+    "schema",
+    "across",
+];
+
+/// Files under one mentioned directory counted at most.
+const SCOPE_WALK_LIMIT: usize = 500;
+
+/// How complex a task looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Complexity {
+    Low,
+    Medium,
+    High,
+}
+
+impl std::fmt::Display for Complexity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        })
+    }
+}
+
+/// Models per complexity tier for one provider.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TierModels {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub medium: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high: Option<String>,
+}
+
+impl TierModels {
+    fn get(&self, complexity: Complexity) -> Option<&str> {
+        match complexity {
+            Complexity::Low => self.low.as_deref(),
+            Complexity::Medium => self.medium.as_deref(),
+            Complexity::High => self.high.as_deref(),
+        }
+        .filter(|model| !model.is_empty())
+    }
+}
+
+/// `model_routing` in `ccswarm.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelRoutingConfig {
+    pub enabled: bool,
+    /// Tier models by provider name.
+    pub models: BTreeMap<String, TierModels>,
+    /// Model per agent, used whatever the task's complexity.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub agents: BTreeMap<String, String>,
+}
+
+impl Default for ModelRoutingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            models: BTreeMap::from([(
+                ProviderKind::Claude.as_str().to_string(),
+                TierModels {
+                    low: Some("haiku".to_string()),
+                    medium: None,
+                    high: Some("opus".to_string()),
+                },
+            )]),
+            agents: BTreeMap::new(),
+        }
+    }
+}
+
+impl ModelRoutingConfig {
+    /// Provider names must be known.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for name in self.models.keys() {
+            if ProviderKind::parse(name).is_none() {
+                anyhow::bail!(
+                    "model_routing.models has unknown provider '{}' (expected: claude | codex | gemini | aider | copilot | mock)",
+                    name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The model `agent` should run `task` on with `provider`, and why;
+    /// `None` leaves the provider's default.
+    pub(crate) fn route(
+        &self,
+        provider: ProviderKind,
+        agent: &str,
+        task: &str,
+        work_dir: &Path,
+    ) -> Option<Route> {
+        if !self.enabled {
+            return None;
+        }
+        if let Some(model) = self.agents.get(agent) {
+            return Some(Route {
+                model: model.clone(),
+                reason: format!("pinned for agent '{agent}'"),
+            });
+        }
+        let models = self
+            .models
+            .iter()
+            .find(|(name, _)| ProviderKind::parse(name) == Some(provider))
+            .map(|(_, models)| models)?;
+        let estimate = estimate(task, scope(task, work_dir));
+        let model = models.get(estimate.complexity)?;
+        Some(Route {
+            model: model.to_string(),
+            reason: format!(
+                "{} complexity ({})",
+                estimate.complexity,
+                estimate.reasons.join(", ")
+            ),
+        })
+    }
+}
+
+/// A routing decision.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub model: String,
+    pub reason: String,
+}
+
+/// What the estimator made of a task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    pub complexity: Complexity,
+    pub score: i32,
+    pub reasons: Vec<String>,
+}
+
+/// Estimate a task's complexity from its description and the number of
+/// files in its scope.
+pub fn estimate(task: &str, scope_files: usize) -> Estimate {
+    let text = task.to_lowercase();
+    let mut score = 0;
+    let mut reasons = Vec::new();
+
+    let low: Vec<&str> = LOW_SIGNALS
+        .iter()
+        .copied()
+        .filter(|signal| text.contains(signal))
+        .collect();
+    if !low.is_empty() {
+        score -= 2;
+        reasons.push(format!("mentions {}", low.join("/")));
+    }
+    let high: Vec<&str> = HIGH_SIGNALS
+        .iter()
+        .copied()
+        .filter(|signal| text.contains(signal))
+        .collect();
+    if !high.is_empty() {
+        score += 2 * high.len().min(2) as i32;
+        reasons.push(format!("mentions {}", high.join("/")));
+    }
+
+    let words = text.split_whitespace().count();
+    let length = match words {
+        0..=40 => 0,
+        41..=120 => 1,
+        _ => 2,
+    };
+    if length > 0 {
+        score += length;
+        reasons.push(format!("{words} words"));
+    }
+
+    let breadth = match scope_files {
+        0..=1 => 0,
+        2..=5 => 1,
+        6..=20 => 2,
+        _ => 3,
+    };
+    if breadth > 0 {
+        score += breadth;
+        reasons.push(format!("{scope_files} files in scope"));
+    }
+
+    let complexity = match score {
+        ..=-1 => Complexity::Low,
+        0..=2 => Complexity::Medium,
+        _ => Complexity::High,
+    };
+    if reasons.is_empty() {
+        reasons.push("no strong signals".to_string());
+    }
+    Estimate {
+        complexity,
+        score,
+        reasons,
+    }
+}
+
+/// Files covered by the paths `task` mentions, resolved against
+/// `work_dir`: one per file, every file below a directory. A path that
+/// doesn't exist yet but names a file still counts as one.
+pub fn scope(task: &str, work_dir: &Path) -> usize {
+    let mut seen = std::collections::BTreeSet::new();
+    let mut files = 0;
+    for token in task.split_whitespace() {
+        let token = token
+            .trim_matches(|c: char| !(c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-')));
+        let token = token.trim_end_matches('.');
+        let looks_like_path = token.contains('/') || has_source_extension(token);
+        if !looks_like_path || token.starts_with("http") || !seen.insert(token.to_string()) {
+            continue;
+        }
+        let path = work_dir.join(token);
+        files += if path.is_dir() {
+            walkdir::WalkDir::new(&path)
+                .into_iter()
+                .filter_entry(|entry| {
+                    let name = entry.file_name().to_string_lossy();
+                    entry.depth() == 0
+                        || !(name.starts_with('.') || name == "target" || name == "node_modules")
+                })
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+                .take(SCOPE_WALK_LIMIT)
+                .count()
+        } else {
+            usize::from(path.is_file() || has_source_extension(token))
+        };
+    }
+    files
+}
+
+fn has_source_extension(token: &str) -> bool {
+    Path::new(token)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            matches!(
+                ext,
+                "rs" | "ts"
+                    | "tsx"
+                    | "js"
+                    | "jsx"
+                    | "py"
+                    | "go"
+                    | "java"
+                    | "kt"
+                    | "rb"
+                    | "c"
+                    | "h"
+                    | "cpp"
+                    | "swift"
+                    | "md"
+                    | "toml"
+                    | "yaml"
+                    | "yml"
+                    | "json"
+                    | "sql"
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_chores_route_to_the_cheap_model_and_broad_work_to_the_flagship() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src/net");
+        std::fs::create_dir_all(&src).unwrap();
+        for i in 0..8 {
+            std::fs::write(src.join(format!("m{i}.rs")), "").unwrap();
+        }
+        let config = ModelRoutingConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let route = |agent: &str, task: &str| {
+            config
+                .route(ProviderKind::Claude, agent, task, dir.path())
+                .map(|route| route.model)
+        };
+
+        assert_eq!(
+            route("backend", "Fix the typo in the doc comment of src/lib.rs").as_deref(),
+            Some("haiku")
+        );
+        let redesign =
+            "Redesign the retry protocol in src/net so reconnects are safe under concurrency";
+        assert_eq!(scope(redesign, dir.path()), 8);
+        assert_eq!(estimate(redesign, 8).complexity, Complexity::High);
+        assert_eq!(route("backend", redesign).as_deref(), Some("opus"));
+        // Medium has no model configured: the provider default stays.
+        assert_eq!(route("backend", "Add a --verbose flag to the CLI"), None);
+        assert_eq!(
+            config.route(ProviderKind::Codex, "backend", redesign, dir.path()),
+            None
+        );
+
+        let pinned = ModelRoutingConfig {
+            agents: BTreeMap::from([("architect".to_string(), "opus".to_string())]),
+            ..config.clone()
+        };
+        let route = pinned
+            .route(ProviderKind::Claude, "architect", "Fix a typo", dir.path())
+            .unwrap();
+        assert_eq!(route.model, "opus");
+        assert!(route.reason.contains("architect"));
+
+        let off = ModelRoutingConfig::default();
+        assert_eq!(
+            off.route(ProviderKind::Claude, "backend", redesign, dir.path()),
+            None
+        );
+    }
+}
@@ -0,0 +1,592 @@
+//! Global scheduling of provider requests.
+//!
+//! Every live provider call takes a slot before its CLI starts and gives it
+//! back when the CLI exits. Slots are shared by every ccswarm process working
+//! in the repository (daemon subsystems, queue drains, interactive runs)
+//! through `.ccswarm/provider-queue.json`, which is only read and written
+//! under an exclusive lock. A request waits while
+//!
+//! - `max_in_flight` calls are running across all providers,
+//! - its provider has its own `max_in_flight` calls running, or has started
+//!   `requests_per_minute` calls in the last minute,
+//! - its provider hit a rate limit less than `cooldown_secs` ago.
+//!
+//! Waiting requests are served fairly across agents: a free slot goes to the
+//! agent with the fewest calls in flight, oldest request first, so one busy
+//! agent cannot starve the rest. Entries of processes that died are dropped
+//! whenever the file is read. `ccswarm status` shows the queue depth.
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::ProviderKind;
+
+/// Queue state file, in the `.ccswarm` state dir.
+pub const QUEUE_FILE: &str = "provider-queue.json";
+
+/// Longest pause between two looks at the queue while waiting.
+const MAX_POLL: Duration = Duration::from_secs(2);
+
+/// Limits on provider calls, under `scheduler` in `ccswarm.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SchedulerConfig {
+    /// Whether provider calls go through the scheduler at all.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Calls running at once across all providers and processes.
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Limits of individual providers, by name (`claude`, `codex`, ...).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, ProviderLimits>,
+    /// Seconds a provider takes no new calls after one hit its rate limit.
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// Seconds a request waits for a slot before it fails.
+    #[serde(default = "default_max_wait_secs")]
+    pub max_wait_secs: u64,
+}
+
+/// Limits of one provider.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProviderLimits {
+    /// Calls to this provider running at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<usize>,
+    /// Calls to this provider started per minute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<usize>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_in_flight() -> usize {
+    8
+}
+
+fn default_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_max_wait_secs() -> u64 {
+    1800
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_in_flight: default_max_in_flight(),
+            providers: BTreeMap::new(),
+            cooldown_secs: default_cooldown_secs(),
+            max_wait_secs: default_max_wait_secs(),
+        }
+    }
+}
+
+/// One request, waiting or in flight.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Entry {
+    ticket: String,
+    pid: u32,
+    agent: String,
+    provider: String,
+    since: DateTime<Utc>,
+}
+
+/// Contents of the queue file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueState {
+    #[serde(default)]
+    in_flight: Vec<Entry>,
+    #[serde(default)]
+    waiting: Vec<Entry>,
+    /// Start times of the last minute's calls, by provider.
+    #[serde(default)]
+    started: BTreeMap<String, Vec<DateTime<Utc>>>,
+    /// Until when each rate-limited provider takes no new calls.
+    #[serde(default)]
+    cooldown_until: BTreeMap<String, DateTime<Utc>>,
+}
+
+impl QueueState {
+    /// Drop entries of dead processes and history that no longer counts.
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let alive =
+            |e: &Entry| e.pid == std::process::id() || ai_session::core::suspend::is_alive(e.pid);
+        self.in_flight.retain(alive);
+        self.waiting.retain(alive);
+        let minute_ago = now - ChronoDuration::minutes(1);
+        for starts in self.started.values_mut() {
+            starts.retain(|t| *t > minute_ago);
+        }
+        self.started.retain(|_, starts| !starts.is_empty());
+        self.cooldown_until.retain(|_, until| *until > now);
+    }
+
+    fn forget(&mut self, ticket: &str) {
+        self.in_flight.retain(|e| e.ticket != ticket);
+        self.waiting.retain(|e| e.ticket != ticket);
+    }
+
+    /// Whether the waiting request `ticket` gets a slot now. Waiting requests
+    /// are taken in fair order, each one that fits reserving its slot, so
+    /// `ticket` only goes ahead of requests that cannot run anyway.
+    fn grant(&mut self, ticket: &str, config: &SchedulerConfig, now: DateTime<Utc>) -> bool {
+        let mut total = self.in_flight.len();
+        let mut by_provider: HashMap<&str, usize> = HashMap::new();
+        let mut by_agent: HashMap<&str, usize> = HashMap::new();
+        for e in &self.in_flight {
+            *by_provider.entry(e.provider.as_str()).or_default() += 1;
+            *by_agent.entry(e.agent.as_str()).or_default() += 1;
+        }
+        let mut started: HashMap<&str, usize> = self
+            .started
+            .iter()
+            .map(|(provider, starts)| (provider.as_str(), starts.len()))
+            .collect();
+
+        let mut order: Vec<&Entry> = self.waiting.iter().collect();
+        order.sort_by_key(|e| {
+            (
+                by_agent.get(e.agent.as_str()).copied().unwrap_or(0),
+                e.since,
+            )
+        });
+        let mut granted = None;
+        for entry in order {
+            if total >= config.max_in_flight {
+                break;
+            }
+            let provider = entry.provider.as_str();
+            let limits = config.providers.get(provider);
+            let running = by_provider.get(provider).copied().unwrap_or(0);
+            let recent = started.get(provider).copied().unwrap_or(0);
+            let fits = !self.cooldown_until.contains_key(provider)
+                && limits
+                    .and_then(|l| l.max_in_flight)
+                    .is_none_or(|max| running < max)
+                && limits
+                    .and_then(|l| l.requests_per_minute)
+                    .is_none_or(|max| recent < max);
+            if !fits {
+                continue;
+            }
+            if entry.ticket == ticket {
+                granted = Some(entry.clone());
+                break;
+            }
+            total += 1;
+            *by_provider.entry(provider).or_default() += 1;
+            *started.entry(provider).or_default() += 1;
+        }
+
+        let Some(entry) = granted else {
+            return false;
+        };
+        self.waiting.retain(|e| e.ticket != ticket);
+        self.started
+            .entry(entry.provider.clone())
+            .or_default()
+            .push(now);
+        self.in_flight.push(entry);
+        true
+    }
+
+    fn stats(&self, now: DateTime<Utc>) -> QueueStats {
+        let mut stats = QueueStats {
+            in_flight: self.in_flight.len(),
+            waiting: self.waiting.len(),
+            oldest_wait_secs: self
+                .waiting
+                .iter()
+                .map(|e| (now - e.since).num_seconds().max(0))
+                .max(),
+            ..Default::default()
+        };
+        for e in &self.in_flight {
+            stats
+                .providers
+                .entry(e.provider.clone())
+                .or_default()
+                .in_flight += 1;
+        }
+        for e in &self.waiting {
+            stats
+                .providers
+                .entry(e.provider.clone())
+                .or_default()
+                .waiting += 1;
+            *stats.waiting_by_agent.entry(e.agent.clone()).or_default() += 1;
+        }
+        for (provider, starts) in &self.started {
+            stats
+                .providers
+                .entry(provider.clone())
+                .or_default()
+                .started_last_minute = starts.len();
+        }
+        for (provider, until) in &self.cooldown_until {
+            stats
+                .providers
+                .entry(provider.clone())
+                .or_default()
+                .cooling_down_until = Some(*until);
+        }
+        stats
+    }
+}
+
+/// Queue depth across all processes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct QueueStats {
+    pub in_flight: usize,
+    pub waiting: usize,
+    /// How long the longest-waiting request has waited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oldest_wait_secs: Option<i64>,
+    pub providers: BTreeMap<String, ProviderStats>,
+    pub waiting_by_agent: BTreeMap<String, usize>,
+}
+
+/// Queue depth of one provider.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProviderStats {
+    pub in_flight: usize,
+    pub waiting: usize,
+    pub started_last_minute: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooling_down_until: Option<DateTime<Utc>>,
+}
+
+/// Hands out provider call slots; cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    path: PathBuf,
+    config: SchedulerConfig,
+}
+
+impl Scheduler {
+    /// Scheduler keeping its queue in `state_dir`.
+    pub fn new(state_dir: &Path, config: SchedulerConfig) -> Self {
+        Self {
+            path: state_dir.join(QUEUE_FILE),
+            config,
+        }
+    }
+
+    /// Wait for a slot to call `provider` on behalf of `agent`. The slot is
+    /// held until the returned permit is released or dropped.
+    pub(crate) async fn acquire(&self, agent: &str, provider: ProviderKind) -> Result<Permit> {
+        let ticket = Ticket {
+            path: self.path.clone(),
+            id: uuid::Uuid::new_v4().to_string(),
+            released: false,
+        };
+        let entry = Entry {
+            ticket: ticket.id.clone(),
+            pid: std::process::id(),
+            agent: agent.to_string(),
+            provider: provider.as_str().to_string(),
+            since: Utc::now(),
+        };
+        let started = Instant::now();
+        let max_wait = Duration::from_secs(self.config.max_wait_secs);
+        let mut poll = Duration::from_millis(100);
+        loop {
+            let (path, config, entry_now) = (self.path.clone(), self.config.clone(), entry.clone());
+            let granted = tokio::task::spawn_blocking(move || {
+                update(&path, |state, now| {
+                    if !state.waiting.iter().any(|e| e.ticket == entry_now.ticket) {
+                        state.waiting.push(entry_now.clone());
+                    }
+                    state.grant(&entry_now.ticket, &config, now)
+                })
+            })
+            .await
+            .context("provider scheduler task panicked")??;
+            if granted {
+                let waited = started.elapsed();
+                if waited >= Duration::from_secs(1) {
+                    tracing::info!(
+                        "{} waited {:.1}s for a {} slot",
+                        agent,
+                        waited.as_secs_f64(),
+                        provider.as_str()
+                    );
+                }
+                return Ok(Permit { ticket });
+            }
+            if started.elapsed() >= max_wait {
+                ticket.release().await;
+                bail!(
+                    "No {} slot came free within {}s; see `ccswarm status` for the provider queue",
+                    provider.as_str(),
+                    max_wait.as_secs()
+                );
+            }
+            tokio::time::sleep(poll).await;
+            poll = (poll * 2).min(MAX_POLL);
+        }
+    }
+
+    /// Hold new calls to `provider` back for `cooldown_secs`: it just
+    /// answered with a rate limit.
+    pub(crate) async fn rate_limited(&self, provider: ProviderKind) {
+        let path = self.path.clone();
+        let cooldown = ChronoDuration::seconds(self.config.cooldown_secs as i64);
+        let result = tokio::task::spawn_blocking(move || {
+            update(&path, |state, now| {
+                state
+                    .cooldown_until
+                    .insert(provider.as_str().to_string(), now + cooldown);
+            })
+        })
+        .await;
+        match result {
+            Ok(Ok(())) => tracing::warn!(
+                "{} is rate limiting; new calls wait {}s",
+                provider.as_str(),
+                self.config.cooldown_secs
+            ),
+            Ok(Err(e)) => tracing::warn!("Failed to record rate limit: {e:#}"),
+            Err(e) => tracing::warn!("Failed to record rate limit: {e}"),
+        }
+    }
+}
+
+/// A slot to call a provider, given back by [`Permit::release`] or on drop.
+#[derive(Debug)]
+pub struct Permit {
+    ticket: Ticket,
+}
+
+impl Permit {
+    /// Give the slot back.
+    pub(crate) async fn release(self) {
+        self.ticket.release().await;
+    }
+}
+
+/// A request in the queue file, whether it got a slot or its caller is
+/// still waiting. Taken out of the file by [`Ticket::release`]; dropping it
+/// unreleased (a cancelled wait, an early return) does the same in the
+/// background.
+#[derive(Debug)]
+struct Ticket {
+    path: PathBuf,
+    id: String,
+    released: bool,
+}
+
+impl Ticket {
+    async fn release(mut self) {
+        self.released = true;
+        let (path, id) = (self.path.clone(), self.id.clone());
+        match tokio::task::spawn_blocking(move || forget(&path, &id)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("Failed to release provider slot: {e:#}"),
+            Err(e) => tracing::warn!("Failed to release provider slot: {e}"),
+        }
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let (path, id) = (std::mem::take(&mut self.path), std::mem::take(&mut self.id));
+        let release = move || {
+            if let Err(e) = forget(&path, &id) {
+                tracing::warn!("Failed to release provider slot: {e:#}");
+            }
+        };
+        // The queue file lock blocks; keep it off the runtime's workers.
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(release)),
+            Err(_) => release(),
+        }
+    }
+}
+
+fn forget(path: &Path, ticket: &str) -> Result<()> {
+    update(path, |state, _| state.forget(ticket))
+}
+
+/// The provider queue in `state_dir`, as of now.
+pub async fn stats(state_dir: &Path) -> Result<QueueStats> {
+    let path = state_dir.join(QUEUE_FILE);
+    if !path.exists() {
+        return Ok(QueueStats::default());
+    }
+    tokio::task::spawn_blocking(move || update(&path, |state, now| state.stats(now)))
+        .await
+        .context("provider scheduler task panicked")?
+}
+
+/// Apply `change` to the queue file under an exclusive lock, with dead
+/// entries and stale history already pruned.
+fn update<T>(path: &Path, change: impl FnOnce(&mut QueueState, DateTime<Utc>) -> T) -> Result<T> {
+    use std::io::{Read, Seek, Write};
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut file = lock(path)?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut state: QueueState = if text.trim().is_empty() {
+        QueueState::default()
+    } else {
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Resetting unreadable provider queue {}: {}",
+                path.display(),
+                e
+            );
+            QueueState::default()
+        })
+    };
+    let now = Utc::now();
+    state.prune(now);
+    let result = change(&mut state, now);
+    let json = serde_json::to_string(&state)?;
+    file.set_len(0)?;
+    file.rewind()?;
+    file.write_all(json.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(result)
+}
+
+/// Open the queue file under an exclusive lock, held until the returned
+/// file is dropped.
+#[cfg(unix)]
+fn lock(path: &Path) -> Result<nix::fcntl::Flock<std::fs::File>> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open provider queue {}", path.display()))?;
+    nix::fcntl::Flock::lock(file, nix::fcntl::FlockArg::LockExclusive)
+        .map_err(|(_, err)| anyhow!("Failed to lock provider queue: {err}"))
+}
+
+#[cfg(not(unix))]
+fn lock(path: &Path) -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open provider queue {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ticket: &str, agent: &str, provider: &str, age_secs: i64) -> Entry {
+        Entry {
+            ticket: ticket.to_string(),
+            pid: std::process::id(),
+            agent: agent.to_string(),
+            provider: provider.to_string(),
+            since: Utc::now() - ChronoDuration::seconds(age_secs),
+        }
+    }
+
+    #[test]
+    fn free_slots_go_to_the_agent_with_fewest_calls_in_flight() {
+        let now = Utc::now();
+        let config: SchedulerConfig = serde_json::from_value(serde_json::json!({
+            "max_in_flight": 2,
+            "providers": { "codex": { "requests_per_minute": 1 } }
+        }))
+        .unwrap();
+        let mut state = QueueState {
+            in_flight: vec![entry("a1", "busy", "claude", 60)],
+            waiting: vec![
+                entry("a2", "busy", "claude", 30),
+                entry("b1", "quiet", "claude", 10),
+            ],
+            ..Default::default()
+        };
+
+        // One slot left: the quiet agent's newer request goes first.
+        assert!(!state.grant("a2", &config, now));
+        assert!(state.grant("b1", &config, now));
+        assert!(!state.grant("a2", &config, now));
+        state.forget("a1");
+        assert!(state.grant("a2", &config, now));
+
+        // Per-minute limit and rate-limit cooldown hold a provider back.
+        state.forget("a2");
+        state.forget("b1");
+        state.waiting = vec![entry("c1", "x", "codex", 5), entry("c2", "y", "codex", 4)];
+        assert!(state.grant("c1", &config, now));
+        assert!(!state.grant("c2", &config, now));
+        state.waiting.push(entry("d1", "z", "claude", 3));
+        state
+            .cooldown_until
+            .insert("claude".into(), now + ChronoDuration::seconds(30));
+        assert!(!state.grant("d1", &config, now));
+
+        let stats = state.stats(now);
+        assert_eq!((stats.in_flight, stats.waiting), (1, 2));
+        assert_eq!(stats.providers["codex"].started_last_minute, 1);
+        assert_eq!(stats.waiting_by_agent["y"], 1);
+        assert!(stats.providers["claude"].cooling_down_until.is_some());
+    }
+
+    #[tokio::test]
+    async fn permits_are_shared_through_the_queue_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SchedulerConfig {
+            max_in_flight: 1,
+            max_wait_secs: 0,
+            ..Default::default()
+        };
+        let scheduler = Scheduler::new(dir.path(), config.clone());
+        let permit = scheduler
+            .acquire("coder", ProviderKind::Claude)
+            .await
+            .unwrap();
+        // Another process's scheduler sees the same slots.
+        let other = Scheduler::new(dir.path(), config);
+        let err = other
+            .acquire("tester", ProviderKind::Codex)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No codex slot"));
+
+        let busy = stats(dir.path()).await.unwrap();
+        assert_eq!((busy.in_flight, busy.waiting), (1, 0));
+        permit.release().await;
+        assert_eq!(stats(dir.path()).await.unwrap().in_flight, 0);
+        let permit = other.acquire("tester", ProviderKind::Codex).await.unwrap();
+
+        // A permit dropped without `release` is given back in the background.
+        drop(permit);
+        let mut in_flight = 1;
+        for _ in 0..50 {
+            in_flight = stats(dir.path()).await.unwrap().in_flight;
+            if in_flight == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(in_flight, 0);
+    }
+}
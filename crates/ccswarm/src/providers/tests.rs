@@ -26,9 +26,12 @@ fn kind_parse_round_trip() {
         ("CLAUDE", ProviderKind::Claude),
         ("claude-code", ProviderKind::Claude),
         ("codex", ProviderKind::Codex),
+        ("gemini", ProviderKind::Gemini),
+        ("aider", ProviderKind::Aider),
         ("copilot", ProviderKind::Copilot),
         ("gh-copilot", ProviderKind::Copilot),
         ("github-copilot", ProviderKind::Copilot),
+        ("mock", ProviderKind::Mock),
     ] {
         assert_eq!(ProviderKind::parse(input), Some(expected));
     }
@@ -49,7 +52,10 @@ fn claude_command_includes_expected_flags() {
         max_budget: Some(0.25),
         worktree_name: Some("wt-1".to_string()),
         claude_stream_json: false,
+        claude_partial_messages: false,
         codex_json: false,
+        typed_tools: None,
+        extra_args: Vec::new(),
     };
     let cmd = provider.build_command("do the thing", Path::new("/tmp"), &opts);
     let argv = argv_of(&cmd);
@@ -90,6 +96,53 @@ fn claude_command_switches_to_stream_json_when_requested() {
     // pair them so the bridge can actually see per-event lines.
     assert!(argv.iter().any(|a| a == "--verbose"));
     assert!(!argv.iter().any(|a| a == "text"));
+    assert!(!argv.iter().any(|a| a == "--include-partial-messages"));
+
+    let opts = ProviderOptions {
+        claude_stream_json: true,
+        claude_partial_messages: true,
+        ..Default::default()
+    };
+    let argv = argv_of(&provider.build_command("hi", Path::new("/tmp"), &opts));
+    assert!(argv.iter().any(|a| a == "--include-partial-messages"));
+}
+
+#[test]
+fn gemini_and_aider_edit_only_in_editing_stages() {
+    let readonly = ProviderOptions {
+        allowed_tools: vec!["read".to_string(), "grep".to_string()],
+        model: Some("m-1".to_string()),
+        system_prompt: Some("You review.".to_string()),
+        extra_args: vec!["--debug".to_string()],
+        ..Default::default()
+    };
+    let editing = ProviderOptions {
+        allowed_tools: vec!["read".to_string(), "edit".to_string()],
+        ..Default::default()
+    };
+    let merged = "You review.\n\n---\n\nfix it";
+
+    let gemini = resolve(ProviderKind::Gemini);
+    let argv = argv_of(&gemini.build_command("fix it", Path::new("/tmp"), &readonly));
+    assert_eq!(
+        argv,
+        ["gemini", "--model", "m-1", "--debug", "--prompt", merged]
+    );
+    let argv = argv_of(&gemini.build_command("fix it", Path::new("/tmp"), &editing));
+    assert_eq!(argv, ["gemini", "--yolo", "--prompt", "fix it"]);
+
+    let aider = resolve(ProviderKind::Aider);
+    let argv = argv_of(&aider.build_command("fix it", Path::new("/tmp"), &readonly));
+    assert_eq!(argv[0], "aider");
+    assert!(argv.iter().any(|a| a == "--no-auto-commits"));
+    assert!(argv.iter().any(|a| a == "--dry-run"));
+    assert_eq!(
+        argv[argv.len() - 4..],
+        ["m-1", "--debug", "--message", merged]
+    );
+    let argv = argv_of(&aider.build_command("fix it", Path::new("/tmp"), &editing));
+    assert!(!argv.iter().any(|a| a == "--dry-run"));
+    assert_eq!(argv.last().map(String::as_str), Some("fix it"));
 }
 
 #[test]
@@ -108,6 +161,51 @@ fn provider_continuation_capabilities_are_explicit() {
     );
 }
 
+#[test]
+fn typed_tools_replace_builtin_tools_with_the_mcp_server() {
+    use crate::workflow::flow::MovementPermission;
+
+    let typed = super::TypedTools::for_session(
+        Path::new("/work"),
+        &MovementPermission::Readonly,
+        &[],
+        None,
+    );
+    let opts = ProviderOptions {
+        allowed_tools: vec!["read".to_string()],
+        typed_tools: Some(typed),
+        ..Default::default()
+    };
+
+    let argv =
+        argv_of(&resolve(ProviderKind::Claude).build_command("p", Path::new("/work"), &opts));
+    let flag = |name: &str| {
+        let i = argv.iter().position(|a| a == name).expect(name);
+        argv[i + 1].clone()
+    };
+    assert_eq!(
+        flag("--allowed-tools"),
+        "mcp__ccswarm__read_file,mcp__ccswarm__search_code"
+    );
+    assert!(flag("--disallowed-tools").contains("Bash"));
+    let config: serde_json::Value = serde_json::from_str(&flag("--mcp-config")).unwrap();
+    let args = &config["mcpServers"]["ccswarm"]["args"];
+    assert_eq!(args[0], "tools");
+    assert_eq!(args[1], "serve");
+    assert!(args.as_array().unwrap().iter().any(|a| a == "readonly"));
+
+    let argv = argv_of(&resolve(ProviderKind::Codex).build_command("p", Path::new("/work"), &opts));
+    assert!(
+        argv.iter()
+            .any(|a| a.starts_with("mcp_servers.ccswarm.command=\""))
+    );
+    assert!(
+        argv.iter()
+            .any(|a| a.starts_with("mcp_servers.ccswarm.args=[\"tools\",\"serve\""))
+    );
+    assert_eq!(argv.last().unwrap(), "p");
+}
+
 #[test]
 fn codex_command_merges_system_prompt_and_has_no_session_flag() {
     // codex #3 regression guard: Codex CLI has no `--session` flag — resume
@@ -145,6 +243,7 @@ fn codex_command_with_session_id_uses_resume_subcommand() {
     let opts = ProviderOptions {
         session_id: Some("thread-42".to_string()),
         codex_json: true,
+        typed_tools: None,
         ..Default::default()
     };
     let cmd = provider.build_command("CONTINUE", Path::new("/tmp"), &opts);
@@ -173,3 +272,187 @@ fn copilot_provider_emits_error_command() {
     assert!(argv[2].contains("not supported"));
     assert!(argv[2].contains("exit 2"));
 }
+
+#[tokio::test]
+async fn mock_provider_plays_scenario_replies_in_a_real_subprocess() {
+    let dir = tempfile::tempdir().unwrap();
+    let provider = resolve(ProviderKind::Mock);
+    let run = |prompt: &str, tools: &[&str]| {
+        let options = ProviderOptions {
+            allowed_tools: tools.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut cmd = provider.build_command(prompt, dir.path(), &options);
+        async move { cmd.output().await.unwrap() }
+    };
+
+    // Built-in scenario: readonly stages plan and vote, edit stages write.
+    let plan = run(
+        "Analyze the task and create an implementation plan",
+        &["read"],
+    )
+    .await;
+    assert!(plan.status.success());
+    assert!(String::from_utf8_lossy(&plan.stdout).starts_with("Plan:"));
+    assert!(!dir.path().join("MOCK_AGENT.md").exists());
+    let vote = run("End with SANGHA_DECISION=APPROVE", &["read"]).await;
+    assert!(String::from_utf8_lossy(&vote.stdout).contains("SANGHA_DECISION=APPROVE"));
+    let edit = run("Implement the planned changes", &["read", "write"]).await;
+    assert!(edit.status.success());
+    assert!(dir.path().join("MOCK_AGENT.md").exists());
+
+    std::fs::create_dir_all(dir.path().join(".ccswarm")).unwrap();
+    std::fs::write(
+        dir.path().join(super::mock::SCENARIO_FILE),
+        "replies:\n  - match: LOGIN\n    stdout: added it\n    files:\n      \"src/it's.rs\": \"fn login() {}\\n\"\n  - stdout: nope\n    stderr: rate limited\n    success: false\n",
+    )
+    .unwrap();
+    let login = run("Add login", &[]).await;
+    assert_eq!(String::from_utf8_lossy(&login.stdout), "added it\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("src/it's.rs")).unwrap(),
+        "fn login() {}\n"
+    );
+    let other = run("Something else", &[]).await;
+    assert!(!other.status.success());
+    assert_eq!(String::from_utf8_lossy(&other.stderr), "rate limited\n");
+
+    assert!(
+        super::mock::MockScenario::parse("replies:\n  - files:\n      ../escape: x\n").is_err()
+    );
+}
+
+mod transcript {
+    use super::super::transcript::{
+        ProviderResponse, TranscriptRecorder, TranscriptReplay, entry, rekey,
+    };
+    use super::{ProviderKind, ProviderOptions};
+    use crate::redaction::{RedactionConfig, RedactionRule, Redactor};
+
+    fn recorded(
+        agent: &str,
+        prompt: &str,
+        stdout: &str,
+    ) -> super::super::transcript::TranscriptEntry {
+        entry(
+            ProviderKind::Claude,
+            agent,
+            prompt,
+            &ProviderOptions::default(),
+            ProviderResponse {
+                success: true,
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            },
+            42,
+        )
+    }
+
+    #[test]
+    fn replay_prefers_exact_prompt_then_falls_back_in_order() {
+        let replay = TranscriptReplay::new(vec![
+            recorded("coder", "first", "one"),
+            recorded("coder", "second", "two"),
+            recorded("reviewer", "review", "lgtm"),
+        ]);
+
+        let (response, latency) = replay
+            .next(ProviderKind::Claude, "coder", "second")
+            .unwrap();
+        assert_eq!(response.stdout, "two");
+        assert_eq!(latency, 42);
+        // No exact match: next unused entry for the same agent.
+        let (response, _) = replay
+            .next(ProviderKind::Claude, "coder", "first, reworded")
+            .unwrap();
+        assert_eq!(response.stdout, "one");
+        assert_eq!(replay.remaining(), 1);
+
+        let err = replay
+            .next(ProviderKind::Claude, "coder", "third")
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("no recorded claude response left for agent 'coder'")
+        );
+        // Same agent under a different provider doesn't match either.
+        assert!(
+            replay
+                .next(ProviderKind::Codex, "reviewer", "review")
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn recorder_appends_redacted_entries_that_replay_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("calls.jsonl");
+        let redactor: Redactor = RedactionConfig {
+            rules: vec![RedactionRule {
+                pattern: "sk-[a-z0-9]+".to_string(),
+                replacement: "[KEY]".to_string(),
+            }],
+        }
+        .compile()
+        .unwrap();
+
+        let recorder = TranscriptRecorder::new(&path);
+        recorder
+            .record(
+                recorded("coder", "use sk-abc123", "saw sk-abc123"),
+                &redactor,
+            )
+            .await
+            .unwrap();
+        recorder
+            .record(recorded("coder", "again", "ok"), &redactor)
+            .await
+            .unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert!(!text.contains("sk-abc123"));
+
+        let replay = TranscriptReplay::load(&path).unwrap();
+        let (response, _) = replay
+            .next(ProviderKind::Claude, "coder", "use [KEY]")
+            .unwrap();
+        assert_eq!(response.stdout, "saw [KEY]");
+        assert_eq!(replay.remaining(), 1);
+    }
+
+    #[tokio::test]
+    async fn encrypted_transcripts_replay_and_rekey() {
+        use ai_session::persistence::{EncryptionKey, Keyring};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("calls.jsonl");
+        let old = EncryptionKey::generate();
+        let new = EncryptionKey::generate();
+
+        // One plaintext line from before encryption, one sealed with `old`.
+        TranscriptRecorder::new(&path)
+            .record(recorded("coder", "first", "plain"), &Redactor::default())
+            .await
+            .unwrap();
+        TranscriptRecorder::new(&path)
+            .with_encryption(Keyring::new(old.clone()))
+            .record(recorded("coder", "second", "secret"), &Redactor::default())
+            .await
+            .unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("secret"));
+        assert!(TranscriptReplay::load(&path).is_err());
+
+        let keyring = Keyring::new(new.clone()).with_previous(old);
+        assert_eq!(rekey(&path, &keyring).unwrap(), 2);
+        assert_eq!(rekey(&path, &keyring).unwrap(), 0);
+
+        let replay = TranscriptReplay::load_with(&path, Some(&Keyring::new(new))).unwrap();
+        let (response, _) = replay
+            .next(ProviderKind::Claude, "coder", "second")
+            .unwrap();
+        assert_eq!(response.stdout, "secret");
+        assert_eq!(replay.remaining(), 1);
+    }
+}
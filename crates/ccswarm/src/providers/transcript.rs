@@ -0,0 +1,315 @@
+//! Provider transcripts: record every provider call to a JSONL file, or
+//! replay a recorded file instead of spawning the provider CLI.
+//!
+//! - `CCSWARM_PROVIDER_RECORD=<file>` appends one [`TranscriptEntry`] per call
+//!   (prompt, parameters, response, latency). Redaction rules apply to what
+//!   gets written.
+//! - `CCSWARM_PROVIDER_REPLAY=<file>` answers each call from the recording,
+//!   so flows (delegation, team_leader, sangha) run deterministically in
+//!   tests and offline.
+//!
+//! When an ai-session encryption key is configured (`AI_SESSION_ENCRYPTION_KEY`
+//! or `AI_SESSION_ENCRYPTION_KEY_FILE`), each recorded line is sealed, and
+//! replay opens sealed and plaintext lines alike.
+//!
+//! Replay matches a call to the first unused entry with the same provider,
+//! agent and prompt. Prompts that embed something volatile won't match
+//! exactly; those fall back to the next unused entry for the same provider
+//! and agent, in recorded order.
+
+use ai_session::persistence::Keyring;
+use ai_session::persistence::encryption::{line_needs_rekey, open_line};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
+
+use super::{ProviderKind, ProviderOptions};
+use crate::redaction::Redactor;
+
+pub(crate) const RECORD_ENV: &str = "CCSWARM_PROVIDER_RECORD";
+pub(crate) const REPLAY_ENV: &str = "CCSWARM_PROVIDER_REPLAY";
+
+/// One provider call as written to a transcript file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct TranscriptEntry {
+    pub provider: String,
+    pub agent_id: String,
+    /// The prompt as the bridge received it, before the working-directory
+    /// preamble (which differs between machines) is added.
+    pub prompt: String,
+    pub params: TranscriptParams,
+    pub response: ProviderResponse,
+    pub latency_ms: u64,
+    pub recorded_at: DateTime<Utc>,
+    /// The ccswarm invocation that made the call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orchestrator_run_id: Option<crate::run_id::RunId>,
+}
+
+/// The [`ProviderOptions`] that shape a call. The system prompt is left out:
+/// it is persona text, not something a replayed test varies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub(crate) struct TranscriptParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub continue_session: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_budget: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree_name: Option<String>,
+}
+
+impl From<&ProviderOptions> for TranscriptParams {
+    fn from(options: &ProviderOptions) -> Self {
+        Self {
+            model: options.model.clone(),
+            allowed_tools: options.allowed_tools.clone(),
+            agent_name: options.agent_name.clone(),
+            session_id: options.session_id.clone(),
+            continue_session: options.continue_session,
+            max_budget: options.max_budget,
+            worktree_name: options.worktree_name.clone(),
+        }
+    }
+}
+
+/// What the provider CLI produced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct ProviderResponse {
+    pub success: bool,
+    pub stdout: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+}
+
+/// How the bridge treats provider calls.
+#[derive(Debug, Default)]
+pub(crate) enum TranscriptMode {
+    #[default]
+    Live,
+    Record(TranscriptRecorder),
+    /// Shared so every bridge built for one scripted run draws from the
+    /// same recording.
+    Replay(std::sync::Arc<TranscriptReplay>),
+}
+
+impl TranscriptMode {
+    /// Read `CCSWARM_PROVIDER_RECORD` / `CCSWARM_PROVIDER_REPLAY`, sealing
+    /// with the ai-session encryption keys if any are configured.
+    pub(crate) fn from_env() -> Result<Self> {
+        let record = std::env::var_os(RECORD_ENV).filter(|v| !v.is_empty());
+        let replay = std::env::var_os(REPLAY_ENV).filter(|v| !v.is_empty());
+        match (record, replay) {
+            (Some(_), Some(_)) => Err(anyhow!(
+                "{RECORD_ENV} and {REPLAY_ENV} are mutually exclusive"
+            )),
+            (Some(path), None) => {
+                let recorder = TranscriptRecorder::new(path);
+                Ok(Self::Record(match Keyring::from_env()? {
+                    Some(keyring) => recorder.with_encryption(keyring),
+                    None => recorder,
+                }))
+            }
+            (None, Some(path)) => Ok(Self::Replay(
+                TranscriptReplay::load_with(Path::new(&path), Keyring::from_env()?.as_ref())?
+                    .into(),
+            )),
+            (None, None) => Ok(Self::Live),
+        }
+    }
+}
+
+/// Appends entries to a JSONL transcript.
+#[derive(Debug)]
+pub(crate) struct TranscriptRecorder {
+    path: PathBuf,
+    lock: tokio::sync::Mutex<()>,
+    keyring: Option<Keyring>,
+}
+
+impl TranscriptRecorder {
+    pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: tokio::sync::Mutex::new(()),
+            keyring: None,
+        }
+    }
+
+    /// Seal each entry with `keyring` before it is written.
+    pub(crate) fn with_encryption(mut self, keyring: Keyring) -> Self {
+        self.keyring = Some(keyring);
+        self
+    }
+
+    /// Redact the entry's text with `redactor` and append it.
+    pub(crate) async fn record(
+        &self,
+        mut entry: TranscriptEntry,
+        redactor: &Redactor,
+    ) -> Result<()> {
+        entry.prompt = redactor.redact(&entry.prompt).into_owned();
+        entry.response.stdout = redactor.redact(&entry.response.stdout).into_owned();
+        entry.response.stderr = redactor.redact(&entry.response.stderr).into_owned();
+        let mut line = serde_json::to_string(&entry)?;
+        if let Some(keyring) = &self.keyring {
+            line = keyring.seal_line(&line)?;
+        }
+        line.push('\n');
+
+        let _guard = self.lock.lock().await;
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("Failed to open transcript {}", self.path.display()))?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Serves recorded responses in place of the provider CLI.
+#[derive(Debug)]
+pub(crate) struct TranscriptReplay {
+    /// Entries paired with whether a call has consumed them.
+    entries: Mutex<Vec<(TranscriptEntry, bool)>>,
+}
+
+impl TranscriptReplay {
+    pub(crate) fn new(entries: Vec<TranscriptEntry>) -> Self {
+        Self {
+            entries: Mutex::new(entries.into_iter().map(|e| (e, false)).collect()),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        Self::load_with(path, None)
+    }
+
+    /// Load a transcript, opening sealed lines with `keyring`.
+    pub(crate) fn load_with(path: &Path, keyring: Option<&Keyring>) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read transcript {}", path.display()))?;
+        let entries = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let location = || format!("{}:{}", path.display(), i + 1);
+                let line = open_line(keyring, line).with_context(location)?;
+                serde_json::from_str(&line)
+                    .with_context(|| format!("{}: invalid transcript entry", location()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self::new(entries))
+    }
+
+    /// The recorded response for this call, consuming its entry.
+    pub(crate) fn next(
+        &self,
+        provider: ProviderKind,
+        agent_id: &str,
+        prompt: &str,
+    ) -> Result<(ProviderResponse, u64)> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| anyhow!("transcript replay lock poisoned"))?;
+        let candidate = |(entry, used): &(TranscriptEntry, bool)| {
+            !used && entry.provider == provider.as_str() && entry.agent_id == agent_id
+        };
+        let index = entries
+            .iter()
+            .position(|e| candidate(e) && e.0.prompt == prompt)
+            .or_else(|| entries.iter().position(candidate))
+            .ok_or_else(|| {
+                let used = entries.iter().filter(|(_, used)| *used).count();
+                anyhow!(
+                    "no recorded {} response left for agent '{}' ({} of {} transcript entries used)",
+                    provider.as_str(),
+                    agent_id,
+                    used,
+                    entries.len()
+                )
+            })?;
+        entries[index].1 = true;
+        let entry = &entries[index].0;
+        Ok((entry.response.clone(), entry.latency_ms))
+    }
+
+    /// Entries no call has asked for yet.
+    #[cfg(test)]
+    pub(crate) fn remaining(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.iter().filter(|(_, used)| !used).count())
+            .unwrap_or(0)
+    }
+}
+
+/// Rewrite every line of the transcript at `path` that is plaintext or sealed
+/// with a retired key, returning how many were rewritten.
+pub(crate) fn rekey(path: &Path, keyring: &Keyring) -> Result<usize> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read transcript {}", path.display()))?;
+    let mut rewritten = 0;
+    let mut out = String::with_capacity(text.len());
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line_needs_rekey(keyring, line) {
+            let plain = open_line(Some(keyring), line)
+                .with_context(|| format!("{}:{}", path.display(), i + 1))?;
+            out.push_str(&keyring.seal_line(&plain)?);
+            rewritten += 1;
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    if rewritten > 0 {
+        let tmp = path.with_extension("rekey.tmp");
+        std::fs::write(&tmp, out)?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to replace transcript {}", path.display()))?;
+    }
+    Ok(rewritten)
+}
+
+/// Build an entry for `prompt` as answered by `response`.
+pub(crate) fn entry(
+    provider: ProviderKind,
+    agent_id: &str,
+    prompt: &str,
+    options: &ProviderOptions,
+    response: ProviderResponse,
+    latency_ms: u64,
+) -> TranscriptEntry {
+    TranscriptEntry {
+        provider: provider.as_str().to_string(),
+        agent_id: agent_id.to_string(),
+        prompt: prompt.to_string(),
+        params: options.into(),
+        response,
+        latency_ms,
+        recorded_at: Utc::now(),
+        orchestrator_run_id: Some(crate::run_id::RunId::current().clone()),
+    }
+}
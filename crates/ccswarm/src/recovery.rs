@@ -0,0 +1,492 @@
+//! Crash-recovery journal for orchestrator decisions.
+//!
+//! The queue has its own log (`queue.wal`) and every run its event log, but
+//! neither says that the orchestrator was half-way through something when it
+//! died: a task claimed for a run that never started, a worktree added but
+//! never set up, a merge left in progress, a run whose process is gone.
+//! Before starting such an operation the orchestrator appends a `begin`
+//! record for it to `.ccswarm/recovery.wal` (fsynced), and a `done` record
+//! once it has finished, whichever way it went.
+//!
+//! [`RecoveryJournal::recover`] compensates every operation that began, never
+//! finished and whose process has exited, newest first so an operation is
+//! undone before the ones it built on:
+//!
+//! - task assigned: the claim goes back to the queue, unless the run got as
+//!   far as its summary (queue reconciliation settles those);
+//! - session spawned: the run is marked interrupted in its event log and its
+//!   stale pid file removed, so nothing later signals a recycled pid;
+//! - worktree created: the worktree is force-removed and pruned;
+//! - merge started: a merge still in progress is aborted.
+//!
+//! Each compensation checks the current state first, so recovering twice, or
+//! an operation that did finish before the crash, changes nothing. Its
+//! outcome is logged as the operation's `done` record, and the log is
+//! truncated once nothing in it is open. `ccswarm recover` runs recovery by
+//! hand; `queue drain`, `auto` and the daemon run it when they start.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cli::handlers::queue_state::{ClaimState, QueueState};
+use crate::events::{Event, EventLevel, EventRecorder, EventType, RUN_PID_FILE};
+use crate::git::shell::git;
+
+/// Log file, relative to the `.ccswarm` state directory.
+pub const RECOVERY_LOG: &str = "recovery.wal";
+
+/// An orchestrator operation with effects recovery may have to undo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Operation {
+    /// A queue task claimed for a pipeline run.
+    TaskAssigned { task_id: String, run_id: String },
+    /// A pipeline run executing in the recording process.
+    SessionSpawned { run_id: String },
+    /// `git worktree add` of `path` in `repo`, until the worktree is ready
+    /// (or, for scratch worktrees, removed again).
+    WorktreeCreated { repo: PathBuf, path: PathBuf },
+    /// `git merge` of `branch` into the checkout at `repo`.
+    MergeStarted { repo: PathBuf, branch: String },
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TaskAssigned { task_id, run_id } => {
+                write!(f, "task {task_id} assigned to run {run_id}")
+            }
+            Self::SessionSpawned { run_id } => write!(f, "run {run_id} spawned"),
+            Self::WorktreeCreated { path, .. } => {
+                write!(f, "worktree {} created", path.display())
+            }
+            Self::MergeStarted { branch, .. } => write!(f, "merge of {branch} started"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RecordState {
+    Begin,
+    Done,
+}
+
+/// One line of the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    at: DateTime<Utc>,
+    /// Process that wrote the record.
+    pid: u32,
+    state: RecordState,
+    op: Operation,
+    /// What recovery did, on a `done` record it wrote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compensation: Option<String>,
+}
+
+/// An operation that began and has not finished.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenOperation {
+    pub op: Operation,
+    pub began_at: DateTime<Utc>,
+    pub pid: u32,
+}
+
+impl OpenOperation {
+    /// Whether the process that began it is still running, so the operation
+    /// may yet finish.
+    pub fn in_flight(&self) -> bool {
+        self.pid == std::process::id() || ai_session::core::suspend::is_alive(self.pid)
+    }
+}
+
+/// What recovery did about one abandoned operation.
+#[derive(Debug, Clone, Serialize)]
+pub struct Compensation {
+    pub op: Operation,
+    pub began_at: DateTime<Utc>,
+    pub ok: bool,
+    /// What was undone, or why it could not be.
+    pub outcome: String,
+}
+
+/// The journal of one `.ccswarm` state directory.
+#[derive(Debug, Clone)]
+pub struct RecoveryJournal {
+    state_dir: PathBuf,
+}
+
+impl RecoveryJournal {
+    pub fn new(state_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            state_dir: state_dir.into(),
+        }
+    }
+
+    pub fn for_repo(repo: &Path) -> Self {
+        Self::new(repo.join(".ccswarm"))
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.state_dir.join(RECOVERY_LOG)
+    }
+
+    /// Record that `op` is about to start. Fails when the record cannot be
+    /// made durable, so nothing starts that recovery would not know about.
+    pub async fn begin(&self, op: Operation) -> Result<()> {
+        self.append(vec![record(RecordState::Begin, op, None)])
+            .await
+    }
+
+    /// Record that `op` finished. A failure is only logged: at worst recovery
+    /// later looks at an operation that did finish, and leaves it be.
+    pub async fn done(&self, op: &Operation) {
+        if let Err(e) = self
+            .append(vec![record(RecordState::Done, op.clone(), None)])
+            .await
+        {
+            tracing::warn!("Failed to record that {op} finished: {e:#}");
+        }
+    }
+
+    /// Operations that began and have not finished, oldest first.
+    pub async fn open(&self) -> Result<Vec<OpenOperation>> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        tokio::task::spawn_blocking(move || -> Result<_> {
+            let _lock = lock(&path)?;
+            read_open(&path)
+        })
+        .await
+        .context("Recovery log reader panicked")?
+    }
+
+    /// Compensate every open operation whose process has exited, newest
+    /// first. Operations still in flight are left alone.
+    pub async fn recover(&self) -> Result<Vec<Compensation>> {
+        let abandoned: Vec<OpenOperation> = self
+            .open()
+            .await?
+            .into_iter()
+            .rev()
+            .filter(|open| !open.in_flight())
+            .collect();
+        let mut compensations = Vec::new();
+        let mut settled = Vec::new();
+        for open in abandoned {
+            let result = self.compensate(&open.op).await;
+            let compensation = Compensation {
+                op: open.op.clone(),
+                began_at: open.began_at,
+                ok: result.is_ok(),
+                outcome: result.unwrap_or_else(|e| format!("{e:#}")),
+            };
+            if compensation.ok {
+                settled.push(record(
+                    RecordState::Done,
+                    open.op,
+                    Some(compensation.outcome.clone()),
+                ));
+            }
+            compensations.push(compensation);
+        }
+        if !settled.is_empty() {
+            self.append(settled).await?;
+        }
+        self.compact().await?;
+        Ok(compensations)
+    }
+
+    async fn compensate(&self, op: &Operation) -> Result<String> {
+        match op {
+            Operation::TaskAssigned { task_id, run_id } => {
+                self.release_claim(task_id, run_id).await
+            }
+            Operation::SessionSpawned { run_id } => self.interrupt_run(run_id).await,
+            Operation::WorktreeCreated { repo, path } => remove_worktree(repo, path).await,
+            Operation::MergeStarted { repo, branch } => abort_merge(repo, branch).await,
+        }
+    }
+
+    async fn release_claim(&self, task_id: &str, run_id: &str) -> Result<String> {
+        let state = QueueState::new(self.state_dir.join("queue.yaml"));
+        let queue = state.load().await?;
+        let Some(task) = queue.tasks.iter().find(|t| t.id == task_id) else {
+            return Ok(format!("task {task_id} is no longer queued"));
+        };
+        let ClaimState::Running {
+            run_id: claimed_by,
+            started_at_ms,
+        } = &task.claim
+        else {
+            return Ok(format!("task {task_id} is no longer claimed"));
+        };
+        if claimed_by != run_id {
+            return Ok(format!("task {task_id} was claimed again since"));
+        }
+        if self.run_dir(run_id).join("summary.json").exists() {
+            return Ok(format!(
+                "run {run_id} finished; queue reconciliation settles task {task_id}"
+            ));
+        }
+        state
+            .requeue_orphaned(task_id, run_id, *started_at_ms)
+            .await?;
+        Ok(format!("task {task_id} requeued"))
+    }
+
+    async fn interrupt_run(&self, run_id: &str) -> Result<String> {
+        let run_dir = self.run_dir(run_id);
+        if !run_dir.exists() || run_dir.join("summary.json").exists() {
+            return Ok(format!("run {run_id} finished"));
+        }
+        let recorder = EventRecorder::new_in_runs_dir(self.state_dir.join("runs"), run_id).await?;
+        recorder
+            .record(Event::new(
+                run_id,
+                EventLevel::Error,
+                EventType::ErrorDetected,
+                "run interrupted: its process exited before the run finished",
+            ))
+            .await?;
+        match tokio::fs::remove_file(run_dir.join(RUN_PID_FILE)).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context("Failed to remove the run's pid file"),
+        }
+        Ok(format!("run {run_id} marked interrupted"))
+    }
+
+    fn run_dir(&self, run_id: &str) -> PathBuf {
+        self.state_dir.join("runs").join(run_id)
+    }
+
+    async fn append(&self, records: Vec<Record>) -> Result<()> {
+        let path = self.path();
+        let mut lines = String::new();
+        for record in &records {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = lock(&path)?;
+            // A torn line left by a crash is terminated first so the new
+            // records stay on lines of their own.
+            if file.metadata()?.len() > 0 {
+                let mut last = [0u8; 1];
+                file.seek(SeekFrom::End(-1))?;
+                file.read_exact(&mut last)?;
+                if last[0] != b'\n' {
+                    lines.insert(0, '\n');
+                }
+            }
+            file.write_all(lines.as_bytes())?;
+            file.sync_data()
+                .with_context(|| format!("Failed to sync recovery log {}", path.display()))
+        })
+        .await
+        .context("Recovery log writer panicked")?
+    }
+
+    /// Truncate the log when every operation in it has finished.
+    async fn compact(&self) -> Result<()> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(());
+        }
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let file = lock(&path)?;
+            if read_open(&path)?.is_empty() && file.metadata()?.len() > 0 {
+                file.set_len(0)?;
+                file.sync_all()?;
+            }
+            Ok(())
+        })
+        .await
+        .context("Recovery log writer panicked")?
+    }
+}
+
+fn record(state: RecordState, op: Operation, compensation: Option<String>) -> Record {
+    Record {
+        at: Utc::now(),
+        pid: std::process::id(),
+        state,
+        op,
+        compensation,
+    }
+}
+
+/// Open the log for appending under an exclusive lock, held until the
+/// returned file is dropped.
+#[cfg(unix)]
+fn lock(path: &Path) -> Result<nix::fcntl::Flock<std::fs::File>> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open recovery log {}", path.display()))?;
+    nix::fcntl::Flock::lock(file, nix::fcntl::FlockArg::LockExclusive)
+        .map_err(|(_, err)| anyhow!("Failed to lock recovery log: {err}"))
+}
+
+#[cfg(not(unix))]
+fn lock(path: &Path) -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open recovery log {}", path.display()))
+}
+
+/// Begun operations without a matching `done`, oldest first. A `done`
+/// closes the oldest open record of the same operation; a torn last line
+/// was never acknowledged and is ignored.
+fn read_open(path: &Path) -> Result<Vec<OpenOperation>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read recovery log {}", path.display()));
+        }
+    };
+    let mut open: Vec<OpenOperation> = Vec::new();
+    for record in content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Record>(line).ok())
+    {
+        match record.state {
+            RecordState::Begin => open.push(OpenOperation {
+                op: record.op,
+                began_at: record.at,
+                pid: record.pid,
+            }),
+            RecordState::Done => {
+                if let Some(i) = open.iter().position(|o| o.op == record.op) {
+                    open.remove(i);
+                }
+            }
+        }
+    }
+    Ok(open)
+}
+
+async fn remove_worktree(repo: &Path, path: &Path) -> Result<String> {
+    let outcome = if path.exists() {
+        git(
+            repo,
+            &["worktree", "remove", "--force", &path.to_string_lossy()],
+        )
+        .await
+        .with_context(|| format!("Failed to remove worktree {}", path.display()))?;
+        format!("removed worktree {}", path.display())
+    } else {
+        format!("worktree {} is gone", path.display())
+    };
+    git(repo, &["worktree", "prune"]).await?;
+    Ok(outcome)
+}
+
+async fn abort_merge(repo: &Path, branch: &str) -> Result<String> {
+    if git(repo, &["rev-parse", "-q", "--verify", "MERGE_HEAD"])
+        .await
+        .is_err()
+    {
+        return Ok(format!("no merge of {branch} in progress"));
+    }
+    git(repo, &["merge", "--abort"]).await?;
+    Ok(format!("aborted the merge of {branch}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_sync(repo: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(repo)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[tokio::test]
+    async fn abandoned_operations_are_compensated_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        git_sync(repo, &["init", "-q", "-b", "main"]);
+        git_sync(repo, &["config", "user.email", "t@example.com"]);
+        git_sync(repo, &["config", "user.name", "t"]);
+        std::fs::write(repo.join("a.txt"), "a\n").unwrap();
+        git_sync(repo, &["add", "."]);
+        git_sync(repo, &["commit", "-q", "-m", "init"]);
+        let worktree = repo.join(".ccswarm/scratch");
+        git_sync(
+            repo,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "--detach",
+                &worktree.to_string_lossy(),
+            ],
+        );
+        std::fs::create_dir_all(repo.join(".ccswarm/runs/run-1")).unwrap();
+        std::fs::write(repo.join(".ccswarm/runs/run-1/pid"), "999999999").unwrap();
+
+        let journal = RecoveryJournal::for_repo(repo);
+        let created = Operation::WorktreeCreated {
+            repo: repo.to_path_buf(),
+            path: worktree.clone(),
+        };
+        let spawned = Operation::SessionSpawned {
+            run_id: "run-1".into(),
+        };
+        let merged = Operation::MergeStarted {
+            repo: repo.to_path_buf(),
+            branch: "task/x".into(),
+        };
+        journal.begin(merged.clone()).await.unwrap();
+        journal.done(&merged).await;
+        journal.begin(created.clone()).await.unwrap();
+        journal.begin(spawned.clone()).await.unwrap();
+        // Still ours, so still in flight: recovery leaves both alone.
+        assert!(journal.recover().await.unwrap().is_empty());
+        assert_eq!(journal.open().await.unwrap().len(), 2);
+
+        // Pretend a process that has exited began them.
+        let log = std::fs::read_to_string(journal.path()).unwrap();
+        let log = log.replace(
+            &format!("\"pid\":{}", std::process::id()),
+            "\"pid\":999999999",
+        );
+        std::fs::write(journal.path(), log + "{\"torn").unwrap();
+        let compensations = journal.recover().await.unwrap();
+        let ops: Vec<&Operation> = compensations.iter().map(|c| &c.op).collect();
+        assert_eq!(ops, [&spawned, &created], "newest first");
+        assert!(compensations.iter().all(|c| c.ok), "{compensations:?}");
+        assert!(!worktree.exists());
+        assert!(!repo.join(".ccswarm/runs/run-1/pid").exists());
+        let events =
+            std::fs::read_to_string(repo.join(".ccswarm/runs/run-1/events.ndjson")).unwrap();
+        assert!(events.contains("run interrupted"));
+
+        // Everything is settled, so the log is empty and recovery is a no-op.
+        assert!(journal.open().await.unwrap().is_empty());
+        assert_eq!(std::fs::metadata(journal.path()).unwrap().len(), 0);
+        assert!(journal.recover().await.unwrap().is_empty());
+    }
+}
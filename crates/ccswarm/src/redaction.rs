@@ -0,0 +1,150 @@
+//! Project-defined redaction of command and provider output.
+//!
+//! Rules live under the `redaction` key of `ccswarm.json` and are applied by
+//! [`AISessionBridge`](crate::session::bridge::AISessionBridge) to everything
+//! a provider CLI prints, and by the flow engine to quality-gate output,
+//! before that text is stored in agent context, persisted sessions, run
+//! events, or anything exported from them:
+//!
+//! ```json
+//! "redaction": {
+//!   "rules": [
+//!     { "pattern": "[a-z0-9-]+\\.corp\\.example\\.com", "replacement": "<internal-host>" },
+//!     { "pattern": "cust_[0-9]{8}" }
+//!   ]
+//! }
+//! ```
+//!
+//! Replacements use `regex` syntax, so `$1` / `${name}` refer to capture
+//! groups. Rules run in order, each on the output of the previous one.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Redaction rules as written in the config file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RedactionConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RedactionRule>,
+}
+
+/// One regex and what to replace its matches with.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RedactionRule {
+    pub pattern: String,
+    #[serde(default = "default_replacement")]
+    pub replacement: String,
+}
+
+fn default_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+impl RedactionConfig {
+    /// Compile every rule, failing on the first invalid pattern.
+    pub fn compile(&self) -> Result<Redactor> {
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                Regex::new(&rule.pattern)
+                    .map(|regex| (regex, rule.replacement.clone()))
+                    .with_context(|| format!("redaction.rules[{i}].pattern is not a valid regex"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Redactor { rules })
+    }
+}
+
+/// Compiled redaction rules. The default redactor has no rules and returns
+/// its input untouched.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Also replace each of `values` verbatim, e.g. secrets handed to agents.
+    pub fn with_literals<'a>(mut self, values: impl IntoIterator<Item = &'a str>) -> Self {
+        self.rules.extend(
+            values
+                .into_iter()
+                .filter(|v| !v.is_empty())
+                .filter_map(|v| Regex::new(&regex::escape(v)).ok())
+                .map(|regex| (regex, default_replacement())),
+        );
+        self
+    }
+
+    /// Apply every rule to `text`. Borrows when nothing matched.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut out = Cow::Borrowed(text);
+        for (regex, replacement) in &self.rules {
+            if let Cow::Owned(replaced) = regex.replace_all(&out, replacement.as_str()) {
+                out = Cow::Owned(replaced);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(rules: &[(&str, Option<&str>)]) -> RedactionConfig {
+        RedactionConfig {
+            rules: rules
+                .iter()
+                .map(|(pattern, replacement)| RedactionRule {
+                    pattern: pattern.to_string(),
+                    replacement: replacement.map_or_else(default_replacement, str::to_string),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_rules_apply_in_order_with_capture_groups() -> Result<()> {
+        let redactor = config(&[
+            (r"([a-z0-9-]+)\.corp\.example\.com", Some("<host:$1>")),
+            (r"cust_[0-9]{8}", None),
+        ])
+        .compile()?;
+        assert_eq!(
+            redactor.redact("GET db-1.corp.example.com for cust_12345678"),
+            "GET <host:db-1> for [REDACTED]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unmatched_text_is_borrowed() -> Result<()> {
+        let redactor = config(&[("secret", None)]).compile()?;
+        assert!(matches!(redactor.redact("all clear"), Cow::Borrowed(_)));
+        assert!(Redactor::default().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_pattern_names_the_rule() {
+        let err = config(&[("ok", None), ("(unclosed", None)])
+            .compile()
+            .unwrap_err();
+        assert!(err.to_string().contains("redaction.rules[1]"));
+    }
+
+    #[test]
+    fn test_replacement_defaults_when_omitted() -> Result<()> {
+        let parsed: RedactionConfig = serde_json::from_str(r#"{"rules":[{"pattern":"x"}]}"#)?;
+        assert_eq!(parsed.rules[0].replacement, "[REDACTED]");
+        Ok(())
+    }
+}
@@ -1,6 +1,21 @@
-//! Run ID validation shared by CLI handlers and event recording.
+//! Run IDs: validation shared by CLI handlers and event recording, and the
+//! [`RunId`] of the ccswarm invocation itself.
+//!
+//! Every pipeline run has its own ID and directory under `.ccswarm/runs/`.
+//! One invocation (a `queue drain`, an `auto` loop) can start many of them,
+//! so the invocation gets a [`RunId`] too. It is fixed for the life of the
+//! process, exported as `CCSWARM_RUN_ID` so provider CLIs, hooks and nested
+//! ccswarm processes inherit it, and stamped on log lines, pipeline run
+//! summaries, queue attempts and provider transcripts; `ccswarm runs show`
+//! puts them back together.
 
 use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Environment variable carrying the invocation's [`RunId`] to child
+/// processes.
+pub const RUN_ID_ENV: &str = "CCSWARM_RUN_ID";
 
 /// Validate that a run ID only contains characters allowed in UUIDs / short hex.
 ///
@@ -25,10 +40,76 @@ pub fn validate_run_id(id: &str) -> Result<()> {
     Ok(())
 }
 
+/// ID of one ccswarm invocation, e.g. `20261017T091502-3f9c2a1b`. Sorts by
+/// start time; cannot be changed once made.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RunId(String);
+
+impl RunId {
+    /// A fresh ID stamped with the current time.
+    pub fn generate() -> Self {
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        Self(format!(
+            "{}-{}",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            &suffix[..8]
+        ))
+    }
+
+    /// This process's ID: the one inherited through `CCSWARM_RUN_ID` when a
+    /// ccswarm parent set it, otherwise generated on first use.
+    pub fn current() -> &'static RunId {
+        static CURRENT: OnceLock<RunId> = OnceLock::new();
+        CURRENT.get_or_init(|| {
+            std::env::var(RUN_ID_ENV)
+                .ok()
+                .and_then(|id| Self::try_from(id).ok())
+                .unwrap_or_else(Self::generate)
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for RunId {
+    type Error = anyhow::Error;
+
+    fn try_from(id: String) -> Result<Self> {
+        validate_run_id(&id)?;
+        Ok(Self(id))
+    }
+}
+
+impl From<RunId> for String {
+    fn from(id: RunId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for RunId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn run_id_is_stable_sortable_and_validated() {
+        assert_eq!(RunId::current(), RunId::current());
+        let id = RunId::generate();
+        assert!(validate_run_id(id.as_str()).is_ok());
+        assert_eq!(id.as_str().len(), "20261017T091502-3f9c2a1b".len());
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(serde_json::from_str::<RunId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<RunId>("\"../x\"").is_err());
+    }
+
     #[test]
     fn rejects_path_traversal() {
         assert!(validate_run_id("../etc/passwd").is_err());
@@ -0,0 +1,266 @@
+//! Ledger of ccswarm invocations, keyed by [`RunId`].
+//!
+//! An invocation that starts at least one pipeline run gets an append-only
+//! `.ccswarm/ledger/<run-id>.ndjson`: a `started` line (command, version,
+//! pid), one `pipeline_run` line per pipeline run it starts, and a
+//! `finished` line with the outcome. Entries are only ever appended, so the
+//! record of a run cannot be rewritten after the fact. `ccswarm runs list`
+//! and `ccswarm runs show` read it back.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+use crate::run_id::RunId;
+
+/// Directory under `.ccswarm/` holding one ledger file per invocation.
+pub const LEDGER_DIR: &str = "ledger";
+
+/// One ledger line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+pub enum LedgerEntry {
+    Started {
+        at: DateTime<Utc>,
+        command: Vec<String>,
+        version: String,
+        pid: u32,
+    },
+    PipelineRun {
+        at: DateTime<Utc>,
+        /// ID of the run directory under `.ccswarm/runs/`.
+        run_id: String,
+        flow: String,
+        /// First line of the task text.
+        task: String,
+    },
+    Finished {
+        at: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+/// An invocation as read back from its ledger.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunRecord {
+    pub id: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub command: Vec<String>,
+    pub version: Option<String>,
+    pub pid: Option<u32>,
+    pub pipeline_runs: Vec<PipelineRunRef>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineRunRef {
+    pub run_id: String,
+    pub flow: String,
+    pub task: String,
+    pub started_at: DateTime<Utc>,
+}
+
+impl RunRecord {
+    /// `running`, `completed`, `failed`, or `interrupted` when the process
+    /// died without finishing its ledger.
+    pub fn status(&self) -> &'static str {
+        match (&self.finished_at, &self.error) {
+            (Some(_), None) => "completed",
+            (Some(_), Some(_)) => "failed",
+            (None, _) => match self.pid {
+                Some(pid) if ai_session::core::suspend::is_alive(pid) => "running",
+                _ => "interrupted",
+            },
+        }
+    }
+
+    fn apply(&mut self, entry: LedgerEntry) {
+        match entry {
+            LedgerEntry::Started {
+                at,
+                command,
+                version,
+                pid,
+            } => {
+                self.started_at = Some(at);
+                self.command = command;
+                self.version = Some(version);
+                self.pid = Some(pid);
+            }
+            LedgerEntry::PipelineRun {
+                at,
+                run_id,
+                flow,
+                task,
+            } => self.pipeline_runs.push(PipelineRunRef {
+                run_id,
+                flow,
+                task,
+                started_at: at,
+            }),
+            LedgerEntry::Finished { at, error } => {
+                self.finished_at = Some(at);
+                self.error = error;
+            }
+        }
+    }
+}
+
+fn ledger_dir(repo: &Path) -> PathBuf {
+    repo.join(".ccswarm").join(LEDGER_DIR)
+}
+
+fn ledger_path(repo: &Path, id: &RunId) -> PathBuf {
+    ledger_dir(repo).join(format!("{id}.ndjson"))
+}
+
+async fn append(path: &Path, entry: &LedgerEntry) -> Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// Note that invocation `id` started pipeline run `run_id`, opening its
+/// ledger on the first call.
+pub async fn record_pipeline_run(
+    repo: &Path,
+    id: &RunId,
+    run_id: &str,
+    flow: &str,
+    task: &str,
+) -> Result<()> {
+    let path = ledger_path(repo, id);
+    if !path.exists() {
+        tokio::fs::create_dir_all(ledger_dir(repo)).await?;
+        append(
+            &path,
+            &LedgerEntry::Started {
+                at: Utc::now(),
+                command: std::env::args().collect(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                pid: std::process::id(),
+            },
+        )
+        .await?;
+    }
+    append(
+        &path,
+        &LedgerEntry::PipelineRun {
+            at: Utc::now(),
+            run_id: run_id.to_string(),
+            flow: flow.to_string(),
+            task: task.lines().next().unwrap_or_default().to_string(),
+        },
+    )
+    .await
+}
+
+/// Close invocation `id`'s ledger, if it opened one.
+pub async fn finish(repo: &Path, id: &RunId, error: Option<String>) -> Result<()> {
+    let path = ledger_path(repo, id);
+    if !path.exists() {
+        return Ok(());
+    }
+    append(
+        &path,
+        &LedgerEntry::Finished {
+            at: Utc::now(),
+            error,
+        },
+    )
+    .await
+}
+
+/// Invocation `id`'s record, or `None` if it has no ledger.
+pub async fn load(repo: &Path, id: &RunId) -> Result<Option<RunRecord>> {
+    let path = ledger_path(repo, id);
+    let text = match tokio::fs::read_to_string(&path).await {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let mut record = RunRecord {
+        id: id.to_string(),
+        ..Default::default()
+    };
+    // A line cut short by a crash is skipped, not fatal.
+    for entry in text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+    {
+        record.apply(entry);
+    }
+    Ok(Some(record))
+}
+
+/// Every recorded invocation, newest first.
+pub async fn list(repo: &Path) -> Result<Vec<RunRecord>> {
+    let mut ids = Vec::new();
+    let mut entries = match tokio::fs::read_dir(ledger_dir(repo)).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if let Some(id) = name
+            .strip_suffix(".ndjson")
+            .and_then(|id| RunId::try_from(id.to_string()).ok())
+        {
+            ids.push(id);
+        }
+    }
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+    let mut records = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(record) = load(repo, &id).await? {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn ledger_records_pipeline_runs_and_the_outcome() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = RunId::generate();
+        finish(dir.path(), &id, None).await.unwrap();
+        assert!(
+            list(dir.path()).await.unwrap().is_empty(),
+            "no runs, no ledger"
+        );
+
+        record_pipeline_run(dir.path(), &id, "run-a", "default", "Add login\nmore")
+            .await
+            .unwrap();
+        record_pipeline_run(dir.path(), &id, "run-b", "default", "Fix tests")
+            .await
+            .unwrap();
+        let record = load(dir.path(), &id).await.unwrap().unwrap();
+        assert_eq!(record.status(), "running");
+        assert_eq!(record.pipeline_runs.len(), 2);
+        assert_eq!(record.pipeline_runs[0].task, "Add login");
+
+        finish(dir.path(), &id, Some("boom".into())).await.unwrap();
+        let runs = list(dir.path()).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].status(), "failed");
+        assert_eq!(runs[0].error.as_deref(), Some("boom"));
+    }
+}
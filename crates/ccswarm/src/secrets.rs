@@ -0,0 +1,467 @@
+//! Which secrets each agent role may receive.
+//!
+//! `secrets.grants` lists, per role, the environment variables that role is
+//! given. Every name granted to any role is governed: provider calls for a
+//! role see only the governed variables granted to it, whatever the
+//! orchestrator's own environment holds. Variables no grant mentions pass
+//! through as before.
+//!
+//! ```yaml
+//! secrets:
+//!   env_file: .ccswarm/secrets.env
+//!   grants:
+//!     backend: [DATABASE_URL, STRIPE_KEY]
+//!     coder: [NPM_TOKEN]
+//! ```
+//!
+//! Values come from `env_file` (dotenv syntax, relative to the repository)
+//! and then from the process environment. A role is the stage's `agent`
+//! for pipeline calls, else its persona, and the agent name for worktrees.
+//!
+//! `ccswarm worktree bootstrap` and `ccswarm secrets sync` write an agent's
+//! grants to a `.env` in its worktree, rewriting it with current values each
+//! time; the file is removed again by `ccswarm secrets revoke` and when the
+//! daemon stops. Only files carrying ccswarm's header are ever overwritten
+//! or removed. The file is added to the repository's `info/exclude` so agent
+//! commits leave it out, and is not written where git tracks a `.env`.
+//! Granted values are also redacted from provider output.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::git::shell::git;
+
+/// The scoped secrets file written into an agent's worktree.
+pub const ENV_FILE: &str = ".env";
+
+/// Where the secrets file is written before it is renamed into place.
+const TMP_FILE: &str = ".env.ccswarm-tmp";
+
+/// First line of every file ccswarm writes; anything else is left alone.
+const HEADER: &str = "# Scoped secrets written by ccswarm";
+
+/// The `secrets` config knob.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecretsConfig {
+    /// Dotenv file the values are read from, relative to the repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+    /// Variable names each role may receive, by role.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub grants: BTreeMap<String, Vec<String>>,
+}
+
+impl SecretsConfig {
+    /// Resolve every granted value from `env_file` and the environment.
+    pub fn load(&self, repo: &Path) -> Result<SecretScope> {
+        let mut grants = BTreeMap::new();
+        for (role, names) in &self.grants {
+            for name in names {
+                if !is_env_name(name) {
+                    bail!("secrets.grants.{role}: '{name}' is not an environment variable name");
+                }
+            }
+            grants.insert(role.clone(), names.iter().cloned().collect::<BTreeSet<_>>());
+        }
+        let from_file = match &self.env_file {
+            Some(file) => {
+                let path = repo.join(file);
+                parse_dotenv(
+                    &std::fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?,
+                )
+            }
+            None => BTreeMap::new(),
+        };
+        let values = grants
+            .values()
+            .flatten()
+            .filter_map(|name| {
+                from_file
+                    .get(name)
+                    .cloned()
+                    .or_else(|| std::env::var(name).ok())
+                    .map(|value| (name.clone(), value))
+            })
+            .collect();
+        Ok(SecretScope { grants, values })
+    }
+}
+
+/// Grants with their values resolved.
+#[derive(Debug, Clone, Default)]
+pub struct SecretScope {
+    grants: BTreeMap<String, BTreeSet<String>>,
+    values: BTreeMap<String, String>,
+}
+
+impl SecretScope {
+    pub fn is_empty(&self) -> bool {
+        self.grants.is_empty()
+    }
+
+    pub fn roles(&self) -> impl Iterator<Item = &str> {
+        self.grants.keys().map(String::as_str)
+    }
+
+    /// Names granted to `role`, each with its value if one was found.
+    pub fn granted(&self, role: &str) -> Vec<(&str, Option<&str>)> {
+        self.grants
+            .get(role)
+            .into_iter()
+            .flatten()
+            .map(|name| (name.as_str(), self.values.get(name).map(String::as_str)))
+            .collect()
+    }
+
+    /// Every resolved value, for redaction.
+    pub fn values(&self) -> impl Iterator<Item = &str> {
+        self.values.values().map(String::as_str)
+    }
+
+    /// Give `cmd` exactly the governed variables granted to `role`.
+    pub fn apply(&self, cmd: &mut tokio::process::Command, role: &str) {
+        for name in self.grants.values().flatten() {
+            cmd.env_remove(name);
+        }
+        for (name, value) in self.granted(role) {
+            if let Some(value) = value {
+                cmd.env(name, value);
+            }
+        }
+    }
+
+    /// Write `role`'s grants to `worktree/.env`, replacing a file written
+    /// earlier. `None` when the role has no grants.
+    pub async fn write_env_file(&self, worktree: &Path, role: &str) -> Result<Option<PathBuf>> {
+        let granted = self.granted(role);
+        if granted.is_empty() {
+            return Ok(None);
+        }
+        let path = worktree.join(ENV_FILE);
+        if let Ok(existing) = tokio::fs::read_to_string(&path).await
+            && !existing.starts_with(HEADER)
+        {
+            bail!(
+                "{} exists and was not written by ccswarm; not replacing it",
+                path.display()
+            );
+        }
+        exclude_from_commits(worktree).await?;
+        let mut text = format!("{HEADER} for '{role}'; removed when the agent stops.\n");
+        for (name, value) in granted {
+            match value {
+                Some(value) => text.push_str(&format!("{name}={}\n", quote(value))),
+                None => text.push_str(&format!("# {name} is granted but not set\n")),
+            }
+        }
+        let tmp = worktree.join(TMP_FILE);
+        write_private(&tmp, &text).await?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(Some(path))
+    }
+}
+
+/// Remove the scoped secrets file from `worktree`, if ccswarm wrote one.
+pub async fn revoke(worktree: &Path) -> Result<bool> {
+    let path = worktree.join(ENV_FILE);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(text) if text.starts_with(HEADER) => {
+            tokio::fs::remove_file(&path)
+                .await
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Whether `worktree` holds a scoped secrets file.
+pub async fn is_scoped(worktree: &Path) -> bool {
+    tokio::fs::read_to_string(worktree.join(ENV_FILE))
+        .await
+        .is_ok_and(|text| text.starts_with(HEADER))
+}
+
+/// Make sure the secrets file in `worktree` cannot be committed: agent
+/// commits stage everything with `git add .`. Adds it to the repository's
+/// `info/exclude` unless git already ignores it; refuses when it is tracked.
+/// Nothing to do outside a git repository.
+async fn exclude_from_commits(worktree: &Path) -> Result<()> {
+    let Ok(exclude) = git(
+        worktree,
+        &[
+            "rev-parse",
+            "--path-format=absolute",
+            "--git-path",
+            "info/exclude",
+        ],
+    )
+    .await
+    else {
+        return Ok(());
+    };
+    if git(worktree, &["ls-files", "--error-unmatch", ENV_FILE])
+        .await
+        .is_ok()
+    {
+        bail!(
+            "{} is tracked by git; not writing secrets where they would be committed",
+            worktree.join(ENV_FILE).display()
+        );
+    }
+    let ignored = || git(worktree, &["check-ignore", "-q", ENV_FILE]);
+    if ignored().await.is_ok() {
+        return Ok(());
+    }
+    let exclude = PathBuf::from(exclude.trim());
+    let mut text = tokio::fs::read_to_string(&exclude)
+        .await
+        .unwrap_or_default();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&format!("{HEADER}\n/{ENV_FILE}\n/{TMP_FILE}\n"));
+    if let Some(dir) = exclude.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(&exclude, text)
+        .await
+        .with_context(|| format!("Failed to write {}", exclude.display()))?;
+    ignored().await.with_context(|| {
+        format!(
+            "git still does not ignore {}",
+            worktree.join(ENV_FILE).display()
+        )
+    })?;
+    Ok(())
+}
+
+async fn write_private(path: &Path, text: &str) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(text.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A dotenv value: bare when that is unambiguous, else double-quoted.
+fn quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.:/@+=,".contains(c))
+    {
+        return value.to_string();
+    }
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '$' => out.push_str("\\$"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `KEY=value` lines, with optional `export`, comments and quotes.
+fn parse_dotenv(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            if line.starts_with('#') {
+                return None;
+            }
+            let (name, value) = line.split_once('=')?;
+            let name = name.trim();
+            if !is_env_name(name) {
+                return None;
+            }
+            let value = value.trim();
+            let value = if let Some(inner) =
+                value.strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+            {
+                let mut out = String::new();
+                let mut chars = inner.chars();
+                while let Some(c) = chars.next() {
+                    match (c, c == '\\') {
+                        (_, true) => match chars.next() {
+                            Some('n') => out.push('\n'),
+                            Some(other) => out.push(other),
+                            None => out.push('\\'),
+                        },
+                        (c, false) => out.push(c),
+                    }
+                }
+                out
+            } else if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\''))
+            {
+                inner.to_string()
+            } else {
+                value.to_string()
+            };
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn roles_get_only_their_grants_in_env_and_worktree() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::write(
+            repo.path().join("secrets.env"),
+            "# test\nexport DB_URL=\"postgres://u:p@h/db\"\nSTRIPE_KEY='sk $x \"y\"'\n",
+        )
+        .unwrap();
+        let config: SecretsConfig = serde_json::from_str(
+            r#"{"env_file": "secrets.env",
+                "grants": {"backend": ["DB_URL", "STRIPE_KEY", "CCSWARM_TEST_UNSET_SECRET"],
+                           "frontend": ["DB_URL"]}}"#,
+        )
+        .unwrap();
+        let scope = config.load(repo.path()).unwrap();
+        assert_eq!(
+            scope.granted("frontend"),
+            [("DB_URL", Some("postgres://u:p@h/db"))]
+        );
+        assert!(scope.granted("qa").is_empty());
+
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.env("STRIPE_KEY", "leaked").env("UNRELATED", "kept");
+        scope.apply(&mut cmd, "frontend");
+        let out = cmd
+            .args(["-c", "echo \"$DB_URL|${STRIPE_KEY-unset}|$UNRELATED\""])
+            .output()
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout),
+            "postgres://u:p@h/db|unset|kept\n"
+        );
+
+        // Written, read back, rewritten in place, then revoked.
+        let worktree = tempfile::tempdir().unwrap();
+        let path = scope
+            .write_env_file(worktree.path(), "backend")
+            .await
+            .unwrap()
+            .unwrap();
+        let written = parse_dotenv(&std::fs::read_to_string(&path).unwrap());
+        assert_eq!(written["STRIPE_KEY"], "sk $x \"y\"");
+        assert!(!written.contains_key("CCSWARM_TEST_UNSET_SECRET"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert!(
+            scope
+                .write_env_file(worktree.path(), "backend")
+                .await
+                .is_ok()
+        );
+        assert!(
+            scope
+                .write_env_file(worktree.path(), "qa")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(revoke(worktree.path()).await.unwrap());
+        assert!(!path.exists());
+
+        // A user's own .env is neither replaced nor removed.
+        std::fs::write(&path, "MINE=1\n").unwrap();
+        assert!(
+            scope
+                .write_env_file(worktree.path(), "backend")
+                .await
+                .is_err()
+        );
+        assert!(!revoke(worktree.path()).await.unwrap());
+
+        let bad: SecretsConfig =
+            serde_json::from_str(r#"{"grants": {"qa": ["NOT-A-NAME"]}}"#).unwrap();
+        assert!(bad.load(repo.path()).is_err());
+    }
+
+    #[tokio::test]
+    async fn the_secrets_file_is_never_committed() {
+        let scope = SecretScope {
+            grants: BTreeMap::from([(
+                "backend".to_string(),
+                BTreeSet::from(["TOKEN".to_string()]),
+            )]),
+            values: BTreeMap::from([("TOKEN".to_string(), "s3cret".to_string())]),
+        };
+        let repo = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "t@example.com"],
+            &["config", "user.name", "t"],
+            &["commit", "-q", "--allow-empty", "-m", "init"],
+        ] {
+            git(repo.path(), args).await.unwrap();
+        }
+
+        scope
+            .write_env_file(repo.path(), "backend")
+            .await
+            .unwrap()
+            .unwrap();
+        std::fs::write(repo.path().join("main.rs"), "fn main() {}\n").unwrap();
+        crate::git::shell::ShellWorktreeManager::new(repo.path().to_path_buf())
+            .unwrap()
+            .commit_worktree_changes(repo.path(), "work")
+            .await
+            .unwrap();
+        let committed = git(repo.path(), &["show", "--name-only", "--format=", "HEAD"])
+            .await
+            .unwrap();
+        assert_eq!(committed.trim(), "main.rs");
+
+        // A tracked .env would be committed on the next change; refuse.
+        assert!(revoke(repo.path()).await.unwrap());
+        std::fs::write(repo.path().join(ENV_FILE), "MINE=1\n").unwrap();
+        git(repo.path(), &["add", "-f", ENV_FILE]).await.unwrap();
+        git(repo.path(), &["commit", "-q", "-m", "env"])
+            .await
+            .unwrap();
+        std::fs::write(repo.path().join(ENV_FILE), format!("{HEADER}\n")).unwrap();
+        let err = scope
+            .write_env_file(repo.path(), "backend")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("tracked by git"), "{err:#}");
+    }
+}
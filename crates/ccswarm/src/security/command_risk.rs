@@ -0,0 +1,739 @@
+//! Static risk analysis of shell commands, for approval before they run.
+//!
+//! [`assess`] splits a command line into its simple commands (on `;`, `&&`,
+//! `||`, `|` and newlines, honouring quotes), looks through wrappers such as
+//! `sudo`, `env`, `xargs` and `sh -c`, and records what each one does that a
+//! reviewer would want to know about: deleting or overwriting files,
+//! rewriting git history, publishing, piping a download into a shell,
+//! changing permissions. Every finding carries a 0–10 score; the command's
+//! score is the highest of them.
+//!
+//! When auto-accept is on (`auto_accept` in `ccswarm.json`), a typed-tools
+//! `run_command` scoring above `risk_threshold` is held for approval. The
+//! reviewer sees [`CommandAssessment::summary`] straight away and a
+//! provider-written explanation from [`explanation_prompt`] once it arrives.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Score above which a finding reads as destructive in listings.
+pub const HIGH_RISK: u8 = 7;
+
+/// One thing a command does that deserves a reviewer's attention.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskFinding {
+    /// 0 (harmless) to 10 (destroys the machine).
+    pub score: u8,
+    /// What happens, as a plain-English clause ("deletes build/ recursively").
+    pub effect: String,
+}
+
+/// Result of [`assess`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandAssessment {
+    pub command: String,
+    /// Highest finding score, 0 when nothing was recognised.
+    pub score: u8,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<RiskFinding>,
+}
+
+impl CommandAssessment {
+    /// Whether the command needs approval under `threshold`.
+    pub fn exceeds(&self, threshold: u8) -> bool {
+        self.score > threshold
+    }
+
+    /// One sentence naming every finding, in command order.
+    pub fn summary(&self) -> String {
+        if self.findings.is_empty() {
+            return "No risky operations recognised.".to_string();
+        }
+        let clauses: Vec<&str> = self.findings.iter().map(|f| f.effect.as_str()).collect();
+        let mut sentence = clauses.join("; then ");
+        if let Some(first) = sentence.get(..1) {
+            sentence.replace_range(..1, &first.to_uppercase());
+        }
+        sentence.push('.');
+        sentence
+    }
+}
+
+/// Writes the natural-language explanation of a held command.
+#[async_trait]
+pub trait CommandExplainer: Send + Sync {
+    async fn explain(&self, assessment: &CommandAssessment) -> Result<String>;
+}
+
+/// Prompt asking a provider to explain `assessment` to a reviewer.
+pub fn explanation_prompt(assessment: &CommandAssessment) -> String {
+    let mut prompt = String::from(
+        "An agent wants to run the shell command below and it was flagged as risky. \
+         Explain in two or three plain sentences what it will do, for a reviewer who has \
+         to allow or refuse it. Name anything it deletes, overwrites, publishes, downloads \
+         or cannot undo. Do not suggest alternatives and do not run anything.\n\n\
+         Command:\n```sh\n",
+    );
+    prompt.push_str(&assessment.command);
+    prompt.push_str("\n```\n");
+    if !assessment.findings.is_empty() {
+        prompt.push_str("\nStatic analysis found:\n");
+        for finding in &assessment.findings {
+            prompt.push_str(&format!("- [{}/10] {}\n", finding.score, finding.effect));
+        }
+    }
+    prompt
+}
+
+/// Analyse `command` without running it.
+pub fn assess(command: &str) -> CommandAssessment {
+    let mut findings = Vec::new();
+    assess_into(command, &mut findings, 0);
+    CommandAssessment {
+        command: command.to_string(),
+        score: findings.iter().map(|f| f.score).max().unwrap_or(0),
+        findings,
+    }
+}
+
+/// `sh -c` nesting deeper than this is not unpacked.
+const MAX_NESTING: usize = 3;
+
+fn assess_into(command: &str, findings: &mut Vec<RiskFinding>, depth: usize) {
+    if command.contains(":(){") || command.contains(":() {") {
+        push(
+            findings,
+            10,
+            "starts a fork bomb that exhausts the machine's processes",
+        );
+    }
+    let segments = split_segments(command);
+    for (i, segment) in segments.iter().enumerate() {
+        let piped_into_shell = segments
+            .get(i + 1)
+            .filter(|next| next.piped)
+            .and_then(|next| program(&next.words))
+            .is_some_and(|(name, _)| SHELLS.contains(&name));
+        assess_segment(segment, piped_into_shell, findings, depth);
+    }
+}
+
+/// Programs that run their stdin as a script.
+const SHELLS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "ksh", "python", "python3", "perl", "ruby", "node",
+];
+
+fn assess_segment(
+    segment: &Segment,
+    piped_into_shell: bool,
+    findings: &mut Vec<RiskFinding>,
+    depth: usize,
+) {
+    for redirect in &segment.redirects {
+        assess_redirect(redirect, findings);
+    }
+    let words = &segment.words;
+    if words.first().is_some_and(|w| w == "sudo" || w == "doas") {
+        let target = program(words).map_or("it", |(name, _)| name);
+        push(findings, 6, &format!("runs {target} as root"));
+    }
+    let Some((name, args)) = program(words) else {
+        return;
+    };
+    let flags = Flags::new(args);
+    let operands = operands(args);
+
+    match name {
+        "rm" | "rmdir" | "unlink" | "shred" => {
+            let targets = if operands.is_empty() {
+                "the paths it is given".to_string()
+            } else {
+                list(&operands)
+            };
+            let sweeping = operands.iter().any(|o| {
+                matches!(
+                    o.as_str(),
+                    "/" | "/*" | "~" | "~/" | "*" | "." | ".." | "$HOME"
+                )
+            });
+            if flags.has('r') || flags.has('R') || flags.long("recursive") {
+                let score = if sweeping { 10 } else { 8 };
+                let prompt = if flags.has('f') || flags.long("force") {
+                    " without asking"
+                } else {
+                    ""
+                };
+                push(
+                    findings,
+                    score,
+                    &format!("deletes {targets} and everything under it{prompt}"),
+                );
+            } else {
+                push(
+                    findings,
+                    if sweeping { 8 } else { 5 },
+                    &format!("deletes {targets}"),
+                );
+            }
+        }
+        "find"
+            if args.iter().any(|a| a == "-delete")
+                || args
+                    .windows(2)
+                    .any(|w| w[0] == "-exec" && matches!(w[1].as_str(), "rm" | "shred")) =>
+        {
+            let root = operands.first().map_or(".", String::as_str);
+            push(
+                findings,
+                7,
+                &format!("deletes every file find matches under {root}"),
+            );
+        }
+        "git" => assess_git(args, findings),
+        "curl" | "wget" => {
+            let url = operands
+                .iter()
+                .find(|o| o.contains("://"))
+                .or(operands.first())
+                .map_or("a URL", String::as_str);
+            if piped_into_shell {
+                push(
+                    findings,
+                    9,
+                    &format!("downloads {url} and runs it as a script"),
+                );
+            } else if flags.has('d')
+                || flags.has('F')
+                || flags.has('T')
+                || flags.long("data")
+                || flags.long("upload-file")
+                || args.windows(2).any(|w| {
+                    w[0] == "-X"
+                        && matches!(w[1].to_uppercase().as_str(), "POST" | "PUT" | "DELETE")
+                })
+            {
+                push(findings, 5, &format!("sends data to {url}"));
+            } else {
+                push(findings, 2, &format!("downloads {url}"));
+            }
+        }
+        "chmod" | "chown" | "chgrp" => {
+            let what = if name == "chmod" {
+                "permissions"
+            } else {
+                "ownership"
+            };
+            let targets = list(operands.get(1..).unwrap_or_default());
+            if name == "chmod"
+                && operands.first().is_some_and(|mode| {
+                    mode == "777" || mode == "666" || mode.contains("o+w") || mode == "a+rwx"
+                })
+            {
+                push(
+                    findings,
+                    6,
+                    &format!("makes {targets} writable by everyone"),
+                );
+            } else if flags.has('R') || flags.long("recursive") {
+                push(
+                    findings,
+                    6,
+                    &format!("changes {what} of everything under {targets}"),
+                );
+            } else {
+                push(findings, 3, &format!("changes {what} of {targets}"));
+            }
+        }
+        "dd" => {
+            let output = args
+                .iter()
+                .find_map(|a| a.strip_prefix("of="))
+                .unwrap_or("stdout");
+            let score = if output.starts_with("/dev/") && output != "/dev/null" {
+                10
+            } else {
+                6
+            };
+            push(findings, score, &format!("writes raw bytes to {output}"));
+        }
+        n if n.starts_with("mkfs") || matches!(n, "fdisk" | "parted" | "wipefs") => {
+            push(
+                findings,
+                10,
+                &format!("reformats or repartitions {}", list(&operands)),
+            );
+        }
+        "shutdown" | "reboot" | "halt" | "poweroff" => {
+            push(findings, 10, "shuts down or restarts the machine");
+        }
+        "kill" | "pkill" | "killall" => {
+            push(
+                findings,
+                5,
+                &format!("terminates processes {}", list(&operands)),
+            );
+        }
+        "mv" if operands.last().is_some_and(|dest| dest == "/dev/null") => {
+            push(
+                findings,
+                8,
+                &format!("destroys {}", list(&operands[..operands.len() - 1])),
+            );
+        }
+        "truncate" => push(findings, 5, &format!("truncates {}", list(&operands))),
+        "crontab" if flags.has('r') => push(findings, 7, "deletes the user's crontab"),
+        "eval" | "source" | "." => {
+            push(findings, 5, "runs dynamically built shell code");
+        }
+        "docker" | "podman" => assess_docker(name, args, findings),
+        "npm" | "pnpm" | "yarn" | "pip" | "pip3" | "cargo" | "gem" | "go" | "apt" | "apt-get"
+        | "yum" | "dnf" | "brew" | "apk" => {
+            let sub = operands.first().map(String::as_str);
+            if matches!(sub, Some("install" | "add" | "i" | "get")) {
+                let packages = list(operands.get(1..).unwrap_or_default());
+                let system = matches!(name, "apt" | "apt-get" | "yum" | "dnf" | "brew" | "apk")
+                    || flags.has('g')
+                    || flags.long("global");
+                if system {
+                    push(
+                        findings,
+                        5,
+                        &format!("installs system-wide packages {packages}"),
+                    );
+                } else if !packages.is_empty() && packages != "nothing" {
+                    push(findings, 3, &format!("installs packages {packages}"));
+                }
+            } else if sub == Some("publish") && matches!(name, "npm" | "pnpm" | "yarn" | "cargo") {
+                push(findings, 8, "publishes the package to its public registry");
+            }
+        }
+        "psql" | "mysql" | "sqlite3" | "mongo" | "mongosh" | "redis-cli" => {
+            let text = args.join(" ").to_lowercase();
+            if [
+                "drop ",
+                "truncate ",
+                "delete from",
+                "flushall",
+                "flushdb",
+                "dropdatabase",
+            ]
+            .iter()
+            .any(|kw| text.contains(kw))
+            {
+                push(
+                    findings,
+                    8,
+                    &format!("runs destructive statements against {name}"),
+                );
+            }
+        }
+        n if SHELLS.contains(&n) && depth < MAX_NESTING => {
+            if let Some(script) = args
+                .windows(2)
+                .find(|w| w[0] == "-c" || w[0] == "-lc" || w[0] == "-e")
+                .map(|w| w[1].clone())
+            {
+                assess_into(&script, findings, depth + 1);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn assess_git(args: &[String], findings: &mut Vec<RiskFinding>) {
+    // Skip global options such as `-C dir` to reach the subcommand.
+    let mut rest = args;
+    while let Some(first) = rest.first() {
+        if first == "-C" || first == "-c" {
+            rest = rest.get(2..).unwrap_or_default();
+        } else if first.starts_with('-') {
+            rest = &rest[1..];
+        } else {
+            break;
+        }
+    }
+    let Some((sub, sub_args)) = rest.split_first() else {
+        return;
+    };
+    let flags = Flags::new(sub_args);
+    let operands = operands(sub_args);
+    match sub.as_str() {
+        "push" => {
+            let remote = operands
+                .first()
+                .map_or("the upstream remote", String::as_str);
+            let deleting = flags.long("delete")
+                || flags.has('d')
+                || operands.iter().skip(1).any(|r| r.starts_with(':'));
+            if flags.has('f')
+                || flags.long("force")
+                || flags.long("force-with-lease")
+                || operands.iter().skip(1).any(|r| r.starts_with('+'))
+            {
+                push(
+                    findings,
+                    8,
+                    &format!("force-pushes to {remote}, overwriting its history"),
+                );
+            } else if deleting {
+                push(findings, 8, &format!("deletes branches on {remote}"));
+            } else {
+                push(findings, 5, &format!("publishes commits to {remote}"));
+            }
+        }
+        "reset" if flags.long("hard") => {
+            push(
+                findings,
+                7,
+                "discards all uncommitted changes in the working tree",
+            );
+        }
+        "clean" if flags.has('f') || flags.long("force") => {
+            let ignored = if flags.has('x') { " and ignored" } else { "" };
+            push(
+                findings,
+                7,
+                &format!("permanently deletes untracked{ignored} files"),
+            );
+        }
+        "checkout" | "restore" if sub_args.iter().any(|a| a == "." || a == "--") => {
+            push(findings, 6, "discards uncommitted changes to tracked files");
+        }
+        "branch" if flags.has('D') => {
+            push(
+                findings,
+                6,
+                &format!("deletes branch {} even if unmerged", list(&operands)),
+            );
+        }
+        "stash"
+            if operands
+                .first()
+                .is_some_and(|o| o == "drop" || o == "clear") =>
+        {
+            push(findings, 6, "throws away stashed changes");
+        }
+        "rebase" | "filter-branch" | "filter-repo" => {
+            push(findings, 4, "rewrites commit history");
+        }
+        _ => {}
+    }
+}
+
+fn assess_docker(name: &str, args: &[String], findings: &mut Vec<RiskFinding>) {
+    let operands = operands(args);
+    let sub: Vec<&str> = operands.iter().take(2).map(String::as_str).collect();
+    match sub.as_slice() {
+        ["rm" | "rmi", ..] | ["volume" | "image" | "container", "rm" | "prune"] => {
+            push(
+                findings,
+                6,
+                &format!("removes {name} containers, images or volumes"),
+            );
+        }
+        ["system", "prune"] => push(findings, 7, &format!("prunes all unused {name} data")),
+        ["run", ..] if args.iter().any(|a| a == "--privileged") => {
+            push(
+                findings,
+                7,
+                "starts a privileged container with full host access",
+            );
+        }
+        _ => {}
+    }
+}
+
+fn assess_redirect(redirect: &Redirect, findings: &mut Vec<RiskFinding>) {
+    let target = redirect.target.as_str();
+    if target == "/dev/null" || target.starts_with('&') {
+        return;
+    }
+    let outside = (target.starts_with('/') && !target.starts_with("/tmp/"))
+        || target.starts_with('~')
+        || target.starts_with("$HOME")
+        || target.split('/').any(|part| part == "..");
+    match (redirect.append, outside) {
+        (false, true) => push(
+            findings,
+            6,
+            &format!("overwrites {target} outside the working directory"),
+        ),
+        (true, true) => push(
+            findings,
+            4,
+            &format!("appends to {target} outside the working directory"),
+        ),
+        (false, false) => push(findings, 2, &format!("overwrites {target}")),
+        (true, false) => {}
+    }
+}
+
+fn push(findings: &mut Vec<RiskFinding>, score: u8, effect: &str) {
+    findings.push(RiskFinding {
+        score,
+        effect: effect.to_string(),
+    });
+}
+
+/// The program a simple command runs and its arguments, looking through
+/// privilege and environment wrappers.
+fn program(words: &[String]) -> Option<(&str, &[String])> {
+    let mut rest = words;
+    loop {
+        let (first, tail) = rest.split_first()?;
+        let name = first.rsplit('/').next().unwrap_or(first);
+        let is_assignment = !first.starts_with('=')
+            && first
+                .split_once('=')
+                .is_some_and(|(var, _)| var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        if is_assignment {
+            rest = tail;
+            continue;
+        }
+        match name {
+            "sudo" | "doas" | "env" | "nohup" | "time" | "exec" | "nice" | "command" | "xargs" => {
+                // Skip the wrapper's own options.
+                let skip = tail.iter().take_while(|w| w.starts_with('-')).count();
+                rest = &tail[skip..];
+            }
+            _ => return Some((name, tail)),
+        }
+    }
+}
+
+/// Non-option arguments.
+fn operands(args: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut options_done = false;
+    for arg in args {
+        if options_done || !arg.starts_with('-') || arg == "-" {
+            out.push(arg.clone());
+        } else if arg == "--" {
+            options_done = true;
+        }
+    }
+    out
+}
+
+fn list(items: &[String]) -> String {
+    match items {
+        [] => "nothing".to_string(),
+        [one] => one.clone(),
+        many if many.len() <= 4 => many.join(", "),
+        many => format!("{} and {} more", many[..3].join(", "), many.len() - 3),
+    }
+}
+
+/// Short (`-rf`) and long (`--force`) options of one command.
+struct Flags<'a> {
+    short: String,
+    long: Vec<&'a str>,
+}
+
+impl<'a> Flags<'a> {
+    fn new(args: &'a [String]) -> Self {
+        let mut short = String::new();
+        let mut long = Vec::new();
+        for arg in args {
+            if arg == "--" {
+                break;
+            }
+            if let Some(name) = arg.strip_prefix("--") {
+                long.push(name.split('=').next().unwrap_or(name));
+            } else if let Some(letters) = arg.strip_prefix('-')
+                && letters.chars().all(|c| c.is_ascii_alphabetic())
+            {
+                short.push_str(letters);
+            }
+        }
+        Self { short, long }
+    }
+
+    fn has(&self, letter: char) -> bool {
+        self.short.contains(letter)
+    }
+
+    fn long(&self, name: &str) -> bool {
+        self.long.contains(&name)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Redirect {
+    target: String,
+    append: bool,
+}
+
+/// One simple command of a command line.
+#[derive(Debug, Default)]
+struct Segment {
+    words: Vec<String>,
+    redirects: Vec<Redirect>,
+    /// Reads the previous segment's output through `|`.
+    piped: bool,
+}
+
+/// Split a command line into simple commands, with shell quoting removed.
+/// Command substitutions and subshells are treated as plain text.
+fn split_segments(command: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut current = Segment::default();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut pending_redirect: Option<bool> = None;
+    let mut chars = command.chars().peekable();
+
+    let finish_word = |word: &mut String,
+                       in_word: &mut bool,
+                       current: &mut Segment,
+                       pending: &mut Option<bool>| {
+        if !*in_word {
+            return;
+        }
+        let text = std::mem::take(word);
+        match pending.take() {
+            Some(append) => current.redirects.push(Redirect {
+                target: text,
+                append,
+            }),
+            None => current.words.push(text),
+        }
+        *in_word = false;
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for q in chars.by_ref() {
+                    if q == '\'' {
+                        break;
+                    }
+                    word.push(q);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(q) = chars.next() {
+                    match q {
+                        '"' => break,
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                word.push(escaped);
+                            }
+                        }
+                        _ => word.push(q),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(escaped) = chars.next()
+                    && escaped != '\n'
+                {
+                    word.push(escaped);
+                }
+            }
+            '>' => {
+                // `2>` and `&>` name a descriptor, not a word.
+                if in_word && word.chars().all(|d| d.is_ascii_digit()) {
+                    word.clear();
+                    in_word = false;
+                }
+                finish_word(&mut word, &mut in_word, &mut current, &mut pending_redirect);
+                let append = chars.next_if_eq(&'>').is_some();
+                if chars.next_if_eq(&'&').is_some() {
+                    // `>&2`: duplicating a descriptor writes no file.
+                    while chars.next_if(|d| d.is_ascii_digit() || *d == '-').is_some() {}
+                    continue;
+                }
+                pending_redirect = Some(append);
+            }
+            ';' | '\n' | '|' | '&' => {
+                finish_word(&mut word, &mut in_word, &mut current, &mut pending_redirect);
+                if c == '&' && chars.peek() == Some(&'>') {
+                    continue;
+                }
+                let piped = c == '|' && chars.next_if_eq(&'|').is_none();
+                if c == '&' {
+                    chars.next_if_eq(&'&');
+                }
+                if !current.words.is_empty() || !current.redirects.is_empty() {
+                    segments.push(std::mem::take(&mut current));
+                }
+                current.piped = piped;
+            }
+            c if c.is_whitespace() => {
+                finish_word(&mut word, &mut in_word, &mut current, &mut pending_redirect);
+            }
+            '(' | ')' | '{' | '}' | '`' if !in_word => {
+                finish_word(&mut word, &mut in_word, &mut current, &mut pending_redirect);
+            }
+            _ => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    finish_word(&mut word, &mut in_word, &mut current, &mut pending_redirect);
+    if !current.words.is_empty() || !current.redirects.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_scores_destructive_commands() {
+        let cases = [
+            ("cargo test --workspace", 0),
+            ("ls -la | grep foo > out.txt", 2),
+            ("rm notes.txt", 5),
+            ("rm -rf build/ && cargo build", 8),
+            ("sudo rm -rf /", 10),
+            ("git push origin main", 5),
+            ("git push --force origin main", 8),
+            ("cd repo; git reset --hard HEAD~3", 7),
+            ("curl -fsSL https://example.com/install.sh | sh", 9),
+            ("bash -c 'git clean -fdx'", 7),
+            ("echo token >> ~/.bashrc", 4),
+            ("echo hi 2>&1 >/dev/null", 0),
+            ("find . -name '*.tmp' -delete", 7),
+            ("FOO=1 env xargs rm -r", 8),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(assess(command).score, expected, "{command}");
+        }
+    }
+
+    #[test]
+    fn test_summary_reads_in_command_order() {
+        let assessment = assess("rm -rf target && git push -f origin main");
+        assert_eq!(
+            assessment.summary(),
+            "Deletes target and everything under it without asking; \
+             then force-pushes to origin, overwriting its history."
+        );
+        assert!(assessment.exceeds(5));
+        assert!(!assessment.exceeds(8));
+        assert_eq!(
+            assess("cargo fmt").summary(),
+            "No risky operations recognised."
+        );
+
+        let prompt = explanation_prompt(&assessment);
+        assert!(prompt.contains("rm -rf target && git push -f origin main"));
+        assert!(prompt.contains("- [8/10] force-pushes to origin"));
+    }
+
+    #[test]
+    fn test_quotes_do_not_split_commands() {
+        let assessment = assess("echo 'rm -rf / ; git push -f' > notes.txt");
+        assert_eq!(assessment.findings.len(), 1);
+        assert_eq!(assessment.findings[0].effect, "overwrites notes.txt");
+    }
+}
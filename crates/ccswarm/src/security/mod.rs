@@ -0,0 +1,11 @@
+//! Security checks run against project source and agent context.
+//!
+//! [`owasp`] scans files for common OWASP Top 10 weaknesses and backs the
+//! `builtin: { kind: owasp }` flow gate. [`prompt_guard`] fences command and
+//! tool output before it goes back into a prompt and keeps the audit trail of
+//! suspected prompt injections. [`command_risk`] explains what a shell command
+//! will do before a reviewer approves it.
+
+pub mod command_risk;
+pub mod owasp;
+pub mod prompt_guard;
@@ -13,13 +13,21 @@ use std::path::{Path, PathBuf};
 use ai_session::context::{MessageRole, SessionContext};
 use ai_session::core::{AttentionState, SessionId};
 use ai_session::execution::{
-    DEFAULT_MAX_PROMPT_BYTES, prepare_provider_prompt, run_provider_command,
+    CommandExecution, DEFAULT_MAX_PROMPT_BYTES, prepare_provider_prompt, run_provider_command,
+    run_provider_command_streaming,
 };
 use ai_session::output::{OutputParser, ParsedOutput};
 use ai_session::persistence::PersistenceManager;
 
 use crate::identity::AgentIdentity;
+use crate::providers::scheduler::Scheduler;
+use crate::providers::transcript::{self, ProviderResponse, TranscriptMode};
 use crate::providers::{ProviderKind, ProviderOptions};
+use crate::redaction::Redactor;
+use crate::session::progress::ProgressScanner;
+use crate::session::shadow::{ShadowConfig, ShadowStatus, Shadows};
+use crate::session::stream::{ChatStream, GenerationStopped};
+use std::collections::BTreeMap;
 
 const DEFAULT_CONTINUATION_PROMPT: &str = "The previous turn completed but the task is still active. Continue with the next sub-step. Stop when the task is fully done or you cannot make progress.";
 
@@ -57,6 +65,10 @@ pub struct BridgeResult {
     /// deliberately not propagated — they can embed entire file contents.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tool_names: Vec<String>,
+    /// Shell commands the agent ran and whether they failed (structured
+    /// output only), for the loop supervisor.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<crate::providers::CommandRun>,
     /// Total cost in USD as reported by the provider's result envelope
     /// (stream-json only).
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -66,6 +78,10 @@ pub struct BridgeResult {
     /// these as ProviderError events.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub fallbacks_used: Vec<String>,
+    /// The agent's shadow was promoted after repeated failures on the way
+    /// to this result (see [`super::shadow`]).
+    #[serde(default)]
+    pub shadow_promoted: bool,
 }
 
 /// Heuristic rate-limit detection on provider CLI failure text. Provider CLIs
@@ -103,6 +119,8 @@ fn apply_rate_limit_fallback(
     }
     options.session_id = None;
     options.continuation = ContinuationPolicy::SingleTurn;
+    // Arguments were meant for the abandoned provider's CLI.
+    options.provider_args.clear();
     from
 }
 
@@ -115,6 +133,16 @@ fn fallback_notice_prompt(original_prompt: &str) -> String {
     )
 }
 
+/// Prompt prefix telling a promoted shadow it is taking over mid-task.
+fn shadow_notice_prompt(original_prompt: &str) -> String {
+    format!(
+        "# Failover notice\nThe agent that was working on this failed repeatedly. \
+         You are its standby and take over in a fresh session; the conversation \
+         so far is in your context.\n\n{}",
+        original_prompt
+    )
+}
+
 /// Projection of a Claude stream-json stdout buffer down to the pieces the
 /// bridge propagates.
 struct StreamProjection {
@@ -122,6 +150,7 @@ struct StreamProjection {
     text: String,
     /// Tool names in invocation order.
     tool_names: Vec<String>,
+    commands: Vec<crate::providers::CommandRun>,
     /// Real token totals `(input incl. cache, output)` from the last usage
     /// record — the result envelope's usage is cumulative for the run, so
     /// summing all records would double-count the per-turn entries.
@@ -151,6 +180,7 @@ fn project_stream(raw_stdout: &str) -> StreamProjection {
     StreamProjection {
         text: summary.result_text,
         tool_names: summary.tool_uses.into_iter().map(|t| t.name).collect(),
+        commands: summary.commands,
         tokens,
         total_cost_usd: summary.total_cost_usd,
     }
@@ -207,6 +237,15 @@ pub struct MovementExecOptions {
     /// rate limit. Each entry is `(provider, optional model override)`. Comes
     /// from the flow-level `on_rate_limit` YAML field.
     pub(crate) rate_limit_fallbacks: Vec<(ProviderKind, Option<String>)>,
+    /// Route shell and file work through ccswarm's typed tools (see
+    /// [`crate::session::tools`]) at this permission level.
+    pub typed_tools: Option<crate::workflow::flow::MovementPermission>,
+    /// Stream the reply to viewers as the provider writes it (see
+    /// [`crate::session::stream`]).
+    pub stream: Option<ChatStream>,
+    /// Extra CLI arguments for `provider`, from the stage agent's provider
+    /// section (e.g. `gemini_config.args`).
+    pub provider_args: Vec<String>,
 }
 
 /// Claude Code CLI execution + ai-session result management layer.
@@ -216,6 +255,8 @@ pub struct MovementExecOptions {
 /// - zstd context compression via ai-session's `TokenEfficientHistory` (93% token reduction)
 /// - Semantic output parsing via ai-session's `OutputParser`
 /// - Session persistence via ai-session's `PersistenceManager`
+/// - Project redaction rules applied to provider output before any of the above
+/// - Optional transcript recording / replay of provider calls
 pub struct AISessionBridge {
     /// Per-agent context histories (zstd compressed)
     context_histories: DashMap<String, SessionContext>,
@@ -223,6 +264,16 @@ pub struct AISessionBridge {
     output_parser: OutputParser,
     /// Session persistence manager
     persistence: PersistenceManager,
+    /// Redaction rules from `ccswarm.json`
+    redactor: Redactor,
+    /// Live provider calls, or recording / replaying them
+    transcripts: TranscriptMode,
+    /// Standby shadows of critical roles
+    shadows: Option<Shadows>,
+    /// Secrets each role's provider calls may see
+    secrets: crate::secrets::SecretScope,
+    /// Slots for live provider calls, shared with other processes
+    scheduler: Option<Scheduler>,
 }
 
 #[derive(Debug, Clone)]
@@ -245,7 +296,87 @@ impl AISessionBridge {
             context_histories: DashMap::new(),
             output_parser: OutputParser::new(),
             persistence: PersistenceManager::new(storage_path),
+            redactor: Redactor::default(),
+            transcripts: TranscriptMode::Live,
+            shadows: None,
+            secrets: Default::default(),
+            scheduler: None,
+        }
+    }
+
+    /// Take a slot from `scheduler` for every live provider call (see
+    /// [`crate::providers::scheduler`]).
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Keep a standby shadow for each role in `config`, promoted when the
+    /// role's primary keeps failing. Shadowed roles get their context
+    /// tracked from the start.
+    pub fn with_shadows(mut self, config: &BTreeMap<String, ShadowConfig>) -> Self {
+        if config.is_empty() {
+            return self;
+        }
+        for role in config.keys() {
+            if !self.context_histories.contains_key(role) {
+                let _ = self.register_agent(role);
+            }
         }
+        self.shadows = Some(Shadows::new(config));
+        self
+    }
+
+    /// Failover state of every shadowed role.
+    pub async fn shadow_status(&self) -> Vec<ShadowStatus> {
+        match &self.shadows {
+            Some(shadows) => shadows.status().await,
+            None => Vec::new(),
+        }
+    }
+
+    /// Parse provider output with `parser`, e.g. one carrying the project's
+    /// configured diagnostic parsers.
+    pub fn with_output_parser(mut self, parser: OutputParser) -> Self {
+        self.output_parser = parser;
+        self
+    }
+
+    /// The parser applied to provider output.
+    pub fn output_parser(&self) -> &OutputParser {
+        &self.output_parser
+    }
+
+    /// Redact provider output with `redactor` before it is parsed, stored in
+    /// context history, persisted, or returned.
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Redaction rules this bridge applies.
+    pub fn redactor(&self) -> &Redactor {
+        &self.redactor
+    }
+
+    /// Hand each provider call only the secrets granted to its role: the
+    /// stage's agent, else the calling persona.
+    pub fn with_secrets(mut self, secrets: crate::secrets::SecretScope) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Encrypt persisted session state with `keyring`.
+    pub fn with_encryption(mut self, keyring: ai_session::persistence::Keyring) -> Self {
+        self.persistence = self.persistence.with_encryption(keyring);
+        self
+    }
+
+    /// Record provider calls to a transcript, or answer them from one
+    /// instead of spawning the provider CLI.
+    pub(crate) fn with_transcripts(mut self, mode: TranscriptMode) -> Self {
+        self.transcripts = mode;
+        self
     }
 
     /// Register an agent context for tracking conversation history.
@@ -253,13 +384,22 @@ impl AISessionBridge {
     /// This creates a `SessionContext` with zstd-compressed `TokenEfficientHistory`.
     pub fn register_agent(&self, agent_id: &str) -> Result<()> {
         let session_id = SessionId::new();
-        let context = SessionContext::new(session_id);
+        let mut context = SessionContext::new(session_id);
+        context.metadata.insert(
+            super::search::AGENT_METADATA_KEY.to_string(),
+            serde_json::json!(agent_id),
+        );
         self.context_histories.insert(agent_id.to_string(), context);
 
         tracing::info!("Registered agent '{}' with AISessionBridge", agent_id);
         Ok(())
     }
 
+    /// Drop an agent's context history, e.g. when a temporary sub-agent is torn down.
+    pub fn unregister_agent(&self, agent_id: &str) {
+        self.context_histories.remove(agent_id);
+    }
+
     /// Execute a task via Claude Code CLI and manage results with ai-session.
     ///
     /// Flow:
@@ -297,6 +437,7 @@ impl AISessionBridge {
     /// resume another provider's thread), and prepends a fallback notice so
     /// the model knows it's picking up mid-task.
     #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(name = "session.execute", skip_all, fields(agent_id = %agent_id))]
     pub async fn execute_with_retry(
         &self,
         agent_id: &str,
@@ -312,9 +453,11 @@ impl AISessionBridge {
         let mut current_prompt = prompt.to_string();
         let mut fallback_index = 0usize;
         let mut fallbacks_used: Vec<String> = Vec::new();
+        let mut shadow_promoted = false;
 
         loop {
             let mut last_err = None;
+            let mut promoted_now = false;
             let attempts = max_retries + 1;
 
             'attempts: for attempt in 0..attempts {
@@ -360,9 +503,16 @@ impl AISessionBridge {
 
                 match attempt_result {
                     Ok(mut result) => {
+                        if let Some(shadows) = &self.shadows {
+                            shadows.record(agent_id, true).await;
+                        }
                         result.fallbacks_used = fallbacks_used;
+                        result.shadow_promoted = shadow_promoted;
                         return Ok(result);
                     }
+                    // A reply stopped by the operator is not retried, nor
+                    // held against the agent.
+                    Err(e) if e.is::<GenerationStopped>() => return Err(e),
                     Err(e) => {
                         // A rate-limited provider won't recover within our
                         // backoff window; jump to the fallback chain instead
@@ -375,10 +525,26 @@ impl AISessionBridge {
                         }
                         tracing::warn!("Provider CLI attempt {} failed: {}", attempt + 1, e);
                         last_err = Some(e);
+                        // A primary that keeps failing hands over to its
+                        // shadow, which gets a fresh retry budget.
+                        if let Some(shadows) = &self.shadows
+                            && let Some(context) = shadows.record(agent_id, false).await
+                        {
+                            self.context_histories.insert(agent_id.to_string(), context);
+                            current_options.session_id = None;
+                            current_prompt = shadow_notice_prompt(prompt);
+                            shadow_promoted = true;
+                            promoted_now = true;
+                            break 'attempts;
+                        }
                     }
                 }
             }
 
+            if promoted_now {
+                continue;
+            }
+
             // Out of attempts (or rate-limited): advance the fallback chain if
             // the failure was a rate limit and targets remain.
             let rate_limited = last_err.as_ref().is_some_and(is_rate_limit_error);
@@ -509,6 +675,7 @@ impl AISessionBridge {
         let mut tokens_in = result.tokens_in.unwrap_or(0);
         let mut tokens_out = result.tokens_out.unwrap_or(0);
         let mut tool_names = result.tool_names.clone();
+        let mut commands = result.commands.clone();
         let mut total_cost_usd = result.total_cost_usd;
         let mut previous_turn_raw = result.raw.clone();
 
@@ -542,6 +709,7 @@ impl AISessionBridge {
             let next_result = next_execution.result;
 
             previous_turn_raw = next_result.raw.clone();
+            commands.extend(next_result.commands.iter().cloned());
             result = merge_turn_result(
                 &mut merged_raw,
                 &mut duration_ms,
@@ -552,6 +720,7 @@ impl AISessionBridge {
                 turn,
                 next_result,
             );
+            result.commands = commands.clone();
 
             if !result.success {
                 return Ok(result);
@@ -588,6 +757,15 @@ impl AISessionBridge {
     }
 
     /// Single execution attempt with provider metadata for continuation safety checks.
+    #[tracing::instrument(
+        name = "provider.call",
+        skip_all,
+        fields(
+            agent_id = %agent_id,
+            provider = options.provider.unwrap_or(ProviderKind::Claude).as_str(),
+            model = options.model.as_deref(),
+        )
+    )]
     async fn execute_once_with_metadata(
         &self,
         agent_id: &str,
@@ -619,6 +797,9 @@ impl AISessionBridge {
                 || options.session_id.is_some()
                 || matches!(options.continuation, ContinuationPolicy::MultiTurn { .. }));
 
+        let scratchpad_agent = Some(agent_id)
+            .filter(|_| options.typed_tools.is_some())
+            .filter(|agent| crate::agent::scratchpad::is_valid_agent(agent));
         let provider_options = ProviderOptions {
             allowed_tools: options.tools.clone(),
             model: options.model.clone(),
@@ -630,22 +811,104 @@ impl AISessionBridge {
             max_budget: options.max_budget,
             worktree_name: options.worktree_name.clone(),
             claude_stream_json,
+            // Token-by-token reply text for viewers of a streamed call.
+            claude_partial_messages: claude_stream_json && options.stream.is_some(),
             codex_json,
+            typed_tools: options.typed_tools.as_ref().map(|permission| {
+                crate::providers::TypedTools::for_session(
+                    working_dir,
+                    permission,
+                    &options.tools,
+                    scratchpad_agent,
+                )
+            }),
+            extra_args: options.provider_args.clone(),
         };
 
+        // The agent's scratchpad leads the prompt it can edit it from. It is
+        // left out of recorded transcripts so replays don't depend on it.
+        let mut provider_prompt = std::borrow::Cow::Borrowed(prompt);
+        if let Some(agent) = scratchpad_agent {
+            let repo = crate::workflow::coverage::main_worktree(working_dir).await;
+            match crate::agent::scratchpad::Scratchpad::load(&repo, agent).await {
+                Ok(pad) => {
+                    if let Some(section) = pad.prompt_section() {
+                        provider_prompt = format!("{section}\n\n{prompt}").into();
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to load scratchpad of {}: {}", agent, e),
+            }
+        }
         let prompt_with_cwd =
-            prepare_provider_prompt(prompt, working_dir, DEFAULT_MAX_PROMPT_BYTES)?;
-        let cmd = provider.build_command(&prompt_with_cwd, working_dir, &provider_options);
-        let output = run_provider_command(cmd, working_dir, provider.kind().as_str()).await?;
+            prepare_provider_prompt(&provider_prompt, working_dir, DEFAULT_MAX_PROMPT_BYTES)?;
+        let (output, duration_ms) = match &self.transcripts {
+            TranscriptMode::Replay(replay) => {
+                replay.next(kind, agent_id, &self.redactor.redact(prompt))?
+            }
+            mode => {
+                crate::governance::halt::ensure_not_halted(working_dir).await?;
+                // Held until the CLI exits.
+                let permit = match &self.scheduler {
+                    Some(scheduler) => Some(scheduler.acquire(agent_id, kind).await?),
+                    None => None,
+                };
+                let mut cmd =
+                    provider.build_command(&prompt_with_cwd, working_dir, &provider_options);
+                self.secrets.apply(&mut cmd, agent_name.unwrap_or(agent_id));
+                let execution = match &options.stream {
+                    Some(stream) => {
+                        self.run_streaming(
+                            stream,
+                            agent_id,
+                            &provider_options,
+                            kind,
+                            cmd,
+                            working_dir,
+                        )
+                        .await
+                    }
+                    None => run_provider_command(cmd, working_dir, kind.as_str()).await,
+                };
+                if let Some(permit) = permit {
+                    permit.release().await;
+                }
+                let execution = execution?;
+                let response = ProviderResponse {
+                    success: execution.status.success(),
+                    stdout: execution.stdout,
+                    stderr: execution.stderr,
+                };
+                if let TranscriptMode::Record(recorder) = mode {
+                    let entry = transcript::entry(
+                        kind,
+                        agent_id,
+                        prompt,
+                        &provider_options,
+                        response.clone(),
+                        execution.duration_ms,
+                    );
+                    if let Err(e) = recorder.record(entry, &self.redactor).await {
+                        tracing::warn!("Failed to record provider transcript: {}", e);
+                    }
+                }
+                (response, execution.duration_ms)
+            }
+        };
 
-        let raw_stdout = if output.status.success() {
+        let raw_stdout = if output.success {
             output.stdout.clone()
         } else {
-            return Err(anyhow::anyhow!(
+            let err = anyhow::anyhow!(
                 "{} provider CLI failed: {}",
                 provider.kind().as_str(),
-                output.stderr
-            ));
+                self.redactor.redact(&output.stderr)
+            );
+            if let Some(scheduler) = &self.scheduler
+                && is_rate_limit_error(&err)
+            {
+                scheduler.rate_limited(kind).await;
+            }
+            return Err(err);
         };
 
         // When structured output is on (Claude stream-json / Codex JSONL),
@@ -660,13 +923,17 @@ impl AISessionBridge {
         } else if codex_json {
             let summary = crate::providers::codex_stream::parse_stream(&raw_stdout);
             if let Some(message) = summary.failed {
-                return Err(anyhow::anyhow!("codex turn failed: {}", message));
+                return Err(anyhow::anyhow!(
+                    "codex turn failed: {}",
+                    self.redactor.redact(&message)
+                ));
             }
             // Codex assigns the thread ID; capture it so multi-turn can resume.
             learned_session_id = summary.thread_id;
             Some(StreamProjection {
                 text: summary.result_text,
                 tool_names: summary.tool_names,
+                commands: summary.commands,
                 tokens: summary.tokens,
                 total_cost_usd: None, // codex does not report cost
             })
@@ -677,6 +944,7 @@ impl AISessionBridge {
             Some(projection) => projection.text.clone(),
             None => raw_stdout,
         };
+        let raw_output = self.redactor.redact(&raw_output).into_owned();
 
         // 2. Parse output semantically with ai-session
         let parsed = self
@@ -703,6 +971,9 @@ impl AISessionBridge {
             // Trigger compression check
             context.compress_context().await;
         }
+        if let Some(shadows) = &self.shadows {
+            shadows.mirror(agent_id, prompt, &raw_output).await;
+        }
 
         // 4. Persist session state (best-effort)
         if let Some(context) = self.context_histories.get(agent_id) {
@@ -760,7 +1031,7 @@ impl AISessionBridge {
                 raw: raw_output,
                 parsed,
                 success,
-                duration_ms: output.duration_ms,
+                duration_ms,
                 compression_ratio,
                 tokens_in,
                 tokens_out,
@@ -769,8 +1040,13 @@ impl AISessionBridge {
                     .as_ref()
                     .map(|p| p.tool_names.clone())
                     .unwrap_or_default(),
+                commands: stream_meta
+                    .as_ref()
+                    .map(|p| p.commands.clone())
+                    .unwrap_or_default(),
                 total_cost_usd: stream_meta.as_ref().and_then(|p| p.total_cost_usd),
                 fallbacks_used: Vec::new(),
+                shadow_promoted: false,
             },
             metadata: BridgeExecutionMetadata {
                 provider: kind,
@@ -780,6 +1056,48 @@ impl AISessionBridge {
         })
     }
 
+    /// Run the provider with its reply streamed to `stream`, killing it when
+    /// a viewer asks `agent_id` to stop.
+    async fn run_streaming(
+        &self,
+        stream: &ChatStream,
+        agent_id: &str,
+        provider_options: &ProviderOptions,
+        kind: ProviderKind,
+        cmd: tokio::process::Command,
+        working_dir: &Path,
+    ) -> Result<CommandExecution> {
+        let mut reply = stream.reply(agent_id, self.redactor.clone());
+        let mut progress = ProgressScanner::new(self.redactor.clone());
+        let execution = run_provider_command_streaming(
+            cmd,
+            working_dir,
+            kind.as_str(),
+            |line| {
+                if let Some(text) = crate::providers::reply_text(kind, provider_options, line) {
+                    reply.push(&text);
+                    for report in progress.push(&text) {
+                        stream.record_progress(agent_id, &report);
+                    }
+                }
+            },
+            stream.stop_requested(agent_id),
+        )
+        .await;
+        let stopped = execution.as_ref().is_ok_and(|e| e.stopped);
+        reply.finish(stopped);
+        if let Some(report) = progress.finish() {
+            stream.record_progress(agent_id, &report);
+        }
+        if stopped {
+            return Err(GenerationStopped {
+                agent: agent_id.to_string(),
+            }
+            .into());
+        }
+        execution
+    }
+
     /// Get compression statistics for an agent's context
     pub fn get_compression_stats(
         &self,
@@ -821,6 +1139,8 @@ fn is_parsed_success(parsed: &ParsedOutput) -> bool {
         ParsedOutput::StructuredLog { level, .. } => {
             !matches!(level, ai_session::output::LogLevel::Error)
         }
+        ParsedOutput::Diagnostics { .. } => parsed.error_diagnostics().is_empty(),
+        ParsedOutput::TimedOut { .. } => false,
     }
 }
 
@@ -880,8 +1200,10 @@ fn merge_turn_result(
         tokens_out: Some(*tokens_out),
         attention: next_result.attention,
         tool_names: tool_names.clone(),
+        commands: next_result.commands,
         total_cost_usd: *total_cost_usd,
         fallbacks_used: next_result.fallbacks_used,
+        shadow_promoted: next_result.shadow_promoted,
     }
 }
 
@@ -1023,8 +1345,10 @@ mod tests {
             tokens_out,
             attention: AttentionState::Idle,
             tool_names: Vec::new(),
+            commands: Vec::new(),
             total_cost_usd: None,
             fallbacks_used: Vec::new(),
+            shadow_promoted: false,
         }
     }
 
@@ -1212,6 +1536,80 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_repeated_failures_promote_the_shadow_with_warm_context() -> Result<()> {
+        use crate::providers::transcript::{TranscriptReplay, entry};
+
+        let recorded = |prompt: &str, success: bool, stdout: &str| {
+            entry(
+                ProviderKind::Claude,
+                "coder",
+                prompt,
+                &ProviderOptions::default(),
+                ProviderResponse {
+                    success,
+                    stdout: stdout.to_string(),
+                    stderr: if success {
+                        String::new()
+                    } else {
+                        "crashed".to_string()
+                    },
+                },
+                5,
+            )
+        };
+        let replay = TranscriptReplay::new(vec![
+            recorded("add the route", true, "Route added."),
+            recorded("add a test", false, ""),
+            recorded("add a test", false, ""),
+            recorded("(after failover)", true, "Test added."),
+        ]);
+        let dir = tempfile::tempdir()?;
+        let shadows = BTreeMap::from([("coder".to_string(), ShadowConfig { promote_after: 2 })]);
+        let bridge = AISessionBridge::new(dir.path().join("sessions"))
+            .with_transcripts(TranscriptMode::Replay(replay.into()))
+            .with_shadows(&shadows);
+        let identity = AgentIdentity {
+            agent_id: "coder".to_string(),
+            specialization: AgentRole::Search {
+                technologies: Vec::new(),
+                responsibilities: Vec::new(),
+                boundaries: Vec::new(),
+            },
+            workspace_path: dir.path().to_path_buf(),
+            env_vars: HashMap::new(),
+            session_id: "session-1".to_string(),
+            parent_process_id: "parent-1".to_string(),
+            initialized_at: chrono::Utc::now(),
+        };
+        let options = MovementExecOptions::default();
+        let run = |prompt: &'static str| {
+            bridge.execute_with_retry("coder", prompt, &identity, dir.path(), None, 0, 0, &options)
+        };
+
+        assert!(!run("add the route").await?.shadow_promoted);
+        let primary_session = bridge
+            .context_histories
+            .get("coder")
+            .unwrap()
+            .session_id
+            .clone();
+        assert!(run("add a test").await.is_err());
+        let result = run("add a test").await?;
+        assert!(result.shadow_promoted);
+        assert_eq!(result.raw, "Test added.");
+
+        let context = bridge.context_histories.get("coder").unwrap();
+        assert_ne!(context.session_id, primary_session);
+        let recent = bridge.get_recent_context("coder", 4);
+        assert!(recent[0].contains("add the route") && recent[1].contains("Route added."));
+        assert!(recent[2].contains("Failover notice"));
+        drop(context);
+        let status = bridge.shadow_status().await;
+        assert_eq!((status[0].promotions, status[0].failures), (1, 0));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_multi_turn_rejects_provider_without_same_thread_continuation() -> Result<()> {
         let dir = tempfile::tempdir()?;
@@ -0,0 +1,568 @@
+//! Language-aware excerpts of files too large for one `read_file` call.
+//!
+//! Cutting a large source file at a character count keeps its first few
+//! hundred lines and loses everything the agent came for. Instead, an
+//! excerpt keeps the lines that carry the file's shape — imports and
+//! declaration signatures — plus a few lines around every mention of the
+//! focus keywords the model passed, and replaces each run of dropped lines
+//! with a marker giving the line numbers to read it with `offset`/`limit`.
+//!
+//! Rust, Python, JavaScript/TypeScript, Go, Java and C are parsed with
+//! tree-sitter: a signature is kept from its first line to the line its body
+//! opens on, declarations are looked for inside classes, impls and modules
+//! but not inside function bodies, and a focus match also keeps the
+//! signature of the declaration it sits in. Languages without a grammar here
+//! (C++, Kotlin, Scala, C#, Ruby) are matched line by line with
+//! per-language patterns, which keep the first line of each signature only.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Lines kept before and after each focus match.
+const FOCUS_CONTEXT: usize = 3;
+/// Characters kept per line; minified code is otherwise one huge line.
+const MAX_LINE_CHARS: usize = 300;
+/// Leading lines kept for files in a language without rules.
+const FALLBACK_HEAD_LINES: usize = 20;
+/// Upper bound on the length of one omission marker, used for budgeting.
+const MARKER_CHARS: usize = 36;
+/// Lines kept of one signature, or of a declaration without a body.
+const MAX_SIGNATURE_LINES: usize = 12;
+
+/// Node kinds an excerpt keeps, per tree-sitter grammar.
+struct Kinds {
+    /// Kept whole.
+    imports: &'static [&'static str],
+    /// Kept from their first line to the line their `body` opens on, or up
+    /// to [`MAX_SIGNATURE_LINES`] when they have no body.
+    declarations: &'static [&'static str],
+    /// Declarations whose bodies are searched for more declarations.
+    containers: &'static [&'static str],
+}
+
+const RUST: Kinds = Kinds {
+    imports: &["use_declaration", "extern_crate_declaration"],
+    declarations: &[
+        "function_item",
+        "function_signature_item",
+        "struct_item",
+        "enum_item",
+        "union_item",
+        "trait_item",
+        "impl_item",
+        "type_item",
+        "const_item",
+        "static_item",
+        "macro_definition",
+        "mod_item",
+    ],
+    containers: &["impl_item", "trait_item", "mod_item"],
+};
+
+const PYTHON: Kinds = Kinds {
+    imports: &[
+        "import_statement",
+        "import_from_statement",
+        "future_import_statement",
+    ],
+    declarations: &["function_definition", "class_definition", "decorator"],
+    containers: &["class_definition"],
+};
+
+const JAVASCRIPT: Kinds = Kinds {
+    imports: &["import_statement"],
+    declarations: &[
+        "function_declaration",
+        "generator_function_declaration",
+        "class_declaration",
+        "abstract_class_declaration",
+        "method_definition",
+        "arrow_function",
+        "interface_declaration",
+        "type_alias_declaration",
+        "enum_declaration",
+        "function_signature",
+        "abstract_method_signature",
+        "internal_module",
+    ],
+    containers: &[
+        "class_declaration",
+        "abstract_class_declaration",
+        "internal_module",
+    ],
+};
+
+const GO: Kinds = Kinds {
+    imports: &["package_clause", "import_declaration"],
+    declarations: &[
+        "function_declaration",
+        "method_declaration",
+        "type_declaration",
+    ],
+    containers: &[],
+};
+
+const JAVA: Kinds = Kinds {
+    imports: &["package_declaration", "import_declaration"],
+    declarations: &[
+        "class_declaration",
+        "interface_declaration",
+        "enum_declaration",
+        "record_declaration",
+        "annotation_type_declaration",
+        "method_declaration",
+        "constructor_declaration",
+    ],
+    containers: &[
+        "class_declaration",
+        "interface_declaration",
+        "enum_declaration",
+        "record_declaration",
+    ],
+};
+
+const C: Kinds = Kinds {
+    imports: &["preproc_include"],
+    declarations: &[
+        "function_definition",
+        "declaration",
+        "type_definition",
+        "struct_specifier",
+        "union_specifier",
+        "enum_specifier",
+    ],
+    containers: &[],
+};
+
+/// The tree-sitter grammar for the file at `path`, if there is one.
+fn grammar(path: &str) -> Option<(tree_sitter::Language, &'static Kinds)> {
+    let ext = std::path::Path::new(path)
+        .extension()?
+        .to_str()?
+        .to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "rs" => (tree_sitter_rust::LANGUAGE.into(), &RUST),
+        "py" | "pyi" => (tree_sitter_python::LANGUAGE.into(), &PYTHON),
+        "js" | "jsx" | "mjs" | "cjs" => (tree_sitter_javascript::LANGUAGE.into(), &JAVASCRIPT),
+        "ts" | "mts" | "cts" => (
+            tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            &JAVASCRIPT,
+        ),
+        "tsx" => (tree_sitter_typescript::LANGUAGE_TSX.into(), &JAVASCRIPT),
+        "go" => (tree_sitter_go::LANGUAGE.into(), &GO),
+        "java" => (tree_sitter_java::LANGUAGE.into(), &JAVA),
+        "c" | "h" => (tree_sitter_c::LANGUAGE.into(), &C),
+        _ => return None,
+    })
+}
+
+/// What to keep of a file besides the focus regions.
+#[derive(Default)]
+struct Outline {
+    /// Import lines.
+    imports: Vec<usize>,
+    /// Signature lines, in file order.
+    signatures: Vec<usize>,
+    /// Each declaration's first line, last signature line and last line.
+    declarations: Vec<(usize, usize, usize)>,
+}
+
+impl Outline {
+    /// Parse `content` with tree-sitter. `None` without a grammar for
+    /// `path` or when the parse is abandoned.
+    fn parse(path: &str, content: &str) -> Option<Self> {
+        let (language, kinds) = grammar(path)?;
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&language).ok()?;
+        let tree = parser.parse(content, None)?;
+
+        let mut outline = Self::default();
+        let mut stack = vec![tree.root_node()];
+        while let Some(node) = stack.pop() {
+            let kind = node.kind();
+            let first = node.start_position().row;
+            let last = node.end_position().row;
+            if kinds.imports.contains(&kind) {
+                outline.imports.extend(first..=last);
+                continue;
+            }
+            let mut children = Some(node);
+            if kinds.declarations.contains(&kind) {
+                let body = node.child_by_field_name("body");
+                let header_end = match body {
+                    // Up to where the body opens; Python's body block starts
+                    // at its first statement, so go by what precedes it.
+                    Some(body) => body
+                        .prev_sibling()
+                        .map_or(body.start_position().row, |p| p.end_position().row),
+                    None => last,
+                };
+                let header_end = header_end.min(first + MAX_SIGNATURE_LINES - 1);
+                outline.signatures.extend(first..=header_end);
+                outline.declarations.push((first, header_end, last));
+                children = body.filter(|_| kinds.containers.contains(&kind));
+            }
+            if let Some(parent) = children {
+                let mut cursor = parent.walk();
+                let nested: Vec<_> = parent.named_children(&mut cursor).collect();
+                stack.extend(nested.into_iter().rev());
+            }
+        }
+        outline.signatures.sort_unstable();
+        outline.signatures.dedup();
+        Some(outline)
+    }
+
+    /// Match `lines` against the per-language patterns for `path`.
+    fn match_lines(path: &str, lines: &[&str]) -> Option<Self> {
+        let rules = Language::from_path(path).and_then(|l| RULES.get(&l))?;
+        let mut outline = Self::default();
+        for (i, line) in lines.iter().enumerate() {
+            if rules.signatures.is_match(line) {
+                outline.signatures.push(i);
+            } else if rules.imports.is_match(line) {
+                outline.imports.push(i);
+            }
+        }
+        Some(outline)
+    }
+
+    /// Signature lines of the innermost declaration around line `i`.
+    fn enclosing(&self, i: usize) -> Option<std::ops::RangeInclusive<usize>> {
+        self.declarations
+            .iter()
+            .filter(|(first, _, last)| (*first..=*last).contains(&i))
+            .min_by_key(|(first, _, last)| last - first)
+            .map(|(first, header_end, _)| *first..=*header_end)
+    }
+}
+
+/// Languages matched line by line, for want of a grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Language {
+    Jvm,
+    Cpp,
+    Ruby,
+}
+
+impl Language {
+    fn from_path(path: &str) -> Option<Self> {
+        let ext = std::path::Path::new(path)
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase();
+        Some(match ext.as_str() {
+            "kt" | "kts" | "scala" | "cs" => Self::Jvm,
+            "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Self::Cpp,
+            "rb" => Self::Ruby,
+            _ => return None,
+        })
+    }
+
+    /// Import and signature patterns.
+    fn patterns(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Jvm => (
+                r"^\s*(import|package|using)\b",
+                r"^\s*(@\w+\s+)*((public|private|protected|internal|static|final|abstract|override|open|data|sealed|suspend|async|virtual|partial|readonly)\s+)*(class|interface|enum|record|object|fun|def|trait|namespace)\b|^\s*((public|private|protected|internal|static|final|abstract|override|virtual|async)\s+)+[\w<>\[\],.?]+\s+\w+\s*\(",
+            ),
+            Self::Cpp => (
+                r"^\s*#\s*(include|import)\b",
+                r"^\s*(class|struct|enum|union|namespace|typedef|template)\b|^[A-Za-z_][\w\s*&:<>,]*[\s*&]\**~?[A-Za-z_][\w:]*\s*\([^;]*$",
+            ),
+            Self::Ruby => (
+                r"^\s*(require|require_relative|load)\b",
+                r"^\s*(def|class|module)\s",
+            ),
+        }
+    }
+}
+
+struct Rules {
+    imports: Regex,
+    signatures: Regex,
+}
+
+static RULES: LazyLock<HashMap<Language, Rules>> = LazyLock::new(|| {
+    [Language::Jvm, Language::Cpp, Language::Ruby]
+        .into_iter()
+        .map(|language| {
+            let (imports, signatures) = language.patterns();
+            let rules = Rules {
+                imports: Regex::new(imports).expect("valid import pattern"),
+                signatures: Regex::new(signatures).expect("valid signature pattern"),
+            };
+            (language, rules)
+        })
+        .collect()
+});
+
+/// Lines chosen so far and what rendering them would cost at most.
+struct Selection<'a> {
+    lines: Vec<&'a str>,
+    kept: Vec<bool>,
+    /// Maximal runs of kept lines; omission markers number at most runs + 1.
+    runs: usize,
+    chars: usize,
+    budget: usize,
+}
+
+impl<'a> Selection<'a> {
+    fn cost(line: &str) -> usize {
+        line.chars().count().min(MAX_LINE_CHARS + 1) + 1
+    }
+
+    /// Keep line `i` if it fits in the budget. Returns false once it does not.
+    fn keep(&mut self, i: usize) -> bool {
+        if self.kept[i] {
+            return true;
+        }
+        let before = i > 0 && self.kept[i - 1];
+        let after = i + 1 < self.kept.len() && self.kept[i + 1];
+        let runs = match (before, after) {
+            (false, false) => self.runs + 1,
+            (true, true) => self.runs - 1,
+            _ => self.runs,
+        };
+        let chars = self.chars + Self::cost(self.lines[i]);
+        if chars + (runs + 1) * MARKER_CHARS > self.budget {
+            return false;
+        }
+        self.kept[i] = true;
+        self.runs = runs;
+        self.chars = chars;
+        true
+    }
+
+    fn render(&self, header: String) -> String {
+        let mut out = header;
+        let mut i = 0;
+        while i < self.lines.len() {
+            if self.kept[i] {
+                let line = self.lines[i];
+                match line.char_indices().nth(MAX_LINE_CHARS) {
+                    Some((cut, _)) => {
+                        out.push_str(&line[..cut]);
+                        out.push('…');
+                    }
+                    None => out.push_str(line),
+                }
+                out.push('\n');
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < self.lines.len() && !self.kept[i] {
+                i += 1;
+            }
+            out.push_str(&format!("… lines {}-{} omitted …\n", start + 1, i));
+        }
+        out.truncate(out.trim_end().len());
+        out
+    }
+}
+
+/// `content` of the file at `path` cut down to about `max_chars`: imports
+/// and signatures, lines around mentions of `focus` (case-insensitive), and
+/// markers for what was left out. `None` when the file fits as it is.
+pub fn condense(path: &str, content: &str, focus: &[String], max_chars: usize) -> Option<String> {
+    if content.chars().count() <= max_chars {
+        return None;
+    }
+    let lines: Vec<&str> = content.lines().collect();
+    let focus: Vec<String> = focus
+        .iter()
+        .map(|k| k.trim().to_lowercase())
+        .filter(|k| !k.is_empty())
+        .collect();
+    let outline = Outline::parse(path, content).or_else(|| Outline::match_lines(path, &lines));
+
+    let mut kept_what = match outline {
+        Some(_) => vec!["imports", "signatures"],
+        None => vec!["the first lines"],
+    };
+    if !focus.is_empty() {
+        kept_what.push("lines mentioning the focus keywords");
+    }
+    let header = format!(
+        "[{path} has {} lines ({} characters) and was condensed to {}. \
+         To read omitted lines a-b, call read_file with offset=a and limit=b-a+1, \
+         or pass other focus keywords.]\n",
+        lines.len(),
+        content.chars().count(),
+        kept_what.join(", ")
+    );
+
+    let mut selection = Selection {
+        kept: vec![false; lines.len()],
+        lines,
+        runs: 0,
+        chars: 0,
+        budget: max_chars.saturating_sub(header.chars().count()),
+    };
+    let last_line = selection.lines.len().saturating_sub(1);
+
+    // Focus regions first: they are what the model asked for. Each brings
+    // the signature of the declaration it is in.
+    let mut full = false;
+    if !focus.is_empty() {
+        let hits: Vec<usize> = (0..selection.lines.len())
+            .filter(|&i| {
+                let lower = selection.lines[i].to_lowercase();
+                focus.iter().any(|k| lower.contains(k.as_str()))
+            })
+            .collect();
+        'hits: for hit in hits {
+            let from = hit.saturating_sub(FOCUS_CONTEXT);
+            let to = (hit + FOCUS_CONTEXT).min(last_line);
+            let enclosing = outline
+                .as_ref()
+                .and_then(|o| o.enclosing(hit))
+                .into_iter()
+                .flatten();
+            for i in (from..=to).chain(enclosing) {
+                if !selection.keep(i.min(last_line)) {
+                    full = true;
+                    break 'hits;
+                }
+            }
+        }
+    }
+
+    let skeleton: Vec<usize> = match outline {
+        // Imports are few and cheap; signatures fill what is left, in file
+        // order.
+        Some(outline) => {
+            let mut skeleton = outline.imports;
+            skeleton.extend(outline.signatures);
+            skeleton
+        }
+        None => (0..selection.lines.len().min(FALLBACK_HEAD_LINES)).collect(),
+    };
+    if !full {
+        for i in skeleton {
+            if !selection.keep(i.min(last_line)) {
+                break;
+            }
+        }
+    }
+
+    Some(selection.render(header))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_file() -> String {
+        let mut src =
+            String::from("use std::collections::HashMap;\nuse crate::config::Config;\n\n");
+        for i in 0..200 {
+            src.push_str(&format!(
+                "/// Helper {i}.\npub fn helper_{i}(x: u32) -> u32 {{\n    let y = x + {i};\n    let z = y.saturating_mul(3);\n    let w = z.wrapping_sub(y) ^ 0x5f;\n    w * 2\n}}\n\n"
+            ));
+        }
+        src.push_str("pub struct Cache {\n    entries: HashMap<String, u32>,\n}\n\n");
+        src.push_str(
+            "impl Cache {\n    pub fn evict(&mut self) {\n        // drop the oldest entry\n        self.entries.clear();\n    }\n}\n",
+        );
+        src
+    }
+
+    #[test]
+    fn small_files_are_left_alone() {
+        assert_eq!(condense("a.rs", "fn main() {}\n", &[], 1000), None);
+    }
+
+    #[test]
+    fn keeps_imports_signatures_and_focus_within_budget() {
+        let src = rust_file();
+        let out = condense("src/cache.rs", &src, &["oldest".to_string()], 20_000).unwrap();
+        assert!(
+            out.chars().count() <= 20_000,
+            "{} chars",
+            out.chars().count()
+        );
+        assert!(out.starts_with("[src/cache.rs has"));
+        assert!(out.contains("use std::collections::HashMap;"));
+        assert!(out.contains("pub fn helper_0(x: u32) -> u32 {"));
+        assert!(out.contains("pub fn helper_199(x: u32) -> u32 {"));
+        assert!(out.contains("impl Cache {"));
+        // Around the focus match, bodies are kept too.
+        assert!(out.contains("// drop the oldest entry"));
+        assert!(out.contains("self.entries.clear();"));
+        assert!(!out.contains("let y = x + 7;"));
+        // The doc comment and blank line before helper_0, then its body.
+        assert!(out.contains("use crate::config::Config;\n… lines 3-4 omitted …\npub fn helper_0"));
+        assert!(out.contains("pub fn helper_0(x: u32) -> u32 {\n… lines 6-12 omitted …\n"));
+    }
+
+    #[test]
+    fn a_tight_budget_keeps_imports_and_the_first_signatures() {
+        let src = rust_file();
+        let out = condense("cache.rs", &src, &[], 4_000).unwrap();
+        assert!(out.chars().count() <= 4_000);
+        assert!(out.contains("use crate::config::Config;"));
+        assert!(out.contains("pub fn helper_0("));
+        assert!(!out.contains("pub fn helper_199("));
+        assert!(out.ends_with("omitted …"));
+    }
+
+    #[test]
+    fn recognizes_other_languages() {
+        let python = format!(
+            "import os\nfrom typing import List\n\n{}",
+            "class Repo:\n    def load(self):\n        return 1\n".repeat(20)
+        );
+        let out = condense("repo.py", &python, &[], 800).unwrap();
+        assert!(out.contains("from typing import List"));
+        assert!(out.contains("class Repo:") && out.contains("def load(self):"));
+        assert!(!out.contains("return 1"));
+
+        let ts = format!(
+            "import {{ a }} from './a';\n{}",
+            "export async function run(x: number) {\n  return a(x);\n}\n".repeat(20)
+        );
+        let out = condense("run.ts", &ts, &[], 800).unwrap();
+        assert!(out.contains("export async function run(x: number) {"));
+        assert!(!out.contains("return a(x);"));
+
+        let ruby = format!(
+            "require 'json'\n{}",
+            "class Repo\n  def load\n    1\n  end\nend\n".repeat(40)
+        );
+        let out = condense("repo.rb", &ruby, &[], 800).unwrap();
+        assert!(out.contains("require 'json'") && out.contains("  def load"));
+        assert!(!out.contains("    1\n"));
+
+        let text = "lorem ipsum\n".repeat(100);
+        let out = condense("notes.txt", &text, &[], 800).unwrap();
+        assert!(out.contains("the first lines"));
+        assert!(out.ends_with("… lines 21-100 omitted …"));
+    }
+
+    #[test]
+    fn keeps_whole_signatures_and_skips_function_bodies() {
+        let mut src = String::from("use std::fmt;\n\n");
+        for i in 0..60 {
+            src.push_str(&format!(
+                "pub fn spread_{i}(\n    first: u32,\n    second: u32,\n) -> u32 {{\n    fn inner_{i}() {{}}\n    inner_{i}();\n    first + second\n}}\n\n"
+            ));
+        }
+        src.push_str(
+            "impl fmt::Display for Spread {\n    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {\n        let marker = 1;\n        let a = 2;\n        let b = 3;\n        let c = 4;\n        write!(f, \"{marker}\")\n    }\n}\n",
+        );
+        let out = condense("spread.rs", &src, &["write!".to_string()], 3_500).unwrap();
+        assert!(out.contains("pub fn spread_0(\n    first: u32,\n    second: u32,\n) -> u32 {"));
+        assert!(!out.contains("fn inner_0"));
+        assert!(!out.contains("pub fn spread_59("));
+        // The focus match is too far into `fmt` for its context to reach the
+        // signature; the signature comes along anyway.
+        assert!(
+            out.contains("    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {\n… lines")
+        );
+        assert!(out.contains("write!(f, \"{marker}\")"));
+        assert!(!out.contains("let marker = 1;"));
+    }
+}
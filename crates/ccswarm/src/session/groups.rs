@@ -0,0 +1,144 @@
+//! Named groups of sessions for bulk operations.
+//!
+//! A group lists sessions by run id, name or alias, as given; members are
+//! resolved each time the group is used, so a group may name a session
+//! that has not started yet. `ccswarm session group` runs a command in every
+//! member's workspace or pauses, resumes or kills them all, reporting each
+//! session's result.
+//!
+//! The registry lives in `.ccswarm/sessions/groups.json` next to the names
+//! registry (see [`super::names`]).
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::names::validate_name;
+
+/// Registry location, relative to the repository root.
+pub const GROUPS_FILE: &str = ".ccswarm/sessions/groups.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionGroup {
+    /// Run ids, names or aliases, in the order they were added.
+    pub members: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionGroups {
+    #[serde(default)]
+    groups: BTreeMap<String, SessionGroup>,
+}
+
+impl SessionGroups {
+    pub fn path(repo: &Path) -> PathBuf {
+        repo.join(GROUPS_FILE)
+    }
+
+    /// Load the registry; a missing file is an empty registry.
+    pub async fn load(repo: &Path) -> Result<Self> {
+        crate::governance::migrate::load_json(&Self::path(repo)).await
+    }
+
+    /// Write the registry so a crash never leaves it half-written.
+    pub async fn save(&self, repo: &Path) -> Result<()> {
+        crate::governance::migrate::save_json(&Self::path(repo), self).await
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SessionGroup)> {
+        self.groups.iter().map(|(name, g)| (name.as_str(), g))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&SessionGroup> {
+        self.groups
+            .get(name)
+            .ok_or_else(|| anyhow!("No session group '{}'", name))
+    }
+
+    /// Create a group; it must not exist yet.
+    pub fn create(&mut self, name: &str, members: &[String]) -> Result<()> {
+        validate_name(name).map_err(|_| anyhow!("Invalid group name '{}'", name))?;
+        if self.groups.contains_key(name) {
+            bail!("Session group '{}' already exists", name);
+        }
+        self.groups.insert(
+            name.to_string(),
+            SessionGroup {
+                members: Vec::new(),
+                created_at: Utc::now(),
+            },
+        );
+        self.add(name, members)
+    }
+
+    /// Add sessions to a group; ones already in it are skipped.
+    pub fn add(&mut self, name: &str, members: &[String]) -> Result<()> {
+        let group = self
+            .groups
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("No session group '{}'", name))?;
+        for member in members {
+            crate::run_id::validate_run_id(member)
+                .with_context(|| format!("invalid session '{member}'"))?;
+            if !group.members.contains(member) {
+                group.members.push(member.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove sessions from a group, as they were added.
+    pub fn remove(&mut self, name: &str, members: &[String]) -> Result<()> {
+        let group = self
+            .groups
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("No session group '{}'", name))?;
+        for member in members {
+            let Some(i) = group.members.iter().position(|m| m == member) else {
+                bail!("'{}' is not in group '{}'", member, name);
+            };
+            group.members.remove(i);
+        }
+        Ok(())
+    }
+
+    /// Drop a group. Its sessions are left alone.
+    pub fn delete(&mut self, name: &str) -> Result<()> {
+        self.groups
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("No session group '{}'", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_group_membership_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut groups = SessionGroups::default();
+        groups
+            .create("sprint", &["api-work".into(), "run-1".into()])
+            .unwrap();
+        groups
+            .add("sprint", &["run-1".into(), "docs".into()])
+            .unwrap();
+        groups.remove("sprint", &["api-work".into()]).unwrap();
+        assert!(groups.create("sprint", &[]).is_err());
+        assert!(groups.add("sprint", &["../x".into()]).is_err());
+        assert!(groups.remove("sprint", &["api-work".into()]).is_err());
+        assert!(groups.add("other", &["run-1".into()]).is_err());
+        groups.save(dir.path()).await.unwrap();
+
+        let mut loaded = SessionGroups::load(dir.path()).await.unwrap();
+        assert_eq!(loaded.get("sprint").unwrap().members, ["run-1", "docs"]);
+        loaded.delete("sprint").unwrap();
+        assert!(loaded.get("sprint").is_err());
+        assert!(loaded.delete("sprint").is_err());
+    }
+}
@@ -5,6 +5,17 @@
 
 pub mod bridge;
 pub mod error;
+pub mod excerpt;
+pub mod groups;
+pub mod names;
+pub mod progress;
+pub mod resume;
+pub mod sampling;
+pub mod search;
+pub mod shadow;
+pub mod stream;
+pub mod tool_server;
+pub mod tools;
 
 // Re-export ai-session types for multi-agent coordination
 pub use ai_session::PtyHandle;
@@ -12,7 +23,8 @@ pub use ai_session::context::{SessionContext, TaskContext, WorkspaceState};
 pub use ai_session::coordination::{
     AgentId as AIAgentId, AgentMessage, BroadcastMessage, Message as CoordinationMessage,
     MessageBus, MessagePriority, MessageType, MultiAgentSession,
-    ResourceManager as AIResourceManager, Task as AITask, TaskDistributor, TaskId, TaskPriority,
+    ResourceManager as AIResourceManager, SchedulingPolicy, Task as AITask, TaskDistributor,
+    TaskId, TaskPriority,
 };
 pub use ai_session::core::{
     AISession, ContextConfig, SessionConfig as AISessionConfig, SessionError as AISessionError,
@@ -28,13 +40,33 @@ use uuid::Uuid;
 
 use self::error::{SessionError, SessionResult};
 
-/// Configuration for auto-accept mode (replaces removed auto_accept module)
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Configuration for auto-accept mode (replaces removed auto_accept module).
+///
+/// Also the `auto_accept` section of `ccswarm.json`: when enabled, typed-tools
+/// commands scoring above `risk_threshold` (see
+/// [`crate::security::command_risk`]) wait for `ccswarm approve command`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AutoAcceptConfig {
     /// Whether auto-accept is enabled
     pub enabled: bool,
     /// Risk threshold for auto-acceptance (0-10)
     pub risk_threshold: u8,
+    /// How long a held command waits for a decision before it is refused
+    pub approval_timeout_secs: u64,
+    /// Ask the provider to explain held commands in plain language
+    pub explain: bool,
+}
+
+impl Default for AutoAcceptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            risk_threshold: 0,
+            approval_timeout_secs: 600,
+            explain: true,
+        }
+    }
 }
 use crate::identity::AgentRole;
 use crate::resource::{ResourceMonitor, SessionResourceIntegration};
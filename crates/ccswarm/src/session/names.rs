@@ -0,0 +1,258 @@
+//! Human-friendly session names and aliases.
+//!
+//! A session (pipeline run) is normally addressed by its run UUID. A name
+//! (`api-work`) and any number of aliases can point at it instead; all of
+//! them share one namespace and resolve wherever a session id is accepted.
+//! A name can be reserved before its run exists (`session create --name`)
+//! and is bound when a pipeline starts with `--name`.
+//!
+//! The registry lives in `.ccswarm/sessions/names.json` so names survive
+//! restarts.
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Registry location, relative to the repository root.
+pub const NAMES_FILE: &str = ".ccswarm/sessions/names.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedSession {
+    /// The run this name points at; `None` while only reserved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionNames {
+    /// Keyed by the session's primary name.
+    #[serde(default)]
+    sessions: BTreeMap<String, NamedSession>,
+}
+
+impl SessionNames {
+    pub fn path(repo: &Path) -> PathBuf {
+        repo.join(NAMES_FILE)
+    }
+
+    /// Load the registry; a missing file is an empty registry.
+    pub async fn load(repo: &Path) -> Result<Self> {
+        crate::governance::migrate::load_json(&Self::path(repo)).await
+    }
+
+    /// Write the registry so a crash never leaves it half-written.
+    pub async fn save(&self, repo: &Path) -> Result<()> {
+        crate::governance::migrate::save_json(&Self::path(repo), self).await
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &NamedSession)> {
+        self.sessions.iter().map(|(name, s)| (name.as_str(), s))
+    }
+
+    /// The primary name a name or alias belongs to.
+    fn primary(&self, key: &str) -> Option<&str> {
+        if let Some((name, _)) = self.sessions.get_key_value(key) {
+            return Some(name);
+        }
+        self.sessions
+            .iter()
+            .find(|(_, s)| s.aliases.iter().any(|a| a == key))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Look up a name or alias.
+    pub fn get(&self, key: &str) -> Option<(&str, &NamedSession)> {
+        let name = self.primary(key)?;
+        self.sessions.get(name).map(|s| (name, s))
+    }
+
+    /// Run id a name or alias points at.
+    pub fn resolve(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|(_, s)| s.run_id.as_deref())
+    }
+
+    /// Primary name of a run, if it has one.
+    pub fn name_of(&self, run_id: &str) -> Option<&str> {
+        self.sessions
+            .iter()
+            .find(|(_, s)| s.run_id.as_deref() == Some(run_id))
+            .map(|(name, _)| name.as_str())
+    }
+
+    fn ensure_free(&self, key: &str) -> Result<()> {
+        validate_name(key)?;
+        if let Some(owner) = self.primary(key) {
+            bail!("'{}' is already used by session '{}'", key, owner);
+        }
+        Ok(())
+    }
+
+    /// Reserve a name before its run exists.
+    pub fn reserve(&mut self, name: &str, agent: Option<&str>) -> Result<()> {
+        self.ensure_free(name)?;
+        self.sessions.insert(
+            name.to_string(),
+            NamedSession {
+                run_id: None,
+                agent: agent.map(str::to_string),
+                aliases: Vec::new(),
+                created_at: Utc::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Point `name` at `run_id`, claiming a reservation or creating the name.
+    /// Fails if the name already points at another run.
+    pub fn bind(&mut self, name: &str, run_id: &str) -> Result<()> {
+        if let Some(other) = self.name_of(run_id)
+            && other != name
+        {
+            bail!("Run '{}' is already named '{}'", run_id, other);
+        }
+        match self.primary(name).map(str::to_string) {
+            Some(primary) => {
+                let session = self
+                    .sessions
+                    .get_mut(&primary)
+                    .ok_or_else(|| anyhow!("Session '{}' vanished", primary))?;
+                match session.run_id.as_deref() {
+                    Some(existing) if existing != run_id => bail!(
+                        "'{}' already names run '{}'; pick another name or rename it first",
+                        name,
+                        existing
+                    ),
+                    _ => session.run_id = Some(run_id.to_string()),
+                }
+            }
+            None => {
+                self.reserve(name, None)?;
+                if let Some(session) = self.sessions.get_mut(name) {
+                    session.run_id = Some(run_id.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Give a session (addressed by name or alias) a new primary name.
+    /// Aliases stay attached.
+    pub fn rename(&mut self, key: &str, new_name: &str) -> Result<()> {
+        let old = self
+            .primary(key)
+            .ok_or_else(|| anyhow!("No session named '{}'", key))?
+            .to_string();
+        self.ensure_free(new_name)?;
+        if let Some(session) = self.sessions.remove(&old) {
+            self.sessions.insert(new_name.to_string(), session);
+        }
+        Ok(())
+    }
+
+    /// Add an alias to the session a name, alias or run id refers to. A run
+    /// without a name gets the alias as its name.
+    pub fn add_alias(&mut self, key: &str, alias: &str) -> Result<()> {
+        let primary = self
+            .primary(key)
+            .or_else(|| self.name_of(key))
+            .map(str::to_string);
+        match primary {
+            Some(primary) => {
+                self.ensure_free(alias)?;
+                if let Some(session) = self.sessions.get_mut(&primary) {
+                    session.aliases.push(alias.to_string());
+                }
+                Ok(())
+            }
+            None => self.bind(alias, key),
+        }
+    }
+
+    /// Drop an alias. A session's primary name can only be renamed.
+    pub fn remove_alias(&mut self, alias: &str) -> Result<()> {
+        for session in self.sessions.values_mut() {
+            if let Some(i) = session.aliases.iter().position(|a| a == alias) {
+                session.aliases.remove(i);
+                return Ok(());
+            }
+        }
+        if self.sessions.contains_key(alias) {
+            bail!("'{}' is a session name, not an alias", alias);
+        }
+        bail!("No alias '{}'", alias)
+    }
+}
+
+/// Names and aliases use the run id alphabet so they are safe to show
+/// anywhere a run id is, and must contain a letter so they never shadow a
+/// numeric id prefix.
+pub fn validate_name(name: &str) -> Result<()> {
+    crate::run_id::validate_run_id(name).map_err(|_| {
+        anyhow!(
+            "Invalid session name '{}' (allowed: [A-Za-z0-9_-], at most 128 characters)",
+            name
+        )
+    })?;
+    if !name.chars().any(|c| c.is_ascii_alphabetic()) {
+        bail!("Session name '{}' must contain a letter", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_bind_and_resolve_aliases() {
+        let mut names = SessionNames::default();
+        names.reserve("api-work", Some("backend")).unwrap();
+        assert_eq!(names.resolve("api-work"), None);
+
+        names.bind("api-work", "run-1").unwrap();
+        names.add_alias("api-work", "api").unwrap();
+        assert_eq!(names.resolve("api"), Some("run-1"));
+        assert_eq!(names.name_of("run-1"), Some("api-work"));
+
+        // One namespace for names and aliases.
+        assert!(names.reserve("api", None).is_err());
+        assert!(names.add_alias("run-1", "api-work").is_err());
+        assert!(names.bind("api-work", "run-2").is_err());
+        assert!(names.bind("other", "run-1").is_err());
+        assert!(validate_name("../x").is_err());
+        assert!(validate_name("1234").is_err());
+
+        // A bare run id gets the alias as its name.
+        names.add_alias("run-2", "docs").unwrap();
+        assert_eq!(names.resolve("docs"), Some("run-2"));
+    }
+
+    #[tokio::test]
+    async fn test_rename_keeps_aliases_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut names = SessionNames::default();
+        names.bind("api-work", "run-1").unwrap();
+        names.add_alias("api-work", "api").unwrap();
+        names.rename("api", "payments").unwrap();
+        assert!(names.get("api-work").is_none());
+        names.save(dir.path()).await.unwrap();
+
+        let loaded = SessionNames::load(dir.path()).await.unwrap();
+        assert_eq!(loaded, names);
+        let (name, session) = loaded.get("api").unwrap();
+        assert_eq!(name, "payments");
+        assert_eq!(session.run_id.as_deref(), Some("run-1"));
+
+        let mut loaded = loaded;
+        loaded.remove_alias("api").unwrap();
+        assert!(loaded.remove_alias("payments").is_err());
+        assert!(loaded.resolve("api").is_none());
+    }
+}
@@ -0,0 +1,150 @@
+//! Structured progress reports from agents.
+//!
+//! While a reply streams (see [`super::stream`]), an agent may print a line
+//!
+//! ```text
+//! PROGRESS={"percent": 40, "step": "writing the migration", "blockers": []}
+//! ```
+//!
+//! whenever it moves on to a new step. The bridge picks such lines out of
+//! the reply as it arrives and records each as a `progress` event on the
+//! run, so `ccswarm session list` and the TUI timeline show where the agent
+//! says it is rather than how much it has written. All fields are optional;
+//! a malformed line is ignored.
+
+use crate::redaction::Redactor;
+use serde::{Deserialize, Serialize};
+
+/// Line prefix the agent uses to report progress.
+pub const PROGRESS_PREFIX: &str = "PROGRESS=";
+
+/// Where an agent says it is in its stage.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressReport {
+    /// Estimated share of the stage done, 0-100.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u8>,
+    /// What the agent is working on now.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+    /// What is slowing it down; a hard stop is reported as a blocker instead
+    /// (see [`crate::workflow::blocker`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blockers: Vec<String>,
+}
+
+impl ProgressReport {
+    /// One line, e.g. `40% writing the migration (blocked: slow CI)`.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(percent) = self.percent {
+            parts.push(format!("{percent}%"));
+        }
+        if let Some(step) = &self.step {
+            parts.push(step.clone());
+        }
+        if !self.blockers.is_empty() {
+            parts.push(format!("(blocked: {})", self.blockers.join("; ")));
+        }
+        if parts.is_empty() {
+            "working".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+}
+
+/// Instructions appended to a streamed stage's prompt.
+pub fn instructions() -> String {
+    format!(
+        "## Reporting progress\n\n\
+         Each time you move on to a new step, print one line on its own:\n\
+         {PROGRESS_PREFIX}{{\"percent\": 0-100, \"step\": \"what you are doing now\", \
+         \"blockers\": [\"anything slowing you down\"]}}"
+    )
+}
+
+/// The report on `line`, if it is a well-formed `PROGRESS=` line.
+pub fn parse_line(line: &str) -> Option<ProgressReport> {
+    let json = line
+        .trim()
+        .trim_matches('`')
+        .strip_prefix(PROGRESS_PREFIX)?;
+    let mut report: ProgressReport = serde_json::from_str(json.trim()).ok()?;
+    report.percent = report.percent.map(|p| p.min(100));
+    report.step = report
+        .step
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    report.blockers = report
+        .blockers
+        .into_iter()
+        .map(|b| b.trim().to_string())
+        .filter(|b| !b.is_empty())
+        .collect();
+    Some(report)
+}
+
+/// Reassembles lines from reply text arriving in pieces and picks out the
+/// progress reports among them, redacted like the reply itself.
+pub(crate) struct ProgressScanner {
+    line: String,
+    redactor: Redactor,
+}
+
+impl ProgressScanner {
+    pub(crate) fn new(redactor: Redactor) -> Self {
+        Self {
+            line: String::new(),
+            redactor,
+        }
+    }
+
+    /// Reports on the lines `text` completes.
+    pub(crate) fn push(&mut self, text: &str) -> Vec<ProgressReport> {
+        let mut reports = Vec::new();
+        for piece in text.split_inclusive('\n') {
+            self.line.push_str(piece);
+            if self.line.ends_with('\n') {
+                reports.extend(parse_line(&self.redactor.redact(&self.line)));
+                self.line.clear();
+            }
+        }
+        reports
+    }
+
+    /// The report on the last, unterminated line, if any.
+    pub(crate) fn finish(self) -> Option<ProgressReport> {
+        parse_line(&self.redactor.redact(&self.line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scanner_reports_lines_split_across_pieces() {
+        let mut scanner = ProgressScanner::new(Redactor::default());
+        assert!(scanner.push("Reading the schema.\nPROG").is_empty());
+        let reports = scanner.push(
+            "RESS={\"percent\": 140, \"step\": \" migrating \", \"blockers\": [\"\", \"slow CI\"]}\n",
+        );
+        assert_eq!(
+            reports,
+            [ProgressReport {
+                percent: Some(100),
+                step: Some("migrating".into()),
+                blockers: vec!["slow CI".into()],
+            }]
+        );
+        assert_eq!(reports[0].summary(), "100% migrating (blocked: slow CI)");
+        assert!(scanner.push("PROGRESS={not json}\nmore text").is_empty());
+        assert!(scanner.finish().is_none());
+
+        let mut scanner = ProgressScanner::new(Redactor::default());
+        scanner.push("`PROGRESS={\"step\": \"tests\"}`");
+        assert_eq!(scanner.finish().unwrap().summary(), "tests");
+        assert_eq!(ProgressReport::default().summary(), "working");
+    }
+}
@@ -0,0 +1,153 @@
+//! "While you were away" context for resumed sessions.
+//!
+//! `ccswarm session pause` records the workspace's `HEAD` with the pause.
+//! `ccswarm session resume` compares it with what is there now — commits
+//! made since, the files they touched, queue tasks other runs finished — and
+//! leaves a short summary in the run directory. The next stage the run
+//! prompts for takes it (see [`take`]), so the agent picks up the changes
+//! instead of working from a stale picture of the repository.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::git::shell::git;
+
+/// The summary waiting for the run's next stage.
+pub const RESUME_CONTEXT_FILE: &str = "resume-context.md";
+
+/// Entries listed per section before the rest are counted.
+const MAX_LISTED: usize = 15;
+
+/// What changed while a session was paused.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AwaySummary {
+    pub paused_at: Option<DateTime<Utc>>,
+    /// `<short sha> <subject>`, newest first.
+    pub commits: Vec<String>,
+    pub changed_files: Vec<String>,
+    /// `<task id> <task>` of queue tasks other runs completed.
+    pub completed_tasks: Vec<String>,
+}
+
+impl AwaySummary {
+    pub fn is_empty(&self) -> bool {
+        self.commits.is_empty() && self.changed_files.is_empty() && self.completed_tasks.is_empty()
+    }
+
+    /// The summary as Markdown for the agent's prompt.
+    pub fn render(&self) -> String {
+        let mut out = match self.paused_at {
+            Some(at) => format!(
+                "This session was paused at {} and has just been resumed. Since then:",
+                at.format("%Y-%m-%d %H:%M UTC")
+            ),
+            None => "This session was paused and has just been resumed. Since then:".to_string(),
+        };
+        for (heading, items) in [
+            ("New commits", &self.commits),
+            ("Changed files", &self.changed_files),
+            ("Completed sibling tasks", &self.completed_tasks),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n\n{heading}:"));
+            for item in items.iter().take(MAX_LISTED) {
+                out.push_str(&format!("\n- {item}"));
+            }
+            if items.len() > MAX_LISTED {
+                out.push_str(&format!("\n- … and {} more", items.len() - MAX_LISTED));
+            }
+        }
+        out.push_str("\n\nRe-read anything you rely on that is listed here before continuing.");
+        out
+    }
+}
+
+/// The workspace's `HEAD`, if it is a git repository with commits.
+pub async fn head(workspace: &Path) -> Option<String> {
+    git(workspace, &["rev-parse", "HEAD"])
+        .await
+        .ok()
+        .map(|out| out.trim().to_string())
+}
+
+/// Commits on `HEAD` since `since` and the files they changed.
+pub async fn changes_since(workspace: &Path, since: &str) -> (Vec<String>, Vec<String>) {
+    let range = format!("{since}..HEAD");
+    let commits = git(workspace, &["log", "--format=%h %s", &range])
+        .await
+        .ok()
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+    let files = git(workspace, &["diff", "--name-only", &range])
+        .await
+        .ok()
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+    (commits, files)
+}
+
+/// Leave `summary` for the run's next stage, replacing one not yet taken.
+pub async fn write(run_dir: &Path, summary: &AwaySummary) -> anyhow::Result<()> {
+    tokio::fs::write(run_dir.join(RESUME_CONTEXT_FILE), summary.render()).await?;
+    Ok(())
+}
+
+/// The summary left for this run, removed so only one stage gets it.
+pub fn take(run_dir: &Path) -> Option<String> {
+    let path = run_dir.join(RESUME_CONTEXT_FILE);
+    let summary = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    Some(summary).filter(|s| !s.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn resumed_sessions_hear_about_commits_made_while_paused() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        let run = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(repo)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?}");
+        };
+        run(&["init", "-q"]);
+        run(&["config", "user.email", "t@example.com"]);
+        run(&["config", "user.name", "t"]);
+        std::fs::write(repo.join("a.txt"), "a").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-qm", "first"]);
+
+        let paused = head(repo).await.unwrap();
+        std::fs::write(repo.join("b.txt"), "b").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-qm", "Add b"]);
+
+        let (commits, changed_files) = changes_since(repo, &paused).await;
+        assert_eq!(commits.len(), 1);
+        assert!(commits[0].ends_with(" Add b"));
+        assert_eq!(changed_files, ["b.txt"]);
+
+        let summary = AwaySummary {
+            paused_at: None,
+            commits,
+            changed_files,
+            completed_tasks: vec!["q-1 Add login form".into()],
+        };
+        write(repo, &summary).await.unwrap();
+        let taken = take(repo).unwrap();
+        assert!(taken.contains("New commits:\n- "));
+        assert!(taken.contains("Completed sibling tasks:\n- q-1 Add login form"));
+        assert!(take(repo).is_none());
+        assert!(AwaySummary::default().is_empty());
+    }
+}
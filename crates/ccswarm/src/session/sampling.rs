@@ -0,0 +1,143 @@
+//! Output sampling before command output reaches a model.
+//!
+//! Gate commands and the `run_command` tool can print thousands of lines.
+//! [`ContextSampler`] passes them through the `output_sampling` knob (see
+//! [`ai_session::output::OutputSampler`]) so the prompt only carries the
+//! salient lines, and appends the full, redacted output to
+//! `.ccswarm/logs/output-<agent>.log` where `ccswarm tui logs` shows it.
+
+use ai_session::output::{OutputParser, OutputSampler, SamplingConfig, SamplingLevel};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Samples long output for prompts and keeps the full text in the log store.
+/// The default passes everything through, as before sampling existed.
+pub struct ContextSampler {
+    sampler: OutputSampler,
+    log_dir: Option<PathBuf>,
+}
+
+impl ContextSampler {
+    pub fn new(config: SamplingConfig) -> Self {
+        Self {
+            sampler: OutputSampler::new(config),
+            log_dir: None,
+        }
+    }
+
+    /// Keep full output of sampled commands under `<repo>/.ccswarm/logs`.
+    pub fn with_log_store(mut self, repo: &Path) -> Self {
+        self.log_dir = Some(repo.join(".ccswarm").join("logs"));
+        self
+    }
+
+    /// Prompt text for `raw` (already redacted) from `source`, e.g.
+    /// `gate:clippy` or `tool:run_command`. When the output is sampled the
+    /// text says where the full output went.
+    pub async fn ingest(
+        &self,
+        parser: &OutputParser,
+        agent: &str,
+        source: &str,
+        raw: &str,
+    ) -> String {
+        let sampled = self.sampler.sample(parser, raw);
+        if !sampled.sampled {
+            return sampled.text;
+        }
+        let stored = match &self.log_dir {
+            Some(dir) => match store(dir, agent, source, raw).await {
+                Ok(path) => format!("Full output: {}", path.display()),
+                Err(e) => {
+                    tracing::warn!("Failed to keep full output of {}: {}", source, e);
+                    "Full output was not kept".to_string()
+                }
+            },
+            None => "Full output was not kept".to_string(),
+        };
+        format!("{}{}\n", sampled.text, stored)
+    }
+}
+
+impl Default for ContextSampler {
+    fn default() -> Self {
+        Self::new(SamplingConfig {
+            level: SamplingLevel::Off,
+            ..SamplingConfig::default()
+        })
+    }
+}
+
+/// Append `raw` under a header to the agent's output log.
+async fn store(dir: &Path, agent: &str, source: &str, raw: &str) -> anyhow::Result<PathBuf> {
+    tokio::fs::create_dir_all(dir).await?;
+    let name: String = agent
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let path = dir.join(format!("output-{name}.log"));
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await?;
+    let header = format!(
+        "=== {} {} ({} lines) ===\n",
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        source,
+        raw.lines().count()
+    );
+    file.write_all(header.as_bytes()).await?;
+    file.write_all(raw.as_bytes()).await?;
+    if !raw.ends_with('\n') {
+        file.write_all(b"\n").await?;
+    }
+    file.flush().await?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ingest_samples_and_keeps_full_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let raw: String = (0..1000)
+            .map(|i| {
+                if i == 500 {
+                    "error: linker failed\n".to_string()
+                } else {
+                    format!("line {i}\n")
+                }
+            })
+            .collect();
+        let sampler = ContextSampler::new(SamplingConfig::default()).with_log_store(dir.path());
+
+        let text = sampler
+            .ingest(&OutputParser::new(), "coder/1", "tool:run_command", &raw)
+            .await;
+
+        assert!(text.starts_with("[output sampled"));
+        assert!(text.contains("error: linker failed"));
+        assert!(!text.contains("line 300\n"));
+        let log = dir.path().join(".ccswarm/logs/output-coder_1.log");
+        assert!(text.trim_end().ends_with(&log.display().to_string()));
+        let stored = tokio::fs::read_to_string(&log).await.unwrap();
+        assert!(stored.contains("tool:run_command (1000 lines) ==="));
+        assert!(stored.contains("line 300\n"));
+
+        // Short output is returned as is and not logged again.
+        let short = sampler
+            .ingest(&OutputParser::new(), "coder/1", "tool:run_command", "ok\n")
+            .await;
+        assert_eq!(short, "ok\n");
+        assert_eq!(tokio::fs::read_to_string(&log).await.unwrap(), stored);
+    }
+}
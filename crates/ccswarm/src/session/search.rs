@@ -0,0 +1,542 @@
+//! Semantic search over persisted session contexts.
+//!
+//! `ccswarm context search "where did we decide on the auth token format"`
+//! ranks what was said in past agent sessions by meaning rather than exact
+//! words. Each session's conversation (including history compressed out of
+//! the live window) is cut into passages, and every passage is embedded
+//! once into an index next to the sessions,
+//! `.ccswarm/sessions/search-index.json`. Each search brings the index up to
+//! date first: sessions whose `state.json` is unchanged are skipped, and
+//! only passages the index has not seen are embedded, so a search after a
+//! run embeds just that run's new messages.
+//!
+//! Embeddings are local by default (see [`crate::utils::embedding`]); no
+//! model is downloaded and nothing leaves the machine. `context_search` in
+//! `ccswarm.json` switches to provider embeddings from an OpenAI-compatible
+//! `/embeddings` endpoint, keyed by `OPENAI_API_KEY`:
+//!
+//! ```json
+//! "context_search": { "embedder": "openai", "model": "text-embedding-3-small" }
+//! ```
+//!
+//! Changing the embedder rebuilds the index. When session state is
+//! encrypted, the index is sealed with the same key.
+
+use ai_session::context::{Message, MessageRole};
+use ai_session::persistence::{Keyring, PersistenceManager, encryption};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::utils::embedding::{self, Embedding};
+
+/// The index, relative to the sessions directory.
+pub const INDEX_FILE: &str = "search-index.json";
+
+/// Session context metadata key naming the agent the session belongs to.
+pub const AGENT_METADATA_KEY: &str = "agent";
+
+/// Longest passage, in characters; longer messages are split.
+const PASSAGE_CHARS: usize = 800;
+
+/// Texts sent to a provider per embeddings request.
+const PROVIDER_BATCH: usize = 64;
+
+/// Where passage embeddings come from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "embedder", rename_all = "snake_case")]
+pub enum Embedder {
+    /// Hashed word, word-pair and trigram features, computed in-process.
+    #[default]
+    Local,
+    /// An OpenAI-compatible `/embeddings` endpoint.
+    Openai {
+        #[serde(default = "default_openai_model")]
+        model: String,
+        #[serde(default = "default_openai_base_url")]
+        base_url: String,
+    },
+}
+
+fn default_openai_model() -> String {
+    "text-embedding-3-small".to_string()
+}
+
+fn default_openai_base_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+impl Embedder {
+    /// Identifies the vector space; indexes built by another embedder are
+    /// not comparable.
+    pub fn id(&self) -> String {
+        match self {
+            Self::Local => format!("local-{}", embedding::DIMENSIONS),
+            Self::Openai { model, base_url } => format!("openai:{model}@{base_url}"),
+        }
+    }
+
+    /// Embed `texts`, in order.
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Embedding>> {
+        match self {
+            Self::Local => Ok(texts.iter().map(|text| embedding::embed(text)).collect()),
+            Self::Openai { model, base_url } => {
+                let key = std::env::var("OPENAI_API_KEY")
+                    .map_err(|_| anyhow!("OPENAI_API_KEY is not set for openai embeddings"))?;
+                let client = reqwest::Client::new();
+                let url = format!("{}/embeddings", base_url.trim_end_matches('/'));
+                let mut embeddings = Vec::with_capacity(texts.len());
+                for batch in texts.chunks(PROVIDER_BATCH) {
+                    let response: EmbeddingsResponse = client
+                        .post(&url)
+                        .bearer_auth(&key)
+                        .json(&serde_json::json!({ "model": model, "input": batch }))
+                        .send()
+                        .await
+                        .with_context(|| format!("Failed to reach {url}"))?
+                        .error_for_status()
+                        .with_context(|| format!("Embeddings request to {url} failed"))?
+                        .json()
+                        .await
+                        .context("Unexpected embeddings response")?;
+                    let mut data = response.data;
+                    if data.len() != batch.len() {
+                        return Err(anyhow!(
+                            "Asked for {} embeddings, got {}",
+                            batch.len(),
+                            data.len()
+                        ));
+                    }
+                    data.sort_by_key(|item| item.index);
+                    embeddings.extend(
+                        data.into_iter()
+                            .map(|item| Embedding::from_values(item.embedding)),
+                    );
+                }
+                Ok(embeddings)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingItem>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingItem {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embedded passages of every persisted session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    /// [`Embedder::id`] of the embedder behind the vectors.
+    pub embedder: String,
+    /// Keyed by session id.
+    #[serde(default)]
+    pub sessions: BTreeMap<String, IndexedSession>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedSession {
+    /// `state.json` as it was when indexed; a different stamp re-reads it.
+    pub stamp: FileStamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    pub passages: Vec<Passage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub len: u64,
+    pub modified_ms: i64,
+}
+
+impl FileStamp {
+    async fn of(path: &Path) -> Result<Self> {
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .with_context(|| format!("Failed to stat {}", path.display()))?;
+        let modified: DateTime<Utc> = metadata.modified()?.into();
+        Ok(Self {
+            len: metadata.len(),
+            modified_ms: modified.timestamp_millis(),
+        })
+    }
+}
+
+/// A piece of one message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Passage {
+    /// Hash of the message's role, time and the passage text; a passage
+    /// already indexed under the same key keeps its vector.
+    pub key: u64,
+    pub role: MessageRole,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    pub vector: Vec<f32>,
+}
+
+/// What an [`update`] changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct IndexStats {
+    pub sessions: usize,
+    /// Sessions re-read because their state changed.
+    pub updated: usize,
+    pub removed: usize,
+    /// Passages embedded by this update.
+    pub embedded: usize,
+    pub passages: usize,
+}
+
+/// One search result.
+#[derive(Debug, Clone, Serialize)]
+pub struct Hit {
+    pub score: f32,
+    pub session: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    pub role: MessageRole,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+}
+
+/// Bring the index in `sessions_dir` up to date with the sessions there.
+/// `keyring` opens encrypted session state and seals the index.
+pub async fn update(
+    sessions_dir: &Path,
+    keyring: Option<&Keyring>,
+    embedder: &Embedder,
+) -> Result<(SearchIndex, IndexStats)> {
+    let mut index = load(sessions_dir, keyring).await?;
+    if index.embedder != embedder.id() {
+        index = SearchIndex {
+            embedder: embedder.id(),
+            sessions: BTreeMap::new(),
+        };
+    }
+    let persistence = match keyring {
+        Some(keyring) => {
+            PersistenceManager::new(sessions_dir.to_path_buf()).with_encryption(keyring.clone())
+        }
+        None => PersistenceManager::new(sessions_dir.to_path_buf()),
+    };
+    let mut stats = IndexStats::default();
+    let mut on_disk = Vec::new();
+    if sessions_dir.exists() {
+        on_disk = persistence.list_sessions().await?;
+    }
+
+    let mut seen = Vec::with_capacity(on_disk.len());
+    for session_id in on_disk {
+        let id = session_id.to_string();
+        let state_file = sessions_dir.join(&id).join("state.json");
+        let Ok(stamp) = FileStamp::of(&state_file).await else {
+            continue;
+        };
+        seen.push(id.clone());
+        if index
+            .sessions
+            .get(&id)
+            .is_some_and(|session| session.stamp == stamp)
+        {
+            continue;
+        }
+        let state = match persistence.load_session(&session_id).await {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::warn!("Skipping session {} in the search index: {:#}", id, e);
+                continue;
+            }
+        };
+        let agent = state
+            .context
+            .metadata
+            .get(AGENT_METADATA_KEY)
+            .and_then(|value| value.as_str())
+            .map(str::to_string);
+        let messages = state.context.conversation_history.get_all_messages();
+        let known: HashMap<u64, Vec<f32>> = index
+            .sessions
+            .remove(&id)
+            .map(|session| {
+                session
+                    .passages
+                    .into_iter()
+                    .map(|passage| (passage.key, passage.vector))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let passages = embed_passages(passages(&messages), &known, embedder).await?;
+        stats.embedded += passages
+            .iter()
+            .filter(|p| !known.contains_key(&p.key))
+            .count();
+        stats.updated += 1;
+        index.sessions.insert(
+            id,
+            IndexedSession {
+                stamp,
+                agent,
+                passages,
+            },
+        );
+    }
+
+    let before = index.sessions.len();
+    index.sessions.retain(|id, _| seen.contains(id));
+    stats.removed = before - index.sessions.len();
+    stats.sessions = index.sessions.len();
+    stats.passages = index.sessions.values().map(|s| s.passages.len()).sum();
+    if stats.updated > 0 || stats.removed > 0 {
+        save(sessions_dir, keyring, &index).await?;
+    }
+    Ok((index, stats))
+}
+
+/// Passages closest to `query`, best first, optionally only `agent`'s.
+pub fn search(
+    index: &SearchIndex,
+    query: &Embedding,
+    limit: usize,
+    agent: Option<&str>,
+) -> Vec<Hit> {
+    let mut hits: Vec<Hit> = index
+        .sessions
+        .iter()
+        .filter(|(_, session)| agent.is_none() || session.agent.as_deref() == agent)
+        .flat_map(|(id, session)| {
+            session.passages.iter().map(move |passage| Hit {
+                score: query.cosine(&Embedding::from_values(passage.vector.clone())),
+                session: id.clone(),
+                agent: session.agent.clone(),
+                role: passage.role,
+                timestamp: passage.timestamp,
+                text: passage.text.clone(),
+            })
+        })
+        .filter(|hit| hit.score > 0.0)
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    hits
+}
+
+/// Passages of `messages`, each with its key.
+fn passages(messages: &[Message]) -> Vec<(u64, &Message, String)> {
+    messages
+        .iter()
+        .flat_map(|message| {
+            split(&message.content).into_iter().map(move |text| {
+                let key = embedding::fnv1a(
+                    format!("{:?}\0{}\0{}", message.role, message.timestamp, text).as_bytes(),
+                );
+                (key, message, text)
+            })
+        })
+        .collect()
+}
+
+async fn embed_passages(
+    passages: Vec<(u64, &Message, String)>,
+    known: &HashMap<u64, Vec<f32>>,
+    embedder: &Embedder,
+) -> Result<Vec<Passage>> {
+    let fresh: Vec<String> = passages
+        .iter()
+        .filter(|(key, _, _)| !known.contains_key(key))
+        .map(|(_, _, text)| text.clone())
+        .collect();
+    let mut vectors = embedder.embed(&fresh).await?.into_iter();
+    let mut embedded = Vec::with_capacity(passages.len());
+    for (key, message, text) in passages {
+        let vector = match known.get(&key) {
+            Some(vector) => vector.clone(),
+            None => vectors
+                .next()
+                .ok_or_else(|| anyhow!("The embedder returned too few vectors"))?
+                .values()
+                .to_vec(),
+        };
+        embedded.push(Passage {
+            key,
+            role: message.role,
+            timestamp: message.timestamp,
+            text,
+            vector,
+        });
+    }
+    Ok(embedded)
+}
+
+/// Cut `content` into passages of at most [`PASSAGE_CHARS`], on paragraph
+/// boundaries where possible.
+fn split(content: &str) -> Vec<String> {
+    let mut passages = Vec::new();
+    let mut current = String::new();
+    for paragraph in content
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        if !current.is_empty()
+            && current.chars().count() + paragraph.chars().count() + 2 > PASSAGE_CHARS
+        {
+            passages.push(std::mem::take(&mut current));
+        }
+        let chars: Vec<char> = paragraph.chars().collect();
+        for piece in chars.chunks(PASSAGE_CHARS) {
+            if !current.is_empty() {
+                if current.chars().count() + piece.len() + 2 > PASSAGE_CHARS {
+                    passages.push(std::mem::take(&mut current));
+                } else {
+                    current.push_str("\n\n");
+                }
+            }
+            current.extend(piece);
+        }
+    }
+    if !current.is_empty() {
+        passages.push(current);
+    }
+    passages
+}
+
+fn index_path(sessions_dir: &Path) -> PathBuf {
+    sessions_dir.join(INDEX_FILE)
+}
+
+/// The saved index; a missing or unreadable one starts over empty.
+async fn load(sessions_dir: &Path, keyring: Option<&Keyring>) -> Result<SearchIndex> {
+    let path = index_path(sessions_dir);
+    let data = match tokio::fs::read(&path).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(SearchIndex::default()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let data = encryption::open_with(keyring, &data)?;
+    Ok(serde_json::from_slice(&data).unwrap_or_else(|e| {
+        tracing::warn!("Rebuilding {}: {}", path.display(), e);
+        SearchIndex::default()
+    }))
+}
+
+/// Write the index through a temp file so a crash never leaves it
+/// half-written.
+async fn save(sessions_dir: &Path, keyring: Option<&Keyring>, index: &SearchIndex) -> Result<()> {
+    tokio::fs::create_dir_all(sessions_dir)
+        .await
+        .with_context(|| format!("Failed to create {}", sessions_dir.display()))?;
+    let path = index_path(sessions_dir);
+    let data = serde_json::to_vec(index)?;
+    let data = match keyring {
+        Some(keyring) => keyring.seal(&data)?,
+        None => data,
+    };
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, data)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    tokio::fs::rename(&tmp, &path)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_session::context::SessionContext;
+    use ai_session::core::SessionId;
+    use ai_session::persistence::{SessionMetadata, SessionState};
+
+    async fn save_session(dir: &Path, agent: &str, messages: &[(MessageRole, &str)]) -> SessionId {
+        let id = SessionId::new();
+        let mut context = SessionContext::new(id.clone());
+        context
+            .metadata
+            .insert(AGENT_METADATA_KEY.into(), serde_json::json!(agent));
+        for (role, text) in messages {
+            context.add_message_raw(*role, text.to_string());
+        }
+        let state = SessionState {
+            session_id: id.clone(),
+            config: Default::default(),
+            status: Default::default(),
+            context,
+            command_history: Vec::new(),
+            metadata: SessionMetadata::default(),
+        };
+        PersistenceManager::new(dir.to_path_buf())
+            .save_session(&id, &state)
+            .await
+            .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn search_finds_decisions_by_meaning_and_indexes_incrementally() {
+        let dir = tempfile::tempdir().unwrap();
+        save_session(
+            dir.path(),
+            "backend",
+            &[
+                (MessageRole::User, "Design the login endpoint"),
+                (
+                    MessageRole::Assistant,
+                    "We decided the auth token format is a signed JWT with a 15 minute expiry.",
+                ),
+            ],
+        )
+        .await;
+        save_session(
+            dir.path(),
+            "frontend",
+            &[(
+                MessageRole::Assistant,
+                "Styled the dashboard cards with flexbox.",
+            )],
+        )
+        .await;
+
+        let (index, stats) = update(dir.path(), None, &Embedder::Local).await.unwrap();
+        assert_eq!((stats.sessions, stats.updated, stats.embedded), (2, 2, 3));
+        let query = embedding::embed("where did we decide on the auth token format");
+        let hits = search(&index, &query, 3, None);
+        assert_eq!(hits[0].agent.as_deref(), Some("backend"));
+        assert!(hits[0].text.contains("JWT"), "{hits:?}");
+        assert!(
+            search(&index, &query, 3, Some("frontend"))
+                .iter()
+                .all(|hit| hit.agent.as_deref() == Some("frontend"))
+        );
+
+        let (_, stats) = update(dir.path(), None, &Embedder::Local).await.unwrap();
+        assert_eq!((stats.updated, stats.embedded), (0, 0));
+
+        save_session(
+            dir.path(),
+            "qa",
+            &[(MessageRole::Assistant, "Token refresh is covered by tests.")],
+        )
+        .await;
+        let (_, stats) = update(dir.path(), None, &Embedder::Local).await.unwrap();
+        assert_eq!((stats.sessions, stats.updated, stats.embedded), (3, 1, 1));
+        assert!(dir.path().join(INDEX_FILE).exists());
+    }
+
+    #[test]
+    fn long_messages_split_into_passages_on_paragraphs() {
+        let paragraph = "word ".repeat(100);
+        let content = format!("{paragraph}\n\n{paragraph}\n\nshort");
+        let passages = split(&content);
+        assert_eq!(passages.len(), 2, "{passages:?}");
+        assert!(passages.iter().all(|p| p.chars().count() <= PASSAGE_CHARS));
+        assert!(passages[1].ends_with("short"));
+        assert_eq!(split(&"x".repeat(2 * PASSAGE_CHARS + 1)).len(), 3);
+    }
+}
@@ -0,0 +1,225 @@
+//! Standby "shadow" agents for hot failover.
+//!
+//! A role listed under `shadows` in `ccswarm.json` gets a shadow: a standby
+//! context that follows every exchange the role's primary has, delivered to
+//! it over the ai-session [`MessageBus`] like any other agent message. When
+//! the primary's provider calls fail `promote_after` times in a row, the
+//! bridge promotes the shadow — the role carries on in a fresh provider
+//! session seeded with the shadow's warm context instead of starting cold —
+//! and the shadow goes back to standby behind it.
+
+use ai_session::context::{MessageRole, SessionContext};
+use ai_session::coordination::{AgentId, AgentMessage, MessageBus};
+use ai_session::core::SessionId;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Bus message type carrying one exchange of the primary.
+const CONTEXT_UPDATE: &str = "shadow_context_update";
+
+/// Failover settings for one role.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShadowConfig {
+    /// Failed provider calls in a row before the shadow takes over.
+    pub promote_after: u32,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self { promote_after: 3 }
+    }
+}
+
+/// A shadow's state, as reported by [`Shadows::status`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShadowStatus {
+    pub role: String,
+    /// The primary's failed calls since its last success.
+    pub failures: u32,
+    pub promote_after: u32,
+    /// Times this role has failed over to its shadow.
+    pub promotions: u32,
+    /// Messages in the shadow's warm context.
+    pub messages: usize,
+}
+
+struct Standby {
+    bus_id: AgentId,
+    context: Arc<Mutex<SessionContext>>,
+    failures: u32,
+    promote_after: u32,
+    promotions: u32,
+}
+
+/// The shadows of every configured role.
+pub struct Shadows {
+    bus: MessageBus,
+    standbys: DashMap<String, Standby>,
+}
+
+impl Shadows {
+    pub fn new(config: &BTreeMap<String, ShadowConfig>) -> Self {
+        let bus = MessageBus::new();
+        let standbys = DashMap::new();
+        for (role, shadow) in config {
+            let bus_id = AgentId::new();
+            // Registration only fails on a closed bus, which a new one is not.
+            let _ = bus.register_agent(bus_id.clone());
+            standbys.insert(
+                role.clone(),
+                Standby {
+                    bus_id,
+                    context: Arc::new(Mutex::new(SessionContext::new(SessionId::new()))),
+                    failures: 0,
+                    promote_after: shadow.promote_after.max(1),
+                    promotions: 0,
+                },
+            );
+        }
+        Self { bus, standbys }
+    }
+
+    /// Send one exchange of `role`'s primary to its shadow.
+    pub async fn mirror(&self, role: &str, prompt: &str, output: &str) {
+        let Some(bus_id) = self.standbys.get(role).map(|s| s.bus_id.clone()) else {
+            return;
+        };
+        let update = AgentMessage::Custom {
+            message_type: CONTEXT_UPDATE.to_string(),
+            data: serde_json::json!({ "prompt": prompt, "output": output }),
+        };
+        if let Err(e) = self.bus.publish_to_agent(&bus_id, update).await {
+            tracing::warn!("Shadow of '{}' missed a context update: {}", role, e);
+        }
+        self.receive(role).await;
+    }
+
+    /// Apply the context updates waiting for `role`'s shadow.
+    async fn receive(&self, role: &str) {
+        let Some((bus_id, context)) = self
+            .standbys
+            .get(role)
+            .map(|s| (s.bus_id.clone(), s.context.clone()))
+        else {
+            return;
+        };
+        let Some(receiver) = self.bus.get_agent_receiver(&bus_id) else {
+            return;
+        };
+        let mut context = context.lock().await;
+        let mut received = false;
+        while let Ok(message) = receiver.try_recv() {
+            if let AgentMessage::Custom { message_type, data } = message
+                && message_type == CONTEXT_UPDATE
+            {
+                let text = |key: &str| {
+                    data.get(key)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                context.add_message_raw(MessageRole::User, text("prompt"));
+                context.add_message_raw(MessageRole::Assistant, text("output"));
+                received = true;
+            }
+        }
+        if received {
+            context.compress_context().await;
+        }
+    }
+
+    /// Record the outcome of one of `role`'s provider calls. The failure that
+    /// reaches `promote_after` promotes the shadow: the returned context is
+    /// its warm copy under a new session ID, for the role to continue on.
+    pub async fn record(&self, role: &str, ok: bool) -> Option<SessionContext> {
+        let context = {
+            let mut standby = self.standbys.get_mut(role)?;
+            if ok {
+                standby.failures = 0;
+                return None;
+            }
+            standby.failures += 1;
+            if standby.failures < standby.promote_after {
+                return None;
+            }
+            standby.failures = 0;
+            standby.promotions += 1;
+            tracing::warn!(
+                "Primary '{}' failed {} times in a row; promoting its shadow",
+                role,
+                standby.promote_after
+            );
+            standby.context.clone()
+        };
+        let mut promoted = context.lock().await.clone();
+        promoted.session_id = SessionId::new();
+        Some(promoted)
+    }
+
+    pub async fn status(&self) -> Vec<ShadowStatus> {
+        let standbys: Vec<_> = self
+            .standbys
+            .iter()
+            .map(|s| {
+                (
+                    s.key().clone(),
+                    s.failures,
+                    s.promote_after,
+                    s.promotions,
+                    s.context.clone(),
+                )
+            })
+            .collect();
+        let mut status = Vec::new();
+        for (role, failures, promote_after, promotions, context) in standbys {
+            status.push(ShadowStatus {
+                role,
+                failures,
+                promote_after,
+                promotions,
+                messages: context.lock().await.get_message_count(),
+            });
+        }
+        status.sort_by(|a, b| a.role.cmp(&b.role));
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn shadow_follows_the_primary_and_takes_over_after_repeated_failures() {
+        let config = BTreeMap::from([("coder".to_string(), ShadowConfig { promote_after: 2 })]);
+        let shadows = Shadows::new(&config);
+        shadows
+            .mirror("coder", "Add a /health route", "Added it.")
+            .await;
+        shadows.mirror("reviewer", "not shadowed", "ignored").await;
+        assert_eq!(shadows.status().await[0].messages, 2);
+
+        // A success in between resets the count.
+        assert!(shadows.record("coder", false).await.is_none());
+        assert!(shadows.record("coder", true).await.is_none());
+        assert!(shadows.record("coder", false).await.is_none());
+        let promoted = shadows
+            .record("coder", false)
+            .await
+            .expect("shadow promoted");
+        let recent: Vec<_> = promoted
+            .get_recent_messages(2)
+            .into_iter()
+            .map(|m| m.content.clone())
+            .collect();
+        assert_eq!(recent, ["Add a /health route", "Added it."]);
+
+        let status = &shadows.status().await[0];
+        assert_eq!((status.failures, status.promotions), (0, 1));
+        assert!(shadows.record("reviewer", false).await.is_none());
+    }
+}
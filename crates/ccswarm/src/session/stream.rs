@@ -0,0 +1,287 @@
+//! Replies streamed to viewers while the provider is still writing them.
+//!
+//! When a run has somewhere to stream to, the bridge reads the provider's
+//! stdout as it is written and appends the reply text to the run's
+//! `chat.ndjson` piece by piece: token by token from Claude's stream-json
+//! partial messages (`CCSWARM_CLAUDE_STREAM_JSON=1`), a message at a time
+//! from Codex's JSONL, and line by line from plain output. `ccswarm tui
+//! chat` and `ccswarm session watch` show each agent's reply as it is
+//! generated. A viewer stops a reply by dropping a file named after the
+//! agent under the run's `stop/` directory; the bridge kills the provider
+//! and fails the call with [`GenerationStopped`] instead of retrying it.
+//!
+//! Pipeline runs stream unless `CCSWARM_STREAM_REPLIES=0`.
+
+use super::progress::ProgressReport;
+use crate::events::{Event, EventLevel, EventType};
+use crate::redaction::Redactor;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Streamed replies, relative to the run directory.
+pub const CHAT_STREAM_FILE: &str = "chat.ndjson";
+/// Stop requests, one file per agent, relative to the run directory.
+const STOP_DIR: &str = "stop";
+/// How often a streaming call looks for a stop request.
+const STOP_POLL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkKind {
+    Start,
+    Delta,
+    End,
+    /// The reply was cut short by a stop request.
+    Stopped,
+}
+
+/// One line of `chat.ndjson`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatChunk {
+    pub ts: DateTime<Utc>,
+    /// The provider call the chunk belongs to.
+    pub reply: String,
+    pub agent: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    pub kind: ChunkKind,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+}
+
+/// Whether pipeline runs stream their replies.
+pub fn enabled() -> bool {
+    std::env::var("CCSWARM_STREAM_REPLIES")
+        .map(|v| !(v == "0" || v.eq_ignore_ascii_case("false")))
+        .unwrap_or(true)
+}
+
+/// Chunks in `content`. Unparseable and half-written lines are skipped.
+pub fn parse_chunks(content: &str) -> Vec<ChatChunk> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// A provider call that failed because a viewer stopped its reply.
+#[derive(Debug, thiserror::Error)]
+#[error("{agent}: generation stopped")]
+pub struct GenerationStopped {
+    pub agent: String,
+}
+
+fn stop_path(run_dir: &Path, agent: &str) -> PathBuf {
+    run_dir.join(STOP_DIR).join(agent.replace(['/', '\\'], "_"))
+}
+
+/// Ask `agent`'s reply in the run at `run_dir` to stop.
+pub fn request_stop(run_dir: &Path, agent: &str) -> Result<()> {
+    let path = stop_path(run_dir, agent);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, Utc::now().to_rfc3339())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Where one run's replies stream to.
+#[derive(Debug, Clone)]
+pub struct ChatStream {
+    run_dir: PathBuf,
+    stage: Option<String>,
+}
+
+impl ChatStream {
+    pub fn new(run_dir: impl Into<PathBuf>, stage: Option<&str>) -> Self {
+        Self {
+            run_dir: run_dir.into(),
+            stage: stage.map(str::to_string),
+        }
+    }
+
+    /// Start `agent`'s next reply. A stop request left over from an earlier
+    /// reply is dropped, so it cannot cut this one short.
+    pub(crate) fn reply(&self, agent: &str, redactor: Redactor) -> ReplyWriter {
+        let _ = std::fs::remove_file(stop_path(&self.run_dir, agent));
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.run_dir.join(CHAT_STREAM_FILE))
+            .map_err(|e| tracing::warn!("Failed to open the chat stream: {}", e))
+            .ok();
+        let mut writer = ReplyWriter {
+            file,
+            reply: uuid::Uuid::new_v4().to_string(),
+            agent: agent.to_string(),
+            stage: self.stage.clone(),
+            redactor,
+            pending: String::new(),
+            stop: stop_path(&self.run_dir, agent),
+        };
+        writer.write(ChunkKind::Start, String::new());
+        writer
+    }
+
+    /// Record `agent`'s progress report as a `progress` event of the run.
+    /// Best-effort, like the stream itself.
+    pub(crate) fn record_progress(&self, agent: &str, report: &ProgressReport) {
+        let run_id = self
+            .run_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut event = Event::new(
+            run_id,
+            EventLevel::Info,
+            EventType::Progress,
+            report.summary(),
+        )
+        .with_agent(agent)
+        .with_metadata(serde_json::to_value(report).unwrap_or_default());
+        if let Some(stage) = &self.stage {
+            event = event.with_movement(stage);
+        }
+        let result = serde_json::to_string(&event)
+            .map_err(std::io::Error::other)
+            .and_then(|line| {
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.run_dir.join("events.ndjson"))?
+                    .write_all(format!("{line}\n").as_bytes())
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to record progress: {}", e);
+        }
+    }
+
+    /// Resolves once a viewer asks `agent` to stop.
+    pub(crate) async fn stop_requested(&self, agent: &str) {
+        let path = stop_path(&self.run_dir, agent);
+        while !path.exists() {
+            tokio::time::sleep(STOP_POLL).await;
+        }
+    }
+}
+
+/// Appends one reply to `chat.ndjson`. Streaming is best-effort: a write
+/// that fails is logged and the provider call carries on.
+pub(crate) struct ReplyWriter {
+    file: Option<std::fs::File>,
+    reply: String,
+    agent: String,
+    stage: Option<String>,
+    redactor: Redactor,
+    /// Text after the last whitespace, held back so a secret split across
+    /// two pieces is still redacted whole.
+    pending: String,
+    stop: PathBuf,
+}
+
+impl ReplyWriter {
+    pub(crate) fn push(&mut self, text: &str) {
+        self.pending.push_str(text);
+        let Some(cut) = self
+            .pending
+            .char_indices()
+            .rfind(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+        else {
+            return;
+        };
+        let rest = self.pending.split_off(cut);
+        let ready = std::mem::replace(&mut self.pending, rest);
+        let ready = self.redactor.redact(&ready).into_owned();
+        self.write(ChunkKind::Delta, ready);
+    }
+
+    /// Write out what is held back and close the reply.
+    pub(crate) fn finish(mut self, stopped: bool) {
+        if !self.pending.is_empty() {
+            let rest = std::mem::take(&mut self.pending);
+            let rest = self.redactor.redact(&rest).into_owned();
+            self.write(ChunkKind::Delta, rest);
+        }
+        let kind = if stopped {
+            ChunkKind::Stopped
+        } else {
+            ChunkKind::End
+        };
+        self.write(kind, String::new());
+        if stopped {
+            let _ = std::fs::remove_file(&self.stop);
+        }
+    }
+
+    fn write(&mut self, kind: ChunkKind, text: String) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        let chunk = ChatChunk {
+            ts: Utc::now(),
+            reply: self.reply.clone(),
+            agent: self.agent.clone(),
+            stage: self.stage.clone(),
+            kind,
+            text,
+        };
+        let Ok(mut line) = serde_json::to_string(&chunk) else {
+            return;
+        };
+        line.push('\n');
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            tracing::warn!("Failed to stream {}'s reply: {}", self.agent, e);
+            self.file = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redaction::{RedactionConfig, RedactionRule};
+
+    #[tokio::test]
+    async fn replies_stream_redacted_and_stop_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let redactor = RedactionConfig {
+            rules: vec![RedactionRule {
+                pattern: "tok_[a-z]+".into(),
+                replacement: "[REDACTED]".into(),
+            }],
+        }
+        .compile()
+        .unwrap();
+        let stream = ChatStream::new(dir.path(), Some("implement"));
+
+        let mut reply = stream.reply("backend", redactor);
+        for piece in ["Using tok_se", "cret now", ", done"] {
+            reply.push(piece);
+        }
+        request_stop(dir.path(), "backend").unwrap();
+        tokio::time::timeout(Duration::from_secs(2), stream.stop_requested("backend"))
+            .await
+            .expect("the stop request is seen");
+        reply.finish(true);
+
+        let content = std::fs::read_to_string(dir.path().join(CHAT_STREAM_FILE)).unwrap();
+        let chunks = parse_chunks(&content);
+        let kinds: Vec<ChunkKind> = chunks.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds.first(), Some(&ChunkKind::Start));
+        assert_eq!(kinds.last(), Some(&ChunkKind::Stopped));
+        let text: String = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(text, "Using [REDACTED] now, done");
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.stage.as_deref() == Some("implement"))
+        );
+        assert!(!stop_path(dir.path(), "backend").exists());
+    }
+}
@@ -0,0 +1,155 @@
+//! MCP server for [`SessionToolbox`] over stdio.
+//!
+//! Provider CLIs that speak the Model Context Protocol (Claude Code, Codex)
+//! start `ccswarm tools serve` as a subprocess and exchange newline-delimited
+//! JSON-RPC 2.0 messages with it. Only the tool methods are implemented:
+//! `initialize`, `tools/list`, `tools/call` and `ping`.
+
+use anyhow::Result;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::tools::{SessionTool, SessionToolbox};
+
+/// Name the server registers under; providers prefix tool names with it
+/// (`mcp__ccswarm__run_command`).
+pub const SERVER_NAME: &str = "ccswarm";
+
+/// Protocol revision answered when the client does not ask for one.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serve `toolbox` until `input` closes.
+pub async fn serve<R, W>(toolbox: &SessionToolbox, input: R, mut output: W) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = input.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = handle_line(toolbox, &line).await else {
+            continue;
+        };
+        output.write_all(response.to_string().as_bytes()).await?;
+        output.write_all(b"\n").await?;
+        output.flush().await?;
+    }
+    Ok(())
+}
+
+/// Answer one message; notifications get no response.
+async fn handle_line(toolbox: &SessionToolbox, line: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => return Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+    };
+    let id = message.get("id").cloned()?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": SERVER_NAME, "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({
+            "tools": toolbox
+                .tools()
+                .into_iter()
+                .map(SessionTool::definition)
+                .collect::<Vec<_>>(),
+        }),
+        "tools/call" => {
+            let Some(name) = params.get("name").and_then(Value::as_str) else {
+                return Some(error(id, INVALID_PARAMS, "Missing tool name"));
+            };
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            let output = toolbox.call(name, arguments).await;
+            json!({
+                "content": [{ "type": "text", "text": output.text }],
+                "isError": output.is_error,
+            })
+        }
+        other => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Method not found: {other}"),
+            ));
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redaction::Redactor;
+    use crate::workflow::flow::MovementPermission;
+
+    #[tokio::test]
+    async fn test_serves_tools_over_json_rpc() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        let toolbox = SessionToolbox::new(
+            dir.path(),
+            MovementPermission::Readonly,
+            &[],
+            Redactor::default(),
+        )
+        .await
+        .unwrap();
+
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18"}}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"read_file","arguments":{"path":"notes.txt"}}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"run_command","arguments":{"command":"true"}}}"#,
+            r#"{"jsonrpc":"2.0","id":5,"method":"resources/list"}"#,
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        serve(&toolbox, input.as_bytes(), &mut output)
+            .await
+            .unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2025-06-18");
+        let names: Vec<&str> = responses[1]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["read_file", "search_code"]);
+        assert_eq!(responses[2]["result"]["content"][0]["text"], "hello");
+        assert_eq!(responses[2]["result"]["isError"], false);
+        assert_eq!(responses[3]["result"]["isError"], true);
+        assert_eq!(responses[4]["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
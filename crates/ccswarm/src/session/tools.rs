@@ -0,0 +1,1369 @@
+//! Typed tools for function-calling providers.
+//!
+//! Instead of free-form shell text, an agent on a stage with
+//! `typed_tools: true` gets four structured tools served over MCP by
+//! `ccswarm tools serve`:
+//!
+//! | tool               | does                                      | permission tool |
+//! |--------------------|-------------------------------------------|-----------------|
+//! | `run_command`      | `sh -c` in the session directory          | `bash`          |
+//! | `command_progress` | next update or result of a running command | `bash`         |
+//! | `read_file`        | read a file, optionally a line range      | `read`          |
+//! | `write_file`       | create or overwrite a file                | `write`         |
+//! | `search_code`      | regex search across the session files     | `grep`          |
+//! | `notes`            | view or edit the agent's scratchpad       | `notes`         |
+//!
+//! `notes` is only served for a session started with an agent name; see
+//! [`crate::agent::scratchpad`].
+//!
+//! Every call is checked before it runs: the stage's
+//! [`PermissionEnforcer`] must allow the tool, file paths must resolve inside
+//! the session directory (symlinks included), and the [`SecurityHook`]
+//! rejects protected files and blocked commands. Output passes through the
+//! project's redaction rules and, when the toolbox has a prompt guard, is
+//! fenced as untrusted before the model sees it. Long `run_command` output is
+//! sampled down to its salient lines first, and a whole-file `read_file` too
+//! long to return is condensed to an [`excerpt`](super::excerpt).
+//!
+//! A `run_command` still running after `partial_output.interval_secs` returns
+//! an interim [`ProgressSummary`] ("300 tests passed so far, 2 failed") and a
+//! job id instead of blocking, so the model can react before the command
+//! ends: `command_progress` waits for the next update or the result, or
+//! cancels the command. Updates per command are capped by count and by
+//! estimated tokens; past either cap the next call waits for the result.
+//!
+//! With auto-accept on, a `run_command` whose [`command_risk`] score is above
+//! the threshold is held as a `command` approval until a reviewer decides,
+//! with a provider-written explanation attached when an explainer is set.
+
+use ai_session::output::ProgressSummary;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::agent::scratchpad::{EntryKind, Scratchpad, ScratchpadConfig};
+use crate::hitl::{ApprovalStore, Gate, GateOutcome};
+use crate::hooks::{HookContext, HookRegistry, PreToolUseInput, SecurityHook};
+use crate::redaction::Redactor;
+use crate::security::command_risk::{self, CommandExplainer};
+use crate::security::prompt_guard::{self, AuditEntry, PromptGuard};
+use crate::session::AutoAcceptConfig;
+use crate::session::sampling::ContextSampler;
+use crate::workflow::PermissionEnforcer;
+use crate::workflow::flow::MovementPermission;
+
+/// Output returned to the model per call.
+const MAX_OUTPUT_CHARS: usize = 30_000;
+/// Files larger than this are not read whole.
+const MAX_READ_BYTES: u64 = 1024 * 1024;
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 120;
+const MAX_COMMAND_TIMEOUT_SECS: u64 = 1800;
+const DEFAULT_SEARCH_RESULTS: usize = 100;
+/// Directories `search_code` never descends into.
+const SKIPPED_DIRS: &[&str] = &[".git", "target", "node_modules", ".ccswarm"];
+/// How often a held command checks for a decision.
+const APPROVAL_POLL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionTool {
+    RunCommand,
+    CommandProgress,
+    ReadFile,
+    WriteFile,
+    SearchCode,
+    Notes,
+}
+
+impl SessionTool {
+    pub const ALL: [SessionTool; 6] = [
+        Self::RunCommand,
+        Self::CommandProgress,
+        Self::ReadFile,
+        Self::WriteFile,
+        Self::SearchCode,
+        Self::Notes,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::RunCommand => "run_command",
+            Self::CommandProgress => "command_progress",
+            Self::ReadFile => "read_file",
+            Self::WriteFile => "write_file",
+            Self::SearchCode => "search_code",
+            Self::Notes => "notes",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    /// Name of the tool in the stage permission model.
+    fn permission_name(self) -> &'static str {
+        match self {
+            Self::RunCommand | Self::CommandProgress => "bash",
+            Self::ReadFile => "read",
+            Self::WriteFile => "write",
+            Self::SearchCode => "grep",
+            Self::Notes => "notes",
+        }
+    }
+
+    /// Name [`SecurityHook`] and other tool hooks know the tool by.
+    fn hook_name(self) -> &'static str {
+        match self {
+            Self::RunCommand | Self::CommandProgress => "Bash",
+            Self::ReadFile => "Read",
+            Self::WriteFile => "Write",
+            Self::SearchCode => "Grep",
+            Self::Notes => "Notes",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::RunCommand => {
+                "Run a shell command in the session's working directory and return its exit code and output. A command still running after the progress interval returns a summary of its output so far and a job_id for command_progress."
+            }
+            Self::CommandProgress => {
+                "Wait for the next progress summary or the final result of a run_command that is still running, or stop it with cancel: true."
+            }
+            Self::ReadFile => {
+                "Read a text file inside the session's working directory, optionally a range of lines. A file too long to return whole comes back condensed to its imports, signatures and the lines around any focus keywords, with markers for the omitted line ranges."
+            }
+            Self::WriteFile => {
+                "Create or overwrite a file inside the session's working directory; parent directories are created."
+            }
+            Self::SearchCode => {
+                "Search files inside the session's working directory for a regular expression and return matching lines as path:line: text."
+            }
+            Self::Notes => {
+                "View or edit your scratchpad: notes, TODOs and hypotheses kept across tasks and sessions. Old entries are summarized when it grows past its size budget."
+            }
+        }
+    }
+
+    /// JSON Schema of the tool's arguments.
+    pub fn input_schema(self) -> Value {
+        match self {
+            Self::RunCommand => json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "description": "Shell command, run with sh -c" },
+                    "timeout_secs": {
+                        "type": "integer", "minimum": 1, "maximum": MAX_COMMAND_TIMEOUT_SECS,
+                        "description": format!("Kill the command after this many seconds (default {DEFAULT_COMMAND_TIMEOUT_SECS})")
+                    }
+                },
+                "required": ["command"],
+                "additionalProperties": false
+            }),
+            Self::CommandProgress => json!({
+                "type": "object",
+                "properties": {
+                    "job_id": { "type": "string", "description": "job_id from run_command's progress summary" },
+                    "cancel": { "type": "boolean", "description": "Kill the command instead of waiting for it" }
+                },
+                "required": ["job_id"],
+                "additionalProperties": false
+            }),
+            Self::ReadFile => json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path relative to the working directory" },
+                    "offset": { "type": "integer", "minimum": 1, "description": "First line to return (1-based)" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Number of lines to return" },
+                    "focus": {
+                        "type": "array", "items": { "type": "string" },
+                        "description": "Keywords from the task; lines mentioning them are kept when a long file is condensed"
+                    }
+                },
+                "required": ["path"],
+                "additionalProperties": false
+            }),
+            Self::WriteFile => json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path relative to the working directory" },
+                    "content": { "type": "string", "description": "Full new file content" }
+                },
+                "required": ["path", "content"],
+                "additionalProperties": false
+            }),
+            Self::SearchCode => json!({
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string", "description": "Regular expression (Rust regex syntax)" },
+                    "path": { "type": "string", "description": "File or directory to search (default: the working directory)" },
+                    "max_results": {
+                        "type": "integer", "minimum": 1,
+                        "description": format!("Stop after this many matches (default {DEFAULT_SEARCH_RESULTS})")
+                    }
+                },
+                "required": ["pattern"],
+                "additionalProperties": false
+            }),
+            Self::Notes => json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["view", "add", "done", "remove"] },
+                    "kind": {
+                        "type": "string", "enum": ["note", "todo", "hypothesis"],
+                        "description": "Kind of entry to add (default note)"
+                    },
+                    "text": { "type": "string", "description": "Text of the entry to add" },
+                    "id": { "type": "integer", "minimum": 1, "description": "Entry to mark done or remove" }
+                },
+                "required": ["action"],
+                "additionalProperties": false
+            }),
+        }
+    }
+
+    /// MCP `tools/list` entry.
+    pub fn definition(self) -> Value {
+        json!({
+            "name": self.name(),
+            "description": self.description(),
+            "inputSchema": self.input_schema(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RunCommandArgs {
+    command: String,
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandProgressArgs {
+    job_id: String,
+    cancel: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReadFileArgs {
+    path: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    #[serde(default)]
+    focus: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WriteFileArgs {
+    path: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum NotesAction {
+    View,
+    Add,
+    Done,
+    Remove,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NotesArgs {
+    action: NotesAction,
+    kind: Option<EntryKind>,
+    text: Option<String>,
+    id: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchCodeArgs {
+    pattern: String,
+    path: Option<String>,
+    max_results: Option<usize>,
+}
+
+/// Result of one tool call, as shown to the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolOutput {
+    pub text: String,
+    pub is_error: bool,
+}
+
+impl ToolOutput {
+    fn ok(text: String) -> Self {
+        Self {
+            text,
+            is_error: false,
+        }
+    }
+
+    fn error(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            is_error: true,
+        }
+    }
+}
+
+/// The `partial_output` config knob: interim summaries of long
+/// `run_command` calls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PartialOutputConfig {
+    pub enabled: bool,
+    /// Seconds between progress summaries of a running command.
+    pub interval_secs: u64,
+    /// Summaries sent per command; after that the next call waits for the
+    /// result.
+    pub max_updates: usize,
+    /// Estimated tokens all summaries of one command may spend.
+    pub max_tokens: usize,
+}
+
+impl Default for PartialOutputConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 30,
+            max_updates: 10,
+            max_tokens: 2_000,
+        }
+    }
+}
+
+/// A `run_command` call whose output is collected while it runs.
+struct CommandJob {
+    id: String,
+    child: tokio::process::Child,
+    stdout: Arc<std::sync::Mutex<Vec<u8>>>,
+    stderr: Arc<std::sync::Mutex<Vec<u8>>>,
+    readers: Vec<tokio::task::JoinHandle<()>>,
+    started: Instant,
+    deadline: Instant,
+    timeout_secs: u64,
+    updates: usize,
+    tokens: usize,
+    /// A cap was hit; wait for the result without further summaries.
+    exhausted: bool,
+}
+
+impl CommandJob {
+    fn spawn(root: &Path, command: &str, timeout_secs: u64) -> Result<Self> {
+        let mut child = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(root)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to spawn sh")?;
+        let stdout = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let stderr = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut readers = Vec::new();
+        if let Some(pipe) = child.stdout.take() {
+            readers.push(capture(pipe, stdout.clone()));
+        }
+        if let Some(pipe) = child.stderr.take() {
+            readers.push(capture(pipe, stderr.clone()));
+        }
+        let started = Instant::now();
+        Ok(Self {
+            id: format!("job-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]),
+            child,
+            stdout,
+            stderr,
+            readers,
+            started,
+            deadline: started + Duration::from_secs(timeout_secs),
+            timeout_secs,
+            updates: 0,
+            tokens: 0,
+            exhausted: false,
+        })
+    }
+
+    /// Output so far, stdout then stderr.
+    fn partial(&self) -> String {
+        let mut text = String::new();
+        for buffer in [&self.stdout, &self.stderr] {
+            if let Ok(bytes) = buffer.lock() {
+                text.push_str(&String::from_utf8_lossy(&bytes));
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// The final result once the process has exited.
+    async fn finish(self, status: std::process::ExitStatus) -> ToolOutput {
+        for reader in self.readers {
+            let _ = reader.await;
+        }
+        let code = status
+            .code()
+            .map_or_else(|| "killed by signal".to_string(), |c| c.to_string());
+        let mut text = format!("exit code: {code}\n");
+        for (label, buffer) in [("stdout", &self.stdout), ("stderr", &self.stderr)] {
+            let bytes = buffer.lock().map(|b| b.clone()).unwrap_or_default();
+            if !bytes.is_empty() {
+                text.push_str(&format!("{label}:\n{}\n", String::from_utf8_lossy(&bytes)));
+            }
+        }
+        ToolOutput {
+            text,
+            is_error: !status.success(),
+        }
+    }
+}
+
+/// Append everything read from `pipe` to `into` until it closes.
+fn capture(
+    mut pipe: impl AsyncRead + Unpin + Send + 'static,
+    into: Arc<std::sync::Mutex<Vec<u8>>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut chunk = [0u8; 8192];
+        while let Ok(n) = pipe.read(&mut chunk).await {
+            if n == 0 {
+                break;
+            }
+            if let Ok(mut buffer) = into.lock() {
+                buffer.extend_from_slice(&chunk[..n]);
+            }
+        }
+    })
+}
+
+/// The typed tools of one session, bound to its working directory and
+/// stage permissions.
+pub struct SessionToolbox {
+    root: PathBuf,
+    permissions: PermissionEnforcer,
+    hooks: HookRegistry,
+    redactor: Redactor,
+    prompt_guard: PromptGuard,
+    sampler: ContextSampler,
+    parser: ai_session::output::OutputParser,
+    agent_id: String,
+    command_gate: Option<CommandGate>,
+    partial_output: PartialOutputConfig,
+    /// Time between progress summaries, from `partial_output.interval_secs`.
+    progress_interval: Duration,
+    /// Commands that returned a progress summary and are still running.
+    jobs: tokio::sync::Mutex<HashMap<String, CommandJob>>,
+    scratchpad: Option<ScratchpadAccess>,
+}
+
+/// The agent whose scratchpad `notes` edits, and where it lives.
+struct ScratchpadAccess {
+    repo: PathBuf,
+    agent: String,
+    config: ScratchpadConfig,
+}
+
+/// Holds risky commands for approval while auto-accept is on.
+struct CommandGate {
+    approvals_root: PathBuf,
+    policy: AutoAcceptConfig,
+    explainer: Option<Arc<dyn CommandExplainer>>,
+    poll: Duration,
+}
+
+impl CommandGate {
+    /// Return once `command` may run: straight away when it scores at or
+    /// below the threshold, otherwise when a reviewer approves it.
+    async fn clear(&self, agent_id: &str, command: &str) -> Result<()> {
+        let assessment = command_risk::assess(command);
+        if !assessment.exceeds(self.policy.risk_threshold) {
+            return Ok(());
+        }
+        let store = ApprovalStore::new(&self.approvals_root);
+        let id = format!("cmd-{}", &uuid::Uuid::new_v4().simple().to_string()[..12]);
+        store.request_command(&id, &assessment, agent_id).await?;
+        tracing::info!(
+            "Holding command for approval as {} (risk {}/10): {}",
+            id,
+            assessment.score,
+            assessment.summary()
+        );
+
+        if let Some(explainer) = self.explainer.clone() {
+            let root = self.approvals_root.clone();
+            let id = id.clone();
+            let assessment = assessment.clone();
+            tokio::spawn(async move {
+                match explainer.explain(&assessment).await {
+                    Ok(text) => {
+                        if let Err(e) = ApprovalStore::new(&root).set_explanation(&id, &text).await
+                        {
+                            tracing::warn!("Failed to attach explanation to {}: {}", id, e);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Could not explain held command {}: {}", id, e),
+                }
+            });
+        }
+
+        let timeout = Duration::from_secs(self.policy.approval_timeout_secs);
+        match store
+            .wait_for_decision(&id, Gate::Command, self.poll, timeout)
+            .await?
+        {
+            GateOutcome::Approved => Ok(()),
+            GateOutcome::Rejected(reason) => bail!(
+                "Command refused by reviewer ({}): {}",
+                id,
+                reason.as_deref().unwrap_or("no reason given")
+            ),
+            GateOutcome::TimedOut => bail!(
+                "Command held for approval as {} was not approved within {}s",
+                id,
+                timeout.as_secs()
+            ),
+        }
+    }
+}
+
+impl SessionToolbox {
+    /// Toolbox for `working_dir` at `permission`. A non-empty `allowed` list
+    /// narrows the tools the same way a stage's `tools:` does.
+    pub async fn new(
+        working_dir: &Path,
+        permission: MovementPermission,
+        allowed: &[String],
+        redactor: Redactor,
+    ) -> Result<Self> {
+        let root = working_dir
+            .canonicalize()
+            .with_context(|| format!("Working directory {} not found", working_dir.display()))?;
+        let hooks = HookRegistry::new();
+        hooks
+            .register_tool_hook(Arc::new(SecurityHook::new()))
+            .await;
+        Ok(Self {
+            root,
+            permissions: PermissionEnforcer::from_movement(permission, allowed),
+            hooks,
+            redactor,
+            prompt_guard: PromptGuard::disabled(),
+            sampler: ContextSampler::default(),
+            parser: ai_session::output::OutputParser::new(),
+            agent_id: "typed-tools".to_string(),
+            command_gate: None,
+            progress_interval: Duration::from_secs(PartialOutputConfig::default().interval_secs),
+            partial_output: PartialOutputConfig::default(),
+            jobs: tokio::sync::Mutex::new(HashMap::new()),
+            scratchpad: None,
+        })
+    }
+
+    /// Serve `notes` on `agent`'s scratchpad in the main worktree `repo`.
+    pub fn with_scratchpad(mut self, repo: &Path, agent: &str, config: ScratchpadConfig) -> Self {
+        self.scratchpad = Some(ScratchpadAccess {
+            repo: repo.to_path_buf(),
+            agent: agent.to_string(),
+            config,
+        });
+        self
+    }
+
+    /// Hold commands scoring above `policy.risk_threshold` as approvals under
+    /// `approvals_root` while `policy.enabled`, explained by `explainer`.
+    pub fn with_command_approval(
+        mut self,
+        approvals_root: &Path,
+        policy: AutoAcceptConfig,
+        explainer: Option<Arc<dyn CommandExplainer>>,
+    ) -> Self {
+        self.command_gate = policy.enabled.then(|| CommandGate {
+            approvals_root: approvals_root.to_path_buf(),
+            explainer: explainer.filter(|_| policy.explain),
+            policy,
+            poll: APPROVAL_POLL,
+        });
+        self
+    }
+
+    /// Fence tool output with `guard` before the model sees it. Suspected
+    /// injections are logged to the session directory's security audit log.
+    pub fn with_prompt_guard(mut self, guard: PromptGuard) -> Self {
+        self.prompt_guard = guard;
+        self
+    }
+
+    /// Sample long `run_command` output with `sampler`, finding diagnostics
+    /// and summaries with `parser`.
+    pub fn with_output_sampling(
+        mut self,
+        sampler: ContextSampler,
+        parser: ai_session::output::OutputParser,
+    ) -> Self {
+        self.sampler = sampler;
+        self.parser = parser;
+        self
+    }
+
+    /// Summarize the output of long `run_command` calls per `config`.
+    pub fn with_partial_output(mut self, config: PartialOutputConfig) -> Self {
+        self.progress_interval = Duration::from_secs(config.interval_secs.max(1));
+        self.partial_output = config;
+        self
+    }
+
+    /// Name reported to tool hooks and used for its output log.
+    pub fn with_agent_id(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = agent_id.into();
+        self
+    }
+
+    /// Tools this session may call.
+    pub fn tools(&self) -> Vec<SessionTool> {
+        allowed_tools(&self.permissions)
+            .into_iter()
+            .filter(|t| *t != SessionTool::CommandProgress || self.partial_output.enabled)
+            .filter(|t| *t != SessionTool::Notes || self.scratchpad.is_some())
+            .collect()
+    }
+
+    /// Check and run one call. Refusals and failures come back as error
+    /// output for the model rather than as `Err`.
+    pub async fn call(&self, name: &str, arguments: Value) -> ToolOutput {
+        let Some(tool) = SessionTool::parse(name) else {
+            return ToolOutput::error(format!("Unknown tool '{name}'"));
+        };
+        let (output, refused) = match self.dispatch(tool, arguments).await {
+            Ok(output) => (output, false),
+            Err(e) => (ToolOutput::error(format!("{e:#}")), true),
+        };
+        let redacted = self.redactor.redact(&output.text);
+        if refused {
+            return ToolOutput::error(truncate(&redacted, MAX_OUTPUT_CHARS));
+        }
+        let source = format!("tool:{}", tool.name());
+        let text = if matches!(tool, SessionTool::RunCommand | SessionTool::CommandProgress) {
+            let sampled = self
+                .sampler
+                .ingest(&self.parser, &self.agent_id, &source, &redacted)
+                .await;
+            truncate(&sampled, MAX_OUTPUT_CHARS)
+        } else {
+            truncate(&redacted, MAX_OUTPUT_CHARS)
+        };
+        let (text, suspects) = self.prompt_guard.fence(&source, &text);
+        if !suspects.is_empty() {
+            let mut entry = AuditEntry::injection(&source, suspects);
+            entry.agent = Some(self.agent_id.clone());
+            if let Err(e) = prompt_guard::audit(&self.root, &entry).await {
+                tracing::warn!("Failed to write security audit entry: {}", e);
+            }
+        }
+        ToolOutput {
+            text,
+            is_error: output.is_error,
+        }
+    }
+
+    async fn dispatch(&self, tool: SessionTool, arguments: Value) -> Result<ToolOutput> {
+        let check = self.permissions.check_tool(tool.permission_name());
+        if !check.allowed {
+            bail!(
+                "{} is not allowed: {}",
+                tool.name(),
+                check.reason.unwrap_or_default()
+            );
+        }
+        let invalid =
+            |e: serde_json::Error| anyhow!("Invalid arguments for {}: {}", tool.name(), e);
+        match tool {
+            SessionTool::RunCommand => {
+                let args: RunCommandArgs = serde_json::from_value(arguments).map_err(invalid)?;
+                self.run_command(args).await
+            }
+            SessionTool::CommandProgress => {
+                let args: CommandProgressArgs =
+                    serde_json::from_value(arguments).map_err(invalid)?;
+                self.command_progress(args).await
+            }
+            SessionTool::ReadFile => {
+                let args: ReadFileArgs = serde_json::from_value(arguments).map_err(invalid)?;
+                self.read_file(args).await
+            }
+            SessionTool::WriteFile => {
+                let args: WriteFileArgs = serde_json::from_value(arguments).map_err(invalid)?;
+                self.write_file(args).await
+            }
+            SessionTool::SearchCode => {
+                let args: SearchCodeArgs = serde_json::from_value(arguments).map_err(invalid)?;
+                self.search_code(args).await
+            }
+            SessionTool::Notes => {
+                let args: NotesArgs = serde_json::from_value(arguments).map_err(invalid)?;
+                self.notes(args).await
+            }
+        }
+    }
+
+    /// Run the tool hooks; a hook that stops the call becomes an error.
+    async fn run_hooks(&self, tool: SessionTool, arguments: Value) -> Result<()> {
+        let input = PreToolUseInput {
+            tool_name: tool.hook_name().to_string(),
+            arguments,
+            description: None,
+        };
+        let ctx = HookContext::new(&self.agent_id)
+            .with_working_directory(self.root.display().to_string());
+        let result = self.hooks.run_pre_tool_use(input, ctx).await;
+        if !result.should_continue() {
+            bail!("{} refused by security policy: {:?}", tool.name(), result);
+        }
+        Ok(())
+    }
+
+    /// Resolve `path` inside the session directory and check access to it.
+    async fn checked_path(&self, tool: SessionTool, path: &str, write: bool) -> Result<PathBuf> {
+        let resolved = confine(&self.root, path)?;
+        let relative = resolved
+            .strip_prefix(&self.root)
+            .unwrap_or(&resolved)
+            .to_string_lossy()
+            .to_string();
+        let check = self.permissions.check_file_access(&relative, write);
+        if !check.allowed {
+            bail!("{}", check.reason.unwrap_or_default());
+        }
+        self.run_hooks(tool, json!({ "file_path": relative }))
+            .await?;
+        Ok(resolved)
+    }
+
+    async fn run_command(&self, args: RunCommandArgs) -> Result<ToolOutput> {
+        let check = self.permissions.check_command_execution(&args.command);
+        if !check.allowed {
+            bail!("{}", check.reason.unwrap_or_default());
+        }
+        self.run_hooks(SessionTool::RunCommand, json!({ "command": args.command }))
+            .await?;
+        if let Some(gate) = &self.command_gate {
+            gate.clear(&self.agent_id, &args.command).await?;
+        }
+
+        let timeout = args
+            .timeout_secs
+            .unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS)
+            .clamp(1, MAX_COMMAND_TIMEOUT_SECS);
+        let job = CommandJob::spawn(&self.root, &args.command, timeout)?;
+        self.advance(job).await
+    }
+
+    async fn command_progress(&self, args: CommandProgressArgs) -> Result<ToolOutput> {
+        let mut job = self.jobs.lock().await.remove(&args.job_id).ok_or_else(|| {
+            anyhow!(
+                "No running command with job_id '{}'; it may have finished or been cancelled",
+                args.job_id
+            )
+        })?;
+        if args.cancel.unwrap_or(false) {
+            let _ = job.child.kill().await;
+            let summary = ProgressSummary::of(&self.parser, &job.partial());
+            return Ok(ToolOutput::error(format!(
+                "Cancelled after {}s\n{}\n",
+                job.started.elapsed().as_secs(),
+                summary.render()
+            )));
+        }
+        self.advance(job).await
+    }
+
+    /// Wait for `job` to exit, returning a progress summary instead when the
+    /// interval passes first and the caps allow another one.
+    async fn advance(&self, mut job: CommandJob) -> Result<ToolOutput> {
+        loop {
+            let remaining = job.deadline.saturating_duration_since(Instant::now());
+            let wait = if self.partial_output.enabled && !job.exhausted {
+                remaining.min(self.progress_interval)
+            } else {
+                remaining
+            };
+            match tokio::time::timeout(wait, job.child.wait()).await {
+                Ok(status) => {
+                    let status = status.context("Failed to wait for sh")?;
+                    return Ok(job.finish(status).await);
+                }
+                Err(_) if Instant::now() >= job.deadline => {
+                    let _ = job.child.kill().await;
+                    let summary = ProgressSummary::of(&self.parser, &job.partial());
+                    return Ok(ToolOutput::error(format!(
+                        "Timed out after {}s\n{}\n",
+                        job.timeout_secs,
+                        summary.render()
+                    )));
+                }
+                Err(_) => {
+                    let config = &self.partial_output;
+                    let text = self.progress_text(&job);
+                    // Rough bytes/4 estimate, as elsewhere for token accounting.
+                    let cost = text.len().div_ceil(4);
+                    if job.updates >= config.max_updates || job.tokens + cost > config.max_tokens {
+                        job.exhausted = true;
+                        continue;
+                    }
+                    job.updates += 1;
+                    job.tokens += cost;
+                    self.jobs.lock().await.insert(job.id.clone(), job);
+                    return Ok(ToolOutput::ok(text));
+                }
+            }
+        }
+    }
+
+    fn progress_text(&self, job: &CommandJob) -> String {
+        let summary = ProgressSummary::of(&self.parser, &job.partial());
+        let next = if job.updates + 1 >= self.partial_output.max_updates {
+            "This is the last progress update: the next command_progress call waits for the result."
+                .to_string()
+        } else {
+            format!(
+                "Call command_progress to wait for the next update (every {}s) or the result.",
+                self.progress_interval.as_secs().max(1)
+            )
+        };
+        format!(
+            "still running after {}s (job_id: {}, update {} of at most {})\n{}\n{} Pass cancel: true to stop it.\n",
+            job.started.elapsed().as_secs(),
+            job.id,
+            job.updates + 1,
+            self.partial_output.max_updates,
+            summary.render(),
+            next
+        )
+    }
+
+    async fn read_file(&self, args: ReadFileArgs) -> Result<ToolOutput> {
+        let path = self
+            .checked_path(SessionTool::ReadFile, &args.path, false)
+            .await?;
+        let metadata = tokio::fs::metadata(&path)
+            .await
+            .with_context(|| format!("Cannot read {}", args.path))?;
+        if metadata.is_dir() {
+            bail!("{} is a directory", args.path);
+        }
+        if metadata.len() > MAX_READ_BYTES && args.limit.is_none() {
+            bail!(
+                "{} is {} bytes; pass offset and limit to read part of it",
+                args.path,
+                metadata.len()
+            );
+        }
+        let bytes = tokio::fs::read(&path).await?;
+        let content = String::from_utf8(bytes)
+            .map_err(|_| anyhow!("{} is not a UTF-8 text file", args.path))?;
+        if args.offset.is_none() && args.limit.is_none() {
+            let condensed =
+                super::excerpt::condense(&args.path, &content, &args.focus, MAX_OUTPUT_CHARS);
+            return Ok(ToolOutput::ok(condensed.unwrap_or(content)));
+        }
+        let skip = args.offset.unwrap_or(1).saturating_sub(1);
+        let lines: Vec<&str> = content
+            .lines()
+            .skip(skip)
+            .take(args.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(ToolOutput::ok(lines.join("\n")))
+    }
+
+    async fn write_file(&self, args: WriteFileArgs) -> Result<ToolOutput> {
+        let path = self
+            .checked_path(SessionTool::WriteFile, &args.path, true)
+            .await?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, &args.content)
+            .await
+            .with_context(|| format!("Cannot write {}", args.path))?;
+        Ok(ToolOutput::ok(format!(
+            "Wrote {} bytes to {}",
+            args.content.len(),
+            args.path
+        )))
+    }
+
+    async fn search_code(&self, args: SearchCodeArgs) -> Result<ToolOutput> {
+        let regex = regex::Regex::new(&args.pattern)
+            .map_err(|e| anyhow!("Invalid pattern '{}': {}", args.pattern, e))?;
+        let start = self
+            .checked_path(
+                SessionTool::SearchCode,
+                args.path.as_deref().unwrap_or("."),
+                false,
+            )
+            .await?;
+        let max = args.max_results.unwrap_or(DEFAULT_SEARCH_RESULTS).max(1);
+        let root = self.root.clone();
+        let permissions = self.permissions.clone();
+        let matches = tokio::task::spawn_blocking(move || {
+            search(&root, &start, &regex, max, &|relative| {
+                permissions.check_file_access(relative, false).allowed
+            })
+        })
+        .await?;
+        if matches.is_empty() {
+            return Ok(ToolOutput::ok("No matches".to_string()));
+        }
+        let mut text = matches.join("\n");
+        if matches.len() == max {
+            text.push_str(&format!("\n(stopped after {max} matches)"));
+        }
+        Ok(ToolOutput::ok(text))
+    }
+
+    async fn notes(&self, args: NotesArgs) -> Result<ToolOutput> {
+        let Some(access) = &self.scratchpad else {
+            bail!("This session has no scratchpad");
+        };
+        let mut pad = Scratchpad::load(&access.repo, &access.agent).await?;
+        let changed = match args.action {
+            NotesAction::View => None,
+            NotesAction::Add => {
+                let text = args.text.context("add needs text")?;
+                let id = pad.add(args.kind.unwrap_or(EntryKind::Note), &text)?;
+                Some(format!("Added #{id}"))
+            }
+            NotesAction::Done => {
+                let id = args.id.context("done needs an id")?;
+                pad.complete(id)?;
+                Some(format!("Marked #{id} done"))
+            }
+            NotesAction::Remove => {
+                let id = args.id.context("remove needs an id")?;
+                pad.remove(id)?;
+                Some(format!("Removed #{id}"))
+            }
+        };
+        let mut text = String::new();
+        if let Some(changed) = changed {
+            let folded = pad.compact(access.config.max_chars);
+            pad.save(&access.repo, &access.agent).await?;
+            text.push_str(&changed);
+            if folded > 0 {
+                text.push_str(&format!(
+                    "; {folded} older entr{} summarized to stay within budget",
+                    if folded == 1 { "y" } else { "ies" }
+                ));
+            }
+            text.push_str("\n\n");
+        }
+        if pad.is_empty() {
+            text.push_str("(scratchpad is empty)");
+        } else {
+            text.push_str(&pad.render());
+        }
+        Ok(ToolOutput::ok(text))
+    }
+}
+
+/// Tools `permissions` allow.
+pub fn allowed_tools(permissions: &PermissionEnforcer) -> Vec<SessionTool> {
+    SessionTool::ALL
+        .into_iter()
+        .filter(|t| permissions.check_tool(t.permission_name()).allowed)
+        .collect()
+}
+
+/// Resolve `path` against `root` and make sure the result, after following
+/// symlinks, is still inside `root`. The file itself need not exist.
+pub fn confine(root: &Path, path: &str) -> Result<PathBuf> {
+    let requested = Path::new(path);
+    let joined = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        root.join(requested)
+    };
+
+    // Lexical normalization first, so `a/../../x` cannot walk out.
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    bail!("Path '{}' is outside the working directory", path);
+                }
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+
+    // Then resolve symlinks on the longest existing prefix. A dangling link
+    // counts as existing (`exists()` follows it), so it fails to resolve
+    // rather than passing for a file yet to be created.
+    let mut existing = normalized.as_path();
+    let mut rest = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .with_context(|| format!("Cannot resolve '{}'", path))?;
+    for name in rest.into_iter().rev() {
+        resolved.push(name);
+    }
+    if !resolved.starts_with(root) {
+        bail!("Path '{}' is outside the working directory", path);
+    }
+    Ok(resolved)
+}
+
+fn search(
+    root: &Path,
+    start: &Path,
+    regex: &regex::Regex,
+    max: usize,
+    readable: &dyn Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut matches = Vec::new();
+    let walker = walkdir::WalkDir::new(start)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            !(entry.file_type().is_dir()
+                && entry.depth() > 0
+                && SKIPPED_DIRS.iter().any(|d| entry.file_name() == *d))
+        });
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(root)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .to_string();
+        if !readable(&relative) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue; // binary or unreadable
+        };
+        for (number, line) in content.lines().enumerate() {
+            if regex.is_match(line) {
+                matches.push(format!("{}:{}: {}", relative, number + 1, line.trim_end()));
+                if matches.len() >= max {
+                    return matches;
+                }
+            }
+        }
+    }
+    matches
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        None => text.to_string(),
+        Some((cut, _)) => format!(
+            "{}\n… (output truncated at {} characters)",
+            &text[..cut],
+            max_chars
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn toolbox(dir: &Path, permission: MovementPermission) -> SessionToolbox {
+        SessionToolbox::new(dir, permission, &[], Redactor::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tools_map_to_session_operations() {
+        let dir = tempfile::tempdir().unwrap();
+        let tools = toolbox(dir.path(), MovementPermission::Full).await;
+
+        let out = tools
+            .call(
+                "write_file",
+                json!({"path": "src/lib.rs", "content": "fn alpha() {}\nfn beta() {}\n"}),
+            )
+            .await;
+        assert!(!out.is_error, "{}", out.text);
+
+        let out = tools
+            .call("read_file", json!({"path": "src/lib.rs", "offset": 2}))
+            .await;
+        assert_eq!(out.text, "fn beta() {}");
+
+        let out = tools.call("search_code", json!({"pattern": "fn b"})).await;
+        assert_eq!(out.text, "src/lib.rs:2: fn beta() {}");
+
+        let out = tools
+            .call("run_command", json!({"command": "ls src; exit 4"}))
+            .await;
+        assert!(out.is_error);
+        assert!(out.text.starts_with("exit code: 4\nstdout:\nlib.rs"));
+
+        let out = tools.call("run_command", json!({"cmd": "ls"})).await;
+        assert!(out.text.contains("Invalid arguments for run_command"));
+    }
+
+    #[tokio::test]
+    async fn test_long_files_are_condensed_around_the_focus() {
+        let dir = tempfile::tempdir().unwrap();
+        let tools = toolbox(dir.path(), MovementPermission::Readonly).await;
+        let mut src = String::from("use std::fmt;\n");
+        for i in 0..2000 {
+            src.push_str(&format!("fn f{i}() -> u32 {{\n    {i} + 1\n}}\n"));
+        }
+        src.push_str("fn retry_backoff() -> u32 {\n    7\n}\n");
+        std::fs::write(dir.path().join("big.rs"), &src).unwrap();
+
+        let out = tools
+            .call("read_file", json!({"path": "big.rs", "focus": ["backoff"]}))
+            .await;
+        assert!(!out.is_error, "{}", out.text);
+        assert!(
+            out.text.starts_with("[big.rs has 6004 lines"),
+            "{}",
+            out.text
+        );
+        assert!(out.text.contains("fn retry_backoff() -> u32 {\n    7\n}"));
+        assert!(
+            out.text
+                .contains("use std::fmt;\nfn f0() -> u32 {\n… lines 3-4 omitted …")
+        );
+        assert!(!out.text.contains("truncated at"));
+
+        let out = tools
+            .call(
+                "read_file",
+                json!({"path": "big.rs", "offset": 3, "limit": 2}),
+            )
+            .await;
+        assert_eq!(out.text, "    0 + 1\n}");
+    }
+
+    #[tokio::test]
+    async fn test_security_checks_refuse_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".env"), "SECRET=1").unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("dangling"), dir.path().join("dangling"))
+            .unwrap();
+
+        let full = toolbox(dir.path(), MovementPermission::Full).await;
+        for (tool, args) in [
+            ("read_file", json!({"path": "../etc/passwd"})),
+            ("read_file", json!({"path": "/etc/passwd"})),
+            ("write_file", json!({"path": "escape/x", "content": ""})),
+            ("write_file", json!({"path": "dangling", "content": ""})),
+            ("read_file", json!({"path": ".env"})),
+            ("run_command", json!({"command": "rm -rf /"})),
+            ("run_command", json!({"command": "git push --force"})),
+        ] {
+            let out = full.call(tool, args.clone()).await;
+            assert!(out.is_error, "{tool} {args} was allowed: {}", out.text);
+        }
+        assert!(!outside.path().join("x").exists());
+        assert!(!outside.path().join("dangling").exists());
+
+        let readonly = toolbox(dir.path(), MovementPermission::Readonly).await;
+        assert_eq!(
+            readonly.tools(),
+            [SessionTool::ReadFile, SessionTool::SearchCode]
+        );
+        let out = readonly
+            .call("write_file", json!({"path": "a.txt", "content": "x"}))
+            .await;
+        assert!(out.is_error);
+        assert!(!dir.path().join("a.txt").exists());
+        assert!(
+            readonly
+                .call("run_command", json!({"command": "true"}))
+                .await
+                .is_error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_long_commands_report_progress_before_finishing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tools = toolbox(dir.path(), MovementPermission::Full)
+            .await
+            .with_partial_output(PartialOutputConfig {
+                max_updates: 2,
+                ..PartialOutputConfig::default()
+            });
+        tools.progress_interval = Duration::from_millis(200);
+        assert!(tools.tools().contains(&SessionTool::CommandProgress));
+
+        let script = "for i in 1 2 3; do echo \"test t$i ... ok\"; done; \
+                      echo 'test t4 ... FAILED'; sleep 1; echo done";
+        let first = tools.call("run_command", json!({"command": script})).await;
+        assert!(!first.is_error, "{}", first.text);
+        assert!(
+            first.text.starts_with("still running after"),
+            "{}",
+            first.text
+        );
+        assert!(first.text.contains("3 tests passed so far, 1 failed (t4)"));
+        let job_id = first
+            .text
+            .split("job_id: ")
+            .nth(1)
+            .and_then(|rest| rest.split(',').next())
+            .unwrap()
+            .to_string();
+
+        // The second update is the last one allowed; after it the call waits.
+        let second = tools
+            .call("command_progress", json!({"job_id": job_id}))
+            .await;
+        assert!(
+            second.text.contains("last progress update"),
+            "{}",
+            second.text
+        );
+        let done = tools
+            .call("command_progress", json!({"job_id": job_id}))
+            .await;
+        assert!(done.text.starts_with("exit code: 0"), "{}", done.text);
+        assert!(done.text.contains("done"));
+        let gone = tools
+            .call("command_progress", json!({"job_id": job_id}))
+            .await;
+        assert!(gone.is_error);
+
+        let running = tools
+            .call("run_command", json!({"command": "sleep 5"}))
+            .await;
+        let job_id = running.text.split("job_id: ").nth(1).unwrap()[..12].to_string();
+        let cancelled = tools
+            .call(
+                "command_progress",
+                json!({"job_id": job_id, "cancel": true}),
+            )
+            .await;
+        assert!(cancelled.is_error);
+        assert!(
+            cancelled.text.starts_with("Cancelled after"),
+            "{}",
+            cancelled.text
+        );
+
+        let quiet = toolbox(dir.path(), MovementPermission::Full)
+            .await
+            .with_partial_output(PartialOutputConfig {
+                enabled: false,
+                ..PartialOutputConfig::default()
+            });
+        assert!(!quiet.tools().contains(&SessionTool::CommandProgress));
+    }
+
+    struct FixedExplainer;
+
+    #[async_trait::async_trait]
+    impl CommandExplainer for FixedExplainer {
+        async fn explain(&self, _: &command_risk::CommandAssessment) -> Result<String> {
+            Ok("Deletes victim.txt.".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_risky_commands_wait_for_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("victim.txt"), "x").unwrap();
+        let policy = AutoAcceptConfig {
+            enabled: true,
+            risk_threshold: 3,
+            approval_timeout_secs: 5,
+            explain: true,
+        };
+        let mut tools = toolbox(dir.path(), MovementPermission::Full)
+            .await
+            .with_command_approval(dir.path(), policy, Some(Arc::new(FixedExplainer)));
+        tools.command_gate.as_mut().unwrap().poll = Duration::from_millis(10);
+        let store = ApprovalStore::new(dir.path());
+
+        // Below the threshold: runs without a record.
+        assert!(
+            !tools
+                .call("run_command", json!({"command": "ls"}))
+                .await
+                .is_error
+        );
+        assert!(store.list().await.unwrap().is_empty());
+
+        let reviewer = async {
+            let mut decided = Vec::new();
+            for approve in [false, true] {
+                let pending = loop {
+                    let records = store.list().await.unwrap();
+                    if let Some(r) = records.into_iter().find(|r| !decided.contains(&r.id)) {
+                        break r;
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                };
+                assert_eq!(pending.gate, Gate::Command);
+                assert_eq!(pending.command.as_ref().unwrap().score, 5);
+                store
+                    .decide(&pending.id, Gate::Command, approve, Some("checked"))
+                    .await
+                    .unwrap();
+                decided.push(pending.id);
+            }
+            decided
+        };
+        let calls = async {
+            let refused = tools
+                .call("run_command", json!({"command": "rm victim.txt"}))
+                .await;
+            assert!(refused.is_error);
+            assert!(
+                refused.text.contains("refused by reviewer"),
+                "{}",
+                refused.text
+            );
+            assert!(dir.path().join("victim.txt").exists());
+
+            let approved = tools
+                .call("run_command", json!({"command": "rm victim.txt"}))
+                .await;
+            assert!(!approved.is_error, "{}", approved.text);
+            assert!(!dir.path().join("victim.txt").exists());
+        };
+        let (decided, ()) = tokio::join!(reviewer, calls);
+
+        let explanation = loop {
+            if let Some(text) = store.explanation(&decided[1]).await.unwrap() {
+                break text;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(explanation, "Deletes victim.txt.");
+    }
+}
@@ -0,0 +1,161 @@
+//! Trace context propagation for OpenTelemetry.
+//!
+//! Spans are exported by the OTLP layer installed in `main.rs` (feature
+//! `otel`). Within one process `tracing` already nests spans correctly; this
+//! module covers the hops where that chain breaks — messages that cross the
+//! [`CoordinationBus`](crate::governance::CoordinationBus) are handled by a
+//! different task, so the sender's W3C `traceparent` travels with the message
+//! and the receiver re-parents its span onto it. The result is one trace per
+//! task in Jaeger, from queue pickup through delegation, provider calls and
+//! quality gates.
+//!
+//! Without the `otel` feature the context is always empty and attaching it is
+//! a no-op.
+
+use serde::{Deserialize, Serialize};
+
+/// W3C trace context captured from the active span.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceContext {
+    /// `traceparent` header value, e.g. `00-<trace-id>-<span-id>-01`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+}
+
+impl TraceContext {
+    /// Capture the context of the current `tracing` span.
+    #[cfg(feature = "otel")]
+    pub fn current() -> Self {
+        use opentelemetry::propagation::TextMapPropagator;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let cx = tracing::Span::current().context();
+        let mut carrier = std::collections::HashMap::new();
+        opentelemetry_sdk::propagation::TraceContextPropagator::new()
+            .inject_context(&cx, &mut carrier);
+        Self {
+            traceparent: carrier.remove("traceparent"),
+        }
+    }
+
+    /// Capture the context of the current `tracing` span.
+    #[cfg(not(feature = "otel"))]
+    pub fn current() -> Self {
+        Self::default()
+    }
+
+    /// Trace ID encoded in the context, if any.
+    pub fn trace_id(&self) -> Option<&str> {
+        self.traceparent.as_deref()?.split('-').nth(1)
+    }
+
+    /// Make `span` a child of the span this context was captured from.
+    #[cfg(feature = "otel")]
+    pub fn attach(&self, span: &tracing::Span) {
+        use opentelemetry::propagation::TextMapPropagator;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let Some(traceparent) = &self.traceparent else {
+            return;
+        };
+        let carrier =
+            std::collections::HashMap::from([("traceparent".to_string(), traceparent.clone())]);
+        let cx = opentelemetry_sdk::propagation::TraceContextPropagator::new().extract(&carrier);
+        if let Err(e) = span.set_parent(cx) {
+            tracing::debug!("otel: could not re-parent span: {e}");
+        }
+    }
+
+    /// Make `span` a child of the span this context was captured from.
+    #[cfg(not(feature = "otel"))]
+    pub fn attach(&self, _span: &tracing::Span) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_id_is_second_traceparent_field() {
+        let cx = TraceContext {
+            traceparent: Some(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+            ),
+        };
+        assert_eq!(cx.trace_id(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert_eq!(TraceContext::default().trace_id(), None);
+    }
+
+    #[test]
+    fn test_current_without_active_trace_is_empty() {
+        assert_eq!(TraceContext::current(), TraceContext::default());
+    }
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod otel_tests {
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, SpanData, SpanExporter};
+    use tracing::Instrument;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::governance::{AgentMessage, CoordinationBus};
+
+    #[derive(Debug, Clone, Default)]
+    struct Collect(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanExporter for Collect {
+        async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+            self.0.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bus_handling_span_is_child_of_send_span() {
+        let spans = Collect::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(spans.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let repo = tempfile::TempDir::new().unwrap();
+        let bus = CoordinationBus::new(repo.path()).await.unwrap();
+        bus.send_message(AgentMessage::Heartbeat {
+            agent_id: "frontend".to_string(),
+            timestamp: chrono::Utc::now(),
+        })
+        .instrument(tracing::info_span!("task"))
+        .await
+        .unwrap();
+
+        let envelope = bus.receive_traced().await.unwrap();
+        drop(envelope.handling_span().entered());
+
+        // The persisted copy carries the same context.
+        let persisted = bus.load_persisted().await.unwrap();
+        assert_eq!(persisted.len(), 1);
+        assert_eq!(persisted[0].trace, envelope.trace);
+
+        let spans = spans.0.lock().unwrap();
+        let by_name = |name: &str| {
+            spans
+                .iter()
+                .find(|span| span.name == name)
+                .unwrap_or_else(|| panic!("no {name} span exported"))
+        };
+        let send = by_name("bus.send");
+        let handle = by_name("bus.handle");
+        assert_eq!(handle.parent_span_id, send.span_context.span_id());
+        assert_eq!(handle.span_context.trace_id(), send.span_context.trace_id());
+        assert_eq!(
+            envelope.trace.trace_id(),
+            Some(send.span_context.trace_id().to_string().as_str())
+        );
+    }
+}
@@ -0,0 +1,402 @@
+//! Chat view: every agent's replies in a run as bubbles, filled in while the
+//! provider is still writing them (see [`crate::session::stream`]).
+//!
+//! The view follows the newest reply until the selection moves up. `s` on a
+//! reply that is still being written returns [`Action::Stop`]; the loop
+//! takes the agent with [`ChatApp::take_stop`] and asks the run to stop it.
+
+use super::Action;
+use crate::session::stream::{ChatChunk, ChunkKind};
+use chrono::{DateTime, Utc};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyState {
+    Streaming,
+    Done,
+    Stopped,
+}
+
+/// One provider call's reply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bubble {
+    pub reply: String,
+    pub agent: String,
+    pub stage: Option<String>,
+    pub started: DateTime<Utc>,
+    pub text: String,
+    pub state: ReplyState,
+}
+
+/// Fold streamed chunks into replies, oldest first.
+pub fn bubbles(chunks: &[ChatChunk]) -> Vec<Bubble> {
+    let mut bubbles: Vec<Bubble> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for chunk in chunks {
+        let i = *index.entry(&chunk.reply).or_insert_with(|| {
+            bubbles.push(Bubble {
+                reply: chunk.reply.clone(),
+                agent: chunk.agent.clone(),
+                stage: chunk.stage.clone(),
+                started: chunk.ts,
+                text: String::new(),
+                state: ReplyState::Streaming,
+            });
+            bubbles.len() - 1
+        });
+        let bubble = &mut bubbles[i];
+        bubble.text.push_str(&chunk.text);
+        match chunk.kind {
+            ChunkKind::Start | ChunkKind::Delta => {}
+            ChunkKind::End => bubble.state = ReplyState::Done,
+            ChunkKind::Stopped => bubble.state = ReplyState::Stopped,
+        }
+    }
+    bubbles
+}
+
+/// Break `text` into lines of at most `width` characters, at spaces where
+/// possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for source in text.trim_end().split('\n') {
+        let mut line = String::new();
+        for word in source.split(' ') {
+            let mut word = word.to_string();
+            let used = line.chars().count();
+            if used > 0 && used + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.chars().count() > width {
+                let rest = word.split_off(word.char_indices().nth(width).map_or(0, |(i, _)| i));
+                lines.push(std::mem::replace(&mut word, rest));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+pub struct ChatApp {
+    bubbles: Vec<Bubble>,
+    /// Show only this agent's replies.
+    agent: Option<String>,
+    /// Index into the shown replies.
+    selected: usize,
+    /// Keep the newest reply selected as replies arrive.
+    follow: bool,
+    status: Option<String>,
+    stop: Option<String>,
+}
+
+impl ChatApp {
+    pub fn new(bubbles: Vec<Bubble>) -> Self {
+        let mut app = Self {
+            bubbles: Vec::new(),
+            agent: None,
+            selected: 0,
+            follow: true,
+            status: None,
+            stop: None,
+        };
+        app.set_bubbles(bubbles);
+        app
+    }
+
+    /// Replace the replies; a following view moves to the newest.
+    pub fn set_bubbles(&mut self, bubbles: Vec<Bubble>) {
+        self.bubbles = bubbles;
+        let shown = self.shown().len();
+        self.selected = if self.follow {
+            shown.saturating_sub(1)
+        } else {
+            self.selected.min(shown.saturating_sub(1))
+        };
+    }
+
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    /// The agent [`Action::Stop`] refers to.
+    pub fn take_stop(&mut self) -> Option<String> {
+        self.stop.take()
+    }
+
+    fn shown(&self) -> Vec<&Bubble> {
+        self.bubbles
+            .iter()
+            .filter(|b| self.agent.as_ref().is_none_or(|agent| &b.agent == agent))
+            .collect()
+    }
+
+    fn agents(&self) -> Vec<&str> {
+        let mut agents: Vec<&str> = self.bubbles.iter().map(|b| b.agent.as_str()).collect();
+        agents.sort();
+        agents.dedup();
+        agents
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        self.status = None;
+        let key = super::settings::keys().translate(key);
+        let last = self.shown().len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = (self.selected + 1).min(last);
+                self.follow = self.selected == last;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                self.follow = self.selected == last;
+            }
+            KeyCode::Char('g') | KeyCode::Home => {
+                self.selected = 0;
+                self.follow = last == 0;
+            }
+            KeyCode::Char('G') | KeyCode::End => {
+                self.selected = last;
+                self.follow = true;
+            }
+            KeyCode::Char('a') => {
+                let agents = self.agents();
+                let next = match &self.agent {
+                    None => agents.first(),
+                    Some(current) => agents
+                        .iter()
+                        .position(|a| a == current)
+                        .and_then(|i| agents.get(i + 1)),
+                };
+                self.agent = next.map(|a| a.to_string());
+                self.follow = true;
+                let bubbles = std::mem::take(&mut self.bubbles);
+                self.set_bubbles(bubbles);
+            }
+            KeyCode::Char('s') => match self.shown().get(self.selected) {
+                Some(bubble) if bubble.state == ReplyState::Streaming => {
+                    self.stop = Some(bubble.agent.clone());
+                    return Action::Stop;
+                }
+                Some(_) => self.set_status(crate::t!("tui-reply-finished")),
+                None => {}
+            },
+            KeyCode::Char('R') => return Action::Reload,
+            _ => {}
+        }
+        Action::None
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let theme = super::settings::theme();
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let shown = self.shown();
+        let streaming = shown
+            .iter()
+            .filter(|b| b.state == ReplyState::Streaming)
+            .count();
+        let mut title = vec![
+            Span::styled(
+                " ccswarm chat ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" {} replies · {streaming} streaming", shown.len())),
+        ];
+        if let Some(agent) = &self.agent {
+            title.push(Span::raw(format!(" · {agent} only")));
+        }
+        frame.render_widget(
+            Paragraph::new(Line::from(title)).style(theme.header()),
+            header,
+        );
+
+        let palette = [
+            theme.accent,
+            theme.info,
+            theme.special,
+            theme.ok,
+            theme.warn,
+        ];
+        let agents = self.agents();
+        let color_of = |agent: &str| -> Color {
+            let i = agents.iter().position(|a| *a == agent).unwrap_or(0);
+            palette[i % palette.len()]
+        };
+
+        let width = body.width.saturating_sub(4) as usize;
+        let mut lines: Vec<Line> = Vec::new();
+        let mut selected_span = (0, 0);
+        for (i, bubble) in shown.iter().enumerate() {
+            let start = lines.len();
+            let color = color_of(&bubble.agent);
+            let bar = if i == self.selected {
+                Style::default().fg(color).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            let mut head = vec![
+                Span::styled(if i == self.selected { "▌ " } else { "  " }, bar),
+                Span::styled(
+                    bubble.agent.clone(),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ),
+            ];
+            if let Some(stage) = &bubble.stage {
+                head.push(Span::styled(
+                    format!(" · {stage}"),
+                    Style::default().fg(theme.muted),
+                ));
+            }
+            head.push(Span::styled(
+                format!(" · {}", bubble.started.format("%H:%M:%S")),
+                Style::default().fg(theme.muted),
+            ));
+            match bubble.state {
+                ReplyState::Streaming => {
+                    head.push(Span::styled(" ● writing…", Style::default().fg(theme.warn)))
+                }
+                ReplyState::Stopped => {
+                    head.push(Span::styled(" ■ stopped", Style::default().fg(theme.error)))
+                }
+                ReplyState::Done => {}
+            }
+            lines.push(Line::from(head));
+            for text in wrap(&bubble.text, width) {
+                lines.push(Line::from(vec![
+                    Span::styled("│ ", Style::default().fg(color)),
+                    Span::raw(text),
+                ]));
+            }
+            lines.push(Line::raw(""));
+            if i == self.selected {
+                selected_span = (start, lines.len());
+            }
+        }
+        if lines.is_empty() {
+            lines.push(Line::raw(format!(" {}", crate::t!("tui-no-replies"))));
+        }
+
+        // Keep the end of the selected reply in view, then its start.
+        let height = body.height.saturating_sub(2) as usize;
+        let (start, end) = selected_span;
+        let scroll = end.saturating_sub(height).min(start);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .scroll((scroll as u16, 0))
+                .block(Block::default().borders(Borders::ALL)),
+            body,
+        );
+
+        let footer_line = match &self.status {
+            Some(status) => Line::from(Span::styled(
+                format!(" {status}"),
+                Style::default().fg(theme.warn),
+            )),
+            None => Line::raw(format!(" {}", crate::t!("tui-chat-help"))),
+        };
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    fn chunk(reply: &str, agent: &str, kind: ChunkKind, text: &str) -> ChatChunk {
+        ChatChunk {
+            ts: Utc::now(),
+            reply: reply.to_string(),
+            agent: agent.to_string(),
+            stage: Some("implement".to_string()),
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    fn press(app: &mut ChatApp, keys: &str) -> Action {
+        let mut action = Action::None;
+        for c in keys.chars() {
+            action = app.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+        action
+    }
+
+    fn screen(app: &ChatApp) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(60, 14)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    #[test]
+    fn partial_replies_show_as_they_stream_and_can_be_stopped() {
+        let mut chunks = vec![
+            chunk("r1", "backend", ChunkKind::Start, ""),
+            chunk("r1", "backend", ChunkKind::Delta, "Adding the "),
+            chunk("r2", "frontend", ChunkKind::Start, ""),
+            chunk("r1", "backend", ChunkKind::Delta, "endpoint."),
+            chunk("r1", "backend", ChunkKind::End, ""),
+            chunk("r2", "frontend", ChunkKind::Delta, "Wiring the form "),
+        ];
+        let mut app = ChatApp::new(bubbles(&chunks));
+        let shown = screen(&app);
+        assert!(shown.contains("Adding the endpoint."));
+        assert!(shown.contains("Wiring the form"));
+        assert!(shown.contains("writing…"));
+        assert!(shown.contains("2 replies · 1 streaming"));
+
+        // The newest reply is selected; the finished one can't be stopped.
+        assert_eq!(press(&mut app, "s"), Action::Stop);
+        assert_eq!(app.take_stop().as_deref(), Some("frontend"));
+        assert_eq!(press(&mut app, "ks"), Action::None);
+        assert_eq!(app.take_stop(), None);
+
+        // Moving up stops following; new chunks land in place.
+        chunks.push(chunk("r2", "frontend", ChunkKind::Delta, "to the API"));
+        chunks.push(chunk("r2", "frontend", ChunkKind::Stopped, ""));
+        app.set_bubbles(bubbles(&chunks));
+        assert_eq!(app.selected, 0);
+        let shown = screen(&app);
+        assert!(shown.contains("Wiring the form to the API"));
+        assert!(shown.contains("stopped"));
+
+        // Filtering to one agent.
+        press(&mut app, "aa");
+        assert_eq!(app.agent.as_deref(), Some("frontend"));
+        assert!(!screen(&app).contains("endpoint"));
+    }
+
+    #[test]
+    fn wrap_breaks_at_spaces_and_splits_long_words() {
+        assert_eq!(wrap("one two three", 7), ["one two", "three"]);
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("a\n\nb", 10), ["a", "", "b"]);
+    }
+}
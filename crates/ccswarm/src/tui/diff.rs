@@ -0,0 +1,316 @@
+//! Unified diff parsing and side-by-side row layout.
+
+/// One file section of a `git diff` (or `git diff --no-index`) output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub old_path: String,
+    pub new_path: String,
+    /// Lines between `diff --git` and the first hunk (`index`, modes, `---`/`+++`, ...).
+    pub header: Vec<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    /// The path to show: the new path, or the old one for deletions.
+    pub fn path(&self) -> &str {
+        if self.new_path == "/dev/null" {
+            &self.old_path
+        } else {
+            &self.new_path
+        }
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.hunks.is_empty() && self.header.iter().any(|l| l.starts_with("Binary files "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@ ...` line.
+    pub header: String,
+    pub old_start: u32,
+    pub new_start: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+    /// `\ No newline at end of file`
+    NoNewline,
+}
+
+impl Hunk {
+    /// The hunk as patch text, header included.
+    pub fn to_patch(&self) -> String {
+        let mut out = format!("{}\n", self.header);
+        for line in &self.lines {
+            match line {
+                DiffLine::Context(t) => out.push_str(&format!(" {t}\n")),
+                DiffLine::Removed(t) => out.push_str(&format!("-{t}\n")),
+                DiffLine::Added(t) => out.push_str(&format!("+{t}\n")),
+                DiffLine::NoNewline => out.push_str("\\ No newline at end of file\n"),
+            }
+        }
+        out
+    }
+
+    pub fn added(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|l| matches!(l, DiffLine::Added(_)))
+            .count()
+    }
+
+    pub fn removed(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|l| matches!(l, DiffLine::Removed(_)))
+            .count()
+    }
+
+    /// Pair old and new lines for side-by-side display. Context lines sit on
+    /// both sides; a run of removals followed by additions is laid out row by
+    /// row, padding the shorter side.
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let (mut old_no, mut new_no) = (self.old_start, self.new_start);
+        let mut removed: Vec<Side> = Vec::new();
+        let mut added: Vec<Side> = Vec::new();
+
+        let flush = |rows: &mut Vec<Row>, removed: &mut Vec<Side>, added: &mut Vec<Side>| {
+            let n = removed.len().max(added.len());
+            let mut r = removed.drain(..);
+            let mut a = added.drain(..);
+            for _ in 0..n {
+                rows.push(Row {
+                    left: r.next(),
+                    right: a.next(),
+                });
+            }
+        };
+
+        for line in &self.lines {
+            match line {
+                DiffLine::Removed(text) => {
+                    if !added.is_empty() {
+                        flush(&mut rows, &mut removed, &mut added);
+                    }
+                    removed.push(Side::new(old_no, text, Change::Removed));
+                    old_no += 1;
+                }
+                DiffLine::Added(text) => {
+                    added.push(Side::new(new_no, text, Change::Added));
+                    new_no += 1;
+                }
+                DiffLine::Context(text) => {
+                    flush(&mut rows, &mut removed, &mut added);
+                    rows.push(Row {
+                        left: Some(Side::new(old_no, text, Change::Unchanged)),
+                        right: Some(Side::new(new_no, text, Change::Unchanged)),
+                    });
+                    old_no += 1;
+                    new_no += 1;
+                }
+                DiffLine::NoNewline => {}
+            }
+        }
+        flush(&mut rows, &mut removed, &mut added);
+        rows
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Unchanged,
+    Removed,
+    Added,
+}
+
+/// One side (old or new) of a side-by-side row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Side {
+    pub line_no: u32,
+    pub text: String,
+    pub change: Change,
+}
+
+impl Side {
+    fn new(line_no: u32, text: &str, change: Change) -> Self {
+        Self {
+            line_no,
+            text: text.to_string(),
+            change,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub left: Option<Side>,
+    pub right: Option<Side>,
+}
+
+/// Parse `git diff` output into files and hunks. Anything that isn't part
+/// of a recognised file section is ignored.
+pub fn parse_unified(text: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let (old_path, new_path) = split_git_paths(rest);
+            files.push(FileDiff {
+                old_path,
+                new_path,
+                header: vec![line.to_string()],
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@")
+            && let Some((old_start, new_start)) = parse_hunk_header(line)
+        {
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                old_start,
+                new_start,
+                lines: Vec::new(),
+            });
+            continue;
+        }
+
+        match file.hunks.last_mut() {
+            Some(hunk) => {
+                let parsed = if let Some(t) = line.strip_prefix('+') {
+                    DiffLine::Added(t.to_string())
+                } else if let Some(t) = line.strip_prefix('-') {
+                    DiffLine::Removed(t.to_string())
+                } else if line.starts_with('\\') {
+                    DiffLine::NoNewline
+                } else {
+                    DiffLine::Context(line.strip_prefix(' ').unwrap_or(line).to_string())
+                };
+                hunk.lines.push(parsed);
+            }
+            None => {
+                if let Some(p) = line.strip_prefix("--- ") {
+                    file.old_path = strip_side_prefix(p);
+                } else if let Some(p) = line.strip_prefix("+++ ") {
+                    file.new_path = strip_side_prefix(p);
+                }
+                file.header.push(line.to_string());
+            }
+        }
+    }
+    files
+}
+
+/// `a/src/x.rs b/src/x.rs` → (`src/x.rs`, `src/x.rs`). The `---`/`+++`
+/// lines, when present, override this.
+fn split_git_paths(rest: &str) -> (String, String) {
+    match rest.rfind(" b/") {
+        Some(i) => (
+            strip_side_prefix(&rest[..i]),
+            strip_side_prefix(&rest[i + 1..]),
+        ),
+        None => (rest.to_string(), rest.to_string()),
+    }
+}
+
+fn strip_side_prefix(path: &str) -> String {
+    let path = path.trim_end_matches('\t').trim();
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// `@@ -12,4 +12,6 @@ fn main()` → (12, 12).
+fn parse_hunk_header(line: &str) -> Option<(u32, u32)> {
+    let mut parts = line.split_whitespace().skip(1);
+    let old = parts.next()?.strip_prefix('-')?;
+    let new = parts.next()?.strip_prefix('+')?;
+    let start = |range: &str| range.split(',').next()?.parse::<u32>().ok();
+    Some((start(old)?, start(new)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,5 @@ mod a;
+ use std::fmt;
+-fn old() {}
+-fn gone() {}
++fn new() {}
+
++fn extra() {}
+ // end
+@@ -20,2 +21,2 @@
+-let x = 1;
++let x = 2;
+\\ No newline at end of file
+diff --git a/notes.md b/notes.md
+new file mode 100644
+--- /dev/null
++++ b/notes.md
+@@ -0,0 +1 @@
++hello
+diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ
+";
+
+    #[test]
+    fn test_parse_files_and_hunks() {
+        let files = parse_unified(DIFF);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].path(), "src/lib.rs");
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(files[0].hunks[0].old_start, 1);
+        assert_eq!(files[0].hunks[1].new_start, 21);
+        assert_eq!(files[0].hunks[0].removed(), 2);
+        assert_eq!(files[0].hunks[0].added(), 2);
+        assert_eq!(files[1].old_path, "/dev/null");
+        assert_eq!(files[1].path(), "notes.md");
+        assert!(files[2].is_binary());
+    }
+
+    #[test]
+    fn test_rows_pair_removals_with_additions() {
+        let files = parse_unified(DIFF);
+        let rows = files[0].hunks[0].rows();
+        let render = |s: &Option<Side>| s.as_ref().map(|s| (s.line_no, s.text.clone()));
+
+        assert_eq!(render(&rows[0].left), Some((1, "use std::fmt;".into())));
+        assert_eq!(render(&rows[1].left), Some((2, "fn old() {}".into())));
+        assert_eq!(render(&rows[1].right), Some((2, "fn new() {}".into())));
+        // Two removals, one addition: the second removal has no partner.
+        assert_eq!(render(&rows[2].left), Some((3, "fn gone() {}".into())));
+        assert_eq!(rows[2].right, None);
+        // Pure addition after a context line.
+        assert_eq!(rows[4].left, None);
+        assert_eq!(render(&rows[4].right), Some((4, "fn extra() {}".into())));
+        assert_eq!(rows.len(), 6);
+    }
+
+    #[test]
+    fn test_hunk_round_trips_to_patch() {
+        let files = parse_unified(DIFF);
+        assert_eq!(
+            files[0].hunks[1].to_patch(),
+            "@@ -20,2 +21,2 @@\n-let x = 1;\n+let x = 2;\n\\ No newline at end of file\n"
+        );
+    }
+}
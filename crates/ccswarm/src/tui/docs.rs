@@ -0,0 +1,376 @@
+//! Shared documents view: browse and edit the specs agents keep together
+//! (see [`ai_session::coordination::shared_doc`]).
+//!
+//! Edits go into the open [`SharedDoc`] as this replica's ops and
+//! [`Action::Save`] asks the loop to append them to the document's log;
+//! [`Action::Reload`] merges whatever agents wrote in the meantime, so a
+//! human and several agents can edit the same document at once.
+
+use super::Action;
+use ai_session::coordination::{OpId, SharedDoc};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    /// Typing the name of a new document.
+    Name(String),
+    /// Typing a line to add below the cursor.
+    Add(String),
+    /// Editing the line under the cursor.
+    Edit(String),
+}
+
+pub struct DocsApp {
+    names: Vec<String>,
+    selected: usize,
+    doc: Option<SharedDoc>,
+    /// Line under the cursor in the open document.
+    cursor: usize,
+    mode: Mode,
+    status: Option<String>,
+}
+
+impl DocsApp {
+    pub fn new(names: Vec<String>) -> Self {
+        Self {
+            names,
+            selected: 0,
+            doc: None,
+            cursor: 0,
+            mode: Mode::Browse,
+            status: None,
+        }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Replace the document list, keeping the selection on the same name.
+    pub fn set_names(&mut self, names: Vec<String>) {
+        let current = self.selected_name().map(str::to_string);
+        self.names = names;
+        self.selected = current
+            .and_then(|c| self.names.iter().position(|n| *n == c))
+            .unwrap_or(0);
+    }
+
+    /// Select `name`, adding it to the list if it isn't stored yet.
+    pub fn select(&mut self, name: &str) {
+        self.selected = match self.names.iter().position(|n| n == name) {
+            Some(i) => i,
+            None => {
+                self.names.push(name.to_string());
+                self.names.sort();
+                self.names.iter().position(|n| n == name).unwrap_or(0)
+            }
+        };
+    }
+
+    /// The document [`Action::Open`] refers to.
+    pub fn selected_name(&self) -> Option<&str> {
+        self.names.get(self.selected).map(String::as_str)
+    }
+
+    pub fn show(&mut self, doc: SharedDoc) {
+        self.doc = Some(doc);
+        self.cursor = 0;
+    }
+
+    pub fn doc(&self) -> Option<&SharedDoc> {
+        self.doc.as_ref()
+    }
+
+    pub fn doc_mut(&mut self) -> Option<&mut SharedDoc> {
+        self.doc.as_mut()
+    }
+
+    /// Keep the cursor on a line after the document changed underneath it.
+    pub fn clamp_cursor(&mut self) {
+        let len = self.doc.as_ref().map_or(0, |d| d.lines().len());
+        self.cursor = self.cursor.min(len.saturating_sub(1));
+    }
+
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    fn cursor_line(&self) -> Option<(OpId, String)> {
+        let doc = self.doc.as_ref()?;
+        doc.lines()
+            .get(self.cursor)
+            .map(|(id, text)| ((*id).clone(), text.to_string()))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        if self.mode != Mode::Browse {
+            return self.handle_input(key);
+        }
+        self.status = None;
+        let key = super::settings::keys().translate(key);
+        if self.doc.is_some() {
+            return self.handle_doc_key(key);
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.names.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Enter if self.selected_name().is_some() => return Action::Open,
+            KeyCode::Char('n') => self.mode = Mode::Name(String::new()),
+            KeyCode::Char('R') => return Action::Reload,
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn handle_doc_key(&mut self, key: KeyEvent) -> Action {
+        let len = self.doc.as_ref().map_or(0, |d| d.lines().len());
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.doc = None;
+                return Action::Reload;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.cursor = (self.cursor + 1).min(len.saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Char('g') => self.cursor = 0,
+            KeyCode::Char('G') => self.cursor = len.saturating_sub(1),
+            KeyCode::Char('a') => self.mode = Mode::Add(String::new()),
+            KeyCode::Char('e') => {
+                if let Some((_, text)) = self.cursor_line() {
+                    self.mode = Mode::Edit(text);
+                }
+            }
+            KeyCode::Char('d') => {
+                if let (Some((id, _)), Some(doc)) = (self.cursor_line(), self.doc.as_mut()) {
+                    doc.delete(&id);
+                    self.clamp_cursor();
+                    return Action::Save;
+                }
+            }
+            KeyCode::Char('R') => return Action::Reload,
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn handle_input(&mut self, key: KeyEvent) -> Action {
+        let (Mode::Name(input) | Mode::Add(input) | Mode::Edit(input)) = &mut self.mode else {
+            return Action::None;
+        };
+        match key.code {
+            KeyCode::Esc => self.mode = Mode::Browse,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Enter => {
+                let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+                return self.submit(mode);
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn submit(&mut self, mode: Mode) -> Action {
+        let cursor_line = self.cursor_line();
+        match mode {
+            Mode::Name(name) => {
+                let name = name.trim();
+                if name.is_empty() {
+                    return Action::None;
+                }
+                self.select(name);
+                Action::Open
+            }
+            Mode::Add(text) => {
+                let Some(doc) = self.doc.as_mut() else {
+                    return Action::None;
+                };
+                let after = cursor_line.map(|(id, _)| id);
+                let id = doc.insert_after(after.as_ref(), &text);
+                self.cursor = doc
+                    .lines()
+                    .iter()
+                    .position(|(line, _)| **line == id)
+                    .unwrap_or(self.cursor);
+                Action::Save
+            }
+            Mode::Edit(text) => match (cursor_line, self.doc.as_mut()) {
+                (Some((id, old)), Some(doc)) if old != text => {
+                    doc.replace(&id, &text);
+                    Action::Save
+                }
+                _ => Action::None,
+            },
+            Mode::Browse => Action::None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let theme = super::settings::theme();
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let title = match &self.doc {
+            Some(doc) => format!(
+                " {} · {} lines · editing as {}",
+                doc.name(),
+                doc.lines().len(),
+                doc.replica()
+            ),
+            None => format!(" {} documents", self.names.len()),
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    " ccswarm docs ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(title),
+            ]))
+            .style(theme.header()),
+            header,
+        );
+
+        let (items, selected, block_title): (Vec<ListItem>, usize, String) = match &self.doc {
+            Some(doc) => (
+                doc.lines()
+                    .into_iter()
+                    .map(|(id, text)| {
+                        ListItem::new(Line::from(vec![
+                            Span::styled(
+                                format!("{:<12} ", truncate(author(&id.replica), 12)),
+                                Style::default().fg(theme.muted),
+                            ),
+                            Span::raw(text.to_string()),
+                        ]))
+                    })
+                    .collect(),
+                self.cursor,
+                format!(" {} ", doc.name()),
+            ),
+            None => (
+                self.names
+                    .iter()
+                    .map(|name| ListItem::new(name.as_str()))
+                    .collect(),
+                self.selected,
+                " Documents ".to_string(),
+            ),
+        };
+        let mut state = ListState::default().with_selected(Some(selected));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(block_title))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            body,
+            &mut state,
+        );
+
+        let prompt = |label: &str, input: &str| {
+            Line::from(vec![
+                Span::styled(format!(" {label} "), theme.prompt()),
+                Span::raw(format!(" {input}_  (Enter to save, Esc to cancel)")),
+            ])
+        };
+        let footer_line = match (&self.mode, &self.status) {
+            (Mode::Name(input), _) => prompt("New document", input),
+            (Mode::Add(input), _) => prompt("Add line", input),
+            (Mode::Edit(input), _) => prompt("Edit line", input),
+            (Mode::Browse, Some(status)) => Line::from(Span::styled(
+                format!(" {status}"),
+                Style::default().fg(theme.warn),
+            )),
+            (Mode::Browse, None) if self.doc.is_some() => Line::from(
+                " j/k line · a add below · e edit · d delete · R reload · q back to list",
+            ),
+            (Mode::Browse, None) => {
+                Line::from(" j/k document · Enter open · n new · R reload · q quit")
+            }
+        };
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+}
+
+/// Who wrote a line: the replica name without a `:<pid>` suffix.
+fn author(replica: &str) -> &str {
+    replica.split(':').next().unwrap_or(replica)
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let mut out: String = s.chars().take(max.saturating_sub(1)).collect();
+        out.push('…');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_text(app: &mut DocsApp, text: &str) -> Action {
+        for c in text.chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        app.handle_key(key(KeyCode::Enter))
+    }
+
+    #[test]
+    fn test_edit_keys_produce_ops_to_save() {
+        let mut app = DocsApp::new(vec!["api".to_string()]);
+        app.handle_key(key(KeyCode::Char('n')));
+        assert_eq!(type_text(&mut app, "design"), Action::Open);
+        assert_eq!(app.selected_name(), Some("design"));
+
+        let mut agent = SharedDoc::new("design", "agent-a");
+        agent.append("Use Postgres\nCache in Redis");
+        let mut doc = SharedDoc::new("design", "human:1");
+        doc.merge(agent.take_unsaved());
+        app.show(doc);
+
+        // Add a line below the first, edit it, then delete the last one.
+        app.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(type_text(&mut app, "Migrations via sqlx"), Action::Save);
+        app.handle_key(key(KeyCode::Char('e')));
+        app.handle_key(key(KeyCode::Backspace));
+        assert_eq!(type_text(&mut app, "X"), Action::Save);
+        app.handle_key(key(KeyCode::Char('G')));
+        assert_eq!(app.handle_key(key(KeyCode::Char('d'))), Action::Save);
+
+        let doc = app.doc_mut().unwrap();
+        assert_eq!(doc.text(), "Use Postgres\nMigrations via sqlX\n");
+        // Every edit is this replica's op, ready for the store to append.
+        let ops = doc.take_unsaved();
+        assert_eq!(ops.len(), 4);
+        assert_eq!(author(doc.replica()), "human");
+
+        assert_eq!(app.handle_key(key(KeyCode::Char('q'))), Action::Reload);
+        assert!(app.doc().is_none());
+        assert_eq!(app.handle_key(key(KeyCode::Char('q'))), Action::Quit);
+    }
+}
@@ -0,0 +1,402 @@
+//! Human inbox view: tasks delegated to people
+//! (see [`crate::governance::human`]).
+//!
+//! Accepting, completing, declining or commenting queues a [`Response`] and
+//! returns [`Action::Respond`]; the loop takes it with
+//! [`InboxApp::take_response`] and records it through the inbox, which
+//! reports it on the coordination bus.
+
+use super::Action;
+use crate::governance::human::{InboxItem, InboxStatus};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+
+/// What the human did with the selected task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Accept,
+    Complete(String),
+    Decline(String),
+    Comment(String),
+}
+
+/// A reply to one inbox item, waiting for the loop to record it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub id: String,
+    pub reply: Reply,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    Complete,
+    Decline,
+    Comment,
+}
+
+impl Prompt {
+    fn label(self) -> &'static str {
+        match self {
+            Prompt::Complete => " Summary ",
+            Prompt::Decline => " Why decline? ",
+            Prompt::Comment => " Comment ",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    Input(Prompt, String),
+}
+
+pub struct InboxApp {
+    items: Vec<InboxItem>,
+    selected: usize,
+    mode: Mode,
+    status: Option<String>,
+    response: Option<Response>,
+}
+
+impl InboxApp {
+    pub fn new(items: Vec<InboxItem>) -> Self {
+        Self {
+            items,
+            selected: 0,
+            mode: Mode::Browse,
+            status: None,
+            response: None,
+        }
+    }
+
+    pub fn items(&self) -> &[InboxItem] {
+        &self.items
+    }
+
+    /// Replace the items, keeping the selection on the same task.
+    pub fn set_items(&mut self, items: Vec<InboxItem>) {
+        let current = self.selected_item().map(|item| item.id.clone());
+        self.items = items;
+        self.selected = current
+            .and_then(|id| self.items.iter().position(|item| item.id == id))
+            .unwrap_or(self.selected.min(self.items.len().saturating_sub(1)));
+    }
+
+    pub fn selected_item(&self) -> Option<&InboxItem> {
+        self.items.get(self.selected)
+    }
+
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    /// The reply [`Action::Respond`] refers to.
+    pub fn take_response(&mut self) -> Option<Response> {
+        self.response.take()
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        if let Mode::Input(prompt, text) = &mut self.mode {
+            match key.code {
+                KeyCode::Enter => {
+                    let text = text.trim().to_string();
+                    let prompt = *prompt;
+                    self.mode = Mode::Browse;
+                    if text.is_empty() && prompt != Prompt::Complete {
+                        self.set_status("Nothing entered");
+                        return Action::None;
+                    }
+                    return self.respond(match prompt {
+                        Prompt::Complete => Reply::Complete(text),
+                        Prompt::Decline => Reply::Decline(text),
+                        Prompt::Comment => Reply::Comment(text),
+                    });
+                }
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+            return Action::None;
+        }
+
+        self.status = None;
+        let key = super::settings::keys().translate(key);
+        let open = self
+            .selected_item()
+            .is_some_and(|item| !item.status.is_closed());
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.items.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('R') => return Action::Reload,
+            KeyCode::Char('m') if self.selected_item().is_some() => {
+                self.mode = Mode::Input(Prompt::Comment, String::new());
+            }
+            KeyCode::Char('a' | 'c' | 'd') if !open => {
+                self.set_status("This task is already closed");
+            }
+            KeyCode::Char('a') => {
+                if self
+                    .selected_item()
+                    .is_some_and(|item| item.status == InboxStatus::Accepted)
+                {
+                    self.set_status("Already accepted");
+                } else {
+                    return self.respond(Reply::Accept);
+                }
+            }
+            KeyCode::Char('c') => self.mode = Mode::Input(Prompt::Complete, String::new()),
+            KeyCode::Char('d') => self.mode = Mode::Input(Prompt::Decline, String::new()),
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn respond(&mut self, reply: Reply) -> Action {
+        let Some(item) = self.selected_item() else {
+            return Action::None;
+        };
+        self.response = Some(Response {
+            id: item.id.clone(),
+            reply,
+        });
+        Action::Respond
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let theme = super::settings::theme();
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list_area, detail_area] = super::settings::layout().split(body, 45);
+
+        let open = self
+            .items
+            .iter()
+            .filter(|item| !item.status.is_closed())
+            .count();
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    " ccswarm inbox ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!(" {} tasks · {open} open", self.items.len())),
+            ]))
+            .style(theme.header()),
+            header,
+        );
+
+        let rows: Vec<ListItem> = self
+            .items
+            .iter()
+            .map(|item| {
+                let color = match item.status {
+                    InboxStatus::New => theme.warn,
+                    InboxStatus::Accepted => theme.accent,
+                    InboxStatus::Completed => theme.ok,
+                    InboxStatus::Declined => theme.error,
+                };
+                let preview = item.task.description.lines().next().unwrap_or("");
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<9} ", item.status), Style::default().fg(color)),
+                    Span::styled(format!("{} ", item.human), Style::default().fg(theme.muted)),
+                    Span::raw(preview.to_string()),
+                ]))
+            })
+            .collect();
+        let mut state = ListState::default();
+        if !self.items.is_empty() {
+            state.select(Some(self.selected));
+        }
+        frame.render_stateful_widget(
+            List::new(rows)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(" {} ", crate::t!("tui-tasks"))),
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            list_area,
+            &mut state,
+        );
+
+        let detail = match self.selected_item() {
+            Some(item) => {
+                let mut lines = vec![
+                    Line::from(Span::styled(
+                        item.id.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(format!(
+                        "for {} · from {} · {}",
+                        item.human,
+                        item.delegated_by,
+                        item.delegated_at.format("%Y-%m-%d %H:%M")
+                    )),
+                    Line::raw(""),
+                ];
+                lines.extend(
+                    item.task
+                        .description
+                        .lines()
+                        .map(|l| Line::raw(l.to_string())),
+                );
+                if let Some(result) = &item.result {
+                    lines.push(Line::raw(""));
+                    lines.push(Line::from(Span::styled(
+                        format!("{}: {result}", item.status),
+                        Style::default().fg(theme.ok),
+                    )));
+                }
+                if !item.comments.is_empty() {
+                    lines.push(Line::raw(""));
+                    for comment in &item.comments {
+                        lines.push(Line::from(vec![
+                            Span::styled(
+                                format!("{}: ", comment.author),
+                                Style::default().fg(theme.muted),
+                            ),
+                            Span::raw(comment.text.clone()),
+                        ]));
+                    }
+                }
+                lines
+            }
+            None => vec![Line::raw(crate::t!("tui-no-human-tasks"))],
+        };
+        frame.render_widget(
+            Paragraph::new(detail).wrap(Wrap { trim: false }).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} ", crate::t!("tui-task"))),
+            ),
+            detail_area,
+        );
+
+        let footer_line = match &self.mode {
+            Mode::Input(prompt, text) => Line::from(vec![
+                Span::styled(prompt.label(), theme.prompt()),
+                Span::raw(format!(" {text}█  {}", crate::t!("tui-input-help"))),
+            ]),
+            Mode::Browse => match &self.status {
+                Some(status) => Line::from(Span::styled(
+                    format!(" {status}"),
+                    Style::default().fg(theme.warn),
+                )),
+                None => Line::raw(format!(" {}", crate::t!("tui-inbox-help"))),
+            },
+        };
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{Priority, Task, TaskType};
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    fn item(id: &str, status: InboxStatus) -> InboxItem {
+        let now = chrono::Utc::now();
+        InboxItem {
+            id: id.to_string(),
+            human: "alice".to_string(),
+            task: Task::new(
+                id.to_string(),
+                format!("Review {id}"),
+                Priority::Medium,
+                TaskType::Review,
+            ),
+            delegated_by: "orchestrator".to_string(),
+            delegated_at: now,
+            status,
+            queue_task: None,
+            comments: Vec::new(),
+            result: None,
+            updated_at: now,
+        }
+    }
+
+    fn press(app: &mut InboxApp, keys: &str) -> Action {
+        let mut action = Action::None;
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                c => KeyCode::Char(c),
+            };
+            action = app.handle_key(KeyEvent::from(code));
+        }
+        action
+    }
+
+    #[test]
+    fn test_replies_are_queued_for_the_selected_task() {
+        let mut app = InboxApp::new(vec![
+            item("h-1", InboxStatus::New),
+            item("h-2", InboxStatus::Accepted),
+            item("h-3", InboxStatus::Completed),
+        ]);
+
+        assert_eq!(press(&mut app, "a"), Action::Respond);
+        assert_eq!(
+            app.take_response(),
+            Some(Response {
+                id: "h-1".to_string(),
+                reply: Reply::Accept
+            })
+        );
+        assert_eq!(app.take_response(), None);
+
+        assert_eq!(press(&mut app, "jcShipped\n"), Action::Respond);
+        assert_eq!(
+            app.take_response().map(|r| (r.id, r.reply)),
+            Some(("h-2".to_string(), Reply::Complete("Shipped".to_string())))
+        );
+
+        // A decline needs a reason, and closed tasks only take comments.
+        assert_eq!(press(&mut app, "d\n"), Action::None);
+        assert_eq!(press(&mut app, "jd"), Action::None);
+        assert_eq!(press(&mut app, "mLGTM\n"), Action::Respond);
+        assert_eq!(
+            app.take_response().map(|r| (r.id, r.reply)),
+            Some(("h-3".to_string(), Reply::Comment("LGTM".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_render_shows_tasks_and_the_reply_prompt() {
+        let mut app = InboxApp::new(vec![item("h-1", InboxStatus::New)]);
+        press(&mut app, "cdone");
+        let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("1 tasks · 1 open"));
+        assert!(screen.contains("Review h-1"));
+        assert!(screen.contains("from orchestrator"));
+        assert!(screen.contains("Summary"));
+        assert!(screen.contains("done█"));
+    }
+}
@@ -0,0 +1,593 @@
+//! Log viewer: run events, agent logs and the daemon log in one stream,
+//! filtered by level and agent and searchable by regex.
+//!
+//! Run events (`events.ndjson`) carry their own level and agent. Plain-text
+//! logs get a level from their wording, raised wherever the output parser's
+//! diagnostic plugins find an error or warning in the file, so `e` jumps to
+//! the compiler and linter errors as well as to logged ones.
+
+use super::Action;
+use crate::events::{Event, EventLevel};
+use ai_session::output::{OutputParser, Severity};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use regex::Regex;
+
+/// Lines moved by PageUp / PageDown.
+const PAGE: usize = 20;
+
+/// One line of any log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub ts: Option<DateTime<Utc>>,
+    pub level: EventLevel,
+    pub agent: Option<String>,
+    /// Run id or log file the line came from.
+    pub source: String,
+    pub text: String,
+}
+
+/// Lines from one run's `events.ndjson`. Unparseable lines are skipped.
+pub fn from_events(run_id: &str, content: &str) -> Vec<LogLine> {
+    let source: String = run_id.chars().take(8).collect();
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Event>(line).ok())
+        .map(|event| LogLine {
+            ts: Some(event.ts),
+            level: event.level,
+            agent: event.agent,
+            source: source.clone(),
+            text: match event.stage {
+                Some(stage) => format!("{stage}: {}", event.message),
+                None => event.message,
+            },
+        })
+        .collect()
+}
+
+/// Lines from a plain-text log. Lines without a leading timestamp take the
+/// one before them, so they sort next to it.
+pub fn from_text(
+    source: &str,
+    agent: Option<&str>,
+    content: &str,
+    parser: &OutputParser,
+) -> Vec<LogLine> {
+    let mut ts = None;
+    let mut lines: Vec<LogLine> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            ts = leading_timestamp(line).or(ts);
+            LogLine {
+                ts,
+                level: guess_level(line),
+                agent: agent.map(str::to_string),
+                source: source.to_string(),
+                text: line.to_string(),
+            }
+        })
+        .collect();
+    if let Some((_, diagnostics)) = parser.diagnostics(content) {
+        for diagnostic in diagnostics {
+            let level = match diagnostic.severity {
+                Severity::Error | Severity::Critical => EventLevel::Error,
+                Severity::Warning => EventLevel::Warn,
+                Severity::Info => continue,
+            };
+            for line in lines
+                .iter_mut()
+                .filter(|l| l.text.contains(&diagnostic.message))
+            {
+                if rank(&line.level) < rank(&level) {
+                    line.level = level.clone();
+                }
+            }
+        }
+    }
+    lines
+}
+
+fn guess_level(line: &str) -> EventLevel {
+    if line.contains("ERROR")
+        || line.contains("rror:")
+        || line.contains("panicked")
+        || line.contains("FAILED")
+    {
+        EventLevel::Error
+    } else if line.contains("WARN") || line.contains("arning:") {
+        EventLevel::Warn
+    } else if line.contains("DEBUG") || line.contains("TRACE") {
+        EventLevel::Debug
+    } else {
+        EventLevel::Info
+    }
+}
+
+/// `2026-01-02T03:04:05Z ...`, `[2026-01-02 03:04:05] ...` and the like.
+fn leading_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    let stamp: String = line
+        .trim_start_matches('[')
+        .chars()
+        .take(19)
+        .map(|c| if c == 'T' { ' ' } else { c })
+        .collect();
+    NaiveDateTime::parse_from_str(&stamp, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|ts| ts.and_utc())
+}
+
+fn rank(level: &EventLevel) -> u8 {
+    match level {
+        EventLevel::Debug => 0,
+        EventLevel::Info => 1,
+        EventLevel::Warn => 2,
+        EventLevel::Error => 3,
+    }
+}
+
+fn level_label(level: &EventLevel) -> &'static str {
+    match level {
+        EventLevel::Debug => "debug",
+        EventLevel::Info => "info",
+        EventLevel::Warn => "warn",
+        EventLevel::Error => "error",
+    }
+}
+
+fn level_color(level: &EventLevel) -> Color {
+    let theme = super::settings::theme();
+    match level {
+        EventLevel::Debug => theme.muted,
+        EventLevel::Info => theme.text,
+        EventLevel::Warn => theme.warn,
+        EventLevel::Error => theme.error,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    Search,
+}
+
+/// State of the log view.
+pub struct LogsApp {
+    lines: Vec<LogLine>,
+    /// Indices into `lines` that pass the filters.
+    visible: Vec<usize>,
+    min_level: EventLevel,
+    agent: Option<String>,
+    search: Option<Regex>,
+    mode: Mode,
+    input: String,
+    /// Keep the cursor on the newest line as lines arrive.
+    follow: bool,
+    cursor: usize,
+    status: Option<String>,
+}
+
+impl LogsApp {
+    pub fn new(lines: Vec<LogLine>) -> Self {
+        let mut app = Self {
+            lines: Vec::new(),
+            visible: Vec::new(),
+            min_level: EventLevel::Debug,
+            agent: None,
+            search: None,
+            mode: Mode::Browse,
+            input: String::new(),
+            follow: true,
+            cursor: 0,
+            status: None,
+        };
+        app.set_lines(lines);
+        app
+    }
+
+    /// Replace the lines (sorted by time); the cursor stays put unless
+    /// following.
+    pub fn set_lines(&mut self, mut lines: Vec<LogLine>) {
+        lines.sort_by_key(|l| l.ts);
+        self.lines = lines;
+        self.refilter();
+    }
+
+    pub fn set_min_level(&mut self, level: EventLevel) {
+        self.min_level = level;
+        self.refilter();
+    }
+
+    pub fn set_agent(&mut self, agent: Option<String>) {
+        self.agent = agent;
+        self.refilter();
+    }
+
+    /// Search for `pattern` (case-insensitive) and jump to the first match.
+    pub fn set_search(&mut self, pattern: &str) -> Result<(), regex::Error> {
+        if pattern.is_empty() {
+            self.search = None;
+            return Ok(());
+        }
+        self.search = Some(Regex::new(&format!("(?i){pattern}"))?);
+        self.input = pattern.to_string();
+        self.follow = false;
+        if !self.is_match(self.cursor) {
+            self.jump(true, |app, i| app.is_match(i));
+        }
+        Ok(())
+    }
+
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    /// Lines that pass the filters, oldest first.
+    pub fn visible(&self) -> impl Iterator<Item = &LogLine> {
+        self.visible.iter().map(|&i| &self.lines[i])
+    }
+
+    pub fn selected(&self) -> Option<&LogLine> {
+        self.visible.get(self.cursor).map(|&i| &self.lines[i])
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.follow
+    }
+
+    fn agents(&self) -> Vec<String> {
+        let mut agents: Vec<String> = self.lines.iter().filter_map(|l| l.agent.clone()).collect();
+        agents.sort();
+        agents.dedup();
+        agents
+    }
+
+    fn refilter(&mut self) {
+        let selected = self.visible.get(self.cursor).copied();
+        self.visible = (0..self.lines.len())
+            .filter(|&i| {
+                let line = &self.lines[i];
+                rank(&line.level) >= rank(&self.min_level)
+                    && self
+                        .agent
+                        .as_ref()
+                        .is_none_or(|a| line.agent.as_ref() == Some(a))
+            })
+            .collect();
+        self.cursor = if self.follow {
+            self.visible.len().saturating_sub(1)
+        } else {
+            // Stay on the same line, or the nearest one before it.
+            selected
+                .map(|s| self.visible.partition_point(|&i| i <= s).saturating_sub(1))
+                .unwrap_or(0)
+        };
+    }
+
+    fn is_match(&self, cursor: usize) -> bool {
+        match (&self.search, self.visible.get(cursor)) {
+            (Some(regex), Some(&i)) => regex.is_match(&self.lines[i].text),
+            _ => false,
+        }
+    }
+
+    fn is_error(&self, cursor: usize) -> bool {
+        self.visible
+            .get(cursor)
+            .is_some_and(|&i| self.lines[i].level == EventLevel::Error)
+    }
+
+    /// Move to the next (or previous) visible line satisfying `hit`.
+    fn jump(&mut self, forward: bool, hit: impl Fn(&Self, usize) -> bool) -> bool {
+        let found = if forward {
+            (self.cursor + 1..self.visible.len()).find(|&i| hit(self, i))
+        } else {
+            (0..self.cursor).rev().find(|&i| hit(self, i))
+        };
+        if let Some(i) = found {
+            self.cursor = i;
+            self.follow = false;
+        }
+        found.is_some()
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.visible.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(last);
+        self.follow = false;
+    }
+
+    fn set_follow(&mut self, follow: bool) {
+        self.follow = follow;
+        if follow {
+            self.cursor = self.visible.len().saturating_sub(1);
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        self.status = None;
+        if self.mode == Mode::Search {
+            match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Enter => {
+                    self.mode = Mode::Browse;
+                    let pattern = self.input.clone();
+                    if let Err(e) = self.set_search(&pattern) {
+                        self.set_status(format!("Invalid regex: {e}"));
+                    }
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                _ => {}
+            }
+            return Action::None;
+        }
+        let key = super::settings::keys().translate(key);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('j') | KeyCode::Down => self.move_by(1),
+            KeyCode::Char('k') | KeyCode::Up => self.move_by(-1),
+            KeyCode::PageDown => self.move_by(PAGE as isize),
+            KeyCode::PageUp => self.move_by(-(PAGE as isize)),
+            KeyCode::Char('g') | KeyCode::Home => {
+                self.cursor = 0;
+                self.follow = false;
+            }
+            KeyCode::Char('G') | KeyCode::End => self.set_follow(true),
+            KeyCode::Char('f') | KeyCode::Char(' ') => self.set_follow(!self.follow),
+            KeyCode::Char('l') => {
+                let next = match self.min_level {
+                    EventLevel::Debug => EventLevel::Info,
+                    EventLevel::Info => EventLevel::Warn,
+                    EventLevel::Warn => EventLevel::Error,
+                    EventLevel::Error => EventLevel::Debug,
+                };
+                self.set_min_level(next);
+            }
+            KeyCode::Char('a') => {
+                let agents = self.agents();
+                let next = match &self.agent {
+                    None => agents.first().cloned(),
+                    Some(current) => agents
+                        .iter()
+                        .position(|a| a == current)
+                        .and_then(|i| agents.get(i + 1))
+                        .cloned(),
+                };
+                self.set_agent(next);
+            }
+            KeyCode::Char('/') => {
+                self.mode = Mode::Search;
+                self.input.clear();
+            }
+            KeyCode::Char('c') => self.search = None,
+            KeyCode::Char(c @ ('n' | 'N')) => {
+                if self.search.is_none() {
+                    self.set_status("No search; press / to search");
+                } else if !self.jump(c == 'n', |app, i| app.is_match(i)) {
+                    self.set_status("No more matches");
+                }
+            }
+            KeyCode::Char(c @ ('e' | 'E')) => {
+                let found = self.jump(c == 'e', |app, i| app.is_error(i));
+                if !found {
+                    self.set_status("No more errors");
+                }
+            }
+            KeyCode::Char('R') => return Action::Reload,
+            _ => {}
+        }
+        Action::None
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let theme = super::settings::theme();
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let errors = self
+            .visible()
+            .filter(|l| l.level == EventLevel::Error)
+            .count();
+        let mut title = vec![
+            Span::styled(
+                " ccswarm logs ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " level ≥ {} · agent: {} · {}/{} lines · {} errors",
+                level_label(&self.min_level),
+                self.agent.as_deref().unwrap_or("all"),
+                self.visible.len(),
+                self.lines.len(),
+                errors,
+            )),
+        ];
+        if let Some(regex) = &self.search {
+            title.push(Span::raw(format!(
+                " · /{}/",
+                regex.as_str().trim_start_matches("(?i)")
+            )));
+        }
+        title.push(if self.follow {
+            Span::styled(" · ● following", Style::default().fg(theme.ok))
+        } else {
+            Span::styled(" · ⏸ paused", Style::default().fg(theme.warn))
+        });
+        frame.render_widget(
+            Paragraph::new(Line::from(title)).style(theme.header()),
+            header,
+        );
+
+        let items: Vec<ListItem> = self
+            .visible()
+            .map(|line| {
+                let color = level_color(&line.level);
+                let mut spans = vec![
+                    Span::styled(
+                        line.ts
+                            .map(|ts| ts.format("%H:%M:%S ").to_string())
+                            .unwrap_or_else(|| " ".repeat(9)),
+                        Style::default().fg(theme.muted),
+                    ),
+                    Span::styled(
+                        format!("{:<5} ", level_label(&line.level)),
+                        Style::default().fg(color),
+                    ),
+                    Span::styled(
+                        format!("{:<12} ", line.agent.as_deref().unwrap_or(&line.source)),
+                        Style::default().fg(theme.accent),
+                    ),
+                ];
+                spans.extend(highlight(&line.text, self.search.as_ref(), color));
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let mut state = ListState::default().with_selected(Some(self.cursor));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            body,
+            &mut state,
+        );
+
+        let footer_line = match (&self.mode, &self.status) {
+            (Mode::Search, _) => Line::from(format!(" /{}█", self.input)),
+            (Mode::Browse, Some(status)) => Line::from(Span::styled(
+                format!(" {status}"),
+                Style::default().fg(theme.warn),
+            )),
+            (Mode::Browse, None) => Line::from(
+                " j/k scroll · f follow · l level · a agent · / search · n/N match · e/E error · q quit",
+            ),
+        };
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+}
+
+/// `text` split into spans with the search matches highlighted.
+fn highlight<'a>(text: &'a str, search: Option<&Regex>, color: Color) -> Vec<Span<'a>> {
+    let theme = super::settings::theme();
+    let base = Style::default().fg(color);
+    let Some(regex) = search else {
+        return vec![Span::styled(text, base)];
+    };
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for m in regex.find_iter(text).filter(|m| !m.is_empty()) {
+        spans.push(Span::styled(&text[last..m.start()], base));
+        spans.push(Span::styled(m.as_str(), theme.prompt()));
+        last = m.end();
+    }
+    spans.push(Span::styled(&text[last..], base));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    const EVENTS: &str = r#"{"ts":"2026-03-01T10:00:00Z","level":"info","run_id":"run-1234abcd","event_type":"movement_start","stage":"plan","agent":"planner","message":"Stage 'plan' started"}
+{"ts":"2026-03-01T10:00:05Z","level":"error","run_id":"run-1234abcd","event_type":"provider_error","stage":"implement","agent":"coder","message":"Provider exited with 1"}
+not json
+"#;
+
+    const LINT_LOG: &str = "[2026-03-01 10:00:02] running eslint\n\
+        /repo/src/index.js\n  1:10  error    'foo' is defined but never used  no-unused-vars\n\
+        \n[2026-03-01 10:00:07] DEBUG cache hit\n";
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn sample() -> LogsApp {
+        let mut lines = from_events("run-1234abcd", EVENTS);
+        lines.extend(from_text(
+            "frontend.log",
+            Some("frontend"),
+            LINT_LOG,
+            &OutputParser::new(),
+        ));
+        LogsApp::new(lines)
+    }
+
+    #[test]
+    fn test_sources_merge_with_levels_from_events_and_diagnostics() {
+        let app = sample();
+        let texts: Vec<(&str, &EventLevel)> =
+            app.visible().map(|l| (l.text.as_str(), &l.level)).collect();
+        assert_eq!(texts.len(), 6);
+        assert_eq!(texts[0], ("plan: Stage 'plan' started", &EventLevel::Info));
+        assert_eq!(texts[1].0, "[2026-03-01 10:00:02] running eslint");
+        // The eslint row only reads as an error to the diagnostic parser.
+        assert!(texts[3].0.contains("'foo' is defined"));
+        assert_eq!(texts[3].1, &EventLevel::Error);
+        assert_eq!(texts[4].1, &EventLevel::Error);
+        assert_eq!(
+            texts[5],
+            ("[2026-03-01 10:00:07] DEBUG cache hit", &EventLevel::Debug)
+        );
+        assert_eq!(app.selected().unwrap().level, EventLevel::Debug);
+        assert!(app.is_following());
+    }
+
+    #[test]
+    fn test_filters_search_and_error_jumps() {
+        let mut app = sample();
+        app.handle_key(key(KeyCode::Char('g')));
+        assert!(!app.is_following());
+        app.handle_key(key(KeyCode::Char('e')));
+        assert!(app.selected().unwrap().text.contains("'foo'"));
+        app.handle_key(key(KeyCode::Char('e')));
+        assert_eq!(app.selected().unwrap().agent.as_deref(), Some("coder"));
+        app.handle_key(key(KeyCode::Char('E')));
+        assert!(app.selected().unwrap().text.contains("'foo'"));
+
+        app.handle_key(key(KeyCode::Char('/')));
+        for c in "STAGE '".chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        app.handle_key(key(KeyCode::Enter));
+        // The only match is above the cursor.
+        app.handle_key(key(KeyCode::Char('N')));
+        assert_eq!(app.selected().unwrap().agent.as_deref(), Some("planner"));
+
+        app.set_min_level(EventLevel::Warn);
+        assert_eq!(app.visible().count(), 2);
+        app.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(app.visible().count(), 1);
+        assert_eq!(app.selected().unwrap().agent.as_deref(), Some("coder"));
+
+        app.handle_key(key(KeyCode::Char('f')));
+        assert!(app.is_following());
+        assert!(app.set_search("(").is_err());
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 10)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("agent: coder"));
+        assert!(screen.contains("Provider exited with 1"));
+    }
+}
@@ -0,0 +1,88 @@
+//! Terminal UI (`ccswarm tui`).
+//!
+//! Each view is a plain state struct that turns key events into an
+//! [`Action`] and renders itself into a ratatui [`Frame`](ratatui::Frame);
+//! the CLI handler owns the event loop and does any I/O an action asks for.
+//! That keeps views testable without a terminal. Colors, pane layout and
+//! extra keys come from [`settings`].
+
+pub mod chat;
+pub mod diff;
+pub mod docs;
+pub mod inbox;
+pub mod logs;
+pub mod review;
+pub mod settings;
+pub mod timeline;
+pub mod usage;
+
+use anyhow::Result;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use std::ops::{Deref, DerefMut};
+use std::time::Duration;
+
+/// What a view wants the event loop to do after handling a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Quit,
+    /// Re-read whatever the view displays from disk.
+    Reload,
+    /// Hand the rejected hunks back to the agent.
+    SendBack,
+    /// Stop the selected run's process tree.
+    Pause,
+    /// Continue a paused run.
+    Resume,
+    /// Load the selected document.
+    Open,
+    /// Persist the edits made in the view.
+    Save,
+    /// Record the reply given to the selected inbox task.
+    Respond,
+    /// Stop the selected agent's reply while it is being written.
+    Stop,
+}
+
+/// Raw-mode alternate screen, restored when dropped (or on panic).
+pub struct Screen(DefaultTerminal);
+
+impl Screen {
+    pub fn enter() -> Result<Self> {
+        Ok(Self(ratatui::try_init()?))
+    }
+}
+
+impl Deref for Screen {
+    type Target = DefaultTerminal;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Screen {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+/// Wait up to `timeout` for a key press. Releases and repeats are ignored.
+/// Edits to the TUI config file take effect here, between key presses.
+pub fn next_key(timeout: Duration) -> Result<Option<KeyEvent>> {
+    settings::reload_if_changed();
+    if !event::poll(timeout)? {
+        return Ok(None);
+    }
+    match event::read()? {
+        Event::Key(key) if key.kind == KeyEventKind::Press => Ok(Some(key)),
+        _ => Ok(None),
+    }
+}
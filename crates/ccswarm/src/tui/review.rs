@@ -0,0 +1,617 @@
+//! Diff review view: an agent's worktree against its base branch, rendered
+//! side by side, with an accept/reject decision per hunk.
+//!
+//! Accepting a hunk only marks it; nothing in the worktree changes. Sending
+//! ([`Action::SendBack`]) turns the rejected hunks and their notes into a
+//! follow-up prompt ([`ReviewApp::follow_up_prompt`]) for the agent to act on.
+//!
+//! Diagnostics from a check command (`--check`) are shown under the lines they
+//! point at and go along with rejected hunks they fall in.
+
+use super::Action;
+use super::diff::{Change, FileDiff, Hunk, Side};
+use ai_session::output::{Diagnostic, Severity};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Decision {
+    #[default]
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+/// Position of a hunk: (file index, hunk index within the file).
+pub type HunkRef = (usize, usize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    /// Typing the reviewer note for a rejected hunk.
+    Note(String),
+}
+
+pub struct ReviewApp {
+    /// Shown in the title bar, e.g. "agent-frontend vs main".
+    title: String,
+    base: String,
+    files: Vec<FileDiff>,
+    decisions: HashMap<HunkRef, Decision>,
+    notes: HashMap<HunkRef, String>,
+    cursor: HunkRef,
+    mode: Mode,
+    status: Option<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ReviewApp {
+    pub fn new(title: impl Into<String>, base: impl Into<String>, files: Vec<FileDiff>) -> Self {
+        let first_file = files.iter().position(|f| !f.hunks.is_empty()).unwrap_or(0);
+        Self {
+            title: title.into(),
+            base: base.into(),
+            files,
+            decisions: HashMap::new(),
+            notes: HashMap::new(),
+            cursor: (first_file, 0),
+            mode: Mode::Browse,
+            status: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Annotate the diff with diagnostics from a check command.
+    pub fn with_diagnostics(mut self, diagnostics: Vec<Diagnostic>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Diagnostics reported against `file`.
+    fn file_diagnostics<'a>(&'a self, file: &'a FileDiff) -> impl Iterator<Item = &'a Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.file.as_deref().is_some_and(|p| same_file(p, file.path())))
+    }
+
+    /// Diagnostics on lines the hunk adds or keeps.
+    fn hunk_diagnostics<'a>(&'a self, file: &'a FileDiff, hunk: &Hunk) -> Vec<&'a Diagnostic> {
+        let lines: Vec<u32> = hunk
+            .rows()
+            .iter()
+            .filter_map(|row| row.right.as_ref().map(|side| side.line_no))
+            .collect();
+        self.file_diagnostics(file)
+            .filter(|d| d.line.is_some_and(|l| lines.contains(&(l as u32))))
+            .collect()
+    }
+
+    pub fn files(&self) -> &[FileDiff] {
+        &self.files
+    }
+
+    pub fn cursor(&self) -> HunkRef {
+        self.cursor
+    }
+
+    pub fn decision(&self, hunk: HunkRef) -> Decision {
+        self.decisions.get(&hunk).copied().unwrap_or_default()
+    }
+
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    /// Every hunk with the given decision, in diff order.
+    pub fn hunks_with(&self, decision: Decision) -> Vec<HunkRef> {
+        self.hunk_refs()
+            .filter(|h| self.decision(*h) == decision)
+            .collect()
+    }
+
+    fn hunk_refs(&self) -> impl Iterator<Item = HunkRef> + '_ {
+        self.files
+            .iter()
+            .enumerate()
+            .flat_map(|(f, file)| (0..file.hunks.len()).map(move |h| (f, h)))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if let Mode::Note(note) = &mut self.mode {
+            match key.code {
+                KeyCode::Enter => {
+                    let note = note.trim().to_string();
+                    if note.is_empty() {
+                        self.notes.remove(&self.cursor);
+                    } else {
+                        self.notes.insert(self.cursor, note);
+                    }
+                    self.mode = Mode::Browse;
+                    self.advance();
+                }
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    note.pop();
+                }
+                KeyCode::Char(c) => note.push(c),
+                _ => {}
+            }
+            return Action::None;
+        }
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        self.status = None;
+        let key = super::settings::keys().translate(key);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Down | KeyCode::Char('j') => self.advance(),
+            KeyCode::Up | KeyCode::Char('k') => self.retreat(),
+            KeyCode::Tab | KeyCode::Char('n') => self.jump_file(1),
+            KeyCode::BackTab | KeyCode::Char('p') => self.jump_file(-1),
+            KeyCode::Char('a') if self.has_hunk() => {
+                self.decisions.insert(self.cursor, Decision::Accepted);
+                self.notes.remove(&self.cursor);
+                self.advance();
+            }
+            KeyCode::Char('r') if self.has_hunk() => {
+                self.decisions.insert(self.cursor, Decision::Rejected);
+                let existing = self.notes.get(&self.cursor).cloned().unwrap_or_default();
+                self.mode = Mode::Note(existing);
+            }
+            KeyCode::Char('u') => {
+                self.decisions.remove(&self.cursor);
+                self.notes.remove(&self.cursor);
+            }
+            KeyCode::Char('R') => return Action::Reload,
+            KeyCode::Char('s') => {
+                if self.hunks_with(Decision::Rejected).is_empty() {
+                    self.set_status("No rejected hunks to send");
+                } else {
+                    return Action::SendBack;
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn has_hunk(&self) -> bool {
+        self.files
+            .get(self.cursor.0)
+            .is_some_and(|f| self.cursor.1 < f.hunks.len())
+    }
+
+    fn advance(&mut self) {
+        let refs: Vec<HunkRef> = self.hunk_refs().collect();
+        if let Some(next) = refs.iter().find(|h| **h > self.cursor) {
+            self.cursor = *next;
+        }
+    }
+
+    fn retreat(&mut self) {
+        let refs: Vec<HunkRef> = self.hunk_refs().collect();
+        if let Some(prev) = refs.iter().rev().find(|h| **h < self.cursor) {
+            self.cursor = *prev;
+        }
+    }
+
+    fn jump_file(&mut self, step: isize) {
+        let mut f = self.cursor.0 as isize;
+        loop {
+            f += step;
+            if f < 0 || f as usize >= self.files.len() {
+                return;
+            }
+            if !self.files[f as usize].hunks.is_empty() {
+                self.cursor = (f as usize, 0);
+                return;
+            }
+        }
+    }
+
+    /// Instructions for the agent covering every rejected hunk (with the
+    /// reviewer's note) and naming the accepted ones it must leave alone.
+    /// `None` when nothing was rejected.
+    pub fn follow_up_prompt(&self) -> Option<String> {
+        let rejected = self.hunks_with(Decision::Rejected);
+        if rejected.is_empty() {
+            return None;
+        }
+        let mut prompt = format!(
+            "A reviewer went through your changes against `{}` hunk by hunk and \
+             rejected {} of them. Rework each rejected hunk below as the note \
+             says (or, without a note, revert or redo it in a way that fits the \
+             surrounding code). Do not touch the hunks listed as accepted.\n",
+            self.base,
+            rejected.len()
+        );
+        for (f, h) in &rejected {
+            let file = &self.files[*f];
+            let hunk = &file.hunks[*h];
+            prompt.push_str(&format!("\n## Rejected: {} {}\n", file.path(), hunk.header));
+            if let Some(note) = self.notes.get(&(*f, *h)) {
+                prompt.push_str(&format!("Reviewer note: {note}\n"));
+            }
+            for d in self.hunk_diagnostics(file, hunk) {
+                prompt.push_str(&format!("Diagnostic: {}\n", d.to_line()));
+            }
+            prompt.push_str(&format!("```diff\n{}```\n", hunk.to_patch()));
+        }
+        let accepted = self.hunks_with(Decision::Accepted);
+        if !accepted.is_empty() {
+            prompt.push_str("\n## Accepted (keep as is)\n");
+            for (f, h) in accepted {
+                let file = &self.files[f];
+                prompt.push_str(&format!("- {} {}\n", file.path(), file.hunks[h].header));
+            }
+        }
+        Some(prompt)
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let theme = super::settings::theme();
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [sidebar, main] = super::settings::layout().split(body, 25);
+
+        let rejected = self.hunks_with(Decision::Rejected).len();
+        let accepted = self.hunks_with(Decision::Accepted).len();
+        let total = self.hunk_refs().count();
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    format!(" ccswarm review: {} ", self.title),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    " {total} hunks · {accepted} accepted · {rejected} rejected"
+                )),
+            ]))
+            .style(theme.header()),
+            header,
+        );
+
+        self.render_files(frame, sidebar);
+        self.render_diff(frame, main);
+
+        let footer_line = match &self.mode {
+            Mode::Note(note) => Line::from(vec![
+                Span::styled(" Why reject? ", theme.prompt()),
+                Span::raw(format!(" {note}_  (Enter to save, Esc to skip)")),
+            ]),
+            Mode::Browse => match &self.status {
+                Some(status) => Line::from(Span::styled(
+                    format!(" {status}"),
+                    Style::default().fg(theme.warn),
+                )),
+                None => Line::from(vec![
+                    Span::raw(
+                        " j/k hunk · n/p file · a accept · r reject · u undo · R reload · q quit  ",
+                    ),
+                    Span::styled(
+                        format!("[s] send {rejected} rejected to agent"),
+                        Style::default()
+                            .fg(if rejected > 0 {
+                                theme.prompt_fg
+                            } else {
+                                theme.muted
+                            })
+                            .bg(if rejected > 0 {
+                                theme.error
+                            } else {
+                                Color::Reset
+                            }),
+                    ),
+                ]),
+            },
+        };
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+
+    fn render_files(&self, frame: &mut Frame, area: Rect) {
+        let theme = super::settings::theme();
+        let items: Vec<ListItem> = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(f, file)| {
+                let decided = |d| {
+                    (0..file.hunks.len())
+                        .filter(|h| self.decision((f, *h)) == d)
+                        .count()
+                };
+                let (ok, no) = (decided(Decision::Accepted), decided(Decision::Rejected));
+                let mut spans = vec![Span::raw(file.path().to_string())];
+                if ok > 0 {
+                    spans.push(Span::styled(
+                        format!(" ✓{ok}"),
+                        Style::default().fg(theme.ok),
+                    ));
+                }
+                if no > 0 {
+                    spans.push(Span::styled(
+                        format!(" ✗{no}"),
+                        Style::default().fg(theme.error),
+                    ));
+                }
+                let found = self.file_diagnostics(file).count();
+                if found > 0 {
+                    spans.push(Span::styled(
+                        format!(" ⚠{found}"),
+                        Style::default().fg(theme.warn),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let mut state = ListState::default().with_selected(Some(self.cursor.0));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(" Files "))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            area,
+            &mut state,
+        );
+    }
+
+    fn render_diff(&self, frame: &mut Frame, area: Rect) {
+        let theme = super::settings::theme();
+        let [old_area, new_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(area);
+        let Some(file) = self.files.get(self.cursor.0) else {
+            return;
+        };
+
+        let mut old_lines: Vec<Line> = Vec::new();
+        let mut new_lines: Vec<Line> = Vec::new();
+        let mut scroll = 0u16;
+        if file.is_binary() {
+            old_lines.push(Line::from("(binary file)"));
+        }
+        for (h, hunk) in file.hunks.iter().enumerate() {
+            let selected = h == self.cursor.1;
+            if selected {
+                scroll = old_lines.len() as u16;
+            }
+            let decision = self.decision((self.cursor.0, h));
+            let (marker, color) = match decision {
+                Decision::Pending => ("·", theme.accent),
+                Decision::Accepted => ("✓ accepted", theme.ok),
+                Decision::Rejected => ("✗ rejected", theme.error),
+            };
+            let mut style = Style::default().fg(color);
+            if selected {
+                style = style.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+            }
+            old_lines.push(Line::styled(hunk.header.clone(), style));
+            let note = self
+                .notes
+                .get(&(self.cursor.0, h))
+                .map(|n| format!(" — {n}"))
+                .unwrap_or_default();
+            new_lines.push(Line::styled(format!("{marker}{note}"), style));
+
+            let found = self.hunk_diagnostics(file, hunk);
+            for row in hunk.rows() {
+                old_lines.push(side_line(row.left.as_ref()));
+                new_lines.push(side_line(row.right.as_ref()));
+                let Some(line_no) = row.right.as_ref().map(|side| side.line_no) else {
+                    continue;
+                };
+                for d in found.iter().filter(|d| d.line == Some(line_no as usize)) {
+                    old_lines.push(Line::from(""));
+                    new_lines.push(diagnostic_line(d));
+                }
+            }
+        }
+
+        let block = |title: String| Block::default().borders(Borders::ALL).title(title);
+        frame.render_widget(
+            Paragraph::new(old_lines)
+                .block(block(format!(" {} ", self.base)))
+                .scroll((scroll, 0)),
+            old_area,
+        );
+        frame.render_widget(
+            Paragraph::new(new_lines)
+                .block(block(format!(" {} ", file.path())))
+                .scroll((scroll, 0)),
+            new_area,
+        );
+    }
+}
+
+/// Whether a diagnostic's path (relative, `./`-prefixed or absolute) names
+/// the diff path.
+fn same_file(reported: &str, path: &str) -> bool {
+    let reported = reported.trim_start_matches("./");
+    reported == path || reported.ends_with(&format!("/{path}"))
+}
+
+fn diagnostic_line(d: &Diagnostic) -> Line<'static> {
+    let theme = super::settings::theme();
+    let color = match d.severity {
+        Severity::Info => theme.accent,
+        Severity::Warning => theme.warn,
+        Severity::Error | Severity::Critical => theme.error,
+    };
+    let code = d
+        .code
+        .as_deref()
+        .map(|c| format!(" [{c}]"))
+        .unwrap_or_default();
+    Line::styled(
+        format!("     ⚠ {}: {}{code}", d.tool, d.message),
+        Style::default().fg(color).add_modifier(Modifier::ITALIC),
+    )
+}
+
+fn side_line(side: Option<&Side>) -> Line<'static> {
+    let theme = super::settings::theme();
+    let Some(side) = side else {
+        return Line::from("");
+    };
+    let style = match side.change {
+        Change::Unchanged => Style::default(),
+        Change::Removed => Style::default().fg(theme.error),
+        Change::Added => Style::default().fg(theme.ok),
+    };
+    Line::from(vec![
+        Span::styled(
+            format!("{:>4} ", side.line_no),
+            Style::default().fg(theme.muted),
+        ),
+        Span::styled(side.text.replace('\t', "    "), style),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::diff::parse_unified;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    const DIFF: &str = "\
+diff --git a/src/a.rs b/src/a.rs
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,2 +1,2 @@
+ fn a() {
+-    old_a();
++    new_a();
+@@ -10 +10 @@
+-const X: u8 = 1;
++const X: u8 = 2;
+diff --git a/src/b.rs b/src/b.rs
+--- a/src/b.rs
++++ b/src/b.rs
+@@ -5 +5,2 @@
+ fn b() {}
++fn c() {}
+";
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_text(app: &mut ReviewApp, text: &str) {
+        for c in text.chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn test_navigation_crosses_files() {
+        let mut app = ReviewApp::new("wt", "main", parse_unified(DIFF));
+        assert_eq!(app.cursor(), (0, 0));
+        app.handle_key(key(KeyCode::Char('j')));
+        app.handle_key(key(KeyCode::Char('j')));
+        assert_eq!(app.cursor(), (1, 0));
+        // Already on the last hunk.
+        app.handle_key(key(KeyCode::Down));
+        assert_eq!(app.cursor(), (1, 0));
+        app.handle_key(key(KeyCode::Char('p')));
+        assert_eq!(app.cursor(), (0, 0));
+    }
+
+    #[test]
+    fn test_reject_with_note_then_send() {
+        let mut app = ReviewApp::new("wt", "main", parse_unified(DIFF));
+        assert_eq!(app.handle_key(key(KeyCode::Char('s'))), Action::None);
+
+        app.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(app.cursor(), (0, 1));
+        app.handle_key(key(KeyCode::Char('r')));
+        type_text(&mut app, "keep X at 1");
+        app.handle_key(key(KeyCode::Enter));
+        assert_eq!(app.decision((0, 1)), Decision::Rejected);
+        assert_eq!(app.cursor(), (1, 0));
+        assert_eq!(app.handle_key(key(KeyCode::Char('s'))), Action::SendBack);
+
+        let prompt = app.follow_up_prompt().unwrap();
+        assert!(prompt.contains("against `main`"));
+        assert!(prompt.contains("## Rejected: src/a.rs @@ -10 +10 @@"));
+        assert!(prompt.contains("Reviewer note: keep X at 1"));
+        assert!(prompt.contains("-const X: u8 = 1;\n+const X: u8 = 2;"));
+        assert!(prompt.contains("- src/a.rs @@ -1,2 +1,2 @@"));
+        assert!(!prompt.contains("src/b.rs"));
+    }
+
+    #[test]
+    fn test_undo_clears_decision_and_note() {
+        let mut app = ReviewApp::new("wt", "main", parse_unified(DIFF));
+        app.handle_key(key(KeyCode::Char('r')));
+        type_text(&mut app, "no");
+        app.handle_key(key(KeyCode::Enter));
+        app.handle_key(key(KeyCode::Char('k')));
+        app.handle_key(key(KeyCode::Char('u')));
+        assert_eq!(app.decision((0, 0)), Decision::Pending);
+        assert!(app.follow_up_prompt().is_none());
+    }
+
+    #[test]
+    fn test_diagnostics_annotate_rows_and_follow_up() {
+        let diagnostic = Diagnostic {
+            tool: "tsc".into(),
+            severity: Severity::Error,
+            file: Some("/repo/src/a.rs".into()),
+            line: Some(10),
+            column: Some(1),
+            code: Some("E1".into()),
+            message: "X is out of range".into(),
+        };
+        let mut app =
+            ReviewApp::new("wt", "main", parse_unified(DIFF)).with_diagnostics(vec![diagnostic]);
+        app.handle_key(key(KeyCode::Char('j')));
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("⚠ tsc: X is out of range [E1]"));
+        assert!(screen.contains("src/a.rs ⚠1"));
+
+        app.handle_key(key(KeyCode::Char('r')));
+        app.handle_key(key(KeyCode::Enter));
+        let prompt = app.follow_up_prompt().unwrap();
+        assert!(prompt.contains("Diagnostic: /repo/src/a.rs:10:1: error: X is out of range [E1]"));
+    }
+
+    #[test]
+    fn test_render_side_by_side() {
+        let mut app = ReviewApp::new("agent-a", "main", parse_unified(DIFF));
+        app.handle_key(key(KeyCode::Char('a')));
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("ccswarm review: agent-a"));
+        // Accepting moved to the second hunk, which is scrolled into view.
+        assert!(screen.contains("const X: u8 = 1;"));
+        assert!(screen.contains("const X: u8 = 2;"));
+        assert!(!screen.contains("old_a();"));
+        assert!(screen.contains("1 accepted"));
+    }
+}
@@ -0,0 +1,512 @@
+//! Theme, pane layout, default view and keybindings for the TUI, read from
+//! `~/.config/ccswarm/tui.toml` (or `$CCSWARM_TUI_CONFIG`):
+//!
+//! ```toml
+//! theme = "light"            # default | high-contrast | light
+//! default_view = "inbox"     # view `ccswarm tui` opens without a subcommand
+//!
+//! [colors]                   # override single roles of the theme
+//! warn = "#b58900"
+//! error = "lightred"
+//!
+//! [layout]
+//! panes = "stacked"          # side-by-side (default) | stacked
+//! list_percent = 40          # share of the list pane in split views
+//!
+//! [keys]                     # extra keys per action, next to the defaults
+//! quit = ["x"]
+//! down = ["n", "ctrl-n"]
+//! ```
+//!
+//! Views read the active settings through [`theme`], [`layout`] and [`keys`].
+//! [`crate::tui::next_key`] checks the file between key presses and applies
+//! edits while the TUI runs; a file that fails to parse leaves the previous
+//! settings in place.
+
+use anyhow::{Context, Result, bail};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// Environment variable pointing at a TUI config file elsewhere.
+pub const CONFIG_ENV: &str = "CCSWARM_TUI_CONFIG";
+
+/// Names accepted by `theme = "..."` and `--theme`.
+pub const THEMES: &[&str] = &["default", "high-contrast", "light"];
+
+/// Colors by role rather than by hue, so themes can swap them wholesale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Title bar text and background.
+    pub header_fg: Color,
+    pub header_bg: Color,
+    /// Ordinary text.
+    pub text: Color,
+    /// Secondary text: timestamps, authors, hints.
+    pub muted: Color,
+    /// Highlights that are neither good nor bad news.
+    pub accent: Color,
+    pub info: Color,
+    /// A third hue for categories (e.g. commands on the timeline).
+    pub special: Color,
+    pub ok: Color,
+    pub warn: Color,
+    pub error: Color,
+    /// Text on an input prompt, drawn over `warn`.
+    pub prompt_fg: Color,
+}
+
+impl Theme {
+    pub fn builtin(name: &str) -> Option<Self> {
+        Some(match name {
+            "default" => Self::default(),
+            "high-contrast" => Self {
+                header_fg: Color::Black,
+                header_bg: Color::White,
+                text: Color::White,
+                muted: Color::Gray,
+                accent: Color::LightCyan,
+                info: Color::LightBlue,
+                special: Color::LightMagenta,
+                ok: Color::LightGreen,
+                warn: Color::LightYellow,
+                error: Color::LightRed,
+                prompt_fg: Color::Black,
+            },
+            // Darker hues that keep their contrast on a white background.
+            "light" => Self {
+                header_fg: Color::White,
+                header_bg: Color::Rgb(0x1f, 0x4e, 0xb4),
+                text: Color::Black,
+                muted: Color::Rgb(0x6a, 0x73, 0x7d),
+                accent: Color::Rgb(0x00, 0x6b, 0x85),
+                info: Color::Rgb(0x1f, 0x4e, 0xb4),
+                special: Color::Rgb(0x8a, 0x2b, 0xa8),
+                ok: Color::Rgb(0x1a, 0x7f, 0x37),
+                warn: Color::Rgb(0x8a, 0x5a, 0x00),
+                error: Color::Rgb(0xb3, 0x1d, 0x28),
+                prompt_fg: Color::White,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Title bar style.
+    pub fn header(&self) -> Style {
+        Style::default().fg(self.header_fg).bg(self.header_bg)
+    }
+
+    /// Style of an input prompt label.
+    pub fn prompt(&self) -> Style {
+        Style::default().fg(self.prompt_fg).bg(self.warn)
+    }
+
+    fn set(&mut self, role: &str, color: Color) -> Result<()> {
+        let slot = match role {
+            "header_fg" => &mut self.header_fg,
+            "header_bg" => &mut self.header_bg,
+            "text" => &mut self.text,
+            "muted" => &mut self.muted,
+            "accent" => &mut self.accent,
+            "info" => &mut self.info,
+            "special" => &mut self.special,
+            "ok" => &mut self.ok,
+            "warn" => &mut self.warn,
+            "error" => &mut self.error,
+            "prompt_fg" => &mut self.prompt_fg,
+            other => bail!("unknown color role '{other}'"),
+        };
+        *slot = color;
+        Ok(())
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            header_fg: Color::White,
+            header_bg: Color::Blue,
+            text: Color::Reset,
+            muted: Color::DarkGray,
+            accent: Color::Cyan,
+            info: Color::Blue,
+            special: Color::Magenta,
+            ok: Color::Green,
+            warn: Color::Yellow,
+            error: Color::Red,
+            prompt_fg: Color::Black,
+        }
+    }
+}
+
+/// How views with a list and a detail pane split the screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Panes {
+    #[default]
+    SideBySide,
+    Stacked,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaneLayout {
+    #[serde(default)]
+    pub panes: Panes,
+    /// Share of the list pane in percent; each view has its own default.
+    #[serde(default)]
+    pub list_percent: Option<u16>,
+}
+
+impl PaneLayout {
+    /// Split `area` into a list and a detail pane.
+    pub fn split(&self, area: Rect, default_percent: u16) -> [Rect; 2] {
+        let percent = self.list_percent.unwrap_or(default_percent).clamp(10, 90);
+        let constraints = [
+            Constraint::Percentage(percent),
+            Constraint::Percentage(100 - percent),
+        ];
+        match self.panes {
+            Panes::SideBySide => Layout::horizontal(constraints).areas(area),
+            Panes::Stacked => Layout::vertical(constraints).areas(area),
+        }
+    }
+}
+
+/// Actions that can take extra keys, each with the key views listen for.
+const ACTIONS: &[(&str, KeyCode)] = &[
+    ("quit", KeyCode::Char('q')),
+    ("down", KeyCode::Char('j')),
+    ("up", KeyCode::Char('k')),
+    ("top", KeyCode::Char('g')),
+    ("bottom", KeyCode::Char('G')),
+    ("page_down", KeyCode::PageDown),
+    ("page_up", KeyCode::PageUp),
+    ("reload", KeyCode::Char('R')),
+];
+
+/// Extra keys mapped onto the ones views handle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyMap {
+    bindings: Vec<(KeyEvent, KeyCode)>,
+}
+
+impl KeyMap {
+    fn from_config(keys: &BTreeMap<String, Vec<String>>) -> Result<Self> {
+        let mut bindings = Vec::new();
+        for (action, specs) in keys {
+            let Some((_, code)) = ACTIONS.iter().find(|(name, _)| name == action) else {
+                bail!(
+                    "unknown key action '{action}' (expected one of {})",
+                    ACTIONS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            };
+            for spec in specs {
+                bindings.push((parse_key(spec)?, *code));
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    /// The key a view should see for `key`: the default key of the action it
+    /// is bound to, or `key` itself. Apply it only outside text input.
+    pub fn translate(&self, key: KeyEvent) -> KeyEvent {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound.code == key.code && bound.modifiers == key.modifiers)
+            .map_or(key, |(_, code)| KeyEvent::new(*code, KeyModifiers::NONE))
+    }
+}
+
+/// `x`, `G`, `ctrl-n`, `alt-x`, `esc`, `enter`, `tab`, `space`, arrows,
+/// `pageup`/`pagedown`, `home`/`end` and `f1`–`f12`.
+fn parse_key(spec: &str) -> Result<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec.trim();
+    loop {
+        let lower = rest.to_ascii_lowercase();
+        if let Some(tail) = lower.strip_prefix("ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = &rest[rest.len() - tail.len()..];
+        } else if let Some(tail) = lower.strip_prefix("alt-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = &rest[rest.len() - tail.len()..];
+        } else {
+            break;
+        }
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "esc" => KeyCode::Esc,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            f if f.starts_with('f') => match f[1..].parse::<u8>() {
+                Ok(n @ 1..=12) => KeyCode::F(n),
+                _ => bail!("unknown key '{spec}'"),
+            },
+            _ => bail!("unknown key '{spec}'"),
+        },
+    };
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+/// `tui.toml` as written.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingsFile {
+    #[serde(default)]
+    theme: Option<String>,
+    #[serde(default)]
+    default_view: Option<String>,
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+    #[serde(default)]
+    layout: PaneLayout,
+    #[serde(default)]
+    keys: BTreeMap<String, Vec<String>>,
+}
+
+/// Resolved TUI settings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TuiSettings {
+    pub theme: Theme,
+    pub layout: PaneLayout,
+    pub default_view: Option<String>,
+    pub keys: KeyMap,
+}
+
+impl TuiSettings {
+    /// Parse a config file; `theme` (from `--theme`) wins over the file's.
+    pub fn parse(toml_text: &str, theme: Option<&str>) -> Result<Self> {
+        let file: SettingsFile = toml::from_str(toml_text).context("Invalid TUI config")?;
+        let name = theme.or(file.theme.as_deref()).unwrap_or("default");
+        let mut resolved = Theme::builtin(name).with_context(|| {
+            format!(
+                "unknown theme '{name}' (expected one of {})",
+                THEMES.join(", ")
+            )
+        })?;
+        for (role, color) in &file.colors {
+            let color: Color = color
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid color '{color}' for '{role}'"))?;
+            resolved.set(role, color)?;
+        }
+        Ok(Self {
+            theme: resolved,
+            layout: file.layout,
+            default_view: file.default_view,
+            keys: KeyMap::from_config(&file.keys)?,
+        })
+    }
+
+    /// Load `path`, or the defaults when it doesn't exist.
+    pub fn load(path: &Path, theme: Option<&str>) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text, theme)
+                .with_context(|| format!("Failed to load {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::parse("", theme),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+}
+
+/// `$CCSWARM_TUI_CONFIG`, else `~/.config/ccswarm/tui.toml`.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    dirs::home_dir().map(|home| home.join(".config").join("ccswarm").join("tui.toml"))
+}
+
+struct Active {
+    settings: Arc<TuiSettings>,
+    path: Option<PathBuf>,
+    theme_override: Option<String>,
+    modified: Option<SystemTime>,
+}
+
+static ACTIVE: RwLock<Option<Active>> = RwLock::new(None);
+
+fn modified(path: Option<&Path>) -> Option<SystemTime> {
+    std::fs::metadata(path?).and_then(|m| m.modified()).ok()
+}
+
+/// Load the config file and make it the active settings.
+pub fn install(theme: Option<&str>) -> Result<Arc<TuiSettings>> {
+    if let Some(name) = theme
+        && Theme::builtin(name).is_none()
+    {
+        bail!(
+            "unknown theme '{name}' (expected one of {})",
+            THEMES.join(", ")
+        );
+    }
+    let path = config_path();
+    let settings = Arc::new(match &path {
+        Some(path) => TuiSettings::load(path, theme)?,
+        None => TuiSettings::parse("", theme)?,
+    });
+    let mut active = ACTIVE.write().unwrap_or_else(|e| e.into_inner());
+    *active = Some(Active {
+        settings: settings.clone(),
+        modified: modified(path.as_deref()),
+        path,
+        theme_override: theme.map(str::to_string),
+    });
+    Ok(settings)
+}
+
+/// Re-read the config file if it changed since it was loaded. Returns
+/// whether the settings changed; a broken edit is logged and ignored.
+pub fn reload_if_changed() -> bool {
+    let mut guard = ACTIVE.write().unwrap_or_else(|e| e.into_inner());
+    let Some(active) = guard.as_mut() else {
+        return false;
+    };
+    let now = modified(active.path.as_deref());
+    if now == active.modified {
+        return false;
+    }
+    active.modified = now;
+    let Some(path) = &active.path else {
+        return false;
+    };
+    match TuiSettings::load(path, active.theme_override.as_deref()) {
+        Ok(settings) => {
+            let changed = *active.settings != settings;
+            active.settings = Arc::new(settings);
+            changed
+        }
+        Err(e) => {
+            tracing::warn!("keeping the previous TUI settings: {e:#}");
+            false
+        }
+    }
+}
+
+/// The active settings (defaults until [`install`] runs).
+pub fn current() -> Arc<TuiSettings> {
+    ACTIVE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|active| active.settings.clone())
+        .unwrap_or_default()
+}
+
+pub fn theme() -> Theme {
+    current().theme
+}
+
+pub fn layout() -> PaneLayout {
+    current().layout
+}
+
+pub fn keys() -> KeyMap {
+    current().keys.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_applies_theme_overrides_layout_and_keys() {
+        let settings = TuiSettings::parse(
+            r##"
+theme = "light"
+default_view = "logs"
+
+[colors]
+warn = "#b58900"
+error = "lightred"
+
+[layout]
+panes = "stacked"
+list_percent = 30
+
+[keys]
+quit = ["x"]
+down = ["ctrl-n", "pagedown"]
+"##,
+            None,
+        )
+        .unwrap();
+        assert_eq!(settings.theme.warn, Color::Rgb(0xb5, 0x89, 0x00));
+        assert_eq!(settings.theme.error, Color::LightRed);
+        assert_eq!(settings.theme.text, Color::Black);
+        assert_eq!(settings.default_view.as_deref(), Some("logs"));
+        assert_eq!(settings.layout.panes, Panes::Stacked);
+
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        let keys = &settings.keys;
+        assert_eq!(
+            keys.translate(key(KeyCode::Char('x'), KeyModifiers::NONE))
+                .code,
+            KeyCode::Char('q')
+        );
+        assert_eq!(
+            keys.translate(key(KeyCode::Char('n'), KeyModifiers::CONTROL))
+                .code,
+            KeyCode::Char('j')
+        );
+        // Unbound keys, and bound keys with other modifiers, pass through.
+        assert_eq!(
+            keys.translate(key(KeyCode::Char('n'), KeyModifiers::NONE))
+                .code,
+            KeyCode::Char('n')
+        );
+
+        let forced = TuiSettings::parse("theme = \"light\"", Some("high-contrast")).unwrap();
+        assert_eq!(forced.theme, Theme::builtin("high-contrast").unwrap());
+    }
+
+    #[test]
+    fn parse_rejects_unknown_names() {
+        for bad in [
+            "theme = \"solarized\"",
+            "[colors]\nwarn = \"not-a-color\"",
+            "[colors]\nbackground = \"red\"",
+            "[keys]\nexplode = [\"x\"]",
+            "[keys]\nquit = [\"hyper-x\"]",
+            "colour = \"red\"",
+        ] {
+            assert!(TuiSettings::parse(bad, None).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn stacked_layout_splits_vertically() {
+        let area = Rect::new(0, 0, 100, 40);
+        let side = PaneLayout::default().split(area, 25);
+        assert_eq!((side[0].width, side[1].x), (25, 25));
+        let stacked = PaneLayout {
+            panes: Panes::Stacked,
+            list_percent: Some(50),
+        }
+        .split(area, 25);
+        assert_eq!((stacked[0].height, stacked[1].y), (20, 20));
+    }
+}
@@ -0,0 +1,344 @@
+//! Session timeline: one bar per stage, provider call, gate command,
+//! approval wait and pause, laid out against the session's wall clock, with
+//! detected errors marked on their stage and a running stage labelled with
+//! its agent's latest progress report.
+//!
+//! The view only draws a [`Timeline`]; the handler rebuilds it from the
+//! run's `events.ndjson` on [`Action::Reload`] so a live run keeps growing.
+
+use super::Action;
+use crate::events::timeline::{MarkKind, SpanKind, Timeline, display_label, format_ms};
+use chrono::{DateTime, Utc};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+
+/// Columns kept for span labels.
+const LABEL_WIDTH: usize = 24;
+/// Columns kept for the duration after each bar.
+const DURATION_WIDTH: usize = 16;
+
+pub struct TimelineApp {
+    timeline: Timeline,
+    now: DateTime<Utc>,
+    selected: usize,
+    status: Option<String>,
+}
+
+impl TimelineApp {
+    pub fn new(timeline: Timeline, now: DateTime<Utc>) -> Self {
+        Self {
+            timeline,
+            now,
+            selected: 0,
+            status: None,
+        }
+    }
+
+    /// Swap in a rebuilt timeline, keeping the selection in place.
+    pub fn update(&mut self, timeline: Timeline, now: DateTime<Utc>) {
+        self.timeline = timeline;
+        self.now = now;
+        self.selected = self
+            .selected
+            .min(self.timeline.spans.len().saturating_sub(1));
+    }
+
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        self.status = None;
+        let key = super::settings::keys().translate(key);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.selected + 1 < self.timeline.spans.len() {
+                    self.selected += 1;
+                }
+                Action::None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                Action::None
+            }
+            KeyCode::Char('R') => Action::Reload,
+            _ => Action::None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let theme = super::settings::theme();
+        let [header, body, detail, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let breakdown = self.timeline.breakdown(self.now);
+        let mut head = vec![
+            Span::styled(
+                format!(" {} ", self.timeline.run_id),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format_ms(breakdown.total_ms)),
+        ];
+        if self.timeline.is_running() {
+            head.push(Span::styled(" running", Style::default().fg(theme.ok)));
+        }
+        for (label, ms, color) in [
+            ("provider", breakdown.provider_ms, theme.accent),
+            ("commands", breakdown.commands_ms, theme.special),
+            ("waiting", breakdown.waiting_ms, theme.warn),
+            ("paused", breakdown.paused_ms, theme.muted),
+            ("other", breakdown.other_ms, theme.text),
+        ] {
+            if ms > 0 {
+                head.push(Span::raw("  "));
+                head.push(Span::styled(
+                    format!("{label} {}", format_ms(ms)),
+                    Style::default().fg(color),
+                ));
+            }
+        }
+        frame.render_widget(Paragraph::new(Line::from(head)), header);
+
+        let bar_width = (body.width as usize).saturating_sub(LABEL_WIDTH + DURATION_WIDTH + 4);
+        let items: Vec<ListItem> = self
+            .timeline
+            .spans
+            .iter()
+            .map(|span| ListItem::new(self.bar_line(span, bar_width.max(1))))
+            .collect();
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(" {} ", crate::t!("tui-timeline"))),
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            body,
+            &mut state,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.detail_lines()).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} ", crate::t!("tui-detail"))),
+            ),
+            detail,
+        );
+
+        let help = self
+            .status
+            .clone()
+            .unwrap_or_else(|| crate::t!("tui-timeline-help"));
+        frame.render_widget(
+            Paragraph::new(help).style(Style::default().fg(theme.muted)),
+            footer,
+        );
+    }
+
+    fn bar_line(&self, span: &crate::events::timeline::Span, width: usize) -> Line<'static> {
+        let theme = super::settings::theme();
+        let (from, to) = self.timeline.bar_range(span, width, self.now);
+        let color = kind_color(span.kind);
+        let failed = matches!(
+            span.status.as_deref(),
+            Some("failed" | "timeout" | "rejected" | "timed_out")
+        );
+        // Errors detected inside a stage are drawn on that stage's bar.
+        let errors: Vec<usize> = match (&span.kind, &self.timeline.started) {
+            (SpanKind::Stage, Some(started)) => {
+                let total = self.timeline.total_ms(self.now).max(1) as f64;
+                self.timeline
+                    .marks
+                    .iter()
+                    .filter(|m| m.kind == MarkKind::Error && m.stage == span.stage)
+                    .map(|m| {
+                        let at = (m.ts - *started).num_milliseconds().max(0) as f64;
+                        (((at / total) * width as f64) as usize).min(width.saturating_sub(1))
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let label: String = display_label(span).chars().take(LABEL_WIDTH).collect();
+        let mut spans = vec![Span::raw(format!("{label:<LABEL_WIDTH$} "))];
+        let mut bar = String::new();
+        let mut in_span = false;
+        let flush = |spans: &mut Vec<Span<'static>>, bar: &mut String, in_span: bool| {
+            if !bar.is_empty() {
+                let style = if in_span {
+                    Style::default().fg(color)
+                } else {
+                    Style::default()
+                };
+                spans.push(Span::styled(std::mem::take(bar), style));
+            }
+        };
+        for col in 0..width {
+            if errors.contains(&col) {
+                flush(&mut spans, &mut bar, in_span);
+                spans.push(Span::styled(
+                    "✗",
+                    Style::default()
+                        .fg(theme.error)
+                        .add_modifier(Modifier::BOLD),
+                ));
+                continue;
+            }
+            let inside = (from..to).contains(&col);
+            if inside != in_span {
+                flush(&mut spans, &mut bar, in_span);
+                in_span = inside;
+            }
+            bar.push(if inside { '█' } else { ' ' });
+        }
+        flush(&mut spans, &mut bar, in_span);
+
+        let duration = format_ms(span.duration_ms(self.now));
+        let (state, state_style) = match (&span.end, &span.status) {
+            // A running stage shows where its agent says it is.
+            (None, _) => (
+                self.timeline
+                    .marks
+                    .iter()
+                    .rev()
+                    .find(|m| {
+                        span.kind == SpanKind::Stage
+                            && m.kind == MarkKind::Progress
+                            && m.stage == span.stage
+                            && m.ts >= span.start
+                    })
+                    .map_or_else(|| "running".to_string(), |m| m.message.clone()),
+                Style::default().fg(theme.ok),
+            ),
+            (Some(_), Some(status)) if failed => (status.clone(), Style::default().fg(theme.error)),
+            (Some(_), Some(status)) => (status.clone(), Style::default().fg(theme.muted)),
+            _ => (String::new(), Style::default()),
+        };
+        spans.push(Span::raw(format!(" {duration:>7} ")));
+        spans.push(Span::styled(state, state_style));
+        Line::from(spans)
+    }
+
+    fn detail_lines(&self) -> Vec<Line<'static>> {
+        let theme = super::settings::theme();
+        let Some(span) = self.timeline.spans.get(self.selected) else {
+            return vec![Line::from(
+                "No timeline events recorded for this session yet.",
+            )];
+        };
+        let offset = self
+            .timeline
+            .started
+            .map(|s| format_ms((span.start - s).num_milliseconds().max(0) as u64))
+            .unwrap_or_default();
+        let mut lines = vec![Line::from(vec![
+            Span::styled(
+                format!("{} ", span.kind.as_str()),
+                Style::default().fg(kind_color(span.kind)),
+            ),
+            Span::styled(
+                span.label.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "  started +{offset}, took {}{}",
+                format_ms(span.duration_ms(self.now)),
+                span.stage
+                    .as_deref()
+                    .filter(|s| *s != span.label)
+                    .map(|s| format!(", stage {s}"))
+                    .unwrap_or_default()
+            )),
+        ])];
+        let end = span.end.unwrap_or(self.now);
+        for mark in self
+            .timeline
+            .marks
+            .iter()
+            .filter(|m| m.ts >= span.start && m.ts <= end && m.stage == span.stage)
+            .rev()
+            .take(3)
+        {
+            let style = match mark.kind {
+                MarkKind::Error => Style::default().fg(theme.error),
+                MarkKind::Output => Style::default().fg(theme.muted),
+                MarkKind::Progress => Style::default().fg(theme.ok),
+            };
+            lines.push(Line::styled(mark.message.clone(), style));
+        }
+        lines
+    }
+}
+
+fn kind_color(kind: SpanKind) -> Color {
+    let theme = super::settings::theme();
+    match kind {
+        SpanKind::Stage => theme.info,
+        SpanKind::Provider => theme.accent,
+        SpanKind::Command => theme.special,
+        SpanKind::Waiting => theme.warn,
+        SpanKind::Paused => theme.muted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn timeline() -> Timeline {
+        let events = [
+            r#"{"ts":"2026-01-01T00:00:00Z","level":"info","run_id":"r1","event_type":"movement_start","stage":"build","message":"start"}"#,
+            r#"{"ts":"2026-01-01T00:00:30Z","level":"info","run_id":"r1","event_type":"provider_call","stage":"build","message":"call","metadata":{"elapsed_ms":20000}}"#,
+            r#"{"ts":"2026-01-01T00:00:35Z","level":"warn","run_id":"r1","event_type":"error_detected","stage":"build","message":"2 diagnostics from gate 'check'"}"#,
+            r#"{"ts":"2026-01-01T00:00:40Z","level":"info","run_id":"r1","event_type":"movement_end","stage":"build","message":"end","metadata":{"status":"failed"}}"#,
+        ]
+        .join("\n");
+        Timeline::from_events("r1", &events)
+    }
+
+    #[test]
+    fn test_renders_bars_errors_and_breakdown() {
+        let timeline = timeline();
+        let now = timeline.last_event.unwrap();
+        let mut app = TimelineApp::new(timeline, now);
+        assert_eq!(app.handle_key(key('j')), Action::None);
+        assert_eq!(app.handle_key(key('R')), Action::Reload);
+
+        let mut terminal = Terminal::new(TestBackend::new(90, 14)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("provider 20.0s"), "{screen}");
+        assert!(screen.contains("other 20.0s"));
+        assert!(screen.contains("✗"));
+        assert!(screen.contains("failed"));
+        assert!(screen.contains("provider provider call"));
+        assert_eq!(app.handle_key(key('q')), Action::Quit);
+    }
+}
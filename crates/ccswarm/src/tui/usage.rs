@@ -0,0 +1,465 @@
+//! Token usage meter: live per-agent token and cost totals for running
+//! pipelines, with a sparkline of recent stages and budget colouring.
+//!
+//! Usage comes from the run event logs the providers layer feeds: each
+//! stage's `movement_end` carries its token counts and each `provider_call`
+//! the reported cost. Pausing ([`Action::Pause`]) stops the run the selected
+//! agent belongs to, so everything it spawned stops spending too.
+
+use super::Action;
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Sparkline};
+use std::collections::HashMap;
+
+/// Share of a budget at which a meter turns yellow.
+pub const WARN_AT: f64 = 0.75;
+/// Share of a budget at which a meter turns red.
+pub const ALERT_AT: f64 = 0.9;
+
+/// Stage samples kept per agent for the sparkline.
+const HISTORY: usize = 60;
+
+/// Per-agent limits the meters are measured against.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Budget {
+    pub tokens: Option<u64>,
+    pub usd: Option<f64>,
+}
+
+/// Whether the process behind a run is alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    Running,
+    Paused,
+    Finished,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentUsage {
+    pub run_id: String,
+    pub agent: String,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub cost_usd: f64,
+    /// Tokens used per stage, oldest first.
+    pub history: Vec<u64>,
+    pub state: RunState,
+}
+
+impl AgentUsage {
+    pub fn tokens(&self) -> u64 {
+        self.tokens_in + self.tokens_out
+    }
+
+    /// How much of the budget is used: the larger of the token and cost
+    /// shares, or `None` without a budget.
+    pub fn budget_used(&self, budget: Budget) -> Option<f64> {
+        let tokens = budget
+            .tokens
+            .filter(|t| *t > 0)
+            .map(|t| self.tokens() as f64 / t as f64);
+        let usd = budget.usd.filter(|u| *u > 0.0).map(|u| self.cost_usd / u);
+        match (tokens, usd) {
+            (Some(t), Some(u)) => Some(t.max(u)),
+            (t, u) => t.or(u),
+        }
+    }
+}
+
+/// Meter colour for a budget share.
+pub fn budget_color(used: Option<f64>) -> Color {
+    let theme = super::settings::theme();
+    match used {
+        Some(u) if u >= ALERT_AT => theme.error,
+        Some(u) if u >= WARN_AT => theme.warn,
+        _ => theme.ok,
+    }
+}
+
+/// Per-agent usage from one run's `events.ndjson`, in the order agents first
+/// used tokens. Stages without an agent are listed under the stage id.
+pub fn from_events(run_id: &str, content: &str, state: RunState) -> Vec<AgentUsage> {
+    let mut rows: Vec<AgentUsage> = Vec::new();
+    // Cost events only name the stage; remember which agent ran it.
+    let mut stage_agent: HashMap<String, String> = HashMap::new();
+
+    for line in content.lines() {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+            continue;
+        };
+        let str_field = |key: &str| event.get(key).and_then(|v| v.as_str());
+        let stage = str_field("stage");
+        if let (Some(stage), Some(agent)) = (stage, str_field("agent")) {
+            stage_agent.insert(stage.to_string(), agent.to_string());
+        }
+        let agent = str_field("agent")
+            .or_else(|| stage.and_then(|s| stage_agent.get(s).map(String::as_str)))
+            .or(stage);
+        let (Some(agent), Some(metadata)) = (agent, event.get("metadata")) else {
+            continue;
+        };
+        let row = |rows: &mut Vec<AgentUsage>| -> usize {
+            match rows.iter().position(|r| r.agent == agent) {
+                Some(i) => i,
+                None => {
+                    rows.push(AgentUsage {
+                        run_id: run_id.to_string(),
+                        agent: agent.to_string(),
+                        tokens_in: 0,
+                        tokens_out: 0,
+                        cost_usd: 0.0,
+                        history: Vec::new(),
+                        state,
+                    });
+                    rows.len() - 1
+                }
+            }
+        };
+        let number = |key: &str| metadata.get(key).and_then(|v| v.as_u64());
+
+        match str_field("event_type") {
+            Some("movement_end") => {
+                let (tokens_in, tokens_out) = (number("tokens_in"), number("tokens_out"));
+                if tokens_in.is_none() && tokens_out.is_none() {
+                    continue;
+                }
+                let i = row(&mut rows);
+                let usage = &mut rows[i];
+                usage.tokens_in += tokens_in.unwrap_or(0);
+                usage.tokens_out += tokens_out.unwrap_or(0);
+                usage
+                    .history
+                    .push(tokens_in.unwrap_or(0) + tokens_out.unwrap_or(0));
+                if usage.history.len() > HISTORY {
+                    usage.history.remove(0);
+                }
+            }
+            Some("provider_call") => {
+                if let Some(cost) = metadata.get("cost_usd").and_then(|v| v.as_f64()) {
+                    let i = row(&mut rows);
+                    rows[i].cost_usd += cost;
+                }
+            }
+            _ => {}
+        }
+    }
+    rows
+}
+
+pub struct UsageApp {
+    rows: Vec<AgentUsage>,
+    budget: Budget,
+    selected: usize,
+    status: Option<String>,
+}
+
+impl UsageApp {
+    pub fn new(rows: Vec<AgentUsage>, budget: Budget) -> Self {
+        Self {
+            rows,
+            budget,
+            selected: 0,
+            status: None,
+        }
+    }
+
+    /// Swap in freshly loaded rows, keeping the same agent selected.
+    pub fn update(&mut self, rows: Vec<AgentUsage>) {
+        let current = self.selected().map(|r| (r.run_id.clone(), r.agent.clone()));
+        self.rows = rows;
+        self.selected = current
+            .and_then(|(run, agent)| {
+                self.rows
+                    .iter()
+                    .position(|r| r.run_id == run && r.agent == agent)
+            })
+            .unwrap_or(0)
+            .min(self.rows.len().saturating_sub(1));
+    }
+
+    pub fn rows(&self) -> &[AgentUsage] {
+        &self.rows
+    }
+
+    pub fn selected(&self) -> Option<&AgentUsage> {
+        self.rows.get(self.selected)
+    }
+
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        self.status = None;
+        let key = super::settings::keys().translate(key);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.selected + 1 < self.rows.len() {
+                    self.selected += 1;
+                }
+                Action::None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                Action::None
+            }
+            KeyCode::Char('p') => match self.selected().map(|r| r.state) {
+                Some(RunState::Running) => Action::Pause,
+                Some(RunState::Paused) => {
+                    self.set_status("Already paused; press r to resume");
+                    Action::None
+                }
+                _ => {
+                    self.set_status("That run has finished");
+                    Action::None
+                }
+            },
+            KeyCode::Char('r') => match self.selected().map(|r| r.state) {
+                Some(RunState::Paused) => Action::Resume,
+                _ => {
+                    self.set_status("Not paused");
+                    Action::None
+                }
+            },
+            KeyCode::Char('R') => Action::Reload,
+            _ => Action::None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let theme = super::settings::theme();
+        let [header, body, history, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(6),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let tokens: u64 = self.rows.iter().map(AgentUsage::tokens).sum();
+        let cost: f64 = self.rows.iter().map(|r| r.cost_usd).sum();
+        let live = self
+            .rows
+            .iter()
+            .filter(|r| r.state != RunState::Finished)
+            .count();
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    " ccswarm usage ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    " {} agents ({live} live) · {} tokens · ${cost:.2}{}",
+                    self.rows.len(),
+                    format_tokens(tokens),
+                    budget_label(self.budget),
+                )),
+            ]))
+            .style(theme.header()),
+            header,
+        );
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let used = row.budget_used(self.budget);
+                let color = budget_color(used);
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{:<20}", truncate(&row.agent, 20)),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(" {:<12}", truncate(&row.run_id, 12)),
+                        Style::default().fg(theme.muted),
+                    ),
+                    match row.state {
+                        RunState::Running => {
+                            Span::styled(" ● live  ", Style::default().fg(theme.ok))
+                        }
+                        RunState::Paused => {
+                            Span::styled(" ⏸ paused", Style::default().fg(theme.warn))
+                        }
+                        RunState::Finished => {
+                            Span::styled(" done    ", Style::default().fg(theme.muted))
+                        }
+                    },
+                    Span::raw(format!(
+                        " {:>7} in {:>7} out  ${:<7.2}",
+                        format_tokens(row.tokens_in),
+                        format_tokens(row.tokens_out),
+                        row.cost_usd
+                    )),
+                ];
+                if let Some(used) = used {
+                    spans.push(Span::styled(
+                        format!(" {} {:>3.0}%", meter(used, 12), used * 100.0),
+                        Style::default().fg(color),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(" {} ", crate::t!("tui-agents"))),
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            body,
+            &mut state,
+        );
+
+        let (title, data, color) = match self.selected() {
+            Some(row) => (
+                format!(" {} · {} ", row.agent, crate::t!("tui-tokens-per-stage")),
+                row.history.as_slice(),
+                budget_color(row.budget_used(self.budget)),
+            ),
+            None => (
+                format!(" {} ", crate::t!("tui-tokens-per-stage")),
+                &[][..],
+                theme.ok,
+            ),
+        };
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .data(data)
+                .style(Style::default().fg(color)),
+            history,
+        );
+
+        let footer_line = match &self.status {
+            Some(status) => Line::from(Span::styled(
+                format!(" {status}"),
+                Style::default().fg(theme.warn),
+            )),
+            None => Line::from(format!(" {}", crate::t!("tui-usage-help"))),
+        };
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+}
+
+fn budget_label(budget: Budget) -> String {
+    let mut parts = Vec::new();
+    if let Some(tokens) = budget.tokens {
+        parts.push(format!("{} tokens", format_tokens(tokens)));
+    }
+    if let Some(usd) = budget.usd {
+        parts.push(format!("${usd:.2}"));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" · budget {} per agent", parts.join(" / "))
+    }
+}
+
+fn format_tokens(tokens: u64) -> String {
+    match tokens {
+        t if t >= 1_000_000 => format!("{:.1}M", t as f64 / 1_000_000.0),
+        t if t >= 10_000 => format!("{}k", t / 1000),
+        t => t.to_string(),
+    }
+}
+
+fn meter(used: f64, width: usize) -> String {
+    let filled = ((used.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("[{}{}]", "█".repeat(filled), "·".repeat(width - filled))
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let mut out: String = s.chars().take(max - 1).collect();
+        out.push('…');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    const EVENTS: &str = r#"{"run_id":"r1","event_type":"movement_start","stage":"plan","agent":"planner","message":"Stage 'plan' started"}
+{"run_id":"r1","event_type":"provider_call","stage":"plan","message":"Provider call completed","metadata":{"cost_usd":0.25}}
+{"run_id":"r1","event_type":"movement_end","stage":"plan","agent":"planner","message":"Stage 'plan' completed","metadata":{"tokens_in":1000,"tokens_out":500}}
+{"run_id":"r1","event_type":"movement_end","stage":"implement","agent":"coder","message":"Stage 'implement' completed","metadata":{"tokens_in":4000,"tokens_out":3000}}
+{"run_id":"r1","event_type":"movement_end","stage":"plan","agent":"planner","message":"Stage 'plan' completed","metadata":{"tokens_in":200,"tokens_out":100}}
+not json
+"#;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_from_events_aggregates_per_agent() {
+        let rows = from_events("r1", EVENTS, RunState::Running);
+        assert_eq!(rows.len(), 2);
+        let planner = &rows[0];
+        assert_eq!(planner.agent, "planner");
+        assert_eq!(planner.tokens(), 1800);
+        assert_eq!(planner.history, vec![1500, 300]);
+        // The cost event only names the stage.
+        assert!((planner.cost_usd - 0.25).abs() < f64::EPSILON);
+        assert_eq!(rows[1].agent, "coder");
+        assert_eq!(rows[1].tokens(), 7000);
+    }
+
+    #[test]
+    fn test_budget_thresholds_and_pause_key() {
+        let rows = from_events("r1", EVENTS, RunState::Running);
+        let budget = Budget {
+            tokens: Some(7500),
+            usd: Some(1.0),
+        };
+        assert_eq!(budget_color(rows[0].budget_used(budget)), Color::Green);
+        assert_eq!(budget_color(rows[1].budget_used(budget)), Color::Red);
+        assert_eq!(budget_color(Some(0.8)), Color::Yellow);
+        assert_eq!(
+            budget_color(rows[1].budget_used(Budget::default())),
+            Color::Green
+        );
+
+        let mut app = UsageApp::new(rows, budget);
+        app.handle_key(key(KeyCode::Char('j')));
+        assert_eq!(app.selected().unwrap().agent, "coder");
+        assert_eq!(app.handle_key(key(KeyCode::Char('p'))), Action::Pause);
+        assert_eq!(app.handle_key(key(KeyCode::Char('r'))), Action::None);
+
+        let mut paused = from_events("r1", EVENTS, RunState::Paused);
+        paused.reverse();
+        app.update(paused);
+        assert_eq!(app.selected().unwrap().agent, "coder");
+        assert_eq!(app.handle_key(key(KeyCode::Char('r'))), Action::Resume);
+
+        let mut terminal = Terminal::new(TestBackend::new(110, 14)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("coder"));
+        assert!(screen.contains("93%"));
+        assert!(screen.contains("paused"));
+    }
+}
@@ -101,3 +101,17 @@ impl CommandExecutor {
             .unwrap_or(false)
     }
 }
+
+/// `arg` as one POSIX shell word: bare when it holds only characters the
+/// shell leaves alone, single-quoted otherwise.
+pub fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
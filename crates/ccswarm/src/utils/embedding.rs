@@ -0,0 +1,129 @@
+//! Local text embeddings for comparing short task descriptions.
+//!
+//! Vectors are built with the hashing trick: words (lowercased, lightly
+//! stemmed, stop words dropped), adjacent word pairs and character trigrams
+//! are hashed into a fixed number of signed buckets, then the vector is
+//! normalised. No model or network call is involved, so it is cheap enough
+//! to run on every enqueue, and two descriptions of the same change in
+//! slightly different words still land close together.
+
+/// Number of buckets in an [`Embedding`].
+pub const DIMENSIONS: usize = 512;
+
+const WORD_WEIGHT: f32 = 1.0;
+const PAIR_WEIGHT: f32 = 0.5;
+const TRIGRAM_WEIGHT: f32 = 0.25;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "into", "is", "it", "of",
+    "on", "or", "so", "that", "the", "this", "to", "with",
+];
+
+/// A unit-length embedding of a piece of text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embedding(Vec<f32>);
+
+impl Embedding {
+    /// An embedding of `values` computed elsewhere (read back from disk or
+    /// returned by a provider), scaled to unit length.
+    pub fn from_values(mut values: Vec<f32>) -> Self {
+        normalise(&mut values);
+        Self(values)
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.0
+    }
+
+    /// Cosine similarity in `[-1, 1]`; 0 when either text had no words.
+    pub fn cosine(&self, other: &Embedding) -> f32 {
+        self.0.iter().zip(&other.0).map(|(a, b)| a * b).sum()
+    }
+}
+
+/// Embed `text`.
+pub fn embed(text: &str) -> Embedding {
+    let mut vector = vec![0.0f32; DIMENSIONS];
+    let words = words(text);
+    for word in &words {
+        add(&mut vector, word.as_bytes(), WORD_WEIGHT);
+        let padded: Vec<char> = format!("^{word}$").chars().collect();
+        for trigram in padded.windows(3) {
+            let trigram: String = trigram.iter().collect();
+            add(&mut vector, trigram.as_bytes(), TRIGRAM_WEIGHT);
+        }
+    }
+    for pair in words.windows(2) {
+        add(
+            &mut vector,
+            format!("{} {}", pair[0], pair[1]).as_bytes(),
+            PAIR_WEIGHT,
+        );
+    }
+    normalise(&mut vector);
+    Embedding(vector)
+}
+
+fn normalise(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| !word.is_empty() && !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| stem(&word))
+        .collect()
+}
+
+/// Strip the most common English suffixes so "adds", "adding" and "added"
+/// count as the same word.
+fn stem(word: &str) -> String {
+    for suffix in ["ing", "ed", "es", "s"] {
+        if let Some(stem) = word.strip_suffix(suffix)
+            && stem.chars().count() >= 3
+        {
+            return stem.to_string();
+        }
+    }
+    word.to_string()
+}
+
+fn add(vector: &mut [f32], feature: &[u8], weight: f32) {
+    let hash = fnv1a(feature);
+    let bucket = (hash % DIMENSIONS as u64) as usize;
+    // A second hash bit picks the sign, so collisions cancel out on average.
+    let sign = if hash & (1 << 63) == 0 { 1.0 } else { -1.0 };
+    vector[bucket] += sign * weight;
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewordings_are_closer_than_unrelated_tasks() {
+        let task = embed("Add rate limiting to the login endpoint");
+        let reworded = embed("add rate-limiting for login endpoints");
+        let unrelated = embed("Fix typo in the README installation section");
+
+        assert!((task.cosine(&task) - 1.0).abs() < 1e-5);
+        assert!(task.cosine(&reworded) > 0.9, "{}", task.cosine(&reworded));
+        assert!(task.cosine(&unrelated) < 0.3, "{}", task.cosine(&unrelated));
+    }
+
+    #[test]
+    fn text_without_words_embeds_to_zero() {
+        let empty = embed("  -- !! ");
+        assert_eq!(empty.cosine(&embed("anything")), 0.0);
+    }
+}
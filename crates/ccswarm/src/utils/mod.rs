@@ -3,6 +3,7 @@
 pub mod async_error_boundary;
 pub mod command;
 pub mod common;
+pub mod embedding;
 pub mod error;
 pub mod error_recovery;
 pub mod user_error;
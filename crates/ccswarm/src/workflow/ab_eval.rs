@@ -0,0 +1,497 @@
+//! A/B evaluation of two run configurations behind `ccswarm lab ab`.
+//!
+//! The same task runs twice in parallel, each run in its own isolated
+//! worktree and session, with a different model, think mode or flow (the
+//! prompt template). The two outcomes are then compared: a run that failed
+//! loses to one that succeeded outright, and when both succeeded a reviewer
+//! call reads the two results and names the better one. Every experiment is
+//! appended to `.ccswarm/experiments/ab.jsonl`, and the per-configuration
+//! win/loss tally shows up in `ccswarm lab evolution metrics` and `report`.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::ThinkMode;
+
+/// File under `.ccswarm/experiments/` holding one experiment per line.
+pub const EXPERIMENTS_FILE: &str = "ab.jsonl";
+
+/// Characters of each run's output shown to the reviewer.
+const REVIEW_OUTPUT_CHARS: usize = 4000;
+
+/// Directory holding experiment records and per-experiment run outputs.
+pub fn experiments_dir(repo: &Path) -> PathBuf {
+    repo.join(".ccswarm").join("experiments")
+}
+
+/// One side of an experiment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    /// `A` or `B`.
+    pub label: String,
+    /// Model override for every stage; `None` keeps the flow's models.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Think mode appended to the task prompt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub think_mode: Option<ThinkMode>,
+    /// Flow to run; `None` uses the experiment's default flow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow: Option<String>,
+}
+
+impl Variant {
+    /// Parse a `key=value[,key=value]` spec such as
+    /// `model=opus,think=megathink,flow=review-fix`.
+    pub fn parse(label: &str, spec: &str) -> Result<Self> {
+        let mut variant = Self {
+            label: label.to_string(),
+            model: None,
+            think_mode: None,
+            flow: None,
+        };
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| anyhow!("variant {label}: expected key=value, got '{part}'"))?;
+            if value.is_empty() {
+                return Err(anyhow!("variant {label}: '{key}' needs a value"));
+            }
+            match key {
+                "model" => variant.model = Some(value.to_string()),
+                "think" => variant.think_mode = Some(parse_think_mode(value)?),
+                "flow" => variant.flow = Some(value.to_string()),
+                other => {
+                    return Err(anyhow!(
+                        "variant {label}: unknown key '{other}' (expected model, think or flow)"
+                    ));
+                }
+            }
+        }
+        Ok(variant)
+    }
+
+    /// Flow this variant runs.
+    pub fn flow_or<'a>(&'a self, default_flow: &'a str) -> &'a str {
+        self.flow.as_deref().unwrap_or(default_flow)
+    }
+
+    /// Task prompt for this variant, carrying the think-mode keyword if set.
+    pub fn task_text(&self, task: &str) -> String {
+        match &self.think_mode {
+            Some(mode) => format!("{}\n\n{}.", task.trim_end(), mode.to_prompt_suffix()),
+            None => task.to_string(),
+        }
+    }
+
+    /// Stable description of the configuration, used as the tally key.
+    pub fn describe(&self, default_flow: &str) -> String {
+        let mut parts = vec![format!("flow={}", self.flow_or(default_flow))];
+        if let Some(model) = &self.model {
+            parts.push(format!("model={model}"));
+        }
+        if let Some(mode) = &self.think_mode {
+            parts.push(format!(
+                "think={}",
+                mode.to_prompt_suffix().replace(' ', "_")
+            ));
+        }
+        parts.join(",")
+    }
+}
+
+/// Accepts the serde names (`think_hard`) and the prompt keywords
+/// (`think hard`, `ultrathink`) alike.
+fn parse_think_mode(value: &str) -> Result<ThinkMode> {
+    let normalize = |s: &str| {
+        s.chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let wanted = normalize(value);
+    [
+        ThinkMode::Think,
+        ThinkMode::ThinkHard,
+        ThinkMode::ThinkHarder,
+        ThinkMode::UltraThink,
+        ThinkMode::MegaThink,
+    ]
+    .into_iter()
+    .find(|mode| normalize(mode.to_prompt_suffix()) == wanted)
+    .ok_or_else(|| {
+        anyhow!(
+            "unknown think mode '{value}' (expected think, think_hard, think_harder, ultrathink or megathink)"
+        )
+    })
+}
+
+/// What one variant's run produced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantOutcome {
+    pub variant: Variant,
+    /// Configuration key, see [`Variant::describe`].
+    pub config: String,
+    pub run_id: String,
+    pub success: bool,
+    pub duration_ms: u64,
+    /// Stages executed.
+    #[serde(default)]
+    pub stages: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Which side of an experiment won.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Winner {
+    A,
+    B,
+    Tie,
+}
+
+impl std::fmt::Display for Winner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Winner::A => "A",
+            Winner::B => "B",
+            Winner::Tie => "tie",
+        })
+    }
+}
+
+/// How the winner was determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecidedBy {
+    /// Only one run succeeded.
+    Outcome,
+    /// The reviewer compared two successful runs.
+    Review,
+    /// Both succeeded and no review was available; the faster run wins.
+    Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbVerdict {
+    pub winner: Winner,
+    pub decided_by: DecidedBy,
+    pub reason: String,
+}
+
+/// Prompt asking a reviewer to compare two successful runs of `task`.
+pub fn review_prompt(
+    task: &str,
+    a: &VariantOutcome,
+    a_output: &str,
+    b: &VariantOutcome,
+    b_output: &str,
+) -> String {
+    format!(
+        "You are reviewing two attempts at the same task, made with different \
+         configurations. Each ran in its own git worktree. Judge which result \
+         better accomplishes the task: correctness first, then completeness, \
+         then code quality. Ignore which configuration was used.\n\n\
+         # Task\n{task}\n\n\
+         # Attempt A (run {}, {} stages, {}s)\n{}\n\n\
+         # Attempt B (run {}, {} stages, {}s)\n{}\n\n\
+         Reply with `WINNER: A`, `WINNER: B` or `WINNER: TIE` on the first line \
+         and `REASON: <one sentence>` on the second.",
+        a.run_id,
+        a.stages,
+        a.duration_ms / 1000,
+        tail_chars(a_output, REVIEW_OUTPUT_CHARS),
+        b.run_id,
+        b.stages,
+        b.duration_ms / 1000,
+        tail_chars(b_output, REVIEW_OUTPUT_CHARS),
+    )
+}
+
+/// The last `max` characters of `text`, where run summaries end up.
+fn tail_chars(text: &str, max: usize) -> &str {
+    let count = text.chars().count();
+    if count <= max {
+        return text;
+    }
+    let start = text
+        .char_indices()
+        .nth(count - max)
+        .map_or(0, |(index, _)| index);
+    &text[start..]
+}
+
+/// Parse a reviewer reply. `None` when no `WINNER:` line is present.
+pub fn parse_review_reply(reply: &str) -> Option<(Winner, String)> {
+    let mut winner = None;
+    let mut reason = String::new();
+    for line in reply.lines().map(|l| l.trim().trim_matches('*').trim()) {
+        let upper = line.to_uppercase();
+        if winner.is_none()
+            && let Some(value) = upper.strip_prefix("WINNER:")
+        {
+            winner = match value.trim().trim_matches(|c| c == '`' || c == '.') {
+                "A" => Some(Winner::A),
+                "B" => Some(Winner::B),
+                "TIE" => Some(Winner::Tie),
+                _ => None,
+            };
+        } else if reason.is_empty() && upper.starts_with("REASON:") {
+            reason = line["REASON:".len()..].trim().to_string();
+        }
+    }
+    winner.map(|w| (w, reason))
+}
+
+/// Decide the experiment. `review` is the parsed reviewer reply, consulted
+/// only when both runs succeeded.
+pub fn decide(
+    a: &VariantOutcome,
+    b: &VariantOutcome,
+    review: Option<(Winner, String)>,
+) -> AbVerdict {
+    match (a.success, b.success) {
+        (true, false) => AbVerdict {
+            winner: Winner::A,
+            decided_by: DecidedBy::Outcome,
+            reason: "B failed".to_string(),
+        },
+        (false, true) => AbVerdict {
+            winner: Winner::B,
+            decided_by: DecidedBy::Outcome,
+            reason: "A failed".to_string(),
+        },
+        (false, false) => AbVerdict {
+            winner: Winner::Tie,
+            decided_by: DecidedBy::Outcome,
+            reason: "both runs failed".to_string(),
+        },
+        (true, true) => match review {
+            Some((winner, reason)) => AbVerdict {
+                winner,
+                decided_by: DecidedBy::Review,
+                reason,
+            },
+            None => {
+                let winner = match a.duration_ms.cmp(&b.duration_ms) {
+                    std::cmp::Ordering::Less => Winner::A,
+                    std::cmp::Ordering::Greater => Winner::B,
+                    std::cmp::Ordering::Equal => Winner::Tie,
+                };
+                AbVerdict {
+                    winner,
+                    decided_by: DecidedBy::Duration,
+                    reason: "both succeeded without a review; faster run wins".to_string(),
+                }
+            }
+        },
+    }
+}
+
+/// One completed experiment, as stored in the experiments file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentRecord {
+    pub id: String,
+    pub task: String,
+    pub created_at: DateTime<Utc>,
+    pub a: VariantOutcome,
+    pub b: VariantOutcome,
+    pub verdict: AbVerdict,
+}
+
+impl ExperimentRecord {
+    /// Configuration key of the winning side, `None` for a tie.
+    pub fn winning_config(&self) -> Option<&str> {
+        match self.verdict.winner {
+            Winner::A => Some(&self.a.config),
+            Winner::B => Some(&self.b.config),
+            Winner::Tie => None,
+        }
+    }
+}
+
+/// Append `record` to the project's experiments file.
+pub async fn append_record(repo: &Path, record: &ExperimentRecord) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let dir = experiments_dir(repo);
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(EXPERIMENTS_FILE);
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// Every readable experiment, oldest first. Unparsable lines are skipped.
+pub async fn load_records(repo: &Path) -> Result<Vec<ExperimentRecord>> {
+    let path = experiments_dir(repo).join(EXPERIMENTS_FILE);
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Win/loss record of one configuration across experiments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigTally {
+    pub config: String,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+}
+
+/// Tally every configuration's results, most wins first.
+pub fn tally(records: &[ExperimentRecord]) -> Vec<ConfigTally> {
+    let mut by_config: BTreeMap<&str, ConfigTally> = BTreeMap::new();
+    for record in records {
+        for (side, outcome) in [(Winner::A, &record.a), (Winner::B, &record.b)] {
+            let entry = by_config
+                .entry(&outcome.config)
+                .or_insert_with(|| ConfigTally {
+                    config: outcome.config.clone(),
+                    ..Default::default()
+                });
+            match record.verdict.winner {
+                Winner::Tie => entry.ties += 1,
+                winner if winner == side => entry.wins += 1,
+                _ => entry.losses += 1,
+            }
+        }
+    }
+    let mut tallies: Vec<ConfigTally> = by_config.into_values().collect();
+    tallies.sort_by(|x, y| y.wins.cmp(&x.wins).then(x.losses.cmp(&y.losses)));
+    tallies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(label: &str, spec: &str, success: bool, duration_ms: u64) -> VariantOutcome {
+        let variant = Variant::parse(label, spec).unwrap();
+        VariantOutcome {
+            config: variant.describe("default"),
+            variant,
+            run_id: format!("run-{label}"),
+            success,
+            duration_ms,
+            stages: 3,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_variant_spec() {
+        let v = Variant::parse("A", "model=opus, think=think hard,flow=review-fix").unwrap();
+        assert_eq!(v.model.as_deref(), Some("opus"));
+        assert_eq!(v.think_mode, Some(ThinkMode::ThinkHard));
+        assert_eq!(v.flow.as_deref(), Some("review-fix"));
+        assert_eq!(
+            v.describe("default"),
+            "flow=review-fix,model=opus,think=think_hard"
+        );
+        assert!(v.task_text("Fix it").ends_with("\n\nthink hard."));
+
+        assert_eq!(
+            Variant::parse("B", "think=ultrathink").unwrap().think_mode,
+            Some(ThinkMode::UltraThink)
+        );
+        assert!(Variant::parse("A", "temperature=1").is_err());
+        assert!(Variant::parse("A", "think=deeply").is_err());
+        assert!(Variant::parse("A", "model").is_err());
+    }
+
+    #[test]
+    fn test_parse_review_reply() {
+        assert_eq!(
+            parse_review_reply("WINNER: B\nREASON: handles the empty input case."),
+            Some((Winner::B, "handles the empty input case.".to_string()))
+        );
+        assert_eq!(
+            parse_review_reply("**Winner: tie**").map(|(w, _)| w),
+            Some(Winner::Tie)
+        );
+        assert_eq!(parse_review_reply("Both look fine to me."), None);
+    }
+
+    #[test]
+    fn test_decide_prefers_success_then_review() {
+        let ok_a = outcome("A", "model=sonnet", true, 5_000);
+        let ok_b = outcome("B", "model=opus", true, 9_000);
+        let failed_b = outcome("B", "model=opus", false, 1_000);
+
+        let verdict = decide(&ok_a, &failed_b, Some((Winner::B, String::new())));
+        assert_eq!(
+            (verdict.winner, verdict.decided_by),
+            (Winner::A, DecidedBy::Outcome)
+        );
+
+        let verdict = decide(&ok_a, &ok_b, Some((Winner::B, "cleaner".to_string())));
+        assert_eq!(
+            (verdict.winner, verdict.decided_by),
+            (Winner::B, DecidedBy::Review)
+        );
+
+        let verdict = decide(&ok_a, &ok_b, None);
+        assert_eq!(
+            (verdict.winner, verdict.decided_by),
+            (Winner::A, DecidedBy::Duration)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_records_round_trip_and_tally() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let a = outcome("A", "model=sonnet", true, 5_000);
+        let b = outcome("B", "model=opus", true, 9_000);
+        for winner in [Winner::B, Winner::B, Winner::Tie] {
+            let record = ExperimentRecord {
+                id: uuid::Uuid::new_v4().to_string(),
+                task: "Add a health check".to_string(),
+                created_at: Utc::now(),
+                a: a.clone(),
+                b: b.clone(),
+                verdict: AbVerdict {
+                    winner,
+                    decided_by: DecidedBy::Review,
+                    reason: String::new(),
+                },
+            };
+            append_record(dir.path(), &record).await?;
+        }
+
+        let records = load_records(dir.path()).await?;
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].winning_config(), Some("flow=default,model=opus"));
+
+        let tallies = tally(&records);
+        assert_eq!(tallies[0].config, "flow=default,model=opus");
+        assert_eq!(
+            (tallies[0].wins, tallies[0].losses, tallies[0].ties),
+            (2, 0, 1)
+        );
+        assert_eq!(
+            (tallies[1].wins, tallies[1].losses, tallies[1].ties),
+            (0, 2, 1)
+        );
+        Ok(())
+    }
+}
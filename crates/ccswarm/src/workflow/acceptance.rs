@@ -0,0 +1,249 @@
+//! Acceptance criteria for queued tasks.
+//!
+//! A task can say what "done" means in checkable terms: commands that must
+//! pass, files that must exist, endpoints that must answer.
+//!
+//! ```yaml
+//! acceptance:
+//!   criteria:
+//!     - command: cargo test -p api
+//!     - file: docs/api/orders.md
+//!     - endpoint: http://localhost:8080/health
+//!   max_bounces: 2
+//! ```
+//!
+//! The agent sees the criteria in its prompt. When its run reports success,
+//! `queue drain` checks every criterion itself ([`verify`]); a task that
+//! misses one goes back to the agent with what failed, up to `max_bounces`
+//! times, and is marked failed after that.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Longest a criterion command may run.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Longest an endpoint may take to answer.
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Bytes of a failing command's output kept as the failure detail.
+const MAX_DETAIL: usize = 2000;
+
+/// Where a run's verification is kept, inside its run directory.
+pub const ACCEPTANCE_FILE: &str = "acceptance.json";
+
+/// Outcome of an attempt sent back for missing its criteria.
+pub const REJECTED: &str = "rejected";
+
+/// One checkable condition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Criterion {
+    /// A shell command that must exit 0, run from the repository root.
+    Command { command: String },
+    /// A path, relative to the repository root, that must exist.
+    File { file: String },
+    /// A URL that must answer a GET with a 2xx or 3xx status.
+    Endpoint { endpoint: String },
+}
+
+impl std::fmt::Display for Criterion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Command { command } => write!(f, "`{command}` passes"),
+            Self::File { file } => write!(f, "{file} exists"),
+            Self::Endpoint { endpoint } => write!(f, "{endpoint} responds"),
+        }
+    }
+}
+
+/// A task's criteria and how often it may be sent back for missing them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Acceptance {
+    pub criteria: Vec<Criterion>,
+    #[serde(default = "default_max_bounces")]
+    pub max_bounces: u32,
+}
+
+pub fn default_max_bounces() -> u32 {
+    2
+}
+
+impl Acceptance {
+    /// The criteria as a prompt section. After a bounce, `rejection` is what
+    /// the last attempt failed.
+    pub fn prompt(&self, rejection: Option<&str>) -> String {
+        let mut out = "Acceptance criteria (checked automatically when you finish):".to_string();
+        for criterion in &self.criteria {
+            out.push_str(&format!("\n- {criterion}"));
+        }
+        if let Some(rejection) = rejection {
+            out.push_str(&format!(
+                "\n\nThe previous attempt was sent back because it did not meet them:\n{rejection}"
+            ));
+        }
+        out
+    }
+}
+
+/// How one criterion fared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub criterion: Criterion,
+    pub passed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Every criterion checked after one run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verification {
+    pub checked_at: DateTime<Utc>,
+    pub results: Vec<CheckResult>,
+}
+
+impl Verification {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.passed)
+    }
+
+    /// The unmet criteria with their details, one bullet each.
+    pub fn failures(&self) -> String {
+        self.results
+            .iter()
+            .filter(|r| !r.passed)
+            .map(|r| match &r.detail {
+                Some(detail) => format!("- {}: {}", r.criterion, detail),
+                None => format!("- {}", r.criterion),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Keep the verification with the run it checked.
+    pub async fn save(&self, run_dir: &Path) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(run_dir).await?;
+        tokio::fs::write(
+            run_dir.join(ACCEPTANCE_FILE),
+            serde_json::to_string_pretty(self)?,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Check `criteria` against the repository at `repo`.
+pub async fn verify(criteria: &[Criterion], repo: &Path) -> Verification {
+    let mut results = Vec::new();
+    for criterion in criteria {
+        let outcome = match criterion {
+            Criterion::Command { command } => check_command(command, repo, COMMAND_TIMEOUT).await,
+            Criterion::File { file } => {
+                if repo.join(file).exists() {
+                    Ok(())
+                } else {
+                    Err("not found".to_string())
+                }
+            }
+            Criterion::Endpoint { endpoint } => check_endpoint(endpoint, ENDPOINT_TIMEOUT).await,
+        };
+        results.push(CheckResult {
+            criterion: criterion.clone(),
+            passed: outcome.is_ok(),
+            detail: outcome.err(),
+        });
+    }
+    Verification {
+        checked_at: Utc::now(),
+        results,
+    }
+}
+
+/// Run `command` under `sh -c` in `repo`; the error carries the exit status
+/// and the tail of its output.
+pub(crate) async fn check_command(
+    command: &str,
+    repo: &Path,
+    timeout: Duration,
+) -> Result<(), String> {
+    let run = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(repo)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(timeout, run).await {
+        Err(_) => return Err(format!("timed out after {}s", timeout.as_secs())),
+        Ok(Err(e)) => return Err(format!("could not run: {e}")),
+        Ok(Ok(output)) => output,
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    let text = text.trim();
+    let start = text.len().saturating_sub(MAX_DETAIL);
+    let start = (start..=text.len())
+        .find(|i| text.is_char_boundary(*i))
+        .unwrap_or(text.len());
+    let status = match output.status.code() {
+        Some(code) => format!("exit {code}"),
+        None => "killed by a signal".to_string(),
+    };
+    Err(if text.is_empty() {
+        status
+    } else {
+        format!("{status}\n```\n{}\n```", &text[start..])
+    })
+}
+
+/// GET `url`, expecting a 2xx or 3xx status.
+pub(crate) async fn check_endpoint(url: &str, timeout: Duration) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.is_success() || status.is_redirection() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {status}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn unmet_criteria_come_back_with_details() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("present.md"), "x").unwrap();
+        let acceptance: Acceptance = serde_yml::from_str(
+            "criteria:\n  - command: 'true'\n  - command: echo nope >&2; exit 3\n  - file: present.md\n  - file: missing.md\n",
+        )
+        .unwrap();
+        assert_eq!(acceptance.max_bounces, 2);
+
+        let verification = verify(&acceptance.criteria, dir.path()).await;
+        assert!(!verification.passed());
+        let passed: Vec<bool> = verification.results.iter().map(|r| r.passed).collect();
+        assert_eq!(passed, [true, false, true, false]);
+        let failures = verification.failures();
+        assert!(failures.contains("exit 3"));
+        assert!(failures.contains("nope"));
+        assert!(failures.contains("- missing.md exists: not found"));
+
+        let prompt = acceptance.prompt(Some(&failures));
+        assert!(prompt.contains("- `true` passes"));
+        assert!(prompt.contains("sent back"));
+
+        verification.save(dir.path()).await.unwrap();
+        assert!(dir.path().join(ACCEPTANCE_FILE).is_file());
+    }
+}
@@ -0,0 +1,590 @@
+//! Finding the commit that caused a regression with `git bisect`.
+//!
+//! A regression task — queued with `ccswarm queue add --regression-since
+//! <good>`, or started directly with `ccswarm bisect` — names a commit where
+//! things still worked. The QA agent then drives `git bisect` between that
+//! commit and `HEAD` in a scratch worktree under `.ccswarm/bisect/`, leaving
+//! the agents' own checkouts alone: at each step its session builds and tests
+//! the commit git picked (exit 0 is good, 125 skips the commit, anything else
+//! is bad) and tells git the verdict, until git names the first bad commit.
+//!
+//! The culprit is pinned on an agent: the one whose branch carries the
+//! commit, or failing that the one the delegation heuristic picks for what
+//! the commit touched. That agent gets a high-priority fix task describing
+//! the culprit and the failure, and the whole bisection is kept as a report
+//! in `.ccswarm/bisect/<id>.json`.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::agent::orchestrator::delegation::{self, DelegationStrategy};
+use crate::git::shell::git;
+use crate::recovery::{Operation, RecoveryJournal};
+
+/// Reports and scratch worktrees, relative to the repository.
+pub const BISECT_DIR: &str = ".ccswarm/bisect";
+/// Label of queue tasks that report a regression.
+pub const REGRESSION_LABEL: &str = "regression";
+/// Label prefix carrying a regression task's last known good commit.
+pub const GOOD_LABEL_PREFIX: &str = "good:";
+/// Agent that runs the bisection unless told otherwise.
+pub const DEFAULT_AGENT: &str = "qa";
+/// Steps allowed before giving up; bisecting 2^64 commits takes 64.
+pub const DEFAULT_MAX_STEPS: usize = 64;
+/// Exit code that marks a commit as untestable, as with `git bisect run`.
+const SKIP_EXIT_CODE: i32 = 125;
+/// Output lines kept per step.
+const OUTPUT_TAIL_LINES: usize = 30;
+
+/// The last known good commit of a regression task, from its labels.
+pub fn regression_since(labels: &[String]) -> Option<&str> {
+    if !labels.iter().any(|label| label == REGRESSION_LABEL) {
+        return None;
+    }
+    labels
+        .iter()
+        .find_map(|label| label.strip_prefix(GOOD_LABEL_PREFIX))
+}
+
+/// The labels that make a queue task a regression task.
+pub fn regression_labels(good: &str) -> Vec<String> {
+    vec![
+        REGRESSION_LABEL.to_string(),
+        format!("{GOOD_LABEL_PREFIX}{good}"),
+    ]
+}
+
+/// What one step made of the commit it tested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl Verdict {
+    fn from_exit_code(code: Option<i32>) -> Self {
+        match code {
+            Some(0) => Self::Good,
+            Some(SKIP_EXIT_CODE) => Self::Skip,
+            _ => Self::Bad,
+        }
+    }
+
+    fn git_arg(self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::Bad => "bad",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BisectStep {
+    pub commit: String,
+    pub verdict: Verdict,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// The command outran the task's command timeout and was stopped; the
+    /// commit counts as bad.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    /// Last lines of the build and test output.
+    pub output_tail: String,
+}
+
+/// The first bad commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Culprit {
+    pub commit: String,
+    pub subject: String,
+    pub author: String,
+    pub files: Vec<String>,
+}
+
+impl Culprit {
+    pub fn short(&self) -> &str {
+        &self.commit[..self.commit.len().min(10)]
+    }
+}
+
+/// The agent a culprit is pinned on, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Responsible {
+    pub agent: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BisectReport {
+    pub id: String,
+    pub good: String,
+    pub bad: String,
+    pub command: String,
+    /// Agent whose session ran the steps.
+    pub agent: String,
+    /// Queue task that asked for the bisection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub steps: Vec<BisectStep>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub culprit: Option<Culprit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub responsible: Option<Responsible>,
+    /// Why no culprit was found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inconclusive: Option<String>,
+    /// Queue task created to fix the culprit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix_task: Option<String>,
+}
+
+impl BisectReport {
+    /// Output of the first step that failed on the culprit, else of the
+    /// last failing step.
+    pub fn failure_output(&self) -> Option<&str> {
+        let bad = |step: &&BisectStep| step.verdict == Verdict::Bad;
+        self.culprit
+            .as_ref()
+            .and_then(|culprit| {
+                self.steps
+                    .iter()
+                    .filter(bad)
+                    .find(|step| step.commit == culprit.commit)
+            })
+            .or_else(|| self.steps.iter().rev().find(bad))
+            .map(|step| step.output_tail.as_str())
+    }
+
+    /// The fix task for the responsible agent; `None` without a culprit.
+    pub fn fix_task_body(&self, original: Option<&str>) -> Option<String> {
+        let culprit = self.culprit.as_ref()?;
+        let mut body = format!(
+            "Fix the regression introduced by commit {} \"{}\" ({}).\n\n\
+             `{}` passes at {} and fails from {} on; git bisect found it in {} step(s).\n",
+            culprit.short(),
+            culprit.subject,
+            culprit.author,
+            self.command,
+            self.good,
+            culprit.short(),
+            self.steps.len()
+        );
+        if let Some(responsible) = &self.responsible {
+            body.push_str(&format!(
+                "\nResponsible agent: {} ({})\n",
+                responsible.agent, responsible.reason
+            ));
+        }
+        if !culprit.files.is_empty() {
+            body.push_str("\nFiles the commit changed:\n");
+            for file in &culprit.files {
+                body.push_str(&format!("- {file}\n"));
+            }
+        }
+        if let Some(output) = self.failure_output().filter(|o| !o.trim().is_empty()) {
+            body.push_str(&format!(
+                "\nFailure output:\n```\n{}\n```\n",
+                output.trim_end()
+            ));
+        }
+        if let Some(original) = original {
+            body.push_str(&format!("\nOriginal report:\n{original}\n"));
+        }
+        Some(body)
+    }
+}
+
+/// What to bisect and how.
+#[derive(Debug, Clone)]
+pub struct BisectOptions {
+    pub good: String,
+    pub bad: String,
+    /// Build and test command run at each step via the session's shell.
+    pub command: String,
+    pub env: BTreeMap<String, String>,
+    pub agent: String,
+    pub max_steps: usize,
+    pub task_id: Option<String>,
+    /// Limit on each step's command, from the task being bisected.
+    pub command_timeout: Option<ai_session::CommandTimeout>,
+}
+
+/// Build and test command for the project: its `build` and `test` checks,
+/// one after the other.
+pub fn default_command(
+    checks: &[super::quality_checks::QualityCheck],
+    repo: &Path,
+) -> Result<(String, BTreeMap<String, String>)> {
+    let checks = super::quality_checks::project_checks(checks, repo)?;
+    let chosen: Vec<_> = ["build", "test"]
+        .iter()
+        .filter_map(|name| checks.iter().find(|c| c.name == *name))
+        .collect();
+    if chosen.is_empty() {
+        bail!("No build or test check to bisect with; pass --command");
+    }
+    let mut env = BTreeMap::new();
+    for check in &chosen {
+        env.extend(check.env.clone());
+        // Steps share the repository's build cache instead of rebuilding
+        // everything in the scratch worktree.
+        if let Some(dir) = check.target_dir(&super::quality_checks::shared_target_dir(repo)) {
+            env.insert("CARGO_TARGET_DIR".into(), dir.display().to_string());
+        }
+    }
+    let command = chosen
+        .iter()
+        .map(|c| c.command.as_str())
+        .collect::<Vec<_>>()
+        .join(" && ");
+    Ok((command, env))
+}
+
+async fn resolve(repo: &Path, rev: &str) -> Result<String> {
+    let sha = git(
+        repo,
+        &["rev-parse", "--verify", &format!("{rev}^{{commit}}")],
+    )
+    .await
+    .with_context(|| format!("'{rev}' is not a commit"))?;
+    Ok(sha.trim().to_string())
+}
+
+/// The first bad commit named in the output of `git bisect good|bad|skip`.
+fn first_bad_commit(output: &str) -> Option<&str> {
+    let line = output
+        .lines()
+        .find(|line| line.contains("is the first bad commit"))?;
+    line.split_whitespace().next()
+}
+
+fn tail(output: &str, lines: usize) -> String {
+    let all: Vec<&str> = output.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+async fn culprit(repo: &Path, commit: &str) -> Result<Culprit> {
+    let meta = git(repo, &["show", "-s", "--format=%s%x00%an <%ae>", commit]).await?;
+    let (subject, author) = meta
+        .trim_end()
+        .split_once('\0')
+        .unwrap_or((meta.trim(), ""));
+    let files = git(
+        repo,
+        &[
+            "diff-tree",
+            "--no-commit-id",
+            "--name-only",
+            "-r",
+            "--root",
+            commit,
+        ],
+    )
+    .await?;
+    Ok(Culprit {
+        commit: commit.to_string(),
+        subject: subject.to_string(),
+        author: author.to_string(),
+        files: files.lines().map(String::from).collect(),
+    })
+}
+
+/// Pin `culprit` on one of `agents` (name to branch): the only one whose
+/// branch contains it, else the agent delegation picks for its subject and
+/// files.
+pub async fn responsible_agent(
+    repo: &Path,
+    culprit: &Culprit,
+    agents: &BTreeMap<String, String>,
+) -> Responsible {
+    let containing = git(
+        repo,
+        &[
+            "branch",
+            "--format=%(refname:short)",
+            "--contains",
+            &culprit.commit,
+        ],
+    )
+    .await
+    .unwrap_or_default();
+    let carriers: Vec<&String> = agents
+        .iter()
+        .filter(|(_, branch)| containing.lines().any(|b| b.trim() == branch.as_str()))
+        .map(|(agent, _)| agent)
+        .collect();
+    if let [agent] = carriers.as_slice() {
+        return Responsible {
+            agent: (*agent).clone(),
+            reason: format!("the commit is on its branch {}", agents[*agent]),
+        };
+    }
+    let described = format!("{}\n{}", culprit.subject, culprit.files.join("\n"));
+    let decision = delegation::delegate(DelegationStrategy::Keyword, &described, None);
+    Responsible {
+        agent: decision.agent,
+        reason: format!("what the commit touched: {}", decision.reasoning),
+    }
+}
+
+/// Bisect in a scratch worktree of `repo`, running each step in a session
+/// of `options.agent`. `on_step` sees each step as it finishes.
+pub async fn run(
+    repo: &Path,
+    options: &BisectOptions,
+    mut on_step: impl FnMut(&BisectStep),
+) -> Result<BisectReport> {
+    let good = resolve(repo, &options.good).await?;
+    let bad = resolve(repo, &options.bad).await?;
+    if git(repo, &["merge-base", "--is-ancestor", &good, &bad])
+        .await
+        .is_err()
+    {
+        bail!("{} is not an ancestor of {}", options.good, options.bad);
+    }
+
+    let id = format!("bisect-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let worktree = repo.join(BISECT_DIR).join(&id);
+    // The scratch worktree lives until the bisect is over; a crash in between
+    // leaves it to crash recovery.
+    let recovery = RecoveryJournal::for_repo(repo);
+    let scratch = Operation::WorktreeCreated {
+        repo: repo.to_path_buf(),
+        path: worktree.clone(),
+    };
+    recovery.begin(scratch.clone()).await?;
+    git(
+        repo,
+        &[
+            "worktree",
+            "add",
+            "--detach",
+            &worktree.to_string_lossy(),
+            &bad,
+        ],
+    )
+    .await?;
+    let started_at = Utc::now();
+    let outcome = bisect_in(&worktree, &good, &bad, options, &mut on_step).await;
+    let _ = git(&worktree, &["bisect", "reset"]).await;
+    if let Err(e) = git(
+        repo,
+        &["worktree", "remove", "--force", &worktree.to_string_lossy()],
+    )
+    .await
+    {
+        tracing::warn!("Failed to remove bisect worktree: {e:#}");
+    } else {
+        recovery.done(&scratch).await;
+    }
+    let (steps, first_bad, inconclusive) = outcome?;
+
+    let culprit = match first_bad {
+        Some(commit) => Some(culprit(repo, &commit).await?),
+        None => None,
+    };
+    Ok(BisectReport {
+        id,
+        good,
+        bad,
+        command: options.command.clone(),
+        agent: options.agent.clone(),
+        task_id: options.task_id.clone(),
+        started_at,
+        finished_at: Utc::now(),
+        steps,
+        culprit,
+        responsible: None,
+        inconclusive,
+        fix_task: None,
+    })
+}
+
+type Bisection = (Vec<BisectStep>, Option<String>, Option<String>);
+
+async fn bisect_in(
+    worktree: &Path,
+    good: &str,
+    bad: &str,
+    options: &BisectOptions,
+    on_step: &mut impl FnMut(&BisectStep),
+) -> Result<Bisection> {
+    let session = ai_session::AISession::new(ai_session::SessionConfig {
+        name: Some(format!("{}-bisect", options.agent)),
+        agent_role: Some(options.agent.clone()),
+        working_directory: worktree.to_path_buf(),
+        environment: options.env.clone().into_iter().collect(),
+        shell: Some("/bin/sh".to_string()),
+        ..Default::default()
+    })
+    .await?;
+
+    let mut output = git(worktree, &["bisect", "start", bad, good]).await?;
+    let mut steps = Vec::new();
+    while steps.len() < options.max_steps {
+        if let Some(commit) = first_bad_commit(&output) {
+            return Ok((steps, Some(commit.to_string()), None));
+        }
+        if output.contains("only 'skip'ped commits left") {
+            return Ok((
+                steps,
+                None,
+                Some("every remaining candidate was skipped".to_string()),
+            ));
+        }
+        let commit = git(worktree, &["rev-parse", "HEAD"])
+            .await?
+            .trim()
+            .to_string();
+        let started = Instant::now();
+        let result = session
+            .execute_command_with_timeout(&options.command, options.command_timeout.as_ref())
+            .await;
+        let record = session.get_command_history().await.pop();
+        let exit_code = record.as_ref().and_then(|record| record.exit_code);
+        let timed_out = record.is_some_and(|record| record.timed_out);
+        let (verdict, text) = match result {
+            Ok(text) => (Verdict::from_exit_code(exit_code), text),
+            Err(e) => (Verdict::Skip, format!("{e:#}")),
+        };
+        let step = BisectStep {
+            commit,
+            verdict,
+            exit_code,
+            duration_ms: started.elapsed().as_millis() as u64,
+            timed_out,
+            output_tail: tail(&text, OUTPUT_TAIL_LINES),
+        };
+        on_step(&step);
+        steps.push(step);
+        output = git(worktree, &["bisect", verdict.git_arg()]).await?;
+    }
+    Ok((
+        steps,
+        None,
+        Some(format!("gave up after {} steps", options.max_steps)),
+    ))
+}
+
+pub fn report_path(repo: &Path, id: &str) -> PathBuf {
+    repo.join(BISECT_DIR).join(format!("{id}.json"))
+}
+
+pub async fn save_report(repo: &Path, report: &BisectReport) -> Result<PathBuf> {
+    let path = report_path(repo, &report.id);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, serde_json::to_string_pretty(report)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_sync(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?}");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Ten commits counting up in `value`; the check breaks at 7.
+    fn counting_repo() -> (tempfile::TempDir, Vec<String>) {
+        let dir = tempfile::tempdir().unwrap();
+        git_sync(dir.path(), &["init", "-q", "-b", "main"]);
+        git_sync(dir.path(), &["config", "user.name", "ccswarm"]);
+        git_sync(dir.path(), &["config", "user.email", "ccswarm@example.com"]);
+        let mut commits = Vec::new();
+        for n in 0..10 {
+            let file = if n == 7 {
+                "src/api/handler.rs"
+            } else {
+                "value"
+            };
+            std::fs::create_dir_all(dir.path().join("src/api")).unwrap();
+            std::fs::write(dir.path().join("value"), n.to_string()).unwrap();
+            if n == 7 {
+                std::fs::write(dir.path().join(file), "// endpoint\n").unwrap();
+            }
+            git_sync(dir.path(), &["add", "."]);
+            git_sync(
+                dir.path(),
+                &["commit", "-qm", &format!("api endpoint step {n}")],
+            );
+            commits.push(git_sync(dir.path(), &["rev-parse", "HEAD"]));
+        }
+        (dir, commits)
+    }
+
+    #[test]
+    fn regression_tasks_carry_their_good_commit() {
+        let labels = regression_labels("v1.2");
+        assert_eq!(regression_since(&labels), Some("v1.2"));
+        assert_eq!(regression_since(&labels[1..]), None);
+        assert_eq!(
+            first_bad_commit("abc123 is the first bad commit\ncommit abc123\n"),
+            Some("abc123")
+        );
+    }
+
+    #[tokio::test]
+    async fn bisect_names_the_culprit_and_the_agent_to_fix_it() {
+        let (dir, commits) = counting_repo();
+        let options = BisectOptions {
+            good: commits[0].clone(),
+            bad: "HEAD".to_string(),
+            command: "test \"$(cat value)\" -lt 7".to_string(),
+            env: BTreeMap::new(),
+            agent: DEFAULT_AGENT.to_string(),
+            max_steps: DEFAULT_MAX_STEPS,
+            task_id: Some("q-1".to_string()),
+            command_timeout: None,
+        };
+        let mut seen = 0;
+        let mut report = run(dir.path(), &options, |_| seen += 1).await.unwrap();
+
+        let culprit = report.culprit.clone().expect("culprit");
+        assert_eq!(culprit.commit, commits[7]);
+        assert_eq!(culprit.subject, "api endpoint step 7");
+        assert!(culprit.files.contains(&"src/api/handler.rs".to_string()));
+        assert_eq!(seen, report.steps.len());
+        assert!(report.steps.len() <= 4, "{} steps", report.steps.len());
+        assert!(!dir.path().join(BISECT_DIR).join(&report.id).exists());
+        assert_eq!(
+            git_sync(dir.path(), &["worktree", "list"]).lines().count(),
+            1
+        );
+
+        // No agent branch carries it, so delegation decides from the subject.
+        let agents = BTreeMap::from([("qa".to_string(), "agent/qa".to_string())]);
+        report.responsible = Some(responsible_agent(dir.path(), &culprit, &agents).await);
+        assert_eq!(report.responsible.as_ref().unwrap().agent, "backend");
+        git_sync(dir.path(), &["branch", "agent/qa", &commits[8]]);
+        assert_eq!(
+            responsible_agent(dir.path(), &culprit, &agents).await.agent,
+            "qa"
+        );
+
+        let body = report.fix_task_body(Some("Login broke")).unwrap();
+        assert!(body.contains(culprit.short()));
+        assert!(body.contains("src/api/handler.rs"));
+        assert!(body.contains("Original report:\nLogin broke"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Structured blockers: an agent's way of saying it cannot go on.
+//!
+//! Editing stages are told to end their reply with a `BLOCKED={...}` line
+//! instead of guessing when something outside their control stops them: a
+//! credential they don't have, another task that has to land first, or a
+//! requirement that can be read more than one way. The engine stops the
+//! flow at that stage and records the [`Blocker`] on the run's `task_end`
+//! event (`reason: "blocked"`), where `queue drain` picks it up to park the
+//! task as `blocked` rather than failing or retrying it.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Line prefix the agent uses to report a blocker.
+pub const BLOCKED_PREFIX: &str = "BLOCKED=";
+
+/// What kind of thing is in the way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockerKind {
+    /// A secret, token or account the agent has no access to.
+    MissingCredential,
+    /// Another task has to finish first.
+    WaitingOnTask,
+    /// The task can be read more than one way and a person has to choose.
+    AmbiguousRequirement,
+    /// A service the task needs is down; set by a failing pre-flight probe.
+    ServiceUnavailable,
+    Other,
+}
+
+impl std::fmt::Display for BlockerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::MissingCredential => "missing credential",
+            Self::WaitingOnTask => "waiting on task",
+            Self::AmbiguousRequirement => "ambiguous requirement",
+            Self::ServiceUnavailable => "service unavailable",
+            Self::Other => "other",
+        })
+    }
+}
+
+/// Why a task cannot proceed, and what would unblock it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blocker {
+    pub kind: BlockerKind,
+    /// What is missing or unclear, in the agent's words.
+    pub detail: String,
+    /// Environment variable holding the missing credential; setting it
+    /// unblocks the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+    /// Queue task ID being waited on; its completion unblocks the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// Pre-flight probe that failed; its passing again unblocks the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// Question for a person; the answer unblocks the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub question: Option<String>,
+}
+
+impl Blocker {
+    /// Whether a person has to act: everything except waiting on a named
+    /// task or a service coming back. A missing credential still needs
+    /// someone to provide it.
+    pub fn needs_human(&self) -> bool {
+        match self.kind {
+            BlockerKind::WaitingOnTask => self.task.is_none(),
+            BlockerKind::MissingCredential => true,
+            BlockerKind::ServiceUnavailable => false,
+            BlockerKind::AmbiguousRequirement | BlockerKind::Other => true,
+        }
+    }
+
+    /// The question put to a person.
+    pub fn question_text(&self) -> String {
+        self.question.clone().unwrap_or_else(|| match self.kind {
+            BlockerKind::MissingCredential => match &self.credential {
+                Some(var) => format!("Please provide {var}: {}", self.detail),
+                None => format!("A credential is missing: {}", self.detail),
+            },
+            _ => self.detail.clone(),
+        })
+    }
+
+    /// One line, e.g. `missing credential (STRIPE_KEY): no API key for tests`.
+    pub fn summary(&self) -> String {
+        let on = self
+            .credential
+            .as_deref()
+            .or(self.task.as_deref())
+            .or(self.service.as_deref());
+        match on {
+            Some(on) => format!("{} ({on}): {}", self.kind, self.detail),
+            None => format!("{}: {}", self.kind, self.detail),
+        }
+    }
+}
+
+/// Instructions appended to an editing stage's prompt.
+pub fn instructions() -> String {
+    format!(
+        "## If you are blocked\n\n\
+         If something outside your control stops you from finishing, do not guess \
+         or fake it. Stop and end your reply with exactly one line:\n\
+         {BLOCKED_PREFIX}{{\"kind\": \"missing_credential\" | \"waiting_on_task\" | \
+         \"ambiguous_requirement\" | \"other\", \"detail\": \"...\", \
+         \"credential\": \"ENV_VAR (if a credential)\", \"task\": \"queue task id (if known)\", \
+         \"question\": \"what a person must decide (if any)\"}}"
+    )
+}
+
+/// Extract the last `BLOCKED=` line from a reply; a malformed line yields
+/// `None`.
+pub fn parse_blocker(reply: &str) -> Option<Blocker> {
+    let json = reply
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().trim_matches('`').strip_prefix(BLOCKED_PREFIX))?;
+    let mut blocker: Blocker = serde_json::from_str(json.trim()).ok()?;
+    let tidy = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    blocker.detail = blocker.detail.trim().to_string();
+    blocker.credential = tidy(blocker.credential);
+    blocker.task = tidy(blocker.task);
+    blocker.service = tidy(blocker.service);
+    blocker.question = tidy(blocker.question);
+    Some(blocker)
+}
+
+/// The blocker a run stopped on, read from its `events.ndjson`.
+pub fn from_run(run_dir: &Path) -> Option<Blocker> {
+    let events = std::fs::read_to_string(run_dir.join("events.ndjson")).ok()?;
+    events.lines().rev().find_map(|line| {
+        let event: serde_json::Value = serde_json::from_str(line).ok()?;
+        let metadata = event.get("metadata")?;
+        if metadata.get("reason").and_then(|r| r.as_str()) != Some("blocked") {
+            return None;
+        }
+        serde_json::from_value(metadata.get("blocker")?.clone()).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_blocker_reads_the_last_line_and_tidies_fields() {
+        let reply = "Tried the payment tests.\n\
+            BLOCKED={\"kind\": \"missing_credential\", \"detail\": \" no Stripe key \", \
+            \"credential\": \"STRIPE_KEY\", \"task\": \"\"}\n";
+        let blocker = parse_blocker(reply).unwrap();
+        assert_eq!(blocker.kind, BlockerKind::MissingCredential);
+        assert_eq!(blocker.detail, "no Stripe key");
+        assert_eq!(blocker.task, None);
+        assert_eq!(
+            blocker.summary(),
+            "missing credential (STRIPE_KEY): no Stripe key"
+        );
+        assert_eq!(
+            blocker.question_text(),
+            "Please provide STRIPE_KEY: no Stripe key"
+        );
+        assert!(blocker.needs_human());
+
+        let waiting = parse_blocker(
+            "`BLOCKED={\"kind\": \"waiting_on_task\", \"detail\": \"needs schema\", \"task\": \"q-1\"}`",
+        )
+        .unwrap();
+        assert!(!waiting.needs_human());
+        assert!(parse_blocker("All done.").is_none());
+        assert!(parse_blocker("BLOCKED={\"kind\": \"bored\"}").is_none());
+    }
+}
@@ -0,0 +1,345 @@
+//! Seed persona knowledge from the repository's own documentation.
+//!
+//! `ccswarm context seed` scans the README, CONTRIBUTING guide, architecture
+//! decision records and package manifests, picks the excerpts each persona
+//! cares about, and writes them as knowledge facets named `project-<persona>`
+//! under `.ccswarm/facets/knowledge/`. Stages that do not pin a `knowledge:`
+//! facet pick up their persona's seeded facet automatically (see
+//! [`FacetRegistry::knowledge_for`](super::facets::FacetRegistry::knowledge_for)),
+//! so a fresh swarm starts with project context instead of a blank slate.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::facets::KnowledgeFacet;
+
+/// Prefix of seeded knowledge facet names: `project-<persona>`.
+pub const SEEDED_KNOWLEDGE_PREFIX: &str = "project-";
+
+/// Upper bound on the excerpt text kept per persona, before summarization.
+const MAX_EXCERPT_CHARS: usize = 6000;
+
+/// Upper bound on the text read from any single source file.
+const MAX_SOURCE_CHARS: usize = 20_000;
+
+/// Directories that conventionally hold architecture decision records.
+const ADR_DIRS: &[&str] = &["docs/adr", "docs/adrs", "docs/decisions", "adr", "doc/adr"];
+
+/// Manifests that describe the toolchain and dependencies.
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+    "Gemfile",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "composer.json",
+    "mix.exs",
+];
+
+/// What a source file is, which decides which personas see it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    Readme,
+    Contributing,
+    Adr,
+    Manifest,
+}
+
+impl SourceKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SourceKind::Readme => "readme",
+            SourceKind::Contributing => "contributing",
+            SourceKind::Adr => "adr",
+            SourceKind::Manifest => "manifest",
+        }
+    }
+}
+
+/// One documentation file found in the repository.
+#[derive(Debug, Clone)]
+pub struct SeedSource {
+    pub kind: SourceKind,
+    /// Path relative to the repository root.
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// Collect README, CONTRIBUTING, ADRs and manifests from `repo`. Missing files
+/// are skipped; unreadable ones too, since seeding is best-effort.
+pub async fn discover_sources(repo: &Path) -> Result<Vec<SeedSource>> {
+    let mut sources = Vec::new();
+
+    let mut entries = tokio::fs::read_dir(repo)
+        .await
+        .with_context(|| format!("Failed to read {}", repo.display()))?;
+    let mut top_level = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await.is_ok_and(|t| t.is_file()) {
+            top_level.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    top_level.sort();
+
+    for name in &top_level {
+        let upper = name.to_ascii_uppercase();
+        let kind = if upper.starts_with("README") {
+            SourceKind::Readme
+        } else if upper.starts_with("CONTRIBUTING") {
+            SourceKind::Contributing
+        } else if upper.starts_with("ARCHITECTURE") {
+            SourceKind::Adr
+        } else if MANIFESTS.contains(&name.as_str()) {
+            SourceKind::Manifest
+        } else {
+            continue;
+        };
+        push_source(&mut sources, repo, PathBuf::from(name), kind).await;
+    }
+
+    for dir in ADR_DIRS {
+        let Ok(mut entries) = tokio::fs::read_dir(repo.join(dir)).await else {
+            continue;
+        };
+        let mut records = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "md") {
+                records.push(Path::new(dir).join(entry.file_name()));
+            }
+        }
+        records.sort();
+        for record in records {
+            push_source(&mut sources, repo, record, SourceKind::Adr).await;
+        }
+    }
+
+    Ok(sources)
+}
+
+async fn push_source(sources: &mut Vec<SeedSource>, repo: &Path, path: PathBuf, kind: SourceKind) {
+    match tokio::fs::read_to_string(repo.join(&path)).await {
+        Ok(content) => sources.push(SeedSource {
+            kind,
+            path,
+            content: truncate_chars(&content, MAX_SOURCE_CHARS),
+        }),
+        Err(e) => tracing::debug!("context seed: skipping {}: {}", path.display(), e),
+    }
+}
+
+/// Source kinds and heading keywords that matter to a persona. Unknown
+/// personas get everything.
+fn relevance(persona: &str) -> (&'static [SourceKind], &'static [&'static str]) {
+    use SourceKind::*;
+    match persona {
+        "planner" => (
+            &[Readme, Adr, Manifest],
+            &["architecture", "design", "overview", "structure", "roadmap"],
+        ),
+        "coder" => (
+            &[Readme, Contributing, Manifest],
+            &[
+                "build", "install", "test", "develop", "style", "usage", "setup",
+            ],
+        ),
+        "reviewer" | "ai-antipattern-reviewer" => (
+            &[Contributing, Adr],
+            &[
+                "review",
+                "style",
+                "convention",
+                "test",
+                "guideline",
+                "security",
+            ],
+        ),
+        "researcher" => (
+            &[Readme, Adr, Manifest],
+            &["overview", "architecture", "dependencies", "background"],
+        ),
+        "supervisor" => (
+            &[Readme, Contributing],
+            &["overview", "release", "test", "contribut", "workflow"],
+        ),
+        _ => (&[Readme, Contributing, Adr, Manifest], &[]),
+    }
+}
+
+/// Role-relevant excerpts for `persona`, capped at [`MAX_EXCERPT_CHARS`].
+///
+/// Markdown sources are cut into heading sections and only sections whose
+/// heading matches one of the persona's keywords are kept (the intro before
+/// the first heading always is). ADRs and manifests are kept whole.
+pub fn excerpts_for(persona: &str, sources: &[SeedSource]) -> Vec<(PathBuf, String)> {
+    let (kinds, keywords) = relevance(persona);
+    let mut budget = MAX_EXCERPT_CHARS;
+    let mut out = Vec::new();
+
+    for source in sources.iter().filter(|s| kinds.contains(&s.kind)) {
+        if budget == 0 {
+            break;
+        }
+        let text = match source.kind {
+            SourceKind::Readme | SourceKind::Contributing if !keywords.is_empty() => {
+                markdown_sections(&source.content)
+                    .into_iter()
+                    .filter(|(heading, _)| {
+                        heading.as_deref().is_none_or(|heading| {
+                            let heading = heading.to_lowercase();
+                            keywords.iter().any(|k| heading.contains(k))
+                        })
+                    })
+                    .map(|(_, body)| body)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            _ => source.content.clone(),
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let excerpt = truncate_chars(text, budget);
+        budget = budget.saturating_sub(excerpt.chars().count());
+        out.push((source.path.clone(), excerpt));
+    }
+    out
+}
+
+/// Split markdown into `(heading, section text)` pairs. The text before the
+/// first heading has no heading.
+fn markdown_sections(content: &str) -> Vec<(Option<String>, String)> {
+    let mut sections: Vec<(Option<String>, String)> = vec![(None, String::new())];
+    let mut in_fence = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if !in_fence && line.starts_with('#') {
+            let heading = line.trim_start_matches('#').trim().to_string();
+            sections.push((Some(heading), String::new()));
+        }
+        if let Some((_, body)) = sections.last_mut() {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    sections
+}
+
+/// Prompt asking the provider to condense `excerpts` for `persona`.
+pub fn summary_prompt(persona: &str, role: &str, excerpts: &[(PathBuf, String)]) -> String {
+    let mut prompt = format!(
+        "You are preparing background knowledge for the `{persona}` agent ({role}) \
+         of a multi-agent coding swarm working in this repository.\n\n\
+         Summarize the documentation excerpts below into at most 25 bullet points \
+         that this agent needs before its first task: conventions, commands, \
+         architecture decisions and constraints. Quote commands exactly. \
+         Reply with the bullet list only.\n"
+    );
+    for (path, text) in excerpts {
+        prompt.push_str(&format!("\n--- {} ---\n{}\n", path.display(), text));
+    }
+    prompt
+}
+
+/// Knowledge facet holding the seeded context for `persona`.
+pub fn seeded_facet(
+    persona: &str,
+    content: String,
+    excerpts: &[(PathBuf, String)],
+) -> KnowledgeFacet {
+    KnowledgeFacet {
+        name: format!("{SEEDED_KNOWLEDGE_PREFIX}{persona}"),
+        context: Vec::new(),
+        references: excerpts
+            .iter()
+            .map(|(path, _)| path.display().to_string())
+            .collect(),
+        content,
+    }
+}
+
+/// Raw excerpts joined into facet content, used when summarization is off or
+/// the provider call fails.
+pub fn excerpt_content(excerpts: &[(PathBuf, String)]) -> String {
+    excerpts
+        .iter()
+        .map(|(path, text)| format!("### {}\n\n{}", path.display(), text))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}\n[truncated]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn write(repo: &Path, path: &str, content: &str) {
+        let path = repo.join(path);
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        tokio::fs::write(path, content).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_discover_sources_finds_docs_adrs_and_manifests() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        write(dir.path(), "README.md", "# App").await;
+        write(dir.path(), "CONTRIBUTING.md", "# Contributing").await;
+        write(dir.path(), "Cargo.toml", "[package]").await;
+        write(dir.path(), "docs/adr/0001-use-sqlite.md", "# Use SQLite").await;
+        write(dir.path(), "notes.txt", "ignored").await;
+
+        let sources = discover_sources(dir.path()).await?;
+        let found: Vec<_> = sources
+            .iter()
+            .map(|s| (s.kind, s.path.display().to_string()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (SourceKind::Contributing, "CONTRIBUTING.md".to_string()),
+                (SourceKind::Manifest, "Cargo.toml".to_string()),
+                (SourceKind::Readme, "README.md".to_string()),
+                (SourceKind::Adr, "docs/adr/0001-use-sqlite.md".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_excerpts_keep_only_role_relevant_sections() {
+        let readme = SeedSource {
+            kind: SourceKind::Readme,
+            path: PathBuf::from("README.md"),
+            content: "Intro line\n## Testing\nrun `make test`\n## License\nMIT\n".to_string(),
+        };
+        let coder = excerpts_for("coder", std::slice::from_ref(&readme));
+        assert_eq!(coder.len(), 1);
+        assert!(coder[0].1.contains("make test"));
+        assert!(coder[0].1.contains("Intro line"));
+        assert!(!coder[0].1.contains("MIT"));
+
+        // Reviewers don't read the README at all.
+        assert!(excerpts_for("reviewer", &[readme]).is_empty());
+    }
+
+    #[test]
+    fn test_headings_inside_code_fences_do_not_split_sections() {
+        let sections = markdown_sections("## Build\n```sh\n# comment\ncargo build\n```\n");
+        assert_eq!(sections.len(), 2);
+        assert!(sections[1].1.contains("cargo build"));
+    }
+}
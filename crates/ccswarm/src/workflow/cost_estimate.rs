@@ -0,0 +1,503 @@
+//! Token and cost estimates for tasks before they run.
+//!
+//! An estimate starts from past tasks with similar descriptions (compared
+//! with [`crate::utils::embedding`]) and what their runs actually used, read
+//! from `.ccswarm/runs/<run-id>/events.ndjson`. With no similar history it
+//! falls back to a heuristic on the description's length. Tokens are priced
+//! with the target model's rates: a built-in table of list prices, overridden
+//! by the `cost` key of the ccswarm config:
+//!
+//! ```json
+//! "cost": {
+//!   "default_model": "sonnet",
+//!   "max_task_usd": 2.0,
+//!   "prices": [{ "model": "opus", "input_per_mtok": 15.0, "output_per_mtok": 75.0 }]
+//! }
+//! ```
+//!
+//! Every estimate made before a queued task runs is appended to
+//! [`ESTIMATES_LOG`], so `ccswarm cost --estimates` can compare it with the
+//! run's actual usage afterwards.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::agent::prompt_builder::estimate_tokens;
+use crate::utils::embedding::embed;
+
+/// Journal of estimates made before dispatch, relative to the repo root.
+pub const ESTIMATES_LOG: &str = ".ccswarm/cost_estimates.ndjson";
+
+/// Similarity at or above which a past task informs an estimate.
+const MIN_SIMILARITY: f32 = 0.6;
+/// Most similar past tasks an estimate is drawn from.
+const MAX_SAMPLES: usize = 5;
+
+/// USD per million tokens for models whose name contains `model`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub model: String,
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrice {
+    fn new(model: &str, input_per_mtok: f64, output_per_mtok: f64) -> Self {
+        Self {
+            model: model.to_string(),
+            input_per_mtok,
+            output_per_mtok,
+        }
+    }
+
+    pub fn cost(&self, tokens_in: u64, tokens_out: u64) -> f64 {
+        (tokens_in as f64 * self.input_per_mtok + tokens_out as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// List prices, matched in order; the first entry is also the fallback.
+fn builtin_prices() -> Vec<ModelPrice> {
+    vec![
+        ModelPrice::new("sonnet", 3.0, 15.0),
+        ModelPrice::new("opus", 15.0, 75.0),
+        ModelPrice::new("haiku", 1.0, 5.0),
+        ModelPrice::new("gpt-5", 1.25, 10.0),
+        ModelPrice::new("codex", 1.25, 10.0),
+    ]
+}
+
+/// Cost settings: model prices and the per-task ceiling.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CostConfig {
+    /// Model assumed when a task or agent does not name one.
+    pub default_model: String,
+    /// Queued tasks estimated above this many USD are not dispatched, and
+    /// runs that are dispatched stop once their tokens would cost more.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_task_usd: Option<f64>,
+    /// Prices tried before the built-in table.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub prices: Vec<ModelPrice>,
+}
+
+impl Default for CostConfig {
+    fn default() -> Self {
+        Self {
+            default_model: "sonnet".to_string(),
+            max_task_usd: None,
+            prices: Vec::new(),
+        }
+    }
+}
+
+impl CostConfig {
+    /// Price for `model`, matched case-insensitively by substring.
+    pub fn price(&self, model: &str) -> ModelPrice {
+        let model = model.to_lowercase();
+        let builtin = builtin_prices();
+        self.prices
+            .iter()
+            .chain(&builtin)
+            .find(|price| model.contains(&price.model.to_lowercase()))
+            .cloned()
+            .unwrap_or_else(|| builtin[0].clone())
+    }
+}
+
+/// Tokens and cost one run used, summed over its events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    /// Cost the provider reported, when it reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+impl Usage {
+    /// Usage of the run in `run_dir`; `None` when it has no event log.
+    pub fn of_run(run_dir: &Path) -> Result<Option<Self>> {
+        let path = run_dir.join("events.ndjson");
+        let file = match std::fs::File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let mut usage = Self::default();
+        for line in std::io::BufReader::new(file).lines().map_while(Result::ok) {
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) else {
+                continue;
+            };
+            let Some(metadata) = event.get("metadata") else {
+                continue;
+            };
+            usage.tokens_in += metadata
+                .get("tokens_in")
+                .and_then(|n| n.as_u64())
+                .unwrap_or(0);
+            usage.tokens_out += metadata
+                .get("tokens_out")
+                .and_then(|n| n.as_u64())
+                .unwrap_or(0);
+            if let Some(cost) = metadata.get("cost_usd").and_then(|c| c.as_f64()) {
+                *usage.cost_usd.get_or_insert(0.0) += cost;
+            }
+        }
+        Ok(Some(usage))
+    }
+
+    pub fn tokens(&self) -> u64 {
+        self.tokens_in + self.tokens_out
+    }
+
+    /// Reported cost, or the tokens priced at `price`; `None` when the run
+    /// recorded neither.
+    pub fn usd(&self, price: &ModelPrice) -> Option<f64> {
+        self.cost_usd
+            .or_else(|| (self.tokens() > 0).then(|| price.cost(self.tokens_in, self.tokens_out)))
+    }
+}
+
+/// A finished task and what its run used.
+#[derive(Debug, Clone)]
+pub struct PastTask {
+    pub id: String,
+    pub task: String,
+    pub usage: Usage,
+}
+
+/// What an estimate was based on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EstimateBasis {
+    /// Similar past tasks, by id.
+    History { similar: Vec<String> },
+    /// The description's length alone.
+    Description,
+}
+
+/// Expected tokens and cost of a task, with a low-high envelope.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostEstimate {
+    pub model: String,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub usd: f64,
+    pub low_usd: f64,
+    pub high_usd: f64,
+    pub basis: EstimateBasis,
+}
+
+impl CostEstimate {
+    /// Token cap that keeps a run of this task under `ceiling_usd`, at the
+    /// estimate's input/output mix.
+    pub fn token_cap(&self, ceiling_usd: f64, config: &CostConfig) -> u64 {
+        let price = config.price(&self.model);
+        let tokens = (self.tokens_in + self.tokens_out).max(1);
+        let per_token = price.cost(self.tokens_in, self.tokens_out) / tokens as f64;
+        if per_token <= 0.0 {
+            return u64::MAX;
+        }
+        (ceiling_usd / per_token) as u64
+    }
+
+    /// e.g. `~$0.42 ($0.21–$1.05), ~48k in / 6k out on sonnet, from 3 similar tasks`.
+    pub fn describe(&self) -> String {
+        format!(
+            "~${:.2} (${:.2}–${:.2}), ~{} in / {} out on {}, {}",
+            self.usd,
+            self.low_usd,
+            self.high_usd,
+            kilo(self.tokens_in),
+            kilo(self.tokens_out),
+            self.model,
+            match &self.basis {
+                EstimateBasis::History { similar } => format!(
+                    "from {} similar task{}",
+                    similar.len(),
+                    if similar.len() == 1 { "" } else { "s" }
+                ),
+                EstimateBasis::Description => "from the description alone".to_string(),
+            }
+        )
+    }
+}
+
+fn kilo(tokens: u64) -> String {
+    if tokens >= 1000 {
+        format!("{}k", (tokens + 500) / 1000)
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// Estimate what running `task` on `model` will cost.
+pub fn estimate(
+    task: &str,
+    model: &str,
+    history: &[PastTask],
+    config: &CostConfig,
+) -> CostEstimate {
+    let price = config.price(model);
+    let target = embed(task);
+    let mut similar: Vec<(f32, &PastTask, f64)> = history
+        .iter()
+        .filter_map(|past| {
+            let similarity = target.cosine(&embed(&past.task));
+            let usd = past.usage.usd(&price)?;
+            (similarity >= MIN_SIMILARITY).then_some((similarity, past, usd))
+        })
+        .collect();
+    similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+    similar.truncate(MAX_SAMPLES);
+
+    // Heuristic: a fixed context overhead per run plus the work the
+    // description asks for, which grows with how much it says.
+    let description_tokens = estimate_tokens(task) as u64;
+    let mut tokens_in = 40_000 + description_tokens * 50;
+    let mut tokens_out = 5_000 + description_tokens * 10;
+
+    if similar.is_empty() {
+        let usd = price.cost(tokens_in, tokens_out);
+        return CostEstimate {
+            model: model.to_string(),
+            tokens_in,
+            tokens_out,
+            usd,
+            low_usd: usd * 0.5,
+            high_usd: usd * 2.5,
+            basis: EstimateBasis::Description,
+        };
+    }
+
+    let weight: f64 = similar.iter().map(|(s, _, _)| *s as f64).sum();
+    let usd = similar
+        .iter()
+        .map(|(s, _, usd)| *s as f64 * usd)
+        .sum::<f64>()
+        / weight;
+    let with_tokens: Vec<_> = similar
+        .iter()
+        .filter(|(_, past, _)| past.usage.tokens() > 0)
+        .collect();
+    if !with_tokens.is_empty() {
+        let weight: f64 = with_tokens.iter().map(|(s, _, _)| *s as f64).sum();
+        let mean = |field: fn(&Usage) -> u64| {
+            (with_tokens
+                .iter()
+                .map(|(s, past, _)| *s as f64 * field(&past.usage) as f64)
+                .sum::<f64>()
+                / weight) as u64
+        };
+        tokens_in = mean(|u| u.tokens_in);
+        tokens_out = mean(|u| u.tokens_out);
+    }
+    let low = similar
+        .iter()
+        .map(|(_, _, usd)| *usd)
+        .fold(f64::MAX, f64::min);
+    let high = similar.iter().map(|(_, _, usd)| *usd).fold(0.0, f64::max);
+    CostEstimate {
+        model: model.to_string(),
+        tokens_in,
+        tokens_out,
+        usd,
+        low_usd: low.min(usd * 0.75),
+        high_usd: high.max(usd * 1.25),
+        basis: EstimateBasis::History {
+            similar: similar.iter().map(|(_, past, _)| past.id.clone()).collect(),
+        },
+    }
+}
+
+/// Past tasks, given as `(id, description, run id)`, with their runs' usage.
+/// Tasks whose run left no event log are skipped.
+pub fn past_tasks<'a>(
+    repo: &Path,
+    finished: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>,
+) -> Vec<PastTask> {
+    finished
+        .into_iter()
+        .filter_map(|(id, task, run_id)| {
+            let usage = Usage::of_run(&repo.join(".ccswarm/runs").join(run_id)).ok()??;
+            Some(PastTask {
+                id: id.to_string(),
+                task: task.to_string(),
+                usage,
+            })
+        })
+        .collect()
+}
+
+/// An estimate made before a run, as journaled in [`ESTIMATES_LOG`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateRecord {
+    pub run_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    pub estimate: CostEstimate,
+    pub recorded_at: DateTime<Utc>,
+}
+
+pub async fn record(repo: &Path, record: &EstimateRecord) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+    let path = repo.join(ESTIMATES_LOG);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// An estimate next to what the run actually used.
+#[derive(Debug, Clone, Serialize)]
+pub struct Reconciled {
+    pub record: EstimateRecord,
+    /// `None` while the run has no event log.
+    pub actual: Option<Usage>,
+    pub actual_usd: Option<f64>,
+}
+
+impl Reconciled {
+    /// Actual cost over estimated cost.
+    pub fn ratio(&self) -> Option<f64> {
+        let actual = self.actual_usd?;
+        (self.record.estimate.usd > 0.0).then(|| actual / self.record.estimate.usd)
+    }
+
+    /// Whether the actual cost fell inside the estimate's envelope.
+    pub fn within_envelope(&self) -> Option<bool> {
+        let actual = self.actual_usd?;
+        let estimate = &self.record.estimate;
+        Some(actual >= estimate.low_usd && actual <= estimate.high_usd)
+    }
+}
+
+/// Every journaled estimate paired with its run's actual usage.
+pub fn reconcile(repo: &Path, config: &CostConfig) -> Result<Vec<Reconciled>> {
+    let path = repo.join(ESTIMATES_LOG);
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<EstimateRecord>(line).ok())
+        .map(|record| {
+            let actual = Usage::of_run(&repo.join(".ccswarm/runs").join(&record.run_id))?;
+            let price = config.price(&record.estimate.model);
+            let actual_usd = actual.and_then(|usage| usage.usd(&price));
+            Ok(Reconciled {
+                record,
+                actual,
+                actual_usd,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn past(id: &str, task: &str, tokens_in: u64, tokens_out: u64) -> PastTask {
+        PastTask {
+            id: id.to_string(),
+            task: task.to_string(),
+            usage: Usage {
+                tokens_in,
+                tokens_out,
+                cost_usd: None,
+            },
+        }
+    }
+
+    #[test]
+    fn estimates_follow_similar_history_and_price_the_model() {
+        let config = CostConfig::default();
+        assert_eq!(config.price("claude-opus-4").model, "opus");
+        assert_eq!(config.price("mystery-model").model, "sonnet");
+
+        let history = vec![
+            past(
+                "q-1",
+                "Add a login form to the settings page",
+                100_000,
+                10_000,
+            ),
+            past(
+                "q-2",
+                "Add a login form to the admin settings page",
+                120_000,
+                12_000,
+            ),
+            past("q-3", "Upgrade the database driver", 900_000, 90_000),
+        ];
+        let estimate = estimate(
+            "Add a login form to the user settings page",
+            "sonnet",
+            &history,
+            &config,
+        );
+        let EstimateBasis::History { similar } = &estimate.basis else {
+            panic!("expected a history-based estimate: {estimate:?}");
+        };
+        assert!(similar.contains(&"q-1".to_string()) && !similar.contains(&"q-3".to_string()));
+        assert!((100_000..=120_000).contains(&estimate.tokens_in));
+        assert!(estimate.low_usd <= estimate.usd && estimate.usd <= estimate.high_usd);
+        // 110k in and 11k out on sonnet is about $0.50.
+        assert!((estimate.usd - 0.495).abs() < 0.05, "{}", estimate.usd);
+        assert!(estimate.token_cap(1.0, &config) > estimate.tokens_in + estimate.tokens_out);
+
+        let fresh = super::estimate("Write a haiku about Rust", "opus", &history, &config);
+        assert_eq!(fresh.basis, EstimateBasis::Description);
+        assert!(fresh.usd > 0.0 && fresh.describe().contains("on opus"));
+    }
+
+    #[tokio::test]
+    async fn reconcile_pairs_estimates_with_run_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CostConfig::default();
+        let run_dir = dir.path().join(".ccswarm/runs/run-1");
+        std::fs::create_dir_all(&run_dir).unwrap();
+        std::fs::write(
+            run_dir.join("events.ndjson"),
+            "{\"metadata\":{\"tokens_in\":50000,\"tokens_out\":5000}}\n\
+             {\"metadata\":{\"cost_usd\":0.4}}\n",
+        )
+        .unwrap();
+        let estimate = estimate("Fix the flaky test", "sonnet", &[], &config);
+        record(
+            dir.path(),
+            &EstimateRecord {
+                run_id: "run-1".to_string(),
+                task_id: Some("q-1".to_string()),
+                estimate: estimate.clone(),
+                recorded_at: Utc::now(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let reconciled = reconcile(dir.path(), &config).unwrap();
+        assert_eq!(reconciled.len(), 1);
+        assert_eq!(reconciled[0].actual.unwrap().tokens(), 55_000);
+        assert_eq!(reconciled[0].actual_usd, Some(0.4));
+        assert!((reconciled[0].ratio().unwrap() - 0.4 / estimate.usd).abs() < 1e-9);
+    }
+}
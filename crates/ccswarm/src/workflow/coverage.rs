@@ -0,0 +1,654 @@
+//! Line coverage collection and thresholds for the `coverage` gate.
+//!
+//! Coverage comes from one of two Cargo tools, each behind a small adapter
+//! that knows the command to run and how to read its JSON report:
+//! `cargo llvm-cov` (preferred) and `cargo tarpaulin`. Per-file line counts
+//! are rolled up into workspace packages using `cargo metadata`, checked
+//! against a default minimum and per-package minimums, and compared with
+//! the last passing run recorded in `.ccswarm/coverage/history.jsonl` so a
+//! failure can point at the files whose coverage fell the most.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// History file, relative to the main worktree.
+pub const HISTORY_FILE: &str = ".ccswarm/coverage/history.jsonl";
+
+/// Snapshots kept in the history file.
+const HISTORY_LIMIT: usize = 50;
+
+/// Where the tools write their reports, relative to the working directory.
+const REPORT_DIR: &str = "target/ccswarm-coverage";
+
+/// Which tool collects coverage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageTool {
+    /// `cargo llvm-cov` when installed, otherwise `cargo tarpaulin`.
+    #[default]
+    Auto,
+    LlvmCov,
+    Tarpaulin,
+}
+
+impl std::fmt::Display for CoverageTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Auto => "auto",
+            Self::LlvmCov => "cargo llvm-cov",
+            Self::Tarpaulin => "cargo tarpaulin",
+        })
+    }
+}
+
+/// Covered and coverable line counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lines {
+    pub covered: u64,
+    pub coverable: u64,
+}
+
+impl Lines {
+    /// Percentage of lines covered; 100 when nothing is coverable.
+    pub fn percent(&self) -> f64 {
+        if self.coverable == 0 {
+            100.0
+        } else {
+            self.covered as f64 * 100.0 / self.coverable as f64
+        }
+    }
+
+    fn add(&mut self, other: Lines) {
+        self.covered += other.covered;
+        self.coverable += other.coverable;
+    }
+}
+
+/// Coverage of one source file, its path relative to the working directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileCoverage {
+    pub path: PathBuf,
+    pub lines: Lines,
+}
+
+/// Per-file coverage from one tool run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub files: Vec<FileCoverage>,
+}
+
+impl CoverageReport {
+    pub fn total(&self) -> Lines {
+        let mut total = Lines::default();
+        for file in &self.files {
+            total.add(file.lines);
+        }
+        total
+    }
+
+    /// Line counts per package. A file belongs to the package with the
+    /// deepest directory containing it; files outside every package are
+    /// left out.
+    pub fn by_package(&self, packages: &[Package]) -> BTreeMap<String, Lines> {
+        let mut totals: BTreeMap<String, Lines> = BTreeMap::new();
+        for file in &self.files {
+            let owner = packages
+                .iter()
+                .filter(|package| file.path.starts_with(&package.dir))
+                .max_by_key(|package| package.dir.components().count());
+            if let Some(package) = owner {
+                totals
+                    .entry(package.name.clone())
+                    .or_default()
+                    .add(file.lines);
+            }
+        }
+        totals
+    }
+}
+
+/// A workspace package and its directory relative to the working directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+/// Coverage of one passing run, as stored in the history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageSnapshot {
+    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub total: Lines,
+    #[serde(default)]
+    pub packages: BTreeMap<String, Lines>,
+    /// Percent covered per file.
+    #[serde(default)]
+    pub files: BTreeMap<PathBuf, f64>,
+}
+
+impl CoverageSnapshot {
+    pub fn new(report: &CoverageReport, packages: &[Package], commit: Option<String>) -> Self {
+        Self {
+            at: Utc::now(),
+            commit,
+            total: report.total(),
+            packages: report.by_package(packages),
+            files: report
+                .files
+                .iter()
+                .map(|file| (file.path.clone(), file.lines.percent()))
+                .collect(),
+        }
+    }
+}
+
+/// Minimums a report is checked against.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Thresholds {
+    /// Minimum percent for the whole workspace and for every package
+    /// without its own entry in `packages`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_percent: Option<f64>,
+    /// Minimum percent per package name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub packages: BTreeMap<String, f64>,
+    /// Largest fall in total coverage, in percentage points, allowed since
+    /// the last passing run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_drop: Option<f64>,
+}
+
+/// A file whose coverage fell since the previous snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDrop {
+    pub path: PathBuf,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl FileDrop {
+    pub fn points(&self) -> f64 {
+        self.before - self.after
+    }
+}
+
+/// Outcome of checking a report.
+#[derive(Debug, Clone, Default)]
+pub struct Evaluation {
+    /// One line per threshold that was missed; empty when the gate passes.
+    pub violations: Vec<String>,
+    /// Files whose coverage fell, largest fall first.
+    pub drops: Vec<FileDrop>,
+}
+
+impl Evaluation {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Check `snapshot` against `thresholds` and compare it with `previous`.
+pub fn evaluate(
+    snapshot: &CoverageSnapshot,
+    thresholds: &Thresholds,
+    previous: Option<&CoverageSnapshot>,
+) -> Evaluation {
+    let mut violations = Vec::new();
+    if let Some(min) = thresholds.min_percent
+        && snapshot.total.percent() < min
+    {
+        violations.push(format!(
+            "workspace: {:.1}% < {min:.1}%",
+            snapshot.total.percent()
+        ));
+    }
+    for (name, lines) in &snapshot.packages {
+        let Some(min) = thresholds
+            .packages
+            .get(name)
+            .copied()
+            .or(thresholds.min_percent)
+        else {
+            continue;
+        };
+        if lines.percent() < min {
+            violations.push(format!(
+                "{name}: {:.1}% < {min:.1}% ({}/{} lines)",
+                lines.percent(),
+                lines.covered,
+                lines.coverable
+            ));
+        }
+    }
+    for name in thresholds.packages.keys() {
+        if !snapshot.packages.contains_key(name) {
+            violations.push(format!("{name}: no coverage data for this package"));
+        }
+    }
+
+    let mut drops = Vec::new();
+    if let Some(previous) = previous {
+        if let Some(max_drop) = thresholds.max_drop {
+            let fell = previous.total.percent() - snapshot.total.percent();
+            if fell > max_drop {
+                violations.push(format!(
+                    "workspace: fell {fell:.1} points since the last passing run \
+                     ({:.1}% -> {:.1}%, at most {max_drop:.1} allowed)",
+                    previous.total.percent(),
+                    snapshot.total.percent()
+                ));
+            }
+        }
+        for (path, after) in &snapshot.files {
+            if let Some(before) = previous.files.get(path)
+                && after < before
+            {
+                drops.push(FileDrop {
+                    path: path.clone(),
+                    before: *before,
+                    after: *after,
+                });
+            }
+        }
+        drops.sort_by(|a, b| b.points().total_cmp(&a.points()));
+    }
+    Evaluation { violations, drops }
+}
+
+/// Run `tool` in `work_dir` and read its report.
+pub async fn collect(work_dir: &Path, tool: CoverageTool) -> Result<CoverageReport> {
+    let tool = match tool {
+        CoverageTool::Auto => detect_tool(work_dir).await?,
+        tool => tool,
+    };
+    let report_dir = work_dir.join(REPORT_DIR);
+    tokio::fs::create_dir_all(&report_dir)
+        .await
+        .with_context(|| format!("Failed to create {}", report_dir.display()))?;
+    let mut command = tokio::process::Command::new("cargo");
+    // The gate's timeout drops this future; the build must not outlive it.
+    command.current_dir(work_dir).kill_on_drop(true);
+    let report_file = if tool == CoverageTool::LlvmCov {
+        let file = report_dir.join("llvm-cov.json");
+        command
+            .args(["llvm-cov", "--workspace", "--json", "--summary-only"])
+            .arg("--output-path")
+            .arg(&file);
+        file
+    } else {
+        command
+            .args(["tarpaulin", "--workspace", "--out", "Json", "--output-dir"])
+            .arg(&report_dir);
+        report_dir.join("tarpaulin-report.json")
+    };
+    let output = command
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {tool}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+        return Err(anyhow!(
+            "{tool} exited with {}:\n{}",
+            output.status.code().unwrap_or(-1),
+            tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+        ));
+    }
+    let raw = tokio::fs::read_to_string(&report_file)
+        .await
+        .with_context(|| format!("{tool} wrote no report at {}", report_file.display()))?;
+    let root = work_dir
+        .canonicalize()
+        .unwrap_or_else(|_| work_dir.to_path_buf());
+    match tool {
+        CoverageTool::LlvmCov => parse_llvm_cov(&raw, &root),
+        _ => parse_tarpaulin(&raw, &root),
+    }
+}
+
+async fn detect_tool(work_dir: &Path) -> Result<CoverageTool> {
+    for (tool, subcommand) in [
+        (CoverageTool::LlvmCov, "llvm-cov"),
+        (CoverageTool::Tarpaulin, "tarpaulin"),
+    ] {
+        let installed = tokio::process::Command::new("cargo")
+            .args([subcommand, "--version"])
+            .current_dir(work_dir)
+            .output()
+            .await
+            .is_ok_and(|output| output.status.success());
+        if installed {
+            return Ok(tool);
+        }
+    }
+    Err(anyhow!(
+        "no coverage tool found; install cargo-llvm-cov or cargo-tarpaulin"
+    ))
+}
+
+/// Read `cargo llvm-cov --json` (the `llvm-cov export` format).
+pub fn parse_llvm_cov(raw: &str, root: &Path) -> Result<CoverageReport> {
+    #[derive(Deserialize)]
+    struct Export {
+        data: Vec<Data>,
+    }
+    #[derive(Deserialize)]
+    struct Data {
+        files: Vec<File>,
+    }
+    #[derive(Deserialize)]
+    struct File {
+        filename: PathBuf,
+        summary: Summary,
+    }
+    #[derive(Deserialize)]
+    struct Summary {
+        lines: Count,
+    }
+    #[derive(Deserialize)]
+    struct Count {
+        count: u64,
+        covered: u64,
+    }
+
+    let export: Export = serde_json::from_str(raw).context("not an llvm-cov JSON export")?;
+    let files = export
+        .data
+        .into_iter()
+        .flat_map(|data| data.files)
+        .filter_map(|file| {
+            Some(FileCoverage {
+                path: relative_to(&file.filename, root)?,
+                lines: Lines {
+                    covered: file.summary.lines.covered,
+                    coverable: file.summary.lines.count,
+                },
+            })
+        })
+        .collect();
+    Ok(CoverageReport { files })
+}
+
+/// Read `cargo tarpaulin --out Json` (`tarpaulin-report.json`).
+pub fn parse_tarpaulin(raw: &str, root: &Path) -> Result<CoverageReport> {
+    #[derive(Deserialize)]
+    struct Report {
+        files: Vec<File>,
+    }
+    #[derive(Deserialize)]
+    struct File {
+        path: FilePath,
+        covered: u64,
+        coverable: u64,
+    }
+    /// Path components in current releases, a plain string in older ones.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FilePath {
+        Components(Vec<String>),
+        Joined(PathBuf),
+    }
+
+    let report: Report = serde_json::from_str(raw).context("not a tarpaulin JSON report")?;
+    let files = report
+        .files
+        .into_iter()
+        .filter_map(|file| {
+            let path = match file.path {
+                FilePath::Components(parts) => parts.iter().collect(),
+                FilePath::Joined(path) => path,
+            };
+            Some(FileCoverage {
+                path: relative_to(&path, root)?,
+                lines: Lines {
+                    covered: file.covered,
+                    coverable: file.coverable,
+                },
+            })
+        })
+        .collect();
+    Ok(CoverageReport { files })
+}
+
+/// `path` relative to `root`; `None` for files outside it (dependencies).
+fn relative_to(path: &Path, root: &Path) -> Option<PathBuf> {
+    if path.is_relative() {
+        return Some(path.to_path_buf());
+    }
+    path.strip_prefix(root).ok().map(Path::to_path_buf)
+}
+
+/// Workspace packages of the Cargo project in `work_dir`.
+pub async fn workspace_packages(work_dir: &Path) -> Result<Vec<Package>> {
+    #[derive(Deserialize)]
+    struct Metadata {
+        packages: Vec<MetadataPackage>,
+    }
+    #[derive(Deserialize)]
+    struct MetadataPackage {
+        name: String,
+        manifest_path: PathBuf,
+    }
+
+    let output = tokio::process::Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(work_dir)
+        .output()
+        .await
+        .context("Failed to run cargo metadata")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let metadata: Metadata =
+        serde_json::from_slice(&output.stdout).context("Failed to parse cargo metadata")?;
+    let root = work_dir
+        .canonicalize()
+        .unwrap_or_else(|_| work_dir.to_path_buf());
+    Ok(metadata
+        .packages
+        .into_iter()
+        .filter_map(|package| {
+            let dir = package.manifest_path.parent()?;
+            Some(Package {
+                name: package.name,
+                dir: relative_to(dir, &root)?,
+            })
+        })
+        .collect())
+}
+
+/// `HEAD` of `work_dir`, if it is a git checkout with commits.
+pub async fn head_commit(work_dir: &Path) -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(work_dir)
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// History file for the repository `work_dir` belongs to. Worktrees of one
+/// repository share the main worktree's history.
+pub async fn history_path(work_dir: &Path) -> PathBuf {
+    main_worktree(work_dir).await.join(HISTORY_FILE)
+}
+
+/// The main worktree of the repository `work_dir` belongs to, else
+/// `work_dir` itself.
+pub async fn main_worktree(work_dir: &Path) -> PathBuf {
+    let common_dir = tokio::process::Command::new("git")
+        .args(["rev-parse", "--path-format=absolute", "--git-common-dir"])
+        .current_dir(work_dir)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()));
+    common_dir
+        .as_deref()
+        .filter(|dir| dir.ends_with(".git"))
+        .and_then(Path::parent)
+        .unwrap_or(work_dir)
+        .to_path_buf()
+}
+
+/// Snapshots in `path`, oldest first. Unreadable lines are skipped.
+pub async fn load_history(path: &Path) -> Vec<CoverageSnapshot> {
+    let Ok(raw) = tokio::fs::read_to_string(path).await else {
+        return Vec::new();
+    };
+    raw.lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Append `snapshot`, keeping the newest [`HISTORY_LIMIT`] entries.
+pub async fn record_snapshot(path: &Path, snapshot: &CoverageSnapshot) -> Result<()> {
+    let mut history = load_history(path).await;
+    history.push(snapshot.clone());
+    let start = history.len().saturating_sub(HISTORY_LIMIT);
+    let mut body = String::new();
+    for entry in &history[start..] {
+        body.push_str(&serde_json::to_string(entry)?);
+        body.push('\n');
+    }
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, body)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packages() -> Vec<Package> {
+        vec![
+            Package {
+                name: "ccswarm".to_string(),
+                dir: PathBuf::from("crates/ccswarm"),
+            },
+            Package {
+                name: "ai-session".to_string(),
+                dir: PathBuf::from("crates/ai-session"),
+            },
+        ]
+    }
+
+    #[test]
+    fn both_report_formats_resolve_to_workspace_files() {
+        let root = Path::new("/work/repo");
+        let llvm = r#"{"type":"llvm.coverage.json.export","version":"2.0.1","data":[{"files":[
+            {"filename":"/work/repo/crates/ccswarm/src/lib.rs","summary":{"lines":{"count":10,"covered":8,"percent":80.0}}},
+            {"filename":"/home/u/.cargo/registry/src/dep/lib.rs","summary":{"lines":{"count":5,"covered":0,"percent":0.0}}}
+        ],"totals":{}}]}"#;
+        let report = parse_llvm_cov(llvm, root).unwrap();
+        assert_eq!(report.files.len(), 1, "dependencies are dropped");
+        assert_eq!(report.files[0].path, Path::new("crates/ccswarm/src/lib.rs"));
+        assert_eq!(report.total().percent(), 80.0);
+
+        let tarpaulin = r#"{"files":[
+            {"path":["/","work","repo","crates","ai-session","src","lib.rs"],"content":"","traces":[],"covered":3,"coverable":4},
+            {"path":"/work/repo/crates/ccswarm/src/main.rs","covered":0,"coverable":0}
+        ],"coverage":75.0,"covered":3,"coverable":4}"#;
+        let report = parse_tarpaulin(tarpaulin, root).unwrap();
+        let by_package = report.by_package(&packages());
+        assert_eq!(by_package["ai-session"].percent(), 75.0);
+        assert_eq!(by_package["ccswarm"].percent(), 100.0, "nothing coverable");
+    }
+
+    #[test]
+    fn evaluation_applies_package_minimums_and_lists_drops() {
+        let report = |core: u64, session: u64| CoverageReport {
+            files: vec![
+                FileCoverage {
+                    path: PathBuf::from("crates/ccswarm/src/a.rs"),
+                    lines: Lines {
+                        covered: core,
+                        coverable: 100,
+                    },
+                },
+                FileCoverage {
+                    path: PathBuf::from("crates/ai-session/src/b.rs"),
+                    lines: Lines {
+                        covered: session,
+                        coverable: 100,
+                    },
+                },
+            ],
+        };
+        let previous = CoverageSnapshot::new(&report(80, 60), &packages(), None);
+        let current = CoverageSnapshot::new(&report(70, 58), &packages(), None);
+        let thresholds = Thresholds {
+            min_percent: Some(50.0),
+            packages: BTreeMap::from([("ccswarm".to_string(), 75.0)]),
+            max_drop: Some(5.0),
+        };
+
+        let evaluation = evaluate(&current, &thresholds, Some(&previous));
+        assert!(!evaluation.passed());
+        assert_eq!(
+            evaluation.violations.len(),
+            2,
+            "{:?}",
+            evaluation.violations
+        );
+        assert!(evaluation.violations[0].starts_with("ccswarm: 70.0% < 75.0%"));
+        assert!(evaluation.violations[1].contains("fell 6.0 points"));
+        let dropped: Vec<&Path> = evaluation.drops.iter().map(|d| d.path.as_path()).collect();
+        assert_eq!(
+            dropped,
+            [
+                Path::new("crates/ccswarm/src/a.rs"),
+                Path::new("crates/ai-session/src/b.rs")
+            ]
+        );
+
+        let relaxed = Thresholds {
+            min_percent: Some(50.0),
+            ..Default::default()
+        };
+        assert!(evaluate(&current, &relaxed, Some(&previous)).passed());
+    }
+
+    #[tokio::test]
+    async fn history_keeps_the_newest_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        for covered in 0..(HISTORY_LIMIT as u64 + 3) {
+            let report = CoverageReport {
+                files: vec![FileCoverage {
+                    path: PathBuf::from("src/lib.rs"),
+                    lines: Lines {
+                        covered,
+                        coverable: 100,
+                    },
+                }],
+            };
+            record_snapshot(&path, &CoverageSnapshot::new(&report, &[], None))
+                .await
+                .unwrap();
+        }
+        let history = load_history(&path).await;
+        assert_eq!(history.len(), HISTORY_LIMIT);
+        assert_eq!(
+            history.last().unwrap().total.covered,
+            HISTORY_LIMIT as u64 + 2
+        );
+        assert_eq!(history_path(dir.path()).await, path, "outside git");
+    }
+}
@@ -279,6 +279,8 @@ mod tests {
             gates: Vec::new(),
             team_leader: None,
             sangha: None,
+            review: None,
+            typed_tools: false,
         }
     }
 
@@ -289,6 +291,7 @@ mod tests {
             max_stages: 10,
             max_stage_visits: 3,
             on_rate_limit: Vec::new(),
+            self_rating: Default::default(),
             initial_movement: initial.to_string(),
             stages,
             variables: HashMap::new(),
@@ -0,0 +1,253 @@
+//! Project doctrine distilled from the swarm's own history.
+//!
+//! `ccswarm context distill` mines completed queue tasks, review comments
+//! (HITL decision reasons and Sangha vote reasons) and settled Sangha
+//! proposals, drafts an updated `DOCTRINE.md`, and submits the draft as a
+//! Sangha proposal of type `doctrine`. A later run adopts the draft once the
+//! proposal has more approving than rejecting votes. The adopted file is
+//! handed to every stage as project instructions
+//! (see [`FlowEngine::set_doctrine`](super::flow::FlowEngine::set_doctrine)).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// The adopted doctrine, at the repository root.
+pub const DOCTRINE_FILE: &str = "DOCTRINE.md";
+
+/// Drafts awaiting a Sangha decision, plus the distillation watermark.
+pub const DOCTRINE_DIR: &str = ".ccswarm/doctrine";
+
+/// `proposal_type` of the Sangha proposals this module creates.
+pub const PROPOSAL_TYPE: &str = "doctrine";
+
+const STATE_FILE: &str = "state.json";
+
+/// Upper bound on the doctrine text placed in a stage prompt.
+const MAX_DOCTRINE_CHARS: usize = 12_000;
+
+/// Where a lesson was mined from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LessonKind {
+    /// A queue task that completed.
+    Task,
+    /// The reason given on a HITL decision or a Sangha vote.
+    Review,
+    /// A Sangha proposal that was approved or rejected.
+    Decision,
+}
+
+impl LessonKind {
+    fn heading(self) -> &'static str {
+        match self {
+            LessonKind::Review => "Review notes",
+            LessonKind::Decision => "Decisions",
+            LessonKind::Task => "Completed work",
+        }
+    }
+}
+
+/// One piece of history that may shape the doctrine.
+#[derive(Debug, Clone, Serialize)]
+pub struct Lesson {
+    pub kind: LessonKind,
+    pub at: DateTime<Utc>,
+    /// Where it came from, e.g. `queue:task-12` or `sangha:prop-1a2b3c4d`.
+    pub source: String,
+    pub text: String,
+}
+
+pub use super::sangha_tally::Verdict;
+
+/// Tally a proposal's votes by its own tally method, ignoring delegations
+/// and vote weights.
+pub fn verdict(proposal: &serde_json::Value) -> Verdict {
+    super::sangha_tally::Electorate::default()
+        .tally(proposal)
+        .verdict
+}
+
+/// When the last distillation ran; only newer history is mined.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DistillState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_distilled_at: Option<DateTime<Utc>>,
+}
+
+impl DistillState {
+    pub async fn load(repo: &Path) -> Result<Self> {
+        let path = repo.join(DOCTRINE_DIR).join(STATE_FILE);
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub async fn save(&self, repo: &Path) -> Result<()> {
+        let dir = repo.join(DOCTRINE_DIR);
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(dir.join(STATE_FILE), serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+/// Draft file submitted with proposal `proposal_id`.
+pub fn draft_path(repo: &Path, proposal_id: &str) -> PathBuf {
+    repo.join(DOCTRINE_DIR).join(format!("{proposal_id}.md"))
+}
+
+/// The adopted doctrine, trimmed for a prompt, if the repository has one.
+pub async fn load(repo: &Path) -> Result<Option<String>> {
+    let path = repo.join(DOCTRINE_FILE);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) if content.trim().is_empty() => Ok(None),
+        Ok(content) => Ok(Some(match content.char_indices().nth(MAX_DOCTRINE_CHARS) {
+            Some((end, _)) => format!("{}\n[truncated]", &content[..end]),
+            None => content,
+        })),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Prompt asking the provider to fold `lessons` into the current doctrine.
+pub fn distill_prompt(current: Option<&str>, lessons: &[Lesson]) -> String {
+    let mut prompt = String::from(
+        "You maintain DOCTRINE.md, the conventions and accepted patterns every agent \
+         of a multi-agent coding swarm reads before working in this repository.\n\n\
+         Rewrite the doctrine so it reflects the history below: add conventions that \
+         review notes and approved decisions establish, drop or amend rules that \
+         rejected decisions overturn, and keep rules that still hold. Completed work \
+         only matters where it shows a repeated pattern. Keep it short and imperative, \
+         grouped under `##` headings. Reply with the full Markdown document only.\n",
+    );
+    prompt.push_str("\n--- Current DOCTRINE.md ---\n");
+    prompt.push_str(current.unwrap_or("(none yet)"));
+    prompt.push('\n');
+    for kind in [LessonKind::Review, LessonKind::Decision, LessonKind::Task] {
+        let lines: Vec<String> = lessons
+            .iter()
+            .filter(|l| l.kind == kind)
+            .map(|l| format!("- [{}] {}", l.source, l.text))
+            .collect();
+        if !lines.is_empty() {
+            prompt.push_str(&format!(
+                "\n--- {} ---\n{}\n",
+                kind.heading(),
+                lines.join("\n")
+            ));
+        }
+    }
+    prompt
+}
+
+/// Draft without a provider: the current doctrine with a dated section of
+/// the new lessons appended, skipping any already written down.
+pub fn draft(current: Option<&str>, lessons: &[Lesson], date: NaiveDate) -> String {
+    let mut out = match current {
+        Some(text) => text.trim_end().to_string(),
+        None => "# Project Doctrine\n\n\
+                 Conventions and accepted patterns for every agent working in this repository."
+            .to_string(),
+    };
+    let known = current.unwrap_or_default().to_lowercase();
+    let mut seen = HashSet::new();
+    let mut sections = Vec::new();
+    for kind in [LessonKind::Review, LessonKind::Decision, LessonKind::Task] {
+        let bullets: Vec<String> = lessons
+            .iter()
+            .filter(|l| l.kind == kind)
+            .map(|l| l.text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|text| {
+                let key = text.to_lowercase();
+                !known.contains(&key) && seen.insert(key)
+            })
+            .map(|text| format!("- {text}"))
+            .collect();
+        if !bullets.is_empty() {
+            sections.push(format!("### {}\n\n{}", kind.heading(), bullets.join("\n")));
+        }
+    }
+    if !sections.is_empty() {
+        out.push_str(&format!(
+            "\n\n## Distilled {}\n\n{}",
+            date.format("%Y-%m-%d"),
+            sections.join("\n\n")
+        ));
+    }
+    out.push('\n');
+    out
+}
+
+/// Proposal text summarizing what a draft was distilled from.
+pub fn proposal_description(lessons: &[Lesson]) -> String {
+    let count = |kind| lessons.iter().filter(|l| l.kind == kind).count();
+    format!(
+        "Update {} from {} review note(s), {} Sangha decision(s) and {} completed task(s). \
+         Approving adopts the draft on the next `ccswarm context distill`.",
+        DOCTRINE_FILE,
+        count(LessonKind::Review),
+        count(LessonKind::Decision),
+        count(LessonKind::Task),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lesson(kind: LessonKind, text: &str) -> Lesson {
+        Lesson {
+            kind,
+            at: Utc::now(),
+            source: "test".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_verdict_needs_a_strict_majority() {
+        let vote = |approve| serde_json::json!({ "approve": approve });
+        assert_eq!(verdict(&serde_json::json!({ "votes": [] })), Verdict::Open);
+        assert_eq!(
+            verdict(&serde_json::json!({ "votes": [vote(true), vote(false)] })),
+            Verdict::Open
+        );
+        assert_eq!(
+            verdict(&serde_json::json!({ "votes": [vote(true), vote(true), vote(false)] })),
+            Verdict::Approved
+        );
+        assert_eq!(
+            verdict(&serde_json::json!({ "votes": [vote(false)] })),
+            Verdict::Rejected
+        );
+    }
+
+    #[test]
+    fn test_draft_appends_only_new_lessons() {
+        let current = "# Project Doctrine\n\n- Use anyhow for errors in handlers.\n";
+        let lessons = [
+            lesson(LessonKind::Review, "Use anyhow for errors in handlers."),
+            lesson(LessonKind::Review, "Add a CHANGELOG entry"),
+            lesson(LessonKind::Review, "add a   changelog entry"),
+            lesson(LessonKind::Decision, "Approved: adopt ratatui for TUIs"),
+        ];
+        let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let text = draft(Some(current), &lessons, date);
+
+        assert!(text.starts_with(current.trim_end()));
+        assert!(text.contains("## Distilled 2026-10-16"));
+        assert_eq!(text.matches("anyhow").count(), 1);
+        assert_eq!(text.to_lowercase().matches("changelog entry").count(), 1);
+        assert!(text.contains("### Decisions\n\n- Approved: adopt ratatui for TUIs"));
+        assert!(!text.contains("### Completed work"));
+
+        // Nothing new leaves the doctrine as it was.
+        assert_eq!(draft(Some(current), &lessons[..1], date), current);
+    }
+}
@@ -263,6 +263,26 @@ impl FacetRegistry {
         self.knowledge.insert(knowledge.name.clone(), knowledge);
     }
 
+    /// Knowledge facet to use for a stage: the one it pins, else the
+    /// `project-<persona>` facet written by `ccswarm context seed`, if any.
+    pub fn knowledge_for<'a>(
+        &'a self,
+        stage_knowledge: Option<&'a str>,
+        persona: Option<&str>,
+    ) -> Option<&'a str> {
+        if stage_knowledge.is_some() {
+            return stage_knowledge;
+        }
+        let seeded = format!(
+            "{}{}",
+            super::context_seed::SEEDED_KNOWLEDGE_PREFIX,
+            persona?
+        );
+        self.knowledge
+            .get_key_value(&seeded)
+            .map(|(name, _)| name.as_str())
+    }
+
     /// Compose a prompt from facet references and an instruction.
     ///
     /// Composition order:
@@ -885,6 +905,28 @@ mod tests {
         assert!(prompt.user.contains("FastAPI"));
     }
 
+    #[test]
+    fn test_knowledge_for_falls_back_to_seeded_persona_facet() {
+        let mut registry = FacetRegistry::new();
+        assert_eq!(registry.knowledge_for(None, Some("coder")), None);
+
+        registry.register_knowledge(KnowledgeFacet {
+            name: "project-coder".to_string(),
+            context: Vec::new(),
+            references: Vec::new(),
+            content: "Run `make test` before committing.".to_string(),
+        });
+        assert_eq!(
+            registry.knowledge_for(None, Some("coder")),
+            Some("project-coder")
+        );
+        assert_eq!(
+            registry.knowledge_for(Some("architecture"), Some("coder")),
+            Some("architecture")
+        );
+        assert_eq!(registry.knowledge_for(None, None), None);
+    }
+
     #[test]
     fn test_persona_yaml_parse() {
         let yaml = r#"
@@ -36,12 +36,15 @@
 //!         next: fix
 //! ```
 
+use super::subagent::SubagentParent;
+use crate::agent::context_pack::{self, ContextItem};
+use crate::agent::{PromptBuilder, Slot};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
-use tracing::{debug, info, warn};
+use tracing::{Instrument, debug, info, warn};
 
 /// A Flow is a complete workflow definition loaded from YAML
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +72,11 @@ pub struct Flow {
     #[serde(default)]
     pub on_rate_limit: Vec<FallbackTarget>,
 
+    /// Self-rating rubric for editing stages and the confidence threshold
+    /// below which team_leader work is reviewed.
+    #[serde(default)]
+    pub self_rating: super::self_rating::SelfRatingSpec,
+
     /// ID of the first stage to execute
     pub initial_movement: String,
 
@@ -214,6 +222,13 @@ pub struct Stage {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub gates: Vec<CommandGate>,
 
+    /// Review by an ephemeral `reviewer` once the gates pass (see
+    /// [`super::review`]): a one-shot readonly session scores the stage's
+    /// diff against a rubric, and a review asking for changes fails the
+    /// stage's quality gate like a failing command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<super::review::ReviewSpec>,
+
     /// Orchestrator-worker decomposition (takt's `team_leader`): a leader
     /// call splits this stage's task into parts at runtime, the parts execute
     /// concurrently as synthesized worker stages, and their outputs aggregate
@@ -227,6 +242,12 @@ pub struct Stage {
     /// exclusive with `parallel`, `call`, and `team_leader`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sangha: Option<super::sangha::SanghaSpec>,
+    /// Give the agent ccswarm's typed tools (`run_command`, `read_file`,
+    /// `write_file`, `search_code`) over MCP instead of the provider's own
+    /// shell and file tools, so every action passes the stage's permission
+    /// and security checks.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub typed_tools: bool,
 }
 
 /// One machine-executed gate command on a stage.
@@ -235,10 +256,67 @@ pub struct CommandGate {
     /// Display name, used in events and failure feedback.
     pub name: String,
     /// Shell command executed via `sh -c` in the stage's working directory.
+    /// Empty when `builtin` is set.
+    #[serde(default)]
     pub command: String,
     /// Per-gate timeout in seconds (default 300).
     #[serde(default = "default_gate_timeout_secs")]
     pub timeout_secs: u64,
+    /// In-process check run instead of `command`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builtin: Option<BuiltinGate>,
+    /// Fail only on issues missing from the quality baseline recorded under
+    /// the gate's name (see [`crate::workflow::quality_baseline`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub baseline: bool,
+}
+
+/// Checks ccswarm runs itself rather than through a shell command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BuiltinGate {
+    /// [`crate::security::owasp`] scan of the files changed in the working
+    /// tree (the whole project outside a git repo). Fails on any finding at
+    /// or above `min_severity`.
+    Owasp {
+        #[serde(default = "default_owasp_min_severity")]
+        min_severity: crate::security::owasp::Severity,
+    },
+    /// Line coverage from `cargo llvm-cov` or `cargo tarpaulin` (see
+    /// [`crate::workflow::coverage`]). Fails when the workspace or a package
+    /// is under its minimum, or total coverage fell more than `max_drop`
+    /// points since the last passing run.
+    Coverage {
+        #[serde(default)]
+        tool: crate::workflow::coverage::CoverageTool,
+        #[serde(flatten)]
+        thresholds: crate::workflow::coverage::Thresholds,
+    },
+    /// The test command across `quality.matrix` from `ccswarm.json` (see
+    /// [`crate::workflow::test_matrix`]). Fails when any cell fails; cells
+    /// use the matrix's own timeout rather than `timeout_secs`.
+    TestMatrix,
+    /// [`crate::workflow::commit_hygiene`] lint of the commits the branch
+    /// adds on top of `base`: giant commits, generated artifacts,
+    /// non-conventional messages and secrets. Fails on any violation; the
+    /// feedback carries a fix for each.
+    CommitHygiene {
+        #[serde(flatten)]
+        rules: crate::workflow::commit_hygiene::HygieneRules,
+    },
+    /// The checks of a quality profile (see
+    /// [`crate::workflow::quality_profiles`]): `profile`, else the one
+    /// `quality.branches` picks for the branch checked out in the working
+    /// directory, else every check. Fails when any check fails; checks use
+    /// their own timeout rather than `timeout_secs`.
+    Quality {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<String>,
+    },
+}
+
+fn default_owasp_min_severity() -> crate::security::owasp::Severity {
+    crate::security::owasp::Severity::High
 }
 
 fn default_gate_timeout_secs() -> u64 {
@@ -376,10 +454,17 @@ pub struct OutputContract {
     #[serde(default)]
     pub required_sections: Vec<String>,
 
-    /// JSON schema for structured output validation
+    /// JSON schema the agent's reply must satisfy. The reply is parsed as
+    /// JSON and validated (see [`super::output_schema`]); the typed value is
+    /// stored on the stage output as `structured`.
     #[serde(default)]
     pub schema: Option<serde_json::Value>,
 
+    /// How many times the agent is re-prompted with the violations when its
+    /// reply doesn't satisfy `schema` (default 2).
+    #[serde(default = "default_schema_retries")]
+    pub schema_retries: u32,
+
     /// Output file name (for file-based contracts)
     #[serde(default)]
     pub output_file: Option<String>,
@@ -492,6 +577,10 @@ fn default_format() -> String {
     "text".to_string()
 }
 
+fn default_schema_retries() -> u32 {
+    2
+}
+
 /// Runtime state of a flow execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowState {
@@ -581,7 +670,7 @@ impl Flow {
         for target in &self.on_rate_limit {
             if crate::providers::ProviderKind::parse(&target.provider).is_none() {
                 return Err(anyhow::anyhow!(
-                    "Flow '{}' has unknown on_rate_limit provider '{}' (expected: claude | codex | copilot)",
+                    "Flow '{}' has unknown on_rate_limit provider '{}' (expected: claude | codex | gemini | aider | copilot | mock)",
                     self.name,
                     target.provider
                 ));
@@ -594,7 +683,7 @@ impl Flow {
                 && crate::providers::ProviderKind::parse(provider).is_none()
             {
                 return Err(anyhow::anyhow!(
-                    "Stage '{}' has unknown provider '{}' (expected: claude | codex | copilot)",
+                    "Stage '{}' has unknown provider '{}' (expected: claude | codex | gemini | aider | copilot | mock)",
                     stage.id,
                     provider
                 ));
@@ -605,13 +694,62 @@ impl Flow {
                     && crate::providers::ProviderKind::parse(provider).is_none()
                 {
                     return Err(anyhow::anyhow!(
-                        "Stage '{}' has unknown promotion provider '{}' (expected: claude | codex | copilot)",
+                        "Stage '{}' has unknown promotion provider '{}' (expected: claude | codex | gemini | aider | copilot | mock)",
+                        stage.id,
+                        provider
+                    ));
+                }
+            }
+
+            for gate in &stage.gates {
+                if gate.command.trim().is_empty() && gate.builtin.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "Gate '{}' in stage '{}' needs a command or a builtin check",
+                        gate.name,
+                        stage.id
+                    ));
+                }
+            }
+
+            if let Some(review) = &stage.review {
+                if stage.parallel
+                    || stage.call.is_some()
+                    || stage.team_leader.is_some()
+                    || stage.sangha.is_some()
+                {
+                    return Err(anyhow::anyhow!(
+                        "Stage '{}' has a review, which only covers a single agent's stage",
+                        stage.id
+                    ));
+                }
+                if !(1..=super::review::MAX_SCORE).contains(&review.min_score) {
+                    return Err(anyhow::anyhow!(
+                        "Stage '{}': review.min_score must be between 1 and {}",
+                        stage.id,
+                        super::review::MAX_SCORE
+                    ));
+                }
+                if let Some(provider) = &review.provider
+                    && crate::providers::ProviderKind::parse(provider).is_none()
+                {
+                    return Err(anyhow::anyhow!(
+                        "Stage '{}' has unknown review provider '{}' (expected: claude | codex | gemini | aider | copilot | mock)",
                         stage.id,
                         provider
                     ));
                 }
             }
 
+            if let Some(schema) = stage
+                .output_contract
+                .as_ref()
+                .and_then(|c| c.schema.as_ref())
+            {
+                super::output_schema::check_schema(schema).map_err(|e| {
+                    anyhow::anyhow!("Stage '{}' has an invalid output schema: {}", stage.id, e)
+                })?;
+            }
+
             for rule in &stage.rules {
                 if !self.stages.iter().any(|m| m.id == rule.next) {
                     return Err(anyhow::anyhow!(
@@ -768,8 +906,31 @@ pub struct FlowEngine {
     default_provider: Option<crate::providers::ProviderKind>,
     /// CLI model override applied to every live stage.
     model_override: Option<String>,
+    /// Picks models for stages nothing else pins one for.
+    model_routing: crate::providers::routing::ModelRoutingConfig,
     /// Optional isolated worktree name forwarded through AISessionBridge.
     worktree_name: Option<String>,
+    /// Snapshots taken before writing stages, for `ccswarm task undo`.
+    journal: Option<std::sync::Arc<crate::journal::ActionJournal>>,
+    /// Limits for sub-agents requested by stage replies; `None` ignores requests.
+    subagents: Option<super::subagent::SubagentConfig>,
+    /// Checks, profiles and matrix for `quality` and `test_matrix` gates.
+    quality: crate::config::QualityConfig,
+    /// Fences earlier stage, session and gate output before it is re-prompted.
+    prompt_guard: crate::security::prompt_guard::PromptGuard,
+    /// Cuts long gate output to its salient lines for retry feedback.
+    sampler: crate::session::sampling::ContextSampler,
+    /// Adopted DOCTRINE.md, given to every stage as project instructions.
+    doctrine: Option<String>,
+    /// Working language and time zone, per agent.
+    locales: crate::i18n::Locales,
+    /// Steps in when the agent keeps re-running a failing command.
+    supervisor: std::sync::Mutex<super::supervisor::LoopSupervisor>,
+    /// Where a quality gate that ran out of retries is reported.
+    notifications: crate::notify::NotificationsConfig,
+    /// `agents` from `ccswarm.json`: a stage's agent (else persona) picks
+    /// its provider and provider section here.
+    agents: HashMap<String, crate::config::AgentConfig>,
 }
 
 /// Progress notification sent after each stage completes
@@ -814,7 +975,18 @@ impl FlowEngine {
             interactive: false,
             default_provider: None,
             model_override: None,
+            model_routing: Default::default(),
             worktree_name: None,
+            journal: None,
+            subagents: None,
+            quality: Default::default(),
+            prompt_guard: Default::default(),
+            sampler: Default::default(),
+            doctrine: None,
+            locales: Default::default(),
+            supervisor: Default::default(),
+            notifications: Default::default(),
+            agents: HashMap::new(),
         }
     }
 
@@ -867,6 +1039,54 @@ impl FlowEngine {
         self.model_override = Some(model.into());
     }
 
+    /// Route stages without a pinned model by task complexity
+    /// (`model_routing` in config).
+    pub(crate) fn set_model_routing(
+        &mut self,
+        routing: crate::providers::routing::ModelRoutingConfig,
+    ) {
+        self.model_routing = routing;
+    }
+
+    /// Thresholds for intervening in runaway command loops (`supervisor` in
+    /// config).
+    pub(crate) fn set_supervisor(&mut self, config: super::supervisor::SupervisorConfig) {
+        self.supervisor = std::sync::Mutex::new(super::supervisor::LoopSupervisor::new(config));
+    }
+
+    /// Sinks told when a stage's quality gate fails for good.
+    pub(crate) fn set_notifications(&mut self, config: crate::notify::NotificationsConfig) {
+        self.notifications = config;
+    }
+
+    /// Agents from `ccswarm.json`, for their `provider` and provider sections.
+    pub(crate) fn set_agents(&mut self, agents: HashMap<String, crate::config::AgentConfig>) {
+        self.agents = agents;
+    }
+
+    /// The configured agent a stage runs as: its `agent`, else its persona.
+    fn stage_agent_config(&self, stage: &Stage) -> Option<&crate::config::AgentConfig> {
+        stage
+            .agent
+            .as_deref()
+            .or(stage.persona.as_deref())
+            .and_then(|name| self.agents.get(name))
+    }
+
+    /// Extra CLI arguments the stage's agent gives `provider`.
+    fn stage_provider_args(
+        &self,
+        stage: &Stage,
+        provider: Option<crate::providers::ProviderKind>,
+    ) -> Vec<String> {
+        self.stage_agent_config(stage)
+            .and_then(|agent| {
+                agent.provider_config(provider.unwrap_or(crate::providers::ProviderKind::Claude))
+            })
+            .map(|section| section.args.clone())
+            .unwrap_or_default()
+    }
+
     /// Set the provider worktree isolation name for live stage execution.
     pub(crate) fn set_worktree_name(&mut self, name: impl Into<String>) {
         self.worktree_name = Some(name.into());
@@ -874,22 +1094,33 @@ impl FlowEngine {
 
     /// Resolve the provider and model a stage execution should use.
     ///
-    /// Base precedence: stage YAML `provider:` > `--provider` flag >
-    /// `CCSWARM_PROVIDER` env > Claude default (stage YAML wins because it
-    /// expresses deliberate per-stage intent). On top of that, `promotion`
+    /// Base precedence: stage YAML `provider:` > the stage agent's
+    /// `provider` in `ccswarm.json` > `--provider` flag > `CCSWARM_PROVIDER`
+    /// env > Claude default (stage YAML wins because it expresses deliberate
+    /// per-stage intent; the agent's because a mixed swarm is configured per
+    /// agent). On top of that, `promotion`
     /// rules escalate provider/model from the Nth visit of the stage onward
     /// (takt-style, last matching entry wins). Promotion is skipped when no
     /// visit count is available — notably for parallel sub-stages, whose
     /// count would otherwise reflect the parent stage.
+    /// A stage left without a model after all that takes the one in its
+    /// agent's provider section (e.g. `gemini_config.model`), else is routed
+    /// by its task's complexity when `model_routing` is on.
     fn resolve_effective_provider(
         &self,
         stage: &Stage,
         state: &FlowState,
     ) -> (Option<crate::providers::ProviderKind>, Option<String>) {
+        let agent_config = self.stage_agent_config(stage);
         let mut provider = stage
             .provider
             .as_deref()
             .and_then(crate::providers::ProviderKind::parse)
+            .or_else(|| {
+                agent_config
+                    .and_then(|agent| agent.provider.as_deref())
+                    .and_then(crate::providers::ProviderKind::parse)
+            })
             .or(self.default_provider)
             .or_else(|| {
                 std::env::var("CCSWARM_PROVIDER")
@@ -933,14 +1164,88 @@ impl FlowEngine {
             model = self.model_override.clone();
         }
 
+        if model.is_none() {
+            model = agent_config
+                .and_then(|agent| {
+                    agent
+                        .provider_config(provider.unwrap_or(crate::providers::ProviderKind::Claude))
+                })
+                .and_then(|section| section.model.clone());
+        }
+
+        if model.is_none() {
+            let agent = stage.persona.as_deref().unwrap_or("default");
+            let task = state
+                .variables
+                .get("task")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&stage.instruction);
+            let work_dir = stage
+                .working_dir
+                .as_ref()
+                .map(std::path::PathBuf::from)
+                .unwrap_or_else(|| self.working_dir.clone());
+            if let Some(route) = self.model_routing.route(
+                provider.unwrap_or(crate::providers::ProviderKind::Claude),
+                agent,
+                task,
+                &work_dir,
+            ) {
+                info!(
+                    "Stage '{}' routed to model {}: {}",
+                    stage.id, route.model, route.reason
+                );
+                model = Some(route.model);
+            }
+        }
+
         (provider, model)
     }
 
+    /// Honor sub-agent requests in stage replies within `config`'s limits.
+    pub fn set_subagents(&mut self, config: super::subagent::SubagentConfig) {
+        self.subagents = config.enabled.then_some(config);
+    }
+
+    /// Quality settings from `ccswarm.json`, for `quality` and `test_matrix`
+    /// gates.
+    pub fn set_quality(&mut self, quality: crate::config::QualityConfig) {
+        self.quality = quality;
+    }
+
+    /// Replace the built-in prompt guard (e.g. with `prompt_guard` from config).
+    pub fn set_prompt_guard(&mut self, guard: crate::security::prompt_guard::PromptGuard) {
+        self.prompt_guard = guard;
+    }
+
+    /// Replace the default output sampling of gate feedback (e.g. with
+    /// `output_sampling` from config and a log store for the full output).
+    pub fn set_output_sampling(&mut self, sampler: crate::session::sampling::ContextSampler) {
+        self.sampler = sampler;
+    }
+
+    /// Give every stage the project doctrine (see [`super::doctrine`]).
+    pub fn set_doctrine(&mut self, doctrine: impl Into<String>) {
+        self.doctrine = Some(doctrine.into());
+    }
+
+    /// Tell each stage's agent the language and time zone to work in,
+    /// looked up by the stage persona.
+    pub fn set_locales(&mut self, locales: crate::i18n::Locales) {
+        self.locales = locales;
+    }
+
     /// Set the working directory for agent execution
     pub fn set_working_dir(&mut self, dir: std::path::PathBuf) {
         self.working_dir = dir;
     }
 
+    /// Snapshot the working directory into `journal` before each stage that
+    /// may write to it.
+    pub fn set_journal(&mut self, journal: std::sync::Arc<crate::journal::ActionJournal>) {
+        self.journal = Some(journal);
+    }
+
     /// Set the event recorder for NDJSON observability
     pub fn set_event_recorder(&mut self, recorder: crate::events::EventRecorder) {
         self.event_recorder = Some(recorder);
@@ -1079,15 +1384,25 @@ impl FlowEngine {
             name, state.current_movement
         );
 
-        // Stash run_id in state so expand_template can resolve `{report:<name>}`
-        // by reading from `.ccswarm/runs/<run_id>/reports/`. Uses a double-underscore
-        // prefix to flag as internal — user instructions should never name a variable
-        // `__run_id`.
+        // Stash run_id and its reports directory in state so expand_template can
+        // resolve `{report:<name>}` without depending on the process's cwd. Uses
+        // a double-underscore prefix to flag as internal — user instructions
+        // should never name a variable `__run_id`.
         if !run_id.is_empty() {
             state.variables.insert(
                 "__run_id".to_string(),
                 serde_json::Value::String(run_id.clone()),
             );
+            state.variables.insert(
+                "__reports_dir".to_string(),
+                serde_json::Value::String(
+                    self.runs_dir()
+                        .join(&run_id)
+                        .join("reports")
+                        .to_string_lossy()
+                        .into_owned(),
+                ),
+            );
         }
 
         let mut cumulative_tokens: u64 = 0;
@@ -1270,6 +1585,39 @@ impl FlowEngine {
             }
             self.record_event(ev_end).await;
 
+            // An agent that reports a blocker stops the flow here; queue drain
+            // reads the blocker back from this event.
+            if let Some(blocker) = output
+                .get("output")
+                .and_then(|o| o.as_str())
+                .and_then(super::blocker::parse_blocker)
+                .or_else(|| self.supervisor().take_pause())
+            {
+                warn!(
+                    "Flow '{}' blocked at stage '{}': {}",
+                    name,
+                    stage.id,
+                    blocker.summary()
+                );
+                self.record_event(
+                    crate::events::Event::new(
+                        &run_id,
+                        crate::events::EventLevel::Warn,
+                        crate::events::EventType::TaskEnd,
+                        format!("Stage '{}' blocked: {}", stage.id, blocker.summary()),
+                    )
+                    .with_movement(&stage.id)
+                    .with_metadata(serde_json::json!({
+                        "reason": "blocked",
+                        "blocker": blocker,
+                    })),
+                )
+                .await;
+                state.status = FlowStatus::Aborted;
+                state.completed_at = Some(Utc::now());
+                break;
+            }
+
             // Enforce the run-level token budget. Done after the stage records its
             // end event so `ccswarm cost` still sees the usage of the stage that
             // pushed us over. We abort on the *next* iteration boundary rather
@@ -1309,16 +1657,18 @@ impl FlowEngine {
             state
                 .variables
                 .insert(format!("{}_output", stage.id), output.clone());
+            if let Some(structured) = output.get("structured") {
+                state
+                    .variables
+                    .insert(format!("{}_structured", stage.id), structured.clone());
+            }
 
-            // Save stage report to .ccswarm/runs/{run-id}/reports/.
+            // Save stage report to <runs dir>/{run-id}/reports/.
             // Always writes a `<stage.id>.md` for backward-compat; additionally
             // writes any declared `output_contract.reports[*].name` files so
             // downstream stages can pull them via `{report:<name>}`.
             if !run_id.is_empty() {
-                let report_dir = std::path::PathBuf::from(".ccswarm")
-                    .join("runs")
-                    .join(&run_id)
-                    .join("reports");
+                let report_dir = self.runs_dir().join(&run_id).join("reports");
                 let _ = tokio::fs::create_dir_all(&report_dir).await;
                 let report_content = output
                     .as_object()
@@ -1426,6 +1776,7 @@ impl FlowEngine {
                 tasks_completed: if completed { 1 } else { 0 },
                 tasks_failed: if completed { 0 } else { 1 },
                 agents_used: agents_used.into_iter().collect(),
+                orchestrator_run_id: Some(crate::run_id::RunId::current().clone()),
             };
             if let Err(e) = recorder.write_summary(&summary).await {
                 warn!("Failed to write run summary: {}", e);
@@ -1483,7 +1834,10 @@ impl FlowEngine {
         }
 
         // Build the prompt from instruction + persona + context
-        let prompt = self.build_movement_prompt(stage, state);
+        let (prompt, flagged) = self.build_movement_prompt(stage, state);
+        for entry in &flagged {
+            self.audit_prompt_injection(entry).await;
+        }
 
         let output = if let Some(ref bridge) = self.bridge {
             // Real execution via Claude Code CLI + ai-session result management
@@ -1536,36 +1890,63 @@ impl FlowEngine {
                 })
                 .unwrap_or_default();
 
+            let provider_args = self.stage_provider_args(stage, provider);
             let exec_options = crate::session::bridge::MovementExecOptions {
                 provider,
+                provider_args,
                 tools: effective_tools,
                 model,
-                system_prompt: stage.persona.as_ref().and_then(|p| {
-                    self.facet_registry.get_persona(p).and_then(|f| {
-                        if f.system_prompt.is_empty() {
-                            None
-                        } else {
-                            Some(f.system_prompt.clone())
-                        }
-                    })
+                system_prompt: self.persona_system_prompt(stage).map(|system| {
+                    let boundary = self.role_boundary(stage);
+                    if boundary.is_empty() {
+                        system
+                    } else {
+                        format!("{system}\n\n{boundary}")
+                    }
                 }),
                 max_budget: self.budget_usd,
                 worktree_name: self.worktree_name.clone(),
                 session_id: None,
                 continuation: crate::session::bridge::ContinuationPolicy::SingleTurn,
                 rate_limit_fallbacks,
+                typed_tools: stage.typed_tools.then(|| stage.permission.clone()),
+                stream: self
+                    .event_recorder
+                    .as_ref()
+                    .filter(|_| crate::session::stream::enabled())
+                    .map(|recorder| {
+                        crate::session::stream::ChatStream::new(recorder.run_dir(), Some(&stage.id))
+                    }),
             };
 
             // Command-gate loop: when the agent call succeeds but a declared
             // gate fails, bounded gate output is appended to the prompt and
-            // the stage re-runs (up to max_retries additional attempts).
-            let mut gate_feedback: Option<String> = None;
+            // the stage re-runs (up to max_retries additional attempts). A
+            // reply that breaks the contract's schema is re-prompted the same
+            // way, up to schema_retries times.
+            let schema = stage
+                .output_contract
+                .as_ref()
+                .and_then(|c| c.schema.as_ref().map(|s| (s, c.schema_retries)));
+            let mut retry_feedback: Option<String> = None;
             let mut gate_attempts_left = stage.max_retries;
+            let mut schema_attempts_left = schema.map_or(0, |(_, retries)| retries);
             loop {
-                let effective_prompt = match &gate_feedback {
+                let effective_prompt = self.supervised_prompt(match &retry_feedback {
                     Some(feedback) => format!("{}\n\n{}", prompt, feedback),
                     None => prompt.clone(),
-                };
+                });
+                if let Some(journal) = &self.journal
+                    && stage.permission != MovementPermission::Readonly
+                {
+                    let label = match &retry_feedback {
+                        Some(_) => format!("{} (retry)", stage.id),
+                        None => stage.id.clone(),
+                    };
+                    journal
+                        .record_or_warn(crate::journal::JournalAction::Stage, &label, &work_dir)
+                        .await;
+                }
                 let attempt_output = match bridge
                     .execute_with_retry(
                         agent_id,
@@ -1608,33 +1989,121 @@ impl FlowEngine {
                             )
                             .await;
                         }
+                        if result.shadow_promoted {
+                            let run_id = self
+                                .event_recorder
+                                .as_ref()
+                                .map(|r| r.run_id().to_string())
+                                .unwrap_or_default();
+                            self.record_event(
+                                crate::events::Event::new(
+                                    &run_id,
+                                    crate::events::EventLevel::Warn,
+                                    crate::events::EventType::ProviderError,
+                                    format!(
+                                        "Shadow of '{}' promoted for stage '{}' after repeated failures",
+                                        agent_id, stage.id
+                                    ),
+                                )
+                                .with_movement(&stage.id)
+                                .with_metadata(serde_json::json!({
+                                    "reason": "shadow_promoted",
+                                    "role": agent_id,
+                                })),
+                            )
+                            .await;
+                        }
                         // Stream-json metadata (tool names, cost) rides on
                         // BridgeResult; surface it as a ProviderCall event so
                         // events.ndjson carries per-call telemetry. Token counts are
                         // deliberately NOT repeated here — they ride on the stage's
                         // MovementEnd event, and `ccswarm cost` sums token fields
                         // across all events, so duplicating them would double-count.
-                        if !result.tool_names.is_empty() || result.total_cost_usd.is_some() {
-                            let run_id = self
-                                .event_recorder
-                                .as_ref()
-                                .map(|r| r.run_id().to_string())
-                                .unwrap_or_default();
+                        // The call's wall time goes in `elapsed_ms` rather than
+                        // `duration_ms` for the same reason: introspection sums
+                        // `duration_ms` per stage.
+                        let run_id = self
+                            .event_recorder
+                            .as_ref()
+                            .map(|r| r.run_id().to_string())
+                            .unwrap_or_default();
+                        self.record_event(
+                            crate::events::Event::new(
+                                &run_id,
+                                crate::events::EventLevel::Info,
+                                crate::events::EventType::ProviderCall,
+                                format!("Provider call completed for stage '{}'", stage.id),
+                            )
+                            .with_movement(&stage.id)
+                            .with_metadata(serde_json::json!({
+                                "tool_names": result.tool_names,
+                                "cost_usd": result.total_cost_usd,
+                                "elapsed_ms": result.duration_ms,
+                            })),
+                        )
+                        .await;
+                        let command_loop = self.supervisor().observe(&result.commands);
+                        if let Some(command_loop) = command_loop {
+                            warn!(
+                                "Stage '{}' is looping on `{}` ({} failures): {:?}",
+                                stage.id,
+                                command_loop.command,
+                                command_loop.failures,
+                                command_loop.intervention
+                            );
                             self.record_event(
                                 crate::events::Event::new(
                                     &run_id,
-                                    crate::events::EventLevel::Info,
-                                    crate::events::EventType::ProviderCall,
-                                    format!("Provider call completed for stage '{}'", stage.id),
+                                    crate::events::EventLevel::Warn,
+                                    crate::events::EventType::ErrorDetected,
+                                    format!(
+                                        "Command failed {} times in a row in stage '{}'",
+                                        command_loop.failures, stage.id
+                                    ),
+                                )
+                                .with_movement(&stage.id)
+                                .with_metadata(serde_json::json!({
+                                    "source": "supervisor",
+                                    "reason": "command_loop",
+                                    "loop": command_loop,
+                                })),
+                            )
+                            .await;
+                        }
+                        if !result.raw.is_empty() {
+                            self.record_event(
+                                crate::events::Event::new(
+                                    &run_id,
+                                    crate::events::EventLevel::Debug,
+                                    crate::events::EventType::OutputBurst,
+                                    format!(
+                                        "{} lines of provider output for stage '{}'",
+                                        result.raw.lines().count(),
+                                        stage.id
+                                    ),
                                 )
                                 .with_movement(&stage.id)
                                 .with_metadata(serde_json::json!({
-                                    "tool_names": result.tool_names,
-                                    "cost_usd": result.total_cost_usd,
+                                    "source": "provider",
+                                    "bytes": result.raw.len(),
+                                    "lines": result.raw.lines().count(),
                                 })),
                             )
                             .await;
                         }
+                        if result.attention == ai_session::core::attention::AttentionState::Error {
+                            self.record_event(
+                                crate::events::Event::new(
+                                    &run_id,
+                                    crate::events::EventLevel::Warn,
+                                    crate::events::EventType::ErrorDetected,
+                                    format!("Errors in provider output for stage '{}'", stage.id),
+                                )
+                                .with_movement(&stage.id)
+                                .with_metadata(serde_json::json!({ "source": "provider" })),
+                            )
+                            .await;
+                        }
                         serde_json::json!({
                             "stage": stage.id,
                             "output": result.raw,
@@ -1648,6 +2117,22 @@ impl FlowEngine {
                     }
                     Err(e) => {
                         warn!("Stage '{}' execution failed: {}", stage.id, e);
+                        let run_id = self
+                            .event_recorder
+                            .as_ref()
+                            .map(|r| r.run_id().to_string())
+                            .unwrap_or_default();
+                        self.record_event(
+                            crate::events::Event::new(
+                                &run_id,
+                                crate::events::EventLevel::Warn,
+                                crate::events::EventType::ErrorDetected,
+                                format!("Provider call failed for stage '{}': {}", stage.id, e),
+                            )
+                            .with_movement(&stage.id)
+                            .with_metadata(serde_json::json!({ "source": "provider" })),
+                        )
+                        .await;
                         serde_json::json!({
                             "stage": stage.id,
                             "error": e.to_string(),
@@ -1662,27 +2147,134 @@ impl FlowEngine {
                     .get("status")
                     .and_then(|s| s.as_str())
                     .is_some_and(|s| s == "completed");
-                if stage.gates.is_empty() || !call_succeeded {
+                let mut attempt_output = attempt_output;
+                if call_succeeded && let Some(config) = &self.subagents {
+                    self.run_requested_subagents(
+                        bridge,
+                        stage,
+                        config,
+                        SubagentParent {
+                            agent_id,
+                            identity: &identity,
+                            work_dir: &work_dir,
+                            options: &exec_options,
+                            context_sources: vec![&effective_prompt],
+                        },
+                        &mut attempt_output,
+                    )
+                    .await;
+                }
+                if call_succeeded && let Some((schema, _)) = schema {
+                    let reply = attempt_output
+                        .get("output")
+                        .and_then(|o| o.as_str())
+                        .unwrap_or_default();
+                    match super::output_schema::parse_reply(schema, reply) {
+                        Ok(value) => {
+                            if let Some(obj) = attempt_output.as_object_mut() {
+                                obj.insert("structured".into(), value);
+                            }
+                        }
+                        Err(problems) => {
+                            warn!(
+                                "Stage '{}' reply breaks its output schema ({} retry attempts left): {}",
+                                stage.id,
+                                schema_attempts_left,
+                                problems.join("; ")
+                            );
+                            // Out of retries: the contract check below fails
+                            // the stage with the same violations.
+                            if schema_attempts_left == 0 {
+                                break attempt_output;
+                            }
+                            schema_attempts_left -= 1;
+                            retry_feedback =
+                                Some(super::output_schema::retry_feedback(schema, &problems));
+                            continue;
+                        }
+                    }
+                }
+                if (stage.gates.is_empty() && stage.review.is_none()) || !call_succeeded {
                     break attempt_output;
                 }
 
-                match run_command_gates(&stage.gates, &work_dir).await {
+                let gate_events = self.event_recorder.as_ref().map(|recorder| GateEvents {
+                    recorder,
+                    stage: &stage.id,
+                });
+                let failure = match run_command_gates(
+                    &stage.gates,
+                    &work_dir,
+                    bridge.redactor(),
+                    bridge.output_parser(),
+                    &self.sampler,
+                    &self.quality,
+                    gate_events.as_ref(),
+                )
+                .await
+                {
+                    Some(failure) => Some(failure),
+                    // The reviewer only sees attempts the machine checks pass.
+                    None => match &stage.review {
+                        Some(spec) => {
+                            self.review_attempt(
+                                bridge,
+                                stage,
+                                spec,
+                                state,
+                                SubagentParent {
+                                    agent_id,
+                                    identity: &identity,
+                                    work_dir: &work_dir,
+                                    options: &exec_options,
+                                    context_sources: Vec::new(),
+                                },
+                                &mut attempt_output,
+                            )
+                            .await
+                        }
+                        None => None,
+                    },
+                };
+                match failure {
                     None => break attempt_output,
-                    Some((gate_name, feedback)) => {
+                    Some(failure) => {
                         warn!(
                             "Stage '{}' gate '{}' failed ({} retry attempts left)",
-                            stage.id, gate_name, gate_attempts_left
+                            stage.id, failure.gate, gate_attempts_left
                         );
                         if gate_attempts_left == 0 {
+                            crate::notify::send(
+                                &self.notifications,
+                                crate::notify::Notification::new(
+                                    crate::notify::NotifyEvent::QualityGateFailed,
+                                    format!("ccswarm: gate '{}' failed", failure.gate),
+                                    format!("stage '{}' is out of retries", stage.id),
+                                ),
+                            )
+                            .await;
                             let mut failed = attempt_output;
                             if let Some(obj) = failed.as_object_mut() {
                                 obj.insert("status".into(), serde_json::json!("failed"));
-                                obj.insert("gate_failed".into(), serde_json::json!(gate_name));
+                                obj.insert("gate_failed".into(), serde_json::json!(failure.gate));
+                                if !failure.diagnostics.is_empty() {
+                                    obj.insert(
+                                        "gate_diagnostics".into(),
+                                        serde_json::json!(failure.diagnostics),
+                                    );
+                                }
                             }
                             break failed;
                         }
                         gate_attempts_left -= 1;
-                        gate_feedback = Some(feedback);
+                        let source = format!("gate:{}", failure.gate);
+                        let (feedback, suspects) =
+                            self.prompt_guard.fence(&source, &failure.feedback);
+                        if !suspects.is_empty() {
+                            let entry = self.injection_entry(stage, state, &source, suspects);
+                            self.audit_prompt_injection(&entry).await;
+                        }
+                        retry_feedback = Some(feedback);
                     }
                 }
             }
@@ -1696,6 +2288,11 @@ impl FlowEngine {
             })
         };
 
+        let mut output = output;
+        if self.rates_itself(stage, state) {
+            self.attach_self_rating(stage, state, &mut output).await;
+        }
+
         // Validate output contract if specified
         if let Some(ref contract) = stage.output_contract {
             self.validate_output_contract(contract, &output)?;
@@ -1743,6 +2340,7 @@ impl FlowEngine {
     /// parallel shape `{"parallel": true, "agents": {id: output}, ...}` that
     /// `evaluate_rules` and `all()`/`any()` aggregation understand. Shared by
     /// declared `parallel:` stages and team_leader-synthesized workers.
+    #[tracing::instrument(name = "flow.delegate", skip_all, fields(parent = %parent_id, workers = stages.len()))]
     async fn run_stages_parallel(
         &self,
         parent_id: &str,
@@ -1797,6 +2395,7 @@ impl FlowEngine {
     /// parse error attached; if that also fails, the stage degrades to a
     /// single worker executing the original instruction — a decomposition
     /// failure must not kill work a single agent could do.
+    #[tracing::instrument(name = "flow.team_leader", skip_all, fields(stage = %stage.id))]
     async fn execute_team_leader(
         &self,
         stage: &Stage,
@@ -1813,15 +2412,31 @@ impl FlowEngine {
         let mut leader_stage = stage.clone();
         leader_stage.team_leader = None;
         leader_stage.gates = Vec::new();
+        leader_stage.review = None;
         leader_stage.permission = MovementPermission::Readonly;
         leader_stage.tools = Vec::new();
 
+        // Areas earlier runs rated low-confidence: the leader is asked to
+        // isolate them, and parts touching them get a review pass.
+        let weak_areas = match self.self_rating_spec(state) {
+            Some(_) => super::self_rating::RatingHistory::load(&self.runs_dir())
+                .await
+                .weak_areas(),
+            None => Default::default(),
+        };
+
         let mut parts = None;
         let mut last_parse_error = String::new();
         for attempt in 0..2 {
             let mut prompt_stage = leader_stage.clone();
             prompt_stage.instruction =
                 team_leader::decomposition_prompt(&expanded_instruction, max_parts);
+            let mentioned = super::self_rating::mentioned_areas(&weak_areas, &expanded_instruction);
+            if !mentioned.is_empty() {
+                prompt_stage
+                    .instruction
+                    .push_str(&team_leader::weak_areas_note(&mentioned));
+            }
             if attempt > 0 {
                 prompt_stage.instruction.push_str(&format!(
                     "\n\n# Previous attempt failed\n{}\nReply with ONLY the JSON array this time.",
@@ -1883,7 +2498,106 @@ impl FlowEngine {
             .map(|part| team_leader::worker_stage(stage, spec, part))
             .collect();
 
-        Box::pin(self.run_stages_parallel(&stage.id, &workers, state)).await
+        let mut output = Box::pin(self.run_stages_parallel(&stage.id, &workers, state)).await?;
+        if let Some(spec) = self.self_rating_spec(state) {
+            self.review_low_confidence_parts(&workers, spec, &weak_areas, state, &mut output)
+                .await?;
+        }
+        Ok(output)
+    }
+
+    /// Give each worker that rated itself below the threshold, or whose part
+    /// touches a weak area, a readonly review pass. The review lands in the
+    /// worker's output as `review`, with `review_required` and the reasons.
+    async fn review_low_confidence_parts(
+        &self,
+        workers: &[Stage],
+        spec: &super::self_rating::SelfRatingSpec,
+        weak_areas: &std::collections::BTreeSet<String>,
+        state: &FlowState,
+        output: &mut serde_json::Value,
+    ) -> Result<()> {
+        use super::self_rating;
+
+        let mut reasons_by_part: HashMap<String, Vec<String>> = HashMap::new();
+        let mut reviewers = Vec::new();
+        for worker in workers {
+            let Some(worker_output) = output.get("agents").and_then(|a| a.get(&worker.id)) else {
+                continue;
+            };
+            let mut reasons = Vec::new();
+            if worker_output.get("needs_review").and_then(|v| v.as_bool()) == Some(true) {
+                let rating = &worker_output["self_rating"];
+                reasons.push(format!(
+                    "the worker rated itself confidence {}/5, completeness {}/5",
+                    rating["confidence"], rating["completeness"]
+                ));
+            }
+            for area in self_rating::mentioned_areas(weak_areas, &worker.instruction) {
+                reasons.push(format!("earlier runs rated '{area}' low-confidence"));
+            }
+            if reasons.is_empty() {
+                continue;
+            }
+
+            let report = worker_output
+                .get("output")
+                .and_then(|o| o.as_str())
+                .unwrap_or_default();
+            let mut reviewer = worker.clone();
+            reviewer.id = format!("{}-review", worker.id);
+            reviewer.persona = Some(spec.reviewer_persona.clone());
+            reviewer.permission = MovementPermission::Readonly;
+            reviewer.tools = Vec::new();
+            reviewer.instruction = self_rating::review_instruction(
+                &worker.instruction,
+                &truncate_for_context(report, 4000),
+                &reasons,
+            );
+            reasons_by_part.insert(worker.id.clone(), reasons);
+            reviewers.push(reviewer);
+        }
+        if reviewers.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "Reviewing {} low-confidence part(s): [{}]",
+            reviewers.len(),
+            reasons_by_part
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let parent_id = output
+            .get("stage")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let reviews = Box::pin(self.run_stages_parallel(&parent_id, &reviewers, state)).await?;
+        let Some(agents) = output.get_mut("agents").and_then(|a| a.as_object_mut()) else {
+            return Ok(());
+        };
+        for (part_id, reasons) in reasons_by_part {
+            let review = reviews["agents"][format!("{part_id}-review")].clone();
+            if let Some(obj) = agents.get_mut(&part_id).and_then(|o| o.as_object_mut()) {
+                obj.insert("review_required".into(), serde_json::json!(true));
+                obj.insert("review_reasons".into(), serde_json::json!(reasons));
+                obj.insert("review".into(), review);
+            }
+        }
+        Ok(())
+    }
+
+    /// Where this engine's runs live: the event recorder's directory, else
+    /// `.ccswarm/runs` under the working directory.
+    fn runs_dir(&self) -> std::path::PathBuf {
+        self.event_recorder
+            .as_ref()
+            .and_then(|r| r.runs_dir())
+            .map(std::path::Path::to_path_buf)
+            .unwrap_or_else(|| self.working_dir.join(".ccswarm").join("runs"))
     }
 
     /// Execute a Sangha consensus stage. Members run independently, then their
@@ -2086,8 +2800,16 @@ impl FlowEngine {
     ///
     /// Composition order (takt-style):
     /// - System: persona (via FacetRegistry)
-    /// - User: knowledge → instruction → policy → output contract → tools → tags
-    fn build_movement_prompt(&self, stage: &Stage, state: &FlowState) -> String {
+    /// - User: doctrine → knowledge → instruction → policy → output contract → tools → tags
+    ///
+    /// Earlier output is fenced by the prompt guard; whatever it removed is
+    /// returned alongside the prompt for the audit log.
+    fn build_movement_prompt(
+        &self,
+        stage: &Stage,
+        state: &FlowState,
+    ) -> (String, Vec<crate::security::prompt_guard::AuditEntry>) {
+        let mut flagged = Vec::new();
         // Build output contract text if present
         let contract_text = stage.output_contract.as_ref().map(|c| {
             let mut parts = vec![format!("Format: {}", c.format)];
@@ -2100,6 +2822,12 @@ impl FlowEngine {
             if let Some(ref file) = c.output_file {
                 parts.push(format!("Write output to: {}", file));
             }
+            if let Some(schema) = &c.schema {
+                parts.push(format!(
+                    "Reply with a single JSON document matching this schema:\n{}",
+                    serde_json::to_string_pretty(schema).unwrap_or_default()
+                ));
+            }
             for report in &c.reports {
                 let mut line = format!(
                     "This stage produces a named report `{}` ({}); downstream stages reference it as `{{report:{}}}`",
@@ -2121,43 +2849,56 @@ impl FlowEngine {
         let composed = self.facet_registry.compose(
             stage.persona.as_deref(),
             stage.policy.as_deref(),
-            stage.knowledge.as_deref(),
+            self.facet_registry
+                .knowledge_for(stage.knowledge.as_deref(), stage.persona.as_deref()),
             &expanded_instruction,
             contract_text.as_deref(),
         );
 
-        let mut parts = Vec::new();
+        let mut prompt = PromptBuilder::new();
+        if let Some(doctrine) = &self.doctrine {
+            prompt = prompt.project_instructions(doctrine.as_str());
+        }
 
         // Inject task description when the stage instruction did not already
         // expand `{task}` into the user prompt.
         if let Some(task_text) = state.variables.get("task").and_then(|v| v.as_str())
             && !expanded_instruction.contains(task_text)
         {
-            parts.push(format!("## User Task\n\n{}", task_text));
+            prompt = prompt.task("User Task", task_text);
         }
 
         // User message (knowledge → instruction → policy → output contract).
         // The persona system prompt is forwarded separately through
         // ProviderOptions.system_prompt during live execution.
-        if !composed.user.is_empty() {
-            parts.push(composed.user);
-        }
+        prompt = prompt.instructions(composed.user);
 
-        // Add available tools
         if !stage.tools.is_empty() {
-            parts.push(format!("Available tools: {}", stage.tools.join(", ")));
+            prompt = prompt.constraint(format!("Available tools: {}", stage.tools.join(", ")));
+        }
+        prompt = prompt.constraint(format!("Permission level: {:?}", stage.permission));
+        if let Some(locale) = self.locales.for_agent(stage.persona.as_deref()).prompt() {
+            prompt = prompt.constraint(locale);
+        }
+        // Without a persona system prompt the role boundary goes here instead.
+        if self.persona_system_prompt(stage).is_none() {
+            let boundary = self.role_boundary(stage);
+            if !boundary.is_empty() {
+                prompt = prompt.constraint(boundary);
+            }
         }
-
-        // Add permission context
-        parts.push(format!("Permission level: {:?}", stage.permission));
 
         // Inject context from previous stages for continuity
         // (skip if pass_previous_response is false — used for fix stages)
-        if stage.pass_previous_response && !state.variables.is_empty() {
-            let var_summary: Vec<String> = state
+        if stage.pass_previous_response {
+            let mut outputs: Vec<_> = state
                 .variables
                 .iter()
                 .filter(|(k, _)| k.ends_with("_output"))
+                .collect();
+            outputs.sort_by_key(|(k, _)| k.as_str());
+            let items: Vec<ContextItem> = outputs
+                .into_iter()
                 .map(|(k, v)| {
                     let key = k.trim_end_matches("_output");
                     // Extract the actual output text from JSON if possible
@@ -2167,37 +2908,226 @@ impl FlowEngine {
                         .and_then(|o| o.as_str())
                         .map(|s| truncate_for_context(s, 2000))
                         .unwrap_or_else(|| truncate_for_context(&v.to_string(), 500));
-                    format!("[Previous '{}' result]:\n{}", key, output_text)
+                    let source = format!("stage:{key}");
+                    let (fenced, suspects) = self.prompt_guard.fence(&source, &output_text);
+                    if !suspects.is_empty() {
+                        flagged.push(self.injection_entry(stage, state, &source, suspects));
+                    }
+                    // The most recently run stage matters most.
+                    let recency = state
+                        .history
+                        .iter()
+                        .rposition(|t| t.from == key)
+                        .map_or(1, |i| i as u32 + 2);
+                    ContextItem::new(
+                        source,
+                        format!("[Previous '{}' result]:\n{}", key, fenced),
+                        recency,
+                    )
                 })
                 .collect();
-            if !var_summary.is_empty() {
-                parts.push(format!(
-                    "## Context from previous steps\n\n{}",
-                    var_summary.join("\n\n")
-                ));
+            let budget = Slot::Context.default_budget().unwrap_or(usize::MAX);
+            let packing = context_pack::pack(items, budget, &context_pack::PriorityScorer);
+            if packing.excluded().next().is_some() {
+                info!(
+                    stage = %stage.id,
+                    "Context from previous steps over budget; {}",
+                    packing.report()
+                );
+            } else {
+                debug!(stage = %stage.id, "{}", packing.report());
             }
+            prompt = prompt.context("Context from previous steps", packing.join());
+        }
+
+        // What changed while the run was paused, left by `session resume`
+        if let Some(away) = self
+            .event_recorder
+            .as_ref()
+            .and_then(|recorder| crate::session::resume::take(recorder.run_dir()))
+        {
+            let (fenced, suspects) = self.prompt_guard.fence("resume", &away);
+            if !suspects.is_empty() {
+                flagged.push(self.injection_entry(stage, state, "resume", suspects));
+            }
+            prompt = prompt.context("While you were away", fenced);
         }
 
         // Also inject ai-session context if bridge is available
         if let Some(ref bridge) = self.bridge {
             let agent_id = stage.persona.as_deref().unwrap_or("default");
             let recent = bridge.get_recent_context(agent_id, 3);
-            if !recent.is_empty() {
-                parts.push(format!(
-                    "## Recent conversation context\n\n{}",
-                    recent.join("\n")
-                ));
+            let source = format!("session:{agent_id}");
+            let (fenced, suspects) = self.prompt_guard.fence(&source, &recent.join("\n"));
+            if !suspects.is_empty() {
+                flagged.push(self.injection_entry(stage, state, &source, suspects));
             }
+            prompt = prompt.recent_output("Recent conversation context", fenced);
+        }
+
+        // Tell the agent how to hand off bounded subtasks (read by run_requested_subagents)
+        if let Some(config) = &self.subagents {
+            prompt = prompt.response_format(super::subagent::request_instructions(config));
+        }
+
+        // Editing stages rate their own work (read by attach_self_rating)
+        if self.rates_itself(stage, state) {
+            prompt = prompt.response_format(super::self_rating::rubric_prompt());
+        }
+
+        // Editing stages report what stops them instead of guessing
+        if stage.permission != MovementPermission::Readonly {
+            prompt = prompt.response_format(super::blocker::instructions());
+        }
+
+        // Streamed stages report progress as they go (read by the bridge)
+        if self.event_recorder.is_some() && crate::session::stream::enabled() {
+            prompt = prompt.response_format(crate::session::progress::instructions());
         }
 
         // Inject tag instructions for routing (takt-style [STEP:N] tags)
         if !stage.rules.is_empty() {
-            let tag_instructions =
-                super::judge::MovementJudge::generate_tag_instructions(&stage.rules);
-            parts.push(tag_instructions);
+            prompt = prompt.response_format(
+                super::judge::MovementJudge::generate_tag_instructions(&stage.rules),
+            );
+        }
+
+        (prompt.build().user, flagged)
+    }
+
+    fn supervisor(&self) -> std::sync::MutexGuard<'_, super::supervisor::LoopSupervisor> {
+        self.supervisor
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// `prompt` with what the loop supervisor wants the agent to hear: its
+    /// guidance after a streak of failures and, once escalated, the
+    /// think-mode keyword.
+    fn supervised_prompt(&self, prompt: String) -> String {
+        let mut supervisor = self.supervisor();
+        let mut prompt = match supervisor.take_guidance() {
+            Some(guidance) => format!("{prompt}\n\n## Supervisor\n\n{guidance}"),
+            None => prompt,
+        };
+        if let Some(mode) = supervisor.think_mode() {
+            prompt = format!("{}\n\n{}.", prompt.trim_end(), mode.to_prompt_suffix());
         }
+        prompt
+    }
+
+    /// The stage persona's system prompt, if it has a non-empty one.
+    fn persona_system_prompt(&self, stage: &Stage) -> Option<String> {
+        stage
+            .persona
+            .as_deref()
+            .and_then(|p| self.facet_registry.get_persona(p))
+            .filter(|f| !f.system_prompt.is_empty())
+            .map(|f| f.system_prompt.clone())
+    }
+
+    /// Role boundary for the stage's persona and permission level.
+    fn role_boundary(&self, stage: &Stage) -> String {
+        let permission = format!("{:?}", stage.permission).to_lowercase();
+        self.prompt_guard
+            .boundary(stage.persona.as_deref(), &permission)
+    }
+
+    fn injection_entry(
+        &self,
+        stage: &Stage,
+        state: &FlowState,
+        source: &str,
+        suspects: Vec<crate::security::prompt_guard::Suspect>,
+    ) -> crate::security::prompt_guard::AuditEntry {
+        let mut entry = crate::security::prompt_guard::AuditEntry::injection(source, suspects);
+        entry.run_id = state
+            .variables
+            .get("__run_id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        entry.stage = Some(stage.id.clone());
+        entry.agent = stage.persona.clone();
+        entry
+    }
+
+    async fn audit_prompt_injection(&self, entry: &crate::security::prompt_guard::AuditEntry) {
+        tracing::warn!(
+            "Suspected prompt injection in {} ({} span(s) removed)",
+            entry.source,
+            entry.suspects.len()
+        );
+        if let Err(e) = crate::security::prompt_guard::audit(&self.working_dir, entry).await {
+            tracing::warn!("Failed to write security audit entry: {}", e);
+        }
+    }
 
-        parts.join("\n\n")
+    /// The flow's self-rating settings, when the flow has them enabled.
+    fn self_rating_spec(&self, state: &FlowState) -> Option<&super::self_rating::SelfRatingSpec> {
+        self.flows
+            .get(&state.flow_name)
+            .map(|flow| &flow.self_rating)
+            .filter(|spec| spec.enabled)
+    }
+
+    /// Whether `stage` is asked to rate itself: only stages that change the
+    /// tree do work worth rating; readonly planners, reviewers and leaders
+    /// don't.
+    fn rates_itself(&self, stage: &Stage, state: &FlowState) -> bool {
+        stage.permission != MovementPermission::Readonly && self.self_rating_spec(state).is_some()
+    }
+
+    /// Parse the agent's `SELF_RATING=` line out of `output`, attach it as
+    /// `self_rating` / `needs_review`, and record a `self_rating` event.
+    async fn attach_self_rating(
+        &self,
+        stage: &Stage,
+        state: &FlowState,
+        output: &mut serde_json::Value,
+    ) {
+        let Some(spec) = self.self_rating_spec(state) else {
+            return;
+        };
+        let Some(rating) = output
+            .get("output")
+            .and_then(|o| o.as_str())
+            .and_then(super::self_rating::parse_rating)
+        else {
+            return;
+        };
+        let needs_review = rating.needs_review(spec.review_below);
+        if let Some(obj) = output.as_object_mut() {
+            obj.insert("self_rating".into(), serde_json::json!(rating));
+            obj.insert("needs_review".into(), serde_json::json!(needs_review));
+        }
+
+        let run_id = self
+            .event_recorder
+            .as_ref()
+            .map(|r| r.run_id().to_string())
+            .unwrap_or_default();
+        self.record_event(
+            crate::events::Event::new(
+                &run_id,
+                if needs_review {
+                    crate::events::EventLevel::Warn
+                } else {
+                    crate::events::EventLevel::Info
+                },
+                crate::events::EventType::SelfRating,
+                format!(
+                    "Stage '{}' self-rated confidence {}/5, completeness {}/5",
+                    stage.id, rating.confidence, rating.completeness
+                ),
+            )
+            .with_agent(stage.persona.as_deref().unwrap_or("default"))
+            .with_movement(&stage.id)
+            .with_metadata(serde_json::json!({
+                "rating": rating,
+                "needs_review": needs_review,
+            })),
+        )
+        .await;
     }
 
     /// Evaluate routing rules against stage output using the MovementJudge.
@@ -2288,6 +3218,237 @@ impl FlowEngine {
         Ok(None)
     }
 
+    /// Run the sub-agents requested in a successful stage reply and merge
+    /// their results into `output`. Each one is recorded as a ProviderCall
+    /// event attributed to the sub-agent.
+    async fn run_requested_subagents(
+        &self,
+        bridge: &crate::session::bridge::AISessionBridge,
+        stage: &Stage,
+        config: &super::subagent::SubagentConfig,
+        parent: SubagentParent<'_>,
+        output: &mut serde_json::Value,
+    ) {
+        let reply = output
+            .get("output")
+            .and_then(|o| o.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let requests = super::subagent::parse_requests(&reply);
+        if requests.is_empty() {
+            return;
+        }
+        info!(
+            "Stage '{}' requested {} sub-agent(s)",
+            stage.id,
+            requests.len()
+        );
+        let mut context_sources = parent.context_sources.clone();
+        context_sources.push(&reply);
+        let parent = SubagentParent {
+            context_sources,
+            ..parent
+        };
+        let outcomes = super::subagent::run_subagents(bridge, &parent, &requests, config).await;
+
+        let run_id = self
+            .event_recorder
+            .as_ref()
+            .map(|r| r.run_id().to_string())
+            .unwrap_or_default();
+        for outcome in &outcomes {
+            let level = if outcome.status == super::subagent::SubagentStatus::Completed {
+                crate::events::EventLevel::Info
+            } else {
+                crate::events::EventLevel::Warn
+            };
+            self.record_event(
+                crate::events::Event::new(
+                    &run_id,
+                    level,
+                    crate::events::EventType::ProviderCall,
+                    format!("Sub-agent '{}' {}", outcome.id, outcome.status),
+                )
+                .with_movement(&stage.id)
+                .with_agent(&outcome.id)
+                .with_metadata(serde_json::json!({
+                    "subagent": outcome.id,
+                    "status": outcome.status,
+                    "duration_ms": outcome.duration_ms,
+                    "error": outcome.error,
+                })),
+            )
+            .await;
+        }
+        super::subagent::merge_outcomes(output, &outcomes);
+    }
+
+    /// Have an ephemeral reviewer score the attempt's changes (see
+    /// [`super::review`]). The review lands in `output` as `review`; one that
+    /// does not pass, or a reviewer that gives no verdict, is returned as a
+    /// `review` gate failure.
+    async fn review_attempt(
+        &self,
+        bridge: &crate::session::bridge::AISessionBridge,
+        stage: &Stage,
+        spec: &super::review::ReviewSpec,
+        state: &FlowState,
+        parent: SubagentParent<'_>,
+        output: &mut serde_json::Value,
+    ) -> Option<GateFailure> {
+        use super::review;
+
+        let reviewer_id = format!("{}.review", parent.agent_id);
+        let run_id = self
+            .event_recorder
+            .as_ref()
+            .map(|r| r.run_id().to_string())
+            .unwrap_or_default();
+        let same_provider = spec.provider.is_none();
+        let options = crate::session::bridge::MovementExecOptions {
+            provider: match &spec.provider {
+                Some(provider) => crate::providers::ProviderKind::parse(provider),
+                None => parent.options.provider,
+            },
+            model: spec.model.clone().or_else(|| {
+                same_provider
+                    .then(|| parent.options.model.clone())
+                    .flatten()
+            }),
+            provider_args: if same_provider {
+                parent.options.provider_args.clone()
+            } else {
+                Vec::new()
+            },
+            tools: super::permissions::PermissionEnforcer::from_movement(
+                MovementPermission::Readonly,
+                &[],
+            )
+            .available_tools(),
+            system_prompt: self
+                .facet_registry
+                .get_persona(&spec.persona)
+                .filter(|f| !f.system_prompt.is_empty())
+                .map(|f| f.system_prompt.clone()),
+            session_id: None,
+            continuation: crate::session::bridge::ContinuationPolicy::SingleTurn,
+            typed_tools: parent
+                .options
+                .typed_tools
+                .as_ref()
+                .map(|_| MovementPermission::Readonly),
+            stream: None,
+            ..parent.options.clone()
+        };
+        let report = output
+            .get("output")
+            .and_then(|o| o.as_str())
+            .unwrap_or_default();
+        let diff = review::working_diff(parent.work_dir)
+            .await
+            .unwrap_or_else(|| "(not a git repository: inspect the working tree)".to_string());
+        let prompt = review::prompt(
+            spec,
+            &expand_template(&stage.instruction, &state.variables),
+            &truncate_for_context(report, 4000),
+            &diff,
+        );
+
+        info!("Stage '{}': reviewer '{}' started", stage.id, reviewer_id);
+        self.record_event(
+            crate::events::Event::new(
+                &run_id,
+                crate::events::EventLevel::Info,
+                crate::events::EventType::ReviewRequest,
+                format!(
+                    "Reviewer '{}' started for stage '{}'",
+                    reviewer_id, stage.id
+                ),
+            )
+            .with_movement(&stage.id)
+            .with_agent(&reviewer_id),
+        )
+        .await;
+        let result = review::run_reviewer(
+            bridge,
+            &reviewer_id,
+            &prompt,
+            parent.identity,
+            parent.work_dir,
+            &options,
+            std::time::Duration::from_secs(spec.timeout_secs),
+        )
+        .await;
+
+        let (level, message, metadata, failure) = match result {
+            Ok((review, _)) => {
+                let problems = review.problems(spec);
+                let passed = problems.is_empty();
+                if let Some(obj) = output.as_object_mut() {
+                    obj.insert("review".into(), serde_json::json!(review));
+                    obj.insert("review_passed".into(), serde_json::json!(passed));
+                }
+                (
+                    if passed {
+                        crate::events::EventLevel::Info
+                    } else {
+                        crate::events::EventLevel::Warn
+                    },
+                    format!(
+                        "Review of stage '{}' {}: {}",
+                        stage.id,
+                        if passed { "passed" } else { "failed" },
+                        review.score_line()
+                    ),
+                    serde_json::json!({
+                        "reviewer": reviewer_id,
+                        "passed": passed,
+                        "review": review,
+                        "problems": problems,
+                    }),
+                    (!passed).then(|| review::feedback(spec, &review)),
+                )
+            }
+            Err(e) => {
+                warn!("Reviewer '{}' gave no verdict: {:#}", reviewer_id, e);
+                (
+                    crate::events::EventLevel::Warn,
+                    format!("Review of stage '{}' failed: {:#}", stage.id, e),
+                    serde_json::json!({
+                        "reviewer": reviewer_id,
+                        "passed": false,
+                        "error": format!("{e:#}"),
+                    }),
+                    Some(format!(
+                        "# Gate failure: review
+The reviewer could not review this \
+                         attempt: {e:#}
+
+Finish the task and leave the changes in \
+                         the working tree so they can be reviewed."
+                    )),
+                )
+            }
+        };
+        self.record_event(
+            crate::events::Event::new(
+                &run_id,
+                level,
+                crate::events::EventType::ReviewResult,
+                message,
+            )
+            .with_movement(&stage.id)
+            .with_agent(&reviewer_id)
+            .with_metadata(metadata),
+        )
+        .await;
+        failure.map(|feedback| GateFailure {
+            gate: "review".to_string(),
+            feedback,
+            diagnostics: Vec::new(),
+        })
+    }
+
     /// Evaluate `ai()` rule conditions with a real LLM call (one short YES/NO
     /// question per rule). Returns a verdict map keyed by rule index, or
     /// `None` when disabled (`CCSWARM_LLM_JUDGE` unset), no bridge is
@@ -2541,19 +3702,22 @@ impl FlowEngine {
             }
         }
 
-        // 7. JSON schema validation (basic key/type checking)
-        if let Some(serde_json::Value::Object(schema_obj)) = contract.schema.as_ref()
-            && let Some(serde_json::Value::Object(required)) = schema_obj.get("properties")
-            && let serde_json::Value::Object(output_obj) = output
-        {
-            for (key, _prop_schema) in required {
-                if !output_obj.contains_key(key) {
-                    violations.push(ContractViolation {
-                        kind: ViolationKind::SchemaViolation,
-                        message: format!("Schema violation: missing property '{key}'"),
-                    });
+        // 7. JSON schema validation. Agent stages are checked on the value
+        // parsed from their reply; anything else on the output itself.
+        if let Some(schema) = contract.schema.as_ref() {
+            let problems = match (output.get("structured"), output.get("output")) {
+                (Some(structured), _) => super::output_schema::validate(schema, structured),
+                (None, Some(serde_json::Value::String(reply))) => {
+                    super::output_schema::parse_reply(schema, reply)
+                        .err()
+                        .unwrap_or_default()
                 }
-            }
+                _ => super::output_schema::validate(schema, output),
+            };
+            violations.extend(problems.into_iter().map(|problem| ContractViolation {
+                kind: ViolationKind::SchemaViolation,
+                message: format!("Schema violation: {problem}"),
+            }));
         }
 
         ContractValidationResult {
@@ -2642,6 +3806,7 @@ pub fn builtin_flows() -> Vec<Flow> {
             max_stages: 30,
             max_stage_visits: 3,
             on_rate_limit: Vec::new(),
+            self_rating: Default::default(),
             initial_movement: "plan".to_string(),
             stages: vec![
                 Stage {
@@ -2672,7 +3837,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 Stage {
                     id: "sangha".to_string(),
                     persona: Some("planner".to_string()),
@@ -2710,6 +3877,8 @@ pub fn builtin_flows() -> Vec<Flow> {
                         member_tools: None,
                         member_timeout_secs: None,
                     }),
+                    review: None,
+                    typed_tools: false,
                 },
                 Stage {
                     id: "implement".to_string(),
@@ -2752,7 +3921,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 Stage {
                     id: "review".to_string(),
                     persona: Some("reviewer".to_string()),
@@ -2790,7 +3961,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 Stage {
                     id: "fix".to_string(),
                     persona: Some("coder".to_string()),
@@ -2824,7 +3997,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 Stage {
                     id: "complete".to_string(),
                     persona: None,
@@ -2848,7 +4023,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
             ],
             variables: HashMap::new(),
             metadata: HashMap::new(),
@@ -2861,6 +4038,7 @@ pub fn builtin_flows() -> Vec<Flow> {
             max_stages: 20,
             max_stage_visits: 3,
             on_rate_limit: Vec::new(),
+            self_rating: Default::default(),
             initial_movement: "investigate".to_string(),
             stages: vec![
                 Stage {
@@ -2896,7 +4074,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 Stage {
                     id: "summarize".to_string(),
                     persona: Some("writer".to_string()),
@@ -2914,6 +4094,7 @@ pub fn builtin_flows() -> Vec<Flow> {
                         format: "markdown".to_string(),
                         required_sections: vec!["summary".to_string(), "findings".to_string()],
                         schema: None,
+                        schema_retries: default_schema_retries(),
                         output_file: Some("research-report.md".to_string()),
                         required_keys: vec![],
                         min_length: None,
@@ -2932,7 +4113,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
             ],
             variables: HashMap::new(),
             metadata: HashMap::new(),
@@ -2945,6 +4128,7 @@ pub fn builtin_flows() -> Vec<Flow> {
             max_stages: 10,
             max_stage_visits: 3,
             on_rate_limit: Vec::new(),
+            self_rating: Default::default(),
             initial_movement: "review".to_string(),
             stages: vec![
                 Stage {
@@ -2983,7 +4167,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 Stage {
                     id: "fix".to_string(),
                     persona: Some("coder".to_string()),
@@ -3017,7 +4203,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 Stage {
                     id: "done".to_string(),
                     persona: None,
@@ -3041,7 +4229,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
             ],
             variables: HashMap::new(),
             metadata: HashMap::new(),
@@ -3055,6 +4245,7 @@ pub fn builtin_flows() -> Vec<Flow> {
             max_stages: 1,
             max_stage_visits: 3,
             on_rate_limit: Vec::new(),
+            self_rating: Default::default(),
             initial_movement: "execute".to_string(),
             stages: vec![Stage {
                 id: "execute".to_string(),
@@ -3086,7 +4277,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                 call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,            }],
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,            }],
             variables: HashMap::new(),
             metadata: HashMap::new(),
             interactive_mode: None,
@@ -3098,6 +4291,7 @@ pub fn builtin_flows() -> Vec<Flow> {
             max_stages: 10,
             max_stage_visits: 3,
             on_rate_limit: Vec::new(),
+            self_rating: Default::default(),
             initial_movement: "plan".to_string(),
             stages: vec![
                 Stage {
@@ -3128,7 +4322,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 // Parallel hub: dispatches to frontend-impl and backend-impl simultaneously
                 Stage {
                     id: "parallel-implement".to_string(),
@@ -3158,7 +4354,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 // Frontend agent (runs in parallel)
                 Stage {
                     id: "frontend-impl".to_string(),
@@ -3183,7 +4381,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 // Backend agent (runs in parallel)
                 Stage {
                     id: "backend-impl".to_string(),
@@ -3206,9 +4406,19 @@ pub fn builtin_flows() -> Vec<Flow> {
                     retry_delay_ms: default_retry_delay(),
                     pass_previous_response: true,
                     call: None, promotion: Vec::new(),
-            gates: Vec::new(),
+            gates: vec![CommandGate {
+                name: "owasp".to_string(),
+                command: String::new(),
+                timeout_secs: default_gate_timeout_secs(),
+                baseline: false,
+                builtin: Some(BuiltinGate::Owasp {
+                    min_severity: crate::security::owasp::Severity::High,
+                }),
+            }],
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 // Supervisor reviews the combined output
                 Stage {
                     id: "review".to_string(),
@@ -3238,7 +4448,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
                 // Local completion
                 Stage {
                     id: "complete".to_string(),
@@ -3263,7 +4475,9 @@ pub fn builtin_flows() -> Vec<Flow> {
                     call: None, promotion: Vec::new(),
             gates: Vec::new(),
             team_leader: None,
-                    sangha: None,                },
+                    sangha: None,
+                    review: None,
+                    typed_tools: false,                },
             ],
             variables: HashMap::new(),
             metadata: HashMap::new(),
@@ -3292,8 +4506,8 @@ fn is_safe_report_name(name: &str) -> bool {
 /// - `{key}`: replaced with the string form of `variables[key]`.
 /// - `{key_output}`: prefers the `output` field from a JSON object value (the
 ///   standard shape stages emit).
-/// - `{report:<name>}`: reads `.ccswarm/runs/<run_id>/reports/<name>` from disk,
-///   using the `__run_id` variable stashed by `execute_piece_state`. Missing /
+/// - `{report:<name>}`: reads `<name>` from the run's reports directory, using
+///   the `__reports_dir` variable stashed by `execute_piece_state`. Missing /
 ///   unsafe names expand to an empty string so the prompt doesn't leak the
 ///   literal token. Adopted from takt's `output_contracts` — replaces brittle
 ///   `{plan_output}` state-variable chaining with a named on-disk contract.
@@ -3337,8 +4551,8 @@ fn expand_report_references(
         return template.to_string();
     }
 
-    let run_id = variables
-        .get("__run_id")
+    let reports_dir = variables
+        .get("__reports_dir")
         .and_then(|v| v.as_str())
         .unwrap_or("");
 
@@ -3358,21 +4572,17 @@ fn expand_report_references(
         let name = &after_prefix[..end];
         let remainder = &after_prefix[end + 1..];
 
-        if run_id.is_empty() || !is_safe_report_name(name) {
+        if reports_dir.is_empty() || !is_safe_report_name(name) {
             // Silent empty-expand: surfacing the raw token would leak internals
             // into the prompt and confuse the model.
             tracing::warn!(
-                "Skipping `{{report:{}}}` expansion (run_id_empty={}, unsafe_name={})",
+                "Skipping `{{report:{}}}` expansion (no_run={}, unsafe_name={})",
                 name,
-                run_id.is_empty(),
+                reports_dir.is_empty(),
                 !is_safe_report_name(name)
             );
         } else {
-            let path = std::path::PathBuf::from(".ccswarm")
-                .join("runs")
-                .join(run_id)
-                .join("reports")
-                .join(name);
+            let path = std::path::Path::new(reports_dir).join(name);
             match std::fs::read_to_string(&path) {
                 Ok(content) => out.push_str(&truncate_for_context(&content, 8000)),
                 Err(e) => tracing::warn!(
@@ -3404,16 +4614,153 @@ fn truncate_for_context(s: &str, max_len: usize) -> String {
     }
 }
 
+/// The first failing command gate of a stage.
+struct GateFailure {
+    gate: String,
+    /// Bounded, prompt-ready block (stdout/stderr each ≤1000 chars, or ≤4000
+    /// when sampled down to their salient lines) the engine appends to the
+    /// instruction before re-running the stage.
+    feedback: String,
+    /// What the output parser's diagnostic plugins found in the command output.
+    diagnostics: Vec<ai_session::output::Diagnostic>,
+}
+
+/// Diagnostics listed in gate feedback; the rest are only counted.
+const GATE_FEEDBACK_DIAGNOSTICS: usize = 30;
+
+/// One stream of a failed gate for the retry prompt: the salient lines when
+/// the sampler cuts it, otherwise its first 1000 characters.
+async fn gate_output(
+    parser: &ai_session::output::OutputParser,
+    sampler: &crate::session::sampling::ContextSampler,
+    gate: &str,
+    stream: &str,
+    text: &str,
+) -> String {
+    let ingested = sampler
+        .ingest(parser, "gates", &format!("gate:{gate}:{stream}"), text)
+        .await;
+    if ingested == text {
+        truncate_for_context(text, 1000)
+    } else {
+        truncate_for_context(&ingested, 4000)
+    }
+}
+
+/// Where gate commands report to the run's timeline.
+struct GateEvents<'a> {
+    recorder: &'a crate::events::EventRecorder,
+    stage: &'a str,
+}
+
+impl GateEvents<'_> {
+    async fn record(
+        &self,
+        level: crate::events::EventLevel,
+        event_type: crate::events::EventType,
+        message: String,
+        metadata: serde_json::Value,
+    ) {
+        let event = crate::events::Event::new(self.recorder.run_id(), level, event_type, message)
+            .with_movement(self.stage)
+            .with_metadata(metadata);
+        if let Err(e) = self.recorder.record(event).await {
+            warn!("Failed to record event: {}", e);
+        }
+    }
+
+    /// `command_end` (and `output_burst` when it printed anything) for one
+    /// finished gate command.
+    async fn command_finished(
+        &self,
+        gate: &str,
+        result: &std::result::Result<
+            std::io::Result<std::process::Output>,
+            tokio::time::error::Elapsed,
+        >,
+        elapsed: std::time::Duration,
+    ) {
+        use crate::events::{EventLevel, EventType};
+
+        let (status, exit_code, output) = match result {
+            Err(_) => ("timeout", None, None),
+            Ok(Err(_)) => ("spawn_failed", None, None),
+            Ok(Ok(output)) if output.status.success() => {
+                ("passed", output.status.code(), Some(output))
+            }
+            Ok(Ok(output)) => ("failed", output.status.code(), Some(output)),
+        };
+        let level = if status == "passed" {
+            EventLevel::Info
+        } else {
+            EventLevel::Warn
+        };
+        self.record(
+            level,
+            EventType::CommandEnd,
+            format!("Gate '{}' {}", gate, status.replace('_', " ")),
+            serde_json::json!({
+                "gate": gate,
+                "status": status,
+                "exit_code": exit_code,
+                "elapsed_ms": elapsed.as_millis() as u64,
+            }),
+        )
+        .await;
+        if let Some(output) = output {
+            let bytes = output.stdout.len() + output.stderr.len();
+            if bytes > 0 {
+                let lines = output.stdout.split(|b| *b == b'\n').count()
+                    + output.stderr.split(|b| *b == b'\n').count()
+                    - 2;
+                self.record(
+                    EventLevel::Debug,
+                    EventType::OutputBurst,
+                    format!("{} lines of output from gate '{}'", lines, gate),
+                    serde_json::json!({
+                        "source": format!("gate:{gate}"),
+                        "bytes": bytes,
+                        "lines": lines,
+                    }),
+                )
+                .await;
+            }
+        }
+    }
+}
+
 /// Run a stage's command gates sequentially in `work_dir`. Returns `None`
-/// when every gate passes, or `Some((gate_name, feedback))` for the first
-/// failure — a bounded, prompt-ready block (stdout/stderr each ≤1000 chars)
-/// the engine appends to the instruction before re-running the stage.
+/// when every gate passes, or the first failure.
 async fn run_command_gates(
     gates: &[CommandGate],
     work_dir: &std::path::Path,
-) -> Option<(String, String)> {
+    redactor: &crate::redaction::Redactor,
+    parser: &ai_session::output::OutputParser,
+    sampler: &crate::session::sampling::ContextSampler,
+    quality: &crate::config::QualityConfig,
+    events: Option<&GateEvents<'_>>,
+) -> Option<GateFailure> {
     for gate in gates {
+        if let Some(builtin) = &gate.builtin {
+            match run_builtin_gate(gate, builtin, work_dir, redactor, quality).await {
+                Some(failure) => return Some(failure),
+                None => continue,
+            }
+        }
         info!("Running gate '{}': {}", gate.name, gate.command);
+        // The command line can name hosts too, so it is redacted like its output.
+        let command = redactor.redact(&gate.command);
+        if let Some(events) = events {
+            events
+                .record(
+                    crate::events::EventLevel::Info,
+                    crate::events::EventType::CommandStart,
+                    format!("Gate '{}' started: {}", gate.name, command),
+                    serde_json::json!({ "gate": gate.name, "command": command }),
+                )
+                .await;
+        }
+        let started = std::time::Instant::now();
         let result = tokio::time::timeout(
             std::time::Duration::from_secs(gate.timeout_secs),
             tokio::process::Command::new("sh")
@@ -3422,60 +4769,563 @@ async fn run_command_gates(
                 .current_dir(work_dir)
                 .output(),
         )
+        .instrument(tracing::info_span!("quality.gate", gate = %gate.name))
         .await;
+        if let Some(events) = events {
+            events
+                .command_finished(&gate.name, &result, started.elapsed())
+                .await;
+        }
 
+        let mut diagnostics = Vec::new();
         let feedback = match result {
             Err(_) => format!(
                 "# Gate failure: {}\nCommand `{}` timed out after {}s. \
                  Make the change converge faster or fix what the command checks.",
-                gate.name, gate.command, gate.timeout_secs
+                gate.name, command, gate.timeout_secs
             ),
             Ok(Err(e)) => format!(
                 "# Gate failure: {}\nCommand `{}` could not be spawned: {}",
-                gate.name, gate.command, e
+                gate.name, command, e
             ),
             Ok(Ok(output)) if output.status.success() => continue,
             Ok(Ok(output)) => {
-                let stdout = truncate_for_context(&String::from_utf8_lossy(&output.stdout), 1000);
-                let stderr = truncate_for_context(&String::from_utf8_lossy(&output.stderr), 1000);
+                let stdout = redactor
+                    .redact(&String::from_utf8_lossy(&output.stdout))
+                    .into_owned();
+                let stderr = redactor
+                    .redact(&String::from_utf8_lossy(&output.stderr))
+                    .into_owned();
+                // Diagnostics are parsed from the full output, before it is
+                // truncated for the prompt.
+                if let Some((_, found)) = parser.diagnostics(&format!("{stdout}\n{stderr}")) {
+                    diagnostics = found;
+                }
+                if let Some(events) = events
+                    && let Some(first) = diagnostics.first()
+                {
+                    events
+                        .record(
+                            crate::events::EventLevel::Warn,
+                            crate::events::EventType::ErrorDetected,
+                            format!(
+                                "{} diagnostics from gate '{}': {}",
+                                diagnostics.len(),
+                                gate.name,
+                                first.to_line()
+                            ),
+                            serde_json::json!({
+                                "source": format!("gate:{}", gate.name),
+                                "count": diagnostics.len(),
+                            }),
+                        )
+                        .await;
+                }
+                let mut regressions = String::new();
+                if gate.baseline
+                    && let Some(comparison) = super::quality_baseline::judge_gate(
+                        work_dir,
+                        &gate.name,
+                        &format!("{stdout}\n{stderr}"),
+                    )
+                    .await
+                {
+                    if comparison.passed() {
+                        info!(
+                            "Gate '{}' failed only on its {} baselined issue(s)",
+                            gate.name, comparison.found
+                        );
+                        if let Some(events) = events {
+                            events
+                                .record(
+                                    crate::events::EventLevel::Info,
+                                    crate::events::EventType::CommandEnd,
+                                    format!("Gate '{}' passed against its baseline", gate.name),
+                                    serde_json::json!({
+                                        "gate": gate.name,
+                                        "status": "baselined",
+                                        "baseline": comparison,
+                                    }),
+                                )
+                                .await;
+                        }
+                        continue;
+                    }
+                    regressions = if comparison.new.is_empty() {
+                        format!(
+                            "## Over the baseline\n{} known issues, {} allowed now: fix {} of them.\n\n",
+                            comparison.found,
+                            comparison.allowed,
+                            comparison.found - comparison.allowed
+                        )
+                    } else {
+                        let mut block =
+                            format!("## Not in the baseline ({})\n", comparison.new.len());
+                        for issue in comparison.new.iter().take(GATE_FEEDBACK_DIAGNOSTICS) {
+                            block.push_str(&format!("- {issue}\n"));
+                        }
+                        block.push_str("Only these are new; the other issues are known.\n\n");
+                        block
+                    };
+                }
+                let listed = if diagnostics.is_empty() {
+                    String::new()
+                } else {
+                    let mut block = format!("## Diagnostics ({})\n", diagnostics.len());
+                    for d in diagnostics.iter().take(GATE_FEEDBACK_DIAGNOSTICS) {
+                        block.push_str(&format!("- {}\n", d.to_line()));
+                    }
+                    if diagnostics.len() > GATE_FEEDBACK_DIAGNOSTICS {
+                        block.push_str(&format!(
+                            "- ... and {} more\n",
+                            diagnostics.len() - GATE_FEEDBACK_DIAGNOSTICS
+                        ));
+                    }
+                    block.push('\n');
+                    block
+                };
+                let stdout = gate_output(parser, sampler, &gate.name, "stdout", &stdout).await;
+                let stderr = gate_output(parser, sampler, &gate.name, "stderr", &stderr).await;
                 format!(
                     "# Gate failure: {} (exit code {})\nCommand: `{}`\n\n\
-                     ## stdout\n{}\n\n## stderr\n{}\n\n\
+                     {}{}## stdout\n{}\n\n## stderr\n{}\n\n\
                      Fix the issues above and ensure `{}` passes.",
                     gate.name,
                     output.status.code().unwrap_or(-1),
-                    gate.command,
+                    command,
+                    regressions,
+                    listed,
                     stdout,
                     stderr,
-                    gate.command
+                    command
                 )
             }
         };
-        return Some((gate.name.clone(), feedback));
+        return Some(GateFailure {
+            gate: gate.name.clone(),
+            feedback,
+            diagnostics,
+        });
     }
     None
 }
 
-fn stage_output_succeeded(output: &serde_json::Value) -> bool {
-    output
-        .get("status")
-        .and_then(|status| status.as_str())
-        .is_some_and(|status| status == "completed")
+async fn run_builtin_gate(
+    gate: &CommandGate,
+    builtin: &BuiltinGate,
+    work_dir: &std::path::Path,
+    redactor: &crate::redaction::Redactor,
+    quality: &crate::config::QualityConfig,
+) -> Option<GateFailure> {
+    match builtin {
+        BuiltinGate::Owasp { min_severity } => run_owasp_gate(gate, *min_severity, work_dir).await,
+        BuiltinGate::Coverage { tool, thresholds } => {
+            run_coverage_gate(gate, *tool, thresholds, work_dir).await
+        }
+        BuiltinGate::TestMatrix => {
+            run_test_matrix_gate(gate, quality.matrix.as_ref(), work_dir, redactor).await
+        }
+        BuiltinGate::CommitHygiene { rules } => {
+            run_commit_hygiene_gate(gate, rules, work_dir).await
+        }
+        BuiltinGate::Quality { profile } => {
+            run_quality_gate(gate, profile.as_deref(), quality, work_dir, redactor).await
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_piece_from_yaml() {
-        let yaml = r#"
-name: test-flow
-description: "A test flow"
-max_stages: 10
-initial_movement: start
+async fn run_quality_gate(
+    gate: &CommandGate,
+    profile: Option<&str>,
+    quality: &crate::config::QualityConfig,
+    work_dir: &std::path::Path,
+    redactor: &crate::redaction::Redactor,
+) -> Option<GateFailure> {
+    use crate::workflow::{quality_checks, quality_profiles};
+
+    let failure = |detail: String| GateFailure {
+        gate: gate.name.clone(),
+        feedback: format!("# Gate failure: {}\n{}", gate.name, detail),
+        diagnostics: Vec::new(),
+    };
+    let profile = match profile {
+        Some(name) => Some(name.to_string()),
+        None => quality_profiles::current_branch(work_dir)
+            .await
+            .and_then(|branch| quality_profiles::for_branch(quality, &branch).map(str::to_string)),
+    };
+    let selected =
+        quality_checks::project_checks(&quality.checks, work_dir).and_then(
+            |checks| match &profile {
+                Some(name) => quality_profiles::checks(quality, name, checks),
+                None => Ok(checks),
+            },
+        );
+    let selected = match selected {
+        Ok(selected) => selected,
+        Err(e) => return Some(failure(format!("Quality checks unavailable: {e:#}"))),
+    };
+    info!(
+        "Running gate '{}': {} quality check(s), profile {}",
+        gate.name,
+        selected.len(),
+        profile.as_deref().unwrap_or("(all)")
+    );
+    let options = quality_checks::RunOptions {
+        jobs: quality_checks::default_jobs(),
+        fail_fast: false,
+        target_dir: quality_checks::shared_target_dir(work_dir),
+        timeout: quality_checks::DEFAULT_TIMEOUT,
+    };
+    let mut report = quality_checks::run(&selected, work_dir, &options, redactor, &|_| {})
+        .instrument(tracing::info_span!("quality.gate", gate = %gate.name))
+        .await;
+    report.profile = profile;
+    if let Err(e) = quality_checks::save_report(work_dir, &report).await {
+        warn!("Failed to write quality check report: {e:#}");
+    }
+    if report.passed() {
+        return None;
+    }
 
-stages:
+    let mut detail = String::new();
+    if let Some(name) = &report.profile {
+        detail.push_str(&format!("Quality profile: {name}\n"));
+    }
+    for check in report
+        .checks
+        .iter()
+        .filter(|c| c.state != quality_checks::CheckState::Passed)
+    {
+        detail.push_str(&format!(
+            "\n## {}\nCommand: `{}`\n",
+            check.name,
+            redactor.redact(&check.command)
+        ));
+        if check.timed_out {
+            detail.push_str(&format!(
+                "Timed out after {}s.\n",
+                options.timeout.as_secs()
+            ));
+        } else if !check.output_tail.is_empty() {
+            detail.push_str(&check.output_tail);
+            detail.push('\n');
+        }
+    }
+    detail.push_str("\nFix the failures above so every quality check passes.");
+    Some(failure(detail))
+}
+
+async fn run_commit_hygiene_gate(
+    gate: &CommandGate,
+    rules: &crate::workflow::commit_hygiene::HygieneRules,
+    work_dir: &std::path::Path,
+) -> Option<GateFailure> {
+    use crate::workflow::commit_hygiene;
+
+    info!("Running gate '{}': commit hygiene", gate.name);
+    let failure = |detail: String| GateFailure {
+        gate: gate.name.clone(),
+        feedback: format!("# Gate failure: {}\n{}", gate.name, detail),
+        diagnostics: Vec::new(),
+    };
+    let report = match tokio::time::timeout(
+        std::time::Duration::from_secs(gate.timeout_secs),
+        commit_hygiene::check(work_dir, rules),
+    )
+    .instrument(tracing::info_span!("quality.gate", gate = %gate.name))
+    .await
+    {
+        Ok(Ok(report)) => report,
+        Ok(Err(e)) => return Some(failure(format!("Commit hygiene check failed: {e:#}"))),
+        Err(_) => {
+            return Some(failure(format!(
+                "Commit hygiene check timed out after {}s.",
+                gate.timeout_secs
+            )));
+        }
+    };
+    if report.passed() {
+        return None;
+    }
+    Some(failure(format!(
+        "{}\nApply the fixes above and make sure the branch's history is clean.",
+        commit_hygiene::feedback(&report)
+    )))
+}
+
+async fn run_test_matrix_gate(
+    gate: &CommandGate,
+    matrix: Option<&super::test_matrix::TestMatrix>,
+    work_dir: &std::path::Path,
+    redactor: &crate::redaction::Redactor,
+) -> Option<GateFailure> {
+    use crate::workflow::test_matrix;
+
+    let failure = |detail: String| GateFailure {
+        gate: gate.name.clone(),
+        feedback: format!("# Gate failure: {}\n{}", gate.name, detail),
+        diagnostics: Vec::new(),
+    };
+    let Some(matrix) = matrix else {
+        return Some(failure(
+            "No test matrix is configured; add `quality.matrix` to ccswarm.json.".to_string(),
+        ));
+    };
+    info!(
+        "Running gate '{}': test matrix ({} cells)",
+        gate.name,
+        matrix.cells().len()
+    );
+    let report = test_matrix::run(matrix, work_dir, redactor)
+        .instrument(tracing::info_span!("quality.gate", gate = %gate.name))
+        .await;
+    if let Err(e) = test_matrix::save_report(work_dir, &report).await {
+        warn!("Failed to write test matrix report: {e:#}");
+    }
+    if report.passed() {
+        info!("Gate '{}' passed: {}", gate.name, report.table().trim_end());
+        return None;
+    }
+
+    let mut detail = format!("## Matrix\n{}", report.table());
+    for cell in report.failed() {
+        detail.push_str(&format!(
+            "\n## {}\nCommand: `{}`\n",
+            cell.cell, cell.command
+        ));
+        if cell.timed_out {
+            detail.push_str(&format!("Timed out after {}s.\n", matrix.timeout_secs));
+        } else if !cell.output_tail.is_empty() {
+            detail.push_str(&cell.output_tail);
+            detail.push('\n');
+        }
+    }
+    detail.push_str("\nFix the failures above so every cell of the matrix passes.");
+    Some(failure(detail))
+}
+
+async fn run_owasp_gate(
+    gate: &CommandGate,
+    min_severity: crate::security::owasp::Severity,
+    work_dir: &std::path::Path,
+) -> Option<GateFailure> {
+    info!("Running gate '{}': owasp scan", gate.name);
+    let changed = changed_files(work_dir).await;
+    let root = work_dir.to_path_buf();
+    let scan = tokio::task::spawn_blocking(move || match changed {
+        Some(files) => crate::security::owasp::scan_files(&root, &files),
+        None => crate::security::owasp::scan_project(&root),
+    });
+    let report = match tokio::time::timeout(std::time::Duration::from_secs(gate.timeout_secs), scan)
+        .instrument(tracing::info_span!("quality.gate", gate = %gate.name))
+        .await
+    {
+        Ok(Ok(report)) => report,
+        Ok(Err(e)) => {
+            return Some(GateFailure {
+                gate: gate.name.clone(),
+                feedback: format!("# Gate failure: {}\nOWASP scan failed: {}", gate.name, e),
+                diagnostics: Vec::new(),
+            });
+        }
+        Err(_) => {
+            return Some(GateFailure {
+                gate: gate.name.clone(),
+                feedback: format!(
+                    "# Gate failure: {}\nOWASP scan timed out after {}s.",
+                    gate.name, gate.timeout_secs
+                ),
+                diagnostics: Vec::new(),
+            });
+        }
+    };
+
+    let findings = report.at_or_above(min_severity);
+    if findings.is_empty() {
+        return None;
+    }
+    let mut listed = String::new();
+    for finding in findings.iter().take(GATE_FEEDBACK_DIAGNOSTICS) {
+        listed.push_str(&format!(
+            "- {}\n  `{}`\n",
+            finding.to_line(),
+            finding.snippet
+        ));
+    }
+    if findings.len() > GATE_FEEDBACK_DIAGNOSTICS {
+        listed.push_str(&format!(
+            "- ... and {} more\n",
+            findings.len() - GATE_FEEDBACK_DIAGNOSTICS
+        ));
+    }
+    Some(GateFailure {
+        gate: gate.name.clone(),
+        feedback: format!(
+            "# Gate failure: {}\nOWASP scan found {} issue(s) at {} severity or above \
+             in {} file(s):\n{}\n\
+             Fix each issue, or append `{}` to a line whose finding is a false positive.",
+            gate.name,
+            findings.len(),
+            min_severity,
+            report.files_scanned,
+            listed,
+            crate::security::owasp::IGNORE_MARKER
+        ),
+        diagnostics: findings.iter().map(|f| f.to_diagnostic()).collect(),
+    })
+}
+
+/// Files listed under "largest drops" in coverage gate feedback.
+const COVERAGE_FEEDBACK_FILES: usize = 10;
+
+async fn run_coverage_gate(
+    gate: &CommandGate,
+    tool: crate::workflow::coverage::CoverageTool,
+    thresholds: &crate::workflow::coverage::Thresholds,
+    work_dir: &std::path::Path,
+) -> Option<GateFailure> {
+    use crate::workflow::coverage;
+
+    info!("Running gate '{}': coverage ({})", gate.name, tool);
+    let failure = |detail: String| GateFailure {
+        gate: gate.name.clone(),
+        feedback: format!("# Gate failure: {}\n{}", gate.name, detail),
+        diagnostics: Vec::new(),
+    };
+    let collected =
+        tokio::time::timeout(std::time::Duration::from_secs(gate.timeout_secs), async {
+            let report = coverage::collect(work_dir, tool).await?;
+            let packages = coverage::workspace_packages(work_dir).await?;
+            Ok::<_, anyhow::Error>((report, packages))
+        })
+        .instrument(tracing::info_span!("quality.gate", gate = %gate.name))
+        .await;
+    let (report, packages) = match collected {
+        Ok(Ok(collected)) => collected,
+        Ok(Err(e)) => return Some(failure(format!("Coverage could not be collected: {e:#}"))),
+        Err(_) => {
+            return Some(failure(format!(
+                "Coverage collection timed out after {}s.",
+                gate.timeout_secs
+            )));
+        }
+    };
+
+    let snapshot =
+        coverage::CoverageSnapshot::new(&report, &packages, coverage::head_commit(work_dir).await);
+    let history = coverage::history_path(work_dir).await;
+    let previous = coverage::load_history(&history).await.pop();
+    let evaluation = coverage::evaluate(&snapshot, thresholds, previous.as_ref());
+    if evaluation.passed() {
+        // Only passing runs become the baseline, so a retry after a failure
+        // is still compared with the last good state.
+        if let Err(e) = coverage::record_snapshot(&history, &snapshot).await {
+            warn!("Failed to record coverage history: {e:#}");
+        }
+        return None;
+    }
+
+    let mut detail = format!(
+        "Line coverage is {:.1}% ({}/{} lines) and misses its minimums:\n",
+        snapshot.total.percent(),
+        snapshot.total.covered,
+        snapshot.total.coverable
+    );
+    for violation in &evaluation.violations {
+        detail.push_str(&format!("- {violation}\n"));
+    }
+    if evaluation.drops.is_empty() {
+        // Nothing to compare with (or nothing fell): point at the least
+        // covered files instead.
+        let mut weakest: Vec<_> = report
+            .files
+            .iter()
+            .filter(|file| file.lines.covered < file.lines.coverable)
+            .collect();
+        weakest.sort_by(|a, b| a.lines.percent().total_cmp(&b.lines.percent()));
+        if !weakest.is_empty() {
+            detail.push_str("\n## Least covered files\n");
+            for file in weakest.iter().take(COVERAGE_FEEDBACK_FILES) {
+                detail.push_str(&format!(
+                    "- {}: {:.1}% ({}/{} lines)\n",
+                    file.path.display(),
+                    file.lines.percent(),
+                    file.lines.covered,
+                    file.lines.coverable
+                ));
+            }
+        }
+    } else {
+        detail.push_str("\n## Largest drops since the last passing run\n");
+        for drop in evaluation.drops.iter().take(COVERAGE_FEEDBACK_FILES) {
+            detail.push_str(&format!(
+                "- {}: {:.1}% -> {:.1}% (-{:.1})\n",
+                drop.path.display(),
+                drop.before,
+                drop.after,
+                drop.points()
+            ));
+        }
+        if evaluation.drops.len() > COVERAGE_FEEDBACK_FILES {
+            detail.push_str(&format!(
+                "- ... and {} more\n",
+                evaluation.drops.len() - COVERAGE_FEEDBACK_FILES
+            ));
+        }
+    }
+    detail
+        .push_str("\nAdd tests that exercise the code you changed, starting with the files above.");
+    Some(failure(detail))
+}
+
+/// Files changed since `HEAD` plus untracked files in `work_dir`, relative
+/// to it. `None` when `work_dir` is not in a git repository with commits.
+async fn changed_files(work_dir: &std::path::Path) -> Option<Vec<std::path::PathBuf>> {
+    let mut files = Vec::new();
+    for args in [
+        &["diff", "--name-only", "--relative", "HEAD"][..],
+        &["ls-files", "--others", "--exclude-standard"][..],
+    ] {
+        let output = tokio::process::Command::new("git")
+            .args(args)
+            .current_dir(work_dir)
+            .output()
+            .await
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        files.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(std::path::PathBuf::from),
+        );
+    }
+    Some(files)
+}
+
+fn stage_output_succeeded(output: &serde_json::Value) -> bool {
+    output
+        .get("status")
+        .and_then(|status| status.as_str())
+        .is_some_and(|status| status == "completed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piece_from_yaml() {
+        let yaml = r#"
+name: test-flow
+description: "A test flow"
+max_stages: 10
+initial_movement: start
+
+stages:
   - id: start
     persona: planner
     instruction: "Plan the task"
@@ -3589,6 +5439,14 @@ stages:
         assert!(flow.is_terminal("end"));
     }
 
+    /// An engine without a bridge whose audit log and runs go under `dir`.
+    /// Offline outputs echo the fenced prompt, which the prompt guard flags.
+    fn offline_engine(dir: &tempfile::TempDir) -> FlowEngine {
+        let mut engine = FlowEngine::new();
+        engine.set_working_dir(dir.path().to_path_buf());
+        engine
+    }
+
     #[tokio::test]
     async fn test_piece_engine_execute() {
         let yaml = r#"
@@ -3604,7 +5462,8 @@ stages:
     instruction: "Step 2 (terminal)"
 "#;
         let flow = Flow::from_yaml(yaml).expect("parse failed");
-        let mut engine = FlowEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = offline_engine(&dir);
         engine.flows.insert("exec-test".to_string(), flow);
 
         let state = engine
@@ -3636,7 +5495,8 @@ stages:
         next: ping
 "#;
         let flow = Flow::from_yaml(yaml).expect("parse failed");
-        let mut engine = FlowEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = offline_engine(&dir);
         engine.flows.insert("loop-test".to_string(), flow);
 
         let state = engine
@@ -3679,7 +5539,8 @@ stages:
     instruction: "terminal"
 "#;
         let flow = Flow::from_yaml(yaml).expect("parse failed");
-        let mut engine = FlowEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = offline_engine(&dir);
         engine.flows.insert("par-all".to_string(), flow);
 
         let state = engine
@@ -3724,7 +5585,8 @@ stages:
     instruction: "terminal"
 "#;
         let flow = Flow::from_yaml(yaml).expect("parse failed");
-        let mut engine = FlowEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = offline_engine(&dir);
         engine.flows.insert("par-any".to_string(), flow);
 
         let state = engine
@@ -3783,6 +5645,55 @@ stages:
         assert_eq!(model.as_deref(), Some("gpt-5"));
     }
 
+    #[test]
+    fn test_stage_agent_picks_provider_model_and_args() {
+        let yaml = r#"
+name: mixed
+initial_movement: plan
+stages:
+  - id: plan
+    instruction: "plan it"
+    agent: planner
+  - id: pinned
+    instruction: "do it"
+    agent: planner
+    provider: codex
+    model: gpt-5
+"#;
+        let flow = Flow::from_yaml(yaml).expect("parse failed");
+        let mut engine = FlowEngine::new();
+        engine.set_default_provider(crate::providers::ProviderKind::Claude);
+        let planner: crate::config::AgentConfig = serde_json::from_value(serde_json::json!({
+            "specialization": "planning",
+            "worktree": "../worktrees/planner",
+            "branch": "feature/planner",
+            "provider": "gemini",
+            "gemini_config": { "model": "gemini-2.5-pro", "args": ["--debug"] },
+            "codex_config": { "args": ["-c", "model_reasoning_effort=high"] }
+        }))
+        .unwrap();
+        engine.set_agents(HashMap::from([("planner".to_string(), planner)]));
+        let state = flow.create_state();
+
+        // The agent's provider wins over --provider; its section fills the model.
+        let (provider, model) = engine.resolve_effective_provider(&flow.stages[0], &state);
+        assert_eq!(provider, Some(crate::providers::ProviderKind::Gemini));
+        assert_eq!(model.as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(
+            engine.stage_provider_args(&flow.stages[0], provider),
+            ["--debug"]
+        );
+
+        // Stage YAML still wins, and takes that provider's section.
+        let (provider, model) = engine.resolve_effective_provider(&flow.stages[1], &state);
+        assert_eq!(provider, Some(crate::providers::ProviderKind::Codex));
+        assert_eq!(model.as_deref(), Some("gpt-5"));
+        assert_eq!(
+            engine.stage_provider_args(&flow.stages[1], provider),
+            ["-c", "model_reasoning_effort=high"]
+        );
+    }
+
     #[test]
     fn test_promotion_skipped_without_visit_count() {
         // Parallel sub-stages run with __visit_count stripped; promotion must
@@ -3876,17 +5787,83 @@ stages:
         );
     }
 
+    fn parser() -> ai_session::output::OutputParser {
+        ai_session::output::OutputParser::new()
+    }
+
     #[tokio::test]
     async fn test_command_gates_pass_returns_none() {
         let gates = vec![CommandGate {
             name: "noop".to_string(),
             command: "true".to_string(),
             timeout_secs: 30,
+            baseline: false,
+            builtin: None,
         }];
-        let result = run_command_gates(&gates, std::path::Path::new("/tmp")).await;
+        let result = run_command_gates(
+            &gates,
+            std::path::Path::new("/tmp"),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await;
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn test_baselined_gate_fails_only_on_new_issues() {
+        use crate::workflow::quality_baseline::{self, Baseline, CheckBaseline};
+
+        let dir = tempfile::tempdir().unwrap();
+        let now = chrono::Utc::now();
+        let mut baseline = Baseline::new(None, now);
+        baseline.checks.insert(
+            "test".to_string(),
+            CheckBaseline::new(["test failed: legacy::flaky".to_string()].into(), now),
+        );
+        let path = quality_baseline::baseline_path(dir.path()).await;
+        quality_baseline::save(&path, &baseline).await.unwrap();
+
+        let gate = |failing: &str| CommandGate {
+            name: "test".to_string(),
+            command: format!("printf '{failing}'; exit 101"),
+            timeout_secs: 30,
+            builtin: None,
+            baseline: true,
+        };
+        let run = |gate: CommandGate| {
+            let dir = dir.path().to_path_buf();
+            async move {
+                run_command_gates(
+                    &[gate],
+                    &dir,
+                    &Default::default(),
+                    &parser(),
+                    &Default::default(),
+                    &Default::default(),
+                    None,
+                )
+                .await
+            }
+        };
+
+        assert!(
+            run(gate("test legacy::flaky ... FAILED\\n"))
+                .await
+                .is_none()
+        );
+        let failure = run(gate(
+            "test legacy::flaky ... FAILED\\ntest api::health ... FAILED\\n",
+        ))
+        .await
+        .expect("a new failing test fails the gate");
+        assert!(failure.feedback.contains("## Not in the baseline (1)"));
+        assert!(failure.feedback.contains("- test failed: api::health"));
+    }
+
     #[tokio::test]
     async fn test_command_gates_failure_returns_bounded_feedback() {
         let gates = vec![
@@ -3894,16 +5871,32 @@ stages:
                 name: "ok".to_string(),
                 command: "true".to_string(),
                 timeout_secs: 30,
+                baseline: false,
+                builtin: None,
             },
             CommandGate {
                 name: "boom".to_string(),
                 command: "echo broken output; echo to stderr 1>&2; exit 3".to_string(),
                 timeout_secs: 30,
+                baseline: false,
+                builtin: None,
             },
         ];
-        let (name, feedback) = run_command_gates(&gates, std::path::Path::new("/tmp"))
-            .await
-            .expect("second gate fails");
+        let GateFailure {
+            gate: name,
+            feedback,
+            ..
+        } = run_command_gates(
+            &gates,
+            std::path::Path::new("/tmp"),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("second gate fails");
         assert_eq!(name, "boom");
         assert!(feedback.contains("# Gate failure: boom (exit code 3)"));
         assert!(feedback.contains("broken output"));
@@ -3916,10 +5909,20 @@ stages:
             name: "noisy".to_string(),
             command: "yes x | head -c 5000; exit 1".to_string(),
             timeout_secs: 30,
+            baseline: false,
+            builtin: None,
         }];
-        let (_, feedback) = run_command_gates(&gates, std::path::Path::new("/tmp"))
-            .await
-            .expect("gate fails");
+        let GateFailure { feedback, .. } = run_command_gates(
+            &gates,
+            std::path::Path::new("/tmp"),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("gate fails");
         assert!(feedback.contains("[truncated]"));
         // 5000 chars of stdout must have been bounded to ~1000.
         assert!(
@@ -3929,20 +5932,470 @@ stages:
         );
     }
 
+    #[tokio::test]
+    async fn test_command_gates_sample_chatty_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let gates = vec![CommandGate {
+            name: "chatty".to_string(),
+            command: "seq 1 3000; echo 'error: widget.rs is broken'; seq 1 50; exit 1".to_string(),
+            timeout_secs: 30,
+            baseline: false,
+            builtin: None,
+        }];
+        let sampler = crate::session::sampling::ContextSampler::new(Default::default())
+            .with_log_store(dir.path());
+        let GateFailure { feedback, .. } = run_command_gates(
+            &gates,
+            dir.path(),
+            &Default::default(),
+            &parser(),
+            &sampler,
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("gate fails");
+        assert!(feedback.contains("[output sampled: kept"), "{feedback}");
+        assert!(feedback.contains("error: widget.rs is broken"));
+        assert!(feedback.contains("Full output: "));
+        let log = dir.path().join(".ccswarm/logs/output-gates.log");
+        let stored = tokio::fs::read_to_string(log).await.unwrap();
+        assert!(stored.contains("gate:chatty:stdout (3051 lines)"));
+    }
+
+    #[tokio::test]
+    async fn test_command_gates_redact_output() {
+        let gates = vec![CommandGate {
+            name: "leaky".to_string(),
+            command: "echo connect db-7.corp.example.com; exit 1".to_string(),
+            timeout_secs: 30,
+            baseline: false,
+            builtin: None,
+        }];
+        let redactor = crate::redaction::RedactionConfig {
+            rules: vec![crate::redaction::RedactionRule {
+                pattern: r"[a-z0-9-]+\.corp\.example\.com".to_string(),
+                replacement: "<host>".to_string(),
+            }],
+        }
+        .compile()
+        .unwrap();
+        let GateFailure { feedback, .. } = run_command_gates(
+            &gates,
+            std::path::Path::new("/tmp"),
+            &redactor,
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("gate fails");
+        assert!(feedback.contains("connect <host>"));
+        assert!(!feedback.contains("corp.example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_command_gates_list_parsed_diagnostics() {
+        let gates = vec![CommandGate {
+            name: "typecheck".to_string(),
+            command: "echo \"src/app.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.\"; exit 2"
+                .to_string(),
+            timeout_secs: 30,
+            baseline: false,
+            builtin: None,
+        }];
+        let failure = run_command_gates(
+            &gates,
+            std::path::Path::new("/tmp"),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("gate fails");
+        assert_eq!(failure.diagnostics.len(), 1);
+        assert_eq!(failure.diagnostics[0].tool, "tsc");
+        assert!(failure.feedback.contains("## Diagnostics (1)"));
+        assert!(
+            failure
+                .feedback
+                .contains("- src/app.ts:3:7: error: Type 'string' is not assignable")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_gates_record_timeline_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = crate::events::EventRecorder::new_in_runs_dir(dir.path(), "r1")
+            .await
+            .unwrap();
+        let gates = vec![CommandGate {
+            name: "typecheck".to_string(),
+            command: "echo \"src/app.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.\"; exit 2"
+                .to_string(),
+            timeout_secs: 30,
+            baseline: false,
+            builtin: None,
+        }];
+        let events = GateEvents {
+            recorder: &recorder,
+            stage: "build",
+        };
+        run_command_gates(
+            &gates,
+            std::path::Path::new("/tmp"),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            Some(&events),
+        )
+        .await
+        .expect("gate fails");
+
+        let content = std::fs::read_to_string(recorder.events_path()).unwrap();
+        let timeline = crate::events::timeline::Timeline::from_events("r1", &content);
+        assert_eq!(timeline.spans.len(), 1);
+        assert_eq!(timeline.spans[0].label, "typecheck");
+        assert_eq!(timeline.spans[0].status.as_deref(), Some("failed"));
+        let kinds: Vec<_> = timeline.marks.iter().map(|m| m.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                crate::events::timeline::MarkKind::Output,
+                crate::events::timeline::MarkKind::Error
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_command_gates_timeout_is_reported() {
         let gates = vec![CommandGate {
             name: "slow".to_string(),
             command: "sleep 5".to_string(),
             timeout_secs: 1,
+            baseline: false,
+            builtin: None,
         }];
-        let (name, feedback) = run_command_gates(&gates, std::path::Path::new("/tmp"))
-            .await
-            .expect("gate times out");
+        let GateFailure {
+            gate: name,
+            feedback,
+            ..
+        } = run_command_gates(
+            &gates,
+            std::path::Path::new("/tmp"),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("gate times out");
         assert_eq!(name, "slow");
         assert!(feedback.contains("timed out after 1s"));
     }
 
+    #[tokio::test]
+    async fn test_owasp_builtin_gate_reports_findings_at_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("handler.py"),
+            "import hashlib\n\
+             digest = hashlib.md5(body).hexdigest()\n\
+             cur.execute(f\"SELECT * FROM users WHERE id = {user_id}\")\n",
+        )
+        .unwrap();
+        let gate = |min_severity| CommandGate {
+            name: "owasp".to_string(),
+            command: String::new(),
+            timeout_secs: 30,
+            baseline: false,
+            builtin: Some(BuiltinGate::Owasp { min_severity }),
+        };
+
+        let failure = run_command_gates(
+            &[gate(crate::security::owasp::Severity::High)],
+            dir.path(),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("SQL injection is high severity");
+        assert_eq!(failure.gate, "owasp");
+        assert_eq!(failure.diagnostics.len(), 1, "md5 is below the threshold");
+        assert_eq!(failure.diagnostics[0].code.as_deref(), Some("sql-format"));
+        assert!(
+            failure
+                .feedback
+                .contains("handler.py:3 [high] A03:2021 sql-format")
+        );
+
+        std::fs::write(
+            dir.path().join("handler.py"),
+            "cur.execute(\"SELECT * FROM users WHERE id = %s\", (user_id,))\n",
+        )
+        .unwrap();
+        assert!(
+            run_command_gates(
+                &[gate(crate::security::owasp::Severity::High)],
+                dir.path(),
+                &Default::default(),
+                &parser(),
+                &Default::default(),
+                &Default::default(),
+                None,
+            )
+            .await
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_builtin_gate_parses_and_empty_gate_is_rejected() {
+        let yaml = r#"
+name: gated
+initial_movement: build
+stages:
+  - id: build
+    instruction: "implement"
+    gates:
+      - { name: security, builtin: { kind: owasp, min_severity: critical } }
+      - { name: scan, builtin: { kind: owasp } }
+      - { name: hygiene, builtin: { kind: commit_hygiene, max_commit_lines: 400 } }
+"#;
+        let flow = Flow::from_yaml(yaml).expect("parse failed");
+        let gates = &flow.stages[0].gates;
+        assert!(matches!(
+            gates[0].builtin,
+            Some(BuiltinGate::Owasp {
+                min_severity: crate::security::owasp::Severity::Critical
+            })
+        ));
+        assert!(matches!(
+            gates[1].builtin,
+            Some(BuiltinGate::Owasp {
+                min_severity: crate::security::owasp::Severity::High
+            })
+        ));
+        let Some(BuiltinGate::CommitHygiene { rules }) = &gates[2].builtin else {
+            panic!("expected a commit hygiene gate: {:?}", gates[2].builtin);
+        };
+        assert_eq!(rules.max_commit_lines, 400);
+        assert!(rules.conventional);
+
+        let yaml = yaml.replace("builtin: { kind: owasp }", "command: \"\"");
+        let err = Flow::from_yaml(&yaml).unwrap_err().to_string();
+        assert!(err.contains("needs a command or a builtin"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_coverage_gate_parses_thresholds_and_reports_collection_errors() {
+        let yaml = r#"
+name: gated
+initial_movement: build
+stages:
+  - id: build
+    instruction: "implement"
+    gates:
+      - name: coverage
+        timeout_secs: 60
+        builtin:
+          kind: coverage
+          tool: tarpaulin
+          min_percent: 60
+          packages: { ccswarm: 70.5 }
+          max_drop: 2
+"#;
+        let flow = Flow::from_yaml(yaml).expect("parse failed");
+        let gate = &flow.stages[0].gates[0];
+        let Some(BuiltinGate::Coverage { tool, thresholds }) = &gate.builtin else {
+            panic!("expected a coverage gate: {:?}", gate.builtin);
+        };
+        assert_eq!(*tool, crate::workflow::coverage::CoverageTool::Tarpaulin);
+        assert_eq!(thresholds.min_percent, Some(60.0));
+        assert_eq!(thresholds.packages["ccswarm"], 70.5);
+        assert_eq!(thresholds.max_drop, Some(2.0));
+
+        // No Cargo project here, so collection fails whether or not the
+        // tool is installed; the gate must fail rather than pass silently.
+        let dir = tempfile::tempdir().unwrap();
+        let failure = run_command_gates(
+            std::slice::from_ref(gate),
+            dir.path(),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("collection error fails the gate");
+        assert_eq!(failure.gate, "coverage");
+        assert!(
+            failure.feedback.contains("Coverage could not be collected"),
+            "{}",
+            failure.feedback
+        );
+    }
+
+    #[tokio::test]
+    async fn test_matrix_gate_reports_failed_cells() {
+        let yaml = r#"
+name: gated
+initial_movement: test
+stages:
+  - id: test
+    instruction: "run the suite"
+    gates:
+      - name: matrix
+        builtin: { kind: test_matrix }
+"#;
+        let flow = Flow::from_yaml(yaml).expect("parse failed");
+        let gate = &flow.stages[0].gates[0];
+        assert!(matches!(gate.builtin, Some(BuiltinGate::TestMatrix)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let unconfigured = run_command_gates(
+            std::slice::from_ref(gate),
+            dir.path(),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .expect("a missing matrix fails the gate");
+        assert!(unconfigured.feedback.contains("quality.matrix"));
+
+        let matrix: crate::workflow::test_matrix::TestMatrix =
+            serde_json::from_value(serde_json::json!({
+                "command": "echo node {node}; test {node} != 18",
+                "axes": { "node": ["18", "20"] },
+            }))
+            .unwrap();
+        let failure = run_command_gates(
+            std::slice::from_ref(gate),
+            dir.path(),
+            &Default::default(),
+            &parser(),
+            &Default::default(),
+            &crate::config::QualityConfig {
+                matrix: Some(matrix),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .expect("the node=18 cell fails");
+        assert!(
+            failure.feedback.contains("1 of 2 cells passed"),
+            "{}",
+            failure.feedback
+        );
+        assert!(
+            failure.feedback.contains("## node=18"),
+            "{}",
+            failure.feedback
+        );
+        assert!(
+            dir.path()
+                .join(crate::workflow::test_matrix::REPORT_FILE)
+                .exists()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quality_gate_runs_the_profile_of_the_checked_out_branch() {
+        let yaml = r#"
+name: gated
+initial_movement: test
+stages:
+  - id: test
+    instruction: "run the checks"
+    gates:
+      - name: quality
+        builtin: { kind: quality }
+"#;
+        let flow = Flow::from_yaml(yaml).expect("parse failed");
+        let gate = &flow.stages[0].gates[0];
+        assert!(matches!(
+            gate.builtin,
+            Some(BuiltinGate::Quality { profile: None })
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q", "-b", "feature/login"]);
+        let quality: crate::config::QualityConfig = serde_json::from_value(serde_json::json!({
+            "checks": [
+                { "name": "lint", "command": "echo lint ok" },
+                { "name": "audit", "command": "echo vulnerable crate; exit 1" },
+            ],
+            "profiles": {
+                "fast": { "checks": ["lint"] },
+                "full": { "extends": ["fast"], "checks": ["audit"] },
+            },
+            "branches": [
+                { "pattern": "feature/*", "profile": "fast" },
+                { "pattern": "release/*", "profile": "full" },
+            ],
+        }))
+        .unwrap();
+        let redactor = crate::redaction::Redactor::default();
+        let parser = parser();
+        let sampler = crate::session::sampling::ContextSampler::default();
+        let run = || {
+            run_command_gates(
+                std::slice::from_ref(gate),
+                dir.path(),
+                &redactor,
+                &parser,
+                &sampler,
+                &quality,
+                None,
+            )
+        };
+
+        assert!(run().await.is_none(), "feature branches skip the audit");
+        let report = crate::workflow::quality_checks::load_report(dir.path())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.profile.as_deref(), Some("fast"));
+
+        git(&["checkout", "-q", "-b", "release/1.0"]);
+        let failure = run().await.expect("release branches run the audit");
+        assert!(
+            failure.feedback.contains("Quality profile: full"),
+            "{}",
+            failure.feedback
+        );
+        assert!(
+            failure.feedback.contains("## audit") && failure.feedback.contains("vulnerable crate"),
+            "{}",
+            failure.feedback
+        );
+        assert!(
+            !failure.feedback.contains("## lint"),
+            "{}",
+            failure.feedback
+        );
+    }
+
     #[test]
     fn test_gates_parse_from_yaml_with_default_timeout() {
         let yaml = r#"
@@ -4135,6 +6588,54 @@ stages:
         assert_eq!(engine.worktree_name.as_deref(), Some("ccswarm-test-run"));
     }
 
+    #[test]
+    fn unpinned_stages_are_routed_by_task_complexity() {
+        let flow = Flow::from_yaml(
+            r#"
+name: routed
+initial_movement: edit
+stages:
+  - id: edit
+    persona: coder
+    provider: claude
+    instruction: "Do the task"
+  - id: pinned
+    provider: claude
+    model: sonnet
+    instruction: "Do the task"
+"#,
+        )
+        .expect("flow should parse");
+        let mut state = flow.create_state();
+        state.variables.insert(
+            "task".to_string(),
+            serde_json::json!("Fix a typo in the README"),
+        );
+        let mut engine = FlowEngine::new();
+        engine.set_working_dir(std::env::temp_dir());
+        engine.set_model_routing(crate::providers::routing::ModelRoutingConfig {
+            enabled: true,
+            ..Default::default()
+        });
+
+        let (_, model) = engine.resolve_effective_provider(&flow.stages[0], &state);
+        assert_eq!(model.as_deref(), Some("haiku"));
+        let (_, model) = engine.resolve_effective_provider(&flow.stages[1], &state);
+        assert_eq!(model.as_deref(), Some("sonnet"));
+
+        state.variables.insert(
+            "task".to_string(),
+            serde_json::json!("Redesign the sync protocol for concurrency safety"),
+        );
+        let (_, model) = engine.resolve_effective_provider(&flow.stages[0], &state);
+        assert_eq!(model.as_deref(), Some("opus"));
+
+        // A task's own model arrives as the run's model override.
+        engine.set_model_override("sonnet");
+        let (_, model) = engine.resolve_effective_provider(&flow.stages[0], &state);
+        assert_eq!(model.as_deref(), Some("sonnet"));
+    }
+
     #[tokio::test]
     async fn test_team_leader_offline_aggregates_parallel_shape() {
         // Without a bridge, the leader's "output" is the prompt echo (no JSON
@@ -4157,7 +6658,8 @@ stages:
     instruction: "terminal"
 "#;
         let flow = Flow::from_yaml(yaml).expect("parse failed");
-        let mut engine = FlowEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = offline_engine(&dir);
         engine.flows.insert("tl-offline".to_string(), flow);
 
         let state = engine
@@ -4183,6 +6685,217 @@ stages:
         assert!(agents.contains_key("build-worker"));
     }
 
+    #[tokio::test]
+    async fn test_team_leader_delegates_against_replayed_transcript() {
+        use crate::providers::transcript::{
+            ProviderResponse, TranscriptMode, TranscriptReplay, entry,
+        };
+        use crate::providers::{ProviderKind, ProviderOptions};
+
+        let reply = |stdout: &str| ProviderResponse {
+            success: true,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        };
+        let recorded = |prompt: &str, stdout: &str| {
+            entry(
+                ProviderKind::Claude,
+                "default",
+                prompt,
+                &ProviderOptions::default(),
+                reply(stdout),
+                5,
+            )
+        };
+        // Prompts are placeholders: replay falls back to recorded order per
+        // agent, so the leader gets the decomposition and the workers the rest.
+        let replay = TranscriptReplay::new(vec![
+            recorded(
+                "leader",
+                r#"[{"id":"api","instruction":"build the API"},{"id":"ui","instruction":"build the UI"}]"#,
+            ),
+            recorded("worker", "part finished"),
+            recorded("worker", "part finished"),
+        ]);
+
+        let yaml = r#"
+name: tl-replay
+initial_movement: build
+stages:
+  - id: build
+    instruction: "implement the thing"
+    team_leader:
+      max_parts: 3
+"#;
+        let sessions = tempfile::tempdir().expect("tempdir");
+        let bridge = crate::session::bridge::AISessionBridge::new(sessions.path().to_path_buf())
+            .with_transcripts(TranscriptMode::Replay(replay.into()));
+        let bridge = std::sync::Arc::new(bridge);
+        let mut engine = FlowEngine::new();
+        engine.set_bridge(bridge);
+        engine.flows.insert(
+            "tl-replay".to_string(),
+            Flow::from_yaml(yaml).expect("parse"),
+        );
+
+        let state = engine
+            .execute_piece("tl-replay")
+            .await
+            .expect("execution failed");
+        assert_eq!(state.status, FlowStatus::Completed);
+        let agents = state.variables["build_output"]
+            .get("agents")
+            .and_then(|v| v.as_object())
+            .expect("agents map");
+        let mut ids: Vec<&str> = agents.keys().map(String::as_str).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["api", "ui"]);
+        for output in agents.values() {
+            assert_eq!(output["status"], "completed");
+            assert_eq!(output["output"], "part finished");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schema_violation_reprompts_and_stores_structured_result() {
+        use crate::providers::transcript::{
+            ProviderResponse, TranscriptMode, TranscriptReplay, entry,
+        };
+        use crate::providers::{ProviderKind, ProviderOptions};
+
+        let recorded = |stdout: &str| {
+            entry(
+                ProviderKind::Claude,
+                "default",
+                "placeholder",
+                &ProviderOptions::default(),
+                ProviderResponse {
+                    success: true,
+                    stdout: stdout.to_string(),
+                    stderr: String::new(),
+                },
+                5,
+            )
+        };
+        let replay = TranscriptReplay::new(vec![
+            recorded("Looks good to me."),
+            recorded("```json\n{\"verdict\": \"ship it\"}\n```"),
+            recorded("Verdict below.\n```json\n{\"verdict\": \"approve\", \"issues\": 0}\n```"),
+        ]);
+
+        let yaml = r#"
+name: typed-review
+initial_movement: review
+stages:
+  - id: review
+    instruction: "review the change"
+    output_contract:
+      format: json
+      schema:
+        type: object
+        required: [verdict, issues]
+        properties:
+          verdict: { enum: [approve, reject] }
+          issues: { type: integer }
+"#;
+        let sessions = tempfile::tempdir().expect("tempdir");
+        let bridge = crate::session::bridge::AISessionBridge::new(sessions.path().to_path_buf())
+            .with_transcripts(TranscriptMode::Replay(replay.into()));
+        let mut engine = FlowEngine::new();
+        engine.set_bridge(std::sync::Arc::new(bridge));
+        engine.flows.insert(
+            "typed-review".to_string(),
+            Flow::from_yaml(yaml).expect("parse"),
+        );
+
+        let state = engine
+            .execute_piece("typed-review")
+            .await
+            .expect("execution failed");
+        assert_eq!(state.status, FlowStatus::Completed);
+        let expected = serde_json::json!({"verdict": "approve", "issues": 0});
+        assert_eq!(state.variables["review_output"]["structured"], expected);
+        assert_eq!(state.variables["review_structured"], expected);
+    }
+
+    #[tokio::test]
+    async fn test_low_self_rating_routes_team_leader_part_through_review() {
+        use crate::providers::transcript::{
+            ProviderResponse, TranscriptMode, TranscriptReplay, entry,
+        };
+        use crate::providers::{ProviderKind, ProviderOptions};
+
+        let recorded = |agent: &str, stdout: &str| {
+            entry(
+                ProviderKind::Claude,
+                agent,
+                "placeholder",
+                &ProviderOptions::default(),
+                ProviderResponse {
+                    success: true,
+                    stdout: stdout.to_string(),
+                    stderr: String::new(),
+                },
+                5,
+            )
+        };
+        let replay = TranscriptReplay::new(vec![
+            recorded(
+                "default",
+                r#"[{"id":"auth","title":"Auth","instruction":"add login"}]"#,
+            ),
+            recorded(
+                "default",
+                "Login added, tests not run.\nSELF_RATING={\"confidence\": 2, \"completeness\": 4, \"low_confidence_areas\": [\"session expiry\"]}",
+            ),
+            recorded("reviewer", "Expiry is never checked.\nREVIEW=revise"),
+        ]);
+
+        let yaml = r#"
+name: tl-rating
+initial_movement: build
+stages:
+  - id: build
+    instruction: "implement login"
+    team_leader:
+      max_parts: 2
+"#;
+        let dir = tempfile::tempdir().expect("tempdir");
+        let bridge = crate::session::bridge::AISessionBridge::new(dir.path().join("sessions"))
+            .with_transcripts(TranscriptMode::Replay(replay.into()));
+        let mut engine = FlowEngine::new();
+        engine.set_bridge(std::sync::Arc::new(bridge));
+        engine.set_working_dir(dir.path().to_path_buf());
+        let runs_dir = dir.path().join(".ccswarm").join("runs");
+        engine.set_event_recorder(
+            crate::events::EventRecorder::new_in_runs_dir(&runs_dir, "rating-run")
+                .await
+                .expect("recorder"),
+        );
+        engine.flows.insert(
+            "tl-rating".to_string(),
+            Flow::from_yaml(yaml).expect("parse"),
+        );
+
+        let state = engine.execute_piece("tl-rating").await.expect("execute");
+        let part = &state.variables["build_output"]["agents"]["auth"];
+        assert_eq!(part["self_rating"]["confidence"], 2);
+        assert_eq!(part["needs_review"], true);
+        assert_eq!(part["review_required"], true);
+        assert!(
+            part["review"]["output"]
+                .as_str()
+                .unwrap()
+                .contains("REVIEW=revise")
+        );
+
+        // The rating is in the run's event log, and its area now counts as weak.
+        let history = super::super::self_rating::RatingHistory::load(&runs_dir).await;
+        assert_eq!(history.ratings.len(), 1);
+        assert_eq!(history.ratings[0].stage, "auth");
+        assert!(history.weak_areas().contains("session expiry"));
+    }
+
     #[test]
     fn test_builtin_flows_roundtrip_through_yaml() {
         // `flow check <builtin>` serializes a builtin flow to YAML and
@@ -4290,7 +7003,7 @@ stages:
         );
         let engine = FlowEngine::new();
 
-        let prompt = engine.build_movement_prompt(stage, &state);
+        let (prompt, _) = engine.build_movement_prompt(stage, &state);
 
         assert!(
             !prompt.contains("You are the implementer"),
@@ -4303,6 +7016,59 @@ stages:
         );
     }
 
+    #[test]
+    fn build_prompt_leads_with_the_project_doctrine() {
+        let yaml = r#"
+name: doctrine-test
+initial_movement: plan
+stages:
+  - id: plan
+    instruction: "Plan the change"
+"#;
+        let flow = Flow::from_yaml(yaml).expect("parse failed");
+        let state = flow.create_state();
+        let mut engine = FlowEngine::new();
+        engine.set_doctrine("# Project Doctrine\n\n- Keep handlers thin.");
+
+        let (prompt, _) = engine.build_movement_prompt(&flow.stages[0], &state);
+
+        let doctrine = prompt
+            .find("Keep handlers thin.")
+            .expect("doctrine missing");
+        assert!(doctrine < prompt.find("Plan the change").unwrap());
+    }
+
+    #[test]
+    fn build_prompt_states_the_agents_working_language() {
+        let yaml = r#"
+name: locale-test
+initial_movement: plan
+stages:
+  - id: plan
+    persona: reviewer
+    instruction: "Plan the change"
+"#;
+        let flow = Flow::from_yaml(yaml).expect("parse failed");
+        let state = flow.create_state();
+        let mut config = crate::config::CcswarmConfig::default();
+        config.i18n.working_language = Some("en".into());
+        config.agents.insert(
+            "reviewer".into(),
+            crate::config::AgentConfig {
+                locale: Some("ja".into()),
+                timezone: Some("Asia/Tokyo".into()),
+                ..Default::default()
+            },
+        );
+        let mut engine = FlowEngine::new();
+        engine.set_locales(crate::i18n::Locales::from_config(&config));
+
+        let (prompt, _) = engine.build_movement_prompt(&flow.stages[0], &state);
+
+        assert!(prompt.contains("Working language: Japanese."));
+        assert!(prompt.contains("Time zone: Asia/Tokyo."));
+    }
+
     /// An explicit `tools:` list on a stage is honored verbatim, even if the
     /// permission level would allow a broader set.
     #[test]
@@ -4350,10 +7116,9 @@ stages:
         use tempfile::TempDir;
 
         let tmp = TempDir::new().expect("tempdir");
-        let prev = std::env::current_dir().expect("cwd");
-        std::env::set_current_dir(tmp.path()).expect("chdir");
-
-        let reports_dir = std::path::Path::new(".ccswarm")
+        let reports_dir = tmp
+            .path()
+            .join(".ccswarm")
             .join("runs")
             .join("test-run-1")
             .join("reports");
@@ -4365,12 +7130,13 @@ stages:
             "__run_id".to_string(),
             serde_json::Value::String("test-run-1".to_string()),
         );
+        vars.insert(
+            "__reports_dir".to_string(),
+            serde_json::Value::String(reports_dir.to_string_lossy().into_owned()),
+        );
 
         let out = super::expand_template("Plan was: {report:plan.md}", &vars);
 
-        // Restore CWD before asserting so a failure doesn't leak directory state.
-        std::env::set_current_dir(prev).expect("restore cwd");
-
         assert_eq!(out, "Plan was: PLAN_BODY");
     }
 
@@ -4383,6 +7149,10 @@ stages:
             "__run_id".to_string(),
             serde_json::Value::String("test-run-2".to_string()),
         );
+        vars.insert(
+            "__reports_dir".to_string(),
+            serde_json::Value::String("/nonexistent/reports".to_string()),
+        );
 
         let out = super::expand_template("escape: {report:../passwd}", &vars);
         assert_eq!(out, "escape: ");
@@ -4429,6 +7199,8 @@ stages:
             gates: Vec::new(),
             team_leader: None,
             sangha: None,
+            review: None,
+            typed_tools: false,
         };
         let call = stage.call.clone().expect("call");
 
@@ -4,16 +4,38 @@
 //! `FlowEngine` with faceted prompting, rule-based routing, and NDJSON
 //! event recording.
 
+pub mod ab_eval;
+pub mod acceptance;
+pub mod bisect;
+pub mod blocker;
+pub mod commit_hygiene;
+pub mod context_seed;
+pub mod cost_estimate;
+pub mod coverage;
 pub mod cycle;
+pub mod doctrine;
 pub mod facets;
 pub mod flow;
 pub mod interactive;
 pub mod judge;
+pub mod output_schema;
 pub mod permissions;
 pub mod pipeline;
+pub mod preflight;
+pub mod quality_baseline;
+pub mod quality_checks;
+pub mod quality_profiles;
 pub mod repertoire;
+pub mod retry_policy;
+pub mod review;
 pub mod sangha;
+pub mod sangha_tally;
+pub mod self_rating;
+pub mod subagent;
+pub mod supervisor;
 pub mod team_leader;
+pub mod test_impact;
+pub mod test_matrix;
 
 pub use cycle::{CycleAnalysis, LoopTracker, analyze_flow};
 pub use facets::{
@@ -31,3 +53,4 @@ pub use pipeline::{
     PipelineConfig, PipelineConfigBuilder, PipelineExitCode, PipelineOutput, PipelineRunner,
     PipelineStatus,
 };
+pub use retry_policy::{AttemptPlan, RetryPolicies, RetryPolicy};
@@ -0,0 +1,349 @@
+//! Structured stage output: JSON extraction and schema validation.
+//!
+//! A stage whose `output_contract` declares a `schema` is expected to answer
+//! with a JSON document. The engine pulls that document out of the reply
+//! (the whole reply, a ```json fence, or the outermost `{…}` / `[…]`),
+//! checks it against the schema, and re-prompts with the violations until it
+//! conforms or `schema_retries` runs out. The typed value is stored on the
+//! stage output under `structured` and exposed to later stages as
+//! `{<stage>_structured}`.
+//!
+//! Only the commonly used subset of JSON Schema is understood: `type`,
+//! `enum`, `const`, `properties`, `required`, `additionalProperties`,
+//! `items`, `minItems`/`maxItems`, `minLength`/`maxLength`, `pattern` and
+//! `minimum`/`maximum`. Other keywords are ignored.
+
+use anyhow::Result;
+use serde_json::Value;
+
+const TYPES: &[&str] = &[
+    "object", "array", "string", "number", "integer", "boolean", "null",
+];
+
+/// Pull a JSON document out of an agent reply.
+pub fn extract_json(reply: &str) -> Option<Value> {
+    let trimmed = reply.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+
+    let mut rest = trimmed;
+    while let Some(start) = rest.find("```") {
+        let after = &rest[start + 3..];
+        let body_start = after.find('\n').map_or(after.len(), |i| i + 1);
+        let lang = after[..body_start].trim();
+        let body = &after[body_start..];
+        let Some(end) = body.find("```") else {
+            break;
+        };
+        if (lang.is_empty() || lang.eq_ignore_ascii_case("json"))
+            && let Ok(value) = serde_json::from_str(body[..end].trim())
+        {
+            return Some(value);
+        }
+        rest = &body[end + 3..];
+    }
+
+    [('{', '}'), ('[', ']')]
+        .into_iter()
+        .find_map(|(open, close)| {
+            let start = trimmed.find(open)?;
+            let end = trimmed.rfind(close)?;
+            (start < end)
+                .then(|| serde_json::from_str(&trimmed[start..=end]).ok())
+                .flatten()
+        })
+}
+
+/// Extract the JSON document from `reply` and validate it, returning the
+/// typed value or the violations to re-prompt with.
+pub fn parse_reply(schema: &Value, reply: &str) -> Result<Value, Vec<String>> {
+    let Some(value) = extract_json(reply) else {
+        return Err(vec!["reply contains no JSON document".to_string()]);
+    };
+    let violations = validate(schema, &value);
+    if violations.is_empty() {
+        Ok(value)
+    } else {
+        Err(violations)
+    }
+}
+
+/// Validate `value` against `schema`, returning one message per violation,
+/// each prefixed with the JSON path it applies to (`$.items[2].name`).
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut violations = Vec::new();
+    check(schema, value, "$", &mut violations);
+    violations
+}
+
+fn check(schema: &Value, value: &Value, path: &str, out: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        if schema == &Value::Bool(false) {
+            out.push(format!("{path}: not allowed"));
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| is_type(value, t)) {
+            out.push(format!(
+                "{path}: expected {}, got {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            // Nothing below makes sense against the wrong type.
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array)
+        && !options.contains(value)
+    {
+        out.push(format!(
+            "{path}: {value} is not one of {}",
+            Value::Array(options.clone())
+        ));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        out.push(format!("{path}: expected {expected}, got {value}"));
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for key in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !map.contains_key(key) {
+                    out.push(format!("{path}: missing required property '{key}'"));
+                }
+            }
+            for (key, item) in map {
+                let item_path = format!("{path}.{key}");
+                match properties.and_then(|p| p.get(key)) {
+                    Some(sub) => check(sub, item, &item_path, out),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            out.push(format!("{path}: unexpected property '{key}'"))
+                        }
+                        Some(sub @ Value::Object(_)) => check(sub, item, &item_path, out),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            bounds(
+                schema,
+                "minItems",
+                "maxItems",
+                items.len(),
+                "items",
+                path,
+                out,
+            );
+            if let Some(sub) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(sub, item, &format!("{path}[{i}]"), out);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count();
+            bounds(
+                schema,
+                "minLength",
+                "maxLength",
+                len,
+                "characters",
+                path,
+                out,
+            );
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str)
+                && let Ok(re) = regex::Regex::new(pattern)
+                && !re.is_match(s)
+            {
+                out.push(format!("{path}: does not match pattern '{pattern}'"));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+                && n < min
+            {
+                out.push(format!("{path}: {n} is below the minimum {min}"));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+                && n > max
+            {
+                out.push(format!("{path}: {n} is above the maximum {max}"));
+            }
+        }
+        Value::Bool(_) | Value::Null => {}
+    }
+}
+
+fn bounds(
+    schema: &serde_json::Map<String, Value>,
+    min_key: &str,
+    max_key: &str,
+    len: usize,
+    unit: &str,
+    path: &str,
+    out: &mut Vec<String>,
+) {
+    if let Some(min) = schema.get(min_key).and_then(Value::as_u64)
+        && (len as u64) < min
+    {
+        out.push(format!("{path}: {len} {unit}, at least {min} required"));
+    }
+    if let Some(max) = schema.get(max_key).and_then(Value::as_u64)
+        && (len as u64) > max
+    {
+        out.push(format!("{path}: {len} {unit}, at most {max} allowed"));
+    }
+}
+
+fn is_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+    }
+}
+
+/// Reject schemas the validator would silently misread: unknown type names
+/// and patterns that don't compile. Called when a flow is loaded.
+pub fn check_schema(schema: &Value) -> Result<()> {
+    let Some(obj) = schema.as_object() else {
+        if schema.is_boolean() {
+            return Ok(());
+        }
+        anyhow::bail!("schema must be an object, got {}", type_name(schema));
+    };
+    let types = match obj.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if let Some(unknown) = types.iter().find(|t| !TYPES.contains(t)) {
+        anyhow::bail!("unknown schema type '{unknown}'");
+    }
+    if let Some(pattern) = obj.get("pattern").and_then(Value::as_str) {
+        regex::Regex::new(pattern)
+            .map_err(|e| anyhow::anyhow!("invalid schema pattern '{pattern}': {e}"))?;
+    }
+    for sub in obj
+        .get("properties")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flat_map(|p| p.values())
+        .chain(obj.get("items"))
+        .chain(obj.get("additionalProperties"))
+    {
+        check_schema(sub)?;
+    }
+    Ok(())
+}
+
+/// Text appended to the prompt when a reply failed the schema.
+pub fn retry_feedback(schema: &Value, problem: &[String]) -> String {
+    format!(
+        "Your previous reply did not satisfy the required output schema:\n- {}\n\n\
+         Reply again with a single JSON document (a ```json block is fine) matching:\n{}",
+        problem.join("\n- "),
+        serde_json::to_string_pretty(schema).unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_json_from_prose_and_fences() {
+        assert_eq!(extract_json(r#"{"ok": true}"#), Some(json!({"ok": true})));
+        assert_eq!(
+            extract_json("Here you go:\n```json\n[1, 2]\n```\nDone."),
+            Some(json!([1, 2]))
+        );
+        assert_eq!(
+            extract_json("Result: {\"score\": 4} as requested"),
+            Some(json!({"score": 4}))
+        );
+        assert_eq!(extract_json("no structure here"), None);
+    }
+
+    #[test]
+    fn test_validate_reports_each_violation_with_its_path() {
+        let schema = json!({
+            "type": "object",
+            "required": ["verdict", "findings"],
+            "additionalProperties": false,
+            "properties": {
+                "verdict": {"enum": ["approve", "reject"]},
+                "findings": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "required": ["line"],
+                        "properties": {"line": {"type": "integer", "minimum": 1}}
+                    }
+                }
+            }
+        });
+        check_schema(&schema).unwrap();
+        assert!(
+            validate(
+                &schema,
+                &json!({"verdict": "approve", "findings": [{"line": 3}]})
+            )
+            .is_empty()
+        );
+
+        let violations = validate(
+            &schema,
+            &json!({"verdict": "maybe", "findings": [{"line": 0}, {}], "extra": 1}),
+        );
+        assert_eq!(
+            violations,
+            vec![
+                "$: unexpected property 'extra'",
+                "$.findings[0].line: 0 is below the minimum 1",
+                "$.findings[1]: missing required property 'line'",
+                r#"$.verdict: "maybe" is not one of ["approve","reject"]"#,
+            ]
+        );
+        assert!(check_schema(&json!({"type": "text"})).is_err());
+    }
+}
@@ -15,13 +15,15 @@ use tracing::{debug, warn};
 use super::flow::MovementPermission;
 
 /// Tools available at each permission level
-const READONLY_TOOLS: &[&str] = &["read", "grep", "glob", "search", "list", "cat", "find"];
+const READONLY_TOOLS: &[&str] = &[
+    "read", "grep", "glob", "search", "list", "cat", "find", "notes",
+];
 const EDIT_TOOLS: &[&str] = &[
-    "read", "grep", "glob", "search", "list", "cat", "find", "edit", "write", "replace",
+    "read", "grep", "glob", "search", "list", "cat", "find", "notes", "edit", "write", "replace",
 ];
 const FULL_TOOLS: &[&str] = &[
-    "read", "grep", "glob", "search", "list", "cat", "find", "edit", "write", "replace", "bash",
-    "shell", "exec", "delete", "create", "mkdir", "rm",
+    "read", "grep", "glob", "search", "list", "cat", "find", "notes", "edit", "write", "replace",
+    "bash", "shell", "exec", "delete", "create", "mkdir", "rm",
 ];
 
 /// Permission enforcer that validates tool usage and file access
@@ -0,0 +1,149 @@
+//! Pre-flight health probes for the services a task depends on.
+//!
+//! A task that needs a database, the Docker daemon or a package registry
+//! should not start while that service is down: the agent would only burn
+//! tokens finding out. Probes are configured once and run by the dispatcher
+//! right before a task starts.
+//!
+//! ```yaml
+//! preflight:
+//!   probes:
+//!     - name: db
+//!       command: pg_isready -h localhost
+//!       labels: [db, backend]
+//!     - name: docker
+//!       command: docker info
+//!     - name: npm
+//!       url: https://registry.npmjs.org/
+//!       timeout_secs: 5
+//! ```
+//!
+//! A probe with `labels` guards only tasks carrying one of them; one without
+//! guards every task. The first failing probe parks the task as `blocked`
+//! with a `service_unavailable` [`Blocker`]; reconciliation re-runs that
+//! probe and requeues the task once it passes.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use super::acceptance::{check_command, check_endpoint};
+use super::blocker::{Blocker, BlockerKind};
+
+/// The `preflight` config knob.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreflightConfig {
+    pub probes: Vec<Probe>,
+}
+
+/// One service check: a shell command that must exit 0, or a URL that must
+/// answer a GET with a 2xx or 3xx status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Probe {
+    /// Service name, shown in the blocker.
+    pub name: String,
+    /// Run with `sh -c` from the repository root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Task labels the probe guards; empty guards every task.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+impl PreflightConfig {
+    /// The probes guarding a task with `labels`, in config order.
+    pub fn for_labels<'a>(&'a self, labels: &'a [String]) -> impl Iterator<Item = &'a Probe> {
+        self.probes
+            .iter()
+            .filter(|p| p.labels.is_empty() || p.labels.iter().any(|l| labels.contains(l)))
+    }
+
+    pub fn probe(&self, name: &str) -> Option<&Probe> {
+        self.probes.iter().find(|p| p.name == name)
+    }
+}
+
+impl Probe {
+    /// Check the service from `repo`; the error says what failed.
+    pub async fn check(&self, repo: &Path) -> Result<(), String> {
+        let timeout = Duration::from_secs(self.timeout_secs);
+        if self.command.is_none() && self.url.is_none() {
+            return Err("probe has neither a command nor a url".to_string());
+        }
+        if let Some(command) = &self.command {
+            check_command(command, repo, timeout).await?;
+        }
+        if let Some(url) = &self.url {
+            check_endpoint(url, timeout).await?;
+        }
+        Ok(())
+    }
+
+    /// The blocker a task is parked on while this probe fails.
+    pub fn blocker(&self, failure: &str) -> Blocker {
+        let target = self
+            .command
+            .as_deref()
+            .or(self.url.as_deref())
+            .unwrap_or("");
+        Blocker {
+            kind: BlockerKind::ServiceUnavailable,
+            detail: format!("pre-flight probe `{target}` failed: {failure}"),
+            credential: None,
+            task: None,
+            service: Some(self.name.clone()),
+            question: None,
+        }
+    }
+}
+
+/// Run `probes` in order and stop at the first failure, returning the
+/// blocker for it.
+pub async fn run<'a>(probes: impl IntoIterator<Item = &'a Probe>, repo: &Path) -> Option<Blocker> {
+    for probe in probes {
+        if let Err(failure) = probe.check(repo).await {
+            tracing::info!(probe = %probe.name, "pre-flight probe failed: {failure}");
+            return Some(probe.blocker(&failure));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn failing_probe_for_a_label_yields_a_service_blocker() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: PreflightConfig = serde_yml::from_str(
+            "probes:\n  - name: shell\n    command: 'true'\n  - name: db\n    command: echo refused >&2; exit 2\n    labels: [db]\n  - name: broken\n    labels: [never]\n",
+        )
+        .unwrap();
+        assert_eq!(config.probes[0].timeout_secs, 10);
+
+        let plain: Vec<String> = vec!["docs".into()];
+        assert_eq!(config.for_labels(&plain).count(), 1);
+        assert!(run(config.for_labels(&plain), dir.path()).await.is_none());
+
+        let db: Vec<String> = vec!["db".into()];
+        let blocker = run(config.for_labels(&db), dir.path()).await.unwrap();
+        assert_eq!(blocker.kind, BlockerKind::ServiceUnavailable);
+        assert_eq!(blocker.service.as_deref(), Some("db"));
+        assert!(blocker.detail.contains("refused"));
+        assert!(!blocker.needs_human());
+        assert!(blocker.summary().starts_with("service unavailable (db):"));
+
+        let broken = config.probe("broken").unwrap();
+        assert!(broken.check(dir.path()).await.is_err());
+    }
+}
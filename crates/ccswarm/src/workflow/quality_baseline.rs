@@ -0,0 +1,344 @@
+//! Accepted-issue baselines for quality checks and gates.
+//!
+//! A legacy codebase rarely passes `clippy -D warnings` or its whole test
+//! suite on day one. `ccswarm quality baseline` runs the checks once and
+//! records what they report — lint and compiler diagnostics, failing tests —
+//! as known issues in `.ccswarm/quality/baseline.json` (in the main worktree,
+//! so every worktree shares it). From then on `quality check`, and flow
+//! command gates with `baseline: true`, fail only when a run reports an issue
+//! the baseline doesn't know. Issues are identified by file and message, not
+//! line number, so unrelated edits that shift code around don't make them new.
+//!
+//! Fixed issues drop out of the baseline on the next passing run and cannot
+//! come back. With a ratchet, the number of issues a check may still report
+//! also shrinks by `step` every `every_days` days, so the remaining debt has
+//! to be paid down over time instead of living in the baseline forever.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use super::quality_checks::{CheckReport, CheckState};
+
+/// Baseline file, relative to the main worktree.
+pub const BASELINE_FILE: &str = ".ccswarm/quality/baseline.json";
+
+/// How fast a ratchet tightens the issues a check may report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ratchet {
+    /// Issues taken off the allowance each period.
+    pub step: usize,
+    pub every_days: u32,
+}
+
+/// What one check was allowed to report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckBaseline {
+    pub issues: BTreeSet<String>,
+    /// Issues allowed as of `since`; never more than `issues`.
+    pub allowed: usize,
+    pub since: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub recorded_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratchet: Option<Ratchet>,
+    pub checks: BTreeMap<String, CheckBaseline>,
+}
+
+/// One run of a check measured against its baseline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comparison {
+    /// Issues the run reported.
+    pub found: usize,
+    /// Issues the check may report today.
+    pub allowed: usize,
+    /// Reported issues the baseline doesn't know.
+    pub new: Vec<String>,
+    /// Baselined issues the run no longer reports.
+    pub fixed: Vec<String>,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.new.is_empty() && self.found <= self.allowed
+    }
+}
+
+impl CheckBaseline {
+    pub fn new(issues: BTreeSet<String>, now: DateTime<Utc>) -> Self {
+        Self {
+            allowed: issues.len(),
+            issues,
+            since: now,
+        }
+    }
+
+    /// Issues allowed at `now`: the recorded allowance, less one ratchet
+    /// step per full period since it was set.
+    pub fn allowed_at(&self, ratchet: Option<&Ratchet>, now: DateTime<Utc>) -> usize {
+        let Some(ratchet) = ratchet else {
+            return self.allowed;
+        };
+        let periods = (now - self.since).num_days().max(0) / i64::from(ratchet.every_days.max(1));
+        self.allowed
+            .saturating_sub(ratchet.step.saturating_mul(periods as usize))
+    }
+
+    pub fn compare(
+        &self,
+        found: &BTreeSet<String>,
+        ratchet: Option<&Ratchet>,
+        now: DateTime<Utc>,
+    ) -> Comparison {
+        Comparison {
+            found: found.len(),
+            allowed: self.allowed_at(ratchet, now),
+            new: found.difference(&self.issues).cloned().collect(),
+            fixed: self.issues.difference(found).cloned().collect(),
+        }
+    }
+
+    /// Tighten after a passing run: fixed issues leave the baseline, and the
+    /// allowance drops to what the run reported.
+    fn settle(&mut self, found: &BTreeSet<String>, now: DateTime<Utc>) {
+        self.issues = found.clone();
+        if found.len() < self.allowed {
+            self.allowed = found.len();
+            self.since = now;
+        }
+    }
+}
+
+impl Baseline {
+    pub fn new(ratchet: Option<Ratchet>, now: DateTime<Utc>) -> Self {
+        Self {
+            recorded_at: now,
+            ratchet,
+            checks: BTreeMap::new(),
+        }
+    }
+
+    /// Compare a run of `check` with its baseline, settling the baseline when
+    /// the run passes. `None` when the check has no baseline.
+    pub fn judge(
+        &mut self,
+        check: &str,
+        found: &BTreeSet<String>,
+        now: DateTime<Utc>,
+    ) -> Option<Comparison> {
+        let entry = self.checks.get_mut(check)?;
+        let comparison = entry.compare(found, self.ratchet.as_ref(), now);
+        if comparison.passed() {
+            entry.settle(found, now);
+        }
+        Some(comparison)
+    }
+}
+
+/// Judge every check in `report` that has a baseline: it passes when it
+/// reports nothing new and stays within its allowance, whatever its exit
+/// code. Failures the baseline can't account for — timeouts, or failures
+/// with no recognisable issue — still fail.
+pub fn apply(baseline: &mut Baseline, report: &mut CheckReport, now: DateTime<Utc>) {
+    for check in &mut report.checks {
+        let unexplained =
+            check.state == CheckState::Failed && (check.timed_out || check.issues.is_empty());
+        if !matches!(check.state, CheckState::Passed | CheckState::Failed) || unexplained {
+            continue;
+        }
+        let found = check.issues.iter().cloned().collect();
+        let Some(comparison) = baseline.judge(&check.name, &found, now) else {
+            continue;
+        };
+        check.state = if comparison.passed() {
+            CheckState::Passed
+        } else {
+            CheckState::Failed
+        };
+        check.baseline = Some(comparison);
+    }
+}
+
+/// Baseline file for the repository `work_dir` belongs to.
+pub async fn baseline_path(work_dir: &Path) -> PathBuf {
+    super::coverage::main_worktree(work_dir)
+        .await
+        .join(BASELINE_FILE)
+}
+
+pub async fn load(path: &Path) -> Result<Option<Baseline>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(raw) => {
+            Ok(Some(serde_json::from_str(&raw).with_context(|| {
+                format!("Invalid baseline {}", path.display())
+            })?))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn save(path: &Path, baseline: &Baseline) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, serde_json::to_string_pretty(baseline)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Judge one gate's output against the baseline of the same name, saving
+/// the settled baseline when it passes. `None` when there is no baseline for
+/// the gate, or nothing recognisable in its output to compare.
+pub async fn judge_gate(work_dir: &Path, gate: &str, output: &str) -> Option<Comparison> {
+    let found = issues(output);
+    if found.is_empty() {
+        return None;
+    }
+    let path = baseline_path(work_dir).await;
+    let mut baseline = match load(&path).await {
+        Ok(baseline) => baseline?,
+        Err(e) => {
+            tracing::warn!("Ignoring the quality baseline: {e:#}");
+            return None;
+        }
+    };
+    let comparison = baseline.judge(gate, &found, Utc::now())?;
+    if comparison.passed()
+        && let Err(e) = save(&path, &baseline).await
+    {
+        tracing::warn!("Failed to update the quality baseline: {e:#}");
+    }
+    Some(comparison)
+}
+
+/// The issues a check's output reports: rustc and clippy diagnostics with a
+/// location, failed cargo and pytest tests, and whatever the output parser's
+/// diagnostic plugins (eslint, tsc, go vet, ...) recognise.
+pub fn issues(output: &str) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    let lines: Vec<&str> = output.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim_end();
+        // `warning: unused variable: `x`` followed by `  --> src/lib.rs:3:9`;
+        // summaries like "could not compile" have no location and are skipped.
+        if is_rustc_header(line)
+            && let Some(file) = lines
+                .get(i + 1)
+                .and_then(|next| next.trim_start().strip_prefix("--> "))
+                .and_then(|location| location.split(':').next())
+        {
+            found.insert(format!("{file}: {line}"));
+        } else if let Some(test) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.strip_suffix(" ... FAILED"))
+        {
+            found.insert(format!("test failed: {test}"));
+        } else if let Some(rest) = line.strip_prefix("FAILED ") {
+            let test = rest.split(" - ").next().unwrap_or(rest);
+            found.insert(format!("test failed: {test}"));
+        }
+    }
+    if found.is_empty()
+        && let Some((_, diagnostics)) = ai_session::output::OutputParser::new().diagnostics(output)
+    {
+        for d in diagnostics {
+            let code = d.code.map(|code| format!("[{code}] ")).unwrap_or_default();
+            found.insert(format!(
+                "{}: {}{}",
+                d.file.as_deref().unwrap_or(&d.tool),
+                code,
+                d.message
+            ));
+        }
+    }
+    found
+}
+
+fn is_rustc_header(line: &str) -> bool {
+    ["warning", "error"].iter().any(|level| {
+        line.strip_prefix(level)
+            .is_some_and(|rest| rest.starts_with(": ") || rest.starts_with('['))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const CLIPPY: &str = "\
+warning: unused variable: `x`
+  --> src/lib.rs:3:9
+   |
+error[E0425]: cannot find value `y` in this scope
+ --> src/main.rs:10:5
+warning: `demo` (lib) generated 1 warning
+error: could not compile `demo` due to 1 previous error
+test api::health ... FAILED
+test api::ready ... ok
+FAILED tests/test_app.py::test_login - AssertionError
+";
+
+    #[test]
+    fn issues_are_keyed_by_file_and_message_not_line() {
+        let found = issues(CLIPPY);
+        assert_eq!(
+            found.into_iter().collect::<Vec<_>>(),
+            [
+                "src/lib.rs: warning: unused variable: `x`",
+                "src/main.rs: error[E0425]: cannot find value `y` in this scope",
+                "test failed: api::health",
+                "test failed: tests/test_app.py::test_login",
+            ]
+        );
+        let moved = CLIPPY.replace("src/lib.rs:3:9", "src/lib.rs:40:1");
+        assert_eq!(issues(&moved), issues(CLIPPY));
+    }
+
+    #[test]
+    fn only_new_issues_fail_and_the_ratchet_tightens_over_time() {
+        let now = Utc::now();
+        let known: BTreeSet<String> = ["a", "b", "c"].map(String::from).into();
+        let mut baseline = Baseline::new(None, now);
+        baseline
+            .checks
+            .insert("lint".into(), CheckBaseline::new(known.clone(), now));
+
+        let with_new: BTreeSet<String> = ["a", "d"].map(String::from).into();
+        let comparison = baseline.judge("lint", &with_new, now).unwrap();
+        assert!(!comparison.passed());
+        assert_eq!(comparison.new, ["d"]);
+        assert!(baseline.judge("other", &with_new, now).is_none());
+
+        // A fix settles the baseline: "c" can't come back.
+        let fixed: BTreeSet<String> = ["a", "b"].map(String::from).into();
+        assert!(baseline.judge("lint", &fixed, now).unwrap().passed());
+        assert!(!baseline.judge("lint", &known, now).unwrap().passed());
+
+        // Two weeks at one issue a week leaves no room for "a" or "b".
+        baseline.ratchet = Some(Ratchet {
+            step: 1,
+            every_days: 7,
+        });
+        let later = now + Duration::days(8);
+        let comparison = baseline.judge("lint", &fixed, later).unwrap();
+        assert_eq!((comparison.found, comparison.allowed), (2, 1));
+        assert!(!comparison.passed());
+        let one: BTreeSet<String> = ["a".to_string()].into();
+        assert!(baseline.judge("lint", &one, later).unwrap().passed());
+        assert_eq!(
+            baseline.checks["lint"].allowed_at(baseline.ratchet.as_ref(), later),
+            1
+        );
+        let comparison = baseline
+            .judge("lint", &one, later + Duration::days(7))
+            .unwrap();
+        assert_eq!(comparison.allowed, 0);
+    }
+}
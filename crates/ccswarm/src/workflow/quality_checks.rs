@@ -0,0 +1,498 @@
+//! Concurrent runner behind `ccswarm quality`.
+//!
+//! A quality check is a shell command (format, lint, build, test, security)
+//! with a name. Checks are independent of each other, so `quality check`
+//! runs up to `jobs` of them at a time instead of one after another.
+//!
+//! Checks that build with cargo share one target directory (the project's
+//! `CARGO_TARGET_DIR`, else `target/`), so a crate compiled for one check is
+//! not compiled again for the next; cargo's lock on that directory keeps
+//! concurrent builds safe. A check that sets its own `RUSTFLAGS` would
+//! invalidate the shared artifacts and gets `target/quality/<name>` instead.
+//!
+//! Cargo projects get a built-in check list. Others, or projects that want
+//! different commands, list them under `quality.checks` in `ccswarm.json`:
+//!
+//! ```json
+//! "quality": {
+//!   "checks": [
+//!     { "name": "lint", "command": "npm run lint", "fix": "npm run lint -- --fix" },
+//!     { "name": "test", "command": "npm test" }
+//!   ]
+//! }
+//! ```
+//!
+//! The latest report is written to `.ccswarm/quality/check.json`.
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::test_matrix::tail;
+use crate::redaction::Redactor;
+
+/// Latest check report, relative to the working directory.
+pub const REPORT_FILE: &str = ".ccswarm/quality/check.json";
+
+/// Characters of output kept per check.
+const OUTPUT_TAIL_CHARS: usize = 2_000;
+
+/// Timeout of a single check.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// One named check.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QualityCheck {
+    pub name: String,
+    /// Shell command run via `sh -c`; passes when it exits 0.
+    pub command: String,
+    /// Command run instead by `--fix`, where the check supports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Builds with cargo, so `CARGO_TARGET_DIR` points at the shared cache.
+    #[serde(default)]
+    pub cargo: bool,
+}
+
+impl QualityCheck {
+    fn new(name: &str, command: &str, cargo: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            command: command.to_string(),
+            fix: None,
+            env: BTreeMap::new(),
+            cargo,
+        }
+    }
+
+    fn with_fix(mut self, fix: &str) -> Self {
+        self.fix = Some(fix.to_string());
+        self
+    }
+
+    /// `CARGO_TARGET_DIR` for this check, given the shared one.
+    pub fn target_dir(&self, shared: &Path) -> Option<PathBuf> {
+        if !self.cargo {
+            None
+        } else if self.env.contains_key("RUSTFLAGS") {
+            Some(shared.join("quality").join(&self.name))
+        } else {
+            Some(shared.to_path_buf())
+        }
+    }
+}
+
+/// Built-in checks for a Cargo workspace.
+pub fn cargo_checks() -> Vec<QualityCheck> {
+    vec![
+        QualityCheck::new("format", "cargo fmt --all -- --check", false)
+            .with_fix("cargo fmt --all"),
+        QualityCheck::new(
+            "lint",
+            "cargo clippy --workspace --all-targets -- -D warnings",
+            true,
+        )
+        .with_fix("cargo clippy --workspace --all-targets --fix --allow-dirty --allow-staged"),
+        QualityCheck::new("build", "cargo build --workspace", true),
+        QualityCheck::new("test", "cargo test --workspace", true),
+        QualityCheck::new("security", "cargo audit", false),
+    ]
+}
+
+/// The configured checks, else the built-in ones for the project type.
+pub fn project_checks(configured: &[QualityCheck], work_dir: &Path) -> Result<Vec<QualityCheck>> {
+    if !configured.is_empty() {
+        return Ok(configured.to_vec());
+    }
+    if work_dir.join("Cargo.toml").exists() {
+        return Ok(cargo_checks());
+    }
+    bail!(
+        "No quality checks for {}: add quality.checks to ccswarm.json",
+        work_dir.display()
+    )
+}
+
+/// Apply `--only` and `--skip`, rejecting names no check has.
+pub fn select(
+    checks: Vec<QualityCheck>,
+    only: &[String],
+    skip: &[String],
+) -> Result<Vec<QualityCheck>> {
+    for name in only.iter().chain(skip) {
+        if !checks.iter().any(|c| &c.name == name) {
+            let known: Vec<&str> = checks.iter().map(|c| c.name.as_str()).collect();
+            bail!("Unknown check '{}' (known: {})", name, known.join(", "));
+        }
+    }
+    Ok(checks
+        .into_iter()
+        .filter(|c| only.is_empty() || only.contains(&c.name))
+        .filter(|c| !skip.contains(&c.name))
+        .collect())
+}
+
+/// The shared cargo target directory for `work_dir`.
+pub fn shared_target_dir(work_dir: &Path) -> PathBuf {
+    match std::env::var_os("CARGO_TARGET_DIR") {
+        Some(dir) => work_dir.join(dir),
+        None => work_dir.join("target"),
+    }
+}
+
+/// Checks run at once when `--jobs` is not given.
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(2, |n| n.get())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckState {
+    Pending,
+    Running,
+    Passed,
+    Failed,
+    /// Not run, or stopped, because another check failed under `--fail-fast`.
+    Cancelled,
+}
+
+impl CheckState {
+    pub fn is_done(self) -> bool {
+        !matches!(self, Self::Pending | Self::Running)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub command: String,
+    pub state: CheckState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub timed_out: bool,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub output_tail: String,
+    /// Issues found in the full output (see [`super::quality_baseline`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+    /// How the run compared with the check's baseline, when it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<super::quality_baseline::Comparison>,
+    /// When the check started, for live elapsed times.
+    #[serde(skip)]
+    pub started: Option<Instant>,
+}
+
+impl CheckResult {
+    /// Time spent so far, or in total once done.
+    pub fn elapsed(&self) -> Duration {
+        match (self.state, self.started) {
+            (CheckState::Running, Some(started)) => started.elapsed(),
+            _ => Duration::from_millis(self.duration_ms),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckReport {
+    pub started_at: DateTime<Utc>,
+    pub jobs: usize,
+    /// Quality profile the checks came from (see
+    /// [`super::quality_profiles`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub checks: Vec<CheckResult>,
+}
+
+impl CheckReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.state == CheckState::Passed)
+    }
+}
+
+/// How [`run`] executes a set of checks.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    /// Checks running at the same time.
+    pub jobs: usize,
+    /// Cancel everything still pending or running once a check fails.
+    pub fail_fast: bool,
+    /// Shared `CARGO_TARGET_DIR` for cargo checks.
+    pub target_dir: PathBuf,
+    pub timeout: Duration,
+}
+
+/// Check results behind a lock, reported to a callback on every change.
+struct Board<'a> {
+    results: Mutex<Vec<CheckResult>>,
+    on_change: &'a (dyn Fn(&[CheckResult]) + Sync),
+}
+
+impl Board<'_> {
+    fn update(&self, index: usize, f: impl FnOnce(&mut CheckResult)) {
+        let mut results = self.results.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut results[index]);
+        (self.on_change)(&results);
+    }
+
+    fn notify(&self) {
+        (self.on_change)(&self.results.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+/// Run `checks` in `work_dir`, up to `options.jobs` at a time. `on_change`
+/// sees the full result table whenever a check starts or finishes.
+pub async fn run(
+    checks: &[QualityCheck],
+    work_dir: &Path,
+    options: &RunOptions,
+    redactor: &Redactor,
+    on_change: &(dyn Fn(&[CheckResult]) + Sync),
+) -> CheckReport {
+    let started_at = Utc::now();
+    let jobs = options.jobs.max(1);
+    let board = Board {
+        results: Mutex::new(
+            checks
+                .iter()
+                .map(|check| CheckResult {
+                    name: check.name.clone(),
+                    command: redactor.redact(&check.command).into_owned(),
+                    state: CheckState::Pending,
+                    target_dir: check.target_dir(&options.target_dir),
+                    exit_code: None,
+                    timed_out: false,
+                    duration_ms: 0,
+                    output_tail: String::new(),
+                    issues: Vec::new(),
+                    baseline: None,
+                    started: None,
+                })
+                .collect(),
+        ),
+        on_change,
+    };
+    board.notify();
+
+    let permits = tokio::sync::Semaphore::new(jobs);
+    let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+    let runs = checks.iter().enumerate().map(|(i, check)| {
+        let (board, permits, cancel_tx) = (&board, &permits, &cancel_tx);
+        let mut cancel = cancel_rx.clone();
+        async move {
+            // The semaphore is never closed, so acquiring cannot fail.
+            let _permit = permits.acquire().await.ok();
+            if *cancel.borrow() {
+                board.update(i, |r| r.state = CheckState::Cancelled);
+                return;
+            }
+            let started = Instant::now();
+            board.update(i, |r| {
+                r.state = CheckState::Running;
+                r.started = Some(started);
+            });
+            let outcome = tokio::select! {
+                outcome = execute(check, work_dir, options, redactor) => Some(outcome),
+                _ = cancel.wait_for(|cancelled| *cancelled) => None,
+            };
+            let failed = outcome.as_ref().is_some_and(|o| !o.passed);
+            board.update(i, |r| {
+                r.duration_ms = started.elapsed().as_millis() as u64;
+                match outcome {
+                    None => r.state = CheckState::Cancelled,
+                    Some(o) => {
+                        r.state = if o.passed {
+                            CheckState::Passed
+                        } else {
+                            CheckState::Failed
+                        };
+                        r.exit_code = o.exit_code;
+                        r.timed_out = o.timed_out;
+                        r.output_tail = o.output_tail;
+                        r.issues = o.issues;
+                    }
+                }
+            });
+            if failed && options.fail_fast {
+                cancel_tx.send_replace(true);
+            }
+        }
+    });
+    futures::future::join_all(runs).await;
+
+    CheckReport {
+        started_at,
+        jobs,
+        profile: None,
+        checks: board
+            .results
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner()),
+    }
+}
+
+struct Outcome {
+    passed: bool,
+    exit_code: Option<i32>,
+    timed_out: bool,
+    output_tail: String,
+    issues: Vec<String>,
+}
+
+async fn execute(
+    check: &QualityCheck,
+    work_dir: &Path,
+    options: &RunOptions,
+    redactor: &Redactor,
+) -> Outcome {
+    let mut command = tokio::process::Command::new("sh");
+    command
+        .arg("-c")
+        .arg(&check.command)
+        .current_dir(work_dir)
+        .envs(&check.env)
+        .kill_on_drop(true);
+    if let Some(dir) = check.target_dir(&options.target_dir) {
+        command.env("CARGO_TARGET_DIR", dir);
+    }
+
+    let mut outcome = Outcome {
+        passed: false,
+        exit_code: None,
+        timed_out: false,
+        output_tail: String::new(),
+        issues: Vec::new(),
+    };
+    match tokio::time::timeout(options.timeout, command.output()).await {
+        Err(_) => outcome.timed_out = true,
+        Ok(Err(e)) => outcome.output_tail = format!("could not be spawned: {e}"),
+        Ok(Ok(output)) => {
+            outcome.passed = output.status.success();
+            outcome.exit_code = output.status.code();
+            let combined = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            let combined = redactor.redact(&combined);
+            outcome.issues = super::quality_baseline::issues(&combined)
+                .into_iter()
+                .collect();
+            outcome.output_tail = tail(&combined, OUTPUT_TAIL_CHARS);
+        }
+    }
+    outcome
+}
+
+/// Write `report` to [`REPORT_FILE`] under `work_dir`.
+pub async fn save_report(work_dir: &Path, report: &CheckReport) -> Result<()> {
+    let path = work_dir.join(REPORT_FILE);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, serde_json::to_string_pretty(report)?).await?;
+    Ok(())
+}
+
+/// Read the latest report, if one was saved.
+pub async fn load_report(work_dir: &Path) -> Result<Option<CheckReport>> {
+    match tokio::fs::read_to_string(work_dir.join(REPORT_FILE)).await {
+        Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(jobs: usize, fail_fast: bool) -> RunOptions {
+        RunOptions {
+            jobs,
+            fail_fast,
+            target_dir: PathBuf::from("/shared/target"),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    #[test]
+    fn test_select_and_target_dirs() {
+        let checks = select(cargo_checks(), &[], &["security".to_string()]).unwrap();
+        assert_eq!(checks.len(), 4);
+        let only = select(cargo_checks(), &["lint".to_string()], &[]).unwrap();
+        assert_eq!(only.len(), 1);
+        assert!(select(cargo_checks(), &["typo".to_string()], &[]).is_err());
+
+        let shared = Path::new("/shared/target");
+        let mut lint = only[0].clone();
+        assert_eq!(lint.target_dir(shared), Some(shared.to_path_buf()));
+        lint.env
+            .insert("RUSTFLAGS".to_string(), "-Cinstrument-coverage".to_string());
+        assert_eq!(
+            lint.target_dir(shared),
+            Some(PathBuf::from("/shared/target/quality/lint"))
+        );
+        assert_eq!(checks[0].target_dir(shared), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_shares_target_dir_and_fails_fast() {
+        let dir = tempfile::tempdir().unwrap();
+        let checks = vec![
+            QualityCheck::new(
+                "shared",
+                "test \"$CARGO_TARGET_DIR\" = /shared/target",
+                true,
+            ),
+            QualityCheck::new("plain", "test -z \"$CARGO_TARGET_DIR\"", false),
+        ];
+        let report = run(
+            &checks,
+            dir.path(),
+            &options(2, false),
+            &Redactor::default(),
+            &|_| {},
+        )
+        .await;
+        assert!(report.passed(), "{:?}", report.checks);
+
+        let checks = vec![
+            QualityCheck::new("broken", "echo nope; exit 3", false),
+            QualityCheck::new("slow", "sleep 20", false),
+            QualityCheck::new("queued", "true", false),
+        ];
+        let started = Instant::now();
+        let report = run(
+            &checks,
+            dir.path(),
+            &options(2, true),
+            &Redactor::default(),
+            &|_| {},
+        )
+        .await;
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let states: Vec<CheckState> = report.checks.iter().map(|c| c.state).collect();
+        assert_eq!(
+            states,
+            [
+                CheckState::Failed,
+                CheckState::Cancelled,
+                CheckState::Cancelled
+            ]
+        );
+        assert_eq!(report.checks[0].exit_code, Some(3));
+        assert_eq!(report.checks[0].output_tail, "nope");
+    }
+}
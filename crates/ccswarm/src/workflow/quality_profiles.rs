@@ -0,0 +1,261 @@
+//! Quality profiles picked by branch.
+//!
+//! A profile names the quality checks worth running on a kind of branch:
+//! feature branches get the fast checks, release branches the whole test
+//! matrix and the security audit. `quality.branches` maps branch patterns to
+//! profiles, first match wins, so `ccswarm quality check` and the `quality`
+//! gate pick the profile of the branch they run on:
+//!
+//! ```json
+//! "quality": {
+//!   "profiles": {
+//!     "fast": { "checks": ["format", "lint", "test"] },
+//!     "full": { "extends": ["fast"], "checks": ["build", "security"], "matrix": true }
+//!   },
+//!   "branches": [
+//!     { "pattern": "feature/*", "profile": "fast" },
+//!     { "pattern": "release/*", "profile": "full" }
+//!   ]
+//! }
+//! ```
+//!
+//! Check names refer to `quality.checks`, or to the built-in cargo checks
+//! when none are configured. A branch no pattern matches runs every check.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::quality_checks::{self, QualityCheck};
+use crate::config::QualityConfig;
+
+/// A named set of checks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct QualityProfile {
+    /// Profiles whose checks this one runs too.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<String>,
+    /// Also run every cell of `quality.matrix`, one check per cell.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub matrix: bool,
+}
+
+/// Branches matching `pattern` (a glob, `*` spans `/`) use `profile`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BranchProfile {
+    pub pattern: String,
+    pub profile: String,
+}
+
+/// A profile with everything it extends folded in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resolved {
+    /// Check names, in the order they were first listed.
+    pub checks: Vec<String>,
+    pub matrix: bool,
+}
+
+/// Fold `name` and the profiles it extends into one set of checks.
+pub fn resolve(profiles: &BTreeMap<String, QualityProfile>, name: &str) -> Result<Resolved> {
+    fn visit(
+        profiles: &BTreeMap<String, QualityProfile>,
+        name: &str,
+        path: &mut Vec<String>,
+        resolved: &mut Resolved,
+    ) -> Result<()> {
+        if path.iter().any(|seen| seen == name) {
+            path.push(name.to_string());
+            bail!("Quality profiles extend each other: {}", path.join(" -> "));
+        }
+        let Some(profile) = profiles.get(name) else {
+            let known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            bail!(
+                "Unknown quality profile '{}' (known: {})",
+                name,
+                known.join(", ")
+            );
+        };
+        path.push(name.to_string());
+        for parent in &profile.extends {
+            visit(profiles, parent, path, resolved)?;
+        }
+        path.pop();
+        for check in &profile.checks {
+            if !resolved.checks.contains(check) {
+                resolved.checks.push(check.clone());
+            }
+        }
+        resolved.matrix |= profile.matrix;
+        Ok(())
+    }
+
+    let mut resolved = Resolved::default();
+    visit(profiles, name, &mut Vec::new(), &mut resolved)?;
+    Ok(resolved)
+}
+
+/// The profile the first matching pattern gives `branch`.
+pub fn for_branch<'a>(config: &'a QualityConfig, branch: &str) -> Option<&'a str> {
+    config
+        .branches
+        .iter()
+        .find(|rule| {
+            glob::Pattern::new(&rule.pattern)
+                .map(|pattern| pattern.matches(branch))
+                .unwrap_or(false)
+        })
+        .map(|rule| rule.profile.as_str())
+}
+
+/// Patterns must compile and every profile must resolve.
+pub fn validate(config: &QualityConfig) -> Result<()> {
+    for rule in &config.branches {
+        glob::Pattern::new(&rule.pattern)
+            .with_context(|| format!("Invalid quality.branches pattern '{}'", rule.pattern))?;
+        if !config.profiles.contains_key(&rule.profile) {
+            bail!(
+                "quality.branches pattern '{}' uses unknown profile '{}'",
+                rule.pattern,
+                rule.profile
+            );
+        }
+    }
+    for name in config.profiles.keys() {
+        let resolved = resolve(&config.profiles, name)?;
+        if resolved.matrix && config.matrix.is_none() {
+            bail!("Quality profile '{name}' runs the matrix, but quality.matrix is not configured");
+        }
+        if resolved.checks.is_empty() && !resolved.matrix {
+            bail!("Quality profile '{name}' selects no checks");
+        }
+    }
+    Ok(())
+}
+
+/// The checks profile `name` runs, out of the project's `checks`.
+pub fn checks(
+    config: &QualityConfig,
+    name: &str,
+    checks: Vec<QualityCheck>,
+) -> Result<Vec<QualityCheck>> {
+    let resolved = resolve(&config.profiles, name)?;
+    let mut selected = if resolved.checks.is_empty() {
+        Vec::new()
+    } else {
+        quality_checks::select(checks, &resolved.checks, &[])?
+    };
+    if resolved.matrix {
+        let Some(matrix) = &config.matrix else {
+            bail!("Quality profile '{name}' runs the matrix, but quality.matrix is not configured");
+        };
+        selected.extend(matrix.cells().into_iter().map(|cell| {
+            let command = cell.command(&matrix.command);
+            QualityCheck {
+                name: format!("matrix-{}", cell.slug()),
+                cargo: command.trim_start().starts_with("cargo"),
+                command,
+                fix: None,
+                env: cell.env().into_iter().collect(),
+            }
+        }));
+    }
+    Ok(selected)
+}
+
+/// Branch checked out in `work_dir`; `None` when detached or not a repo.
+pub async fn current_branch(work_dir: &Path) -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(work_dir)
+        .output()
+        .await
+        .ok()?;
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !branch.is_empty()).then_some(branch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> QualityConfig {
+        serde_json::from_value(serde_json::json!({
+            "matrix": {
+                "command": "cargo test {features}",
+                "axes": { "features": ["", "--all-features"] },
+            },
+            "profiles": {
+                "fast": { "checks": ["format", "lint", "test"] },
+                "full": { "extends": ["fast"], "checks": ["test", "security"], "matrix": true },
+            },
+            "branches": [
+                { "pattern": "feature/*", "profile": "fast" },
+                { "pattern": "release/*", "profile": "full" },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn branches_pick_their_profile_and_profiles_fold_in_what_they_extend() {
+        let config = config();
+        validate(&config).unwrap();
+        assert_eq!(for_branch(&config, "feature/login/form"), Some("fast"));
+        assert_eq!(for_branch(&config, "release/1.4"), Some("full"));
+        assert_eq!(for_branch(&config, "main"), None);
+
+        let fast = checks(&config, "fast", quality_checks::cargo_checks()).unwrap();
+        let names: Vec<&str> = fast.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["format", "lint", "test"]);
+
+        let full = checks(&config, "full", quality_checks::cargo_checks()).unwrap();
+        let names: Vec<&str> = full.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "format",
+                "lint",
+                "test",
+                "security",
+                "matrix-none",
+                "matrix-all-features"
+            ]
+        );
+        let cell = full.last().unwrap();
+        assert_eq!(cell.command, "cargo test --all-features");
+        assert!(cell.cargo);
+        assert_eq!(
+            cell.env.get("CCSWARM_MATRIX_FEATURES").map(String::as_str),
+            Some("--all-features")
+        );
+    }
+
+    #[test]
+    fn profiles_that_cannot_resolve_are_rejected() {
+        let mut config = config();
+        config.profiles.get_mut("fast").unwrap().extends = vec!["full".into()];
+        let err = validate(&config).unwrap_err().to_string();
+        assert!(err.contains("extend each other"), "{err}");
+
+        let mut config = self::config();
+        config.branches[0].profile = "quick".into();
+        assert!(validate(&config).is_err());
+
+        let mut config = self::config();
+        config.matrix = None;
+        assert!(validate(&config).is_err());
+
+        let config = self::config();
+        let unknown = QualityProfile {
+            checks: vec!["typos".into()],
+            ..Default::default()
+        };
+        let mut profiles = config.profiles.clone();
+        profiles.insert("odd".into(), unknown);
+        let config = QualityConfig { profiles, ..config };
+        assert!(checks(&config, "odd", quality_checks::cargo_checks()).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+//! Policy-driven automatic retries for queued tasks.
+//!
+//! A failed queue task is not marked `failed` straight away: the policy for its
+//! task type (the flow it runs) decides whether another attempt is scheduled,
+//! how long to back off first, and whether the retry should switch to a
+//! fallback agent model or escalate the think mode. Policies live under the
+//! `retry` key of the ccswarm config:
+//!
+//! ```json
+//! "retry": {
+//!   "default": { "max_retries": 1 },
+//!   "task_types": {
+//!     "review-fix": {
+//!       "max_retries": 3,
+//!       "initial_backoff_secs": 60,
+//!       "fallback_models": ["opus"],
+//!       "escalate_think": true
+//!     }
+//!   }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::config::ThinkMode;
+
+/// Retry policies keyed by task type (flow name), with a fallback default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryPolicies {
+    /// Policy for task types without an explicit entry.
+    #[serde(default)]
+    pub default: RetryPolicy,
+    /// Per task type overrides.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub task_types: HashMap<String, RetryPolicy>,
+}
+
+impl RetryPolicies {
+    /// Resolve the policy that applies to `task_type`.
+    pub fn policy_for(&self, task_type: &str) -> &RetryPolicy {
+        self.task_types.get(task_type).unwrap_or(&self.default)
+    }
+}
+
+/// How a single task type is retried.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt. `0` disables automatic retries.
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Delay before the first retry.
+    #[serde(default = "default_initial_backoff_secs")]
+    pub initial_backoff_secs: u64,
+    /// Factor applied to the delay after every further failure.
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    /// Upper bound for any single delay.
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+    /// Models to hand the retry to, in rotation. Empty keeps the original agent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_models: Vec<String>,
+    /// Ask for progressively deeper thinking on each retry.
+    #[serde(default)]
+    pub escalate_think: bool,
+}
+
+fn default_initial_backoff_secs() -> u64 {
+    30
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_backoff_secs() -> u64 {
+    600
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff_secs: default_initial_backoff_secs(),
+            backoff_multiplier: default_backoff_multiplier(),
+            max_backoff_secs: default_max_backoff_secs(),
+            fallback_models: Vec::new(),
+            escalate_think: false,
+        }
+    }
+}
+
+/// Execution parameters for one attempt of a task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttemptPlan {
+    /// 1-based attempt number.
+    pub attempt: u32,
+    /// Model override for this attempt, if the policy switches agents.
+    pub model: Option<String>,
+    /// Think mode requested for this attempt, if the policy escalates.
+    pub think_mode: Option<ThinkMode>,
+}
+
+impl AttemptPlan {
+    /// Plan for a task that has never been retried.
+    pub fn first() -> Self {
+        Self {
+            attempt: 1,
+            model: None,
+            think_mode: None,
+        }
+    }
+
+    /// Bump the think mode one step, e.g. for a task that ran out of time.
+    pub fn escalate(&mut self) {
+        self.think_mode = Some(match self.think_mode.take() {
+            Some(mode) => mode.escalate(),
+            None => ThinkMode::Think,
+        });
+    }
+
+    /// Task prompt for this attempt. Escalated attempts carry the think-mode
+    /// keyword, which is how Claude Code selects its thinking budget.
+    pub fn task_text(&self, task: &str) -> String {
+        match &self.think_mode {
+            Some(mode) => format!("{}\n\n{}.", task.trim_end(), mode.to_prompt_suffix()),
+            None => task.to_string(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Parameters for `attempt` (1-based). The first attempt always runs as
+    /// queued; retries rotate through `fallback_models` and climb the think
+    /// ladder.
+    pub fn plan(&self, attempt: u32) -> AttemptPlan {
+        let retry = attempt.saturating_sub(1);
+        if retry == 0 {
+            return AttemptPlan::first();
+        }
+
+        let model = (!self.fallback_models.is_empty()).then(|| {
+            let index = (retry as usize - 1) % self.fallback_models.len();
+            self.fallback_models[index].clone()
+        });
+        let think_mode = self
+            .escalate_think
+            .then(|| (1..retry).fold(ThinkMode::Think, |mode, _| mode.escalate()));
+
+        AttemptPlan {
+            attempt,
+            model,
+            think_mode,
+        }
+    }
+
+    /// Backoff before the attempt that follows failed attempt `failed_attempt`,
+    /// or `None` when the retry budget is spent.
+    pub fn next_retry(&self, failed_attempt: u32) -> Option<Duration> {
+        if failed_attempt == 0 || failed_attempt > self.max_retries {
+            return None;
+        }
+        let exponent = i32::try_from(failed_attempt - 1).unwrap_or(i32::MAX);
+        let secs =
+            self.initial_backoff_secs as f64 * self.backoff_multiplier.max(1.0).powi(exponent);
+        Some(Duration::from_secs_f64(
+            secs.min(self.max_backoff_secs as f64),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff_secs: 10,
+            backoff_multiplier: 3.0,
+            max_backoff_secs: 60,
+            fallback_models: vec!["opus".to_string(), "sonnet".to_string()],
+            escalate_think: true,
+        }
+    }
+
+    #[test]
+    fn test_default_policy_never_retries() {
+        assert_eq!(RetryPolicy::default().next_retry(1), None);
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = policy();
+        assert_eq!(policy.next_retry(1), Some(Duration::from_secs(10)));
+        assert_eq!(policy.next_retry(2), Some(Duration::from_secs(30)));
+        assert_eq!(policy.next_retry(3), Some(Duration::from_secs(60)));
+        assert_eq!(policy.next_retry(4), None);
+    }
+
+    #[test]
+    fn test_first_attempt_runs_as_queued() {
+        let plan = policy().plan(1);
+        assert_eq!(plan.model, None);
+        assert_eq!(plan.think_mode, None);
+        assert_eq!(plan.task_text("fix it"), "fix it");
+    }
+
+    #[test]
+    fn test_retries_rotate_models_and_escalate_think() {
+        let policy = policy();
+        let second = policy.plan(2);
+        assert_eq!(second.model.as_deref(), Some("opus"));
+        assert_eq!(second.think_mode, Some(ThinkMode::Think));
+
+        let fourth = policy.plan(4);
+        assert_eq!(fourth.model.as_deref(), Some("opus"));
+        assert_eq!(fourth.think_mode, Some(ThinkMode::ThinkHarder));
+        assert_eq!(fourth.task_text("fix it\n"), "fix it\n\nthink harder.");
+    }
+
+    #[test]
+    fn test_task_type_override_falls_back_to_default() {
+        let policies: RetryPolicies = serde_json::from_str(
+            r#"{"default":{"max_retries":1},"task_types":{"review-fix":{"max_retries":3}}}"#,
+        )
+        .unwrap();
+        assert_eq!(policies.policy_for("review-fix").max_retries, 3);
+        assert_eq!(policies.policy_for("default").max_retries, 1);
+        assert_eq!(policies.policy_for("default").initial_backoff_secs, 30);
+    }
+}
@@ -0,0 +1,373 @@
+//! On-demand review of a stage's changes by an ephemeral reviewer.
+//!
+//! A stage with `review:` has its diff checked by a `reviewer` that exists
+//! only for that check. Once the stage's agent call succeeds and its command
+//! gates pass, the flow engine starts a one-shot readonly session
+//! (`<agent>.review`) with the reviewer persona, hands it the stage's task,
+//! the agent's report, the working tree's diff and the rubric, and
+//! unregisters it as soon as it replies. The reviewer ends its reply with
+//!
+//! ```text
+//! REVIEW={"scores": {"correctness": 4, "tests": 2}, "comments": [{"file": "src/lib.rs", "line": 12, "text": "..."}], "required_changes": ["..."]}
+//! ```
+//!
+//! which is parsed into a [`Review`]. A review that asks for changes or
+//! scores a criterion under `min_score` fails the stage's quality gate like a
+//! failing command: its feedback is appended to the instruction and the stage
+//! re-runs, up to `max_retries` times, with a fresh reviewer each time.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::identity::AgentIdentity;
+use crate::session::bridge::{AISessionBridge, MovementExecOptions};
+
+/// Line prefix the reviewer puts its verdict behind.
+pub const REVIEW_PREFIX: &str = "REVIEW=";
+
+/// Highest score a criterion can get.
+pub const MAX_SCORE: u8 = 5;
+
+/// Characters of diff handed to the reviewer; the rest is cut.
+const MAX_DIFF_CHARS: usize = 20_000;
+
+/// Stage-level review configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSpec {
+    /// What the reviewer scores. Defaults to correctness, tests,
+    /// readability and security.
+    #[serde(default = "default_rubric")]
+    pub rubric: Vec<Criterion>,
+    /// Lowest score, 1-5, every criterion needs for the review to pass.
+    #[serde(default = "default_min_score")]
+    pub min_score: u8,
+    /// Persona facet the reviewer runs with.
+    #[serde(default = "default_persona")]
+    pub persona: String,
+    /// Provider for the reviewer (default: the stage's).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Model for the reviewer (default: the stage's).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// How long the reviewer may take, in seconds.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// One rubric criterion.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Criterion {
+    /// Key the reviewer scores it under.
+    pub name: String,
+    /// What a 5 looks like.
+    #[serde(default)]
+    pub description: String,
+}
+
+fn default_rubric() -> Vec<Criterion> {
+    [
+        (
+            "correctness",
+            "Does what the task asks, without bugs or regressions",
+        ),
+        (
+            "tests",
+            "Changed behaviour is covered by tests that would catch a regression",
+        ),
+        (
+            "readability",
+            "Fits the surrounding code; names and structure are clear",
+        ),
+        (
+            "security",
+            "No injection, secret leaks, unsafe input handling or widened permissions",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, description)| Criterion {
+        name: name.to_string(),
+        description: description.to_string(),
+    })
+    .collect()
+}
+
+fn default_min_score() -> u8 {
+    3
+}
+
+fn default_persona() -> String {
+    "reviewer".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    600
+}
+
+/// A structured review of one attempt at a stage.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Review {
+    /// Score per rubric criterion, 1-5.
+    #[serde(default)]
+    pub scores: BTreeMap<String, u8>,
+    /// Remarks that do not block the change.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<ReviewComment>,
+    /// Changes the work needs before it is accepted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_changes: Vec<String>,
+}
+
+/// A remark on the diff, optionally anchored to a file and line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewComment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    pub text: String,
+}
+
+impl std::fmt::Display for ReviewComment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{file}:{line}: {}", self.text),
+            (Some(file), None) => write!(f, "{file}: {}", self.text),
+            _ => f.write_str(&self.text),
+        }
+    }
+}
+
+impl Review {
+    /// Why the review does not pass under `spec`; empty when it does.
+    pub fn problems(&self, spec: &ReviewSpec) -> Vec<String> {
+        let mut problems = Vec::new();
+        for criterion in &spec.rubric {
+            match self.scores.get(&criterion.name.to_lowercase()) {
+                None => problems.push(format!("{} was not scored", criterion.name)),
+                Some(&score) if score < spec.min_score => problems.push(format!(
+                    "{} scored {score}/{MAX_SCORE} (minimum {})",
+                    criterion.name, spec.min_score
+                )),
+                Some(_) => {}
+            }
+        }
+        problems.extend(
+            self.required_changes
+                .iter()
+                .map(|change| format!("required change: {change}")),
+        );
+        problems
+    }
+
+    /// Scores by criterion, e.g. `correctness 4/5, tests 2/5`.
+    pub fn score_line(&self) -> String {
+        self.scores
+            .iter()
+            .map(|(name, score)| format!("{name} {score}/{MAX_SCORE}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The prompt for the reviewer of one attempt.
+pub fn prompt(spec: &ReviewSpec, task: &str, report: &str, diff: &str) -> String {
+    let rubric = spec
+        .rubric
+        .iter()
+        .map(|c| {
+            if c.description.is_empty() {
+                format!("- {}", c.name.to_lowercase())
+            } else {
+                format!("- {}: {}", c.name.to_lowercase(), c.description)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "Review another agent's changes before they are accepted. You only \
+         review: do not edit files.\n\n\
+         # Their task\n{task}\n\n\
+         # Their report\n{report}\n\n\
+         # Diff of the working tree\n```diff\n{diff}\n```\n\n\
+         # Rubric\nScore each criterion from 1 (unacceptable) to {MAX_SCORE} \
+         (nothing to improve); {min} or more passes.\n{rubric}\n\n\
+         List a required change for anything that must be fixed before the work \
+         is accepted, and leave everything else as comments. End your reply with \
+         one line:\n\
+         {REVIEW_PREFIX}{{\"scores\": {{\"<criterion>\": 1-{MAX_SCORE}}}, \
+         \"comments\": [{{\"file\": \"path\", \"line\": 1, \"text\": \"...\"}}], \
+         \"required_changes\": [\"...\"]}}",
+        min = spec.min_score,
+    )
+}
+
+/// The review on the last well-formed `REVIEW=` line of `reply`.
+pub fn parse_review(reply: &str) -> Option<Review> {
+    let mut review: Review = reply.lines().rev().find_map(|line| {
+        let json = line.trim().trim_matches('`').strip_prefix(REVIEW_PREFIX)?;
+        serde_json::from_str(json.trim()).ok()
+    })?;
+    review.scores = review
+        .scores
+        .into_iter()
+        .map(|(name, score)| (name.trim().to_lowercase(), score.clamp(1, MAX_SCORE)))
+        .collect();
+    review.comments.retain(|c| !c.text.trim().is_empty());
+    review.required_changes = review
+        .required_changes
+        .into_iter()
+        .map(|change| change.trim().to_string())
+        .filter(|change| !change.is_empty())
+        .collect();
+    Some(review)
+}
+
+/// Prompt-ready feedback for an attempt whose review did not pass.
+pub fn feedback(spec: &ReviewSpec, review: &Review) -> String {
+    let mut text = String::from("# Gate failure: review\n");
+    let low: Vec<String> = review
+        .problems(spec)
+        .into_iter()
+        .filter(|p| !p.starts_with("required change: "))
+        .collect();
+    if !low.is_empty() {
+        text.push_str("## Scores\n");
+        for problem in &low {
+            text.push_str(&format!("- {problem}\n"));
+        }
+        text.push('\n');
+    }
+    if !review.required_changes.is_empty() {
+        text.push_str("## Required changes\n");
+        for change in &review.required_changes {
+            text.push_str(&format!("- {change}\n"));
+        }
+        text.push('\n');
+    }
+    if !review.comments.is_empty() {
+        text.push_str("## Comments\n");
+        for comment in &review.comments {
+            text.push_str(&format!("- {comment}\n"));
+        }
+        text.push('\n');
+    }
+    text.push_str("Make every required change and address the low scores above.");
+    text
+}
+
+/// `git diff HEAD` of `work_dir` plus its untracked files, cut to a size a
+/// reviewer can read. `None` outside a git repository with commits.
+pub async fn working_diff(work_dir: &Path) -> Option<String> {
+    let git = |args: &'static [&'static str]| async move {
+        let output = tokio::process::Command::new("git")
+            .args(args)
+            .current_dir(work_dir)
+            .output()
+            .await
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let mut diff = git(&["diff", "--relative", "HEAD"]).await?;
+    let untracked = git(&["ls-files", "--others", "--exclude-standard"]).await?;
+    if !untracked.trim().is_empty() {
+        diff.push_str("\n# Untracked files\n");
+        diff.push_str(&untracked);
+    }
+    if diff.len() > MAX_DIFF_CHARS {
+        let cut = diff
+            .char_indices()
+            .map(|(index, _)| index)
+            .take_while(|index| *index <= MAX_DIFF_CHARS)
+            .last()
+            .unwrap_or(0);
+        diff.truncate(cut);
+        diff.push_str("\n... [diff truncated]");
+    }
+    Some(diff)
+}
+
+/// Run one reviewer as a fresh single-turn session under `reviewer_id` and
+/// parse its verdict. The session is unregistered whatever the outcome.
+pub async fn run_reviewer(
+    bridge: &AISessionBridge,
+    reviewer_id: &str,
+    prompt: &str,
+    identity: &AgentIdentity,
+    work_dir: &Path,
+    options: &MovementExecOptions,
+    timeout: Duration,
+) -> Result<(Review, String)> {
+    if let Err(e) = bridge.register_agent(reviewer_id) {
+        tracing::warn!("Failed to register reviewer '{}': {}", reviewer_id, e);
+    }
+    let result = tokio::time::timeout(
+        timeout,
+        bridge.execute_with_retry(reviewer_id, prompt, identity, work_dir, None, 0, 0, options),
+    )
+    .await;
+    bridge.unregister_agent(reviewer_id);
+
+    let result = match result {
+        Err(_) => bail!("reviewer stopped after {}s", timeout.as_secs()),
+        Ok(result) => result.context("reviewer call failed")?,
+    };
+    if !result.success {
+        bail!("reviewer's provider reported failure");
+    }
+    match parse_review(&result.raw) {
+        Some(review) => Ok((review, result.raw)),
+        None => bail!("reviewer's reply has no {REVIEW_PREFIX} line"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_passes_only_with_every_criterion_scored_and_no_required_changes() {
+        let spec: ReviewSpec = serde_yml::from_str("min_score: 3").unwrap();
+        assert_eq!(spec.rubric.len(), 4);
+        assert_eq!(spec.persona, "reviewer");
+
+        let reply = "Looks mostly fine.\n\
+            REVIEW={\"scores\": {\"correctness\": 1}}\n\
+            On reflection:\n\
+            `REVIEW={\"scores\": {\" Correctness \": 4, \"tests\": 9, \"readability\": 0}, \
+            \"comments\": [{\"file\": \"src/lib.rs\", \"line\": 12, \"text\": \"rename x\"}, {\"text\": \" \"}], \
+            \"required_changes\": [\"\", \"handle the empty input\"]}`\n";
+        let review = parse_review(reply).unwrap();
+        assert_eq!(
+            review.score_line(),
+            "correctness 4/5, readability 1/5, tests 5/5"
+        );
+        assert_eq!(review.comments.len(), 1);
+        assert_eq!(review.comments[0].to_string(), "src/lib.rs:12: rename x");
+        assert_eq!(
+            review.problems(&spec),
+            [
+                "readability scored 1/5 (minimum 3)",
+                "security was not scored",
+                "required change: handle the empty input",
+            ]
+        );
+        let feedback = feedback(&spec, &review);
+        assert!(feedback.contains("## Required changes\n- handle the empty input"));
+        assert!(feedback.contains("- src/lib.rs:12: rename x"));
+
+        let clean = parse_review(
+            "REVIEW={\"scores\": {\"correctness\": 5, \"tests\": 3, \"readability\": 4, \"security\": 5}}",
+        )
+        .unwrap();
+        assert!(clean.problems(&spec).is_empty());
+        assert!(parse_review("REVIEW=approve").is_none());
+        assert!(prompt(&spec, "Add a flag", "Added it.", "+flag").contains("- tests: "));
+    }
+}
@@ -159,6 +159,8 @@ pub fn member_stage(parent: &Stage, spec: &SanghaSpec, member: &SanghaMember) ->
         gates: Vec::new(),
         team_leader: None,
         sangha: None,
+        review: None,
+        typed_tools: parent.typed_tools,
     }
 }
 
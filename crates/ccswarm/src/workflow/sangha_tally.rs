@@ -0,0 +1,533 @@
+//! Tallying the votes on Sangha proposals.
+//!
+//! Proposals under `coordination/proposals/` collect votes from named voters
+//! (`ccswarm lab sangha vote <id> --voter <agent>`). An agent can delegate its
+//! vote to another agent, for some proposal categories (the proposal's
+//! `proposal_type`) or for all of them. When it does not vote on a proposal
+//! itself, the vote of its delegate — followed along a chain of delegations —
+//! is counted for it; a direct vote always overrides the delegation.
+//!
+//! Each proposal names its tally method (`--tally`, default `majority`). The
+//! `weighted` method scales every counted vote by the decision quality of the
+//! agent that cast it: how often its past votes on settled proposals agreed
+//! with the outcome, as reported by `ccswarm lab evolution metrics`.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Proposal files, relative to the repository root.
+pub const PROPOSALS_DIR: &str = "coordination/proposals";
+
+/// Standing delegations, relative to the repository root.
+pub const DELEGATIONS_FILE: &str = "coordination/sangha/delegations.json";
+
+/// How a proposal's votes are turned into a verdict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TallyMethod {
+    /// More approving than rejecting votes.
+    #[default]
+    Majority,
+    /// At least two thirds of the counted votes approve.
+    Supermajority,
+    /// Every counted vote approves.
+    Unanimous,
+    /// Majority with each vote weighted by its caster's decision quality.
+    Weighted,
+}
+
+impl TallyMethod {
+    pub fn parse(name: &str) -> Result<Self> {
+        Ok(match name.trim().to_ascii_lowercase().as_str() {
+            "majority" => Self::Majority,
+            "supermajority" => Self::Supermajority,
+            "unanimous" => Self::Unanimous,
+            "weighted" => Self::Weighted,
+            other => bail!(
+                "unknown tally method '{other}' (expected majority, supermajority, unanimous or weighted)"
+            ),
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Majority => "majority",
+            Self::Supermajority => "supermajority",
+            Self::Unanimous => "unanimous",
+            Self::Weighted => "weighted",
+        }
+    }
+
+    /// The method `proposal` was created with; older proposals use majority.
+    pub fn of(proposal: &Value) -> Self {
+        proposal
+            .get("tally")
+            .and_then(|t| t.as_str())
+            .and_then(|t| Self::parse(t).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Outcome of the votes on a Sangha proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Open,
+    Approved,
+    Rejected,
+}
+
+/// `from` lets `to` vote for it on proposals of `category`, or on every
+/// proposal when `category` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delegation {
+    pub from: String,
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub async fn load_delegations(repo: &Path) -> Result<Vec<Delegation>> {
+    let path = repo.join(DELEGATIONS_FILE);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+pub async fn save_delegations(repo: &Path, delegations: &[Delegation]) -> Result<()> {
+    let path = repo.join(DELEGATIONS_FILE);
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(&path, serde_json::to_string_pretty(delegations)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Every readable proposal under [`PROPOSALS_DIR`].
+pub async fn load_proposals(repo: &Path) -> Result<Vec<Value>> {
+    let mut proposals = Vec::new();
+    let mut entries = match tokio::fs::read_dir(repo.join(PROPOSALS_DIR)).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(proposals),
+        Err(e) => return Err(e.into()),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|e| e == "json")
+            && let Ok(content) = tokio::fs::read_to_string(&path).await
+            && let Ok(proposal) = serde_json::from_str::<Value>(&content)
+        {
+            proposals.push(proposal);
+        }
+    }
+    Ok(proposals)
+}
+
+fn same_category(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Make `to` the delegate of `from` for `category`, replacing the delegation
+/// `from` had for it.
+pub fn delegate(
+    delegations: &mut Vec<Delegation>,
+    from: &str,
+    to: &str,
+    category: Option<&str>,
+) -> Result<()> {
+    if from == to {
+        bail!("'{from}' cannot delegate to itself");
+    }
+    revoke(delegations, from, Some(category));
+    delegations.push(Delegation {
+        from: from.to_string(),
+        to: to.to_string(),
+        category: category.map(str::to_string),
+        created_at: Utc::now(),
+    });
+    Ok(())
+}
+
+/// Remove `from`'s delegation for `category` (`Some(None)`: its catch-all
+/// one), or all of its delegations for `None`. Returns how many went.
+pub fn revoke(
+    delegations: &mut Vec<Delegation>,
+    from: &str,
+    category: Option<Option<&str>>,
+) -> usize {
+    let before = delegations.len();
+    delegations.retain(|d| {
+        d.from != from
+            || category.is_some_and(|category| !same_category(d.category.as_deref(), category))
+    });
+    before - delegations.len()
+}
+
+/// How often a voter's past votes agreed with the outcome of the proposal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecisionQuality {
+    pub voter: String,
+    /// Direct votes on proposals that have since been accepted or rejected.
+    pub settled_votes: usize,
+    /// Of those, votes that matched the outcome.
+    pub agreed: usize,
+    /// Vote weight under the `weighted` tally: 1.0 with no history, from 0
+    /// (always on the losing side) up to 2 (always on the winning side).
+    pub weight: f64,
+}
+
+/// Each named voter's latest vote on `proposal`, plus anonymous votes.
+fn ballots(proposal: &Value) -> (BTreeMap<String, bool>, Vec<bool>) {
+    let mut named = BTreeMap::new();
+    let mut anonymous = Vec::new();
+    for vote in proposal
+        .get("votes")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let approve = vote
+            .get("approve")
+            .and_then(|a| a.as_bool())
+            .unwrap_or(false);
+        match vote
+            .get("voter")
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.is_empty())
+        {
+            Some(voter) => {
+                named.insert(voter.to_string(), approve);
+            }
+            None => anonymous.push(approve),
+        }
+    }
+    (named, anonymous)
+}
+
+/// Decision quality of every voter with a direct vote on a settled proposal
+/// (status `accepted` or `rejected`), by voter name.
+pub fn decision_quality<'a>(
+    proposals: impl IntoIterator<Item = &'a Value>,
+) -> Vec<DecisionQuality> {
+    let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for proposal in proposals {
+        let outcome = match proposal.get("status").and_then(|s| s.as_str()) {
+            Some("accepted") => true,
+            Some("rejected") => false,
+            _ => continue,
+        };
+        for (voter, approve) in ballots(proposal).0 {
+            let (settled, agreed) = counts.entry(voter).or_default();
+            *settled += 1;
+            *agreed += usize::from(approve == outcome);
+        }
+    }
+    counts
+        .into_iter()
+        .map(|(voter, (settled_votes, agreed))| DecisionQuality {
+            voter,
+            settled_votes,
+            agreed,
+            // Laplace-smoothed agreement rate, scaled so a newcomer weighs 1.
+            weight: 2.0 * (agreed + 1) as f64 / (settled_votes + 2) as f64,
+        })
+        .collect()
+}
+
+/// One vote as counted for a proposal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CountedVote {
+    /// Whose vote this is; `None` for votes cast without `--voter`.
+    pub voter: Option<String>,
+    pub approve: bool,
+    pub weight: f64,
+    /// The delegate whose vote was counted, when the voter did not vote.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub via: Option<String>,
+}
+
+/// Result of tallying a proposal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tally {
+    pub method: TallyMethod,
+    /// Total weight for and against (vote counts unless `weighted`).
+    pub approve: f64,
+    pub reject: f64,
+    pub votes: Vec<CountedVote>,
+    pub verdict: Verdict,
+}
+
+/// The delegations and vote weights proposals are tallied against.
+#[derive(Debug, Clone, Default)]
+pub struct Electorate {
+    delegations: Vec<Delegation>,
+    weights: HashMap<String, f64>,
+}
+
+impl Electorate {
+    pub fn new(delegations: Vec<Delegation>, quality: &[DecisionQuality]) -> Self {
+        Self {
+            delegations,
+            weights: quality
+                .iter()
+                .map(|q| (q.voter.clone(), q.weight))
+                .collect(),
+        }
+    }
+
+    /// Standing delegations, with weights from the proposals in `proposals`.
+    pub async fn load<'a>(
+        repo: &Path,
+        proposals: impl IntoIterator<Item = &'a Value>,
+    ) -> Result<Self> {
+        Ok(Self::new(
+            load_delegations(repo).await?,
+            &decision_quality(proposals),
+        ))
+    }
+
+    /// `from`'s delegate for `category`: a delegation naming the category
+    /// wins over a catch-all one.
+    fn delegate_of(&self, from: &str, category: Option<&str>) -> Option<&str> {
+        let mine = || self.delegations.iter().filter(move |d| d.from == from);
+        mine()
+            .find(|d| category.is_some() && same_category(d.category.as_deref(), category))
+            .or_else(|| mine().find(|d| d.category.is_none()))
+            .map(|d| d.to.as_str())
+    }
+
+    /// The first agent down `from`'s delegation chain that voted, with its
+    /// vote. `None` when nobody on the chain voted or the chain loops.
+    fn resolve<'a>(
+        &'a self,
+        from: &'a str,
+        category: Option<&str>,
+        direct: &'a BTreeMap<String, bool>,
+    ) -> Option<(&'a str, bool)> {
+        let mut seen = HashSet::from([from]);
+        let mut current = from;
+        loop {
+            let next = self.delegate_of(current, category)?;
+            if !seen.insert(next) {
+                return None;
+            }
+            if let Some(approve) = direct.get(next) {
+                return Some((next, *approve));
+            }
+            current = next;
+        }
+    }
+
+    pub fn weight(&self, voter: &str) -> f64 {
+        self.weights.get(voter).copied().unwrap_or(1.0)
+    }
+
+    pub fn tally(&self, proposal: &Value) -> Tally {
+        let method = TallyMethod::of(proposal);
+        let category = proposal.get("proposal_type").and_then(|t| t.as_str());
+        let (direct, anonymous) = ballots(proposal);
+        let weight = |caster: &str| match method {
+            TallyMethod::Weighted => self.weight(caster),
+            _ => 1.0,
+        };
+
+        let mut votes: Vec<CountedVote> = direct
+            .iter()
+            .map(|(voter, approve)| CountedVote {
+                voter: Some(voter.clone()),
+                approve: *approve,
+                weight: weight(voter),
+                via: None,
+            })
+            .collect();
+        let delegators: BTreeMap<&str, ()> = self
+            .delegations
+            .iter()
+            .filter(|d| !direct.contains_key(&d.from))
+            .map(|d| (d.from.as_str(), ()))
+            .collect();
+        for from in delegators.keys() {
+            if let Some((caster, approve)) = self.resolve(from, category, &direct) {
+                votes.push(CountedVote {
+                    voter: Some(from.to_string()),
+                    approve,
+                    weight: weight(caster),
+                    via: Some(caster.to_string()),
+                });
+            }
+        }
+        votes.extend(anonymous.into_iter().map(|approve| CountedVote {
+            voter: None,
+            approve,
+            weight: 1.0,
+            via: None,
+        }));
+
+        let approve: f64 = votes.iter().filter(|v| v.approve).map(|v| v.weight).sum();
+        let reject: f64 = votes.iter().filter(|v| !v.approve).map(|v| v.weight).sum();
+        let total = approve + reject;
+        let verdict = match method {
+            TallyMethod::Majority | TallyMethod::Weighted => match approve.partial_cmp(&reject) {
+                Some(std::cmp::Ordering::Greater) => Verdict::Approved,
+                Some(std::cmp::Ordering::Less) => Verdict::Rejected,
+                _ => Verdict::Open,
+            },
+            _ if votes.is_empty() => Verdict::Open,
+            TallyMethod::Supermajority if approve * 3.0 >= total * 2.0 => Verdict::Approved,
+            TallyMethod::Unanimous if reject == 0.0 => Verdict::Approved,
+            TallyMethod::Supermajority | TallyMethod::Unanimous => Verdict::Rejected,
+        };
+        Tally {
+            method,
+            approve,
+            reject,
+            votes,
+            verdict,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn proposal(kind: &str, tally: &str, votes: &[(&str, bool)]) -> Value {
+        json!({
+            "proposal_type": kind,
+            "tally": tally,
+            "status": "open",
+            "votes": votes
+                .iter()
+                .map(|(voter, approve)| json!({ "voter": voter, "approve": approve }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    #[test]
+    fn delegated_votes_follow_the_category_and_direct_votes_win() {
+        let mut delegations = Vec::new();
+        delegate(&mut delegations, "qa", "reviewer", None).unwrap();
+        delegate(&mut delegations, "qa", "planner", Some("refactor")).unwrap();
+        delegate(&mut delegations, "frontend", "qa", None).unwrap();
+        delegate(&mut delegations, "backend", "devops", None).unwrap();
+        assert!(delegate(&mut delegations, "qa", "qa", None).is_err());
+        let electorate = Electorate::new(delegations.clone(), &[]);
+
+        // qa → planner for refactors; frontend → qa → planner.
+        let refactor = proposal(
+            "refactor",
+            "majority",
+            &[("planner", false), ("reviewer", true)],
+        );
+        let tally = electorate.tally(&refactor);
+        let via: Vec<_> = tally
+            .votes
+            .iter()
+            .map(|v| (v.voter.as_deref().unwrap(), v.via.as_deref()))
+            .collect();
+        assert_eq!(
+            via,
+            [
+                ("planner", None),
+                ("reviewer", None),
+                ("frontend", Some("planner")),
+                ("qa", Some("planner")),
+            ]
+        );
+        assert_eq!(tally.verdict, Verdict::Rejected);
+
+        // Other categories use qa's catch-all delegate; qa voting itself
+        // overrides it. backend's delegate never voted, so it does not count.
+        let feature = proposal("feature", "majority", &[("reviewer", true), ("qa", false)]);
+        let tally = electorate.tally(&feature);
+        assert_eq!((tally.approve, tally.reject), (1.0, 2.0));
+
+        assert_eq!(revoke(&mut delegations, "qa", Some(Some("REFACTOR"))), 1);
+        assert_eq!(revoke(&mut delegations, "backend", None), 1);
+        assert_eq!(delegations.len(), 2);
+    }
+
+    #[test]
+    fn delegation_cycles_count_nothing() {
+        let mut delegations = Vec::new();
+        delegate(&mut delegations, "a", "b", None).unwrap();
+        delegate(&mut delegations, "b", "a", None).unwrap();
+        let tally = Electorate::new(delegations, &[]).tally(&proposal("x", "majority", &[]));
+        assert!(tally.votes.is_empty());
+        assert_eq!(tally.verdict, Verdict::Open);
+    }
+
+    #[test]
+    fn weighted_tally_favours_voters_with_a_good_record() {
+        let mut history = Vec::new();
+        for _ in 0..4 {
+            let mut settled = proposal(
+                "feature",
+                "majority",
+                &[("oracle", true), ("contrarian", false)],
+            );
+            settled["status"] = json!("accepted");
+            history.push(settled);
+        }
+        history.push(proposal("feature", "majority", &[("oracle", false)]));
+        let quality = decision_quality(&history);
+        assert_eq!(quality[0].voter, "contrarian");
+        assert_eq!((quality[0].settled_votes, quality[0].agreed), (4, 0));
+        assert_eq!((quality[1].settled_votes, quality[1].agreed), (4, 4));
+        assert!(quality[1].weight > 1.5 && quality[0].weight < 0.5);
+
+        let electorate = Electorate::new(Vec::new(), &quality);
+        let votes = [("oracle", true), ("contrarian", false), ("newcomer", false)];
+        assert_eq!(
+            electorate
+                .tally(&proposal("feature", "majority", &votes))
+                .verdict,
+            Verdict::Rejected
+        );
+        let weighted = electorate.tally(&proposal("feature", "weighted", &votes));
+        assert_eq!(weighted.verdict, Verdict::Approved);
+        assert_eq!(weighted.method, TallyMethod::Weighted);
+    }
+
+    #[test]
+    fn methods_apply_their_thresholds() {
+        let electorate = Electorate::default();
+        let two_to_one = [("a", true), ("b", true), ("c", false)];
+        let verdict = |tally: &str, votes: &[(&str, bool)]| {
+            electorate.tally(&proposal("x", tally, votes)).verdict
+        };
+        assert_eq!(verdict("supermajority", &two_to_one), Verdict::Approved);
+        assert_eq!(verdict("unanimous", &two_to_one), Verdict::Rejected);
+        assert_eq!(verdict("unanimous", &two_to_one[..2]), Verdict::Approved);
+        assert_eq!(
+            verdict("supermajority", &[("a", true), ("b", false)]),
+            Verdict::Rejected
+        );
+        assert_eq!(
+            verdict("majority", &[("a", true), ("b", false)]),
+            Verdict::Open
+        );
+        assert_eq!(verdict("unanimous", &[]), Verdict::Open);
+        // A voter's latest vote replaces the earlier one.
+        assert_eq!(
+            verdict("majority", &[("a", false), ("a", true)]),
+            Verdict::Approved
+        );
+        assert!(TallyMethod::parse("plurality").is_err());
+        assert_eq!(TallyMethod::of(&json!({})), TallyMethod::Majority);
+    }
+}
@@ -0,0 +1,318 @@
+//! Agent self-rating and confidence propagation.
+//!
+//! Editing stages end their reply with a `SELF_RATING={...}` line scoring
+//! confidence and completeness on a 1–5 rubric and naming the areas the agent
+//! is unsure about. The engine attaches the parsed rating to the stage output
+//! and records it as a `self_rating` event, so it lands in the run's
+//! `events.ndjson` next to everything else.
+//!
+//! Ratings feed back into delegation: a `team_leader` worker that rates
+//! itself below `review_below`, or whose part touches an area earlier runs
+//! flagged as low-confidence, gets a readonly review pass before its output
+//! is aggregated. `ccswarm status --detailed` shows the per-agent picture.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Line prefix the agent uses to report its rating.
+pub const RATING_PREFIX: &str = "SELF_RATING=";
+
+/// Flow-level self-rating configuration (`self_rating:` in flow YAML).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfRatingSpec {
+    /// Ask editing stages to rate themselves (default true).
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Ratings with confidence or completeness below this need review
+    /// (default 3).
+    #[serde(default = "default_review_below")]
+    pub review_below: u8,
+    /// Persona for the review pass on low-confidence work.
+    #[serde(default = "default_reviewer")]
+    pub reviewer_persona: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_review_below() -> u8 {
+    3
+}
+
+fn default_reviewer() -> String {
+    "reviewer".to_string()
+}
+
+impl Default for SelfRatingSpec {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            review_below: default_review_below(),
+            reviewer_persona: default_reviewer(),
+        }
+    }
+}
+
+/// An agent's assessment of its own work on one stage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelfRating {
+    /// How sure the agent is that the work is correct (1–5).
+    pub confidence: u8,
+    /// How much of the task the agent believes it finished (1–5).
+    pub completeness: u8,
+    /// Parts of the work the agent is least sure about.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub low_confidence_areas: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl SelfRating {
+    pub fn needs_review(&self, review_below: u8) -> bool {
+        self.confidence < review_below || self.completeness < review_below
+    }
+}
+
+/// The rubric appended to an editing stage's prompt.
+pub fn rubric_prompt() -> String {
+    format!(
+        "## Self-rating\n\n\
+         When you are done, rate your own work. End your reply with exactly one line:\n\
+         {RATING_PREFIX}{{\"confidence\": N, \"completeness\": N, \
+         \"low_confidence_areas\": [\"...\"], \"notes\": \"...\"}}\n\n\
+         - confidence (1-5): 5 = verified it works (tests/build pass), 3 = likely \
+         correct but unverified, 1 = guessing.\n\
+         - completeness (1-5): 5 = everything asked for is done, 3 = core done with \
+         gaps, 1 = barely started.\n\
+         - low_confidence_areas: short names of the parts you are least sure about \
+         (e.g. \"auth middleware\"); empty if none.\n\
+         Be honest: low ratings route the work to review, they are not penalized."
+    )
+}
+
+/// Extract the last `SELF_RATING=` line from a reply. Scores are clamped to
+/// 1–5; a missing or malformed line yields `None`.
+pub fn parse_rating(reply: &str) -> Option<SelfRating> {
+    let json = reply
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().trim_matches('`').strip_prefix(RATING_PREFIX))?;
+    let mut rating: SelfRating = serde_json::from_str(json.trim()).ok()?;
+    rating.confidence = rating.confidence.clamp(1, 5);
+    rating.completeness = rating.completeness.clamp(1, 5);
+    rating.low_confidence_areas = rating
+        .low_confidence_areas
+        .into_iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+    Some(rating)
+}
+
+/// A rating as recorded in a run's event log.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedRating {
+    pub run_id: String,
+    pub agent: String,
+    pub stage: String,
+    pub ts: String,
+    pub rating: SelfRating,
+    pub needs_review: bool,
+}
+
+/// Per-agent aggregate of recorded ratings.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AgentRatings {
+    pub count: usize,
+    pub avg_confidence: f64,
+    pub avg_completeness: f64,
+    pub needs_review: usize,
+}
+
+/// Ratings gathered from `.ccswarm/runs/*/events.ndjson`, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct RatingHistory {
+    pub ratings: Vec<RecordedRating>,
+}
+
+impl RatingHistory {
+    /// Collect `self_rating` events from NDJSON content.
+    pub fn extend_from_events(&mut self, content: &str) {
+        for line in content.lines() {
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            if event.get("event_type").and_then(|v| v.as_str()) != Some("self_rating") {
+                continue;
+            }
+            let Some(meta) = event.get("metadata") else {
+                continue;
+            };
+            let Some(rating) = meta
+                .get("rating")
+                .and_then(|r| serde_json::from_value::<SelfRating>(r.clone()).ok())
+            else {
+                continue;
+            };
+            let text = |key: &str| {
+                event
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            self.ratings.push(RecordedRating {
+                run_id: text("run_id"),
+                agent: text("agent"),
+                stage: text("stage"),
+                ts: text("ts"),
+                needs_review: meta
+                    .get("needs_review")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                rating,
+            });
+        }
+    }
+
+    /// Load every run under `runs_dir`. Missing directories give an empty
+    /// history.
+    pub async fn load(runs_dir: &Path) -> Self {
+        let mut history = Self::default();
+        let Ok(mut entries) = tokio::fs::read_dir(runs_dir).await else {
+            return history;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if let Ok(content) = tokio::fs::read_to_string(entry.path().join("events.ndjson")).await
+            {
+                history.extend_from_events(&content);
+            }
+        }
+        history.ratings.sort_by(|a, b| a.ts.cmp(&b.ts));
+        history
+    }
+
+    pub fn by_agent(&self) -> BTreeMap<String, AgentRatings> {
+        let mut out: BTreeMap<String, AgentRatings> = BTreeMap::new();
+        for r in &self.ratings {
+            let agg = out.entry(r.agent.clone()).or_default();
+            agg.count += 1;
+            agg.avg_confidence += r.rating.confidence as f64;
+            agg.avg_completeness += r.rating.completeness as f64;
+            agg.needs_review += r.needs_review as usize;
+        }
+        for agg in out.values_mut() {
+            agg.avg_confidence /= agg.count as f64;
+            agg.avg_completeness /= agg.count as f64;
+        }
+        out
+    }
+
+    /// Areas named by ratings that needed review, lowercased.
+    pub fn weak_areas(&self) -> BTreeSet<String> {
+        self.ratings
+            .iter()
+            .filter(|r| r.needs_review)
+            .flat_map(|r| r.rating.low_confidence_areas.iter())
+            .map(|a| a.to_lowercase())
+            .collect()
+    }
+}
+
+/// The weak areas `text` mentions (case-insensitive).
+pub fn mentioned_areas(weak: &BTreeSet<String>, text: &str) -> Vec<String> {
+    let text = text.to_lowercase();
+    weak.iter()
+        .filter(|area| text.contains(area.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Instruction for the review pass over a low-confidence worker's output.
+pub fn review_instruction(
+    part_instruction: &str,
+    worker_output: &str,
+    reasons: &[String],
+) -> String {
+    format!(
+        "Review another agent's work before it is accepted.\n\n\
+         # Why review is required\n{reasons}\n\n\
+         # Their task\n{task}\n\n\
+         # Their report\n{output}\n\n\
+         Inspect the changes in the working tree. List concrete problems with \
+         file and line, or state that the work is acceptable. End with \
+         REVIEW=approve or REVIEW=revise.",
+        reasons = reasons
+            .iter()
+            .map(|r| format!("- {r}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        task = part_instruction,
+        output = worker_output,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rating_takes_last_line_and_clamps() {
+        let reply = "Done.\nSELF_RATING={\"confidence\": 1, \"completeness\": 1}\n\
+                     Fixed the tests too.\n\
+                     `SELF_RATING={\"confidence\": 9, \"completeness\": 4, \
+                     \"low_confidence_areas\": [\" migrations \", \"\"]}`\n";
+        let rating = parse_rating(reply).unwrap();
+        assert_eq!(rating.confidence, 5);
+        assert_eq!(rating.completeness, 4);
+        assert_eq!(rating.low_confidence_areas, ["migrations"]);
+        assert!(!rating.needs_review(3));
+
+        assert!(parse_rating("no rating here").is_none());
+        assert!(parse_rating("SELF_RATING={oops}").is_none());
+    }
+
+    #[test]
+    fn history_aggregates_by_agent_and_collects_weak_areas() {
+        let events = [
+            serde_json::json!({
+                "ts": "2026-10-01T10:00:00Z", "run_id": "r1", "event_type": "self_rating",
+                "agent": "coder", "stage": "implement", "level": "info", "message": "",
+                "metadata": {"rating": {"confidence": 2, "completeness": 4,
+                    "low_confidence_areas": ["Auth middleware"]}, "needs_review": true}
+            }),
+            serde_json::json!({
+                "ts": "2026-10-01T10:05:00Z", "run_id": "r1", "event_type": "self_rating",
+                "agent": "coder", "stage": "fix", "level": "info", "message": "",
+                "metadata": {"rating": {"confidence": 4, "completeness": 4,
+                    "low_confidence_areas": ["caching"]}, "needs_review": false}
+            }),
+            serde_json::json!({
+                "ts": "2026-10-01T10:06:00Z", "run_id": "r1", "event_type": "movement_end",
+                "level": "info", "message": ""
+            }),
+        ];
+        let content = events
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut history = RatingHistory::default();
+        history.extend_from_events(&content);
+
+        let coder = &history.by_agent()["coder"];
+        assert_eq!(coder.count, 2);
+        assert_eq!(coder.avg_confidence, 3.0);
+        assert_eq!(coder.needs_review, 1);
+
+        let weak = history.weak_areas();
+        assert_eq!(weak.iter().collect::<Vec<_>>(), ["auth middleware"]);
+        assert_eq!(
+            mentioned_areas(&weak, "Add JWT checks to the Auth Middleware"),
+            ["auth middleware"]
+        );
+        assert!(mentioned_areas(&weak, "Add a caching layer").is_empty());
+    }
+}
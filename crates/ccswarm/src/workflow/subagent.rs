@@ -0,0 +1,503 @@
+//! Temporary sub-agents requested by a stage's agent.
+//!
+//! An agent that wants a bounded piece of work done on the side ends its
+//! reply with one fenced block per subtask:
+//!
+//! ~~~text
+//! ```ccswarm-subagent
+//! {"task": "Write unit tests for parse_duration", "context": ["parse_duration"]}
+//! ```
+//! ~~~
+//!
+//! After the stage's provider call succeeds, the flow engine runs each request
+//! as a one-shot session of its own (`<agent>.sub-<n>`), in the same working
+//! directory and with the stage's provider options. The sub-agent does not see
+//! the parent's conversation: it only inherits the paragraphs of the parent's
+//! prompt and reply that mention one of the request's `context` keywords. Each
+//! sub-agent is stopped when its lifetime runs out, and a reply that used more
+//! tokens than its budget is reported but not merged. Finished sub-agents are
+//! unregistered from the bridge; their replies are appended to the stage output
+//! and their tokens count toward the stage's.
+//!
+//! Limits live under `subagents` in `ccswarm.json`. A request can ask for less
+//! than the configured limits, never more.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::identity::AgentIdentity;
+use crate::session::bridge::{AISessionBridge, ContinuationPolicy, MovementExecOptions};
+
+/// Info string of the fenced block that carries a request.
+const REQUEST_FENCE: &str = "```ccswarm-subagent";
+
+/// Limits for sub-agents spawned during flow stages.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubagentConfig {
+    /// Whether stages may spawn sub-agents at all.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Requests honored per stage execution; later ones are rejected.
+    #[serde(default = "default_max_per_stage")]
+    pub max_per_stage: usize,
+    /// Wall-clock lifetime of one sub-agent.
+    #[serde(default = "default_max_lifetime_secs")]
+    pub max_lifetime_secs: u64,
+    /// Input plus output tokens one sub-agent may use.
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u64,
+    /// Characters of parent context handed to a sub-agent.
+    #[serde(default = "default_max_context_chars")]
+    pub max_context_chars: usize,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_per_stage() -> usize {
+    3
+}
+
+fn default_max_lifetime_secs() -> u64 {
+    600
+}
+
+fn default_max_tokens() -> u64 {
+    50_000
+}
+
+fn default_max_context_chars() -> usize {
+    8_000
+}
+
+impl Default for SubagentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_per_stage: default_max_per_stage(),
+            max_lifetime_secs: default_max_lifetime_secs(),
+            max_tokens: default_max_tokens(),
+            max_context_chars: default_max_context_chars(),
+        }
+    }
+}
+
+impl SubagentConfig {
+    /// Lifetime and token budget for `request`, capped by this config.
+    pub fn limits_for(&self, request: &SubagentRequest) -> (Duration, u64) {
+        let lifetime = request.timeout_secs.map_or(self.max_lifetime_secs, |secs| {
+            secs.min(self.max_lifetime_secs)
+        });
+        let tokens = request
+            .max_tokens
+            .map_or(self.max_tokens, |tokens| tokens.min(self.max_tokens));
+        (Duration::from_secs(lifetime), tokens)
+    }
+}
+
+/// A subtask an agent asked to hand to a sub-agent.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct SubagentRequest {
+    pub task: String,
+    /// Keywords selecting the parent context the sub-agent inherits.
+    #[serde(default)]
+    pub context: Vec<String>,
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Response-format section telling a stage's agent how to request sub-agents.
+pub fn request_instructions(config: &SubagentConfig) -> String {
+    format!(
+        "If a self-contained part of the work is better done separately, you may \
+         delegate up to {} subtask(s) to temporary sub-agents. End your reply with one \
+         block per subtask:\n\n{REQUEST_FENCE}\n{{\"task\": \"<what to do>\", \
+         \"context\": [\"<keyword>\"]}}\n```\n\nA sub-agent only sees paragraphs of this \
+         conversation that mention one of the keywords, runs for at most {}s, and its \
+         result is appended to your reply.",
+        config.max_per_stage, config.max_lifetime_secs
+    )
+}
+
+/// Requests in an agent reply, in order. Malformed blocks are logged and skipped.
+pub fn parse_requests(output: &str) -> Vec<SubagentRequest> {
+    let mut requests = Vec::new();
+    let mut rest = output;
+    while let Some(start) = rest.find(REQUEST_FENCE) {
+        let body = &rest[start + REQUEST_FENCE.len()..];
+        let Some(end) = body.find("```") else {
+            warn!("Unterminated sub-agent request block ignored");
+            break;
+        };
+        match serde_json::from_str::<SubagentRequest>(body[..end].trim()) {
+            Ok(request) if !request.task.trim().is_empty() => requests.push(request),
+            Ok(_) => warn!("Sub-agent request without a task ignored"),
+            Err(e) => warn!("Malformed sub-agent request ignored: {}", e),
+        }
+        rest = &body[end + 3..];
+    }
+    requests
+}
+
+/// Paragraphs of `sources` that mention one of `keywords` (case-insensitive),
+/// in order and cut off at `max_chars`. Empty when no keywords are given.
+pub fn filter_context(sources: &[&str], keywords: &[String], max_chars: usize) -> String {
+    let keywords: Vec<String> = keywords
+        .iter()
+        .map(|k| k.trim().to_lowercase())
+        .filter(|k| !k.is_empty())
+        .collect();
+    let mut context = String::new();
+    if keywords.is_empty() {
+        return context;
+    }
+    for paragraph in sources.iter().flat_map(|source| source.split("\n\n")) {
+        let paragraph = paragraph.trim();
+        if paragraph.is_empty() || paragraph.contains(REQUEST_FENCE) {
+            continue;
+        }
+        let lower = paragraph.to_lowercase();
+        if !keywords.iter().any(|k| lower.contains(k.as_str())) {
+            continue;
+        }
+        let remaining = max_chars.saturating_sub(context.len());
+        if remaining == 0 {
+            break;
+        }
+        let kept: String = paragraph.chars().take(remaining).collect();
+        context.push_str(&kept);
+        context.push_str("\n\n");
+    }
+    context.truncate(context.trim_end().len());
+    context
+}
+
+fn build_prompt(parent_id: &str, request: &SubagentRequest, context: &str) -> String {
+    let mut prompt = format!(
+        "You are a temporary sub-agent working for the '{parent_id}' agent. \
+         Do only the subtask below, then reply with a short summary of what you \
+         did and anything the parent needs to know. Do not start further sub-agents.\n\n\
+         # Subtask\n{}\n",
+        request.task.trim()
+    );
+    if !context.is_empty() {
+        prompt.push_str(&format!("\n# Context from the parent agent\n{context}\n"));
+    }
+    prompt
+}
+
+/// How a sub-agent ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubagentStatus {
+    Completed,
+    Failed,
+    TimedOut,
+    OverBudget,
+    /// Not started: the stage already used its `max_per_stage` sub-agents.
+    Rejected,
+}
+
+impl std::fmt::Display for SubagentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::TimedOut => "timed_out",
+            Self::OverBudget => "over_budget",
+            Self::Rejected => "rejected",
+        })
+    }
+}
+
+/// Result of one sub-agent, as merged into the stage output.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubagentOutcome {
+    pub id: String,
+    pub task: String,
+    pub status: SubagentStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub duration_ms: u64,
+}
+
+impl SubagentOutcome {
+    fn rejected(id: String, request: &SubagentRequest, max_per_stage: usize) -> Self {
+        Self {
+            id,
+            task: request.task.clone(),
+            status: SubagentStatus::Rejected,
+            output: None,
+            error: Some(format!(
+                "limit of {max_per_stage} sub-agents per stage reached"
+            )),
+            tokens_in: 0,
+            tokens_out: 0,
+            duration_ms: 0,
+        }
+    }
+}
+
+/// Everything a sub-agent inherits from the stage that requested it.
+pub struct SubagentParent<'a> {
+    pub agent_id: &'a str,
+    pub identity: &'a AgentIdentity,
+    pub work_dir: &'a Path,
+    pub options: &'a MovementExecOptions,
+    /// Prompt and reply of the parent, the pool inherited context is picked from.
+    pub context_sources: Vec<&'a str>,
+}
+
+/// Run `requests` one after another on `bridge`, honoring `config`.
+pub async fn run_subagents(
+    bridge: &AISessionBridge,
+    parent: &SubagentParent<'_>,
+    requests: &[SubagentRequest],
+    config: &SubagentConfig,
+) -> Vec<SubagentOutcome> {
+    let mut outcomes = Vec::with_capacity(requests.len());
+    for (index, request) in requests.iter().enumerate() {
+        let id = format!("{}.sub-{}", parent.agent_id, index + 1);
+        if index >= config.max_per_stage {
+            outcomes.push(SubagentOutcome::rejected(id, request, config.max_per_stage));
+            continue;
+        }
+        outcomes.push(run_one(bridge, parent, id, request, config).await);
+    }
+    outcomes
+}
+
+async fn run_one(
+    bridge: &AISessionBridge,
+    parent: &SubagentParent<'_>,
+    id: String,
+    request: &SubagentRequest,
+    config: &SubagentConfig,
+) -> SubagentOutcome {
+    let (lifetime, token_budget) = config.limits_for(request);
+    let context = filter_context(
+        &parent.context_sources,
+        &request.context,
+        config.max_context_chars,
+    );
+    let prompt = build_prompt(parent.agent_id, request, &context);
+    // A sub-agent is a fresh one-shot session: never resume the parent's thread.
+    let options = MovementExecOptions {
+        session_id: None,
+        continuation: ContinuationPolicy::SingleTurn,
+        ..parent.options.clone()
+    };
+
+    if let Err(e) = bridge.register_agent(&id) {
+        warn!("Failed to register sub-agent '{}': {}", id, e);
+    }
+    let started = Instant::now();
+    let result = tokio::time::timeout(
+        lifetime,
+        bridge.execute_with_retry(
+            &id,
+            &prompt,
+            parent.identity,
+            parent.work_dir,
+            None,
+            0,
+            0,
+            &options,
+        ),
+    )
+    .await;
+    bridge.unregister_agent(&id);
+
+    let mut outcome = SubagentOutcome {
+        id,
+        task: request.task.clone(),
+        status: SubagentStatus::Completed,
+        output: None,
+        error: None,
+        tokens_in: 0,
+        tokens_out: 0,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    match result {
+        Err(_) => {
+            outcome.status = SubagentStatus::TimedOut;
+            outcome.error = Some(format!("stopped after {}s", lifetime.as_secs()));
+        }
+        Ok(Err(e)) => {
+            outcome.status = SubagentStatus::Failed;
+            outcome.error = Some(e.to_string());
+        }
+        Ok(Ok(result)) => {
+            outcome.tokens_in = result.tokens_in.unwrap_or(0);
+            outcome.tokens_out = result.tokens_out.unwrap_or(0);
+            let used = outcome.tokens_in + outcome.tokens_out;
+            if used > token_budget {
+                outcome.status = SubagentStatus::OverBudget;
+                outcome.error = Some(format!(
+                    "used {used} tokens, budget is {token_budget}; reply discarded"
+                ));
+            } else if result.success {
+                outcome.output = Some(result.raw);
+            } else {
+                outcome.status = SubagentStatus::Failed;
+                outcome.error = Some("provider reported failure".to_string());
+                outcome.output = Some(result.raw);
+            }
+        }
+    }
+    outcome
+}
+
+/// Merge `outcomes` into a stage output: completed replies are appended to
+/// `output`, every outcome is listed under `subagents`, and sub-agent tokens
+/// are added to the stage's.
+pub fn merge_outcomes(stage_output: &mut serde_json::Value, outcomes: &[SubagentOutcome]) {
+    let Some(obj) = stage_output.as_object_mut() else {
+        return;
+    };
+    if outcomes.is_empty() {
+        return;
+    }
+
+    let mut section = String::from("\n\n## Sub-agent results\n");
+    for outcome in outcomes {
+        section.push_str(&format!("\n### {} — {}\n", outcome.id, outcome.task));
+        match (&outcome.status, &outcome.output, &outcome.error) {
+            (SubagentStatus::Completed, Some(output), _) => section.push_str(output.trim()),
+            (status, _, error) => section.push_str(&format!(
+                "({status}: {})",
+                error.as_deref().unwrap_or("no reply")
+            )),
+        }
+        section.push('\n');
+    }
+    let merged = match obj.get("output").and_then(|o| o.as_str()) {
+        Some(output) => format!("{output}{section}"),
+        None => section.trim_start().to_string(),
+    };
+    obj.insert("output".into(), serde_json::json!(merged));
+
+    for (key, extra) in [
+        (
+            "tokens_in",
+            outcomes.iter().map(|o| o.tokens_in).sum::<u64>(),
+        ),
+        (
+            "tokens_out",
+            outcomes.iter().map(|o| o.tokens_out).sum::<u64>(),
+        ),
+    ] {
+        let current = obj.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        obj.insert(key.into(), serde_json::json!(current + extra));
+    }
+    obj.insert("subagents".into(), serde_json::json!(outcomes));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(status: SubagentStatus, output: Option<&str>, tokens: u64) -> SubagentOutcome {
+        SubagentOutcome {
+            id: "coder.sub-1".to_string(),
+            task: "write tests".to_string(),
+            status,
+            output: output.map(String::from),
+            error: (status != SubagentStatus::Completed).then(|| "stopped after 5s".to_string()),
+            tokens_in: tokens,
+            tokens_out: tokens,
+            duration_ms: 10,
+        }
+    }
+
+    #[test]
+    fn parses_requests_and_skips_malformed_blocks() {
+        let reply = "Done with the parser.\n\n\
+            ```ccswarm-subagent\n{\"task\": \"Write tests for parse_duration\", \"context\": [\"parse_duration\"], \"timeout_secs\": 60}\n```\n\
+            ```ccswarm-subagent\nnot json\n```\n\
+            ```ccswarm-subagent\n{\"task\": \"  \"}\n```\n\
+            ```ccswarm-subagent\n{\"task\": \"Update the changelog\"}\n```";
+
+        let requests = parse_requests(reply);
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].task, "Write tests for parse_duration");
+        assert_eq!(requests[0].context, ["parse_duration"]);
+        assert_eq!(requests[1].task, "Update the changelog");
+    }
+
+    #[test]
+    fn request_limits_never_exceed_config() {
+        let config = SubagentConfig {
+            max_lifetime_secs: 120,
+            max_tokens: 1_000,
+            ..SubagentConfig::default()
+        };
+        let mut request = SubagentRequest {
+            task: "t".to_string(),
+            context: Vec::new(),
+            max_tokens: Some(5_000),
+            timeout_secs: Some(30),
+        };
+        assert_eq!(
+            config.limits_for(&request),
+            (Duration::from_secs(30), 1_000)
+        );
+        request.max_tokens = None;
+        request.timeout_secs = None;
+        assert_eq!(
+            config.limits_for(&request),
+            (Duration::from_secs(120), 1_000)
+        );
+    }
+
+    #[test]
+    fn context_keeps_only_matching_paragraphs() {
+        let prompt = "Implement parse_duration in src/duration.rs.\n\nAlso fix the README.";
+        let reply = "I added Parse_Duration.\n\nThe CI config is untouched.\n\n\
+                     ```ccswarm-subagent\n{\"task\": \"parse_duration tests\"}\n```";
+
+        let context = filter_context(&[prompt, reply], &["parse_duration".to_string()], 1_000);
+        assert_eq!(
+            context,
+            "Implement parse_duration in src/duration.rs.\n\nI added Parse_Duration."
+        );
+        assert_eq!(filter_context(&[prompt], &[], 1_000), "");
+        assert_eq!(
+            filter_context(&[prompt], &["parse_duration".to_string()], 9),
+            "Implement"
+        );
+    }
+
+    #[test]
+    fn merge_appends_completed_replies_and_adds_tokens() {
+        let mut stage = serde_json::json!({
+            "output": "parent reply",
+            "tokens_in": 100,
+            "tokens_out": 50,
+        });
+        merge_outcomes(
+            &mut stage,
+            &[
+                outcome(SubagentStatus::Completed, Some("tests written"), 10),
+                outcome(SubagentStatus::TimedOut, None, 0),
+            ],
+        );
+
+        let output = stage["output"].as_str().unwrap();
+        assert!(output.starts_with("parent reply\n\n## Sub-agent results"));
+        assert!(output.contains("tests written"));
+        assert!(output.contains("(timed_out: stopped after 5s)"));
+        assert_eq!(stage["tokens_in"], 110);
+        assert_eq!(stage["tokens_out"], 60);
+        assert_eq!(stage["subagents"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,284 @@
+//! Supervisor policies for runaway command loops.
+//!
+//! An agent that keeps running the same command and keeps getting the same
+//! failure back burns its budget without getting anywhere. The engine feeds
+//! every shell command a provider call reports (Claude stream-json, Codex
+//! JSONL) to a [`LoopSupervisor`], which counts how many times in a row the
+//! same command — compared by a hash of its normalized text — has failed.
+//! As the streak grows it steps in, once per level:
+//!
+//! 1. **guide** — the next prompt tells the agent what it has been repeating
+//!    and asks it to change approach;
+//! 2. **escalate** — later prompts carry a stronger think-mode keyword;
+//! 3. **pause** — the flow stops with a blocker asking a person how to go on,
+//!    which `queue drain` parks as `blocked`.
+//!
+//! ```json
+//! "supervisor": { "guide_after": 3, "escalate_after": 5, "pause_after": 8 }
+//! ```
+//!
+//! A threshold of `0` turns that level off. A different command, or the same
+//! one succeeding, ends the streak.
+
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+use super::blocker::{Blocker, BlockerKind};
+use crate::config::ThinkMode;
+use crate::providers::CommandRun;
+
+/// Characters of the looping command quoted back in guidance and blockers.
+const MAX_QUOTED: usize = 200;
+
+/// Consecutive failures of one command before each intervention.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    #[serde(default = "default_guide_after")]
+    pub guide_after: u32,
+    #[serde(default = "default_escalate_after")]
+    pub escalate_after: u32,
+    #[serde(default = "default_pause_after")]
+    pub pause_after: u32,
+}
+
+fn default_guide_after() -> u32 {
+    3
+}
+
+fn default_escalate_after() -> u32 {
+    5
+}
+
+fn default_pause_after() -> u32 {
+    8
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            guide_after: default_guide_after(),
+            escalate_after: default_escalate_after(),
+            pause_after: default_pause_after(),
+        }
+    }
+}
+
+/// How the supervisor steps in, mildest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Intervention {
+    Guide,
+    Escalate,
+    Pause,
+}
+
+impl SupervisorConfig {
+    /// The strongest intervention a streak of `failures` calls for.
+    pub fn intervention(&self, failures: u32) -> Option<Intervention> {
+        [
+            (Intervention::Pause, self.pause_after),
+            (Intervention::Escalate, self.escalate_after),
+            (Intervention::Guide, self.guide_after),
+        ]
+        .into_iter()
+        .find(|(_, threshold)| *threshold > 0 && failures >= *threshold)
+        .map(|(intervention, _)| intervention)
+    }
+}
+
+/// A command the agent is stuck on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandLoop {
+    pub command: String,
+    /// Hash of the normalized command.
+    pub hash: String,
+    /// Failures in a row.
+    pub failures: u32,
+    pub intervention: Intervention,
+}
+
+/// Watches one run's commands and holds what its next prompts need.
+#[derive(Debug, Default)]
+pub struct LoopSupervisor {
+    config: SupervisorConfig,
+    hash: Option<u64>,
+    command: String,
+    failures: u32,
+    /// Strongest intervention made during the current streak.
+    applied: Option<Intervention>,
+    guidance: Option<String>,
+    think_mode: Option<ThinkMode>,
+    pause: Option<Blocker>,
+}
+
+impl LoopSupervisor {
+    pub fn new(config: SupervisorConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Feed the commands one provider call ran. Returns the loop when the
+    /// streak reached a level not acted on yet.
+    pub fn observe(&mut self, commands: &[CommandRun]) -> Option<CommandLoop> {
+        let mut reached = None;
+        for run in commands {
+            let hash = command_hash(&run.command);
+            if !run.failed {
+                self.reset(None);
+                continue;
+            }
+            if self.hash != Some(hash) {
+                self.reset(Some((hash, &run.command)));
+            }
+            self.failures += 1;
+            if let Some(intervention) = self.config.intervention(self.failures)
+                && self.applied < Some(intervention)
+            {
+                self.applied = Some(intervention);
+                reached = Some(intervention);
+            }
+        }
+        let intervention = reached?;
+        let command = quote(&self.command);
+        match intervention {
+            Intervention::Guide => {}
+            Intervention::Escalate => {
+                self.think_mode = Some(match self.think_mode.take() {
+                    Some(mode) => mode.escalate(),
+                    None => ThinkMode::ThinkHard,
+                });
+            }
+            Intervention::Pause => {
+                self.pause = Some(Blocker {
+                    kind: BlockerKind::Other,
+                    detail: format!(
+                        "the agent ran `{command}` {} times in a row and it failed every time",
+                        self.failures
+                    ),
+                    credential: None,
+                    task: None,
+                    service: None,
+                    question: Some(format!(
+                        "How should the agent get past the failing `{command}`?"
+                    )),
+                });
+            }
+        }
+        self.guidance = Some(format!(
+            "You have run `{command}` {} times in a row and it failed every time. \
+             Running it again unchanged will fail the same way. Stop, read the \
+             error output carefully, and change approach: fix the cause, run a \
+             different command to investigate, or report a blocker if something \
+             outside your control is in the way.",
+            self.failures
+        ));
+        Some(CommandLoop {
+            command: self.command.clone(),
+            hash: format!("{:016x}", command_hash(&self.command)),
+            failures: self.failures,
+            intervention,
+        })
+    }
+
+    /// Guidance for the next prompt, given once.
+    pub fn take_guidance(&mut self) -> Option<String> {
+        self.guidance.take()
+    }
+
+    /// Think mode the run's prompts carry since an escalation.
+    pub fn think_mode(&self) -> Option<&ThinkMode> {
+        self.think_mode.as_ref()
+    }
+
+    /// The blocker that pauses the run, once the streak reached `pause_after`.
+    pub fn take_pause(&mut self) -> Option<Blocker> {
+        self.pause.take()
+    }
+
+    fn reset(&mut self, command: Option<(u64, &str)>) {
+        self.hash = command.map(|(hash, _)| hash);
+        self.command = command.map(|(_, c)| c.to_string()).unwrap_or_default();
+        self.failures = 0;
+        self.applied = None;
+    }
+}
+
+/// Hash of `command` with whitespace runs collapsed, so reformatting the
+/// same invocation doesn't hide a loop.
+fn command_hash(command: &str) -> u64 {
+    let normalized = command.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    normalized.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn quote(command: &str) -> String {
+    let line = command.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(MAX_QUOTED) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(command: &str, failed: bool) -> CommandRun {
+        CommandRun {
+            command: command.to_string(),
+            failed,
+        }
+    }
+
+    #[test]
+    fn repeated_failures_guide_then_escalate_then_pause() {
+        let mut supervisor = LoopSupervisor::new(SupervisorConfig {
+            guide_after: 2,
+            escalate_after: 3,
+            pause_after: 4,
+        });
+        // A success or a different command ends the streak.
+        let mixed = [
+            run("cargo test", true),
+            run("cargo test", false),
+            run("cargo test", true),
+            run("cargo build", true),
+        ];
+        assert_eq!(supervisor.observe(&mixed), None);
+
+        let reached = supervisor
+            .observe(&[run("cargo  build", true)])
+            .expect("second failure guides");
+        assert_eq!(reached.intervention, Intervention::Guide);
+        assert_eq!(reached.failures, 2);
+        assert!(
+            supervisor
+                .take_guidance()
+                .unwrap()
+                .contains("`cargo build` 2 times")
+        );
+        assert!(supervisor.take_guidance().is_none());
+        assert!(supervisor.think_mode().is_none());
+
+        let reached = supervisor.observe(&[run("cargo build", true)]).unwrap();
+        assert_eq!(reached.intervention, Intervention::Escalate);
+        assert_eq!(supervisor.think_mode(), Some(&ThinkMode::ThinkHard));
+        assert!(supervisor.take_pause().is_none());
+
+        let reached = supervisor.observe(&[run("cargo build", true)]).unwrap();
+        assert_eq!(reached.intervention, Intervention::Pause);
+        let blocker = supervisor.take_pause().unwrap();
+        assert!(blocker.needs_human());
+        assert_eq!(supervisor.observe(&[run("cargo build", true)]), None);
+
+        let off = SupervisorConfig {
+            guide_after: 0,
+            escalate_after: 0,
+            pause_after: 0,
+        };
+        assert_eq!(off.intervention(100), None);
+    }
+}
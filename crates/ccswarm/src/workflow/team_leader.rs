@@ -66,6 +66,22 @@ pub fn decomposition_prompt(task_instruction: &str, max_parts: u32) -> String {
     )
 }
 
+/// Appended to the decomposition prompt when the task touches areas earlier
+/// runs rated low-confidence, so each lands in a part of its own and can be
+/// reviewed separately.
+pub fn weak_areas_note(areas: &[String]) -> String {
+    format!(
+        "\n\n# Low-confidence areas\nEarlier work on {} was rated low-confidence. \
+         Give each of these its own part; those parts will be reviewed before \
+         they are accepted.",
+        areas
+            .iter()
+            .map(|a| format!("\"{a}\""))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
 /// Extract the JSON array of parts from a leader reply. Tolerates markdown
 /// code fences and surrounding prose by scanning for the outermost `[...]`.
 /// Caps the result at `max_parts` and drops parts with empty instructions.
@@ -150,6 +166,8 @@ pub fn worker_stage(parent: &Stage, spec: &TeamLeaderSpec, part: &TaskPart) -> S
         gates: Vec::new(),
         team_leader: None,
         sangha: None,
+        review: None,
+        typed_tools: parent.typed_tools,
     }
 }
 
@@ -225,6 +243,8 @@ mod tests {
             gates: Vec::new(),
             team_leader: None,
             sangha: None,
+            review: None,
+            typed_tools: false,
         };
         let spec = TeamLeaderSpec {
             max_parts: 3,
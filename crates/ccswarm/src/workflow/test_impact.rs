@@ -0,0 +1,454 @@
+//! Test impact analysis behind `ccswarm quality test`.
+//!
+//! Maps the files changed in a worktree to the tests they can affect, so the
+//! test check runs only those instead of the whole suite:
+//!
+//! - **cargo**: the workspace packages containing a changed file, plus every
+//!   workspace package depending on them (from `cargo metadata`), run as
+//!   `cargo test -p a -p b`.
+//! - **jest**: the changed sources, handed to `jest --findRelatedTests`.
+//! - **pytest**: changed test files, and `test_<name>.py` / `<name>_test.py`
+//!   for each changed `<name>.py`.
+//!
+//! Anything the analysis can't see through (manifests, lockfiles, test
+//! configuration, a source file with no matching test) falls back to the
+//! full suite. `--full` always runs everything.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use super::quality_checks::QualityCheck;
+use crate::git::shell::git;
+use crate::utils::command::shell_quote;
+
+/// Files whose change can affect every cargo test.
+const CARGO_GLOBAL: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    "rust-toolchain",
+    "rust-toolchain.toml",
+];
+
+const JEST_GLOBAL_PREFIXES: &[&str] = &[
+    "package.json",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "jest.config",
+    "jest.setup",
+    "tsconfig",
+    "babel.config",
+    ".babelrc",
+];
+
+const JS_EXTENSIONS: &[&str] = &["js", "jsx", "ts", "tsx", "mjs", "cjs"];
+
+const PYTEST_GLOBAL: &[&str] = &[
+    "conftest.py",
+    "pytest.ini",
+    "pyproject.toml",
+    "setup.cfg",
+    "setup.py",
+    "tox.ini",
+    "__init__.py",
+];
+
+/// Directories never searched for tests.
+const SKIP_DIRS: &[&str] = &["node_modules", "target", "venv", "__pycache__"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ecosystem {
+    Cargo,
+    Jest,
+    Pytest,
+}
+
+/// Which tests a set of changes calls for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "scope", rename_all = "snake_case")]
+pub enum Impact {
+    /// Run the whole suite.
+    Full { reason: String },
+    /// Run only these packages (cargo) or files (jest, pytest).
+    Scoped {
+        ecosystem: Ecosystem,
+        targets: Vec<String>,
+    },
+    /// No change can affect a test.
+    Nothing,
+}
+
+/// A workspace package, as far as impact analysis cares.
+#[derive(Debug, Clone)]
+pub struct CargoPackage {
+    pub name: String,
+    /// Package directory relative to the workspace root, `""` for the root.
+    pub dir: String,
+    /// Names of the workspace packages it depends on.
+    pub depends_on: Vec<String>,
+}
+
+/// Files changed in `repo` relative to `base`: committed, staged and
+/// unstaged changes plus untracked files under `repo`, relative to it.
+pub async fn changed_files(repo: &Path, base: &str) -> Result<Vec<String>> {
+    let diff = git(repo, &["diff", "--name-only", "--relative", base, "--"]).await?;
+    let untracked = git(repo, &["ls-files", "--others", "--exclude-standard"]).await?;
+    let files: BTreeSet<String> = diff
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    Ok(files.into_iter().collect())
+}
+
+/// Work out the impact of `changed` on the tests of the project in `repo`.
+pub async fn analyze(repo: &Path, changed: &[String]) -> Result<Impact> {
+    if changed.is_empty() {
+        return Ok(Impact::Nothing);
+    }
+    if repo.join("Cargo.toml").exists() {
+        let packages = cargo_packages(repo).await?;
+        return Ok(cargo_impact(&packages, changed));
+    }
+    if std::fs::read_to_string(repo.join("package.json")).is_ok_and(|s| s.contains("\"jest"))
+        || ["jest.config.js", "jest.config.ts"]
+            .iter()
+            .any(|f| repo.join(f).exists())
+    {
+        return Ok(jest_impact(repo, changed));
+    }
+    if ["pytest.ini", "pyproject.toml", "setup.cfg", "conftest.py"]
+        .iter()
+        .any(|f| repo.join(f).exists())
+    {
+        return Ok(pytest_impact(repo, changed));
+    }
+    Ok(Impact::Full {
+        reason: "no impact analysis for this kind of project".to_string(),
+    })
+}
+
+async fn cargo_packages(repo: &Path) -> Result<Vec<CargoPackage>> {
+    let output = tokio::process::Command::new("cargo")
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(repo)
+        .output()
+        .await
+        .context("Failed to run cargo metadata")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let root = metadata["workspace_root"].as_str().unwrap_or_default();
+    let root = Path::new(root);
+    let packages = metadata["packages"].as_array().cloned().unwrap_or_default();
+    let names: BTreeSet<&str> = packages.iter().filter_map(|p| p["name"].as_str()).collect();
+    Ok(packages
+        .iter()
+        .map(|p| {
+            let manifest = Path::new(p["manifest_path"].as_str().unwrap_or_default());
+            let dir = manifest
+                .parent()
+                .and_then(|d| d.strip_prefix(root).ok())
+                .map(|d| d.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            CargoPackage {
+                name: p["name"].as_str().unwrap_or_default().to_string(),
+                dir,
+                depends_on: p["dependencies"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|d| d["name"].as_str())
+                    .filter(|name| names.contains(name))
+                    .map(str::to_string)
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+/// Packages owning a changed file, plus their workspace dependents.
+pub fn cargo_impact(packages: &[CargoPackage], changed: &[String]) -> Impact {
+    let mut affected = BTreeSet::new();
+    for file in changed {
+        if CARGO_GLOBAL.contains(&file.as_str()) || file.starts_with(".cargo/") {
+            return Impact::Full {
+                reason: format!("{file} affects the whole workspace"),
+            };
+        }
+        let owner = packages
+            .iter()
+            .filter(|p| p.dir.is_empty() || file.starts_with(&format!("{}/", p.dir)))
+            .max_by_key(|p| p.dir.len());
+        // Outside every package (docs, CI config): no test can see it.
+        if let Some(package) = owner {
+            affected.insert(package.name.as_str());
+        }
+    }
+
+    let mut dependents: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for package in packages {
+        for dep in &package.depends_on {
+            dependents
+                .entry(dep.as_str())
+                .or_default()
+                .push(&package.name);
+        }
+    }
+    let mut queue: Vec<&str> = affected.iter().copied().collect();
+    while let Some(name) = queue.pop() {
+        for &dependent in dependents.get(name).into_iter().flatten() {
+            if affected.insert(dependent) {
+                queue.push(dependent);
+            }
+        }
+    }
+
+    if affected.is_empty() {
+        Impact::Nothing
+    } else {
+        Impact::Scoped {
+            ecosystem: Ecosystem::Cargo,
+            targets: affected.into_iter().map(str::to_string).collect(),
+        }
+    }
+}
+
+fn jest_impact(repo: &Path, changed: &[String]) -> Impact {
+    let mut targets = Vec::new();
+    for file in changed {
+        let name = file.rsplit('/').next().unwrap_or(file);
+        if JEST_GLOBAL_PREFIXES.iter().any(|p| name.starts_with(p)) {
+            return Impact::Full {
+                reason: format!("{file} affects every test"),
+            };
+        }
+        if !has_extension(file, JS_EXTENSIONS) {
+            continue;
+        }
+        if !repo.join(file).exists() {
+            return Impact::Full {
+                reason: format!("{file} was deleted; its dependents can't be traced"),
+            };
+        }
+        targets.push(file.clone());
+    }
+    scoped(Ecosystem::Jest, targets)
+}
+
+fn pytest_impact(repo: &Path, changed: &[String]) -> Impact {
+    let python: Vec<&String> = changed
+        .iter()
+        .filter(|f| has_extension(f, &["py"]))
+        .collect();
+    let mut tests_by_name: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    if !python.is_empty() {
+        for entry in walkdir::WalkDir::new(repo)
+            .into_iter()
+            .filter_entry(|e| {
+                let name = e.file_name().to_string_lossy();
+                e.depth() == 0 || !(name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref()))
+            })
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if is_python_test(&name)
+                && let Ok(path) = entry.path().strip_prefix(repo)
+            {
+                tests_by_name
+                    .entry(name)
+                    .or_default()
+                    .push(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    let mut targets = BTreeSet::new();
+    for file in changed {
+        let name = file.rsplit('/').next().unwrap_or(file);
+        if PYTEST_GLOBAL.contains(&name) || name.starts_with("requirements") {
+            return Impact::Full {
+                reason: format!("{file} affects every test"),
+            };
+        }
+        if !has_extension(file, &["py"]) {
+            continue;
+        }
+        if is_python_test(name) {
+            if repo.join(file).exists() {
+                targets.insert(file.clone());
+            }
+            continue;
+        }
+        let stem = name.trim_end_matches(".py");
+        let matches: Vec<&String> = [format!("test_{stem}.py"), format!("{stem}_test.py")]
+            .iter()
+            .filter_map(|n| tests_by_name.get(n))
+            .flatten()
+            .collect();
+        if matches.is_empty() {
+            return Impact::Full {
+                reason: format!("no test matches {file}"),
+            };
+        }
+        targets.extend(matches.into_iter().cloned());
+    }
+    scoped(Ecosystem::Pytest, targets.into_iter().collect())
+}
+
+fn scoped(ecosystem: Ecosystem, targets: Vec<String>) -> Impact {
+    if targets.is_empty() {
+        Impact::Nothing
+    } else {
+        Impact::Scoped { ecosystem, targets }
+    }
+}
+
+fn has_extension(file: &str, extensions: &[&str]) -> bool {
+    Path::new(file)
+        .extension()
+        .is_some_and(|ext| extensions.iter().any(|e| ext == *e))
+}
+
+fn is_python_test(name: &str) -> bool {
+    name.ends_with(".py") && (name.starts_with("test_") || name.ends_with("_test.py"))
+}
+
+/// `check`'s command narrowed to the impacted targets, or `None` when the
+/// command isn't one this module knows how to narrow.
+pub fn scope_command(
+    check: &QualityCheck,
+    ecosystem: Ecosystem,
+    targets: &[String],
+) -> Option<String> {
+    let command = check.command.trim();
+    let quoted: Vec<String> = targets.iter().map(|t| shell_quote(t)).collect();
+    match ecosystem {
+        Ecosystem::Cargo => {
+            let packages = targets
+                .iter()
+                .map(|t| format!("-p {}", shell_quote(t)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let words: Vec<&str> = command.split_whitespace().collect();
+            if words.get(..2) != Some(&["cargo", "test"]) {
+                return None;
+            }
+            let rest: Vec<&str> = words[2..]
+                .iter()
+                .copied()
+                .filter(|w| *w != "--workspace" && *w != "--all")
+                .collect();
+            Some(
+                format!("cargo test {packages} {}", rest.join(" "))
+                    .trim_end()
+                    .to_string(),
+            )
+        }
+        Ecosystem::Jest => {
+            let files = quoted.join(" ");
+            if command.contains("jest") {
+                Some(format!("{command} --findRelatedTests {files}"))
+            } else if command == "npm test" || command == "npm run test" {
+                Some(format!("{command} -- --findRelatedTests {files}"))
+            } else if matches!(command, "yarn test" | "pnpm test") {
+                Some(format!("{command} --findRelatedTests {files}"))
+            } else {
+                None
+            }
+        }
+        Ecosystem::Pytest => command
+            .contains("pytest")
+            .then(|| format!("{command} {}", quoted.join(" "))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, dir: &str, depends_on: &[&str]) -> CargoPackage {
+        CargoPackage {
+            name: name.to_string(),
+            dir: dir.to_string(),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_cargo_impact_follows_reverse_dependencies() {
+        let packages = vec![
+            package("core", "crates/core", &[]),
+            package("cli", "crates/cli", &["core"]),
+            package("extras", "crates/extras", &[]),
+        ];
+        assert_eq!(
+            cargo_impact(&packages, &files(&["crates/core/src/lib.rs", "README.md"])),
+            Impact::Scoped {
+                ecosystem: Ecosystem::Cargo,
+                targets: files(&["cli", "core"]),
+            }
+        );
+        assert_eq!(
+            cargo_impact(&packages, &files(&["docs/guide.md"])),
+            Impact::Nothing
+        );
+        assert!(matches!(
+            cargo_impact(&packages, &files(&["crates/cli/src/main.rs", "Cargo.lock"])),
+            Impact::Full { .. }
+        ));
+
+        let check = QualityCheck {
+            name: "test".to_string(),
+            command: "cargo test --workspace --lib".to_string(),
+            fix: None,
+            env: Default::default(),
+            cargo: true,
+        };
+        assert_eq!(
+            scope_command(&check, Ecosystem::Cargo, &files(&["cli", "core"])).as_deref(),
+            Some("cargo test -p cli -p core --lib")
+        );
+    }
+
+    #[test]
+    fn test_pytest_impact_matches_tests_by_module_name() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repo.path().join("app")).unwrap();
+        std::fs::create_dir_all(repo.path().join("tests/unit")).unwrap();
+        for file in [
+            "app/billing.py",
+            "app/orphan.py",
+            "tests/unit/test_billing.py",
+        ] {
+            std::fs::write(repo.path().join(file), "").unwrap();
+        }
+
+        assert_eq!(
+            pytest_impact(repo.path(), &files(&["app/billing.py", "notes.txt"])),
+            Impact::Scoped {
+                ecosystem: Ecosystem::Pytest,
+                targets: files(&["tests/unit/test_billing.py"]),
+            }
+        );
+        assert_eq!(
+            pytest_impact(repo.path(), &files(&["app/orphan.py"])),
+            Impact::Full {
+                reason: "no test matches app/orphan.py".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Environment matrix for the `test_matrix` gate.
+//!
+//! `quality.matrix` in `ccswarm.json` names a test command and the axes it is
+//! run across, e.g. Rust toolchains, Node versions and feature sets:
+//!
+//! ```json
+//! "quality": {
+//!   "matrix": {
+//!     "command": "CARGO_TARGET_DIR=target/matrix/{cell} cargo +{rust} test {features}",
+//!     "axes": { "rust": ["stable", "beta"], "features": ["", "--all-features"] },
+//!     "exclude": [{ "rust": "beta", "features": "" }]
+//!   }
+//! }
+//! ```
+//!
+//! Every combination of axis values is a cell. `{axis}` placeholders in the
+//! command are replaced by the cell's value and `{cell}` by a filesystem-safe
+//! cell name; the values are also exported as `CCSWARM_MATRIX_<AXIS>`. Cells
+//! run as separate processes, up to `max_parallel` at a time, and the
+//! per-cell results are written to `.ccswarm/quality/matrix.json`.
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::redaction::Redactor;
+
+/// Latest matrix report, relative to the working directory.
+pub const REPORT_FILE: &str = ".ccswarm/quality/matrix.json";
+
+/// Characters of output kept per cell.
+const OUTPUT_TAIL_CHARS: usize = 1_000;
+
+/// Test command and the axes it runs across.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TestMatrix {
+    /// Shell command run via `sh -c` for every cell.
+    pub command: String,
+    /// Axis name to the values it takes.
+    pub axes: BTreeMap<String, Vec<String>>,
+    /// Partial cells to skip: a cell is dropped when it matches every key of an entry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<BTreeMap<String, String>>,
+    /// Cells run at the same time.
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: usize,
+    /// Timeout of a single cell.
+    #[serde(default = "default_cell_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_max_parallel() -> usize {
+    4
+}
+
+fn default_cell_timeout_secs() -> u64 {
+    900
+}
+
+impl TestMatrix {
+    /// Reject matrices that would run nothing.
+    pub fn validate(&self) -> Result<()> {
+        if self.command.trim().is_empty() {
+            return Err(anyhow!("quality.matrix.command must not be empty"));
+        }
+        if self.axes.is_empty() {
+            return Err(anyhow!("quality.matrix.axes must name at least one axis"));
+        }
+        if let Some((name, _)) = self.axes.iter().find(|(_, values)| values.is_empty()) {
+            return Err(anyhow!("quality.matrix.axes.{name} has no values"));
+        }
+        if self.max_parallel == 0 {
+            return Err(anyhow!(
+                "quality.matrix.max_parallel must be greater than 0"
+            ));
+        }
+        if self.cells().is_empty() {
+            return Err(anyhow!("quality.matrix.exclude removes every cell"));
+        }
+        Ok(())
+    }
+
+    /// Every combination of axis values not excluded, in axis order.
+    pub fn cells(&self) -> Vec<MatrixCell> {
+        let mut cells = vec![BTreeMap::new()];
+        for (axis, values) in &self.axes {
+            cells = cells
+                .into_iter()
+                .flat_map(|cell: BTreeMap<String, String>| {
+                    values.iter().map(move |value| {
+                        let mut cell = cell.clone();
+                        cell.insert(axis.clone(), value.clone());
+                        cell
+                    })
+                })
+                .collect();
+        }
+        cells
+            .into_iter()
+            .filter(|values| {
+                !self.exclude.iter().any(|rule| {
+                    rule.iter()
+                        .all(|(axis, value)| values.get(axis) == Some(value))
+                })
+            })
+            .map(|values| MatrixCell { values })
+            .collect()
+    }
+}
+
+/// One combination of axis values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixCell {
+    pub values: BTreeMap<String, String>,
+}
+
+impl MatrixCell {
+    /// Human-readable name, e.g. `features=--all-features, rust=beta`.
+    pub fn label(&self) -> String {
+        self.values
+            .iter()
+            .map(|(axis, value)| {
+                if value.is_empty() {
+                    format!("{axis}=(none)")
+                } else {
+                    format!("{axis}={value}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Name usable in paths, e.g. `all-features-beta`.
+    pub fn slug(&self) -> String {
+        let raw: Vec<String> = self
+            .values
+            .values()
+            .map(|value| {
+                let slug: String = value
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                    .collect();
+                let slug = slug
+                    .split('-')
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>();
+                if slug.is_empty() {
+                    "none".to_string()
+                } else {
+                    slug.join("-")
+                }
+            })
+            .collect();
+        raw.join("-")
+    }
+
+    /// `template` with `{axis}` and `{cell}` substituted.
+    pub fn command(&self, template: &str) -> String {
+        let mut command = template.replace("{cell}", &self.slug());
+        for (axis, value) in &self.values {
+            command = command.replace(&format!("{{{axis}}}"), value);
+        }
+        command
+    }
+
+    /// `CCSWARM_MATRIX_<AXIS>` variables describing the cell.
+    pub fn env(&self) -> Vec<(String, String)> {
+        self.values
+            .iter()
+            .map(|(axis, value)| {
+                let name: String = axis
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() {
+                            c.to_ascii_uppercase()
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect();
+                (format!("CCSWARM_MATRIX_{name}"), value.clone())
+            })
+            .chain(std::iter::once((
+                "CCSWARM_MATRIX_CELL".to_string(),
+                self.slug(),
+            )))
+            .collect()
+    }
+}
+
+/// Outcome of one cell.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CellResult {
+    pub cell: String,
+    pub values: BTreeMap<String, String>,
+    pub command: String,
+    pub passed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// End of the combined stdout and stderr, redacted.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub output_tail: String,
+}
+
+/// Results of every cell of one matrix run, in cell order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MatrixReport {
+    pub started_at: DateTime<Utc>,
+    pub cells: Vec<CellResult>,
+}
+
+impl MatrixReport {
+    pub fn passed(&self) -> bool {
+        self.cells.iter().all(|cell| cell.passed)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &CellResult> {
+        self.cells.iter().filter(|cell| !cell.passed)
+    }
+
+    /// One line per cell with its verdict and duration.
+    pub fn table(&self) -> String {
+        let width = self.cells.iter().map(|c| c.cell.len()).max().unwrap_or(0);
+        let mut table = format!(
+            "{} of {} cells passed\n",
+            self.cells.iter().filter(|c| c.passed).count(),
+            self.cells.len()
+        );
+        for cell in &self.cells {
+            let verdict = if cell.passed {
+                "pass".to_string()
+            } else if cell.timed_out {
+                "FAIL (timed out)".to_string()
+            } else {
+                match cell.exit_code {
+                    Some(code) => format!("FAIL (exit {code})"),
+                    None => "FAIL".to_string(),
+                }
+            };
+            table.push_str(&format!(
+                "- {:width$}  {}  {:.1}s\n",
+                cell.cell,
+                verdict,
+                cell.duration_ms as f64 / 1000.0
+            ));
+        }
+        table
+    }
+}
+
+/// Run every cell of `matrix` in `work_dir`.
+pub async fn run(matrix: &TestMatrix, work_dir: &Path, redactor: &Redactor) -> MatrixReport {
+    let started_at = Utc::now();
+    let permits = Arc::new(tokio::sync::Semaphore::new(matrix.max_parallel.max(1)));
+    let timeout = Duration::from_secs(matrix.timeout_secs);
+    let runs = matrix.cells().into_iter().map(|cell| {
+        let permits = Arc::clone(&permits);
+        async move {
+            // The semaphore is never closed, so acquiring cannot fail.
+            let _permit = permits.acquire().await.ok();
+            run_cell(&cell, &matrix.command, work_dir, timeout, redactor).await
+        }
+    });
+    MatrixReport {
+        started_at,
+        cells: futures::future::join_all(runs).await,
+    }
+}
+
+async fn run_cell(
+    cell: &MatrixCell,
+    template: &str,
+    work_dir: &Path,
+    timeout: Duration,
+    redactor: &Redactor,
+) -> CellResult {
+    let command = cell.command(template);
+    let started = Instant::now();
+    let output = tokio::time::timeout(
+        timeout,
+        tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(work_dir)
+            .envs(cell.env())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await;
+
+    let mut result = CellResult {
+        cell: cell.label(),
+        values: cell.values.clone(),
+        command: redactor.redact(&command).into_owned(),
+        passed: false,
+        exit_code: None,
+        timed_out: false,
+        duration_ms: started.elapsed().as_millis() as u64,
+        output_tail: String::new(),
+    };
+    match output {
+        Err(_) => result.timed_out = true,
+        Ok(Err(e)) => result.output_tail = format!("could not be spawned: {e}"),
+        Ok(Ok(output)) => {
+            result.passed = output.status.success();
+            result.exit_code = output.status.code();
+            let combined = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            result.output_tail = tail(&redactor.redact(&combined), OUTPUT_TAIL_CHARS);
+        }
+    }
+    result
+}
+
+pub(crate) fn tail(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.trim_end().to_string();
+    }
+    let kept: String = text.chars().skip(count - max_chars).collect();
+    format!("…{}", kept.trim_end())
+}
+
+/// Write `report` to [`REPORT_FILE`] under `work_dir`.
+pub async fn save_report(work_dir: &Path, report: &MatrixReport) -> Result<()> {
+    let path = work_dir.join(REPORT_FILE);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, serde_json::to_string_pretty(report)?).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(command: &str) -> TestMatrix {
+        TestMatrix {
+            command: command.to_string(),
+            axes: BTreeMap::from([
+                (
+                    "rust".to_string(),
+                    vec!["stable".to_string(), "beta".to_string()],
+                ),
+                (
+                    "features".to_string(),
+                    vec![String::new(), "--all-features".to_string()],
+                ),
+            ]),
+            exclude: vec![BTreeMap::from([
+                ("rust".to_string(), "beta".to_string()),
+                ("features".to_string(), String::new()),
+            ])],
+            max_parallel: 2,
+            timeout_secs: 30,
+        }
+    }
+
+    #[test]
+    fn cells_cover_every_combination_except_excluded() {
+        let m = matrix("cargo +{rust} test {features}");
+        let labels: Vec<String> = m.cells().iter().map(MatrixCell::label).collect();
+        assert_eq!(
+            labels,
+            [
+                "features=(none), rust=stable",
+                "features=--all-features, rust=stable",
+                "features=--all-features, rust=beta",
+            ]
+        );
+
+        let cell = &m.cells()[2];
+        assert_eq!(cell.slug(), "all-features-beta");
+        assert_eq!(
+            cell.command("CARGO_TARGET_DIR=t/{cell} cargo +{rust} test {features}"),
+            "CARGO_TARGET_DIR=t/all-features-beta cargo +beta test --all-features"
+        );
+        assert!(m.validate().is_ok());
+
+        let mut empty = m.clone();
+        empty.axes.insert("node".to_string(), Vec::new());
+        assert!(empty.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn run_reports_each_cell() {
+        let dir = tempfile::tempdir().unwrap();
+        // Fails only for the beta cell, and checks the exported variable.
+        let m =
+            matrix("echo \"running $CCSWARM_MATRIX_CELL\"; test \"$CCSWARM_MATRIX_RUST\" = stable");
+
+        let report = run(&m, dir.path(), &Redactor::default()).await;
+        assert!(!report.passed());
+        let verdicts: Vec<(bool, &str)> = report
+            .cells
+            .iter()
+            .map(|c| (c.passed, c.output_tail.as_str()))
+            .collect();
+        assert_eq!(
+            verdicts,
+            [
+                (true, "running none-stable"),
+                (true, "running all-features-stable"),
+                (false, "running all-features-beta"),
+            ]
+        );
+        assert!(report.table().contains("2 of 3 cells passed"));
+        assert!(report.table().contains("FAIL (exit 1)"));
+
+        save_report(dir.path(), &report).await.unwrap();
+        let saved: MatrixReport =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join(REPORT_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(saved, report);
+    }
+}
@@ -368,6 +368,51 @@ fn test_session_list() {
     );
 }
 
+#[test]
+fn test_session_names_and_aliases_resolve() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_path = temp_dir.path();
+
+    Command::new("git")
+        .args(["init"])
+        .current_dir(project_path)
+        .output()
+        .unwrap();
+    let _ = run_ccswarm(&["init", "--name", "NameTest"], Some(project_path));
+
+    let run_dir = project_path.join(".ccswarm/runs/run-0001");
+    std::fs::create_dir_all(&run_dir).unwrap();
+    std::fs::write(
+        run_dir.join("events.ndjson"),
+        r#"{"ts":"2026-03-26T15:30:12.139Z","level":"info","run_id":"run-0001","event_type":"task_start","message":"Starting flow 'default'"}
+"#,
+    )
+    .unwrap();
+
+    run_ccswarm_success(
+        &["session", "alias", "run-0001", "api-work"],
+        Some(project_path),
+    );
+    run_ccswarm_success(&["session", "alias", "api-work", "api"], Some(project_path));
+    let view = run_ccswarm_success(&["--json", "session", "view", "api"], Some(project_path));
+    assert!(view.contains("run-0001"), "view by alias: {view}");
+
+    // Names and aliases are unique.
+    let taken = run_ccswarm(
+        &["session", "create", "--agent", "backend", "--name", "api"],
+        Some(project_path),
+    );
+    assert!(!taken.status.success());
+
+    run_ccswarm_success(
+        &["session", "rename", "api", "payments"],
+        Some(project_path),
+    );
+    let list = run_ccswarm_success(&["--json", "session", "list"], Some(project_path));
+    assert!(list.contains("\"name\": \"payments\""), "list: {list}");
+    run_ccswarm_success(&["run", "view", "payments"], Some(project_path));
+}
+
 // ============================================================================
 // JSON Output Tests
 // ============================================================================
@@ -433,6 +478,39 @@ fn test_queue_list_json_output() {
     assert_eq!(parsed["tasks"][0]["task"], "Smoke check task");
 }
 
+#[test]
+fn test_delegate_eval_json_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let output = run_ccswarm(
+        &[
+            "--json",
+            "delegate",
+            "eval",
+            "--dataset",
+            "default",
+            "--strategy",
+            "all",
+        ],
+        Some(temp_dir.path()),
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        output.status.success(),
+        "delegate eval should succeed. stdout: {}, stderr: {}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let parsed: serde_json::Value =
+        serde_json::from_str(&stdout).expect("delegate eval --json should emit valid JSON");
+    let reports = parsed["reports"].as_array().unwrap();
+    assert_eq!(reports.len(), 3);
+    let hybrid = reports.iter().find(|r| r["strategy"] == "hybrid").unwrap();
+    assert!(hybrid["accuracy"].as_f64().unwrap() > 0.0);
+    assert_eq!(hybrid["confusion"]["qa"].as_object().unwrap().len(), 4);
+}
+
 // ============================================================================
 // Error Handling Tests
 // ============================================================================
@@ -8,7 +8,8 @@ use std::sync::Arc;
 
 #[tokio::test]
 async fn test_coordination_messages() {
-    let bus = Arc::new(CoordinationBus::new().await.unwrap());
+    let repo = tempfile::tempdir().unwrap();
+    let bus = Arc::new(CoordinationBus::new(repo.path()).await.unwrap());
 
     let task_msg = AgentMessage::TaskAssignment {
         task_id: "test-task-001".to_string(),
@@ -29,3 +30,24 @@ async fn test_coordination_messages() {
         _ => panic!("Unexpected message type received"),
     }
 }
+
+#[tokio::test]
+async fn test_receive_traced_returns_envelope() {
+    let repo = tempfile::tempdir().unwrap();
+    let bus = CoordinationBus::new(repo.path()).await.unwrap();
+
+    bus.send_message(AgentMessage::Heartbeat {
+        agent_id: "backend-agent".to_string(),
+        timestamp: chrono::Utc::now(),
+    })
+    .await
+    .unwrap();
+    let envelope = bus.receive_traced().await.unwrap();
+
+    assert!(matches!(
+        envelope.message,
+        AgentMessage::Heartbeat { ref agent_id, .. } if agent_id == "backend-agent"
+    ));
+    // No OTLP layer is installed in tests, so there is no trace to carry.
+    assert_eq!(envelope.trace.trace_id(), None);
+}